@@ -1,6 +1,6 @@
 extern crate krill;
 
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use clap::{App, Arg};
 use log::error;
@@ -8,6 +8,7 @@ use log::error;
 use krill::{
     constants::{KRILL_DEFAULT_CONFIG_FILE, KRILL_SERVER_APP, KRILL_VERSION},
     daemon::{config::Config, http::server},
+    upgrades::{check_upgrade, check_upgrade_on_copy},
 };
 
 #[tokio::main]
@@ -25,10 +26,31 @@ async fn main() {
                 ))
                 .required(false),
         )
+        .arg(
+            Arg::with_name("upgrade-check")
+                .long("upgrade-check")
+                .help("Report which data migrations an upgrade to this version would run, then exit")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("upgrade-check-copy")
+                .long("upgrade-check-copy")
+                .value_name("DIR")
+                .help(
+                    "With --upgrade-check: validate the migrations against a copy of the data in this new or empty dir",
+                )
+                .requires("upgrade-check")
+                .required(false),
+        )
         .get_matches();
 
     let config_file = matches.value_of("config").unwrap_or(KRILL_DEFAULT_CONFIG_FILE);
 
+    if matches.is_present("upgrade-check") {
+        upgrade_check(config_file, matches.value_of("upgrade-check-copy").map(Path::new));
+        return;
+    }
+
     match Config::create(config_file, false) {
         Ok(config) => {
             if let Err(e) = server::start_krill_daemon(Arc::new(config)).await {
@@ -42,3 +64,34 @@ async fn main() {
         }
     }
 }
+
+/// Checks the upgrade of the data to this version, without changing the data,
+/// and exits with a non-zero code if the upgrade would fail.
+fn upgrade_check(config_file: &str, copy_dir: Option<&Path>) {
+    let config = match Config::create(config_file, true) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Could not parse config: {}", e);
+            ::std::process::exit(1);
+        }
+    };
+
+    let check = match copy_dir {
+        Some(copy_dir) => check_upgrade_on_copy(&config, copy_dir),
+        None => check_upgrade(&config),
+    };
+
+    match check {
+        Ok(None) => println!("No upgrade needed"),
+        Ok(Some(check)) => {
+            print!("{}", check);
+            if check.has_sufficient_space() == Some(false) {
+                ::std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("*** ERROR *** {}", e);
+            ::std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,765 @@
+//! Support for managing CAs declaratively, i.e. from YAML or JSON files which
+//! describe the desired state of CAs, rather than through separate commands.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use rpki::ca::idexchange::{self, CaHandle, ParentHandle};
+
+use crate::{
+    cli::options::Error,
+    commons::{
+        api::{
+            AspaCustomer, AspaDefinition, AspaDefinitionUpdates, ParentCaContact, ParentCaReq, RepositoryContact,
+            RoaConfiguration, RoaConfigurationUpdates, RoaPayload,
+        },
+        util::file,
+    },
+};
+
+//------------ DesiredState --------------------------------------------------
+
+/// The desired state of CAs, as read from one or more files.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DesiredState {
+    cas: Vec<DesiredCa>,
+}
+
+impl DesiredState {
+    /// Reads the desired state from a file, or from all files ending with
+    /// .yaml, .yml or .json in a directory, in alphabetical order.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let files = if path.is_dir() {
+            let mut files = vec![];
+            let entries = std::fs::read_dir(path).map_err(|e| {
+                Error::GeneralArgumentError(format!("Cannot read directory '{}': {}", path.display(), e))
+            })?;
+            for entry in entries {
+                let entry = entry.map_err(|e| {
+                    Error::GeneralArgumentError(format!("Cannot read directory '{}': {}", path.display(), e))
+                })?;
+                let path = entry.path();
+                if path.is_file() && StateFormat::for_path(&path).is_some() {
+                    files.push(path);
+                }
+            }
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+
+        let mut state = DesiredState::default();
+        for path in files {
+            let format = StateFormat::for_path(&path).ok_or_else(|| {
+                Error::GeneralArgumentError(format!(
+                    "Unsupported file '{}', use .yaml, .yml or .json",
+                    path.display()
+                ))
+            })?;
+            let bytes = file::read(&path)?;
+            let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+            let file = StateFile::parse(format, bytes.as_ref())
+                .map_err(|e| Error::GeneralArgumentError(format!("Cannot parse '{}': {}", path.display(), e)))?;
+            for ca in file.cas {
+                state.add(ca.resolve(base_dir)?)?;
+            }
+        }
+
+        Ok(state)
+    }
+
+    fn add(&mut self, ca: DesiredCa) -> Result<(), Error> {
+        if self.cas.iter().any(|existing| existing.handle == ca.handle) {
+            Err(Error::GeneralArgumentError(format!(
+                "CA '{}' is described more than once",
+                ca.handle
+            )))
+        } else {
+            self.cas.push(ca);
+            Ok(())
+        }
+    }
+
+    pub fn cas(&self) -> &Vec<DesiredCa> {
+        &self.cas
+    }
+}
+
+//------------ DesiredCa -----------------------------------------------------
+
+/// The desired state of a single CA. Parents, ROAs and ASPAs which are not
+/// listed are removed. The repository is left alone if it is not listed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DesiredCa {
+    handle: CaHandle,
+    repository: Option<idexchange::RepositoryResponse>,
+    parents: Vec<ParentCaReq>,
+    roas: Vec<RoaConfiguration>,
+    aspas: Vec<AspaDefinition>,
+}
+
+impl DesiredCa {
+    pub fn handle(&self) -> &CaHandle {
+        &self.handle
+    }
+}
+
+//------------ CurrentCa -----------------------------------------------------
+
+/// The current state of an existing CA, as reported by the server.
+#[derive(Clone, Debug, Default)]
+pub struct CurrentCa {
+    pub repository: Option<RepositoryContact>,
+    pub parents: HashMap<ParentHandle, ParentCaContact>,
+    pub roas: Vec<RoaConfiguration>,
+    pub aspas: Vec<AspaDefinition>,
+}
+
+//------------ CaChanges -----------------------------------------------------
+
+/// The changes needed to bring a CA from its current to its desired state.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct CaChanges {
+    ca: CaHandle,
+    add_ca: bool,
+    repository: Option<idexchange::RepositoryResponse>,
+    parents_add: Vec<ParentCaReq>,
+    parents_update: Vec<ParentCaReq>,
+    parents_remove: Vec<ParentHandle>,
+    roas: RoaConfigurationUpdates,
+    aspas: AspaDefinitionUpdates,
+}
+
+impl CaChanges {
+    /// Plans the changes for a CA, which does not exist yet if current is
+    /// None.
+    pub fn plan(desired: &DesiredCa, current: Option<&CurrentCa>) -> Self {
+        let add_ca = current.is_none();
+        let empty = CurrentCa::default();
+        let current = current.unwrap_or(&empty);
+
+        let repository = desired.repository.as_ref().and_then(|response| {
+            let unchanged = match (&current.repository, RepositoryContact::for_response(response.clone())) {
+                (Some(current), Ok(desired)) => current == &desired,
+                _ => false,
+            };
+            if unchanged {
+                None
+            } else {
+                Some(response.clone())
+            }
+        });
+
+        let mut parents_add = vec![];
+        let mut parents_update = vec![];
+        for parent in &desired.parents {
+            match current.parents.get(parent.handle()) {
+                None => parents_add.push(parent.clone()),
+                Some(contact) => {
+                    let unchanged = ParentCaContact::for_rfc8183_parent_response(parent.response().clone())
+                        .map(|desired| &desired == contact)
+                        .unwrap_or(false);
+                    if !unchanged {
+                        parents_update.push(parent.clone());
+                    }
+                }
+            }
+        }
+
+        let desired_parents: HashSet<&ParentHandle> = desired.parents.iter().map(|p| p.handle()).collect();
+        let mut parents_remove: Vec<ParentHandle> = current
+            .parents
+            .keys()
+            .filter(|handle| !desired_parents.contains(handle))
+            .cloned()
+            .collect();
+        parents_remove.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        CaChanges {
+            ca: desired.handle.clone(),
+            add_ca,
+            repository,
+            parents_add,
+            parents_update,
+            parents_remove,
+            roas: roa_updates(&current.roas, &desired.roas),
+            aspas: aspa_updates(&current.aspas, &desired.aspas),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.add_ca
+            && self.repository.is_none()
+            && self.parents_add.is_empty()
+            && self.parents_update.is_empty()
+            && self.parents_remove.is_empty()
+            && self.roas.is_empty()
+            && self.aspas.is_empty()
+    }
+
+    pub fn ca(&self) -> &CaHandle {
+        &self.ca
+    }
+
+    pub fn add_ca(&self) -> bool {
+        self.add_ca
+    }
+
+    pub fn repository(&self) -> Option<&idexchange::RepositoryResponse> {
+        self.repository.as_ref()
+    }
+
+    /// Returns the parents to add or update, which use the same API call.
+    pub fn parents_add_or_update(&self) -> impl Iterator<Item = &ParentCaReq> {
+        self.parents_add.iter().chain(self.parents_update.iter())
+    }
+
+    pub fn parents_remove(&self) -> &Vec<ParentHandle> {
+        &self.parents_remove
+    }
+
+    pub fn roas(&self) -> &RoaConfigurationUpdates {
+        &self.roas
+    }
+
+    pub fn aspas(&self) -> &AspaDefinitionUpdates {
+        &self.aspas
+    }
+}
+
+impl fmt::Display for CaChanges {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "CA '{}':", self.ca)?;
+        if self.add_ca {
+            writeln!(f, "  add CA")?;
+        }
+        if let Some(repository) = &self.repository {
+            writeln!(f, "  set repository: {}", repository.service_uri())?;
+        }
+        for parent in &self.parents_add {
+            writeln!(f, "  add parent: {}", parent.handle())?;
+        }
+        for parent in &self.parents_update {
+            writeln!(f, "  update parent: {}", parent.handle())?;
+        }
+        for parent in &self.parents_remove {
+            writeln!(f, "  remove parent: {}", parent)?;
+        }
+        for line in self.roas.to_string().lines() {
+            match (line.strip_prefix("A: "), line.strip_prefix("R: ")) {
+                (Some(added), _) => writeln!(f, "  add ROA: {}", added)?,
+                (_, Some(removed)) => writeln!(f, "  remove ROA: {}", removed)?,
+                _ => {}
+            }
+        }
+        let (add_or_replace, remove) = self.aspas.clone().unpack();
+        for aspa in add_or_replace {
+            writeln!(f, "  add or replace ASPA: {}", aspa)?;
+        }
+        for customer in remove {
+            writeln!(f, "  remove ASPA for customer: {}", customer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the ROA updates to go from the current to the desired ROAs. ROAs
+/// for which only the comment changed are added again with the new comment.
+/// ROAs for which only the disabled flag changed are disabled or enabled.
+fn roa_updates(current: &[RoaConfiguration], desired: &[RoaConfiguration]) -> RoaConfigurationUpdates {
+    let current_configs: HashMap<RoaPayload, RoaConfiguration> = current
+        .iter()
+        .cloned()
+        .map(RoaConfiguration::into_explicit_max_length)
+        .map(|config| (config.payload(), config))
+        .collect();
+    let desired_configs: Vec<RoaConfiguration> = desired
+        .iter()
+        .cloned()
+        .map(RoaConfiguration::into_explicit_max_length)
+        .collect();
+    let desired_payloads: HashSet<RoaPayload> = desired_configs.iter().map(|config| config.payload()).collect();
+
+    let mut added = vec![];
+    let mut disabled = vec![];
+    let mut enabled = vec![];
+
+    for config in desired_configs {
+        match current_configs.get(&config.payload()) {
+            None => added.push(config),
+            Some(current_config) => {
+                if current_config.comment() != config.comment() {
+                    added.push(config.clone());
+                }
+                if current_config.is_disabled() != config.is_disabled() {
+                    if config.is_disabled() {
+                        disabled.push(config.payload());
+                    } else {
+                        enabled.push(config.payload());
+                    }
+                }
+            }
+        }
+    }
+    added.sort();
+    disabled.sort();
+    enabled.sort();
+
+    let mut removed: Vec<RoaPayload> = current_configs
+        .keys()
+        .filter(|payload| !desired_payloads.contains(payload))
+        .copied()
+        .collect();
+    removed.sort();
+
+    let mut updates = RoaConfigurationUpdates::new(added, removed);
+    for payload in disabled {
+        updates.disable(payload);
+    }
+    for payload in enabled {
+        updates.enable(payload);
+    }
+    updates
+}
+
+/// Returns the ASPA updates to go from the current to the desired ASPAs.
+fn aspa_updates(current: &[AspaDefinition], desired: &[AspaDefinition]) -> AspaDefinitionUpdates {
+    let add_or_replace = desired
+        .iter()
+        .filter(|definition| !current.contains(definition))
+        .cloned()
+        .collect();
+
+    let desired_customers: HashSet<AspaCustomer> = desired.iter().map(|definition| definition.customer()).collect();
+    let mut remove: Vec<AspaCustomer> = current
+        .iter()
+        .map(|definition| definition.customer())
+        .filter(|customer| !desired_customers.contains(customer))
+        .collect();
+    remove.sort();
+
+    AspaDefinitionUpdates::new(add_or_replace, remove)
+}
+
+//------------ ApplyProgress -------------------------------------------------
+
+/// Keeps track of the steps applied for a CA, so that it can be reported
+/// how far applying the changes for the CA got if a step fails.
+#[derive(Clone, Debug)]
+pub struct ApplyProgress {
+    ca: CaHandle,
+    applied: Vec<String>,
+}
+
+impl ApplyProgress {
+    pub fn new(ca: CaHandle) -> Self {
+        ApplyProgress { ca, applied: vec![] }
+    }
+
+    /// Records the result of a step, returns the failure if it failed.
+    pub fn step<E: fmt::Display>(&mut self, step: impl fmt::Display, res: Result<(), E>) -> Result<(), ApplyFailure> {
+        match res {
+            Ok(()) => {
+                self.applied.push(step.to_string());
+                Ok(())
+            }
+            Err(e) => Err(ApplyFailure {
+                ca: self.ca.clone(),
+                applied: self.applied.clone(),
+                failed: step.to_string(),
+                error: e.to_string(),
+            }),
+        }
+    }
+}
+
+//------------ ApplyFailure --------------------------------------------------
+
+/// A step which failed when applying the changes for a CA, and the steps
+/// for the CA which were applied before it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ApplyFailure {
+    ca: CaHandle,
+    applied: Vec<String>,
+    failed: String,
+    error: String,
+}
+
+//------------ ApplyReport ---------------------------------------------------
+
+/// The changes that were applied, or would be applied in a dry run. If
+/// applying the changes failed, the changes for the CAs before the CA for
+/// which it failed were applied, and the changes for the CAs after it were
+/// not.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ApplyReport {
+    dry_run: bool,
+    changes: Vec<CaChanges>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failure: Option<ApplyFailure>,
+}
+
+impl ApplyReport {
+    pub fn new(dry_run: bool, changes: Vec<CaChanges>, failure: Option<ApplyFailure>) -> Self {
+        ApplyReport {
+            dry_run,
+            changes,
+            failure,
+        }
+    }
+
+    pub fn failure(&self) -> Option<&ApplyFailure> {
+        self.failure.as_ref()
+    }
+}
+
+impl fmt::Display for ApplyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(f, "No changes needed");
+        }
+
+        let failure = match &self.failure {
+            Some(failure) => failure,
+            None => {
+                if self.dry_run {
+                    writeln!(f, "Changes to apply (dry run):")?;
+                } else {
+                    writeln!(f, "Applied changes:")?;
+                }
+                for changes in &self.changes {
+                    write!(f, "{}", changes)?;
+                }
+                return Ok(());
+            }
+        };
+
+        let failed_index = self.changes.iter().position(|changes| changes.ca == failure.ca);
+        let (applied, not_applied) = self.changes.split_at(failed_index.unwrap_or(self.changes.len()));
+
+        if !applied.is_empty() {
+            writeln!(f, "Applied changes:")?;
+            for changes in applied {
+                write!(f, "{}", changes)?;
+            }
+        }
+
+        writeln!(
+            f,
+            "Failed to {} for CA '{}': {}",
+            failure.failed, failure.ca, failure.error
+        )?;
+        if !failure.applied.is_empty() {
+            writeln!(
+                f,
+                "Already applied for CA '{}': {}",
+                failure.ca,
+                failure.applied.join(", ")
+            )?;
+        }
+
+        writeln!(f, "Not applied, or only in part:")?;
+        for changes in not_applied {
+            write!(f, "{}", changes)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ StateFile -----------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum StateFormat {
+    Json,
+    Yaml,
+}
+
+impl StateFormat {
+    fn for_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Some(StateFormat::Json),
+            Some("yaml") | Some("yml") => Some(StateFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// The contents of a single file. RFC 8183 responses for parents and the
+/// repository are referred to by path, relative to the file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StateFile {
+    #[serde(default)]
+    cas: Vec<CaEntry>,
+}
+
+impl StateFile {
+    fn parse(format: StateFormat, bytes: &[u8]) -> Result<Self, String> {
+        match format {
+            StateFormat::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+            StateFormat::Yaml => serde_yaml::from_slice(bytes).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CaEntry {
+    handle: CaHandle,
+    #[serde(default)]
+    repository: Option<PathBuf>,
+    #[serde(default)]
+    parents: Vec<ParentEntry>,
+    #[serde(default)]
+    roas: Vec<RoaEntry>,
+    #[serde(default)]
+    aspas: Vec<AspaEntry>,
+}
+
+impl CaEntry {
+    fn resolve(self, base_dir: &Path) -> Result<DesiredCa, Error> {
+        let repository = match self.repository {
+            Some(path) => {
+                let bytes = file::read(&base_dir.join(path))?;
+                Some(idexchange::RepositoryResponse::parse(bytes.as_ref())?)
+            }
+            None => None,
+        };
+
+        let mut parents = vec![];
+        for parent in self.parents {
+            let bytes = file::read(&base_dir.join(parent.response))?;
+            let response = idexchange::ParentResponse::parse(bytes.as_ref())?;
+            parents.push(ParentCaReq::new(parent.handle, response));
+        }
+
+        let roas = self
+            .roas
+            .into_iter()
+            .map(RoaEntry::into_configuration)
+            .collect::<Result<_, _>>()?;
+
+        let aspas = self
+            .aspas
+            .into_iter()
+            .map(AspaEntry::into_definition)
+            .collect::<Result<_, _>>()?;
+
+        Ok(DesiredCa {
+            handle: self.handle,
+            repository,
+            parents,
+            roas,
+            aspas,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ParentEntry {
+    handle: ParentHandle,
+    response: PathBuf,
+}
+
+/// A ROA as used by 'krillc roas update', e.g. "192.0.2.0/24 => 64496", or
+/// as the JSON used by the API.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum RoaEntry {
+    Text(String),
+    Configuration(RoaConfiguration),
+}
+
+impl RoaEntry {
+    fn into_configuration(self) -> Result<RoaConfiguration, Error> {
+        match self {
+            RoaEntry::Text(s) => RoaConfiguration::from_str(&s).map_err(Error::from),
+            RoaEntry::Configuration(configuration) => Ok(configuration),
+        }
+    }
+}
+
+/// An ASPA as used by 'krillc aspas add', e.g. "AS65000 => AS65001", or as
+/// the JSON used by the API.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum AspaEntry {
+    Text(String),
+    Definition(AspaDefinition),
+}
+
+impl AspaEntry {
+    fn into_definition(self) -> Result<AspaDefinition, Error> {
+        match self {
+            AspaEntry::Text(s) => AspaDefinition::from_str(&s).map_err(Error::from),
+            AspaEntry::Definition(definition) => Ok(definition),
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roa(s: &str) -> RoaConfiguration {
+        RoaConfiguration::from_str(s).unwrap()
+    }
+
+    fn aspa(s: &str) -> AspaDefinition {
+        AspaDefinition::from_str(s).unwrap()
+    }
+
+    fn desired_ca(yaml: &str) -> DesiredCa {
+        let file = StateFile::parse(StateFormat::Yaml, yaml.as_bytes()).unwrap();
+        file.cas.into_iter().next().unwrap().resolve(Path::new(".")).unwrap()
+    }
+
+    #[test]
+    fn plan_new_ca() {
+        let desired = desired_ca(
+            r#"
+            cas:
+              - handle: ca1
+                roas:
+                  - "192.0.2.0/24 => 64496 # docs"
+                  - { asn: 64497, prefix: "2001:db8::/32", max_length: 48 }
+                aspas:
+                  - "AS65000 => AS65001, AS65002"
+            "#,
+        );
+
+        let changes = CaChanges::plan(&desired, None);
+        assert!(changes.add_ca());
+        assert!(changes.repository().is_none());
+        assert_eq!(
+            changes.roas(),
+            &RoaConfigurationUpdates::new(
+                vec![roa("192.0.2.0/24-24 => 64496 # docs"), roa("2001:db8::/32-48 => 64497")],
+                vec![]
+            )
+        );
+        assert_eq!(
+            changes.aspas(),
+            &AspaDefinitionUpdates::new(vec![aspa("AS65000 => AS65001, AS65002")], vec![])
+        );
+    }
+
+    #[test]
+    fn plan_existing_ca() {
+        let desired = desired_ca(
+            r#"
+            cas:
+              - handle: ca1
+                roas:
+                  - "192.0.2.0/24 => 64496"
+                  - "198.51.100.0/24 => 64496 # new comment"
+                aspas:
+                  - "AS65000 => AS65001"
+            "#,
+        );
+
+        let current = CurrentCa {
+            roas: vec![
+                roa("192.0.2.0/24-24 => 64496"),
+                roa("198.51.100.0/24-24 => 64496 # old comment"),
+                roa("203.0.113.0/24-24 => 64496"),
+            ],
+            aspas: vec![aspa("AS65000 => AS65001"), aspa("AS65010 => AS65011")],
+            ..Default::default()
+        };
+
+        let changes = CaChanges::plan(&desired, Some(&current));
+        assert!(!changes.add_ca());
+        assert_eq!(
+            changes.roas(),
+            &RoaConfigurationUpdates::new(
+                vec![roa("198.51.100.0/24-24 => 64496 # new comment")],
+                vec![roa("203.0.113.0/24-24 => 64496").payload()]
+            )
+        );
+        assert_eq!(
+            changes.aspas(),
+            &AspaDefinitionUpdates::new(vec![], vec![AspaCustomer::from_str("AS65010").unwrap()])
+        );
+
+        let unchanged = CurrentCa {
+            roas: desired.roas.clone(),
+            aspas: desired.aspas.clone(),
+            ..Default::default()
+        };
+        assert!(CaChanges::plan(&desired, Some(&unchanged)).is_empty());
+    }
+
+    #[test]
+    fn plan_disabled_roas() {
+        let desired = desired_ca(
+            r#"
+            cas:
+              - handle: ca1
+                roas:
+                  - { asn: 64496, prefix: "192.0.2.0/24", disabled: true }
+                  - "198.51.100.0/24 => 64496"
+            "#,
+        );
+
+        let current = CurrentCa {
+            roas: vec![
+                roa("192.0.2.0/24-24 => 64496"),
+                roa("198.51.100.0/24-24 => 64496").with_disabled(true),
+            ],
+            ..Default::default()
+        };
+
+        let mut expected = RoaConfigurationUpdates::empty();
+        expected.disable(roa("192.0.2.0/24-24 => 64496").payload());
+        expected.enable(roa("198.51.100.0/24-24 => 64496").payload());
+
+        let changes = CaChanges::plan(&desired, Some(&current));
+        assert_eq!(changes.roas(), &expected);
+    }
+
+    #[test]
+    fn report_partially_applied_changes() {
+        let changes: Vec<_> = ["ca1", "ca2", "ca3"]
+            .iter()
+            .map(|ca| CaChanges::plan(&desired_ca(&format!("cas: [ {{ handle: {} }} ]", ca)), None))
+            .collect();
+
+        let mut progress = ApplyProgress::new(changes[1].ca().clone());
+        progress.step("add CA", Ok::<(), String>(())).unwrap();
+        let failure = progress
+            .step("add parent 'testbed'", Err("parent unreachable"))
+            .unwrap_err();
+
+        let report = ApplyReport::new(false, changes, Some(failure));
+        assert_eq!(
+            report.to_string(),
+            "Applied changes:\n\
+             CA 'ca1':\n  add CA\n\
+             Failed to add parent 'testbed' for CA 'ca2': parent unreachable\n\
+             Already applied for CA 'ca2': add CA\n\
+             Not applied, or only in part:\n\
+             CA 'ca2':\n  add CA\n\
+             CA 'ca3':\n  add CA\n"
+        );
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["failure"]["failed"], "add parent 'testbed'");
+        assert_eq!(json["failure"]["applied"][0], "add CA");
+    }
+
+    #[test]
+    fn reject_duplicate_cas() {
+        let mut state = DesiredState::default();
+        let ca = desired_ca("cas: [ { handle: ca1 } ]");
+        state.add(ca.clone()).unwrap();
+        assert!(state.add(ca).is_err());
+    }
+}
@@ -1,26 +1,46 @@
-use std::{env, fmt};
+use std::{
+    collections::{HashMap, HashSet},
+    env, fmt,
+    io::{self, BufRead, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
 
 use serde::{de::DeserializeOwned, Serialize};
 
-use rpki::ca::idexchange;
+use rpki::ca::idexchange::{self, CaHandle};
 
 use crate::{
     cli::{
-        options::{BulkCaCommand, CaCommand, Command, KrillInitDetails, Options, PubServerCommand},
-        report::{ApiResponse, ReportError},
+        apply::{ApplyFailure, ApplyProgress, ApplyReport, CaChanges, CurrentCa, DesiredState},
+        options::{
+            BackupCommand, BulkCaCommand, CaCommand, Command, HaCommand, JobsCommand, KeysCommand, KrillInitDetails,
+            Options, PubServerCommand, TaskCommand, TimeCommand, XmlCommand,
+        },
+        report::{ApiResponse, ReportError, ReportFormat},
+        xml::{Rfc8183Xml, XmlDiff, XmlIdCert},
     },
     commons::{
+        api::import::CaMigration,
         api::{
-            AllCertAuthIssues, ApiRepositoryContact, AspaDefinitionUpdates, BgpSecDefinitionUpdates, CaRepoDetails,
-            CertAuthIssues, ChildCaInfo, ChildrenConnectionStats, ParentCaContact, ParentStatuses, PublisherDetails,
-            PublisherList, RepoStatus, Token,
+            AllCertAuthIssues, ApiRepositoryContact, ApiVersion, ApiVersions, AspaDefinitionList,
+            AspaDefinitionUpdates, BgpSecDefinitionUpdates, CaLabels, CaRepoDetails, CertAuthInfo, CertAuthInit,
+            CertAuthIssues, CertAuthList, ChildCaInfo, ChildIssuancePolicy, ChildrenConnectionStats, ChildrenStats,
+            ClassRepoDetails, ConfiguredRoas, DoctorFinding, DoctorReport, IssuanceTimingOverrides,
+            IssuanceTimingReport, ObjectsExpiry, ParentCaContact, ParentExchangeLog, ParentStatuses, ParentsStats,
+            PendingChildRequests, PublicationCheck, PublisherDetails, PublisherList, Reconciliation,
+            RepoMigrationReport, RepoMigrationStatus, RepoStatus, RepoStatuses, ResourceTransfer, ResourceTransferList,
+            RetryPolicies, ScheduledChangeList, SearchQuery, SearchResults, SignerMigrationStatus, Timestamp, Token,
         },
         bgp::BgpAnalysisAdvice,
         error::KrillIoError,
         util::{file, httpclient},
     },
-    constants::KRILL_CLI_API_ENV,
-    daemon::config::Config,
+    constants::{
+        HTTP_CLIENT_TIMEOUT_SECS, KRILL_CLIENT_BIN, KRILL_CLI_API_ENV, KRILL_CLI_FORMAT_ENV, KRILL_CLI_PROFILE_ENV,
+        KRILL_CLI_TOKEN_ENV,
+    },
+    daemon::{ca::RpkiSignedChecklist, config::Config},
 };
 
 #[cfg(feature = "multi-user")]
@@ -29,30 +49,74 @@ use crate::{
     constants::{PW_HASH_LOG_N, PW_HASH_P, PW_HASH_R},
 };
 
-fn resolve_uri(server: &idexchange::ServiceUri, path: &str) -> String {
-    format!("{}{}", server, path)
+//------------ ApiServer -----------------------------------------------------
+
+/// The server to send requests to, and the API version to use.
+struct ApiServer {
+    uri: idexchange::ServiceUri,
+    version: ApiVersion,
+}
+
+impl ApiServer {
+    /// Uses the most recent API version supported by both the server and
+    /// this client. Servers which do not advertise their versions only
+    /// support v1.
+    async fn negotiate(uri: idexchange::ServiceUri) -> Result<Self, Error> {
+        let versions_uri = format!("{}api/versions", uri);
+        let version = match httpclient::get_json::<ApiVersions>(&versions_uri, None).await {
+            Ok(versions) => versions
+                .negotiate(&ApiVersion::all())
+                .ok_or(Error::ApiVersionUnsupported)?,
+            Err(e) => {
+                debug!("Could not get API versions, assuming v1: {}", e);
+                ApiVersion::V1
+            }
+        };
+        Ok(ApiServer { uri, version })
+    }
+}
+
+impl fmt::Display for ApiServer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.uri)
+    }
+}
+
+/// Paths are given for API v1. Later versions serve the same paths, so only
+/// the version in the path needs to be changed.
+fn resolve_uri(server: &ApiServer, path: &str) -> String {
+    match path.strip_prefix("api/v1/") {
+        Some(remaining) => format!("{}api/{}/{}", server.uri, server.version, remaining),
+        None => format!("{}{}", server.uri, path),
+    }
 }
 
-async fn get_json<T: DeserializeOwned>(server: &idexchange::ServiceUri, token: &Token, path: &str) -> Result<T, Error> {
+async fn get_json<T: DeserializeOwned>(server: &ApiServer, token: &Token, path: &str) -> Result<T, Error> {
     let uri = resolve_uri(server, path);
     httpclient::get_json(&uri, Some(token))
         .await
         .map_err(Error::HttpClientError)
 }
 
-async fn post_empty(server: &idexchange::ServiceUri, token: &Token, path: &str) -> Result<(), Error> {
+async fn post_empty(server: &ApiServer, token: &Token, path: &str) -> Result<(), Error> {
     let uri = resolve_uri(server, path);
     httpclient::post_empty(&uri, Some(token))
         .await
         .map_err(Error::HttpClientError)
 }
 
-async fn post_json(
-    server: &idexchange::ServiceUri,
+async fn post_empty_with_response<T: DeserializeOwned>(
+    server: &ApiServer,
     token: &Token,
     path: &str,
-    data: impl Serialize,
-) -> Result<(), Error> {
+) -> Result<T, Error> {
+    let uri = resolve_uri(server, path);
+    httpclient::post_empty_with_response(&uri, Some(token))
+        .await
+        .map_err(Error::HttpClientError)
+}
+
+async fn post_json(server: &ApiServer, token: &Token, path: &str, data: impl Serialize) -> Result<(), Error> {
     let uri = resolve_uri(server, path);
     httpclient::post_json(&uri, data, Some(token))
         .await
@@ -60,7 +124,7 @@ async fn post_json(
 }
 
 async fn post_json_with_response<T: DeserializeOwned>(
-    server: &idexchange::ServiceUri,
+    server: &ApiServer,
     token: &Token,
     path: &str,
     data: impl Serialize,
@@ -72,7 +136,7 @@ async fn post_json_with_response<T: DeserializeOwned>(
 }
 
 async fn post_json_with_opt_response<T: DeserializeOwned>(
-    server: &idexchange::ServiceUri,
+    server: &ApiServer,
     token: &Token,
     uri: &str,
     data: impl Serialize,
@@ -83,7 +147,7 @@ async fn post_json_with_opt_response<T: DeserializeOwned>(
         .map_err(Error::HttpClientError)
 }
 
-async fn delete(server: &idexchange::ServiceUri, token: &Token, uri: &str) -> Result<(), Error> {
+async fn delete(server: &ApiServer, token: &Token, uri: &str) -> Result<(), Error> {
     let uri = resolve_uri(server, uri);
     httpclient::delete(&uri, Some(token))
         .await
@@ -92,7 +156,7 @@ async fn delete(server: &idexchange::ServiceUri, token: &Token, uri: &str) -> Re
 
 /// Command line tool for Krill admin tasks
 pub struct KrillClient {
-    server: idexchange::ServiceUri,
+    server: ApiServer,
     token: Token,
 }
 
@@ -113,8 +177,26 @@ impl KrillClient {
     /// Note that this function is public to help integration testing the API
     /// and client.
     pub async fn process(options: Options) -> Result<ApiResponse, Error> {
+        // Only ask the server which API version to use if the command needs
+        // the server, and the request will not just be printed.
+        let negotiate = match &options.command {
+            Command::Init(_) | Command::Xml(_) | Command::Completions(_) | Command::NotSet => false,
+            #[cfg(feature = "multi-user")]
+            Command::User(_) => false,
+            _ => !options.api,
+        };
+
+        let server = if negotiate {
+            ApiServer::negotiate(options.server).await?
+        } else {
+            ApiServer {
+                uri: options.server,
+                version: ApiVersion::LATEST,
+            }
+        };
+
         let client = KrillClient {
-            server: options.server,
+            server,
             token: options.token,
         };
 
@@ -124,21 +206,231 @@ impl KrillClient {
             env::set_var(KRILL_CLI_API_ENV, "1")
         }
 
-        trace!("Sending command: {:?}", options.command);
-
         match options.command {
-            Command::Health => client.health().await,
-            Command::Info => client.info().await,
-            Command::Bulk(cmd) => client.bulk(cmd).await,
-            Command::CertAuth(cmd) => client.certauth(cmd).await,
-            Command::PubServer(cmd) => client.publishers(cmd).await,
-            Command::Init(details) => client.init_config(details),
+            Command::Shell => client.shell(options.format, options.profile).await,
+            command => client.execute(command).await,
+        }
+    }
+
+    async fn execute(&self, command: Command) -> Result<ApiResponse, Error> {
+        trace!("Sending command: {:?}", command);
+
+        match command {
+            Command::Health => self.health().await,
+            Command::Info => self.info().await,
+            Command::Reload => self.reload().await,
+            Command::Doctor => self.doctor().await,
+            Command::Schema => self.schema().await,
+            Command::Search(query) => self.search(query).await,
+            Command::Bulk(cmd) => self.bulk(cmd).await,
+            Command::Backup(cmd) => self.backup(cmd).await,
+            Command::Ha(cmd) => self.ha(cmd).await,
+            Command::Time(cmd) => self.time(cmd).await,
+            Command::Keys(cmd) => self.keys(cmd).await,
+            Command::Jobs(cmd) => self.jobs(cmd).await,
+            Command::Tasks(cmd) => self.tasks(cmd).await,
+            Command::CertAuth(cmd) => self.certauth(cmd).await,
+            Command::PubServer(cmd) => self.publishers(cmd).await,
+            Command::Init(details) => self.init_config(details),
             #[cfg(feature = "multi-user")]
-            Command::User(cmd) => client.user(cmd),
+            Command::User(cmd) => self.user(cmd),
+            Command::Apply(state, dry_run) => self.apply(state, dry_run).await,
+            Command::Xml(cmd) => Self::xml(cmd),
+            Command::Completions(shell) => Ok(ApiResponse::GenericBody(Options::completions(shell))),
+            Command::Shell => Err(Error::input("Already in the interactive shell")),
             Command::NotSet => Err(Error::MissingCommand),
         }
     }
 
+    /// Runs commands entered on stdin one by one, until 'exit' or the end of
+    /// input. The server, its API version and the token are kept for all
+    /// commands, so only the format may be changed per command.
+    async fn shell(&self, format: ReportFormat, profile: Option<String>) -> Result<ApiResponse, Error> {
+        // The token is needed to parse commands, but need not be repeated.
+        // The format of the shell is used, unless given for a command, and
+        // so is its profile, e.g. for the default CA.
+        env::set_var(KRILL_CLI_TOKEN_ENV, self.token.as_ref());
+        env::set_var(KRILL_CLI_FORMAT_ENV, format.to_string());
+        if let Some(profile) = profile {
+            env::set_var(KRILL_CLI_PROFILE_ENV, profile);
+        }
+
+        let stdin = io::stdin();
+        let mut line = String::new();
+
+        loop {
+            print!("{}> ", KRILL_CLIENT_BIN);
+            io::stdout()
+                .flush()
+                .map_err(|e| Error::input(format!("Cannot write prompt: {}", e)))?;
+
+            line.clear();
+            let read = stdin
+                .lock()
+                .read_line(&mut line)
+                .map_err(|e| Error::input(format!("Cannot read command: {}", e)))?;
+            if read == 0 {
+                println!();
+                break;
+            }
+
+            let words = match split_words(&line) {
+                Ok(words) => words,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+            };
+
+            match words.first().map(String::as_str) {
+                None => continue,
+                Some("exit") | Some("quit") => break,
+                _ => {}
+            }
+
+            let options = match Options::from_words(words) {
+                Ok(options) => options,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+            };
+
+            match self.execute(options.command).await {
+                Ok(res) => match res.report(options.format) {
+                    Ok(Some(string)) => println!("{}", string),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("{}", e),
+                },
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+
+        Ok(ApiResponse::Empty)
+    }
+
+    /// Brings the CAs to their desired state, or only reports the changes
+    /// needed for this in a dry run. CAs which are not described are left
+    /// alone. If a change cannot be applied, the remaining changes are not
+    /// applied, and the error reports which changes were.
+    async fn apply(&self, state: DesiredState, dry_run: bool) -> Result<ApiResponse, Error> {
+        let list: CertAuthList = get_json(&self.server, &self.token, "api/v1/cas").await?;
+        let existing: HashSet<&CaHandle> = list.cas().iter().map(|ca| ca.handle()).collect();
+
+        let mut changes = vec![];
+        for desired in state.cas() {
+            let current = if existing.contains(desired.handle()) {
+                Some(self.current_ca(desired.handle()).await?)
+            } else {
+                None
+            };
+
+            let ca_changes = CaChanges::plan(desired, current.as_ref());
+            if !ca_changes.is_empty() {
+                changes.push(ca_changes);
+            }
+        }
+
+        let mut failure = None;
+        if !dry_run {
+            for ca_changes in &changes {
+                if let Err(e) = self.apply_ca_changes(ca_changes).await {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let report = ApplyReport::new(dry_run, changes, failure);
+        if report.failure().is_some() {
+            Err(Error::ApplyFailed(report))
+        } else {
+            Ok(ApiResponse::ApplyReport(report))
+        }
+    }
+
+    async fn current_ca(&self, ca: &CaHandle) -> Result<CurrentCa, Error> {
+        let info: CertAuthInfo = get_json(&self.server, &self.token, &format!("api/v1/cas/{}", ca)).await?;
+
+        let repository = if info.repo_info().is_some() {
+            let uri = format!("api/v1/cas/{}/repo", ca);
+            let details: CaRepoDetails = get_json(&self.server, &self.token, &uri).await?;
+            Some(details.contact().clone())
+        } else {
+            None
+        };
+
+        let mut parents = HashMap::new();
+        for parent in info.parents() {
+            let uri = format!("api/v1/cas/{}/parents/{}", ca, parent.handle());
+            let contact = get_json(&self.server, &self.token, &uri).await?;
+            parents.insert(parent.handle().clone(), contact);
+        }
+
+        let uri = format!("api/v1/cas/{}/routes", ca);
+        let roas: ConfiguredRoas = get_json(&self.server, &self.token, &uri).await?;
+        let roas = roas
+            .unpack()
+            .into_iter()
+            .map(|roa| roa.roa_configuration().clone())
+            .collect();
+
+        let uri = format!("api/v1/cas/{}/aspas", ca);
+        let aspas: AspaDefinitionList = get_json(&self.server, &self.token, &uri).await?;
+
+        Ok(CurrentCa {
+            repository,
+            parents,
+            roas,
+            aspas: aspas.unpack(),
+        })
+    }
+
+    /// Applies the changes for a CA. The repository is set before parents
+    /// are added, so that the CA can publish when it gets its resources.
+    async fn apply_ca_changes(&self, changes: &CaChanges) -> Result<(), ApplyFailure> {
+        let ca = changes.ca();
+        let mut progress = ApplyProgress::new(ca.clone());
+
+        if changes.add_ca() {
+            let res = post_json(&self.server, &self.token, "api/v1/cas", CertAuthInit::new(ca.clone())).await;
+            progress.step("add CA", res)?;
+        }
+
+        if let Some(response) = changes.repository() {
+            let uri = format!("api/v1/cas/{}/repo", ca);
+            let contact = ApiRepositoryContact::new(response.clone());
+            let res = post_json(&self.server, &self.token, &uri, contact).await;
+            progress.step("set repository", res)?;
+        }
+
+        for parent in changes.parents_add_or_update() {
+            let uri = format!("api/v1/cas/{}/parents", ca);
+            let res = post_json(&self.server, &self.token, &uri, parent).await;
+            progress.step(format!("add or update parent '{}'", parent.handle()), res)?;
+        }
+
+        for parent in changes.parents_remove() {
+            let uri = format!("api/v1/cas/{}/parents/{}", ca, parent);
+            let res = delete(&self.server, &self.token, &uri).await;
+            progress.step(format!("remove parent '{}'", parent), res)?;
+        }
+
+        if !changes.roas().is_empty() {
+            let uri = format!("api/v1/cas/{}/routes", ca);
+            let res = post_json(&self.server, &self.token, &uri, changes.roas()).await;
+            progress.step("update ROAs", res)?;
+        }
+
+        if !changes.aspas().is_empty() {
+            let uri = format!("api/v1/cas/{}/aspas", ca);
+            let res = post_json(&self.server, &self.token, &uri, changes.aspas()).await;
+            progress.step("update ASPAs", res)?;
+        }
+
+        Ok(())
+    }
+
     async fn health(&self) -> Result<ApiResponse, Error> {
         httpclient::get_ok(&resolve_uri(&self.server, "api/v1/authorized"), Some(&self.token)).await?;
         Ok(ApiResponse::Health)
@@ -149,6 +441,116 @@ impl KrillClient {
         Ok(ApiResponse::Info(info))
     }
 
+    async fn reload(&self) -> Result<ApiResponse, Error> {
+        let report = post_empty_with_response(&self.server, &self.token, "api/v1/admin/reload").await?;
+        Ok(ApiResponse::ConfigReload(report))
+    }
+
+    async fn schema(&self) -> Result<ApiResponse, Error> {
+        let versions = get_json(&self.server, &self.token, "api/v1/admin/schema").await?;
+        Ok(ApiResponse::SchemaVersions(versions))
+    }
+
+    /// Inspects RFC 8183 XML files, without contacting the server.
+    fn xml(cmd: XmlCommand) -> Result<ApiResponse, Error> {
+        match cmd {
+            XmlCommand::Show(xml) => Ok(match xml {
+                Rfc8183Xml::ChildRequest(req) => ApiResponse::Rfc8183ChildRequest(req),
+                Rfc8183Xml::ParentResponse(res) => ApiResponse::Rfc8183ParentResponse(res),
+                Rfc8183Xml::PublisherRequest(req) => ApiResponse::Rfc8183PublisherRequest(req),
+                Rfc8183Xml::RepositoryResponse(res) => ApiResponse::Rfc8183RepositoryResponse(res),
+            }),
+            XmlCommand::Validate(xml) => {
+                let summary = xml.summary();
+                match summary.invalid() {
+                    None => Ok(ApiResponse::XmlSummary(summary)),
+                    Some(e) => Err(Error::input(format!("Invalid {}: {}", xml.kind(), e))),
+                }
+            }
+            XmlCommand::Certs(xml) => xml
+                .id_cert()
+                .map(|cert| ApiResponse::XmlIdCert(XmlIdCert::from(&cert)))
+                .map_err(Error::input),
+            XmlCommand::Diff(left, right) => Ok(ApiResponse::XmlDiff(XmlDiff::new(&left.summary(), &right.summary()))),
+        }
+    }
+
+    async fn search(&self, query: SearchQuery) -> Result<ApiResponse, Error> {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("q", &query.to_string())
+            .finish();
+        let uri = format!("api/v1/search?{}", query);
+        let results: SearchResults = get_json(&self.server, &self.token, &uri).await?;
+        Ok(ApiResponse::SearchResults(results))
+    }
+
+    /// Gets the findings of the server, and adds checks of the HTTPS
+    /// certificate and the server clock as seen from this client.
+    async fn doctor(&self) -> Result<ApiResponse, Error> {
+        let before = Timestamp::now();
+        let mut report: DoctorReport = get_json(&self.server, &self.token, "api/v1/admin/doctor").await?;
+        let after = Timestamp::now();
+
+        report.add(self.doctor_https_certificate());
+        report.add(Self::doctor_clock_skew(report.server_time(), before, after));
+
+        Ok(ApiResponse::Doctor(report))
+    }
+
+    fn doctor_https_certificate(&self) -> DoctorFinding {
+        const CHECK: &str = "https-certificate";
+
+        let uri = match url::Url::parse(&self.server.uri.to_string()) {
+            Ok(uri) if uri.scheme() == "https" => uri,
+            _ => return DoctorFinding::info(CHECK, format!("not checked, {} does not use HTTPS", self.server)),
+        };
+        let host = uri.host_str().unwrap_or("localhost");
+        let port = uri.port_or_known_default().unwrap_or(443);
+
+        match https_certificate_days_left(host, port) {
+            Err(e) => DoctorFinding::error(
+                CHECK,
+                format!("cannot get the certificate of {}:{}: {}", host, port, e),
+                "check that the server can be reached on this address",
+            ),
+            Ok(days) if days < 0 => DoctorFinding::error(
+                CHECK,
+                format!("certificate expired {} days ago", -days),
+                "replace the certificate, or let Krill get one using ACME",
+            ),
+            Ok(days) if days < DOCTOR_CERT_WARN_DAYS => DoctorFinding::warning(
+                CHECK,
+                format!("certificate expires in {} days", days),
+                "replace the certificate, or let Krill get one using ACME",
+            ),
+            Ok(days) => DoctorFinding::ok(CHECK, format!("certificate is valid for {} more days", days)),
+        }
+    }
+
+    /// Compares the server time with the time on this client around the
+    /// request, so that a slow response is not mistaken for clock skew.
+    fn doctor_clock_skew(server: Timestamp, before: Timestamp, after: Timestamp) -> DoctorFinding {
+        const CHECK: &str = "clock-skew";
+        let action = "synchronize the clocks using NTP, relying parties reject objects which are not yet valid";
+
+        let (server, before, after) = (i64::from(server), i64::from(before), i64::from(after));
+        let skew = if server < before {
+            before - server
+        } else if server > after {
+            server - after
+        } else {
+            0
+        };
+
+        if skew > DOCTOR_CLOCK_ERROR_SECS {
+            DoctorFinding::error(CHECK, format!("server clock is {} seconds off", skew), action)
+        } else if skew > DOCTOR_CLOCK_WARN_SECS {
+            DoctorFinding::warning(CHECK, format!("server clock is {} seconds off", skew), action)
+        } else {
+            DoctorFinding::ok(CHECK, "server clock agrees with this client")
+        }
+    }
+
     async fn bulk(&self, command: BulkCaCommand) -> Result<ApiResponse, Error> {
         match command {
             BulkCaCommand::Refresh => {
@@ -169,10 +571,125 @@ impl KrillClient {
             BulkCaCommand::Import(structure) => {
                 post_json(&self.server, &self.token, "api/v1/bulk/cas/import", structure).await?;
             }
+            BulkCaCommand::Migrate(migration, true) => {
+                let uri = "api/v1/bulk/cas/migrate?background=true";
+                let job = post_json_with_response(&self.server, &self.token, uri, migration).await?;
+                return Ok(ApiResponse::Job(job));
+            }
+            BulkCaCommand::Migrate(migration, false) => {
+                let uri = "api/v1/bulk/cas/migrate";
+                let report = post_json_with_response(&self.server, &self.token, uri, migration).await?;
+                return Ok(ApiResponse::CaMigration(report));
+            }
+            BulkCaCommand::Snapshot => {
+                let uri = "api/v1/bulk/snapshots";
+                let snapshots = post_empty_with_response(&self.server, &self.token, uri).await?;
+                return Ok(ApiResponse::AggregateSnapshots(snapshots));
+            }
+            BulkCaCommand::VerifySnapshots => {
+                let uri = "api/v1/bulk/snapshots/verify";
+                let snapshots = get_json(&self.server, &self.token, uri).await?;
+                return Ok(ApiResponse::AggregateSnapshots(snapshots));
+            }
+            BulkCaCommand::JobStart(request) => {
+                let uri = "api/v1/bulk/jobs";
+                let job = post_json_with_response(&self.server, &self.token, uri, request).await?;
+                return Ok(ApiResponse::Job(job));
+            }
         }
         Ok(ApiResponse::Empty)
     }
 
+    async fn backup(&self, command: BackupCommand) -> Result<ApiResponse, Error> {
+        match command {
+            BackupCommand::Create => {
+                let info = post_empty_with_response(&self.server, &self.token, "api/v1/backups").await?;
+                Ok(ApiResponse::BackupInfo(info))
+            }
+            BackupCommand::List => {
+                let list = get_json(&self.server, &self.token, "api/v1/backups").await?;
+                Ok(ApiResponse::BackupList(list))
+            }
+            BackupCommand::Restore(name, force) => {
+                let uri = if force {
+                    format!("api/v1/backups/{}/restore?force=true", name)
+                } else {
+                    format!("api/v1/backups/{}/restore", name)
+                };
+                let info = post_empty_with_response(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::BackupInfo(info))
+            }
+        }
+    }
+
+    async fn ha(&self, command: HaCommand) -> Result<ApiResponse, Error> {
+        let status = match command {
+            HaCommand::Status => get_json(&self.server, &self.token, "api/v1/ha").await?,
+            HaCommand::Promote => post_empty_with_response(&self.server, &self.token, "api/v1/ha/promote").await?,
+        };
+        Ok(ApiResponse::HaStatus(status))
+    }
+
+    async fn time(&self, command: TimeCommand) -> Result<ApiResponse, Error> {
+        let check = match command {
+            TimeCommand::Status => get_json(&self.server, &self.token, "api/v1/time").await?,
+            TimeCommand::Check => post_empty_with_response(&self.server, &self.token, "api/v1/time/check").await?,
+        };
+        Ok(ApiResponse::TimeCheck(check))
+    }
+
+    async fn keys(&self, command: KeysCommand) -> Result<ApiResponse, Error> {
+        match command {
+            KeysCommand::List => {
+                let inventory = get_json(&self.server, &self.token, "api/v1/admin/keys").await?;
+                Ok(ApiResponse::KeyInventory(inventory))
+            }
+            KeysCommand::Gc(confirm) => {
+                let uri = format!("api/v1/admin/keys/gc?confirm={}", confirm);
+                let report = post_empty_with_response(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::KeyGc(report))
+            }
+        }
+    }
+
+    async fn jobs(&self, command: JobsCommand) -> Result<ApiResponse, Error> {
+        match command {
+            JobsCommand::List => {
+                let list = get_json(&self.server, &self.token, "api/v1/jobs").await?;
+                Ok(ApiResponse::JobList(list))
+            }
+            JobsCommand::Show(id) => {
+                let uri = format!("api/v1/jobs/{}", id);
+                let job = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Job(job))
+            }
+        }
+    }
+
+    async fn tasks(&self, command: TaskCommand) -> Result<ApiResponse, Error> {
+        match command {
+            TaskCommand::List => {
+                let list = get_json(&self.server, &self.token, "api/v1/tasks").await?;
+                Ok(ApiResponse::TaskList(list))
+            }
+            TaskCommand::Trigger(name) => {
+                let uri = format!("api/v1/tasks/{}/trigger", name);
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+            TaskCommand::Pause(name) => {
+                let uri = format!("api/v1/tasks/{}/pause", name);
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+            TaskCommand::Resume(name) => {
+                let uri = format!("api/v1/tasks/{}/resume", name);
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+        }
+    }
+
     #[allow(clippy::cognitive_complexity)]
     async fn certauth(&self, command: CaCommand) -> Result<ApiResponse, Error> {
         match command {
@@ -181,8 +698,12 @@ impl KrillClient {
                 Ok(ApiResponse::Empty)
             }
 
-            CaCommand::Delete(ca) => {
-                let uri = format!("api/v1/cas/{}", ca);
+            CaCommand::Delete(ca, force) => {
+                let uri = if force {
+                    format!("api/v1/cas/{}?force=true", ca)
+                } else {
+                    format!("api/v1/cas/{}", ca)
+                };
                 delete(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::Empty)
             }
@@ -211,6 +732,25 @@ impl KrillClient {
                 Ok(ApiResponse::Rfc8183PublisherRequest(req))
             }
 
+            CaCommand::RepoIdRollInit(handle) => {
+                let uri = format!("api/v1/cas/{}/repo/id/init", handle);
+                let req: idexchange::PublisherRequest =
+                    post_empty_with_response(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Rfc8183PublisherRequest(req))
+            }
+
+            CaCommand::RepoIdRollRequest(handle) => {
+                let uri = format!("api/v1/cas/{}/repo/id/publisher_request.json", handle);
+                let req: idexchange::PublisherRequest = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Rfc8183PublisherRequest(req))
+            }
+
+            CaCommand::RepoIdRollActivate(handle) => {
+                let uri = format!("api/v1/cas/{}/repo/id/activate", handle);
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+
             CaCommand::RepoDetails(handle) => {
                 let uri = format!("api/v1/cas/{}/repo", handle);
                 let details: CaRepoDetails = get_json(&self.server, &self.token, &uri).await?;
@@ -223,6 +763,138 @@ impl KrillClient {
                 Ok(ApiResponse::RepoStatus(status))
             }
 
+            CaCommand::RepoStatuses(ca) => {
+                let uri = format!("api/v1/cas/{}/repo/statuses", ca);
+                let statuses: RepoStatuses = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RepoStatuses(statuses))
+            }
+
+            CaCommand::ClassRepoList(ca) => {
+                let uri = format!("api/v1/cas/{}/repo/classes", ca);
+                let details: ClassRepoDetails = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ClassRepoDetails(details))
+            }
+
+            CaCommand::ClassRepoUpdate(ca, rcn, update) => {
+                let uri = format!("api/v1/cas/{}/repo/classes/{}", ca, rcn);
+                match update {
+                    Some(update) => {
+                        let api_contact = ApiRepositoryContact::new(update);
+                        post_json(&self.server, &self.token, &uri, api_contact).await?;
+                    }
+                    None => delete(&self.server, &self.token, &uri).await?,
+                }
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RepoMigrationStatus(ca) => {
+                let uri = format!("api/v1/cas/{}/repo/migration", ca);
+                let status: RepoMigrationStatus = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RepoMigrationStatus(status))
+            }
+
+            CaCommand::RepoMigrationActivate(ca) => {
+                let uri = format!("api/v1/cas/{}/repo/migration/activate", ca);
+                let status: RepoMigrationStatus = post_empty_with_response(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RepoMigrationStatus(status))
+            }
+
+            CaCommand::RepoMigrationAbort(ca) => {
+                let uri = format!("api/v1/cas/{}/repo/migration/abort", ca);
+                let status: RepoMigrationStatus = post_empty_with_response(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RepoMigrationStatus(status))
+            }
+
+            CaCommand::RepoMigrate(ca, response) => {
+                let uri = format!("api/v1/cas/{}/repo/migrate", ca);
+                let api_contact = ApiRepositoryContact::new(response);
+                let report: RepoMigrationReport =
+                    post_json_with_response(&self.server, &self.token, &uri, api_contact).await?;
+                Ok(ApiResponse::RepoMigrationReport(report))
+            }
+
+            CaCommand::RepoCheck(ca) => {
+                let uri = format!("api/v1/cas/{}/repo/check", ca);
+                let check: PublicationCheck = post_empty_with_response(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::PublicationCheck(check))
+            }
+
+            CaCommand::IssuanceTimingShow(ca) => {
+                let uri = format!("api/v1/cas/{}/timing", ca);
+                let overrides: IssuanceTimingOverrides = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::IssuanceTiming(overrides))
+            }
+
+            CaCommand::IssuanceTimingEffective(ca) => {
+                let uri = format!("api/v1/cas/{}/timing/effective", ca);
+                let report: IssuanceTimingReport = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::IssuanceTimingReport(report))
+            }
+
+            CaCommand::IssuanceTimingUpdate(ca, overrides) => {
+                let uri = format!("api/v1/cas/{}/timing", ca);
+                post_json(&self.server, &self.token, &uri, overrides).await?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RetryPolicyShow(ca) => {
+                let uri = format!("api/v1/cas/{}/retry", ca);
+                let policies: RetryPolicies = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RetryPolicies(policies))
+            }
+
+            CaCommand::RetryPolicyUpdate(ca, parent, policy) => {
+                let uri = match parent {
+                    None => format!("api/v1/cas/{}/retry/repo", ca),
+                    Some(parent) => format!("api/v1/cas/{}/retry/parents/{}", ca, parent),
+                };
+                post_json(&self.server, &self.token, &uri, policy).await?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::LabelsShow(ca) => {
+                let uri = format!("api/v1/cas/{}/labels", ca);
+                let labels: CaLabels = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::CaLabels(labels))
+            }
+
+            CaCommand::LabelsUpdate(ca, update) => {
+                let uri = format!("api/v1/cas/{}/labels", ca);
+                post_json(&self.server, &self.token, &uri, update).await?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::ChildTransferStart(handle, request) => {
+                let uri = format!("api/v1/cas/{}/transfers", handle);
+                post_json(&self.server, &self.token, &uri, request).await?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::ChildTransfers(handle) => {
+                let uri = format!("api/v1/cas/{}/transfers", handle);
+                let transfers: ResourceTransferList = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ResourceTransfers(transfers))
+            }
+            CaCommand::ChildTransferShow(handle, id) => {
+                let uri = format!("api/v1/cas/{}/transfers/{}", handle, id);
+                let transfer: ResourceTransfer = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ResourceTransfer(transfer))
+            }
+
+            CaCommand::ScheduledChangeAdd(handle, request) => {
+                let uri = format!("api/v1/cas/{}/scheduled", handle);
+                post_json(&self.server, &self.token, &uri, request).await?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::ScheduledChanges(handle) => {
+                let uri = format!("api/v1/cas/{}/scheduled", handle);
+                let changes: ScheduledChangeList = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ScheduledChanges(changes))
+            }
+            CaCommand::ScheduledChangeCancel(handle, id) => {
+                let uri = format!("api/v1/cas/{}/scheduled/{}", handle, id);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+
             CaCommand::RepoUpdate(handle, update) => {
                 let uri = format!("api/v1/cas/{}/repo", handle);
                 let api_contact = ApiRepositoryContact::new(update);
@@ -236,6 +908,12 @@ impl KrillClient {
                 Ok(ApiResponse::Empty)
             }
 
+            CaCommand::AddParentFetch(handle, parent, fetch) => {
+                let uri = format!("api/v1/cas/{}/parents/{}/fetch", handle, parent);
+                post_json(&self.server, &self.token, &uri, fetch).await?;
+                Ok(ApiResponse::Empty)
+            }
+
             CaCommand::RemoveParent(handle, parent) => {
                 let uri = format!("api/v1/cas/{}/parents/{}", handle, parent);
                 delete(&self.server, &self.token, &uri).await?;
@@ -247,6 +925,16 @@ impl KrillClient {
                 let statuses: ParentStatuses = get_json(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::ParentStatuses(statuses))
             }
+            CaCommand::ParentStats(handle) => {
+                let uri = format!("api/v1/cas/{}/stats/parents", handle);
+                let stats: ParentsStats = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ParentsReport(stats))
+            }
+            CaCommand::ParentExchanges(handle, parent) => {
+                let uri = format!("api/v1/cas/{}/parents/{}/exchanges", handle, parent);
+                let exchanges: ParentExchangeLog = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ParentExchanges(exchanges))
+            }
 
             CaCommand::MyParentCaContact(handle, parent) => {
                 let uri = format!("api/v1/cas/{}/parents/{}", handle, parent);
@@ -286,6 +974,34 @@ impl KrillClient {
                 let stats: ChildrenConnectionStats = get_json(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::ChildrenStats(stats))
             }
+            CaCommand::ChildStats(handle) => {
+                let uri = format!("api/v1/cas/{}/stats/children", handle);
+                let stats: ChildrenStats = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ChildrenReport(stats))
+            }
+            CaCommand::ChildRequests(handle, child) => {
+                let uri = format!("api/v1/cas/{}/children/{}/requests", handle, child);
+                let requests: PendingChildRequests = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ChildRequests(requests))
+            }
+            CaCommand::ChildRequestApprove(handle, child, kind, key) => {
+                let uri = format!(
+                    "api/v1/cas/{}/children/{}/requests/{}/approve?kind={}",
+                    handle, child, key, kind
+                );
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::ChildIssuancePolicyShow(handle) => {
+                let uri = format!("api/v1/cas/{}/child_policy", handle);
+                let policy: ChildIssuancePolicy = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ChildIssuancePolicy(policy))
+            }
+            CaCommand::ChildIssuancePolicyUpdate(handle, policy) => {
+                let uri = format!("api/v1/cas/{}/child_policy", handle);
+                post_json(&self.server, &self.token, &uri, policy).await?;
+                Ok(ApiResponse::Empty)
+            }
 
             CaCommand::KeyRollInit(handle) => {
                 let uri = format!("api/v1/cas/{}/keys/roll_init", handle);
@@ -297,6 +1013,16 @@ impl KrillClient {
                 post_empty(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::Empty)
             }
+            CaCommand::KeyRollSigners(handle) => {
+                let uri = format!("api/v1/cas/{}/keys/signers", handle);
+                let status: SignerMigrationStatus = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::SignerMigrationStatus(status))
+            }
+            CaCommand::KeyRollMigrate(handle) => {
+                let uri = format!("api/v1/cas/{}/keys/migrate", handle);
+                let status: SignerMigrationStatus = post_empty_with_response(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::SignerMigrationStatus(status))
+            }
 
             CaCommand::RouteAuthorizationsList(handle) => {
                 let uri = format!("api/v1/cas/{}/routes", handle);
@@ -304,8 +1030,12 @@ impl KrillClient {
                 Ok(ApiResponse::RouteAuthorizations(roas))
             }
 
-            CaCommand::RouteAuthorizationsUpdate(handle, updates) => {
-                let uri = format!("api/v1/cas/{}/routes", handle);
+            CaCommand::RouteAuthorizationsUpdate(handle, updates, force) => {
+                let uri = if force {
+                    format!("api/v1/cas/{}/routes?force=true", handle)
+                } else {
+                    format!("api/v1/cas/{}/routes", handle)
+                };
                 post_json(&self.server, &self.token, &uri, updates).await?;
                 Ok(ApiResponse::Empty)
             }
@@ -326,6 +1056,76 @@ impl KrillClient {
                 Ok(ApiResponse::BgpAnalysisFull(report))
             }
 
+            CaCommand::RouteAuthorizationsImport(handle, import, dry_run, force) => {
+                let uri = if dry_run {
+                    format!("api/v1/cas/{}/routes/import?dryrun=true", handle)
+                } else if force {
+                    format!("api/v1/cas/{}/routes/import?force=true", handle)
+                } else {
+                    format!("api/v1/cas/{}/routes/import", handle)
+                };
+                let report = post_json_with_response(&self.server, &self.token, &uri, import).await?;
+                Ok(ApiResponse::RoaImportReport(report))
+            }
+
+            CaCommand::RouteAuthorizationsPropose(handle, updates) => {
+                let uri = format!("api/v1/cas/{}/routes/proposals", handle);
+                post_json(&self.server, &self.token, &uri, updates).await?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RouteAuthorizationsProposals(handle) => {
+                let uri = format!("api/v1/cas/{}/routes/proposals", handle);
+                let proposals = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RoaProposals(proposals))
+            }
+
+            CaCommand::RoaStrategyShow(handle) => {
+                let uri = format!("api/v1/cas/{}/routes/strategy", handle);
+                let info = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RoaIssuanceStrategy(info))
+            }
+
+            CaCommand::RoaAutoPilotShow(handle) => {
+                let uri = format!("api/v1/cas/{}/routes/autopilot", handle);
+                let info = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RoaAutoPilot(info))
+            }
+
+            CaCommand::RoaAutoPilotUpdate(handle, policy) => {
+                let uri = format!("api/v1/cas/{}/routes/autopilot", handle);
+                match policy {
+                    Some(policy) => post_json(&self.server, &self.token, &uri, policy).await?,
+                    None => delete(&self.server, &self.token, &uri).await?,
+                }
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RoaStrategyUpdate(handle, strategy) => {
+                let uri = format!("api/v1/cas/{}/routes/strategy", handle);
+                match strategy {
+                    Some(strategy) => post_json(&self.server, &self.token, &uri, strategy).await?,
+                    None => delete(&self.server, &self.token, &uri).await?,
+                }
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RouteAuthorizationsApprove(handle, id, force) => {
+                let uri = if force {
+                    format!("api/v1/cas/{}/routes/proposals/{}/approve?force=true", handle, id)
+                } else {
+                    format!("api/v1/cas/{}/routes/proposals/{}/approve", handle, id)
+                };
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RouteAuthorizationsReject(handle, id) => {
+                let uri = format!("api/v1/cas/{}/routes/proposals/{}/reject", handle, id);
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+
             CaCommand::BgpAnalysisFull(handle) => {
                 let uri = format!("api/v1/cas/{}/routes/analysis/full", handle);
                 let report = get_json(&self.server, &self.token, &uri).await?;
@@ -384,12 +1184,43 @@ impl KrillClient {
                 Ok(ApiResponse::Empty)
             }
 
+            CaCommand::AspasImport(handle, definitions) => {
+                let uri = format!("api/v1/cas/{}/aspas/import", handle);
+                let updates = post_json_with_response(&self.server, &self.token, &uri, definitions).await?;
+                Ok(ApiResponse::AspaDefinitionUpdates(updates))
+            }
+
+            CaCommand::AspasExport(handle, csv, out) => {
+                let uri = format!("api/v1/cas/{}/aspas", handle);
+                let definitions: AspaDefinitionList = get_json(&self.server, &self.token, &uri).await?;
+
+                match out {
+                    Some(out) => {
+                        let content = if csv {
+                            definitions.to_csv()
+                        } else {
+                            serde_json::to_string_pretty(&definitions).unwrap()
+                        };
+                        file::save(content.as_bytes(), &out)?;
+                        Ok(ApiResponse::Empty)
+                    }
+                    None if csv => Ok(ApiResponse::GenericBody(definitions.to_csv())),
+                    None => Ok(ApiResponse::AspaDefinitions(definitions)),
+                }
+            }
+
             CaCommand::AspasUpdate(handle, customer, update) => {
                 let uri = format!("api/v1/cas/{}/aspas/as/{}", handle, customer);
                 post_json(&self.server, &self.token, &uri, update).await?;
                 Ok(ApiResponse::Empty)
             }
 
+            CaCommand::AspasUpdateDryRun(handle, customer, update) => {
+                let uri = format!("api/v1/cas/{}/aspas/as/{}?dryrun=true", handle, customer);
+                let dry_run = post_json_with_response(&self.server, &self.token, &uri, update).await?;
+                Ok(ApiResponse::AspaUpdateDryRun(dry_run))
+            }
+
             CaCommand::Show(handle) => {
                 let uri = format!("api/v1/cas/{}", handle);
                 let ca_info = get_json(&self.server, &self.token, &uri).await?;
@@ -399,9 +1230,10 @@ impl KrillClient {
 
             CaCommand::ShowHistoryCommands(handle, options) => {
                 let uri = format!(
-                    "api/v1/cas/{}/history/commands/{}",
+                    "api/v1/cas/{}/history/commands/{}{}",
                     handle,
-                    options.url_path_parameters()
+                    options.url_path_parameters(),
+                    options.url_query_parameters()
                 );
                 let history = get_json(&self.server, &self.token, &uri).await?;
 
@@ -415,6 +1247,17 @@ impl KrillClient {
                 Ok(ApiResponse::CertAuthAction(action))
             }
 
+            CaCommand::RevertHistoryCommand(handle, key, force) => {
+                let uri = if force {
+                    format!("api/v1/cas/{}/history/revert/{}?force=true", handle, key)
+                } else {
+                    format!("api/v1/cas/{}/history/revert/{}", handle, key)
+                };
+                let revert = post_empty_with_response(&self.server, &self.token, &uri).await?;
+
+                Ok(ApiResponse::CommandRevert(revert))
+            }
+
             CaCommand::Issues(ca_opt) => match ca_opt {
                 Some(ca) => {
                     let uri = format!("api/v1/cas/{}/issues", ca);
@@ -428,6 +1271,55 @@ impl KrillClient {
                 }
             },
 
+            CaCommand::Expiry(ca, window_hours) => {
+                let uri = match window_hours {
+                    None => format!("api/v1/cas/{}/expiry", ca),
+                    Some(hours) => format!("api/v1/cas/{}/expiry?window_hours={}", ca, hours),
+                };
+                let expiry: ObjectsExpiry = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ObjectsExpiry(expiry))
+            }
+
+            CaCommand::Reconcile(ca, repair) => {
+                let uri = format!("api/v1/cas/{}/reconcile?repair={}", ca, repair);
+                let reconciliation: Reconciliation = post_empty_with_response(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Reconciliation(reconciliation))
+            }
+
+            CaCommand::ReconcileLast(ca) => {
+                let uri = format!("api/v1/cas/{}/reconcile", ca);
+                let reconciliation: Reconciliation = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Reconciliation(reconciliation))
+            }
+
+            CaCommand::Export(ca, full, include_keys, out) => {
+                let uri = format!("api/v1/cas/{}/export?full={}&keys={}", ca, full, include_keys);
+                let export: CaMigration = get_json(&self.server, &self.token, &uri).await?;
+
+                match out {
+                    Some(out) => {
+                        let content = serde_json::to_string_pretty(&export).unwrap();
+                        file::save(content.as_bytes(), &out)?;
+                        Ok(ApiResponse::Empty)
+                    }
+                    None => Ok(ApiResponse::CaExport(export)),
+                }
+            }
+
+            CaCommand::RscSign(ca, request, out) => {
+                let uri = format!("api/v1/cas/{}/rsc", ca);
+                let rsc: RpkiSignedChecklist =
+                    post_json_with_response(&self.server, &self.token, &uri, request).await?;
+
+                match out {
+                    None => Ok(ApiResponse::Rsc(rsc)),
+                    Some(out) => {
+                        file::save(rsc.as_ref(), &out)?;
+                        Ok(ApiResponse::Empty)
+                    }
+                }
+            }
+
             CaCommand::RtaList(ca) => {
                 let uri = format!("api/v1/cas/{}/rta/", ca);
                 let list = get_json(&self.server, &self.token, &uri).await?;
@@ -465,8 +1357,17 @@ impl KrillClient {
                 Ok(ApiResponse::Empty)
             }
 
-            CaCommand::List => {
-                let cas = get_json(&self.server, &self.token, "api/v1/cas").await?;
+            CaCommand::List(label) => {
+                let uri = match label {
+                    None => "api/v1/cas".to_string(),
+                    Some(label) => {
+                        let query = url::form_urlencoded::Serializer::new(String::new())
+                            .append_pair("label", &label.to_string())
+                            .finish();
+                        format!("api/v1/cas?{}", query)
+                    }
+                };
+                let cas = get_json(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::CertAuths(cas))
             }
         }
@@ -490,6 +1391,10 @@ impl KrillClient {
                 let stats = get_json(&self.server, &self.token, "stats/repo").await?;
                 Ok(ApiResponse::RepoStats(stats))
             }
+            PubServerCommand::RrdpStats => {
+                let stats = get_json(&self.server, &self.token, "api/v1/pubd/rrdp").await?;
+                Ok(ApiResponse::RrdpStats(stats))
+            }
             PubServerCommand::RepositoryInit(uris) => {
                 let uri = "api/v1/pubd/init";
                 post_json(&self.server, &self.token, uri, uris).await?;
@@ -505,6 +1410,24 @@ impl KrillClient {
                 post_empty(&self.server, &self.token, uri).await?;
                 Ok(ApiResponse::Empty)
             }
+            PubServerCommand::RepositoryArchiveList => {
+                let list = get_json(&self.server, &self.token, "api/v1/pubd/archive").await?;
+                Ok(ApiResponse::RepositoryArchiveList(list))
+            }
+            PubServerCommand::RepositoryArchiveCreate => {
+                let uri = "api/v1/pubd/archive";
+                let entry = post_empty_with_response(&self.server, &self.token, uri).await?;
+                Ok(ApiResponse::RepositoryArchiveEntry(entry))
+            }
+            PubServerCommand::RepositoryReplicaStatus => {
+                let status = get_json(&self.server, &self.token, "api/v1/pubd/replica").await?;
+                Ok(ApiResponse::RepositoryReplicaStatus(status))
+            }
+            PubServerCommand::RepositoryReplicaSync => {
+                let uri = "api/v1/pubd/replica";
+                let status = post_empty_with_response(&self.server, &self.token, uri).await?;
+                Ok(ApiResponse::RepositoryReplicaStatus(status))
+            }
             PubServerCommand::AddPublisher(req) => {
                 let res = post_json_with_response(&self.server, &self.token, "api/v1/pubd/publishers", req).await?;
                 Ok(ApiResponse::Rfc8183RepositoryResponse(res))
@@ -529,6 +1452,56 @@ impl KrillClient {
                 let res = get_json(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::Rfc8183RepositoryResponse(res))
             }
+            PubServerCommand::ShowPublisherStats(handle) => match handle {
+                Some(handle) => {
+                    let uri = format!("api/v1/pubd/publishers/{}/stats", handle);
+                    let stats = get_json(&self.server, &self.token, &uri).await?;
+                    Ok(ApiResponse::PublisherStats(stats))
+                }
+                None => {
+                    let list = get_json(&self.server, &self.token, "api/v1/pubd/stats/publishers").await?;
+                    Ok(ApiResponse::PublisherStatsList(list))
+                }
+            },
+            PubServerCommand::ShowPublisherQuota(handle) => {
+                let uri = format!("api/v1/pubd/publishers/{}/quota", handle);
+                let quota = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::PublisherQuota(quota))
+            }
+            PubServerCommand::UpdatePublisherQuota(handle, quota) => {
+                let uri = format!("api/v1/pubd/publishers/{}/quota", handle);
+                match quota {
+                    Some(quota) => post_json(&self.server, &self.token, &uri, quota).await?,
+                    None => delete(&self.server, &self.token, &uri).await?,
+                }
+                Ok(ApiResponse::Empty)
+            }
+            PubServerCommand::ShowPublisherValidation(handle) => {
+                let uri = format!("api/v1/pubd/publishers/{}/validation", handle);
+                let validation = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::PublisherValidation(validation))
+            }
+            PubServerCommand::UpdatePublisherValidation(handle, validation) => {
+                let uri = format!("api/v1/pubd/publishers/{}/validation", handle);
+                match validation {
+                    Some(validation) => post_json(&self.server, &self.token, &uri, validation).await?,
+                    None => delete(&self.server, &self.token, &uri).await?,
+                }
+                Ok(ApiResponse::Empty)
+            }
+            PubServerCommand::StagePublisherIdCert(handle, req) => {
+                let uri = format!("api/v1/pubd/publishers/{}/id_cert", handle);
+                match req {
+                    Some(req) => post_json(&self.server, &self.token, &uri, req).await?,
+                    None => delete(&self.server, &self.token, &uri).await?,
+                }
+                Ok(ApiResponse::Empty)
+            }
+            PubServerCommand::ActivatePublisherIdCert(handle) => {
+                let uri = format!("api/v1/pubd/publishers/{}/id_cert/activate", handle);
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
         }
     }
 
@@ -646,6 +1619,92 @@ impl KrillClient {
     }
 }
 
+//------------ split_words ---------------------------------------------------
+
+/// Splits a command entered in the interactive shell into words, like a
+/// shell would. Words may be quoted with single or double quotes, and a
+/// backslash escapes the next character outside single quotes.
+fn split_words(line: &str) -> Result<Vec<String>, Error> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| Error::input("Unfinished escape at end of line"))?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'') | (None, '"') => {
+                word.get_or_insert_with(String::new);
+                quote = Some(c);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(Error::input("Unmatched quote"));
+    }
+    words.extend(word);
+
+    Ok(words)
+}
+
+//------------ Doctor --------------------------------------------------------
+
+/// Warn about HTTPS certificates which expire within this many days.
+const DOCTOR_CERT_WARN_DAYS: i32 = 14;
+
+/// Warn, or report an error, if the server clock is off by more than this.
+const DOCTOR_CLOCK_WARN_SECS: i64 = 30;
+const DOCTOR_CLOCK_ERROR_SECS: i64 = 300;
+
+/// Returns the number of days until the certificate of an HTTPS server
+/// expires. The certificate is not verified, so that self-signed certificates
+/// can be checked as well.
+fn https_certificate_days_left(host: &str, port: u16) -> Result<i32, String> {
+    use openssl::{
+        asn1::Asn1Time,
+        ssl::{SslConnector, SslMethod, SslVerifyMode},
+    };
+
+    let timeout = Duration::from_secs(HTTP_CLIENT_TIMEOUT_SECS);
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| "no address found".to_string())?;
+    let stream = TcpStream::connect_timeout(&addr, timeout).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+
+    let mut connector = SslConnector::builder(SslMethod::tls_client()).map_err(|e| e.to_string())?;
+    connector.set_verify(SslVerifyMode::NONE);
+    let stream = connector
+        .build()
+        .configure()
+        .map_err(|e| e.to_string())?
+        .verify_hostname(false)
+        .connect(host, stream)
+        .map_err(|e| e.to_string())?;
+
+    let cert = stream
+        .ssl()
+        .peer_certificate()
+        .ok_or_else(|| "no certificate received".to_string())?;
+    let now = Asn1Time::days_from_now(0).map_err(|e| e.to_string())?;
+    now.diff(cert.not_after())
+        .map(|diff| diff.days)
+        .map_err(|e| e.to_string())
+}
+
 //------------ Error ---------------------------------------------------------
 
 #[derive(Debug)]
@@ -660,6 +1719,8 @@ pub enum Error {
     Rfc8183(idexchange::Error),
     InitError(String),
     InputError(String),
+    ApiVersionUnsupported,
+    ApplyFailed(ApplyReport),
 }
 
 impl fmt::Display for Error {
@@ -674,6 +1735,8 @@ impl fmt::Display for Error {
             Error::Rfc8183(e) => e.fmt(f),
             Error::InitError(s) => s.fmt(f),
             Error::InputError(s) => s.fmt(f),
+            Error::ApiVersionUnsupported => write!(f, "The server does not support any API version known to krillc"),
+            Error::ApplyFailed(report) => report.fmt(f),
         }
     }
 }
@@ -682,6 +1745,10 @@ impl Error {
     fn init(msg: impl fmt::Display) -> Self {
         Error::InitError(msg.to_string())
     }
+
+    fn input(msg: impl fmt::Display) -> Self {
+        Error::InputError(msg.to_string())
+    }
 }
 
 impl From<httpclient::Error> for Error {
@@ -713,10 +1780,81 @@ impl From<idexchange::Error> for Error {
 #[cfg(test)]
 mod tests {
 
+    use std::str::FromStr;
+
     use super::*;
-    use crate::cli::options::KrillInitDetails;
+    use crate::cli::options::{CompletionShell, KrillInitDetails};
+    use crate::commons::api::FindingSeverity;
     use crate::test;
 
+    #[test]
+    fn resolve_uri_for_api_version() {
+        let server = ApiServer {
+            uri: test::service_uri("https://localhost:3001/"),
+            version: ApiVersion::V1,
+        };
+        assert_eq!(resolve_uri(&server, "api/v1/cas"), "https://localhost:3001/api/v1/cas");
+        assert_eq!(resolve_uri(&server, "stats/info"), "https://localhost:3001/stats/info");
+    }
+
+    #[test]
+    fn split_shell_words() {
+        let words = split_words("roas update --ca 'my ca' --add \"10.0.0.0/8 => 64496\"\n").unwrap();
+        assert_eq!(
+            words,
+            vec!["roas", "update", "--ca", "my ca", "--add", "10.0.0.0/8 => 64496"]
+        );
+
+        assert_eq!(split_words("  list  ").unwrap(), vec!["list"]);
+        assert_eq!(split_words("a\\ b ''").unwrap(), vec!["a b", ""]);
+        assert!(split_words("list 'oops").is_err());
+    }
+
+    #[test]
+    fn completions_complete_ca_handles() {
+        let list_cas = "$(krillc list --format text 2>/dev/null)";
+
+        let bash = Options::completions(CompletionShell::Bash);
+        assert!(bash.contains("_krillc() {"));
+        assert!(bash.contains(list_cas));
+        assert!(bash.contains("[[ \"${prev}\" == \"--ca\" || \"${prev}\" == \"-c\" ]]"));
+        assert!(bash.ends_with("complete -F _krillc_with_cas -o bashdefault -o default krillc\n"));
+
+        // The --ca and -c options complete CA handles, using a function that
+        // is defined before the completion function is called.
+        let zsh = Options::completions(CompletionShell::Zsh);
+        let ca_options: Vec<&str> = zsh
+            .lines()
+            .filter(|line| line.contains("'-c+[") || line.contains("'--ca=["))
+            .collect();
+        assert!(!ca_options.is_empty());
+        assert!(ca_options.iter().all(|line| line.ends_with("]: :_krillc_cas' \\")));
+
+        let cas_fn = zsh.find("_krillc_cas() {").unwrap();
+        let call = zsh.rfind("_krillc \"$@\"").unwrap();
+        assert!(cas_fn < call);
+        assert!(zsh[cas_fn..call].contains(list_cas));
+
+        let fish = Options::completions(CompletionShell::Fish);
+        assert!(fish.contains("complete -c krillc -n \"__fish_use_subcommand\""));
+        assert!(fish.ends_with("complete -c krillc -s c -l ca -x -a '(krillc list --format text 2>/dev/null)'\n"));
+
+        assert!(CompletionShell::from_str("powershell").is_err());
+    }
+
+    #[test]
+    fn doctor_clock_skew_ignores_response_time() {
+        let skew = |server: i64| {
+            KrillClient::doctor_clock_skew(Timestamp::new(server), Timestamp::new(1000), Timestamp::new(1100))
+                .severity()
+        };
+
+        assert_eq!(skew(1050), FindingSeverity::Ok);
+        assert_eq!(skew(1110), FindingSeverity::Ok);
+        assert_eq!(skew(940), FindingSeverity::Warning);
+        assert_eq!(skew(1500), FindingSeverity::Error);
+    }
+
     #[test]
     fn init_config_file() {
         let mut details = KrillInitDetails::default();
@@ -724,7 +1862,10 @@ mod tests {
         details.with_log_file("/var/log/krill/krill.log");
 
         let client = KrillClient {
-            server: test::service_uri("https://localhost:3001/"),
+            server: ApiServer {
+                uri: test::service_uri("https://localhost:3001/"),
+                version: ApiVersion::LATEST,
+            },
             token: Token::from("secret"),
         };
 
@@ -748,7 +1889,10 @@ mod tests {
         details.with_log_file("/var/log/krill/krill.log");
 
         let client = KrillClient {
-            server: test::service_uri("https://localhost:3001/"),
+            server: ApiServer {
+                uri: test::service_uri("https://localhost:3001/"),
+                version: ApiVersion::LATEST,
+            },
             token: Token::from("secret"),
         };
 
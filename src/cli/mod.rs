@@ -1,5 +1,8 @@
+pub mod apply;
 pub mod options;
+pub mod profile;
 pub mod report;
+pub mod xml;
 
 mod client;
 pub use self::client::Error;
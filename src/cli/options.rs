@@ -2,13 +2,14 @@
 use std::collections::HashMap;
 
 use std::{
+    collections::BTreeMap,
     path::PathBuf,
-    str::{from_utf8_unchecked, FromStr},
+    str::{from_utf8, from_utf8_unchecked, FromStr},
     {env, fmt},
 };
 
 use bytes::Bytes;
-use clap::{App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches, Shell, SubCommand};
 
 use rpki::{
     ca::{
@@ -16,6 +17,7 @@ use rpki::{
         idcert::IdCert,
         idexchange,
         idexchange::{CaHandle, ChildHandle, ParentHandle, PublisherHandle},
+        provisioning::ResourceClassName,
     },
     crypto::KeyIdentifier,
     repository::{
@@ -27,20 +29,29 @@ use rpki::{
 };
 
 use crate::{
-    cli::report::{ReportError, ReportFormat},
+    cli::{
+        apply::DesiredState,
+        profile::{self, Profile},
+        report::{ReportError, ReportFormat},
+        xml::Rfc8183Xml,
+    },
     commons::{
         api::{
-            self, AddChildRequest, AspaCustomer, AspaDefinition, AspaDefinitionFormatError, AspaProvidersUpdate,
-            AuthorizationFmtError, BgpSecAsnKey, BgpSecDefinition, CertAuthInit, ParentCaReq, PublicationServerUris,
-            RepoFileDeleteCriteria, RoaConfiguration, RoaConfigurationUpdates, RoaPayload, RtaName, Token,
-            UpdateChildRequest,
+            self, AddChildRequest, AspaCustomer, AspaDefinition, AspaDefinitionFormatError, AspaDefinitionList,
+            AspaDefinitionUpdates, AspaProvidersUpdate, AuthorizationFmtError, BgpSecAsnKey, BgpSecDefinition,
+            BulkJobRequest, BulkOperation, CaLabelSelector, CaLabelsUpdate, CaSelector, CertAuthInit,
+            ChildIssuancePolicy, HistoryOrder, IssuanceTimingOverrides, ParentCaReq, ParentResponseFetch,
+            PendingChildRequestKind, PublicationServerUris, PublisherQuota, PublisherValidation,
+            RepoFileDeleteCriteria, ResourceTransferRequest, RetryPolicy, RoaAutoPilotPolicy, RoaConfiguration,
+            RoaConfigurationUpdates, RoaImport, RoaImportFormat, RoaIssuanceStrategy, RoaPayload, RtaName,
+            ScheduledChangeRequest, ScheduledUpdate, SearchQuery, Timestamp, Token, UpdateChildRequest,
         },
         crypto::SignSupport,
         error::KrillIoError,
         util::file,
     },
     constants::*,
-    daemon::ca::{ResourceTaggedAttestation, RtaContentRequest, RtaPrepareRequest},
+    daemon::ca::{ResourceTaggedAttestation, RscContentRequest, RscFileHash, RtaContentRequest, RtaPrepareRequest},
 };
 
 #[derive(Debug)]
@@ -49,6 +60,7 @@ pub struct GeneralArgs {
     pub token: Token,
     pub format: ReportFormat,
     pub api: bool,
+    pub profile: Option<String>,
 }
 
 impl GeneralArgs {
@@ -61,6 +73,18 @@ impl GeneralArgs {
                 .help("The full URI to the Krill server. Or set env: KRILL_CLI_SERVER")
                 .required(false),
         )
+        .arg(
+            Arg::with_name(KRILL_CLI_PROFILE_ARG)
+                .long(KRILL_CLI_PROFILE_ARG)
+                .value_name("name")
+                .help(
+                    "The profile in ~/.krillc/config to take the server, token and CA from, \
+                     unless given as arguments. Or set env: KRILL_CLI_PROFILE, but then \
+                     the KRILL_CLI_SERVER, KRILL_CLI_TOKEN and KRILL_CLI_MY_CA env \
+                     variables take precedence",
+                )
+                .required(false),
+        )
         .arg(
             Arg::with_name(KRILL_CLI_ADMIN_TOKEN_ARG)
                 .short("t")
@@ -69,14 +93,7 @@ impl GeneralArgs {
                 .help("The secret token for the Krill server. Or set env: KRILL_CLI_TOKEN")
                 .required(false),
         )
-        .arg(
-            Arg::with_name(KRILL_CLI_FORMAT_ARG)
-                .short("f")
-                .long(KRILL_CLI_FORMAT_ARG)
-                .value_name("type")
-                .help("Report format: none|json|text (default). Or set env: KRILL_CLI_FORMAT")
-                .required(false),
-        )
+        .arg(Self::format_arg())
         .arg(
             Arg::with_name(KRILL_CLI_API_ARG)
                 .long(KRILL_CLI_API_ARG)
@@ -85,52 +102,74 @@ impl GeneralArgs {
         )
     }
 
-    pub fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
-        let server = {
-            let mut server = match env::var(KRILL_CLI_SERVER_ENV) {
-                Ok(server_str) => Some(idexchange::ServiceUri::from_str(&server_str)?),
-                Err(_) => None,
-            };
-
-            if let Some(server_str) = matches.value_of(KRILL_CLI_SERVER_ARG) {
-                server = Some(idexchange::ServiceUri::from_str(server_str)?);
-            }
-
-            server.unwrap_or_else(|| idexchange::ServiceUri::from_str(KRILL_CLI_SERVER_DFLT).unwrap())
-        };
+    /// Adds only the report format, for commands which work offline.
+    pub fn add_format_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(Self::format_arg())
+    }
 
-        let token = {
-            let mut token = env::var(KRILL_CLI_TOKEN_ENV).ok().map(Token::from);
+    fn format_arg<'a, 'b>() -> Arg<'a, 'b> {
+        Arg::with_name(KRILL_CLI_FORMAT_ARG)
+            .short("f")
+            .long(KRILL_CLI_FORMAT_ARG)
+            .value_name("type")
+            .help("Report format: none|json|yaml|table|text (default). Or set env: KRILL_CLI_FORMAT")
+            .required(false)
+    }
 
-            if let Some(token_str) = matches.value_of(KRILL_CLI_ADMIN_TOKEN_ARG) {
-                token = Some(Token::from(token_str));
-            }
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
+        let profile = Profile::select(matches)?.unwrap_or_default();
 
-            token.ok_or_else(|| Error::missing_arg_with_env(KRILL_CLI_ADMIN_TOKEN_ARG, KRILL_CLI_TOKEN_ENV))?
+        let server = match setting(matches, KRILL_CLI_SERVER_ARG, KRILL_CLI_SERVER_ENV, &profile.server) {
+            Some(server_str) => idexchange::ServiceUri::from_str(&server_str)?,
+            None => idexchange::ServiceUri::from_str(KRILL_CLI_SERVER_DFLT).unwrap(),
         };
 
-        let format = {
-            let mut format = match env::var(KRILL_CLI_FORMAT_ENV) {
-                Ok(fmt_str) => Some(ReportFormat::from_str(&fmt_str)?),
-                Err(_) => None,
-            };
-
-            if let Some(fmt_str) = matches.value_of(KRILL_CLI_FORMAT_ARG) {
-                format = Some(ReportFormat::from_str(fmt_str)?);
-            }
+        let token = setting(matches, KRILL_CLI_ADMIN_TOKEN_ARG, KRILL_CLI_TOKEN_ENV, &profile.token)
+            .map(Token::from)
+            .ok_or_else(|| Error::missing_arg_with_env(KRILL_CLI_ADMIN_TOKEN_ARG, KRILL_CLI_TOKEN_ENV))?;
 
-            format.unwrap_or(ReportFormat::Text)
-        };
+        let format = Self::format_from_matches(matches)?;
 
         let api = env::var(KRILL_CLI_API_ENV).is_ok() || matches.is_present(KRILL_CLI_API_ARG);
 
+        let profile = matches
+            .value_of(KRILL_CLI_PROFILE_ARG)
+            .map(str::to_string)
+            .or_else(|| env::var(KRILL_CLI_PROFILE_ENV).ok());
+
         Ok(GeneralArgs {
             server,
             token,
             format,
             api,
+            profile,
         })
     }
+
+    pub fn format_from_matches(matches: &ArgMatches) -> Result<ReportFormat, Error> {
+        let mut format = match env::var(KRILL_CLI_FORMAT_ENV) {
+            Ok(fmt_str) => Some(ReportFormat::from_str(&fmt_str)?),
+            Err(_) => None,
+        };
+
+        if let Some(fmt_str) = matches.value_of(KRILL_CLI_FORMAT_ARG) {
+            format = Some(ReportFormat::from_str(fmt_str)?);
+        }
+
+        Ok(format.unwrap_or(ReportFormat::Text))
+    }
+}
+
+/// Returns the value of a setting given on the command line, in the
+/// environment, or in the selected profile. A profile selected on the
+/// command line takes precedence over the environment.
+fn setting(matches: &ArgMatches, arg: &str, env_var: &str, profile_value: &Option<String>) -> Option<String> {
+    profile::resolve_setting(
+        matches.value_of(arg),
+        env::var(env_var).ok(),
+        profile_value.as_deref(),
+        matches.is_present(KRILL_CLI_PROFILE_ARG),
+    )
 }
 
 impl Default for GeneralArgs {
@@ -140,6 +179,7 @@ impl Default for GeneralArgs {
             token: Token::from(""),
             format: ReportFormat::Text,
             api: false,
+            profile: None,
         }
     }
 }
@@ -151,6 +191,7 @@ pub struct Options {
     pub token: Token,
     pub format: ReportFormat,
     pub api: bool,
+    pub profile: Option<String>,
     pub command: Command,
 }
 
@@ -161,6 +202,7 @@ impl Options {
             token: general.token,
             format: general.format,
             api: general.api,
+            profile: general.profile,
             command,
         }
     }
@@ -176,17 +218,28 @@ impl Options {
             token: Token::from(token),
             format,
             api: false,
+            profile: None,
             command,
         }
     }
 
+    fn add_activate_at_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("at")
+                .long("at")
+                .value_name("RFC 3339 time")
+                .help("Schedule the change to be applied at this time, e.g. 2024-06-01T02:00:00Z")
+                .required(false),
+        )
+    }
+
     fn add_my_ca_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         app.arg(
             Arg::with_name(KRILL_CLI_MY_CA_ARG)
                 .value_name("name")
                 .short("c")
                 .long(KRILL_CLI_MY_CA_ARG)
-                .help("The name of the CA you wish to control. Or set env: KRILL_CLI_MY_CA")
+                .help("The name of the CA you wish to control. Or set env: KRILL_CLI_MY_CA, or use a profile")
                 .required(false),
         )
     }
@@ -316,6 +369,13 @@ impl Options {
         let sub = SubCommand::with_name("list").about("List the current CAs");
 
         let sub = GeneralArgs::add_args(sub);
+        let sub = sub.arg(
+            Arg::with_name("label")
+                .long("label")
+                .value_name("selector")
+                .help("Select the CAs with matching labels, e.g. 'customer=acme,env=prod', or 'env' for CAs with that label set")
+                .required(false),
+        );
 
         app.subcommand(sub)
     }
@@ -346,6 +406,25 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_history_revert_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("revert").about("Revert the ROA or ASPA changes of a command in the history of a CA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("key")
+                .long("key")
+                .value_name("command key string")
+                .help("The command key as shown in 'history commands'")
+                .required(true),
+        );
+        sub = Self::add_roas_force_arg(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_show_history_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("commands").about("Show the commands sent to a CA");
 
@@ -384,6 +463,48 @@ impl Options {
                 .required(false),
         );
 
+        sub = sub.arg(
+            Arg::with_name("actor")
+                .long("actor")
+                .help("Show only commands sent by this user or actor, e.g. joe@example.com or admin-token")
+                .value_name("<actor>")
+                .required(false),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("archived")
+                .long("archived")
+                .help("Include commands from the history archive. This can be slow.")
+                .required(false),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("type")
+                .long("type")
+                .help("Show only commands of this type, e.g. cmd-ca-roas-updated. Can be given multiple times.")
+                .value_name("<type>")
+                .multiple(true)
+                .number_of_values(1)
+                .required(false),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("order")
+                .long("order")
+                .help("Show the oldest (asc) or the most recent (desc) commands first, defaults to asc")
+                .value_name("<asc|desc>")
+                .possible_values(&["asc", "desc"])
+                .required(false),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("cursor")
+                .long("cursor")
+                .help("Show the commands following this cursor, as shown at the end of a previous page")
+                .value_name("<cursor>")
+                .required(false),
+        );
+
         app.subcommand(sub)
     }
 
@@ -392,6 +513,7 @@ impl Options {
 
         sub = Self::make_cas_show_history_list_sc(sub);
         sub = Self::make_cas_show_history_details_sc(sub);
+        sub = Self::make_cas_history_revert_sc(sub);
 
         app.subcommand(sub)
     }
@@ -407,10 +529,16 @@ impl Options {
 
     fn make_cas_delete_ca_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("delete")
-            .about("Delete a CA and let it withdraw its objects and request revocation. WARNING: Irreversible!");
+            .about("Delete a CA and let it revoke its children, withdraw its objects and request revocation. WARNING: Irreversible!");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("force")
+                .long("force")
+                .help("Delete the CA even if its parents or repository cannot be reached")
+                .required(false),
+        );
 
         app.subcommand(sub)
     }
@@ -448,6 +576,22 @@ impl Options {
                 .value_name("DER encoded certificate")
                 .required(false),
         );
+        sub = sub.arg(
+            Arg::with_name("suspension_exempt")
+                .long("suspension-exempt")
+                .help("Exempt the child from automatic suspension when it is inactive")
+                .value_name("true|false")
+                .possible_values(&["true", "false"])
+                .required(false),
+        );
+        sub = sub.arg(
+            Arg::with_name("manual_approval")
+                .long("manual-approval")
+                .help("Hold back all issuance and revocation requests from the child until they are approved")
+                .value_name("true|false")
+                .possible_values(&["true", "false"])
+                .required(false),
+        );
 
         app.subcommand(sub)
     }
@@ -491,6 +635,16 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_children_stats_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("stats")
+            .about("Show resources, last contact and outstanding revocations for children of a CA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_children_suspend_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("suspend").about("Suspend a child CA: hide certificate(s) issued to child");
 
@@ -512,6 +666,166 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_children_requests_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list").about("List the requests from a child awaiting approval");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_child_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_requests_approve_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("approve")
+            .about("Approve a pending request from a child, it is honored when the child asks again");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_child_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("key")
+                .long("key")
+                .value_name("key identifier")
+                .help("The key identifier of the pending request")
+                .required(true),
+        );
+        sub = sub.arg(
+            Arg::with_name("kind")
+                .long("kind")
+                .value_name("kind")
+                .possible_values(&["issue", "revoke"])
+                .default_value("issue")
+                .help("The kind of the pending request"),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_requests_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("requests").about("Manage requests from a child awaiting approval");
+
+        sub = Self::make_cas_children_requests_list_sc(sub);
+        sub = Self::make_cas_children_requests_approve_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_policy_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show").about("Show the policy enforced before issuing to children");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_policy_update_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("update")
+            .about("Replace the policy enforced before issuing to children. Values which are not set are disabled");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("max-validity-weeks")
+                .long("max-validity-weeks")
+                .value_name("number")
+                .help("Maximum validity time in weeks for certificates issued to children")
+                .required(false),
+        );
+        sub = sub.arg(
+            Arg::with_name("deny-asn-only")
+                .long("deny-asn-only")
+                .help("Refuse to issue certificates to children which would only contain ASNs")
+                .required(false),
+        );
+        sub = sub.arg(
+            Arg::with_name("approve-new-keys")
+                .long("approve-new-keys")
+                .help("Hold back requests for new child keys until they are approved")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_policy_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("policy").about("Manage the policy for issuing certificates to children");
+
+        sub = Self::make_cas_children_policy_show_sc(sub);
+        sub = Self::make_cas_children_policy_update_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_transfer_start_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("start")
+            .about("Move resources from one child to another, once the first child got certificates without them");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("from")
+                .long("from")
+                .value_name("name")
+                .help("The name of the child giving up the resources")
+                .required(true),
+        );
+        sub = sub.arg(
+            Arg::with_name("to")
+                .long("to")
+                .value_name("name")
+                .help("The name of the child receiving the resources")
+                .required(true),
+        );
+        sub = Self::add_resource_args(sub);
+        sub = sub.arg(
+            Arg::with_name("timeout-hours")
+                .long("timeout-hours")
+                .value_name("number")
+                .help("Hours to wait for re-issuance before the transfer is rolled back (default 24)")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_transfer_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list").about("List the resource transfers between children");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_transfer_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show").about("Show a resource transfer between children");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("id")
+                .long("id")
+                .value_name("number")
+                .help("The id of the transfer")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_transfer_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("transfer").about("Manage resource transfers between children");
+
+        sub = Self::make_cas_children_transfer_start_sc(sub);
+        sub = Self::make_cas_children_transfer_list_sc(sub);
+        sub = Self::make_cas_children_transfer_show_sc(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_children_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("children").about("Manage children for a CA");
 
@@ -521,8 +835,12 @@ impl Options {
         sub = Self::make_cas_children_remove_sc(sub);
         sub = Self::make_cas_children_response_sc(sub);
         sub = Self::make_cas_children_connections_sc(sub);
+        sub = Self::make_cas_children_stats_sc(sub);
         sub = Self::make_cas_children_suspend_sc(sub);
         sub = Self::make_cas_children_unsuspend_sc(sub);
+        sub = Self::make_cas_children_requests_sc(sub);
+        sub = Self::make_cas_children_policy_sc(sub);
+        sub = Self::make_cas_children_transfer_sc(sub);
 
         app.subcommand(sub)
     }
@@ -546,10 +864,17 @@ impl Options {
             Arg::with_name("response")
                 .long("response")
                 .short("r")
-                .help("The location of the RFC 8183 Parent Response XML file")
-                .value_name("<XML file>")
+                .help("The location of the RFC 8183 Parent Response XML file, or an HTTPS URI from which Krill can fetch it")
+                .value_name("<XML file or URI>")
                 .required(true),
         );
+        sub = sub.arg(
+            Arg::with_name("response_token")
+                .long("response-token")
+                .help("Bearer token to use when Krill fetches the Parent Response XML from an HTTPS URI")
+                .value_name("token")
+                .required(false),
+        );
 
         app.subcommand(sub)
     }
@@ -573,6 +898,27 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_parents_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show")
+            .about("Show the recent exchanges with a parent of a CA, to help diagnose issues");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_parent_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_parents_stats_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("stats")
+            .about("Show entitled and received resources, expiry and pending requests per parent of a CA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_parents_remove_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("remove").about("Remove an existing parent from a CA");
 
@@ -590,6 +936,8 @@ impl Options {
         sub = Self::make_cas_parents_add_sc(sub);
         sub = Self::make_cas_parents_contact_sc(sub);
         sub = Self::make_cas_parents_statuses_sc(sub);
+        sub = Self::make_cas_parents_show_sc(sub);
+        sub = Self::make_cas_parents_stats_sc(sub);
         sub = Self::make_cas_parents_remove_sc(sub);
 
         app.subcommand(sub)
@@ -613,11 +961,32 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_keyroll_signers_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("signers").about("Show which signer holds each key of a CA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_keyroll_migrate_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("migrate")
+            .about("Roll keys held by another signer than the default signer. Repeat until complete.");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_keyroll_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("keyroll").about("Perform a manual key rollover for a CA");
 
         sub = Self::make_cas_keyroll_init_sc(sub);
         sub = Self::make_cas_keyroll_activate_sc(sub);
+        sub = Self::make_cas_keyroll_signers_sc(sub);
+        sub = Self::make_cas_keyroll_migrate_sc(sub);
 
         app.subcommand(sub)
     }
@@ -648,6 +1017,8 @@ impl Options {
                     "A: 192.168.0.0/16 => 64496 # inline comment\n",
                     "A: 192.168.1.0/24 => 64496\n",
                     "R: 192.168.3.0/24 => 64496\n",
+                    "D: 192.168.4.0/24 => 64496 # keep, but do not issue a ROA\n",
+                    "E: 192.168.5.0/24 => 64496 # issue a ROA again\n",
                 ))
                 .value_name("<file>")
                 .required(false),
@@ -671,6 +1042,24 @@ impl Options {
                 .required(false),
         );
 
+        sub = sub.arg(
+            Arg::with_name("disable")
+                .long("disable")
+                .help("One or more ROAs to keep, but for which no ROA objects should be issued")
+                .value_name("<roa definition>")
+                .multiple(true)
+                .required(false),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("enable")
+                .long("enable")
+                .help("One or more disabled ROAs for which ROA objects should be issued again")
+                .value_name("<roa definition>")
+                .multiple(true)
+                .required(false),
+        );
+
         sub = sub.arg(
             Arg::with_name("dryrun")
                 .long("dryrun")
@@ -685,6 +1074,16 @@ impl Options {
                 .required(false),
         );
 
+        sub = sub.arg(
+            Arg::with_name("propose")
+                .long("propose")
+                .help("Propose the update, it will only be applied after it has been approved")
+                .required(false),
+        );
+
+        sub = Self::add_roas_force_arg(sub);
+        sub = Self::add_activate_at_arg(sub);
+
         app.subcommand(sub)
     }
 
@@ -733,23 +1132,237 @@ impl Options {
         app.subcommand(sub)
     }
 
-    fn make_cas_routes_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("roas").about("Manage ROAs for a CA");
-
-        sub = Self::make_cas_routes_list_sc(sub);
-        sub = Self::make_cas_routes_update_sc(sub);
-        sub = Self::make_cas_routes_bgp_sc(sub);
-
-        app.subcommand(sub)
-    }
-
-    fn make_cas_bgpsec_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("list").about("Show current BGPSec configurations");
+    fn make_cas_routes_import_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("import").about("Import RPSL route objects, or CSV, as ROAs");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
 
-        app.subcommand(sub)
+        sub = sub.arg(
+            Arg::with_name("rpsl")
+                .long("rpsl")
+                .help("File with RPSL route and route6 objects, e.g. exported from an IRR")
+                .value_name("<file>")
+                .required_unless("csv")
+                .conflicts_with("csv"),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("csv")
+                .long("csv")
+                .help(concat!(
+                    "File with ROAs using the following CSV format, max length is optional:\n",
+                    "# prefix,origin,maxlength\n",
+                    "192.168.0.0/16,AS64496,24\n",
+                ))
+                .value_name("<file>")
+                .required_unless("rpsl"),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("dryrun")
+                .long("dryrun")
+                .help("Only show the ROAs that would be added, and conflicts with existing ROAs")
+                .required(false),
+        );
+
+        sub = Self::add_roas_force_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_proposals_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list").about("Show pending ROA proposals");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_proposals_decide_sc<'a, 'b>(app: App<'a, 'b>, name: &'a str, about: &'a str) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name(name).about(about);
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("id")
+                .long("id")
+                .help("The id of the proposal")
+                .value_name("number")
+                .required(true),
+        );
+
+        if name == "approve" {
+            sub = Self::add_roas_force_arg(sub);
+        }
+
+        app.subcommand(sub)
+    }
+
+    fn add_roas_force_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("force")
+                .long("force")
+                .help("Apply the update even if it would invalidate announcements seen in BGP")
+                .required(false),
+        )
+    }
+
+    fn make_cas_routes_proposals_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("proposals").about("Manage proposed ROA updates");
+
+        sub = Self::make_cas_routes_proposals_list_sc(sub);
+        sub = Self::make_cas_routes_proposals_decide_sc(sub, "approve", "Approve a proposal and issue its ROAs");
+        sub = Self::make_cas_routes_proposals_decide_sc(sub, "reject", "Reject a proposal");
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_strategy_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("strategy").about("Manage how ROA prefixes are grouped into ROA objects");
+
+        let mut show = SubCommand::with_name("show").about("Show the ROA issuance strategy in use");
+        show = GeneralArgs::add_args(show);
+        show = Self::add_my_ca_arg(show);
+        sub = sub.subcommand(show);
+
+        let mut update = SubCommand::with_name("update").about("Set the ROA issuance strategy and re-issue ROAs");
+        update = GeneralArgs::add_args(update);
+        update = Self::add_my_ca_arg(update);
+        update = update
+            .arg(
+                Arg::with_name("strategy")
+                    .long("strategy")
+                    .help("The strategy to use")
+                    .value_name("strategy")
+                    .possible_values(&["auto", "per_prefix", "per_asn", "grouped"])
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("max_prefixes")
+                    .long("max-prefixes")
+                    .help("The maximum number of prefixes per ROA, required for 'grouped'")
+                    .value_name("number")
+                    .required(false),
+            );
+        sub = sub.subcommand(update);
+
+        let mut reset = SubCommand::with_name("reset").about("Use the ROA issuance strategy from the server config");
+        reset = GeneralArgs::add_args(reset);
+        reset = Self::add_my_ca_arg(reset);
+        sub = sub.subcommand(reset);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_autopilot_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("autopilot").about("Manage the automatic authorization of BGP announcements");
+
+        let mut show = SubCommand::with_name("show").about("Show the ROA auto-pilot policy in use");
+        show = GeneralArgs::add_args(show);
+        show = Self::add_my_ca_arg(show);
+        sub = sub.subcommand(show);
+
+        let mut update = SubCommand::with_name("update").about("Set the ROA auto-pilot policy for this CA");
+        update = GeneralArgs::add_args(update);
+        update = Self::add_my_ca_arg(update);
+        update = update
+            .arg(
+                Arg::with_name("off")
+                    .long("off")
+                    .help("Turn the auto-pilot off for this CA, regardless of the server config")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("min_seen_hours")
+                    .long("min-seen-hours")
+                    .help("The number of hours an announcement must be seen before it is authorized")
+                    .value_name("hours")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("max_prefix_length_v4")
+                    .long("max-prefix-length-v4")
+                    .help("The longest IPv4 prefix that is authorized")
+                    .value_name("length")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("max_prefix_length_v6")
+                    .long("max-prefix-length-v6")
+                    .help("The longest IPv6 prefix that is authorized")
+                    .value_name("length")
+                    .required(false),
+            );
+        sub = sub.subcommand(update);
+
+        let mut reset = SubCommand::with_name("reset").about("Use the ROA auto-pilot policy from the server config");
+        reset = GeneralArgs::add_args(reset);
+        reset = Self::add_my_ca_arg(reset);
+        sub = sub.subcommand(reset);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("roas").about("Manage ROAs for a CA");
+
+        sub = Self::make_cas_routes_list_sc(sub);
+        sub = Self::make_cas_routes_update_sc(sub);
+        sub = Self::make_cas_routes_bgp_sc(sub);
+        sub = Self::make_cas_routes_import_sc(sub);
+        sub = Self::make_cas_routes_proposals_sc(sub);
+        sub = Self::make_cas_routes_strategy_sc(sub);
+        sub = Self::make_cas_routes_autopilot_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_scheduled_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list").about("List the pending scheduled ROA and ASPA changes");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_scheduled_cancel_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("cancel").about("Cancel a pending scheduled change");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("id")
+                .long("id")
+                .value_name("number")
+                .help("The id of the scheduled change")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_scheduled_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("scheduled").about("Manage ROA and ASPA changes scheduled with --at for a CA");
+
+        sub = Self::make_cas_scheduled_list_sc(sub);
+        sub = Self::make_cas_scheduled_cancel_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_bgpsec_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list").about("Show current BGPSec configurations");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
     }
 
     fn make_cas_bgpsec_add_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
@@ -814,6 +1427,54 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_rsc_sign_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("sign").about("Create an RPKI Signed Checklist (RSC) for files");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = Self::add_resource_args(sub);
+
+        sub = sub.arg(
+            Arg::with_name("days")
+                .long("days")
+                .short("d")
+                .value_name("number of days")
+                .help("Validity time of the RSC in days")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("file")
+                .long("file")
+                .short("f")
+                .value_name("path")
+                .multiple(true)
+                .number_of_values(1)
+                .help("File to include on the checklist, can be repeated")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("out")
+                .long("out")
+                .short("o")
+                .value_name("path")
+                .help("File to write the RSC to, e.g. 'geofeed.sig'")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_rsc_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("rsc").about("Manage RPKI Signed Checklists");
+
+        sub = Self::make_cas_rsc_sign_sc(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_aspas_add_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("add").about("Add or replace an ASPA configuration");
 
@@ -828,6 +1489,8 @@ impl Options {
                 .required(true),
         );
 
+        sub = Self::add_activate_at_arg(sub);
+
         app.subcommand(sub)
     }
 
@@ -845,6 +1508,8 @@ impl Options {
                 .required(true),
         );
 
+        sub = Self::add_activate_at_arg(sub);
+
         app.subcommand(sub)
     }
 
@@ -880,6 +1545,13 @@ impl Options {
                 .required(false),
         );
 
+        sub = sub.arg(
+            Arg::with_name("dryrun")
+                .long("dryrun")
+                .help("Perform a dry run of the update and show the ASPA objects that would change")
+                .required(false),
+        );
+
         app.subcommand(sub)
     }
 
@@ -892,6 +1564,61 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_aspas_import_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("import")
+            .about("Replace all ASPA configurations, only changed configurations are re-issued");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("file")
+                .long("file")
+                .short("f")
+                .value_name("path")
+                .help("File containing the complete set of ASPA configurations (JSON, or CSV with --csv)")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("csv")
+                .long("csv")
+                .help(concat!(
+                    "Read the file as CSV using the following format:\n",
+                    "# Some comment\n",
+                    "AS65000,AS65001,AS65002(v4),AS65003(v6)\n",
+                ))
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_aspas_export_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("export").about("Export all ASPA configurations");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("out")
+                .long("out")
+                .short("o")
+                .value_name("path")
+                .help("File to write the ASPA configurations to")
+                .required(false),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("csv")
+                .long("csv")
+                .help("Export as CSV rather than JSON")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_aspas_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("aspas").about("Manage ASPAs for a CA (experimental)");
 
@@ -899,12 +1626,364 @@ impl Options {
         sub = Self::make_cas_aspas_remove_sc(sub);
         sub = Self::make_cas_aspas_update_sc(sub);
         sub = Self::make_cas_aspas_list_sc(sub);
+        sub = Self::make_cas_aspas_import_sc(sub);
+        sub = Self::make_cas_aspas_export_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_request_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("request").about("Show RFC 8183 Publisher Request XML");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show").about("Show current repo config");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_status_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("status").about("Show current repo status");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_statuses_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("statuses").about("Show the status of each repository that a CA publishes to");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_check_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("check").about("Check the objects published by a CA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_configure_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("configure").about("Configure which repository a CA uses");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("response")
+                .value_name("file")
+                .long("response")
+                .short("r")
+                .help("The location of the RFC 8183 Publisher Response XML file")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_idroll_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("idroll").about("Roll the ID certificate used with the repository");
+
+        let mut init = SubCommand::with_name("init").about(
+            "Generate a new ID certificate and show the RFC 8183 Publisher Request XML to give to the repository",
+        );
+        init = GeneralArgs::add_args(init);
+        init = Self::add_my_ca_arg(init);
+        sub = sub.subcommand(init);
+
+        let mut request = SubCommand::with_name("request")
+            .about("Show the RFC 8183 Publisher Request XML for the new ID certificate");
+        request = GeneralArgs::add_args(request);
+        request = Self::add_my_ca_arg(request);
+        sub = sub.subcommand(request);
+
+        let mut activate = SubCommand::with_name("activate").about(
+            "Start using the new ID certificate. Note that it is also used with parents, \
+             which must be given a new RFC 8183 Child Request as well",
+        );
+        activate = GeneralArgs::add_args(activate);
+        activate = Self::add_my_ca_arg(activate);
+        sub = sub.subcommand(activate);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_classes_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("classes")
+            .about("Manage resource classes which publish to another repository than the CA");
+
+        let class_arg = Arg::with_name("class")
+            .long("class")
+            .value_name("name")
+            .help("The name of the resource class")
+            .required(true);
+
+        let mut list = SubCommand::with_name("list")
+            .about("Show the resource classes which publish to another repository than the CA");
+        list = GeneralArgs::add_args(list);
+        list = Self::add_my_ca_arg(list);
+        sub = sub.subcommand(list);
+
+        let mut configure = SubCommand::with_name("configure").about(
+            "Configure the repository for a resource class. This starts a key roll, the new key publishes to the new repository",
+        );
+        configure = GeneralArgs::add_args(configure);
+        configure = Self::add_my_ca_arg(configure);
+        configure = configure.arg(class_arg.clone());
+        configure = configure.arg(
+            Arg::with_name("response")
+                .value_name("file")
+                .long("response")
+                .short("r")
+                .help("The location of the RFC 8183 Publisher Response XML file")
+                .required(true),
+        );
+        sub = sub.subcommand(configure);
+
+        let mut reset = SubCommand::with_name("reset")
+            .about("Let a resource class publish to the repository of the CA again. This starts a key roll");
+        reset = GeneralArgs::add_args(reset);
+        reset = Self::add_my_ca_arg(reset);
+        reset = reset.arg(class_arg);
+        sub = sub.subcommand(reset);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_migration_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("migration").about("Follow up on a migration to another repository");
+
+        let mut status = SubCommand::with_name("status").about("Show the progress of the migration");
+        status = GeneralArgs::add_args(status);
+        status = Self::add_my_ca_arg(status);
+        sub = sub.subcommand(status);
+
+        let mut activate = SubCommand::with_name("activate").about(
+            "Verify that the new repository serves the objects of the CA, and if so activate the new keys. \
+             The old repository is cleaned when the key rolls are finished",
+        );
+        activate = GeneralArgs::add_args(activate);
+        activate = Self::add_my_ca_arg(activate);
+        sub = sub.subcommand(activate);
+
+        let mut abort = SubCommand::with_name("abort").about(
+            "Abort the migration before the new keys are activated, and return to the old repository. \
+             New keys are revoked and withdrawn from the new repository",
+        );
+        abort = GeneralArgs::add_args(abort);
+        abort = Self::add_my_ca_arg(abort);
+        sub = sub.subcommand(abort);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_migrate_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("migrate").about(
+            "Migrate a CA to a new repository, or resume the migration. The CA keeps using its old repository \
+             until the new repository is verified to serve its objects, so this can be stopped safely",
+        );
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("response")
+                .value_name("file")
+                .long("response")
+                .short("r")
+                .help("The location of the RFC 8183 Publisher Response XML file of the new repository")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("repo").about("Manage the repository for a CA");
+
+        sub = Self::make_cas_repo_request_sc(sub);
+        sub = Self::make_cas_repo_show_sc(sub);
+        sub = Self::make_cas_repo_status_sc(sub);
+        sub = Self::make_cas_repo_statuses_sc(sub);
+        sub = Self::make_cas_repo_check_sc(sub);
+        sub = Self::make_cas_repo_configure_sc(sub);
+        sub = Self::make_cas_repo_idroll_sc(sub);
+        sub = Self::make_cas_repo_classes_sc(sub);
+        sub = Self::make_cas_repo_migration_sc(sub);
+        sub = Self::make_cas_repo_migrate_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_timing_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show").about("Show the issuance timing overrides for a CA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_timing_effective_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("effective")
+            .about("Show the issuance timing in effect for a CA, with warnings about values which may be unwise");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_timing_update_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("update").about(
+            "Update the issuance timing overrides for a CA. Values which are not set use the global configuration",
+        );
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        for (name, help) in [
+            (
+                "publish-next-hours",
+                "Hours until the next update time of manifests and CRLs",
+            ),
+            (
+                "publish-next-jitter-hours",
+                "Maximum hours of random jitter subtracted from the next update time",
+            ),
+            (
+                "publish-hours-before-next",
+                "Republish manifests and CRLs this many hours before they would become stale",
+            ),
+            ("roa-valid-weeks", "Validity time in weeks for ROAs"),
+            (
+                "roa-reissue-weeks-before",
+                "Reissue ROAs this many weeks before they would expire",
+            ),
+            ("aspa-valid-weeks", "Validity time in weeks for ASPA objects"),
+            (
+                "aspa-reissue-weeks-before",
+                "Reissue ASPA objects this many weeks before they would expire",
+            ),
+            (
+                "roa-valid-jitter-hours",
+                "Maximum hours of random jitter added to the validity time of ROAs",
+            ),
+            (
+                "aspa-valid-jitter-hours",
+                "Maximum hours of random jitter added to the validity time of ASPA objects",
+            ),
+            (
+                "renew-spread-hours",
+                "Spread the renewal of objects which expire at the same time over this many hours",
+            ),
+        ] {
+            sub = sub.arg(
+                Arg::with_name(name)
+                    .long(name)
+                    .value_name("number")
+                    .help(help)
+                    .required(false),
+            );
+        }
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_timing_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("timing").about("Manage issuance timing overrides for a CA");
+
+        sub = Self::make_cas_timing_show_sc(sub);
+        sub = Self::make_cas_timing_effective_sc(sub);
+        sub = Self::make_cas_timing_update_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_retry_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show")
+            .about("Show the retry policy overrides for contacting the repository and parents of a CA");
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_retry_update_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("update").about(
+            "Update the retry policy overrides for the repository, or a parent, of a CA. Values which are not set use the global configuration",
+        );
+
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("parent")
+                .long("parent")
+                .short("p")
+                .value_name("name")
+                .help("Update the policy for this parent, rather than for the repository")
+                .required(false),
+        );
+
+        for (name, help) in [
+            (
+                "initial-delay-seconds",
+                "Seconds to wait before the first retry after a failure",
+            ),
+            (
+                "backoff-factor",
+                "Multiply the delay by this factor for each further failure",
+            ),
+            ("max-delay-seconds", "Maximum seconds to wait between retries"),
+            (
+                "max-attempts",
+                "Fall back to the regular refresh after this many consecutive failures",
+            ),
+            ("jitter-seconds", "Maximum seconds of random jitter added to the delay"),
+            (
+                "timeout-seconds",
+                "Timeout in seconds for a request-response round-trip",
+            ),
+        ] {
+            sub = sub.arg(
+                Arg::with_name(name)
+                    .long(name)
+                    .value_name("number")
+                    .help(help)
+                    .required(false),
+            );
+        }
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_retry_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("retry")
+            .about("Manage retry policy overrides for contacting the repository and parents of a CA");
+
+        sub = Self::make_cas_retry_show_sc(sub);
+        sub = Self::make_cas_retry_update_sc(sub);
 
         app.subcommand(sub)
     }
 
-    fn make_cas_repo_request_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("request").about("Show RFC 8183 Publisher Request XML");
+    fn make_cas_labels_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show").about("Show the labels of a CA");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
@@ -912,17 +1991,45 @@ impl Options {
         app.subcommand(sub)
     }
 
-    fn make_cas_repo_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("show").about("Show current repo config");
+    fn make_cas_labels_update_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("update").about("Set and/or remove labels of a CA, other labels are kept");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
 
+        sub = sub
+            .arg(
+                Arg::with_name("set")
+                    .long("set")
+                    .value_name("key=value")
+                    .help("One or more labels to set, e.g.: customer=acme")
+                    .multiple(true)
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("remove")
+                    .long("remove")
+                    .value_name("key")
+                    .help("One or more labels to remove")
+                    .multiple(true)
+                    .required(false),
+            );
+
         app.subcommand(sub)
     }
 
-    fn make_cas_repo_status_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("status").about("Show current repo status");
+    fn make_cas_labels_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("labels")
+            .about("Manage the labels of a CA, used to select CAs by e.g. customer, region or environment");
+
+        sub = Self::make_cas_labels_show_sc(sub);
+        sub = Self::make_cas_labels_update_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_issues_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("issues").about("Show issues for a CA");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
@@ -930,39 +2037,71 @@ impl Options {
         app.subcommand(sub)
     }
 
-    fn make_cas_repo_configure_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("configure").about("Configure which repository a CA uses");
+    fn make_cas_expiry_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("expiry").about("Show the expiry times of objects published by a CA");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
         sub = sub.arg(
-            Arg::with_name("response")
-                .value_name("file")
-                .long("response")
-                .short("r")
-                .help("The location of the RFC 8183 Publisher Response XML file")
-                .required(true),
+            Arg::with_name("window-hours")
+                .long("window-hours")
+                .value_name("hours")
+                .help("Mark objects expiring within this many hours (default: server config)")
+                .required(false),
         );
 
         app.subcommand(sub)
     }
 
-    fn make_cas_repo_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("repo").about("Manage the repository for a CA");
+    fn make_cas_reconcile_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("reconcile")
+            .about("Compare the configuration of a CA with its issued and published objects");
 
-        sub = Self::make_cas_repo_request_sc(sub);
-        sub = Self::make_cas_repo_show_sc(sub);
-        sub = Self::make_cas_repo_status_sc(sub);
-        sub = Self::make_cas_repo_configure_sc(sub);
+        sub = GeneralArgs::add_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("repair")
+                .long("repair")
+                .help("Re-issue and re-publish objects to repair divergences, where possible")
+                .required(false),
+        );
+        sub = sub.arg(
+            Arg::with_name("last")
+                .long("last")
+                .help("Show the result of the last reconciliation, rather than reconciling now")
+                .conflicts_with("repair")
+                .required(false),
+        );
 
         app.subcommand(sub)
     }
 
-    fn make_cas_issues_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("issues").about("Show issues for a CA");
+    fn make_cas_export_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("export")
+            .about("Export the configuration of a CA, for import into another Krill instance");
 
         sub = GeneralArgs::add_args(sub);
         sub = Self::add_my_ca_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("full")
+                .long("full")
+                .help("Also export the resources, children and identity certificate")
+                .required(false),
+        );
+        sub = sub.arg(
+            Arg::with_name("include-keys")
+                .long("include-keys")
+                .help("Include the private key of the identity certificate. Handle the export with care!")
+                .required(false),
+        );
+        sub = sub.arg(
+            Arg::with_name("out")
+                .long("out")
+                .short("o")
+                .value_name("path")
+                .help("File to write the export to")
+                .required(false),
+        );
 
         app.subcommand(sub)
     }
@@ -1133,7 +2272,8 @@ impl Options {
     }
 
     fn make_bulk_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("bulk").about("Manually trigger refresh/republish/resync for all CAs");
+        let mut sub = SubCommand::with_name("bulk")
+            .about("Manually trigger refresh/republish/resync/snapshot for all or selected CAs");
 
         let mut refresh =
             SubCommand::with_name("refresh").about("Force that all CAs ask their parents for updated certificates");
@@ -1146,7 +2286,220 @@ impl Options {
         let mut resync = SubCommand::with_name("sync").about("Force that all CAs sync with their repo server");
         resync = GeneralArgs::add_args(resync);
 
-        sub = sub.subcommand(refresh).subcommand(republish).subcommand(resync);
+        let mut snapshot = SubCommand::with_name("snapshot")
+            .about("Force that snapshots are saved for all CAs and the Publication Server");
+        snapshot = GeneralArgs::add_args(snapshot);
+
+        let mut verify = SubCommand::with_name("verify")
+            .about("Verify that the snapshots for all CAs and the Publication Server match their events");
+        verify = GeneralArgs::add_args(verify);
+
+        let mut migrate = SubCommand::with_name("migrate")
+            .about("Migrate CAs from another RPKI CA implementation, using the JSON interchange format");
+        migrate = GeneralArgs::add_args(migrate);
+        migrate = migrate
+            .arg(
+                Arg::with_name("file")
+                    .long("file")
+                    .short("f")
+                    .value_name("path")
+                    .help(
+                        "File with the exported state of the CAs: identity keys, repository and parent responses, ROAs",
+                    )
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("background")
+                    .long("background")
+                    .help("Migrate the CAs in a job in the background, and show the id of the job")
+                    .required(false),
+            );
+
+        let mut run = SubCommand::with_name("run").about(
+            "Start a job in the background which does an operation for the selected CAs, \
+             shows the id of the job to follow its progress with 'krillc jobs show'",
+        );
+        run = GeneralArgs::add_args(run);
+        run = run
+            .arg(
+                Arg::with_name("operation")
+                    .long("operation")
+                    .value_name("operation")
+                    .possible_values(&["sync_parents", "sync_repo", "publish", "force_publish"])
+                    .help("The operation to do for each CA")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("glob")
+                    .long("glob")
+                    .value_name("pattern")
+                    .help("Select the CAs with names matching this pattern, e.g. 'customer-*'")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("regex")
+                    .long("regex")
+                    .value_name("regex")
+                    .help("Select the CAs with names matching this regular expression")
+                    .required(false)
+                    .conflicts_with("glob"),
+            )
+            .arg(
+                Arg::with_name("label")
+                    .long("label")
+                    .value_name("selector")
+                    .help("Select the CAs with matching labels, e.g. 'customer=acme,env=prod', or 'env' for CAs with that label set")
+                    .required(false)
+                    .conflicts_with_all(&["glob", "regex"]),
+            );
+
+        sub = sub
+            .subcommand(refresh)
+            .subcommand(republish)
+            .subcommand(resync)
+            .subcommand(snapshot)
+            .subcommand(verify)
+            .subcommand(migrate)
+            .subcommand(run);
+
+        app.subcommand(sub)
+    }
+
+    fn make_backup_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("backup").about("Manage backups of all Krill state");
+
+        let mut create = SubCommand::with_name("create").about("Create a backup while the server keeps running");
+        create = GeneralArgs::add_args(create);
+
+        let mut list = SubCommand::with_name("list").about("List all backups");
+        list = GeneralArgs::add_args(list);
+
+        let mut restore = SubCommand::with_name("restore")
+            .about("Stage the restore of a backup, which is applied when the server is restarted");
+        restore = GeneralArgs::add_args(restore);
+        restore = restore
+            .arg(
+                Arg::with_name("name")
+                    .long("name")
+                    .value_name("name")
+                    .help("The name of the backup")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("force")
+                    .long("force")
+                    .help(
+                        "Restore the backup even if it was made by another Krill version, or for another configuration",
+                    )
+                    .required(false),
+            );
+
+        sub = sub.subcommand(create).subcommand(list).subcommand(restore);
+
+        app.subcommand(sub)
+    }
+
+    fn make_ha_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("ha").about("Manage high availability (active/standby) mode");
+
+        let mut status = SubCommand::with_name("status").about("Show whether this instance is active or standby");
+        status = GeneralArgs::add_args(status);
+
+        let mut promote = SubCommand::with_name("promote").about("Promote this instance to be the active instance");
+        promote = GeneralArgs::add_args(promote);
+
+        sub = sub.subcommand(status).subcommand(promote);
+
+        app.subcommand(sub)
+    }
+
+    fn make_time_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("time").about("Compare the system time with the configured time sources");
+
+        let mut status = SubCommand::with_name("status").about("Show the result of the last check of the system time");
+        status = GeneralArgs::add_args(status);
+
+        let mut check = SubCommand::with_name("check").about("Check the system time now");
+        check = GeneralArgs::add_args(check);
+
+        sub = sub.subcommand(status).subcommand(check);
+
+        app.subcommand(sub)
+    }
+
+    fn make_keys_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("keys").about("Show and clean up the keys stored by the signers");
+
+        let mut list = SubCommand::with_name("list").about("List the stored keys with what uses them");
+        list = GeneralArgs::add_args(list);
+
+        let mut gc = SubCommand::with_name("gc").about(
+            "Archive stored keys which are not used by anything. Shows the keys which would be archived, \
+             unless --confirm is given",
+        );
+        gc = GeneralArgs::add_args(gc);
+        gc = gc.arg(
+            Arg::with_name("confirm")
+                .long("confirm")
+                .help("Archive the keys, rather than only showing them")
+                .required(false),
+        );
+
+        sub = sub.subcommand(list).subcommand(gc);
+
+        app.subcommand(sub)
+    }
+
+    fn make_jobs_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("jobs").about("Show the progress and results of jobs in the background");
+
+        let mut list = SubCommand::with_name("list").about("List the running and recently finished jobs");
+        list = GeneralArgs::add_args(list);
+
+        let mut show = SubCommand::with_name("show").about("Show a job, including its results for all CAs");
+        show = GeneralArgs::add_args(show);
+        show = show.arg(
+            Arg::with_name("id")
+                .long("id")
+                .value_name("id")
+                .help("The id of the job")
+                .required(true),
+        );
+
+        sub = sub.subcommand(list).subcommand(show);
+
+        app.subcommand(sub)
+    }
+
+    fn make_tasks_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("tasks").about("Show and control the tasks of the scheduler");
+
+        let mut list = SubCommand::with_name("list").about("List queued tasks with their next and last run");
+        list = GeneralArgs::add_args(list);
+
+        let name_arg = Arg::with_name("name")
+            .long("name")
+            .value_name("name")
+            .help("The name of the task, as shown in the list")
+            .required(true);
+
+        let mut trigger = SubCommand::with_name("trigger").about("Run all queued tasks with this name now");
+        trigger = GeneralArgs::add_args(trigger);
+        trigger = trigger.arg(name_arg.clone());
+
+        let mut pause = SubCommand::with_name("pause").about("Do not run tasks with this name until resumed");
+        pause = GeneralArgs::add_args(pause);
+        pause = pause.arg(name_arg.clone());
+
+        let mut resume = SubCommand::with_name("resume").about("Resume running paused tasks with this name");
+        resume = GeneralArgs::add_args(resume);
+        resume = resume.arg(name_arg);
+
+        sub = sub
+            .subcommand(list)
+            .subcommand(trigger)
+            .subcommand(pause)
+            .subcommand(resume);
 
         app.subcommand(sub)
     }
@@ -1163,6 +2516,132 @@ impl Options {
         app.subcommand(info)
     }
 
+    fn make_apply_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut apply = SubCommand::with_name("apply")
+            .about("Bring CAs to the state described in YAML or JSON files, by adding or removing what differs");
+        apply = GeneralArgs::add_args(apply);
+        apply = apply
+            .arg(
+                Arg::with_name("file")
+                    .long("file")
+                    .value_name("path")
+                    .help("A file, or a directory with .yaml, .yml or .json files, describing the desired state")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("dry-run")
+                    .long("dry-run")
+                    .alias("dryrun")
+                    .help("Only show the changes, without applying them")
+                    .required(false),
+            );
+        app.subcommand(apply)
+    }
+
+    fn make_xml_file_sc<'a, 'b>(name: &'a str, about: &'a str) -> App<'a, 'b> {
+        let sc = SubCommand::with_name(name).about(about).arg(
+            Arg::with_name("file")
+                .long("file")
+                .value_name("path")
+                .help("A child request, parent response, publisher request or repository response XML file")
+                .required(true),
+        );
+        GeneralArgs::add_format_arg(sc)
+    }
+
+    fn make_xml_diff_sc<'a, 'b>() -> App<'a, 'b> {
+        let diff = SubCommand::with_name("diff")
+            .about("Show the differences in content between two XML files, ignoring formatting")
+            .arg(
+                Arg::with_name("left")
+                    .value_name("left")
+                    .help("The first XML file")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("right")
+                    .value_name("right")
+                    .help("The second XML file")
+                    .required(true),
+            );
+        GeneralArgs::add_format_arg(diff)
+    }
+
+    fn make_xml_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let xml = SubCommand::with_name("xml")
+            .about("Inspect RFC 8183 XML files, without contacting a server")
+            .subcommand(Self::make_xml_file_sc(
+                "show",
+                "Parse an XML file and show it in standard form",
+            ))
+            .subcommand(Self::make_xml_file_sc(
+                "validate",
+                "Check that an XML file can be parsed and has a valid ID certificate",
+            ))
+            .subcommand(Self::make_xml_file_sc(
+                "certs",
+                "Show the ID certificate in an XML file, with its fingerprints",
+            ))
+            .subcommand(Self::make_xml_diff_sc());
+        app.subcommand(xml)
+    }
+
+    fn make_completions_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let completions = SubCommand::with_name("completions")
+            .about("Print a completion script for the shell, including completion of CA handles")
+            .arg(
+                Arg::with_name("shell")
+                    .value_name("shell")
+                    .possible_values(&["bash", "zsh", "fish"])
+                    .help("The shell to generate completions for")
+                    .required(true),
+            );
+        app.subcommand(completions)
+    }
+
+    fn make_shell_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let shell = SubCommand::with_name("shell")
+            .about("Start an interactive shell, which runs krillc commands against the same server and token");
+        let shell = GeneralArgs::add_args(shell);
+        app.subcommand(shell)
+    }
+
+    fn make_reload_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let reload = SubCommand::with_name("reload")
+            .about("Reload the server config file, and show which changed settings need a restart");
+        let reload = GeneralArgs::add_args(reload);
+        app.subcommand(reload)
+    }
+
+    fn make_doctor_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let doctor = SubCommand::with_name("doctor").about(
+            "Check the server for problems, such as unreachable parents or repositories, \
+             unavailable signers and an expiring HTTPS certificate",
+        );
+        let doctor = GeneralArgs::add_args(doctor);
+        app.subcommand(doctor)
+    }
+
+    fn make_schema_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let schema =
+            SubCommand::with_name("schema").about("Show the Krill and schema versions which last used the stored data");
+        let schema = GeneralArgs::add_args(schema);
+        app.subcommand(schema)
+    }
+
+    fn make_search_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("search")
+            .about("Find the CAs, ROAs, children and publishers for an ASN, prefix, URI or text");
+        sub = GeneralArgs::add_args(sub);
+        sub = sub.arg(
+            Arg::with_name("query")
+                .value_name("query")
+                .help("E.g. AS65000, 192.168.0.0/24, rsync://host/repo/ca/ or a name")
+                .required(true),
+        );
+        app.subcommand(sub)
+    }
+
     fn make_publishers_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("list").about("List all publishers");
         sub = GeneralArgs::add_args(sub);
@@ -1263,6 +2742,123 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_publishers_stats_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("stats").about("Show content and publication statistics for publishers");
+        sub = GeneralArgs::add_args(sub);
+        sub = sub.arg(
+            Arg::with_name("publisher")
+                .value_name("handle")
+                .short("p")
+                .long("publisher")
+                .help("The handle (name) of the publisher, shows all publishers if omitted")
+                .required(false),
+        );
+        app.subcommand(sub)
+    }
+
+    fn make_publishers_quota_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("quota").about("Manage the quota for a publisher");
+
+        let mut show = SubCommand::with_name("show").about("Show the quota and current usage for a publisher");
+        show = GeneralArgs::add_args(show);
+        show = Self::add_publisher_arg(show);
+        sub = sub.subcommand(show);
+
+        let mut set =
+            SubCommand::with_name("set").about("Set a custom quota for a publisher, limits not given do not apply");
+        set = GeneralArgs::add_args(set);
+        set = Self::add_publisher_arg(set);
+        set = set
+            .arg(
+                Arg::with_name("max-objects")
+                    .long("max-objects")
+                    .value_name("number")
+                    .help("The maximum number of objects")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("max-bytes")
+                    .long("max-bytes")
+                    .value_name("number")
+                    .help("The maximum total size of all objects in bytes")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("max-deltas-per-hour")
+                    .long("max-deltas-per-hour")
+                    .value_name("number")
+                    .help("The maximum number of RFC 8181 deltas per hour")
+                    .required(false),
+            );
+        sub = sub.subcommand(set);
+
+        let mut reset = SubCommand::with_name("reset").about("Use the configured default quota for a publisher");
+        reset = GeneralArgs::add_args(reset);
+        reset = Self::add_publisher_arg(reset);
+        sub = sub.subcommand(reset);
+
+        app.subcommand(sub)
+    }
+
+    fn make_publishers_validation_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("validation").about("Manage the validation of content from a publisher");
+
+        let mut show = SubCommand::with_name("show").about("Show the validation for a publisher");
+        show = GeneralArgs::add_args(show);
+        show = Self::add_publisher_arg(show);
+        sub = sub.subcommand(show);
+
+        let mut set = SubCommand::with_name("set").about("Set a custom validation for a publisher");
+        set = GeneralArgs::add_args(set);
+        set = Self::add_publisher_arg(set);
+        set = set.arg(
+            Arg::with_name("validation")
+                .long("validation")
+                .value_name("off|warn|reject")
+                .help("Do not check objects, log invalid objects, or reject deltas with invalid objects")
+                .required(true),
+        );
+        sub = sub.subcommand(set);
+
+        let mut reset = SubCommand::with_name("reset").about("Use the configured default validation for a publisher");
+        reset = GeneralArgs::add_args(reset);
+        reset = Self::add_publisher_arg(reset);
+        sub = sub.subcommand(reset);
+
+        app.subcommand(sub)
+    }
+
+    fn make_publishers_idcert_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("idcert").about("Manage the rollover of the ID certificate of a publisher");
+
+        let mut stage = SubCommand::with_name("stage")
+            .about("Stage a new ID certificate, both the current and new certificate are accepted until activated");
+        stage = GeneralArgs::add_args(stage);
+        stage = Self::add_publisher_arg(stage);
+        stage = stage.arg(
+            Arg::with_name("request")
+                .value_name("file")
+                .long("request")
+                .short("r")
+                .help("The location of the RFC 8183 Publisher Request XML file with the new certificate")
+                .required(true),
+        );
+        sub = sub.subcommand(stage);
+
+        let mut activate = SubCommand::with_name("activate")
+            .about("Activate the staged ID certificate, the old one is no longer accepted");
+        activate = GeneralArgs::add_args(activate);
+        activate = Self::add_publisher_arg(activate);
+        sub = sub.subcommand(activate);
+
+        let mut cancel = SubCommand::with_name("cancel").about("Remove the staged ID certificate");
+        cancel = GeneralArgs::add_args(cancel);
+        cancel = Self::add_publisher_arg(cancel);
+        sub = sub.subcommand(cancel);
+
+        app.subcommand(sub)
+    }
+
     fn make_publication_server_stats_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("stats").about("Show publication server stats");
         sub = GeneralArgs::add_args(sub);
@@ -1279,15 +2875,49 @@ impl Options {
         app.subcommand(sub)
     }
 
-    fn make_publication_server_clear_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("clear").about("Clear the publication server so it can re-initialized");
+    fn make_publication_server_clear_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("clear").about("Clear the publication server so it can re-initialized");
+        sub = GeneralArgs::add_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_publication_server_session_reset_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("session-reset").about("Reset the RRDP session");
+        sub = GeneralArgs::add_args(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_publication_server_rrdp_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("rrdp").about("Show the RRDP session, snapshot and delta stats");
         sub = GeneralArgs::add_args(sub);
         app.subcommand(sub)
     }
 
-    fn make_publication_server_session_reset_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("session-reset").about("Reset the RRDP session");
-        sub = GeneralArgs::add_args(sub);
+    fn make_publication_server_archive_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("archive").about("Manage the archive of the repository content");
+
+        let mut list = SubCommand::with_name("list").about("List the entries in the archive");
+        list = GeneralArgs::add_args(list);
+        sub = sub.subcommand(list);
+
+        let mut create = SubCommand::with_name("create").about("Add an entry with the current content to the archive");
+        create = GeneralArgs::add_args(create);
+        sub = sub.subcommand(create);
+
+        app.subcommand(sub)
+    }
+
+    fn make_publication_server_replica_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("replica").about("Manage the replica of a primary Publication Server");
+
+        let mut status = SubCommand::with_name("status").about("Show the status of the replica");
+        status = GeneralArgs::add_args(status);
+        sub = sub.subcommand(status);
+
+        let mut sync = SubCommand::with_name("sync").about("Synchronise the replica with the primary now");
+        sync = GeneralArgs::add_args(sync);
+        sub = sub.subcommand(sync);
 
         app.subcommand(sub)
     }
@@ -1295,9 +2925,12 @@ impl Options {
     fn make_publication_server_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("server").about("Manage the Publication Server (init/stats)");
         sub = Self::make_publication_server_stats_sc(sub);
+        sub = Self::make_publication_server_rrdp_sc(sub);
         sub = Self::make_publication_server_init_sc(sub);
         sub = Self::make_publication_server_clear_sc(sub);
         sub = Self::make_publication_server_session_reset_sc(sub);
+        sub = Self::make_publication_server_archive_sc(sub);
+        sub = Self::make_publication_server_replica_sc(sub);
         app.subcommand(sub)
     }
 
@@ -1310,6 +2943,10 @@ impl Options {
         sub = Self::make_publishers_remove_sc(sub);
         sub = Self::make_publishers_show_sc(sub);
         sub = Self::make_publishers_response_sc(sub);
+        sub = Self::make_publishers_stats_sc(sub);
+        sub = Self::make_publishers_quota_sc(sub);
+        sub = Self::make_publishers_validation_sc(sub);
+        sub = Self::make_publishers_idcert_sc(sub);
 
         app.subcommand(sub)
     }
@@ -1341,7 +2978,7 @@ impl Options {
         app.subcommand(sub)
     }
 
-    fn make_matches<'a>() -> ArgMatches<'a> {
+    fn make_app<'a, 'b>() -> App<'a, 'b> {
         let mut app = App::new(KRILL_CLIENT_APP).version(KRILL_VERSION);
 
         app = Self::make_config_sc(app);
@@ -1355,10 +2992,18 @@ impl Options {
         app = Self::make_cas_keyroll_sc(app);
         app = Self::make_cas_routes_sc(app);
         app = Self::make_cas_bgpsec_sc(app);
+        app = Self::make_cas_rsc_sc(app);
         app = Self::make_cas_repo_sc(app);
+        app = Self::make_cas_timing_sc(app);
+        app = Self::make_cas_retry_sc(app);
+        app = Self::make_cas_labels_sc(app);
         app = Self::make_cas_issues_sc(app);
+        app = Self::make_cas_expiry_sc(app);
+        app = Self::make_cas_reconcile_sc(app);
+        app = Self::make_cas_export_sc(app);
         app = Self::make_pubserver_sc(app);
         app = Self::make_cas_aspas_sc(app);
+        app = Self::make_cas_scheduled_sc(app);
 
         #[cfg(feature = "rta")]
         {
@@ -1369,34 +3014,63 @@ impl Options {
 
         app = Self::make_info_sc(app);
 
+        app = Self::make_reload_sc(app);
+
+        app = Self::make_doctor_sc(app);
+        app = Self::make_schema_sc(app);
+
+        app = Self::make_search_sc(app);
+
         app = Self::make_bulk_sc(app);
 
-        app.get_matches()
+        app = Self::make_backup_sc(app);
+
+        app = Self::make_ha_sc(app);
+
+        app = Self::make_time_sc(app);
+
+        app = Self::make_keys_sc(app);
+
+        app = Self::make_jobs_sc(app);
+
+        app = Self::make_tasks_sc(app);
+
+        app = Self::make_apply_sc(app);
+
+        app = Self::make_xml_sc(app);
+
+        app = Self::make_completions_sc(app);
+
+        app = Self::make_shell_sc(app);
+
+        app
     }
 
     //---------------------- Parsing
 
+    fn parse_activate_at(matches: &ArgMatches) -> Result<Option<Timestamp>, Error> {
+        match matches.value_of("at") {
+            None => Ok(None),
+            Some(at) => {
+                let time = chrono::DateTime::parse_from_rfc3339(at).map_err(|e| {
+                    Error::GeneralArgumentError(format!("Invalid time '{}', expected RFC 3339: {}", at, e))
+                })?;
+                Ok(Some(Timestamp::new(time.timestamp())))
+            }
+        }
+    }
+
     fn read_file_arg(path: &str) -> Result<Bytes, Error> {
         let path = PathBuf::from(path);
         file::read(&path).map_err(Error::IoError)
     }
 
     fn parse_my_ca(matches: &ArgMatches) -> Result<CaHandle, Error> {
-        let my_ca = {
-            let mut my_ca = None;
-
-            if let Ok(my_ca_env) = env::var(KRILL_CLI_MY_CA_ENV) {
-                my_ca = Some(CaHandle::from_str(&my_ca_env).map_err(|_| Error::InvalidHandle)?);
-            }
+        let profile = Profile::select(matches)?.unwrap_or_default();
+        let my_ca = setting(matches, KRILL_CLI_MY_CA_ARG, KRILL_CLI_MY_CA_ENV, &profile.ca)
+            .ok_or_else(|| Error::missing_arg_with_env(KRILL_CLI_MY_CA_ARG, KRILL_CLI_MY_CA_ENV))?;
 
-            if let Some(my_ca_str) = matches.value_of(KRILL_CLI_MY_CA_ARG) {
-                my_ca = Some(CaHandle::from_str(my_ca_str).map_err(|_| Error::InvalidHandle)?);
-            }
-
-            my_ca.ok_or_else(|| Error::missing_arg_with_env(KRILL_CLI_MY_CA_ARG, KRILL_CLI_MY_CA_ENV))?
-        };
-
-        Ok(my_ca)
+        CaHandle::from_str(&my_ca).map_err(|_| Error::InvalidHandle)
     }
 
     fn parse_resource_args(matches: &ArgMatches) -> Result<Option<ResourceSet>, Error> {
@@ -1480,7 +3154,11 @@ impl Options {
 
     fn parse_matches_cas_list(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
-        let command = Command::CertAuth(CaCommand::List);
+        let label = match matches.value_of("label") {
+            None => None,
+            Some(label) => Some(CaLabelSelector::from_str(label).map_err(Error::GeneralArgumentError)?),
+        };
+        let command = Command::CertAuth(CaCommand::List(label));
         Ok(Options::make(general_args, command))
     }
 
@@ -1499,7 +3177,9 @@ impl Options {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
 
-        let command = Command::CertAuth(CaCommand::Delete(my_ca));
+        let force = matches.is_present("force");
+
+        let command = Command::CertAuth(CaCommand::Delete(my_ca, force));
 
         Ok(Options::make(general_args, command))
     }
@@ -1521,6 +3201,16 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_history_revert(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let key = matches.value_of("key").unwrap();
+        let force = matches.is_present("force");
+
+        let command = Command::CertAuth(CaCommand::RevertHistoryCommand(my_ca, key.to_string(), force));
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_history_commands(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
@@ -1550,6 +3240,25 @@ impl Options {
             options.before = Some(time);
         }
 
+        if let Some(actor) = matches.value_of("actor") {
+            options.actor = Some(actor.to_string());
+        }
+
+        options.archived = matches.is_present("archived");
+
+        if let Some(types) = matches.values_of("type") {
+            options.types = types.map(|t| t.to_string()).collect();
+        }
+
+        if let Some(order) = matches.value_of("order") {
+            options.order = HistoryOrder::from_str(order).map_err(|e| Error::general(&e))?;
+        }
+
+        if let Some(cursor) = matches.value_of("cursor") {
+            let cursor = u64::from_str(cursor).map_err(|e| Error::general(&format!("invalid cursor: {}", e)))?;
+            options.cursor = Some(cursor);
+        }
+
         let command = Command::CertAuth(CaCommand::ShowHistoryCommands(my_ca, options));
         Ok(Options::make(general_args, command))
     }
@@ -1559,6 +3268,8 @@ impl Options {
             Self::parse_matches_cas_history_commands(m)
         } else if let Some(m) = matches.subcommand_matches("details") {
             Self::parse_matches_cas_history_details(m)
+        } else if let Some(m) = matches.subcommand_matches("revert") {
+            Self::parse_matches_cas_history_revert(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -1600,8 +3311,10 @@ impl Options {
             }
         };
         let resources = Self::parse_resource_args(matches)?;
+        let suspension_exempt = matches.value_of("suspension_exempt").map(|exempt| exempt == "true");
+        let manual_approval = matches.value_of("manual_approval").map(|manual| manual == "true");
 
-        let update = UpdateChildRequest::new(id_cert, resources, None);
+        let update = UpdateChildRequest::new(id_cert, resources, None, suspension_exempt, manual_approval);
 
         let command = Command::CertAuth(CaCommand::ChildUpdate(my_ca, child, update));
         Ok(Options::make(general_args, command))
@@ -1648,6 +3361,14 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_children_stats(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ChildStats(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_children_suspend(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
@@ -1674,6 +3395,132 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_children_requests_list(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let child = matches.value_of("child").unwrap();
+        let child = ChildHandle::from_str(child).map_err(|_| Error::InvalidHandle)?;
+
+        let command = Command::CertAuth(CaCommand::ChildRequests(my_ca, child));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_requests_approve(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let child = matches.value_of("child").unwrap();
+        let child = ChildHandle::from_str(child).map_err(|_| Error::InvalidHandle)?;
+
+        let key = matches.value_of("key").unwrap();
+        let key = KeyIdentifier::from_str(key).map_err(|_| Error::general("Invalid key identifier"))?;
+
+        let kind = matches.value_of("kind").unwrap();
+        let kind = PendingChildRequestKind::from_str(kind).map_err(|e| Error::general(&e))?;
+
+        let command = Command::CertAuth(CaCommand::ChildRequestApprove(my_ca, child, kind, key));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_requests(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_children_requests_list(m)
+        } else if let Some(m) = matches.subcommand_matches("approve") {
+            Self::parse_matches_cas_children_requests_approve(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_children_policy_show(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ChildIssuancePolicyShow(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_policy_update(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let policy = ChildIssuancePolicy {
+            max_validity_weeks: Self::parse_timing_arg(matches, "max-validity-weeks")?,
+            deny_asn_only: matches.is_present("deny-asn-only"),
+            approve_new_keys: matches.is_present("approve-new-keys"),
+        };
+
+        let command = Command::CertAuth(CaCommand::ChildIssuancePolicyUpdate(my_ca, policy));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_policy(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_cas_children_policy_show(m)
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            Self::parse_matches_cas_children_policy_update(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_children_transfer_start(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let from = matches.value_of("from").unwrap();
+        let from = ChildHandle::from_str(from).map_err(|_| Error::InvalidHandle)?;
+
+        let to = matches.value_of("to").unwrap();
+        let to = ChildHandle::from_str(to).map_err(|_| Error::InvalidHandle)?;
+
+        let resources = Self::parse_resource_args(matches)?
+            .ok_or_else(|| Error::general("You must specify the resources to transfer"))?;
+        let timeout_hours = Self::parse_timing_arg(matches, "timeout-hours")?;
+
+        let request = ResourceTransferRequest {
+            from,
+            to,
+            resources,
+            timeout_hours,
+        };
+
+        let command = Command::CertAuth(CaCommand::ChildTransferStart(my_ca, request));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_transfer_list(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ChildTransfers(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_transfer_show(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let id = matches.value_of("id").unwrap();
+        let id = u64::from_str(id).map_err(|_| Error::general("Invalid transfer id"))?;
+
+        let command = Command::CertAuth(CaCommand::ChildTransferShow(my_ca, id));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_transfer(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("start") {
+            Self::parse_matches_cas_children_transfer_start(m)
+        } else if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_children_transfer_list(m)
+        } else if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_cas_children_transfer_show(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_cas_children(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("add") {
             Self::parse_matches_cas_children_add(m)
@@ -1687,10 +3534,18 @@ impl Options {
             Self::parse_matches_cas_children_remove(m)
         } else if let Some(m) = matches.subcommand_matches("connections") {
             Self::parse_matches_cas_children_connections(m)
+        } else if let Some(m) = matches.subcommand_matches("stats") {
+            Self::parse_matches_cas_children_stats(m)
         } else if let Some(m) = matches.subcommand_matches("suspend") {
             Self::parse_matches_cas_children_suspend(m)
         } else if let Some(m) = matches.subcommand_matches("unsuspend") {
             Self::parse_matches_cas_children_unsuspend(m)
+        } else if let Some(m) = matches.subcommand_matches("requests") {
+            Self::parse_matches_cas_children_requests(m)
+        } else if let Some(m) = matches.subcommand_matches("policy") {
+            Self::parse_matches_cas_children_policy(m)
+        } else if let Some(m) = matches.subcommand_matches("transfer") {
+            Self::parse_matches_cas_children_transfer(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -1706,18 +3561,33 @@ impl Options {
     }
 
     fn parse_matches_cas_parents_add(matches: &ArgMatches) -> Result<Options, Error> {
-        let path = matches.value_of("response").unwrap();
-        let bytes = Self::read_file_arg(path)?;
-        let response = idexchange::ParentResponse::parse(bytes.as_ref())?;
-
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
 
         let parent = matches.value_of("parent").unwrap();
         let parent = ParentHandle::from_str(parent).map_err(|_| Error::InvalidHandle)?;
-        let parent_req = ParentCaReq::new(parent, response);
 
-        let command = Command::CertAuth(CaCommand::AddParent(my_ca, parent_req));
+        let response = matches.value_of("response").unwrap();
+        let token = matches.value_of("response_token").map(Token::from);
+
+        let command = if response.starts_with("https://") {
+            // Let Krill fetch and validate the response itself.
+            let uri = uri::Https::from_str(response)
+                .map_err(|e| Error::GeneralArgumentError(format!("Invalid parent response URI: {}", e)))?;
+            let fetch = ParentResponseFetch::new(uri, token);
+            Command::CertAuth(CaCommand::AddParentFetch(my_ca, parent, fetch))
+        } else {
+            if token.is_some() {
+                return Err(Error::general(
+                    "A response token can only be used with an HTTPS response URI",
+                ));
+            }
+            let bytes = Self::read_file_arg(response)?;
+            let response = idexchange::ParentResponse::parse(bytes.as_ref())?;
+            let parent_req = ParentCaReq::new(parent, response);
+            Command::CertAuth(CaCommand::AddParent(my_ca, parent_req))
+        };
+
         Ok(Options::make(general_args, command))
     }
 
@@ -1739,6 +3609,24 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_parents_show(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let parent = matches.value_of("parent").unwrap();
+        let parent = ParentHandle::from_str(parent).map_err(|_| Error::InvalidHandle)?;
+
+        let command = Command::CertAuth(CaCommand::ParentExchanges(my_ca, parent));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_parents_stats(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ParentStats(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_parents_remove(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
@@ -1758,6 +3646,10 @@ impl Options {
             Self::parse_matches_cas_parents_info(m)
         } else if let Some(m) = matches.subcommand_matches("statuses") {
             Self::parse_matches_cas_parents_statuses(m)
+        } else if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_cas_parents_show(m)
+        } else if let Some(m) = matches.subcommand_matches("stats") {
+            Self::parse_matches_cas_parents_stats(m)
         } else if let Some(m) = matches.subcommand_matches("remove") {
             Self::parse_matches_cas_parents_remove(m)
         } else {
@@ -1783,11 +3675,33 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_keyroll_signers(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::KeyRollSigners(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_keyroll_migrate(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::KeyRollMigrate(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_keyroll(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("init") {
             Self::parse_matches_cas_keyroll_init(m)
         } else if let Some(m) = matches.subcommand_matches("activate") {
             Self::parse_matches_cas_keyroll_activate(m)
+        } else if let Some(m) = matches.subcommand_matches("signers") {
+            Self::parse_matches_cas_keyroll_signers(m)
+        } else if let Some(m) = matches.subcommand_matches("migrate") {
+            Self::parse_matches_cas_keyroll_migrate(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -1807,8 +3721,13 @@ impl Options {
         let my_ca = Self::parse_my_ca(matches)?;
 
         let updates = if let Some(path) = matches.value_of("delta") {
-            if matches.is_present("add") || matches.is_present("remove") {
-                return Err(Error::general("Cannot use --add or --remove if --delta is specified"));
+            if ["add", "remove", "disable", "enable"]
+                .iter()
+                .any(|arg| matches.is_present(arg))
+            {
+                return Err(Error::general(
+                    "Cannot use --add, --remove, --disable or --enable if --delta is specified",
+                ));
             }
 
             let bytes = Self::read_file_arg(path)?;
@@ -1832,25 +3751,54 @@ impl Options {
                 }
             }
 
-            if added.is_empty() && removed.is_empty() {
+            let mut updates = RoaConfigurationUpdates::new(added, removed);
+
+            if let Some(disable) = matches.values_of("disable") {
+                for roa_str in disable {
+                    updates.disable(RoaPayload::from_str(roa_str)?);
+                }
+            }
+
+            if let Some(enable) = matches.values_of("enable") {
+                for roa_str in enable {
+                    updates.enable(RoaPayload::from_str(roa_str)?);
+                }
+            }
+
+            if updates.is_empty() {
                 return Err(Error::general(
-                    "You MUST specify either --delta, or --add and/or --remove",
+                    "You MUST specify either --delta, or --add, --remove, --disable and/or --enable",
                 ));
             }
 
-            RoaConfigurationUpdates::new(added, removed)
+            updates
         };
 
-        if matches.is_present("dryrun") && matches.is_present("try") {
-            return Err(Error::general("You cannot use both --dryrun and --try"));
+        let modes = ["dryrun", "try", "propose", "at"];
+        if modes.iter().filter(|mode| matches.is_present(mode)).count() > 1 {
+            return Err(Error::general(
+                "You can only use one of --dryrun, --try, --propose and --at",
+            ));
         }
 
-        let command = if matches.is_present("dryrun") {
+        let command = if let Some(activate_at) = Self::parse_activate_at(matches)? {
+            let request = ScheduledChangeRequest {
+                activate_at,
+                update: ScheduledUpdate::Roas(updates),
+            };
+            Command::CertAuth(CaCommand::ScheduledChangeAdd(my_ca, request))
+        } else if matches.is_present("dryrun") {
             Command::CertAuth(CaCommand::RouteAuthorizationsDryRunUpdate(my_ca, updates))
         } else if matches.is_present("try") {
             Command::CertAuth(CaCommand::RouteAuthorizationsTryUpdate(my_ca, updates))
+        } else if matches.is_present("propose") {
+            Command::CertAuth(CaCommand::RouteAuthorizationsPropose(my_ca, updates))
         } else {
-            Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(my_ca, updates))
+            Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
+                my_ca,
+                updates,
+                matches.is_present("force"),
+            ))
         };
 
         Ok(Options::make(general_args, command))
@@ -1869,25 +3817,184 @@ impl Options {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
 
-        let v4 = matches.value_of("ipv4").unwrap_or("");
-        let v6 = matches.value_of("ipv6").unwrap_or("");
+        let v4 = matches.value_of("ipv4").unwrap_or("");
+        let v6 = matches.value_of("ipv6").unwrap_or("");
+
+        let resources = ResourceSet::from_strs("", v4, v6)
+            .map_err(|e| Error::GeneralArgumentError(format!("Could not parse IP resources: {}", e)))?;
+
+        let resources = if resources.is_empty() { None } else { Some(resources) };
+
+        Ok(Options::make(
+            general_args,
+            Command::CertAuth(CaCommand::BgpAnalysisSuggest(my_ca, resources)),
+        ))
+    }
+
+    fn parse_matches_cas_routes_bgp(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("analyze") {
+            Self::parse_matches_cas_routes_bgp_full(m)
+        } else if let Some(m) = matches.subcommand_matches("suggest") {
+            Self::parse_matches_cas_routes_bgp_suggest(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_routes_import(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let (format, path) = match matches.value_of("rpsl") {
+            Some(path) => (RoaImportFormat::Rpsl, path),
+            None => (RoaImportFormat::Csv, matches.value_of("csv").unwrap()), // required unless rpsl
+        };
+
+        let bytes = Self::read_file_arg(path)?;
+        let content = String::from_utf8(bytes.to_vec()).map_err(|_| Error::general("File is not valid UTF-8"))?;
+
+        let import = RoaImport::new(format, content);
+
+        // Parse locally as well, so that format errors are reported early.
+        import.payloads()?;
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsImport(
+            my_ca,
+            import,
+            matches.is_present("dryrun"),
+            matches.is_present("force"),
+        ));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_proposals_list(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsProposals(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_proposals_decide(matches: &ArgMatches, approve: bool) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let id = matches.value_of("id").unwrap(); // required
+        let id = u64::from_str(id).map_err(|_| Error::GeneralArgumentError(format!("Invalid proposal id: {}", id)))?;
+
+        let command = if approve {
+            Command::CertAuth(CaCommand::RouteAuthorizationsApprove(
+                my_ca,
+                id,
+                matches.is_present("force"),
+            ))
+        } else {
+            Command::CertAuth(CaCommand::RouteAuthorizationsReject(my_ca, id))
+        };
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_proposals(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_routes_proposals_list(m)
+        } else if let Some(m) = matches.subcommand_matches("approve") {
+            Self::parse_matches_cas_routes_proposals_decide(m, true)
+        } else if let Some(m) = matches.subcommand_matches("reject") {
+            Self::parse_matches_cas_routes_proposals_decide(m, false)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_routes_strategy(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("show") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let my_ca = Self::parse_my_ca(m)?;
+            Ok(Options::make(
+                general_args,
+                Command::CertAuth(CaCommand::RoaStrategyShow(my_ca)),
+            ))
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let my_ca = Self::parse_my_ca(m)?;
+
+            let max_prefixes = match m.value_of("max_prefixes") {
+                None => None,
+                Some(s) => Some(
+                    usize::from_str(s)
+                        .map_err(|_| Error::GeneralArgumentError(format!("Invalid number of prefixes: {}", s)))?,
+                ),
+            };
+
+            let strategy = match m.value_of("strategy").unwrap() {
+                "auto" => RoaIssuanceStrategy::Auto,
+                "per_prefix" => RoaIssuanceStrategy::PerPrefix,
+                "per_asn" => RoaIssuanceStrategy::PerAsn,
+                _ => match max_prefixes {
+                    Some(max_prefixes) => RoaIssuanceStrategy::Grouped { max_prefixes },
+                    None => {
+                        return Err(Error::general("The 'grouped' strategy requires --max-prefixes"));
+                    }
+                },
+            };
 
-        let resources = ResourceSet::from_strs("", v4, v6)
-            .map_err(|e| Error::GeneralArgumentError(format!("Could not parse IP resources: {}", e)))?;
+            Ok(Options::make(
+                general_args,
+                Command::CertAuth(CaCommand::RoaStrategyUpdate(my_ca, Some(strategy))),
+            ))
+        } else if let Some(m) = matches.subcommand_matches("reset") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let my_ca = Self::parse_my_ca(m)?;
+            Ok(Options::make(
+                general_args,
+                Command::CertAuth(CaCommand::RoaStrategyUpdate(my_ca, None)),
+            ))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
 
-        let resources = if resources.is_empty() { None } else { Some(resources) };
+    fn parse_matches_cas_routes_autopilot(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("show") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let my_ca = Self::parse_my_ca(m)?;
+            Ok(Options::make(
+                general_args,
+                Command::CertAuth(CaCommand::RoaAutoPilotShow(my_ca)),
+            ))
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let my_ca = Self::parse_my_ca(m)?;
 
-        Ok(Options::make(
-            general_args,
-            Command::CertAuth(CaCommand::BgpAnalysisSuggest(my_ca, resources)),
-        ))
-    }
+            let mut policy = RoaAutoPilotPolicy::with_defaults();
+            policy.enabled = !m.is_present("off");
+            if let Some(s) = m.value_of("min_seen_hours") {
+                policy.min_seen_hours = u32::from_str(s)
+                    .map_err(|_| Error::GeneralArgumentError(format!("Invalid number of hours: {}", s)))?;
+            }
+            if let Some(s) = m.value_of("max_prefix_length_v4") {
+                policy.max_prefix_length_v4 = u8::from_str(s)
+                    .map_err(|_| Error::GeneralArgumentError(format!("Invalid prefix length: {}", s)))?;
+            }
+            if let Some(s) = m.value_of("max_prefix_length_v6") {
+                policy.max_prefix_length_v6 = u8::from_str(s)
+                    .map_err(|_| Error::GeneralArgumentError(format!("Invalid prefix length: {}", s)))?;
+            }
 
-    fn parse_matches_cas_routes_bgp(matches: &ArgMatches) -> Result<Options, Error> {
-        if let Some(m) = matches.subcommand_matches("analyze") {
-            Self::parse_matches_cas_routes_bgp_full(m)
-        } else if let Some(m) = matches.subcommand_matches("suggest") {
-            Self::parse_matches_cas_routes_bgp_suggest(m)
+            Ok(Options::make(
+                general_args,
+                Command::CertAuth(CaCommand::RoaAutoPilotUpdate(my_ca, Some(policy))),
+            ))
+        } else if let Some(m) = matches.subcommand_matches("reset") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let my_ca = Self::parse_my_ca(m)?;
+            Ok(Options::make(
+                general_args,
+                Command::CertAuth(CaCommand::RoaAutoPilotUpdate(my_ca, None)),
+            ))
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -1900,6 +4007,43 @@ impl Options {
             Self::parse_matches_cas_routes_update(m)
         } else if let Some(m) = matches.subcommand_matches("bgp") {
             Self::parse_matches_cas_routes_bgp(m)
+        } else if let Some(m) = matches.subcommand_matches("import") {
+            Self::parse_matches_cas_routes_import(m)
+        } else if let Some(m) = matches.subcommand_matches("proposals") {
+            Self::parse_matches_cas_routes_proposals(m)
+        } else if let Some(m) = matches.subcommand_matches("strategy") {
+            Self::parse_matches_cas_routes_strategy(m)
+        } else if let Some(m) = matches.subcommand_matches("autopilot") {
+            Self::parse_matches_cas_routes_autopilot(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_scheduled_list(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ScheduledChanges(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_scheduled_cancel(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let id = matches.value_of("id").unwrap();
+        let id = u64::from_str(id).map_err(|_| Error::general("Invalid scheduled change id"))?;
+
+        let command = Command::CertAuth(CaCommand::ScheduledChangeCancel(my_ca, id));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_scheduled(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_scheduled_list(m)
+        } else if let Some(m) = matches.subcommand_matches("cancel") {
+            Self::parse_matches_cas_scheduled_cancel(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -1957,6 +4101,58 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_rsc_sign(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let ca = Self::parse_my_ca(matches)?;
+
+        let days = matches.value_of("days").unwrap();
+        let days =
+            i64::from_str(days).map_err(|e| Error::GeneralArgumentError(format!("Invalid number of days: {}", e)))?;
+        let validity = SignSupport::sign_validity_days(days);
+
+        let resources = Self::parse_resource_args(matches)?
+            .ok_or_else(|| Error::general("You must specify at least one of --ipv4, --ipv6 or --asn"))?;
+
+        let mut checklist = vec![];
+        for in_file in matches.values_of("file").unwrap() {
+            let in_file = PathBuf::from_str(in_file)
+                .map_err(|_| Error::GeneralArgumentError(format!("Invalid filename: {}", in_file)))?;
+
+            let content = file::read(&in_file).map_err(|e| {
+                Error::GeneralArgumentError(format!("Can't read file '{}', error: {}", in_file.to_string_lossy(), e,))
+            })?;
+
+            // Only the file name is signed, not the local path to it
+            let file_name = in_file.file_name().map(|name| name.to_string_lossy().to_string());
+
+            checklist.push(RscFileHash::for_content(file_name, &content));
+        }
+
+        let out_file = matches.value_of("out").unwrap();
+        let out_file = PathBuf::from_str(out_file)
+            .map_err(|_| Error::GeneralArgumentError(format!("Invalid filename: {}", out_file)))?;
+
+        file::save(&[], &out_file).map_err(|e| {
+            Error::GeneralArgumentError(format!(
+                "Cannot save to file: {}, error: {}",
+                out_file.to_string_lossy(),
+                e
+            ))
+        })?;
+
+        let request = RscContentRequest::new(resources, validity, checklist);
+        let command = Command::CertAuth(CaCommand::RscSign(ca, request, Some(out_file)));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_rsc(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("sign") {
+            Self::parse_matches_cas_rsc_sign(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_cas_bgpsec(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("list") {
             Self::parse_matches_cas_bgpsec_list(m)
@@ -1984,7 +4180,16 @@ impl Options {
         } else if aspa.providers().is_empty() {
             Err(Error::general("At least one provider MUST be specified."))
         } else {
-            let command = Command::CertAuth(CaCommand::AspasAddOrReplace(my_ca, aspa));
+            let command = match Self::parse_activate_at(matches)? {
+                Some(activate_at) => {
+                    let request = ScheduledChangeRequest {
+                        activate_at,
+                        update: ScheduledUpdate::Aspas(AspaDefinitionUpdates::new(vec![aspa], vec![])),
+                    };
+                    Command::CertAuth(CaCommand::ScheduledChangeAdd(my_ca, request))
+                }
+                None => Command::CertAuth(CaCommand::AspasAddOrReplace(my_ca, aspa)),
+            };
             Ok(Options::make(general_args, command))
         }
     }
@@ -1995,7 +4200,16 @@ impl Options {
         let customer_str = matches.value_of("customer").unwrap();
         let customer = AspaCustomer::from_str(customer_str).map_err(|_| Error::invalid_asn(customer_str))?;
 
-        let command = Command::CertAuth(CaCommand::AspasRemove(my_ca, customer));
+        let command = match Self::parse_activate_at(matches)? {
+            Some(activate_at) => {
+                let request = ScheduledChangeRequest {
+                    activate_at,
+                    update: ScheduledUpdate::Aspas(AspaDefinitionUpdates::new(vec![], vec![customer])),
+                };
+                Command::CertAuth(CaCommand::ScheduledChangeAdd(my_ca, request))
+            }
+            None => Command::CertAuth(CaCommand::AspasRemove(my_ca, customer)),
+        };
 
         Ok(Options::make(general_args, command))
     }
@@ -2040,7 +4254,11 @@ impl Options {
             return Err(Error::general("You MUST specify at least one of --add or --remove"));
         }
 
-        let command = Command::CertAuth(CaCommand::AspasUpdate(my_ca, customer, update));
+        let command = if matches.is_present("dryrun") {
+            Command::CertAuth(CaCommand::AspasUpdateDryRun(my_ca, customer, update))
+        } else {
+            Command::CertAuth(CaCommand::AspasUpdate(my_ca, customer, update))
+        };
 
         Ok(Options::make(general_args, command))
     }
@@ -2054,6 +4272,43 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_aspas_import(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let path = matches.value_of("file").unwrap(); // required argument
+        let bytes = Self::read_file_arg(path)?;
+
+        let definitions = if matches.is_present("csv") {
+            let csv = from_utf8(&bytes).map_err(|_| Error::general("CSV file is not valid UTF-8"))?;
+            AspaDefinitionList::from_csv(csv)?
+        } else {
+            serde_json::from_slice(&bytes)
+                .map_err(|e| Error::GeneralArgumentError(format!("Invalid ASPA configurations JSON: {}", e)))?
+        };
+
+        let command = Command::CertAuth(CaCommand::AspasImport(my_ca, definitions));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_aspas_export(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let out = match matches.value_of("out") {
+            None => None,
+            Some(out_file) => Some(
+                PathBuf::from_str(out_file)
+                    .map_err(|_| Error::GeneralArgumentError(format!("Invalid filename: {}", out_file)))?,
+            ),
+        };
+
+        let command = Command::CertAuth(CaCommand::AspasExport(my_ca, matches.is_present("csv"), out));
+
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_aspas(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("add") {
             Self::parse_matches_cas_aspas_add(m)
@@ -2063,6 +4318,10 @@ impl Options {
             Self::parse_matches_cas_aspas_update(m)
         } else if let Some(m) = matches.subcommand_matches("list") {
             Self::parse_matches_cas_aspas_list(m)
+        } else if let Some(m) = matches.subcommand_matches("import") {
+            Self::parse_matches_cas_aspas_import(m)
+        } else if let Some(m) = matches.subcommand_matches("export") {
+            Self::parse_matches_cas_aspas_export(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2095,6 +4354,24 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_repo_statuses(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::RepoStatuses(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_repo_check(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::RepoCheck(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_repo_configure(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
@@ -2103,20 +4380,256 @@ impl Options {
         let bytes = Self::read_file_arg(path)?;
         let response = idexchange::RepositoryResponse::parse(bytes.as_ref())?;
 
-        let command = Command::CertAuth(CaCommand::RepoUpdate(my_ca, response));
+        let command = Command::CertAuth(CaCommand::RepoUpdate(my_ca, response));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_repo_idroll(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("init") {
+            (m, CaCommand::RepoIdRollInit(Self::parse_my_ca(m)?))
+        } else if let Some(m) = matches.subcommand_matches("request") {
+            (m, CaCommand::RepoIdRollRequest(Self::parse_my_ca(m)?))
+        } else if let Some(m) = matches.subcommand_matches("activate") {
+            (m, CaCommand::RepoIdRollActivate(Self::parse_my_ca(m)?))
+        } else {
+            return Err(Error::UnrecognizedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::CertAuth(command)))
+    }
+
+    fn parse_matches_cas_repo_migrate(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let path = matches.value_of("response").unwrap();
+        let bytes = Self::read_file_arg(path)?;
+        let response = idexchange::RepositoryResponse::parse(bytes.as_ref())?;
+
+        let command = Command::CertAuth(CaCommand::RepoMigrate(my_ca, response));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_repo_classes(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("list") {
+            (m, CaCommand::ClassRepoList(Self::parse_my_ca(m)?))
+        } else if let Some(m) = matches.subcommand_matches("configure") {
+            let rcn = ResourceClassName::from(m.value_of("class").unwrap());
+            let bytes = Self::read_file_arg(m.value_of("response").unwrap())?;
+            let response = idexchange::RepositoryResponse::parse(bytes.as_ref())?;
+            (
+                m,
+                CaCommand::ClassRepoUpdate(Self::parse_my_ca(m)?, rcn, Some(response)),
+            )
+        } else if let Some(m) = matches.subcommand_matches("reset") {
+            let rcn = ResourceClassName::from(m.value_of("class").unwrap());
+            (m, CaCommand::ClassRepoUpdate(Self::parse_my_ca(m)?, rcn, None))
+        } else {
+            return Err(Error::UnrecognizedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::CertAuth(command)))
+    }
+
+    fn parse_matches_cas_repo_migration(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("status") {
+            (m, CaCommand::RepoMigrationStatus(Self::parse_my_ca(m)?))
+        } else if let Some(m) = matches.subcommand_matches("activate") {
+            (m, CaCommand::RepoMigrationActivate(Self::parse_my_ca(m)?))
+        } else if let Some(m) = matches.subcommand_matches("abort") {
+            (m, CaCommand::RepoMigrationAbort(Self::parse_my_ca(m)?))
+        } else {
+            return Err(Error::UnrecognizedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::CertAuth(command)))
+    }
+
+    fn parse_matches_cas_repo(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("request") {
+            Self::parse_matches_cas_repo_request(m)
+        } else if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_cas_repo_details(m)
+        } else if let Some(m) = matches.subcommand_matches("status") {
+            Self::parse_matches_cas_repo_status(m)
+        } else if let Some(m) = matches.subcommand_matches("statuses") {
+            Self::parse_matches_cas_repo_statuses(m)
+        } else if let Some(m) = matches.subcommand_matches("check") {
+            Self::parse_matches_cas_repo_check(m)
+        } else if let Some(m) = matches.subcommand_matches("configure") {
+            Self::parse_matches_cas_repo_configure(m)
+        } else if let Some(m) = matches.subcommand_matches("idroll") {
+            Self::parse_matches_cas_repo_idroll(m)
+        } else if let Some(m) = matches.subcommand_matches("classes") {
+            Self::parse_matches_cas_repo_classes(m)
+        } else if let Some(m) = matches.subcommand_matches("migration") {
+            Self::parse_matches_cas_repo_migration(m)
+        } else if let Some(m) = matches.subcommand_matches("migrate") {
+            Self::parse_matches_cas_repo_migrate(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_timing_show(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::IssuanceTimingShow(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_timing_effective(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::IssuanceTimingEffective(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_timing_arg(matches: &ArgMatches, name: &str) -> Result<Option<u32>, Error> {
+        match matches.value_of(name) {
+            None => Ok(None),
+            Some(value) => u32::from_str(value)
+                .map(Some)
+                .map_err(|e| Error::general(&format!("Invalid number for '{}': {}", name, e))),
+        }
+    }
+
+    fn parse_matches_cas_timing_update(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let overrides = IssuanceTimingOverrides {
+            timing_publish_next_hours: Self::parse_timing_arg(matches, "publish-next-hours")?,
+            timing_publish_next_jitter_hours: Self::parse_timing_arg(matches, "publish-next-jitter-hours")?,
+            timing_publish_hours_before_next: Self::parse_timing_arg(matches, "publish-hours-before-next")?,
+            timing_roa_valid_weeks: Self::parse_timing_arg(matches, "roa-valid-weeks")?,
+            timing_roa_reissue_weeks_before: Self::parse_timing_arg(matches, "roa-reissue-weeks-before")?,
+            timing_aspa_valid_weeks: Self::parse_timing_arg(matches, "aspa-valid-weeks")?,
+            timing_aspa_reissue_weeks_before: Self::parse_timing_arg(matches, "aspa-reissue-weeks-before")?,
+            timing_roa_valid_jitter_hours: Self::parse_timing_arg(matches, "roa-valid-jitter-hours")?,
+            timing_aspa_valid_jitter_hours: Self::parse_timing_arg(matches, "aspa-valid-jitter-hours")?,
+            timing_renew_spread_hours: Self::parse_timing_arg(matches, "renew-spread-hours")?,
+        };
+
+        let command = Command::CertAuth(CaCommand::IssuanceTimingUpdate(my_ca, overrides));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_timing(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_cas_timing_show(m)
+        } else if let Some(m) = matches.subcommand_matches("effective") {
+            Self::parse_matches_cas_timing_effective(m)
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            Self::parse_matches_cas_timing_update(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_retry_show(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::RetryPolicyShow(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_retry_update(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let parent = match matches.value_of("parent") {
+            None => None,
+            Some(parent) => Some(ParentHandle::from_str(parent).map_err(|_| Error::InvalidHandle)?),
+        };
+
+        let timeout_seconds = match matches.value_of("timeout-seconds") {
+            None => None,
+            Some(value) => Some(
+                u64::from_str(value)
+                    .map_err(|e| Error::general(&format!("Invalid number for 'timeout-seconds': {}", e)))?,
+            ),
+        };
+
+        let policy = RetryPolicy {
+            initial_delay_seconds: Self::parse_timing_arg(matches, "initial-delay-seconds")?,
+            backoff_factor: Self::parse_timing_arg(matches, "backoff-factor")?,
+            max_delay_seconds: Self::parse_timing_arg(matches, "max-delay-seconds")?,
+            max_attempts: Self::parse_timing_arg(matches, "max-attempts")?,
+            jitter_seconds: Self::parse_timing_arg(matches, "jitter-seconds")?,
+            timeout_seconds,
+        };
+
+        let command = Command::CertAuth(CaCommand::RetryPolicyUpdate(my_ca, parent, policy));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_retry(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_cas_retry_show(m)
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            Self::parse_matches_cas_retry_update(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_cas_labels_show(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::LabelsShow(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_labels_update(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let mut set = BTreeMap::new();
+        if let Some(labels) = matches.values_of("set") {
+            for label in labels {
+                let (key, value) = label
+                    .split_once('=')
+                    .ok_or_else(|| Error::general(&format!("Invalid label '{}', expected key=value", label)))?;
+                set.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+
+        let remove = match matches.values_of("remove") {
+            None => vec![],
+            Some(keys) => keys.map(|key| key.to_string()).collect(),
+        };
+
+        let update = CaLabelsUpdate::new(set, remove);
+        if update.is_empty() {
+            return Err(Error::general("Specify at least one label to set or remove"));
+        }
+
+        let command = Command::CertAuth(CaCommand::LabelsUpdate(my_ca, update));
 
         Ok(Options::make(general_args, command))
     }
 
-    fn parse_matches_cas_repo(matches: &ArgMatches) -> Result<Options, Error> {
-        if let Some(m) = matches.subcommand_matches("request") {
-            Self::parse_matches_cas_repo_request(m)
-        } else if let Some(m) = matches.subcommand_matches("show") {
-            Self::parse_matches_cas_repo_details(m)
-        } else if let Some(m) = matches.subcommand_matches("status") {
-            Self::parse_matches_cas_repo_status(m)
-        } else if let Some(m) = matches.subcommand_matches("configure") {
-            Self::parse_matches_cas_repo_configure(m)
+    fn parse_matches_cas_labels(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_cas_labels_show(m)
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            Self::parse_matches_cas_labels_update(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2132,6 +4645,46 @@ impl Options {
         Ok(Options::make(general, command))
     }
 
+    fn parse_matches_cas_expiry(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let ca = Self::parse_my_ca(matches)?;
+        let window_hours = Self::parse_timing_arg(matches, "window-hours")?;
+        let command = Command::CertAuth(CaCommand::Expiry(ca, window_hours));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_reconcile(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let ca = Self::parse_my_ca(matches)?;
+        let command = if matches.is_present("last") {
+            Command::CertAuth(CaCommand::ReconcileLast(ca))
+        } else {
+            Command::CertAuth(CaCommand::Reconcile(ca, matches.is_present("repair")))
+        };
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_export(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let ca = Self::parse_my_ca(matches)?;
+
+        let out = match matches.value_of("out") {
+            None => None,
+            Some(out_file) => Some(
+                PathBuf::from_str(out_file)
+                    .map_err(|_| Error::GeneralArgumentError(format!("Invalid filename: {}", out_file)))?,
+            ),
+        };
+
+        let command = Command::CertAuth(CaCommand::Export(
+            ca,
+            matches.is_present("full"),
+            matches.is_present("include-keys"),
+            out,
+        ));
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_rta_list(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let ca = Self::parse_my_ca(matches)?;
@@ -2274,6 +4827,139 @@ impl Options {
             let general_args = GeneralArgs::from_matches(m)?;
             let command = Command::Bulk(BulkCaCommand::Sync);
             Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("snapshot") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Bulk(BulkCaCommand::Snapshot);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("verify") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Bulk(BulkCaCommand::VerifySnapshots);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("migrate") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let path = m.value_of("file").unwrap(); // required argument
+            let bytes = Self::read_file_arg(path)?;
+            let migration = serde_json::from_slice(&bytes)
+                .map_err(|e| Error::GeneralArgumentError(format!("Invalid CA migration JSON: {}", e)))?;
+            let command = Command::Bulk(BulkCaCommand::Migrate(migration, m.is_present("background")));
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("run") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let operation = BulkOperation::from_str(m.value_of("operation").unwrap()) // required argument
+                .map_err(Error::GeneralArgumentError)?;
+            let selector = if let Some(glob) = m.value_of("glob") {
+                CaSelector::Glob(glob.to_string())
+            } else if let Some(regex) = m.value_of("regex") {
+                CaSelector::Regex(regex.to_string())
+            } else if let Some(label) = m.value_of("label") {
+                CaSelector::Label(label.to_string())
+            } else {
+                CaSelector::All
+            };
+            let command = Command::Bulk(BulkCaCommand::JobStart(BulkJobRequest::new(selector, operation)));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_backup(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("create") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Backup(BackupCommand::Create);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("list") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Backup(BackupCommand::List);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("restore") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let name = m.value_of("name").unwrap().to_string();
+            let force = m.is_present("force");
+            let command = Command::Backup(BackupCommand::Restore(name, force));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_ha(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("status") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Ha(HaCommand::Status);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("promote") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Ha(HaCommand::Promote);
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_time(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("status") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Time(TimeCommand::Status);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("check") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Time(TimeCommand::Check);
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_keys(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Keys(KeysCommand::List);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("gc") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Keys(KeysCommand::Gc(m.is_present("confirm")));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_jobs(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Jobs(JobsCommand::List);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("show") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let id = m.value_of("id").unwrap().to_string();
+            let command = Command::Jobs(JobsCommand::Show(id));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_tasks(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Tasks(TaskCommand::List);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("trigger") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let name = m.value_of("name").unwrap().to_string();
+            let command = Command::Tasks(TaskCommand::Trigger(name));
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("pause") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let name = m.value_of("name").unwrap().to_string();
+            let command = Command::Tasks(TaskCommand::Pause(name));
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("resume") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let name = m.value_of("name").unwrap().to_string();
+            let command = Command::Tasks(TaskCommand::Resume(name));
+            Ok(Options::make(general_args, command))
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2291,6 +4977,86 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_apply(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let path = PathBuf::from(matches.value_of("file").unwrap());
+        let state = DesiredState::load(&path)?;
+        let dry_run = matches.is_present("dry-run");
+        let command = Command::Apply(state, dry_run);
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_xml_file(matches: &ArgMatches) -> Result<(GeneralArgs, Rfc8183Xml), Error> {
+        let general_args = GeneralArgs {
+            format: GeneralArgs::format_from_matches(matches)?,
+            ..GeneralArgs::default()
+        };
+        let xml = Rfc8183Xml::load(&PathBuf::from(matches.value_of("file").unwrap()))?;
+        Ok((general_args, xml))
+    }
+
+    fn parse_matches_xml(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("show") {
+            let (general_args, xml) = Self::parse_matches_xml_file(m)?;
+            Ok(Options::make(general_args, Command::Xml(XmlCommand::Show(xml))))
+        } else if let Some(m) = matches.subcommand_matches("validate") {
+            let (general_args, xml) = Self::parse_matches_xml_file(m)?;
+            Ok(Options::make(general_args, Command::Xml(XmlCommand::Validate(xml))))
+        } else if let Some(m) = matches.subcommand_matches("certs") {
+            let (general_args, xml) = Self::parse_matches_xml_file(m)?;
+            Ok(Options::make(general_args, Command::Xml(XmlCommand::Certs(xml))))
+        } else if let Some(m) = matches.subcommand_matches("diff") {
+            let general_args = GeneralArgs {
+                format: GeneralArgs::format_from_matches(m)?,
+                ..GeneralArgs::default()
+            };
+            let left = Rfc8183Xml::load(&PathBuf::from(m.value_of("left").unwrap()))?;
+            let right = Rfc8183Xml::load(&PathBuf::from(m.value_of("right").unwrap()))?;
+            Ok(Options::make(general_args, Command::Xml(XmlCommand::Diff(left, right))))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_completions(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::default();
+        let shell = CompletionShell::from_str(matches.value_of("shell").unwrap())?;
+        let command = Command::Completions(shell);
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_shell(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let command = Command::Shell;
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_reload(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let command = Command::Reload;
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_doctor(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let command = Command::Doctor;
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_schema(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let command = Command::Schema;
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_search(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let query = matches.value_of("query").unwrap(); // required argument
+        let query = SearchQuery::from_str(query).map_err(|e| Error::GeneralArgumentError(e.to_string()))?;
+        let command = Command::Search(query);
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_publisher_arg(matches: &ArgMatches) -> Result<PublisherHandle, Error> {
         let publisher_str = matches.value_of("publisher").unwrap();
         PublisherHandle::from_str(publisher_str).map_err(|_| Error::InvalidHandle)
@@ -2351,12 +5117,106 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_publishers_stats(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let publisher = match matches.value_of("publisher") {
+            Some(publisher) => Some(PublisherHandle::from_str(publisher).map_err(|_| Error::InvalidHandle)?),
+            None => None,
+        };
+        let command = Command::PubServer(PubServerCommand::ShowPublisherStats(publisher));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_quota_arg(matches: &ArgMatches, name: &str) -> Result<Option<usize>, Error> {
+        match matches.value_of(name) {
+            None => Ok(None),
+            Some(value) => usize::from_str(value)
+                .map(Some)
+                .map_err(|e| Error::general(&format!("Invalid number for '{}': {}", name, e))),
+        }
+    }
+
+    fn parse_matches_publishers_quota(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("show") {
+            let publisher = Self::parse_publisher_arg(m)?;
+            (m, PubServerCommand::ShowPublisherQuota(publisher))
+        } else if let Some(m) = matches.subcommand_matches("set") {
+            let publisher = Self::parse_publisher_arg(m)?;
+            let quota = PublisherQuota::new(
+                Self::parse_quota_arg(m, "max-objects")?,
+                Self::parse_quota_arg(m, "max-bytes")?,
+                Self::parse_quota_arg(m, "max-deltas-per-hour")?,
+            );
+            (m, PubServerCommand::UpdatePublisherQuota(publisher, Some(quota)))
+        } else if let Some(m) = matches.subcommand_matches("reset") {
+            let publisher = Self::parse_publisher_arg(m)?;
+            (m, PubServerCommand::UpdatePublisherQuota(publisher, None))
+        } else {
+            return Err(Error::UnrecognizedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::PubServer(command)))
+    }
+
+    fn parse_matches_publishers_validation(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("show") {
+            let publisher = Self::parse_publisher_arg(m)?;
+            (m, PubServerCommand::ShowPublisherValidation(publisher))
+        } else if let Some(m) = matches.subcommand_matches("set") {
+            let publisher = Self::parse_publisher_arg(m)?;
+            let validation = PublisherValidation::from_str(m.value_of("validation").unwrap())
+                .map_err(|e| Error::GeneralArgumentError(e.to_string()))?;
+            (
+                m,
+                PubServerCommand::UpdatePublisherValidation(publisher, Some(validation)),
+            )
+        } else if let Some(m) = matches.subcommand_matches("reset") {
+            let publisher = Self::parse_publisher_arg(m)?;
+            (m, PubServerCommand::UpdatePublisherValidation(publisher, None))
+        } else {
+            return Err(Error::UnrecognizedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::PubServer(command)))
+    }
+
+    fn parse_matches_publishers_idcert(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("stage") {
+            let publisher = Self::parse_publisher_arg(m)?;
+            let bytes = Self::read_file_arg(m.value_of("request").unwrap())?;
+            let req = idexchange::PublisherRequest::parse(bytes.as_ref())?;
+            req.validate().map_err(|e| {
+                Error::GeneralArgumentError(format!("Invalid certificate in RFC 8183 Publisher Request XML: {}", e))
+            })?;
+            (m, PubServerCommand::StagePublisherIdCert(publisher, Some(req)))
+        } else if let Some(m) = matches.subcommand_matches("activate") {
+            let publisher = Self::parse_publisher_arg(m)?;
+            (m, PubServerCommand::ActivatePublisherIdCert(publisher))
+        } else if let Some(m) = matches.subcommand_matches("cancel") {
+            let publisher = Self::parse_publisher_arg(m)?;
+            (m, PubServerCommand::StagePublisherIdCert(publisher, None))
+        } else {
+            return Err(Error::UnrecognizedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::PubServer(command)))
+    }
+
     fn parse_matches_publication_server_stats(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let command = Command::PubServer(PubServerCommand::RepositoryStats);
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_publication_server_rrdp(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let command = Command::PubServer(PubServerCommand::RrdpStats);
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_publication_server_init(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
 
@@ -2395,15 +5255,49 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_publication_server_archive(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::PubServer(PubServerCommand::RepositoryArchiveList);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("create") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::PubServer(PubServerCommand::RepositoryArchiveCreate);
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_publication_server_replica(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("status") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::PubServer(PubServerCommand::RepositoryReplicaStatus);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("sync") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::PubServer(PubServerCommand::RepositoryReplicaSync);
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_publication_server(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("stats") {
             Self::parse_matches_publication_server_stats(m)
+        } else if let Some(m) = matches.subcommand_matches("rrdp") {
+            Self::parse_matches_publication_server_rrdp(m)
         } else if let Some(m) = matches.subcommand_matches("init") {
             Self::parse_matches_publication_server_init(m)
         } else if let Some(m) = matches.subcommand_matches("clear") {
             Self::parse_matches_publication_server_clear(m)
         } else if let Some(m) = matches.subcommand_matches("session-reset") {
             Self::parse_matches_publication_server_server_reset(m)
+        } else if let Some(m) = matches.subcommand_matches("archive") {
+            Self::parse_matches_publication_server_archive(m)
+        } else if let Some(m) = matches.subcommand_matches("replica") {
+            Self::parse_matches_publication_server_replica(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2422,6 +5316,14 @@ impl Options {
             Self::parse_matches_publishers_show(m)
         } else if let Some(m) = matches.subcommand_matches("response") {
             Self::parse_matches_publishers_repo_response(m)
+        } else if let Some(m) = matches.subcommand_matches("stats") {
+            Self::parse_matches_publishers_stats(m)
+        } else if let Some(m) = matches.subcommand_matches("quota") {
+            Self::parse_matches_publishers_quota(m)
+        } else if let Some(m) = matches.subcommand_matches("validation") {
+            Self::parse_matches_publishers_validation(m)
+        } else if let Some(m) = matches.subcommand_matches("idcert") {
+            Self::parse_matches_publishers_idcert(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2474,31 +5376,161 @@ impl Options {
             Self::parse_matches_cas_routes(m)
         } else if let Some(m) = matches.subcommand_matches("bgpsec") {
             Self::parse_matches_cas_bgpsec(m)
+        } else if let Some(m) = matches.subcommand_matches("rsc") {
+            Self::parse_matches_cas_rsc(m)
         } else if let Some(m) = matches.subcommand_matches("aspas") {
             Self::parse_matches_cas_aspas(m)
+        } else if let Some(m) = matches.subcommand_matches("scheduled") {
+            Self::parse_matches_cas_scheduled(m)
         } else if let Some(m) = matches.subcommand_matches("repo") {
             Self::parse_matches_cas_repo(m)
+        } else if let Some(m) = matches.subcommand_matches("timing") {
+            Self::parse_matches_cas_timing(m)
+        } else if let Some(m) = matches.subcommand_matches("retry") {
+            Self::parse_matches_cas_retry(m)
+        } else if let Some(m) = matches.subcommand_matches("labels") {
+            Self::parse_matches_cas_labels(m)
         } else if let Some(m) = matches.subcommand_matches("issues") {
             Self::parse_matches_cas_issues(m)
+        } else if let Some(m) = matches.subcommand_matches("expiry") {
+            Self::parse_matches_cas_expiry(m)
+        } else if let Some(m) = matches.subcommand_matches("reconcile") {
+            Self::parse_matches_cas_reconcile(m)
+        } else if let Some(m) = matches.subcommand_matches("export") {
+            Self::parse_matches_cas_export(m)
         } else if let Some(m) = matches.subcommand_matches("rta") {
             Self::parse_matches_cas_rta(m)
         } else if let Some(m) = matches.subcommand_matches("bulk") {
             Self::parse_matches_bulk(m)
+        } else if let Some(m) = matches.subcommand_matches("backup") {
+            Self::parse_matches_backup(m)
+        } else if let Some(m) = matches.subcommand_matches("ha") {
+            Self::parse_matches_ha(m)
+        } else if let Some(m) = matches.subcommand_matches("time") {
+            Self::parse_matches_time(m)
+        } else if let Some(m) = matches.subcommand_matches("keys") {
+            Self::parse_matches_keys(m)
+        } else if let Some(m) = matches.subcommand_matches("jobs") {
+            Self::parse_matches_jobs(m)
+        } else if let Some(m) = matches.subcommand_matches("tasks") {
+            Self::parse_matches_tasks(m)
         } else if let Some(m) = matches.subcommand_matches("health") {
             Self::parse_matches_health(m)
         } else if let Some(m) = matches.subcommand_matches("info") {
             Self::parse_matches_info(m)
+        } else if let Some(m) = matches.subcommand_matches("reload") {
+            Self::parse_matches_reload(m)
+        } else if let Some(m) = matches.subcommand_matches("doctor") {
+            Self::parse_matches_doctor(m)
+        } else if let Some(m) = matches.subcommand_matches("schema") {
+            Self::parse_matches_schema(m)
+        } else if let Some(m) = matches.subcommand_matches("search") {
+            Self::parse_matches_search(m)
         } else if let Some(m) = matches.subcommand_matches("pubserver") {
             Self::parse_matches_pubserver(m)
+        } else if let Some(m) = matches.subcommand_matches("apply") {
+            Self::parse_matches_apply(m)
+        } else if let Some(m) = matches.subcommand_matches("xml") {
+            Self::parse_matches_xml(m)
+        } else if let Some(m) = matches.subcommand_matches("completions") {
+            Self::parse_matches_completions(m)
+        } else if let Some(m) = matches.subcommand_matches("shell") {
+            Self::parse_matches_shell(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
     }
 
     pub fn from_args() -> Result<Options, Error> {
-        let matches = Self::make_matches();
+        let matches = Self::make_app().get_matches();
+        Self::parse_matches(matches)
+    }
+
+    /// Parses the words of a command entered in the interactive shell, which
+    /// does not start with the name of the binary.
+    pub fn from_words(words: Vec<String>) -> Result<Options, Error> {
+        let args = std::iter::once(KRILL_CLIENT_BIN.to_string()).chain(words);
+        let matches = Self::make_app()
+            .get_matches_from_safe(args)
+            .map_err(|e| Error::GeneralArgumentError(e.message))?;
         Self::parse_matches(matches)
     }
+
+    /// Returns the completion script for the shell. The generated static
+    /// completions are extended to complete the values of --ca with the CA
+    /// handles listed by the server, using the KRILL_CLI_SERVER and
+    /// KRILL_CLI_TOKEN of the shell.
+    pub fn completions(shell: CompletionShell) -> String {
+        let mut script = Vec::new();
+        Self::make_app().gen_completions_to(KRILL_CLIENT_BIN, shell.clap_shell(), &mut script);
+        let script = String::from_utf8_lossy(&script);
+
+        let list_cas = format!("{} list --format text 2>/dev/null", KRILL_CLIENT_BIN);
+
+        match shell {
+            CompletionShell::Bash => format!(
+                "{script}
+_{bin}_with_cas() {{
+    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"
+    if [[ \"${{prev}}\" == \"--{ca}\" || \"${{prev}}\" == \"-c\" ]]; then
+        COMPREPLY=( $(compgen -W \"$({list_cas})\" -- \"${{COMP_WORDS[COMP_CWORD]}}\") )
+        return 0
+    fi
+    _{bin} \"$@\"
+}}
+
+complete -F _{bin}_with_cas -o bashdefault -o default {bin}
+",
+                script = script,
+                bin = KRILL_CLIENT_BIN,
+                ca = KRILL_CLI_MY_CA_ARG,
+                list_cas = list_cas
+            ),
+            CompletionShell::Zsh => {
+                // Complete the values of --ca and -c with the CA handles, and
+                // define the function for this before the script completes.
+                let short = "'-c+[";
+                let long = format!("'--{}=[", KRILL_CLI_MY_CA_ARG);
+                let lines: Vec<String> = script
+                    .lines()
+                    .map(|line| {
+                        if (line.contains(short) || line.contains(&long)) && line.ends_with("]' \\") {
+                            format!("{}]: :_{}_cas' \\", &line[..line.len() - 4], KRILL_CLIENT_BIN)
+                        } else {
+                            line.to_string()
+                        }
+                    })
+                    .collect();
+                let script = lines.join("\n");
+
+                let call = format!("_{} \"$@\"", KRILL_CLIENT_BIN);
+                let cas_fn = format!(
+                    "_{bin}_cas() {{
+    local -a cas
+    cas=(${{(f)\"$({list_cas})\"}})
+    _describe 'CA' cas
+}}
+
+",
+                    bin = KRILL_CLIENT_BIN,
+                    list_cas = list_cas
+                );
+                match script.rfind(&call) {
+                    Some(pos) => format!("{}{}{}\n", &script[..pos], cas_fn, &script[pos..]),
+                    None => format!("{}\n{}", script, cas_fn),
+                }
+            }
+            CompletionShell::Fish => format!(
+                "{script}
+complete -c {bin} -s c -l {ca} -x -a '({list_cas})'
+",
+                script = script,
+                bin = KRILL_CLIENT_BIN,
+                ca = KRILL_CLI_MY_CA_ARG,
+                list_cas = list_cas
+            ),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -2507,32 +5539,106 @@ pub enum Command {
     NotSet,
     Health,
     Info,
+    Reload,
+    Doctor,
+    Schema,
+    Search(SearchQuery),
     Bulk(BulkCaCommand),
+    Backup(BackupCommand),
+    Ha(HaCommand),
+    Time(TimeCommand),
+    Keys(KeysCommand),
+    Jobs(JobsCommand),
+    Tasks(TaskCommand),
     CertAuth(CaCommand),
     PubServer(PubServerCommand),
     Init(KrillInitDetails),
     #[cfg(feature = "multi-user")]
     User(KrillUserDetails),
+    Apply(DesiredState, bool), // bool: dry run
+    Xml(XmlCommand),
+    Completions(CompletionShell),
+    Shell,
+}
+
+//------------ CompletionShell -----------------------------------------------
+
+/// The shells for which krillc can print a completion script.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl CompletionShell {
+    fn clap_shell(self) -> Shell {
+        match self {
+            CompletionShell::Bash => Shell::Bash,
+            CompletionShell::Zsh => Shell::Zsh,
+            CompletionShell::Fish => Shell::Fish,
+        }
+    }
+}
+
+impl FromStr for CompletionShell {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(CompletionShell::Bash),
+            "zsh" => Ok(CompletionShell::Zsh),
+            "fish" => Ok(CompletionShell::Fish),
+            _ => Err(Error::GeneralArgumentError(format!("Unsupported shell: {}", s))),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[allow(clippy::large_enum_variant)]
 pub enum CaCommand {
-    Init(CertAuthInit), // Initialize a CA
-    UpdateId(CaHandle), // Update CA id
-    Delete(CaHandle),   // Delete the CA -> let it withdraw and request revocation as well
+    Init(CertAuthInit),     // Initialize a CA
+    UpdateId(CaHandle),     // Update CA id
+    Delete(CaHandle, bool), // Delete the CA -> let it withdraw and request revocation as well. bool: force
 
     // Publishing
     RepoPublisherRequest(CaHandle), // Get the RFC 8183 Publisher Request
     RepoDetails(CaHandle),
     RepoUpdate(CaHandle, idexchange::RepositoryResponse),
     RepoStatus(CaHandle),
+    RepoCheck(CaHandle),
+    RepoIdRollInit(CaHandle), // Generate a new ID cert to roll to, and get its RFC 8183 Publisher Request
+    RepoIdRollRequest(CaHandle), // Get the RFC 8183 Publisher Request for the new ID cert
+    RepoIdRollActivate(CaHandle), // Start using the new ID cert
+    RepoStatuses(CaHandle),   // Status for each repository used
+    ClassRepoList(CaHandle),
+    ClassRepoUpdate(CaHandle, ResourceClassName, Option<idexchange::RepositoryResponse>), // None: use the CA repository
+    RepoMigrationStatus(CaHandle),
+    RepoMigrationActivate(CaHandle),
+    RepoMigrationAbort(CaHandle),
+    RepoMigrate(CaHandle, idexchange::RepositoryResponse), // Migrate to the new repository, or resume
+
+    // Issuance timing
+    IssuanceTimingShow(CaHandle),
+    IssuanceTimingEffective(CaHandle),
+    IssuanceTimingUpdate(CaHandle, IssuanceTimingOverrides),
+
+    // Retry policies
+    RetryPolicyShow(CaHandle),
+    RetryPolicyUpdate(CaHandle, Option<ParentHandle>, RetryPolicy),
+
+    // Labels
+    LabelsShow(CaHandle),
+    LabelsUpdate(CaHandle, CaLabelsUpdate),
 
     // Parents (to this CA)
     ChildRequest(CaHandle), // Get the RFC 8183 Child Request
     AddParent(CaHandle, ParentCaReq),
+    AddParentFetch(CaHandle, ParentHandle, ParentResponseFetch),
     MyParentCaContact(CaHandle, ParentHandle),
     ParentStatuses(CaHandle),
+    ParentStats(CaHandle),
+    ParentExchanges(CaHandle, ParentHandle),
     RemoveParent(CaHandle, ParentHandle),
     Refresh(CaHandle), // Refresh with all parents
 
@@ -2543,16 +5649,40 @@ pub enum CaCommand {
     ChildUpdate(CaHandle, ChildHandle, UpdateChildRequest),
     ChildDelete(CaHandle, ChildHandle),
     ChildConnections(CaHandle),
+    ChildStats(CaHandle),
+    ChildRequests(CaHandle, ChildHandle),
+    ChildRequestApprove(CaHandle, ChildHandle, PendingChildRequestKind, KeyIdentifier),
+    ChildIssuancePolicyShow(CaHandle),
+    ChildIssuancePolicyUpdate(CaHandle, ChildIssuancePolicy),
+    ChildTransferStart(CaHandle, ResourceTransferRequest),
+    ChildTransfers(CaHandle),
+    ChildTransferShow(CaHandle, u64),
+
+    // Scheduled ROA and ASPA changes
+    ScheduledChangeAdd(CaHandle, ScheduledChangeRequest),
+    ScheduledChanges(CaHandle),
+    ScheduledChangeCancel(CaHandle, u64),
 
     // Key Management
     KeyRollInit(CaHandle),
     KeyRollActivate(CaHandle),
+    KeyRollSigners(CaHandle),
+    KeyRollMigrate(CaHandle),
 
     // Authorizations
     RouteAuthorizationsList(CaHandle),
-    RouteAuthorizationsUpdate(CaHandle, RoaConfigurationUpdates),
+    RouteAuthorizationsUpdate(CaHandle, RoaConfigurationUpdates, bool), // bool: force
     RouteAuthorizationsTryUpdate(CaHandle, RoaConfigurationUpdates),
     RouteAuthorizationsDryRunUpdate(CaHandle, RoaConfigurationUpdates),
+    RouteAuthorizationsImport(CaHandle, RoaImport, bool, bool), // bools: dry run, force
+    RouteAuthorizationsPropose(CaHandle, RoaConfigurationUpdates),
+    RouteAuthorizationsProposals(CaHandle),
+    RouteAuthorizationsApprove(CaHandle, u64, bool), // bool: force
+    RouteAuthorizationsReject(CaHandle, u64),
+    RoaStrategyShow(CaHandle),
+    RoaStrategyUpdate(CaHandle, Option<RoaIssuanceStrategy>), // None: use the server default
+    RoaAutoPilotShow(CaHandle),
+    RoaAutoPilotUpdate(CaHandle, Option<RoaAutoPilotPolicy>), // None: use the server default
     BgpAnalysisFull(CaHandle),
     BgpAnalysisSuggest(CaHandle, Option<ResourceSet>),
 
@@ -2560,7 +5690,10 @@ pub enum CaCommand {
     AspasList(CaHandle),
     AspasAddOrReplace(CaHandle, AspaDefinition),
     AspasUpdate(CaHandle, AspaCustomer, AspaProvidersUpdate),
+    AspasUpdateDryRun(CaHandle, AspaCustomer, AspaProvidersUpdate),
     AspasRemove(CaHandle, AspaCustomer),
+    AspasImport(CaHandle, AspaDefinitionList),
+    AspasExport(CaHandle, bool, Option<PathBuf>), // bool: export as CSV
 
     // BGPSec
     BgpSecList(CaHandle),
@@ -2571,7 +5704,15 @@ pub enum CaCommand {
     Show(CaHandle),
     ShowHistoryCommands(CaHandle, HistoryOptions),
     ShowHistoryDetails(CaHandle, String),
+    RevertHistoryCommand(CaHandle, String, bool), // bool: force
     Issues(Option<CaHandle>),
+    Expiry(CaHandle, Option<u32>), // window in hours
+    Reconcile(CaHandle, bool),     // bool: repair
+    ReconcileLast(CaHandle),
+    Export(CaHandle, bool, bool, Option<PathBuf>), // bools: full, include keys
+
+    // RSC
+    RscSign(CaHandle, RscContentRequest, Option<PathBuf>),
 
     // RTA
     RtaList(CaHandle),
@@ -2580,8 +5721,8 @@ pub enum CaCommand {
     RtaMultiPrep(CaHandle, RtaName, RtaPrepareRequest),
     RtaMultiCoSign(CaHandle, RtaName, ResourceTaggedAttestation),
 
-    // List all CAs
-    List,
+    // List all CAs, or those with matching labels
+    List(Option<CaLabelSelector>),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -2590,6 +5731,11 @@ pub struct HistoryOptions {
     pub rows: u64,
     pub after: Option<Time>,
     pub before: Option<Time>,
+    pub actor: Option<String>,
+    pub archived: bool,
+    pub types: Vec<String>,
+    pub order: HistoryOrder,
+    pub cursor: Option<u64>,
 }
 
 impl Default for HistoryOptions {
@@ -2599,6 +5745,11 @@ impl Default for HistoryOptions {
             rows: 100,
             after: None,
             before: None,
+            actor: None,
+            archived: false,
+            types: vec![],
+            order: HistoryOrder::default(),
+            cursor: None,
         }
     }
 }
@@ -2618,6 +5769,32 @@ impl HistoryOptions {
             "".to_string()
         }
     }
+
+    pub fn url_query_parameters(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(actor) = &self.actor {
+            query.append_pair("actor", actor);
+        }
+        if self.archived {
+            query.append_pair("archived", "true");
+        }
+        if !self.types.is_empty() {
+            query.append_pair("type", &self.types.join(","));
+        }
+        if self.order != HistoryOrder::default() {
+            query.append_pair("order", &self.order.to_string());
+        }
+        if let Some(cursor) = self.cursor {
+            query.append_pair("cursor", &cursor.to_string());
+        }
+
+        let query = query.finish();
+        if query.is_empty() {
+            "".to_string()
+        } else {
+            format!("?{}", query)
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -2628,6 +5805,57 @@ pub enum BulkCaCommand {
     Sync,
     Suspend,
     Import(api::import::Structure),
+    Migrate(api::import::CaMigration, bool), // background
+    Snapshot,                                // save snapshots for all CAs and the Publication Server
+    VerifySnapshots,                         // verify snapshots against events
+    JobStart(BulkJobRequest),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BackupCommand {
+    Create,
+    List,
+    Restore(String, bool), // name of the backup, force
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum HaCommand {
+    Status,
+    Promote,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TimeCommand {
+    Status,
+    Check,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KeysCommand {
+    List,
+    Gc(bool), // confirm
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum JobsCommand {
+    List,
+    Show(String), // id of the job
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TaskCommand {
+    List,
+    Trigger(String), // name of the task
+    Pause(String),
+    Resume(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum XmlCommand {
+    Show(Rfc8183Xml),
+    Validate(Rfc8183Xml),
+    Certs(Rfc8183Xml),
+    Diff(Rfc8183Xml, Rfc8183Xml),
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -2705,12 +5933,24 @@ pub enum PubServerCommand {
     RemovePublisher(PublisherHandle),
     DeleteFiles(RepoFileDeleteCriteria),
     RepositoryResponse(PublisherHandle),
+    ShowPublisherStats(Option<PublisherHandle>),
+    ShowPublisherQuota(PublisherHandle),
+    UpdatePublisherQuota(PublisherHandle, Option<PublisherQuota>),
+    ShowPublisherValidation(PublisherHandle),
+    UpdatePublisherValidation(PublisherHandle, Option<PublisherValidation>),
+    StagePublisherIdCert(PublisherHandle, Option<idexchange::PublisherRequest>),
+    ActivatePublisherIdCert(PublisherHandle),
     StalePublishers(i64),
     PublisherList,
     RepositoryStats,
+    RrdpStats,
     RepositoryInit(PublicationServerUris),
     RepositoryClear,
     RepositorySessionReset,
+    RepositoryArchiveList,
+    RepositoryArchiveCreate,
+    RepositoryReplicaStatus,
+    RepositoryReplicaSync,
 }
 
 //------------ Error ---------------------------------------------------------
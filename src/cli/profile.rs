@@ -0,0 +1,264 @@
+//! Support for named profiles, which give the server, token and default CA
+//! to use, so that operators of several Krill servers do not need to change
+//! environment variables to switch between them.
+//!
+//! Profiles are read from ~/.krillc/config, or from the file set in the
+//! KRILL_CLI_CONFIG environment variable:
+//!
+//! ```toml
+//! default_profile = "production"
+//!
+//! [profiles.production]
+//! server = "https://krill.example.net/"
+//! token = "secret"
+//! ca = "example"
+//!
+//! [profiles.lab]
+//! server = "https://localhost:3000/"
+//! token = "lab-secret"
+//! ```
+//!
+//! A setting is taken from, in order of priority:
+//!  1. the command line, e.g. --server;
+//!  2. the profile selected with --profile;
+//!  3. the environment, e.g. KRILL_CLI_SERVER;
+//!  4. the profile selected with KRILL_CLI_PROFILE, or the default profile.
+//!
+//! So a profile asked for on the command line overrides the environment,
+//! while the environment overrides a profile which was not asked for.
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use clap::ArgMatches;
+
+use crate::{
+    cli::options::Error,
+    constants::{KRILL_CLI_CONFIG_DFLT, KRILL_CLI_CONFIG_ENV, KRILL_CLI_PROFILE_ARG, KRILL_CLI_PROFILE_ENV},
+};
+
+//------------ ProfilesFile --------------------------------------------------
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProfilesFile {
+    /// The profile to use if none is selected with --profile.
+    #[serde(default)]
+    default_profile: Option<String>,
+
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+impl ProfilesFile {
+    /// Returns the path given in the environment, or the default path in the
+    /// home directory of the user.
+    pub fn path() -> Option<PathBuf> {
+        match env::var_os(KRILL_CLI_CONFIG_ENV) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => env::var_os("HOME")
+                .or_else(|| env::var_os("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(KRILL_CLI_CONFIG_DFLT)),
+        }
+    }
+
+    /// Reads the file, returns None if it does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Self::from_str(&content)
+                .map(Some)
+                .map_err(|e| Error::GeneralArgumentError(format!("Cannot parse '{}': {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::GeneralArgumentError(format!(
+                "Cannot read '{}': {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    /// Returns the profile with the given name, or the default profile if
+    /// no name is given.
+    pub fn profile(&self, name: Option<&str>) -> Result<Option<&Profile>, String> {
+        match name.or(self.default_profile.as_deref()) {
+            None => Ok(None),
+            Some(name) => match self.profiles.get(name) {
+                Some(profile) => Ok(Some(profile)),
+                None => {
+                    let mut known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                    known.sort_unstable();
+                    Err(format!(
+                        "Unknown profile '{}', known profiles: {}",
+                        name,
+                        known.join(", ")
+                    ))
+                }
+            },
+        }
+    }
+}
+
+impl FromStr for ProfilesFile {
+    type Err = toml::de::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s)
+    }
+}
+
+//------------ Profile -------------------------------------------------------
+
+/// The settings of a profile. See the module documentation for which
+/// settings take precedence.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    #[serde(default)]
+    pub server: Option<String>,
+
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// The CA to use for commands which need one.
+    #[serde(default)]
+    pub ca: Option<String>,
+}
+
+impl Profile {
+    /// Returns the profile selected with --profile or KRILL_CLI_PROFILE, or
+    /// else the default profile if one is set.
+    pub fn select(matches: &ArgMatches) -> Result<Option<Profile>, Error> {
+        let name = matches
+            .value_of(KRILL_CLI_PROFILE_ARG)
+            .map(str::to_string)
+            .or_else(|| env::var(KRILL_CLI_PROFILE_ENV).ok());
+
+        let path = match ProfilesFile::path() {
+            Some(path) => path,
+            None => {
+                return match name {
+                    None => Ok(None),
+                    Some(name) => Err(Error::GeneralArgumentError(format!(
+                        "Cannot find profile '{}', set the file to use in env: {}",
+                        name, KRILL_CLI_CONFIG_ENV
+                    ))),
+                }
+            }
+        };
+
+        let file = match ProfilesFile::load(&path)? {
+            Some(file) => file,
+            None => {
+                return match name {
+                    None => Ok(None),
+                    Some(name) => Err(Error::GeneralArgumentError(format!(
+                        "Cannot find profile '{}', there is no file '{}'",
+                        name,
+                        path.display()
+                    ))),
+                }
+            }
+        };
+
+        file.profile(name.as_deref())
+            .map(|profile| profile.cloned())
+            .map_err(|e| Error::GeneralArgumentError(format!("{} in '{}'", e, path.display())))
+    }
+}
+
+/// Returns the value of a setting with the highest priority, see the module
+/// documentation.
+pub fn resolve_setting(
+    arg_value: Option<&str>,
+    env_value: Option<String>,
+    profile_value: Option<&str>,
+    profile_on_command_line: bool,
+) -> Option<String> {
+    let arg_value = arg_value.map(str::to_string);
+    let profile_value = profile_value.map(str::to_string);
+    if profile_on_command_line {
+        arg_value.or(profile_value).or(env_value)
+    } else {
+        arg_value.or(env_value).or(profile_value)
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILES: &str = r#"
+        default_profile = "production"
+
+        [profiles.production]
+        server = "https://krill.example.net/"
+        token = "secret"
+        ca = "example"
+
+        [profiles.lab]
+        server = "https://localhost:3000/"
+    "#;
+
+    #[test]
+    fn select_profile() {
+        let file = ProfilesFile::from_str(PROFILES).unwrap();
+
+        let production = file.profile(None).unwrap().unwrap();
+        assert_eq!(production.server.as_deref(), Some("https://krill.example.net/"));
+        assert_eq!(production.token.as_deref(), Some("secret"));
+        assert_eq!(production.ca.as_deref(), Some("example"));
+
+        let lab = file.profile(Some("lab")).unwrap().unwrap();
+        assert_eq!(lab.server.as_deref(), Some("https://localhost:3000/"));
+        assert_eq!(lab.token, None);
+
+        assert_eq!(
+            file.profile(Some("testbed")).unwrap_err(),
+            "Unknown profile 'testbed', known profiles: lab, production"
+        );
+    }
+
+    #[test]
+    fn setting_priority() {
+        let env = || Some("env".to_string());
+
+        // The command line always wins.
+        assert_eq!(
+            resolve_setting(Some("arg"), env(), Some("profile"), true),
+            Some("arg".to_string())
+        );
+
+        // A profile given with --profile overrides the environment, but not
+        // a profile from the environment or the default profile.
+        assert_eq!(
+            resolve_setting(None, env(), Some("profile"), true),
+            Some("profile".to_string())
+        );
+        assert_eq!(
+            resolve_setting(None, env(), Some("profile"), false),
+            Some("env".to_string())
+        );
+
+        // Settings missing from a profile are still taken from the
+        // environment.
+        assert_eq!(resolve_setting(None, env(), None, true), Some("env".to_string()));
+        assert_eq!(
+            resolve_setting(None, None, Some("profile"), false),
+            Some("profile".to_string())
+        );
+        assert_eq!(resolve_setting(None, None, None, false), None);
+    }
+
+    #[test]
+    fn no_default_profile() {
+        let file = ProfilesFile::from_str("[profiles.lab]\ntoken = \"secret\"\n").unwrap();
+        assert_eq!(file.profile(None).unwrap(), None);
+
+        assert!(ProfilesFile::from_str("[profiles.lab]\ntokn = \"secret\"\n").is_err());
+    }
+}
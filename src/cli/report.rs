@@ -6,22 +6,35 @@ use serde::Serialize;
 use rpki::ca::idexchange;
 
 use crate::{
+    cli::{
+        apply::ApplyReport,
+        xml::{XmlDiff, XmlIdCert, XmlSummary},
+    },
     commons::{
+        api::import::{CaMigration, CaMigrationReport},
         api::{
-            AllCertAuthIssues, AspaDefinitionList, BgpSecCsrInfoList, CaCommandDetails, CaRepoDetails, CertAuthInfo,
-            CertAuthIssues, CertAuthList, ChildCaInfo, ChildrenConnectionStats, CommandHistory, ConfiguredRoas,
-            IdCertInfo, ParentCaContact, ParentStatuses, PublisherDetails, PublisherList, RepoStatus,
-            RepositoryContact, RtaList, RtaPrepResponse, ServerInfo,
+            AggregateSnapshots, AllCertAuthIssues, AspaDefinitionList, AspaDefinitionUpdates, BackupInfo, BackupList,
+            BgpSecCsrInfoList, CaCommandDetails, CaLabels, CaRepoDetails, CertAuthInfo, CertAuthIssues, CertAuthList,
+            ChildCaInfo, ChildIssuancePolicy, ChildrenConnectionStats, ChildrenStats, ClassRepoDetails, CommandHistory,
+            CommandRevert, ConfigReloadReport, ConfiguredRoas, DoctorReport, HaStatus, IdCertInfo,
+            IssuanceTimingOverrides, IssuanceTimingReport, Job, JobList, KeyGcReport, KeyInventory, ObjectsExpiry,
+            ParentCaContact, ParentExchangeLog, ParentStatuses, ParentsStats, PendingChildRequests, PublicationCheck,
+            PublisherDetails, PublisherList, PublisherQuotaInfo, PublisherStatsInfo, PublisherStatsList,
+            PublisherValidationInfo, Reconciliation, RepoMigrationReport, RepoMigrationStatus, RepoStatus,
+            RepoStatuses, RepositoryContact, ResourceTransfer, ResourceTransferList, RetryPolicies, RoaAutoPilotInfo,
+            RoaImportReport, RoaIssuanceStrategyInfo, RoaProposalList, RtaList, RtaPrepResponse, ScheduledChangeList,
+            SchemaVersions, SearchResults, ServerInfo, SignerMigrationStatus, TaskList, TimeCheck,
         },
         bgp::{BgpAnalysisAdvice, BgpAnalysisReport, BgpAnalysisSuggestion},
     },
     daemon::{
-        ca::ResourceTaggedAttestation,
+        ca::{AspaUpdateDryRun, ResourceTaggedAttestation, RpkiSignedChecklist},
         ta::{
-            TrustAnchorProxySignerExchanges, TrustAnchorSignedRequest, TrustAnchorSignedResponse, TrustAnchorSignerInfo,
+            TrustAnchorLocator, TrustAnchorProxySignerExchanges, TrustAnchorSignedRequest, TrustAnchorSignedResponse,
+            TrustAnchorSignerInfo, TrustAnchorTalCheck,
         },
     },
-    pubd::RepoStats,
+    pubd::{RepoStats, RepositoryArchiveEntry, RepositoryArchiveList, RepositoryReplicaStatus, RrdpStats},
 };
 
 //------------ ApiResponse ---------------------------------------------------
@@ -36,6 +49,7 @@ pub enum ApiResponse {
     CertAuthInfo(CertAuthInfo),
     CertAuthHistory(CommandHistory),
     CertAuthAction(CaCommandDetails),
+    CommandRevert(CommandRevert),
     CertAuths(CertAuthList),
 
     // ROA related
@@ -43,22 +57,44 @@ pub enum ApiResponse {
     BgpAnalysisAdvice(BgpAnalysisAdvice),
     BgpAnalysisFull(BgpAnalysisReport),
     BgpAnalysisSuggestions(BgpAnalysisSuggestion),
+    RoaImportReport(RoaImportReport),
+    RoaProposals(RoaProposalList),
+    RoaIssuanceStrategy(RoaIssuanceStrategyInfo),
+    RoaAutoPilot(RoaAutoPilotInfo),
 
     // ASPA related
     AspaDefinitions(AspaDefinitionList),
+    AspaDefinitionUpdates(AspaDefinitionUpdates),
+    AspaUpdateDryRun(AspaUpdateDryRun),
 
     // BGPSec related
     BgpSecDefinitions(BgpSecCsrInfoList),
 
     ParentCaContact(ParentCaContact),
     ParentStatuses(ParentStatuses),
+    ParentsReport(ParentsStats),
+    ParentExchanges(ParentExchangeLog),
 
     ChildInfo(ChildCaInfo),
     ChildrenStats(ChildrenConnectionStats),
+    ChildrenReport(ChildrenStats),
+    ChildIssuancePolicy(ChildIssuancePolicy),
+    ChildRequests(PendingChildRequests),
+    ResourceTransfer(ResourceTransfer),
+    ResourceTransfers(ResourceTransferList),
+    ScheduledChanges(ScheduledChangeList),
 
     PublisherDetails(PublisherDetails),
+    PublisherQuota(PublisherQuotaInfo),
+    PublisherStats(PublisherStatsInfo),
+    PublisherStatsList(PublisherStatsList),
+    PublisherValidation(PublisherValidationInfo),
     PublisherList(PublisherList),
     RepoStats(RepoStats),
+    RrdpStats(RrdpStats),
+    RepositoryArchiveList(RepositoryArchiveList),
+    RepositoryArchiveEntry(RepositoryArchiveEntry),
+    RepositoryReplicaStatus(RepositoryReplicaStatus),
 
     Rfc8183ParentResponse(idexchange::ParentResponse),
     Rfc8183RepositoryResponse(idexchange::RepositoryResponse),
@@ -67,9 +103,40 @@ pub enum ApiResponse {
 
     RepoDetails(CaRepoDetails),
     RepoStatus(RepoStatus),
+    RepoStatuses(RepoStatuses),
+    ClassRepoDetails(ClassRepoDetails),
+    RepoMigrationStatus(RepoMigrationStatus),
+    RepoMigrationReport(RepoMigrationReport),
+    PublicationCheck(PublicationCheck),
+    Reconciliation(Reconciliation),
+
+    IssuanceTiming(IssuanceTimingOverrides),
+    IssuanceTimingReport(IssuanceTimingReport),
+    RetryPolicies(RetryPolicies),
+    CaLabels(CaLabels),
 
     CertAuthIssues(CertAuthIssues),
     AllCertAuthIssues(AllCertAuthIssues),
+    ObjectsExpiry(ObjectsExpiry),
+    SignerMigrationStatus(SignerMigrationStatus),
+    AggregateSnapshots(AggregateSnapshots),
+    BackupInfo(BackupInfo),
+    BackupList(BackupList),
+    HaStatus(HaStatus),
+    TimeCheck(TimeCheck),
+    Job(Job),
+    JobList(JobList),
+    KeyInventory(KeyInventory),
+    KeyGc(KeyGcReport),
+    ConfigReload(ConfigReloadReport),
+    TaskList(TaskList),
+    Doctor(DoctorReport),
+    CaMigration(CaMigrationReport),
+    CaExport(CaMigration),
+    SchemaVersions(SchemaVersions),
+    SearchResults(SearchResults),
+
+    Rsc(RpkiSignedChecklist),
 
     RtaList(RtaList),
     RtaMultiPrep(RtaPrepResponse),
@@ -77,6 +144,12 @@ pub enum ApiResponse {
 
     Empty,               // Typically a successful post just gets an empty 200 response
     GenericBody(String), // For when the server echos Json to a successful post
+
+    ApplyReport(ApplyReport),
+
+    XmlSummary(XmlSummary),
+    XmlIdCert(XmlIdCert),
+    XmlDiff(XmlDiff),
 }
 
 impl ApiResponse {
@@ -91,31 +164,87 @@ impl ApiResponse {
                 ApiResponse::CertAuthInfo(info) => Ok(Some(info.report(fmt)?)),
                 ApiResponse::CertAuthHistory(history) => Ok(Some(history.report(fmt)?)),
                 ApiResponse::CertAuthAction(details) => Ok(Some(details.report(fmt)?)),
+                ApiResponse::CommandRevert(revert) => Ok(Some(revert.report(fmt)?)),
                 ApiResponse::CertAuthIssues(issues) => Ok(Some(issues.report(fmt)?)),
                 ApiResponse::AllCertAuthIssues(issues) => Ok(Some(issues.report(fmt)?)),
+                ApiResponse::ObjectsExpiry(expiry) => Ok(Some(expiry.report(fmt)?)),
+                ApiResponse::SignerMigrationStatus(status) => Ok(Some(status.report(fmt)?)),
+                ApiResponse::AggregateSnapshots(snapshots) => Ok(Some(snapshots.report(fmt)?)),
+                ApiResponse::BackupInfo(info) => Ok(Some(info.report(fmt)?)),
+                ApiResponse::BackupList(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::HaStatus(status) => Ok(Some(status.report(fmt)?)),
+                ApiResponse::TimeCheck(check) => Ok(Some(check.report(fmt)?)),
+                ApiResponse::Job(job) => Ok(Some(job.report(fmt)?)),
+                ApiResponse::JobList(jobs) => Ok(Some(jobs.report(fmt)?)),
+                ApiResponse::KeyInventory(inventory) => Ok(Some(inventory.report(fmt)?)),
+                ApiResponse::KeyGc(report) => Ok(Some(report.report(fmt)?)),
+                ApiResponse::ConfigReload(report) => Ok(Some(report.report(fmt)?)),
+                ApiResponse::TaskList(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::Doctor(report) => Ok(Some(report.report(fmt)?)),
+                ApiResponse::CaMigration(report) => Ok(Some(report.report(fmt)?)),
+                ApiResponse::CaExport(export) => Ok(Some(export.report(fmt)?)),
+                ApiResponse::SchemaVersions(versions) => Ok(Some(versions.report(fmt)?)),
+                ApiResponse::SearchResults(results) => Ok(Some(results.report(fmt)?)),
+                ApiResponse::XmlSummary(summary) => Ok(Some(summary.report(fmt)?)),
+                ApiResponse::XmlIdCert(cert) => Ok(Some(cert.report(fmt)?)),
+                ApiResponse::XmlDiff(diff) => Ok(Some(diff.report(fmt)?)),
                 ApiResponse::RouteAuthorizations(definitions) => Ok(Some(definitions.report(fmt)?)),
                 ApiResponse::BgpAnalysisAdvice(analysis) => Ok(Some(analysis.report(fmt)?)),
                 ApiResponse::BgpAnalysisFull(table) => Ok(Some(table.report(fmt)?)),
                 ApiResponse::BgpAnalysisSuggestions(suggestions) => Ok(Some(suggestions.report(fmt)?)),
+                ApiResponse::RoaImportReport(report) => Ok(Some(report.report(fmt)?)),
+                ApiResponse::RoaProposals(proposals) => Ok(Some(proposals.report(fmt)?)),
+                ApiResponse::RoaIssuanceStrategy(info) => Ok(Some(info.report(fmt)?)),
+                ApiResponse::RoaAutoPilot(info) => Ok(Some(info.report(fmt)?)),
                 ApiResponse::AspaDefinitions(definitions) => Ok(Some(definitions.report(fmt)?)),
+                ApiResponse::AspaDefinitionUpdates(updates) => Ok(Some(updates.report(fmt)?)),
+                ApiResponse::AspaUpdateDryRun(dry_run) => Ok(Some(dry_run.report(fmt)?)),
                 ApiResponse::BgpSecDefinitions(definitions) => Ok(Some(definitions.report(fmt)?)),
                 ApiResponse::ParentCaContact(contact) => Ok(Some(contact.report(fmt)?)),
                 ApiResponse::ParentStatuses(statuses) => Ok(Some(statuses.report(fmt)?)),
+                ApiResponse::ParentsReport(stats) => Ok(Some(stats.report(fmt)?)),
+                ApiResponse::ParentExchanges(exchanges) => Ok(Some(exchanges.report(fmt)?)),
                 ApiResponse::ChildInfo(info) => Ok(Some(info.report(fmt)?)),
                 ApiResponse::ChildrenStats(stats) => Ok(Some(stats.report(fmt)?)),
+                ApiResponse::ChildrenReport(stats) => Ok(Some(stats.report(fmt)?)),
+                ApiResponse::ChildIssuancePolicy(policy) => Ok(Some(policy.report(fmt)?)),
+                ApiResponse::ChildRequests(requests) => Ok(Some(requests.report(fmt)?)),
+                ApiResponse::ResourceTransfer(transfer) => Ok(Some(transfer.report(fmt)?)),
+                ApiResponse::ResourceTransfers(transfers) => Ok(Some(transfers.report(fmt)?)),
+                ApiResponse::ScheduledChanges(changes) => Ok(Some(changes.report(fmt)?)),
                 ApiResponse::PublisherList(list) => Ok(Some(list.report(fmt)?)),
                 ApiResponse::PublisherDetails(details) => Ok(Some(details.report(fmt)?)),
+                ApiResponse::PublisherQuota(quota) => Ok(Some(quota.report(fmt)?)),
+                ApiResponse::PublisherStats(stats) => Ok(Some(stats.report(fmt)?)),
+                ApiResponse::PublisherStatsList(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::PublisherValidation(validation) => Ok(Some(validation.report(fmt)?)),
                 ApiResponse::RepoStats(stats) => Ok(Some(stats.report(fmt)?)),
+                ApiResponse::RrdpStats(stats) => Ok(Some(stats.report(fmt)?)),
+                ApiResponse::RepositoryArchiveList(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::RepositoryArchiveEntry(entry) => Ok(Some(entry.report(fmt)?)),
+                ApiResponse::RepositoryReplicaStatus(status) => Ok(Some(status.report(fmt)?)),
                 ApiResponse::Rfc8183ParentResponse(res) => Ok(Some(res.report(fmt)?)),
                 ApiResponse::Rfc8183ChildRequest(req) => Ok(Some(req.report(fmt)?)),
                 ApiResponse::Rfc8183PublisherRequest(req) => Ok(Some(req.report(fmt)?)),
                 ApiResponse::Rfc8183RepositoryResponse(res) => Ok(Some(res.report(fmt)?)),
                 ApiResponse::RepoDetails(details) => Ok(Some(details.report(fmt)?)),
                 ApiResponse::RepoStatus(status) => Ok(Some(status.report(fmt)?)),
+                ApiResponse::RepoStatuses(statuses) => Ok(Some(statuses.report(fmt)?)),
+                ApiResponse::ClassRepoDetails(details) => Ok(Some(details.report(fmt)?)),
+                ApiResponse::RepoMigrationStatus(status) => Ok(Some(status.report(fmt)?)),
+                ApiResponse::RepoMigrationReport(report) => Ok(Some(report.report(fmt)?)),
+                ApiResponse::PublicationCheck(check) => Ok(Some(check.report(fmt)?)),
+                ApiResponse::Reconciliation(reconciliation) => Ok(Some(reconciliation.report(fmt)?)),
+                ApiResponse::IssuanceTiming(overrides) => Ok(Some(overrides.report(fmt)?)),
+                ApiResponse::IssuanceTimingReport(report) => Ok(Some(report.report(fmt)?)),
+                ApiResponse::RetryPolicies(policies) => Ok(Some(policies.report(fmt)?)),
+                ApiResponse::CaLabels(labels) => Ok(Some(labels.report(fmt)?)),
+                ApiResponse::Rsc(rsc) => Ok(Some(rsc.report(fmt)?)),
                 ApiResponse::Rta(rta) => Ok(Some(rta.report(fmt)?)),
                 ApiResponse::RtaList(list) => Ok(Some(list.report(fmt)?)),
                 ApiResponse::RtaMultiPrep(res) => Ok(Some(res.report(fmt)?)),
                 ApiResponse::GenericBody(body) => Ok(Some(body.clone())),
+                ApiResponse::ApplyReport(report) => Ok(Some(report.report(fmt)?)),
                 ApiResponse::Empty => Ok(None),
             }
         }
@@ -129,6 +258,8 @@ impl ApiResponse {
 pub enum ReportFormat {
     None,
     Json,
+    Yaml,
+    Table,
     Text,
 }
 
@@ -139,12 +270,26 @@ impl FromStr for ReportFormat {
         match s {
             "none" => Ok(ReportFormat::None),
             "json" => Ok(ReportFormat::Json),
+            "yaml" => Ok(ReportFormat::Yaml),
+            "table" => Ok(ReportFormat::Table),
             "text" => Ok(ReportFormat::Text),
             _ => Err(ReportError::UnrecognizedFormat(s.to_string())),
         }
     }
 }
 
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportFormat::None => write!(f, "none"),
+            ReportFormat::Json => write!(f, "json"),
+            ReportFormat::Yaml => write!(f, "yaml"),
+            ReportFormat::Table => write!(f, "table"),
+            ReportFormat::Text => write!(f, "text"),
+        }
+    }
+}
+
 //------------ ReportError ---------------------------------------------------
 
 /// This type defines possible Errors for KeyStore
@@ -152,6 +297,7 @@ impl FromStr for ReportFormat {
 pub enum ReportError {
     UnsupportedFormat,
     UnrecognizedFormat(String),
+    SerializationError(String),
 }
 
 impl fmt::Display for ReportError {
@@ -159,6 +305,7 @@ impl fmt::Display for ReportError {
         match self {
             ReportError::UnsupportedFormat => write!(f, "This report format is not supported for this data"),
             ReportError::UnrecognizedFormat(s) => write!(f, "This report format is not recognized: {}", s),
+            ReportError::SerializationError(s) => write!(f, "Cannot format report: {}", s),
         }
     }
 }
@@ -176,10 +323,21 @@ pub trait Report: Serialize + ToString {
         serde_json::to_string_pretty(self).unwrap()
     }
 
+    fn yaml(&self) -> Result<String, ReportError> {
+        serde_yaml::to_string(self).map_err(|e| ReportError::SerializationError(e.to_string()))
+    }
+
+    fn table(&self) -> Result<String, ReportError> {
+        let value = serde_json::to_value(self).map_err(|e| ReportError::SerializationError(e.to_string()))?;
+        Ok(Table::for_value(&value).render(use_color()))
+    }
+
     fn report(&self, format: ReportFormat) -> Result<String, ReportError> {
         match format {
             ReportFormat::None => Ok("".to_string()),
             ReportFormat::Json => Ok(self.json()),
+            ReportFormat::Yaml => self.yaml(),
+            ReportFormat::Table => self.table(),
             ReportFormat::Text => self.text(),
         }
     }
@@ -194,16 +352,63 @@ impl Report for ChildCaInfo {}
 
 impl Report for ParentCaContact {}
 impl Report for ParentStatuses {}
+impl Report for ParentsStats {}
+impl Report for ParentExchangeLog {}
 
 impl Report for CommandHistory {}
+impl Report for AggregateSnapshots {}
+impl Report for BackupInfo {}
+impl Report for BackupList {}
+impl Report for HaStatus {}
+impl Report for TimeCheck {}
+impl Report for Job {}
+impl Report for JobList {}
+impl Report for KeyInventory {}
+impl Report for KeyGcReport {}
+impl Report for ConfigReloadReport {}
+impl Report for TaskList {}
+impl Report for SchemaVersions {}
+impl Report for CaMigrationReport {}
+
+impl Report for CaMigration {
+    fn text(&self) -> Result<String, ReportError> {
+        Ok(self.json())
+    }
+}
+
+impl Report for DoctorReport {
+    /// Shows the findings only, as a table of all fields of the report would
+    /// not be readable.
+    fn table(&self) -> Result<String, ReportError> {
+        let value =
+            serde_json::to_value(self.findings()).map_err(|e| ReportError::SerializationError(e.to_string()))?;
+        Ok(Table::for_value(&value).render(use_color()))
+    }
+}
 impl Report for CaCommandDetails {}
+impl Report for CommandRevert {}
+
+impl Report for ApplyReport {}
+
+impl Report for XmlSummary {}
+impl Report for XmlIdCert {}
+impl Report for XmlDiff {}
 
 impl Report for PublisherList {}
 
 impl Report for RepoStats {}
+impl Report for RrdpStats {}
+impl Report for RepositoryArchiveList {}
+impl Report for RepositoryArchiveEntry {}
+impl Report for RepositoryReplicaStatus {}
 impl Report for ChildrenConnectionStats {}
+impl Report for ChildrenStats {}
 
 impl Report for PublisherDetails {}
+impl Report for PublisherQuotaInfo {}
+impl Report for PublisherStatsInfo {}
+impl Report for PublisherStatsList {}
+impl Report for PublisherValidationInfo {}
 
 impl Report for idexchange::RepositoryResponse {
     fn text(&self) -> Result<String, ReportError> {
@@ -234,13 +439,38 @@ impl Report for ConfiguredRoas {}
 impl Report for BgpAnalysisAdvice {}
 impl Report for BgpAnalysisReport {}
 impl Report for BgpAnalysisSuggestion {}
+impl Report for RoaImportReport {}
+impl Report for RoaProposalList {}
+impl Report for RoaIssuanceStrategyInfo {}
+impl Report for RoaAutoPilotInfo {}
 
 impl Report for AspaDefinitionList {}
+impl Report for AspaDefinitionUpdates {}
+impl Report for AspaUpdateDryRun {}
 
 impl Report for BgpSecCsrInfoList {}
 
 impl Report for CaRepoDetails {}
+impl Report for ClassRepoDetails {}
+
+impl Report for IssuanceTimingOverrides {}
+impl Report for IssuanceTimingReport {}
+impl Report for ChildIssuancePolicy {}
+impl Report for PendingChildRequests {}
+impl Report for ResourceTransfer {}
+impl Report for ResourceTransferList {}
+impl Report for ScheduledChangeList {}
+impl Report for SearchResults {}
+impl Report for RetryPolicies {}
+impl Report for CaLabels {}
 impl Report for RepoStatus {}
+impl Report for RepoStatuses {}
+impl Report for RepoMigrationStatus {}
+impl Report for RepoMigrationReport {}
+impl Report for PublicationCheck {}
+impl Report for Reconciliation {}
+impl Report for ObjectsExpiry {}
+impl Report for SignerMigrationStatus {}
 
 impl Report for CertAuthIssues {}
 
@@ -248,6 +478,8 @@ impl Report for AllCertAuthIssues {}
 
 impl Report for ServerInfo {}
 
+impl Report for RpkiSignedChecklist {}
+
 impl Report for ResourceTaggedAttestation {}
 impl Report for RtaList {}
 impl Report for RtaPrepResponse {}
@@ -256,3 +488,186 @@ impl Report for TrustAnchorSignerInfo {}
 impl Report for TrustAnchorSignedRequest {}
 impl Report for TrustAnchorSignedResponse {}
 impl Report for TrustAnchorProxySignerExchanges {}
+impl Report for TrustAnchorLocator {}
+impl Report for TrustAnchorTalCheck {}
+
+//------------ Table ---------------------------------------------------------
+
+/// Renders any report as a table with aligned columns, based on its JSON
+/// representation. A list of objects gets a column per field, any other
+/// object gets a row per field. An object with a single list, like the list
+/// of CAs, is shown as that list.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn for_value(value: &serde_json::Value) -> Self {
+        use serde_json::Value;
+
+        match value {
+            Value::Array(items) => Self::for_items(items),
+            Value::Object(fields) => match fields.values().next() {
+                Some(Value::Array(items)) if fields.len() == 1 => Self::for_items(items),
+                _ => Table {
+                    header: vec!["field".to_string(), "value".to_string()],
+                    rows: fields
+                        .iter()
+                        .map(|(name, value)| vec![name.clone(), Self::cell(value)])
+                        .collect(),
+                },
+            },
+            _ => Table {
+                header: vec!["value".to_string()],
+                rows: vec![vec![Self::cell(value)]],
+            },
+        }
+    }
+
+    fn for_items(items: &[serde_json::Value]) -> Self {
+        let mut header: Vec<String> = vec![];
+        for item in items {
+            match item {
+                serde_json::Value::Object(fields) => {
+                    for name in fields.keys() {
+                        if !header.contains(name) {
+                            header.push(name.clone());
+                        }
+                    }
+                }
+                _ => {
+                    if !header.iter().any(|name| name == "value") {
+                        header.push("value".to_string());
+                    }
+                }
+            }
+        }
+
+        let rows = items
+            .iter()
+            .map(|item| {
+                header
+                    .iter()
+                    .map(|name| match item {
+                        serde_json::Value::Object(fields) => fields.get(name).map(Self::cell).unwrap_or_default(),
+                        _ if name == "value" => Self::cell(item),
+                        _ => String::new(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Table { header, rows }
+    }
+
+    /// Shows strings without quotes, lists of plain values separated by
+    /// commas, and anything else as compact JSON.
+    fn cell(value: &serde_json::Value) -> String {
+        use serde_json::Value;
+
+        match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            Value::Array(items) if items.iter().all(|item| !item.is_array() && !item.is_object()) => {
+                items.iter().map(Self::cell).collect::<Vec<_>>().join(", ")
+            }
+            _ => value.to_string(),
+        }
+    }
+
+    fn render(&self, color: bool) -> String {
+        if self.header.is_empty() {
+            return String::new();
+        }
+
+        let mut widths: Vec<usize> = self.header.iter().map(|name| name.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let line = |cells: &[String]| -> String {
+            let mut line = String::new();
+            for (i, (cell, width)) in cells.iter().zip(&widths).enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                line.push_str(cell);
+                if i < cells.len() - 1 {
+                    line.push_str(&" ".repeat(width - cell.chars().count()));
+                }
+            }
+            // Trailing columns can be empty, which would leave the padding
+            // of the columns before them.
+            line.trim_end().to_string()
+        };
+
+        let header: Vec<String> = self.header.iter().map(|name| name.to_uppercase()).collect();
+        let mut table = if color {
+            format!("\x1b[1m{}\x1b[0m\n", line(&header))
+        } else {
+            format!("{}\n", line(&header))
+        };
+        for row in &self.rows {
+            table.push_str(&line(row));
+            table.push('\n');
+        }
+        table
+    }
+}
+
+/// Returns whether to use color in tables, i.e. if the output is a terminal
+/// and the NO_COLOR environment variable is not set.
+fn use_color() -> bool {
+    if std::env::var_os("NO_COLOR").is_some() {
+        return false;
+    }
+
+    #[cfg(unix)]
+    {
+        unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+    }
+
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_for_list_of_objects() {
+        let value = serde_json::json!({
+            "cas": [
+                { "handle": "ca1" },
+                { "handle": "long-ca-name", "roas": ["192.0.2.0/24 => 64496", "2001:db8::/32 => 64496"] },
+            ]
+        });
+
+        let expected = "HANDLE        ROAS\n\
+                        ca1\n\
+                        long-ca-name  192.0.2.0/24 => 64496, 2001:db8::/32 => 64496\n";
+
+        assert_eq!(Table::for_value(&value).render(false), expected);
+    }
+
+    #[test]
+    fn table_for_object() {
+        let value = serde_json::json!({ "version": "0.13.0", "started": 1700000000, "nested": { "a": 1 } });
+
+        let expected = "FIELD    VALUE\n\
+                        nested   {\"a\":1}\n\
+                        started  1700000000\n\
+                        version  0.13.0\n";
+
+        assert_eq!(Table::for_value(&value).render(false), expected);
+    }
+}
@@ -36,8 +36,9 @@ use crate::{
     daemon::{
         config::{LogType, SignerConfig, SignerReference, SignerType},
         ta::{
-            TrustAnchorHandle, TrustAnchorProxySignerExchanges, TrustAnchorSignedRequest, TrustAnchorSignedResponse,
-            TrustAnchorSigner, TrustAnchorSignerCommand, TrustAnchorSignerInfo, TrustAnchorSignerInitCommand,
+            TrustAnchorHandle, TrustAnchorLocator, TrustAnchorProxySignerExchanges, TrustAnchorSignedRequest,
+            TrustAnchorSignedResponse, TrustAnchorSigner, TrustAnchorSignerCommand, TrustAnchorSignerInfo,
+            TrustAnchorSignerInitCommand, TrustAnchorTalCheck,
         },
     },
 };
@@ -138,6 +139,8 @@ pub enum ProxyCommandDetails {
     SignerProcessResponse(TrustAnchorSignedResponse),
     ChildAdd(AddChildRequest),
     ChildResponse(ChildHandle),
+    TalShow,
+    TalCheck,
 }
 
 #[derive(Debug)]
@@ -195,6 +198,7 @@ impl TrustAnchorClientCommand {
         sub = Self::make_proxy_repo_sc(sub);
         sub = Self::make_proxy_signer_sc(sub);
         sub = Self::make_proxy_children_sc(sub);
+        sub = Self::make_proxy_tal_sc(sub);
 
         app.subcommand(sub)
     }
@@ -211,6 +215,26 @@ impl TrustAnchorClientCommand {
         app.subcommand(sub)
     }
 
+    fn make_proxy_tal_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("tal").about("Show and verify the TAL");
+        sub = Self::make_proxy_tal_show_sc(sub);
+        sub = Self::make_proxy_tal_check_sc(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_proxy_tal_show_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("show").about("Show the current TAL");
+        sub = GeneralArgs::add_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_proxy_tal_check_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("check")
+            .about("Verify that the TAL matches the TA certificate and where it is published");
+        sub = GeneralArgs::add_args(sub);
+        app.subcommand(sub)
+    }
+
     fn make_proxy_repo_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("repo").about("Manage the repository for proxy");
         sub = Self::make_proxy_repo_request_sc(sub);
@@ -472,7 +496,7 @@ impl TrustAnchorClientCommand {
                 .long("format")
                 .value_name("type")
                 .short("f")
-                .help("Report format: none|json (default)|text. Or set env: KRILL_CLI_FORMAT")
+                .help("Report format: none|json (default)|yaml|table|text. Or set env: KRILL_CLI_FORMAT")
                 .required(false),
         )
     }
@@ -513,6 +537,8 @@ impl TrustAnchorClientCommand {
             Self::parse_matches_proxy_signer(m)
         } else if let Some(m) = matches.subcommand_matches("children") {
             Self::parse_matches_proxy_children(m)
+        } else if let Some(m) = matches.subcommand_matches("tal") {
+            Self::parse_matches_proxy_tal(m)
         } else {
             Err(Error::UnrecognizedMatch)
         }
@@ -532,6 +558,22 @@ impl TrustAnchorClientCommand {
         Ok(TrustAnchorClientCommand::Proxy(ProxyCommand { general, details }))
     }
 
+    fn parse_matches_proxy_tal(matches: &ArgMatches) -> Result<Self, Error> {
+        if let Some(m) = matches.subcommand_matches("show") {
+            Self::parse_matches_proxy_tal_details(m, ProxyCommandDetails::TalShow)
+        } else if let Some(m) = matches.subcommand_matches("check") {
+            Self::parse_matches_proxy_tal_details(m, ProxyCommandDetails::TalCheck)
+        } else {
+            Err(Error::UnrecognizedMatch)
+        }
+    }
+
+    fn parse_matches_proxy_tal_details(matches: &ArgMatches, details: ProxyCommandDetails) -> Result<Self, Error> {
+        let general = GeneralArgs::from_matches(matches).map_err(|e| Error::Other(e.to_string()))?;
+
+        Ok(TrustAnchorClientCommand::Proxy(ProxyCommand { general, details }))
+    }
+
     fn parse_matches_proxy_repo(matches: &ArgMatches) -> Result<Self, Error> {
         if let Some(m) = matches.subcommand_matches("request") {
             Self::parse_matches_proxy_repo_request(m)
@@ -870,6 +912,14 @@ impl TrustAnchorClient {
                         let response = client.get_json(&uri_path).await?;
                         Ok(TrustAnchorClientApiResponse::ParentResponse(response))
                     }
+                    ProxyCommandDetails::TalShow => {
+                        let tal = client.get_json("api/v1/ta/tal").await?;
+                        Ok(TrustAnchorClientApiResponse::Tal(tal))
+                    }
+                    ProxyCommandDetails::TalCheck => {
+                        let check = client.get_json("api/v1/ta/tal/check").await?;
+                        Ok(TrustAnchorClientApiResponse::TalCheck(check))
+                    }
                 }
             }
             TrustAnchorClientCommand::Signer(signer_command) => {
@@ -897,6 +947,8 @@ pub enum TrustAnchorClientApiResponse {
     SignerRequest(TrustAnchorSignedRequest),
     SignerResponse(TrustAnchorSignedResponse),
     ProxySignerExchanges(TrustAnchorProxySignerExchanges),
+    Tal(TrustAnchorLocator),
+    TalCheck(TrustAnchorTalCheck),
     Empty,
 }
 
@@ -914,6 +966,8 @@ impl TrustAnchorClientApiResponse {
                 TrustAnchorClientApiResponse::SignerRequest(request) => request.report(fmt).map(Some),
                 TrustAnchorClientApiResponse::SignerResponse(response) => response.report(fmt).map(Some),
                 TrustAnchorClientApiResponse::ProxySignerExchanges(exchanges) => exchanges.report(fmt).map(Some),
+                TrustAnchorClientApiResponse::Tal(tal) => tal.report(fmt).map(Some),
+                TrustAnchorClientApiResponse::TalCheck(check) => check.report(fmt).map(Some),
                 TrustAnchorClientApiResponse::Empty => Ok(None),
             }
         }
@@ -0,0 +1,386 @@
+//! Offline inspection of RFC 8183 XML files, to debug the exchange of child
+//! requests, parent responses, publisher requests and repository responses
+//! without contacting a server.
+use std::{fmt, path::Path};
+
+use rpki::ca::{idcert::IdCert, idexchange};
+
+use crate::{
+    cli::options::Error,
+    commons::{api::IdCertInfo, util::file},
+};
+
+//------------ Rfc8183Xml ----------------------------------------------------
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Rfc8183Xml {
+    ChildRequest(idexchange::ChildRequest),
+    ParentResponse(idexchange::ParentResponse),
+    PublisherRequest(idexchange::PublisherRequest),
+    RepositoryResponse(idexchange::RepositoryResponse),
+}
+
+impl Rfc8183Xml {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let bytes = file::read(path).map_err(Error::IoError)?;
+        Self::parse(bytes.as_ref())
+            .map_err(|e| Error::GeneralArgumentError(format!("Cannot parse '{}': {}", path.display(), e)))
+    }
+
+    /// Parses the XML as the type given by its root element. Does not check
+    /// the embedded ID certificate, see [`Self::validate`].
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let xml = std::str::from_utf8(bytes).map_err(|_| "not valid UTF-8".to_string())?;
+        match root_element(xml) {
+            Some("child_request") => idexchange::ChildRequest::parse(bytes).map(Rfc8183Xml::ChildRequest),
+            Some("parent_response") => idexchange::ParentResponse::parse(bytes).map(Rfc8183Xml::ParentResponse),
+            Some("publisher_request") => idexchange::PublisherRequest::parse(bytes).map(Rfc8183Xml::PublisherRequest),
+            Some("repository_response") => {
+                idexchange::RepositoryResponse::parse(bytes).map(Rfc8183Xml::RepositoryResponse)
+            }
+            Some(other) => return Err(format!("unsupported root element '{}'", other)),
+            None => return Err("no root element found".to_string()),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Rfc8183Xml::ChildRequest(_) => "child_request",
+            Rfc8183Xml::ParentResponse(_) => "parent_response",
+            Rfc8183Xml::PublisherRequest(_) => "publisher_request",
+            Rfc8183Xml::RepositoryResponse(_) => "repository_response",
+        }
+    }
+
+    /// The element holding the base64 encoded ID certificate.
+    fn bpki_ta_element(&self) -> &'static str {
+        match self {
+            Rfc8183Xml::ChildRequest(_) => "child_bpki_ta",
+            Rfc8183Xml::ParentResponse(_) => "parent_bpki_ta",
+            Rfc8183Xml::PublisherRequest(_) => "publisher_bpki_ta",
+            Rfc8183Xml::RepositoryResponse(_) => "repository_bpki_ta",
+        }
+    }
+
+    pub fn to_xml_string(&self) -> String {
+        match self {
+            Rfc8183Xml::ChildRequest(req) => req.to_xml_string(),
+            Rfc8183Xml::ParentResponse(res) => res.to_xml_string(),
+            Rfc8183Xml::PublisherRequest(req) => req.to_xml_string(),
+            Rfc8183Xml::RepositoryResponse(res) => res.to_xml_string(),
+        }
+    }
+
+    /// Returns the handles and URIs in the XML.
+    pub fn fields(&self) -> Vec<XmlField> {
+        match self {
+            Rfc8183Xml::ChildRequest(req) => vec![XmlField::new("child_handle", req.child_handle())],
+            Rfc8183Xml::ParentResponse(res) => vec![
+                XmlField::new("parent_handle", res.parent_handle()),
+                XmlField::new("child_handle", res.child_handle()),
+                XmlField::new("service_uri", res.service_uri()),
+            ],
+            Rfc8183Xml::PublisherRequest(req) => vec![XmlField::new("publisher_handle", req.publisher_handle())],
+            Rfc8183Xml::RepositoryResponse(res) => {
+                let repo_info = res.repo_info();
+                let mut fields = vec![
+                    XmlField::new("publisher_handle", res.publisher_handle()),
+                    XmlField::new("service_uri", res.service_uri()),
+                    XmlField::new("sia_base", repo_info.base_uri()),
+                ];
+                if let Some(notify) = repo_info.rpki_notify() {
+                    fields.push(XmlField::new("rrdp_notification_uri", notify));
+                }
+                fields
+            }
+        }
+    }
+
+    /// Checks that the embedded ID certificate is a valid self-signed
+    /// certificate, and returns it.
+    pub fn validate(&self) -> Result<IdCert, idexchange::Error> {
+        match self {
+            Rfc8183Xml::ChildRequest(req) => req.validate(),
+            Rfc8183Xml::ParentResponse(res) => res.validate(),
+            Rfc8183Xml::PublisherRequest(req) => req.validate(),
+            Rfc8183Xml::RepositoryResponse(res) => res.validate(),
+        }
+    }
+
+    /// Returns the embedded ID certificate, also if it is not valid, so that
+    /// it can be inspected.
+    pub fn id_cert(&self) -> Result<IdCert, String> {
+        let xml = self.to_xml_string();
+        let element = self.bpki_ta_element();
+        let start_tag = format!("<{}>", element);
+        let end_tag = format!("</{}>", element);
+
+        let start = xml
+            .find(&start_tag)
+            .map(|idx| idx + start_tag.len())
+            .ok_or_else(|| format!("no {} element found", element))?;
+        let end = xml[start..]
+            .find(&end_tag)
+            .map(|idx| start + idx)
+            .ok_or_else(|| format!("no end of {} element found", element))?;
+
+        let base64: String = xml[start..end].chars().filter(|c| !c.is_whitespace()).collect();
+        let der = base64::decode(&base64).map_err(|e| format!("invalid base64 in {}: {}", element, e))?;
+        IdCert::decode(der.as_slice()).map_err(|e| format!("invalid certificate in {}: {}", element, e))
+    }
+
+    pub fn summary(&self) -> XmlSummary {
+        XmlSummary {
+            kind: self.kind().to_string(),
+            fields: self.fields(),
+            id_cert: self.id_cert().ok().map(|cert| XmlIdCert::from(&cert)),
+            invalid: self.validate().err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Returns the name of the root element without namespace prefix, skipping
+/// the XML declaration and comments.
+fn root_element(xml: &str) -> Option<&str> {
+    let mut rest = xml.trim_start_matches('\u{feff}');
+    loop {
+        rest = rest.trim_start();
+        if let Some(remaining) = rest.strip_prefix("<?") {
+            rest = &remaining[remaining.find("?>")? + 2..];
+        } else if let Some(remaining) = rest.strip_prefix("<!--") {
+            rest = &remaining[remaining.find("-->")? + 3..];
+        } else if let Some(remaining) = rest.strip_prefix('<') {
+            let end = remaining.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+            let name = &remaining[..end];
+            return name.rsplit(':').next();
+        } else {
+            return None;
+        }
+    }
+}
+
+//------------ XmlField ------------------------------------------------------
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct XmlField {
+    name: String,
+    value: String,
+}
+
+impl XmlField {
+    fn new(name: &str, value: impl fmt::Display) -> Self {
+        XmlField {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+//------------ XmlIdCert -----------------------------------------------------
+
+/// An ID certificate with the fingerprints used to compare it with what the
+/// other party shows.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct XmlIdCert {
+    /// The SHA-256 hash of the DER encoded certificate.
+    sha256: String,
+    key_identifier: String,
+    pem: String,
+}
+
+impl From<&IdCert> for XmlIdCert {
+    fn from(cert: &IdCert) -> Self {
+        let info = IdCertInfo::from(cert);
+        XmlIdCert {
+            sha256: info.hash().to_string(),
+            key_identifier: cert.public_key().key_identifier().to_string(),
+            pem: info.pem(),
+        }
+    }
+}
+
+impl fmt::Display for XmlIdCert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "SHA-256:        {}", self.sha256)?;
+        writeln!(f, "Key identifier: {}", self.key_identifier)?;
+        write!(f, "{}", self.pem)
+    }
+}
+
+//------------ XmlSummary ----------------------------------------------------
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct XmlSummary {
+    #[serde(rename = "type")]
+    kind: String,
+    fields: Vec<XmlField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_cert: Option<XmlIdCert>,
+
+    /// Why the XML is not valid, if it is not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invalid: Option<String>,
+}
+
+impl XmlSummary {
+    pub fn invalid(&self) -> Option<&String> {
+        self.invalid.as_ref()
+    }
+
+    /// Returns the values to compare, by name.
+    fn values(&self) -> Vec<(String, String)> {
+        let mut values = vec![("type".to_string(), self.kind.clone())];
+        for field in &self.fields {
+            values.push((field.name.clone(), field.value.clone()));
+        }
+        if let Some(id_cert) = &self.id_cert {
+            values.push(("id_cert_sha256".to_string(), id_cert.sha256.clone()));
+        }
+        values.push((
+            "valid".to_string(),
+            self.invalid
+                .as_ref()
+                .map(|e| format!("no: {}", e))
+                .unwrap_or_else(|| "yes".to_string()),
+        ));
+        values
+    }
+}
+
+impl fmt::Display for XmlSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Type: {}", self.kind)?;
+        for field in &self.fields {
+            writeln!(f, "{}: {}", field.name, field.value)?;
+        }
+        if let Some(id_cert) = &self.id_cert {
+            writeln!(f, "ID certificate SHA-256: {}", id_cert.sha256)?;
+            writeln!(f, "ID certificate key identifier: {}", id_cert.key_identifier)?;
+        }
+        match &self.invalid {
+            None => write!(f, "Valid: yes"),
+            Some(e) => write!(f, "Valid: no, {}", e),
+        }
+    }
+}
+
+//------------ XmlDiff -------------------------------------------------------
+
+/// The differences between two RFC 8183 XML files, ignoring formatting.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct XmlDiff {
+    differences: Vec<XmlDifference>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct XmlDifference {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    left: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    right: Option<String>,
+}
+
+impl XmlDiff {
+    pub fn new(left: &XmlSummary, right: &XmlSummary) -> Self {
+        let left = left.values();
+        let right = right.values();
+
+        let mut names: Vec<&String> = left.iter().map(|(name, _)| name).collect();
+        for (name, _) in &right {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        let value = |values: &Vec<(String, String)>, name: &String| {
+            values.iter().find(|(n, _)| n == name).map(|(_, value)| value.clone())
+        };
+
+        let differences = names
+            .into_iter()
+            .filter_map(|name| {
+                let left = value(&left, name);
+                let right = value(&right, name);
+                if left == right {
+                    None
+                } else {
+                    Some(XmlDifference {
+                        name: name.clone(),
+                        left,
+                        right,
+                    })
+                }
+            })
+            .collect();
+
+        XmlDiff { differences }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for XmlDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.differences.is_empty() {
+            return write!(f, "No differences");
+        }
+        for difference in &self.differences {
+            writeln!(f, "{}:", difference.name)?;
+            writeln!(f, "  - {}", difference.left.as_deref().unwrap_or("<none>"))?;
+            writeln!(f, "  + {}", difference.right.as_deref().unwrap_or("<none>"))?;
+        }
+        Ok(())
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_root_element() {
+        assert_eq!(root_element("<child_request version=\"1\">"), Some("child_request"));
+        assert_eq!(
+            root_element("\u{feff}<?xml version=\"1.0\"?>\n<!-- from RIR -->\n<ns:parent_response/>"),
+            Some("parent_response")
+        );
+        assert_eq!(root_element("not xml"), None);
+    }
+
+    #[test]
+    fn summary_and_diff() {
+        let xml = include_str!("../../test-resources/remote/carol_child_id.xml");
+        let child_request = Rfc8183Xml::parse(xml.as_bytes()).unwrap();
+        assert_eq!(child_request.kind(), "child_request");
+
+        // the certificate is expired, but can still be shown
+        let summary = child_request.summary();
+        assert_eq!(summary.fields, vec![XmlField::new("child_handle", "Carol")]);
+        assert!(summary.id_cert.is_some());
+        assert!(summary.invalid().is_some());
+        assert!(XmlDiff::new(&summary, &summary).is_empty());
+
+        let reparsed = Rfc8183Xml::parse(child_request.to_xml_string().as_bytes()).unwrap();
+        assert_eq!(reparsed, child_request);
+
+        let xml = include_str!("../../test-resources/oob/repository_response.xml");
+        let repository_response = Rfc8183Xml::parse(xml.as_bytes()).unwrap();
+        let other = repository_response.summary();
+        assert!(other
+            .fields
+            .contains(&XmlField::new("sia_base", "rsync://a.example/rpki/Alice/Bob-42/")));
+
+        let diff = XmlDiff::new(&summary, &other);
+        assert_eq!(diff.differences[0].name, "type");
+        assert!(diff
+            .differences
+            .iter()
+            .any(|d| d.name == "child_handle" && d.right.is_none()));
+    }
+}
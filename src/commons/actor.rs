@@ -194,6 +194,10 @@ impl Actor {
         self.new_auth.clone()
     }
 
+    pub fn auth_error(&self) -> Option<&ApiAuthError> {
+        self.auth_error.as_ref()
+    }
+
     pub fn attributes(&self) -> HashMap<String, String> {
         self.attributes.as_map()
     }
@@ -1,6 +1,6 @@
 //! Support for admin tasks, such as managing publishers and RFC8181 clients
 
-use std::{convert::TryFrom, fmt};
+use std::{convert::TryFrom, fmt, net::IpAddr, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
@@ -8,17 +8,22 @@ use rpki::{
     ca::{
         idcert::IdCert,
         idexchange::{self, ServiceUri},
-        idexchange::{CaHandle, ChildHandle, ParentHandle, PublisherHandle, RepoInfo},
+        idexchange::{CaHandle, ChildHandle, MyHandle, ParentHandle, PublisherHandle, RepoInfo},
+        provisioning::ResourceClassName,
     },
-    crypto::PublicKey,
+    crypto::{KeyIdentifier, PublicKey},
     repository::resources::ResourceSet,
     uri,
 };
 
-use crate::commons::{
-    api::{rrdp::PublishElement, IdCertInfo, Timestamp},
-    error::Error,
-    KrillResult,
+use crate::{
+    commons::{
+        api::{rrdp::PublishElement, IdCertInfo, Timestamp},
+        error::Error,
+        util::KrillVersion,
+        KrillResult,
+    },
+    constants::KRILL_SCHEMA_VERSION,
 };
 
 //------------ Token ------------------------------------------------------
@@ -145,6 +150,8 @@ impl fmt::Display for PublisherList {
 pub struct PublisherDetails {
     handle: PublisherHandle,
     id_cert: IdCertInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    staged_id_cert: Option<IdCertInfo>,
     base_uri: uri::Rsync,
     current_files: Vec<PublishElement>,
 }
@@ -159,17 +166,26 @@ impl PublisherDetails {
         PublisherDetails {
             handle: handle.clone(),
             id_cert,
+            staged_id_cert: None,
             base_uri,
             current_files,
         }
     }
 
+    pub fn with_staged_id_cert(mut self, staged_id_cert: Option<IdCertInfo>) -> Self {
+        self.staged_id_cert = staged_id_cert;
+        self
+    }
+
     pub fn handle(&self) -> &PublisherHandle {
         &self.handle
     }
     pub fn id_cert(&self) -> &IdCertInfo {
         &self.id_cert
     }
+    pub fn staged_id_cert(&self) -> Option<&IdCertInfo> {
+        self.staged_id_cert.as_ref()
+    }
     pub fn base_uri(&self) -> &uri::Rsync {
         &self.base_uri
     }
@@ -182,6 +198,9 @@ impl fmt::Display for PublisherDetails {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "handle: {}", self.handle())?;
         writeln!(f, "id: {}", self.id_cert.public_key().key_identifier())?;
+        if let Some(staged) = &self.staged_id_cert {
+            writeln!(f, "staged id: {}", staged.public_key().key_identifier())?;
+        }
         writeln!(f, "base uri: {}", self.base_uri())?;
         writeln!(f, "objects:")?;
         for e in &self.current_files {
@@ -192,6 +211,367 @@ impl fmt::Display for PublisherDetails {
     }
 }
 
+//------------ PublisherQuota ------------------------------------------------
+
+/// Limits for the content and publication rate of a publisher. Limits
+/// which are not set do not apply.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherQuota {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_objects: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_deltas_per_hour: Option<usize>,
+}
+
+impl PublisherQuota {
+    pub fn new(max_objects: Option<usize>, max_bytes: Option<usize>, max_deltas_per_hour: Option<usize>) -> Self {
+        PublisherQuota {
+            max_objects,
+            max_bytes,
+            max_deltas_per_hour,
+        }
+    }
+}
+
+impl fmt::Display for PublisherQuota {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn limit(f: &mut fmt::Formatter, name: &str, limit: Option<usize>) -> fmt::Result {
+            match limit {
+                Some(limit) => writeln!(f, "{}: {}", name, limit),
+                None => writeln!(f, "{}: unlimited", name),
+            }
+        }
+
+        limit(f, "max objects", self.max_objects)?;
+        limit(f, "max bytes", self.max_bytes)?;
+        limit(f, "max deltas per hour", self.max_deltas_per_hour)
+    }
+}
+
+//------------ PublisherQuotaInfo --------------------------------------------
+
+/// This type defines the quota details for:
+/// /api/v1/pubd/publishers/{handle}/quota
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherQuotaInfo {
+    publisher: PublisherHandle,
+
+    /// The quota which applies to the publisher.
+    quota: PublisherQuota,
+
+    /// Whether the quota was set for this publisher, rather than
+    /// taken from the server configuration.
+    custom: bool,
+
+    objects: usize,
+    bytes: usize,
+    deltas_last_hour: usize,
+}
+
+impl PublisherQuotaInfo {
+    pub fn new(
+        publisher: PublisherHandle,
+        quota: PublisherQuota,
+        custom: bool,
+        objects: usize,
+        bytes: usize,
+        deltas_last_hour: usize,
+    ) -> Self {
+        PublisherQuotaInfo {
+            publisher,
+            quota,
+            custom,
+            objects,
+            bytes,
+            deltas_last_hour,
+        }
+    }
+
+    pub fn publisher(&self) -> &PublisherHandle {
+        &self.publisher
+    }
+
+    pub fn quota(&self) -> PublisherQuota {
+        self.quota
+    }
+
+    pub fn custom(&self) -> bool {
+        self.custom
+    }
+
+    pub fn objects(&self) -> usize {
+        self.objects
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn deltas_last_hour(&self) -> usize {
+        self.deltas_last_hour
+    }
+}
+
+impl fmt::Display for PublisherQuotaInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "publisher: {}", self.publisher)?;
+        if self.custom {
+            writeln!(f, "quota (custom):")?;
+        } else {
+            writeln!(f, "quota (server default):")?;
+        }
+        for line in self.quota.to_string().lines() {
+            writeln!(f, "  {}", line)?;
+        }
+        writeln!(f, "usage:")?;
+        writeln!(f, "  objects: {}", self.objects)?;
+        writeln!(f, "  bytes: {}", self.bytes)?;
+        writeln!(f, "  deltas last hour: {}", self.deltas_last_hour)
+    }
+}
+
+//------------ PublisherActivity ---------------------------------------------
+
+/// The publication activity of a publisher. This is saved by the server, so
+/// the totals are not reset when the server restarts.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherActivity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_publish: Option<Timestamp>,
+
+    pub deltas_last_hour: usize,
+    pub deltas_total: u64,
+    pub errors_total: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<Timestamp>,
+}
+
+//------------ PublisherStatsInfo --------------------------------------------
+
+/// This type defines the statistics for:
+/// /api/v1/pubd/publishers/{handle}/stats
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherStatsInfo {
+    publisher: PublisherHandle,
+    objects: usize,
+    bytes: usize,
+
+    /// The RRDP serial at which the content of the publisher last changed,
+    /// if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_changed_serial: Option<u64>,
+
+    activity: PublisherActivity,
+}
+
+impl PublisherStatsInfo {
+    pub fn new(
+        publisher: PublisherHandle,
+        objects: usize,
+        bytes: usize,
+        last_changed_serial: Option<u64>,
+        activity: PublisherActivity,
+    ) -> Self {
+        PublisherStatsInfo {
+            publisher,
+            objects,
+            bytes,
+            last_changed_serial,
+            activity,
+        }
+    }
+
+    pub fn publisher(&self) -> &PublisherHandle {
+        &self.publisher
+    }
+
+    pub fn objects(&self) -> usize {
+        self.objects
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn last_changed_serial(&self) -> Option<u64> {
+        self.last_changed_serial
+    }
+
+    pub fn activity(&self) -> &PublisherActivity {
+        &self.activity
+    }
+}
+
+impl fmt::Display for PublisherStatsInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn time(time: Option<Timestamp>) -> String {
+            time.map(|t| t.to_rfc3339()).unwrap_or_else(|| "never".to_string())
+        }
+
+        writeln!(f, "publisher: {}", self.publisher)?;
+        writeln!(f, "objects: {}", self.objects)?;
+        writeln!(f, "bytes: {}", self.bytes)?;
+        match self.last_changed_serial {
+            Some(serial) => writeln!(f, "last changed at RRDP serial: {}", serial)?,
+            None => writeln!(f, "last changed at RRDP serial: unknown")?,
+        }
+        writeln!(f, "activity:")?;
+        writeln!(f, "  last publish: {}", time(self.activity.last_publish))?;
+        writeln!(f, "  deltas last hour: {}", self.activity.deltas_last_hour)?;
+        writeln!(f, "  deltas total: {}", self.activity.deltas_total)?;
+        writeln!(f, "  errors total: {}", self.activity.errors_total)?;
+        writeln!(f, "  last error: {}", time(self.activity.last_error))
+    }
+}
+
+//------------ PublisherStatsList --------------------------------------------
+
+/// This type defines the statistics for all publishers for:
+/// /api/v1/pubd/stats/publishers
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherStatsList {
+    publishers: Vec<PublisherStatsInfo>,
+}
+
+impl PublisherStatsList {
+    pub fn new(publishers: Vec<PublisherStatsInfo>) -> Self {
+        PublisherStatsList { publishers }
+    }
+
+    pub fn publishers(&self) -> &Vec<PublisherStatsInfo> {
+        &self.publishers
+    }
+}
+
+impl fmt::Display for PublisherStatsList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Publisher, Objects, Size, Serial, Last Publish, Deltas Last Hour, Deltas, Errors, Last Error"
+        )?;
+        for stats in &self.publishers {
+            let time = |time: Option<Timestamp>| time.map(|t| t.to_rfc3339()).unwrap_or_else(|| "never".to_string());
+            let serial = stats
+                .last_changed_serial
+                .map(|serial| serial.to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+
+            writeln!(
+                f,
+                "{}, {}, {}, {}, {}, {}, {}, {}, {}",
+                stats.publisher,
+                stats.objects,
+                stats.bytes,
+                serial,
+                time(stats.activity.last_publish),
+                stats.activity.deltas_last_hour,
+                stats.activity.deltas_total,
+                stats.activity.errors_total,
+                time(stats.activity.last_error)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//------------ PublisherValidation -------------------------------------------
+
+/// How strictly the content submitted by a publisher is checked before it
+/// is accepted.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublisherValidation {
+    /// Objects are not checked, beyond the checks required by RFC 8181.
+    #[default]
+    Off,
+
+    /// Objects are checked, issues are logged but the delta is accepted.
+    Warn,
+
+    /// Objects are checked, deltas with issues are rejected.
+    Reject,
+}
+
+impl FromStr for PublisherValidation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(PublisherValidation::Off),
+            "warn" => Ok(PublisherValidation::Warn),
+            "reject" => Ok(PublisherValidation::Reject),
+            _ => Err(Error::custom(format!(
+                "Invalid publisher validation '{}', expected: off, warn or reject",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for PublisherValidation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PublisherValidation::Off => write!(f, "off"),
+            PublisherValidation::Warn => write!(f, "warn"),
+            PublisherValidation::Reject => write!(f, "reject"),
+        }
+    }
+}
+
+//------------ PublisherValidationInfo ---------------------------------------
+
+/// This type defines the validation details for:
+/// /api/v1/pubd/publishers/{handle}/validation
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherValidationInfo {
+    publisher: PublisherHandle,
+
+    /// The validation which applies to the publisher.
+    validation: PublisherValidation,
+
+    /// Whether the validation was set for this publisher, rather than
+    /// taken from the server configuration.
+    custom: bool,
+}
+
+impl PublisherValidationInfo {
+    pub fn new(publisher: PublisherHandle, validation: PublisherValidation, custom: bool) -> Self {
+        PublisherValidationInfo {
+            publisher,
+            validation,
+            custom,
+        }
+    }
+
+    pub fn publisher(&self) -> &PublisherHandle {
+        &self.publisher
+    }
+
+    pub fn validation(&self) -> PublisherValidation {
+        self.validation
+    }
+
+    pub fn custom(&self) -> bool {
+        self.custom
+    }
+}
+
+impl fmt::Display for PublisherValidationInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "publisher: {}", self.publisher)?;
+        if self.custom {
+            writeln!(f, "validation (custom): {}", self.validation)
+        } else {
+            writeln!(f, "validation (server default): {}", self.validation)
+        }
+    }
+}
+
 //------------ PublicationServerInfo -----------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -335,6 +715,38 @@ impl ParentCaReq {
     }
 }
 
+//------------ ParentResponseFetch -------------------------------------------
+
+/// This type defines where Krill can fetch the RFC 8183 Parent Response XML
+/// for a parent itself, e.g. from the portal of an RIR. The optional token is
+/// sent as a bearer token, for endpoints which require authentication.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentResponseFetch {
+    uri: uri::Https,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<Token>,
+}
+
+impl fmt::Display for ParentResponseFetch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.uri)
+    }
+}
+
+impl ParentResponseFetch {
+    pub fn new(uri: uri::Https, token: Option<Token>) -> Self {
+        ParentResponseFetch { uri, token }
+    }
+
+    pub fn uri(&self) -> &uri::Https {
+        &self.uri
+    }
+
+    pub fn token(&self) -> Option<&Token> {
+        self.token.as_ref()
+    }
+}
+
 //------------ ParentServerInfo ----------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -496,6 +908,10 @@ impl CertAuthInit {
         CertAuthInit { handle }
     }
 
+    pub fn handle(&self) -> &CaHandle {
+        &self.handle
+    }
+
     pub fn unpack(self) -> CaHandle {
         self.handle
     }
@@ -529,6 +945,10 @@ impl AddChildRequest {
         &self.handle
     }
 
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
     pub fn unpack(self) -> (ChildHandle, ResourceSet, IdCert) {
         (self.handle, self.resources, self.id_cert)
     }
@@ -546,14 +966,28 @@ pub struct UpdateChildRequest {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     suspend: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suspension_exempt: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manual_approval: Option<bool>,
 }
 
 impl UpdateChildRequest {
-    pub fn new(id_cert: Option<IdCert>, resources: Option<ResourceSet>, suspend: Option<bool>) -> Self {
+    pub fn new(
+        id_cert: Option<IdCert>,
+        resources: Option<ResourceSet>,
+        suspend: Option<bool>,
+        suspension_exempt: Option<bool>,
+        manual_approval: Option<bool>,
+    ) -> Self {
         UpdateChildRequest {
             id_cert,
             resources,
             suspend,
+            suspension_exempt,
+            manual_approval,
         }
     }
     pub fn id_cert(id_cert: IdCert) -> Self {
@@ -561,6 +995,8 @@ impl UpdateChildRequest {
             id_cert: Some(id_cert),
             resources: None,
             suspend: None,
+            suspension_exempt: None,
+            manual_approval: None,
         }
     }
 
@@ -569,6 +1005,8 @@ impl UpdateChildRequest {
             id_cert: None,
             resources: Some(resources),
             suspend: None,
+            suspension_exempt: None,
+            manual_approval: None,
         }
     }
 
@@ -577,6 +1015,8 @@ impl UpdateChildRequest {
             id_cert: None,
             resources: None,
             suspend: Some(true),
+            suspension_exempt: None,
+            manual_approval: None,
         }
     }
 
@@ -585,26 +1025,70 @@ impl UpdateChildRequest {
             id_cert: None,
             resources: None,
             suspend: Some(false),
+            suspension_exempt: None,
+            manual_approval: None,
+        }
+    }
+
+    pub fn suspension_exempt(exempt: bool) -> Self {
+        UpdateChildRequest {
+            id_cert: None,
+            resources: None,
+            suspend: None,
+            suspension_exempt: Some(exempt),
+            manual_approval: None,
+        }
+    }
+
+    pub fn manual_approval(manual: bool) -> Self {
+        UpdateChildRequest {
+            id_cert: None,
+            resources: None,
+            suspend: None,
+            suspension_exempt: None,
+            manual_approval: Some(manual),
         }
     }
 
-    pub fn unpack(self) -> (Option<IdCert>, Option<ResourceSet>, Option<bool>) {
-        (self.id_cert, self.resources, self.suspend)
+    #[allow(clippy::type_complexity)]
+    pub fn unpack(
+        self,
+    ) -> (
+        Option<IdCert>,
+        Option<ResourceSet>,
+        Option<bool>,
+        Option<bool>,
+        Option<bool>,
+    ) {
+        (
+            self.id_cert,
+            self.resources,
+            self.suspend,
+            self.suspension_exempt,
+            self.manual_approval,
+        )
     }
 }
 
 impl fmt::Display for UpdateChildRequest {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut changes = vec![];
         if self.id_cert.is_some() {
-            write!(f, "new id cert ")?;
+            changes.push("new id cert".to_string());
         }
         if let Some(resources) = &self.resources {
-            write!(f, "new resources: {} ", resources)?;
+            changes.push(format!("new resources: {}", resources));
         }
         if let Some(suspend) = self.suspend {
-            write!(f, "change suspend status to: {}", suspend)?;
+            changes.push(format!("change suspend status to: {}", suspend));
         }
-        Ok(())
+        if let Some(exempt) = self.suspension_exempt {
+            changes.push(format!("change suspension exempt to: {}", exempt));
+        }
+        if let Some(manual) = self.manual_approval {
+            changes.push(format!("change manual approval to: {}", manual));
+        }
+        write!(f, "{}", changes.join(" "))
     }
 }
 
@@ -661,43 +1145,1585 @@ impl From<RepoFileDeleteCriteria> for uri::Rsync {
     }
 }
 
-//------------ Tests ---------------------------------------------------------
+//------------ ApiTokenScope -------------------------------------------------
 
-#[cfg(test)]
-mod tests {
+/// The scope of an API token determines what the holder of the token may do.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiTokenScope {
+    /// Anything, like the admin token.
+    Admin,
 
-    use std::convert::TryFrom;
-    use std::path::PathBuf;
-    use std::str::FromStr;
+    /// Everything that the built-in "readwrite" role may do.
+    ReadWrite,
 
-    use super::*;
+    /// Everything that the built-in "readonly" role may do.
+    ReadOnly,
 
-    #[test]
-    fn should_accept_rfc8183_handle() {
-        // See appendix A of RFC8183
-        // handle  = xsd:string { maxLength="255" pattern="[\-_A-Za-z0-9/]*" }
-        CaHandle::from_str("abcDEF012/\\-_").unwrap();
+    /// Manage publishers in the publication server only.
+    Publish,
+
+    /// Read CAs and manage their ROAs only.
+    Roas,
+}
+
+impl ApiTokenScope {
+    /// The name of the role in the authorization policy that is given to
+    /// holders of a token with this scope.
+    pub fn role(&self) -> &'static str {
+        match self {
+            ApiTokenScope::Admin => "admin",
+            ApiTokenScope::ReadWrite => "readwrite",
+            ApiTokenScope::ReadOnly => "readonly",
+            ApiTokenScope::Publish => "publish-only",
+            ApiTokenScope::Roas => "roas-only",
+        }
     }
+}
 
-    #[test]
-    fn should_reject_invalid_handle() {
-        // See appendix A of RFC8183
-        // handle  = xsd:string { maxLength="255" pattern="[\-_A-Za-z0-9/]*" }
-        assert!(CaHandle::from_str("&").is_err());
+impl fmt::Display for ApiTokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiTokenScope::Admin => write!(f, "admin"),
+            ApiTokenScope::ReadWrite => write!(f, "read_write"),
+            ApiTokenScope::ReadOnly => write!(f, "read_only"),
+            ApiTokenScope::Publish => write!(f, "publish"),
+            ApiTokenScope::Roas => write!(f, "roas"),
+        }
     }
+}
 
-    #[test]
-    fn should_make_file_system_safe() {
-        let handle = CaHandle::from_str("abcDEF012/\\-_").unwrap();
-        let expected_path_buf = PathBuf::from("abcDEF012+=-_");
-        assert_eq!(handle.to_path_buf(), expected_path_buf);
+//------------ ApiTokenRequest -----------------------------------------------
+
+/// Request to create a new named API token.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenRequest {
+    name: String,
+    scope: ApiTokenScope,
+
+    /// Restrict the token to these CAs. If empty, the token is not restricted
+    /// to specific CAs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cas: Vec<CaHandle>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<Timestamp>,
+}
+
+impl ApiTokenRequest {
+    pub fn new(name: String, scope: ApiTokenScope, cas: Vec<CaHandle>, expires: Option<Timestamp>) -> Self {
+        ApiTokenRequest {
+            name,
+            scope,
+            cas,
+            expires,
+        }
     }
 
-    #[test]
-    fn should_make_handle_from_dir() {
-        let path = PathBuf::from("a/b/abcDEF012+=-_");
-        let handle = CaHandle::try_from(&path).unwrap();
-        let expected_handle = CaHandle::from_str("abcDEF012/\\-_").unwrap();
-        assert_eq!(handle, expected_handle);
+    pub fn unpack(self) -> (String, ApiTokenScope, Vec<CaHandle>, Option<Timestamp>) {
+        (self.name, self.scope, self.cas, self.expires)
+    }
+}
+
+//------------ ApiTokenInfo --------------------------------------------------
+
+/// Details of an API token, excluding the token itself which is only
+/// revealed once when the token is created.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenInfo {
+    name: String,
+    scope: ApiTokenScope,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cas: Vec<CaHandle>,
+    created: Timestamp,
+    created_by: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<Timestamp>,
+
+    /// The organization of the user who created the token. The token is
+    /// scoped to the same organization.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    org: Option<String>,
+}
+
+impl ApiTokenInfo {
+    pub fn new(
+        name: String,
+        scope: ApiTokenScope,
+        cas: Vec<CaHandle>,
+        created: Timestamp,
+        created_by: String,
+        expires: Option<Timestamp>,
+        org: Option<String>,
+    ) -> Self {
+        ApiTokenInfo {
+            name,
+            scope,
+            cas,
+            created,
+            created_by,
+            expires,
+            org,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn scope(&self) -> ApiTokenScope {
+        self.scope
+    }
+
+    pub fn cas(&self) -> &Vec<CaHandle> {
+        &self.cas
+    }
+
+    pub fn created(&self) -> Timestamp {
+        self.created
+    }
+
+    pub fn created_by(&self) -> &str {
+        &self.created_by
+    }
+
+    pub fn expires(&self) -> Option<Timestamp> {
+        self.expires
+    }
+
+    pub fn org(&self) -> Option<&String> {
+        self.org.as_ref()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.map(|expires| expires <= Timestamp::now()).unwrap_or(false)
+    }
+
+    /// Returns true if this token was created to delegate ROA management of
+    /// the given CA, i.e. if it has the ROAs scope and is restricted to this
+    /// CA only.
+    pub fn is_delegated_for(&self, ca: &CaHandle) -> bool {
+        self.scope == ApiTokenScope::Roas && self.cas.len() == 1 && &self.cas[0] == ca
+    }
+}
+
+impl fmt::Display for ApiTokenInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: scope: {}", self.name, self.scope)?;
+        if !self.cas.is_empty() {
+            let cas: Vec<_> = self.cas.iter().map(|ca| ca.to_string()).collect();
+            write!(f, ", cas: {}", cas.join(","))?;
+        }
+        if let Some(expires) = self.expires {
+            write!(f, ", expires: {}", expires.to_rfc3339())?;
+        }
+        if let Some(org) = &self.org {
+            write!(f, ", org: {}", org)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ ApiTokenList --------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenList {
+    tokens: Vec<ApiTokenInfo>,
+}
+
+impl ApiTokenList {
+    pub fn new(tokens: Vec<ApiTokenInfo>) -> Self {
+        ApiTokenList { tokens }
+    }
+
+    pub fn tokens(&self) -> &Vec<ApiTokenInfo> {
+        &self.tokens
+    }
+}
+
+impl fmt::Display for ApiTokenList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in &self.tokens {
+            writeln!(f, "{}", token)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ ApiTokenCreated -----------------------------------------------
+
+/// The response to creating an API token. This is the only time that the
+/// token itself is revealed, Krill only keeps a hash of it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenCreated {
+    info: ApiTokenInfo,
+    token: Token,
+}
+
+impl ApiTokenCreated {
+    pub fn new(info: ApiTokenInfo, token: Token) -> Self {
+        ApiTokenCreated { info, token }
+    }
+
+    pub fn info(&self) -> &ApiTokenInfo {
+        &self.info
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+}
+
+impl fmt::Display for ApiTokenCreated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.token)?;
+        write!(f, "{}", self.info)
+    }
+}
+
+//------------ CaDelegationRequest -------------------------------------------
+
+/// Request to delegate the management of the ROAs of a CA, e.g. to the holder
+/// of its resources. This results in an API token with the ROAs scope which
+/// is restricted to the CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CaDelegationRequest {
+    name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<Timestamp>,
+}
+
+impl CaDelegationRequest {
+    pub fn new(name: String, expires: Option<Timestamp>) -> Self {
+        CaDelegationRequest { name, expires }
+    }
+
+    pub fn into_token_request(self, ca: CaHandle) -> ApiTokenRequest {
+        ApiTokenRequest::new(self.name, ApiTokenScope::Roas, vec![ca], self.expires)
+    }
+}
+
+//------------ AuditEventKind ------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditEventKind {
+    Login,
+    LoginFailed,
+    Logout,
+    TokenRefresh,
+    AuthFailed,
+    PermissionDenied,
+    ApiCall,
+}
+
+impl fmt::Display for AuditEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            AuditEventKind::Login => "login",
+            AuditEventKind::LoginFailed => "login-failed",
+            AuditEventKind::Logout => "logout",
+            AuditEventKind::TokenRefresh => "token-refresh",
+            AuditEventKind::AuthFailed => "auth-failed",
+            AuditEventKind::PermissionDenied => "permission-denied",
+            AuditEventKind::ApiCall => "api-call",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+//------------ AuditEvent ----------------------------------------------------
+
+/// An entry in the authentication and authorization audit log.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditEvent {
+    time: Timestamp,
+    kind: AuditEventKind,
+    actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<IpAddr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ca: Option<CaHandle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    details: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(kind: AuditEventKind, actor: impl Into<String>) -> Self {
+        AuditEvent {
+            time: Timestamp::now(),
+            kind,
+            actor: actor.into(),
+            source: None,
+            method: None,
+            path: None,
+            ca: None,
+            status: None,
+            details: None,
+        }
+    }
+
+    pub fn with_source(mut self, source: Option<IpAddr>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn with_request(mut self, method: impl Into<String>, path: impl Into<String>) -> Self {
+        self.method = Some(method.into());
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn with_ca(mut self, ca: Option<CaHandle>) -> Self {
+        self.ca = ca;
+        self
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    pub fn time(&self) -> Timestamp {
+        self.time
+    }
+
+    pub fn kind(&self) -> AuditEventKind {
+        self.kind
+    }
+
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
+    pub fn source(&self) -> Option<IpAddr> {
+        self.source
+    }
+
+    pub fn ca(&self) -> Option<&CaHandle> {
+        self.ca.as_ref()
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    pub fn details(&self) -> Option<&str> {
+        self.details.as_deref()
+    }
+}
+
+impl fmt::Display for AuditEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.time.to_rfc3339(), self.kind, self.actor)?;
+        if let Some(source) = self.source {
+            write!(f, " from {}", source)?;
+        }
+        if let (Some(method), Some(path)) = (&self.method, &self.path) {
+            write!(f, " {} {}", method, path)?;
+        }
+        if let Some(status) = self.status {
+            write!(f, " {}", status)?;
+        }
+        if let Some(details) = &self.details {
+            write!(f, ": {}", details)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ AuditEventList ------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditEventList {
+    events: Vec<AuditEvent>,
+}
+
+impl AuditEventList {
+    pub fn new(events: Vec<AuditEvent>) -> Self {
+        AuditEventList { events }
+    }
+
+    pub fn events(&self) -> &Vec<AuditEvent> {
+        &self.events
+    }
+}
+
+impl fmt::Display for AuditEventList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ AggregateSnapshot ---------------------------------------------
+
+/// The snapshot status of an event-sourced aggregate, e.g. a CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AggregateSnapshot {
+    name_space: String,
+    handle: MyHandle,
+    version: u64,
+    snapshot_version: u64,
+
+    // Whether the state loaded from the snapshot and later events is
+    // equivalent to the state rebuilt from all events, if verified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    equivalent: Option<bool>,
+}
+
+impl AggregateSnapshot {
+    pub fn new(
+        name_space: String,
+        handle: MyHandle,
+        version: u64,
+        snapshot_version: u64,
+        equivalent: Option<bool>,
+    ) -> Self {
+        AggregateSnapshot {
+            name_space,
+            handle,
+            version,
+            snapshot_version,
+            equivalent,
+        }
+    }
+
+    pub fn name_space(&self) -> &str {
+        &self.name_space
+    }
+
+    pub fn handle(&self) -> &MyHandle {
+        &self.handle
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn snapshot_version(&self) -> u64 {
+        self.snapshot_version
+    }
+
+    pub fn equivalent(&self) -> Option<bool> {
+        self.equivalent
+    }
+}
+
+impl fmt::Display for AggregateSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}: version {}, snapshot version {}",
+            self.name_space, self.handle, self.version, self.snapshot_version
+        )?;
+        match self.equivalent {
+            Some(true) => write!(f, ", snapshot is equivalent to events"),
+            Some(false) => write!(f, ", snapshot is NOT equivalent to events"),
+            None => Ok(()),
+        }
+    }
+}
+
+//------------ AggregateSnapshots --------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AggregateSnapshots {
+    aggregates: Vec<AggregateSnapshot>,
+}
+
+impl AggregateSnapshots {
+    pub fn new(aggregates: Vec<AggregateSnapshot>) -> Self {
+        AggregateSnapshots { aggregates }
+    }
+
+    pub fn aggregates(&self) -> &Vec<AggregateSnapshot> {
+        &self.aggregates
+    }
+
+    /// Returns true if all verified snapshots are equivalent to their events.
+    pub fn all_equivalent(&self) -> bool {
+        self.aggregates.iter().all(|agg| agg.equivalent != Some(false))
+    }
+}
+
+impl fmt::Display for AggregateSnapshots {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for agg in &self.aggregates {
+            writeln!(f, "{}", agg)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ BackupInfo ----------------------------------------------------
+
+/// Describes a backup of the Krill state.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BackupInfo {
+    name: String,
+    created: Timestamp,
+    krill_version: KrillVersion,
+    config_fingerprint: String,
+    checksum: String,
+    size: u64,
+}
+
+impl BackupInfo {
+    pub fn new(
+        name: String,
+        created: Timestamp,
+        krill_version: KrillVersion,
+        config_fingerprint: String,
+        checksum: String,
+        size: u64,
+    ) -> Self {
+        BackupInfo {
+            name,
+            created,
+            krill_version,
+            config_fingerprint,
+            checksum,
+            size,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn created(&self) -> Timestamp {
+        self.created
+    }
+
+    pub fn krill_version(&self) -> &KrillVersion {
+        &self.krill_version
+    }
+
+    /// The SHA-256 of the configuration settings which determine the
+    /// identity of the Krill instance, as a hex string.
+    pub fn config_fingerprint(&self) -> &str {
+        &self.config_fingerprint
+    }
+
+    /// The SHA-256 of the backup file, as a hex string.
+    pub fn checksum(&self) -> &str {
+        &self.checksum
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl fmt::Display for BackupInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: created {}, krill version {}, size {} bytes, sha256 {}",
+            self.name,
+            self.created.to_rfc3339(),
+            self.krill_version,
+            self.size,
+            self.checksum
+        )
+    }
+}
+
+//------------ BackupList ----------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BackupList {
+    backups: Vec<BackupInfo>,
+}
+
+impl BackupList {
+    pub fn new(backups: Vec<BackupInfo>) -> Self {
+        BackupList { backups }
+    }
+
+    pub fn backups(&self) -> &Vec<BackupInfo> {
+        &self.backups
+    }
+}
+
+impl fmt::Display for BackupList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for backup in &self.backups {
+            writeln!(f, "{}", backup)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ HaStatus ------------------------------------------------------
+
+/// The high availability status of a Krill instance.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct HaStatus {
+    instance: String,
+    active: bool,
+    auto_failover: bool,
+
+    /// The instance holding the lease, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    leader: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lease_expires: Option<Timestamp>,
+}
+
+impl HaStatus {
+    pub fn new(
+        instance: String,
+        active: bool,
+        auto_failover: bool,
+        leader: Option<String>,
+        lease_expires: Option<Timestamp>,
+    ) -> Self {
+        HaStatus {
+            instance,
+            active,
+            auto_failover,
+            leader,
+            lease_expires,
+        }
+    }
+
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn auto_failover(&self) -> bool {
+        self.auto_failover
+    }
+
+    pub fn leader(&self) -> Option<&String> {
+        self.leader.as_ref()
+    }
+
+    pub fn lease_expires(&self) -> Option<Timestamp> {
+        self.lease_expires
+    }
+}
+
+impl fmt::Display for HaStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let role = if self.active { "active" } else { "standby" };
+        writeln!(f, "Instance '{}' is {}", self.instance, role)?;
+        if let (Some(leader), Some(expires)) = (self.leader.as_ref(), self.lease_expires) {
+            writeln!(f, "Lease held by '{}' until {}", leader, expires.to_rfc3339())?;
+        }
+        writeln!(
+            f,
+            "Automatic failover is {}",
+            if self.auto_failover { "enabled" } else { "disabled" }
+        )
+    }
+}
+
+//------------ TimeCheck -----------------------------------------------------
+
+/// The result of comparing the system time with the configured time sources.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TimeCheck {
+    timestamp: Timestamp,
+    sources: Vec<TimeSourceCheck>,
+
+    /// The median of the offsets measured by the sources which responded,
+    /// in milliseconds. Positive if the system time is behind.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    skew_millis: Option<i64>,
+
+    max_skew_seconds: u32,
+    issuance_refused: bool,
+}
+
+impl TimeCheck {
+    pub fn new(sources: Vec<TimeSourceCheck>, max_skew_seconds: u32, refuse_issuance: bool) -> Self {
+        let mut offsets: Vec<i64> = sources.iter().filter_map(|source| source.offset_millis).collect();
+        offsets.sort_unstable();
+        let skew_millis = offsets.get(offsets.len() / 2).copied();
+
+        let mut check = TimeCheck {
+            timestamp: Timestamp::now(),
+            sources,
+            skew_millis,
+            max_skew_seconds,
+            issuance_refused: false,
+        };
+        check.issuance_refused = refuse_issuance && check.skew_exceeded();
+        check
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    pub fn sources(&self) -> &Vec<TimeSourceCheck> {
+        &self.sources
+    }
+
+    /// Returns the skew of the system time, or None if no time source
+    /// responded.
+    pub fn skew_millis(&self) -> Option<i64> {
+        self.skew_millis
+    }
+
+    pub fn max_skew_seconds(&self) -> u32 {
+        self.max_skew_seconds
+    }
+
+    /// Returns true if the skew of the system time is known to exceed the
+    /// maximum.
+    pub fn skew_exceeded(&self) -> bool {
+        self.skew_millis
+            .map(|skew| skew.unsigned_abs() > u64::from(self.max_skew_seconds) * 1000)
+            .unwrap_or(false)
+    }
+
+    pub fn issuance_refused(&self) -> bool {
+        self.issuance_refused
+    }
+}
+
+impl fmt::Display for TimeCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Checked at: {}", self.timestamp.to_rfc3339())?;
+        match self.skew_millis {
+            Some(skew) => writeln!(
+                f,
+                "System time skew: {} ms ({}, maximum {} seconds)",
+                skew,
+                if self.skew_exceeded() { "EXCEEDED" } else { "ok" },
+                self.max_skew_seconds
+            )?,
+            None => writeln!(f, "System time skew: unknown, no time source responded")?,
+        }
+        if self.issuance_refused {
+            writeln!(f, "Issuance is refused until the system time is corrected")?;
+        }
+        writeln!(f, "Sources:")?;
+        for source in &self.sources {
+            writeln!(f, "  {}", source)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ TimeSourceCheck -----------------------------------------------
+
+/// The offset of the system time measured with a single time source.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TimeSourceCheck {
+    source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    offset_millis: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl TimeSourceCheck {
+    pub fn new(source: impl fmt::Display, offset: Result<i64, String>) -> Self {
+        let (offset_millis, error) = match offset {
+            Ok(offset) => (Some(offset), None),
+            Err(e) => (None, Some(e)),
+        };
+        TimeSourceCheck {
+            source: source.to_string(),
+            offset_millis,
+            error,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn offset_millis(&self) -> Option<i64> {
+        self.offset_millis
+    }
+
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+}
+
+impl fmt::Display for TimeSourceCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.offset_millis, self.error.as_ref()) {
+            (Some(offset), _) => write!(f, "{}: offset {} ms", self.source, offset),
+            (None, Some(e)) => write!(f, "{}: error: {}", self.source, e),
+            (None, None) => write!(f, "{}: no response", self.source),
+        }
+    }
+}
+
+//------------ ConfigReloadReport --------------------------------------------
+
+/// The settings which changed when the configuration file was reloaded.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConfigReloadReport {
+    /// Changed settings which are now in use.
+    applied: Vec<String>,
+
+    /// Changed settings which are only used after Krill is restarted.
+    restart_required: Vec<String>,
+}
+
+impl ConfigReloadReport {
+    pub fn new(applied: Vec<String>, restart_required: Vec<String>) -> Self {
+        ConfigReloadReport {
+            applied,
+            restart_required,
+        }
+    }
+
+    pub fn applied(&self) -> &Vec<String> {
+        &self.applied
+    }
+
+    pub fn restart_required(&self) -> &Vec<String> {
+        &self.restart_required
+    }
+}
+
+impl fmt::Display for ConfigReloadReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.applied.is_empty() && self.restart_required.is_empty() {
+            return writeln!(f, "No changed settings found");
+        }
+        if !self.applied.is_empty() {
+            writeln!(f, "Applied settings: {}", self.applied.join(", "))?;
+        }
+        if !self.restart_required.is_empty() {
+            writeln!(
+                f,
+                "Settings which require a restart: {}",
+                self.restart_required.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//------------ StoreVersion --------------------------------------------------
+
+/// The version of Krill, and of the format of the stored data, which last
+/// used a store.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StoreVersion {
+    name_space: String,
+    krill_version: KrillVersion,
+    schema_version: u64,
+}
+
+impl StoreVersion {
+    pub fn new(name_space: &str, krill_version: KrillVersion, schema_version: u64) -> Self {
+        StoreVersion {
+            name_space: name_space.to_string(),
+            krill_version,
+            schema_version,
+        }
+    }
+
+    pub fn name_space(&self) -> &str {
+        &self.name_space
+    }
+
+    pub fn krill_version(&self) -> &KrillVersion {
+        &self.krill_version
+    }
+
+    pub fn schema_version(&self) -> u64 {
+        self.schema_version
+    }
+
+    /// Returns true if the store was last used by a newer version of Krill,
+    /// or with a newer data format, than the running code.
+    pub fn is_newer_than_code(&self) -> bool {
+        self.krill_version > KrillVersion::code_version() || self.schema_version > KRILL_SCHEMA_VERSION
+    }
+}
+
+impl fmt::Display for StoreVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: Krill {}, schema version {}",
+            self.name_space, self.krill_version, self.schema_version
+        )
+    }
+}
+
+//------------ SchemaVersions ------------------------------------------------
+
+/// The versions of the running code, and of the stores it uses.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SchemaVersions {
+    krill_version: KrillVersion,
+    schema_version: u64,
+    stores: Vec<StoreVersion>,
+}
+
+impl SchemaVersions {
+    /// Creates the versions for the given stores and the running code.
+    pub fn for_stores(stores: Vec<StoreVersion>) -> Self {
+        SchemaVersions {
+            krill_version: KrillVersion::code_version(),
+            schema_version: KRILL_SCHEMA_VERSION,
+            stores,
+        }
+    }
+
+    pub fn krill_version(&self) -> &KrillVersion {
+        &self.krill_version
+    }
+
+    pub fn schema_version(&self) -> u64 {
+        self.schema_version
+    }
+
+    pub fn stores(&self) -> &Vec<StoreVersion> {
+        &self.stores
+    }
+
+    /// Returns the stores last used by a newer version than the running code.
+    pub fn newer_stores(&self) -> Vec<&StoreVersion> {
+        self.stores.iter().filter(|store| store.is_newer_than_code()).collect()
+    }
+}
+
+impl fmt::Display for SchemaVersions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Running: Krill {}, schema version {}",
+            self.krill_version, self.schema_version
+        )?;
+        writeln!(f, "Stores:")?;
+        for store in &self.stores {
+            writeln!(f, "  {}", store)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ TaskList ------------------------------------------------------
+
+/// The tasks known to the scheduler: queued tasks, and tasks which ran
+/// before but are no longer queued.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TaskList {
+    tasks: Vec<TaskInfo>,
+}
+
+impl TaskList {
+    pub fn new(tasks: Vec<TaskInfo>) -> Self {
+        TaskList { tasks }
+    }
+
+    pub fn tasks(&self) -> &Vec<TaskInfo> {
+        &self.tasks
+    }
+}
+
+impl fmt::Display for TaskList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for task in &self.tasks {
+            writeln!(f, "{}", task)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ TaskInfo ------------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TaskInfo {
+    /// The name of the kind of task, which can be used to trigger or pause it.
+    name: String,
+    description: String,
+    paused: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_run: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_run: Option<TaskOutcome>,
+}
+
+impl TaskInfo {
+    pub fn new(
+        name: String,
+        description: String,
+        paused: bool,
+        next_run: Option<Timestamp>,
+        last_run: Option<TaskOutcome>,
+    ) -> Self {
+        TaskInfo {
+            name,
+            description,
+            paused,
+            next_run,
+            last_run,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn next_run(&self) -> Option<Timestamp> {
+        self.next_run
+    }
+
+    pub fn last_run(&self) -> Option<&TaskOutcome> {
+        self.last_run.as_ref()
+    }
+}
+
+impl fmt::Display for TaskInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let next_run = match self.next_run {
+            Some(next_run) if self.paused => format!("paused, next run {}", next_run.to_rfc3339()),
+            Some(next_run) => format!("next run {}", next_run.to_rfc3339()),
+            None => "not queued".to_string(),
+        };
+        write!(f, "{} ({}): {}", self.name, self.description, next_run)?;
+        if let Some(last_run) = &self.last_run {
+            write!(f, ", last run {}", last_run)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ TaskOutcome ---------------------------------------------------
+
+/// The outcome of the last run of a task.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TaskOutcome {
+    finished: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl TaskOutcome {
+    pub fn new(finished: Timestamp, error: Option<String>) -> Self {
+        TaskOutcome { finished, error }
+    }
+
+    pub fn finished(&self) -> Timestamp {
+        self.finished
+    }
+
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+}
+
+impl fmt::Display for TaskOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.error {
+            None => write!(f, "{} succeeded", self.finished.to_rfc3339()),
+            Some(e) => write!(f, "{} failed: {}", self.finished.to_rfc3339(), e),
+        }
+    }
+}
+
+//------------ DoctorReport --------------------------------------------------
+
+/// The findings of the self-diagnosis checks, as shown by 'krillc doctor'.
+/// The server does the checks which need access to its data and signers,
+/// the client adds checks which depend on how it sees the server.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DoctorReport {
+    /// The time on the server when it did its checks, used to detect clock
+    /// skew.
+    server_time: Timestamp,
+    findings: Vec<DoctorFinding>,
+}
+
+impl DoctorReport {
+    pub fn new(server_time: Timestamp, findings: Vec<DoctorFinding>) -> Self {
+        DoctorReport { server_time, findings }
+    }
+
+    pub fn server_time(&self) -> Timestamp {
+        self.server_time
+    }
+
+    pub fn findings(&self) -> &Vec<DoctorFinding> {
+        &self.findings
+    }
+
+    pub fn add(&mut self, finding: DoctorFinding) {
+        self.findings.push(finding);
+    }
+
+    /// Returns the number of findings with the given severity.
+    pub fn count(&self, severity: FindingSeverity) -> usize {
+        self.findings.iter().filter(|f| f.severity == severity).count()
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+        writeln!(f)?;
+        write!(
+            f,
+            "{} error(s), {} warning(s)",
+            self.count(FindingSeverity::Error),
+            self.count(FindingSeverity::Warning)
+        )
+    }
+}
+
+//------------ DoctorFinding -------------------------------------------------
+
+/// The outcome of a single check, with a suggested action for findings which
+/// need attention.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DoctorFinding {
+    severity: FindingSeverity,
+
+    /// The name of the check, e.g. 'data-dir' or 'parents'.
+    check: String,
+
+    /// What was checked, e.g. a CA or parent, if the check is done for more
+    /// than one thing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subject: Option<String>,
+
+    message: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+}
+
+impl DoctorFinding {
+    pub fn ok(check: &str, message: impl fmt::Display) -> Self {
+        Self::new(FindingSeverity::Ok, check, message, None)
+    }
+
+    pub fn info(check: &str, message: impl fmt::Display) -> Self {
+        Self::new(FindingSeverity::Info, check, message, None)
+    }
+
+    pub fn warning(check: &str, message: impl fmt::Display, action: impl fmt::Display) -> Self {
+        Self::new(FindingSeverity::Warning, check, message, Some(action.to_string()))
+    }
+
+    pub fn error(check: &str, message: impl fmt::Display, action: impl fmt::Display) -> Self {
+        Self::new(FindingSeverity::Error, check, message, Some(action.to_string()))
+    }
+
+    fn new(severity: FindingSeverity, check: &str, message: impl fmt::Display, action: Option<String>) -> Self {
+        DoctorFinding {
+            severity,
+            check: check.to_string(),
+            subject: None,
+            message: message.to_string(),
+            action,
+        }
+    }
+
+    pub fn with_subject(mut self, subject: impl fmt::Display) -> Self {
+        self.subject = Some(subject.to_string());
+        self
+    }
+
+    pub fn severity(&self) -> FindingSeverity {
+        self.severity
+    }
+
+    pub fn check(&self) -> &str {
+        &self.check
+    }
+
+    pub fn subject(&self) -> Option<&String> {
+        self.subject.as_ref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn action(&self) -> Option<&String> {
+        self.action.as_ref()
+    }
+}
+
+impl fmt::Display for DoctorFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<9} {}", format!("[{}]", self.severity), self.check)?;
+        if let Some(subject) = &self.subject {
+            write!(f, " ({})", subject)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(action) = &self.action {
+            write!(f, "\n          -> {}", action)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ FindingSeverity -----------------------------------------------
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingSeverity {
+    Ok,
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for FindingSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FindingSeverity::Ok => write!(f, "OK"),
+            FindingSeverity::Info => write!(f, "INFO"),
+            FindingSeverity::Warning => write!(f, "WARNING"),
+            FindingSeverity::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+//------------ KeyInventory --------------------------------------------------
+
+/// The keys stored by the signers, with what uses them, as shown by
+/// 'krillc keys list'. Keys held by HSMs cannot be listed and are not
+/// included.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyInventory {
+    timestamp: Timestamp,
+    keys: Vec<StoredKey>,
+}
+
+impl KeyInventory {
+    pub fn new(timestamp: Timestamp, keys: Vec<StoredKey>) -> Self {
+        KeyInventory { timestamp, keys }
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    pub fn keys(&self) -> &Vec<StoredKey> {
+        &self.keys
+    }
+
+    /// Returns the keys which are not used by anything.
+    pub fn unreferenced(&self) -> Vec<&StoredKey> {
+        self.keys.iter().filter(|key| !key.is_referenced()).collect()
+    }
+
+    /// Returns the unreferenced keys which were last modified before the
+    /// threshold, and those which were modified after it or for which this
+    /// is unknown.
+    pub fn gc_candidates(&self, threshold: Timestamp) -> (Vec<StoredKey>, Vec<StoredKey>) {
+        let mut keys = vec![];
+        let mut skipped_recent = vec![];
+        for key in self.unreferenced() {
+            match key.modified() {
+                Some(modified) if modified < threshold => keys.push(key.clone()),
+                _ => skipped_recent.push(key.clone()),
+            }
+        }
+        (keys, skipped_recent)
+    }
+}
+
+impl fmt::Display for KeyInventory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for key in &self.keys {
+            writeln!(f, "{}", key)?;
+        }
+        writeln!(f)?;
+        write!(
+            f,
+            "{} key(s), {} unreferenced",
+            self.keys.len(),
+            self.unreferenced().len()
+        )
+    }
+}
+
+//------------ StoredKey -----------------------------------------------------
+
+/// A key stored by a signer, and what uses it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StoredKey {
+    key_id: KeyIdentifier,
+    signer: String,
+
+    /// The time the key was last modified, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<Timestamp>,
+
+    usages: Vec<KeyUsage>,
+}
+
+impl StoredKey {
+    pub fn new(key_id: KeyIdentifier, signer: String, modified: Option<Timestamp>, usages: Vec<KeyUsage>) -> Self {
+        StoredKey {
+            key_id,
+            signer,
+            modified,
+            usages,
+        }
+    }
+
+    pub fn key_id(&self) -> &KeyIdentifier {
+        &self.key_id
+    }
+
+    pub fn signer(&self) -> &str {
+        &self.signer
+    }
+
+    pub fn modified(&self) -> Option<Timestamp> {
+        self.modified
+    }
+
+    pub fn usages(&self) -> &Vec<KeyUsage> {
+        &self.usages
+    }
+
+    pub fn is_referenced(&self) -> bool {
+        !self.usages.is_empty()
+    }
+}
+
+impl fmt::Display for StoredKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (signer: {}", self.key_id, self.signer)?;
+        if let Some(modified) = self.modified {
+            write!(f, ", modified: {}", modified.to_rfc3339())?;
+        }
+        write!(f, ")")?;
+        if self.usages.is_empty() {
+            write!(f, " UNREFERENCED")
+        } else {
+            for usage in &self.usages {
+                write!(f, "\n  used by {}", usage)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ KeyUsage ------------------------------------------------------
+
+/// A use of a key, e.g. as the current key of a resource class of a CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyUsage {
+    owner: KeyOwner,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resource_class: Option<ResourceClassName>,
+
+    /// The role of the key for the owner, e.g. 'id' or 'current'.
+    role: String,
+}
+
+impl KeyUsage {
+    pub fn new(owner: KeyOwner, resource_class: Option<ResourceClassName>, role: impl fmt::Display) -> Self {
+        KeyUsage {
+            owner,
+            resource_class,
+            role: role.to_string(),
+        }
+    }
+
+    pub fn owner(&self) -> &KeyOwner {
+        &self.owner
+    }
+
+    pub fn resource_class(&self) -> Option<&ResourceClassName> {
+        self.resource_class.as_ref()
+    }
+
+    pub fn role(&self) -> &str {
+        &self.role
+    }
+}
+
+impl fmt::Display for KeyUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.owner)?;
+        if let Some(class) = &self.resource_class {
+            write!(f, ", resource class {}", class)?;
+        }
+        write!(f, ", as {} key", self.role)
+    }
+}
+
+//------------ KeyOwner ------------------------------------------------------
+
+/// What uses a key.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyOwner {
+    Ca(CaHandle),
+    PublicationServer,
+    TaProxy,
+    TaSigner,
+
+    /// The key is used to identify a signer.
+    Signer,
+}
+
+impl fmt::Display for KeyOwner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyOwner::Ca(ca) => write!(f, "CA '{}'", ca),
+            KeyOwner::PublicationServer => write!(f, "Publication Server"),
+            KeyOwner::TaProxy => write!(f, "TA proxy"),
+            KeyOwner::TaSigner => write!(f, "TA signer"),
+            KeyOwner::Signer => write!(f, "signer"),
+        }
+    }
+}
+
+//------------ KeyGcReport ---------------------------------------------------
+
+/// The unreferenced keys which are, or with 'archived' false would be,
+/// archived by 'krillc keys gc'. Unreferenced keys which were modified
+/// recently are skipped, because they may be in the process of being
+/// taken into use.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyGcReport {
+    archived: bool,
+    keys: Vec<StoredKey>,
+    skipped_recent: Vec<StoredKey>,
+}
+
+impl KeyGcReport {
+    pub fn new(archived: bool, keys: Vec<StoredKey>, skipped_recent: Vec<StoredKey>) -> Self {
+        KeyGcReport {
+            archived,
+            keys,
+            skipped_recent,
+        }
+    }
+
+    pub fn archived(&self) -> bool {
+        self.archived
+    }
+
+    pub fn keys(&self) -> &Vec<StoredKey> {
+        &self.keys
+    }
+
+    pub fn skipped_recent(&self) -> &Vec<StoredKey> {
+        &self.skipped_recent
+    }
+}
+
+impl fmt::Display for KeyGcReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.keys.is_empty() {
+            writeln!(f, "No unreferenced keys to archive.")?;
+        } else {
+            if self.archived {
+                writeln!(f, "Archived keys:")?;
+            } else {
+                writeln!(f, "Keys which would be archived (use --confirm to archive):")?;
+            }
+            for key in &self.keys {
+                writeln!(f, "  {}", key)?;
+            }
+        }
+        if !self.skipped_recent.is_empty() {
+            writeln!(f, "Skipped recently modified keys:")?;
+            for key in &self.skipped_recent {
+                writeln!(f, "  {}", key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use std::convert::TryFrom;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn should_accept_rfc8183_handle() {
+        // See appendix A of RFC8183
+        // handle  = xsd:string { maxLength="255" pattern="[\-_A-Za-z0-9/]*" }
+        CaHandle::from_str("abcDEF012/\\-_").unwrap();
+    }
+
+    #[test]
+    fn should_reject_invalid_handle() {
+        // See appendix A of RFC8183
+        // handle  = xsd:string { maxLength="255" pattern="[\-_A-Za-z0-9/]*" }
+        assert!(CaHandle::from_str("&").is_err());
+    }
+
+    #[test]
+    fn should_make_file_system_safe() {
+        let handle = CaHandle::from_str("abcDEF012/\\-_").unwrap();
+        let expected_path_buf = PathBuf::from("abcDEF012+=-_");
+        assert_eq!(handle.to_path_buf(), expected_path_buf);
+    }
+
+    #[test]
+    fn should_make_handle_from_dir() {
+        let path = PathBuf::from("a/b/abcDEF012+=-_");
+        let handle = CaHandle::try_from(&path).unwrap();
+        let expected_handle = CaHandle::from_str("abcDEF012/\\-_").unwrap();
+        assert_eq!(handle, expected_handle);
+    }
+
+    #[test]
+    fn update_child_request_display() {
+        assert_eq!(
+            UpdateChildRequest::suspension_exempt(true).to_string(),
+            "change suspension exempt to: true"
+        );
+
+        let update = UpdateChildRequest::new(None, None, Some(true), Some(false), Some(true));
+        assert_eq!(
+            update.to_string(),
+            "change suspend status to: true change suspension exempt to: false change manual approval to: true"
+        );
     }
 }
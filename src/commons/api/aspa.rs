@@ -27,6 +27,14 @@ impl AspaDefinitionUpdates {
     pub fn unpack(self) -> (Vec<AspaDefinition>, Vec<AspaCustomer>) {
         (self.add_or_replace, self.remove)
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.add_or_replace.is_empty() && self.remove.is_empty()
+    }
+
+    pub fn contains_changes(&self) -> bool {
+        !self.is_empty()
+    }
 }
 
 impl fmt::Display for AspaDefinitionUpdates {
@@ -57,6 +65,63 @@ impl AspaDefinitionList {
     pub fn new(definitions: Vec<AspaDefinition>) -> Self {
         AspaDefinitionList(definitions)
     }
+
+    pub fn definitions(&self) -> &Vec<AspaDefinition> {
+        &self.0
+    }
+
+    pub fn unpack(self) -> Vec<AspaDefinition> {
+        self.0
+    }
+
+    /// Parses a list of definitions from CSV, using one line per
+    /// definition: the customer AS followed by its provider ASs.
+    ///
+    /// example:
+    ///   # comment
+    ///   AS65000,AS65001,AS65002(v4),AS65003(v6)
+    pub fn from_csv(s: &str) -> Result<Self, AspaDefinitionFormatError> {
+        let mut definitions: Vec<AspaDefinition> = vec![];
+
+        for line in s.lines() {
+            let line = match line.find('#') {
+                Some(pos) => &line[..pos],
+                None => line,
+            }
+            .trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let definition_str = match line.split_once(',') {
+                Some((customer, providers)) => format!("{} => {}", customer, providers),
+                None => line.to_string(),
+            };
+
+            let definition = AspaDefinition::from_str(&definition_str)?;
+            if definitions.iter().any(|d| d.customer() == definition.customer()) {
+                return Err(AspaDefinitionFormatError::CustomerAsDuplicate(definition.customer()));
+            }
+            definitions.push(definition);
+        }
+
+        Ok(AspaDefinitionList(definitions))
+    }
+
+    /// Formats this list as CSV, see [`AspaDefinitionList::from_csv`].
+    pub fn to_csv(&self) -> String {
+        let mut res = String::new();
+        for def in self.0.iter() {
+            res.push_str(&def.customer().to_string());
+            for provider in def.providers() {
+                res.push(',');
+                res.push_str(&provider.to_string());
+            }
+            res.push('\n');
+        }
+        res
+    }
 }
 
 impl fmt::Display for AspaDefinitionList {
@@ -282,6 +347,7 @@ impl FromStr for AspaDefinition {
 pub enum AspaDefinitionFormatError {
     CustomerAsMissing,
     CustomerAsInvalid(String),
+    CustomerAsDuplicate(AspaCustomer),
     ProviderAsInvalid(String),
     ProviderAsDuplicate(ProviderAs, ProviderAs),
     ExtraParts,
@@ -293,6 +359,7 @@ impl fmt::Display for AspaDefinitionFormatError {
         match self {
             AspaDefinitionFormatError::CustomerAsMissing => write!(f, "customer AS missing"),
             AspaDefinitionFormatError::CustomerAsInvalid(s) => write!(f, "cannot parse customer AS: {}", s),
+            AspaDefinitionFormatError::CustomerAsDuplicate(asn) => write!(f, "duplicate customer AS: {}", asn),
             AspaDefinitionFormatError::ProviderAsInvalid(s) => write!(f, "cannot parse provider AS: {}", s),
             AspaDefinitionFormatError::ProviderAsDuplicate(l, r) => {
                 write!(f, "duplicate AS in provider list. Found {} and {}", l, r)
@@ -349,6 +416,26 @@ impl AspaProvidersUpdate {
     pub fn removed(&self) -> &Vec<ProviderAs> {
         &self.removed
     }
+
+    /// Returns true if any provider ASN is listed more than once in
+    /// this update, i.e. added or removed twice, or both added and
+    /// removed. The outcome of such updates would be ambiguous, so
+    /// they are rejected by Krill.
+    pub fn contains_duplicate_providers(&self) -> bool {
+        let mut providers: Vec<Asn> = self
+            .added
+            .iter()
+            .chain(self.removed.iter())
+            .map(|p| p.provider())
+            .collect();
+
+        let len_before_duplicates = providers.len();
+
+        providers.sort();
+        providers.dedup();
+
+        len_before_duplicates > providers.len()
+    }
 }
 
 impl fmt::Display for AspaProvidersUpdate {
@@ -409,4 +496,37 @@ mod tests {
         let from_str = AspaDefinition::from_str(config_str).unwrap();
         assert_eq!(config, from_str);
     }
+
+    #[test]
+    fn aspa_definition_list_to_from_csv() {
+        let csv = "# customer,providers\nAS65000,AS65001,AS65002(v4),AS65003(v6)\n\nAS65010,AS65011 # comment\n";
+
+        let list = AspaDefinitionList::from_csv(csv).unwrap();
+        let expected = AspaDefinitionList::new(vec![
+            AspaDefinition::from_str("AS65000 => AS65001, AS65002(v4), AS65003(v6)").unwrap(),
+            AspaDefinition::from_str("AS65010 => AS65011").unwrap(),
+        ]);
+        assert_eq!(expected, list);
+
+        assert_eq!(
+            list.to_csv(),
+            "AS65000,AS65001,AS65002(v4),AS65003(v6)\nAS65010,AS65011\n"
+        );
+        assert_eq!(AspaDefinitionList::from_csv(&list.to_csv()).unwrap(), list);
+
+        assert!(AspaDefinitionList::from_csv("AS65000,AS65001\nAS65000,AS65002").is_err());
+        assert!(AspaDefinitionList::from_csv("AS65000,AS65001,AS65001(v4)").is_err());
+    }
+
+    #[test]
+    fn aspa_providers_update_duplicates() {
+        let update = AspaProvidersUpdate::new(vec![provider("AS65001")], vec![provider("AS65002")]);
+        assert!(!update.contains_duplicate_providers());
+
+        let update = AspaProvidersUpdate::new(vec![provider("AS65001(v4)"), provider("AS65001(v6)")], vec![]);
+        assert!(update.contains_duplicate_providers());
+
+        let update = AspaProvidersUpdate::new(vec![provider("AS65001")], vec![provider("AS65001(v6)")]);
+        assert!(update.contains_duplicate_providers());
+    }
 }
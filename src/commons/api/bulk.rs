@@ -0,0 +1,263 @@
+//! Operations on many CAs at once, which are run as jobs in the background.
+
+use std::{fmt, str::FromStr};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use rpki::ca::idexchange::CaHandle;
+
+use crate::commons::api::{CaLabelSelector, CertAuthSummary};
+
+//------------ CaSelector ----------------------------------------------------
+
+/// Selects the CAs for a bulk operation.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaSelector {
+    All,
+
+    /// A glob pattern for the CA names, where '*' matches any number of
+    /// characters and '?' matches a single character, e.g. 'customer-*'.
+    Glob(String),
+
+    /// A regular expression for the CA names. It must match the whole
+    /// name, unless it is anchored otherwise.
+    Regex(String),
+
+    /// A label selector, e.g. 'customer=acme,env=prod'. See
+    /// [`CaLabelSelector`].
+    Label(String),
+}
+
+impl CaSelector {
+    /// Returns the handles of the given CAs which are selected, in the same
+    /// order. Returns an error if this is an invalid regular expression or
+    /// label selector.
+    pub fn select(&self, cas: Vec<CertAuthSummary>) -> Result<Vec<CaHandle>, String> {
+        let selected: Vec<CertAuthSummary> = match self {
+            CaSelector::All => cas,
+            CaSelector::Glob(glob) => cas
+                .into_iter()
+                .filter(|ca| glob_matches(glob, ca.handle().as_str()))
+                .collect(),
+            CaSelector::Regex(re) => {
+                let re = Regex::new(&format!("^(?:{})$", re)).map_err(|e| e.to_string())?;
+                cas.into_iter().filter(|ca| re.is_match(ca.handle().as_str())).collect()
+            }
+            CaSelector::Label(selector) => {
+                let selector = CaLabelSelector::from_str(selector)?;
+                cas.into_iter().filter(|ca| selector.matches(ca.labels())).collect()
+            }
+        };
+        Ok(selected.into_iter().map(|ca| ca.handle().clone()).collect())
+    }
+}
+
+impl fmt::Display for CaSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CaSelector::All => write!(f, "all CAs"),
+            CaSelector::Glob(glob) => write!(f, "CAs matching glob '{}'", glob),
+            CaSelector::Regex(re) => write!(f, "CAs matching regex '{}'", re),
+            CaSelector::Label(selector) => write!(f, "CAs with labels '{}'", selector),
+        }
+    }
+}
+
+/// Returns true if the name matches the glob pattern.
+fn glob_matches(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // Try to match the rest of the name after the last '*' seen, one more
+    // character at a time, if the match fails.
+    let (mut g, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if g < glob.len() && (glob[g] == '?' || glob[g] == name[n]) {
+            g += 1;
+            n += 1;
+        } else if g < glob.len() && glob[g] == '*' {
+            star = Some((g, n));
+            g += 1;
+        } else if let Some((star_g, star_n)) = star {
+            g = star_g + 1;
+            n = star_n + 1;
+            star = Some((star_g, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    glob[g..].iter().all(|c| *c == '*')
+}
+
+//------------ BulkOperation -------------------------------------------------
+
+/// An operation which can be done for many CAs at once.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkOperation {
+    /// Ask the parents for updated entitlements and certificates.
+    SyncParents,
+
+    /// Synchronise with the repository.
+    SyncRepo,
+
+    /// Re-issue the manifest and CRL if they are close to their next update
+    /// time, and publish.
+    Publish,
+
+    /// Re-issue the manifest and CRL, and publish.
+    ForcePublish,
+}
+
+impl FromStr for BulkOperation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sync_parents" => Ok(BulkOperation::SyncParents),
+            "sync_repo" => Ok(BulkOperation::SyncRepo),
+            "publish" => Ok(BulkOperation::Publish),
+            "force_publish" => Ok(BulkOperation::ForcePublish),
+            _ => Err(format!(
+                "unknown operation '{}', expected one of: sync_parents, sync_repo, publish, force_publish",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BulkOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BulkOperation::SyncParents => write!(f, "sync_parents"),
+            BulkOperation::SyncRepo => write!(f, "sync_repo"),
+            BulkOperation::Publish => write!(f, "publish"),
+            BulkOperation::ForcePublish => write!(f, "force_publish"),
+        }
+    }
+}
+
+//------------ BulkJobRequest ------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BulkJobRequest {
+    selector: CaSelector,
+    operation: BulkOperation,
+}
+
+impl BulkJobRequest {
+    pub fn new(selector: CaSelector, operation: BulkOperation) -> Self {
+        BulkJobRequest { selector, operation }
+    }
+
+    pub fn selector(&self) -> &CaSelector {
+        &self.selector
+    }
+
+    pub fn operation(&self) -> BulkOperation {
+        self.operation
+    }
+}
+
+impl fmt::Display for BulkJobRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} for {}", self.operation, self.selector)
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::commons::api::{CaLabels, CaLabelsUpdate};
+
+    use super::*;
+
+    fn cas(names: &[&str]) -> Vec<CaHandle> {
+        names.iter().map(|name| CaHandle::from_str(name).unwrap()).collect()
+    }
+
+    fn summary(name: &str, labels: &[(&str, &str)]) -> CertAuthSummary {
+        let set: BTreeMap<String, String> = labels
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let mut ca_labels = CaLabels::default();
+        ca_labels.apply(CaLabelsUpdate::new(set, vec![]));
+        CertAuthSummary::new(CaHandle::from_str(name).unwrap(), ca_labels)
+    }
+
+    #[test]
+    fn select_cas() {
+        let all: Vec<_> = ["customer-1", "customer-22", "region-eu", "testbed"]
+            .iter()
+            .map(|name| summary(name, &[]))
+            .collect();
+
+        let glob = CaSelector::Glob("customer-*".to_string());
+        assert_eq!(glob.select(all.clone()).unwrap(), cas(&["customer-1", "customer-22"]));
+
+        let glob = CaSelector::Glob("customer-?".to_string());
+        assert_eq!(glob.select(all.clone()).unwrap(), cas(&["customer-1"]));
+
+        let glob = CaSelector::Glob("*e*-*u".to_string());
+        assert_eq!(glob.select(all.clone()).unwrap(), cas(&["region-eu"]));
+
+        let re = CaSelector::Regex("customer-[0-9]+|testbed".to_string());
+        assert_eq!(
+            re.select(all.clone()).unwrap(),
+            cas(&["customer-1", "customer-22", "testbed"])
+        );
+
+        // The regex must match the whole name.
+        let re = CaSelector::Regex("region".to_string());
+        assert!(re.select(all.clone()).unwrap().is_empty());
+
+        assert!(CaSelector::Regex("(".to_string()).select(all.clone()).is_err());
+        assert_eq!(
+            CaSelector::All.select(all).unwrap(),
+            cas(&["customer-1", "customer-22", "region-eu", "testbed"])
+        );
+    }
+
+    #[test]
+    fn select_cas_by_label() {
+        let all = vec![
+            summary("ca1", &[("customer", "acme"), ("env", "prod")]),
+            summary("ca2", &[("customer", "acme"), ("env", "test")]),
+            summary("ca3", &[("customer", "other")]),
+            summary("ca4", &[]),
+        ];
+
+        let select = |selector: &str| CaSelector::Label(selector.to_string()).select(all.clone());
+
+        assert_eq!(select("customer=acme").unwrap(), cas(&["ca1", "ca2"]));
+        assert_eq!(select("customer=acme,env=prod").unwrap(), cas(&["ca1"]));
+        assert_eq!(select("env").unwrap(), cas(&["ca1", "ca2"]));
+        assert_eq!(select("customer, env = test").unwrap(), cas(&["ca2"]));
+        assert!(select("customer=none").unwrap().is_empty());
+
+        assert!(select("").is_err());
+        assert!(select("-customer=acme").is_err());
+    }
+
+    #[test]
+    fn selector_json() {
+        let json = r#"{"selector":{"glob":"customer-*"},"operation":"force_publish"}"#;
+        let request: BulkJobRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            request,
+            BulkJobRequest::new(CaSelector::Glob("customer-*".to_string()), BulkOperation::ForcePublish)
+        );
+        assert_eq!(serde_json::to_string(&request).unwrap(), json);
+
+        let json = r#"{"selector":"all","operation":"sync_parents"}"#;
+        let request: BulkJobRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.selector(), &CaSelector::All);
+    }
+}
@@ -1,7 +1,7 @@
 //! Common data types for Certificate Authorities, defined here so that the CLI
 //! can have access without needing to depend on the full krill_ca module.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::ops::{self};
 use std::str::FromStr;
@@ -9,7 +9,7 @@ use std::sync::Arc;
 use std::{fmt, str};
 
 use bytes::Bytes;
-use chrono::{Duration, TimeZone, Utc};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use rpki::ca::publication::{PublishDelta, PublishDeltaElement};
 use rpki::repository::x509::{Name, Validity};
 use serde::{Deserialize, Serialize};
@@ -44,8 +44,8 @@ use crate::daemon::ca::BgpSecCertInfo;
 use crate::{
     commons::{
         api::{
-            rrdp::PublishElement, AspaDefinition, ErrorResponse, ParentCaContact, RepositoryContact, RoaAggregateKey,
-            RoaPayload,
+            rrdp::PublishElement, AspaDefinition, AspaDefinitionUpdates, ErrorResponse, ParentCaContact,
+            RepositoryContact, RoaAggregateKey, RoaConfigurationUpdates, RoaPayload,
         },
         util::KrillVersion,
     },
@@ -162,6 +162,10 @@ pub struct ChildCaInfo {
     state: ChildState,
     id_cert: IdCertInfo,
     entitled_resources: ResourceSet,
+    #[serde(default)]
+    suspension_exempt: bool,
+    #[serde(default)]
+    manual_approval: bool,
 }
 
 impl ChildCaInfo {
@@ -170,13 +174,33 @@ impl ChildCaInfo {
             state,
             id_cert,
             entitled_resources,
+            suspension_exempt: false,
+            manual_approval: false,
         }
     }
 
+    pub fn with_suspension_exempt(mut self, suspension_exempt: bool) -> Self {
+        self.suspension_exempt = suspension_exempt;
+        self
+    }
+
+    pub fn with_manual_approval(mut self, manual_approval: bool) -> Self {
+        self.manual_approval = manual_approval;
+        self
+    }
+
     pub fn state(&self) -> ChildState {
         self.state
     }
 
+    pub fn suspension_exempt(&self) -> bool {
+        self.suspension_exempt
+    }
+
+    pub fn manual_approval(&self) -> bool {
+        self.manual_approval
+    }
+
     pub fn id_cert(&self) -> &IdCertInfo {
         &self.id_cert
     }
@@ -191,7 +215,14 @@ impl fmt::Display for ChildCaInfo {
         writeln!(f, "{}", self.id_cert.pem())?;
         writeln!(f, "SHA256 hash of PEM encoded certificate: {}", self.id_cert.hash())?;
         writeln!(f, "resources: {}", self.entitled_resources)?;
-        writeln!(f, "state: {}", self.state)
+        writeln!(f, "state: {}", self.state)?;
+        if self.suspension_exempt {
+            writeln!(f, "exempt from suspension when inactive")?;
+        }
+        if self.manual_approval {
+            writeln!(f, "requests require manual approval")?;
+        }
+        Ok(())
     }
 }
 
@@ -832,8 +863,21 @@ impl CertAuthList {
     pub fn cas(&self) -> &Vec<CertAuthSummary> {
         &self.cas
     }
+
+    /// Keeps only the CAs with labels matching the selector.
+    pub fn select(self, selector: &CaLabelSelector) -> Self {
+        CertAuthList {
+            cas: self
+                .cas
+                .into_iter()
+                .filter(|ca| selector.matches(ca.labels()))
+                .collect(),
+        }
+    }
 }
 
+/// Lists only the handles, one per line, so that the text output can be used
+/// in scripts and shell completions. Labels are included in the JSON output.
 impl fmt::Display for CertAuthList {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for ca in self.cas() {
@@ -855,16 +899,22 @@ impl AsRef<Vec<CertAuthSummary>> for CertAuthList {
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CertAuthSummary {
     handle: CaHandle,
+    #[serde(skip_serializing_if = "CaLabels::is_empty", default)]
+    labels: CaLabels,
 }
 
 impl CertAuthSummary {
-    pub fn new(name: CaHandle) -> Self {
-        CertAuthSummary { handle: name }
+    pub fn new(name: CaHandle, labels: CaLabels) -> Self {
+        CertAuthSummary { handle: name, labels }
     }
 
     pub fn handle(&self) -> &CaHandle {
         &self.handle
     }
+
+    pub fn labels(&self) -> &CaLabels {
+        &self.labels
+    }
 }
 
 //------------ ParentKindInfo ------------------------------------------------
@@ -899,6 +949,10 @@ impl ParentInfo {
         let kind = ParentKindInfo::Rfc6492;
         ParentInfo { handle, kind }
     }
+
+    pub fn handle(&self) -> &ParentHandle {
+        &self.handle
+    }
 }
 
 impl fmt::Display for ParentInfo {
@@ -1018,6 +1072,9 @@ impl fmt::Display for ParentStatuses {
                     writeln!(f, "URI: {}", exchange.uri)?;
                     writeln!(f, "Status: {}", exchange.result)?;
                     writeln!(f, "Last contacted: {}", exchange.timestamp().to_rfc3339())?;
+                    if let Some(backoff) = &status.backoff {
+                        writeln!(f, "Backoff: {}", backoff)?;
+                    }
 
                     if exchange.was_success() {
                         write!(f, "Resource Entitlements:")?;
@@ -1101,6 +1158,9 @@ pub struct ParentStatus {
     // be updated as soon as the CA synchronizes with its parent again.
     #[serde(default)]
     classes: Vec<ResourceClassEntitlements>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    backoff: Option<Backoff>,
 }
 
 impl ParentStatus {
@@ -1120,6 +1180,10 @@ impl ParentStatus {
         self.last_exchange.as_ref().and_then(|e| e.to_failure_opt())
     }
 
+    pub fn backoff(&self) -> Option<&Backoff> {
+        self.backoff.as_ref()
+    }
+
     pub fn set_failure(&mut self, uri: ServiceUri, error: ErrorResponse) {
         self.last_exchange = Some(ParentExchange {
             timestamp: Timestamp::now(),
@@ -1128,6 +1192,10 @@ impl ParentStatus {
         });
     }
 
+    pub fn set_backoff(&mut self, backoff: Backoff) {
+        self.backoff = Some(backoff);
+    }
+
     pub fn set_entitlements(&mut self, uri: ServiceUri, entitlements: &ResourceClassListResponse) {
         self.set_last_updated(uri);
 
@@ -1149,6 +1217,165 @@ impl ParentStatus {
             result: ExchangeResult::Success,
         });
         self.last_success = Some(timestamp);
+        self.backoff = None;
+    }
+}
+
+//------------ ParentsStats --------------------------------------------------
+
+/// A consolidated report on the resources which a CA is entitled to, and
+/// has received, from each of its parents. This helps to see which
+/// resources come from where when a CA has more than one parent.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentsStats {
+    parents: Vec<ParentStats>,
+}
+
+impl ParentsStats {
+    pub fn new(parents: Vec<ParentStats>) -> Self {
+        ParentsStats { parents }
+    }
+
+    pub fn parents(&self) -> &Vec<ParentStats> {
+        &self.parents
+    }
+}
+
+impl fmt::Display for ParentsStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for parent in &self.parents {
+            writeln!(f, "Parent: {}", parent.handle)?;
+            match parent.last_success {
+                None => writeln!(f, "  Last successful contact: never")?,
+                Some(success) => writeln!(f, "  Last successful contact: {}", success.to_rfc3339())?,
+            }
+            for class in &parent.classes {
+                writeln!(f, "  Resource class: {}", class.class_name)?;
+                writeln!(f, "    Entitled resources: {}", class.entitled_resources)?;
+                writeln!(f, "    Received resources: {}", class.received_resources)?;
+                if !class.not_received_resources.is_empty() {
+                    writeln!(f, "    Entitled, but not received: {}", class.not_received_resources)?;
+                }
+                match class.cert_expires {
+                    None => writeln!(f, "    Certificate expires: n/a")?,
+                    Some(expires) => writeln!(f, "    Certificate expires: {}", expires.to_rfc3339())?,
+                }
+                if !class.pending_requests.is_empty() {
+                    writeln!(f, "    Pending certificate requests for keys:")?;
+                    for ki in &class.pending_requests {
+                        writeln!(f, "      {}", ki)?;
+                    }
+                }
+                if let Some(ki) = &class.pending_revocation {
+                    writeln!(f, "    Pending revocation request for key: {}", ki)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The resource classes which a CA holds under a parent, together with the
+/// last successful exchange with that parent.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentStats {
+    handle: ParentHandle,
+    last_success: Option<Timestamp>,
+    classes: Vec<ParentClassStats>,
+}
+
+impl ParentStats {
+    pub fn new(handle: ParentHandle, last_success: Option<Timestamp>, classes: Vec<ParentClassStats>) -> Self {
+        ParentStats {
+            handle,
+            last_success,
+            classes,
+        }
+    }
+
+    pub fn handle(&self) -> &ParentHandle {
+        &self.handle
+    }
+
+    pub fn last_success(&self) -> Option<Timestamp> {
+        self.last_success
+    }
+
+    pub fn classes(&self) -> &Vec<ParentClassStats> {
+        &self.classes
+    }
+}
+
+/// The entitled and received resources for a resource class under a parent.
+///
+/// The class name is the name used by the parent. The entitled resources
+/// are taken from the last entitlements received from the parent, while
+/// the received resources are those on the current certificate held by
+/// the CA. Pending requests are listed by the key identifier for which a
+/// certificate is requested, or which should be revoked.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentClassStats {
+    class_name: ResourceClassName,
+    entitled_resources: ResourceSet,
+    received_resources: ResourceSet,
+    not_received_resources: ResourceSet,
+    cert_expires: Option<Time>,
+    pending_requests: Vec<KeyIdentifier>,
+    pending_revocation: Option<KeyIdentifier>,
+}
+
+impl ParentClassStats {
+    pub fn new(
+        class_name: ResourceClassName,
+        entitled_resources: ResourceSet,
+        received_resources: ResourceSet,
+        cert_expires: Option<Time>,
+        pending_requests: Vec<KeyIdentifier>,
+        pending_revocation: Option<KeyIdentifier>,
+    ) -> Self {
+        let not_received_resources = ResourceSet::new(
+            entitled_resources.asn().difference(received_resources.asn()),
+            entitled_resources.ipv4().difference(received_resources.ipv4()).into(),
+            entitled_resources.ipv6().difference(received_resources.ipv6()).into(),
+        );
+        ParentClassStats {
+            class_name,
+            entitled_resources,
+            received_resources,
+            not_received_resources,
+            cert_expires,
+            pending_requests,
+            pending_revocation,
+        }
+    }
+
+    pub fn class_name(&self) -> &ResourceClassName {
+        &self.class_name
+    }
+
+    pub fn entitled_resources(&self) -> &ResourceSet {
+        &self.entitled_resources
+    }
+
+    pub fn received_resources(&self) -> &ResourceSet {
+        &self.received_resources
+    }
+
+    pub fn not_received_resources(&self) -> &ResourceSet {
+        &self.not_received_resources
+    }
+
+    pub fn cert_expires(&self) -> Option<Time> {
+        self.cert_expires
+    }
+
+    pub fn pending_requests(&self) -> &Vec<KeyIdentifier> {
+        &self.pending_requests
+    }
+
+    pub fn pending_revocation(&self) -> Option<KeyIdentifier> {
+        self.pending_revocation
     }
 }
 
@@ -1159,6 +1386,10 @@ pub struct RepoStatus {
     last_exchange: Option<ParentExchange>,
     last_success: Option<Timestamp>,
     published: Vec<PublishElement>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    backoff: Option<Backoff>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    confirmation: Option<PublicationConfirmation>,
 }
 
 impl RepoStatus {
@@ -1173,6 +1404,14 @@ impl RepoStatus {
     pub fn to_failure_opt(&self) -> Option<ErrorResponse> {
         self.last_exchange.as_ref().and_then(|e| e.to_failure_opt())
     }
+
+    pub fn backoff(&self) -> Option<&Backoff> {
+        self.backoff.as_ref()
+    }
+
+    pub fn confirmation(&self) -> Option<&PublicationConfirmation> {
+        self.confirmation.as_ref()
+    }
 }
 
 impl RepoStatus {
@@ -1185,6 +1424,10 @@ impl RepoStatus {
         });
     }
 
+    pub fn set_backoff(&mut self, backoff: Backoff) {
+        self.backoff = Some(backoff);
+    }
+
     pub fn update_published(&mut self, uri: ServiceUri, delta: PublishDelta) {
         let timestamp = Timestamp::now();
         self.last_exchange = Some(ParentExchange {
@@ -1211,6 +1454,7 @@ impl RepoStatus {
         }
 
         self.last_success = Some(timestamp);
+        self.backoff = None;
     }
 
     pub fn set_last_updated(&mut self, uri: ServiceUri) {
@@ -1221,6 +1465,35 @@ impl RepoStatus {
             result: ExchangeResult::Success,
         });
         self.last_success = Some(timestamp);
+        self.backoff = None;
+    }
+
+    /// Marks that objects were published which are not yet confirmed to be
+    /// available over RRDP. If earlier objects were not confirmed either then
+    /// the lag is counted from their publication.
+    pub fn set_unconfirmed(&mut self) {
+        let confirmation = self.confirmation.get_or_insert_with(PublicationConfirmation::default);
+        if confirmation.pending_since.is_none() {
+            confirmation.pending_since = Some(Timestamp::now());
+        }
+    }
+
+    /// Marks that all published objects were found in the RRDP snapshot with
+    /// the given serial.
+    pub fn set_confirmed(&mut self, serial: u64) {
+        self.confirmation = Some(PublicationConfirmation {
+            last_confirmed: Some(Timestamp::now()),
+            serial: Some(serial),
+            pending_since: None,
+            lagging: false,
+        });
+    }
+
+    /// Marks that an alert was raised because confirmation lags.
+    pub fn set_confirm_lagging(&mut self) {
+        if let Some(confirmation) = self.confirmation.as_mut() {
+            confirmation.lagging = true;
+        }
     }
 }
 
@@ -1237,116 +1510,178 @@ impl fmt::Display for RepoStatus {
                 if let Some(success) = self.last_success() {
                     writeln!(f, "Last successful contact: {}", success.to_rfc3339())?;
                 }
+                if let Some(backoff) = &self.backoff {
+                    writeln!(f, "Backoff: {}", backoff)?;
+                }
+                if let Some(confirmation) = &self.confirmation {
+                    write!(f, "{}", confirmation)?;
+                }
             }
         }
         Ok(())
     }
 }
 
-//------------ ParentExchange ------------------------------------------------
+//------------ PublicationConfirmation ---------------------------------------
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct ParentExchange {
-    timestamp: Timestamp,
-    uri: ServiceUri,
-    result: ExchangeResult,
+/// Tracks whether the objects published at a repository could be fetched
+/// back over RRDP. This is only done if 'publication_confirm_lag_minutes' is
+/// set in the configuration.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublicationConfirmation {
+    /// When all published objects were last confirmed.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    last_confirmed: Option<Timestamp>,
+
+    /// The RRDP serial of the snapshot in which they were confirmed.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    serial: Option<u64>,
+
+    /// When objects were published which are not yet confirmed.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pending_since: Option<Timestamp>,
+
+    /// Whether an alert was raised for the pending objects.
+    #[serde(default)]
+    lagging: bool,
 }
 
-impl ParentExchange {
-    pub fn timestamp(&self) -> Timestamp {
-        self.timestamp
+impl PublicationConfirmation {
+    pub fn last_confirmed(&self) -> Option<Timestamp> {
+        self.last_confirmed
     }
 
-    pub fn uri(&self) -> &ServiceUri {
-        &self.uri
+    pub fn serial(&self) -> Option<u64> {
+        self.serial
     }
 
-    pub fn result(&self) -> &ExchangeResult {
-        &self.result
+    pub fn pending_since(&self) -> Option<Timestamp> {
+        self.pending_since
     }
 
-    pub fn was_success(&self) -> bool {
-        self.result.was_success()
+    pub fn is_lagging(&self) -> bool {
+        self.lagging
     }
 
-    pub fn to_failure_opt(&self) -> Option<ErrorResponse> {
-        match &self.result {
-            ExchangeResult::Success => None,
-            ExchangeResult::Failure(error) => Some(error.clone()),
+    /// Returns true if there are objects which were published at least the
+    /// given number of minutes ago, and which are not yet confirmed.
+    pub fn lags(&self, minutes: u32) -> bool {
+        self.pending_since
+            .map(|since| since <= Timestamp::now_minus_seconds(i64::from(minutes) * 60))
+            .unwrap_or(false)
+    }
+}
+
+impl fmt::Display for PublicationConfirmation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let (Some(confirmed), Some(serial)) = (self.last_confirmed, self.serial) {
+            writeln!(
+                f,
+                "Publication last confirmed: {} (RRDP serial {})",
+                confirmed.to_rfc3339(),
+                serial
+            )?;
+        }
+        if let Some(since) = self.pending_since {
+            writeln!(f, "Publication unconfirmed since: {}", since.to_rfc3339())?;
         }
+        Ok(())
     }
 }
 
-//------------ ExchangeResult ------------------------------------------------
+//------------ RepoStatuses --------------------------------------------------
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-#[allow(clippy::large_enum_variant)]
-pub enum ExchangeResult {
-    Success,
-    Failure(ErrorResponse),
-}
+/// The status of each repository that a CA publishes to, keyed by the service
+/// URI of the repository. A CA normally publishes to a single repository, but
+/// it may use more while it is migrating, or if some resource classes use
+/// another repository.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepoStatuses(HashMap<String, RepoStatus>);
 
-impl ExchangeResult {
-    pub fn was_success(&self) -> bool {
-        match self {
-            ExchangeResult::Success => true,
-            ExchangeResult::Failure(_) => false,
-        }
+impl RepoStatuses {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, uri: &ServiceUri) -> Option<&RepoStatus> {
+        self.0.get(&uri.to_string())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &RepoStatus)> {
+        self.0.iter()
+    }
+
+    pub fn get_mut_status(&mut self, uri: &ServiceUri) -> &mut RepoStatus {
+        self.0.entry(uri.to_string()).or_default()
+    }
+
+    pub fn remove(&mut self, uri: &ServiceUri) {
+        self.0.remove(&uri.to_string());
     }
 }
 
-impl fmt::Display for ExchangeResult {
+impl fmt::Display for RepoStatuses {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            ExchangeResult::Success => write!(f, "success"),
-            ExchangeResult::Failure(e) => write!(f, "failure: {}", e.msg()),
+        if self.0.is_empty() {
+            return writeln!(f, "Status: connection still pending");
+        }
+
+        let mut uris: Vec<&String> = self.0.keys().collect();
+        uris.sort();
+        for uri in uris {
+            writeln!(f, "Repository: {}", uri)?;
+            write!(f, "{}", self.0[uri])?;
+            writeln!(f)?;
         }
+        Ok(())
     }
 }
 
-//------------ ChildConnectionStats ------------------------------------------
+//------------ PublicationCheck ----------------------------------------------
 
+/// The result of fetching the objects published by a CA from its repository,
+/// and checking them in the way that a relying party would.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct ChildrenConnectionStats {
-    children: Vec<ChildConnectionStats>,
+pub struct PublicationCheck {
+    timestamp: Timestamp,
+    sources: Vec<PublicationCheckSource>,
 }
 
-impl ChildrenConnectionStats {
-    pub fn new(children: Vec<ChildConnectionStats>) -> Self {
-        ChildrenConnectionStats { children }
+impl PublicationCheck {
+    pub fn new(sources: Vec<PublicationCheckSource>) -> Self {
+        PublicationCheck {
+            timestamp: Timestamp::now(),
+            sources,
+        }
     }
 
-    pub fn suspension_candidates(&self, threshold_seconds: i64) -> Vec<ChildHandle> {
-        self.children
-            .iter()
-            .filter(|child| child.is_suspension_candidate(threshold_seconds))
-            .map(|child| child.handle.clone())
-            .collect()
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    pub fn sources(&self) -> &Vec<PublicationCheckSource> {
+        &self.sources
+    }
+
+    /// Returns true if no issues were found for any of the sources.
+    pub fn is_ok(&self) -> bool {
+        self.sources.iter().all(|source| source.issues.is_empty())
     }
 }
 
-impl fmt::Display for ChildrenConnectionStats {
+impl fmt::Display for PublicationCheck {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if !self.children.is_empty() {
-            writeln!(f, "handle,user_agent,last_exchange,result,state")?;
-            for child in &self.children {
-                match &child.last_exchange {
-                    None => {
-                        writeln!(f, "{},n/a,never,n/a,{}", child.handle, child.state)?;
-                    }
-                    Some(exchange) => {
-                        let agent = exchange.user_agent.as_deref().unwrap_or("");
-
-                        writeln!(
-                            f,
-                            "{},{},{},{},{}",
-                            child.handle,
-                            agent,
-                            exchange.timestamp.to_rfc3339(),
-                            exchange.result,
-                            child.state
-                        )?;
-                    }
+        writeln!(f, "Checked at: {}", self.timestamp.to_rfc3339())?;
+        for source in &self.sources {
+            writeln!(f)?;
+            writeln!(f, "{} fetched from: {}", source.protocol, source.uri)?;
+            writeln!(f, "  Objects found: {}", source.objects)?;
+            if source.issues.is_empty() {
+                writeln!(f, "  No issues found")?;
+            } else {
+                writeln!(f, "  Issues:")?;
+                for issue in &source.issues {
+                    writeln!(f, "    {}", issue)?;
                 }
             }
         }
@@ -1354,765 +1689,3161 @@ impl fmt::Display for ChildrenConnectionStats {
     }
 }
 
+/// The result of checking the objects fetched using one protocol.
+///
+/// The uri is the RRDP notification URI, or the rsync directory, which
+/// the objects were fetched from. If the objects could not be fetched at
+/// all, then this is reported as an issue without an object uri.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct ChildConnectionStats {
-    handle: ChildHandle,
-    last_exchange: Option<ChildExchange>,
-    state: ChildState,
+pub struct PublicationCheckSource {
+    protocol: PublicationCheckProtocol,
+    uri: String,
+    objects: usize,
+    issues: Vec<PublicationCheckIssue>,
 }
 
-impl ChildConnectionStats {
-    pub fn new(handle: ChildHandle, last_exchange: Option<ChildExchange>, state: ChildState) -> Self {
-        ChildConnectionStats {
-            handle,
-            last_exchange,
-            state,
+impl PublicationCheckSource {
+    pub fn new(
+        protocol: PublicationCheckProtocol,
+        uri: String,
+        objects: usize,
+        issues: Vec<PublicationCheckIssue>,
+    ) -> Self {
+        PublicationCheckSource {
+            protocol,
+            uri,
+            objects,
+            issues,
         }
     }
 
-    /// The child is considered a candidate for suspension if:
-    ///  - it is Krill 0.9.2-rc and up (see #670)
-    ///  - the last exchange is longer ago than the specified threshold hours
-    ///  - and the child is not already suspended
-    pub fn is_suspension_candidate(&self, threshold_seconds: i64) -> bool {
-        if self.state == ChildState::Suspended {
-            false
-        } else {
-            self.last_exchange
-                .as_ref()
-                .map(|exchange| exchange.is_krill_above_0_9_1() && exchange.more_than_seconds_ago(threshold_seconds))
-                .unwrap_or(false)
-        }
+    pub fn protocol(&self) -> PublicationCheckProtocol {
+        self.protocol
     }
-}
-
-//------------ ChildStatus ---------------------------------------------------
 
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
-pub struct ChildStatus {
-    last_exchange: Option<ChildExchange>,
-    last_success: Option<Timestamp>,
-    suspended: Option<Timestamp>,
-}
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
 
-impl ChildStatus {
-    pub fn set_success(&mut self, user_agent: Option<String>) {
-        let timestamp = Timestamp::now();
-        self.last_exchange = Some(ChildExchange {
-            result: ExchangeResult::Success,
-            timestamp,
-            user_agent,
-        });
-        self.last_success = Some(timestamp);
-        self.suspended = None;
+    pub fn objects(&self) -> usize {
+        self.objects
     }
 
-    pub fn set_failure(&mut self, user_agent: Option<String>, error_response: ErrorResponse) {
-        self.last_exchange = Some(ChildExchange {
-            timestamp: Timestamp::now(),
-            result: ExchangeResult::Failure(error_response),
-            user_agent,
-        });
-        self.suspended = None;
+    pub fn issues(&self) -> &Vec<PublicationCheckIssue> {
+        &self.issues
     }
+}
 
-    pub fn set_suspended(&mut self) {
-        self.suspended = Some(Timestamp::now())
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicationCheckProtocol {
+    Rrdp,
+    Rsync,
+}
+
+impl fmt::Display for PublicationCheckProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PublicationCheckProtocol::Rrdp => write!(f, "RRDP"),
+            PublicationCheckProtocol::Rsync => write!(f, "rsync"),
+        }
     }
+}
 
-    pub fn last_exchange(&self) -> Option<&ChildExchange> {
-        self.last_exchange.as_ref()
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublicationCheckIssue {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri: Option<uri::Rsync>,
+    msg: String,
+}
+
+impl PublicationCheckIssue {
+    pub fn new(uri: Option<uri::Rsync>, msg: impl fmt::Display) -> Self {
+        PublicationCheckIssue {
+            uri,
+            msg: msg.to_string(),
+        }
     }
 
-    pub fn last_success(&self) -> Option<Timestamp> {
-        self.last_success
+    pub fn for_object(uri: &uri::Rsync, msg: impl fmt::Display) -> Self {
+        Self::new(Some(uri.clone()), msg)
     }
 
-    pub fn suspended(&self) -> Option<Timestamp> {
-        self.suspended
+    pub fn uri(&self) -> Option<&uri::Rsync> {
+        self.uri.as_ref()
     }
 
-    pub fn child_state(&self) -> ChildState {
-        if self.suspended.is_none() {
-            ChildState::Active
-        } else {
-            ChildState::Suspended
-        }
+    pub fn msg(&self) -> &str {
+        &self.msg
     }
 }
 
-impl From<ChildStatus> for Option<ChildExchange> {
-    fn from(status: ChildStatus) -> Self {
-        status.last_exchange
+impl fmt::Display for PublicationCheckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.uri {
+            Some(uri) => write!(f, "{}: {}", uri, self.msg),
+            None => write!(f, "{}", self.msg),
+        }
     }
 }
 
-//------------ ChildExchange -------------------------------------------------
+//------------ Reconciliation ------------------------------------------------
 
+/// The result of comparing the configuration of a CA, i.e. its ROA and ASPA
+/// definitions and its children, with the objects that it actually issued
+/// and published.
+///
+/// If a repair was done, then this reports the divergences which remained
+/// after the repair.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct ChildExchange {
+pub struct Reconciliation {
     timestamp: Timestamp,
-    result: ExchangeResult,
-    user_agent: Option<String>,
+    divergences: Vec<Divergence>,
+    #[serde(default)]
+    repaired: bool,
 }
 
-impl ChildExchange {
-    pub fn was_success(&self) -> bool {
-        self.result.was_success()
+impl Reconciliation {
+    pub fn new(divergences: Vec<Divergence>, repaired: bool) -> Self {
+        Reconciliation {
+            timestamp: Timestamp::now(),
+            divergences,
+            repaired,
+        }
     }
 
     pub fn timestamp(&self) -> Timestamp {
         self.timestamp
     }
 
-    pub fn user_agent(&self) -> Option<&String> {
-        self.user_agent.as_ref()
+    pub fn divergences(&self) -> &Vec<Divergence> {
+        &self.divergences
     }
 
-    pub fn more_than_seconds_ago(&self, seconds: i64) -> bool {
-        self.timestamp < Timestamp::now_minus_seconds(seconds)
+    pub fn repaired(&self) -> bool {
+        self.repaired
     }
 
-    pub fn is_krill_above_0_9_1(&self) -> bool {
-        if let Some(agent) = &self.user_agent {
-            // local-child is used by local children, it is extremely
-            // unlikely that they would become suspend candidates in
-            // the real world - but.. we have to use these to test the
-            // auto-suspend logic in the high-level "suspend.rs" test
-            if agent == "local-child" {
-                return true;
-            } else if let Some(version) = agent.strip_prefix("krill/") {
-                if let Ok(krill_version) = KrillVersion::from_str(version) {
-                    return krill_version > KrillVersion::release(0, 9, 1);
-                }
-            }
-        }
-        false
+    /// Returns true if no divergences were found.
+    pub fn is_ok(&self) -> bool {
+        self.divergences.is_empty()
     }
 }
 
-//------------ Timestamp -----------------------------------------------------
-
-/// A wrapper for unix timestamps with second precision, with some convenient stuff.
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
-pub struct Timestamp(i64);
-
-impl Timestamp {
-    pub fn new(ts: i64) -> Self {
-        Timestamp(ts)
+impl fmt::Display for Reconciliation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Checked at: {}", self.timestamp.to_rfc3339())?;
+        if self.repaired {
+            writeln!(f, "A repair was done, remaining divergences are shown")?;
+        }
+        if self.divergences.is_empty() {
+            writeln!(f, "No divergences found")?;
+        } else {
+            writeln!(f, "Divergences:")?;
+            for divergence in &self.divergences {
+                writeln!(f, "  {}", divergence)?;
+            }
+        }
+        Ok(())
     }
+}
 
-    pub fn now() -> Self {
-        Timestamp(Time::now().timestamp())
-    }
+/// A single difference between the configuration of a CA and the objects
+/// it issued or published. The subject identifies the object, e.g. the ROA
+/// payload, the ASPA customer, the child, or the URI of a published object.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Divergence {
+    kind: DivergenceKind,
+    object: DivergenceObject,
+    subject: String,
+    msg: String,
+}
 
-    pub fn now_plus_hours(hours: i64) -> Self {
-        Timestamp::now().plus_hours(hours)
+impl Divergence {
+    pub fn new(
+        kind: DivergenceKind,
+        object: DivergenceObject,
+        subject: impl fmt::Display,
+        msg: impl fmt::Display,
+    ) -> Self {
+        Divergence {
+            kind,
+            object,
+            subject: subject.to_string(),
+            msg: msg.to_string(),
+        }
     }
 
-    pub fn plus_hours(self, hours: i64) -> Self {
-        self + Duration::hours(hours)
+    pub fn missing(object: DivergenceObject, subject: impl fmt::Display, msg: impl fmt::Display) -> Self {
+        Self::new(DivergenceKind::Missing, object, subject, msg)
     }
 
-    pub fn now_minus_hours(hours: i64) -> Self {
-        Timestamp::now().minus_hours(hours)
+    pub fn stale(object: DivergenceObject, subject: impl fmt::Display, msg: impl fmt::Display) -> Self {
+        Self::new(DivergenceKind::Stale, object, subject, msg)
     }
 
-    pub fn minus_hours(self, hours: i64) -> Self {
-        self - Duration::hours(hours)
+    pub fn orphaned(object: DivergenceObject, subject: impl fmt::Display, msg: impl fmt::Display) -> Self {
+        Self::new(DivergenceKind::Orphaned, object, subject, msg)
     }
 
-    pub fn now_plus_minutes(minutes: i64) -> Self {
-        Timestamp::now().plus_minutes(minutes)
+    pub fn kind(&self) -> DivergenceKind {
+        self.kind
     }
 
-    pub fn plus_minutes(self, minutes: i64) -> Self {
-        self + Duration::minutes(minutes)
+    pub fn object(&self) -> DivergenceObject {
+        self.object
     }
 
-    pub fn minus_seconds(self, seconds: i64) -> Self {
-        self - Duration::seconds(seconds)
+    pub fn subject(&self) -> &str {
+        &self.subject
     }
 
-    pub fn plus_seconds(self, seconds: i64) -> Self {
-        self + Duration::seconds(seconds)
+    pub fn msg(&self) -> &str {
+        &self.msg
     }
+}
 
-    pub fn now_minus_seconds(seconds: i64) -> Self {
-        Timestamp::now().minus_seconds(seconds)
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}: {}", self.kind, self.object, self.subject, self.msg)
     }
+}
 
-    pub fn now_plus_seconds(seconds: i64) -> Self {
-        Timestamp::now().plus_seconds(seconds)
-    }
+/// Missing objects should have been issued or published, but were not.
+/// Stale objects were issued, but no longer match the configuration or
+/// have expired. Orphaned objects exist, but nothing in the configuration
+/// calls for them.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceKind {
+    Missing,
+    Stale,
+    Orphaned,
+}
 
-    pub fn to_rfc3339(self) -> String {
-        Time::from(self).to_rfc3339()
+impl fmt::Display for DivergenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DivergenceKind::Missing => write!(f, "missing"),
+            DivergenceKind::Stale => write!(f, "stale"),
+            DivergenceKind::Orphaned => write!(f, "orphaned"),
+        }
     }
 }
 
-impl From<Timestamp> for Time {
-    fn from(timestamp: Timestamp) -> Self {
-        Time::new(
-            Utc.timestamp_opt(timestamp.0, 0)
-                .single()
-                .expect("timestamp out-of-range"),
-        )
-    }
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceObject {
+    Roa,
+    Aspa,
+    ChildCertificate,
+    PublishedObject,
 }
 
-impl From<Time> for Timestamp {
-    fn from(time: Time) -> Self {
-        Timestamp(time.timestamp())
+impl fmt::Display for DivergenceObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DivergenceObject::Roa => write!(f, "ROA"),
+            DivergenceObject::Aspa => write!(f, "ASPA"),
+            DivergenceObject::ChildCertificate => write!(f, "child certificate"),
+            DivergenceObject::PublishedObject => write!(f, "published object"),
+        }
     }
 }
 
-impl From<Timestamp> for i64 {
-    fn from(t: Timestamp) -> Self {
-        t.0
-    }
+//------------ ObjectsExpiry -------------------------------------------------
+
+/// The expiry times of all objects published by a CA, sorted by the time
+/// of expiry. Objects which expire within the window are marked.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ObjectsExpiry {
+    window_hours: u32,
+    objects: Vec<ObjectExpiry>,
 }
 
-//--- Display
+impl ObjectsExpiry {
+    pub fn new(window_hours: u32, mut objects: Vec<ObjectExpiry>) -> Self {
+        objects.sort_by_key(|object| object.not_after);
+        ObjectsExpiry { window_hours, objects }
+    }
 
-impl fmt::Display for Timestamp {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.fmt(f)
+    pub fn window_hours(&self) -> u32 {
+        self.window_hours
     }
-}
 
-//--- Add
+    pub fn objects(&self) -> &Vec<ObjectExpiry> {
+        &self.objects
+    }
 
-impl ops::Add<Duration> for Timestamp {
-    type Output = Self;
+    pub fn expiring(&self) -> impl Iterator<Item = &ObjectExpiry> {
+        self.objects.iter().filter(|object| object.expiring)
+    }
 
-    fn add(self, duration: Duration) -> Self::Output {
-        Timestamp(self.0 + duration.num_seconds())
+    /// Returns the distribution of the hours until objects expire for each
+    /// type of object, using the given upper bounds for the buckets.
+    pub fn distribution(&self, bounds_hours: &[i64]) -> BTreeMap<ObjectExpiryType, ExpiryDistribution> {
+        let now = Time::now();
+        let mut distribution: BTreeMap<ObjectExpiryType, ExpiryDistribution> = BTreeMap::new();
+        for object in &self.objects {
+            let hours = (object.not_after - now).num_hours().max(0);
+            distribution
+                .entry(object.object_type)
+                .or_insert_with(|| ExpiryDistribution::new(bounds_hours))
+                .add(hours);
+        }
+        distribution
     }
 }
 
-impl ops::AddAssign<Duration> for Timestamp {
-    fn add_assign(&mut self, duration: Duration) {
-        self.0 += duration.num_seconds();
+impl fmt::Display for ObjectsExpiry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Objects expiring within {} hours: {}",
+            self.window_hours,
+            self.expiring().count()
+        )?;
+        writeln!(f)?;
+        for object in &self.objects {
+            writeln!(
+                f,
+                "{} {:<11} {}{}",
+                object.not_after.to_rfc3339(),
+                object.object_type,
+                object.uri,
+                if object.expiring { "  (expiring)" } else { "" }
+            )?;
+        }
+        Ok(())
     }
 }
 
-//--- Sub
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ObjectExpiry {
+    uri: uri::Rsync,
+    object_type: ObjectExpiryType,
+    not_after: Time,
+    expiring: bool,
+}
 
-impl ops::Sub<Duration> for Timestamp {
-    type Output = Self;
+impl ObjectExpiry {
+    pub fn new(uri: uri::Rsync, not_after: Time, window_hours: u32) -> Self {
+        let object_type = ObjectExpiryType::for_uri(&uri);
+        let expiring = not_after < Time::now() + Duration::hours(window_hours.into());
+        ObjectExpiry {
+            uri,
+            object_type,
+            not_after,
+            expiring,
+        }
+    }
 
-    fn sub(self, duration: Duration) -> Self::Output {
-        Timestamp(self.0 - duration.num_seconds())
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
     }
-}
 
-impl ops::SubAssign<Duration> for Timestamp {
-    fn sub_assign(&mut self, duration: Duration) {
-        self.0 -= duration.num_seconds()
+    pub fn object_type(&self) -> ObjectExpiryType {
+        self.object_type
     }
-}
 
-//------------ CertAuthInfo --------------------------------------------------
+    pub fn not_after(&self) -> Time {
+        self.not_after
+    }
 
-/// This type represents the details of a CertAuth that need
-/// to be exposed through the API/CLI/UI
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct CertAuthInfo {
-    handle: CaHandle,
-    id_cert: IdCertInfo,
-    repo_info: Option<RepoInfo>,
-    parents: Vec<ParentInfo>,
-    resources: ResourceSet,
-    resource_classes: HashMap<ResourceClassName, ResourceClassInfo>,
-    children: Vec<ChildHandle>,
-    suspended_children: Vec<ChildHandle>,
+    pub fn expiring(&self) -> bool {
+        self.expiring
+    }
 }
 
-impl CertAuthInfo {
-    pub fn new(
-        handle: CaHandle,
-        id_cert: IdCertInfo,
-        repo_info: Option<RepoInfo>,
-        parents: HashMap<ParentHandle, ParentCaContact>,
-        resource_classes: HashMap<ResourceClassName, ResourceClassInfo>,
-        children: Vec<ChildHandle>,
-        suspended_children: Vec<ChildHandle>,
-    ) -> Self {
-        let parents = parents.into_keys().map(ParentInfo::new).collect();
+//------------ ExpiryDistribution --------------------------------------------
 
-        let empty = ResourceSet::default();
-        let resources = resource_classes.values().fold(ResourceSet::default(), |res, rci| {
-            let rc_resources = rci.current_resources().unwrap_or(&empty);
-            res.union(rc_resources)
-        });
+/// The distribution of the hours until objects expire, as a cumulative
+/// histogram: every bucket counts the objects which expire within its upper
+/// bound of hours. This shows whether re-issuance is spread out over time,
+/// or whether many objects will be re-issued at the same time.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpiryDistribution {
+    buckets: Vec<(i64, usize)>,
+    count: usize,
+    sum_hours: i64,
+}
 
-        CertAuthInfo {
-            handle,
-            id_cert,
-            repo_info,
-            parents,
-            resources,
-            resource_classes,
-            children,
-            suspended_children,
+impl ExpiryDistribution {
+    fn new(bounds_hours: &[i64]) -> Self {
+        ExpiryDistribution {
+            buckets: bounds_hours.iter().map(|bound| (*bound, 0)).collect(),
+            count: 0,
+            sum_hours: 0,
         }
     }
 
-    pub fn handle(&self) -> &CaHandle {
-        &self.handle
+    fn add(&mut self, hours: i64) {
+        for (bound, count) in self.buckets.iter_mut() {
+            if hours <= *bound {
+                *count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum_hours += hours;
     }
 
-    pub fn id_cert(&self) -> &IdCertInfo {
-        &self.id_cert
+    /// The upper bound in hours and the number of objects for each bucket.
+    pub fn buckets(&self) -> &[(i64, usize)] {
+        &self.buckets
     }
 
-    pub fn repo_info(&self) -> Option<&RepoInfo> {
-        self.repo_info.as_ref()
+    pub fn count(&self) -> usize {
+        self.count
     }
 
-    pub fn parents(&self) -> &Vec<ParentInfo> {
-        &self.parents
+    pub fn sum_hours(&self) -> i64 {
+        self.sum_hours
+    }
+}
+
+/// The type of a published object, derived from its file name extension.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectExpiryType {
+    Manifest,
+    Crl,
+    Roa,
+    Aspa,
+    Certificate,
+    Other,
+}
+
+impl ObjectExpiryType {
+    pub fn for_uri(uri: &uri::Rsync) -> Self {
+        if uri.ends_with(".mft") {
+            ObjectExpiryType::Manifest
+        } else if uri.ends_with(".crl") {
+            ObjectExpiryType::Crl
+        } else if uri.ends_with(".roa") {
+            ObjectExpiryType::Roa
+        } else if uri.ends_with(".asa") {
+            ObjectExpiryType::Aspa
+        } else if uri.ends_with(".cer") {
+            ObjectExpiryType::Certificate
+        } else {
+            ObjectExpiryType::Other
+        }
+    }
+}
+
+impl fmt::Display for ObjectExpiryType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjectExpiryType::Manifest => write!(f, "manifest"),
+            ObjectExpiryType::Crl => write!(f, "crl"),
+            ObjectExpiryType::Roa => write!(f, "roa"),
+            ObjectExpiryType::Aspa => write!(f, "aspa"),
+            ObjectExpiryType::Certificate => write!(f, "certificate"),
+            ObjectExpiryType::Other => write!(f, "other"),
+        }
+    }
+}
+
+//------------ PublicCaStatus ------------------------------------------------
+
+/// The read-only status of a CA which may be shown without credentials: the
+/// objects it publishes with their expiry times, and the health of the
+/// connection to its repository.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublicCaStatus {
+    ca: CaHandle,
+    repo_healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    repo_last_exchange: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    repo_last_success: Option<Timestamp>,
+    objects: ObjectsExpiry,
+}
+
+impl PublicCaStatus {
+    pub fn new(ca: CaHandle, repo: &RepoStatus, objects: ObjectsExpiry) -> Self {
+        PublicCaStatus {
+            ca,
+            repo_healthy: repo.last_exchange().map(|e| e.was_success()).unwrap_or(false),
+            repo_last_exchange: repo.last_exchange().map(|e| e.timestamp()),
+            repo_last_success: repo.last_success(),
+            objects,
+        }
+    }
+
+    pub fn ca(&self) -> &CaHandle {
+        &self.ca
+    }
+
+    pub fn repo_healthy(&self) -> bool {
+        self.repo_healthy
+    }
+
+    pub fn repo_last_exchange(&self) -> Option<Timestamp> {
+        self.repo_last_exchange
+    }
+
+    pub fn repo_last_success(&self) -> Option<Timestamp> {
+        self.repo_last_success
+    }
+
+    pub fn objects(&self) -> &ObjectsExpiry {
+        &self.objects
+    }
+}
+
+impl fmt::Display for PublicCaStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "CA: {}", self.ca)?;
+        writeln!(
+            f,
+            "Repository: {}",
+            if self.repo_healthy { "healthy" } else { "not healthy" }
+        )?;
+        if let Some(timestamp) = self.repo_last_exchange {
+            writeln!(f, "Last contact: {}", timestamp.to_rfc3339())?;
+        }
+        if let Some(timestamp) = self.repo_last_success {
+            writeln!(f, "Last success: {}", timestamp.to_rfc3339())?;
+        }
+        writeln!(f)?;
+        self.objects.fmt(f)
+    }
+}
+
+//------------ SignerMigrationStatus -----------------------------------------
+
+/// Shows which signers hold the keys of a CA, when moving its keys to the
+/// signer which is used for new keys, i.e. the default signer.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SignerMigrationStatus {
+    target_signer: String,
+    keys: Vec<SignerMigrationKey>,
+}
+
+impl SignerMigrationStatus {
+    pub fn new(target_signer: String, keys: Vec<SignerMigrationKey>) -> Self {
+        SignerMigrationStatus { target_signer, keys }
+    }
+
+    pub fn target_signer(&self) -> &str {
+        &self.target_signer
+    }
+
+    pub fn keys(&self) -> &Vec<SignerMigrationKey> {
+        &self.keys
+    }
+
+    /// Returns true if all keys are held by the target signer.
+    pub fn is_complete(&self) -> bool {
+        self.keys
+            .iter()
+            .all(|key| key.signer.as_deref() == Some(self.target_signer.as_str()))
+    }
+}
+
+impl fmt::Display for SignerMigrationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Target signer: {}", self.target_signer)?;
+        writeln!(f, "Complete: {}", self.is_complete())?;
+        writeln!(f)?;
+        for key in &self.keys {
+            writeln!(
+                f,
+                "{} {:<7} {} {}",
+                key.resource_class_name,
+                key.role,
+                key.key_id,
+                key.signer.as_deref().unwrap_or("<unknown>")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SignerMigrationKey {
+    resource_class_name: ResourceClassName,
+    role: KeyRole,
+    key_id: KeyIdentifier,
+    signer: Option<String>,
+}
+
+impl SignerMigrationKey {
+    pub fn new(
+        resource_class_name: ResourceClassName,
+        role: KeyRole,
+        key_id: KeyIdentifier,
+        signer: Option<String>,
+    ) -> Self {
+        SignerMigrationKey {
+            resource_class_name,
+            role,
+            key_id,
+            signer,
+        }
+    }
+
+    pub fn resource_class_name(&self) -> &ResourceClassName {
+        &self.resource_class_name
+    }
+
+    pub fn role(&self) -> KeyRole {
+        self.role
+    }
+
+    pub fn key_id(&self) -> KeyIdentifier {
+        self.key_id
+    }
+
+    /// The name of the signer which holds the key, if known.
+    pub fn signer(&self) -> Option<&str> {
+        self.signer.as_deref()
+    }
+}
+
+/// The role of a key in the life cycle of a resource class.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyRole {
+    Pending,
+    New,
+    Current,
+    Old,
+}
+
+impl fmt::Display for KeyRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyRole::Pending => write!(f, "pending"),
+            KeyRole::New => write!(f, "new"),
+            KeyRole::Current => write!(f, "current"),
+            KeyRole::Old => write!(f, "old"),
+        }
+    }
+}
+
+//------------ RepoMigrationStatus -------------------------------------------
+
+/// Shows the progress of moving a CA, or some of its resource classes, to
+/// another repository.
+///
+/// The move is done by a key roll. The new key publishes to the new
+/// repository, while the current key keeps publishing to the old one. Once
+/// the new repository has been verified, the new key is activated so that
+/// the objects of the CA are found in the new repository. The objects in
+/// the old repository are withdrawn when the key roll is finished.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepoMigrationStatus {
+    classes: Vec<RepoMigrationClass>,
+    deprecated_repos: Vec<ServiceUri>,
+}
+
+impl RepoMigrationStatus {
+    pub fn new(classes: Vec<RepoMigrationClass>, deprecated_repos: Vec<ServiceUri>) -> Self {
+        RepoMigrationStatus {
+            classes,
+            deprecated_repos,
+        }
+    }
+
+    pub fn classes(&self) -> &Vec<RepoMigrationClass> {
+        &self.classes
+    }
+
+    /// The repositories which are no longer used, and which still need to
+    /// be cleaned.
+    pub fn deprecated_repos(&self) -> &Vec<ServiceUri> {
+        &self.deprecated_repos
+    }
+
+    /// Returns true if the new key of some resource class is published in
+    /// the new repository, and can be activated.
+    pub fn is_ready_to_activate(&self) -> bool {
+        self.classes
+            .iter()
+            .any(|class| class.stage == RepoMigrationStage::Staged)
+    }
+
+    /// Returns true if no resource class is migrating, and all old
+    /// repositories have been cleaned.
+    pub fn is_complete(&self) -> bool {
+        self.classes.is_empty() && self.deprecated_repos.is_empty()
+    }
+}
+
+impl fmt::Display for RepoMigrationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_complete() {
+            return writeln!(f, "No repository migration in progress.");
+        }
+
+        for class in &self.classes {
+            writeln!(
+                f,
+                "Resource class '{}': {}, from {} to {}",
+                class.resource_class_name,
+                class.stage,
+                class.old_repo.base_uri(),
+                class.repo.base_uri()
+            )?;
+        }
+        for repo in &self.deprecated_repos {
+            writeln!(f, "Old repository still to be cleaned: {}", repo)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepoMigrationClass {
+    resource_class_name: ResourceClassName,
+    stage: RepoMigrationStage,
+    old_repo: RepoInfo,
+    repo: RepoInfo,
+}
+
+impl RepoMigrationClass {
+    pub fn new(
+        resource_class_name: ResourceClassName,
+        stage: RepoMigrationStage,
+        old_repo: RepoInfo,
+        repo: RepoInfo,
+    ) -> Self {
+        RepoMigrationClass {
+            resource_class_name,
+            stage,
+            old_repo,
+            repo,
+        }
+    }
+
+    pub fn resource_class_name(&self) -> &ResourceClassName {
+        &self.resource_class_name
+    }
+
+    pub fn stage(&self) -> RepoMigrationStage {
+        self.stage
+    }
+
+    pub fn old_repo(&self) -> &RepoInfo {
+        &self.old_repo
+    }
+
+    pub fn repo(&self) -> &RepoInfo {
+        &self.repo
+    }
+}
+
+/// The stage of the migration of a resource class to another repository.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepoMigrationStage {
+    /// A certificate for the new key was requested from the parent.
+    Requested,
+
+    /// The new key is published in the new repository, and the current
+    /// key in the old repository.
+    Staged,
+
+    /// The new key is active. The objects in the old repository will be
+    /// withdrawn once the parent has revoked the old key.
+    Activated,
+}
+
+impl fmt::Display for RepoMigrationStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepoMigrationStage::Requested => write!(f, "waiting for certificate of new key"),
+            RepoMigrationStage::Staged => write!(f, "publishing in both repositories, ready to activate"),
+            RepoMigrationStage::Activated => write!(f, "activated, withdrawing from old repository"),
+        }
+    }
+}
+
+//------------ RepoMigrationReport -------------------------------------------
+
+/// Reports the steps taken by the repository migration wizard, and the
+/// resulting progress of the migration.
+///
+/// The wizard can be run again with the same repository to resume the
+/// migration. It stops at the first step which cannot be completed yet, or
+/// which failed. Until the new repository is verified the CA is found in
+/// its old repository, so stopping the wizard at any point is safe.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepoMigrationReport {
+    steps: Vec<RepoMigrationStep>,
+    status: RepoMigrationStatus,
+}
+
+impl RepoMigrationReport {
+    pub fn new(steps: Vec<RepoMigrationStep>, status: RepoMigrationStatus) -> Self {
+        RepoMigrationReport { steps, status }
+    }
+
+    pub fn steps(&self) -> &Vec<RepoMigrationStep> {
+        &self.steps
+    }
+
+    pub fn status(&self) -> &RepoMigrationStatus {
+        &self.status
+    }
+
+    /// Returns true if any step failed. The migration was stopped before the
+    /// CA was moved to the new repository.
+    pub fn is_aborted(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|step| matches!(step.result, RepoMigrationStepResult::Failed { .. }))
+    }
+}
+
+impl fmt::Display for RepoMigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        writeln!(f)?;
+        if self.is_aborted() {
+            writeln!(f, "Migration aborted, the CA still uses its old repository.")?;
+        } else if self.status.is_complete() {
+            writeln!(f, "Migration complete.")?;
+        } else {
+            writeln!(f, "Migration in progress, run this command again to continue.")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepoMigrationStep {
+    description: String,
+    result: RepoMigrationStepResult,
+}
+
+impl RepoMigrationStep {
+    pub fn done(description: impl fmt::Display) -> Self {
+        RepoMigrationStep {
+            description: description.to_string(),
+            result: RepoMigrationStepResult::Done,
+        }
+    }
+
+    pub fn waiting(description: impl fmt::Display, reason: impl fmt::Display) -> Self {
+        RepoMigrationStep {
+            description: description.to_string(),
+            result: RepoMigrationStepResult::Waiting {
+                reason: reason.to_string(),
+            },
+        }
+    }
+
+    pub fn failed(description: impl fmt::Display, reason: impl fmt::Display) -> Self {
+        RepoMigrationStep {
+            description: description.to_string(),
+            result: RepoMigrationStepResult::Failed {
+                reason: reason.to_string(),
+            },
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn result(&self) -> &RepoMigrationStepResult {
+        &self.result
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.result == RepoMigrationStepResult::Done
+    }
+}
+
+impl fmt::Display for RepoMigrationStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.result {
+            RepoMigrationStepResult::Done => write!(f, "[done]    {}", self.description),
+            RepoMigrationStepResult::Waiting { reason } => write!(f, "[waiting] {}: {}", self.description, reason),
+            RepoMigrationStepResult::Failed { reason } => write!(f, "[failed]  {}: {}", self.description, reason),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "result")]
+pub enum RepoMigrationStepResult {
+    Done,
+    Waiting { reason: String },
+    Failed { reason: String },
+}
+
+//------------ ParentExchange ------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentExchange {
+    timestamp: Timestamp,
+    uri: ServiceUri,
+    result: ExchangeResult,
+}
+
+impl ParentExchange {
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    pub fn uri(&self) -> &ServiceUri {
+        &self.uri
+    }
+
+    pub fn result(&self) -> &ExchangeResult {
+        &self.result
+    }
+
+    pub fn was_success(&self) -> bool {
+        self.result.was_success()
+    }
+
+    pub fn to_failure_opt(&self) -> Option<ErrorResponse> {
+        match &self.result {
+            ExchangeResult::Success => None,
+            ExchangeResult::Failure(error) => Some(error.clone()),
+        }
+    }
+}
+
+//------------ ExchangeResult ------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum ExchangeResult {
+    Success,
+    Failure(ErrorResponse),
+}
+
+impl ExchangeResult {
+    pub fn was_success(&self) -> bool {
+        match self {
+            ExchangeResult::Success => true,
+            ExchangeResult::Failure(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ExchangeResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExchangeResult::Success => write!(f, "success"),
+            ExchangeResult::Failure(e) => write!(f, "failure: {}", e.msg()),
+        }
+    }
+}
+
+//------------ ParentExchangeLog ---------------------------------------------
+
+/// The most recent RFC 6492 exchanges of a CA with one of its parents, oldest
+/// first, to help diagnose issues with the parent.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentExchangeLog(Vec<ParentExchangeRecord>);
+
+impl ParentExchangeLog {
+    /// Adds a record, and drops the oldest records if there are more than
+    /// the given number of records.
+    pub fn add(&mut self, record: ParentExchangeRecord, max: usize) {
+        self.0.push(record);
+        if self.0.len() > max {
+            let excess = self.0.len() - max;
+            self.0.drain(..excess);
+        }
+    }
+
+    pub fn records(&self) -> &Vec<ParentExchangeRecord> {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for ParentExchangeLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            writeln!(f, "No exchanges with this parent were recorded.")
+        } else {
+            for record in &self.0 {
+                write!(f, "{}", record)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ ParentExchangeRecord ------------------------------------------
+
+/// A summary of a single RFC 6492 request to a parent, and its response.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentExchangeRecord {
+    timestamp: Timestamp,
+    uri: ServiceUri,
+
+    /// The type of the request, e.g. "list" or "issue".
+    request: String,
+
+    /// The type of the response, if a valid response was received.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    response: Option<String>,
+
+    /// The HTTP status code of the response, if the parent was contacted
+    /// over HTTP and responded.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    http_status: Option<u16>,
+
+    cms: CmsValidation,
+    duration_ms: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    error: Option<String>,
+}
+
+impl ParentExchangeRecord {
+    pub fn new(
+        uri: ServiceUri,
+        request: String,
+        response: Option<String>,
+        http_status: Option<u16>,
+        cms: CmsValidation,
+        duration_ms: u64,
+        error: Option<String>,
+    ) -> Self {
+        ParentExchangeRecord {
+            timestamp: Timestamp::now(),
+            uri,
+            request,
+            response,
+            http_status,
+            cms,
+            duration_ms,
+            error,
+        }
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    pub fn uri(&self) -> &ServiceUri {
+        &self.uri
+    }
+
+    pub fn request(&self) -> &str {
+        &self.request
+    }
+
+    pub fn response(&self) -> Option<&String> {
+        self.response.as_ref()
+    }
+
+    pub fn http_status(&self) -> Option<u16> {
+        self.http_status
+    }
+
+    pub fn cms(&self) -> &CmsValidation {
+        &self.cms
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        self.duration_ms
+    }
+
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+}
+
+impl fmt::Display for ParentExchangeRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} -> {}",
+            self.timestamp.to_rfc3339(),
+            self.request,
+            self.response.as_deref().unwrap_or("no response")
+        )?;
+        if let Some(status) = self.http_status {
+            write!(f, ", HTTP {}", status)?;
+        }
+        writeln!(f, ", CMS {}, {} ms", self.cms, self.duration_ms)?;
+        writeln!(f, "  URI: {}", self.uri)?;
+        if let Some(error) = &self.error {
+            writeln!(f, "  Error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ CmsValidation -------------------------------------------------
+
+/// The outcome of decoding and validating the CMS of a response from a
+/// parent.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "result", content = "reason")]
+pub enum CmsValidation {
+    /// The response was decoded and its signature is valid.
+    Valid,
+
+    /// The response could not be decoded.
+    Undecodable(String),
+
+    /// The response was decoded but it is not validly signed by the parent.
+    Invalid(String),
+
+    /// No response was received.
+    NoResponse,
+
+    /// The parent is a CA in this Krill instance, no CMS is exchanged.
+    Local,
+}
+
+impl fmt::Display for CmsValidation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CmsValidation::Valid => write!(f, "valid"),
+            CmsValidation::Undecodable(reason) => write!(f, "cannot be decoded: {}", reason),
+            CmsValidation::Invalid(reason) => write!(f, "invalid: {}", reason),
+            CmsValidation::NoResponse => write!(f, "not received"),
+            CmsValidation::Local => write!(f, "not used (local parent)"),
+        }
+    }
+}
+
+//------------ ChildConnectionStats ------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildrenConnectionStats {
+    children: Vec<ChildConnectionStats>,
+}
+
+impl ChildrenConnectionStats {
+    pub fn new(children: Vec<ChildConnectionStats>) -> Self {
+        ChildrenConnectionStats { children }
+    }
+
+    pub fn suspension_candidates(&self, threshold_seconds: i64) -> Vec<ChildHandle> {
+        self.children
+            .iter()
+            .filter(|child| child.is_suspension_candidate(threshold_seconds))
+            .map(|child| child.handle.clone())
+            .collect()
+    }
+}
+
+impl fmt::Display for ChildrenConnectionStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.children.is_empty() {
+            writeln!(f, "handle,user_agent,last_exchange,result,state")?;
+            for child in &self.children {
+                match &child.last_exchange {
+                    None => {
+                        writeln!(f, "{},n/a,never,n/a,{}", child.handle, child.state)?;
+                    }
+                    Some(exchange) => {
+                        let agent = exchange.user_agent.as_deref().unwrap_or("");
+
+                        writeln!(
+                            f,
+                            "{},{},{},{},{}",
+                            child.handle,
+                            agent,
+                            exchange.timestamp.to_rfc3339(),
+                            exchange.result,
+                            child.state
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildConnectionStats {
+    handle: ChildHandle,
+    last_exchange: Option<ChildExchange>,
+    state: ChildState,
+}
+
+impl ChildConnectionStats {
+    pub fn new(handle: ChildHandle, last_exchange: Option<ChildExchange>, state: ChildState) -> Self {
+        ChildConnectionStats {
+            handle,
+            last_exchange,
+            state,
+        }
+    }
+
+    /// The child is considered a candidate for suspension if:
+    ///  - it is Krill 0.9.2-rc and up (see #670)
+    ///  - the last exchange is longer ago than the specified threshold hours
+    ///  - and the child is not already suspended
+    pub fn is_suspension_candidate(&self, threshold_seconds: i64) -> bool {
+        if self.state == ChildState::Suspended {
+            false
+        } else {
+            self.last_exchange
+                .as_ref()
+                .map(|exchange| exchange.is_krill_above_0_9_1() && exchange.more_than_seconds_ago(threshold_seconds))
+                .unwrap_or(false)
+        }
+    }
+}
+
+//------------ ChildrenStats -------------------------------------------------
+
+/// A consolidated report on all children of a CA, to help spot children
+/// which are stale or misconfigured.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildrenStats {
+    children: Vec<ChildStats>,
+}
+
+impl ChildrenStats {
+    pub fn new(children: Vec<ChildStats>) -> Self {
+        ChildrenStats { children }
+    }
+
+    pub fn children(&self) -> &Vec<ChildStats> {
+        &self.children
+    }
+}
+
+impl fmt::Display for ChildrenStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            writeln!(f, "Child: {}", child.handle)?;
+            writeln!(f, "  State: {}", child.state)?;
+            writeln!(f, "  Entitled resources: {}", child.entitled_resources)?;
+            writeln!(f, "  Certified resources: {}", child.certified_resources)?;
+            if !child.uncertified_resources.is_empty() {
+                writeln!(f, "  Entitled, but not certified: {}", child.uncertified_resources)?;
+            }
+            match &child.last_exchange {
+                None => writeln!(f, "  Last contact: never")?,
+                Some(exchange) => {
+                    writeln!(f, "  Last contact: {}", exchange.timestamp.to_rfc3339())?;
+                    writeln!(f, "  Last result: {}", exchange.result)?;
+                    writeln!(f, "  User agent: {}", exchange.user_agent.as_deref().unwrap_or("n/a"))?;
+                }
+            }
+            if !child.outstanding_revocations.is_empty() {
+                writeln!(f, "  Keys which should be revoked:")?;
+                for ki in &child.outstanding_revocations {
+                    writeln!(f, "    {}", ki)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The resources entitled to, and certified for, a child together with
+/// its last RFC 6492 exchange.
+///
+/// The outstanding revocations are the keys for which the child holds a
+/// certificate, while it also holds a certificate for a newer key in the
+/// same resource class. Normally the child revokes the old key as the last
+/// step of a key roll. If this does not happen, then the child may be stuck
+/// in a key roll.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildStats {
+    handle: ChildHandle,
+    state: ChildState,
+    entitled_resources: ResourceSet,
+    certified_resources: ResourceSet,
+    uncertified_resources: ResourceSet,
+    last_exchange: Option<ChildExchange>,
+    outstanding_revocations: Vec<KeyIdentifier>,
+}
+
+impl ChildStats {
+    pub fn new(
+        handle: ChildHandle,
+        state: ChildState,
+        entitled_resources: ResourceSet,
+        certified_resources: ResourceSet,
+        last_exchange: Option<ChildExchange>,
+        outstanding_revocations: Vec<KeyIdentifier>,
+    ) -> Self {
+        let uncertified_resources = ResourceSet::new(
+            entitled_resources.asn().difference(certified_resources.asn()),
+            entitled_resources.ipv4().difference(certified_resources.ipv4()).into(),
+            entitled_resources.ipv6().difference(certified_resources.ipv6()).into(),
+        );
+        ChildStats {
+            handle,
+            state,
+            entitled_resources,
+            certified_resources,
+            uncertified_resources,
+            last_exchange,
+            outstanding_revocations,
+        }
+    }
+
+    pub fn handle(&self) -> &ChildHandle {
+        &self.handle
+    }
+
+    pub fn state(&self) -> ChildState {
+        self.state
+    }
+
+    pub fn entitled_resources(&self) -> &ResourceSet {
+        &self.entitled_resources
+    }
+
+    pub fn certified_resources(&self) -> &ResourceSet {
+        &self.certified_resources
+    }
+
+    pub fn uncertified_resources(&self) -> &ResourceSet {
+        &self.uncertified_resources
+    }
+
+    pub fn last_exchange(&self) -> Option<&ChildExchange> {
+        self.last_exchange.as_ref()
+    }
+
+    pub fn outstanding_revocations(&self) -> &Vec<KeyIdentifier> {
+        &self.outstanding_revocations
+    }
+}
+
+//------------ ChildStatus ---------------------------------------------------
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildStatus {
+    last_exchange: Option<ChildExchange>,
+    last_success: Option<Timestamp>,
+    suspended: Option<Timestamp>,
+}
+
+impl ChildStatus {
+    pub fn set_success(&mut self, user_agent: Option<String>) {
+        let timestamp = Timestamp::now();
+        self.last_exchange = Some(ChildExchange {
+            result: ExchangeResult::Success,
+            timestamp,
+            user_agent,
+        });
+        self.last_success = Some(timestamp);
+        self.suspended = None;
+    }
+
+    pub fn set_failure(&mut self, user_agent: Option<String>, error_response: ErrorResponse) {
+        self.last_exchange = Some(ChildExchange {
+            timestamp: Timestamp::now(),
+            result: ExchangeResult::Failure(error_response),
+            user_agent,
+        });
+        self.suspended = None;
+    }
+
+    pub fn set_suspended(&mut self) {
+        self.suspended = Some(Timestamp::now())
+    }
+
+    pub fn last_exchange(&self) -> Option<&ChildExchange> {
+        self.last_exchange.as_ref()
+    }
+
+    pub fn last_success(&self) -> Option<Timestamp> {
+        self.last_success
+    }
+
+    pub fn suspended(&self) -> Option<Timestamp> {
+        self.suspended
+    }
+
+    pub fn child_state(&self) -> ChildState {
+        if self.suspended.is_none() {
+            ChildState::Active
+        } else {
+            ChildState::Suspended
+        }
+    }
+}
+
+impl From<ChildStatus> for Option<ChildExchange> {
+    fn from(status: ChildStatus) -> Self {
+        status.last_exchange
+    }
+}
+
+//------------ ChildExchange -------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildExchange {
+    timestamp: Timestamp,
+    result: ExchangeResult,
+    user_agent: Option<String>,
+}
+
+impl ChildExchange {
+    pub fn was_success(&self) -> bool {
+        self.result.was_success()
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    pub fn user_agent(&self) -> Option<&String> {
+        self.user_agent.as_ref()
+    }
+
+    pub fn more_than_seconds_ago(&self, seconds: i64) -> bool {
+        self.timestamp < Timestamp::now_minus_seconds(seconds)
+    }
+
+    pub fn is_krill_above_0_9_1(&self) -> bool {
+        if let Some(agent) = &self.user_agent {
+            // local-child is used by local children, it is extremely
+            // unlikely that they would become suspend candidates in
+            // the real world - but.. we have to use these to test the
+            // auto-suspend logic in the high-level "suspend.rs" test
+            if agent == "local-child" {
+                return true;
+            } else if let Some(version) = agent.strip_prefix("krill/") {
+                if let Ok(krill_version) = KrillVersion::from_str(version) {
+                    return krill_version > KrillVersion::release(0, 9, 1);
+                }
+            }
+        }
+        false
+    }
+}
+
+//------------ Timestamp -----------------------------------------------------
+
+/// A wrapper for unix timestamps with second precision, with some convenient stuff.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn new(ts: i64) -> Self {
+        Timestamp(ts)
+    }
+
+    pub fn now() -> Self {
+        Timestamp(Time::now().timestamp())
+    }
+
+    pub fn now_plus_hours(hours: i64) -> Self {
+        Timestamp::now().plus_hours(hours)
+    }
+
+    pub fn plus_hours(self, hours: i64) -> Self {
+        self + Duration::hours(hours)
+    }
+
+    pub fn now_minus_hours(hours: i64) -> Self {
+        Timestamp::now().minus_hours(hours)
+    }
+
+    pub fn minus_hours(self, hours: i64) -> Self {
+        self - Duration::hours(hours)
+    }
+
+    pub fn now_plus_minutes(minutes: i64) -> Self {
+        Timestamp::now().plus_minutes(minutes)
+    }
+
+    pub fn plus_minutes(self, minutes: i64) -> Self {
+        self + Duration::minutes(minutes)
+    }
+
+    pub fn minus_seconds(self, seconds: i64) -> Self {
+        self - Duration::seconds(seconds)
+    }
+
+    pub fn plus_seconds(self, seconds: i64) -> Self {
+        self + Duration::seconds(seconds)
+    }
+
+    pub fn now_minus_seconds(seconds: i64) -> Self {
+        Timestamp::now().minus_seconds(seconds)
+    }
+
+    pub fn now_plus_seconds(seconds: i64) -> Self {
+        Timestamp::now().plus_seconds(seconds)
+    }
+
+    pub fn to_rfc3339(self) -> String {
+        Time::from(self).to_rfc3339()
+    }
+
+    /// Returns the time for this timestamp, or None if it is beyond the
+    /// range of times that can be represented.
+    pub fn to_time(self) -> Option<Time> {
+        Utc.timestamp_opt(self.0, 0).single().map(Time::new)
+    }
+}
+
+impl From<Timestamp> for Time {
+    /// Timestamps beyond the range of times that can be represented are
+    /// converted to the earliest or latest time, rather than panicking.
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_time().unwrap_or_else(|| {
+            if timestamp.0 < 0 {
+                Time::new(DateTime::<Utc>::MIN_UTC)
+            } else {
+                Time::new(DateTime::<Utc>::MAX_UTC)
+            }
+        })
+    }
+}
+
+impl From<Time> for Timestamp {
+    fn from(time: Time) -> Self {
+        Timestamp(time.timestamp())
+    }
+}
+
+impl From<Timestamp> for i64 {
+    fn from(t: Timestamp) -> Self {
+        t.0
+    }
+}
+
+//--- Display
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+//--- Add
+
+impl ops::Add<Duration> for Timestamp {
+    type Output = Self;
+
+    fn add(self, duration: Duration) -> Self::Output {
+        Timestamp(self.0 + duration.num_seconds())
+    }
+}
+
+impl ops::AddAssign<Duration> for Timestamp {
+    fn add_assign(&mut self, duration: Duration) {
+        self.0 += duration.num_seconds();
+    }
+}
+
+//--- Sub
+
+impl ops::Sub<Duration> for Timestamp {
+    type Output = Self;
+
+    fn sub(self, duration: Duration) -> Self::Output {
+        Timestamp(self.0 - duration.num_seconds())
+    }
+}
+
+impl ops::SubAssign<Duration> for Timestamp {
+    fn sub_assign(&mut self, duration: Duration) {
+        self.0 -= duration.num_seconds()
+    }
+}
+
+//------------ CertAuthInfo --------------------------------------------------
+
+/// This type represents the details of a CertAuth that need
+/// to be exposed through the API/CLI/UI
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CertAuthInfo {
+    handle: CaHandle,
+    id_cert: IdCertInfo,
+    repo_info: Option<RepoInfo>,
+    parents: Vec<ParentInfo>,
+    resources: ResourceSet,
+    resource_classes: HashMap<ResourceClassName, ResourceClassInfo>,
+    children: Vec<ChildHandle>,
+    suspended_children: Vec<ChildHandle>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    scheduled_changes: Vec<ScheduledChange>,
+}
+
+impl CertAuthInfo {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        handle: CaHandle,
+        id_cert: IdCertInfo,
+        repo_info: Option<RepoInfo>,
+        parents: HashMap<ParentHandle, ParentCaContact>,
+        resource_classes: HashMap<ResourceClassName, ResourceClassInfo>,
+        children: Vec<ChildHandle>,
+        suspended_children: Vec<ChildHandle>,
+        scheduled_changes: Vec<ScheduledChange>,
+    ) -> Self {
+        let parents = parents.into_keys().map(ParentInfo::new).collect();
+
+        let empty = ResourceSet::default();
+        let resources = resource_classes.values().fold(ResourceSet::default(), |res, rci| {
+            let rc_resources = rci.current_resources().unwrap_or(&empty);
+            res.union(rc_resources)
+        });
+
+        CertAuthInfo {
+            handle,
+            id_cert,
+            repo_info,
+            parents,
+            resources,
+            resource_classes,
+            children,
+            suspended_children,
+            scheduled_changes,
+        }
+    }
+
+    pub fn handle(&self) -> &CaHandle {
+        &self.handle
+    }
+
+    pub fn id_cert(&self) -> &IdCertInfo {
+        &self.id_cert
+    }
+
+    pub fn repo_info(&self) -> Option<&RepoInfo> {
+        self.repo_info.as_ref()
+    }
+
+    pub fn parents(&self) -> &Vec<ParentInfo> {
+        &self.parents
     }
 
     pub fn resources(&self) -> &ResourceSet {
         &self.resources
     }
 
-    pub fn resource_classes(&self) -> &HashMap<ResourceClassName, ResourceClassInfo> {
-        &self.resource_classes
+    pub fn resource_classes(&self) -> &HashMap<ResourceClassName, ResourceClassInfo> {
+        &self.resource_classes
+    }
+
+    pub fn children(&self) -> &Vec<ChildHandle> {
+        &self.children
+    }
+
+    pub fn suspended_children(&self) -> &Vec<ChildHandle> {
+        &self.suspended_children
+    }
+
+    pub fn scheduled_changes(&self) -> &Vec<ScheduledChange> {
+        &self.scheduled_changes
+    }
+}
+
+impl fmt::Display for CertAuthInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Name:     {}", self.handle())?;
+        writeln!(f)?;
+
+        if let Some(repo_info) = self.repo_info() {
+            let base_uri = repo_info.base_uri();
+            let rrdp_uri = repo_info.rpki_notify().map(|uri| uri.as_str()).unwrap_or("<none>");
+
+            writeln!(f, "Base uri: {}", base_uri)?;
+            writeln!(f, "RRDP uri: {}", rrdp_uri)?;
+        } else {
+            writeln!(f, "No repository configured.")?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "ID cert PEM:\n{}", self.id_cert().pem())?;
+        writeln!(f, "Hash: {}", self.id_cert().hash())?;
+        writeln!(f)?;
+
+        let resources = self.resources();
+        if resources.is_empty() {
+            writeln!(f, "Total resources: <none>")?;
+        } else {
+            writeln!(f, "Total resources:")?;
+            writeln!(f, "    ASNs: {}", resources.asn())?;
+            writeln!(f, "    IPv4: {}", resources.ipv4())?;
+            writeln!(f, "    IPv6: {}", resources.ipv6())?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Parents:")?;
+        if !self.parents().is_empty() {
+            for parent in self.parents().iter() {
+                writeln!(f, "{}", parent)?;
+            }
+            writeln!(f)?;
+        } else {
+            writeln!(f, "<none>")?;
+        }
+
+        for (name, rc) in self.resource_classes() {
+            writeln!(f, "Resource Class: {}", name,)?;
+            writeln!(f, "Parent: {}", rc.parent_handle())?;
+            writeln!(f, "{}", rc.keys())?;
+        }
+
+        writeln!(f, "Children:")?;
+        if !self.children().is_empty() {
+            for child_handle in self.children() {
+                writeln!(f, "{}", child_handle)?;
+            }
+        } else {
+            writeln!(f, "<none>")?;
+        }
+
+        if !self.scheduled_changes().is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Scheduled changes:")?;
+            for change in self.scheduled_changes() {
+                write!(f, "{}", change)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ KeyStateInfo -------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResourceClassInfo {
+    name_space: String,
+    parent_handle: ParentHandle,
+    keys: ResourceClassKeysInfo,
+}
+
+impl ResourceClassInfo {
+    pub fn new(name_space: String, parent_handle: ParentHandle, keys: ResourceClassKeysInfo) -> Self {
+        ResourceClassInfo {
+            name_space,
+            parent_handle,
+            keys,
+        }
+    }
+
+    pub fn name_space(&self) -> &str {
+        &self.name_space
+    }
+    pub fn parent_handle(&self) -> &ParentHandle {
+        &self.parent_handle
+    }
+    pub fn keys(&self) -> &ResourceClassKeysInfo {
+        &self.keys
+    }
+
+    pub fn current_key(&self) -> Option<&CertifiedKeyInfo> {
+        self.keys.current_key()
+    }
+
+    pub fn new_key(&self) -> Option<&CertifiedKeyInfo> {
+        self.keys.new_key()
+    }
+
+    pub fn current_resources(&self) -> Option<&ResourceSet> {
+        self.current_key().map(|k| k.incoming_cert().resources())
+    }
+}
+
+//------------ ResourceClassKeysInfo -----------------------------------------
+
+/// Contains the current key status for a resource class.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[allow(clippy::large_enum_variant)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceClassKeysInfo {
+    Pending(PendingInfo),
+    Active(ActiveInfo),
+    RollPending(RollPendingInfo),
+    RollNew(RollNewInfo),
+    RollOld(RollOldInfo),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PendingInfo {
+    #[serde(rename = "pending_key")]
+    pub _pending_key: PendingKeyInfo,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ActiveInfo {
+    #[serde(rename = "active_key")]
+    pub _active_key: CertifiedKeyInfo,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RollPendingInfo {
+    #[serde(rename = "pending_key")]
+    pub _pending_key: PendingKeyInfo,
+    #[serde(rename = "active_key")]
+    pub _active_key: CertifiedKeyInfo,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RollNewInfo {
+    #[serde(rename = "new_key")]
+    pub _new_key: CertifiedKeyInfo,
+    #[serde(rename = "active_key")]
+    pub _active_key: CertifiedKeyInfo,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RollOldInfo {
+    #[serde(rename = "active_key")]
+    pub _active_key: CertifiedKeyInfo,
+    #[serde(rename = "old_key")]
+    pub _old_key: CertifiedKeyInfo,
+}
+
+impl ResourceClassKeysInfo {
+    pub fn current_key(&self) -> Option<&CertifiedKeyInfo> {
+        match &self {
+            ResourceClassKeysInfo::Active(current) => Some(&current._active_key),
+            ResourceClassKeysInfo::RollPending(pending) => Some(&pending._active_key),
+            ResourceClassKeysInfo::RollNew(new) => Some(&new._active_key),
+            ResourceClassKeysInfo::RollOld(old) => Some(&old._active_key),
+            _ => None,
+        }
+    }
+
+    pub fn new_key(&self) -> Option<&CertifiedKeyInfo> {
+        if let ResourceClassKeysInfo::RollNew(new) = self {
+            Some(&new._new_key)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ResourceClassKeysInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "State: ")?;
+
+        match &self {
+            ResourceClassKeysInfo::Pending(_) => write!(f, "pending")?,
+            ResourceClassKeysInfo::Active(_) => write!(f, "active")?,
+            ResourceClassKeysInfo::RollPending(_) => write!(f, "roll phase 1: pending and active key")?,
+            ResourceClassKeysInfo::RollNew(_) => write!(f, "roll phase 2: new and active key")?,
+            ResourceClassKeysInfo::RollOld(_) => write!(f, "roll phase 3: active and old key")?,
+        }
+
+        if let Some(key) = self.current_key() {
+            let resources = key.incoming_cert().resources();
+            writeln!(f, "    Resources:")?;
+            writeln!(f, "    ASNs: {}", resources.asn())?;
+            writeln!(f, "    IPv4: {}", resources.ipv4())?;
+            writeln!(f, "    IPv6: {}", resources.ipv6())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// This struct contains the API details for the configure Repository server,
+/// and objects published there, for a CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CaRepoDetails {
+    contact: RepositoryContact,
+}
+
+impl CaRepoDetails {
+    pub fn new(contact: RepositoryContact) -> Self {
+        CaRepoDetails { contact }
+    }
+
+    pub fn contact(&self) -> &RepositoryContact {
+        &self.contact
+    }
+}
+
+impl fmt::Display for CaRepoDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let repo_info = self.contact.repo_info();
+        let server_info = self.contact.server_info();
+        let rrdp_uri = repo_info.rpki_notify().map(|uri| uri.as_str()).unwrap_or("<none>");
+
+        writeln!(f, "Repository Details:")?;
+        writeln!(f, "  service uri:    {}", server_info.service_uri())?;
+        writeln!(f, "  key identifier: {}", server_info.public_key().key_identifier())?;
+        writeln!(f, "  base_uri:       {}", repo_info.base_uri())?;
+        writeln!(f, "  rpki_notify:    {}", rrdp_uri)?;
+        writeln!(f)?;
+
+        Ok(())
+    }
+}
+
+//------------ ClassRepoDetails ----------------------------------------------
+
+/// The repositories used by resource classes of a CA which do not publish to
+/// the repository of the CA as a whole.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ClassRepoDetails(HashMap<ResourceClassName, RepositoryContact>);
+
+impl ClassRepoDetails {
+    pub fn new(classes: HashMap<ResourceClassName, RepositoryContact>) -> Self {
+        ClassRepoDetails(classes)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, rcn: &ResourceClassName) -> Option<&RepositoryContact> {
+        self.0.get(rcn)
+    }
+}
+
+impl fmt::Display for ClassRepoDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return writeln!(f, "All resource classes use the repository of the CA.");
+        }
+
+        let mut classes: Vec<&ResourceClassName> = self.0.keys().collect();
+        classes.sort_by_key(|rcn| rcn.to_string());
+        for rcn in classes {
+            writeln!(f, "Resource class '{}':", rcn)?;
+            write!(f, "{}", CaRepoDetails::new(self.0[rcn].clone()))?;
+        }
+        Ok(())
+    }
+}
+
+//------------ AllCertAuthIssues ---------------------------------------------
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AllCertAuthIssues {
+    cas: HashMap<CaHandle, CertAuthIssues>,
+}
+
+impl AllCertAuthIssues {
+    pub fn add(&mut self, ca: CaHandle, ca_issues: CertAuthIssues) {
+        self.cas.insert(ca, ca_issues);
+    }
+
+    pub fn cas(&self) -> &HashMap<CaHandle, CertAuthIssues> {
+        &self.cas
+    }
+}
+
+impl fmt::Display for AllCertAuthIssues {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cas = self.cas();
+        if cas.is_empty() {
+            writeln!(f, "no issues found")?;
+        } else {
+            for (ca, issues) in cas.iter() {
+                writeln!(f, "Found issue for CA '{}':", ca)?;
+
+                if let Some(repo_issue) = issues.repo_issue() {
+                    writeln!(f, "   Repository Issue: {}", repo_issue)?;
+                }
+                let parent_issues = issues.parent_issues();
+                if !parent_issues.is_empty() {
+                    for parent_issue in parent_issues.iter() {
+                        writeln!(
+                            f,
+                            "   Parent '{}' has issue: {}",
+                            parent_issue.parent, parent_issue.issue
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+//------------ CertAuthIssues ------------------------------------------------
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CertAuthIssues {
+    repo_issue: Option<ErrorResponse>,
+    parent_issues: Vec<CertAuthParentIssue>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CertAuthParentIssue {
+    pub parent: ParentHandle,
+    pub issue: ErrorResponse,
+}
+
+impl CertAuthIssues {
+    pub fn add_repo_issue(&mut self, issue: ErrorResponse) {
+        self.repo_issue = Some(issue);
+    }
+
+    pub fn repo_issue(&self) -> Option<&ErrorResponse> {
+        self.repo_issue.as_ref()
+    }
+
+    pub fn add_parent_issue(&mut self, parent: ParentHandle, issue: ErrorResponse) {
+        let parent_issue = CertAuthParentIssue { parent, issue };
+        self.parent_issues.push(parent_issue);
+    }
+
+    pub fn parent_issues(&self) -> &Vec<CertAuthParentIssue> {
+        &self.parent_issues
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.repo_issue.is_none() && self.parent_issues.is_empty()
+    }
+}
+
+impl fmt::Display for CertAuthIssues {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            writeln!(f, "no issues found")?;
+        } else {
+            if let Some(repo_issue) = self.repo_issue() {
+                writeln!(f, "Repository Issue: {}", repo_issue)?;
+            }
+            let parent_issues = self.parent_issues();
+            if !parent_issues.is_empty() {
+                for parent_issue in parent_issues.iter() {
+                    writeln!(f, "Parent '{}' has issue: {}", parent_issue.parent, parent_issue.issue)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+//------------ CertAuthStats -------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CertAuthStats {
+    roa_count: usize,
+    #[serde(default)]
+    aspa_count: usize,
+    child_count: usize,
+    bgp_stats: BgpStats,
+}
+
+impl CertAuthStats {
+    pub fn new(roa_count: usize, aspa_count: usize, child_count: usize, bgp_stats: BgpStats) -> Self {
+        CertAuthStats {
+            roa_count,
+            aspa_count,
+            child_count,
+            bgp_stats,
+        }
+    }
+
+    pub fn roa_count(&self) -> usize {
+        self.roa_count
+    }
+
+    pub fn aspa_count(&self) -> usize {
+        self.aspa_count
+    }
+
+    pub fn child_count(&self) -> usize {
+        self.child_count
+    }
+
+    pub fn bgp_stats(&self) -> &BgpStats {
+        &self.bgp_stats
+    }
+}
+
+//------------ BgpStats ------------------------------------------------------
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BgpStats {
+    pub announcements_valid: usize,
+    pub announcements_invalid_asn: usize,
+    pub announcements_invalid_length: usize,
+    pub announcements_disallowed: usize,
+    pub announcements_not_found: usize,
+    pub roas_too_permissive: usize,
+    pub roas_redundant: usize,
+    pub roas_stale: usize,
+    pub roas_disallowing: usize,
+    pub roas_not_held: usize,
+    pub roas_total: usize,
+}
+
+impl BgpStats {
+    pub fn increment_valid(&mut self) {
+        self.announcements_valid += 1;
+    }
+
+    pub fn increment_invalid_asn(&mut self) {
+        self.announcements_invalid_asn += 1;
+    }
+
+    pub fn increment_invalid_length(&mut self) {
+        self.announcements_invalid_length += 1;
+    }
+
+    pub fn increment_disallowed(&mut self) {
+        self.announcements_disallowed += 1;
+    }
+
+    pub fn increment_not_found(&mut self) {
+        self.announcements_not_found += 1;
+    }
+
+    pub fn increment_roas_too_permissive(&mut self) {
+        self.roas_too_permissive += 1;
+    }
+
+    pub fn increment_roas_redundant(&mut self) {
+        self.roas_redundant += 1;
+    }
+
+    pub fn increment_roas_not_held(&mut self) {
+        self.roas_not_held += 1;
+    }
+
+    pub fn increment_roas_stale(&mut self) {
+        self.roas_stale += 1;
+    }
+
+    pub fn increment_roas_disallowing(&mut self) {
+        self.roas_disallowing += 1;
+    }
+
+    pub fn increment_roas_total(&mut self) {
+        self.roas_total += 1;
+    }
+}
+
+//------------ IssuanceTimingOverrides ---------------------------------------
+
+/// Overrides of the issuance timing configuration for a single CA. Values
+/// which are not set fall back to the global configuration.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct IssuanceTimingOverrides {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_publish_next_hours: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_publish_next_jitter_hours: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_publish_hours_before_next: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_roa_valid_weeks: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_roa_reissue_weeks_before: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_aspa_valid_weeks: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_aspa_reissue_weeks_before: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_roa_valid_jitter_hours: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_aspa_valid_jitter_hours: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timing_renew_spread_hours: Option<u32>,
+}
+
+impl IssuanceTimingOverrides {
+    pub fn is_empty(&self) -> bool {
+        self == &IssuanceTimingOverrides::default()
+    }
+
+    /// Returns the overridden values on a single line, e.g. for the history.
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            "none".to_string()
+        } else {
+            let values: Vec<String> = self
+                .values()
+                .into_iter()
+                .filter_map(|(name, value)| value.map(|value| format!("{} = {}", name, value)))
+                .collect();
+            values.join(", ")
+        }
+    }
+
+    fn values(&self) -> Vec<(&'static str, Option<u32>)> {
+        vec![
+            ("timing_publish_next_hours", self.timing_publish_next_hours),
+            (
+                "timing_publish_next_jitter_hours",
+                self.timing_publish_next_jitter_hours,
+            ),
+            (
+                "timing_publish_hours_before_next",
+                self.timing_publish_hours_before_next,
+            ),
+            ("timing_roa_valid_weeks", self.timing_roa_valid_weeks),
+            ("timing_roa_reissue_weeks_before", self.timing_roa_reissue_weeks_before),
+            ("timing_aspa_valid_weeks", self.timing_aspa_valid_weeks),
+            (
+                "timing_aspa_reissue_weeks_before",
+                self.timing_aspa_reissue_weeks_before,
+            ),
+            ("timing_roa_valid_jitter_hours", self.timing_roa_valid_jitter_hours),
+            ("timing_aspa_valid_jitter_hours", self.timing_aspa_valid_jitter_hours),
+            ("timing_renew_spread_hours", self.timing_renew_spread_hours),
+        ]
+    }
+}
+
+impl fmt::Display for IssuanceTimingOverrides {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            writeln!(f, "No issuance timing overrides, the global configuration is used.")
+        } else {
+            for (name, value) in self.values() {
+                if let Some(value) = value {
+                    writeln!(f, "{} = {}", name, value)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ IssuanceTimingReport ------------------------------------------
+
+/// The issuance timing in effect for a CA, i.e. the global configuration
+/// with the CA specific overrides applied, together with warnings about
+/// values which are allowed but may be unwise.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct IssuanceTimingReport {
+    overrides: IssuanceTimingOverrides,
+    effective: IssuanceTimingOverrides,
+    warnings: Vec<String>,
+}
+
+impl IssuanceTimingReport {
+    pub fn new(overrides: IssuanceTimingOverrides, effective: IssuanceTimingOverrides, warnings: Vec<String>) -> Self {
+        IssuanceTimingReport {
+            overrides,
+            effective,
+            warnings,
+        }
+    }
+
+    pub fn overrides(&self) -> &IssuanceTimingOverrides {
+        &self.overrides
+    }
+
+    /// All values in effect, including the values which are not overridden.
+    pub fn effective(&self) -> &IssuanceTimingOverrides {
+        &self.effective
+    }
+
+    pub fn warnings(&self) -> &Vec<String> {
+        &self.warnings
+    }
+}
+
+impl fmt::Display for IssuanceTimingReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Effective issuance timing:")?;
+        for ((name, value), (_, overridden)) in self.effective.values().into_iter().zip(self.overrides.values()) {
+            if let Some(value) = value {
+                let source = if overridden.is_some() { " (override)" } else { "" };
+                writeln!(f, "  {} = {}{}", name, value, source)?;
+            }
+        }
+        if !self.warnings.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Warnings:")?;
+            for warning in &self.warnings {
+                writeln!(f, "  - {}", warning)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//------------ ChildIssuancePolicy -------------------------------------------
+
+/// Constraints which a parent CA enforces before it issues certificates to
+/// its children, on top of the resource entitlements of each child.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildIssuancePolicy {
+    /// The maximum validity time in weeks for certificates issued to
+    /// children. If set, this caps 'timing_child_certificate_valid_weeks'.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_validity_weeks: Option<u32>,
+
+    /// Refuse to issue certificates which would only contain ASNs.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub deny_asn_only: bool,
+
+    /// Queue requests for keys which were not certified to the child before,
+    /// until they are approved by an operator.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub approve_new_keys: bool,
+}
+
+impl ChildIssuancePolicy {
+    pub fn is_empty(&self) -> bool {
+        self == &ChildIssuancePolicy::default()
+    }
+
+    /// Returns the policy on a single line, e.g. for the history.
+    pub fn summary(&self) -> String {
+        let mut values = vec![];
+        if let Some(weeks) = self.max_validity_weeks {
+            values.push(format!("max_validity_weeks = {}", weeks));
+        }
+        if self.deny_asn_only {
+            values.push("deny_asn_only".to_string());
+        }
+        if self.approve_new_keys {
+            values.push("approve_new_keys".to_string());
+        }
+
+        if values.is_empty() {
+            "none".to_string()
+        } else {
+            values.join(", ")
+        }
+    }
+}
+
+impl fmt::Display for ChildIssuancePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            writeln!(
+                f,
+                "No child issuance policy, certificates are issued for all entitlements."
+            )
+        } else {
+            if let Some(weeks) = self.max_validity_weeks {
+                writeln!(f, "Maximum validity: {} weeks", weeks)?;
+            }
+            if self.deny_asn_only {
+                writeln!(f, "Certificates with only ASNs are refused")?;
+            }
+            if self.approve_new_keys {
+                writeln!(f, "New child keys require manual approval")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ PendingChildRequest -------------------------------------------
+
+/// A certificate or revocation request from a child which is held back by
+/// the parent, until it is approved. The child will keep asking, and the
+/// request is honored when it does so after the approval.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PendingChildRequest {
+    #[serde(default)]
+    pub kind: PendingChildRequestKind,
+    pub key: KeyIdentifier,
+    pub class_name: ResourceClassName,
+    pub reason: String,
+    pub since: Timestamp,
+}
+
+impl PendingChildRequest {
+    pub fn new(
+        kind: PendingChildRequestKind,
+        key: KeyIdentifier,
+        class_name: ResourceClassName,
+        reason: String,
+    ) -> Self {
+        PendingChildRequest {
+            kind,
+            key,
+            class_name,
+            reason,
+            since: Timestamp::now(),
+        }
     }
+}
 
-    pub fn children(&self) -> &Vec<ChildHandle> {
-        &self.children
+impl fmt::Display for PendingChildRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} key: {} class: {} since: {} reason: {}",
+            self.kind,
+            self.key,
+            self.class_name,
+            self.since.to_rfc3339(),
+            self.reason
+        )
     }
+}
 
-    pub fn suspended_children(&self) -> &Vec<ChildHandle> {
-        &self.suspended_children
+/// The type of RFC 6492 request which is held back. A child can have both
+/// an issuance and a revocation request pending for the same key.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingChildRequestKind {
+    Issue,
+    Revoke,
+}
+
+impl Default for PendingChildRequestKind {
+    fn default() -> Self {
+        PendingChildRequestKind::Issue
     }
 }
 
-impl fmt::Display for CertAuthInfo {
+impl fmt::Display for PendingChildRequestKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "Name:     {}", self.handle())?;
-        writeln!(f)?;
+        match self {
+            PendingChildRequestKind::Issue => write!(f, "issue"),
+            PendingChildRequestKind::Revoke => write!(f, "revoke"),
+        }
+    }
+}
 
-        if let Some(repo_info) = self.repo_info() {
-            let base_uri = repo_info.base_uri();
-            let rrdp_uri = repo_info.rpki_notify().map(|uri| uri.as_str()).unwrap_or("<none>");
+impl FromStr for PendingChildRequestKind {
+    type Err = String;
 
-            writeln!(f, "Base uri: {}", base_uri)?;
-            writeln!(f, "RRDP uri: {}", rrdp_uri)?;
-        } else {
-            writeln!(f, "No repository configured.")?;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "issue" => Ok(PendingChildRequestKind::Issue),
+            "revoke" => Ok(PendingChildRequestKind::Revoke),
+            _ => Err(format!("Unknown request kind '{}', expected 'issue' or 'revoke'", s)),
         }
-        writeln!(f)?;
+    }
+}
 
-        writeln!(f, "ID cert PEM:\n{}", self.id_cert().pem())?;
-        writeln!(f, "Hash: {}", self.id_cert().hash())?;
-        writeln!(f)?;
+/// The pending requests of a single child.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PendingChildRequests(Vec<PendingChildRequest>);
 
-        let resources = self.resources();
-        if resources.is_empty() {
-            writeln!(f, "Total resources: <none>")?;
-        } else {
-            writeln!(f, "Total resources:")?;
-            writeln!(f, "    ASNs: {}", resources.asn())?;
-            writeln!(f, "    IPv4: {}", resources.ipv4())?;
-            writeln!(f, "    IPv6: {}", resources.ipv6())?;
-        }
-        writeln!(f)?;
+impl PendingChildRequests {
+    pub fn new(requests: Vec<PendingChildRequest>) -> Self {
+        PendingChildRequests(requests)
+    }
 
-        writeln!(f, "Parents:")?;
-        if !self.parents().is_empty() {
-            for parent in self.parents().iter() {
-                writeln!(f, "{}", parent)?;
-            }
-            writeln!(f)?;
-        } else {
-            writeln!(f, "<none>")?;
-        }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 
-        for (name, rc) in self.resource_classes() {
-            writeln!(f, "Resource Class: {}", name,)?;
-            writeln!(f, "Parent: {}", rc.parent_handle())?;
-            writeln!(f, "{}", rc.keys())?;
-        }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
 
-        writeln!(f, "Children:")?;
-        if !self.children().is_empty() {
-            for child_handle in self.children() {
-                writeln!(f, "{}", child_handle)?;
-            }
+    pub fn iter(&self) -> impl Iterator<Item = &PendingChildRequest> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for PendingChildRequests {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            writeln!(f, "No pending requests.")
         } else {
-            writeln!(f, "<none>")?;
+            for request in &self.0 {
+                writeln!(f, "{}", request)?;
+            }
+            Ok(())
         }
-
-        Ok(())
     }
 }
 
-//------------ KeyStateInfo -------------------------------------------------
+//------------ ResourceTransferRequest ---------------------------------------
 
+/// A request to move resources from one child of a CA to another. The
+/// resources are first removed from the 'from' child, and only added to
+/// the 'to' child once the 'from' child got certificates without them.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct ResourceClassInfo {
-    name_space: String,
-    parent_handle: ParentHandle,
-    keys: ResourceClassKeysInfo,
+pub struct ResourceTransferRequest {
+    pub from: ChildHandle,
+    pub to: ChildHandle,
+    pub resources: ResourceSet,
+
+    /// The number of hours to wait for the 'from' child to get certificates
+    /// without the resources, before the transfer is rolled back.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timeout_hours: Option<u32>,
 }
 
-impl ResourceClassInfo {
-    pub fn new(name_space: String, parent_handle: ParentHandle, keys: ResourceClassKeysInfo) -> Self {
-        ResourceClassInfo {
-            name_space,
-            parent_handle,
-            keys,
+impl fmt::Display for ResourceTransferRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "transfer resources '{}' from child '{}' to child '{}'",
+            self.resources, self.from, self.to
+        )
+    }
+}
+
+//------------ ResourceTransfer ----------------------------------------------
+
+/// The state of a resource transfer between two children.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceTransferState {
+    /// The resources were removed from the 'from' child, waiting until it
+    /// got certificates without them.
+    Waiting,
+
+    /// The resources were added to the 'to' child.
+    Completed,
+
+    /// The resources were given back to the 'from' child.
+    RolledBack,
+}
+
+impl fmt::Display for ResourceTransferState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResourceTransferState::Waiting => write!(f, "waiting for re-issuance"),
+            ResourceTransferState::Completed => write!(f, "completed"),
+            ResourceTransferState::RolledBack => write!(f, "rolled back"),
         }
     }
+}
 
-    pub fn name_space(&self) -> &str {
-        &self.name_space
+/// A tracked transfer of resources between two children of a CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResourceTransfer {
+    id: u64,
+    from: ChildHandle,
+    to: ChildHandle,
+    resources: ResourceSet,
+    state: ResourceTransferState,
+    started: Timestamp,
+    deadline: Timestamp,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    finished: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    reason: Option<String>,
+}
+
+impl ResourceTransfer {
+    pub fn new(id: u64, request: ResourceTransferRequest, timeout_hours: u32) -> Self {
+        let started = Timestamp::now();
+        let deadline = started.plus_hours(timeout_hours.into());
+
+        ResourceTransfer {
+            id,
+            from: request.from,
+            to: request.to,
+            resources: request.resources,
+            state: ResourceTransferState::Waiting,
+            started,
+            deadline,
+            finished: None,
+            reason: None,
+        }
     }
-    pub fn parent_handle(&self) -> &ParentHandle {
-        &self.parent_handle
+
+    pub fn id(&self) -> u64 {
+        self.id
     }
-    pub fn keys(&self) -> &ResourceClassKeysInfo {
-        &self.keys
+
+    pub fn from(&self) -> &ChildHandle {
+        &self.from
     }
 
-    pub fn current_key(&self) -> Option<&CertifiedKeyInfo> {
-        self.keys.current_key()
+    pub fn to(&self) -> &ChildHandle {
+        &self.to
     }
 
-    pub fn new_key(&self) -> Option<&CertifiedKeyInfo> {
-        self.keys.new_key()
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
     }
 
-    pub fn current_resources(&self) -> Option<&ResourceSet> {
-        self.current_key().map(|k| k.incoming_cert().resources())
+    pub fn state(&self) -> ResourceTransferState {
+        self.state
+    }
+
+    pub fn deadline(&self) -> Timestamp {
+        self.deadline
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.state == ResourceTransferState::Waiting
+    }
+
+    /// Returns true if the transfer is still waiting after its deadline.
+    pub fn is_expired(&self) -> bool {
+        self.is_waiting() && Timestamp::now() > self.deadline
+    }
+
+    /// Returns a copy of this transfer, marked as completed.
+    pub fn completed(&self) -> Self {
+        let mut transfer = self.clone();
+        transfer.state = ResourceTransferState::Completed;
+        transfer.finished = Some(Timestamp::now());
+        transfer
+    }
+
+    /// Returns a copy of this transfer, marked as rolled back for the
+    /// given reason.
+    pub fn rolled_back(&self, reason: String) -> Self {
+        let mut transfer = self.clone();
+        transfer.state = ResourceTransferState::RolledBack;
+        transfer.finished = Some(Timestamp::now());
+        transfer.reason = Some(reason);
+        transfer
     }
 }
 
-//------------ ResourceClassKeysInfo -----------------------------------------
+impl fmt::Display for ResourceTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Transfer {}: from '{}' to '{}'", self.id, self.from, self.to)?;
+        writeln!(f, "  resources: {}", self.resources)?;
+        writeln!(f, "  state: {}", self.state)?;
+        writeln!(f, "  started: {}", self.started.to_rfc3339())?;
+        match self.finished {
+            Some(finished) => writeln!(f, "  finished: {}", finished.to_rfc3339())?,
+            None => writeln!(f, "  deadline: {}", self.deadline.to_rfc3339())?,
+        }
+        if let Some(reason) = &self.reason {
+            writeln!(f, "  reason: {}", reason)?;
+        }
+        Ok(())
+    }
+}
 
-/// Contains the current key status for a resource class.
+/// The resource transfers for a CA, ordered by id.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-#[allow(clippy::large_enum_variant)]
-#[serde(rename_all = "snake_case")]
-pub enum ResourceClassKeysInfo {
-    Pending(PendingInfo),
-    Active(ActiveInfo),
-    RollPending(RollPendingInfo),
-    RollNew(RollNewInfo),
-    RollOld(RollOldInfo),
+pub struct ResourceTransferList(Vec<ResourceTransfer>);
+
+impl ResourceTransferList {
+    pub fn new(transfers: Vec<ResourceTransfer>) -> Self {
+        ResourceTransferList(transfers)
+    }
+
+    pub fn transfers(&self) -> &Vec<ResourceTransfer> {
+        &self.0
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct PendingInfo {
-    #[serde(rename = "pending_key")]
-    pub _pending_key: PendingKeyInfo,
+impl fmt::Display for ResourceTransferList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            writeln!(f, "No resource transfers.")
+        } else {
+            for transfer in &self.0 {
+                writeln!(f, "{}", transfer)?;
+            }
+            Ok(())
+        }
+    }
 }
 
+//------------ ScheduledChange -----------------------------------------------
+
+/// An update of the ROAs or ASPAs of a CA which can be scheduled.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct ActiveInfo {
-    #[serde(rename = "active_key")]
-    pub _active_key: CertifiedKeyInfo,
+#[serde(rename_all = "snake_case", tag = "type", content = "updates")]
+pub enum ScheduledUpdate {
+    Roas(RoaConfigurationUpdates),
+    Aspas(AspaDefinitionUpdates),
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct RollPendingInfo {
-    #[serde(rename = "pending_key")]
-    pub _pending_key: PendingKeyInfo,
-    #[serde(rename = "active_key")]
-    pub _active_key: CertifiedKeyInfo,
+impl fmt::Display for ScheduledUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScheduledUpdate::Roas(updates) => {
+                write!(f, "Update ROA definitions:")?;
+                for line in updates.to_string().lines() {
+                    write!(f, " {}", line)?;
+                }
+                Ok(())
+            }
+            ScheduledUpdate::Aspas(updates) => updates.fmt(f),
+        }
+    }
 }
 
+/// A request to apply an update of the ROAs or ASPAs of a CA at the given
+/// moment, e.g. at the start of an announced maintenance window.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct RollNewInfo {
-    #[serde(rename = "new_key")]
-    pub _new_key: CertifiedKeyInfo,
-    #[serde(rename = "active_key")]
-    pub _active_key: CertifiedKeyInfo,
+pub struct ScheduledChangeRequest {
+    pub activate_at: Timestamp,
+    pub update: ScheduledUpdate,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct RollOldInfo {
-    #[serde(rename = "active_key")]
-    pub _active_key: CertifiedKeyInfo,
-    #[serde(rename = "old_key")]
-    pub _old_key: CertifiedKeyInfo,
+impl fmt::Display for ScheduledChangeRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at {}: {}", self.activate_at.to_rfc3339(), self.update)
+    }
 }
 
-impl ResourceClassKeysInfo {
-    pub fn current_key(&self) -> Option<&CertifiedKeyInfo> {
-        match &self {
-            ResourceClassKeysInfo::Active(current) => Some(&current._active_key),
-            ResourceClassKeysInfo::RollPending(pending) => Some(&pending._active_key),
-            ResourceClassKeysInfo::RollNew(new) => Some(&new._active_key),
-            ResourceClassKeysInfo::RollOld(old) => Some(&old._active_key),
-            _ => None,
+/// An update of the ROAs or ASPAs of a CA which will be applied by the
+/// scheduler when it is due.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScheduledChange {
+    id: u64,
+    activate_at: Timestamp,
+    scheduled_by: String,
+    update: ScheduledUpdate,
+}
+
+impl ScheduledChange {
+    pub fn new(id: u64, scheduled_by: String, request: ScheduledChangeRequest) -> Self {
+        ScheduledChange {
+            id,
+            activate_at: request.activate_at,
+            scheduled_by,
+            update: request.update,
         }
     }
 
-    pub fn new_key(&self) -> Option<&CertifiedKeyInfo> {
-        if let ResourceClassKeysInfo::RollNew(new) = self {
-            Some(&new._new_key)
-        } else {
-            None
-        }
+    pub fn id(&self) -> u64 {
+        self.id
     }
-}
 
-impl fmt::Display for ResourceClassKeysInfo {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "State: ")?;
+    pub fn activate_at(&self) -> Timestamp {
+        self.activate_at
+    }
 
-        match &self {
-            ResourceClassKeysInfo::Pending(_) => write!(f, "pending")?,
-            ResourceClassKeysInfo::Active(_) => write!(f, "active")?,
-            ResourceClassKeysInfo::RollPending(_) => write!(f, "roll phase 1: pending and active key")?,
-            ResourceClassKeysInfo::RollNew(_) => write!(f, "roll phase 2: new and active key")?,
-            ResourceClassKeysInfo::RollOld(_) => write!(f, "roll phase 3: active and old key")?,
-        }
+    pub fn scheduled_by(&self) -> &str {
+        &self.scheduled_by
+    }
 
-        if let Some(key) = self.current_key() {
-            let resources = key.incoming_cert().resources();
-            writeln!(f, "    Resources:")?;
-            writeln!(f, "    ASNs: {}", resources.asn())?;
-            writeln!(f, "    IPv4: {}", resources.ipv4())?;
-            writeln!(f, "    IPv6: {}", resources.ipv6())?;
-        }
+    pub fn update(&self) -> &ScheduledUpdate {
+        &self.update
+    }
 
-        Ok(())
+    pub fn is_due(&self) -> bool {
+        self.activate_at <= Timestamp::now()
     }
 }
 
-/// This struct contains the API details for the configure Repository server,
-/// and objects published there, for a CA.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct CaRepoDetails {
-    contact: RepositoryContact,
+impl fmt::Display for ScheduledChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Scheduled change {} by '{}', at {}",
+            self.id,
+            self.scheduled_by,
+            self.activate_at.to_rfc3339()
+        )?;
+        writeln!(f, "  {}", self.update)
+    }
 }
 
-impl CaRepoDetails {
-    pub fn new(contact: RepositoryContact) -> Self {
-        CaRepoDetails { contact }
+/// The pending scheduled changes for a CA, ordered by id.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScheduledChangeList(Vec<ScheduledChange>);
+
+impl ScheduledChangeList {
+    pub fn new(changes: Vec<ScheduledChange>) -> Self {
+        ScheduledChangeList(changes)
     }
 
-    pub fn contact(&self) -> &RepositoryContact {
-        &self.contact
+    pub fn changes(&self) -> &Vec<ScheduledChange> {
+        &self.0
     }
 }
 
-impl fmt::Display for CaRepoDetails {
+impl fmt::Display for ScheduledChangeList {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let repo_info = self.contact.repo_info();
-        let server_info = self.contact.server_info();
-        let rrdp_uri = repo_info.rpki_notify().map(|uri| uri.as_str()).unwrap_or("<none>");
-
-        writeln!(f, "Repository Details:")?;
-        writeln!(f, "  service uri:    {}", server_info.service_uri())?;
-        writeln!(f, "  key identifier: {}", server_info.public_key().key_identifier())?;
-        writeln!(f, "  base_uri:       {}", repo_info.base_uri())?;
-        writeln!(f, "  rpki_notify:    {}", rrdp_uri)?;
-        writeln!(f)?;
-
-        Ok(())
+        if self.0.is_empty() {
+            writeln!(f, "No scheduled changes.")
+        } else {
+            for change in &self.0 {
+                writeln!(f, "{}", change)?;
+            }
+            Ok(())
+        }
     }
 }
 
-//------------ AllCertAuthIssues ---------------------------------------------
+//------------ RetryPolicy ---------------------------------------------------
 
+/// Overrides of the retry policy configuration for contacting a parent or
+/// the repository. Values which are not set fall back to the global
+/// configuration.
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
-pub struct AllCertAuthIssues {
-    cas: HashMap<CaHandle, CertAuthIssues>,
-}
+pub struct RetryPolicy {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub initial_delay_seconds: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub backoff_factor: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_delay_seconds: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_attempts: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub jitter_seconds: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timeout_seconds: Option<u64>,
+}
+
+impl RetryPolicy {
+    pub fn is_empty(&self) -> bool {
+        self == &RetryPolicy::default()
+    }
 
-impl AllCertAuthIssues {
-    pub fn add(&mut self, ca: CaHandle, ca_issues: CertAuthIssues) {
-        self.cas.insert(ca, ca_issues);
+    /// Returns the overridden values on a single line, e.g. for the history.
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            "none".to_string()
+        } else {
+            let values: Vec<String> = self
+                .values()
+                .into_iter()
+                .filter_map(|(name, value)| value.map(|value| format!("{} = {}", name, value)))
+                .collect();
+            values.join(", ")
+        }
     }
 
-    pub fn cas(&self) -> &HashMap<CaHandle, CertAuthIssues> {
-        &self.cas
+    fn values(&self) -> Vec<(&'static str, Option<u64>)> {
+        vec![
+            ("initial_delay_seconds", self.initial_delay_seconds.map(u64::from)),
+            ("backoff_factor", self.backoff_factor.map(u64::from)),
+            ("max_delay_seconds", self.max_delay_seconds.map(u64::from)),
+            ("max_attempts", self.max_attempts.map(u64::from)),
+            ("jitter_seconds", self.jitter_seconds.map(u64::from)),
+            ("timeout_seconds", self.timeout_seconds),
+        ]
     }
 }
 
-impl fmt::Display for AllCertAuthIssues {
+impl fmt::Display for RetryPolicy {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let cas = self.cas();
-        if cas.is_empty() {
-            writeln!(f, "no issues found")?;
+        if self.is_empty() {
+            writeln!(f, "No retry policy overrides, the global configuration is used.")
         } else {
-            for (ca, issues) in cas.iter() {
-                writeln!(f, "Found issue for CA '{}':", ca)?;
-
-                if let Some(repo_issue) = issues.repo_issue() {
-                    writeln!(f, "   Repository Issue: {}", repo_issue)?;
-                }
-                let parent_issues = issues.parent_issues();
-                if !parent_issues.is_empty() {
-                    for parent_issue in parent_issues.iter() {
-                        writeln!(
-                            f,
-                            "   Parent '{}' has issue: {}",
-                            parent_issue.parent, parent_issue.issue
-                        )?;
-                    }
+            for (name, value) in self.values() {
+                if let Some(value) = value {
+                    writeln!(f, "{} = {}", name, value)?;
                 }
             }
+            Ok(())
         }
-        Ok(())
     }
 }
 
-//------------ CertAuthIssues ------------------------------------------------
+//------------ RetryPolicies -------------------------------------------------
 
+/// The retry policy overrides of a CA for contacting its repository and
+/// each of its parents.
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
-pub struct CertAuthIssues {
-    repo_issue: Option<ErrorResponse>,
-    parent_issues: Vec<CertAuthParentIssue>,
+pub struct RetryPolicies {
+    #[serde(skip_serializing_if = "RetryPolicy::is_empty", default)]
+    pub repository: RetryPolicy,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub parents: HashMap<ParentHandle, RetryPolicy>,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct CertAuthParentIssue {
-    pub parent: ParentHandle,
-    pub issue: ErrorResponse,
-}
+impl RetryPolicies {
+    pub fn is_empty(&self) -> bool {
+        self.repository.is_empty() && self.parents.is_empty()
+    }
 
-impl CertAuthIssues {
-    pub fn add_repo_issue(&mut self, issue: ErrorResponse) {
-        self.repo_issue = Some(issue);
+    pub fn repository(&self) -> &RetryPolicy {
+        &self.repository
     }
 
-    pub fn repo_issue(&self) -> Option<&ErrorResponse> {
-        self.repo_issue.as_ref()
+    /// Returns the overrides for the given parent, or an empty policy
+    /// if there are none.
+    pub fn parent(&self, parent: &ParentHandle) -> RetryPolicy {
+        self.parents.get(parent).cloned().unwrap_or_default()
     }
 
-    pub fn add_parent_issue(&mut self, parent: ParentHandle, issue: ErrorResponse) {
-        let parent_issue = CertAuthParentIssue { parent, issue };
-        self.parent_issues.push(parent_issue);
+    /// Replaces the overrides for the repository, or for the given parent.
+    /// An empty policy removes the overrides.
+    pub fn set(&mut self, parent: Option<ParentHandle>, policy: RetryPolicy) {
+        match parent {
+            None => self.repository = policy,
+            Some(parent) => {
+                if policy.is_empty() {
+                    self.parents.remove(&parent);
+                } else {
+                    self.parents.insert(parent, policy);
+                }
+            }
+        }
     }
 
-    pub fn parent_issues(&self) -> &Vec<CertAuthParentIssue> {
-        &self.parent_issues
+    /// Removes the overrides for a parent which is removed from the CA.
+    pub fn remove_parent(&mut self, parent: &ParentHandle) {
+        self.parents.remove(parent);
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.repo_issue.is_none() && self.parent_issues.is_empty()
+    /// Returns the overridden values on a single line, e.g. for the history.
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            "none".to_string()
+        } else {
+            let mut values = vec![];
+            if !self.repository.is_empty() {
+                values.push(format!("repository: {}", self.repository.summary()));
+            }
+            let mut parents: Vec<_> = self.parents.iter().collect();
+            parents.sort_by_key(|(parent, _)| parent.to_string());
+            for (parent, policy) in parents {
+                values.push(format!("parent '{}': {}", parent, policy.summary()));
+            }
+            values.join("; ")
+        }
     }
 }
 
-impl fmt::Display for CertAuthIssues {
+impl fmt::Display for RetryPolicies {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.is_empty() {
-            writeln!(f, "no issues found")?;
+            writeln!(f, "No retry policy overrides, the global configuration is used.")
         } else {
-            if let Some(repo_issue) = self.repo_issue() {
-                writeln!(f, "Repository Issue: {}", repo_issue)?;
+            if !self.repository.is_empty() {
+                writeln!(f, "Repository:")?;
+                for line in self.repository.to_string().lines() {
+                    writeln!(f, "  {}", line)?;
+                }
             }
-            let parent_issues = self.parent_issues();
-            if !parent_issues.is_empty() {
-                for parent_issue in parent_issues.iter() {
-                    writeln!(f, "Parent '{}' has issue: {}", parent_issue.parent, parent_issue.issue)?;
+            let mut parents: Vec<_> = self.parents.iter().collect();
+            parents.sort_by_key(|(parent, _)| parent.to_string());
+            for (parent, policy) in parents {
+                writeln!(f, "Parent: {}", parent)?;
+                for line in policy.to_string().lines() {
+                    writeln!(f, "  {}", line)?;
                 }
             }
+            Ok(())
         }
-        Ok(())
     }
 }
 
-//------------ CertAuthStats -------------------------------------------------
+//------------ Backoff -------------------------------------------------------
 
+/// The current backoff state after one or more consecutive failures to
+/// contact a parent or the repository.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct CertAuthStats {
-    roa_count: usize,
-    child_count: usize,
-    bgp_stats: BgpStats,
+pub struct Backoff {
+    failures: u32,
+    next_attempt: Timestamp,
 }
 
-impl CertAuthStats {
-    pub fn new(roa_count: usize, child_count: usize, bgp_stats: BgpStats) -> Self {
-        CertAuthStats {
-            roa_count,
-            child_count,
-            bgp_stats,
-        }
+impl Backoff {
+    pub fn new(failures: u32, next_attempt: Timestamp) -> Self {
+        Backoff { failures, next_attempt }
     }
 
-    pub fn roa_count(&self) -> usize {
-        self.roa_count
+    pub fn failures(&self) -> u32 {
+        self.failures
     }
 
-    pub fn child_count(&self) -> usize {
-        self.child_count
+    pub fn next_attempt(&self) -> Timestamp {
+        self.next_attempt
     }
+}
 
-    pub fn bgp_stats(&self) -> &BgpStats {
-        &self.bgp_stats
+impl fmt::Display for Backoff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} consecutive failure(s), next attempt: {}",
+            self.failures,
+            self.next_attempt.to_rfc3339()
+        )
     }
 }
 
-//------------ BgpStats ------------------------------------------------------
+//------------ CaLabels ------------------------------------------------------
 
+/// Arbitrary key/value labels of a CA, e.g. to group CAs by customer, region
+/// or environment. Labels can be used to select CAs in listings, in bulk
+/// jobs and in the metrics.
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
-pub struct BgpStats {
-    pub announcements_valid: usize,
-    pub announcements_invalid_asn: usize,
-    pub announcements_invalid_length: usize,
-    pub announcements_disallowed: usize,
-    pub announcements_not_found: usize,
-    pub roas_too_permissive: usize,
-    pub roas_redundant: usize,
-    pub roas_stale: usize,
-    pub roas_disallowing: usize,
-    pub roas_not_held: usize,
-    pub roas_total: usize,
-}
+#[serde(transparent)]
+pub struct CaLabels(BTreeMap<String, String>);
 
-impl BgpStats {
-    pub fn increment_valid(&mut self) {
-        self.announcements_valid += 1;
+impl CaLabels {
+    /// The maximum length of a label key.
+    const MAX_KEY_LEN: usize = 63;
+
+    /// The maximum length of a label value.
+    const MAX_VALUE_LEN: usize = 255;
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 
-    pub fn increment_invalid_asn(&mut self) {
-        self.announcements_invalid_asn += 1;
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.0.get(key)
     }
 
-    pub fn increment_invalid_length(&mut self) {
-        self.announcements_invalid_length += 1;
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter()
     }
 
-    pub fn increment_disallowed(&mut self) {
-        self.announcements_disallowed += 1;
+    /// Applies the update: values are set first, then keys are removed.
+    pub fn apply(&mut self, update: CaLabelsUpdate) {
+        self.0.extend(update.set);
+        for key in update.remove {
+            self.0.remove(&key);
+        }
     }
 
-    pub fn increment_not_found(&mut self) {
-        self.announcements_not_found += 1;
+    /// Returns an error describing the first invalid label, if any.
+    ///
+    /// Keys must start with a letter or digit and may further contain
+    /// letters, digits, '.', '_', '-' and '/'. Values may be empty, but
+    /// must not contain ',' or control characters, so that they can be
+    /// used in a [`CaLabelSelector`].
+    pub fn validate(&self) -> Result<(), String> {
+        for (key, value) in self.0.iter() {
+            Self::validate_key(key)?;
+            if value.len() > Self::MAX_VALUE_LEN {
+                return Err(format!(
+                    "value for label '{}' is longer than {} characters",
+                    key,
+                    Self::MAX_VALUE_LEN
+                ));
+            }
+            if value.chars().any(|c| c == ',' || c.is_control()) {
+                return Err(format!("value for label '{}' contains a ',' or control character", key));
+            }
+        }
+        Ok(())
     }
 
-    pub fn increment_roas_too_permissive(&mut self) {
-        self.roas_too_permissive += 1;
+    fn validate_key(key: &str) -> Result<(), String> {
+        if key.is_empty() || key.len() > Self::MAX_KEY_LEN {
+            return Err(format!(
+                "label key '{}' must have 1 to {} characters",
+                key,
+                Self::MAX_KEY_LEN
+            ));
+        }
+        let valid = key.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/'));
+        if valid {
+            Ok(())
+        } else {
+            Err(format!(
+                "label key '{}' must start with a letter or digit, and contain only letters, digits, '.', '_', '-' and '/'",
+                key
+            ))
+        }
     }
 
-    pub fn increment_roas_redundant(&mut self) {
-        self.roas_redundant += 1;
+    /// Returns the labels on a single line, e.g. for the history.
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            "none".to_string()
+        } else {
+            self.0
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
     }
+}
 
-    pub fn increment_roas_not_held(&mut self) {
-        self.roas_not_held += 1;
+impl fmt::Display for CaLabels {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            writeln!(f, "No labels.")
+        } else {
+            for (key, value) in self.0.iter() {
+                writeln!(f, "{}={}", key, value)?;
+            }
+            Ok(())
+        }
     }
+}
 
-    pub fn increment_roas_stale(&mut self) {
-        self.roas_stale += 1;
+//------------ CaLabelsUpdate ------------------------------------------------
+
+/// Sets and/or removes labels of a CA. Other labels are left as they are.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CaLabelsUpdate {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    set: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    remove: Vec<String>,
+}
+
+impl CaLabelsUpdate {
+    pub fn new(set: BTreeMap<String, String>, remove: Vec<String>) -> Self {
+        CaLabelsUpdate { set, remove }
     }
 
-    pub fn increment_roas_disallowing(&mut self) {
-        self.roas_disallowing += 1;
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.remove.is_empty()
     }
+}
 
-    pub fn increment_roas_total(&mut self) {
-        self.roas_total += 1;
+impl fmt::Display for CaLabelsUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = vec![];
+        if !self.set.is_empty() {
+            let set: Vec<_> = self
+                .set
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            parts.push(format!("set {}", set.join(", ")));
+        }
+        if !self.remove.is_empty() {
+            parts.push(format!("remove {}", self.remove.join(", ")));
+        }
+        if parts.is_empty() {
+            write!(f, "no changes")
+        } else {
+            write!(f, "{}", parts.join("; "))
+        }
+    }
+}
+
+//------------ CaLabelSelector -----------------------------------------------
+
+/// Selects CAs by their labels, using a comma separated list of
+/// requirements which must all be met: 'key=value' requires that the
+/// label has that value, 'key' only requires that the label is set. E.g.
+/// 'customer=acme,env=prod' or 'region'.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CaLabelSelector(Vec<(String, Option<String>)>);
+
+impl CaLabelSelector {
+    pub fn matches(&self, labels: &CaLabels) -> bool {
+        self.0.iter().all(|(key, value)| match (labels.get(key), value) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(actual), Some(value)) => actual == value,
+        })
+    }
+}
+
+impl FromStr for CaLabelSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut requirements = vec![];
+        for requirement in s.split(',') {
+            let (key, value) = match requirement.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+                None => (requirement.trim(), None),
+            };
+            CaLabels::validate_key(key)?;
+            requirements.push((key.to_string(), value));
+        }
+        Ok(CaLabelSelector(requirements))
+    }
+}
+
+impl fmt::Display for CaLabelSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let requirements: Vec<_> = self
+            .0
+            .iter()
+            .map(|(key, value)| match value {
+                Some(value) => format!("{}={}", key, value),
+                None => key.clone(),
+            })
+            .collect();
+        write!(f, "{}", requirements.join(","))
     }
 }
 
@@ -2375,6 +5106,7 @@ mod test {
             last_success: None,
             all_resources: ResourceSet::default(),
             classes: vec![],
+            backoff: None,
         };
 
         let p4_status_success = ParentStatus {
@@ -2386,6 +5118,7 @@ mod test {
             last_success: None,
             all_resources: ResourceSet::default(),
             classes: vec![],
+            backoff: None,
         };
 
         let p5_status_failure = ParentStatus {
@@ -2397,6 +5130,7 @@ mod test {
             last_success: None,
             all_resources: ResourceSet::default(),
             classes: vec![],
+            backoff: None,
         };
 
         let p6_status_success_long_ago = ParentStatus {
@@ -2408,6 +5142,7 @@ mod test {
             last_success: None,
             all_resources: ResourceSet::default(),
             classes: vec![],
+            backoff: None,
         };
 
         let mut inner_statuses = HashMap::new();
@@ -2446,4 +5181,176 @@ mod test {
 
         assert_eq!(candidates_trimmed, expected_trimmed);
     }
+
+    #[test]
+    fn repo_migration_report() {
+        let in_progress = RepoMigrationReport::new(
+            vec![
+                RepoMigrationStep::done("checked that the CA can migrate"),
+                RepoMigrationStep::waiting("certify new keys", "waiting for parent"),
+            ],
+            RepoMigrationStatus::new(vec![], vec![]),
+        );
+        assert!(!in_progress.is_aborted());
+        assert!(in_progress
+            .to_string()
+            .contains("[waiting] certify new keys: waiting for parent"));
+
+        let aborted = RepoMigrationReport::new(
+            vec![RepoMigrationStep::failed("check that the CA can migrate", "no RRDP")],
+            RepoMigrationStatus::new(vec![], vec![]),
+        );
+        assert!(aborted.is_aborted());
+        assert!(aborted.to_string().contains("Migration aborted"));
+
+        let json = serde_json::to_string(&aborted).unwrap();
+        assert_eq!(serde_json::from_str::<RepoMigrationReport>(&json).unwrap(), aborted);
+    }
+
+    #[test]
+    fn repo_status_publication_confirmation() {
+        let mut status = RepoStatus::default();
+        assert!(status.confirmation().is_none());
+
+        status.set_unconfirmed();
+        let pending_since = status.confirmation().unwrap().pending_since();
+        assert!(status.confirmation().unwrap().lags(0));
+        assert!(!status.confirmation().unwrap().lags(5));
+
+        // Publishing again does not reset the lag.
+        status.set_unconfirmed();
+        assert_eq!(status.confirmation().unwrap().pending_since(), pending_since);
+
+        status.set_confirm_lagging();
+        assert!(status.confirmation().unwrap().is_lagging());
+
+        status.set_confirmed(42);
+        let confirmation = status.confirmation().unwrap();
+        assert_eq!(confirmation.serial(), Some(42));
+        assert!(confirmation.last_confirmed().is_some());
+        assert!(!confirmation.lags(0));
+        assert!(!confirmation.is_lagging());
+    }
+
+    #[test]
+    fn parent_exchange_log_keeps_most_recent() {
+        let uri = ServiceUri::try_from("https://localhost/rfc6492/parent".to_string()).unwrap();
+        let record = |request: &str| {
+            ParentExchangeRecord::new(
+                uri.clone(),
+                request.to_string(),
+                None,
+                Some(500),
+                CmsValidation::NoResponse,
+                12,
+                Some("server error".to_string()),
+            )
+        };
+
+        let mut log = ParentExchangeLog::default();
+        log.add(record("list"), 2);
+        log.add(record("issue"), 2);
+        log.add(record("revoke"), 2);
+
+        let requests: Vec<&str> = log.records().iter().map(|record| record.request()).collect();
+        assert_eq!(requests, vec!["issue", "revoke"]);
+        assert!(log
+            .to_string()
+            .contains("revoke -> no response, HTTP 500, CMS not received, 12 ms"));
+
+        let json = serde_json::to_string(&log).unwrap();
+        assert_eq!(serde_json::from_str::<ParentExchangeLog>(&json).unwrap(), log);
+    }
+
+    #[test]
+    fn ca_labels_update() {
+        let set = |labels: &[(&str, &str)]| -> BTreeMap<String, String> {
+            labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+
+        let mut labels = CaLabels::default();
+        labels.apply(CaLabelsUpdate::new(
+            set(&[("customer", "acme"), ("env", "test")]),
+            vec![],
+        ));
+        labels.apply(CaLabelsUpdate::new(
+            set(&[("env", "prod")]),
+            vec!["customer".to_string()],
+        ));
+        assert_eq!(labels.summary(), "env=prod");
+        assert!(labels.validate().is_ok());
+
+        let json = serde_json::to_string(&labels).unwrap();
+        assert_eq!(json, r#"{"env":"prod"}"#);
+
+        for (key, value) in [("", "x"), ("-env", "x"), ("env name", "x"), ("env", "a,b")] {
+            let mut invalid = labels.clone();
+            invalid.apply(CaLabelsUpdate::new(set(&[(key, value)]), vec![]));
+            assert!(invalid.validate().is_err());
+        }
+
+        let selector = CaLabelSelector::from_str("env=prod,region").unwrap();
+        assert!(!selector.matches(&labels));
+        assert_eq!(selector.to_string(), "env=prod,region");
+    }
+
+    #[test]
+    fn cert_auth_list_text_has_plain_handles() {
+        let mut labels = CaLabels::default();
+        labels.apply(CaLabelsUpdate::new(
+            vec![("env".to_string(), "prod".to_string())].into_iter().collect(),
+            vec![],
+        ));
+        let list = CertAuthList::new(vec![
+            CertAuthSummary::new(CaHandle::from_str("ca1").unwrap(), labels),
+            CertAuthSummary::new(CaHandle::from_str("ca2").unwrap(), CaLabels::default()),
+        ]);
+
+        assert_eq!(list.to_string(), "ca1\nca2\n");
+        assert!(serde_json::to_string(&list).unwrap().contains(r#""env":"prod""#));
+
+        let selected = list.select(&CaLabelSelector::from_str("env=prod").unwrap());
+        assert_eq!(selected.to_string(), "ca1\n");
+    }
+
+    #[test]
+    fn resource_transfer_expires_after_deadline() {
+        let request = ResourceTransferRequest {
+            from: ChildHandle::from_str("from").unwrap(),
+            to: ChildHandle::from_str("to").unwrap(),
+            resources: ResourceSet::from_strs("", "10.0.0.0/16", "").unwrap(),
+            timeout_hours: None,
+        };
+
+        let transfer = ResourceTransfer::new(1, request, 1);
+        assert!(transfer.is_waiting());
+        assert!(!transfer.is_expired());
+
+        let overdue = ResourceTransfer {
+            started: Timestamp::now_minus_hours(2),
+            deadline: Timestamp::now_minus_hours(1),
+            ..transfer
+        };
+        assert!(overdue.is_expired());
+
+        // Finished transfers no longer expire.
+        let rolled_back = overdue.rolled_back("deadline passed".to_string());
+        assert_eq!(rolled_back.state(), ResourceTransferState::RolledBack);
+        assert!(!rolled_back.is_expired());
+        assert!(!overdue.completed().is_expired());
+    }
+
+    #[test]
+    fn timestamp_out_of_range() {
+        let max = Timestamp::new(i64::MAX);
+        assert!(max.to_time().is_none());
+        assert_eq!(Time::from(max), Time::new(DateTime::<Utc>::MAX_UTC));
+        assert!(!max.to_rfc3339().is_empty());
+
+        let min = Timestamp::new(i64::MIN);
+        assert_eq!(Time::from(min), Time::new(DateTime::<Utc>::MIN_UTC));
+
+        let now = Timestamp::now();
+        assert_eq!(now.to_time().map(Timestamp::from), Some(now));
+    }
 }
@@ -15,15 +15,20 @@ use rpki::{
 use crate::{
     commons::{
         api::{
-            ArgKey, ArgVal, AspaCustomer, AspaProvidersUpdate, Label, Message, RoaConfigurationUpdates, RtaName,
-            StorableParentContact,
+            ArgKey, ArgVal, AspaCustomer, AspaDefinitionList, AspaProvidersUpdate, Label, Message, RoaConfiguration,
+            RoaConfigurationUpdates, RtaName, StorableParentContact,
         },
         eventsourcing::{CommandKey, CommandKeyError, StoredCommand, WithStorableDetails},
     },
+    constants::ACTOR_USER_PREFIX,
     daemon::ca::{self, DropReason},
 };
 
-use super::{AspaDefinitionUpdates, ResourceSetSummary};
+use super::{
+    AspaDefinitionUpdates, CaLabelsUpdate, ChildIssuancePolicy, IssuanceTimingOverrides, PendingChildRequestKind,
+    PublisherValidation, ResourceSetSummary, ResourceTransferRequest, RetryPolicy, RoaAutoPilotPolicy,
+    RoaIssuanceStrategy, ScheduledChangeRequest,
+};
 
 //------------ CaCommandDetails ----------------------------------------------
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -85,6 +90,91 @@ impl CaCommandResult {
     }
 }
 
+//------------ CommandRevert -------------------------------------------------
+
+/// The compensating changes which undo the ROA or ASPA changes made by a
+/// previous command. These are applied as a new command, so that the revert
+/// shows up in the history like any other change.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandRevert {
+    Roas(RoaConfigurationUpdates),
+    Aspas(AspaDefinitionUpdates),
+}
+
+impl CommandRevert {
+    /// Returns the changes which revert the given command. The current ASPA
+    /// definitions of the CA are needed to undo updates of ASPA providers.
+    ///
+    /// Not all changes can be reverted: ROA configurations which were removed
+    /// are added again without their comment, and changes to comments only,
+    /// or removed ASPA definitions, cannot be reverted at all.
+    pub fn for_command(details: &CaCommandDetails, aspas: &AspaDefinitionList) -> Result<Self, String> {
+        let events = match details.effect() {
+            CaCommandResult::Error(_) => return Err("the command failed, so there is nothing to revert".to_string()),
+            CaCommandResult::Events(events) => events,
+        };
+
+        let mut roas = RoaConfigurationUpdates::empty();
+        let mut aspa_updates = vec![];
+        let mut aspa_removes = vec![];
+        let mut comments_changed = false;
+
+        for evt in events {
+            match evt.details() {
+                ca::CaEvtDet::RouteAuthorizationAdded { auth } => roas.remove(**auth),
+                ca::CaEvtDet::RouteAuthorizationRemoved { auth } => roas.add(RoaConfiguration::from(**auth)),
+                ca::CaEvtDet::RouteAuthorizationDisabled { auth } => roas.enable(**auth),
+                ca::CaEvtDet::RouteAuthorizationEnabled { auth } => roas.disable(**auth),
+                ca::CaEvtDet::RouteAuthorizationComment { .. } => comments_changed = true,
+                ca::CaEvtDet::AspaConfigAdded { aspa_config } => aspa_removes.push(aspa_config.customer()),
+                ca::CaEvtDet::AspaConfigUpdated { customer, update } => {
+                    let mut definition = aspas
+                        .definitions()
+                        .iter()
+                        .find(|definition| definition.customer() == *customer)
+                        .cloned()
+                        .ok_or_else(|| format!("the ASPA definition for customer {} no longer exists", customer))?;
+                    definition.apply_update(&AspaProvidersUpdate::new(
+                        update.removed().clone(),
+                        update.added().clone(),
+                    ));
+                    aspa_updates.push(definition);
+                }
+                ca::CaEvtDet::AspaConfigRemoved { customer } => {
+                    return Err(format!(
+                        "the ASPA definition for customer {} was removed, add it again instead",
+                        customer
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        let aspas = AspaDefinitionUpdates::new(aspa_updates, aspa_removes);
+
+        match (roas.is_empty(), aspas.is_empty()) {
+            (false, false) => Err("the command changed both ROAs and ASPAs".to_string()),
+            (false, true) => Ok(CommandRevert::Roas(roas)),
+            (true, false) => Ok(CommandRevert::Aspas(aspas)),
+            (true, true) if comments_changed => Err("changes to ROA comments cannot be reverted".to_string()),
+            (true, true) => Err("the command did not change any ROAs or ASPAs".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for CommandRevert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandRevert::Roas(updates) => {
+                writeln!(f, "Reverted with ROA changes:")?;
+                write!(f, "{}", updates)
+            }
+            CommandRevert::Aspas(updates) => writeln!(f, "Reverted with: {}", updates),
+        }
+    }
+}
+
 //------------ CommandHistory ------------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -92,14 +182,19 @@ pub struct CommandHistory {
     offset: usize,
     total: usize,
     commands: Vec<CommandHistoryRecord>,
+
+    // The cursor to use for the next page, if there are more commands.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<u64>,
 }
 
 impl CommandHistory {
-    pub fn new(offset: usize, total: usize, commands: Vec<CommandHistoryRecord>) -> Self {
+    pub fn new(offset: usize, total: usize, commands: Vec<CommandHistoryRecord>, next_cursor: Option<u64>) -> Self {
         CommandHistory {
             offset,
             total,
             commands,
+            next_cursor,
         }
     }
 
@@ -114,11 +209,17 @@ impl CommandHistory {
     pub fn commands(&self) -> &Vec<CommandHistoryRecord> {
         &self.commands
     }
+
+    /// Returns the cursor to get the next page of commands, if there are
+    /// more commands matching the criteria.
+    pub fn next_cursor(&self) -> Option<u64> {
+        self.next_cursor
+    }
 }
 
 impl fmt::Display for CommandHistory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "time::command::key::success")?;
+        writeln!(f, "time::actor::command::key::success")?;
 
         for command in self.commands() {
             let success_string = match &command.effect {
@@ -127,14 +228,19 @@ impl fmt::Display for CommandHistory {
             };
             writeln!(
                 f,
-                "{}::{} ::{}::{}",
+                "{}::{}::{} ::{}::{}",
                 command.time().to_rfc3339_opts(SecondsFormat::Secs, true),
+                command.actor,
                 command.summary.msg,
                 command.key,
                 success_string
             )?;
         }
 
+        if let Some(cursor) = self.next_cursor {
+            writeln!(f, "More commands available, use cursor: {}", cursor)?;
+        }
+
         Ok(())
     }
 }
@@ -291,6 +397,39 @@ impl CommandSummary {
     }
 }
 
+//------------ HistoryOrder --------------------------------------------------
+
+/// The order in which commands are listed in the history.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum HistoryOrder {
+    #[default]
+    #[serde(rename = "asc")]
+    Ascending,
+    #[serde(rename = "desc")]
+    Descending,
+}
+
+impl FromStr for HistoryOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "asc" => Ok(HistoryOrder::Ascending),
+            "desc" => Ok(HistoryOrder::Descending),
+            _ => Err(format!("expected \"asc\" or \"desc\", found: \"{}\"", s)),
+        }
+    }
+}
+
+impl fmt::Display for HistoryOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HistoryOrder::Ascending => write!(f, "asc"),
+            HistoryOrder::Descending => write!(f, "desc"),
+        }
+    }
+}
+
 //------------ CommandHistoryCriteria ----------------------------------------
 
 /// Used to limit the scope when finding commands to show in the history.
@@ -306,6 +445,17 @@ pub struct CommandHistoryCriteria {
     label_includes: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     label_excludes: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actor: Option<String>,
+    #[serde(default)]
+    include_archived: bool,
+
+    // Only include commands after this sequence in the given order. Unlike
+    // an offset, this is not affected by commands added concurrently.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cursor: Option<u64>,
+    #[serde(default)]
+    order: HistoryOrder,
 
     offset: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -329,10 +479,32 @@ impl CommandHistoryCriteria {
         self.before = Some(timestamp);
     }
 
+    /// Only include commands sent by this actor. Users can be given with or
+    /// without the "user:" prefix that is used for them in the history.
+    pub fn set_actor(&mut self, actor: String) {
+        self.actor = Some(actor);
+    }
+
+    /// Also include commands that were moved to the history archive. This
+    /// is slower, as the archived commands need to be decompressed.
+    pub fn set_include_archived(&mut self) {
+        self.include_archived = true;
+    }
+
     pub fn set_after_sequence(&mut self, sequence: u64) {
         self.after_sequence = Some(sequence)
     }
 
+    /// Continue after the command with this sequence, as returned in the
+    /// `next_cursor` of a previous page.
+    pub fn set_cursor(&mut self, sequence: u64) {
+        self.cursor = Some(sequence)
+    }
+
+    pub fn set_order(&mut self, order: HistoryOrder) {
+        self.order = order;
+    }
+
     pub fn set_rows(&mut self, rows: usize) {
         self.rows_limit = Some(rows);
     }
@@ -360,9 +532,15 @@ impl CommandHistoryCriteria {
     }
 
     pub fn matches_sequence(&self, sequence: u64) -> bool {
-        match self.after_sequence {
-            None => true,
-            Some(seq_crit) => sequence > seq_crit,
+        if let Some(seq_crit) = self.after_sequence {
+            if sequence <= seq_crit {
+                return false;
+            }
+        }
+        match (self.cursor, self.order) {
+            (None, _) => true,
+            (Some(cursor), HistoryOrder::Ascending) => sequence > cursor,
+            (Some(cursor), HistoryOrder::Descending) => sequence < cursor,
         }
     }
 
@@ -382,6 +560,27 @@ impl CommandHistoryCriteria {
         true
     }
 
+    /// Returns whether commands need to be filtered by actor. The actor is
+    /// not part of the command key, so this requires that commands are read.
+    pub fn has_actor(&self) -> bool {
+        self.actor.is_some()
+    }
+
+    pub fn matches_actor(&self, actor: &str) -> bool {
+        match &self.actor {
+            None => true,
+            Some(crit) => actor == crit || actor.strip_prefix(ACTOR_USER_PREFIX) == Some(crit),
+        }
+    }
+
+    pub fn include_archived(&self) -> bool {
+        self.include_archived
+    }
+
+    pub fn order(&self) -> HistoryOrder {
+        self.order
+    }
+
     pub fn offset(&self) -> usize {
         self.offset
     }
@@ -399,6 +598,10 @@ impl Default for CommandHistoryCriteria {
             after_sequence: None,
             label_includes: None,
             label_excludes: None,
+            actor: None,
+            include_archived: false,
+            cursor: None,
+            order: HistoryOrder::default(),
             offset: 0,
             rows_limit: Some(100),
         }
@@ -444,7 +647,38 @@ pub enum StorableCaCommand {
     ChildUnsuspend {
         child: ChildHandle,
     },
+    ChildUpdateSuspensionExempt {
+        child: ChildHandle,
+        exempt: bool,
+    },
+    ChildUpdateManualApproval {
+        child: ChildHandle,
+        manual: bool,
+    },
+    ChildIssuancePolicyUpdate {
+        policy: ChildIssuancePolicy,
+    },
+    ChildRequestQueue {
+        child: ChildHandle,
+        #[serde(default)]
+        kind: PendingChildRequestKind,
+        ki: KeyIdentifier,
+    },
+    ChildRequestApprove {
+        child: ChildHandle,
+        #[serde(default)]
+        kind: PendingChildRequestKind,
+        ki: KeyIdentifier,
+    },
+    ChildResourceTransferStart {
+        request: ResourceTransferRequest,
+    },
+    ChildResourceTransferProgress {
+        id: u64,
+    },
     GenerateNewIdKey,
+    IdRollInit,
+    IdRollActivate,
     AddParent {
         parent: ParentHandle,
         contact: StorableParentContact,
@@ -471,6 +705,9 @@ pub enum StorableCaCommand {
     KeyRollInitiate {
         older_than_seconds: i64,
     },
+    KeyRollMigrate {
+        target_signer: String,
+    },
     KeyRollActivate {
         staged_for_seconds: i64,
     },
@@ -480,6 +717,34 @@ pub enum StorableCaCommand {
     RoaDefinitionUpdates {
         updates: RoaConfigurationUpdates,
     },
+    RoaProposalAdd {
+        updates: RoaConfigurationUpdates,
+    },
+    RoaProposalApprove {
+        id: u64,
+    },
+    RoaProposalReject {
+        id: u64,
+    },
+    RoaIssuanceStrategyUpdate {
+        strategy: Option<RoaIssuanceStrategy>,
+    },
+    RoaAutoPilotUpdate {
+        policy: Option<RoaAutoPilotPolicy>,
+    },
+    ScheduledChangeAdd {
+        request: ScheduledChangeRequest,
+    },
+    ScheduledChangeApply {
+        id: u64,
+    },
+    ScheduledChangeCancel {
+        id: u64,
+    },
+    CommandRevert {
+        command: CommandKey,
+        revert: CommandRevert,
+    },
     ReissueBeforeExpiring,
     ForceReissue,
     AspasUpdate {
@@ -496,6 +761,25 @@ pub enum StorableCaCommand {
     RepoUpdate {
         service_uri: ServiceUri,
     },
+    ClassRepoUpdate {
+        resource_class_name: ResourceClassName,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        service_uri: Option<ServiceUri>,
+    },
+    RepoMigrationAbort {
+        service_uri: ServiceUri,
+    },
+    IssuanceTimingUpdate {
+        overrides: IssuanceTimingOverrides,
+    },
+    RetryPolicyUpdate {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        parent: Option<ParentHandle>,
+        policy: RetryPolicy,
+    },
+    LabelsUpdate {
+        update: CaLabelsUpdate,
+    },
     RtaPrepare {
         name: RtaName,
     },
@@ -547,11 +831,47 @@ impl WithStorableDetails for StorableCaCommand {
             StorableCaCommand::ChildUnsuspend { child } => {
                 CommandSummary::new("cmd-ca-child-unsuspend", self).with_child(child)
             }
+            StorableCaCommand::ChildUpdateSuspensionExempt { child, exempt } => {
+                CommandSummary::new("cmd-ca-child-update-suspension-exempt", self)
+                    .with_child(child)
+                    .with_arg("exempt", exempt)
+            }
+            StorableCaCommand::ChildUpdateManualApproval { child, manual } => {
+                CommandSummary::new("cmd-ca-child-update-manual-approval", self)
+                    .with_child(child)
+                    .with_arg("manual", manual)
+            }
+            StorableCaCommand::ChildIssuancePolicyUpdate { .. } => {
+                CommandSummary::new("cmd-ca-child-issuance-policy-update", self)
+            }
+            StorableCaCommand::ChildRequestQueue { child, kind, ki } => {
+                CommandSummary::new("cmd-ca-child-request-queue", self)
+                    .with_child(child)
+                    .with_arg("kind", kind)
+                    .with_key(*ki)
+            }
+            StorableCaCommand::ChildRequestApprove { child, kind, ki } => {
+                CommandSummary::new("cmd-ca-child-request-approve", self)
+                    .with_child(child)
+                    .with_arg("kind", kind)
+                    .with_key(*ki)
+            }
+            StorableCaCommand::ChildResourceTransferStart { request } => {
+                CommandSummary::new("cmd-ca-child-transfer-start", self)
+                    .with_arg("from", &request.from)
+                    .with_arg("to", &request.to)
+                    .with_arg("resources", &request.resources)
+            }
+            StorableCaCommand::ChildResourceTransferProgress { id } => {
+                CommandSummary::new("cmd-ca-child-transfer-progress", self).with_arg("id", id)
+            }
             StorableCaCommand::ChildRevokeKey { child, revoke_req } => CommandSummary::new("cmd-ca-child-revoke", self)
                 .with_child(child)
                 .with_rcn(revoke_req.class_name())
                 .with_key(revoke_req.key()),
             StorableCaCommand::GenerateNewIdKey => CommandSummary::new("cmd-ca-generate-new-id", self),
+            StorableCaCommand::IdRollInit => CommandSummary::new("cmd-ca-id-roll-init", self),
+            StorableCaCommand::IdRollActivate => CommandSummary::new("cmd-ca-id-roll-activate", self),
             StorableCaCommand::AddParent { parent, contact } => CommandSummary::new("cmd-ca-parent-add", self)
                 .with_parent(parent)
                 .with_parent_contact(contact),
@@ -583,6 +903,9 @@ impl WithStorableDetails for StorableCaCommand {
             StorableCaCommand::KeyRollInitiate { older_than_seconds } => {
                 CommandSummary::new("cmd-ca-keyroll-init", self).with_seconds(*older_than_seconds)
             }
+            StorableCaCommand::KeyRollMigrate { target_signer } => {
+                CommandSummary::new("cmd-ca-keyroll-migrate", self).with_arg("signer", target_signer)
+            }
             StorableCaCommand::KeyRollActivate { staged_for_seconds } => {
                 CommandSummary::new("cmd-ca-keyroll-activate", self).with_seconds(*staged_for_seconds)
             }
@@ -594,6 +917,38 @@ impl WithStorableDetails for StorableCaCommand {
             StorableCaCommand::RoaDefinitionUpdates { updates } => CommandSummary::new("cmd-ca-roas-updated", self)
                 .with_added(updates.added().len())
                 .with_removed(updates.removed().len()),
+            StorableCaCommand::RoaProposalAdd { updates } => CommandSummary::new("cmd-ca-roas-proposed", self)
+                .with_added(updates.added().len())
+                .with_removed(updates.removed().len()),
+            StorableCaCommand::RoaProposalApprove { id } => {
+                CommandSummary::new("cmd-ca-roas-proposal-approved", self).with_arg("proposal", id)
+            }
+            StorableCaCommand::RoaProposalReject { id } => {
+                CommandSummary::new("cmd-ca-roas-proposal-rejected", self).with_arg("proposal", id)
+            }
+            StorableCaCommand::RoaIssuanceStrategyUpdate { strategy } => match strategy {
+                Some(strategy) => {
+                    CommandSummary::new("cmd-ca-roas-strategy-updated", self).with_arg("strategy", strategy)
+                }
+                None => CommandSummary::new("cmd-ca-roas-strategy-updated", self).with_arg("strategy", "default"),
+            },
+            StorableCaCommand::RoaAutoPilotUpdate { policy } => match policy {
+                Some(policy) => CommandSummary::new("cmd-ca-roas-autopilot-updated", self).with_arg("policy", policy),
+                None => CommandSummary::new("cmd-ca-roas-autopilot-updated", self).with_arg("policy", "default"),
+            },
+            StorableCaCommand::ScheduledChangeAdd { request } => {
+                CommandSummary::new("cmd-ca-scheduled-change-added", self)
+                    .with_arg("activate_at", request.activate_at.to_rfc3339())
+            }
+            StorableCaCommand::ScheduledChangeApply { id } => {
+                CommandSummary::new("cmd-ca-scheduled-change-applied", self).with_arg("change", id)
+            }
+            StorableCaCommand::ScheduledChangeCancel { id } => {
+                CommandSummary::new("cmd-ca-scheduled-change-cancelled", self).with_arg("change", id)
+            }
+            StorableCaCommand::CommandRevert { command, .. } => {
+                CommandSummary::new("cmd-ca-command-reverted", self).with_arg("command", command)
+            }
 
             // ASPA
             StorableCaCommand::AspasUpdate { .. } => CommandSummary::new("cmd-ca-aspas-update", self),
@@ -607,6 +962,27 @@ impl WithStorableDetails for StorableCaCommand {
             StorableCaCommand::RepoUpdate { service_uri } => {
                 CommandSummary::new("cmd-ca-repo-update", self).with_service_uri(service_uri)
             }
+            StorableCaCommand::ClassRepoUpdate {
+                resource_class_name,
+                service_uri,
+            } => {
+                let summary = CommandSummary::new("cmd-ca-class-repo-update", self).with_rcn(resource_class_name);
+                match service_uri {
+                    Some(service_uri) => summary.with_service_uri(service_uri),
+                    None => summary,
+                }
+            }
+            StorableCaCommand::RepoMigrationAbort { service_uri } => {
+                CommandSummary::new("cmd-ca-repo-migration-abort", self).with_service_uri(service_uri)
+            }
+            StorableCaCommand::IssuanceTimingUpdate { .. } => {
+                CommandSummary::new("cmd-ca-issuance-timing-update", self)
+            }
+            StorableCaCommand::RetryPolicyUpdate { parent, .. } => match parent {
+                Some(parent) => CommandSummary::new("cmd-ca-retry-policy-update", self).with_parent(parent),
+                None => CommandSummary::new("cmd-ca-retry-policy-update", self),
+            },
+            StorableCaCommand::LabelsUpdate { .. } => CommandSummary::new("cmd-ca-labels-update", self),
 
             StorableCaCommand::ReissueBeforeExpiring => CommandSummary::new("cmd-ca-reissue-before-expiring", self),
             StorableCaCommand::ForceReissue => CommandSummary::new("cmd-ca-force-reissue", self),
@@ -664,11 +1040,46 @@ impl fmt::Display for StorableCaCommand {
             StorableCaCommand::ChildUnsuspend { child } => {
                 write!(f, "Unsuspend child '{}': publish its unexpired certs", child)
             }
+            StorableCaCommand::ChildUpdateSuspensionExempt { child, exempt } => {
+                if *exempt {
+                    write!(f, "Exempt child '{}' from suspension when inactive", child)
+                } else {
+                    write!(f, "Allow suspension of child '{}' when inactive", child)
+                }
+            }
+            StorableCaCommand::ChildUpdateManualApproval { child, manual } => {
+                if *manual {
+                    write!(f, "Require manual approval of requests from child '{}'", child)
+                } else {
+                    write!(f, "Process requests from child '{}' without manual approval", child)
+                }
+            }
+            StorableCaCommand::ChildIssuancePolicyUpdate { policy } => {
+                write!(f, "Update child issuance policy: {}", policy.summary())
+            }
+            StorableCaCommand::ChildRequestQueue { child, kind, ki } => {
+                write!(
+                    f,
+                    "Queue {} request from child '{}' for key '{}' for approval",
+                    kind, child, ki
+                )
+            }
+            StorableCaCommand::ChildRequestApprove { child, kind, ki } => {
+                write!(f, "Approve {} request from child '{}' for key '{}'", kind, child, ki)
+            }
+            StorableCaCommand::ChildResourceTransferStart { request } => {
+                write!(f, "Start to {}", request)
+            }
+            StorableCaCommand::ChildResourceTransferProgress { id } => {
+                write!(f, "Check progress of resource transfer {}", id)
+            }
 
             // ------------------------------------------------------------
             // Being a child (only allowed if this CA is not self-signed)
             // ------------------------------------------------------------
             StorableCaCommand::GenerateNewIdKey => write!(f, "Generate a new RFC8183 ID."),
+            StorableCaCommand::IdRollInit => write!(f, "Generate a new RFC8183 ID to roll to."),
+            StorableCaCommand::IdRollActivate => write!(f, "Activate the new RFC8183 ID."),
             StorableCaCommand::AddParent { parent, contact } => write!(f, "Add parent '{}' as '{}'", parent, contact),
             StorableCaCommand::UpdateParentContact { parent, contact } => {
                 write!(f, "Update contact for parent '{}' to '{}'", parent, contact)
@@ -715,6 +1126,9 @@ impl fmt::Display for StorableCaCommand {
                     older_than_seconds
                 )
             }
+            StorableCaCommand::KeyRollMigrate { target_signer } => {
+                write!(f, "Initiate key roll for keys not held by signer '{}'", target_signer)
+            }
             StorableCaCommand::KeyRollActivate { staged_for_seconds } => {
                 write!(
                     f,
@@ -744,8 +1158,53 @@ impl fmt::Display for StorableCaCommand {
                         write!(f, " {}", rem)?;
                     }
                 }
+                if !updates.disabled().is_empty() {
+                    write!(f, "  DISABLE:",)?;
+                    for dis in updates.disabled() {
+                        write!(f, " {}", dis)?;
+                    }
+                }
+                if !updates.enabled().is_empty() {
+                    write!(f, "  ENABLE:",)?;
+                    for en in updates.enabled() {
+                        write!(f, " {}", en)?;
+                    }
+                }
+                Ok(())
+            }
+            StorableCaCommand::RoaProposalAdd { updates } => {
+                write!(f, "Propose ROA update",)?;
+                if !updates.added().is_empty() {
+                    write!(f, "  ADD:",)?;
+                    for addition in updates.added() {
+                        write!(f, " {}", addition)?;
+                    }
+                }
+                if !updates.removed().is_empty() {
+                    write!(f, "  REMOVE:",)?;
+                    for rem in updates.removed() {
+                        write!(f, " {}", rem)?;
+                    }
+                }
                 Ok(())
             }
+            StorableCaCommand::RoaProposalApprove { id } => write!(f, "Approve ROA proposal {}", id),
+            StorableCaCommand::RoaProposalReject { id } => write!(f, "Reject ROA proposal {}", id),
+            StorableCaCommand::RoaIssuanceStrategyUpdate { strategy } => match strategy {
+                Some(strategy) => write!(f, "Update ROA issuance strategy to: {}", strategy),
+                None => write!(f, "Use the default ROA issuance strategy"),
+            },
+            StorableCaCommand::RoaAutoPilotUpdate { policy } => match policy {
+                Some(policy) => write!(f, "Update ROA auto-pilot to: {}", policy),
+                None => write!(f, "Use the default ROA auto-pilot policy"),
+            },
+            StorableCaCommand::ScheduledChangeAdd { request } => write!(f, "Schedule change {}", request),
+            StorableCaCommand::ScheduledChangeApply { id } => write!(f, "Apply scheduled change {}", id),
+            StorableCaCommand::ScheduledChangeCancel { id } => write!(f, "Cancel scheduled change {}", id),
+            StorableCaCommand::CommandRevert { command, revert } => match revert {
+                CommandRevert::Roas(_) => write!(f, "Revert the ROA changes of command '{}'", command),
+                CommandRevert::Aspas(_) => write!(f, "Revert the ASPA changes of command '{}'", command),
+            },
             StorableCaCommand::ReissueBeforeExpiring => {
                 write!(f, "Automatically re-issue objects before they would expire")
             }
@@ -775,6 +1234,37 @@ impl fmt::Display for StorableCaCommand {
             // Publishing
             // ------------------------------------------------------------
             StorableCaCommand::RepoUpdate { service_uri } => write!(f, "Update repo to server at: {}", service_uri),
+            StorableCaCommand::ClassRepoUpdate {
+                resource_class_name,
+                service_uri,
+            } => match service_uri {
+                Some(service_uri) => write!(
+                    f,
+                    "Update repo for resource class '{}' to server at: {}",
+                    resource_class_name, service_uri
+                ),
+                None => write!(
+                    f,
+                    "Update repo for resource class '{}' to the CA repo",
+                    resource_class_name
+                ),
+            },
+            StorableCaCommand::RepoMigrationAbort { service_uri } => {
+                write!(f, "Abort repo migration, return to server at: {}", service_uri)
+            }
+            StorableCaCommand::IssuanceTimingUpdate { overrides } => {
+                write!(f, "Update issuance timing overrides: {}", overrides.summary())
+            }
+            StorableCaCommand::RetryPolicyUpdate { parent, policy } => match parent {
+                Some(parent) => write!(
+                    f,
+                    "Update retry policy overrides for parent '{}': {}",
+                    parent,
+                    policy.summary()
+                ),
+                None => write!(f, "Update retry policy overrides for repository: {}", policy.summary()),
+            },
+            StorableCaCommand::LabelsUpdate { update } => write!(f, "Update labels: {}", update),
 
             // ------------------------------------------------------------
             // RTA
@@ -797,8 +1287,26 @@ impl fmt::Display for StorableCaCommand {
 #[allow(clippy::large_enum_variant)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum StorableRepositoryCommand {
-    AddPublisher { name: PublisherHandle },
-    RemovePublisher { name: PublisherHandle },
+    AddPublisher {
+        name: PublisherHandle,
+    },
+    RemovePublisher {
+        name: PublisherHandle,
+    },
+    UpdatePublisherQuota {
+        name: PublisherHandle,
+    },
+    UpdatePublisherValidation {
+        name: PublisherHandle,
+        validation: Option<PublisherValidation>,
+    },
+    StagePublisherIdCert {
+        name: PublisherHandle,
+        key: Option<KeyIdentifier>,
+    },
+    ActivatePublisherIdCert {
+        name: PublisherHandle,
+    },
 }
 
 impl WithStorableDetails for StorableRepositoryCommand {
@@ -810,6 +1318,26 @@ impl WithStorableDetails for StorableRepositoryCommand {
             StorableRepositoryCommand::RemovePublisher { name } => {
                 CommandSummary::new("pubd-publisher-remove", self).with_publisher(name)
             }
+            StorableRepositoryCommand::UpdatePublisherQuota { name } => {
+                CommandSummary::new("pubd-publisher-quota", self).with_publisher(name)
+            }
+            StorableRepositoryCommand::UpdatePublisherValidation { name, validation } => {
+                let summary = CommandSummary::new("pubd-publisher-validation", self).with_publisher(name);
+                match validation {
+                    Some(validation) => summary.with_arg("validation", validation),
+                    None => summary,
+                }
+            }
+            StorableRepositoryCommand::StagePublisherIdCert { name, key } => {
+                let summary = CommandSummary::new("pubd-publisher-id-stage", self).with_publisher(name);
+                match key {
+                    Some(key) => summary.with_id_ski(&key.to_string()),
+                    None => summary,
+                }
+            }
+            StorableRepositoryCommand::ActivatePublisherIdCert { name } => {
+                CommandSummary::new("pubd-publisher-id-activate", self).with_publisher(name)
+            }
         }
     }
 }
@@ -821,6 +1349,189 @@ impl fmt::Display for StorableRepositoryCommand {
                 write!(f, "Added publisher '{}'", name)
             }
             StorableRepositoryCommand::RemovePublisher { name } => write!(f, "Removed publisher '{}'", name),
+            StorableRepositoryCommand::UpdatePublisherQuota { name } => {
+                write!(f, "Updated quota for publisher '{}'", name)
+            }
+            StorableRepositoryCommand::UpdatePublisherValidation { name, validation } => match validation {
+                Some(validation) => write!(f, "Set validation for publisher '{}' to '{}'", name, validation),
+                None => write!(f, "Reset validation for publisher '{}' to server default", name),
+            },
+            StorableRepositoryCommand::StagePublisherIdCert { name, key } => match key {
+                Some(key) => write!(
+                    f,
+                    "Staged new ID certificate for publisher '{}' with key id: {}",
+                    name, key
+                ),
+                None => write!(f, "Cancelled ID certificate rollover for publisher '{}'", name),
+            },
+            StorableRepositoryCommand::ActivatePublisherIdCert { name } => {
+                write!(f, "Activated staged ID certificate for publisher '{}'", name)
+            }
         }
     }
 }
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use rpki::repository::aspa::ProviderAs;
+
+    use crate::{
+        commons::{api::AspaDefinition, eventsourcing::StoredEvent},
+        daemon::ca::{CaEvtDet, RoaPayloadJsonMapKey},
+        test,
+    };
+
+    use super::*;
+
+    fn details(result: CaCommandResult) -> CaCommandDetails {
+        let command = StoredCommand::new(
+            "admin".to_string(),
+            Time::now(),
+            MyHandle::from_str("ca").unwrap(),
+            1,
+            1,
+            StorableCaCommand::BgpSecDefinitionUpdates,
+            StoredEffect::Success { events: vec![] },
+        );
+        CaCommandDetails::new(command, result)
+    }
+
+    fn command_with(events: Vec<CaEvtDet>) -> CaCommandDetails {
+        let handle = MyHandle::from_str("ca").unwrap();
+        let events = events
+            .into_iter()
+            .enumerate()
+            .map(|(nr, evt)| StoredEvent::new(&handle, nr as u64 + 1, evt))
+            .collect();
+        details(CaCommandResult::Events(events))
+    }
+
+    fn auth(s: &str) -> RoaPayloadJsonMapKey {
+        RoaPayloadJsonMapKey::from(test::roa_payload(s))
+    }
+
+    fn aspa(s: &str) -> AspaDefinition {
+        AspaDefinition::from_str(s).unwrap()
+    }
+
+    fn revert(events: Vec<CaEvtDet>, aspas: &[&str]) -> Result<CommandRevert, String> {
+        let aspas = AspaDefinitionList::new(aspas.iter().map(|s| aspa(s)).collect());
+        CommandRevert::for_command(&command_with(events), &aspas)
+    }
+
+    #[test]
+    fn revert_roa_changes() {
+        let added = "10.0.0.0/24 => 65000";
+        let removed = "10.0.1.0/24 => 65000";
+        let disabled = "10.0.2.0/24 => 65000";
+        let enabled = "10.0.3.0/24 => 65000";
+
+        let events = vec![
+            CaEvtDet::RouteAuthorizationAdded { auth: auth(added) },
+            CaEvtDet::RouteAuthorizationComment {
+                auth: auth(added),
+                comment: Some("new".to_string()),
+            },
+            CaEvtDet::RouteAuthorizationRemoved { auth: auth(removed) },
+            CaEvtDet::RouteAuthorizationDisabled { auth: auth(disabled) },
+            CaEvtDet::RouteAuthorizationEnabled { auth: auth(enabled) },
+        ];
+
+        let mut expected = RoaConfigurationUpdates::empty();
+        expected.remove(test::roa_payload(added));
+        expected.add(RoaConfiguration::from(test::roa_payload(removed)));
+        expected.enable(test::roa_payload(disabled));
+        expected.disable(test::roa_payload(enabled));
+
+        assert_eq!(revert(events, &[]), Ok(CommandRevert::Roas(expected)));
+    }
+
+    #[test]
+    fn revert_aspa_changes() {
+        let added = aspa("AS65000 => AS65001");
+        let customer = added.customer();
+
+        // The providers of AS65010 were updated from AS65011 to AS65012.
+        let updated = aspa("AS65010 => AS65012");
+        let update = AspaProvidersUpdate::new(
+            vec![ProviderAs::from_str("AS65012").unwrap()],
+            vec![ProviderAs::from_str("AS65011").unwrap()],
+        );
+
+        let events = vec![
+            CaEvtDet::AspaConfigAdded { aspa_config: added },
+            CaEvtDet::AspaConfigUpdated {
+                customer: updated.customer(),
+                update,
+            },
+        ];
+
+        let expected = AspaDefinitionUpdates::new(vec![aspa("AS65010 => AS65011")], vec![customer]);
+        assert_eq!(
+            revert(events, &["AS65000 => AS65001", "AS65010 => AS65012"]),
+            Ok(CommandRevert::Aspas(expected))
+        );
+    }
+
+    #[test]
+    fn revert_impossible() {
+        let failed = details(CaCommandResult::Error("failed".to_string()));
+        assert!(CommandRevert::for_command(&failed, &AspaDefinitionList::new(vec![])).is_err());
+
+        // Nothing to revert
+        assert!(revert(vec![], &[]).is_err());
+
+        // Only comments changed
+        let comment = CaEvtDet::RouteAuthorizationComment {
+            auth: auth("10.0.0.0/24 => 65000"),
+            comment: None,
+        };
+        assert!(revert(vec![comment], &[]).is_err());
+
+        // Both ROAs and ASPAs changed
+        let events = vec![
+            CaEvtDet::RouteAuthorizationAdded {
+                auth: auth("10.0.0.0/24 => 65000"),
+            },
+            CaEvtDet::AspaConfigAdded {
+                aspa_config: aspa("AS65000 => AS65001"),
+            },
+        ];
+        assert!(revert(events, &[]).is_err());
+
+        // A removed ASPA definition cannot be restored
+        let removed = CaEvtDet::AspaConfigRemoved {
+            customer: aspa("AS65000 => AS65001").customer(),
+        };
+        assert!(revert(vec![removed], &[]).is_err());
+
+        // An updated ASPA definition which no longer exists
+        let updated = CaEvtDet::AspaConfigUpdated {
+            customer: aspa("AS65010 => AS65012").customer(),
+            update: AspaProvidersUpdate::empty(),
+        };
+        assert!(revert(vec![updated], &[]).is_err());
+    }
+
+    #[test]
+    fn revert_command_refers_to_reverted_command() {
+        let key = CommandKey::from_str("command--1700000000--12--cmd-ca-roas-updated").unwrap();
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.remove(test::roa_payload("10.0.0.0/24 => 65000"));
+
+        let command = StorableCaCommand::CommandRevert {
+            command: key.clone(),
+            revert: CommandRevert::Roas(updates),
+        };
+
+        let summary = command.summary();
+        assert_eq!(summary.label, "cmd-ca-command-reverted");
+        assert_eq!(summary.args.get("command"), Some(&key.to_string()));
+        assert_eq!(
+            command.to_string(),
+            "Revert the ROA changes of command 'command--1700000000--12--cmd-ca-roas-updated'"
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Jobs for operations which can take long, such as bulk operations, ROA
+//! imports and migrations. Jobs run in the background, and their progress
+//! and results can be queried using the id of the job.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use rpki::ca::idexchange::{CaHandle, ServiceUri};
+
+use crate::commons::api::{import::CaMigrationReport, BulkJobRequest, RepoMigrationReport, RoaImportReport, Timestamp};
+
+//------------ JobRequest ----------------------------------------------------
+
+/// What a job does. The CAs for which it is done are kept in the [`Job`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobRequest {
+    /// An operation for the selected CAs.
+    Bulk(BulkJobRequest),
+
+    /// Import ROAs for a CA.
+    RoaImport { force: bool },
+
+    /// Migrate a CA to a new repository, or resume the migration.
+    RepoMigrate { service_uri: ServiceUri },
+
+    /// Migrate CAs from another RPKI CA implementation.
+    CaMigrate,
+}
+
+impl fmt::Display for JobRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobRequest::Bulk(request) => write!(f, "{}", request),
+            JobRequest::RoaImport { force } => {
+                if *force {
+                    write!(f, "import ROAs, forced")
+                } else {
+                    write!(f, "import ROAs")
+                }
+            }
+            JobRequest::RepoMigrate { service_uri } => {
+                write!(f, "migrate to the repository at {}", service_uri)
+            }
+            JobRequest::CaMigrate => write!(f, "migrate CAs"),
+        }
+    }
+}
+
+//------------ JobOutput -----------------------------------------------------
+
+/// The report of a job, for jobs which have one.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobOutput {
+    RoaImport(RoaImportReport),
+    RepoMigration(RepoMigrationReport),
+    CaMigration(CaMigrationReport),
+}
+
+impl fmt::Display for JobOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobOutput::RoaImport(report) => write!(f, "{}", report),
+            JobOutput::RepoMigration(report) => write!(f, "{}", report),
+            JobOutput::CaMigration(report) => write!(f, "{}", report),
+        }
+    }
+}
+
+//------------ Job -----------------------------------------------------------
+
+/// A job, with its progress and the results for the CAs done so far.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Job {
+    id: String,
+    request: JobRequest,
+    state: JobState,
+
+    /// The name of the actor who started the job.
+    started_by: String,
+    started: Timestamp,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    finished: Option<Timestamp>,
+
+    /// The CAs for which the job is done.
+    cas: Vec<CaHandle>,
+
+    results: Vec<JobCaResult>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output: Option<JobOutput>,
+
+    /// Why the job failed as a whole, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Job {
+    /// Creates a new job for the given CAs. A job for no CAs is finished
+    /// immediately.
+    pub fn new(id: String, request: JobRequest, cas: Vec<CaHandle>, started_by: String) -> Self {
+        let started = Timestamp::now();
+        let (state, finished) = if cas.is_empty() {
+            (JobState::Finished, Some(started))
+        } else {
+            (JobState::Running, None)
+        };
+        Job {
+            id,
+            request,
+            state,
+            started_by,
+            started,
+            finished,
+            cas,
+            results: vec![],
+            output: None,
+            error: None,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn request(&self) -> &JobRequest {
+        &self.request
+    }
+
+    pub fn state(&self) -> JobState {
+        self.state
+    }
+
+    pub fn started_by(&self) -> &str {
+        &self.started_by
+    }
+
+    pub fn started(&self) -> Timestamp {
+        self.started
+    }
+
+    pub fn finished(&self) -> Option<Timestamp> {
+        self.finished
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == JobState::Running
+    }
+
+    pub fn cas(&self) -> &Vec<CaHandle> {
+        &self.cas
+    }
+
+    /// Returns the CAs which are not done yet, in order.
+    pub fn remaining_cas(&self) -> Vec<CaHandle> {
+        self.cas
+            .iter()
+            .filter(|ca| !self.results.iter().any(|res| &res.ca == *ca))
+            .cloned()
+            .collect()
+    }
+
+    pub fn results(&self) -> &Vec<JobCaResult> {
+        &self.results
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|res| res.error.is_some()).count()
+    }
+
+    pub fn output(&self) -> Option<&JobOutput> {
+        self.output.as_ref()
+    }
+
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+
+    /// Adds the result for a CA. The job is finished when all CAs are done.
+    pub fn add_result(&mut self, result: JobCaResult) {
+        self.results.push(result);
+        if self.is_running() && self.results.len() >= self.cas.len() {
+            self.state = JobState::Finished;
+            self.finished = Some(Timestamp::now());
+        }
+    }
+
+    pub fn set_output(&mut self, output: JobOutput) {
+        self.output = Some(output);
+    }
+
+    /// Stops the job, without doing the remaining CAs.
+    pub fn fail(&mut self, error: String) {
+        self.state = JobState::Failed;
+        self.finished = Some(Timestamp::now());
+        self.error = Some(error);
+    }
+}
+
+impl fmt::Display for Job {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Job {}: {}", self.id, self.request)?;
+        write!(
+            f,
+            "{}, {} of {} CAs done, {} failed, started {} by {}",
+            self.state,
+            self.results.len(),
+            self.cas.len(),
+            self.failed(),
+            self.started.to_rfc3339(),
+            self.started_by
+        )?;
+        if let Some(finished) = self.finished {
+            write!(f, ", finished {}", finished.to_rfc3339())?;
+        }
+        writeln!(f)?;
+        if let Some(error) = &self.error {
+            writeln!(f, "  error: {}", error)?;
+        }
+        for result in self.results.iter().filter(|res| res.error.is_some()) {
+            writeln!(f, "  {}", result)?;
+        }
+        if let Some(output) = &self.output {
+            writeln!(f)?;
+            write!(f, "{}", output)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ JobState ------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+
+    /// Done for all CAs, though it may have failed for some.
+    Finished,
+
+    /// Stopped before all CAs were done, see the error of the job.
+    Failed,
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobState::Running => write!(f, "running"),
+            JobState::Finished => write!(f, "finished"),
+            JobState::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+//------------ JobCaResult ---------------------------------------------------
+
+/// The result of a job for a single CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct JobCaResult {
+    ca: CaHandle,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl JobCaResult {
+    pub fn new(ca: CaHandle, error: Option<String>) -> Self {
+        JobCaResult { ca, error }
+    }
+
+    pub fn ca(&self) -> &CaHandle {
+        &self.ca
+    }
+
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+}
+
+impl fmt::Display for JobCaResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.error {
+            None => write!(f, "{}: ok", self.ca),
+            Some(error) => write!(f, "{}: failed: {}", self.ca, error),
+        }
+    }
+}
+
+//------------ JobList -------------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct JobList {
+    jobs: Vec<Job>,
+}
+
+impl JobList {
+    pub fn new(jobs: Vec<Job>) -> Self {
+        JobList { jobs }
+    }
+
+    pub fn jobs(&self) -> &Vec<Job> {
+        &self.jobs
+    }
+}
+
+impl fmt::Display for JobList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for job in &self.jobs {
+            writeln!(f, "{}", job)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::commons::api::{BulkOperation, CaSelector};
+
+    use super::*;
+
+    fn ca(name: &str) -> CaHandle {
+        CaHandle::from_str(name).unwrap()
+    }
+
+    #[test]
+    fn job_progress() {
+        let request = JobRequest::Bulk(BulkJobRequest::new(CaSelector::All, BulkOperation::SyncRepo));
+        let mut job = Job::new(
+            "1".to_string(),
+            request,
+            vec![ca("ca1"), ca("ca2")],
+            "admin".to_string(),
+        );
+        assert!(job.is_running());
+
+        job.add_result(JobCaResult::new(ca("ca1"), Some("unreachable".to_string())));
+        assert!(job.is_running());
+        assert_eq!(job.remaining_cas(), vec![ca("ca2")]);
+
+        // A job survives a restart as JSON, and continues with the CAs
+        // which are not done yet.
+        let json = serde_json::to_string(&job).unwrap();
+        let mut job: Job = serde_json::from_str(&json).unwrap();
+        assert_eq!(job.remaining_cas(), vec![ca("ca2")]);
+
+        job.add_result(JobCaResult::new(ca("ca2"), None));
+        assert_eq!(job.state(), JobState::Finished);
+        assert_eq!(job.failed(), 1);
+        assert!(job.finished().is_some());
+
+        let mut job = Job::new(
+            "2".to_string(),
+            JobRequest::CaMigrate,
+            vec![ca("ca1")],
+            "admin".to_string(),
+        );
+        job.fail("interrupted".to_string());
+        job.add_result(JobCaResult::new(ca("ca1"), None));
+        assert_eq!(job.state(), JobState::Failed);
+
+        let job = Job::new("3".to_string(), JobRequest::CaMigrate, vec![], "admin".to_string());
+        assert_eq!(job.state(), JobState::Finished);
+    }
+}
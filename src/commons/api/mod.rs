@@ -9,6 +9,9 @@ pub use self::aspa::*;
 mod bgpsec;
 pub use self::bgpsec::*;
 
+mod bulk;
+pub use self::bulk::*;
+
 mod ca;
 pub use self::ca::*;
 
@@ -17,11 +20,20 @@ pub use self::history::*;
 
 pub mod import;
 
+mod jobs;
+pub use self::jobs::*;
+
 mod roas;
 pub use self::roas::*;
 
 pub mod rrdp;
 
+mod search;
+pub use self::search::*;
+
+mod version;
+pub use self::version::*;
+
 use std::{collections::HashMap, fmt};
 
 use rpki::ca::csr::BgpsecCsr;
@@ -35,7 +47,10 @@ use rpki::{
     repository::resources::Asn,
 };
 
-use crate::{commons::error::RoaDeltaError, daemon::ca::RoaPayloadJsonMapKey};
+use crate::{
+    commons::{bgp::BgpAnalysisImpact, error::RoaDeltaError},
+    daemon::ca::RoaPayloadJsonMapKey,
+};
 
 // Some syntactic sugar to help this old coder's brain deal with the mess of Strings
 pub type Message = String;
@@ -54,6 +69,8 @@ pub struct ErrorResponse {
     args: HashMap<String, String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     delta_error: Option<RoaDeltaError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bgp_impact: Option<BgpAnalysisImpact>,
 }
 
 impl ErrorResponse {
@@ -63,6 +80,7 @@ impl ErrorResponse {
             msg: msg.to_string(),
             args: HashMap::new(),
             delta_error: None,
+            bgp_impact: None,
         }
     }
 
@@ -70,6 +88,10 @@ impl ErrorResponse {
         self.delta_error.as_ref()
     }
 
+    pub fn bgp_impact(&self) -> Option<&BgpAnalysisImpact> {
+        self.bgp_impact.as_ref()
+    }
+
     fn with_arg(mut self, key: &str, value: impl fmt::Display) -> Self {
         self.args.insert(key.to_string(), value.to_string());
         self
@@ -127,6 +149,23 @@ impl ErrorResponse {
         self
     }
 
+    pub fn with_bgp_impact(mut self, impact: &BgpAnalysisImpact) -> Self {
+        self.bgp_impact = Some(impact.clone());
+        self
+    }
+
+    pub fn with_roa_proposal(self, id: u64) -> Self {
+        self.with_arg("proposal", id)
+    }
+
+    pub fn with_resource_transfer(self, id: u64) -> Self {
+        self.with_arg("transfer", id)
+    }
+
+    pub fn with_scheduled_change(self, id: u64) -> Self {
+        self.with_arg("change", id)
+    }
+
     pub fn with_key_identifier(self, ki: &KeyIdentifier) -> Self {
         self.with_arg("key_id", ki)
     }
@@ -93,9 +93,8 @@ impl<'de> Deserialize<'de> for RoaAggregateKey {
 }
 
 /// Ordering is based on ASN first, and group second if there are
-/// multiple keys for the same ASN. Note: we don't currently use
-/// such groups. It's here in case we want to give users more
-/// options in future.
+/// multiple keys for the same ASN. Groups are used when ROAs are
+/// issued using the [`RoaIssuanceStrategy::Grouped`] strategy.
 impl Ord for RoaAggregateKey {
     fn cmp(&self, other: &Self) -> Ordering {
         match self.asn.cmp(&other.asn) {
@@ -129,6 +128,193 @@ impl RoaAggregateKeyFmtError {
     }
 }
 
+//------------ RoaIssuanceStrategy -----------------------------------------
+
+/// Determines how the authorized prefixes of a CA are combined into ROA
+/// objects.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "strategy")]
+pub enum RoaIssuanceStrategy {
+    /// Use one ROA per prefix, unless the number of prefixes exceeds the
+    /// configured aggregation threshold, in which case one ROA per ASN is
+    /// used until the number drops below the de-aggregation threshold.
+    #[default]
+    Auto,
+
+    /// Always use one ROA per prefix.
+    PerPrefix,
+
+    /// Always use one ROA per ASN.
+    PerAsn,
+
+    /// Use ROAs per ASN, with at most the given number of prefixes each.
+    Grouped { max_prefixes: usize },
+}
+
+impl RoaIssuanceStrategy {
+    pub fn is_auto(&self) -> bool {
+        *self == RoaIssuanceStrategy::Auto
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        match self {
+            RoaIssuanceStrategy::Grouped { max_prefixes } if *max_prefixes < 1 => {
+                Err("max_prefixes must be 1 or higher".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for RoaIssuanceStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoaIssuanceStrategy::Auto => write!(f, "auto"),
+            RoaIssuanceStrategy::PerPrefix => write!(f, "per prefix"),
+            RoaIssuanceStrategy::PerAsn => write!(f, "per ASN"),
+            RoaIssuanceStrategy::Grouped { max_prefixes } => {
+                write!(f, "per ASN, with at most {} prefixes per ROA", max_prefixes)
+            }
+        }
+    }
+}
+
+//------------ RoaIssuanceStrategyInfo -------------------------------------
+
+/// The ROA issuance strategy in use by a CA, and whether it was set for
+/// the CA or follows the server configuration.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaIssuanceStrategyInfo {
+    pub effective: RoaIssuanceStrategy,
+    pub ca_override: bool,
+}
+
+impl fmt::Display for RoaIssuanceStrategyInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ROA issuance strategy: {}", self.effective)?;
+        if self.ca_override {
+            writeln!(f, " (set for this CA)")
+        } else {
+            writeln!(f, " (server default)")
+        }
+    }
+}
+
+//------------ RoaAutoPilotPolicy ------------------------------------------
+
+/// Determines which announcements seen in BGP, which are not covered by any
+/// ROA yet, are authorized automatically by the ROA auto-pilot of a CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaAutoPilotPolicy {
+    /// Set to false to turn the auto-pilot off for a CA, even if it is
+    /// turned on for the CA in the server configuration.
+    #[serde(default = "RoaAutoPilotPolicy::dflt_enabled")]
+    pub enabled: bool,
+
+    #[serde(default = "RoaAutoPilotPolicy::dflt_min_seen_hours")]
+    pub min_seen_hours: u32,
+
+    #[serde(default = "RoaAutoPilotPolicy::dflt_max_prefix_length_v4")]
+    pub max_prefix_length_v4: u8,
+
+    #[serde(default = "RoaAutoPilotPolicy::dflt_max_prefix_length_v6")]
+    pub max_prefix_length_v6: u8,
+}
+
+impl RoaAutoPilotPolicy {
+    fn dflt_enabled() -> bool {
+        true
+    }
+
+    // Only authorize announcements which have been seen for at least
+    // a week (default), to avoid authorizing short lived hijacks or
+    // misconfigurations.
+    fn dflt_min_seen_hours() -> u32 {
+        168
+    }
+
+    // Do not authorize IPv4 announcements more specific than a /24,
+    // these are commonly filtered.
+    fn dflt_max_prefix_length_v4() -> u8 {
+        24
+    }
+
+    // Do not authorize IPv6 announcements more specific than a /48,
+    // these are commonly filtered.
+    fn dflt_max_prefix_length_v6() -> u8 {
+        48
+    }
+
+    /// Returns the policy with all default values.
+    pub fn with_defaults() -> Self {
+        RoaAutoPilotPolicy {
+            enabled: Self::dflt_enabled(),
+            min_seen_hours: Self::dflt_min_seen_hours(),
+            max_prefix_length_v4: Self::dflt_max_prefix_length_v4(),
+            max_prefix_length_v6: Self::dflt_max_prefix_length_v6(),
+        }
+    }
+
+    pub fn min_seen(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.min_seen_hours.into())
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        if self.max_prefix_length_v4 > 32 {
+            Err("max_prefix_length_v4 must be 32 or lower".to_string())
+        } else if self.max_prefix_length_v6 > 128 {
+            Err("max_prefix_length_v6 must be 128 or lower".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Display for RoaAutoPilotPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.enabled {
+            write!(
+                f,
+                "on, for announcements seen for at least {} hours, up to /{} for IPv4 and /{} for IPv6",
+                self.min_seen_hours, self.max_prefix_length_v4, self.max_prefix_length_v6
+            )
+        } else {
+            write!(f, "off")
+        }
+    }
+}
+
+//------------ RoaAutoPilotInfo --------------------------------------------
+
+/// The ROA auto-pilot policy in use by a CA, if any, and whether it was set
+/// for the CA or follows the server configuration.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaAutoPilotInfo {
+    pub effective: Option<RoaAutoPilotPolicy>,
+    pub ca_override: bool,
+}
+
+impl RoaAutoPilotInfo {
+    /// Returns the policy to use, if the auto-pilot is on.
+    pub fn enabled(&self) -> Option<&RoaAutoPilotPolicy> {
+        self.effective.as_ref().filter(|policy| policy.enabled)
+    }
+}
+
+impl fmt::Display for RoaAutoPilotInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.enabled() {
+            Some(policy) => write!(f, "ROA auto-pilot: {}", policy)?,
+            None => write!(f, "ROA auto-pilot: off")?,
+        }
+        if self.ca_override {
+            writeln!(f, " (set for this CA)")
+        } else {
+            writeln!(f, " (server default)")
+        }
+    }
+}
+
 //------------ RoaPayload --------------------------------------------------
 
 /// This type defines the definition of a Route Origin Authorization (ROA)
@@ -343,11 +529,25 @@ pub struct RoaConfiguration {
     payload: RoaPayload,
     #[serde(default)] // missing is same as no comment
     comment: Option<String>,
+    // Disabled configurations are kept, but no ROA objects are issued
+    // for them. Missing is the same as enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    disabled: bool,
 }
 
 impl RoaConfiguration {
     pub fn new(payload: RoaPayload, comment: Option<String>) -> Self {
-        RoaConfiguration { payload, comment }
+        RoaConfiguration {
+            payload,
+            comment,
+            disabled: false,
+        }
+    }
+
+    /// Returns this configuration, marked as disabled or enabled.
+    pub fn with_disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
     }
 
     pub fn unpack(self) -> (RoaPayload, Option<String>) {
@@ -362,11 +562,16 @@ impl RoaConfiguration {
         self.comment.as_ref()
     }
 
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+
     /// Ensures that the payload uses an explicit max length
     pub fn into_explicit_max_length(self) -> Self {
         RoaConfiguration {
             payload: self.payload.into_explicit_max_length(),
             comment: self.comment,
+            disabled: self.disabled,
         }
     }
 }
@@ -393,7 +598,7 @@ impl FromStr for RoaConfiguration {
         let payload = RoaPayload::from_str(payload_part)?;
         let comment = parts.next().map(|s| s.trim().to_string());
 
-        Ok(RoaConfiguration { payload, comment })
+        Ok(RoaConfiguration::new(payload, comment))
     }
 }
 
@@ -411,7 +616,7 @@ impl PartialOrd for RoaConfiguration {
 
 impl From<RoaPayload> for RoaConfiguration {
     fn from(payload: RoaPayload) -> Self {
-        RoaConfiguration { payload, comment: None }
+        RoaConfiguration::new(payload, None)
     }
 }
 
@@ -466,6 +671,10 @@ impl ConfiguredRoa {
         self.roa_configuration.payload().as_roa_ip_address()
     }
 
+    pub fn is_disabled(&self) -> bool {
+        self.roa_configuration.disabled
+    }
+
     pub fn roa_objects(&self) -> &Vec<RoaInfo> {
         &self.roa_objects
     }
@@ -479,6 +688,9 @@ impl AsRef<RoaConfiguration> for ConfiguredRoa {
 
 impl fmt::Display for ConfiguredRoa {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_disabled() {
+            write!(f, "[disabled] ")?;
+        }
         write!(f, "{}", self.roa_configuration)
     }
 }
@@ -524,27 +736,50 @@ impl fmt::Display for ConfiguredRoas {
 /// Multiple updates are sent as a single delta, because it's important that
 /// all authorizations for a given prefix are published together in order to
 /// avoid invalidating announcements.
+///
+/// Existing configurations can also be disabled, in which case they are kept
+/// but their ROA objects are withdrawn, or enabled again.
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct RoaConfigurationUpdates {
     added: Vec<RoaConfiguration>,
     removed: Vec<RoaPayload>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    disabled: Vec<RoaPayload>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    enabled: Vec<RoaPayload>,
 }
 
 impl RoaConfigurationUpdates {
     pub fn is_empty(&self) -> bool {
-        self.added.is_empty() && self.removed.is_empty()
+        self.added.is_empty() && self.removed.is_empty() && self.disabled.is_empty() && self.enabled.is_empty()
     }
 
     pub fn new(added: Vec<RoaConfiguration>, removed: Vec<RoaPayload>) -> Self {
-        RoaConfigurationUpdates { added, removed }
+        RoaConfigurationUpdates {
+            added,
+            removed,
+            disabled: vec![],
+            enabled: vec![],
+        }
     }
 
     /// Ensures that an explicit (canonical) max length is used.
     pub fn into_explicit_max_length(self) -> Self {
         let added = self.added.into_iter().map(|a| a.into_explicit_max_length()).collect();
         let removed = self.removed.into_iter().map(|r| r.into_explicit_max_length()).collect();
-
-        RoaConfigurationUpdates { added, removed }
+        let disabled = self
+            .disabled
+            .into_iter()
+            .map(|d| d.into_explicit_max_length())
+            .collect();
+        let enabled = self.enabled.into_iter().map(|e| e.into_explicit_max_length()).collect();
+
+        RoaConfigurationUpdates {
+            added,
+            removed,
+            disabled,
+            enabled,
+        }
     }
 
     /// Reports the resources included in these updates.
@@ -553,7 +788,12 @@ impl RoaConfigurationUpdates {
         for roa_config in &self.added {
             resources = resources.union(&roa_config.payload().prefix().into());
         }
-        for roa_payload in &self.removed {
+        for roa_payload in self
+            .removed
+            .iter()
+            .chain(self.disabled.iter())
+            .chain(self.enabled.iter())
+        {
             resources = resources.union(&roa_payload.prefix().into());
         }
         resources
@@ -584,6 +824,30 @@ impl RoaConfigurationUpdates {
     pub fn remove(&mut self, rem: RoaPayload) {
         self.removed.push(rem);
     }
+
+    pub fn disabled(&self) -> &Vec<RoaPayload> {
+        &self.disabled
+    }
+
+    pub fn disable(&mut self, payload: RoaPayload) {
+        self.disabled.push(payload);
+    }
+
+    pub fn enabled(&self) -> &Vec<RoaPayload> {
+        &self.enabled
+    }
+
+    pub fn enable(&mut self, payload: RoaPayload) {
+        self.enabled.push(payload);
+    }
+
+    // Parses the payload on an R:, D: or E: delta line, ignoring comments.
+    fn payload_from_delta_line(line: &str, stripped: &str) -> Result<RoaPayload, AuthorizationFmtError> {
+        match stripped.split('#').next() {
+            Some(payload_str) => RoaPayload::from_str(payload_str.trim()),
+            None => Err(AuthorizationFmtError::delta(line)),
+        }
+    }
 }
 
 impl fmt::Display for RoaConfigurationUpdates {
@@ -594,6 +858,12 @@ impl fmt::Display for RoaConfigurationUpdates {
         for r in &self.removed {
             writeln!(f, "R: {}", r)?;
         }
+        for d in &self.disabled {
+            writeln!(f, "D: {}", d)?;
+        }
+        for e in &self.enabled {
+            writeln!(f, "E: {}", e)?;
+        }
         Ok(())
     }
 }
@@ -602,8 +872,7 @@ impl FromStr for RoaConfigurationUpdates {
     type Err = AuthorizationFmtError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut added = vec![];
-        let mut removed = vec![];
+        let mut updates = RoaConfigurationUpdates::empty();
 
         for line in s.lines() {
             let line = line.trim();
@@ -612,21 +881,415 @@ impl FromStr for RoaConfigurationUpdates {
                 continue;
             } else if let Some(stripped) = line.strip_prefix("A:") {
                 let auth = RoaConfiguration::from_str(stripped.trim())?;
-                added.push(auth);
+                updates.add(auth);
             } else if let Some(stripped) = line.strip_prefix("R:") {
-                // ignore comments on remove lines
-                if let Some(payload_str) = stripped.split('#').next() {
-                    let auth = RoaPayload::from_str(payload_str.trim())?;
-                    removed.push(auth);
-                } else {
-                    return Err(AuthorizationFmtError::delta(line));
-                }
+                updates.remove(Self::payload_from_delta_line(line, stripped)?);
+            } else if let Some(stripped) = line.strip_prefix("D:") {
+                updates.disable(Self::payload_from_delta_line(line, stripped)?);
+            } else if let Some(stripped) = line.strip_prefix("E:") {
+                updates.enable(Self::payload_from_delta_line(line, stripped)?);
             } else {
                 return Err(AuthorizationFmtError::delta(line));
             }
         }
 
-        Ok(RoaConfigurationUpdates { added, removed })
+        Ok(updates)
+    }
+}
+
+//------------ RoaImport ---------------------------------------------------
+
+/// Route objects to import as ROA configurations, e.g. when migrating
+/// from an IRR. See [`RoaImportFormat`] for the supported formats.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaImport {
+    format: RoaImportFormat,
+    content: String,
+}
+
+impl RoaImport {
+    pub fn new(format: RoaImportFormat, content: String) -> Self {
+        RoaImport { format, content }
+    }
+
+    pub fn format(&self) -> RoaImportFormat {
+        self.format
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Parses the content into ROA payloads, in the order in which they
+    /// were found.
+    pub fn payloads(&self) -> Result<Vec<RoaPayload>, AuthorizationFmtError> {
+        match self.format {
+            RoaImportFormat::Rpsl => Self::parse_rpsl(&self.content),
+            RoaImportFormat::Csv => Self::parse_csv(&self.content),
+        }
+    }
+
+    /// Parses RPSL route and route6 objects. Objects are separated by
+    /// empty lines, other object types are ignored. RPSL has no max
+    /// length, so the payloads will not have a max length either.
+    fn parse_rpsl(s: &str) -> Result<Vec<RoaPayload>, AuthorizationFmtError> {
+        let mut res = vec![];
+
+        let mut prefix: Option<&str> = None;
+        let mut origin: Option<&str> = None;
+
+        for line in s.lines().chain(std::iter::once("")) {
+            if line.starts_with('%') || line.starts_with('#') {
+                // comments, e.g. in whois output
+                continue;
+            }
+
+            if line.trim().is_empty() {
+                // end of object
+                match (prefix.take(), origin.take()) {
+                    (Some(prefix), Some(origin)) => res.push(Self::payload(prefix, origin, None)?),
+                    (Some(prefix), None) => return Err(AuthorizationFmtError::rpsl(prefix)),
+                    _ => {}
+                }
+                continue;
+            }
+
+            // Attribute names are case insensitive, continuation lines
+            // start with whitespace or '+' and will not contain a colon
+            // separated attribute that we are interested in.
+            if line.starts_with(|c: char| c.is_whitespace() || c == '+') {
+                continue;
+            }
+
+            if let Some((attribute, value)) = line.split_once(':') {
+                // values may have trailing comments
+                let value = value.split('#').next().unwrap_or_default().trim();
+                match attribute.trim().to_ascii_lowercase().as_str() {
+                    "route" | "route6" => prefix = Some(value),
+                    "origin" => origin = Some(value),
+                    _ => {}
+                }
+            } else {
+                return Err(AuthorizationFmtError::rpsl(line));
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Parses CSV using the following format, where the max length is
+    /// optional and the origin may use an 'AS' prefix:
+    ///
+    ///   # prefix,origin,maxlength
+    ///   192.168.0.0/16,AS64496,24
+    ///   2001:db8::/32,64496
+    fn parse_csv(s: &str) -> Result<Vec<RoaPayload>, AuthorizationFmtError> {
+        let mut res = vec![];
+
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.eq_ignore_ascii_case("prefix,origin,maxlength") {
+                continue;
+            }
+
+            let mut fields = line.split(',').map(|field| field.trim());
+            let prefix = fields.next().ok_or_else(|| AuthorizationFmtError::csv(line))?;
+            let origin = fields.next().ok_or_else(|| AuthorizationFmtError::csv(line))?;
+            let max_length = match fields.next() {
+                None | Some("") => None,
+                Some(max_length) => Some(u8::from_str(max_length).map_err(|_| AuthorizationFmtError::csv(line))?),
+            };
+
+            if fields.next().is_some() {
+                return Err(AuthorizationFmtError::csv(line));
+            }
+
+            res.push(Self::payload(prefix, origin, max_length)?);
+        }
+
+        Ok(res)
+    }
+
+    fn payload(prefix: &str, origin: &str, max_length: Option<u8>) -> Result<RoaPayload, AuthorizationFmtError> {
+        let prefix = TypedPrefix::from_str(prefix)?;
+
+        let origin = origin
+            .strip_prefix("AS")
+            .or_else(|| origin.strip_prefix("as"))
+            .unwrap_or(origin);
+        let asn = AsNumber::from_str(origin)?;
+
+        Ok(RoaPayload::new(asn, prefix, max_length))
+    }
+}
+
+//------------ RoaImportFormat ---------------------------------------------
+
+/// The supported formats for importing ROA configurations:
+/// - RPSL route and route6 objects, e.g. as exported from an IRR
+/// - CSV using prefix,origin,maxlength
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoaImportFormat {
+    Rpsl,
+    Csv,
+}
+
+impl FromStr for RoaImportFormat {
+    type Err = AuthorizationFmtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rpsl" => Ok(RoaImportFormat::Rpsl),
+            "csv" => Ok(RoaImportFormat::Csv),
+            _ => Err(AuthorizationFmtError::Format(s.to_string())),
+        }
+    }
+}
+
+//------------ RoaImportReport ---------------------------------------------
+
+/// Describes the outcome of a [`RoaImport`] for a CA. The updates contain
+/// the imported payloads which are not yet configured. Imported payloads
+/// that conflict with the CA's resources are left out of the updates,
+/// other conflicts are reported, but do not stop the import.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaImportReport {
+    updates: RoaConfigurationUpdates,
+    unchanged: Vec<RoaPayload>,
+    conflicts: Vec<RoaImportConflict>,
+}
+
+impl RoaImportReport {
+    /// Determines the updates and conflicts for importing the given
+    /// payloads, given the existing ROAs and resources held by a CA.
+    pub fn new(imported: Vec<RoaPayload>, existing: &[ConfiguredRoa], resources_held: &ResourceSet) -> Self {
+        let mut report = RoaImportReport::default();
+
+        let existing: Vec<RoaPayload> = existing
+            .iter()
+            .map(|configured| configured.payload().into_explicit_max_length())
+            .collect();
+
+        let mut seen: Vec<RoaPayload> = vec![];
+
+        for payload in imported {
+            let explicit = payload.into_explicit_max_length();
+
+            if seen.contains(&explicit) {
+                report.add_conflict(payload, RoaImportConflictReason::Duplicate);
+                continue;
+            }
+            seen.push(explicit);
+
+            if existing.contains(&explicit) {
+                report.unchanged.push(payload);
+            } else if !payload.max_length_valid() {
+                report.add_conflict(payload, RoaImportConflictReason::InvalidMaxLength);
+            } else if !resources_held.contains_roa_address(&payload.as_roa_ip_address()) {
+                report.add_conflict(payload, RoaImportConflictReason::NotHeld);
+            } else {
+                let same_prefix: Vec<RoaPayload> = existing
+                    .iter()
+                    .filter(|existing| existing.prefix() == payload.prefix())
+                    .copied()
+                    .collect();
+
+                let other_max_length: Vec<RoaPayload> = same_prefix
+                    .iter()
+                    .filter(|existing| existing.asn() == payload.asn())
+                    .copied()
+                    .collect();
+
+                let other_origin: Vec<RoaPayload> = same_prefix
+                    .iter()
+                    .filter(|existing| existing.asn() != payload.asn())
+                    .copied()
+                    .collect();
+
+                if !other_max_length.is_empty() {
+                    report.add_conflict(payload, RoaImportConflictReason::OtherMaxLength(other_max_length));
+                }
+                if !other_origin.is_empty() {
+                    report.add_conflict(payload, RoaImportConflictReason::OtherOrigin(other_origin));
+                }
+
+                report.updates.add(RoaConfiguration::from(payload));
+            }
+        }
+
+        report
+    }
+
+    fn add_conflict(&mut self, payload: RoaPayload, reason: RoaImportConflictReason) {
+        self.conflicts.push(RoaImportConflict { payload, reason });
+    }
+
+    pub fn updates(&self) -> &RoaConfigurationUpdates {
+        &self.updates
+    }
+
+    pub fn unchanged(&self) -> &Vec<RoaPayload> {
+        &self.unchanged
+    }
+
+    pub fn conflicts(&self) -> &Vec<RoaImportConflict> {
+        &self.conflicts
+    }
+}
+
+impl fmt::Display for RoaImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.updates.is_empty() {
+            writeln!(f, "No ROA configurations to add.")?;
+        } else {
+            writeln!(f, "ROA configurations to add:")?;
+            write!(f, "{}", self.updates)?;
+        }
+
+        if !self.unchanged.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Already configured:")?;
+            for payload in &self.unchanged {
+                writeln!(f, "  {}", payload)?;
+            }
+        }
+
+        if !self.conflicts.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Conflicts:")?;
+            for conflict in &self.conflicts {
+                writeln!(f, "  {}", conflict)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ RoaImportConflict -------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaImportConflict {
+    payload: RoaPayload,
+    reason: RoaImportConflictReason,
+}
+
+impl RoaImportConflict {
+    pub fn payload(&self) -> RoaPayload {
+        self.payload
+    }
+
+    pub fn reason(&self) -> &RoaImportConflictReason {
+        &self.reason
+    }
+}
+
+impl fmt::Display for RoaImportConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.payload)?;
+        match &self.reason {
+            RoaImportConflictReason::Duplicate => write!(f, "duplicate in import, skipped"),
+            RoaImportConflictReason::InvalidMaxLength => write!(f, "invalid max length, skipped"),
+            RoaImportConflictReason::NotHeld => write!(f, "prefix not held, skipped"),
+            RoaImportConflictReason::OtherMaxLength(existing) => {
+                write!(f, "existing ROA(s) for this origin with other max length:")?;
+                for payload in existing {
+                    write!(f, " {}", payload)?;
+                }
+                Ok(())
+            }
+            RoaImportConflictReason::OtherOrigin(existing) => {
+                write!(f, "existing ROA(s) for this prefix with other origin:")?;
+                for payload in existing {
+                    write!(f, " {}", payload)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "existing")]
+pub enum RoaImportConflictReason {
+    Duplicate,
+    InvalidMaxLength,
+    NotHeld,
+    OtherMaxLength(Vec<RoaPayload>),
+    OtherOrigin(Vec<RoaPayload>),
+}
+
+//------------ RoaProposal -------------------------------------------------
+
+/// A ROA delta which was proposed for a CA, but which still needs to be
+/// approved before the CA will issue the resulting ROA objects.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaProposal {
+    id: u64,
+    proposer: String,
+    updates: RoaConfigurationUpdates,
+}
+
+impl RoaProposal {
+    pub fn new(id: u64, proposer: String, updates: RoaConfigurationUpdates) -> Self {
+        RoaProposal { id, proposer, updates }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn proposer(&self) -> &str {
+        &self.proposer
+    }
+
+    pub fn updates(&self) -> &RoaConfigurationUpdates {
+        &self.updates
+    }
+
+    pub fn unpack(self) -> (u64, String, RoaConfigurationUpdates) {
+        (self.id, self.proposer, self.updates)
+    }
+}
+
+impl fmt::Display for RoaProposal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Proposal {} by {}:", self.id, self.proposer)?;
+        write!(f, "{}", self.updates)
+    }
+}
+
+//------------ RoaProposalList ---------------------------------------------
+
+/// The pending ROA proposals for a CA, ordered by id.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaProposalList(Vec<RoaProposal>);
+
+impl RoaProposalList {
+    pub fn new(proposals: Vec<RoaProposal>) -> Self {
+        RoaProposalList(proposals)
+    }
+
+    pub fn proposals(&self) -> &Vec<RoaProposal> {
+        &self.0
+    }
+
+    pub fn unpack(self) -> Vec<RoaProposal> {
+        self.0
+    }
+}
+
+impl fmt::Display for RoaProposalList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            writeln!(f, "No pending ROA proposals.")
+        } else {
+            for proposal in &self.0 {
+                writeln!(f, "{}", proposal)?;
+            }
+            Ok(())
+        }
     }
 }
 
@@ -880,6 +1543,9 @@ pub enum AuthorizationFmtError {
     Asn(String),
     Auth(String),
     Delta(String),
+    Rpsl(String),
+    Csv(String),
+    Format(String),
 }
 
 impl fmt::Display for AuthorizationFmtError {
@@ -889,6 +1555,9 @@ impl fmt::Display for AuthorizationFmtError {
             AuthorizationFmtError::Asn(s) => write!(f, "Invalid asn in string: {}", s),
             AuthorizationFmtError::Auth(s) => write!(f, "Invalid authorization string: {}", s),
             AuthorizationFmtError::Delta(s) => write!(f, "Invalid authorization delta string: {}", s),
+            AuthorizationFmtError::Rpsl(s) => write!(f, "Invalid or incomplete RPSL route object: {}", s),
+            AuthorizationFmtError::Csv(s) => write!(f, "Invalid CSV line, expected prefix,origin,maxlength: {}", s),
+            AuthorizationFmtError::Format(s) => write!(f, "Unsupported import format '{}', use 'rpsl' or 'csv'", s),
         }
     }
 }
@@ -909,6 +1578,14 @@ impl AuthorizationFmtError {
     pub fn delta(s: &str) -> Self {
         AuthorizationFmtError::Delta(s.to_string())
     }
+
+    fn rpsl(s: &str) -> Self {
+        AuthorizationFmtError::Rpsl(s.to_string())
+    }
+
+    fn csv(s: &str) -> Self {
+        AuthorizationFmtError::Csv(s.to_string())
+    }
 }
 
 //------------ Tests -------------------------------------------------------
@@ -917,7 +1594,7 @@ impl AuthorizationFmtError {
 mod tests {
     use super::*;
 
-    use crate::test::{roa_configuration, roa_payload};
+    use crate::test::{configured_roa, resources, roa_configuration, roa_payload};
 
     #[test]
     fn parse_delta() {
@@ -928,6 +1605,8 @@ mod tests {
             "A: 192.168.0.0/16 => 64496 # ROA comment\n",
             "A: 192.168.1.0/24 => 64496\n",
             "R: 192.168.3.0/24 => 64496 # ignored comment for removed ROA\n",
+            "D: 192.168.4.0/24 => 64496 # ignored comment for disabled ROA\n",
+            "E: 192.168.5.0/24 => 64496\n",
         );
 
         let expected = {
@@ -937,7 +1616,10 @@ mod tests {
             ];
 
             let removed = vec![roa_payload("192.168.3.0/24 => 64496")];
-            RoaConfigurationUpdates::new(added, removed)
+            let mut updates = RoaConfigurationUpdates::new(added, removed);
+            updates.disable(roa_payload("192.168.4.0/24 => 64496"));
+            updates.enable(roa_payload("192.168.5.0/24 => 64496"));
+            updates
         };
 
         let parsed = RoaConfigurationUpdates::from_str(delta).unwrap();
@@ -1071,4 +1753,99 @@ mod tests {
         check("10.0.0.0/15-17 => 64496", 4);
         check("10.0.0.0/15-18 => 64496", 8);
     }
+
+    #[test]
+    fn roa_import_rpsl() {
+        let rpsl = concat!(
+            "% whois comment\n",
+            "route:          192.168.0.0/16\n",
+            "descr:          Example\n",
+            "                continued description\n",
+            "origin:         AS64496 # trailing comment\n",
+            "mnt-by:         EXAMPLE-MNT\n",
+            "\n",
+            "mntner:         EXAMPLE-MNT\n",
+            "\n",
+            "route6:         2001:db8::/32\n",
+            "origin:         AS64497\n",
+        );
+
+        let import = RoaImport::new(RoaImportFormat::Rpsl, rpsl.to_string());
+        assert_eq!(
+            import.payloads().unwrap(),
+            vec![
+                roa_payload("192.168.0.0/16 => 64496"),
+                roa_payload("2001:db8::/32 => 64497")
+            ]
+        );
+
+        let missing_origin = RoaImport::new(RoaImportFormat::Rpsl, "route: 192.168.0.0/16\n".to_string());
+        assert!(missing_origin.payloads().is_err());
+    }
+
+    #[test]
+    fn roa_import_csv() {
+        let csv = concat!(
+            "prefix,origin,maxlength\n",
+            "# comment\n",
+            "192.168.0.0/16,AS64496,24\n",
+            "2001:db8::/32, 64497\n",
+            "10.0.0.0/8,64498,\n",
+        );
+
+        let import = RoaImport::new(RoaImportFormat::Csv, csv.to_string());
+        assert_eq!(
+            import.payloads().unwrap(),
+            vec![
+                roa_payload("192.168.0.0/16-24 => 64496"),
+                roa_payload("2001:db8::/32 => 64497"),
+                roa_payload("10.0.0.0/8 => 64498")
+            ]
+        );
+
+        let extra_field = RoaImport::new(RoaImportFormat::Csv, "10.0.0.0/8,64498,8,extra".to_string());
+        assert!(extra_field.payloads().is_err());
+    }
+
+    #[test]
+    fn roa_import_report() {
+        let existing = vec![
+            configured_roa("10.0.0.0/24 => 64496"),
+            configured_roa("10.0.1.0/24-24 => 64496"),
+            configured_roa("10.0.2.0/24 => 64496"),
+        ];
+        let held = resources("", "10.0.0.0/16", "");
+
+        let imported = vec![
+            roa_payload("10.0.0.0/24-24 => 64496"), // unchanged
+            roa_payload("10.0.1.0/24 => 64497"),    // other origin
+            roa_payload("10.0.2.0/24-25 => 64496"), // other max length
+            roa_payload("10.0.3.0/24 => 64496"),    // new
+            roa_payload("10.0.3.0/24 => 64496"),    // duplicate
+            roa_payload("10.1.0.0/24 => 64496"),    // not held
+        ];
+
+        let report = RoaImportReport::new(imported, &existing, &held);
+
+        assert_eq!(report.unchanged(), &vec![roa_payload("10.0.0.0/24-24 => 64496")]);
+        assert_eq!(
+            report.updates().added(),
+            &vec![
+                roa_configuration("10.0.1.0/24 => 64497"),
+                roa_configuration("10.0.2.0/24-25 => 64496"),
+                roa_configuration("10.0.3.0/24 => 64496"),
+            ]
+        );
+
+        let reasons: Vec<&RoaImportConflictReason> = report.conflicts().iter().map(|c| c.reason()).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                &RoaImportConflictReason::OtherOrigin(vec![roa_payload("10.0.1.0/24-24 => 64496")]),
+                &RoaImportConflictReason::OtherMaxLength(vec![roa_payload("10.0.2.0/24-24 => 64496")]),
+                &RoaImportConflictReason::Duplicate,
+                &RoaImportConflictReason::NotHeld,
+            ]
+        );
+    }
 }
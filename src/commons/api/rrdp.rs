@@ -59,6 +59,12 @@ impl AsRef<Uuid> for RrdpSession {
     }
 }
 
+impl From<Uuid> for RrdpSession {
+    fn from(uuid: Uuid) -> Self {
+        RrdpSession(uuid)
+    }
+}
+
 impl From<RrdpSession> for Uuid {
     fn from(rrdp_session: RrdpSession) -> Self {
         rrdp_session.0
@@ -377,6 +383,10 @@ impl CurrentObjects {
         self.0.iter()
     }
 
+    pub fn get(&self, uri: &CurrentObjectUri) -> Option<&Base64> {
+        self.0.get(uri)
+    }
+
     pub fn try_into_publish_elements(self) -> KrillResult<Vec<PublishElement>> {
         let mut elements = vec![];
 
@@ -851,6 +861,10 @@ impl DeltaData {
         &self.random
     }
 
+    pub fn time(&self) -> Time {
+        self.time
+    }
+
     pub fn older_than_seconds(&self, seconds: i64) -> bool {
         let then = Time::now() - Duration::seconds(seconds);
         self.time < then
@@ -0,0 +1,223 @@
+//! Searching for resources, ROAs and URIs across all CAs and publishers.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use rpki::{
+    ca::idexchange::{CaHandle, ChildHandle, PublisherHandle},
+    repository::resources::{Asn, ResourceSet},
+    uri,
+};
+
+use crate::commons::api::{RoaConfiguration, TypedPrefix};
+
+//------------ SearchQuery ---------------------------------------------------
+
+/// What to look for. A query is interpreted as an ASN (e.g. AS65000), a
+/// prefix (e.g. 192.168.0.0/24), an rsync or https URI, or otherwise as
+/// text to look for in the names of CAs, children and publishers and in
+/// ROA comments.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SearchQuery {
+    Asn(Asn),
+    Prefix(TypedPrefix),
+    Uri(String),
+    Text(String),
+}
+
+impl SearchQuery {
+    /// Returns the resources to look for, if this query is for an ASN or
+    /// a prefix.
+    pub fn resources(&self) -> Option<ResourceSet> {
+        match self {
+            SearchQuery::Asn(asn) => ResourceSet::from_strs(&asn.to_string(), "", "").ok(),
+            SearchQuery::Prefix(TypedPrefix::V4(pfx)) => ResourceSet::from_strs("", &pfx.to_string(), "").ok(),
+            SearchQuery::Prefix(TypedPrefix::V6(pfx)) => ResourceSet::from_strs("", "", &pfx.to_string()).ok(),
+            SearchQuery::Uri(_) | SearchQuery::Text(_) => None,
+        }
+    }
+
+    /// Returns true if the ROA configuration matches this query. ROAs match
+    /// if they are for the ASN, if their prefix covers the prefix, or if
+    /// their comment contains the text.
+    pub fn matches_roa(&self, roa: &RoaConfiguration) -> bool {
+        match self {
+            SearchQuery::Asn(asn) => Asn::from(roa.payload().asn()) == *asn,
+            SearchQuery::Prefix(prefix) => roa.payload().prefix().matching_or_less_specific(prefix),
+            SearchQuery::Uri(_) => false,
+            SearchQuery::Text(_) => roa.comment().map(|comment| self.matches_text(comment)).unwrap_or(false),
+        }
+    }
+
+    /// Returns true if this is a text query and the given name or comment
+    /// contains the text, ignoring case.
+    pub fn matches_text(&self, s: &str) -> bool {
+        match self {
+            SearchQuery::Text(text) => s.to_lowercase().contains(&text.to_lowercase()),
+            _ => false,
+        }
+    }
+
+    /// Returns true if this is a URI query for a URI under the given base.
+    pub fn matches_base_uri(&self, base_uri: &uri::Rsync) -> bool {
+        match self {
+            SearchQuery::Uri(uri) => uri.starts_with(base_uri.as_str()),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for SearchQuery {
+    type Err = SearchQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            Err(SearchQueryError)
+        } else if s.starts_with("rsync://") || s.starts_with("https://") {
+            Ok(SearchQuery::Uri(s.to_string()))
+        } else if let Ok(asn) = Asn::from_str(s) {
+            Ok(SearchQuery::Asn(asn))
+        } else if s.contains('/') {
+            TypedPrefix::from_str(s)
+                .map(SearchQuery::Prefix)
+                .map_err(|_| SearchQueryError)
+        } else {
+            Ok(SearchQuery::Text(s.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchQuery::Asn(asn) => asn.fmt(f),
+            SearchQuery::Prefix(prefix) => prefix.fmt(f),
+            SearchQuery::Uri(uri) => uri.fmt(f),
+            SearchQuery::Text(text) => text.fmt(f),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchQueryError;
+
+impl fmt::Display for SearchQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Search query must be an ASN, a prefix, a URI or non-empty text")
+    }
+}
+
+//------------ SearchMatch ---------------------------------------------------
+
+/// Something found by a search.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum SearchMatch {
+    /// A CA with a matching name.
+    Ca { ca: CaHandle },
+
+    /// A CA which holds the resources.
+    CaResources { ca: CaHandle },
+
+    /// A ROA configuration of a CA.
+    Roa { ca: CaHandle, roa: RoaConfiguration },
+
+    /// A child which has a matching name, or which the resources were
+    /// delegated to.
+    Child { ca: CaHandle, child: ChildHandle },
+
+    /// A publisher with a matching name, or which publishes the URI.
+    Publisher {
+        publisher: PublisherHandle,
+        base_uri: uri::Rsync,
+    },
+}
+
+impl fmt::Display for SearchMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchMatch::Ca { ca } => write!(f, "CA '{}'", ca),
+            SearchMatch::CaResources { ca } => write!(f, "CA '{}' holds the resources", ca),
+            SearchMatch::Roa { ca, roa } => write!(f, "CA '{}' has ROA: {}", ca, roa),
+            SearchMatch::Child { ca, child } => write!(f, "CA '{}' has child '{}'", ca, child),
+            SearchMatch::Publisher { publisher, base_uri } => {
+                write!(f, "Publisher '{}' publishes under {}", publisher, base_uri)
+            }
+        }
+    }
+}
+
+//------------ SearchResults -------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SearchResults {
+    query: String,
+    matches: Vec<SearchMatch>,
+}
+
+impl SearchResults {
+    pub fn new(query: &SearchQuery, matches: Vec<SearchMatch>) -> Self {
+        SearchResults {
+            query: query.to_string(),
+            matches,
+        }
+    }
+
+    pub fn matches(&self) -> &Vec<SearchMatch> {
+        &self.matches
+    }
+}
+
+impl fmt::Display for SearchResults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.matches.is_empty() {
+            writeln!(f, "Nothing found for '{}'.", self.query)
+        } else {
+            for found in &self.matches {
+                writeln!(f, "{}", found)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_search_query() {
+        assert_eq!(
+            SearchQuery::from_str("AS65000").unwrap(),
+            SearchQuery::Asn(Asn::from_u32(65000))
+        );
+        assert_eq!(
+            SearchQuery::from_str("192.168.0.0/24").unwrap(),
+            SearchQuery::Prefix(TypedPrefix::from_str("192.168.0.0/24").unwrap())
+        );
+        assert_eq!(
+            SearchQuery::from_str("rsync://localhost/repo/ca/").unwrap(),
+            SearchQuery::Uri("rsync://localhost/repo/ca/".to_string())
+        );
+        assert_eq!(
+            SearchQuery::from_str(" customer ").unwrap(),
+            SearchQuery::Text("customer".to_string())
+        );
+        assert!(SearchQuery::from_str("").is_err());
+        assert!(SearchQuery::from_str("not/a/prefix").is_err());
+    }
+
+    #[test]
+    fn roa_matches_covering_prefix() {
+        let roa = RoaConfiguration::from_str("192.168.0.0/16-24 => 64496 # Customer A").unwrap();
+
+        assert!(SearchQuery::from_str("192.168.1.0/24").unwrap().matches_roa(&roa));
+        assert!(!SearchQuery::from_str("10.0.0.0/24").unwrap().matches_roa(&roa));
+        assert!(SearchQuery::from_str("AS64496").unwrap().matches_roa(&roa));
+        assert!(SearchQuery::from_str("customer a").unwrap().matches_roa(&roa));
+    }
+}
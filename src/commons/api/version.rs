@@ -0,0 +1,139 @@
+//! Versions of the Krill API.
+//!
+//! Each version of the API is served under `/api/<version>/`. Within a
+//! version, changes are backward compatible: endpoints and fields may be
+//! added, but they are not removed, renamed or given a different meaning.
+//! Incompatible changes are only made in a new version, which is added here
+//! together with the serializers for what actually changed. Older versions
+//! are marked as deprecated before they are removed. Currently there is
+//! only v1.
+//!
+//! Clients can learn which versions a server supports, and which of these
+//! are deprecated, from `/api/versions`, and use the most recent version
+//! they support themselves.
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+//------------ ApiVersion ----------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiVersion {
+    V1,
+}
+
+impl ApiVersion {
+    pub const LATEST: ApiVersion = ApiVersion::V1;
+
+    /// Returns all versions supported by this build, oldest first.
+    pub fn all() -> Vec<ApiVersion> {
+        vec![ApiVersion::V1]
+    }
+
+    pub fn is_deprecated(self) -> bool {
+        self != Self::LATEST
+    }
+
+    /// Returns the version and the remaining path, including the leading
+    /// slash, if the path is for a supported API version.
+    pub fn split_path(path: &str) -> Option<(ApiVersion, &str)> {
+        let remaining = path.strip_prefix("/api/")?;
+        let (version, remaining) = match remaining.find('/') {
+            Some(pos) => remaining.split_at(pos),
+            None => (remaining, ""),
+        };
+        ApiVersion::from_str(version).ok().map(|version| (version, remaining))
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "v1" => Ok(ApiVersion::V1),
+            _ => Err(format!("unsupported API version: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiVersion::V1 => write!(f, "v1"),
+        }
+    }
+}
+
+//------------ ApiVersions ---------------------------------------------------
+
+/// The API versions supported by a server.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiVersions {
+    latest: ApiVersion,
+    versions: Vec<ApiVersionInfo>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiVersionInfo {
+    version: ApiVersion,
+    deprecated: bool,
+}
+
+impl ApiVersions {
+    /// Returns the versions supported by this build.
+    pub fn current() -> Self {
+        ApiVersions {
+            latest: ApiVersion::LATEST,
+            versions: ApiVersion::all()
+                .into_iter()
+                .map(|version| ApiVersionInfo {
+                    version,
+                    deprecated: version.is_deprecated(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the most recent version supported by both the server and the
+    /// client, preferring versions which are not deprecated, if any.
+    pub fn negotiate(&self, supported: &[ApiVersion]) -> Option<ApiVersion> {
+        let mut common: Vec<&ApiVersionInfo> = self
+            .versions
+            .iter()
+            .filter(|info| supported.contains(&info.version))
+            .collect();
+        common.sort_by_key(|info| (!info.deprecated, info.version));
+        common.last().map(|info| info.version)
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_path() {
+        assert_eq!(
+            ApiVersion::split_path("/api/v1/cas/ca"),
+            Some((ApiVersion::V1, "/cas/ca"))
+        );
+        assert_eq!(ApiVersion::split_path("/api/v1"), Some((ApiVersion::V1, "")));
+        assert_eq!(ApiVersion::split_path("/api/v2/cas"), None);
+        assert_eq!(ApiVersion::split_path("/api/versions"), None);
+    }
+
+    #[test]
+    fn negotiate() {
+        let versions = ApiVersions::current();
+        assert_eq!(versions.negotiate(&ApiVersion::all()), Some(ApiVersion::LATEST));
+        assert_eq!(versions.negotiate(&[ApiVersion::V1]), Some(ApiVersion::V1));
+        assert_eq!(versions.negotiate(&[]), None);
+
+        let json = serde_json::to_string(&versions).unwrap();
+        assert!(json.contains(r#"{"version":"v1","deprecated":false}"#));
+    }
+}
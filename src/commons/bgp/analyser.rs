@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{collections::HashMap, fmt, path::Path, sync::Mutex};
 
 use chrono::Duration;
 use tokio::sync::RwLock;
@@ -7,43 +7,59 @@ use rpki::{repository::resources::ResourceSet, repository::x509::Time};
 
 use crate::{
     commons::{
-        api::{AsNumber, ConfiguredRoa, RoaPayload},
+        api::{AsNumber, ConfiguredRoa, RoaPayload, TypedPrefix},
         bgp::{
             make_roa_tree, make_validated_announcement_tree, Announcement, AnnouncementValidity, Announcements,
-            BgpAnalysisEntry, BgpAnalysisReport, BgpAnalysisState, BgpAnalysisSuggestion, IpRange, RisDumpError,
-            RisDumpLoader, ValidatedAnnouncement,
+            BgpAnalysisEntry, BgpAnalysisImpact, BgpAnalysisReport, BgpAnalysisState, BgpAnalysisSuggestion, IpRange,
+            RisDumpError, RisDumpLoader, ValidatedAnnouncement,
         },
+        eventsourcing::{KeyStoreKey, KeyValueStore},
+        KrillResult,
     },
-    constants::{test_announcements_enabled, BGP_RIS_REFRESH_MINUTES},
+    constants::{test_announcements_enabled, BGP_RIS_REFRESH_MINUTES, STATUS_DIR},
 };
 
 //------------ BgpAnalyser -------------------------------------------------
 
 /// This type helps analyse ROAs vs BGP and vice versa.
 pub struct BgpAnalyser {
-    dump_loader: Option<RisDumpLoader>,
+    dump_loader: RwLock<Option<RisDumpLoader>>,
     seen: RwLock<Announcements>,
+    autopilot_first_seen: AutoPilotFirstSeen,
 }
 
 impl BgpAnalyser {
-    pub fn new(ris_enabled: bool, ris_v4_uri: &str, ris_v6_uri: &str) -> Self {
+    pub fn new(ris_enabled: bool, ris_v4_uri: &str, ris_v6_uri: &str, data_dir: &Path) -> KrillResult<Self> {
         if test_announcements_enabled() {
-            Self::with_test_announcements()
+            Ok(Self::with_test_announcements())
         } else {
-            let dump_loader = if ris_enabled {
-                Some(RisDumpLoader::new(ris_v4_uri, ris_v6_uri))
-            } else {
-                None
-            };
-            BgpAnalyser {
-                dump_loader,
+            Ok(BgpAnalyser {
+                dump_loader: RwLock::new(Self::dump_loader(ris_enabled, ris_v4_uri, ris_v6_uri)),
                 seen: RwLock::new(Announcements::default()),
-            }
+                autopilot_first_seen: AutoPilotFirstSeen::new(Some(data_dir))?,
+            })
+        }
+    }
+
+    fn dump_loader(ris_enabled: bool, ris_v4_uri: &str, ris_v6_uri: &str) -> Option<RisDumpLoader> {
+        if ris_enabled {
+            Some(RisDumpLoader::new(ris_v4_uri, ris_v6_uri))
+        } else {
+            None
+        }
+    }
+
+    /// Applies changed RIS dump settings from a reloaded configuration. They
+    /// are used when the announcements are next updated.
+    pub async fn update_config(&self, ris_enabled: bool, ris_v4_uri: &str, ris_v6_uri: &str) {
+        if !test_announcements_enabled() {
+            *self.dump_loader.write().await = Self::dump_loader(ris_enabled, ris_v4_uri, ris_v6_uri);
         }
     }
 
     pub async fn update(&self) -> Result<bool, BgpAnalyserError> {
-        if let Some(loader) = &self.dump_loader {
+        let dump_loader = self.dump_loader.read().await.clone();
+        if let Some(loader) = dump_loader {
             let mut seen = self.seen.write().await;
             if let Some(last_time) = seen.last_checked() {
                 if (last_time + Duration::minutes(BGP_RIS_REFRESH_MINUTES)) > Time::now() {
@@ -59,6 +75,7 @@ impl BgpAnalyser {
             } else {
                 info!("Updated announcements ({}) based on BGP Ris Dumps", announcements.len());
                 seen.update(announcements);
+                self.autopilot_first_seen.forget_unseen(&seen);
                 Ok(true)
             }
         } else {
@@ -75,14 +92,17 @@ impl BgpAnalyser {
         let seen = self.seen.read().await;
         let mut entries = vec![];
 
-        let roas: Vec<ConfiguredRoa> = match &limited_scope {
-            None => roas.to_vec(),
-            Some(limit) => roas
-                .iter()
-                .filter(|roa| limit.contains_roa_address(&roa.as_roa_ip_address()))
-                .cloned()
-                .collect(),
-        };
+        // Disabled ROA configurations are not issued, so they do not
+        // authorize any announcements.
+        let roas: Vec<ConfiguredRoa> = roas
+            .iter()
+            .filter(|roa| !roa.is_disabled())
+            .filter(|roa| match &limited_scope {
+                None => true,
+                Some(limit) => limit.contains_roa_address(&roa.as_roa_ip_address()),
+            })
+            .cloned()
+            .collect();
 
         let (roas_held, roas_not_held): (Vec<ConfiguredRoa>, _) = roas
             .into_iter()
@@ -244,6 +264,20 @@ impl BgpAnalyser {
         BgpAnalysisReport::new(entries)
     }
 
+    /// Returns the impact of changing the ROAs from `before` to `after` on
+    /// the announcements seen in BGP, for the given (limited) scope.
+    pub async fn impact(
+        &self,
+        before: &[ConfiguredRoa],
+        after: &[ConfiguredRoa],
+        resources_held: &ResourceSet,
+        limited_scope: ResourceSet,
+    ) -> BgpAnalysisImpact {
+        let before = self.analyse(before, resources_held, Some(limited_scope.clone())).await;
+        let effect = self.analyse(after, resources_held, Some(limited_scope)).await;
+        BgpAnalysisImpact::new(&before, effect)
+    }
+
     pub async fn suggest(
         &self,
         roas: &[ConfiguredRoa],
@@ -288,6 +322,65 @@ impl BgpAnalyser {
         suggestion
     }
 
+    /// Returns the announcements which are not covered by any ROA, which have
+    /// been seen for at least the given duration, and which do not exceed the
+    /// given maximum prefix lengths. These announcements can be authorized by
+    /// a ROA for their exact prefix and ASN.
+    ///
+    /// Announcements are left out if a ROA for them would invalidate other
+    /// uncovered announcements for more specific prefixes, which do not meet
+    /// these criteria themselves.
+    pub async fn stable_not_found(
+        &self,
+        roas: &[ConfiguredRoa],
+        resources_held: &ResourceSet,
+        min_seen: Duration,
+        max_length_v4: u8,
+        max_length_v6: u8,
+    ) -> Vec<Announcement> {
+        let not_found: Vec<Announcement> = self
+            .analyse(roas, resources_held, None)
+            .await
+            .into_entries()
+            .into_iter()
+            .filter(|entry| entry.state() == BgpAnalysisState::AnnouncementNotFound)
+            .map(|entry| entry.announcement())
+            .collect();
+
+        let candidates: Vec<Announcement> = not_found
+            .iter()
+            .filter(|ann| {
+                let max_length = match ann.prefix() {
+                    TypedPrefix::V4(_) => max_length_v4,
+                    TypedPrefix::V6(_) => max_length_v6,
+                };
+                ann.prefix().addr_len() <= max_length
+            })
+            .cloned()
+            .collect();
+
+        let seen = self.seen.read().await;
+        let seen_before = Time::now() - min_seen;
+
+        let stable: Vec<Announcement> = self
+            .autopilot_first_seen
+            .since(candidates, &seen)
+            .into_iter()
+            .filter(|(_, since)| *since <= seen_before)
+            .map(|(ann, _)| ann)
+            .collect();
+
+        stable
+            .iter()
+            .filter(|ann| {
+                !not_found.iter().any(|other| {
+                    other != *ann && ann.prefix().matching_or_less_specific(other.prefix()) && !stable.contains(other)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
     fn test_announcements() -> Vec<Announcement> {
         use crate::test::announcement;
 
@@ -308,8 +401,94 @@ impl BgpAnalyser {
         let mut announcements = Announcements::default();
         announcements.update(Self::test_announcements());
         BgpAnalyser {
-            dump_loader: None,
+            dump_loader: RwLock::new(None),
             seen: RwLock::new(announcements),
+            autopilot_first_seen: AutoPilotFirstSeen::new(None).unwrap(),
+        }
+    }
+}
+
+//------------ AutoPilotFirstSeen -------------------------------------------
+
+const AUTOPILOT_FIRST_SEEN_KEY: &str = "roa-autopilot-first-seen.json";
+
+/// Remembers when the announcements which the ROA auto-pilot may authorize
+/// were first seen. The times for all announcements are only kept in memory,
+/// so they start over when the server restarts. The times for these
+/// announcements are saved in the status directory as well, so that a
+/// restart does not postpone authorizing them.
+struct AutoPilotFirstSeen {
+    store: Option<KeyValueStore>,
+    first_seen: Mutex<HashMap<Announcement, Time>>,
+}
+
+impl AutoPilotFirstSeen {
+    fn new(data_dir: Option<&Path>) -> KrillResult<Self> {
+        let store = data_dir
+            .map(|data_dir| KeyValueStore::disk(data_dir, STATUS_DIR))
+            .transpose()?;
+
+        // If this cannot be read, the announcements are simply considered to
+        // be seen since they were first seen by this server.
+        let first_seen: Vec<(Announcement, Time)> = store
+            .as_ref()
+            .and_then(|store| store.get(&Self::key()).ok().flatten())
+            .unwrap_or_default();
+
+        Ok(AutoPilotFirstSeen {
+            store,
+            first_seen: Mutex::new(first_seen.into_iter().collect()),
+        })
+    }
+
+    fn key() -> KeyStoreKey {
+        KeyStoreKey::simple(AUTOPILOT_FIRST_SEEN_KEY.to_string())
+    }
+
+    /// Returns when each of the candidate announcements was first seen. The
+    /// time is remembered for candidates which were not known before.
+    fn since(&self, candidates: Vec<Announcement>, seen: &Announcements) -> Vec<(Announcement, Time)> {
+        let mut first_seen = self.first_seen.lock().unwrap();
+        let known = first_seen.len();
+
+        let res = candidates
+            .into_iter()
+            .map(|ann| {
+                let since = *first_seen
+                    .entry(ann)
+                    .or_insert_with(|| seen.first_seen(&ann).unwrap_or_else(Time::now));
+                (ann, since)
+            })
+            .collect();
+
+        if first_seen.len() != known {
+            self.save(&first_seen);
+        }
+
+        res
+    }
+
+    /// Forgets the announcements which are no longer seen.
+    fn forget_unseen(&self, seen: &Announcements) {
+        let mut first_seen = self.first_seen.lock().unwrap();
+        let known = first_seen.len();
+
+        first_seen.retain(|ann, _| seen.first_seen(ann).is_some());
+
+        if first_seen.len() != known {
+            self.save(&first_seen);
+        }
+    }
+
+    fn save(&self, first_seen: &HashMap<Announcement, Time>) {
+        if let Some(store) = &self.store {
+            let first_seen: Vec<_> = first_seen.iter().collect();
+            if let Err(e) = store.store(&Self::key(), &first_seen) {
+                warn!(
+                    "Cannot save when announcements for the ROA auto-pilot were first seen: {}",
+                    e
+                );
+            }
         }
     }
 }
@@ -352,13 +531,16 @@ mod tests {
         let bgp_ris_dump_v4_uri = "http://www.ris.ripe.net/dumps/riswhoisdump.IPv4.gz";
         let bgp_ris_dump_v6_uri = "http://www.ris.ripe.net/dumps/riswhoisdump.IPv6.gz";
 
-        let analyser = BgpAnalyser::new(true, bgp_ris_dump_v4_uri, bgp_ris_dump_v6_uri);
+        let d = tmp_dir();
+        let analyser = BgpAnalyser::new(true, bgp_ris_dump_v4_uri, bgp_ris_dump_v6_uri, &d).unwrap();
 
         assert!(analyser.seen.read().await.is_empty());
         assert!(analyser.seen.read().await.last_checked().is_none());
         analyser.update().await.unwrap();
         assert!(!analyser.seen.read().await.is_empty());
         assert!(analyser.seen.read().await.last_checked().is_some());
+
+        let _ = std::fs::remove_dir_all(d);
     }
 
     #[tokio::test]
@@ -439,6 +621,80 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn select_stable_not_found_announcements() {
+        let analyser = BgpAnalyser::with_test_announcements();
+        let resources_held = ResourceSet::from_strs("", "10.0.0.0/8", "2001:DB8::/32").unwrap();
+
+        let mut stable = analyser
+            .stable_not_found(&[], &resources_held, Duration::zero(), 24, 48)
+            .await;
+        stable.sort();
+
+        let mut expected = vec![
+            announcement("10.0.0.0/21 => 64497"),
+            announcement("10.0.0.0/22 => 64496"),
+            announcement("10.0.0.0/22 => 64497"),
+            announcement("10.0.0.0/24 => 64496"),
+            announcement("10.0.2.0/23 => 64496"),
+            announcement("2001:DB8::/32 => 64498"),
+        ];
+        expected.sort();
+        assert_eq!(stable, expected);
+
+        // ROAs for the less specific announcements would invalidate the
+        // more specific announcements which are too long to be included.
+        let stable = analyser
+            .stable_not_found(&[], &resources_held, Duration::zero(), 22, 48)
+            .await;
+        assert_eq!(stable, vec![announcement("2001:DB8::/32 => 64498")]);
+
+        // Already covered announcements are never included
+        let roas = &[configured_roa("2001:DB8::/32 => 64498")];
+        let stable = analyser
+            .stable_not_found(roas, &resources_held, Duration::zero(), 22, 48)
+            .await;
+        assert!(stable.is_empty());
+
+        // None of the test announcements have been seen for long
+        let stable = analyser
+            .stable_not_found(&[], &resources_held, Duration::hours(1), 24, 48)
+            .await;
+        assert!(stable.is_empty());
+    }
+
+    #[test]
+    fn autopilot_first_seen_survives_restart() {
+        test_under_tmp(|d| {
+            let ann_1 = announcement("10.0.0.0/24 => 64496");
+            let ann_2 = announcement("10.0.1.0/24 => 64496");
+
+            let mut seen = Announcements::default();
+            seen.update(vec![ann_1, ann_2]);
+
+            // Pretend that the first announcement was first seen long ago.
+            let ann_1_since = Time::utc(2024, 1, 1, 0, 0, 0);
+            let first_seen = AutoPilotFirstSeen::new(Some(d.as_path())).unwrap();
+            first_seen.first_seen.lock().unwrap().insert(ann_1, ann_1_since);
+            first_seen.since(vec![ann_1, ann_2], &seen);
+
+            // After a restart all announcements are seen again, but the saved
+            // times are used.
+            let mut seen = Announcements::default();
+            seen.update(vec![ann_1, ann_2]);
+
+            let first_seen = AutoPilotFirstSeen::new(Some(d.as_path())).unwrap();
+            assert_eq!(first_seen.since(vec![ann_1], &seen), vec![(ann_1, ann_1_since)]);
+
+            // Announcements which are no longer seen are forgotten.
+            seen.update(vec![ann_2]);
+            first_seen.forget_unseen(&seen);
+
+            let first_seen = AutoPilotFirstSeen::new(Some(d.as_path())).unwrap();
+            assert_eq!(first_seen.first_seen.lock().unwrap().len(), 1);
+        });
+    }
+
     #[tokio::test]
     async fn analyse_bgp_no_announcements() {
         let roa1 = configured_roa("10.0.0.0/23-24 => 64496");
@@ -449,7 +705,8 @@ mod tests {
 
         let resources_held = ResourceSet::from_strs("", "10.0.0.0/16", "").unwrap();
 
-        let analyser = BgpAnalyser::new(false, "", "");
+        let d = tmp_dir();
+        let analyser = BgpAnalyser::new(false, "", "", &d).unwrap();
         let table = analyser.analyse(&roas, &resources_held, None).await;
         let table_entries = table.entries();
         assert_eq!(3, table_entries.len());
@@ -461,6 +718,8 @@ mod tests {
             .collect();
 
         assert_eq!(roas_no_info, roas);
+
+        let _ = std::fs::remove_dir_all(d);
     }
 
     #[tokio::test]
@@ -1,4 +1,9 @@
-use std::{cmp::Ordering, collections::HashSet, fmt, str::FromStr};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
 
 use rpki::repository::x509::Time;
 
@@ -171,19 +176,30 @@ impl AsRef<TypedPrefix> for Announcement {
 
 pub struct Announcements {
     seen: TypedPrefixTree<Announcement>,
+    first_seen: HashMap<Announcement, Time>,
     last_updated: Option<Time>,
     last_checked: Option<Time>,
 }
 
 impl Announcements {
     pub fn update(&mut self, announcements: Vec<Announcement>) {
+        let now = Time::now();
+
+        // Keep the time that announcements were first seen for those
+        // announcements which are still present.
+        let mut first_seen = HashMap::new();
+        for a in &announcements {
+            let since = self.first_seen.get(a).cloned().unwrap_or(now);
+            first_seen.insert(*a, since);
+        }
+        self.first_seen = first_seen;
+
         let mut builder = TypedPrefixTreeBuilder::default();
         for a in announcements {
             builder.add(a);
         }
         let tree = builder.build();
         self.seen = tree;
-        let now = Time::now();
         self.last_updated = Some(now);
         self.last_checked = Some(now);
     }
@@ -218,6 +234,12 @@ impl Announcements {
         self.last_checked
     }
 
+    /// Returns the time that the announcement was first seen, if it is
+    /// currently seen.
+    pub fn first_seen(&self, announcement: &Announcement) -> Option<Time> {
+        self.first_seen.get(announcement).cloned()
+    }
+
     pub fn last_updated(&self) -> Option<Time> {
         self.last_updated
     }
@@ -227,6 +249,7 @@ impl Default for Announcements {
     fn default() -> Self {
         Announcements {
             seen: TypedPrefixTreeBuilder::default().build(),
+            first_seen: HashMap::new(),
             last_updated: None,
             last_checked: None,
         }
@@ -298,6 +321,25 @@ mod tests {
         assert!(matches.contains(&&ann_v6));
     }
 
+    #[test]
+    fn keep_first_seen() {
+        let ann_1 = announcement("10.0.0.0/24 => 64496");
+        let ann_2 = announcement("10.0.1.0/24 => 64496");
+
+        let mut announcements = Announcements::default();
+        announcements.update(vec![ann_1]);
+
+        let ann_1_since = Time::now() - chrono::Duration::days(1);
+        announcements.first_seen.insert(ann_1, ann_1_since);
+
+        announcements.update(vec![ann_1, ann_2]);
+        assert_eq!(announcements.first_seen(&ann_1), Some(ann_1_since));
+        assert!(announcements.first_seen(&ann_2).unwrap() > ann_1_since);
+
+        announcements.update(vec![ann_2]);
+        assert_eq!(announcements.first_seen(&ann_1), None);
+    }
+
     #[test]
     fn validate_announcement() {
         let roa_authorizing_1 = roa_payload("10.0.0.0/23-24 => 64496");
@@ -63,6 +63,79 @@ impl fmt::Display for BgpAnalysisAdvice {
     }
 }
 
+//------------ BgpAnalysisImpact -------------------------------------------
+
+/// The impact of a ROA update on the announcements seen in BGP. Lists the
+/// announcements which would become RPKI invalid because of the update,
+/// i.e. announcements which are already invalid are not included. The full
+/// analysis for the prefixes affected by the update is included as well.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BgpAnalysisImpact {
+    invalidated: Vec<BgpAnalysisEntry>,
+    effect: BgpAnalysisReport,
+}
+
+impl BgpAnalysisImpact {
+    /// Compares the analysis before the update with its effect. Both should
+    /// be limited to the same scope.
+    pub fn new(before: &BgpAnalysisReport, effect: BgpAnalysisReport) -> Self {
+        let invalid_before: Vec<Announcement> = before
+            .entries()
+            .iter()
+            .filter(|entry| entry.state().is_invalid())
+            .map(|entry| entry.announcement())
+            .collect();
+
+        let invalidated = effect
+            .entries()
+            .iter()
+            .filter(|entry| entry.state().is_invalid() && !invalid_before.contains(&entry.announcement()))
+            .cloned()
+            .collect();
+
+        BgpAnalysisImpact { invalidated, effect }
+    }
+
+    pub fn invalidated(&self) -> &Vec<BgpAnalysisEntry> {
+        &self.invalidated
+    }
+
+    pub fn effect(&self) -> &BgpAnalysisReport {
+        &self.effect
+    }
+
+    pub fn invalidates_announcements(&self) -> bool {
+        !self.invalidated.is_empty()
+    }
+}
+
+impl fmt::Display for BgpAnalysisImpact {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.invalidated.is_empty() {
+            return writeln!(f, "The update does not invalidate any announcements seen in BGP.");
+        }
+
+        writeln!(
+            f,
+            "The update would invalidate the following announcements seen in BGP:"
+        )?;
+        writeln!(f)?;
+        for entry in &self.invalidated {
+            let reason = match entry.state() {
+                BgpAnalysisState::AnnouncementInvalidLength => "too specific",
+                _ => "invalid ASN",
+            };
+            write!(f, "  {} ({}), disallowed by:", entry.announcement(), reason)?;
+            for roa in entry.disallowed_by() {
+                write!(f, " {}", roa)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
 //------------ BgpAnalysisSuggestion ---------------------------------------
 
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
@@ -939,4 +1012,34 @@ mod tests {
 
         assert_eq!(found, expected);
     }
+
+    #[test]
+    fn bgp_analysis_impact() {
+        use crate::test::{announcement, roa_payload};
+
+        let already_invalid = announcement("10.0.0.0/24 => 64497");
+        let invalidated = announcement("10.0.1.0/24 => 64497");
+        let valid = announcement("10.0.0.0/22 => 64496");
+
+        let roa = roa_payload("10.0.0.0/22 => 64496");
+        let before = BgpAnalysisReport::new(vec![
+            BgpAnalysisEntry::announcement_invalid_asn(already_invalid, vec![roa]),
+            BgpAnalysisEntry::announcement_not_found(invalidated),
+            BgpAnalysisEntry::announcement_not_found(valid),
+        ]);
+
+        let effect = BgpAnalysisReport::new(vec![
+            BgpAnalysisEntry::announcement_invalid_asn(already_invalid, vec![roa]),
+            BgpAnalysisEntry::announcement_invalid_asn(invalidated, vec![roa]),
+            BgpAnalysisEntry::announcement_valid(valid, roa),
+        ]);
+
+        let impact = BgpAnalysisImpact::new(&before, effect.clone());
+        assert!(impact.invalidates_announcements());
+        assert_eq!(impact.invalidated().len(), 1);
+        assert_eq!(impact.invalidated()[0].announcement(), invalidated);
+
+        let impact = BgpAnalysisImpact::new(&effect, effect.clone());
+        assert!(!impact.invalidates_announcements());
+    }
 }
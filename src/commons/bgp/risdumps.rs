@@ -18,6 +18,7 @@ use crate::commons::{
     error::KrillIoError,
 };
 
+#[derive(Clone, Debug)]
 pub struct RisDumpLoader {
     bgp_risdumps_v4_uri: String,
     bgp_risdumps_v6_uri: String,
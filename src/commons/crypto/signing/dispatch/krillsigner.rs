@@ -1,4 +1,8 @@
-use std::{path::Path, sync::Arc, time::Duration};
+use std::{
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use bytes::Bytes;
 use rpki::{
@@ -18,7 +22,7 @@ use rpki::{
         roa::RoaBuilder,
         rta,
         sigobj::SignedObjectBuilder,
-        x509::{Serial, Time, Validity},
+        x509::{Serial, Validity},
         Cert, Crl, Manifest, Roa,
     },
 };
@@ -33,9 +37,11 @@ use crate::{
                 signerprovider::{SignerFlags, SignerProvider},
                 signerrouter::SignerRouter,
             },
-            CryptoResult, OpenSslSigner, SignSupport,
+            CryptoResult, IssuerSerials, OpenSslSigner, SerialAllocator, SerialNumberStrategy, SignSupport,
         },
         error::Error,
+        eventsourcing::KeyValueStorage,
+        util::clock,
         KrillResult,
     },
     constants::ID_CERTIFICATE_VALIDITY_YEARS,
@@ -50,7 +56,11 @@ use std::collections::HashMap;
 
 #[cfg(feature = "hsm")]
 use crate::commons::crypto::{
-    signers::{kmip::KmipSigner, pkcs11::Pkcs11Signer},
+    signers::{
+        cloudkms::{AwsKmsSigner, GcpKmsSigner},
+        kmip::KmipSigner,
+        pkcs11::Pkcs11Signer,
+    },
     SignerHandle,
 };
 
@@ -59,7 +69,7 @@ use crate::commons::crypto::{
 /// KrillSigner:
 ///   - Delegates Signer management and dispatch to [SignerRouter].
 ///   - Maps Result<SignerError> to KrillResult.
-///   - Directs signers to use the RPKI standard key format (RSA).
+///   - Directs signers to use the RPKI standard key format (RSA), unless another format is configured for CA keys.
 ///   - Directs signers to use the RPKI standard signature algorithm (RSA PKCS #1 v1.5 with SHA-256).
 ///   - Offers additional high level functions compared to the [Signer] trait.
 ///
@@ -76,7 +86,7 @@ use crate::commons::crypto::{
 type SignerBuilderFn = fn(
     &SignerType,
     SignerFlags,
-    &Path,
+    &KeyValueStorage,
     &str,
     std::time::Duration,
     &Option<Arc<SignerMapper>>,
@@ -89,6 +99,9 @@ pub struct KrillSignerBuilder<'a> {
     signer_configs: &'a [SignerConfig],
     default_signer: Option<&'a SignerConfig>,
     one_off_signer: Option<&'a SignerConfig>,
+    ca_key_format: PublicKeyFormat,
+    serial_number_strategy: SerialNumberStrategy,
+    storage: Option<&'a KeyValueStorage>,
 }
 
 impl<'a> KrillSignerBuilder<'a> {
@@ -99,6 +112,9 @@ impl<'a> KrillSignerBuilder<'a> {
             signer_configs,
             default_signer: None,
             one_off_signer: None,
+            ca_key_format: PublicKeyFormat::Rsa,
+            serial_number_strategy: SerialNumberStrategy::default(),
+            storage: None,
         }
     }
 
@@ -112,6 +128,23 @@ impl<'a> KrillSignerBuilder<'a> {
         self
     }
 
+    pub fn with_ca_key_format(&'a mut self, ca_key_format: PublicKeyFormat) -> &'a mut Self {
+        self.ca_key_format = ca_key_format;
+        self
+    }
+
+    pub fn with_serial_number_strategy(&'a mut self, strategy: SerialNumberStrategy) -> &'a mut Self {
+        self.serial_number_strategy = strategy;
+        self
+    }
+
+    /// Keep the signer mapper, serial numbers and OpenSSL keys in the given
+    /// storage instead of the work dir.
+    pub fn with_storage(&'a mut self, storage: &'a KeyValueStorage) -> &'a mut Self {
+        self.storage = Some(storage);
+        self
+    }
+
     pub fn build(&'a mut self) -> KrillResult<KrillSigner> {
         if self.signer_configs.is_empty() {
             return Err(Error::ConfigError("At least one signer must be defined".to_string()));
@@ -148,12 +181,19 @@ impl<'a> KrillSignerBuilder<'a> {
             ));
         }
 
+        let storage = match self.storage {
+            Some(storage) => storage.clone(),
+            None => KeyValueStorage::Disk(self.work_dir.to_path_buf()),
+        };
+
         KrillSigner::build(
-            self.work_dir,
+            &storage,
             self.probe_interval,
             self.signer_configs,
             default_signer,
             one_off_signer,
+            self.ca_key_format,
+            self.serial_number_strategy,
         )
     }
 }
@@ -161,23 +201,31 @@ impl<'a> KrillSignerBuilder<'a> {
 #[derive(Debug)]
 pub struct KrillSigner {
     router: SignerRouter,
+
+    // The format used for new CA keys, see [KrillSigner::create_ca_key].
+    ca_key_format: PublicKeyFormat,
+
+    // Allocates serial numbers, see [KrillSigner::next_serial].
+    serials: SerialAllocator,
 }
 
 impl KrillSigner {
     fn build(
-        work_dir: &Path,
+        storage: &KeyValueStorage,
         probe_interval: Duration,
         signer_configs: &[SignerConfig],
         default_signer: &SignerConfig,
         one_off_signer: &SignerConfig,
+        ca_key_format: PublicKeyFormat,
+        serial_number_strategy: SerialNumberStrategy,
     ) -> KrillResult<Self> {
         #[cfg(not(feature = "hsm"))]
         let signer_mapper = None;
         #[cfg(feature = "hsm")]
-        let signer_mapper = Some(Arc::new(SignerMapper::build(work_dir)?));
+        let signer_mapper = Some(Arc::new(SignerMapper::build_in(storage)?));
         let signers = Self::build_signers(
             signer_builder,
-            work_dir,
+            storage,
             probe_interval,
             &signer_mapper,
             signer_configs,
@@ -185,7 +233,12 @@ impl KrillSigner {
             one_off_signer,
         )?;
         let router = SignerRouter::build(signer_mapper, signers)?;
-        Ok(KrillSigner { router })
+        let serials = SerialAllocator::build_in(storage, serial_number_strategy)?;
+        Ok(KrillSigner {
+            router,
+            ca_key_format,
+            serials,
+        })
     }
 
     #[cfg(feature = "hsm")]
@@ -204,19 +257,87 @@ impl KrillSigner {
             .map_err(crypto::Error::signer)
     }
 
+    /// Creates a new key for use by a CA in one of its resource classes. The
+    /// key uses the configured CA key format.
+    pub fn create_ca_key(&self) -> CryptoResult<KeyIdentifier> {
+        self.router
+            .create_key(self.ca_key_format)
+            .map_err(crypto::Error::signer)
+    }
+
+    /// Returns the name of the signer used for new keys.
+    pub fn default_signer_name(&self) -> String {
+        self.router.default_signer_name().to_string()
+    }
+
+    /// Returns the name of the signer which holds the given key, if known.
+    ///
+    /// Keys can be held by any of the configured signers, not just the
+    /// default signer. E.g. after the default signer was changed keys
+    /// created earlier remain in the previous signer until they are rolled.
+    pub fn key_signer_name(&self, key_id: &KeyIdentifier) -> Option<String> {
+        match self.router.get_mapper() {
+            None => Some(self.default_signer_name()),
+            Some(mapper) => mapper
+                .get_signer_for_key(key_id)
+                .and_then(|handle| mapper.get_signer_name(&handle))
+                .ok(),
+        }
+    }
+
+    /// Returns true if the given key is held by the signer used for new keys.
+    pub fn is_default_signer_key(&self, key_id: &KeyIdentifier) -> bool {
+        self.key_signer_name(key_id) == Some(self.default_signer_name())
+    }
+
     pub fn import_key(&self, pem: &str) -> CryptoResult<KeyIdentifier> {
         self.router.import_key(pem).map_err(crypto::Error::signer)
     }
 
+    /// Returns the identifier of a PEM encoded private key, without
+    /// importing it.
+    pub fn pem_key_identifier(pem: &str) -> CryptoResult<KeyIdentifier> {
+        OpenSslSigner::pem_key_identifier(pem).map_err(crypto::Error::signer)
+    }
+
+    /// Exports the private key as PEM, e.g. to include it in an export of
+    /// a CA. Only keys held by the OpenSSL signer can be exported.
+    pub fn export_key(&self, key_id: &KeyIdentifier) -> CryptoResult<String> {
+        self.router.export_key(key_id).map_err(crypto::Error::signer)
+    }
+
+    /// Returns the keys stored by the OpenSSL signers, by signer name, with
+    /// the time that they were last modified, if known.
+    pub fn stored_keys(&self) -> CryptoResult<Vec<(String, KeyIdentifier, Option<SystemTime>)>> {
+        self.router.list_keys().map_err(crypto::Error::signer)
+    }
+
+    /// Archives a key stored by an OpenSSL signer, so that it can no longer
+    /// be used. The key can be restored manually.
+    pub fn archive_key(&self, key_id: &KeyIdentifier) -> CryptoResult<()> {
+        self.router.archive_key(key_id).map_err(crypto::Error::signer)
+    }
+
+    /// Returns the keys used to identify signers, which must be kept.
+    pub fn binding_keys(&self) -> KrillResult<Vec<KeyIdentifier>> {
+        self.router.binding_keys()
+    }
+
     /// Creates a new self-signed (TA) IdCert
     pub fn create_self_signed_id_cert(&self) -> CryptoResult<IdCert> {
         let key = self.create_key()?;
+        self.create_self_signed_id_cert_for_key(&key)
+    }
+
+    /// Creates a new self-signed (TA) IdCert for an existing key, e.g. an
+    /// imported key.
+    pub fn create_self_signed_id_cert_for_key(&self, key: &KeyIdentifier) -> CryptoResult<IdCert> {
         let validity = Validity::new(
-            Time::five_minutes_ago(),
-            Time::years_from_now(ID_CERTIFICATE_VALIDITY_YEARS),
+            clock::five_minutes_ago(),
+            clock::years_from_now(ID_CERTIFICATE_VALIDITY_YEARS),
         );
 
-        IdCert::new_ta(validity, &key, &self.router).map_err(crypto::Error::signer)
+        IdCert::new_ta(validity, key, &self.router).map_err(crypto::Error::signer)
     }
 
     pub fn destroy_key(&self, key_id: &KeyIdentifier) -> CryptoResult<()> {
@@ -231,6 +352,21 @@ impl KrillSigner {
         Serial::random(&self.router).map_err(crypto::Error::signer)
     }
 
+    /// Returns a serial number for a certificate or signed object issued
+    /// using the given key, allocated according to the configured strategy.
+    pub fn next_serial(&self, issuer: &KeyIdentifier) -> CryptoResult<Serial> {
+        match self.serials.next(issuer).map_err(crypto::Error::signer)? {
+            Some(serial) => Ok(serial),
+            None => self.random_serial(),
+        }
+    }
+
+    /// Returns the serial numbers allocated for the given issuing key, unless
+    /// serial numbers are random.
+    pub fn issuer_serials(&self, issuer: &KeyIdentifier) -> CryptoResult<Option<IssuerSerials>> {
+        self.serials.issuer_serials(issuer).map_err(crypto::Error::signer)
+    }
+
     pub fn sign<D: AsRef<[u8]> + ?Sized>(&self, key_id: &KeyIdentifier, data: &D) -> CryptoResult<RpkiSignature> {
         self.router
             .sign(key_id, RpkiSignatureAlgorithm::default(), data)
@@ -261,11 +397,22 @@ impl KrillSigner {
         RpkiCaCsr::decode(signed_and_encoded_csr.as_slice()).map_err(crypto::Error::signing)
     }
 
+    /// Returns an error if the issuance of RPKI objects is refused, because
+    /// the system time is known to be off. See [`clock::refuse_issuance`].
+    fn check_issuance(&self) -> CryptoResult<()> {
+        match clock::issuance_refused() {
+            None => Ok(()),
+            Some(reason) => Err(crypto::Error::signing(format!("Issuance refused: {}", reason))),
+        }
+    }
+
     pub fn sign_cert(&self, tbs: TbsCert, key_id: &KeyIdentifier) -> CryptoResult<Cert> {
+        self.check_issuance()?;
         tbs.into_cert(&self.router, key_id).map_err(crypto::Error::signing)
     }
 
     pub fn sign_crl(&self, tbs: TbsCertList<Vec<CrlEntry>>, key_id: &KeyIdentifier) -> CryptoResult<Crl> {
+        self.check_issuance()?;
         tbs.into_crl(&self.router, key_id).map_err(crypto::Error::signing)
     }
 
@@ -275,6 +422,7 @@ impl KrillSigner {
         builder: SignedObjectBuilder,
         key_id: &KeyIdentifier,
     ) -> CryptoResult<Manifest> {
+        self.check_issuance()?;
         content
             .into_manifest(builder, &self.router, key_id)
             .map_err(crypto::Error::signing)
@@ -286,6 +434,7 @@ impl KrillSigner {
         object_builder: SignedObjectBuilder,
         key_id: &KeyIdentifier,
     ) -> CryptoResult<Roa> {
+        self.check_issuance()?;
         roa_builder
             .finalize(object_builder, &self.router, key_id)
             .map_err(crypto::Error::signing)
@@ -297,12 +446,14 @@ impl KrillSigner {
         object_builder: SignedObjectBuilder,
         key_id: &KeyIdentifier,
     ) -> CryptoResult<Aspa> {
+        self.check_issuance()?;
         aspa_builder
             .finalize(object_builder, &self.router, key_id)
             .map_err(crypto::Error::signing)
     }
 
     pub fn sign_rta(&self, rta_builder: &mut rta::RtaBuilder, ee: Cert) -> CryptoResult<()> {
+        self.check_issuance()?;
         let key = ee.subject_key_identifier();
         rta_builder.push_cert(ee);
         rta_builder
@@ -336,7 +487,7 @@ impl KrillSigner {
 impl KrillSigner {
     fn build_signers(
         signer_builder: SignerBuilderFn,
-        work_dir: &Path,
+        storage: &KeyValueStorage,
         probe_interval: std::time::Duration,
         mapper: &Option<Arc<SignerMapper>>,
         configs: &[SignerConfig],
@@ -363,7 +514,7 @@ impl KrillSigner {
             let signer = (signer_builder)(
                 &config.signer_type,
                 flags,
-                work_dir,
+                storage,
                 &config.name,
                 probe_interval,
                 mapper,
@@ -379,7 +530,7 @@ impl KrillSigner {
 fn signer_builder(
     r#type: &SignerType,
     flags: SignerFlags,
-    work_dir: &Path,
+    storage: &KeyValueStorage,
     name: &str,
     #[cfg(feature = "hsm")] probe_interval: Duration,
     #[cfg(not(feature = "hsm"))] _probe_interval: Duration,
@@ -387,14 +538,14 @@ fn signer_builder(
 ) -> KrillResult<SignerProvider> {
     match r#type {
         SignerType::OpenSsl(conf) => {
-            let data_dir = if let Some(ref path) = conf.keys_path {
-                path.as_path()
-            } else {
-                work_dir
+            // An explicitly configured keys path takes precedence over the
+            // storage, it is up to the operator to share it between Krill
+            // instances in high availability mode.
+            let signer = match &conf.keys_path {
+                Some(path) => OpenSslSigner::build(path, name, mapper.clone())?,
+                None => OpenSslSigner::build_in(storage, name, mapper.clone())?,
             };
 
-            let signer = OpenSslSigner::build(data_dir, name, mapper.clone())?;
-
             Ok(SignerProvider::OpenSsl(flags, signer))
         }
         #[cfg(feature = "hsm")]
@@ -407,6 +558,16 @@ fn signer_builder(
             let signer = KmipSigner::build(name, conf, probe_interval, mapper.as_ref().unwrap().clone())?;
             Ok(SignerProvider::Kmip(flags, signer))
         }
+        #[cfg(feature = "hsm")]
+        SignerType::AwsKms(conf) => {
+            let signer = AwsKmsSigner::build(name, conf, mapper.as_ref().unwrap().clone())?;
+            Ok(SignerProvider::AwsKms(flags, signer))
+        }
+        #[cfg(feature = "hsm")]
+        SignerType::GcpKms(conf) => {
+            let signer = GcpKmsSigner::build(name, conf, mapper.as_ref().unwrap().clone())?;
+            Ok(SignerProvider::GcpKms(flags, signer))
+        }
     }
 }
 
@@ -433,7 +594,7 @@ pub mod tests {
     fn mock_signer_builder(
         r#type: &SignerType,
         flags: SignerFlags,
-        _work_dir: &Path,
+        _storage: &KeyValueStorage,
         name: &str,
         _probe_interval: Duration,
         mapper: &Option<Arc<SignerMapper>>,
@@ -462,7 +623,7 @@ pub mod tests {
         let probe_interval = std::time::Duration::from_secs(1);
         KrillSigner::build_signers(
             mock_signer_builder,
-            work_dir,
+            &KeyValueStorage::Disk(work_dir.to_path_buf()),
             probe_interval,
             &mapper,
             &config.signers,
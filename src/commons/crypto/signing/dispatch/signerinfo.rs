@@ -10,7 +10,9 @@ use crate::{
         api::CommandSummary,
         crypto::SignerHandle,
         error::Error,
-        eventsourcing::{Aggregate, AggregateStore, CommandDetails, SentCommand, StoredEvent, WithStorableDetails},
+        eventsourcing::{
+            Aggregate, AggregateStore, CommandDetails, KeyValueStorage, SentCommand, StoredEvent, WithStorableDetails,
+        },
         KrillResult,
     },
     constants::{ACTOR_DEF_KRILL, SIGNERS_DIR},
@@ -331,6 +333,12 @@ impl SignerMapper {
         Ok(SignerMapper { store })
     }
 
+    /// Build a SignerMapper that will read/write its data in the given storage.
+    pub fn build_in(storage: &KeyValueStorage) -> KrillResult<SignerMapper> {
+        let store = AggregateStore::<SignerInfo>::create(storage, SIGNERS_DIR)?;
+        Ok(SignerMapper { store })
+    }
+
     /// Record the existence of a new signer.
     ///
     /// A signer has several properties, some fixed, some modifiable. The handle and public key are fixed at signer
@@ -12,7 +12,11 @@ use crate::commons::crypto::{
 use crate::commons::crypto::signers::mocksigner::MockSigner;
 
 #[cfg(feature = "hsm")]
-use crate::commons::crypto::signers::{kmip::KmipSigner, pkcs11::Pkcs11Signer};
+use crate::commons::crypto::signers::{
+    cloudkms::{AwsKmsSigner, GcpKmsSigner},
+    kmip::KmipSigner,
+    pkcs11::Pkcs11Signer,
+};
 
 //------------ SignerProvider ------------------------------------------------
 
@@ -55,6 +59,12 @@ impl SignerFlags {
 /// Dispatchers Signer requests to a particular implementation of the Signer trait.
 ///
 /// Named and modelled after the similar AuthProvider concept that already exists in Krill.
+///
+/// Note that all signers other than OpenSSL perform each operation as one or more round trips to a remote service or
+/// device. For the cloud KMS signers in particular every signature is a HTTPS request, and as these services offer no
+/// way to sign in bulk, requests are made one at a time. See the [cloudkms] module for details.
+///
+/// [cloudkms]: crate::commons::crypto::signers::cloudkms
 #[allow(dead_code)] // Needed as we currently only ever construct one variant
 #[derive(Debug)]
 pub enum SignerProvider {
@@ -66,6 +76,12 @@ pub enum SignerProvider {
     #[cfg(feature = "hsm")]
     Pkcs11(SignerFlags, Pkcs11Signer),
 
+    #[cfg(feature = "hsm")]
+    AwsKms(SignerFlags, AwsKmsSigner),
+
+    #[cfg(feature = "hsm")]
+    GcpKms(SignerFlags, GcpKmsSigner),
+
     #[cfg(all(test, feature = "hsm"))]
     Mock(SignerFlags, MockSigner),
 }
@@ -78,6 +94,10 @@ impl SignerProvider {
             SignerProvider::Kmip(flags, _) => flags.is_default_signer,
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(flags, _) => flags.is_default_signer,
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(flags, _) => flags.is_default_signer,
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(flags, _) => flags.is_default_signer,
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(flags, _) => flags.is_default_signer,
         }
@@ -90,6 +110,10 @@ impl SignerProvider {
             SignerProvider::Kmip(flags, _) => flags.is_one_off_signer,
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(flags, _) => flags.is_one_off_signer,
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(flags, _) => flags.is_one_off_signer,
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(flags, _) => flags.is_one_off_signer,
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(flags, _) => flags.is_one_off_signer,
         }
@@ -102,6 +126,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.create_registration_key(),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.create_registration_key(),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.create_registration_key(),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.create_registration_key(),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.create_registration_key(),
         }
@@ -118,6 +146,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.sign_registration_challenge(signer_private_key_id, challenge),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.sign_registration_challenge(signer_private_key_id, challenge),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.sign_registration_challenge(signer_private_key_id, challenge),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.sign_registration_challenge(signer_private_key_id, challenge),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.sign_registration_challenge(signer_private_key_id, challenge),
         }
@@ -130,6 +162,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.set_handle(handle),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.set_handle(handle),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.set_handle(handle),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.set_handle(handle),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.set_handle(handle),
         }
@@ -142,6 +178,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.get_name(),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.get_name(),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.get_name(),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.get_name(),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.get_name(),
         }
@@ -154,6 +194,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.get_info(),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.get_info(),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.get_info(),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.get_info(),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.get_info(),
         }
@@ -177,6 +221,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.create_key(algorithm),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.create_key(algorithm),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.create_key(algorithm),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.create_key(algorithm),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.create_key(algorithm),
         }
@@ -191,11 +239,37 @@ impl SignerProvider {
             SignerProvider::Kmip(_, _) => Err(SignerError::other("import key not supported for KMIP signers")),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, _) => Err(SignerError::other("import key not supported for PKCS#11 signers")),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, _) => Err(SignerError::other("import key not supported for AWS KMS signers")),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, _) => Err(SignerError::other(
+                "import key not supported for Google Cloud KMS signers",
+            )),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, _) => Err(SignerError::other("import key not supported for the mock signer")),
         }
     }
 
+    /// Export a private key as PEM. Only supported for OpenSslSigner. Other
+    /// signers will return an error.
+    pub fn export_key(&self, key_id: &KeyIdentifier) -> Result<String, SignerError> {
+        match self {
+            SignerProvider::OpenSsl(_, signer) => signer.export_key(key_id),
+            #[cfg(feature = "hsm")]
+            SignerProvider::Kmip(_, _) => Err(SignerError::other("export key not supported for KMIP signers")),
+            #[cfg(feature = "hsm")]
+            SignerProvider::Pkcs11(_, _) => Err(SignerError::other("export key not supported for PKCS#11 signers")),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, _) => Err(SignerError::other("export key not supported for AWS KMS signers")),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, _) => Err(SignerError::other(
+                "export key not supported for Google Cloud KMS signers",
+            )),
+            #[cfg(all(test, feature = "hsm"))]
+            SignerProvider::Mock(_, _) => Err(SignerError::other("export key not supported for the mock signer")),
+        }
+    }
+
     pub fn get_key_info(&self, key: &KeyIdentifier) -> Result<PublicKey, KeyError<SignerError>> {
         match self {
             SignerProvider::OpenSsl(_, signer) => signer.get_key_info(key),
@@ -203,6 +277,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.get_key_info(key),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.get_key_info(key),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.get_key_info(key),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.get_key_info(key),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.get_key_info(key),
         }
@@ -215,6 +293,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.destroy_key(key),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.destroy_key(key),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.destroy_key(key),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.destroy_key(key),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.destroy_key(key),
         }
@@ -237,6 +319,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.sign(key, algorithm, data),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.sign(key, algorithm, data),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.sign(key, algorithm, data),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.sign(key, algorithm, data),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.sign(key, algorithm, data),
         }
@@ -258,6 +344,10 @@ impl SignerProvider {
             SignerProvider::Kmip(_, signer) => signer.sign_one_off(algorithm, data),
             #[cfg(feature = "hsm")]
             SignerProvider::Pkcs11(_, signer) => signer.sign_one_off(algorithm, data),
+            #[cfg(feature = "hsm")]
+            SignerProvider::AwsKms(_, signer) => signer.sign_one_off(algorithm, data),
+            #[cfg(feature = "hsm")]
+            SignerProvider::GcpKms(_, signer) => signer.sign_one_off(algorithm, data),
             #[cfg(all(test, feature = "hsm"))]
             SignerProvider::Mock(_, signer) => signer.sign_one_off(algorithm, data),
         }
@@ -1,18 +1,26 @@
 use std::sync::Arc;
-use std::{collections::HashMap, sync::RwLock};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::RwLock,
+    time::SystemTime,
+};
 
 use rpki::crypto::{
     signer::KeyError, KeyIdentifier, PublicKey, PublicKeyFormat, Signature, SignatureAlgorithm, Signer, SigningError,
 };
 
-use crate::commons::{
-    crypto::{
-        dispatch::{signerinfo::SignerMapper, signerprovider::SignerProvider},
-        signers::error::SignerError,
-        SignerHandle,
+use crate::{
+    commons::{
+        crypto::{
+            dispatch::{signerinfo::SignerMapper, signerprovider::SignerProvider},
+            signers::error::SignerError,
+            SignerHandle,
+        },
+        error::Error,
+        KrillResult,
     },
-    error::Error,
-    KrillResult,
+    daemon::telemetry::{self, Context, KeyValue, SpanKind},
 };
 
 #[cfg(feature = "hsm")]
@@ -160,6 +168,11 @@ impl SignerRouter {
         self.active_signers.read().unwrap().clone()
     }
 
+    /// Returns the name of the signer used to create new (non one-off) keys.
+    pub fn default_signer_name(&self) -> &str {
+        self.default_signer.get_name()
+    }
+
     /// Locate the [SignerProvider] that owns a given [KeyIdentifier], if the signer is active.
     ///
     /// If the signer that owns the key has not yet been promoted from the pending set to the active set or if no
@@ -197,6 +210,83 @@ impl SignerRouter {
         self.bind_ready_signers();
         self.default_signer.import_key(pem)
     }
+
+    /// Export a private key as PEM. Will only work for keys held by the
+    /// OpenSslSigner. Returns an error if another signer holds the key.
+    pub fn export_key(&self, key_id: &KeyIdentifier) -> Result<String, SignerError> {
+        self.bind_ready_signers();
+        self.get_signer_for_key(key_id)?.export_key(key_id)
+    }
+
+    /// Returns the keys stored by the OpenSSL signers in use, by signer name,
+    /// with the time that their key file was last modified, if known. Keys
+    /// held by other signers, e.g. HSMs, cannot be listed and are not
+    /// included.
+    pub fn list_keys(&self) -> Result<Vec<(String, KeyIdentifier, Option<SystemTime>)>, SignerError> {
+        self.bind_ready_signers();
+        let mut keys = vec![];
+        let mut seen = HashSet::new();
+        for signer in self.key_signers() {
+            if let SignerProvider::OpenSsl(_, openssl) = signer.as_ref() {
+                for (key_id, modified) in openssl.list_keys()? {
+                    if seen.insert(key_id) {
+                        keys.push((signer.get_name().to_string(), key_id, modified));
+                    }
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Archives the given key in the OpenSSL signers that store it. Returns
+    /// [SignerError::KeyNotFound] if no OpenSSL signer in use stores the key.
+    pub fn archive_key(&self, key_id: &KeyIdentifier) -> Result<(), SignerError> {
+        self.bind_ready_signers();
+        let mut found = false;
+        for signer in self.key_signers() {
+            if let SignerProvider::OpenSsl(_, openssl) = signer.as_ref() {
+                if openssl.has_key(key_id) {
+                    openssl.archive_key(key_id)?;
+                    found = true;
+                }
+            }
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(SignerError::KeyNotFound)
+        }
+    }
+
+    /// Returns the keys used to bind signers to their [SignerMapper] [Handle].
+    /// These keys are not used by any CA, but must be kept. Fails if the
+    /// signers cannot be read, rather than leaving out their keys.
+    pub fn binding_keys(&self) -> KrillResult<Vec<KeyIdentifier>> {
+        let mut keys = vec![];
+        if let Some(mapper) = &self.signer_mapper {
+            for handle in mapper.get_signer_handles()? {
+                // Keys held by HSMs may have internal ids which are not key
+                // identifiers, but those keys are not listed anyway.
+                let id = mapper.get_signer_private_key_internal_id(&handle)?;
+                if let Ok(key_id) = KeyIdentifier::from_str(&id) {
+                    keys.push(key_id);
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Returns all signers which may hold keys, without duplicates.
+    fn key_signers(&self) -> Vec<Arc<SignerProvider>> {
+        let mut signers = vec![self.default_signer.clone()];
+        let others = std::iter::once(self.one_off_signer.clone()).chain(self.get_active_signers().into_values());
+        for signer in others {
+            if !signers.iter().any(|known| Arc::ptr_eq(known, &signer)) {
+                signers.push(signer);
+            }
+        }
+        signers
+    }
 }
 
 /// When the "hsm" feature is enabled we can no longer assume that signers are immediately and always available as was
@@ -589,18 +679,24 @@ impl Signer for SignerRouter {
     type Error = SignerError;
 
     fn create_key(&self, algorithm: PublicKeyFormat) -> Result<Self::KeyId, Self::Error> {
-        self.bind_ready_signers();
-        self.default_signer.create_key(algorithm)
+        telemetry::traced_sync(signer_span("signer create key", None), || {
+            self.bind_ready_signers();
+            self.default_signer.create_key(algorithm)
+        })
     }
 
     fn get_key_info(&self, key_id: &KeyIdentifier) -> Result<PublicKey, KeyError<Self::Error>> {
-        self.bind_ready_signers();
-        self.get_signer_for_key(key_id)?.get_key_info(key_id)
+        telemetry::traced_sync(signer_span("signer get key info", Some(key_id)), || {
+            self.bind_ready_signers();
+            self.get_signer_for_key(key_id)?.get_key_info(key_id)
+        })
     }
 
     fn destroy_key(&self, key_id: &KeyIdentifier) -> Result<(), KeyError<Self::Error>> {
-        self.bind_ready_signers();
-        self.get_signer_for_key(key_id)?.destroy_key(key_id)
+        telemetry::traced_sync(signer_span("signer destroy key", Some(key_id)), || {
+            self.bind_ready_signers();
+            self.get_signer_for_key(key_id)?.destroy_key(key_id)
+        })
     }
 
     fn sign<Alg: SignatureAlgorithm, D: AsRef<[u8]> + ?Sized>(
@@ -609,8 +705,10 @@ impl Signer for SignerRouter {
         algorithm: Alg,
         data: &D,
     ) -> Result<Signature<Alg>, SigningError<Self::Error>> {
-        self.bind_ready_signers();
-        self.get_signer_for_key(key_id)?.sign(key_id, algorithm, data)
+        telemetry::traced_sync(signer_span("signer sign", Some(key_id)), || {
+            self.bind_ready_signers();
+            self.get_signer_for_key(key_id)?.sign(key_id, algorithm, data)
+        })
     }
 
     fn sign_one_off<Alg: SignatureAlgorithm, D: AsRef<[u8]> + ?Sized>(
@@ -618,8 +716,10 @@ impl Signer for SignerRouter {
         algorithm: Alg,
         data: &D,
     ) -> Result<(Signature<Alg>, PublicKey), Self::Error> {
-        self.bind_ready_signers();
-        self.one_off_signer.sign_one_off(algorithm, data)
+        telemetry::traced_sync(signer_span("signer sign one off", None), || {
+            self.bind_ready_signers();
+            self.one_off_signer.sign_one_off(algorithm, data)
+        })
     }
 
     fn rand(&self, target: &mut [u8]) -> Result<(), Self::Error> {
@@ -628,6 +728,15 @@ impl Signer for SignerRouter {
     }
 }
 
+/// Starts a span for a call to a signer, which may involve a round trip to
+/// an HSM.
+fn signer_span(name: &'static str, key_id: Option<&KeyIdentifier>) -> Context {
+    let attributes = key_id
+        .map(|key_id| vec![KeyValue::new("krill.key", key_id.to_string())])
+        .unwrap_or_default();
+    telemetry::span(name, SpanKind::Internal, attributes)
+}
+
 #[cfg(all(test, feature = "hsm"))]
 pub mod tests {
     use rpki::crypto::RpkiSignatureAlgorithm;
@@ -9,7 +9,7 @@ use rpki::{
     repository::{
         cert::{KeyUsage, Overclaim, TbsCert},
         resources::ResourceSet,
-        x509::{Name, Time, Validity},
+        x509::{Name, Validity},
         Cert,
     },
     uri,
@@ -20,7 +20,7 @@ use crate::{
         api::{IssuedCertificate, ReceivedCert},
         crypto::KrillSigner,
         error::Error,
-        util::AllowedUri,
+        util::{clock, AllowedUri},
         KrillResult,
     },
     daemon::ca::CertifiedKey,
@@ -167,7 +167,7 @@ impl SignSupport {
         request: CertRequest,
         signer: &KrillSigner,
     ) -> KrillResult<TbsCert> {
-        let serial = signer.random_serial()?;
+        let serial = signer.next_serial(&signing_cert.key_identifier())?;
         let issuer = signing_cert.subject().clone();
 
         let validity = match &request {
@@ -229,22 +229,22 @@ impl SignSupport {
     /// Returns a validity period from 5 minutes ago (in case of NTP mess-up), to
     /// X weeks from now.
     pub fn sign_validity_years(years: i32) -> Validity {
-        let from = Time::five_minutes_ago();
-        let until = Time::years_from_now(years);
+        let from = clock::five_minutes_ago();
+        let until = clock::years_from_now(years);
         Validity::new(from, until)
     }
 
     /// Returns a validity period from 5 minutes ago (in case of NTP mess-up), to
     /// X weeks from now.
     pub fn sign_validity_weeks(weeks: i64) -> Validity {
-        let from = Time::five_minutes_ago();
-        let until = Time::now() + chrono::Duration::weeks(weeks);
+        let from = clock::five_minutes_ago();
+        let until = clock::now() + chrono::Duration::weeks(weeks);
         Validity::new(from, until)
     }
 
     pub fn sign_validity_days(days: i64) -> Validity {
-        let from = Time::five_minutes_ago();
-        let until = Time::now() + chrono::Duration::days(days);
+        let from = clock::five_minutes_ago();
+        let until = clock::now() + chrono::Duration::days(days);
         Validity::new(from, until)
     }
 }
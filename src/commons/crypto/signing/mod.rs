@@ -4,10 +4,16 @@ pub(super) mod signers;
 
 mod misc;
 
+mod serial;
+
 pub use dispatch::krillsigner::{KrillSigner, KrillSignerBuilder};
+pub use serial::{IssuerSerials, SerialAllocator, SerialNumberStrategy};
 pub use signers::error::SignerError;
 pub use signers::softsigner::OpenSslSigner;
 
+#[cfg(feature = "hsm")]
+pub use signers::cloudkms::{gcp::GcpProtectionLevel, AwsKmsSignerConfig, GcpKmsSignerConfig};
+
 #[cfg(feature = "hsm")]
 pub use signers::kmip::signer::KmipSignerConfig;
 
@@ -0,0 +1,229 @@
+//! Allocation of serial numbers for certificates and signed objects.
+//!
+//! Serial numbers only need to be unique per issuing key (RFC 5280). Random
+//! serial numbers, as used by default, are unique in practice. The other
+//! strategies allocate serial numbers which are unique by construction, and
+//! they keep track of the last serial number allocated for each issuing key
+//! so that uniqueness can be demonstrated in an audit.
+use std::{fmt, path::Path, sync::Mutex, time::SystemTime};
+
+use rpki::{crypto::KeyIdentifier, repository::x509::Serial};
+
+use crate::{
+    commons::{
+        eventsourcing::{KeyStoreKey, KeyValueError, KeyValueStorage, KeyValueStore},
+        KrillResult,
+    },
+    constants::SERIALS_DIR,
+};
+
+//------------ SerialNumberStrategy ------------------------------------------
+
+/// The strategy used to allocate serial numbers.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerialNumberStrategy {
+    /// A random 20 octet serial number, no state is kept.
+    #[default]
+    Random,
+
+    /// One more than the previous serial number for the issuing key,
+    /// starting at 1.
+    Monotonic,
+
+    /// The current time in nanoseconds since the UNIX epoch, or one more
+    /// than the previous serial number for the issuing key if that is
+    /// higher.
+    Timestamp,
+}
+
+impl fmt::Display for SerialNumberStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerialNumberStrategy::Random => write!(f, "random"),
+            SerialNumberStrategy::Monotonic => write!(f, "monotonic"),
+            SerialNumberStrategy::Timestamp => write!(f, "timestamp"),
+        }
+    }
+}
+
+//------------ IssuerSerials -------------------------------------------------
+
+/// The serial numbers allocated for an issuing key, as stored.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct IssuerSerials {
+    issuer: KeyIdentifier,
+    strategy: SerialNumberStrategy,
+    last: u128,
+    allocated: u64,
+}
+
+impl IssuerSerials {
+    fn new(issuer: KeyIdentifier, strategy: SerialNumberStrategy) -> Self {
+        IssuerSerials {
+            issuer,
+            strategy,
+            last: 0,
+            allocated: 0,
+        }
+    }
+
+    pub fn issuer(&self) -> KeyIdentifier {
+        self.issuer
+    }
+
+    pub fn strategy(&self) -> SerialNumberStrategy {
+        self.strategy
+    }
+
+    /// The last allocated serial number.
+    pub fn last(&self) -> Serial {
+        Serial::from(self.last)
+    }
+
+    /// The number of allocated serial numbers.
+    pub fn allocated(&self) -> u64 {
+        self.allocated
+    }
+
+    fn allocate(&mut self, strategy: SerialNumberStrategy, now: u128) -> u128 {
+        let next = match strategy {
+            SerialNumberStrategy::Timestamp => now.max(self.last + 1),
+            _ => self.last + 1,
+        };
+
+        // Keep the strategy used most recently, the guarantee that serial
+        // numbers increase holds across changes in strategy.
+        self.strategy = strategy;
+        self.last = next;
+        self.allocated += 1;
+
+        next
+    }
+}
+
+//------------ SerialAllocator -----------------------------------------------
+
+/// Allocates serial numbers using the configured strategy.
+///
+/// For strategies other than random, the last allocated serial number is
+/// saved for each issuing key before it is returned. So, serial numbers are
+/// never re-used, not even after a restart.
+#[derive(Debug)]
+pub struct SerialAllocator {
+    strategy: SerialNumberStrategy,
+    store: Option<KeyValueStore>,
+    lock: Mutex<()>,
+}
+
+impl SerialAllocator {
+    pub fn build(work_dir: &Path, strategy: SerialNumberStrategy) -> KrillResult<Self> {
+        Self::build_in(&KeyValueStorage::Disk(work_dir.to_path_buf()), strategy)
+    }
+
+    /// Builds an allocator that saves the last serial numbers in the given
+    /// storage.
+    pub fn build_in(storage: &KeyValueStorage, strategy: SerialNumberStrategy) -> KrillResult<Self> {
+        let store = match strategy {
+            SerialNumberStrategy::Random => None,
+            _ => Some(KeyValueStore::create(storage, SERIALS_DIR)?),
+        };
+
+        Ok(SerialAllocator {
+            strategy,
+            store,
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub fn strategy(&self) -> SerialNumberStrategy {
+        self.strategy
+    }
+
+    /// Returns the next serial number for the issuing key, or `None` if the
+    /// serial number should be random.
+    pub fn next(&self, issuer: &KeyIdentifier) -> Result<Option<Serial>, KeyValueError> {
+        let store = match &self.store {
+            None => return Ok(None),
+            Some(store) => store,
+        };
+
+        let _lock = self.lock.lock().unwrap();
+
+        let key = Self::key(issuer);
+        let mut serials = store
+            .get(&key)?
+            .unwrap_or_else(|| IssuerSerials::new(*issuer, self.strategy));
+
+        let next = serials.allocate(self.strategy, Self::now());
+        store.store(&key, &serials)?;
+
+        Ok(Some(Serial::from(next)))
+    }
+
+    /// Returns the serial numbers allocated for the issuing key, if any.
+    pub fn issuer_serials(&self, issuer: &KeyIdentifier) -> Result<Option<IssuerSerials>, KeyValueError> {
+        match &self.store {
+            None => Ok(None),
+            Some(store) => store.get(&Self::key(issuer)),
+        }
+    }
+
+    fn key(issuer: &KeyIdentifier) -> KeyStoreKey {
+        KeyStoreKey::simple(format!("{}.json", issuer))
+    }
+
+    fn now() -> u128 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default()
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test;
+
+    fn issuer() -> KeyIdentifier {
+        KeyIdentifier::from([1; 20])
+    }
+
+    #[test]
+    fn monotonic_serials_survive_restart() {
+        test::test_under_tmp(|d| {
+            let allocator = SerialAllocator::build(&d, SerialNumberStrategy::Monotonic).unwrap();
+            assert_eq!(allocator.next(&issuer()).unwrap(), Some(Serial::from(1_u64)));
+            assert_eq!(allocator.next(&issuer()).unwrap(), Some(Serial::from(2_u64)));
+
+            let allocator = SerialAllocator::build(&d, SerialNumberStrategy::Monotonic).unwrap();
+            assert_eq!(allocator.next(&issuer()).unwrap(), Some(Serial::from(3_u64)));
+
+            let serials = allocator.issuer_serials(&issuer()).unwrap().unwrap();
+            assert_eq!(serials.last(), Serial::from(3_u64));
+            assert_eq!(serials.allocated(), 3);
+        });
+    }
+
+    #[test]
+    fn timestamp_serials_increase() {
+        let mut serials = IssuerSerials::new(issuer(), SerialNumberStrategy::Timestamp);
+        assert_eq!(serials.allocate(SerialNumberStrategy::Timestamp, 100), 100);
+        assert_eq!(serials.allocate(SerialNumberStrategy::Timestamp, 100), 101);
+        assert_eq!(serials.allocate(SerialNumberStrategy::Timestamp, 50), 102);
+        assert_eq!(serials.allocate(SerialNumberStrategy::Monotonic, 500), 103);
+    }
+
+    #[test]
+    fn random_serials_keep_no_state() {
+        test::test_under_tmp(|d| {
+            let allocator = SerialAllocator::build(&d, SerialNumberStrategy::Random).unwrap();
+            assert_eq!(allocator.next(&issuer()).unwrap(), None);
+            assert_eq!(allocator.issuer_serials(&issuer()).unwrap(), None);
+        });
+    }
+}
@@ -0,0 +1,821 @@
+//! A signer which uses RSA keys held by AWS Key Management Service.
+//!
+//! Requests are made to the KMS JSON 1.1 API and are authenticated using AWS Signature Version 4. Credentials are
+//! taken from the signer configuration, or the standard AWS environment variables, or else the IAM role of the EC2
+//! instance that Krill runs on. The latter is recommended as it requires no secrets in the configuration and the
+//! temporary credentials are rotated automatically.
+//!
+//! The IAM identity needs permission for the `kms:CreateKey`, `kms:GetPublicKey`, `kms:Sign` and
+//! `kms:ScheduleKeyDeletion` actions.
+use std::{
+    env, fmt,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use openssl::{hash::MessageDigest, pkey::PKey, sha::sha256};
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+use serde::de::DeserializeOwned;
+
+use rpki::crypto::{
+    signer::KeyError, KeyIdentifier, PublicKey, PublicKeyFormat, RpkiSignature, RpkiSignatureAlgorithm, Signature,
+    SignatureAlgorithm, SigningError,
+};
+
+use crate::commons::crypto::{
+    dispatch::signerinfo::SignerMapper,
+    signers::cloudkms::{decode_public_key, KmsHttpClient, KmsKeys},
+    SignerError, SignerHandle,
+};
+
+//------------ Types and constants ------------------------------------------------------------------------------------
+
+/// The instance metadata service (IMDSv2) of EC2 instances.
+const INSTANCE_METADATA_URL: &str = "http://169.254.169.254/latest";
+
+/// Refresh temporary credentials this long before they expire.
+const CREDENTIALS_REFRESH_MARGIN_SECS: i64 = 300;
+
+const KMS_CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct AwsKmsSignerConfig {
+    /// The AWS region in which keys are created, e.g. "eu-west-1".
+    pub region: String,
+
+    /// Overrides the KMS endpoint for the region, e.g. to use a VPC endpoint.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+
+    #[serde(default)]
+    pub session_token: Option<String>,
+
+    #[serde(default = "AwsKmsSignerConfig::default_timeout_seconds")]
+    pub timeout_seconds: u64,
+
+    #[serde(default = "AwsKmsSignerConfig::default_deletion_window_days")]
+    pub deletion_window_days: u32,
+}
+
+impl AwsKmsSignerConfig {
+    pub fn default_timeout_seconds() -> u64 {
+        10
+    }
+
+    pub fn default_deletion_window_days() -> u32 {
+        // The minimum waiting period that AWS KMS allows before a key is really deleted.
+        7
+    }
+
+    fn endpoint(&self) -> String {
+        match &self.endpoint {
+            Some(endpoint) => endpoint.trim_end_matches('/').to_string(),
+            None => format!("https://kms.{}.amazonaws.com", self.region),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+
+    /// When temporary credentials expire, None for long term credentials.
+    expiration: Option<DateTime<Utc>>,
+}
+
+impl AwsCredentials {
+    fn is_expiring(&self) -> bool {
+        match self.expiration {
+            Some(expiration) => expiration.timestamp() - Utc::now().timestamp() < CREDENTIALS_REFRESH_MARGIN_SECS,
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never log the secrets.
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("expiration", &self.expiration)
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InstanceCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    token: String,
+    expiration: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CreateKeyResponse {
+    key_metadata: KeyMetadata,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct KeyMetadata {
+    arn: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetPublicKeyResponse {
+    public_key: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SignResponse {
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    #[serde(rename = "__type", default)]
+    error_type: String,
+
+    #[serde(alias = "Message", default)]
+    message: String,
+}
+
+//------------ The AWS KMS signer management interface ----------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct AwsKmsSigner {
+    name: String,
+
+    config: AwsKmsSignerConfig,
+
+    endpoint: String,
+
+    /// The value of the HTTP Host header for the endpoint, which is included in the request signature.
+    host: String,
+
+    http: KmsHttpClient,
+
+    keys: KmsKeys,
+
+    credentials: RwLock<Option<AwsCredentials>>,
+}
+
+impl AwsKmsSigner {
+    /// Creates a new instance of AwsKmsSigner.
+    ///
+    /// Like other remote signers, the service is not contacted until first use so that an unreachable service does
+    /// not block Krill startup.
+    pub fn build(name: &str, conf: &AwsKmsSignerConfig, mapper: Arc<SignerMapper>) -> Result<Self, SignerError> {
+        let endpoint = conf.endpoint();
+        let url = url::Url::parse(&endpoint)
+            .map_err(|e| SignerError::CloudKmsError(format!("Invalid AWS KMS endpoint '{}': {}", endpoint, e)))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(SignerError::CloudKmsError(format!(
+                    "Invalid AWS KMS endpoint '{}': no host",
+                    endpoint
+                )))
+            }
+        };
+
+        let http = KmsHttpClient::build(name, Duration::from_secs(conf.timeout_seconds))?;
+
+        Ok(AwsKmsSigner {
+            name: name.to_string(),
+            config: conf.clone(),
+            endpoint,
+            host,
+            http,
+            keys: KmsKeys::new(mapper),
+            credentials: RwLock::new(None),
+        })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_handle(&self, handle: SignerHandle) {
+        self.keys.set_handle(handle)
+    }
+
+    pub fn get_info(&self) -> Option<String> {
+        Some(format!("AWS KMS at {}", self.endpoint))
+    }
+
+    pub fn create_registration_key(&self) -> Result<(PublicKey, String), SignerError> {
+        self.build_key()
+    }
+
+    pub fn sign_registration_challenge<D: AsRef<[u8]> + ?Sized>(
+        &self,
+        signer_private_key_id: &str,
+        challenge: &D,
+    ) -> Result<RpkiSignature, SignerError> {
+        self.sign_with_key(
+            signer_private_key_id,
+            RpkiSignatureAlgorithm::default(),
+            challenge.as_ref(),
+        )
+    }
+}
+
+//------------ Authentication -----------------------------------------------------------------------------------------
+
+impl AwsKmsSigner {
+    fn credentials(&self) -> Result<AwsCredentials, SignerError> {
+        if let Some(credentials) = self.credentials.read().unwrap().as_ref() {
+            if !credentials.is_expiring() {
+                return Ok(credentials.clone());
+            }
+        }
+
+        let credentials = self.fetch_credentials()?;
+        self.credentials.write().unwrap().replace(credentials.clone());
+        Ok(credentials)
+    }
+
+    fn fetch_credentials(&self) -> Result<AwsCredentials, SignerError> {
+        if let (Some(access_key_id), Some(secret_access_key)) =
+            (&self.config.access_key_id, &self.config.secret_access_key)
+        {
+            return Ok(AwsCredentials {
+                access_key_id: access_key_id.clone(),
+                secret_access_key: secret_access_key.clone(),
+                session_token: self.config.session_token.clone(),
+                expiration: None,
+            });
+        }
+
+        if let (Ok(access_key_id), Ok(secret_access_key)) =
+            (env::var("AWS_ACCESS_KEY_ID"), env::var("AWS_SECRET_ACCESS_KEY"))
+        {
+            return Ok(AwsCredentials {
+                access_key_id,
+                secret_access_key,
+                session_token: env::var("AWS_SESSION_TOKEN").ok(),
+                expiration: None,
+            });
+        }
+
+        self.fetch_instance_credentials()
+    }
+
+    /// Gets temporary credentials for the IAM role of the EC2 instance using IMDSv2.
+    fn fetch_instance_credentials(&self) -> Result<AwsCredentials, SignerError> {
+        let client = self.http.client();
+
+        let token = self.metadata(
+            client
+                .put(format!("{}/api/token", INSTANCE_METADATA_URL))
+                .header("X-aws-ec2-metadata-token-ttl-seconds", "21600"),
+        )?;
+
+        let credentials_url = format!("{}/meta-data/iam/security-credentials/", INSTANCE_METADATA_URL);
+        let roles = self.metadata(client.get(&credentials_url).header("X-aws-ec2-metadata-token", &token))?;
+        let role = roles.lines().next().ok_or_else(|| {
+            SignerError::CloudKmsError("No AWS credentials configured and no EC2 instance role found".to_string())
+        })?;
+
+        let credentials = self.metadata(
+            client
+                .get(format!("{}{}", credentials_url, role))
+                .header("X-aws-ec2-metadata-token", &token),
+        )?;
+        let credentials: InstanceCredentials = serde_json::from_str(&credentials)?;
+
+        Ok(AwsCredentials {
+            access_key_id: credentials.access_key_id,
+            secret_access_key: credentials.secret_access_key,
+            session_token: Some(credentials.token),
+            expiration: Some(credentials.expiration),
+        })
+    }
+
+    fn metadata(&self, request: reqwest::RequestBuilder) -> Result<String, SignerError> {
+        let (status, body) = self.http.execute(request)?;
+        if status == StatusCode::OK {
+            Ok(String::from_utf8_lossy(&body).trim().to_string())
+        } else {
+            Err(SignerError::CloudKmsError(format!(
+                "No AWS credentials configured and EC2 instance metadata service responded with status {}",
+                status
+            )))
+        }
+    }
+
+    /// Returns the AWS Signature Version 4 Authorization header value for a KMS request.
+    fn authorization(
+        credentials: &AwsCredentials,
+        region: &str,
+        host: &str,
+        amz_date: &str,
+        target: &str,
+        body: &[u8],
+    ) -> Result<String, SignerError> {
+        let date = &amz_date[0..8];
+        let scope = format!("{}/{}/kms/aws4_request", date, region);
+
+        let mut canonical_headers = format!(
+            "content-type:{}\nhost:{}\nx-amz-date:{}\n",
+            KMS_CONTENT_TYPE, host, amz_date
+        );
+        let mut signed_headers = "content-type;host;x-amz-date".to_string();
+        if let Some(token) = &credentials.session_token {
+            canonical_headers.push_str(&format!("x-amz-security-token:{}\n", token));
+            signed_headers.push_str(";x-amz-security-token");
+        }
+        canonical_headers.push_str(&format!("x-amz-target:{}\n", target));
+        signed_headers.push_str(";x-amz-target");
+
+        let canonical_request = format!(
+            "POST\n/\n\n{}\n{}\n{}",
+            canonical_headers,
+            signed_headers,
+            hex::encode(sha256(body))
+        );
+
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(sha256(canonical_request.as_bytes()))
+        );
+
+        let signing_key = Self::signing_key(&credentials.secret_access_key, date, region, "kms")?;
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes())?);
+
+        Ok(format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ))
+    }
+
+    fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Result<Vec<u8>, SignerError> {
+        let key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes())?;
+        let key = hmac_sha256(&key, region.as_bytes())?;
+        let key = hmac_sha256(&key, service.as_bytes())?;
+        hmac_sha256(&key, b"aws4_request")
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>, SignerError> {
+    let key = PKey::hmac(key)?;
+    let mut signer = openssl::sign::Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data)?;
+    Ok(signer.sign_to_vec()?)
+}
+
+//------------ KMS API ------------------------------------------------------------------------------------------------
+
+impl AwsKmsSigner {
+    /// Invokes a KMS API action.
+    fn call<T: DeserializeOwned>(&self, action: &str, request: serde_json::Value) -> Result<T, SignerError> {
+        let credentials = self.credentials()?;
+        let target = format!("TrentService.{}", action);
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let body = serde_json::to_vec(&request)?;
+
+        let authorization =
+            Self::authorization(&credentials, &self.config.region, &self.host, &amz_date, &target, &body)?;
+
+        let mut http_request = self
+            .http
+            .client()
+            .post(format!("{}/", self.endpoint))
+            .header(CONTENT_TYPE, KMS_CONTENT_TYPE)
+            .header("X-Amz-Date", &amz_date)
+            .header("X-Amz-Target", &target)
+            .header("Authorization", authorization);
+        if let Some(token) = &credentials.session_token {
+            http_request = http_request.header("X-Amz-Security-Token", token);
+        }
+
+        let (status, body) = self.http.execute(http_request.body(body))?;
+
+        if status.is_success() {
+            return Ok(serde_json::from_slice(&body)?);
+        }
+
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap_or(ErrorResponse {
+            error_type: String::new(),
+            message: String::from_utf8_lossy(&body).to_string(),
+        });
+
+        if error.error_type.ends_with("NotFoundException") {
+            Err(SignerError::KeyNotFound)
+        } else if status.is_server_error() || error.error_type.ends_with("ThrottlingException") {
+            warn!(
+                "[{}] AWS KMS {} failed with status {}: {}",
+                self.name, action, status, error.message
+            );
+            Err(SignerError::TemporarilyUnavailable)
+        } else {
+            Err(SignerError::CloudKmsError(format!(
+                "AWS KMS {} failed with status {}: {} {}",
+                action, status, error.error_type, error.message
+            )))
+        }
+    }
+
+    /// Creates an RSA key pair in KMS and returns its public key and ARN.
+    fn build_key(&self) -> Result<(PublicKey, String), SignerError> {
+        // https://tools.ietf.org/html/rfc6485#section-3: Asymmetric Key Pair Formats
+        //   "The RSA key pairs used to compute the signatures MUST have a 2048-bit
+        //    modulus and a public exponent (e) of 65,537."
+        let response: CreateKeyResponse = self.call(
+            "CreateKey",
+            serde_json::json!({
+                "KeySpec": "RSA_2048",
+                "KeyUsage": "SIGN_VERIFY",
+                "Description": format!("Krill key created by signer '{}'", self.name),
+                "Tags": [{ "TagKey": "krill", "TagValue": "true" }],
+            }),
+        )?;
+
+        let arn = response.key_metadata.arn;
+
+        let public_key = self.get_public_key(&arn).map_err(|err| {
+            let _ = self.delete_key(&arn);
+            err
+        })?;
+
+        Ok((public_key, arn))
+    }
+
+    fn get_public_key(&self, arn: &str) -> Result<PublicKey, SignerError> {
+        let response: GetPublicKeyResponse = self.call("GetPublicKey", serde_json::json!({ "KeyId": arn }))?;
+        let der = base64::decode(response.public_key).map_err(|_| SignerError::DecodeError)?;
+        decode_public_key(der)
+    }
+
+    fn sign_with_key<Alg: SignatureAlgorithm>(
+        &self,
+        arn: &str,
+        algorithm: Alg,
+        data: &[u8],
+    ) -> Result<Signature<Alg>, SignerError> {
+        if algorithm.public_key_format() != PublicKeyFormat::Rsa {
+            return Err(SignerError::CloudKmsError(format!(
+                "Algorithm '{:?}' not supported",
+                algorithm.public_key_format()
+            )));
+        }
+
+        // Only send the digest, this keeps requests small irrespective of the size of the signed data.
+        let response: SignResponse = self.call(
+            "Sign",
+            serde_json::json!({
+                "KeyId": arn,
+                "Message": base64::encode(sha256(data)),
+                "MessageType": "DIGEST",
+                "SigningAlgorithm": "RSASSA_PKCS1_V1_5_SHA_256",
+            }),
+        )?;
+
+        let signature = base64::decode(response.signature).map_err(|_| SignerError::DecodeError)?;
+        Ok(Signature::new(algorithm, Bytes::from(signature)))
+    }
+
+    /// Schedules deletion of the key. KMS does not allow immediate deletion, but the key is disabled right away.
+    fn delete_key(&self, arn: &str) -> Result<(), SignerError> {
+        let _: serde_json::Value = self.call(
+            "ScheduleKeyDeletion",
+            serde_json::json!({
+                "KeyId": arn,
+                "PendingWindowInDays": self.config.deletion_window_days,
+            }),
+        )?;
+        Ok(())
+    }
+}
+
+//------------ Functions required to exist by the `SignerProvider` ----------------------------------------------------
+
+// Implement the functions defined by the `Signer` trait because `SignerProvider` expects to invoke them, but as the
+// dispatching is not trait based we don't actually have to implement the `Signer` trait.
+
+impl AwsKmsSigner {
+    pub fn create_key(&self, algorithm: PublicKeyFormat) -> Result<KeyIdentifier, SignerError> {
+        if !matches!(algorithm, PublicKeyFormat::Rsa) {
+            return Err(SignerError::CloudKmsError(format!(
+                "Algorithm {:?} not supported while creating key",
+                &algorithm
+            )));
+        }
+
+        let (public_key, arn) = self.build_key()?;
+        self.keys.remember(&public_key, &arn)
+    }
+
+    pub fn get_key_info(&self, key_id: &KeyIdentifier) -> Result<PublicKey, KeyError<SignerError>> {
+        if let Some(public_key) = self.keys.cached(key_id) {
+            return Ok(public_key);
+        }
+
+        let arn = self.keys.lookup(key_id)?;
+        let public_key = self.get_public_key(&arn).map_err(|err| match err {
+            SignerError::KeyNotFound => KeyError::KeyNotFound,
+            _ => KeyError::Signer(err),
+        })?;
+        self.keys.cache(public_key.clone());
+
+        Ok(public_key)
+    }
+
+    pub fn destroy_key(&self, key_id: &KeyIdentifier) -> Result<(), KeyError<SignerError>> {
+        let arn = self.keys.lookup(key_id)?;
+
+        let res = self.delete_key(&arn).map_err(|err| match err {
+            SignerError::KeyNotFound => KeyError::KeyNotFound,
+            _ => KeyError::Signer(err),
+        });
+
+        if let Err(err) = &res {
+            warn!(
+                "[{}] Failed to schedule deletion of AWS KMS key {} for key with ID {}: {}",
+                self.name, arn, key_id, err
+            );
+        }
+
+        res.and(self.keys.forget(key_id))
+    }
+
+    pub fn sign<Alg: SignatureAlgorithm, D: AsRef<[u8]> + ?Sized>(
+        &self,
+        key_id: &KeyIdentifier,
+        algorithm: Alg,
+        data: &D,
+    ) -> Result<Signature<Alg>, SigningError<SignerError>> {
+        let arn = self.keys.lookup(key_id)?;
+
+        self.sign_with_key(&arn, algorithm, data.as_ref())
+            .map_err(|err| match err {
+                SignerError::KeyNotFound => SigningError::KeyNotFound,
+                _ => SigningError::Signer(SignerError::CloudKmsError(format!(
+                    "Signing data failed for Krill KeyIdentifier '{}' and AWS KMS key '{}': {}",
+                    key_id, arn, err
+                ))),
+            })
+    }
+
+    pub fn sign_one_off<Alg: SignatureAlgorithm, D: AsRef<[u8]> + ?Sized>(
+        &self,
+        algorithm: Alg,
+        data: &D,
+    ) -> Result<(Signature<Alg>, PublicKey), SignerError> {
+        let (public_key, arn) = self.build_key()?;
+
+        let signature_res = self
+            .sign_with_key(&arn, algorithm, data.as_ref())
+            .map_err(|err| SignerError::CloudKmsError(format!("One-off signing of data failed: {}", err)));
+
+        let _ = self.delete_key(&arn);
+
+        Ok((signature_res?, public_key))
+    }
+}
+
+//------------ Tests --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use openssl::{
+        pkey::{PKey, Private},
+        rsa::Rsa,
+    };
+
+    use crate::{commons::crypto::signers::cloudkms::mock, test};
+
+    use super::*;
+
+    const ACCESS_KEY_ID: &str = "AKIDEXAMPLE";
+    const SECRET_ACCESS_KEY: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+    const REGION: &str = "eu-west-1";
+
+    //------------ Mock AWS KMS --------------------------------------------------------------------------------------
+
+    /// A mock AWS KMS which keeps its keys in memory. It verifies the signature of each request, and can be told to
+    /// throttle requests.
+    #[derive(Default)]
+    struct MockAwsKms {
+        keys: RwLock<HashMap<String, PKey<Private>>>,
+        deleted: RwLock<Vec<String>>,
+        throttle: AtomicBool,
+    }
+
+    impl MockAwsKms {
+        fn respond(&self, host: &str, headers: &hyper::HeaderMap, body: &[u8]) -> (u16, serde_json::Value) {
+            let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+
+            let credentials = AwsCredentials {
+                access_key_id: ACCESS_KEY_ID.to_string(),
+                secret_access_key: SECRET_ACCESS_KEY.to_string(),
+                session_token: None,
+                expiration: None,
+            };
+            let target = header("x-amz-target");
+            let expected_authorization =
+                AwsKmsSigner::authorization(&credentials, REGION, host, header("x-amz-date"), target, body).unwrap();
+            if header("authorization") != expected_authorization {
+                return Self::error(400, "InvalidSignatureException");
+            }
+            if self.throttle.load(Ordering::SeqCst) {
+                return Self::error(400, "ThrottlingException");
+            }
+
+            let request: serde_json::Value = serde_json::from_slice(body).unwrap();
+            let key_id = request["KeyId"].as_str().unwrap_or_default().to_string();
+
+            match target {
+                "TrentService.CreateKey" => {
+                    assert_eq!(request["KeySpec"], "RSA_2048");
+                    let mut keys = self.keys.write().unwrap();
+                    let arn = format!("arn:aws:kms:{}:111122223333:key/{}", REGION, keys.len());
+                    keys.insert(arn.clone(), PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap());
+                    (200, serde_json::json!({ "KeyMetadata": { "Arn": arn } }))
+                }
+                "TrentService.GetPublicKey" => match self.key(&key_id) {
+                    Some(key) => (
+                        200,
+                        serde_json::json!({ "PublicKey": base64::encode(key.public_key_to_der().unwrap()) }),
+                    ),
+                    None => Self::error(400, "NotFoundException"),
+                },
+                "TrentService.Sign" => match self.key(&key_id) {
+                    Some(key) => {
+                        // Only the digest is ever sent.
+                        assert_eq!(request["MessageType"], "DIGEST");
+                        let digest = base64::decode(request["Message"].as_str().unwrap()).unwrap();
+                        assert_eq!(digest.len(), 32);
+                        let signature = mock::sign_digest(&key, &digest);
+                        (200, serde_json::json!({ "Signature": base64::encode(signature) }))
+                    }
+                    None => Self::error(400, "NotFoundException"),
+                },
+                "TrentService.ScheduleKeyDeletion" => match self.key(&key_id) {
+                    Some(_) => {
+                        self.deleted.write().unwrap().push(key_id.clone());
+                        (200, serde_json::json!({ "KeyId": key_id }))
+                    }
+                    None => Self::error(400, "NotFoundException"),
+                },
+                _ => Self::error(400, "UnknownOperationException"),
+            }
+        }
+
+        /// Returns the key, unless it does not exist or is pending deletion.
+        fn key(&self, arn: &str) -> Option<PKey<Private>> {
+            if self.deleted.read().unwrap().iter().any(|deleted| deleted == arn) {
+                None
+            } else {
+                self.keys.read().unwrap().get(arn).cloned()
+            }
+        }
+
+        fn error(status: u16, error_type: &str) -> (u16, serde_json::Value) {
+            (
+                status,
+                serde_json::json!({ "__type": error_type, "message": "mock error" }),
+            )
+        }
+    }
+
+    /// Starts a mock AWS KMS, and builds a signer which uses it.
+    fn signer_with_mock_kms(work_dir: &std::path::Path) -> (AwsKmsSigner, Arc<MockAwsKms>) {
+        let kms = Arc::new(MockAwsKms::default());
+        let mock_kms = kms.clone();
+        let endpoint = mock::serve(move |_, _, headers, body| {
+            let host = headers.get("host").and_then(|v| v.to_str().ok()).unwrap_or_default();
+            mock_kms.respond(host, headers, body)
+        });
+
+        let config = AwsKmsSignerConfig {
+            region: REGION.to_string(),
+            endpoint: Some(endpoint),
+            access_key_id: Some(ACCESS_KEY_ID.to_string()),
+            secret_access_key: Some(SECRET_ACCESS_KEY.to_string()),
+            session_token: None,
+            timeout_seconds: AwsKmsSignerConfig::default_timeout_seconds(),
+            deletion_window_days: AwsKmsSignerConfig::default_deletion_window_days(),
+        };
+        let mapper = Arc::new(SignerMapper::build(work_dir).unwrap());
+        let signer = AwsKmsSigner::build("AWS KMS", &config, mapper.clone()).unwrap();
+
+        let (public_key, internal_id) = signer.create_registration_key().unwrap();
+        let handle = mapper
+            .add_signer("AWS KMS", &signer.get_info().unwrap(), &public_key, &internal_id)
+            .unwrap();
+        signer.set_handle(handle);
+
+        (signer, kms)
+    }
+
+    #[test]
+    fn sign_using_mock_kms() {
+        test::test_under_tmp(|d| {
+            let (signer, kms) = signer_with_mock_kms(&d);
+            let data = b"some data to sign";
+
+            let key_id = signer.create_key(PublicKeyFormat::Rsa).unwrap();
+            let public_key = signer.get_key_info(&key_id).unwrap();
+            assert_eq!(public_key.key_identifier(), key_id);
+
+            let signature = signer.sign(&key_id, RpkiSignatureAlgorithm::default(), data).unwrap();
+            assert!(mock::verify(&public_key, data, signature.value()));
+
+            // A destroyed key is scheduled for deletion, and forgotten.
+            signer.destroy_key(&key_id).unwrap();
+            assert_eq!(kms.deleted.read().unwrap().len(), 1);
+            assert!(matches!(signer.get_key_info(&key_id), Err(KeyError::KeyNotFound)));
+            assert!(matches!(
+                signer.sign(&key_id, RpkiSignatureAlgorithm::default(), data),
+                Err(SigningError::KeyNotFound)
+            ));
+
+            // One-off keys are deleted straight after signing.
+            let (signature, public_key) = signer.sign_one_off(RpkiSignatureAlgorithm::default(), data).unwrap();
+            assert!(mock::verify(&public_key, data, signature.value()));
+            assert_eq!(kms.deleted.read().unwrap().len(), 2);
+        });
+    }
+
+    #[test]
+    fn throttled_mock_kms_is_temporarily_unavailable() {
+        test::test_under_tmp(|d| {
+            let (signer, kms) = signer_with_mock_kms(&d);
+
+            kms.throttle.store(true, Ordering::SeqCst);
+            assert!(matches!(
+                signer.create_key(PublicKeyFormat::Rsa),
+                Err(SignerError::TemporarilyUnavailable)
+            ));
+
+            kms.throttle.store(false, Ordering::SeqCst);
+            assert!(signer.create_key(PublicKeyFormat::Rsa).is_ok());
+        });
+    }
+
+    #[test]
+    fn derive_signing_key() {
+        // Example from the AWS documentation on deriving a Signature Version 4 signing key.
+        let key = AwsKmsSigner::signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        )
+        .unwrap();
+
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn authorization_signs_session_token() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: Some("token".to_string()),
+            expiration: None,
+        };
+
+        let authorization = AwsKmsSigner::authorization(
+            &credentials,
+            "eu-west-1",
+            "kms.eu-west-1.amazonaws.com",
+            "20230101T120000Z",
+            "TrentService.Sign",
+            b"{}",
+        )
+        .unwrap();
+
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20230101/eu-west-1/kms/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date;x-amz-security-token;x-amz-target, Signature="
+        ));
+    }
+}
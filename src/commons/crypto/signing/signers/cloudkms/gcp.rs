@@ -0,0 +1,764 @@
+//! A signer which uses RSA keys held by Google Cloud Key Management Service.
+//!
+//! Requests are made to the Cloud KMS REST API using OAuth 2.0 access tokens. Tokens are obtained using the service
+//! account key file configured for the signer or named by the GOOGLE_APPLICATION_CREDENTIALS environment variable,
+//! or else from the metadata server for the service account of the GCE instance (or GKE workload) that Krill runs on.
+//!
+//! The service account needs the `roles/cloudkms.admin` and `roles/cloudkms.signerVerifier` roles on the key ring,
+//! or a custom role with the `cloudkms.cryptoKeys.create`, `cloudkms.cryptoKeyVersions.viewPublicKey`,
+//! `cloudkms.cryptoKeyVersions.useToSign` and `cloudkms.cryptoKeyVersions.destroy` permissions.
+//!
+//! Note that Cloud KMS never deletes keys. Destroying a key destroys its only key version, which makes the key
+//! material unusable after the destruction period of the key ring (24 hours by default), but the key itself remains
+//! listed in the key ring.
+use std::{
+    env,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use chrono::Utc;
+use openssl::{hash::MessageDigest, pkey::PKey, sha::sha256};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+
+use rpki::crypto::{
+    signer::KeyError, KeyIdentifier, PublicKey, PublicKeyFormat, RpkiSignature, RpkiSignatureAlgorithm, Signature,
+    SignatureAlgorithm, SigningError,
+};
+
+use crate::commons::{
+    api::Timestamp,
+    crypto::{
+        dispatch::signerinfo::SignerMapper,
+        signers::cloudkms::{decode_public_key, random_suffix, KmsHttpClient, KmsKeys},
+        SignerError, SignerHandle,
+    },
+    util::file,
+};
+
+//------------ Types and constants ------------------------------------------------------------------------------------
+
+const OAUTH_SCOPE: &str = "https://www.googleapis.com/auth/cloudkms";
+
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// Refresh access tokens this long before they expire.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Newly created HSM keys are generated asynchronously, wait at most this many times for them to become usable.
+const KEY_GENERATION_ATTEMPTS: u32 = 10;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct GcpKmsSignerConfig {
+    /// The key ring in which keys are created, e.g. "projects/my-project/locations/europe-west4/keyRings/krill".
+    pub key_ring: String,
+
+    #[serde(default = "GcpKmsSignerConfig::default_protection_level")]
+    pub protection_level: GcpProtectionLevel,
+
+    /// A service account key file in JSON format.
+    #[serde(default)]
+    pub credentials_path: Option<PathBuf>,
+
+    #[serde(default = "GcpKmsSignerConfig::default_endpoint")]
+    pub endpoint: String,
+
+    #[serde(default = "GcpKmsSignerConfig::default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+impl GcpKmsSignerConfig {
+    pub fn default_protection_level() -> GcpProtectionLevel {
+        GcpProtectionLevel::Hsm
+    }
+
+    pub fn default_endpoint() -> String {
+        "https://cloudkms.googleapis.com".to_string()
+    }
+
+    pub fn default_timeout_seconds() -> u64 {
+        10
+    }
+}
+
+/// Whether keys are created in software or in a Cloud HSM.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum GcpProtectionLevel {
+    Software,
+    Hsm,
+}
+
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+
+    #[serde(default = "ServiceAccountKey::default_token_uri")]
+    token_uri: String,
+}
+
+impl ServiceAccountKey {
+    fn default_token_uri() -> String {
+        "https://oauth2.googleapis.com/token".to_string()
+    }
+}
+
+impl std::fmt::Debug for ServiceAccountKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never log the private key.
+        f.debug_struct("ServiceAccountKey")
+            .field("client_email", &self.client_email)
+            .field("token_uri", &self.token_uri)
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+struct AccessToken {
+    token: String,
+    expires: Instant,
+}
+
+impl std::fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccessToken").field("expires", &self.expires).finish()
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct CryptoKey {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct PublicKeyResponse {
+    pem: String,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorDetails,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetails {
+    #[serde(default)]
+    message: String,
+
+    #[serde(default)]
+    status: String,
+}
+
+//------------ The Google Cloud KMS signer management interface -------------------------------------------------------
+
+#[derive(Debug)]
+pub struct GcpKmsSigner {
+    name: String,
+
+    config: GcpKmsSignerConfig,
+
+    /// The service account to use, or None to use the service account of the instance.
+    service_account: Option<ServiceAccountKey>,
+
+    http: KmsHttpClient,
+
+    keys: KmsKeys,
+
+    token: RwLock<Option<AccessToken>>,
+}
+
+impl GcpKmsSigner {
+    /// Creates a new instance of GcpKmsSigner.
+    ///
+    /// Like other remote signers, the service is not contacted until first use so that an unreachable service does
+    /// not block Krill startup.
+    pub fn build(name: &str, conf: &GcpKmsSignerConfig, mapper: Arc<SignerMapper>) -> Result<Self, SignerError> {
+        let credentials_path = conf
+            .credentials_path
+            .clone()
+            .or_else(|| env::var_os("GOOGLE_APPLICATION_CREDENTIALS").map(PathBuf::from));
+
+        let service_account = match credentials_path {
+            Some(path) => {
+                let bytes = file::read(&path)?;
+                Some(serde_json::from_slice(&bytes)?)
+            }
+            None => None,
+        };
+
+        let http = KmsHttpClient::build(name, Duration::from_secs(conf.timeout_seconds))?;
+
+        Ok(GcpKmsSigner {
+            name: name.to_string(),
+            config: conf.clone(),
+            service_account,
+            http,
+            keys: KmsKeys::new(mapper),
+            token: RwLock::new(None),
+        })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_handle(&self, handle: SignerHandle) {
+        self.keys.set_handle(handle)
+    }
+
+    pub fn get_info(&self) -> Option<String> {
+        Some(format!("Google Cloud KMS key ring {}", self.config.key_ring))
+    }
+
+    pub fn create_registration_key(&self) -> Result<(PublicKey, String), SignerError> {
+        self.build_key()
+    }
+
+    pub fn sign_registration_challenge<D: AsRef<[u8]> + ?Sized>(
+        &self,
+        signer_private_key_id: &str,
+        challenge: &D,
+    ) -> Result<RpkiSignature, SignerError> {
+        self.sign_with_key(
+            signer_private_key_id,
+            RpkiSignatureAlgorithm::default(),
+            challenge.as_ref(),
+        )
+    }
+}
+
+//------------ Authentication -----------------------------------------------------------------------------------------
+
+impl GcpKmsSigner {
+    fn access_token(&self) -> Result<String, SignerError> {
+        if let Some(token) = self.token.read().unwrap().as_ref() {
+            if token.expires > Instant::now() + TOKEN_REFRESH_MARGIN {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let request = match &self.service_account {
+            Some(service_account) => self.http.client().post(&service_account.token_uri).form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                (
+                    "assertion",
+                    &Self::jwt_assertion(service_account, Utc::now().timestamp())?,
+                ),
+            ]),
+            None => self
+                .http
+                .client()
+                .get(METADATA_TOKEN_URL)
+                .header("Metadata-Flavor", "Google"),
+        };
+
+        let (status, body) = self.http.execute(request)?;
+        if status != StatusCode::OK {
+            return Err(SignerError::CloudKmsError(format!(
+                "Cannot get Google Cloud access token, status {}: {}",
+                status,
+                String::from_utf8_lossy(&body)
+            )));
+        }
+
+        let response: TokenResponse = serde_json::from_slice(&body)?;
+        let token = AccessToken {
+            token: response.access_token,
+            expires: Instant::now() + Duration::from_secs(response.expires_in),
+        };
+        self.token.write().unwrap().replace(token.clone());
+
+        Ok(token.token)
+    }
+
+    /// Creates a signed JWT to exchange for an access token, as per RFC 7523.
+    fn jwt_assertion(service_account: &ServiceAccountKey, now: i64) -> Result<String, SignerError> {
+        let header = serde_json::json!({ "alg": "RS256", "typ": "JWT" });
+        let claims = serde_json::json!({
+            "iss": service_account.client_email,
+            "scope": OAUTH_SCOPE,
+            "aud": service_account.token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        });
+
+        let input = format!(
+            "{}.{}",
+            base64::encode_config(serde_json::to_vec(&header)?, base64::URL_SAFE_NO_PAD),
+            base64::encode_config(serde_json::to_vec(&claims)?, base64::URL_SAFE_NO_PAD)
+        );
+
+        let key = PKey::private_key_from_pem(service_account.private_key.as_bytes())?;
+        let mut signer = openssl::sign::Signer::new(MessageDigest::sha256(), &key)?;
+        signer.update(input.as_bytes())?;
+        let signature = signer.sign_to_vec()?;
+
+        Ok(format!(
+            "{}.{}",
+            input,
+            base64::encode_config(signature, base64::URL_SAFE_NO_PAD)
+        ))
+    }
+}
+
+//------------ Cloud KMS API ------------------------------------------------------------------------------------------
+
+impl GcpKmsSigner {
+    /// Invokes a Cloud KMS API method on the given resource path.
+    fn call<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        request: Option<serde_json::Value>,
+    ) -> Result<T, SignerError> {
+        let url = format!("{}/v1/{}", self.config.endpoint.trim_end_matches('/'), path);
+        let mut http_request = self
+            .http
+            .client()
+            .request(method, url)
+            .bearer_auth(self.access_token()?);
+        if let Some(request) = request {
+            http_request = http_request.json(&request);
+        }
+
+        let (status, body) = self.http.execute(http_request)?;
+
+        if status.is_success() {
+            return Ok(serde_json::from_slice(&body)?);
+        }
+
+        let error = serde_json::from_slice::<ErrorResponse>(&body)
+            .map(|res| res.error)
+            .unwrap_or(ErrorDetails {
+                message: String::from_utf8_lossy(&body).to_string(),
+                status: String::new(),
+            });
+
+        if status == StatusCode::NOT_FOUND {
+            Err(SignerError::KeyNotFound)
+        } else if status.is_server_error()
+            || status == StatusCode::TOO_MANY_REQUESTS
+            || error.status == "FAILED_PRECONDITION"
+        {
+            // FAILED_PRECONDITION is returned while a new key version is still being generated.
+            debug!(
+                "[{}] Google Cloud KMS request for {} failed with status {}: {}",
+                self.name, path, status, error.message
+            );
+            Err(SignerError::TemporarilyUnavailable)
+        } else {
+            Err(SignerError::CloudKmsError(format!(
+                "Google Cloud KMS request for {} failed with status {}: {} {}",
+                path, status, error.status, error.message
+            )))
+        }
+    }
+
+    /// Creates an RSA key in Cloud KMS and returns its public key and the name of its key version.
+    fn build_key(&self) -> Result<(PublicKey, String), SignerError> {
+        // https://tools.ietf.org/html/rfc6485#section-3: Asymmetric Key Pair Formats
+        //   "The RSA key pairs used to compute the signatures MUST have a 2048-bit
+        //    modulus and a public exponent (e) of 65,537."
+        let crypto_key_id = format!("krill-{}-{}", Timestamp::now(), random_suffix());
+
+        let key: CryptoKey = self.call(
+            Method::POST,
+            &format!("{}/cryptoKeys?cryptoKeyId={}", self.config.key_ring, crypto_key_id),
+            Some(serde_json::json!({
+                "purpose": "ASYMMETRIC_SIGN",
+                "versionTemplate": {
+                    "algorithm": "RSA_SIGN_PKCS1_2048_SHA256",
+                    "protectionLevel": self.config.protection_level,
+                },
+                "labels": { "krill": "true" },
+            })),
+        )?;
+
+        let version = format!("{}/cryptoKeyVersions/1", key.name);
+
+        let mut attempt = 1;
+        let public_key = loop {
+            match self.get_public_key(&version) {
+                Err(SignerError::TemporarilyUnavailable) if attempt < KEY_GENERATION_ATTEMPTS => {
+                    attempt += 1;
+                    thread::sleep(Duration::from_secs(1));
+                }
+                Err(err) => {
+                    let _ = self.destroy_key_version(&version);
+                    return Err(err);
+                }
+                Ok(public_key) => break public_key,
+            }
+        };
+
+        Ok((public_key, version))
+    }
+
+    fn get_public_key(&self, version: &str) -> Result<PublicKey, SignerError> {
+        let response: PublicKeyResponse = self.call(Method::GET, &format!("{}/publicKey", version), None)?;
+        let key = PKey::public_key_from_pem(response.pem.as_bytes())?;
+        decode_public_key(key.public_key_to_der()?)
+    }
+
+    fn sign_with_key<Alg: SignatureAlgorithm>(
+        &self,
+        version: &str,
+        algorithm: Alg,
+        data: &[u8],
+    ) -> Result<Signature<Alg>, SignerError> {
+        if algorithm.public_key_format() != PublicKeyFormat::Rsa {
+            return Err(SignerError::CloudKmsError(format!(
+                "Algorithm '{:?}' not supported",
+                algorithm.public_key_format()
+            )));
+        }
+
+        // Only send the digest, this keeps requests small irrespective of the size of the signed data.
+        let response: SignResponse = self.call(
+            Method::POST,
+            &format!("{}:asymmetricSign", version),
+            Some(serde_json::json!({ "digest": { "sha256": base64::encode(sha256(data)) } })),
+        )?;
+
+        let signature = base64::decode(response.signature).map_err(|_| SignerError::DecodeError)?;
+        Ok(Signature::new(algorithm, Bytes::from(signature)))
+    }
+
+    fn destroy_key_version(&self, version: &str) -> Result<(), SignerError> {
+        let _: serde_json::Value = self.call(
+            Method::POST,
+            &format!("{}:destroy", version),
+            Some(serde_json::json!({})),
+        )?;
+        Ok(())
+    }
+}
+
+//------------ Functions required to exist by the `SignerProvider` ----------------------------------------------------
+
+// Implement the functions defined by the `Signer` trait because `SignerProvider` expects to invoke them, but as the
+// dispatching is not trait based we don't actually have to implement the `Signer` trait.
+
+impl GcpKmsSigner {
+    pub fn create_key(&self, algorithm: PublicKeyFormat) -> Result<KeyIdentifier, SignerError> {
+        if !matches!(algorithm, PublicKeyFormat::Rsa) {
+            return Err(SignerError::CloudKmsError(format!(
+                "Algorithm {:?} not supported while creating key",
+                &algorithm
+            )));
+        }
+
+        let (public_key, version) = self.build_key()?;
+        self.keys.remember(&public_key, &version)
+    }
+
+    pub fn get_key_info(&self, key_id: &KeyIdentifier) -> Result<PublicKey, KeyError<SignerError>> {
+        if let Some(public_key) = self.keys.cached(key_id) {
+            return Ok(public_key);
+        }
+
+        let version = self.keys.lookup(key_id)?;
+        let public_key = self.get_public_key(&version).map_err(|err| match err {
+            SignerError::KeyNotFound => KeyError::KeyNotFound,
+            _ => KeyError::Signer(err),
+        })?;
+        self.keys.cache(public_key.clone());
+
+        Ok(public_key)
+    }
+
+    pub fn destroy_key(&self, key_id: &KeyIdentifier) -> Result<(), KeyError<SignerError>> {
+        let version = self.keys.lookup(key_id)?;
+
+        let res = self.destroy_key_version(&version).map_err(|err| match err {
+            SignerError::KeyNotFound => KeyError::KeyNotFound,
+            _ => KeyError::Signer(err),
+        });
+
+        if let Err(err) = &res {
+            warn!(
+                "[{}] Failed to destroy Google Cloud KMS key version {} for key with ID {}: {}",
+                self.name, version, key_id, err
+            );
+        }
+
+        res.and(self.keys.forget(key_id))
+    }
+
+    pub fn sign<Alg: SignatureAlgorithm, D: AsRef<[u8]> + ?Sized>(
+        &self,
+        key_id: &KeyIdentifier,
+        algorithm: Alg,
+        data: &D,
+    ) -> Result<Signature<Alg>, SigningError<SignerError>> {
+        let version = self.keys.lookup(key_id)?;
+
+        self.sign_with_key(&version, algorithm, data.as_ref())
+            .map_err(|err| match err {
+                SignerError::KeyNotFound => SigningError::KeyNotFound,
+                _ => SigningError::Signer(SignerError::CloudKmsError(format!(
+                    "Signing data failed for Krill KeyIdentifier '{}' and Google Cloud KMS key version '{}': {}",
+                    key_id, version, err
+                ))),
+            })
+    }
+
+    pub fn sign_one_off<Alg: SignatureAlgorithm, D: AsRef<[u8]> + ?Sized>(
+        &self,
+        algorithm: Alg,
+        data: &D,
+    ) -> Result<(Signature<Alg>, PublicKey), SignerError> {
+        let (public_key, version) = self.build_key()?;
+
+        let signature_res = self
+            .sign_with_key(&version, algorithm, data.as_ref())
+            .map_err(|err| SignerError::CloudKmsError(format!("One-off signing of data failed: {}", err)));
+
+        let _ = self.destroy_key_version(&version);
+
+        Ok((signature_res?, public_key))
+    }
+}
+
+//------------ Tests --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use openssl::{
+        pkey::{PKey, Private},
+        rsa::Rsa,
+    };
+
+    use crate::{commons::crypto::signers::cloudkms::mock, test};
+
+    use super::*;
+
+    const KEY_RING: &str = "projects/krill/locations/europe-west4/keyRings/krill";
+    const ACCESS_TOKEN: &str = "mock-access-token";
+
+    //------------ Mock Google Cloud KMS -----------------------------------------------------------------------------
+
+    /// A mock Google Cloud KMS, and OAuth 2.0 token endpoint, which keeps its keys in memory. Like the real service, it
+    /// reports new keys as not yet usable the first time their public key is requested.
+    #[derive(Default)]
+    struct MockGcpKms {
+        keys: RwLock<HashMap<String, PKey<Private>>>,
+        pending: RwLock<Vec<String>>,
+        destroyed: RwLock<Vec<String>>,
+        tokens_issued: AtomicUsize,
+    }
+
+    impl MockGcpKms {
+        fn respond(
+            &self,
+            method: &hyper::Method,
+            path: &str,
+            headers: &hyper::HeaderMap,
+            body: &[u8],
+        ) -> (u16, serde_json::Value) {
+            if path == "/token" {
+                self.tokens_issued.fetch_add(1, Ordering::SeqCst);
+                return (
+                    200,
+                    serde_json::json!({ "access_token": ACCESS_TOKEN, "expires_in": 3600 }),
+                );
+            }
+
+            let authorization = headers.get("authorization").and_then(|v| v.to_str().ok());
+            if authorization != Some(format!("Bearer {}", ACCESS_TOKEN).as_str()) {
+                return Self::error(401, "UNAUTHENTICATED");
+            }
+
+            let path = path.strip_prefix("/v1/").unwrap_or_default();
+            let create_prefix = format!("{}/cryptoKeys?cryptoKeyId=", KEY_RING);
+
+            if let Some(crypto_key_id) = path.strip_prefix(&create_prefix) {
+                assert_eq!(method, hyper::Method::POST);
+                let name = format!("{}/cryptoKeys/{}", KEY_RING, crypto_key_id);
+                let version = format!("{}/cryptoKeyVersions/1", name);
+                let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+                self.keys.write().unwrap().insert(version.clone(), key);
+                self.pending.write().unwrap().push(version);
+                (200, serde_json::json!({ "name": name }))
+            } else if let Some(version) = path.strip_suffix("/publicKey") {
+                let mut pending = self.pending.write().unwrap();
+                if pending.iter().any(|pending| pending == version) {
+                    pending.retain(|pending| pending != version);
+                    return Self::error(400, "FAILED_PRECONDITION");
+                }
+                match self.key(version) {
+                    Some(key) => {
+                        let pem = String::from_utf8(key.public_key_to_pem().unwrap()).unwrap();
+                        (200, serde_json::json!({ "pem": pem }))
+                    }
+                    None => Self::error(404, "NOT_FOUND"),
+                }
+            } else if let Some(version) = path.strip_suffix(":asymmetricSign") {
+                match self.key(version) {
+                    Some(key) => {
+                        // Only the digest is ever sent.
+                        let request: serde_json::Value = serde_json::from_slice(body).unwrap();
+                        let digest = base64::decode(request["digest"]["sha256"].as_str().unwrap()).unwrap();
+                        assert_eq!(digest.len(), 32);
+
+                        let signature = mock::sign_digest(&key, &digest);
+                        (200, serde_json::json!({ "signature": base64::encode(signature) }))
+                    }
+                    None => Self::error(404, "NOT_FOUND"),
+                }
+            } else if let Some(version) = path.strip_suffix(":destroy") {
+                match self.key(version) {
+                    Some(_) => {
+                        self.destroyed.write().unwrap().push(version.to_string());
+                        (
+                            200,
+                            serde_json::json!({ "name": version, "state": "DESTROY_SCHEDULED" }),
+                        )
+                    }
+                    None => Self::error(404, "NOT_FOUND"),
+                }
+            } else {
+                Self::error(404, "NOT_FOUND")
+            }
+        }
+
+        /// Returns the key version, unless it does not exist or is destroyed.
+        fn key(&self, version: &str) -> Option<PKey<Private>> {
+            if self
+                .destroyed
+                .read()
+                .unwrap()
+                .iter()
+                .any(|destroyed| destroyed == version)
+            {
+                None
+            } else {
+                self.keys.read().unwrap().get(version).cloned()
+            }
+        }
+
+        fn error(code: u16, status: &str) -> (u16, serde_json::Value) {
+            (
+                code,
+                serde_json::json!({ "error": { "code": code, "message": "mock error", "status": status } }),
+            )
+        }
+    }
+
+    /// Starts a mock Google Cloud KMS, and builds a signer which uses it with a service account key file.
+    fn signer_with_mock_kms(work_dir: &std::path::Path) -> (GcpKmsSigner, Arc<MockGcpKms>) {
+        let kms = Arc::new(MockGcpKms::default());
+        let mock_kms = kms.clone();
+        let endpoint = mock::serve(move |method, path, headers, body| mock_kms.respond(method, path, headers, body));
+
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let service_account = serde_json::json!({
+            "client_email": "krill@krill.iam.gserviceaccount.com",
+            "private_key": String::from_utf8(key.private_key_to_pem_pkcs8().unwrap()).unwrap(),
+            "token_uri": format!("{}/token", endpoint),
+        });
+        let credentials_path = work_dir.join("service-account.json");
+        file::save(service_account.to_string().as_bytes(), &credentials_path).unwrap();
+
+        let config = GcpKmsSignerConfig {
+            key_ring: KEY_RING.to_string(),
+            protection_level: GcpProtectionLevel::Software,
+            credentials_path: Some(credentials_path),
+            endpoint,
+            timeout_seconds: GcpKmsSignerConfig::default_timeout_seconds(),
+        };
+        let mapper = Arc::new(SignerMapper::build(work_dir).unwrap());
+        let signer = GcpKmsSigner::build("Google Cloud KMS", &config, mapper.clone()).unwrap();
+
+        let (public_key, internal_id) = signer.create_registration_key().unwrap();
+        let handle = mapper
+            .add_signer(
+                "Google Cloud KMS",
+                &signer.get_info().unwrap(),
+                &public_key,
+                &internal_id,
+            )
+            .unwrap();
+        signer.set_handle(handle);
+
+        (signer, kms)
+    }
+
+    #[test]
+    fn sign_using_mock_kms() {
+        test::test_under_tmp(|d| {
+            let (signer, kms) = signer_with_mock_kms(&d);
+            let data = b"some data to sign";
+
+            // Keys are usable once their generation is complete.
+            let key_id = signer.create_key(PublicKeyFormat::Rsa).unwrap();
+            let public_key = signer.get_key_info(&key_id).unwrap();
+            assert_eq!(public_key.key_identifier(), key_id);
+
+            let signature = signer.sign(&key_id, RpkiSignatureAlgorithm::default(), data).unwrap();
+            assert!(mock::verify(&public_key, data, signature.value()));
+
+            // The access token is reused until it expires.
+            assert_eq!(kms.tokens_issued.load(Ordering::SeqCst), 1);
+
+            // A destroyed key version is forgotten.
+            signer.destroy_key(&key_id).unwrap();
+            assert_eq!(kms.destroyed.read().unwrap().len(), 1);
+            assert!(matches!(signer.get_key_info(&key_id), Err(KeyError::KeyNotFound)));
+
+            // One-off keys are destroyed straight after signing.
+            let (signature, public_key) = signer.sign_one_off(RpkiSignatureAlgorithm::default(), data).unwrap();
+            assert!(mock::verify(&public_key, data, signature.value()));
+            assert_eq!(kms.destroyed.read().unwrap().len(), 2);
+        });
+    }
+
+    #[test]
+    fn create_verifiable_jwt_assertion() {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let service_account = ServiceAccountKey {
+            client_email: "krill@project.iam.gserviceaccount.com".to_string(),
+            private_key: String::from_utf8(key.private_key_to_pem_pkcs8().unwrap()).unwrap(),
+            token_uri: ServiceAccountKey::default_token_uri(),
+        };
+
+        let jwt = GcpKmsSigner::jwt_assertion(&service_account, 1_700_000_000).unwrap();
+        let parts: Vec<&str> = jwt.split('.').collect();
+        assert_eq!(parts.len(), 3);
+
+        let claims: serde_json::Value =
+            serde_json::from_slice(&base64::decode_config(parts[1], base64::URL_SAFE_NO_PAD).unwrap()).unwrap();
+        assert_eq!(claims["iss"], "krill@project.iam.gserviceaccount.com");
+        assert_eq!(claims["aud"], "https://oauth2.googleapis.com/token");
+        assert_eq!(claims["exp"], 1_700_003_600);
+
+        let signature = base64::decode_config(parts[2], base64::URL_SAFE_NO_PAD).unwrap();
+        let mut verifier = openssl::sign::Verifier::new(MessageDigest::sha256(), &key).unwrap();
+        verifier
+            .update(format!("{}.{}", parts[0], parts[1]).as_bytes())
+            .unwrap();
+        assert!(verifier.verify(&signature).unwrap());
+    }
+}
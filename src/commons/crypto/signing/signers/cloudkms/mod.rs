@@ -0,0 +1,309 @@
+//! Support for signing things using asymmetric keys held by a cloud key management service.
+//!
+//! Two services are supported:
+//!   - `aws`: AWS Key Management Service, using the JSON 1.1 API with AWS Signature Version 4 request signing.
+//!   - `gcp`: Google Cloud Key Management Service, using the REST API with OAuth 2.0 access tokens.
+//!
+//! Both signers authenticate using the identity of the host where possible (an EC2 instance role or a GCE service
+//! account) so that no long-lived secrets need to be present in the Krill configuration.
+//!
+//! # Latency
+//!
+//! Unlike the OpenSSL signer, every key creation, signature and key deletion is a HTTPS round trip to the cloud
+//! service, which typically takes tens of milliseconds and is subject to the rate limits of the service. Neither
+//! service offers an API to sign multiple digests in a single request, so signing cannot be batched. To keep the
+//! number and size of requests down:
+//!   - only the SHA-256 digest of the data to sign is sent, never the data itself,
+//!   - public keys are fetched once and then cached,
+//!   - HTTP connections to the service are kept alive and reused between requests.
+//!
+//! Operations which sign many objects, e.g. republishing a CA with many ROAs, will therefore take noticeably longer
+//! than with a local signer. One-off signing should be left to the (default) OpenSSL one-off signer, as creating and
+//! deleting a key in the cloud service for every signed object is slow and (for AWS) costly.
+pub mod aws;
+pub mod gcp;
+
+pub use aws::{AwsKmsSigner, AwsKmsSignerConfig};
+pub use gcp::{GcpKmsSigner, GcpKmsSignerConfig};
+
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Arc, RwLock},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use reqwest::StatusCode;
+use rpki::crypto::{signer::KeyError, KeyIdentifier, PublicKey};
+use tokio::runtime::Runtime;
+
+use crate::{
+    commons::crypto::{dispatch::signerinfo::SignerMapper, SignerError, SignerHandle},
+    constants::KRILL_VERSION,
+};
+
+//------------ KmsHttpClient -------------------------------------------------
+
+/// A HTTP client for use by the (synchronous) signer interface.
+///
+/// Signer functions may be called both from async code and from plain threads, so requests cannot simply be executed
+/// on the current Tokio runtime. Instead they are spawned on a small runtime owned by the client, and the caller
+/// blocks until the response is received.
+#[derive(Debug)]
+pub struct KmsHttpClient {
+    client: reqwest::Client,
+    runtime: Option<Runtime>,
+}
+
+impl KmsHttpClient {
+    pub fn build(name: &str, timeout: Duration) -> Result<Self, SignerError> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent(format!("krill/{}", KRILL_VERSION))
+            .build()
+            .map_err(|e| SignerError::CloudKmsError(format!("Cannot create HTTP client: {}", e)))?;
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name(format!("kms-{}", name))
+            .enable_all()
+            .build()
+            .map_err(|e| SignerError::CloudKmsError(format!("Cannot create runtime: {}", e)))?;
+
+        Ok(KmsHttpClient {
+            client,
+            runtime: Some(runtime),
+        })
+    }
+
+    /// Returns the underlying client for building requests.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Sends the request and waits for the response status and body.
+    ///
+    /// Failures to reach the service are reported as [`SignerError::TemporarilyUnavailable`] so that the signer will
+    /// be probed again later, as is done for signers with unreachable HSMs.
+    pub fn execute(&self, request: reqwest::RequestBuilder) -> Result<(StatusCode, Bytes), SignerError> {
+        let (sender, receiver) = mpsc::sync_channel(1);
+
+        self.runtime.as_ref().unwrap().spawn(async move {
+            let res = match request.send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    response.bytes().await.map(|body| (status, body))
+                }
+                Err(e) => Err(e),
+            };
+            let _ = sender.send(res);
+        });
+
+        match receiver.recv() {
+            Ok(Ok(res)) => Ok(res),
+            Ok(Err(e)) => {
+                warn!("Cannot reach key management service: {}", e);
+                Err(SignerError::TemporarilyUnavailable)
+            }
+            Err(_) => Err(SignerError::CloudKmsError("HTTP request was aborted".to_string())),
+        }
+    }
+}
+
+impl Drop for KmsHttpClient {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which is not allowed if we are dropped from async code.
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+//------------ KmsKeys -------------------------------------------------------
+
+/// Keeps track of the cloud service key ids which correspond to Krill KeyIdentifiers, and caches their public keys.
+///
+/// The mapping is stored using the [`SignerMapper`] so that keys can be found again after a restart. Public keys are
+/// only cached in memory, fetching a public key from the service again is cheap compared to signing.
+#[derive(Debug)]
+pub struct KmsKeys {
+    handle: RwLock<Option<SignerHandle>>,
+    mapper: Arc<SignerMapper>,
+    public_keys: RwLock<HashMap<KeyIdentifier, PublicKey>>,
+}
+
+impl KmsKeys {
+    pub fn new(mapper: Arc<SignerMapper>) -> Self {
+        KmsKeys {
+            handle: RwLock::new(None),
+            mapper,
+            public_keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_handle(&self, handle: SignerHandle) {
+        let mut writable_handle = self.handle.write().unwrap();
+        if writable_handle.is_some() {
+            panic!("Cannot set signer handle as handle is already set");
+        }
+        *writable_handle = Some(handle);
+    }
+
+    /// Remember that the given cloud service key id corresponds to the given public key.
+    pub fn remember(&self, public_key: &PublicKey, kms_key_id: &str) -> Result<KeyIdentifier, SignerError> {
+        let key_id = public_key.key_identifier();
+
+        let readable_handle = self.handle.read().unwrap();
+        let signer_handle = readable_handle
+            .as_ref()
+            .ok_or_else(|| SignerError::Other("Failed to record signer key: Signer handle not set".to_string()))?;
+
+        self.mapper
+            .add_key(signer_handle, &key_id, kms_key_id)
+            .map_err(|err| SignerError::CloudKmsError(format!("Failed to record signer key: {}", err)))?;
+
+        self.cache(public_key.clone());
+
+        Ok(key_id)
+    }
+
+    /// Given a KeyIdentifier lookup the corresponding cloud service key id.
+    pub fn lookup(&self, key_id: &KeyIdentifier) -> Result<String, KeyError<SignerError>> {
+        let readable_handle = self.handle.read().unwrap();
+        let signer_handle = readable_handle.as_ref().ok_or(KeyError::KeyNotFound)?;
+
+        self.mapper
+            .get_key(signer_handle, key_id)
+            .map_err(|_| KeyError::KeyNotFound)
+    }
+
+    /// Forget the given key, both the mapping and the cached public key.
+    pub fn forget(&self, key_id: &KeyIdentifier) -> Result<(), KeyError<SignerError>> {
+        self.public_keys.write().unwrap().remove(key_id);
+
+        if let Some(signer_handle) = self.handle.read().unwrap().as_ref() {
+            self.mapper
+                .remove_key(signer_handle, key_id)
+                .map_err(|err| KeyError::Signer(SignerError::Other(err.to_string())))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn cached(&self, key_id: &KeyIdentifier) -> Option<PublicKey> {
+        self.public_keys.read().unwrap().get(key_id).cloned()
+    }
+
+    pub fn cache(&self, public_key: PublicKey) {
+        self.public_keys
+            .write()
+            .unwrap()
+            .insert(public_key.key_identifier(), public_key);
+    }
+}
+
+//------------ Helper functions ----------------------------------------------
+
+/// Decodes a DER encoded X.509 SubjectPublicKeyInfo as returned by the cloud services.
+fn decode_public_key(der: Vec<u8>) -> Result<PublicKey, SignerError> {
+    PublicKey::decode(Bytes::from(der)).map_err(|_| SignerError::DecodeError)
+}
+
+/// Returns a random suffix for naming new keys.
+fn random_suffix() -> String {
+    hex::encode(rand::random::<[u8; 8]>())
+}
+
+//------------ Mock key management service -----------------------------------
+
+#[cfg(test)]
+mod mock {
+    use std::{convert::Infallible, sync::mpsc, thread};
+
+    use hyper::{
+        server::conn::AddrIncoming,
+        service::{make_service_fn, service_fn},
+        HeaderMap, Method,
+    };
+    use openssl::{
+        pkey::{PKey, Private},
+        rsa::Padding,
+    };
+
+    use super::*;
+
+    /// Starts a mock HTTP server for a key management service, and returns its endpoint. The server is given a thread
+    /// and runtime of its own, as the signers block while they wait for a response.
+    ///
+    /// The respond function is called with the method, path and query, the headers and the body of each request, and
+    /// returns the status and JSON body of the response.
+    pub fn serve<F>(respond: F) -> String
+    where
+        F: Fn(&Method, &str, &HeaderMap, &[u8]) -> (u16, serde_json::Value) + Send + Sync + 'static,
+    {
+        let respond = Arc::new(respond);
+        let (sender, receiver) = mpsc::sync_channel(1);
+
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(async move {
+                let incoming = AddrIncoming::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+                sender.send(format!("http://{}", incoming.local_addr())).unwrap();
+
+                let service = make_service_fn(move |_| {
+                    let respond = respond.clone();
+                    async move {
+                        Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| {
+                            let respond = respond.clone();
+                            async move {
+                                let (parts, body) = req.into_parts();
+                                let body = hyper::body::to_bytes(body).await.unwrap();
+                                let path = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+                                let (status, json) = respond(&parts.method, path, &parts.headers, &body);
+
+                                Ok::<_, Infallible>(
+                                    hyper::Response::builder()
+                                        .status(status)
+                                        .body(hyper::Body::from(json.to_string()))
+                                        .unwrap(),
+                                )
+                            }
+                        }))
+                    }
+                });
+
+                hyper::Server::builder(incoming).serve(service).await.unwrap();
+            });
+        });
+
+        receiver.recv().unwrap()
+    }
+
+    /// Signs a SHA-256 digest using RSASSA-PKCS1-v1_5, as the services do when they are given a digest rather than the
+    /// message itself.
+    pub fn sign_digest(key: &PKey<Private>, digest: &[u8]) -> Vec<u8> {
+        // The DER encoded DigestInfo prefix for SHA-256, see RFC 8017 section 9.2.
+        let mut digest_info = hex::decode("3031300d060960864801650304020105000420").unwrap();
+        digest_info.extend_from_slice(digest);
+
+        let rsa = key.rsa().unwrap();
+        let mut signature = vec![0; rsa.size() as usize];
+        let len = rsa
+            .private_encrypt(&digest_info, &mut signature, Padding::PKCS1)
+            .unwrap();
+        signature.truncate(len);
+        signature
+    }
+
+    /// Verifies an RSASSA-PKCS1-v1_5 SHA-256 signature over the data.
+    pub fn verify(public_key: &PublicKey, data: &[u8], signature: &[u8]) -> bool {
+        let key = PKey::public_key_from_der(&public_key.to_info_bytes()).unwrap();
+        let mut verifier = openssl::sign::Verifier::new(openssl::hash::MessageDigest::sha256(), &key).unwrap();
+        verifier.update(data).unwrap();
+        verifier.verify(signature).unwrap()
+    }
+}
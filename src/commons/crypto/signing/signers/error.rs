@@ -7,6 +7,7 @@ use crate::commons::error::KrillIoError;
 
 #[derive(Debug)]
 pub enum SignerError {
+    CloudKmsError(String),
     DecodeError,
     InvalidWorkDir(PathBuf),
     IoError(KrillIoError),
@@ -30,6 +31,7 @@ impl SignerError {
 impl fmt::Display for SignerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            SignerError::CloudKmsError(e) => write!(f, "Cloud KMS Error: {}", e),
             SignerError::DecodeError => write!(f, "Could not decode key"),
             SignerError::InvalidWorkDir(path) => write!(f, "Invalid base path: {}", path.to_string_lossy()),
             SignerError::IoError(e) => e.fmt(f),
@@ -1,5 +1,8 @@
 pub mod error;
 
+#[cfg(feature = "hsm")]
+pub mod cloudkms;
+
 #[cfg(feature = "hsm")]
 pub mod kmip;
 
@@ -1,5 +1,6 @@
 //! Support for signing things using software keys (through openssl) and
-//! storing them unencrypted on disk.
+//! storing them unencrypted on disk, or in the shared key value store when
+//! Krill runs in high availability mode.
 use std::{
     fs,
     fs::File,
@@ -8,14 +9,17 @@ use std::{
     str::FromStr,
     sync::Arc,
     sync::RwLock,
+    time::SystemTime,
 };
 
 use bytes::Bytes;
 use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
 
 use openssl::{
+    ec::{EcGroup, EcKey},
     hash::MessageDigest,
-    pkey::{PKey, PKeyRef, Private},
+    nid::Nid,
+    pkey::{Id, PKey, PKeyRef, Private},
     rsa::Rsa,
 };
 
@@ -29,8 +33,9 @@ use crate::{
     commons::{
         crypto::{dispatch::signerinfo::SignerMapper, signers::error::SignerError, SignerHandle},
         error::KrillIoError,
+        eventsourcing::{KeyStoreKey, KeyValueError, KeyValueStorage, KeyValueStore},
     },
-    constants::KEYS_DIR,
+    constants::{KEYS_ARCHIVE_DIR, KEYS_DIR},
 };
 
 //------------ OpenSslSigner -------------------------------------------------
@@ -49,10 +54,21 @@ impl OpenSslSignerConfig {
     }
 }
 
+/// Where an [OpenSslSigner] keeps its keys.
+#[derive(Debug)]
+enum OpenSslKeyStorage {
+    /// One file per key in a local directory.
+    Dir(Arc<Path>),
+
+    /// In a key value store shared by all Krill instances in high
+    /// availability mode, so that a standby can sign after it is promoted.
+    Shared(KeyValueStore),
+}
+
 /// An openssl based signer.
 #[derive(Debug)]
 pub struct OpenSslSigner {
-    keys_dir: Arc<Path>,
+    keys: OpenSslKeyStorage,
 
     name: String,
 
@@ -79,12 +95,37 @@ impl OpenSslSigner {
             )),
             handle: RwLock::new(None), // will be set later
             mapper,
-            keys_dir: keys_dir.into(),
+            keys: OpenSslKeyStorage::Dir(keys_dir.into()),
         };
 
         Ok(s)
     }
 
+    /// Builds a signer that keeps its keys in the given storage rather than
+    /// in a local directory. Disk storage results in the same layout as
+    /// [build](Self::build).
+    pub fn build_in(
+        storage: &KeyValueStorage,
+        name: &str,
+        mapper: Option<Arc<SignerMapper>>,
+    ) -> Result<Self, SignerError> {
+        let store = match storage {
+            KeyValueStorage::Disk(work_dir) => return Self::build(work_dir, name, mapper),
+            KeyValueStorage::Postgres(_) => KeyValueStore::create(storage, KEYS_DIR).map_err(Self::kv_err)?,
+        };
+
+        Ok(OpenSslSigner {
+            name: name.to_string(),
+            info: Some(format!(
+                "OpenSSL Soft Signer [version: {}, keys stored in the shared database]",
+                openssl::version::version(),
+            )),
+            handle: RwLock::new(None), // will be set later
+            mapper,
+            keys: OpenSslKeyStorage::Shared(store),
+        })
+    }
+
     pub fn get_name(&self) -> &str {
         &self.name
     }
@@ -103,7 +144,7 @@ impl OpenSslSigner {
 
     pub fn create_registration_key(&self) -> Result<(PublicKey, String), SignerError> {
         // For the OpenSslSigner we use the KeyIdentifier as the internal key id so the two are the same.
-        let key_id = self.build_key()?;
+        let key_id = self.build_key(PublicKeyFormat::Rsa)?;
         let internal_key_id = key_id.to_string();
         let key_pair = self.load_key(&key_id)?;
         let public_key = key_pair.subject_public_key_info()?;
@@ -150,8 +191,8 @@ impl OpenSslSigner {
         }
     }
 
-    fn build_key(&self) -> Result<KeyIdentifier, SignerError> {
-        let kp = OpenSslKeyPair::build()?;
+    fn build_key(&self, algorithm: PublicKeyFormat) -> Result<KeyIdentifier, SignerError> {
+        let kp = OpenSslKeyPair::build(algorithm)?;
         self.store_key(kp)
     }
 
@@ -159,7 +200,15 @@ impl OpenSslSigner {
         let pk = &kp.subject_public_key_info()?;
         let key_id = pk.key_identifier();
 
-        let path = self.key_path(&key_id);
+        let keys_dir = match &self.keys {
+            OpenSslKeyStorage::Dir(keys_dir) => keys_dir,
+            OpenSslKeyStorage::Shared(store) => {
+                store.store(&Self::kv_key(&key_id), &kp).map_err(Self::kv_err)?;
+                return Ok(key_id);
+            }
+        };
+
+        let path = keys_dir.join(key_id.to_string());
         let json = serde_json::to_string(&kp)?;
 
         let mut f = File::create(&path)
@@ -176,7 +225,11 @@ impl OpenSslSigner {
         data: &D,
     ) -> Result<Signature<Alg>, SignerError> {
         let signing_algorithm = algorithm.signing_algorithm();
-        if !matches!(signing_algorithm, SigningAlgorithm::RsaSha256) {
+        let key_matches_algorithm = match signing_algorithm {
+            SigningAlgorithm::RsaSha256 => pkey.id() == Id::RSA,
+            SigningAlgorithm::EcdsaP256Sha256 => pkey.id() == Id::EC,
+        };
+        if !key_matches_algorithm {
             return Err(SignerError::UnsupportedSigningAlg(signing_algorithm));
         }
 
@@ -189,7 +242,17 @@ impl OpenSslSigner {
     }
 
     fn load_key(&self, id: &KeyIdentifier) -> Result<OpenSslKeyPair, SignerError> {
-        let path = self.key_path(id);
+        let keys_dir = match &self.keys {
+            OpenSslKeyStorage::Dir(keys_dir) => keys_dir,
+            OpenSslKeyStorage::Shared(store) => {
+                return store
+                    .get(&Self::kv_key(id))
+                    .map_err(Self::kv_err)?
+                    .ok_or(SignerError::KeyNotFound)
+            }
+        };
+
+        let path = keys_dir.join(id.to_string());
         if path.exists() {
             let f = File::open(&path)
                 .map_err(|e| KrillIoError::new(format!("Could not read key file '{}'", path.to_string_lossy()), e))?;
@@ -200,10 +263,12 @@ impl OpenSslSigner {
         }
     }
 
-    fn key_path(&self, key_id: &KeyIdentifier) -> PathBuf {
-        let mut path = self.keys_dir.to_path_buf();
-        path.push(&key_id.to_string());
-        path
+    fn kv_key(key_id: &KeyIdentifier) -> KeyStoreKey {
+        KeyStoreKey::simple(key_id.to_string())
+    }
+
+    fn kv_err(e: KeyValueError) -> SignerError {
+        SignerError::Other(format!("Key store error: {}", e))
     }
 
     fn remember_key_id(&self, key_id: &KeyIdentifier) -> Result<(), SignerError> {
@@ -228,8 +293,8 @@ impl OpenSslSigner {
 // Implement the functions defined by the `Signer` trait because `SignerProvider` expects to invoke them, but as the
 // dispatching is not trait based we don't actually have to implement the `Signer` trait.
 impl OpenSslSigner {
-    pub fn create_key(&self, _algorithm: PublicKeyFormat) -> Result<KeyIdentifier, SignerError> {
-        let key_id = self.build_key()?;
+    pub fn create_key(&self, algorithm: PublicKeyFormat) -> Result<KeyIdentifier, SignerError> {
+        let key_id = self.build_key(algorithm)?;
         self.remember_key_id(&key_id)?;
 
         Ok(key_id)
@@ -244,13 +309,121 @@ impl OpenSslSigner {
         Ok(key_id)
     }
 
+    /// Returns the identifier of a PEM encoded private key, without
+    /// importing it.
+    pub fn pem_key_identifier(pem: &str) -> Result<KeyIdentifier, SignerError> {
+        let kp = OpenSslKeyPair::from_pem(pem)?;
+        Ok(kp.subject_public_key_info()?.key_identifier())
+    }
+
+    /// Export the private key as PEM encoded PKCS#8, so that it can be
+    /// imported again with [`import_key`](Self::import_key).
+    pub fn export_key(&self, key_id: &KeyIdentifier) -> Result<String, SignerError> {
+        let kp = self.load_key(key_id)?;
+        let pem = kp.pkey.private_key_to_pem_pkcs8()?;
+        String::from_utf8(pem).map_err(|e| SignerError::other(format!("Could not encode key as PEM: {}", e)))
+    }
+
+    /// Returns the keys stored by this signer, with the time that their key
+    /// file was last modified, if known. Archived keys are not included.
+    /// The modification time is not known for keys in a shared store.
+    pub fn list_keys(&self) -> Result<Vec<(KeyIdentifier, Option<SystemTime>)>, SignerError> {
+        let keys_dir = match &self.keys {
+            OpenSslKeyStorage::Dir(keys_dir) => keys_dir,
+            OpenSslKeyStorage::Shared(store) => {
+                let keys = store.keys(None, "").map_err(Self::kv_err)?;
+                return Ok(keys
+                    .iter()
+                    .filter_map(|key| KeyIdentifier::from_str(key.name()).ok())
+                    .map(|key_id| (key_id, None))
+                    .collect());
+            }
+        };
+
+        let entries = fs::read_dir(keys_dir)
+            .map_err(|e| KrillIoError::new(format!("Could not list keys dir '{}'", keys_dir.to_string_lossy()), e))?;
+
+        let mut keys = vec![];
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            // Other files, e.g. left behind by an editor, are not keys.
+            if let Some(key_id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| KeyIdentifier::from_str(name).ok())
+            {
+                let modified = entry.metadata().and_then(|meta| meta.modified()).ok();
+                keys.push((key_id, modified));
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Returns true if this signer stores the given key.
+    pub fn has_key(&self, key_id: &KeyIdentifier) -> bool {
+        match &self.keys {
+            OpenSslKeyStorage::Dir(keys_dir) => keys_dir.join(key_id.to_string()).is_file(),
+            OpenSslKeyStorage::Shared(store) => store.has(&Self::kv_key(key_id)).unwrap_or(false),
+        }
+    }
+
+    /// Moves the key file to the archive dir under the keys dir. The key can
+    /// no longer be used, but it can be restored by moving the file back.
+    pub fn archive_key(&self, key_id: &KeyIdentifier) -> Result<(), SignerError> {
+        if !self.has_key(key_id) {
+            return Err(SignerError::KeyNotFound);
+        }
+
+        let keys_dir = match &self.keys {
+            OpenSslKeyStorage::Dir(keys_dir) => keys_dir,
+            OpenSslKeyStorage::Shared(store) => {
+                let key = Self::kv_key(key_id);
+                let archived = KeyStoreKey::scoped(KEYS_ARCHIVE_DIR.to_string(), key.name().to_string());
+                return store.move_key(&key, &archived).map_err(Self::kv_err);
+            }
+        };
+
+        let path = keys_dir.join(key_id.to_string());
+        let archive_dir = keys_dir.join(KEYS_ARCHIVE_DIR);
+        fs::create_dir_all(&archive_dir).map_err(|e| {
+            KrillIoError::new(
+                format!("Could not create key archive dir '{}'", archive_dir.to_string_lossy()),
+                e,
+            )
+        })?;
+
+        let archived = archive_dir.join(key_id.to_string());
+        fs::rename(&path, &archived).map_err(|e| {
+            KrillIoError::new(
+                format!(
+                    "Could not move key file '{}' to '{}'",
+                    path.to_string_lossy(),
+                    archived.to_string_lossy()
+                ),
+                e,
+            )
+        })?;
+        Ok(())
+    }
+
     pub fn get_key_info(&self, key_id: &KeyIdentifier) -> Result<PublicKey, KeyError<SignerError>> {
         let key_pair = self.load_key(key_id)?;
         Ok(key_pair.subject_public_key_info()?)
     }
 
     pub fn destroy_key(&self, key_id: &KeyIdentifier) -> Result<(), KeyError<SignerError>> {
-        let path = self.key_path(key_id);
+        let keys_dir = match &self.keys {
+            OpenSslKeyStorage::Dir(keys_dir) => keys_dir,
+            OpenSslKeyStorage::Shared(store) => {
+                store.drop_key(&Self::kv_key(key_id)).map_err(Self::kv_err)?;
+                return Ok(());
+            }
+        };
+
+        let path = keys_dir.join(key_id.to_string());
         if path.exists() {
             fs::remove_file(&path).map_err(|e| {
                 SignerError::IoError(KrillIoError::new(
@@ -277,7 +450,7 @@ impl OpenSslSigner {
         algorithm: Alg,
         data: &D,
     ) -> Result<(Signature<Alg>, PublicKey), SignerError> {
-        let kp = OpenSslKeyPair::build()?;
+        let kp = OpenSslKeyPair::build(algorithm.public_key_format())?;
         let signature = Self::sign_with_key(kp.pkey.as_ref(), algorithm, data)?;
         let key = kp.subject_public_key_info()?;
 
@@ -287,7 +460,7 @@ impl OpenSslSigner {
 
 //------------ OpenSslKeyPair ------------------------------------------------
 
-/// An openssl based RSA key pair
+/// An openssl based RSA or ECDSA P-256 key pair
 pub struct OpenSslKeyPair {
     pkey: PKey<Private>,
 }
@@ -316,19 +489,28 @@ impl<'de> Deserialize<'de> for OpenSslKeyPair {
 }
 
 impl OpenSslKeyPair {
-    fn build() -> Result<OpenSslKeyPair, SignerError> {
-        // Issues unwrapping this indicate a bug in the openssl library.
-        // So, there is no way to recover.
-        let rsa = Rsa::generate(2048)?;
-        let pkey = PKey::from_rsa(rsa)?;
+    fn build(algorithm: PublicKeyFormat) -> Result<OpenSslKeyPair, SignerError> {
+        let pkey = match algorithm {
+            PublicKeyFormat::Rsa => PKey::from_rsa(Rsa::generate(2048)?)?,
+            PublicKeyFormat::EcdsaP256 => {
+                let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+                PKey::from_ec_key(EcKey::generate(&group)?)?
+            }
+        };
         Ok(OpenSslKeyPair { pkey })
     }
 
     fn subject_public_key_info(&self) -> Result<PublicKey, SignerError> {
-        let rsa = self.pkey.rsa().map_err(SignerError::other)?;
-        let bytes = Bytes::from(rsa.public_key_to_der().map_err(SignerError::other)?);
+        let der = match self.pkey.id() {
+            Id::RSA => {
+                let rsa = self.pkey.rsa().map_err(SignerError::other)?;
+                rsa.public_key_to_der().map_err(SignerError::other)?
+            }
+            Id::EC => self.pkey.public_key_to_der().map_err(SignerError::other)?,
+            _ => return Err(SignerError::other("Unsupported key type")),
+        };
 
-        PublicKey::decode(bytes).map_err(SignerError::other)
+        PublicKey::decode(Bytes::from(der)).map_err(SignerError::other)
     }
 
     /// Can be used to import an existing RSA key pair from
@@ -352,6 +534,8 @@ impl OpenSslKeyPair {
 
 #[cfg(test)]
 pub mod tests {
+    use rpki::crypto::BgpsecSignatureAlgorithm;
+
     use crate::test;
 
     use super::*;
@@ -368,7 +552,7 @@ pub mod tests {
 
     #[test]
     fn should_serialize_and_deserialize_key() {
-        let key = OpenSslKeyPair::build().unwrap();
+        let key = OpenSslKeyPair::build(PublicKeyFormat::Rsa).unwrap();
         let json = serde_json::to_string(&key).unwrap();
         let key_des: OpenSslKeyPair = serde_json::from_str(json.as_str()).unwrap();
         let json_from_des = serde_json::to_string(&key_des).unwrap();
@@ -378,6 +562,31 @@ pub mod tests {
         assert_eq!(json, json_from_des);
     }
 
+    #[test]
+    fn should_create_and_sign_with_ecdsa_p256_key() {
+        test::test_under_tmp(|d| {
+            let s = OpenSslSigner::build(&d, "dummy", None).unwrap();
+            let ki = s.create_key(PublicKeyFormat::EcdsaP256).unwrap();
+
+            let key = s.get_key_info(&ki).unwrap();
+            assert_eq!(key.algorithm(), PublicKeyFormat::EcdsaP256);
+            assert_eq!(key.key_identifier(), ki);
+
+            let data = b"some data to sign";
+            let signature = s.sign(&ki, BgpsecSignatureAlgorithm::default(), data).unwrap();
+            key.verify(data, &signature).unwrap();
+
+            // An ECDSA key cannot be used to make RSA signatures
+            assert!(s.sign(&ki, RpkiSignatureAlgorithm::default(), data).is_err());
+
+            let (signature, key) = s.sign_one_off(BgpsecSignatureAlgorithm::default(), data).unwrap();
+            assert_eq!(key.algorithm(), PublicKeyFormat::EcdsaP256);
+            key.verify(data, &signature).unwrap();
+
+            s.destroy_key(&ki).unwrap();
+        })
+    }
+
     #[test]
     fn import_existing_pkcs1_openssl_key() {
         test::test_under_tmp(|d| {
@@ -403,4 +612,37 @@ pub mod tests {
             signer.destroy_key(&ki).unwrap();
         })
     }
+
+    #[test]
+    fn export_and_import_key() {
+        test::test_under_tmp(|d| {
+            let signer = OpenSslSigner::build(&d, "dummy", None).unwrap();
+            let ki = signer.create_key(PublicKeyFormat::Rsa).unwrap();
+            let pem = signer.export_key(&ki).unwrap();
+            signer.destroy_key(&ki).unwrap();
+
+            assert_eq!(signer.import_key(&pem).unwrap(), ki);
+        })
+    }
+
+    #[test]
+    fn list_and_archive_keys() {
+        test::test_under_tmp(|d| {
+            let signer = OpenSslSigner::build(&d, "dummy", None).unwrap();
+            let ki = signer.create_key(PublicKeyFormat::Rsa).unwrap();
+
+            let keys = signer.list_keys().unwrap();
+            assert_eq!(keys.len(), 1);
+            assert_eq!(keys[0].0, ki);
+            assert!(keys[0].1.is_some());
+
+            signer.archive_key(&ki).unwrap();
+            assert!(!signer.has_key(&ki));
+            assert!(signer.list_keys().unwrap().is_empty());
+            assert!(signer.get_key_info(&ki).is_err());
+            assert!(d.join(KEYS_DIR).join(KEYS_ARCHIVE_DIR).join(ki.to_string()).is_file());
+
+            assert!(matches!(signer.archive_key(&ki), Err(SignerError::KeyNotFound)));
+        })
+    }
 }
@@ -19,6 +19,7 @@ use rpki::{
 use crate::{
     commons::{
         api::{rrdp::PublicationDeltaError, AspaCustomer, ErrorResponse, RoaPayload},
+        bgp::BgpAnalysisImpact,
         crypto::SignerError,
         eventsourcing::{AggregateStoreError, KeyValueError},
         util::httpclient,
@@ -177,6 +178,7 @@ pub enum Error {
     ApiUnknownResource,
     ApiInvalidHandle,
     ApiInvalidSeconds,
+    ApiInvalidQueryParam(String),
     PostTooBig,
     PostCannotRead,
     ApiInvalidCredentials(String),
@@ -185,6 +187,19 @@ pub enum Error {
     ApiAuthTransientError(String),
     ApiAuthSessionExpired(String),
     ApiInsufficientRights(String),
+    ApiTokenInvalid(String),
+    ApiTokenDuplicate(String),
+    ApiTokenUnknown(String),
+    ApiAuditLogDisabled,
+    ApiHaNotConfigured,
+    ApiTimeCheckNotConfigured,
+    ApiHaStandby,
+    ApiHaLeaseChanged,
+    ApiRateLimited(u64),
+    ApiTaskUnknown(String),
+    ApiTaskNotQueued(String),
+    ApiInvalidCaSelector(String),
+    ApiJobUnknown(String),
 
     //-----------------------------------------------------------------
     // Repository Issues
@@ -198,6 +213,9 @@ pub enum Error {
     PublisherUriOutsideBase(String, String),
     PublisherBaseUriNoSlash(String),
     PublisherDuplicate(PublisherHandle),
+    PublisherQuotaExceeded(PublisherHandle, String),
+    PublisherInvalidContent(PublisherHandle, String),
+    PublisherNoStagedIdCert(PublisherHandle),
 
     //-----------------------------------------------------------------
     // Repository Server Issues
@@ -205,6 +223,8 @@ pub enum Error {
     RepositoryServerNotInitialized,
     RepositoryServerHasPublishers,
     RepositoryServerAlreadyInitialized,
+    RepositoryArchiveNotConfigured,
+    RepositoryReplicaNotConfigured,
 
     //-----------------------------------------------------------------
     // Publishing
@@ -220,12 +240,22 @@ pub enum Error {
     //-----------------------------------------------------------------
     CaDuplicate(CaHandle),
     CaUnknown(CaHandle),
+    CaDeleteIncomplete(CaHandle, String),
+    CaMigrationKeyInvalid(CaHandle, String),
 
     // CA Repo Issues
     CaRepoInUse(CaHandle),
     CaRepoIssue(CaHandle, String),
     CaRepoResponseInvalid(CaHandle, String),
     CaRepoResponseWrongXml(CaHandle),
+    CaRepoIdRollNotStarted(CaHandle),
+
+    // CA Issuance Timing Issues
+    CaIssuanceTimingInvalid(CaHandle, String),
+
+    // CA Retry Policy Issues
+    CaRetryPolicyInvalid(CaHandle, String),
+    CaLabelsInvalid(CaHandle, String),
 
     // CA Parent Issues
     CaParentDuplicateName(CaHandle, ParentHandle),
@@ -234,6 +264,7 @@ pub enum Error {
     CaParentIssue(CaHandle, ParentHandle, String),
     CaParentResponseInvalid(CaHandle, String),
     CaParentResponseWrongXml(CaHandle),
+    CaParentResponseFetch(CaHandle, String),
     CaParentAddNotResponsive(CaHandle, ParentHandle),
     CaParentSyncError(CaHandle, ParentHandle, ResourceClassName, String),
 
@@ -256,7 +287,14 @@ pub enum Error {
     CaChildUnknown(CaHandle, ChildHandle),
     CaChildMustHaveResources(CaHandle, ChildHandle),
     CaChildExtraResources(CaHandle, ChildHandle),
+    CaChildResourcesNotAllowed(CaHandle, ChildHandle, String),
     CaChildUnauthorized(CaHandle, ChildHandle),
+    CaChildIssuancePolicyInvalid(CaHandle, String),
+    CaChildIssuanceDenied(CaHandle, ChildHandle, String),
+    CaChildRequestNotApproved(CaHandle, ChildHandle, KeyIdentifier),
+    CaChildRequestUnknown(CaHandle, ChildHandle, KeyIdentifier),
+    CaResourceTransferInvalid(CaHandle, String),
+    CaResourceTransferUnknown(CaHandle, u64),
 
     //-----------------------------------------------------------------
     // RouteAuthorizations - ROAs
@@ -266,6 +304,13 @@ pub enum Error {
     CaAuthorizationInvalidMaxLength(CaHandle, RoaPayloadJsonMapKey),
     CaAuthorizationNotEntitled(CaHandle, RoaPayloadJsonMapKey),
     RoaDeltaError(CaHandle, RoaDeltaError),
+    RoaProposalUnknown(CaHandle, u64),
+    RoaProposalSelfApproval(CaHandle, u64),
+    CaRoaIssuanceStrategyInvalid(CaHandle, String),
+    CaRoaAutoPilotInvalid(CaHandle, String),
+    CaScheduledChangeInvalid(CaHandle, String),
+    CaScheduledChangeUnknown(CaHandle, u64),
+    RoaUpdateInvalidatesAnnouncements(CaHandle, BgpAnalysisImpact),
 
     //-----------------------------------------------------------------
     // Autonomous System Provider Authorization - ASPA
@@ -318,12 +363,19 @@ pub enum Error {
     TaProxyHasNoRequest,
     TaProxyHasRequest,
     TaProxyRequestNonceMismatch(ta::Nonce, ta::Nonce),
+    TaProxyResponseStale(u64, u64),
+    TaSignerRequestReplayed(ta::Nonce),
 
     //-----------------------------------------------------------------
     // Resource Tagged Attestation issues
     //-----------------------------------------------------------------
     RtaResourcesNotHeld,
 
+    //-----------------------------------------------------------------
+    // RPKI Signed Checklist issues
+    //-----------------------------------------------------------------
+    RscResourcesNotHeld,
+
     //-----------------------------------------------------------------
     // If we really don't know any more..
     //-----------------------------------------------------------------
@@ -356,6 +408,7 @@ impl fmt::Display for Error {
             Error::ApiUnknownResource => write!(f, "Unknown resource"),
             Error::ApiInvalidHandle => write!(f, "Invalid path argument for handle"),
             Error::ApiInvalidSeconds => write!(f, "Invalid path argument for seconds"),
+            Error::ApiInvalidQueryParam(name) => write!(f, "Invalid value for query parameter '{}'", name),
             Error::PostTooBig => write!(f, "POST body exceeds configured limit"),
             Error::PostCannotRead => write!(f, "POST body cannot be read"),
             Error::ApiInvalidCredentials(e) => write!(f, "Invalid credentials: {}", e),
@@ -364,6 +417,19 @@ impl fmt::Display for Error {
             Error::ApiAuthTransientError(e) => write!(f, "Transient authentication error: {}", e),
             Error::ApiAuthSessionExpired(e) => write!(f, "Session expired: {}", e),
             Error::ApiInsufficientRights(e) => write!(f, "Insufficient rights: {}", e),
+            Error::ApiTokenInvalid(e) => write!(f, "Invalid API token request: {}", e),
+            Error::ApiTokenDuplicate(name) => write!(f, "Duplicate API token '{}'", name),
+            Error::ApiTokenUnknown(name) => write!(f, "Unknown API token '{}'", name),
+            Error::ApiAuditLogDisabled => write!(f, "The audit log is not enabled"),
+            Error::ApiHaNotConfigured => write!(f, "High availability is not configured, see 'ha' in the configuration"),
+            Error::ApiTimeCheckNotConfigured => write!(f, "Time check is not configured, see 'time_check' in the configuration"),
+            Error::ApiHaStandby => write!(f, "This Krill instance is a standby, changes can only be made on the active instance"),
+            Error::ApiHaLeaseChanged => write!(f, "The high availability lease was changed by another instance, try again"),
+            Error::ApiRateLimited(secs) => write!(f, "Too many requests, try again in {} seconds", secs),
+            Error::ApiTaskUnknown(name) => write!(f, "Unknown task '{}'", name),
+            Error::ApiTaskNotQueued(name) => write!(f, "No task '{}' is queued", name),
+            Error::ApiInvalidCaSelector(e) => write!(f, "Invalid CA selector: {}", e),
+            Error::ApiJobUnknown(id) => write!(f, "Unknown job '{}'", id),
 
             //-----------------------------------------------------------------
             // Repository Issues
@@ -378,6 +444,9 @@ impl fmt::Display for Error {
             Error::PublisherUriOutsideBase(uri, jail) => write!(f, "Publishing uri '{}' outside repository uri '{}'", uri, jail),
             Error::PublisherBaseUriNoSlash(uri) => write!(f, "Publisher uri '{}' must have a trailing slash", uri),
             Error::PublisherDuplicate(pbl) => write!(f, "Duplicate publisher '{}'", pbl),
+            Error::PublisherQuotaExceeded(pbl, reason) => write!(f, "Quota exceeded for publisher '{}': {}", pbl, reason),
+            Error::PublisherInvalidContent(pbl, issues) => write!(f, "Invalid content from publisher '{}': {}", pbl, issues),
+            Error::PublisherNoStagedIdCert(pbl) => write!(f, "Publisher '{}' has no staged ID certificate", pbl),
 
             //-----------------------------------------------------------------
             // Repository Server Issues
@@ -385,6 +454,8 @@ impl fmt::Display for Error {
             Error::RepositoryServerNotInitialized => write!(f, "Publication Server not initialized, see 'krillc pubserver server init --help'"),
             Error::RepositoryServerHasPublishers => write!(f, "Publication Server cannot be removed, still has publishers"),
             Error::RepositoryServerAlreadyInitialized => write!(f, "Publication Server already initialized"),
+            Error::RepositoryArchiveNotConfigured => write!(f, "Publication Server archive is not configured, see 'repository_archive' in the configuration"),
+            Error::RepositoryReplicaNotConfigured => write!(f, "Publication Server replica is not configured, see 'repository_replica' in the configuration"),
 
             //-----------------------------------------------------------------
             // RFC 8181 (publishing)
@@ -401,6 +472,8 @@ impl fmt::Display for Error {
             //-----------------------------------------------------------------
             Error::CaDuplicate(ca) => write!(f, "CA '{}' was already initialized", ca),
             Error::CaUnknown(ca) => write!(f, "CA '{}' is unknown", ca),
+            Error::CaDeleteIncomplete(ca, msg) => write!(f, "CA '{}' was not deleted, {}. Use force to delete it anyway", ca, msg),
+            Error::CaMigrationKeyInvalid(ca, msg) => write!(f, "CA '{}' cannot be migrated with this identity key: {}", ca, msg),
 
             // CA Repo Issues
             Error::CaRepoInUse(ca) => write!(f, "CA '{}' already uses this repository", ca),
@@ -408,6 +481,10 @@ impl fmt::Display for Error {
             re-install of Krill you will need to send XML to all other parties again: parent(s), children, and repository", ca,        e),
             Error::CaRepoResponseInvalid(ca, e) => write!(f, "CA '{}' got invalid repository response: {}", ca, e),
             Error::CaRepoResponseWrongXml(ca) => write!(f, "CA '{}' got parent instead of repository response", ca),
+            Error::CaRepoIdRollNotStarted(ca) => write!(f, "CA '{}' has no new ID certificate, see 'krillc repo idroll init --help'", ca),
+            Error::CaIssuanceTimingInvalid(ca, msg) => write!(f, "Invalid issuance timing for CA '{}': {}", ca, msg),
+            Error::CaRetryPolicyInvalid(ca, msg) => write!(f, "Invalid retry policy for CA '{}': {}", ca, msg),
+            Error::CaLabelsInvalid(ca, msg) => write!(f, "Invalid labels for CA '{}': {}", ca, msg),
 
             // CA Parent Issues
             Error::CaParentDuplicateName(ca, parent) => write!(f, "CA '{}' already has a parent named '{}'", ca, parent),
@@ -416,6 +493,7 @@ impl fmt::Display for Error {
             Error::CaParentIssue(ca, parent, e) => write!(f, "CA '{}' got error from parent '{}': {}", ca, parent, e),
             Error::CaParentResponseInvalid(ca, e) => write!(f, "CA '{}' got invalid parent response: {}", ca, e),
             Error::CaParentResponseWrongXml(ca) => write!(f, "CA '{}' got repository response when adding parent", ca),
+            Error::CaParentResponseFetch(ca, e) => write!(f, "CA '{}' could not fetch parent response: {}", ca, e),
             Error::CaParentAddNotResponsive(ca, parent) => write!(f, "CA '{}' cannot get response from parent '{}'. Is the 'service_uri' in the XML reachable? Note that when upgrading Krill you should re-use existing configuration and data. For a fresh re-install of Krill you will need to send XML to all other parties again: parent(s), children, and repository",        ca, parent),
             Error::CaParentSyncError(ca, parent, rcn, error_msg) => {
                 write!(
@@ -447,7 +525,14 @@ impl fmt::Display for Error {
             Error::CaChildUnknown(ca, child) => write!(f, "CA '{}' does not have a child named '{}'", ca, child),
             Error::CaChildMustHaveResources(ca, child) => write!(f, "Child '{}' for CA '{}' MUST have resources specified", child, ca),
             Error::CaChildExtraResources(ca, child) => write!(f, "Child '{}' cannot have resources not held by CA '{}'", child, ca),
+            Error::CaChildResourcesNotAllowed(ca, child, reason) => write!(f, "Child '{}' cannot have these resources under CA '{}': {}", child, ca, reason),
             Error::CaChildUnauthorized(ca, child) => write!(f, "CA '{}' does not know id certificate for child '{}'", ca, child),
+            Error::CaChildIssuancePolicyInvalid(ca, msg) => write!(f, "Invalid child issuance policy for CA '{}': {}", ca, msg),
+            Error::CaChildIssuanceDenied(ca, child, reason) => write!(f, "CA '{}' will not issue a certificate to child '{}': {}", ca, child, reason),
+            Error::CaChildRequestNotApproved(ca, child, ki) => write!(f, "CA '{}' requires approval of the request from child '{}' for key '{}'", ca, child, ki),
+            Error::CaChildRequestUnknown(ca, child, ki) => write!(f, "CA '{}' has no pending request from child '{}' for key '{}'", ca, child, ki),
+            Error::CaResourceTransferInvalid(ca, msg) => write!(f, "Invalid resource transfer for CA '{}': {}", ca, msg),
+            Error::CaResourceTransferUnknown(ca, id) => write!(f, "CA '{}' has no resource transfer '{}'", ca, id),
 
             //-----------------------------------------------------------------
             // RouteAuthorizations - ROAs
@@ -457,6 +542,17 @@ impl fmt::Display for Error {
             Error::CaAuthorizationInvalidMaxLength(_ca, roa) => write!(f, "Invalid max length in ROA: '{}'", roa),
             Error::CaAuthorizationNotEntitled(_ca, roa) => write!(f, "Prefix in ROA '{}' not held by you", roa),
             Error::RoaDeltaError(_ca, e) => write!(f, "ROA delta rejected:\n\n'{}' ", e),
+            Error::RoaProposalUnknown(_ca, id) => write!(f, "Unknown ROA proposal '{}'", id),
+            Error::RoaProposalSelfApproval(_ca, id) => {
+                write!(f, "ROA proposal '{}' must be approved by someone other than its proposer", id)
+            }
+            Error::CaRoaIssuanceStrategyInvalid(_ca, msg) => write!(f, "Invalid ROA issuance strategy: {}", msg),
+            Error::CaRoaAutoPilotInvalid(_ca, msg) => write!(f, "Invalid ROA auto-pilot policy: {}", msg),
+            Error::CaScheduledChangeInvalid(_ca, msg) => write!(f, "Invalid scheduled change: {}", msg),
+            Error::CaScheduledChangeUnknown(_ca, id) => write!(f, "Unknown scheduled change '{}'", id),
+            Error::RoaUpdateInvalidatesAnnouncements(_ca, impact) => {
+                write!(f, "ROA update rejected, use force to apply it anyway.\n\n{}", impact)
+            }
 
             //-----------------------------------------------------------------
             // Autonomous System Provider Authorization - ASPAs
@@ -468,7 +564,7 @@ impl fmt::Display for Error {
             Error::AspaProvidersDuplicates(_ca, asn) => write!(f, "ASPA for customer AS '{}' cannot have duplicate providers", asn),
             Error::AspaCustomerUnknown(_ca, asn) => write!(f, "No current ASPA exists for customer AS '{}'", asn),
             Error::AspaProvidersSingleAfi(_ca, asn) => write!(f, "ASPA for customer AS '{}' only has providers for one address family. Please include an explicit AS0 provider for the missing address family if this is intentional.", asn),
-            
+
             //-----------------------------------------------------------------
             // BGPSec
             //-----------------------------------------------------------------
@@ -511,12 +607,21 @@ impl fmt::Display for Error {
             Error::TaProxyHasNoRequest => write!(f, "Trust Anchor Proxy has no signer request"),
             Error::TaProxyHasRequest => write!(f, "Trust Anchor Proxy already has signer request"),
             Error::TaProxyRequestNonceMismatch(rcvd, expected) => write!(f, "Trust Anchor Response nonce '{}' does not match open Request nonce '{}'", rcvd, expected),
+            Error::TaProxyResponseStale(rcvd, current) => write!(f, "Trust Anchor Response has revision '{}', but the proxy already has revision '{}'", rcvd, current),
+            Error::TaSignerRequestReplayed(nonce) => write!(f, "Trust Anchor Signer already processed request with nonce '{}', use 'exchanges' to find its response", nonce),
 
             //-----------------------------------------------------------------
             // Resource Tagged Attestation issues
             //-----------------------------------------------------------------
             Error::RtaResourcesNotHeld => write!(f, "Your CA does not hold the requested resources"),
 
+            //-----------------------------------------------------------------
+            // RPKI Signed Checklist issues
+            //-----------------------------------------------------------------
+            Error::RscResourcesNotHeld => {
+                write!(f, "None of the resource classes of your CA holds all the requested resources")
+            }
+
             //-----------------------------------------------------------------
             // If we really don't know any more..
             //-----------------------------------------------------------------
@@ -654,7 +759,19 @@ impl Error {
             Error::PublisherUnknown(_)
             | Error::CaUnknown(_)
             | Error::CaChildUnknown(_, _)
+            | Error::CaChildRequestUnknown(_, _, _)
+            | Error::CaResourceTransferUnknown(_, _)
             | Error::CaParentUnknown(_, _)
+            | Error::RoaProposalUnknown(_, _)
+            | Error::CaScheduledChangeUnknown(_, _)
+            | Error::ApiTokenUnknown(_)
+            | Error::ApiAuditLogDisabled
+            | Error::ApiHaNotConfigured
+            | Error::ApiTimeCheckNotConfigured
+            | Error::ApiTaskUnknown(_)
+            | Error::ApiJobUnknown(_)
+            | Error::RepositoryArchiveNotConfigured
+            | Error::RepositoryReplicaNotConfigured
             | Error::ApiUnknownResource => StatusCode::NOT_FOUND,
 
             Error::ApiInvalidCredentials(_)
@@ -662,7 +779,10 @@ impl Error {
             | Error::ApiAuthTransientError(_)
             | Error::ApiAuthSessionExpired(_)
             | Error::ApiLoginError(_) => StatusCode::UNAUTHORIZED,
-            Error::ApiInsufficientRights(_) => StatusCode::FORBIDDEN,
+            Error::ApiInsufficientRights(_) | Error::RoaProposalSelfApproval(_, _) => StatusCode::FORBIDDEN,
+            Error::ApiHaStandby => StatusCode::SERVICE_UNAVAILABLE,
+            Error::ApiHaLeaseChanged => StatusCode::CONFLICT,
+            Error::ApiRateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
 
             _ => StatusCode::BAD_REQUEST,
         }
@@ -717,6 +837,8 @@ impl Error {
 
             Error::ApiInvalidSeconds => ErrorResponse::new("api-invalid-path-seconds", self),
 
+            Error::ApiInvalidQueryParam(_) => ErrorResponse::new("api-invalid-query-param", self),
+
             Error::PostTooBig => ErrorResponse::new("api-post-body-exceeds-limit", self),
 
             Error::PostCannotRead => ErrorResponse::new("api-post-body-cannot-read", self),
@@ -733,6 +855,32 @@ impl Error {
 
             Error::ApiInsufficientRights(e) => ErrorResponse::new("api-insufficient-rights", self).with_cause(e),
 
+            Error::ApiTokenInvalid(e) => ErrorResponse::new("api-token-invalid", self).with_cause(e),
+
+            Error::ApiTokenDuplicate(_) => ErrorResponse::new("api-token-duplicate", self),
+
+            Error::ApiTokenUnknown(_) => ErrorResponse::new("api-token-unknown", self),
+
+            Error::ApiAuditLogDisabled => ErrorResponse::new("api-audit-log-disabled", self),
+
+            Error::ApiHaNotConfigured => ErrorResponse::new("api-ha-not-configured", self),
+
+            Error::ApiTimeCheckNotConfigured => ErrorResponse::new("api-time-check-not-configured", self),
+
+            Error::ApiHaStandby => ErrorResponse::new("api-ha-standby", self),
+
+            Error::ApiHaLeaseChanged => ErrorResponse::new("api-ha-lease-changed", self),
+
+            Error::ApiRateLimited(_) => ErrorResponse::new("api-rate-limited", self),
+
+            Error::ApiTaskUnknown(_) => ErrorResponse::new("api-task-unknown", self),
+
+            Error::ApiTaskNotQueued(_) => ErrorResponse::new("api-task-not-queued", self),
+
+            Error::ApiInvalidCaSelector(_) => ErrorResponse::new("api-invalid-ca-selector", self),
+
+            Error::ApiJobUnknown(_) => ErrorResponse::new("api-job-unknown", self),
+
             //-----------------------------------------------------------------
             // Repository Issues (label: repo-*)
             //-----------------------------------------------------------------
@@ -753,12 +901,24 @@ impl Error {
 
             Error::PublisherBaseUriNoSlash(uri) => ErrorResponse::new("pub-uri-no-slash", self).with_uri(uri),
 
+            Error::PublisherQuotaExceeded(p, reason) => ErrorResponse::new("pub-quota-exceeded", self)
+                .with_publisher(p)
+                .with_cause(reason),
+
+            Error::PublisherInvalidContent(p, issues) => ErrorResponse::new("pub-invalid-content", self)
+                .with_publisher(p)
+                .with_cause(issues),
+
+            Error::PublisherNoStagedIdCert(p) => ErrorResponse::new("pub-no-staged-id", self).with_publisher(p),
+
             //-----------------------------------------------------------------
             // Repository Server Issues
             //-----------------------------------------------------------------
             Error::RepositoryServerNotInitialized => ErrorResponse::new("pub-repo-not-initialized", self),
             Error::RepositoryServerHasPublishers => ErrorResponse::new("pub-repo-has-publishers", self),
             Error::RepositoryServerAlreadyInitialized => ErrorResponse::new("pub-repo-initialized", self),
+            Error::RepositoryArchiveNotConfigured => ErrorResponse::new("pub-repo-archive-not-configured", self),
+            Error::RepositoryReplicaNotConfigured => ErrorResponse::new("pub-repo-replica-not-configured", self),
 
             //-----------------------------------------------------------------
             // Publishing
@@ -778,6 +938,14 @@ impl Error {
 
             Error::CaUnknown(ca) => ErrorResponse::new("ca-unknown", self).with_ca(ca),
 
+            Error::CaDeleteIncomplete(ca, msg) => ErrorResponse::new("ca-delete-incomplete", self)
+                .with_ca(ca)
+                .with_cause(msg),
+
+            Error::CaMigrationKeyInvalid(ca, msg) => ErrorResponse::new("ca-migration-key-invalid", self)
+                .with_ca(ca)
+                .with_cause(msg),
+
             Error::CaRepoInUse(ca) => ErrorResponse::new("ca-repo-same", self).with_ca(ca),
 
             Error::CaRepoIssue(ca, err) => ErrorResponse::new("ca-repo-issue", self).with_ca(ca).with_cause(err),
@@ -787,6 +955,16 @@ impl Error {
                 .with_cause(err),
 
             Error::CaRepoResponseWrongXml(ca) => ErrorResponse::new("ca-repo-response-wrong-xml", self).with_ca(ca),
+            Error::CaRepoIdRollNotStarted(ca) => ErrorResponse::new("ca-repo-id-roll-not-started", self).with_ca(ca),
+            Error::CaIssuanceTimingInvalid(ca, msg) => ErrorResponse::new("ca-issuance-timing-invalid", self)
+                .with_ca(ca)
+                .with_cause(msg),
+            Error::CaRetryPolicyInvalid(ca, msg) => ErrorResponse::new("ca-retry-policy-invalid", self)
+                .with_ca(ca)
+                .with_cause(msg),
+            Error::CaLabelsInvalid(ca, msg) => ErrorResponse::new("ca-labels-invalid", self)
+                .with_ca(ca)
+                .with_cause(msg),
 
             Error::CaParentDuplicateName(ca, parent) => ErrorResponse::new("ca-parent-duplicate", self)
                 .with_ca(ca)
@@ -811,6 +989,10 @@ impl Error {
 
             Error::CaParentResponseWrongXml(ca) => ErrorResponse::new("ca-parent-response-wrong-xml", self).with_ca(ca),
 
+            Error::CaParentResponseFetch(ca, err) => ErrorResponse::new("ca-parent-response-fetch", self)
+                .with_ca(ca)
+                .with_cause(err),
+
             Error::CaParentAddNotResponsive(ca, parent) => ErrorResponse::new("ca-parent-add-unresponsive", self)
                 .with_ca(ca)
                 .with_parent(parent),
@@ -845,9 +1027,39 @@ impl Error {
             Error::CaChildExtraResources(ca, child) => ErrorResponse::new("ca-child-resources-extra", self)
                 .with_ca(ca)
                 .with_child(child),
+            Error::CaChildResourcesNotAllowed(ca, child, _) => {
+                ErrorResponse::new("ca-child-resources-not-allowed", self)
+                    .with_ca(ca)
+                    .with_child(child)
+            }
             Error::CaChildUnauthorized(ca, child) => ErrorResponse::new("ca-child-unauthorized", self)
                 .with_ca(ca)
                 .with_child(child),
+            Error::CaChildIssuancePolicyInvalid(ca, msg) => {
+                ErrorResponse::new("ca-child-issuance-policy-invalid", self)
+                    .with_ca(ca)
+                    .with_cause(msg)
+            }
+            Error::CaChildIssuanceDenied(ca, child, reason) => ErrorResponse::new("ca-child-issuance-denied", self)
+                .with_ca(ca)
+                .with_child(child)
+                .with_cause(reason),
+            Error::CaChildRequestNotApproved(ca, child, ki) => {
+                ErrorResponse::new("ca-child-request-not-approved", self)
+                    .with_ca(ca)
+                    .with_child(child)
+                    .with_key_identifier(ki)
+            }
+            Error::CaChildRequestUnknown(ca, child, ki) => ErrorResponse::new("ca-child-request-unknown", self)
+                .with_ca(ca)
+                .with_child(child)
+                .with_key_identifier(ki),
+            Error::CaResourceTransferInvalid(ca, msg) => ErrorResponse::new("ca-resource-transfer-invalid", self)
+                .with_ca(ca)
+                .with_cause(msg),
+            Error::CaResourceTransferUnknown(ca, id) => ErrorResponse::new("ca-resource-transfer-unknown", self)
+                .with_ca(ca)
+                .with_resource_transfer(*id),
 
             // RouteAuthorizations
             Error::CaAuthorizationUnknown(ca, auth) => {
@@ -869,6 +1081,38 @@ impl Error {
                 .with_ca(ca)
                 .with_roa_delta_error(roa_delta_error),
 
+            Error::RoaProposalUnknown(ca, id) => ErrorResponse::new("ca-roa-proposal-unknown", self)
+                .with_ca(ca)
+                .with_roa_proposal(*id),
+
+            Error::RoaProposalSelfApproval(ca, id) => ErrorResponse::new("ca-roa-proposal-self-approval", self)
+                .with_ca(ca)
+                .with_roa_proposal(*id),
+
+            Error::CaRoaIssuanceStrategyInvalid(ca, msg) => {
+                ErrorResponse::new("ca-roa-issuance-strategy-invalid", self)
+                    .with_ca(ca)
+                    .with_cause(msg)
+            }
+
+            Error::CaRoaAutoPilotInvalid(ca, msg) => ErrorResponse::new("ca-roa-autopilot-invalid", self)
+                .with_ca(ca)
+                .with_cause(msg),
+
+            Error::CaScheduledChangeInvalid(ca, msg) => ErrorResponse::new("ca-scheduled-change-invalid", self)
+                .with_ca(ca)
+                .with_cause(msg),
+
+            Error::CaScheduledChangeUnknown(ca, id) => ErrorResponse::new("ca-scheduled-change-unknown", self)
+                .with_ca(ca)
+                .with_scheduled_change(*id),
+
+            Error::RoaUpdateInvalidatesAnnouncements(ca, impact) => {
+                ErrorResponse::new("ca-roa-update-invalidates-announcements", self)
+                    .with_ca(ca)
+                    .with_bgp_impact(impact)
+            }
+
             //-----------------------------------------------------------------
             // Autonomous System Provider Authorization - ASPA
             //-----------------------------------------------------------------
@@ -946,12 +1190,19 @@ impl Error {
             Error::TaProxyHasNoRequest => ErrorResponse::new("ta-has-no-signer-req", self),
             Error::TaProxyHasRequest => ErrorResponse::new("ta-has-signer-req", self),
             Error::TaProxyRequestNonceMismatch(_rcvd, _expected) => ErrorResponse::new("ta-proxy-response-nonce", self),
+            Error::TaProxyResponseStale(_rcvd, _current) => ErrorResponse::new("ta-proxy-response-stale", self),
+            Error::TaSignerRequestReplayed(_nonce) => ErrorResponse::new("ta-signer-request-replayed", self),
 
             //-----------------------------------------------------------------
             // Resource Tagged Attestation issues
             //-----------------------------------------------------------------
             Error::RtaResourcesNotHeld => ErrorResponse::new("rta-resources-not-held", self),
 
+            //-----------------------------------------------------------------
+            // RPKI Signed Checklist issues
+            //-----------------------------------------------------------------
+            Error::RscResourcesNotHeld => ErrorResponse::new("rsc-resources-not-held", self),
+
             //-----------------------------------------------------------------
             // If we really don't know any more..
             //-----------------------------------------------------------------
@@ -962,8 +1213,11 @@ impl Error {
 
     pub fn to_rfc8181_error_code(&self) -> publication::ReportErrorCode {
         match self {
-            Error::Rfc8181Validation(_) | Error::PublisherUnknown(_) => publication::ReportErrorCode::PermissionFailure,
+            Error::Rfc8181Validation(_) | Error::PublisherUnknown(_) | Error::PublisherQuotaExceeded(_, _) => {
+                publication::ReportErrorCode::PermissionFailure
+            }
             Error::Rfc8181(_) => publication::ReportErrorCode::XmlError,
+            Error::PublisherInvalidContent(_, _) => publication::ReportErrorCode::ConsistencyProblem,
             Error::Rfc8181Delta(e) => match e {
                 PublicationDeltaError::UriOutsideJail(_, _) => publication::ReportErrorCode::PermissionFailure,
                 PublicationDeltaError::NoObjectForHashAndOrUri(_) => publication::ReportErrorCode::NoObjectPresent,
@@ -1105,6 +1359,24 @@ mod tests {
             include_str!("../../test-resources/errors/pub-uri-no-slash.json"),
             Error::PublisherBaseUriNoSlash("rsync://host/module/folder".to_string()),
         );
+        verify(
+            include_str!("../../test-resources/errors/pub-quota-exceeded.json"),
+            Error::PublisherQuotaExceeded(
+                PublisherHandle::from_str("publisher").unwrap(),
+                "would have 11 objects, max is 10".to_string(),
+            ),
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-invalid-content.json"),
+            Error::PublisherInvalidContent(
+                PublisherHandle::from_str("publisher").unwrap(),
+                "'rsync://localhost/repo/publisher/0/file.roa': cannot decode ROA".to_string(),
+            ),
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-no-staged-id.json"),
+            Error::PublisherNoStagedIdCert(PublisherHandle::from_str("publisher").unwrap()),
+        );
 
         //-----------------------------------------------------------------
         // RFC 8181
@@ -1140,6 +1412,10 @@ mod tests {
             include_str!("../../test-resources/errors/ca-repo-same.json"),
             Error::CaRepoInUse(ca.clone()),
         );
+        verify(
+            include_str!("../../test-resources/errors/ca-repo-id-roll-not-started.json"),
+            Error::CaRepoIdRollNotStarted(ca.clone()),
+        );
         verify(
             include_str!("../../test-resources/errors/ca-repo-issue.json"),
             Error::CaRepoIssue(ca.clone(), "cannot connect".to_string()),
@@ -1199,6 +1475,14 @@ mod tests {
             include_str!("../../test-resources/errors/ca-child-resources-extra.json"),
             Error::CaChildExtraResources(ca.clone(), child.clone()),
         );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-resources-not-allowed.json"),
+            Error::CaChildResourcesNotAllowed(
+                ca.clone(),
+                child.clone(),
+                "at most 2 resource blocks may be claimed, not 3".to_string(),
+            ),
+        );
         verify(
             include_str!("../../test-resources/errors/ca-child-unauthorized.json"),
             Error::CaChildUnauthorized(ca.clone(), child),
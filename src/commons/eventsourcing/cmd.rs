@@ -2,13 +2,16 @@ use std::fmt;
 
 use rpki::{ca::idexchange::MyHandle, repository::x509::Time};
 
-use crate::commons::{
-    actor::Actor,
-    api::{CommandHistoryRecord, CommandSummary, StoredEffect},
-    eventsourcing::{
-        store::CommandKey,
-        {Event, Storable},
+use crate::{
+    commons::{
+        actor::Actor,
+        api::{CommandHistoryRecord, CommandSummary, StoredEffect},
+        eventsourcing::{
+            store::CommandKey,
+            {Event, Storable},
+        },
     },
+    constants::ACTOR_USER_PREFIX,
 };
 
 //------------ WithStorableDetails -------------------------------------------
@@ -87,7 +90,7 @@ impl<C: CommandDetails> Command for SentCommand<C> {
 impl<C: CommandDetails> SentCommand<C> {
     pub fn new(id: &MyHandle, version: Option<u64>, details: C, actor: &Actor) -> Self {
         let actor_name = if actor.is_user() {
-            format!("user:{}", actor.name())
+            format!("{}{}", ACTOR_USER_PREFIX, actor.name())
         } else {
             actor.name().to_string()
         };
@@ -169,6 +172,10 @@ impl<S: WithStorableDetails> StoredCommand<S> {
         }
     }
 
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
     pub fn time(&self) -> Time {
         self.time
     }
@@ -0,0 +1,126 @@
+//! Archive the older history of aggregates.
+//!
+//! Commands, and the events that they resulted in, are moved out of the
+//! active store of an aggregate once they are older than a configured age,
+//! and are no longer needed to load the state from the latest snapshot.
+//! Each archive holds a contiguous range of commands and their events, as
+//! base64 encoded, gzip compressed json. An index of the archives is kept
+//! per aggregate, so that archived commands and events can still be found
+//! when the full history is queried, or when the state is rebuilt from the
+//! init event.
+use std::io::{Read, Write};
+
+use rpki::repository::x509::Time;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::commons::eventsourcing::{StoredCommand, WithStorableDetails};
+
+//------------ HistoryArchiveIndex -------------------------------------------
+
+/// The archives for an aggregate, in ascending order of commands.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct HistoryArchiveIndex {
+    archives: Vec<HistoryArchiveInfo>,
+}
+
+impl HistoryArchiveIndex {
+    pub fn add(&mut self, info: HistoryArchiveInfo) {
+        self.archives.push(info);
+    }
+
+    pub fn archives(&self) -> &Vec<HistoryArchiveInfo> {
+        &self.archives
+    }
+
+    pub fn last(&self) -> Option<&HistoryArchiveInfo> {
+        self.archives.last()
+    }
+
+    /// Returns the sequence of the last archived command, or 0 if none.
+    pub fn last_command(&self) -> u64 {
+        self.last().map(|info| info.last_command).unwrap_or(0)
+    }
+
+    /// Returns the archive that contains the command with this sequence.
+    pub fn archive_for_command(&self, sequence: u64) -> Option<&HistoryArchiveInfo> {
+        self.archives
+            .iter()
+            .find(|info| info.first_command <= sequence && sequence <= info.last_command)
+    }
+
+    /// Returns the archive that contains the event with this version. Events
+    /// are archived in order, so this is the first archive which includes
+    /// events up to at least this version.
+    pub fn archive_for_event(&self, version: u64) -> Option<&HistoryArchiveInfo> {
+        if version == 0 {
+            None // the init event is never archived
+        } else {
+            self.archives.iter().find(|info| version <= info.last_event)
+        }
+    }
+}
+
+//------------ HistoryArchiveInfo --------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct HistoryArchiveInfo {
+    pub first_command: u64,
+    pub last_command: u64,
+
+    // The last event in this or any earlier archive, 0 if none.
+    pub last_event: u64,
+
+    // The time of the last command in this archive.
+    pub last_update: Time,
+}
+
+impl HistoryArchiveInfo {
+    pub fn name(&self) -> String {
+        format!("history-{}-{}.json", self.first_command, self.last_command)
+    }
+}
+
+//------------ HistoryArchive ------------------------------------------------
+
+/// The archived commands and events.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct HistoryArchive<S: WithStorableDetails, E> {
+    pub commands: Vec<StoredCommand<S>>,
+    pub events: Vec<E>,
+}
+
+impl<S: WithStorableDetails, E: Serialize + DeserializeOwned> HistoryArchive<S, E> {
+    pub fn compress(&self) -> Result<StoredHistoryArchive, String> {
+        let json = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+
+        let mut encoder = libflate::gzip::Encoder::new(Vec::new()).map_err(|e| e.to_string())?;
+        encoder.write_all(&json).map_err(|e| e.to_string())?;
+        let compressed = encoder.finish().into_result().map_err(|e| e.to_string())?;
+
+        Ok(StoredHistoryArchive {
+            content: base64::encode(compressed),
+        })
+    }
+}
+
+//------------ StoredHistoryArchive ------------------------------------------
+
+/// A compressed `HistoryArchive` as it is kept in the key value store.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StoredHistoryArchive {
+    content: String,
+}
+
+impl StoredHistoryArchive {
+    pub fn decompress<S: WithStorableDetails, E: Serialize + DeserializeOwned>(
+        &self,
+    ) -> Result<HistoryArchive<S, E>, String> {
+        let compressed = base64::decode(&self.content).map_err(|e| e.to_string())?;
+
+        let mut decoder = libflate::gzip::Decoder::new(compressed.as_slice()).map_err(|e| e.to_string())?;
+        let mut json = vec![];
+        decoder.read_to_end(&mut json).map_err(|e| e.to_string())?;
+
+        serde_json::from_slice(&json).map_err(|e| e.to_string())
+    }
+}
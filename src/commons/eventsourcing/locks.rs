@@ -88,4 +88,18 @@ impl HandleLocks {
         let mut map = self.locks.write().unwrap();
         map.drop_handle_lock(handle);
     }
+
+    /// Locks all instances at once, e.g. to export a consistent view of a
+    /// store. This waits until all current users of a [`HandleLock`] are done,
+    /// and new users have to wait until the returned lock is dropped.
+    pub fn lock_all(&self) -> AllHandlesLock<'_> {
+        AllHandlesLock {
+            _map: self.locks.write().unwrap(),
+        }
+    }
+}
+
+/// A lock on all instances, see [`HandleLocks::lock_all`].
+pub struct AllHandlesLock<'a> {
+    _map: RwLockWriteGuard<'a, HandleLockMap>,
 }
@@ -15,6 +15,8 @@ pub use self::cmd::{Command, CommandDetails, SentCommand, StoredCommand, WithSto
 mod store;
 pub use self::store::*;
 
+mod history;
+
 mod listener;
 pub use self::listener::{EventCounter, PostSaveEventListener, PreSaveEventListener};
 
@@ -45,7 +47,7 @@ mod tests {
     use crate::{
         commons::{
             actor::Actor,
-            api::{CommandHistoryCriteria, CommandSummary},
+            api::{CommandHistoryCriteria, CommandSummary, HistoryOrder, Timestamp},
         },
         constants::ACTOR_DEF_TEST,
     };
@@ -355,6 +357,150 @@ mod tests {
         let history = manager.command_history(&id_alice, crit).unwrap();
         assert_eq!(history.total(), 1);
 
+        // Get history for an actor, paginated over the matching commands only
+        let mut crit = CommandHistoryCriteria::default();
+        crit.set_actor("test".to_string());
+        crit.set_offset(20);
+        let history = manager.command_history(&id_alice, crit).unwrap();
+        assert_eq!(history.total(), 22);
+        assert_eq!(history.commands().len(), 2);
+        assert_eq!(history.commands().first().unwrap().actor, "test");
+
+        let mut crit = CommandHistoryCriteria::default();
+        crit.set_actor("alice".to_string());
+        let history = manager.command_history(&id_alice, crit).unwrap();
+        assert_eq!(history.total(), 0);
+
+        // Page through the most recent commands using the cursor. Commands
+        // which are added in the meantime do not affect the next page.
+        let mut crit = CommandHistoryCriteria::default();
+        crit.set_order(HistoryOrder::Descending);
+        crit.set_rows(5);
+        let history = manager.command_history(&id_alice, crit.clone()).unwrap();
+        assert_eq!(history.commands().first().unwrap().sequence, 22);
+        assert_eq!(history.next_cursor(), Some(18));
+
+        manager.command(PersonCommand::go_around_sun(&id_alice, None)).unwrap();
+
+        crit.set_cursor(18);
+        let history = manager.command_history(&id_alice, crit).unwrap();
+        assert_eq!(history.total(), 17);
+        assert_eq!(history.commands().first().unwrap().sequence, 17);
+        assert_eq!(history.next_cursor(), Some(13));
+
+        // The last page has no next cursor
+        let mut crit = CommandHistoryCriteria::default();
+        crit.set_includes(&["person-change-name"]);
+        crit.set_cursor(10);
+        let history = manager.command_history(&id_alice, crit).unwrap();
+        assert_eq!(history.total(), 1);
+        assert_eq!(history.next_cursor(), None);
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn snapshot_policy() {
+        let d = test::tmp_dir();
+
+        let policy = SnapshotPolicy {
+            events: 5,
+            retention: 2,
+        };
+
+        let mut manager = AggregateStore::<Person>::disk(&d, "person").unwrap();
+        manager.set_snapshot_policy(policy);
+
+        let id_alice = MyHandle::from_str("alice").unwrap();
+        manager.add(InitPersonEvent::init(&id_alice, "alice smith")).unwrap();
+
+        for _ in 0..7 {
+            manager.command(PersonCommand::go_around_sun(&id_alice, None)).unwrap();
+        }
+
+        // The snapshot lags behind, but the state is loaded from it plus later events
+        let mut manager = AggregateStore::<Person>::disk(&d, "person").unwrap();
+        manager.set_snapshot_policy(policy);
+
+        let verified = manager.verify_snapshots().unwrap();
+        assert_eq!(verified.len(), 1);
+        assert_eq!(verified[0].version(), 8);
+        assert_eq!(verified[0].snapshot_version(), 5);
+        assert_eq!(verified[0].equivalent(), Some(true));
+        assert_eq!(manager.get_latest(&id_alice).unwrap().age(), 7);
+
+        // Periodic snapshots catch up, and keep retained copies
+        for _ in 0..3 {
+            let saved = manager.save_snapshots(false).unwrap();
+            assert_eq!(saved[0].snapshot_version(), saved[0].version());
+            manager.command(PersonCommand::go_around_sun(&id_alice, None)).unwrap();
+        }
+
+        let alice_dir = d.join("person").join("alice");
+        assert!(!alice_dir.join("snapshot-v8.json").exists());
+        assert!(alice_dir.join("snapshot-v9.json").exists());
+        assert!(alice_dir.join("snapshot-v10.json").exists());
+
+        // A snapshot which does not match the events is reported
+        let snapshot_path = alice_dir.join("snapshot.json");
+        let mut snapshot: serde_json::Value = serde_json::from_slice(&fs::read(&snapshot_path).unwrap()).unwrap();
+        snapshot["age"] = serde_json::json!(99);
+        fs::write(&snapshot_path, serde_json::to_vec(&snapshot).unwrap()).unwrap();
+
+        let verified = manager.verify_snapshots().unwrap();
+        assert_eq!(verified[0].equivalent(), Some(false));
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn archive_history() {
+        let d = test::tmp_dir();
+
+        let manager = AggregateStore::<Person>::disk(&d, "person").unwrap();
+
+        let id_alice = MyHandle::from_str("alice").unwrap();
+        manager.add(InitPersonEvent::init(&id_alice, "alice smith")).unwrap();
+
+        for _ in 0..5 {
+            manager.command(PersonCommand::go_around_sun(&id_alice, None)).unwrap();
+        }
+
+        // Nothing is archived if all commands are more recent
+        assert_eq!(
+            manager.archive_history(Timestamp::now_minus_hours(1).into()).unwrap(),
+            0
+        );
+
+        let archived = manager.archive_history(Timestamp::now_plus_hours(1).into()).unwrap();
+        assert_eq!(archived, 5);
+        assert!(manager.get_event::<PersonEvent>(&id_alice, 1).unwrap().is_none());
+        assert!(manager.get_archived_event(&id_alice, 1).unwrap().is_some());
+
+        // Archived commands are only included in the history if asked
+        let mut crit = CommandHistoryCriteria::default();
+        assert_eq!(manager.command_history(&id_alice, crit.clone()).unwrap().total(), 0);
+
+        crit.set_include_archived();
+        let history = manager.command_history(&id_alice, crit).unwrap();
+        assert_eq!(history.total(), 5);
+
+        let key = history.commands()[0].command_key().unwrap();
+        let command = manager.get_command::<PersonCommandDetails>(&id_alice, &key).unwrap();
+        assert_eq!(command.sequence(), 1);
+
+        // The state can still be loaded, recovered and rebuilt from all events
+        manager.command(PersonCommand::go_around_sun(&id_alice, None)).unwrap();
+
+        let manager = AggregateStore::<Person>::disk(&d, "person").unwrap();
+        assert_eq!(manager.get_latest(&id_alice).unwrap().age(), 6);
+
+        manager.recover().unwrap();
+        assert_eq!(manager.get_latest(&id_alice).unwrap().age(), 6);
+
+        let verified = manager.verify_snapshots().unwrap();
+        assert_eq!(verified[0].equivalent(), Some(true));
+
         let _ = fs::remove_dir_all(d);
     }
 }
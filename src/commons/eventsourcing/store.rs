@@ -11,15 +11,16 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use rpki::{ca::idexchange::MyHandle, repository::x509::Time};
 
 use crate::commons::{
-    api::{CommandHistory, CommandHistoryCriteria, CommandHistoryRecord, Label},
+    api::{AggregateSnapshot, CommandHistory, CommandHistoryCriteria, CommandHistoryRecord, HistoryOrder, Label},
     error::KrillIoError,
     eventsourcing::{
         cmd::{Command, StoredCommandBuilder},
+        history::{HistoryArchive, HistoryArchiveIndex, HistoryArchiveInfo, StoredHistoryArchive},
         locks::HandleLocks,
-        Aggregate, Event, KeyStoreKey, KeyValueError, KeyValueStore, PostSaveEventListener, PreSaveEventListener,
-        StoredCommand, WithStorableDetails,
+        Aggregate, Event, KeyStoreKey, KeyValueEntry, KeyValueError, KeyValueStorage, KeyValueStore,
+        PostSaveEventListener, PreSaveEventListener, StoredCommand, WithStorableDetails,
     },
-    util::KrillVersion,
+    util::{logging::LogContext, KrillVersion},
 };
 
 pub type StoreResult<T> = Result<T, AggregateStoreError>;
@@ -48,6 +49,31 @@ impl Default for StoredValueInfo {
     }
 }
 
+//------------ SnapshotPolicy ------------------------------------------------
+
+/// Determines how often snapshots of aggregates are saved when commands are
+/// processed, and how many older snapshots are retained by `save_snapshots`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SnapshotPolicy {
+    // Save a snapshot when at least this number of events were applied
+    // since the last snapshot. Later events are replayed on load.
+    pub events: u64,
+
+    // Number of older snapshots to keep, in addition to the current and
+    // backup snapshot. These can be used when recovering to an earlier
+    // version of an aggregate.
+    pub retention: usize,
+}
+
+impl Default for SnapshotPolicy {
+    fn default() -> Self {
+        SnapshotPolicy {
+            events: 1,
+            retention: 0,
+        }
+    }
+}
+
 //------------ CommandKey ----------------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -129,6 +155,8 @@ impl fmt::Display for CommandKeyError {
 /// This type is responsible for managing aggregates.
 pub struct AggregateStore<A: Aggregate> {
     kv: KeyValueStore,
+    name_space: String,
+    snapshot_policy: SnapshotPolicy,
     cache: RwLock<HashMap<MyHandle, Arc<A>>>,
     pre_save_listeners: Vec<Arc<dyn PreSaveEventListener<A>>>,
     post_save_listeners: Vec<Arc<dyn PostSaveEventListener<A>>>,
@@ -141,6 +169,18 @@ impl<A: Aggregate> AggregateStore<A>
 where
     A::Error: From<AggregateStoreError>,
 {
+    /// Creates an AggregateStore using a KeyValueStore with the given storage.
+    pub fn create(storage: &KeyValueStorage, name_space: &str) -> StoreResult<Self> {
+        match storage {
+            KeyValueStorage::Disk(work_dir) => Self::disk(work_dir, name_space),
+            KeyValueStorage::Postgres(config) => {
+                // The KeyValueStore sets the version if it is new.
+                let kv = KeyValueStore::postgres(config, name_space)?;
+                Ok(Self::with_kv(kv, name_space))
+            }
+        }
+    }
+
     /// Creates an AggregateStore using a disk based KeyValueStore
     pub fn disk(work_dir: &Path, name_space: &str) -> StoreResult<Self> {
         let mut path = work_dir.to_path_buf();
@@ -148,18 +188,7 @@ where
         let existed = path.exists();
 
         let kv = KeyValueStore::disk(work_dir, name_space)?;
-        let cache = RwLock::new(HashMap::new());
-        let pre_save_listeners = vec![];
-        let post_save_listeners = vec![];
-        let locks = HandleLocks::default();
-
-        let store = AggregateStore {
-            kv,
-            cache,
-            pre_save_listeners,
-            post_save_listeners,
-            locks,
-        };
+        let store = Self::with_kv(kv, name_space);
 
         if !existed {
             store.set_version(&KrillVersion::code_version())?;
@@ -168,6 +197,18 @@ where
         Ok(store)
     }
 
+    fn with_kv(kv: KeyValueStore, name_space: &str) -> Self {
+        AggregateStore {
+            kv,
+            name_space: name_space.to_string(),
+            snapshot_policy: SnapshotPolicy::default(),
+            cache: RwLock::new(HashMap::new()),
+            pre_save_listeners: vec![],
+            post_save_listeners: vec![],
+            locks: HandleLocks::default(),
+        }
+    }
+
     /// Warms up the cache, to be used after startup. Will fail if any aggregates fail to load
     /// in which case a 'recover' operation can be tried.
     pub fn warm(&self) -> StoreResult<()> {
@@ -225,6 +266,9 @@ where
         // Save the snapshot if it does not yet match the latest state
         if info.snapshot_version != agg.version() {
             self.store_snapshot(handle, agg.as_ref())?;
+            let mut info = info;
+            info.snapshot_version = agg.version();
+            self.save_info(handle, &info)?;
             debug!(
                 "Saved updated snapshot for '{}', to decrease future load times.",
                 handle
@@ -263,9 +307,13 @@ where
             //   - save snapshot
             //   - save info
 
-            let mut last_good_cmd = 0;
-            let mut last_good_evt = 0;
-            let mut last_update = Time::now();
+            // Start from the last archived command and event, if any. These
+            // are no longer kept with the other commands and events.
+            let index = self.get_history_index(&handle)?;
+            let (mut last_good_cmd, mut last_good_evt, mut last_update) = match index.last() {
+                Some(archived) => (archived.last_command, archived.last_event, archived.last_update),
+                None => (0, 0, Time::now()),
+            };
 
             // Check all commands and associated events
             let mut all_ok = true;
@@ -331,6 +379,13 @@ where
 
             self.store_snapshot(&handle, &agg)?;
 
+            // Retained snapshots after the recovered version no longer apply
+            for version in self.retained_snapshot_versions(&handle)? {
+                if version > snapshot_version {
+                    self.kv.drop_key(&Self::key_for_retained_snapshot(&handle, version))?;
+                }
+            }
+
             self.cache_update(&handle, Arc::new(agg));
 
             self.save_info(&handle, &info)?;
@@ -339,6 +394,11 @@ where
         Ok(())
     }
 
+    /// Sets the policy for saving and retaining snapshots.
+    pub fn set_snapshot_policy(&mut self, policy: SnapshotPolicy) {
+        self.snapshot_policy = policy;
+    }
+
     /// Adds a listener that will receive all events before they are stored.
     pub fn add_pre_save_listener<L: PreSaveEventListener<A>>(&mut self, sync_listener: Arc<L>) {
         self.pre_save_listeners.push(sync_listener);
@@ -411,6 +471,8 @@ where
         info.last_update = Time::now();
         info.last_command += 1;
 
+        let _log_context = LogContext::enter_command(&handle, info.last_command);
+
         // Get the latest arc.
         let mut latest = self.get_latest_no_lock(&handle)?;
 
@@ -509,8 +571,10 @@ where
                     for event in &events {
                         self.store_event(event)?;
                     }
-                    info.snapshot_version = agg.version();
-                    self.store_snapshot(&handle, agg)?;
+                    if agg.version() - info.snapshot_version >= self.snapshot_policy.events {
+                        info.snapshot_version = agg.version();
+                        self.store_snapshot(&handle, agg)?;
+                    }
 
                     cache.insert(handle.clone(), Arc::new(agg.clone()));
 
@@ -539,6 +603,334 @@ where
     pub fn list(&self) -> Result<Vec<MyHandle>, AggregateStoreError> {
         self.aggregates()
     }
+
+    /// Exports all keys and values in this store, e.g. for a backup, by
+    /// passing them to the given function. All aggregates are locked while
+    /// the store is exported, so that the exported store is consistent.
+    pub fn export(&self, f: &mut dyn FnMut(KeyValueEntry) -> Result<(), KeyValueError>) -> StoreResult<()> {
+        let _lock = self.locks.lock_all();
+
+        for key in self.kv.keys(None, "")? {
+            if let Some(value) = self.kv.get(&key)? {
+                f(KeyValueEntry::new(key, value))?;
+            }
+        }
+
+        for handle in self.list()? {
+            self.kv.export_each(Some(handle.as_str()), f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// # Manage Snapshots
+///
+impl<A: Aggregate> AggregateStore<A>
+where
+    A::Error: From<AggregateStoreError>,
+{
+    /// Saves a snapshot for all aggregates which had events applied since
+    /// their last snapshot, or for all aggregates if `force` is true. If the
+    /// snapshot policy retains older snapshots, then a copy of the current
+    /// snapshot is kept as well, and copies exceeding the retention are removed.
+    pub fn save_snapshots(&self, force: bool) -> StoreResult<Vec<AggregateSnapshot>> {
+        let mut res = vec![];
+
+        for handle in self.list()? {
+            let agg_lock = self.locks.for_handle(handle.clone());
+            let _write_lock = agg_lock.write();
+
+            let agg = self.get_latest_no_lock(&handle)?;
+            let mut info = self.get_info(&handle)?;
+
+            if force || info.snapshot_version != agg.version() {
+                debug!("Saving snapshot for '{}' at version {}", handle, agg.version());
+                self.store_snapshot(&handle, agg.as_ref())?;
+                info.snapshot_version = agg.version();
+                self.save_info(&handle, &info)?;
+            }
+
+            if self.snapshot_policy.retention > 0 {
+                let retained = Self::key_for_retained_snapshot(&handle, agg.version());
+                if !self.kv.has(&retained)? {
+                    self.kv.store(&retained, agg.as_ref())?;
+                }
+
+                for version in self
+                    .retained_snapshot_versions(&handle)?
+                    .into_iter()
+                    .skip(self.snapshot_policy.retention)
+                {
+                    self.kv.drop_key(&Self::key_for_retained_snapshot(&handle, version))?;
+                }
+            }
+
+            res.push(AggregateSnapshot::new(
+                self.name_space.clone(),
+                handle,
+                agg.version(),
+                info.snapshot_version,
+                None,
+            ));
+        }
+
+        Ok(res)
+    }
+
+    /// Verifies for all aggregates that the state loaded from the latest
+    /// snapshot, and any events after it, is equivalent to the state rebuilt
+    /// by replaying all events from the init event. This can take a while for
+    /// aggregates with a long history.
+    pub fn verify_snapshots(&self) -> StoreResult<Vec<AggregateSnapshot>> {
+        let mut res = vec![];
+
+        for handle in self.list()? {
+            let agg_lock = self.locks.for_handle(handle.clone());
+            let _read_lock = agg_lock.read();
+
+            let info = self.get_info(&handle)?;
+
+            let from_snapshot = self
+                .get_aggregate(&handle, None)?
+                .ok_or_else(|| AggregateStoreError::UnknownAggregate(handle.clone()))?;
+
+            let init_key = Self::key_for_event(&handle, 0);
+            let init = self
+                .kv
+                .get::<A::InitEvent>(&init_key)?
+                .ok_or_else(|| AggregateStoreError::InitError(handle.clone()))?;
+            let mut from_events = A::init(init).map_err(|_| AggregateStoreError::InitError(handle.clone()))?;
+            self.update_aggregate(&handle, &mut from_events, None)?;
+
+            let equivalent = from_snapshot.version() == from_events.version()
+                && serde_json::to_value(&from_snapshot).ok() == serde_json::to_value(&from_events).ok();
+
+            if !equivalent {
+                warn!(
+                    "Snapshot for '{}' is not equivalent to the state rebuilt from its events",
+                    handle
+                );
+            }
+
+            res.push(AggregateSnapshot::new(
+                self.name_space.clone(),
+                handle,
+                from_events.version(),
+                info.snapshot_version,
+                Some(equivalent),
+            ));
+        }
+
+        Ok(res)
+    }
+
+    /// Returns the versions of the retained snapshots for an aggregate,
+    /// newest first.
+    fn retained_snapshot_versions(&self, id: &MyHandle) -> StoreResult<Vec<u64>> {
+        let mut versions: Vec<u64> = self
+            .kv
+            .keys(Some(id.to_string()), "snapshot-v")?
+            .iter()
+            .filter_map(|key| {
+                key.name()
+                    .strip_prefix("snapshot-v")
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .and_then(|version| u64::from_str(version).ok())
+            })
+            .collect();
+
+        versions.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(versions)
+    }
+}
+
+/// # Archive History
+///
+impl<A: Aggregate> AggregateStore<A>
+where
+    A::Error: From<AggregateStoreError>,
+{
+    /// Moves commands issued before the given time, and their events, to a
+    /// compressed archive for each aggregate. Only commands with events that
+    /// are included in the latest snapshot are archived, so the state can
+    /// still be loaded without using the archive. Returns the number of
+    /// archived commands.
+    pub fn archive_history(&self, before: Time) -> StoreResult<usize> {
+        let mut archived = 0;
+
+        for handle in self.list()? {
+            let agg_lock = self.locks.for_handle(handle.clone());
+            let _write_lock = agg_lock.write();
+
+            let info = self.get_info(&handle)?;
+            let mut index = self.get_history_index(&handle)?;
+
+            let mut commands: Vec<StoredCommand<A::StorableCommandDetails>> = vec![];
+            let mut events: Vec<A::Event> = vec![];
+            let mut last_event = index.last().map(|archived| archived.last_event).unwrap_or(0);
+
+            for command_key in self.command_keys_ascending(&handle, &CommandHistoryCriteria::default())? {
+                if command_key.sequence <= index.last_command() {
+                    // Archived before, but not removed, e.g. because of a restart.
+                    self.kv.drop_key(&Self::key_for_command(&handle, &command_key))?;
+                    continue;
+                }
+
+                if command_key.sequence > info.last_command || command_key.timestamp_secs >= before.timestamp() {
+                    break;
+                }
+
+                let command = self.get_command::<A::StorableCommandDetails>(&handle, &command_key)?;
+                let versions = command.effect().events().cloned().unwrap_or_default();
+
+                if versions.iter().any(|version| *version >= info.snapshot_version) {
+                    break; // needed to load the state from the snapshot
+                }
+
+                for version in versions {
+                    let event = self.get_event::<A::Event>(&handle, version)?.ok_or_else(|| {
+                        AggregateStoreError::CouldNotArchive(handle.clone(), format!("missing event {}", version))
+                    })?;
+                    events.push(event);
+                    last_event = version;
+                }
+
+                commands.push(command);
+            }
+
+            let (first, last) = match (commands.first(), commands.last()) {
+                (Some(first), Some(last)) => (first.sequence(), last),
+                _ => continue,
+            };
+
+            let archive_info = HistoryArchiveInfo {
+                first_command: first,
+                last_command: last.sequence(),
+                last_event,
+                last_update: last.time(),
+            };
+
+            debug!(
+                "Archiving {} commands and {} events for '{}'",
+                commands.len(),
+                events.len(),
+                handle
+            );
+
+            let archive = HistoryArchive { commands, events };
+            let stored = archive
+                .compress()
+                .map_err(|e| AggregateStoreError::CouldNotArchive(handle.clone(), e))?;
+
+            // Save the archive and index before removing anything, so that
+            // nothing is lost if we are interrupted.
+            self.kv
+                .store(&Self::key_for_history_archive(&handle, &archive_info), &stored)?;
+            index.add(archive_info);
+            self.kv.store(&Self::key_for_history_index(&handle), &index)?;
+
+            for command in &archive.commands {
+                self.kv
+                    .drop_key(&Self::key_for_command(&handle, &CommandKey::for_stored(command)))?;
+            }
+            for event in &archive.events {
+                self.kv.drop_key(&Self::key_for_event(&handle, event.version()))?;
+            }
+
+            archived += archive.commands.len();
+        }
+
+        Ok(archived)
+    }
+
+    /// Get an archived event, if it exists. This is slow, as it needs to
+    /// decompress the archive that holds the event.
+    pub fn get_archived_event(&self, id: &MyHandle, version: u64) -> StoreResult<Option<A::Event>> {
+        Ok(self.archived_events(id, version)?.remove(&version))
+    }
+
+    fn get_history_index(&self, id: &MyHandle) -> StoreResult<HistoryArchiveIndex> {
+        Ok(self.kv.get(&Self::key_for_history_index(id))?.unwrap_or_default())
+    }
+
+    fn get_history_archive(
+        &self,
+        id: &MyHandle,
+        info: &HistoryArchiveInfo,
+    ) -> StoreResult<HistoryArchive<A::StorableCommandDetails, A::Event>> {
+        let key = Self::key_for_history_archive(id, info);
+        let stored: StoredHistoryArchive = self.kv.get(&key)?.ok_or_else(|| {
+            AggregateStoreError::CouldNotArchive(id.clone(), format!("missing history archive {}", key))
+        })?;
+
+        stored
+            .decompress()
+            .map_err(|e| AggregateStoreError::CouldNotArchive(id.clone(), e))
+    }
+
+    /// Returns all events from the archive that contains the event for
+    /// the given version, if any, by version.
+    fn archived_events(&self, id: &MyHandle, version: u64) -> StoreResult<HashMap<u64, A::Event>> {
+        let index = self.get_history_index(id)?;
+        match index.archive_for_event(version) {
+            None => Ok(HashMap::new()),
+            Some(info) => {
+                debug!("Loading archived events for '{}' to find version {}", id, version);
+                let archive = self.get_history_archive(id, info)?;
+                Ok(archive
+                    .events
+                    .into_iter()
+                    .map(|event| (event.version(), event))
+                    .collect())
+            }
+        }
+    }
+
+    fn archived_command<D: WithStorableDetails>(
+        &self,
+        id: &MyHandle,
+        command_key: &CommandKey,
+    ) -> StoreResult<Option<StoredCommand<D>>> {
+        let index = self.get_history_index(id)?;
+        match index.archive_for_command(command_key.sequence) {
+            None => Ok(None),
+            Some(info) => {
+                let key = Self::key_for_history_archive(id, info);
+                let stored: Option<StoredHistoryArchive> = self.kv.get(&key)?;
+                let archive = match stored {
+                    None => return Ok(None),
+                    Some(stored) => stored
+                        .decompress::<D, A::Event>()
+                        .map_err(|e| AggregateStoreError::CouldNotArchive(id.clone(), e))?,
+                };
+                Ok(archive
+                    .commands
+                    .into_iter()
+                    .find(|command| command.sequence() == command_key.sequence))
+            }
+        }
+    }
+
+    /// Returns the archived commands which match the criteria, in order.
+    fn archived_commands(
+        &self,
+        id: &MyHandle,
+        crit: &CommandHistoryCriteria,
+    ) -> StoreResult<Vec<StoredCommand<A::StorableCommandDetails>>> {
+        let mut res = vec![];
+
+        for info in self.get_history_index(id)?.archives() {
+            let archive = self.get_history_archive(id, info)?;
+            for command in archive.commands {
+                if CommandKey::for_stored(&command).matches_crit(crit) && crit.matches_actor(command.actor()) {
+                    res.push(command);
+                }
+            }
+        }
+
+        Ok(res)
+    }
 }
 
 /// # Manage Commands
@@ -555,36 +947,80 @@ where
     ) -> Result<CommandHistory, AggregateStoreError> {
         let offset = crit.offset();
 
-        let command_keys = self.command_keys_ascending(id, &crit)?;
+        // Archived commands are older than any other commands, so they go first
+        // in ascending order. They are read up front, the others only when they
+        // are needed.
+        let mut candidates = vec![];
+        if crit.include_archived() {
+            for stored in self.archived_commands(id, &crit)? {
+                candidates.push((CommandKey::for_stored(&stored), Some(stored)));
+            }
+        }
+        for command_key in self.command_keys_ascending(id, &crit)? {
+            candidates.push((command_key, None));
+        }
+
+        if crit.order() == HistoryOrder::Descending {
+            candidates.reverse();
+        }
 
         let rows = match crit.rows_limit() {
             Some(limit) => limit,
-            None => command_keys.len(),
+            None => candidates.len(),
         };
 
         let mut commands: Vec<CommandHistoryRecord> = Vec::with_capacity(rows);
         let mut skipped = 0;
         let mut total = 0;
 
-        for command_key in command_keys {
+        for (command_key, mut stored) in candidates {
+            // Commands only need to be read up front if they are filtered by actor.
+            if crit.has_actor() {
+                let command = match stored {
+                    Some(command) => command,
+                    None => self.stored_command(id, command_key.clone())?,
+                };
+                if !crit.matches_actor(command.actor()) {
+                    continue;
+                }
+                stored = Some(command);
+            }
+
             total += 1;
             if skipped < offset {
                 skipped += 1;
             } else if commands.len() < rows {
-                let key = Self::key_for_command(id, &command_key);
-                let stored: StoredCommand<A::StorableCommandDetails> = self
-                    .kv
-                    .get(&key)?
-                    .ok_or_else(|| AggregateStoreError::CommandNotFound(id.clone(), command_key))?;
-
-                let stored = stored.into();
-                commands.push(stored);
+                let stored = match stored {
+                    Some(stored) => stored,
+                    None => self.stored_command(id, command_key)?,
+                };
+                commands.push(stored.into());
             }
         }
-        Ok(CommandHistory::new(offset, total, commands))
+
+        // There is a next page if not all matching commands were returned.
+        let next_cursor = if offset + commands.len() < total {
+            commands.last().map(|command| command.sequence)
+        } else {
+            None
+        };
+
+        Ok(CommandHistory::new(offset, total, commands, next_cursor))
+    }
+
+    fn stored_command(
+        &self,
+        id: &MyHandle,
+        command_key: CommandKey,
+    ) -> Result<StoredCommand<A::StorableCommandDetails>, AggregateStoreError> {
+        let key = Self::key_for_command(id, &command_key);
+        self.kv
+            .get(&key)?
+            .ok_or_else(|| AggregateStoreError::CommandNotFound(id.clone(), command_key))
     }
 
-    /// Get the command for this key, if it exists
+    /// Get the command for this key, if it exists. Falls back to the history
+    /// archive if the command was archived.
     pub fn get_command<D: WithStorableDetails>(
         &self,
         id: &MyHandle,
@@ -593,7 +1029,9 @@ where
         let key = Self::key_for_command(id, command_key);
         match self.kv.get(&key) {
             Ok(Some(cmd)) => Ok(cmd),
-            Ok(None) => Err(AggregateStoreError::CommandNotFound(id.clone(), command_key.clone())),
+            Ok(None) => self
+                .archived_command(id, command_key)?
+                .ok_or_else(|| AggregateStoreError::CommandNotFound(id.clone(), command_key.clone())),
             Err(e) => {
                 error!(
                     "Found corrupt command at: {}, will try to archive. Error was: {}",
@@ -605,7 +1043,8 @@ where
         }
     }
 
-    /// Get the value for this key, if any exists.
+    /// Get the value for this key, if any exists. Note that this does not
+    /// include archived events, see `get_archived_event`.
     pub fn get_event<V: Event>(&self, id: &MyHandle, version: u64) -> Result<Option<V>, AggregateStoreError> {
         let key = Self::key_for_event(id, version);
         match self.kv.get(&key) {
@@ -696,6 +1135,10 @@ where
         KeyStoreKey::scoped(agg.to_string(), "snapshot-bk.json".to_string())
     }
 
+    fn key_for_retained_snapshot(agg: &MyHandle, version: u64) -> KeyStoreKey {
+        KeyStoreKey::scoped(agg.to_string(), format!("snapshot-v{}.json", version))
+    }
+
     fn key_for_new_snapshot(agg: &MyHandle) -> KeyStoreKey {
         KeyStoreKey::scoped(agg.to_string(), "snapshot-new.json".to_string())
     }
@@ -708,6 +1151,14 @@ where
         KeyStoreKey::scoped(agg.to_string(), format!("{}.json", command))
     }
 
+    fn key_for_history_index(agg: &MyHandle) -> KeyStoreKey {
+        KeyStoreKey::scoped(agg.to_string(), "index.json".to_string()).sub_scope("history")
+    }
+
+    fn key_for_history_archive(agg: &MyHandle, info: &HistoryArchiveInfo) -> KeyStoreKey {
+        KeyStoreKey::scoped(agg.to_string(), info.name()).sub_scope("history")
+    }
+
     pub fn get_version(&self) -> Result<KrillVersion, AggregateStoreError> {
         match self.kv.get::<KrillVersion>(&Self::key_version())? {
             Some(version) => Ok(version),
@@ -883,6 +1334,20 @@ where
             }
         }
 
+        if aggregate_opt.is_none() {
+            // Use the newest retained snapshot that does not exceed the limit, if any
+            for version in self.retained_snapshot_versions(id)? {
+                if limit.map(|limit| limit + 1 >= version).unwrap_or(true) {
+                    let retained_key = Self::key_for_retained_snapshot(id, version);
+                    if let Ok(Some(agg)) = self.kv.get::<A>(&retained_key) {
+                        debug!("Found retained snapshot for '{}' at version {}", id, version);
+                        aggregate_opt = Some(agg);
+                        break;
+                    }
+                }
+            }
+        }
+
         if aggregate_opt.is_none() {
             warn!(
                 "No suitable snapshot for '{}' will rebuild state from events. This can take some time.",
@@ -915,6 +1380,9 @@ where
     ) -> Result<(), AggregateStoreError> {
         let start = aggregate.version();
 
+        // Events loaded from the history archive, if needed.
+        let mut archived: HashMap<u64, A::Event> = HashMap::new();
+
         if let Some(limit) = limit {
             debug!("Will update '{}' from version: {} to: {}", id, start, limit + 1);
         } else {
@@ -934,7 +1402,18 @@ where
                 }
             }
 
-            if let Some(e) = self.get_event::<A::Event>(id, version)? {
+            let event = match archived.remove(&version) {
+                Some(e) => Some(e),
+                None => match self.get_event::<A::Event>(id, version)? {
+                    Some(e) => Some(e),
+                    None => {
+                        archived = self.archived_events(id, version)?;
+                        archived.remove(&version)
+                    }
+                },
+            };
+
+            if let Some(e) = event {
                 if version != e.version() {
                     error!("Trying to apply event to wrong version of aggregate in replay");
                     return Err(AggregateStoreError::ReplayError(id.clone(), version, e.version()));
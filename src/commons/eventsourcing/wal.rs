@@ -8,7 +8,9 @@ use std::{
 
 use rpki::ca::idexchange::MyHandle;
 
-use crate::commons::eventsourcing::{locks::HandleLocks, KeyStoreKey, KeyValueError, KeyValueStore, Storable};
+use crate::commons::eventsourcing::{
+    locks::HandleLocks, KeyStoreKey, KeyValueEntry, KeyValueError, KeyValueStorage, KeyValueStore, Storable,
+};
 
 //------------ WalSupport ----------------------------------------------------
 
@@ -124,6 +126,16 @@ pub struct WalStore<T: WalSupport> {
 }
 
 impl<T: WalSupport> WalStore<T> {
+    /// Creates a new store using a keystore with the given storage and
+    /// namespace.
+    pub fn create(storage: &KeyValueStorage, name_space: &str) -> WalStoreResult<Self> {
+        let kv = KeyValueStore::create(storage, name_space)?;
+        let cache = RwLock::new(HashMap::new());
+        let locks = HandleLocks::default();
+
+        Ok(WalStore { kv, cache, locks })
+    }
+
     /// Creates a new store using a disk based keystore for the given data
     /// directory and namespace (directory).
     pub fn disk(krill_data_dir: &Path, name_space: &str) -> WalStoreResult<Self> {
@@ -265,6 +277,25 @@ impl<T: WalSupport> WalStore<T> {
         Ok(res)
     }
 
+    /// Exports all keys and values in this store, e.g. for a backup, by
+    /// passing them to the given function. All instances are locked while
+    /// the store is exported, so that the exported store is consistent.
+    pub fn export(&self, f: &mut dyn FnMut(KeyValueEntry) -> Result<(), KeyValueError>) -> WalStoreResult<()> {
+        let _lock = self.locks.lock_all();
+
+        for key in self.kv.keys(None, "")? {
+            if let Some(value) = self.kv.get(&key)? {
+                f(KeyValueEntry::new(key, value))?;
+            }
+        }
+
+        for handle in self.list()? {
+            self.kv.export_each(Some(handle.as_str()), f)?;
+        }
+
+        Ok(())
+    }
+
     /// Process a command:
     /// - gets the instance for the command
     /// - sends the command
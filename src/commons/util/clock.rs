@@ -0,0 +1,104 @@
+//! The clock used when issuing RPKI objects.
+//!
+//! The validity times of issued certificates and signed objects, and the
+//! this and next update times of manifests and CRLs, are based on the time
+//! of this clock rather than on the system time directly. By default it is
+//! the system clock, but another clock can be set, e.g. to issue objects as
+//! of another time in tests.
+//!
+//! An incorrect system clock results in objects which are not valid yet, or
+//! which expire too soon, without any error. Issuance can therefore be
+//! refused while the system time is known to be off, see
+//! [`crate::daemon::timecheck`].
+use std::sync::{Arc, RwLock};
+
+use chrono::{Datelike, Duration};
+use rpki::repository::x509::Time;
+
+//------------ Clock ---------------------------------------------------------
+
+/// A source for the current time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Time;
+}
+
+//------------ SystemClock ---------------------------------------------------
+
+/// The system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Time {
+        Time::now()
+    }
+}
+
+//------------ Process Clock -------------------------------------------------
+
+static CLOCK: RwLock<Option<Arc<dyn Clock>>> = RwLock::new(None);
+
+// The reason why issuance is refused, if it is.
+static ISSUANCE_REFUSED: RwLock<Option<String>> = RwLock::new(None);
+
+/// Sets the clock used for issuance. Use [`reset`] to go back to the system
+/// clock.
+pub fn set(clock: Arc<dyn Clock>) {
+    *CLOCK.write().unwrap() = Some(clock);
+}
+
+/// Goes back to using the system clock for issuance.
+pub fn reset() {
+    *CLOCK.write().unwrap() = None;
+}
+
+/// Returns the current time according to the clock used for issuance.
+pub fn now() -> Time {
+    match CLOCK.read().unwrap().as_ref() {
+        Some(clock) => clock.now(),
+        None => SystemClock.now(),
+    }
+}
+
+/// Returns the time five minutes ago. Objects are valid from this time, to
+/// allow for some difference between the clocks of Krill and relying parties.
+pub fn five_minutes_ago() -> Time {
+    now() - Duration::minutes(5)
+}
+
+/// Returns the time the given number of years from now.
+pub fn years_from_now(years: i32) -> Time {
+    years_from(now(), years)
+}
+
+fn years_from(time: Time, years: i32) -> Time {
+    match time.with_year(time.year() + years) {
+        Some(then) => Time::new(then),
+        None => time + Duration::days(365 * i64::from(years)), // Feb 29
+    }
+}
+
+/// Refuses issuance with the given reason, or allows it again if the reason
+/// is None.
+pub fn refuse_issuance(reason: Option<String>) {
+    *ISSUANCE_REFUSED.write().unwrap() = reason;
+}
+
+/// Returns the reason why issuance is refused, if it is.
+pub fn issuance_refused() -> Option<String> {
+    ISSUANCE_REFUSED.read().unwrap().clone()
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn years_from_leap_day() {
+        let leap_day = Time::utc(2024, 2, 29, 12, 0, 0);
+        assert_eq!(years_from(leap_day, 1), Time::utc(2025, 2, 28, 12, 0, 0));
+        assert_eq!(years_from(leap_day, 4), Time::utc(2028, 2, 29, 12, 0, 0));
+    }
+}
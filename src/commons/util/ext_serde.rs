@@ -29,6 +29,22 @@ where
     base64::encode(b).serialize(s)
 }
 
+pub fn de_hex_bytes<'de, D>(d: D) -> Result<Bytes, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let some = String::deserialize(d)?;
+    let dec = hex::decode(some).map_err(de::Error::custom)?;
+    Ok(Bytes::from(dec))
+}
+
+pub fn ser_hex_bytes<S>(b: &Bytes, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    hex::encode(b).serialize(s)
+}
+
 //------------ AsBlocks ------------------------------------------------------
 
 pub fn ser_as_blocks_opt<S>(blocks: &Option<AsBlocks>, s: S) -> Result<S::Ok, S::Error>
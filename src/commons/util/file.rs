@@ -79,6 +79,68 @@ pub fn save(content: &[u8], full_path: &Path) -> Result<(), KrillIoError> {
     Ok(())
 }
 
+//------------ Compression ---------------------------------------------------
+
+/// The compressed variants which can be saved for a file, see
+/// [`save_compressed`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Brotli,
+}
+
+impl Compression {
+    pub const ALL: [Compression; 2] = [Compression::Gzip, Compression::Brotli];
+
+    /// Returns the name of the compression used in HTTP 'Content-Encoding'
+    /// and 'Accept-Encoding' headers.
+    pub fn content_encoding(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Brotli => "br",
+        }
+    }
+
+    /// Returns the path for the compressed variant of a file, i.e. the path
+    /// with ".gz" or ".br" appended to it.
+    pub fn path(self, path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(match self {
+            Compression::Gzip => ".gz",
+            Compression::Brotli => ".br",
+        });
+        PathBuf::from(name)
+    }
+
+    fn compress(self, content: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = libflate::gzip::Encoder::new(Vec::new())?;
+                encoder.write_all(content)?;
+                encoder.finish().into_result()
+            }
+            Compression::Brotli => {
+                // Quality 9 of 11 compresses nearly as well as the maximum,
+                // at a fraction of the time needed for large snapshots.
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 9, 22);
+                encoder.write_all(content)?;
+                Ok(encoder.into_inner())
+            }
+        }
+    }
+}
+
+/// Saves the compressed content to the compressed variant of the given
+/// path, see [`Compression::path`], creating parent dirs as needed.
+pub fn save_compressed(content: &[u8], full_path: &Path, compression: Compression) -> Result<(), KrillIoError> {
+    let path = compression.path(full_path);
+    let compressed = compression
+        .compress(content)
+        .map_err(|e| KrillIoError::new(format!("Could not compress to: {}", path.to_string_lossy()), e))?;
+
+    save(&compressed, &path)
+}
+
 /// Saves an object to json - unwraps any json errors!
 pub fn save_json<O: Serialize>(object: &O, full_path: &Path) -> Result<(), KrillIoError> {
     let json = serde_json::to_string(object).unwrap();
@@ -306,6 +368,96 @@ pub fn backup_dir(base_path: &Path, target_path: &Path) -> Result<(), Error> {
     }
 }
 
+/// Returns the number of files in a dir, including sub-dirs, and their total
+/// size in bytes. Returns zeroes if the dir does not exist.
+pub fn dir_usage(path: &Path) -> Result<(u64, u64), KrillIoError> {
+    let mut files = 0;
+    let mut bytes = 0;
+
+    if path.is_dir() {
+        for entry in fs::read_dir(path)
+            .map_err(|e| KrillIoError::new(format!("Could not read dir '{}'", path.to_string_lossy()), e))?
+        {
+            let entry = entry.map_err(|e| {
+                KrillIoError::new(format!("Could not read entry in dir '{}'", path.to_string_lossy()), e)
+            })?;
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                let (sub_files, sub_bytes) = dir_usage(&entry_path)?;
+                files += sub_files;
+                bytes += sub_bytes;
+            } else {
+                let metadata = entry.metadata().map_err(|e| {
+                    KrillIoError::new(
+                        format!("Could not read metadata of '{}'", entry_path.to_string_lossy()),
+                        e,
+                    )
+                })?;
+                files += 1;
+                bytes += metadata.len();
+            }
+        }
+    }
+
+    Ok((files, bytes))
+}
+
+/// Returns the number of bytes available to unprivileged users on the file
+/// system which holds the path, if this can be determined.
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> Option<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } == 0 {
+        #[allow(clippy::unnecessary_cast)] // the field types differ per platform
+        Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+//------------ DirLock -------------------------------------------------------
+
+/// An exclusive advisory lock on a directory, which is held until this is
+/// dropped. This excludes other threads as well as other processes which
+/// lock the same directory.
+#[derive(Debug)]
+pub struct DirLock {
+    _dir: File,
+}
+
+impl DirLock {
+    /// Waits until the lock on the directory is acquired.
+    #[cfg(unix)]
+    pub fn exclusive(dir: &Path) -> Result<Self, KrillIoError> {
+        use std::os::unix::io::AsRawFd;
+
+        let err = |e| KrillIoError::new(format!("Could not lock dir '{}'", dir.to_string_lossy()), e);
+
+        let file = File::open(dir).map_err(err)?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(err(io::Error::last_os_error()));
+        }
+        Ok(DirLock { _dir: file })
+    }
+
+    /// Advisory locks are not supported on this platform, so this only
+    /// checks that the directory can be opened.
+    #[cfg(not(unix))]
+    pub fn exclusive(dir: &Path) -> Result<Self, KrillIoError> {
+        let file = File::open(dir)
+            .map_err(|e| KrillIoError::new(format!("Could not lock dir '{}'", dir.to_string_lossy()), e))?;
+        Ok(DirLock { _dir: file })
+    }
+}
+
 //------------ CurrentFile ---------------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -481,4 +633,29 @@ mod tests {
             assert!(files.contains(&file_4));
         });
     }
+
+    #[test]
+    fn should_save_compressed() {
+        test::test_under_tmp(|base_dir| {
+            let path = base_dir.join("dir/file.xml");
+            save_compressed(b"some xml content", &path, Compression::Gzip).unwrap();
+            save_compressed(b"some xml content", &path, Compression::Brotli).unwrap();
+
+            let gzip_path = Compression::Gzip.path(&path);
+            assert_eq!(gzip_path, base_dir.join("dir/file.xml.gz"));
+            let compressed = read(&gzip_path).unwrap();
+            let mut decoder = libflate::gzip::Decoder::new(compressed.as_ref()).unwrap();
+            let mut content = Vec::new();
+            decoder.read_to_end(&mut content).unwrap();
+            assert_eq!(content, b"some xml content".to_vec());
+
+            let brotli_path = Compression::Brotli.path(&path);
+            assert_eq!(brotli_path, base_dir.join("dir/file.xml.br"));
+            let compressed = read(&brotli_path).unwrap();
+            let mut decoder = brotli::Decompressor::new(compressed.as_ref(), 4096);
+            let mut content = Vec::new();
+            decoder.read_to_end(&mut content).unwrap();
+            assert_eq!(content, b"some xml content".to_vec());
+        });
+    }
 }
@@ -2,8 +2,9 @@
 use std::{env, fmt, path::PathBuf, str::FromStr, time::Duration};
 
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use reqwest::{
-    header::{HeaderMap, HeaderValue, CONTENT_TYPE, USER_AGENT},
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE, DATE, USER_AGENT},
     Response, StatusCode,
 };
 use serde::{de::DeserializeOwned, Serialize};
@@ -107,6 +108,63 @@ pub async fn get_text(uri: &str, token: Option<&Token>) -> Result<String, Error>
     text_response(uri, res).await
 }
 
+/// Performs a GET request on behalf of the daemon and expects a 200 OK
+/// response with a text body. Unlike [get_text] this never reports the
+/// request for the CLI '--api' option, and redirects are not followed, so
+/// that the request cannot end up at another host than the one requested.
+pub async fn get_text_no_redirects(uri: &str, token: Option<&Token>, timeout: u64) -> Result<String, Error> {
+    let headers = headers(uri, None, token)?;
+    let res = client_with_tweaks(uri, Duration::from_secs(timeout), false)?
+        .get(uri)
+        .headers(headers)
+        .send()
+        .await
+        .map_err(|e| Error::execute(uri, e))?;
+
+    text_response(uri, res).await
+}
+
+/// Performs a GET request and expects a 200 OK response. The response body
+/// is returned as is, so it can be used where the exact bytes matter.
+pub async fn get_bytes(uri: &str) -> Result<Bytes, Error> {
+    let headers = headers(uri, None, None)?;
+    let res = client(uri)?
+        .get(uri)
+        .headers(headers)
+        .send()
+        .await
+        .map_err(|e| Error::execute(uri, e))?;
+
+    match res.status() {
+        StatusCode::OK => res.bytes().await.map_err(|e| Error::response(uri, e)),
+        StatusCode::FORBIDDEN => Err(Error::Forbidden(uri.to_string())),
+        _ => Err(Error::from_res(uri, res).await),
+    }
+}
+
+/// Performs a HEAD request and returns the time in the Date header of the
+/// response, whatever the status of the response.
+pub async fn head_date(uri: &str) -> Result<DateTime<Utc>, Error> {
+    let headers = headers(uri, None, None)?;
+    let res = client(uri)?
+        .head(uri)
+        .headers(headers)
+        .send()
+        .await
+        .map_err(|e| Error::execute(uri, e))?;
+
+    let date = res
+        .headers()
+        .get(DATE)
+        .ok_or_else(|| Error::response(uri, "no Date header in response"))?
+        .to_str()
+        .map_err(|e| Error::response(uri, e))?;
+
+    DateTime::parse_from_rfc2822(date)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|e| Error::response(uri, format!("invalid Date header '{}': {}", date, e)))
+}
+
 /// Checks that there is a 200 OK response at the given URI. Discards the
 /// response body.
 pub async fn get_ok(uri: &str, token: Option<&Token>) -> Result<(), Error> {
@@ -217,6 +275,9 @@ async fn do_empty_post(uri: &str, token: Option<&Token>) -> Result<Response, Err
 /// as the user agent. Intended for sending RFC 6492 (provisioning) and 8181 (publication)
 /// to the trusted parent or publication server.
 ///
+/// The given headers are added to the request, e.g. to pass on the trace
+/// context of the exchange.
+///
 /// Note: Bytes may be empty if the post was successful, but the response was
 /// empty.
 pub async fn post_binary_with_full_ua(
@@ -224,11 +285,10 @@ pub async fn post_binary_with_full_ua(
     data: &Bytes,
     content_type: &str,
     timeout: u64,
+    mut headers: HeaderMap,
 ) -> Result<Bytes, Error> {
     let body = data.to_vec();
 
-    let mut headers = HeaderMap::new();
-
     let ua_string = format!("krill/{}", KRILL_VERSION);
     let user_agent_value = HeaderValue::from_str(&ua_string).map_err(|e| Error::request_build(uri, e))?;
     let content_type_value = HeaderValue::from_str(content_type).map_err(|e| Error::request_build(uri, e))?;
@@ -463,6 +523,18 @@ impl Error {
         Error::Response(uri.to_string(), Self::unexpected_status(status))
     }
 
+    /// Returns the HTTP status code of the response, if an error response
+    /// was received.
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            Error::Forbidden(_) => Some(StatusCode::FORBIDDEN.as_u16()),
+            Error::ErrorResponseWithBody(_, status, _) | Error::ErrorResponseWithJson(_, status, _) => {
+                Some(status.as_u16())
+            }
+            _ => None,
+        }
+    }
+
     async fn from_res(uri: &str, res: Response) -> Error {
         let status = res.status();
         match res.text().await {
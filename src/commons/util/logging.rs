@@ -0,0 +1,197 @@
+//! Context for log messages, so that all log messages resulting from an API
+//! request or a scheduled task can be correlated.
+//!
+//! The context is kept in a Tokio task local, which is set up at the HTTP
+//! layer for each request and by the scheduler for each task. Code that is
+//! run as part of these can add details, such as the actor or the CA and
+//! command being processed. The context is included in log messages if the
+//! JSON log format is used.
+use std::{cell::RefCell, future::Future};
+
+use rpki::ca::idexchange::MyHandle;
+use serde::Serialize;
+
+tokio::task_local! {
+    static LOG_CONTEXT: RefCell<LogContext>;
+}
+
+/// The header used to pass on a request id, which is reused if set by the
+/// client and returned in the response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// The maximum length of a request id given by a client.
+const REQUEST_ID_MAX_LEN: usize = 64;
+
+//------------ LogContext ----------------------------------------------------
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct LogContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    task: Option<String>,
+
+    // The ids of the requests which caused a task to be scheduled.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    caused_by: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actor: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ca: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+}
+
+impl LogContext {
+    /// Creates a context for an API request. The request id given by the
+    /// client is used if it is acceptable, otherwise a new id is generated.
+    pub fn request(headers: &hyper::HeaderMap) -> Self {
+        let request_id = headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| Self::is_acceptable_request_id(id))
+            .map(ToString::to_string)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        LogContext {
+            request_id: Some(request_id),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a context for a scheduled task, which was caused by the given
+    /// requests, if any.
+    pub fn task(name: &str, caused_by: Vec<String>) -> Self {
+        LogContext {
+            task: Some(name.to_string()),
+            caused_by,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_ca(mut self, ca: Option<&MyHandle>) -> Self {
+        self.ca = ca.map(ToString::to_string);
+        self
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    fn is_acceptable_request_id(id: &str) -> bool {
+        !id.is_empty()
+            && id.len() <= REQUEST_ID_MAX_LEN
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+
+    /// Runs the future with this context.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        LOG_CONTEXT.scope(RefCell::new(self), future).await
+    }
+
+    /// Returns the context of the current request or task, if any.
+    pub fn current() -> Option<LogContext> {
+        LOG_CONTEXT.try_with(|context| context.borrow().clone()).ok()
+    }
+
+    /// Returns the id of the current request, if any.
+    pub fn current_request_id() -> Option<String> {
+        LOG_CONTEXT
+            .try_with(|context| context.borrow().request_id.clone())
+            .ok()
+            .flatten()
+    }
+
+    /// Sets the actor for the current request, if any.
+    pub fn set_actor(actor: &str) {
+        let _ = LOG_CONTEXT.try_with(|context| context.borrow_mut().actor = Some(actor.to_string()));
+    }
+
+    /// Sets the CA or publisher and the command which are processed in the
+    /// current request or task, if any, until the returned guard is dropped.
+    pub fn enter_command(handle: &MyHandle, sequence: u64) -> CommandGuard {
+        let previous = LOG_CONTEXT
+            .try_with(|context| {
+                let mut context = context.borrow_mut();
+                let previous = (context.ca.take(), context.command.take());
+                context.ca = Some(handle.to_string());
+                context.command = Some(format!("{}-{}", handle, sequence));
+                previous
+            })
+            .ok();
+
+        CommandGuard { previous }
+    }
+}
+
+//------------ CommandGuard --------------------------------------------------
+
+/// Restores the CA and command in the log context when dropped.
+pub struct CommandGuard {
+    previous: Option<(Option<String>, Option<String>)>,
+}
+
+impl Drop for CommandGuard {
+    fn drop(&mut self) {
+        if let Some((ca, command)) = self.previous.take() {
+            let _ = LOG_CONTEXT.try_with(|context| {
+                let mut context = context.borrow_mut();
+                context.ca = ca;
+                context.command = command;
+            });
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn command_context_is_restored() {
+        let mut headers = hyper::HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, "click-1".parse().unwrap());
+
+        let context = LogContext::request(&headers);
+        assert_eq!(Some("click-1"), context.request_id());
+
+        context
+            .scope(async {
+                LogContext::set_actor("admin");
+
+                let ca = MyHandle::from_str("ca").unwrap();
+                {
+                    let _guard = LogContext::enter_command(&ca, 7);
+                    let current = LogContext::current().unwrap();
+                    assert_eq!(Some("ca-7".to_string()), current.command);
+                    assert_eq!(Some("admin".to_string()), current.actor);
+                }
+
+                let current = LogContext::current().unwrap();
+                assert_eq!(None, current.ca);
+                assert_eq!(None, current.command);
+                assert_eq!(Some("click-1".to_string()), LogContext::current_request_id());
+            })
+            .await;
+
+        assert!(LogContext::current().is_none());
+    }
+
+    #[test]
+    fn unacceptable_request_id_is_replaced() {
+        let mut headers = hyper::HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, "not acceptable!".parse().unwrap());
+
+        let context = LogContext::request(&headers);
+        assert_ne!(Some("not acceptable!"), context.request_id());
+        assert!(context.request_id().is_some());
+    }
+}
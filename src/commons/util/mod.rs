@@ -11,10 +11,14 @@ use rpki::{
 
 use crate::constants::KRILL_VERSION;
 
+pub mod clock;
 pub mod cmslogger;
 pub mod ext_serde;
 pub mod file;
 pub mod httpclient;
+pub mod logging;
+pub mod postgres;
+pub mod s3;
 
 //------------ KrillVersion --------------------------------------------------
 
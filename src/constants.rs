@@ -7,6 +7,7 @@ pub const KRILL_VERSION_PATCH: &str = env!("CARGO_PKG_VERSION_PATCH");
 pub const KRILL_SERVER_APP: &str = "Krill";
 pub const KRILL_UP_APP: &str = "Krill Upgrade Helper";
 pub const KRILL_CLIENT_APP: &str = "Krill Client";
+pub const KRILL_CLIENT_BIN: &str = "krillc";
 pub const KRILL_TA_CLIENT_APP: &str = "Krill Trust Anchor Client";
 
 pub const KRILL_DEFAULT_CONFIG_FILE: &str = "/etc/krill.conf";
@@ -15,6 +16,7 @@ const KRILL_ENV_TEST: &str = "KRILL_TEST";
 const KRILL_ENV_TEST_ANN: &str = "KRILL_TEST_ANN";
 pub const KRILL_ENV_UPGRADE_ONLY: &str = "KRILL_UPGRADE_ONLY";
 pub const KRILL_ENV_FORCE_RECOVER: &str = "KRILL_FORCE_RECOVER";
+pub const KRILL_ENV_ALLOW_DOWNGRADE: &str = "KRILL_ALLOW_DOWNGRADE";
 pub const KRILL_ENV_LOG_LEVEL: &str = "KRILL_LOG_LEVEL";
 pub const KRILL_ENV_LOG_TYPE: &str = "KRILL_LOG_TYPE";
 pub const KRILL_ENV_ADMIN_TOKEN: &str = "KRILL_ADMIN_TOKEN";
@@ -39,7 +41,10 @@ pub fn test_announcements_enabled() -> bool {
 }
 
 pub const KEYS_DIR: &str = "keys";
+pub const KEYS_ARCHIVE_DIR: &str = "archived";
+pub const KEYS_GC_MIN_AGE_HOURS: u64 = 24;
 pub const SIGNERS_DIR: &str = "signers";
+pub const SERIALS_DIR: &str = "serials";
 
 pub const CASERVER_DIR: &str = "cas";
 pub const TA_PROXY_SERVER_DIR: &str = "ta_proxy";
@@ -54,10 +59,19 @@ pub const PUBSERVER_BACKUP_DIR: &str = "pubd_bk";
 pub const REPOSITORY_DIR: &str = "repo";
 pub const REPOSITORY_RRDP_DIR: &str = "rrdp";
 pub const REPOSITORY_RRDP_ARCHIVE_DIR: &str = "archive";
+pub const REPOSITORY_ARCHIVE_DIR: &str = "repo_archive";
+pub const REPOSITORY_ARCHIVE_INDEX: &str = "repo_archive.json";
+pub const REPOSITORY_S3_INDEX: &str = "repo_s3.json";
 pub const RRDP_FIRST_SERIAL: u64 = 1; // RFC 8182 says we MUST use 1 as the first serial
 pub const REPOSITORY_RSYNC_DIR: &str = "rsync";
+pub const REPOSITORY_RSYNC_CURRENT: &str = "current";
 
 pub const STATUS_DIR: &str = "status";
+pub const PUBLICATION_CHECK_DIR: &str = "publication_check";
+pub const BACKUP_DIR: &str = "backups";
+pub const HA_DIR: &str = "ha";
+pub const JOBS_DIR: &str = "jobs";
+pub const API_TOKENS_DIR: &str = "api_tokens";
 
 pub const KRILL_CLI_SERVER_ARG: &str = "server";
 pub const KRILL_CLI_SERVER_ENV: &str = "KRILL_CLI_SERVER";
@@ -71,16 +85,41 @@ pub const KRILL_CLI_API_ARG: &str = "api";
 pub const KRILL_CLI_API_ENV: &str = "KRILL_CLI_API";
 pub const KRILL_CLI_MY_CA_ARG: &str = "ca";
 pub const KRILL_CLI_MY_CA_ENV: &str = "KRILL_CLI_MY_CA";
+pub const KRILL_CLI_PROFILE_ARG: &str = "profile";
+pub const KRILL_CLI_PROFILE_ENV: &str = "KRILL_CLI_PROFILE";
+pub const KRILL_CLI_CONFIG_ENV: &str = "KRILL_CLI_CONFIG";
+pub const KRILL_CLI_CONFIG_DFLT: &str = ".krillc/config";
 
 pub const CA_REFRESH_SECONDS_MIN: u32 = 3600;
 pub const CA_REFRESH_SECONDS_MAX: u32 = 3 * 24 * 3600; // 3 days
 pub const CA_SUSPEND_MIN_HOURS: u32 = 48; // at least 2 days
+pub const PUBLICATION_CHECK_MIN_INTERVAL_MINS: u32 = 10;
+pub const PUBLICATION_CONFIRM_MIN_LAG_MINS: u32 = 5;
+pub const PUBLICATION_CONFIRM_RETRY_SECONDS: i64 = 60;
+pub const RECONCILE_MIN_INTERVAL_MINS: u32 = 10;
+pub const PARENT_EXCHANGE_LOG_LEN: usize = 20;
+pub const JOBS_KEEP: usize = 100; // The number of finished jobs which are kept.
 pub const SCHEDULER_REQUEUE_DELAY_SECONDS: i64 = 300;
 pub const SCHEDULER_RESYNC_REPO_CAS_THRESHOLD: usize = 5;
 pub const SCHEDULER_USE_JITTER_CAS_THRESHOLD: usize = 50;
 pub const SCHEDULER_USE_JITTER_CAS_PARENTS_THRESHOLD: usize = 5;
 pub const SCHEDULER_INTERVAL_REPUBLISH_MINS: i64 = 5;
 pub const SCHEDULER_INTERVAL_RENEW_MINS: i64 = 60;
+pub const SCHEDULER_INTERVAL_ROA_AUTOPILOT_MINS: i64 = 60;
+pub const SCHEDULER_INTERVAL_RESOURCE_TRANSFER_MINS: i64 = 10;
+pub const SCHEDULER_INTERVAL_SCHEDULED_CHANGES_RETRY_MINS: i64 = 5;
+
+pub const RESOURCE_TRANSFER_TIMEOUT_HOURS_DFLT: u32 = 24;
+
+// Scheduled changes cannot be activated more than ~10 years from now.
+pub const SCHEDULED_CHANGE_MAX_DAYS: i64 = 3653;
+
+// Manifest and CRL timing which is allowed, but results in a warning.
+pub const TIMING_PUBLISH_WARN_HOURS_BEFORE_NEXT: u32 = 4;
+pub const TIMING_PUBLISH_WARN_REPLAY_HOURS: u32 = 168;
+
+// 1 day, 1, 4, 12, 26 and 52 weeks, and 2 years
+pub const METRICS_EXPIRY_BUCKETS_HOURS: [i64; 7] = [24, 168, 672, 2016, 4368, 8760, 17520];
 
 pub const KRILL_HTTPS_ROOT_CERTS_ENV: &str = "KRILL_HTTPS_ROOT_CERTS";
 
@@ -91,6 +130,8 @@ pub const BGP_RIS_REFRESH_MINUTES: i64 = 60;
 pub const HTTP_CLIENT_TIMEOUT_SECS: u64 = 120;
 pub const HTTP_USER_AGENT_TRUNCATE: usize = 256; // Will truncate received user-agent values at this size.
 pub const OPENID_CONNECT_HTTP_CLIENT_TIMEOUT_SECS: u64 = 30;
+pub const NOTIFY_SMTP_TIMEOUT_SECS: u64 = 30;
+pub const REPOSITORY_WEBHOOK_QUEUE_SIZE: usize = 1000;
 
 pub const NO_RESOURCE: NoResourceType = NoResourceType;
 
@@ -98,11 +139,25 @@ pub const ACTOR_DEF_KRILL: ActorDef = ActorDef::system("krill", "admin");
 pub const ACTOR_DEF_KRILLTA: ActorDef = ActorDef::system("krillta", "admin");
 pub const ACTOR_DEF_ANON: ActorDef = ActorDef::anonymous();
 pub const ACTOR_DEF_ADMIN_TOKEN: ActorDef = ActorDef::system("admin-token", "admin");
+pub const ACTOR_DEF_LOCAL_SOCKET: ActorDef = ActorDef::system("local-socket", "admin");
 pub const ACTOR_DEF_TESTBED: ActorDef = ActorDef::system("testbed", "testbed");
+pub const ACTOR_DEF_ROA_AUTOPILOT: ActorDef = ActorDef::system("roa-autopilot", "admin");
+
+// Commands sent by users are attributed to "user:<id>" in the history.
+pub const ACTOR_USER_PREFIX: &str = "user:";
 
 // If we have more than 50 do not re-issue all ROAs. See issue #772
 pub const UPGRADE_REISSUE_ROAS_CAS_LIMIT: usize = 50;
 
+// Rough number of stored files (commands, events and snapshots) migrated per
+// second, used to estimate the duration of a data migration in upgrade checks.
+pub const UPGRADE_CHECK_FILES_PER_SECOND: u64 = 500;
+
+// The version of the format of the stored data. Stores are stamped with it
+// when Krill starts, and older binaries refuse to use data stamped with a
+// higher version. Increase this whenever a data migration is added.
+pub const KRILL_SCHEMA_VERSION: u64 = 1;
+
 #[cfg(test)]
 pub const ACTOR_DEF_TEST: ActorDef = ActorDef::system("test", "admin");
 
@@ -124,3 +179,7 @@ pub const DEFAULT_SIGNER_NAME: &str = "(test mode) Default KMIP signer";
 pub const DEFAULT_SIGNER_NAME: &str = "(test mode) Default PKCS#11 signer";
 
 pub const OPENSSL_ONE_OFF_SIGNER_NAME: &str = "OpenSSL one-off signer";
+
+/// The staging period for new keys when moving keys to another signer, as
+/// required by RFC 6489.
+pub const SIGNER_MIGRATION_STAGING_HOURS: i64 = 24;
@@ -0,0 +1,270 @@
+//! An append-only log of authentication and authorization events.
+//!
+//! When enabled, every login (attempt), logout, session token refresh,
+//! rejected credential, permission denial and API call is appended to a file
+//! in the data directory as a single line of JSON. The log can be queried
+//! through the `/api/v1/audit` API, or exported as is for processing by other
+//! tools.
+//!
+//! When the file grows too large it is renamed, replacing the file renamed
+//! before, and a new file is started. Queries and exports cover both files.
+//!
+//! Failures to write to the audit log are logged, but do not fail the request
+//! that is being audited.
+
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::commons::{
+    api::{AuditEvent, AuditEventList, Timestamp},
+    error::Error,
+    KrillResult,
+};
+
+const AUDIT_LOG_FILE: &str = "auth_audit.jsonl";
+const AUDIT_LOG_ROTATED_FILE: &str = "auth_audit.jsonl.1";
+
+// The size at which the audit log file is rotated.
+const AUDIT_LOG_MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+//------------ AuditLog ------------------------------------------------------
+
+pub struct AuditLog {
+    path: PathBuf,
+    rotated_path: PathBuf,
+    max_size: u64,
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    pub fn new(data_dir: &Path) -> KrillResult<Self> {
+        let path = data_dir.join(AUDIT_LOG_FILE);
+        let file = Self::open(&path).map_err(|e| {
+            Error::io_error_with_context(format!("Cannot open audit log file '{}'", path.to_string_lossy()), e)
+        })?;
+
+        Ok(AuditLog {
+            path,
+            rotated_path: data_dir.join(AUDIT_LOG_ROTATED_FILE),
+            max_size: AUDIT_LOG_MAX_SIZE,
+            file: Mutex::new(file),
+        })
+    }
+
+    fn open(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// Renames the file, replacing the file renamed before, and continues
+    /// in a new file.
+    fn rotate(&self, file: &mut File) -> io::Result<()> {
+        fs::rename(&self.path, &self.rotated_path)?;
+        *file = Self::open(&self.path)?;
+        Ok(())
+    }
+
+    /// Appends the event to the log.
+    pub fn record(&self, event: AuditEvent) {
+        let mut line = match serde_json::to_vec(&event) {
+            Ok(line) => line,
+            Err(e) => {
+                error!("Cannot serialize audit event: {}", e);
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = file.write_all(&line).and_then(|_| file.flush()) {
+            error!(
+                "Cannot write to audit log file '{}': {}",
+                self.path.to_string_lossy(),
+                e
+            );
+        }
+
+        if file.metadata().map(|meta| meta.len() >= self.max_size).unwrap_or(false) {
+            if let Err(e) = self.rotate(&mut *file) {
+                error!("Cannot rotate audit log file '{}': {}", self.path.to_string_lossy(), e);
+            }
+        }
+    }
+
+    /// Returns the events in the given time range, newest first, skipping
+    /// `offset` events and returning at most `rows` events.
+    pub fn events(
+        &self,
+        rows: usize,
+        offset: usize,
+        after: Option<Timestamp>,
+        before: Option<Timestamp>,
+    ) -> KrillResult<AuditEventList> {
+        // Only keep the newest events that can end up on the page.
+        let keep = offset.saturating_add(rows);
+        let mut events = VecDeque::new();
+        self.read(after, before, |event| {
+            if keep > 0 {
+                if events.len() == keep {
+                    events.pop_front();
+                }
+                events.push_back(event);
+            }
+            Ok(())
+        })?;
+
+        Ok(AuditEventList::new(
+            events.into_iter().rev().skip(offset).take(rows).collect(),
+        ))
+    }
+
+    /// Exports the events in the given time range, oldest first, as JSON
+    /// lines.
+    pub fn export(&self, after: Option<Timestamp>, before: Option<Timestamp>) -> KrillResult<String> {
+        let mut export = String::new();
+        self.read(after, before, |event| {
+            let line = serde_json::to_string(&event).map_err(Error::JsonError)?;
+            export.push_str(&line);
+            export.push('\n');
+            Ok(())
+        })?;
+        Ok(export)
+    }
+
+    /// Passes the events in the given time range to `op`, oldest first.
+    ///
+    /// This reads the files through their own handles, so that events can
+    /// be recorded in the meantime. A line which is still being written is
+    /// skipped.
+    fn read(
+        &self,
+        after: Option<Timestamp>,
+        before: Option<Timestamp>,
+        mut op: impl FnMut(AuditEvent) -> KrillResult<()>,
+    ) -> KrillResult<()> {
+        for path in [&self.rotated_path, &self.path] {
+            let read_err =
+                |e| Error::io_error_with_context(format!("Cannot read audit log file '{}'", path.to_string_lossy()), e);
+
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound && path == &self.rotated_path => continue,
+                Err(e) => return Err(read_err(e)),
+            };
+
+            let mut reader = BufReader::new(file);
+            let mut line = vec![];
+            loop {
+                line.clear();
+                if reader.read_until(b'\n', &mut line).map_err(read_err)? == 0 || line.last() != Some(&b'\n') {
+                    break;
+                }
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+
+                match serde_json::from_slice::<AuditEvent>(&line) {
+                    Ok(event) => {
+                        if after.map(|after| event.time() >= after).unwrap_or(true)
+                            && before.map(|before| event.time() < before).unwrap_or(true)
+                        {
+                            op(event)?;
+                        }
+                    }
+                    Err(e) => warn!("Skipping unparsable line in audit log: {}", e),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{commons::api::AuditEventKind, test};
+
+    #[test]
+    fn record_query_and_export() {
+        let data_dir = test::tmp_dir();
+        let log = AuditLog::new(&data_dir).unwrap();
+
+        log.record(AuditEvent::new(AuditEventKind::Login, "alice"));
+        log.record(AuditEvent::new(AuditEventKind::PermissionDenied, "bob").with_status(403));
+        log.record(AuditEvent::new(AuditEventKind::Logout, "alice"));
+
+        let events = log.events(10, 0, None, None).unwrap();
+        let kinds: Vec<_> = events.events().iter().map(|e| e.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AuditEventKind::Logout,
+                AuditEventKind::PermissionDenied,
+                AuditEventKind::Login
+            ]
+        );
+
+        let page = log.events(1, 1, None, None).unwrap();
+        assert_eq!(page.events().len(), 1);
+        assert_eq!(page.events()[0].actor(), "bob");
+
+        let future = Timestamp::now_plus_hours(1);
+        assert!(log.events(10, 0, Some(future), None).unwrap().events().is_empty());
+        assert_eq!(log.export(None, Some(future)).unwrap().lines().count(), 3);
+
+        // Events survive a restart
+        let log = AuditLog::new(&data_dir).unwrap();
+        log.record(AuditEvent::new(AuditEventKind::ApiCall, "alice"));
+        assert_eq!(log.export(None, None).unwrap().lines().count(), 4);
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+
+    #[test]
+    fn skip_partial_line() {
+        let data_dir = test::tmp_dir();
+        let log = AuditLog::new(&data_dir).unwrap();
+
+        log.record(AuditEvent::new(AuditEventKind::Login, "alice"));
+        let partial = serde_json::to_string(&AuditEvent::new(AuditEventKind::Logout, "alice")).unwrap();
+        let mut file = AuditLog::open(&data_dir.join(AUDIT_LOG_FILE)).unwrap();
+        file.write_all(&partial.as_bytes()[..partial.len() / 2]).unwrap();
+
+        let events = log.events(10, 0, None, None).unwrap();
+        assert_eq!(events.events().len(), 1);
+        assert_eq!(events.events()[0].kind(), AuditEventKind::Login);
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+
+    #[test]
+    fn rotate_when_too_large() {
+        let data_dir = test::tmp_dir();
+        let mut log = AuditLog::new(&data_dir).unwrap();
+        log.max_size = 200;
+
+        for actor in ["a", "b", "c", "d", "e", "f"] {
+            log.record(AuditEvent::new(AuditEventKind::ApiCall, actor));
+        }
+        assert!(data_dir.join(AUDIT_LOG_ROTATED_FILE).exists());
+        assert!(std::fs::metadata(data_dir.join(AUDIT_LOG_FILE)).unwrap().len() < 200);
+
+        // The events in both files are returned, in order, but older files
+        // are dropped.
+        let events = log.events(10, 0, None, None).unwrap();
+        let actors: Vec<_> = events.events().iter().map(|e| e.actor().to_string()).collect();
+        assert!(actors.len() < 6);
+        assert_eq!(actors[0], "f");
+        assert!(actors.windows(2).all(|pair| pair[0] > pair[1]));
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+}
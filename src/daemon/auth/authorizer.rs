@@ -1,26 +1,52 @@
 //! Authorization for the API
 
-use std::{any::Any, collections::HashMap, fmt, str::FromStr, sync::Arc};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
 
 use rpki::ca::idexchange::{InvalidHandle, MyHandle};
 
 use crate::{
     commons::{
         actor::{Actor, ActorDef},
-        api::Token,
+        api::{AuditEvent, AuditEventKind, Token},
         error::Error,
+        eventsourcing::{KeyStoreKey, KeyValueStore},
         KrillResult,
     },
-    constants::{ACTOR_DEF_ANON, NO_RESOURCE},
+    constants::{ACTOR_DEF_ANON, ACTOR_DEF_LOCAL_SOCKET, NO_RESOURCE, STATUS_DIR},
     daemon::{
-        auth::{common::permissions::Permission, policy::AuthPolicy, providers::AdminTokenAuthProvider},
+        auth::{
+            audit::AuditLog, common::permissions::Permission, policy::AuthPolicy, providers::AdminTokenAuthProvider,
+        },
         config::Config,
-        http::HttpResponse,
+        http::{HttpResponse, LocalSocket, RemoteAddr},
     },
 };
 
 #[cfg(feature = "multi-user")]
-use crate::daemon::auth::providers::{ConfigFileAuthProvider, OpenIDConnectAuthProvider};
+use {
+    crate::{
+        commons::util::httpclient,
+        daemon::{
+            auth::{
+                common::session::LoginSessionCache,
+                providers::{
+                    ApiTokenAuthProvider, ClientCertAuthProvider, ConfigFileAuthProvider, JwtAuthProvider,
+                    LdapAuthProvider, OpenIDConnectAuthProvider,
+                },
+            },
+            config::AuthType,
+            http::auth::{AUTH_CALLBACK_ENDPOINT, AUTH_LOGIN_ENDPOINT},
+        },
+    },
+    urlparse::{urlparse, GetQuery},
+};
 
 //------------ Authorizer ----------------------------------------------------
 
@@ -46,6 +72,9 @@ pub enum AuthProvider {
 
     #[cfg(feature = "multi-user")]
     OpenIdConnect(OpenIDConnectAuthProvider),
+
+    #[cfg(feature = "multi-user")]
+    Ldap(LdapAuthProvider),
 }
 
 impl From<AdminTokenAuthProvider> for AuthProvider {
@@ -68,7 +97,30 @@ impl From<OpenIDConnectAuthProvider> for AuthProvider {
     }
 }
 
+#[cfg(feature = "multi-user")]
+impl From<LdapAuthProvider> for AuthProvider {
+    fn from(provider: LdapAuthProvider) -> Self {
+        AuthProvider::Ldap(provider)
+    }
+}
+
 impl AuthProvider {
+    #[cfg(feature = "multi-user")]
+    pub fn auth_type(&self) -> AuthType {
+        match &self {
+            AuthProvider::Token(_) => AuthType::AdminToken,
+            AuthProvider::ConfigFile(_) => AuthType::ConfigFile,
+            AuthProvider::OpenIdConnect(_) => AuthType::OpenIDConnect,
+            AuthProvider::Ldap(_) => AuthType::Ldap,
+        }
+    }
+
+    /// Whether users log in by submitting a username and password.
+    #[cfg(feature = "multi-user")]
+    fn accepts_password(&self) -> bool {
+        matches!(self, AuthProvider::ConfigFile(_) | AuthProvider::Ldap(_))
+    }
+
     pub async fn authenticate(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
         match &self {
             AuthProvider::Token(provider) => provider.authenticate(request),
@@ -76,6 +128,8 @@ impl AuthProvider {
             AuthProvider::ConfigFile(provider) => provider.authenticate(request),
             #[cfg(feature = "multi-user")]
             AuthProvider::OpenIdConnect(provider) => provider.authenticate(request).await,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.authenticate(request),
         }
     }
 
@@ -86,6 +140,8 @@ impl AuthProvider {
             AuthProvider::ConfigFile(provider) => provider.get_login_url(),
             #[cfg(feature = "multi-user")]
             AuthProvider::OpenIdConnect(provider) => provider.get_login_url().await,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.get_login_url(),
         }
     }
 
@@ -96,6 +152,8 @@ impl AuthProvider {
             AuthProvider::ConfigFile(provider) => provider.login(request),
             #[cfg(feature = "multi-user")]
             AuthProvider::OpenIdConnect(provider) => provider.login(request).await,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.login(request).await,
         }
     }
 
@@ -106,6 +164,8 @@ impl AuthProvider {
             AuthProvider::ConfigFile(provider) => provider.logout(request),
             #[cfg(feature = "multi-user")]
             AuthProvider::OpenIdConnect(provider) => provider.logout(request).await,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.logout(request),
         }
     }
 }
@@ -114,9 +174,19 @@ impl AuthProvider {
 /// accessed.
 pub struct Authorizer {
     primary_provider: AuthProvider,
+    #[cfg(feature = "multi-user")]
+    additional_providers: Vec<AuthProvider>,
     legacy_provider: Option<AdminTokenAuthProvider>,
+    #[cfg(feature = "multi-user")]
+    api_token_provider: ApiTokenAuthProvider,
+    #[cfg(feature = "multi-user")]
+    jwt_provider: Option<JwtAuthProvider>,
+    #[cfg(feature = "multi-user")]
+    client_cert_provider: Option<ClientCertAuthProvider>,
     policy: AuthPolicy,
     private_attributes: Vec<String>,
+    audit_log: Option<AuditLog>,
+    failures: AuthFailureMetrics,
 }
 
 impl Authorizer {
@@ -133,6 +203,18 @@ impl Authorizer {
     /// `P` an instance of some other provider, an instance of
     /// [AdminTokenAuthProvider] will also be created. This will be used as a
     /// fallback when Lagosta is configured to use some other [AuthProvider].
+    ///
+    /// # API tokens
+    ///
+    /// Named API tokens, see [ApiTokenAuthProvider], and if configured JWTs
+    /// issued by an external identity provider, see [JwtAuthProvider], are
+    /// accepted regardless of the configured [AuthProvider]. The same goes for
+    /// TLS client certificates, see [ClientCertAuthProvider].
+    ///
+    /// # Audit log
+    ///
+    /// If enabled in the config, authentication and authorization events are
+    /// recorded in an [AuditLog].
     pub fn new(config: Arc<Config>, primary_provider: AuthProvider) -> KrillResult<Self> {
         let value_any = &primary_provider as &dyn Any;
         let is_admin_token_provider = value_any.downcast_ref::<AdminTokenAuthProvider>().is_some();
@@ -150,34 +232,190 @@ impl Authorizer {
             Some(AdminTokenAuthProvider::new(config.clone()))
         };
 
+        #[cfg(feature = "multi-user")]
+        let api_token_provider = ApiTokenAuthProvider::new(&config.storage())?;
+
+        #[cfg(feature = "multi-user")]
+        let jwt_provider = match config.auth_jwt {
+            Some(_) => Some(JwtAuthProvider::new(config.clone())?),
+            None => None,
+        };
+
+        #[cfg(feature = "multi-user")]
+        let client_cert_provider = match config.auth_client_certs {
+            Some(_) => Some(ClientCertAuthProvider::new(config.clone())?),
+            None => None,
+        };
+
         #[cfg(feature = "multi-user")]
         let private_attributes = config.auth_private_attributes.clone();
         #[cfg(not(feature = "multi-user"))]
         let private_attributes = vec!["role".to_string()];
 
+        let audit_log = match config.auth_audit_log {
+            true => Some(AuditLog::new(&config.data_dir)?),
+            false => None,
+        };
+
+        let failures = AuthFailureMetrics::new(&config.data_dir)?;
+
         Ok(Authorizer {
             primary_provider,
+            #[cfg(feature = "multi-user")]
+            additional_providers: vec![],
             legacy_provider,
+            #[cfg(feature = "multi-user")]
+            api_token_provider,
+            #[cfg(feature = "multi-user")]
+            jwt_provider,
+            #[cfg(feature = "multi-user")]
+            client_cert_provider,
             policy: AuthPolicy::new(config)?,
             private_attributes,
+            audit_log,
+            failures,
         })
     }
 
+    /// Enables more providers next to the primary provider, e.g. to let
+    /// end-users login with OpenID Connect while automation uses accounts
+    /// defined in the config file.
+    ///
+    /// Session tokens carry the name of the provider that issued them and are
+    /// always authenticated by that provider. The primary provider remains the
+    /// one that the web UI uses unless a login method is selected explicitly.
+    #[cfg(feature = "multi-user")]
+    pub fn with_additional_providers(mut self, providers: Vec<AuthProvider>) -> Self {
+        self.additional_providers = providers;
+        self
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn providers(&self) -> impl Iterator<Item = &AuthProvider> {
+        std::iter::once(&self.primary_provider).chain(self.additional_providers.iter())
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn provider(&self, auth_type: AuthType) -> Option<&AuthProvider> {
+        self.providers().find(|provider| provider.auth_type() == auth_type)
+    }
+
+    /// Selects the provider for the session token in the request, by the name
+    /// of the issuer that the token is prefixed with. Tokens without such a
+    /// prefix, e.g. issued before prefixes were introduced, are left to the
+    /// primary provider.
+    #[cfg_attr(not(feature = "multi-user"), allow(unused_variables))]
+    fn session_provider(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<&AuthProvider> {
+        #[cfg(feature = "multi-user")]
+        if let Some(token) = httpclient::get_bearer_token(request) {
+            let issuer = LoginSessionCache::issuer(&token).and_then(|issuer| AuthType::from_str(issuer).ok());
+            if let Some(auth_type) = issuer {
+                return self.provider(auth_type).ok_or_else(|| {
+                    Error::ApiInvalidCredentials(format!(
+                        "Session token was issued by provider '{}' which is not enabled",
+                        auth_type
+                    ))
+                });
+            }
+        }
+
+        Ok(&self.primary_provider)
+    }
+
+    /// Returns the provider selected using the "method" query parameter, if
+    /// any.
+    #[cfg(feature = "multi-user")]
+    fn requested_provider(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<&AuthProvider>> {
+        let method = match urlparse(request.uri().to_string())
+            .get_parsed_query()
+            .and_then(|query| query.get_first_from_str("method"))
+        {
+            Some(method) => method,
+            None => return Ok(None),
+        };
+
+        AuthType::from_str(&method)
+            .ok()
+            .and_then(|auth_type| self.provider(auth_type))
+            .map(Some)
+            .ok_or_else(|| Error::ApiLoginError(format!("Login method '{}' is not enabled", method)))
+    }
+
+    /// Lists the enabled login methods, the primary provider first.
+    #[cfg(feature = "multi-user")]
+    pub fn login_methods(&self) -> Vec<LoginMethod> {
+        self.providers()
+            .map(|provider| {
+                let name = provider.auth_type().name();
+                LoginMethod {
+                    name: name.to_string(),
+                    login_url: format!("{}?method={}", AUTH_LOGIN_ENDPOINT, name),
+                }
+            })
+            .collect()
+    }
+
     pub async fn actor_from_request(&self, request: &hyper::Request<hyper::Body>) -> Actor {
         trace!("Determining actor for request {:?}", &request);
 
+        // Requests on the local Unix domain socket are already authenticated
+        // by the file system permissions of the socket.
+        if request.extensions().get::<LocalSocket>().is_some() {
+            return self.actor_from_auth_result(Ok(Some(ACTOR_DEF_LOCAL_SOCKET)));
+        }
+
         // Try the legacy provider first, if any
-        let mut authenticate_res = match &self.legacy_provider {
+        let authenticate_res = match &self.legacy_provider {
             Some(provider) => provider.authenticate(request),
             None => Ok(None),
         };
 
+        // Try the API tokens, JWTs and client certificates next. If the request
+        // carries any of these, then do not let the real provider overrule the
+        // outcome.
+        #[cfg(feature = "multi-user")]
+        let authenticate_res = match authenticate_res {
+            Ok(Some(res)) => Ok(Some(res)),
+            other => match self.authenticate_sessionless(request).await {
+                Ok(None) => other,
+                res => return self.actor_from_auth_result(res),
+            },
+        };
+
         // Try the real provider if we did not already successfully authenticate
-        authenticate_res = match authenticate_res {
+        let authenticate_res = match authenticate_res {
             Ok(Some(res)) => Ok(Some(res)),
-            _ => self.primary_provider.authenticate(request).await,
+            _ => match self.session_provider(request) {
+                Ok(provider) => provider.authenticate(request).await,
+                Err(err) => Err(err),
+            },
         };
 
+        self.actor_from_auth_result(authenticate_res)
+    }
+
+    /// Authenticates requests by credentials that were issued outside of a
+    /// login session, i.e. API tokens, JWTs and client certificates. Bearer
+    /// tokens take precedence over the client certificate.
+    #[cfg(feature = "multi-user")]
+    async fn authenticate_sessionless(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
+        if let Some(actor_def) = self.api_token_provider.authenticate(request)? {
+            return Ok(Some(actor_def));
+        }
+
+        if let Some(provider) = &self.jwt_provider {
+            if let Some(actor_def) = provider.authenticate(request).await? {
+                return Ok(Some(actor_def));
+            }
+        }
+
+        match &self.client_cert_provider {
+            Some(provider) => provider.authenticate(request),
+            None => Ok(None),
+        }
+    }
+
+    fn actor_from_auth_result(&self, authenticate_res: KrillResult<Option<ActorDef>>) -> Actor {
         // Create an actor based on the authentication result
         let actor = match authenticate_res {
             // authentication success
@@ -202,16 +440,82 @@ impl Authorizer {
         Actor::new(def, self.policy.clone())
     }
 
+    #[cfg(feature = "multi-user")]
+    pub fn api_tokens(&self) -> &ApiTokenAuthProvider {
+        &self.api_token_provider
+    }
+
     /// Return the URL at which an end-user should be directed to login with the
-    /// configured provider.
-    pub async fn get_login_url(&self) -> KrillResult<HttpResponse> {
+    /// configured provider, or with the provider selected using the "method"
+    /// query parameter.
+    #[cfg_attr(not(feature = "multi-user"), allow(unused_variables))]
+    pub async fn get_login_url(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<HttpResponse> {
+        #[cfg(feature = "multi-user")]
+        if let Some(provider) = self.requested_provider(request)? {
+            return provider.get_login_url().await;
+        }
+
         self.primary_provider.get_login_url().await
     }
 
+    #[cfg(not(feature = "multi-user"))]
+    async fn provider_login(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<LoggedInUser> {
+        self.primary_provider.login(request).await
+    }
+
+    #[cfg(feature = "multi-user")]
+    async fn provider_login(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<LoggedInUser> {
+        if let Some(provider) = self.requested_provider(request)? {
+            return provider.login(request).await;
+        }
+
+        // The OpenID Connect provider sends the user back to the callback
+        // endpoint, which has to match the URI registered with the provider
+        // and thus cannot select the method.
+        if request.uri().path() == AUTH_CALLBACK_ENDPOINT {
+            if let Some(provider) = self.provider(AuthType::OpenIDConnect) {
+                return provider.login(request).await;
+            }
+        }
+
+        // The login form of the web UI does not select the method either. Try
+        // the primary provider first, then the other providers that take a
+        // username and password.
+        match self.primary_provider.login(request).await {
+            Err(err) if !self.primary_provider.accepts_password() || matches!(err, Error::ApiInvalidCredentials(_)) => {
+                for provider in self.additional_providers.iter().filter(|p| p.accepts_password()) {
+                    if let Ok(user) = provider.login(request).await {
+                        return Ok(user);
+                    }
+                }
+                Err(err)
+            }
+            res => res,
+        }
+    }
+
     /// Submit credentials directly to the configured provider to establish a
     /// login session, if supported by the configured provider.
     pub async fn login(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<LoggedInUser> {
-        let user = self.primary_provider.login(request).await?;
+        let res = self.authorized_login(request).await;
+
+        let event = match &res {
+            Ok(user) => AuditEvent::new(AuditEventKind::Login, user.id.clone()),
+            Err(err) => {
+                AuditEvent::new(AuditEventKind::LoginFailed, ACTOR_DEF_ANON.name.as_str()).with_details(err.to_string())
+            }
+        };
+        self.audit(
+            event
+                .with_source(request.extensions().get::<RemoteAddr>().map(|addr| addr.0.ip()))
+                .with_request(request.method().as_str(), request.uri().path()),
+        );
+
+        res
+    }
+
+    async fn authorized_login(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<LoggedInUser> {
+        let user = self.provider_login(request).await?;
 
         // The user has passed authentication, but may still not be
         // authorized to login as that requires a check against the policy
@@ -251,10 +555,119 @@ impl Authorizer {
     /// Return the URL at which an end-user should be directed to logout with
     /// the configured provider.
     pub async fn logout(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<HttpResponse> {
-        self.primary_provider.logout(request).await
+        self.session_provider(request)
+            .unwrap_or(&self.primary_provider)
+            .logout(request)
+            .await
+    }
+
+    /// Ends the login sessions that the OpenID Connect provider tells us to
+    /// end with the given logout token.
+    #[cfg(feature = "multi-user")]
+    pub async fn backchannel_logout(&self, logout_token: &str) -> KrillResult<()> {
+        match self.provider(AuthType::OpenIDConnect) {
+            Some(AuthProvider::OpenIdConnect(provider)) => provider.backchannel_logout(logout_token).await,
+            _ => Err(Error::ApiLoginError(format!(
+                "Login method '{}' is not enabled",
+                AuthType::OpenIDConnect
+            ))),
+        }
+    }
+}
+
+/// # Audit log
+impl Authorizer {
+    /// Records the event in the audit log if enabled, and counts it for the
+    /// metrics if it is a failed login, authentication or authorization.
+    pub fn audit(&self, event: AuditEvent) {
+        self.failures.count(event.kind());
+
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record(event);
+        }
+    }
+
+    pub fn auth_failures(&self) -> &AuthFailureMetrics {
+        &self.failures
+    }
+
+    pub fn audit_log(&self) -> KrillResult<&AuditLog> {
+        self.audit_log.as_ref().ok_or(Error::ApiAuditLogDisabled)
+    }
+}
+
+//------------ AuthFailureMetrics --------------------------------------------
+
+const AUTH_FAILURES_KEY: &str = "auth-failures.json";
+
+/// Keeps track of the number of failed logins, authentications and
+/// authorizations, for reporting in the metrics. The totals are saved in the
+/// status directory, so that they are not reset when the server restarts.
+#[derive(Debug)]
+pub struct AuthFailureMetrics {
+    store: KeyValueStore,
+    totals: Mutex<AuthFailureTotals>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct AuthFailureTotals {
+    login_failed: u64,
+    auth_failed: u64,
+    permission_denied: u64,
+}
+
+impl AuthFailureMetrics {
+    pub fn new(data_dir: &Path) -> KrillResult<Self> {
+        let store = KeyValueStore::disk(data_dir, STATUS_DIR)?;
+
+        // The totals are not critical, so start counting from zero again if
+        // they cannot be read.
+        let totals = store.get(&Self::key()).ok().flatten().unwrap_or_default();
+
+        Ok(AuthFailureMetrics {
+            store,
+            totals: Mutex::new(totals),
+        })
+    }
+
+    fn key() -> KeyStoreKey {
+        KeyStoreKey::simple(AUTH_FAILURES_KEY.to_string())
+    }
+
+    fn count(&self, kind: AuditEventKind) {
+        let mut totals = self.totals.lock().unwrap();
+        let counter = match kind {
+            AuditEventKind::LoginFailed => &mut totals.login_failed,
+            AuditEventKind::AuthFailed => &mut totals.auth_failed,
+            AuditEventKind::PermissionDenied => &mut totals.permission_denied,
+            _ => return,
+        };
+        *counter += 1;
+
+        if let Err(e) = self.store.store(&Self::key(), &*totals) {
+            warn!("Cannot save the number of authentication failures: {}", e);
+        }
+    }
+
+    /// Returns the number of failures for each kind of failure.
+    pub fn totals(&self) -> Vec<(AuditEventKind, u64)> {
+        let totals = self.totals.lock().unwrap();
+        vec![
+            (AuditEventKind::LoginFailed, totals.login_failed),
+            (AuditEventKind::AuthFailed, totals.auth_failed),
+            (AuditEventKind::PermissionDenied, totals.permission_denied),
+        ]
     }
 }
 
+/// A way for end-users to login, as listed at `/auth/methods`.
+#[cfg(feature = "multi-user")]
+#[derive(Serialize, Debug)]
+pub struct LoginMethod {
+    pub name: String,
+    pub login_url: String,
+}
+
 #[derive(Serialize, Debug)]
 pub struct LoggedInUser {
     pub token: Token,
@@ -270,6 +683,7 @@ pub enum Auth {
         state: String,
         nonce: String,
         csrf_token_hash: String,
+        pkce_verifier: String,
     },
     UsernameAndPassword {
         username: String,
@@ -281,12 +695,19 @@ impl Auth {
     pub fn bearer(token: Token) -> Self {
         Auth::Bearer(token)
     }
-    pub fn authorization_code(code: Token, state: String, nonce: String, csrf_token_hash: String) -> Self {
+    pub fn authorization_code(
+        code: Token,
+        state: String,
+        nonce: String,
+        csrf_token_hash: String,
+        pkce_verifier: String,
+    ) -> Self {
         Auth::AuthorizationCode {
             code,
             state,
             nonce,
             csrf_token_hash,
+            pkce_verifier,
         }
     }
 
@@ -345,3 +766,78 @@ impl oso::PolarClass for Handle {
         oso::Class::builder()
     }
 }
+
+//------------ PublisherResource ---------------------------------------------
+
+/// Publisher handle for Authorization purposes.
+// Like Handle, but for publishers in the Publication Server, so that Polar
+// rules can tell access to a publisher apart from access to a CA with the
+// same name.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub struct PublisherResource(MyHandle);
+
+impl fmt::Display for PublisherResource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<&MyHandle> for PublisherResource {
+    fn from(h: &MyHandle) -> Self {
+        PublisherResource(h.clone())
+    }
+}
+
+impl FromStr for PublisherResource {
+    type Err = InvalidHandle;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MyHandle::from_str(s).map(PublisherResource)
+    }
+}
+
+#[cfg(feature = "multi-user")]
+impl oso::PolarClass for PublisherResource {
+    fn get_polar_class() -> oso::Class {
+        Self::get_polar_class_builder()
+            .set_constructor(|name: String| PublisherResource::from_str(&name).unwrap())
+            .set_equality_check(|left: &PublisherResource, right: &PublisherResource| left == right)
+            .add_attribute_getter("name", |instance| instance.to_string())
+            .build()
+    }
+
+    fn get_polar_class_builder() -> oso::ClassBuilder<Self> {
+        oso::Class::builder()
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test::test_under_tmp;
+
+    #[test]
+    fn count_auth_failures() {
+        test_under_tmp(|d| {
+            let failures = AuthFailureMetrics::new(&d).unwrap();
+            failures.count(AuditEventKind::LoginFailed);
+            failures.count(AuditEventKind::PermissionDenied);
+            failures.count(AuditEventKind::PermissionDenied);
+            failures.count(AuditEventKind::Login);
+
+            // The totals survive a restart.
+            let failures = AuthFailureMetrics::new(&d).unwrap();
+            assert_eq!(
+                failures.totals(),
+                vec![
+                    (AuditEventKind::LoginFailed, 1),
+                    (AuditEventKind::AuthFailed, 0),
+                    (AuditEventKind::PermissionDenied, 2),
+                ]
+            );
+        });
+    }
+}
@@ -67,13 +67,28 @@ iterable_enum! {
         ROUTES_READ,
         ROUTES_UPDATE,
         ROUTES_ANALYSIS,
+        ROUTES_PROPOSE,
+        ROUTES_APPROVE,
         ASPAS_READ,
         ASPAS_UPDATE,
         ASPAS_ANALYSIS,
         BGPSEC_READ,
         BGPSEC_UPDATE,
+        RSC_SIGN,
         RTA_LIST,
         RTA_READ,
         RTA_UPDATE
     }
 }
+
+impl<'de> serde::Deserialize<'de> for Permission {
+    fn deserialize<D>(d: D) -> Result<Permission, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use std::str::FromStr;
+
+        let string = String::deserialize(d)?;
+        Permission::from_str(&string).map_err(serde::de::Error::custom)
+    }
+}
@@ -1,16 +1,45 @@
 use std::{
-    collections::HashMap,
-    sync::RwLock,
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hash, Hasher},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock, Weak,
+    },
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use crate::{
-    commons::{api::Token, error::Error, KrillResult},
+    commons::{
+        api::Token,
+        error::Error,
+        eventsourcing::{KeyStoreKey, KeyValueStore},
+        KrillResult,
+    },
     daemon::auth::common::crypt::{self, CryptState, NonceState},
 };
 
 const MAX_CACHE_SECS: u64 = 30;
 
+// How often the background task sweeps expired items out of the cache.
+const SWEEP_INTERVAL_SECS: u64 = 60;
+
+// The number of independently locked parts that the cache is split into, so
+// that concurrent requests for different sessions rarely wait for each other.
+const CACHE_SHARDS: usize = 16;
+
+// How long to remember at least that login sessions were ended by the
+// identity provider. Sessions that are older than this can no longer be
+// refreshed at the provider anyway. If sessions are handed out which live
+// longer, the ended sessions are remembered for as long as those can live.
+const MAX_REVOCATION_SECS: u64 = 24 * 60 * 60;
+
+// The key under which the ended login sessions are saved.
+const REVOKED_SESSIONS_KEY: &str = "revoked-login-sessions.json";
+
+// Separates the name of the issuing provider from the encrypted session in a
+// session token. This character does not occur in base64.
+const ISSUER_SEPARATOR: char = ':';
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ClientSession {
     pub start_time: u64,
@@ -75,6 +104,87 @@ struct CachedSession {
     pub session: ClientSession,
 }
 
+struct CachedReplacement {
+    pub evict_after: u64,
+    pub token: Token,
+}
+
+/// The login sessions which have the given value for a secret and were
+/// started until `revoked_at`, which were ended by the identity provider.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct RevokedSessions {
+    secret: String,
+    value: String,
+    revoked_at: u64,
+    keep_until: u64,
+}
+
+/// Held while a session token is being refreshed, see
+/// [LoginSessionCache::refresh_lock].
+pub struct RefreshLock<'a> {
+    cache: &'a LoginSessionCache,
+    token: Token,
+    _guard: tokio::sync::OwnedMutexGuard<()>,
+}
+
+impl Drop for RefreshLock<'_> {
+    fn drop(&mut self) {
+        // Forget the lock unless other requests are waiting for it. They hold
+        // a reference, as do the map and our guard.
+        if let Ok(mut refreshing) = self.cache.refreshing.lock() {
+            if refreshing.get(&self.token).map(Arc::strong_count) <= Some(2) {
+                refreshing.remove(&self.token);
+            }
+        }
+    }
+}
+
+/// A map from session token to value that is split into shards which are
+/// locked separately.
+struct ShardedMap<V> {
+    hasher: RandomState,
+    shards: Vec<RwLock<HashMap<Token, V>>>,
+}
+
+impl<V> ShardedMap<V> {
+    fn new(nr_shards: usize) -> Self {
+        ShardedMap {
+            hasher: RandomState::new(),
+            shards: (0..nr_shards.max(1)).map(|_| RwLock::new(HashMap::new())).collect(),
+        }
+    }
+
+    fn shard(&self, token: &Token) -> &RwLock<HashMap<Token, V>> {
+        let mut hasher = self.hasher.build_hasher();
+        token.hash(&mut hasher);
+        &self.shards[(hasher.finish() % self.shards.len() as u64) as usize]
+    }
+
+    /// Returns the number of items, skipping any shard that cannot be read.
+    fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .filter_map(|shard| shard.read().ok())
+            .map(|shard| shard.len())
+            .sum()
+    }
+
+    /// Retains only the items for which the predicate holds in all shards,
+    /// returns the number of items removed.
+    fn retain<F: FnMut(&V) -> bool>(&self, mut predicate: F) -> KrillResult<usize> {
+        let mut removed = 0;
+        for shard in &self.shards {
+            let mut shard = shard
+                .write()
+                .map_err(|err| Error::Custom(format!("Unable to lock session cache: {}", err)))?;
+            let size_before = shard.len();
+            shard.retain(|_, v| predicate(v));
+            removed += size_before - shard.len();
+        }
+        Ok(removed)
+    }
+}
+
 pub type EncryptFn = fn(&[u8], &[u8], &NonceState) -> KrillResult<Vec<u8>>;
 pub type DecryptFn = fn(&[u8], &[u8]) -> KrillResult<Vec<u8>>;
 
@@ -83,8 +193,34 @@ pub type DecryptFn = fn(&[u8], &[u8]) -> KrillResult<Vec<u8>>;
 /// Lagosta UI client) while keeping potentially sensitive data in-memory for as
 /// short as possible. This cache is NOT responsible for enforcing token
 /// expiration, that is handled separately by the AuthProvider.
+///
+/// The cache also briefly remembers which token replaced a refreshed session
+/// token, so that requests still carrying the old token can be given the new
+/// token rather than refreshing the session again.
+///
+/// Session tokens are prefixed with the name of the provider that issued them,
+/// so that when multiple providers are enabled a token can be handed to the
+/// right provider without having to decrypt it first.
+///
+/// As session tokens are held by the client, sessions cannot simply be deleted
+/// when the identity provider ends them. Instead the cache remembers which
+/// sessions were ended, see [LoginSessionCache::revoke], and refuses to decode
+/// them from then on. If the cache is given a store, see
+/// [LoginSessionCache::with_store], the ended sessions are saved there, so
+/// that they stay ended when the server restarts.
+///
+/// The cache is sharded by token so that concurrent requests only contend for
+/// a lock if they happen to carry tokens in the same shard, and the list of
+/// ended sessions is only consulted if there are any. Expired items are swept
+/// from the cache by a background task, see [LoginSessionCache::spawn_sweeper].
 pub struct LoginSessionCache {
-    cache: RwLock<HashMap<Token, CachedSession>>,
+    cache: ShardedMap<CachedSession>,
+    replaced: ShardedMap<CachedReplacement>,
+    revoked: RwLock<Vec<RevokedSessions>>,
+    nr_revoked: AtomicUsize,
+    longest_session_secs: AtomicU64,
+    store: Option<KeyValueStore>,
+    refreshing: Mutex<HashMap<Token, Arc<tokio::sync::Mutex<()>>>>,
     encrypt_fn: EncryptFn,
     decrypt_fn: DecryptFn,
     ttl_secs: u64,
@@ -99,7 +235,13 @@ impl Default for LoginSessionCache {
 impl LoginSessionCache {
     pub fn new() -> Self {
         LoginSessionCache {
-            cache: RwLock::new(HashMap::new()),
+            cache: ShardedMap::new(CACHE_SHARDS),
+            replaced: ShardedMap::new(CACHE_SHARDS),
+            revoked: RwLock::new(vec![]),
+            nr_revoked: AtomicUsize::new(0),
+            longest_session_secs: AtomicU64::new(0),
+            store: None,
+            refreshing: Mutex::new(HashMap::new()),
             encrypt_fn: crypt::encrypt,
             decrypt_fn: crypt::decrypt,
             ttl_secs: MAX_CACHE_SECS,
@@ -109,6 +251,12 @@ impl LoginSessionCache {
     pub fn with_ttl(self, ttl_secs: u64) -> Self {
         LoginSessionCache {
             cache: self.cache,
+            replaced: self.replaced,
+            revoked: self.revoked,
+            nr_revoked: self.nr_revoked,
+            longest_session_secs: self.longest_session_secs,
+            store: self.store,
+            refreshing: self.refreshing,
             encrypt_fn: self.encrypt_fn,
             decrypt_fn: self.decrypt_fn,
             ttl_secs,
@@ -118,6 +266,12 @@ impl LoginSessionCache {
     pub fn with_encrypter(self, encrypt_fn: EncryptFn) -> Self {
         LoginSessionCache {
             cache: self.cache,
+            replaced: self.replaced,
+            revoked: self.revoked,
+            nr_revoked: self.nr_revoked,
+            longest_session_secs: self.longest_session_secs,
+            store: self.store,
+            refreshing: self.refreshing,
             encrypt_fn,
             decrypt_fn: self.decrypt_fn,
             ttl_secs: self.ttl_secs,
@@ -127,12 +281,94 @@ impl LoginSessionCache {
     pub fn with_decrypter(self, decrypt_fn: DecryptFn) -> Self {
         LoginSessionCache {
             cache: self.cache,
+            replaced: self.replaced,
+            revoked: self.revoked,
+            nr_revoked: self.nr_revoked,
+            longest_session_secs: self.longest_session_secs,
+            store: self.store,
+            refreshing: self.refreshing,
             encrypt_fn: self.encrypt_fn,
             decrypt_fn,
             ttl_secs: self.ttl_secs,
         }
     }
 
+    pub fn with_shards(self, nr_shards: usize) -> Self {
+        LoginSessionCache {
+            cache: ShardedMap::new(nr_shards),
+            replaced: ShardedMap::new(nr_shards),
+            revoked: self.revoked,
+            nr_revoked: self.nr_revoked,
+            longest_session_secs: self.longest_session_secs,
+            store: self.store,
+            refreshing: self.refreshing,
+            encrypt_fn: self.encrypt_fn,
+            decrypt_fn: self.decrypt_fn,
+            ttl_secs: self.ttl_secs,
+        }
+    }
+
+    /// Saves the ended login sessions in the given store, and loads those
+    /// which were saved before and should still be remembered.
+    pub fn with_store(self, store: KeyValueStore) -> KrillResult<Self> {
+        let now = Self::time_now_secs_since_epoch()?;
+        let mut revoked: Vec<RevokedSessions> = store.get(&Self::revoked_key())?.unwrap_or_default();
+        revoked.retain(|item| item.keep_until > now);
+
+        Ok(LoginSessionCache {
+            cache: self.cache,
+            replaced: self.replaced,
+            nr_revoked: AtomicUsize::new(revoked.len()),
+            revoked: RwLock::new(revoked),
+            longest_session_secs: self.longest_session_secs,
+            store: Some(store),
+            refreshing: self.refreshing,
+            encrypt_fn: self.encrypt_fn,
+            decrypt_fn: self.decrypt_fn,
+            ttl_secs: self.ttl_secs,
+        })
+    }
+
+    fn revoked_key() -> KeyStoreKey {
+        KeyStoreKey::simple(REVOKED_SESSIONS_KEY.to_string())
+    }
+
+    fn save_revoked(&self, revoked: &[RevokedSessions]) -> KrillResult<()> {
+        if let Some(store) = &self.store {
+            store.store(&Self::revoked_key(), &revoked.to_vec())?;
+        }
+        Ok(())
+    }
+
+    /// Remembers how long the given session can live, so that when sessions
+    /// are ended they are remembered for at least that long. Sessions which
+    /// do not expire can live forever.
+    fn note_lifetime(&self, session: &ClientSession) {
+        let secs = session.expires_in.map(|d| d.as_secs()).unwrap_or(u64::MAX);
+        self.longest_session_secs.fetch_max(secs, Ordering::Relaxed);
+    }
+
+    /// Starts a background task that sweeps the cache every minute, until
+    /// the cache is dropped.
+    pub fn spawn_sweeper(cache: &Arc<LoginSessionCache>) {
+        let cache: Weak<LoginSessionCache> = Arc::downgrade(cache);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(SWEEP_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                match cache.upgrade() {
+                    Some(cache) => {
+                        if let Err(e) = cache.sweep() {
+                            error!("Background sweep of session decryption cache failed: {}", e);
+                        }
+                    }
+                    None => break,
+                }
+            }
+        });
+    }
+
     fn time_now_secs_since_epoch() -> KrillResult<u64> {
         Ok(SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -141,7 +377,7 @@ impl LoginSessionCache {
     }
 
     fn lookup_session(&self, token: &Token) -> Option<ClientSession> {
-        match self.cache.read() {
+        match self.cache.shard(token).read() {
             Ok(readable_cache) => {
                 if let Some(cache_item) = readable_cache.get(token) {
                     return Some(cache_item.session.clone());
@@ -154,7 +390,7 @@ impl LoginSessionCache {
     }
 
     fn cache_session(&self, token: &Token, session: &ClientSession) {
-        match self.cache.write() {
+        match self.cache.shard(token).write() {
             Ok(mut writeable_cache) => match Self::time_now_secs_since_epoch() {
                 Ok(now) => {
                     writeable_cache.insert(
@@ -173,6 +409,7 @@ impl LoginSessionCache {
 
     pub fn encode(
         &self,
+        issuer: &str,
         id: &str,
         attributes: &HashMap<String, String>,
         secrets: HashMap<String, String>,
@@ -186,6 +423,7 @@ impl LoginSessionCache {
             attributes: attributes.clone(),
             secrets,
         };
+        self.note_lifetime(&session);
 
         debug!("Creating token for session: {:?}", &session);
 
@@ -194,13 +432,31 @@ impl LoginSessionCache {
         let unencrypted_bytes = session_json_str.as_bytes();
 
         let encrypted_bytes = (self.encrypt_fn)(&crypt_state.key, unencrypted_bytes, &crypt_state.nonce)?;
-        let token = Token::from(base64::encode(encrypted_bytes));
+        let token = Token::from(format!(
+            "{}{}{}",
+            issuer,
+            ISSUER_SEPARATOR,
+            base64::encode(encrypted_bytes)
+        ));
 
         self.cache_session(&token, &session);
         Ok(token)
     }
 
     pub fn decode(&self, token: Token, key: &CryptState, add_to_cache: bool) -> KrillResult<ClientSession> {
+        let session = self.decode_session(token, key, add_to_cache)?;
+
+        if self.is_revoked(&session) {
+            debug!("Rejecting ended login session for session id {}", &session.id);
+            return Err(Error::ApiAuthSessionExpired(
+                "Login session has been ended by the identity provider".to_string(),
+            ));
+        }
+
+        Ok(session)
+    }
+
+    fn decode_session(&self, token: Token, key: &CryptState, add_to_cache: bool) -> KrillResult<ClientSession> {
         if let Some(session) = self.lookup_session(&token) {
             trace!("Session cache hit for session id {}", &session.id);
             return Ok(session);
@@ -208,7 +464,13 @@ impl LoginSessionCache {
             trace!("Session cache miss, deserializing...");
         }
 
-        let bytes = base64::decode(token.as_ref().as_bytes()).map_err(|err| {
+        // Tokens issued before session tokens were prefixed have no issuer.
+        let encoded = match token.as_ref().split_once(ISSUER_SEPARATOR) {
+            Some((_, encoded)) => encoded,
+            None => token.as_ref(),
+        };
+
+        let bytes = base64::decode(encoded.as_bytes()).map_err(|err| {
             debug!("Invalid bearer token: cannot decode: {}", err);
             Error::ApiInvalidCredentials("Invalid bearer token".to_string())
         })?;
@@ -221,6 +483,7 @@ impl LoginSessionCache {
         })?;
 
         trace!("Session cache miss, deserialized session id {}", &session.id);
+        self.note_lifetime(&session);
 
         if add_to_cache {
             self.cache_session(&token, &session);
@@ -229,8 +492,14 @@ impl LoginSessionCache {
         Ok(session)
     }
 
+    /// Returns the name of the provider that issued the session token, if it
+    /// looks like one.
+    pub fn issuer(token: &Token) -> Option<&str> {
+        token.as_ref().split_once(ISSUER_SEPARATOR).map(|(issuer, _)| issuer)
+    }
+
     pub fn remove(&self, token: &Token) {
-        match self.cache.write() {
+        match self.cache.shard(token).write() {
             Ok(mut writeable_cache) => {
                 writeable_cache.remove(token);
             }
@@ -238,35 +507,130 @@ impl LoginSessionCache {
         }
     }
 
-    pub fn size(&self) -> usize {
-        match self.cache.read() {
-            Ok(readable_cache) => readable_cache.len(),
+    /// Ends all login sessions started until now that have the given value for
+    /// the given secret, e.g. all sessions of a user that was logged out by the
+    /// identity provider. Returns the number of such sessions that were in the
+    /// cache.
+    pub fn revoke(&self, secret: &str, value: &str) -> KrillResult<usize> {
+        let now = Self::time_now_secs_since_epoch()?;
+        let keep_secs = MAX_REVOCATION_SECS.max(self.longest_session_secs.load(Ordering::Relaxed));
+
+        let mut revoked = self
+            .revoked
+            .write()
+            .map_err(|err| Error::Custom(format!("Unable to end login sessions: {}", err)))?;
+        revoked.retain(|item| item.secret != secret || item.value != value);
+        revoked.push(RevokedSessions {
+            secret: secret.to_string(),
+            value: value.to_string(),
+            revoked_at: now,
+            keep_until: now.saturating_add(keep_secs),
+        });
+        self.nr_revoked.store(revoked.len(), Ordering::Release);
+        let saved = self.save_revoked(&revoked);
+        drop(revoked);
+        saved?;
+
+        self.cache
+            .retain(|v| v.session.get_secret(secret).map(|s| s.as_str()) != Some(value))
+    }
+
+    fn is_revoked(&self, session: &ClientSession) -> bool {
+        // Avoid taking the lock in the common case that no sessions were ended.
+        if self.nr_revoked.load(Ordering::Acquire) == 0 {
+            return false;
+        }
+
+        match self.revoked.read() {
+            Ok(readable_revoked) => readable_revoked.iter().any(|item| {
+                session.start_time <= item.revoked_at && session.get_secret(&item.secret) == Some(&item.value)
+            }),
             Err(err) => {
-                warn!("Unable to query session cache size: {}", err);
-                0
+                warn!("Unable to query ended login sessions: {}", err);
+                false
             }
         }
     }
 
-    pub fn sweep(&self) -> KrillResult<()> {
-        let mut cache = self
-            .cache
-            .write()
-            .map_err(|err| Error::Custom(format!("Unable to purge session cache: {}", err)))?;
+    /// Remember that the session token `old` was refreshed and replaced by
+    /// the session token `new`.
+    pub fn replace(&self, old: &Token, new: &Token) {
+        match self.replaced.shard(old).write() {
+            Ok(mut writeable_replaced) => match Self::time_now_secs_since_epoch() {
+                Ok(now) => {
+                    writeable_replaced.insert(
+                        old.clone(),
+                        CachedReplacement {
+                            evict_after: now + self.ttl_secs,
+                            token: new.clone(),
+                        },
+                    );
+                }
+                Err(err) => warn!("Unable to cache refreshed session token: {}", err),
+            },
+            Err(err) => warn!("Unable to cache refreshed session token: {}", err),
+        }
+    }
 
-        let size_before = cache.len();
+    /// Waits until no other request is refreshing the given session token,
+    /// and returns a lock which keeps others waiting until it is dropped.
+    /// After getting the lock, check for a [LoginSessionCache::replacement]
+    /// before refreshing, as the request holding it before may have
+    /// refreshed the token already.
+    pub async fn refresh_lock(&self, token: &Token) -> RefreshLock<'_> {
+        let mutex = {
+            let mut refreshing = self.refreshing.lock().unwrap();
+            refreshing.entry(token.clone()).or_default().clone()
+        };
+        RefreshLock {
+            cache: self,
+            token: token.clone(),
+            _guard: mutex.lock_owned().await,
+        }
+    }
 
+    /// Returns the session token that recently replaced the given token, if
+    /// any.
+    pub fn replacement(&self, old: &Token) -> Option<Token> {
+        match self.replaced.shard(old).read() {
+            Ok(readable_replaced) => readable_replaced.get(old).map(|item| item.token.clone()),
+            Err(err) => {
+                warn!("Unable to query refreshed session tokens: {}", err);
+                None
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn sweep(&self) -> KrillResult<()> {
         // Only retain cache items that have been cached for less than the
         // maximum time allowed.
         let now = Self::time_now_secs_since_epoch()?;
-        cache.retain(|_, v| v.evict_after > now);
+        let removed = self.cache.retain(|v| v.evict_after > now)?;
 
-        let size_after = cache.len();
+        self.replaced.retain(|v| v.evict_after > now)?;
+
+        let mut revoked = self
+            .revoked
+            .write()
+            .map_err(|err| Error::Custom(format!("Unable to purge ended login sessions: {}", err)))?;
+        let nr_revoked = revoked.len();
+        revoked.retain(|item| item.keep_until > now);
+        self.nr_revoked.store(revoked.len(), Ordering::Release);
+        if revoked.len() < nr_revoked {
+            if let Err(e) = self.save_revoked(&revoked) {
+                warn!("Unable to save ended login sessions: {}", e);
+            }
+        }
 
-        if size_after != size_before {
+        if removed > 0 {
             debug!(
-                "Login session cache purge: size before={}, size after={}",
-                size_before, size_after
+                "Login session cache purge: removed {} items, size after={}",
+                removed,
+                self.cache.len()
             );
         }
 
@@ -297,9 +661,10 @@ mod tests {
 
         // Add an item to the cache and verify that the cache now has 1 item
         let item1_token = cache
-            .encode("some id", &HashMap::new(), HashMap::new(), &key, None)
+            .encode("config-file", "some id", &HashMap::new(), HashMap::new(), &key, None)
             .unwrap();
         assert_eq!(cache.size(), 1);
+        assert_eq!(LoginSessionCache::issuer(&item1_token), Some("config-file"));
 
         let item1 = cache.decode(item1_token, &key, true).unwrap();
         assert_eq!(item1.id, "some id");
@@ -317,6 +682,7 @@ mod tests {
         let some_secrets = one_attr_map("some secret key", "some secret val");
         let item2_token = cache
             .encode(
+                "ldap",
                 "other id",
                 &some_attrs,
                 some_secrets,
@@ -346,4 +712,283 @@ mod tests {
         cache.sweep().unwrap();
         assert_eq!(cache.size(), 0);
     }
+
+    #[test]
+    fn replaced_login_session_test() {
+        use super::*;
+
+        let cache = LoginSessionCache::new().with_ttl(1);
+        let old = Token::from("old");
+        let new = Token::from("new");
+
+        assert_eq!(cache.replacement(&old), None);
+        cache.replace(&old, &new);
+        assert_eq!(cache.replacement(&old), Some(new.clone()));
+        assert_eq!(cache.replacement(&new), None);
+
+        // The replacement is forgotten when the cache is swept after the ttl
+        std::thread::sleep(Duration::from_secs(2));
+        cache.sweep().unwrap();
+        assert_eq!(cache.replacement(&old), None);
+    }
+
+    #[test]
+    fn revoked_login_session_test() {
+        use super::*;
+
+        let key_bytes: [u8; 32] = [0; 32];
+        let key: CryptState = CryptState::from_key_bytes(key_bytes).unwrap();
+
+        let cache = LoginSessionCache::new()
+            .with_encrypter(|_, v, _| Ok(v.to_vec()))
+            .with_decrypter(|_, v| Ok(v.to_vec()));
+
+        let encode = |sub: &str| {
+            let mut secrets = HashMap::new();
+            secrets.insert("sub".to_string(), sub.to_string());
+            cache
+                .encode("openid-connect", sub, &HashMap::new(), secrets, &key, None)
+                .unwrap()
+        };
+
+        let alice = encode("alice");
+        let bob = encode("bob");
+        assert_eq!(cache.size(), 2);
+
+        // Only the matching session is evicted and rejected, also when it is
+        // no longer cached.
+        assert_eq!(cache.revoke("sub", "alice").unwrap(), 1);
+        assert_eq!(cache.size(), 1);
+        assert!(matches!(
+            cache.decode(alice, &key, false),
+            Err(Error::ApiAuthSessionExpired(_))
+        ));
+        assert!(cache.decode(bob, &key, false).is_ok());
+
+        // Sessions started after the revocation are fine.
+        std::thread::sleep(Duration::from_secs(1));
+        let alice = encode("alice");
+        assert!(cache.decode(alice, &key, false).is_ok());
+    }
+
+    #[test]
+    fn saved_revoked_login_session_test() {
+        use super::*;
+
+        crate::test::test_under_tmp(|data_dir| {
+            let key: CryptState = CryptState::from_key_bytes([0; 32]).unwrap();
+            let store = || KeyValueStore::disk(&data_dir, crate::constants::STATUS_DIR).unwrap();
+            let new_cache = || {
+                LoginSessionCache::new()
+                    .with_encrypter(|_, v, _| Ok(v.to_vec()))
+                    .with_decrypter(|_, v| Ok(v.to_vec()))
+                    .with_store(store())
+                    .unwrap()
+            };
+
+            let cache = new_cache();
+            let mut secrets = HashMap::new();
+            secrets.insert("sub".to_string(), "alice".to_string());
+            let alice = cache
+                .encode("openid-connect", "alice", &HashMap::new(), secrets, &key, None)
+                .unwrap();
+            cache.revoke("sub", "alice").unwrap();
+
+            // The session stays ended after a restart, and as it does not
+            // expire, so does the revocation.
+            let cache = new_cache();
+            assert!(matches!(
+                cache.decode(alice.clone(), &key, false),
+                Err(Error::ApiAuthSessionExpired(_))
+            ));
+            let saved: Vec<RevokedSessions> = store().get(&LoginSessionCache::revoked_key()).unwrap().unwrap();
+            assert_eq!(saved[0].keep_until, u64::MAX);
+
+            // Revocations which need no longer be remembered are dropped when
+            // they are loaded.
+            let expired = RevokedSessions {
+                keep_until: 1,
+                ..saved[0].clone()
+            };
+            store()
+                .store(&LoginSessionCache::revoked_key(), &vec![expired])
+                .unwrap();
+            let cache = new_cache();
+            assert_eq!(cache.nr_revoked.load(Ordering::Acquire), 0);
+            assert!(cache.decode(alice, &key, false).is_ok());
+        });
+    }
+
+    #[test]
+    fn sharded_login_session_cache_test() {
+        use super::*;
+
+        let key: CryptState = CryptState::from_key_bytes([0; 32]).unwrap();
+        let cache = LoginSessionCache::new()
+            .with_ttl(1)
+            .with_encrypter(|_, v, _| Ok(v.to_vec()))
+            .with_decrypter(|_, v| Ok(v.to_vec()));
+
+        let encode = |id: String| {
+            cache
+                .encode("ldap", &id, &HashMap::new(), HashMap::new(), &key, None)
+                .unwrap()
+        };
+        let tokens: Vec<Token> = (0..100).map(|i| encode(format!("user{}", i))).collect();
+        assert_eq!(cache.size(), 100);
+
+        // Items end up in different shards, but can all be found and removed
+        let used_shards = cache.cache.shards.iter().filter(|s| !s.read().unwrap().is_empty());
+        assert!(used_shards.count() > 1);
+        for (i, token) in tokens.iter().enumerate() {
+            assert_eq!(cache.lookup_session(token).unwrap().id, format!("user{}", i));
+        }
+        cache.remove(&tokens[0]);
+        assert_eq!(cache.size(), 99);
+
+        std::thread::sleep(Duration::from_secs(2));
+        cache.sweep().unwrap();
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[tokio::test]
+    async fn background_sweep_login_session_cache_test() {
+        use super::*;
+
+        let key: CryptState = CryptState::from_key_bytes([0; 32]).unwrap();
+        let cache = Arc::new(
+            LoginSessionCache::new()
+                .with_ttl(0)
+                .with_encrypter(|_, v, _| Ok(v.to_vec())),
+        );
+        cache
+            .encode("ldap", "some id", &HashMap::new(), HashMap::new(), &key, None)
+            .unwrap();
+        assert_eq!(cache.size(), 1);
+
+        // The first sweep happens right away.
+        LoginSessionCache::spawn_sweeper(&cache);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[tokio::test]
+    async fn concurrent_refresh_login_session_test() {
+        use super::*;
+
+        let cache = LoginSessionCache::new();
+        let old = Token::from("old");
+        let nr_refreshes = AtomicUsize::new(0);
+
+        // Does what the OpenID Connect provider does for a session token
+        // which needs to be refreshed.
+        let refresh = || async {
+            let _lock = cache.refresh_lock(&old).await;
+            if let Some(new) = cache.replacement(&old) {
+                return new;
+            }
+            nr_refreshes.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let new = Token::from(format!("new-{}", nr_refreshes.load(Ordering::SeqCst)));
+            cache.replace(&old, &new);
+            new
+        };
+
+        let (a, b) = tokio::join!(refresh(), refresh());
+        assert_eq!(a, b);
+        assert_eq!(nr_refreshes.load(Ordering::SeqCst), 1);
+        assert!(cache.refreshing.lock().unwrap().is_empty());
+    }
+
+    /// Compares the latency of decoding cached session tokens from many
+    /// threads at once, while other sessions are being logged in and the
+    /// cache is being swept, using a single lock and using the sharded cache.
+    /// The difference depends on the number of CPUs, so this only reports the
+    /// results. Run with:
+    ///
+    ///   cargo test --release decode_latency_benchmark -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn decode_latency_benchmark() {
+        use super::*;
+        use std::time::Instant;
+
+        const THREADS: usize = 8;
+        const DECODES: usize = 20_000;
+        const SESSIONS: usize = 10_000;
+
+        fn latencies(nr_shards: usize) -> Vec<Duration> {
+            let key = Arc::new(CryptState::from_key_bytes([0; 32]).unwrap());
+            let cache = Arc::new(
+                LoginSessionCache::new()
+                    .with_shards(nr_shards)
+                    .with_encrypter(|_, v, _| Ok(v.to_vec()))
+                    .with_decrypter(|_, v| Ok(v.to_vec())),
+            );
+            for i in 0..SESSIONS {
+                let id = format!("user-{}", i);
+                cache
+                    .encode("ldap", &id, &HashMap::new(), HashMap::new(), &key, None)
+                    .unwrap();
+            }
+
+            let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+            let sweeper = {
+                let cache = cache.clone();
+                let done = done.clone();
+                std::thread::spawn(move || {
+                    while !done.load(Ordering::Relaxed) {
+                        cache.sweep().unwrap();
+                    }
+                })
+            };
+
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let cache = cache.clone();
+                    let key = key.clone();
+                    std::thread::spawn(move || {
+                        let encode = |i: usize| {
+                            let id = format!("user-{}-{}", t, i);
+                            cache
+                                .encode("ldap", &id, &HashMap::new(), HashMap::new(), &key, None)
+                                .unwrap()
+                        };
+                        let token = encode(0);
+
+                        let mut latencies = Vec::with_capacity(DECODES);
+                        for i in 0..DECODES {
+                            // Every so often another user logs in.
+                            if i % 10 == 0 {
+                                encode(i);
+                            }
+                            let start = Instant::now();
+                            cache.decode(token.clone(), &key, true).unwrap();
+                            latencies.push(start.elapsed());
+                        }
+                        latencies
+                    })
+                })
+                .collect();
+
+            let mut latencies: Vec<Duration> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+            done.store(true, Ordering::Relaxed);
+            sweeper.join().unwrap();
+
+            latencies.sort();
+            latencies
+        }
+
+        for nr_shards in [1, CACHE_SHARDS] {
+            let latencies = latencies(nr_shards);
+            let percentile = |p: usize| latencies[latencies.len() * p / 100];
+            println!(
+                "decode latency with {} shard(s): p50 {:?}, p99 {:?}, max {:?}",
+                nr_shards,
+                percentile(50),
+                percentile(99),
+                latencies.last().unwrap()
+            );
+        }
+    }
 }
@@ -1,10 +1,15 @@
+pub mod audit;
 pub mod authorizer;
 pub mod providers;
 
 pub mod common;
 
+#[cfg(feature = "multi-user")]
+pub mod orgs;
 #[cfg(feature = "multi-user")]
 pub mod policy;
+#[cfg(feature = "multi-user")]
+pub mod roles;
 #[cfg(not(feature = "multi-user"))]
 pub mod policy {
     use std::sync::Arc;
@@ -20,4 +25,6 @@ pub mod policy {
     }
 }
 
-pub use authorizer::{Auth, AuthProvider, Authorizer, Handle, LoggedInUser};
+#[cfg(feature = "multi-user")]
+pub use authorizer::LoginMethod;
+pub use authorizer::{Auth, AuthFailureMetrics, AuthProvider, Authorizer, Handle, LoggedInUser, PublisherResource};
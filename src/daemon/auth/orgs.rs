@@ -0,0 +1,275 @@
+//! Operator defined organizations that scope which CAs and publishers users
+//! can see and manage.
+//!
+//! Organizations can be defined in the `[auth_orgs]` section of the Krill
+//! config file. An organization owns the CAs and publishers whose names match
+//! one of its patterns, where a pattern is either an exact name or a prefix
+//! followed by `*`. E.g.:
+//!
+//! ```toml
+//! [auth_orgs.customer-a]
+//! cas = ["customer-a-*"]
+//! publishers = ["customer-a-*", "legacy-publisher"]
+//! ```
+//!
+//! Users are made a member of an organization by setting their "org"
+//! attribute in the `[auth_users]` section or by mapping an OpenID Connect
+//! claim to the "org" attribute. Members of an organization can only see and
+//! act on the CAs and publishers owned by it, whatever their role allows, and
+//! cannot use the server wide admin functions, such as backups or creating
+//! API tokens which are not scoped to a CA. API tokens created by a member
+//! are scoped to the same organization.
+//!
+//! Once organizations are configured, users without an "org" attribute are
+//! denied everything but logging in. Server wide administration is then done
+//! using the admin token.
+//!
+//! The [`OrgTable`] is registered as the Polar constant `ConfigOrgs` so that
+//! the rules in `defaults/orgs.polar` can query it.
+
+use std::collections::HashMap;
+
+pub type ConfigAuthOrgs = HashMap<String, ConfigAuthOrg>;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ConfigAuthOrg {
+    /// Patterns for the names of the CAs owned by the organization.
+    #[serde(default)]
+    pub cas: Vec<String>,
+
+    /// Patterns for the names of the publishers owned by the organization.
+    #[serde(default)]
+    pub publishers: Vec<String>,
+}
+
+impl ConfigAuthOrg {
+    pub fn owns_ca(&self, ca: &str) -> bool {
+        Self::matches_any(&self.cas, ca)
+    }
+
+    pub fn owns_publisher(&self, publisher: &str) -> bool {
+        Self::matches_any(&self.publishers, publisher)
+    }
+
+    fn matches_any(patterns: &[String], name: &str) -> bool {
+        patterns.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => pattern == name,
+        })
+    }
+}
+
+//------------ OrgTable ------------------------------------------------------
+
+/// Lookup table of the organizations defined in the config file, for use by
+/// the Polar policy. Unknown organizations own nothing.
+#[derive(Clone, Debug, Default)]
+pub struct OrgTable {
+    orgs: ConfigAuthOrgs,
+}
+
+impl OrgTable {
+    pub fn new(orgs: ConfigAuthOrgs) -> Self {
+        OrgTable { orgs }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        !self.orgs.is_empty()
+    }
+
+    pub fn owns_ca(&self, org: &str, ca: &str) -> bool {
+        self.orgs.get(org).map(|o| o.owns_ca(ca)).unwrap_or(false)
+    }
+
+    pub fn owns_publisher(&self, org: &str, publisher: &str) -> bool {
+        self.orgs.get(org).map(|o| o.owns_publisher(publisher)).unwrap_or(false)
+    }
+}
+
+impl oso::PolarClass for OrgTable {
+    fn get_polar_class() -> oso::Class {
+        Self::get_polar_class_builder()
+            .add_method("is_configured", OrgTable::is_configured)
+            .add_method("owns_ca", |table: &OrgTable, org: String, ca: String| {
+                table.owns_ca(&org, &ca)
+            })
+            .add_method("owns_publisher", |table: &OrgTable, org: String, publisher: String| {
+                table.owns_publisher(&org, &publisher)
+            })
+            .build()
+    }
+
+    fn get_polar_class_builder() -> oso::ClassBuilder<Self> {
+        oso::Class::builder()
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use super::*;
+
+    use crate::{
+        commons::actor::{Actor, ActorDef},
+        constants::{ACTOR_DEF_ADMIN_TOKEN, NO_RESOURCE},
+        daemon::{
+            auth::{common::permissions::Permission, policy::AuthPolicy, Handle, PublisherResource},
+            config::Config,
+        },
+        test,
+    };
+
+    fn customer_a() -> ConfigAuthOrg {
+        toml::from_str(
+            r#"
+            cas = ["customer-a-*"]
+            publishers = ["customer-a-*", "legacy-publisher"]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn org_owns_matching_names() {
+        let org = customer_a();
+
+        assert!(org.owns_ca("customer-a-ca"));
+        assert!(!org.owns_ca("customer-b-ca"));
+        assert!(!org.owns_ca("legacy-publisher"));
+
+        assert!(org.owns_publisher("customer-a-ca"));
+        assert!(org.owns_publisher("legacy-publisher"));
+        assert!(!org.owns_publisher("legacy-publisher-2"));
+    }
+
+    fn org_policy(data_dir: &std::path::Path) -> AuthPolicy {
+        let mut config = Config::test(data_dir, false, false, false, false);
+        config.auth_orgs.insert("customer-a".to_string(), customer_a());
+        config.auth_orgs.insert(
+            "customer-b".to_string(),
+            toml::from_str(r#"cas = ["customer-b-*"]"#).unwrap(),
+        );
+        AuthPolicy::new(Arc::new(config)).unwrap()
+    }
+
+    fn user(policy: &AuthPolicy, role: &str, org: Option<&str>) -> Actor {
+        let mut attributes = HashMap::new();
+        attributes.insert("role".to_string(), role.to_string());
+        if let Some(org) = org {
+            attributes.insert("org".to_string(), org.to_string());
+        }
+        Actor::new(ActorDef::user("joe".to_string(), attributes, None), policy.clone())
+    }
+
+    #[test]
+    fn policy_scopes_users_to_their_org() {
+        let data_dir = test::tmp_dir();
+        let policy = org_policy(&data_dir);
+
+        let actor = |org: Option<&str>| user(&policy, "readwrite", org);
+        let ca = |name: &str| Handle::from_str(name).unwrap();
+        let publisher = |name: &str| PublisherResource::from_str(name).unwrap();
+
+        let member = actor(Some("customer-a"));
+        assert!(member.is_allowed(Permission::CA_LIST, NO_RESOURCE).unwrap());
+        assert!(member.is_allowed(Permission::CA_READ, ca("customer-a-ca")).unwrap());
+        assert!(member.is_allowed(Permission::CA_UPDATE, ca("customer-a-ca")).unwrap());
+        assert!(!member.is_allowed(Permission::CA_READ, ca("customer-b-ca")).unwrap());
+        assert!(member
+            .is_allowed(Permission::PUB_READ, publisher("legacy-publisher"))
+            .unwrap());
+        assert!(!member
+            .is_allowed(Permission::PUB_READ, publisher("customer-b-ca"))
+            .unwrap());
+
+        let unknown_org = actor(Some("customer-c"));
+        assert!(!unknown_org
+            .is_allowed(Permission::CA_READ, ca("customer-a-ca"))
+            .unwrap());
+
+        // Once organizations are configured, users without one are denied.
+        let no_org = actor(None);
+        assert!(no_org.is_allowed(Permission::LOGIN, NO_RESOURCE).unwrap());
+        assert!(!no_org.is_allowed(Permission::CA_LIST, NO_RESOURCE).unwrap());
+        assert!(!no_org.is_allowed(Permission::CA_READ, ca("customer-b-ca")).unwrap());
+        assert!(!no_org
+            .is_allowed(Permission::PUB_READ, publisher("customer-b-ca"))
+            .unwrap());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+
+    #[test]
+    fn org_admins_cannot_cross_orgs_or_administer_the_server() {
+        let data_dir = test::tmp_dir();
+        let policy = org_policy(&data_dir);
+        let ca = |name: &str| Handle::from_str(name).unwrap();
+
+        let admin_a = user(&policy, "admin", Some("customer-a"));
+        let admin_b = user(&policy, "admin", Some("customer-b"));
+
+        for permission in [
+            Permission::CA_READ,
+            Permission::CA_UPDATE,
+            Permission::CA_ADMIN,
+            Permission::CA_DELETE,
+        ] {
+            assert!(admin_a.is_allowed(permission.clone(), ca("customer-a-ca")).unwrap());
+            assert!(!admin_a.is_allowed(permission.clone(), ca("customer-b-ca")).unwrap());
+            assert!(admin_b.is_allowed(permission.clone(), ca("customer-b-ca")).unwrap());
+            assert!(!admin_b.is_allowed(permission, ca("customer-a-ca")).unwrap());
+        }
+
+        // Server wide admin functions, e.g. creating unscoped API tokens,
+        // backups, audit and HA, need CA_ADMIN or PUB_ADMIN without a
+        // resource.
+        assert!(!admin_a.is_allowed(Permission::CA_ADMIN, NO_RESOURCE).unwrap());
+        assert!(!admin_a.is_allowed(Permission::PUB_ADMIN, NO_RESOURCE).unwrap());
+        assert!(admin_a.is_allowed(Permission::CA_LIST, NO_RESOURCE).unwrap());
+
+        // An admin token minted by a member carries the org, so it has the
+        // same restrictions.
+        let mut attributes = HashMap::new();
+        attributes.insert("role".to_string(), "admin".to_string());
+        attributes.insert("org".to_string(), "customer-a".to_string());
+        let token = Actor::new(
+            ActorDef::user("api-token:automation".to_string(), attributes, None),
+            policy.clone(),
+        );
+        assert!(!token.is_allowed(Permission::CA_ADMIN, NO_RESOURCE).unwrap());
+        assert!(!token.is_allowed(Permission::CA_READ, ca("customer-b-ca")).unwrap());
+
+        // The admin token is not a user, and is not restricted.
+        let admin_token = Actor::new(ACTOR_DEF_ADMIN_TOKEN, policy.clone());
+        assert!(admin_token.is_allowed(Permission::CA_ADMIN, NO_RESOURCE).unwrap());
+        assert!(admin_token
+            .is_allowed(Permission::CA_READ, ca("customer-b-ca"))
+            .unwrap());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+
+    #[test]
+    fn users_without_org_unrestricted_without_orgs() {
+        let data_dir = test::tmp_dir();
+        let config = Config::test(&data_dir, false, false, false, false);
+        let policy = AuthPolicy::new(Arc::new(config)).unwrap();
+
+        let no_org = user(&policy, "admin", None);
+        assert!(no_org.is_allowed(Permission::CA_ADMIN, NO_RESOURCE).unwrap());
+        assert!(no_org
+            .is_allowed(Permission::CA_READ, Handle::from_str("customer-b-ca").unwrap())
+            .unwrap());
+        assert!(no_org
+            .is_allowed(
+                Permission::PUB_READ,
+                PublisherResource::from_str("customer-b-ca").unwrap()
+            )
+            .unwrap());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+}
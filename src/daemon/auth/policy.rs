@@ -12,7 +12,9 @@ use crate::{
     daemon::{
         auth::{
             common::{permissions::Permission, NoResourceType},
-            Handle,
+            orgs::OrgTable,
+            roles::RoleTable,
+            Handle, PublisherResource,
         },
         config::Config,
     },
@@ -36,6 +38,7 @@ impl AuthPolicy {
         let mut oso = Oso::new();
         oso.register_class(Actor::get_polar_class()).unwrap();
         oso.register_class(Handle::get_polar_class()).unwrap();
+        oso.register_class(PublisherResource::get_polar_class()).unwrap();
 
         // Register both the Permission enum as a Polar class and its variants as Polar constants. The former is useful
         // for writing Polar rules that only match on actual Krill Permissions, not on arbitrary strings, e.g.
@@ -49,12 +52,30 @@ impl AuthPolicy {
             oso.register_constant(permission, &name).unwrap();
         }
 
+        // Register the roles defined in the config file so that the rules in
+        // config-roles.polar can look them up.
+        oso.register_class(RoleTable::get_polar_class()).unwrap();
+        oso.register_constant(RoleTable::new(config.auth_roles.clone()), "ConfigRoles")
+            .unwrap();
+
+        // Register the organizations defined in the config file so that the
+        // rules in orgs.polar can look them up.
+        oso.register_class(OrgTable::get_polar_class()).unwrap();
+        oso.register_constant(OrgTable::new(config.auth_orgs.clone()), "ConfigOrgs")
+            .unwrap();
+
         // Load built-in Polar authorization policy rules from embedded strings
         Self::load_internal_policy(&mut oso, include_bytes!("../../../defaults/roles.polar"), "roles")?;
         Self::load_internal_policy(&mut oso, include_bytes!("../../../defaults/rules.polar"), "rules")?;
         Self::load_internal_policy(&mut oso, include_bytes!("../../../defaults/aliases.polar"), "aliases")?;
         Self::load_internal_policy(&mut oso, include_bytes!("../../../defaults/rbac.polar"), "rbac")?;
         Self::load_internal_policy(&mut oso, include_bytes!("../../../defaults/abac.polar"), "abac")?;
+        Self::load_internal_policy(
+            &mut oso,
+            include_bytes!("../../../defaults/config-roles.polar"),
+            "config-roles",
+        )?;
+        Self::load_internal_policy(&mut oso, include_bytes!("../../../defaults/orgs.polar"), "orgs")?;
 
         // Load additional policy rules from files optionally provided by the customer
         Self::load_user_policy(config, &mut oso)?;
@@ -185,6 +206,7 @@ impl PolarClass for Actor {
                 names.contains(&name)
             })
             .add_method("attr", Actor::attribute)
+            .add_method("is_user", Actor::is_user)
             .add_method("attrs", Actor::attributes)
             .build()
     }
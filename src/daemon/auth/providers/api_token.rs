@@ -0,0 +1,489 @@
+//! Named API tokens with a limited scope and an optional expiry time.
+//!
+//! Unlike the single admin token, API tokens are created (and revoked) at
+//! runtime through the `/api/v1/tokens` API. Each token is given a scope which
+//! maps to a role in the authorization policy, and can optionally be
+//! restricted to specific CAs. This allows handing out tokens to automation
+//! that can e.g. only manage the ROAs of one CA.
+//!
+//! Tokens with the ROAs scope for a single CA can also be managed through the
+//! `/api/v1/cas/<ca>/delegations` API, so that an admin can give the holder of
+//! the resources of a CA self-service ROA management.
+//!
+//! The token itself is only revealed once, when it is created. Krill only
+//! keeps a SHA-256 hash of the token.
+//!
+//! The tokens are kept in the configured storage, so that in high
+//! availability mode they are shared with the standby instance. The tokens
+//! are then loaded again periodically, so that tokens created or revoked by
+//! the other instance are picked up.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use rpki::ca::idexchange::CaHandle;
+
+use crate::{
+    commons::{
+        actor::ActorDef,
+        api::{ApiTokenCreated, ApiTokenInfo, ApiTokenList, ApiTokenRequest, ApiTokenScope, Timestamp, Token},
+        error::Error,
+        eventsourcing::{KeyStoreKey, KeyValueStorage, KeyValueStore},
+        util::{httpclient, sha256},
+        KrillResult,
+    },
+    constants::API_TOKENS_DIR,
+};
+
+const JSON_SUFFIX: &str = ".json";
+
+// How often the tokens are loaded again from shared storage.
+const SHARED_TOKENS_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+// All API tokens start with this prefix so that they can be recognised
+// without having to look them up, and so that they are easy to spot when
+// accidentally leaked, e.g. in a git repository.
+const TOKEN_PREFIX: &str = "krillapi_";
+const TOKEN_RANDOM_BYTES: usize = 32;
+
+//------------ StoredApiToken ------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct StoredApiToken {
+    info: ApiTokenInfo,
+
+    /// Hex encoded SHA-256 hash of the token.
+    hash: String,
+}
+
+//------------ ApiTokenAuthProvider ------------------------------------------
+
+pub struct ApiTokenAuthProvider {
+    store: KeyValueStore,
+    cache: RwLock<HashMap<String, StoredApiToken>>,
+
+    // The interval for loading the tokens again, if they are kept in storage
+    // which is shared with another instance, and when they were last loaded.
+    refresh: Option<Duration>,
+    loaded: Mutex<Instant>,
+}
+
+impl ApiTokenAuthProvider {
+    pub fn new(storage: &KeyValueStorage) -> KrillResult<Self> {
+        let refresh = match storage {
+            KeyValueStorage::Disk(_) => None,
+            KeyValueStorage::Postgres(_) => Some(SHARED_TOKENS_REFRESH_INTERVAL),
+        };
+        Self::with_refresh(storage, refresh)
+    }
+
+    fn with_refresh(storage: &KeyValueStorage, refresh: Option<Duration>) -> KrillResult<Self> {
+        let store = KeyValueStore::create(storage, API_TOKENS_DIR)?;
+        let tokens = Self::load(&store)?;
+
+        Ok(ApiTokenAuthProvider {
+            store,
+            cache: RwLock::new(tokens),
+            refresh,
+            loaded: Mutex::new(Instant::now()),
+        })
+    }
+
+    fn load(store: &KeyValueStore) -> KrillResult<HashMap<String, StoredApiToken>> {
+        let mut tokens = HashMap::new();
+        for key in store.keys(None, JSON_SUFFIX)? {
+            if let Some(token) = store.get::<StoredApiToken>(&key)? {
+                tokens.insert(token.info.name().to_string(), token);
+            }
+        }
+        Ok(tokens)
+    }
+
+    /// Loads the tokens again if they are kept in shared storage, and the
+    /// refresh interval has passed. If they cannot be loaded, the tokens
+    /// which were loaded before are used.
+    fn refresh_if_due(&self) {
+        let refresh = match self.refresh {
+            Some(refresh) => refresh,
+            None => return,
+        };
+
+        {
+            let mut loaded = self.loaded.lock().unwrap();
+            if loaded.elapsed() < refresh {
+                return;
+            }
+            *loaded = Instant::now();
+        }
+
+        match Self::load(&self.store) {
+            Ok(tokens) => *self.cache.write().unwrap() = tokens,
+            Err(e) => warn!("Could not load the API tokens from storage: {}", e),
+        }
+    }
+
+    fn key(name: &str) -> KeyStoreKey {
+        KeyStoreKey::simple(format!("{}{}", name, JSON_SUFFIX))
+    }
+
+    fn hash(token: &str) -> String {
+        hex::encode(sha256(token.as_bytes()))
+    }
+}
+
+/// # Token management
+impl ApiTokenAuthProvider {
+    pub fn list(&self) -> ApiTokenList {
+        self.refresh_if_due();
+        let mut tokens: Vec<ApiTokenInfo> = self
+            .cache
+            .read()
+            .unwrap()
+            .values()
+            .map(|token| token.info.clone())
+            .collect();
+        tokens.sort_by(|a, b| a.name().cmp(b.name()));
+
+        ApiTokenList::new(tokens)
+    }
+
+    /// Lists the tokens which delegate the management of the ROAs of the CA.
+    pub fn list_delegated_for(&self, ca: &CaHandle) -> ApiTokenList {
+        self.refresh_if_due();
+        let mut tokens: Vec<ApiTokenInfo> = self
+            .cache
+            .read()
+            .unwrap()
+            .values()
+            .filter(|token| token.info.is_delegated_for(ca))
+            .map(|token| token.info.clone())
+            .collect();
+        tokens.sort_by(|a, b| a.name().cmp(b.name()));
+
+        ApiTokenList::new(tokens)
+    }
+
+    /// Creates a new API token. The returned token is not stored and cannot be
+    /// retrieved again later. A token created by a member of an organization
+    /// is scoped to that organization, so that it cannot be used to escape
+    /// the organization.
+    pub fn create(
+        &self,
+        request: ApiTokenRequest,
+        created_by: &str,
+        org: Option<String>,
+    ) -> KrillResult<ApiTokenCreated> {
+        let (name, scope, cas, expires) = request.unpack();
+
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            || name.starts_with('.')
+        {
+            return Err(Error::ApiTokenInvalid(format!(
+                "name '{}' must consist of letters, digits, '-', '_' and '.' only",
+                name
+            )));
+        }
+
+        if !cas.is_empty() && matches!(scope, ApiTokenScope::Admin | ApiTokenScope::Publish) {
+            return Err(Error::ApiTokenInvalid(format!(
+                "tokens with scope '{}' cannot be restricted to CAs",
+                scope
+            )));
+        }
+
+        if let Some(expires) = expires {
+            if expires <= Timestamp::now() {
+                return Err(Error::ApiTokenInvalid("expiry time is in the past".to_string()));
+            }
+        }
+
+        self.refresh_if_due();
+        let mut cache = self.cache.write().unwrap();
+        if cache.contains_key(&name) {
+            return Err(Error::ApiTokenDuplicate(name));
+        }
+
+        let mut random = [0u8; TOKEN_RANDOM_BYTES];
+        openssl::rand::rand_bytes(&mut random)
+            .map_err(|err| Error::Custom(format!("Unable to generate API token: {}", err)))?;
+        let token = Token::from(format!("{}{}", TOKEN_PREFIX, hex::encode(random)));
+
+        let info = ApiTokenInfo::new(name, scope, cas, Timestamp::now(), created_by.to_string(), expires, org);
+        let stored = StoredApiToken {
+            info: info.clone(),
+            hash: Self::hash(token.as_ref()),
+        };
+
+        self.store.store(&Self::key(info.name()), &stored)?;
+        cache.insert(info.name().to_string(), stored);
+
+        info!(
+            "API token '{}' with scope '{}' created by {}",
+            info.name(),
+            scope,
+            created_by
+        );
+
+        Ok(ApiTokenCreated::new(info, token))
+    }
+
+    pub fn revoke(&self, name: &str) -> KrillResult<()> {
+        self.revoke_if(name, |_| true)
+    }
+
+    /// Revokes a token, but only if it delegates the management of the ROAs
+    /// of the CA. This way access to one CA cannot be used to revoke tokens
+    /// given out for other purposes.
+    pub fn revoke_delegated_for(&self, name: &str, ca: &CaHandle) -> KrillResult<()> {
+        self.revoke_if(name, |token| token.info.is_delegated_for(ca))
+    }
+
+    /// Revokes a token if it exists and the predicate holds for it. The
+    /// token is only removed from the cache once it has been removed from
+    /// the store, so that a failure does not leave it usable after a
+    /// restart while it appears to be revoked.
+    fn revoke_if(&self, name: &str, predicate: impl Fn(&StoredApiToken) -> bool) -> KrillResult<()> {
+        self.refresh_if_due();
+        let mut cache = self.cache.write().unwrap();
+        if !cache.get(name).map(predicate).unwrap_or(false) {
+            return Err(Error::ApiTokenUnknown(name.to_string()));
+        }
+        self.store.drop_key(&Self::key(name))?;
+        cache.remove(name);
+
+        info!("API token '{}' revoked", name);
+        Ok(())
+    }
+}
+
+/// # Authentication
+impl ApiTokenAuthProvider {
+    /// Returns `Ok(None)` if the request does not carry an API token at all,
+    /// so that other providers can have a go at it.
+    pub fn authenticate(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
+        match httpclient::get_bearer_token(request) {
+            Some(token) if token.as_ref().starts_with(TOKEN_PREFIX) => self.authenticate_token(&token).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn authenticate_token(&self, token: &Token) -> KrillResult<ActorDef> {
+        // Comparing hashes rather than the tokens themselves means that timing
+        // differences reveal nothing useful about the token.
+        let hash = Self::hash(token.as_ref());
+
+        self.refresh_if_due();
+        let cache = self.cache.read().unwrap();
+        let stored = cache
+            .values()
+            .find(|stored| stored.hash == hash)
+            .ok_or_else(|| Error::ApiInvalidCredentials("Invalid API token".to_string()))?;
+
+        let info = &stored.info;
+        if info.is_expired() {
+            return Err(Error::ApiInvalidCredentials(format!(
+                "API token '{}' has expired",
+                info.name()
+            )));
+        }
+
+        let mut attributes = HashMap::new();
+        attributes.insert("role".to_string(), info.scope().role().to_string());
+        if !info.cas().is_empty() {
+            let cas: Vec<_> = info.cas().iter().map(|ca| ca.to_string()).collect();
+            attributes.insert("inc_cas".to_string(), cas.join(","));
+        }
+        if let Some(org) = info.org() {
+            attributes.insert("org".to_string(), org.clone());
+        }
+
+        Ok(ActorDef::user(format!("api-token:{}", info.name()), attributes, None))
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    use crate::{commons::api::CaDelegationRequest, test};
+
+    fn request_with_token(token: &Token) -> hyper::Request<hyper::Body> {
+        hyper::Request::builder()
+            .header(hyper::header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(hyper::Body::empty())
+            .unwrap()
+    }
+
+    fn roas_request(name: &str, expires: Option<Timestamp>) -> ApiTokenRequest {
+        ApiTokenRequest::new(
+            name.to_string(),
+            ApiTokenScope::Roas,
+            vec![CaHandle::from_str("ca1").unwrap()],
+            expires,
+        )
+    }
+
+    #[test]
+    fn create_authenticate_and_revoke() {
+        let data_dir = test::tmp_dir();
+        let provider = ApiTokenAuthProvider::new(&KeyValueStorage::Disk(data_dir.clone())).unwrap();
+
+        let created = provider.create(roas_request("ci", None), "admin", None).unwrap();
+        assert!(created.token().as_ref().starts_with(TOKEN_PREFIX));
+        assert!(matches!(
+            provider.create(roas_request("ci", None), "admin", None),
+            Err(Error::ApiTokenDuplicate(_))
+        ));
+
+        let actor = provider
+            .authenticate(&request_with_token(created.token()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(actor.name.as_str(), "api-token:ci");
+        let attributes = actor.attributes.as_map();
+        assert_eq!(attributes.get("role").unwrap(), "roas-only");
+        assert_eq!(attributes.get("inc_cas").unwrap(), "ca1");
+
+        // Tokens without our prefix are left for other providers.
+        let other = Token::from("secret");
+        assert!(provider.authenticate(&request_with_token(&other)).unwrap().is_none());
+
+        // Tokens survive a restart.
+        let provider = ApiTokenAuthProvider::new(&KeyValueStorage::Disk(data_dir.clone())).unwrap();
+        assert_eq!(provider.list().tokens().len(), 1);
+
+        provider.revoke("ci").unwrap();
+        assert!(provider.authenticate(&request_with_token(created.token())).is_err());
+        assert!(matches!(provider.revoke("ci"), Err(Error::ApiTokenUnknown(_))));
+        assert!(provider.list().tokens().is_empty());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+
+    #[test]
+    fn reject_invalid_and_expired_tokens() {
+        let data_dir = test::tmp_dir();
+        let provider = ApiTokenAuthProvider::new(&KeyValueStorage::Disk(data_dir.clone())).unwrap();
+
+        assert!(provider.create(roas_request("../etc", None), "admin", None).is_err());
+        assert!(provider
+            .create(roas_request("old", Some(Timestamp::now_minus_hours(1))), "admin", None)
+            .is_err());
+
+        let admin_for_ca = ApiTokenRequest::new(
+            "admin".to_string(),
+            ApiTokenScope::Admin,
+            vec![CaHandle::from_str("ca1").unwrap()],
+            None,
+        );
+        assert!(provider.create(admin_for_ca, "admin", None).is_err());
+
+        let created = provider.create(roas_request("soon", None), "admin", None).unwrap();
+        provider.cache.write().unwrap().get_mut("soon").unwrap().info = ApiTokenInfo::new(
+            "soon".to_string(),
+            ApiTokenScope::Roas,
+            vec![],
+            Timestamp::now_minus_hours(2),
+            "admin".to_string(),
+            Some(Timestamp::now_minus_hours(1)),
+            None,
+        );
+        assert!(provider.authenticate(&request_with_token(created.token())).is_err());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+
+    #[test]
+    fn delegate_roas_of_ca() {
+        let data_dir = test::tmp_dir();
+        let provider = ApiTokenAuthProvider::new(&KeyValueStorage::Disk(data_dir.clone())).unwrap();
+
+        let ca1 = CaHandle::from_str("ca1").unwrap();
+        let ca2 = CaHandle::from_str("ca2").unwrap();
+
+        let delegation = CaDelegationRequest::new("customer".to_string(), None);
+        let created = provider
+            .create(delegation.into_token_request(ca1.clone()), "admin", None)
+            .unwrap();
+        assert!(created.info().is_delegated_for(&ca1));
+
+        let read_only = ApiTokenRequest::new("monitor".to_string(), ApiTokenScope::ReadOnly, vec![ca1.clone()], None);
+        provider.create(read_only, "admin", None).unwrap();
+
+        assert_eq!(provider.list_delegated_for(&ca1).tokens().len(), 1);
+        assert!(provider.list_delegated_for(&ca2).tokens().is_empty());
+
+        assert!(provider.revoke_delegated_for("customer", &ca2).is_err());
+        assert!(provider.revoke_delegated_for("monitor", &ca1).is_err());
+        provider.revoke_delegated_for("customer", &ca1).unwrap();
+        assert!(provider.list_delegated_for(&ca1).tokens().is_empty());
+        assert_eq!(provider.list().tokens().len(), 1);
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+
+    #[test]
+    fn token_is_scoped_to_org_of_creator() {
+        let data_dir = test::tmp_dir();
+        let provider = ApiTokenAuthProvider::new(&KeyValueStorage::Disk(data_dir.clone())).unwrap();
+
+        let admin = ApiTokenRequest::new("automation".to_string(), ApiTokenScope::Admin, vec![], None);
+        let created = provider.create(admin, "alice", Some("customer-a".to_string())).unwrap();
+        assert_eq!(created.info().org().map(String::as_str), Some("customer-a"));
+
+        let actor = provider
+            .authenticate(&request_with_token(created.token()))
+            .unwrap()
+            .unwrap();
+        let attributes = actor.attributes.as_map();
+        assert_eq!(attributes.get("role").unwrap(), "admin");
+        assert_eq!(attributes.get("org").unwrap(), "customer-a");
+
+        // The org is kept across restarts.
+        let provider = ApiTokenAuthProvider::new(&KeyValueStorage::Disk(data_dir.clone())).unwrap();
+        let actor = provider
+            .authenticate(&request_with_token(created.token()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(actor.attributes.as_map().get("org").unwrap(), "customer-a");
+
+        // Tokens created by users without an org have none.
+        let created = provider.create(roas_request("ci", None), "admin", None).unwrap();
+        let actor = provider
+            .authenticate(&request_with_token(created.token()))
+            .unwrap()
+            .unwrap();
+        assert!(actor.attributes.as_map().get("org").is_none());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+
+    #[test]
+    fn tokens_in_shared_storage_are_loaded_again() {
+        let data_dir = test::tmp_dir();
+        let storage = KeyValueStorage::Disk(data_dir.clone());
+        let active = ApiTokenAuthProvider::new(&storage).unwrap();
+        let standby = ApiTokenAuthProvider::with_refresh(&storage, Some(Duration::from_secs(0))).unwrap();
+
+        // A token created by the active instance can be used on the standby.
+        let created = active.create(roas_request("ci", None), "admin", None).unwrap();
+        assert!(standby.authenticate(&request_with_token(created.token())).is_ok());
+
+        // And when it is revoked, it can no longer be used on the standby.
+        active.revoke("ci").unwrap();
+        assert!(standby.authenticate(&request_with_token(created.token())).is_err());
+        assert!(standby.list().tokens().is_empty());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+}
@@ -0,0 +1,54 @@
+use std::{collections::HashMap, path::PathBuf};
+
+/// Configuration for the [`ClientCertAuthProvider`], read from the
+/// `[auth_client_certs]` section of the Krill configuration file. E.g.:
+///
+/// ```toml
+/// [auth_client_certs]
+/// ca_cert = "/etc/krill/client-ca.pem"
+///
+/// [auth_client_certs.users."automation@example.org"]
+/// subject = "CN=automation,O=Example"
+/// attributes = { role = "readwrite" }
+/// ```
+///
+/// Like `[auth_jwt]` this does not depend on `auth_type`, certificates are
+/// accepted in addition to the configured provider.
+///
+/// [`ClientCertAuthProvider`]: super::provider::ClientCertAuthProvider
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthClientCerts {
+    /// PEM file with the CA certificate(s) that client certificates must be
+    /// issued under.
+    pub ca_cert: PathBuf,
+
+    /// Whether clients must present a certificate to connect at all. If false
+    /// clients without a certificate can still use the other providers.
+    #[serde(default)]
+    pub required: bool,
+
+    /// Krill users by id, each matched to a certificate by its subject, the
+    /// SHA-256 hash of its public key, or both.
+    #[serde(default)]
+    pub users: HashMap<String, ConfigClientCertUser>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigClientCertUser {
+    /// The subject of the certificate in RFC 4514 string form, e.g.
+    /// "CN=automation,O=Example".
+    pub subject: Option<String>,
+
+    /// The hex encoded SHA-256 hash of the DER encoded SubjectPublicKeyInfo
+    /// of the certificate.
+    pub spki_sha256: Option<String>,
+
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
+
+impl ConfigClientCertUser {
+    pub fn is_valid(&self) -> bool {
+        self.subject.is_some() || self.spki_sha256.is_some()
+    }
+}
@@ -0,0 +1,219 @@
+//! Authenticate API clients using the TLS client certificate they presented
+//! when connecting to the HTTPS listener.
+//!
+//! The certificate chain is verified against the configured CA certificate(s)
+//! during the TLS handshake. This provider then only has to map the end-entity
+//! certificate to a configured user, either by its subject or by the SHA-256
+//! hash of its SubjectPublicKeyInfo. The latter keeps working when the
+//! certificate is renewed with the same key, the former when it is renewed
+//! with a new key.
+//!
+//! There is no login session: the certificate is checked for each request.
+
+use std::sync::Arc;
+
+use openssl::{nid::Nid, x509::X509};
+
+use crate::{
+    commons::{actor::ActorDef, error::Error, util::sha256, KrillResult},
+    daemon::{
+        auth::providers::client_cert::config::{ConfigAuthClientCerts, ConfigClientCertUser},
+        config::Config,
+        http::tls::ClientCertificate,
+    },
+};
+
+pub struct ClientCertAuthProvider {
+    conf: ConfigAuthClientCerts,
+}
+
+impl ClientCertAuthProvider {
+    pub fn new(config: Arc<Config>) -> KrillResult<Self> {
+        match &config.auth_client_certs {
+            Some(conf) => Ok(ClientCertAuthProvider { conf: conf.clone() }),
+            None => Err(Error::ConfigError("Missing [auth_client_certs] config section!".into())),
+        }
+    }
+
+    /// Returns `Ok(None)` if the request was not made with a client
+    /// certificate, or with one that is not mapped to a user, so that other
+    /// providers can have a go at it.
+    pub fn authenticate(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
+        let cert = match request.extensions().get::<ClientCertificate>() {
+            Some(cert) => cert,
+            None => return Ok(None),
+        };
+
+        let cert = X509::from_der(&cert.0)
+            .map_err(|err| Error::ApiInvalidCredentials(format!("Invalid client certificate: {}", err)))?;
+        let subject = subject_string(&cert);
+        let spki_sha256 = spki_sha256(&cert)?;
+
+        let res = self
+            .conf
+            .users
+            .iter()
+            .find(|(_, user)| Self::matches(user, &subject, &spki_sha256))
+            .map(|(id, user)| ActorDef::user(id.clone(), user.attributes.clone(), None));
+
+        if res.is_none() {
+            debug!(
+                "Client certificate with subject '{}' and SPKI hash {} is not mapped to a user",
+                subject, spki_sha256
+            );
+        }
+
+        Ok(res)
+    }
+
+    fn matches(user: &ConfigClientCertUser, subject: &str, spki_sha256: &str) -> bool {
+        user.is_valid()
+            && user
+                .subject
+                .as_ref()
+                .map(|s| normalize_subject(s) == subject)
+                .unwrap_or(true)
+            && user
+                .spki_sha256
+                .as_ref()
+                .map(|h| h.eq_ignore_ascii_case(spki_sha256))
+                .unwrap_or(true)
+    }
+}
+
+/// Formats the subject of the certificate like RFC 4514 does, i.e. with the
+/// most specific attribute first, e.g. "CN=automation,O=Example".
+fn subject_string(cert: &X509) -> String {
+    let mut parts: Vec<String> = cert
+        .subject_name()
+        .entries()
+        .map(|entry| {
+            let nid = entry.object().nid();
+            let name = match nid {
+                Nid::UNDEF => entry.object().to_string(),
+                _ => nid.short_name().unwrap_or("UNKNOWN").to_string(),
+            };
+            let value = entry
+                .data()
+                .as_utf8()
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            format!("{}={}", name, value)
+        })
+        .collect();
+    parts.reverse();
+    parts.join(",")
+}
+
+/// Removes optional white space around the separators of a configured
+/// subject, so that "CN=automation, O=Example" matches as well.
+fn normalize_subject(subject: &str) -> String {
+    subject
+        .split(',')
+        .map(|part| {
+            let mut kv = part.splitn(2, '=');
+            let key = kv.next().unwrap_or_default().trim();
+            match kv.next() {
+                Some(value) => format!("{}={}", key, value.trim()),
+                None => key.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn spki_sha256(cert: &X509) -> KrillResult<String> {
+    let spki = cert
+        .public_key()
+        .and_then(|key| key.public_key_to_der())
+        .map_err(|err| Error::ApiInvalidCredentials(format!("Invalid client certificate key: {}", err)))?;
+    Ok(hex::encode(sha256(&spki)))
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use openssl::{
+        asn1::Asn1Time,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        pkey::{PKey, Private},
+        x509::{X509Builder, X509NameBuilder},
+    };
+
+    use super::*;
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn cert(key: &PKey<Private>, cn: &str) -> Vec<u8> {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "Example").unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, cn).unwrap();
+        let name = name.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        builder.sign(key, MessageDigest::sha256()).unwrap();
+        builder.build().to_der().unwrap()
+    }
+
+    fn request(cert: Option<Vec<u8>>) -> hyper::Request<hyper::Body> {
+        let mut request = hyper::Request::builder().body(hyper::Body::empty()).unwrap();
+        if let Some(cert) = cert {
+            request.extensions_mut().insert(ClientCertificate(cert));
+        }
+        request
+    }
+
+    #[test]
+    fn map_certificates_to_users() {
+        let by_key = key();
+        let spki = hex::encode(sha256(&by_key.public_key_to_der().unwrap()));
+
+        let conf: ConfigAuthClientCerts = toml::from_str(&format!(
+            r#"
+            ca_cert = "/dev/null"
+
+            [users.automation]
+            subject = "CN=automation, O=Example"
+            attributes = {{ role = "readwrite" }}
+
+            [users.pinned]
+            spki_sha256 = "{}"
+            attributes = {{ role = "readonly" }}
+            "#,
+            spki.to_uppercase()
+        ))
+        .unwrap();
+        let provider = ClientCertAuthProvider { conf };
+
+        let actor = provider
+            .authenticate(&request(Some(cert(&key(), "automation"))))
+            .unwrap()
+            .unwrap();
+        assert_eq!(actor.name.as_str(), "automation");
+        assert_eq!(actor.attributes.as_map().get("role").unwrap(), "readwrite");
+
+        let actor = provider
+            .authenticate(&request(Some(cert(&by_key, "anything"))))
+            .unwrap()
+            .unwrap();
+        assert_eq!(actor.name.as_str(), "pinned");
+
+        assert!(provider
+            .authenticate(&request(Some(cert(&key(), "other"))))
+            .unwrap()
+            .is_none());
+        assert!(provider.authenticate(&request(None)).unwrap().is_none());
+        assert!(provider.authenticate(&request(Some(vec![1, 2, 3]))).is_err());
+    }
+}
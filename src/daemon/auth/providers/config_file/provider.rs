@@ -12,7 +12,7 @@ use crate::{
         },
         auth::providers::config_file::config::ConfigUserDetails,
         auth::{Auth, LoggedInUser},
-        config::Config,
+        config::{AuthType, Config},
         http::HttpResponse,
     },
 };
@@ -174,9 +174,14 @@ impl ConfigFileAuthProvider {
                 // and don't result in an obvious timing difference between the two scenarios which could potentially
                 // be used to discover user names.
                 if let Some(user) = self.users.get(&username) {
-                    let api_token =
-                        self.session_cache
-                            .encode(&username, &user.attributes, HashMap::new(), &self.session_key, None)?;
+                    let api_token = self.session_cache.encode(
+                        AuthType::ConfigFile.name(),
+                        &username,
+                        &user.attributes,
+                        HashMap::new(),
+                        &self.session_key,
+                        None,
+                    )?;
 
                     Ok(LoggedInUser {
                         token: api_token,
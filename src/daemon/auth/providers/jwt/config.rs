@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+/// Configuration for the [`JwtAuthProvider`], read from the `[auth_jwt]`
+/// section of the Krill configuration file. E.g.:
+///
+/// ```toml
+/// [auth_jwt]
+/// jwks_url = "https://idp.example.net/.well-known/jwks.json"
+/// issuer = "https://idp.example.net/"
+/// audience = "krill"
+///
+/// [auth_jwt.attributes]
+/// role = "krill_role"
+/// inc_cas = "krill_cas"
+/// ```
+///
+/// Unlike the other `[auth_*]` sections this does not depend on `auth_type`,
+/// JWTs are accepted in addition to the configured provider.
+///
+/// [`JwtAuthProvider`]: super::provider::JwtAuthProvider
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthJwt {
+    /// The URL of the JSON Web Key Set with the public keys of the issuer.
+    pub jwks_url: String,
+
+    /// The "iss" claim of the JWT must have this value. This is required, as
+    /// otherwise JWTs issued by the same party for other purposes would be
+    /// accepted.
+    pub issuer: String,
+
+    /// The "aud" claim of the JWT must contain this value. This is required
+    /// for the same reason.
+    pub audience: String,
+
+    /// The claim to use as the Krill user id.
+    #[serde(default = "ConfigDefaults::id_claim")]
+    pub id_claim: String,
+
+    /// Krill attribute to claim mappings. Nested claims can be selected using
+    /// a dotted path, e.g. "realm_access.roles". Array values are joined into
+    /// a comma separated string.
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+
+    /// The role to give if the "role" attribute is not mapped from a claim or
+    /// the claim is missing from the JWT.
+    pub default_role: Option<String>,
+
+    /// The allowed clock difference when checking the "exp" and "nbf" claims.
+    #[serde(default = "ConfigDefaults::leeway_seconds")]
+    pub leeway_seconds: u64,
+
+    /// How long to use the key set before fetching it again.
+    #[serde(default = "ConfigDefaults::jwks_refresh_seconds")]
+    pub jwks_refresh_seconds: u64,
+}
+
+pub struct ConfigDefaults {}
+
+impl ConfigDefaults {
+    fn id_claim() -> String {
+        "sub".to_string()
+    }
+
+    fn leeway_seconds() -> u64 {
+        60
+    }
+
+    fn jwks_refresh_seconds() -> u64 {
+        3600
+    }
+}
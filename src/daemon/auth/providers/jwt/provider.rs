@@ -0,0 +1,656 @@
+//! Authenticate API clients using JSON Web Tokens (JWTs, RFC 7519) issued by
+//! an external identity provider, e.g. obtained by automation using the OAuth
+//! 2.0 client credentials flow.
+//!
+//! Only JWTs signed with RS256 or ES256 are accepted. The signature is checked
+//! against the public keys published by the issuer as a JSON Web Key Set
+//! (JWKS, RFC 7517) at the configured URL. The key set is cached and fetched
+//! again when it is older than the configured refresh interval, or when a JWT
+//! refers to a key that is not (yet) in the cached set.
+//!
+//! There is no login session: each request must carry a valid JWT.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use openssl::{
+    bn::BigNum,
+    ec::{EcGroup, EcKey},
+    ecdsa::EcdsaSig,
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::PKey,
+    rsa::Rsa,
+    sign::Verifier,
+};
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use crate::{
+    commons::{actor::ActorDef, error::Error, util::httpclient, KrillResult},
+    daemon::{auth::providers::jwt::config::ConfigAuthJwt, config::Config},
+};
+
+// Don't fetch the key set more often than this when JWTs refer to unknown
+// keys or when fetching failed, so that clients cannot make us hammer the
+// issuer.
+const JWKS_MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+//------------ Jwk -----------------------------------------------------------
+
+/// A JSON Web Key, see RFC 7517 and RFC 7518 section 6. Only the members
+/// needed for RSA and EC (P-256) public keys are supported.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Jwk {
+    kty: String,
+    #[serde(default)]
+    kid: Option<String>,
+    #[serde(default)]
+    alg: Option<String>,
+    #[serde(default, rename = "use")]
+    key_use: Option<String>,
+
+    // RSA public key members
+    #[serde(default)]
+    n: Option<String>,
+    #[serde(default)]
+    e: Option<String>,
+
+    // EC public key members
+    #[serde(default)]
+    crv: Option<String>,
+    #[serde(default)]
+    x: Option<String>,
+    #[serde(default)]
+    y: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+impl Jwk {
+    fn usable_for(&self, alg: JwtAlg, kid: Option<&str>) -> bool {
+        let kty_ok = match alg {
+            JwtAlg::Rs256 => self.kty == "RSA",
+            JwtAlg::Es256 => self.kty == "EC" && self.crv.as_deref() == Some("P-256"),
+        };
+        let alg_ok = self.alg.as_deref().map(|a| a == alg.name()).unwrap_or(true);
+        let use_ok = self.key_use.as_deref().map(|u| u == "sig").unwrap_or(true);
+        let kid_ok = match kid {
+            Some(kid) => self.kid.as_deref() == Some(kid),
+            None => true,
+        };
+
+        kty_ok && alg_ok && use_ok && kid_ok
+    }
+
+    fn member(&self, value: &Option<String>, name: &str) -> KrillResult<BigNum> {
+        let value = value
+            .as_ref()
+            .ok_or_else(|| Error::custom(format!("JWK is missing member '{}'", name)))?;
+        let bytes = b64_decode(value)?;
+        BigNum::from_slice(&bytes).map_err(|e| Error::custom(format!("JWK has invalid member '{}': {}", name, e)))
+    }
+
+    /// Verifies the signature over the signing input, i.e. the encoded JWT
+    /// header and payload joined by a period.
+    fn verify(&self, alg: JwtAlg, signing_input: &[u8], signature: &[u8]) -> KrillResult<bool> {
+        let invalid_key = |e: openssl::error::ErrorStack| Error::custom(format!("Invalid JWK: {}", e));
+
+        match alg {
+            JwtAlg::Rs256 => {
+                let rsa = Rsa::from_public_components(self.member(&self.n, "n")?, self.member(&self.e, "e")?)
+                    .map_err(invalid_key)?;
+                let key = PKey::from_rsa(rsa).map_err(invalid_key)?;
+                let mut verifier = Verifier::new(MessageDigest::sha256(), &key).map_err(invalid_key)?;
+                verifier.update(signing_input).map_err(invalid_key)?;
+                Ok(verifier.verify(signature).unwrap_or(false))
+            }
+            JwtAlg::Es256 => {
+                // JWS uses the fixed size R || S encoding of the signature
+                // rather than the DER encoding used by OpenSSL, see RFC 7518
+                // section 3.4.
+                if signature.len() != 64 {
+                    return Ok(false);
+                }
+                let r = BigNum::from_slice(&signature[..32]).map_err(invalid_key)?;
+                let s = BigNum::from_slice(&signature[32..]).map_err(invalid_key)?;
+                let signature = EcdsaSig::from_private_components(r, s).map_err(invalid_key)?;
+
+                let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).map_err(invalid_key)?;
+                let x = self.member(&self.x, "x")?;
+                let y = self.member(&self.y, "y")?;
+                let key = EcKey::from_public_key_affine_coordinates(&group, &x, &y).map_err(invalid_key)?;
+
+                let digest = openssl::sha::sha256(signing_input);
+                Ok(signature.verify(&digest, &key).unwrap_or(false))
+            }
+        }
+    }
+}
+
+//------------ JwtAlg --------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum JwtAlg {
+    Rs256,
+    Es256,
+}
+
+impl JwtAlg {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "RS256" => Some(JwtAlg::Rs256),
+            "ES256" => Some(JwtAlg::Es256),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            JwtAlg::Rs256 => "RS256",
+            JwtAlg::Es256 => "ES256",
+        }
+    }
+}
+
+//------------ DecodedJwt ----------------------------------------------------
+
+/// A JWT split into its parts, of which the signature is not yet verified.
+struct DecodedJwt {
+    alg: JwtAlg,
+    kid: Option<String>,
+    claims: serde_json::Map<String, Value>,
+    signing_input: String,
+    signature: Vec<u8>,
+}
+
+impl DecodedJwt {
+    /// Returns `None` if the token does not look like a JWT at all, so that it
+    /// can be left for other providers.
+    fn parse(token: &str) -> Option<KrillResult<Self>> {
+        let parts: Vec<&str> = token.split('.').collect();
+        if parts.len() != 3 {
+            return None;
+        }
+
+        let header: serde_json::Map<String, Value> = b64_decode(parts[0])
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())?;
+        let alg_name = header.get("alg")?.as_str()?;
+
+        Some(Self::parse_jwt(&header, alg_name, &parts))
+    }
+
+    fn parse_jwt(header: &serde_json::Map<String, Value>, alg_name: &str, parts: &[&str]) -> KrillResult<Self> {
+        let invalid = |msg: &str| Error::ApiInvalidCredentials(format!("Invalid JWT: {}", msg));
+
+        let alg = JwtAlg::from_name(alg_name).ok_or_else(|| invalid("unsupported signature algorithm"))?;
+        let kid = header.get("kid").and_then(Value::as_str).map(str::to_string);
+
+        let claims = b64_decode(parts[1])
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| invalid("cannot decode claims"))?;
+        let signature = b64_decode(parts[2]).map_err(|_| invalid("cannot decode signature"))?;
+
+        Ok(DecodedJwt {
+            alg,
+            kid,
+            claims,
+            signing_input: format!("{}.{}", parts[0], parts[1]),
+            signature,
+        })
+    }
+}
+
+fn b64_decode(value: &str) -> KrillResult<Vec<u8>> {
+    base64::decode_config(value, base64::URL_SAFE_NO_PAD)
+        .map_err(|e| Error::custom(format!("Invalid base64url value: {}", e)))
+}
+
+//------------ JwtAuthProvider -----------------------------------------------
+
+struct KeyCache {
+    keys: Vec<Jwk>,
+    fetched: Option<Instant>,
+    failed: Option<Instant>,
+}
+
+impl KeyCache {
+    /// Returns the cached keys, unless they should be fetched again for the
+    /// given JWT.
+    fn current(&self, jwt: &DecodedJwt, refresh_after: Duration) -> Option<Vec<Jwk>> {
+        if let Some(failed) = self.failed {
+            if failed.elapsed() < JWKS_MIN_REFETCH_INTERVAL {
+                return Some(self.keys.clone());
+            }
+        }
+
+        let fetched = self.fetched?;
+        let has_key = self.keys.iter().any(|k| k.usable_for(jwt.alg, jwt.kid.as_deref()));
+        if fetched.elapsed() < refresh_after && (has_key || fetched.elapsed() < JWKS_MIN_REFETCH_INTERVAL) {
+            Some(self.keys.clone())
+        } else {
+            None
+        }
+    }
+}
+
+pub struct JwtAuthProvider {
+    conf: ConfigAuthJwt,
+    keys: RwLock<KeyCache>,
+}
+
+impl JwtAuthProvider {
+    pub fn new(config: Arc<Config>) -> KrillResult<Self> {
+        let conf = match &config.auth_jwt {
+            Some(conf) => conf.clone(),
+            None => return Err(Error::ConfigError("Missing [auth_jwt] config section!".into())),
+        };
+
+        if !conf.jwks_url.starts_with("https://") {
+            warn!(
+                "[auth_jwt] jwks_url '{}' does not use https, the keys could be tampered with in transit",
+                conf.jwks_url
+            );
+        }
+
+        Ok(JwtAuthProvider {
+            conf,
+            keys: RwLock::new(KeyCache {
+                keys: vec![],
+                fetched: None,
+                failed: None,
+            }),
+        })
+    }
+
+    /// Returns `Ok(None)` if the request does not carry a JWT, so that other
+    /// providers can have a go at it.
+    pub async fn authenticate(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
+        let token = match httpclient::get_bearer_token(request) {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+
+        let jwt = match DecodedJwt::parse(token.as_ref()) {
+            Some(jwt) => jwt?,
+            None => return Ok(None),
+        };
+
+        let keys = self.keys_for(&jwt).await?;
+        let res = self.verify(&jwt, &keys, Self::now()).map(Some);
+
+        if log_enabled!(log::Level::Trace) {
+            trace!("JWT authentication result: {:?}", res);
+        }
+
+        res
+    }
+
+    /// Returns the cached keys, fetching the key set first if it is stale or
+    /// if it lacks the key that the JWT was signed with.
+    async fn keys_for(&self, jwt: &DecodedJwt) -> KrillResult<Vec<Jwk>> {
+        let refresh_after = Duration::from_secs(self.conf.jwks_refresh_seconds);
+
+        if let Some(keys) = self.keys.read().await.current(jwt, refresh_after) {
+            return Self::unless_empty(keys);
+        }
+
+        // Another request may have fetched the key set while we were waiting
+        // for the write lock.
+        let mut cache = self.keys.write().await;
+        if let Some(keys) = cache.current(jwt, refresh_after) {
+            return Self::unless_empty(keys);
+        }
+
+        debug!("Fetching JSON Web Key Set from {}", self.conf.jwks_url);
+        match httpclient::get_json::<JwkSet>(&self.conf.jwks_url, None).await {
+            Ok(set) => {
+                cache.keys = set.keys;
+                cache.fetched = Some(Instant::now());
+                cache.failed = None;
+            }
+            Err(e) => {
+                warn!("Unable to fetch JSON Web Key Set from {}: {}", self.conf.jwks_url, e);
+                cache.failed = Some(Instant::now());
+            }
+        }
+
+        Self::unless_empty(cache.keys.clone())
+    }
+
+    /// Returns an error if there are no keys, e.g. because the key set could
+    /// not be fetched.
+    fn unless_empty(keys: Vec<Jwk>) -> KrillResult<Vec<Jwk>> {
+        if keys.is_empty() {
+            Err(Error::ApiAuthTransientError(
+                "Unable to verify JWT: could not fetch the keys of the issuer".to_string(),
+            ))
+        } else {
+            Ok(keys)
+        }
+    }
+
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    }
+
+    fn verify(&self, jwt: &DecodedJwt, keys: &[Jwk], now: i64) -> KrillResult<ActorDef> {
+        let invalid = |msg: &str| Error::ApiInvalidCredentials(format!("Invalid JWT: {}", msg));
+
+        let mut verified = false;
+        for key in keys.iter().filter(|k| k.usable_for(jwt.alg, jwt.kid.as_deref())) {
+            match key.verify(jwt.alg, jwt.signing_input.as_bytes(), &jwt.signature) {
+                Ok(true) => {
+                    verified = true;
+                    break;
+                }
+                Ok(false) => {}
+                Err(e) => debug!("Skipping unusable JWK {:?}: {}", key.kid, e),
+            }
+        }
+        if !verified {
+            return Err(invalid("signature cannot be verified"));
+        }
+
+        let claims = &jwt.claims;
+        let leeway = self.conf.leeway_seconds as i64;
+
+        match claims.get("exp").and_then(Value::as_i64) {
+            Some(exp) if exp + leeway > now => {}
+            Some(_) => return Err(Error::ApiAuthSessionExpired("JWT has expired".to_string())),
+            None => return Err(invalid("missing 'exp' claim")),
+        }
+
+        if let Some(nbf) = claims.get("nbf").and_then(Value::as_i64) {
+            if nbf - leeway > now {
+                return Err(invalid("not yet valid"));
+            }
+        }
+
+        if claims.get("iss").and_then(Value::as_str) != Some(self.conf.issuer.as_str()) {
+            return Err(invalid("unexpected issuer"));
+        }
+
+        let audience = &self.conf.audience;
+        let matches = match claims.get("aud") {
+            Some(Value::String(aud)) => aud == audience,
+            Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience.as_str())),
+            _ => false,
+        };
+        if !matches {
+            return Err(invalid("unexpected audience"));
+        }
+
+        let id = claim_value(claims, &self.conf.id_claim)
+            .ok_or_else(|| invalid(&format!("missing '{}' claim", self.conf.id_claim)))?;
+
+        let mut attributes = HashMap::new();
+        for (attr, claim) in &self.conf.attributes {
+            if let Some(value) = claim_value(claims, claim) {
+                attributes.insert(attr.clone(), value);
+            }
+        }
+        if let Some(role) = &self.conf.default_role {
+            attributes.entry("role".to_string()).or_insert_with(|| role.clone());
+        }
+
+        Ok(ActorDef::user(id, attributes, None))
+    }
+}
+
+/// Looks up a claim by dotted path and returns it as a string. Arrays are
+/// joined into a comma separated string. Returns `None` for missing, null and
+/// object values.
+fn claim_value(claims: &serde_json::Map<String, Value>, path: &str) -> Option<String> {
+    let mut parts = path.split('.');
+    let mut value = claims.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_object()?.get(part)?;
+    }
+
+    fn scalar(value: &Value) -> Option<String> {
+        match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    match value {
+        Value::Array(values) => Some(values.iter().filter_map(scalar).collect::<Vec<_>>().join(",")),
+        other => scalar(other),
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use openssl::{ec::EcKey, pkey::Private, sign::Signer};
+
+    use super::*;
+
+    fn b64(bytes: &[u8]) -> String {
+        base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+    }
+
+    fn provider() -> JwtAuthProvider {
+        let conf: ConfigAuthJwt = toml::from_str(
+            r#"
+            jwks_url = "https://idp.example.net/jwks.json"
+            issuer = "https://idp.example.net/"
+            audience = "krill"
+            default_role = "readonly"
+
+            [attributes]
+            role = "krill.role"
+            inc_cas = "cas"
+            "#,
+        )
+        .unwrap();
+
+        JwtAuthProvider {
+            conf,
+            keys: RwLock::new(KeyCache {
+                keys: vec![],
+                fetched: None,
+                failed: None,
+            }),
+        }
+    }
+
+    fn claims(exp: i64) -> Value {
+        serde_json::json!({
+            "sub": "ci-bot",
+            "iss": "https://idp.example.net/",
+            "aud": ["other", "krill"],
+            "exp": exp,
+            "krill": { "role": "readwrite" },
+            "cas": ["ca1", "ca2"]
+        })
+    }
+
+    fn signing_input(alg: &str, kid: &str, claims: &Value) -> String {
+        let header = serde_json::json!({ "alg": alg, "kid": kid, "typ": "JWT" });
+        format!(
+            "{}.{}",
+            b64(header.to_string().as_bytes()),
+            b64(claims.to_string().as_bytes())
+        )
+    }
+
+    fn rsa_key() -> (PKey<Private>, Jwk) {
+        let rsa = Rsa::generate(2048).unwrap();
+        let jwk = Jwk {
+            kty: "RSA".to_string(),
+            kid: Some("rsa-1".to_string()),
+            alg: Some("RS256".to_string()),
+            key_use: Some("sig".to_string()),
+            n: Some(b64(&rsa.n().to_vec())),
+            e: Some(b64(&rsa.e().to_vec())),
+            crv: None,
+            x: None,
+            y: None,
+        };
+        (PKey::from_rsa(rsa).unwrap(), jwk)
+    }
+
+    fn rs256_jwt(key: &PKey<Private>, claims: &Value) -> String {
+        let input = signing_input("RS256", "rsa-1", claims);
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(input.as_bytes()).unwrap();
+        format!("{}.{}", input, b64(&signer.sign_to_vec().unwrap()))
+    }
+
+    fn ec_key() -> (EcKey<Private>, Jwk) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let mut ctx = openssl::bn::BigNumContext::new().unwrap();
+        let mut x = BigNum::new().unwrap();
+        let mut y = BigNum::new().unwrap();
+        key.public_key()
+            .affine_coordinates_gfp(&group, &mut x, &mut y, &mut ctx)
+            .unwrap();
+        let jwk = Jwk {
+            kty: "EC".to_string(),
+            kid: Some("ec-1".to_string()),
+            alg: None,
+            key_use: None,
+            n: None,
+            e: None,
+            crv: Some("P-256".to_string()),
+            x: Some(b64(&x.to_vec_padded(32).unwrap())),
+            y: Some(b64(&y.to_vec_padded(32).unwrap())),
+        };
+        (key, jwk)
+    }
+
+    fn es256_jwt(key: &EcKey<Private>, claims: &Value) -> String {
+        let input = signing_input("ES256", "ec-1", claims);
+        let sig = EcdsaSig::sign(&openssl::sha::sha256(input.as_bytes()), key).unwrap();
+        let mut raw = sig.r().to_vec_padded(32).unwrap();
+        raw.extend(sig.s().to_vec_padded(32).unwrap());
+        format!("{}.{}", input, b64(&raw))
+    }
+
+    fn decode(token: &str) -> DecodedJwt {
+        DecodedJwt::parse(token).unwrap().unwrap()
+    }
+
+    #[test]
+    fn verify_rs256_and_es256() {
+        let provider = provider();
+        let now = JwtAuthProvider::now();
+        let (rsa, rsa_jwk) = rsa_key();
+        let (ec, ec_jwk) = ec_key();
+        let keys = vec![rsa_jwk, ec_jwk];
+
+        let actor = provider
+            .verify(&decode(&rs256_jwt(&rsa, &claims(now + 300))), &keys, now)
+            .unwrap();
+        assert_eq!(actor.name.as_str(), "ci-bot");
+        let attributes = actor.attributes.as_map();
+        assert_eq!(attributes.get("role").unwrap(), "readwrite");
+        assert_eq!(attributes.get("inc_cas").unwrap(), "ca1,ca2");
+
+        let mut without_role = claims(now + 300);
+        without_role.as_object_mut().unwrap().remove("krill");
+        let actor = provider
+            .verify(&decode(&es256_jwt(&ec, &without_role)), &keys, now)
+            .unwrap();
+        assert_eq!(actor.attributes.as_map().get("role").unwrap(), "readonly");
+    }
+
+    #[test]
+    fn reject_invalid_jwts() {
+        let provider = provider();
+        let now = JwtAuthProvider::now();
+        let (rsa, rsa_jwk) = rsa_key();
+        let (other_rsa, _) = rsa_key();
+        let keys = vec![rsa_jwk];
+
+        // expired, beyond the leeway
+        let jwt = decode(&rs256_jwt(&rsa, &claims(now - 120)));
+        assert!(provider.verify(&jwt, &keys, now).is_err());
+
+        // signed by another key
+        let jwt = decode(&rs256_jwt(&other_rsa, &claims(now + 300)));
+        assert!(provider.verify(&jwt, &keys, now).is_err());
+
+        // wrong audience
+        let mut wrong_aud = claims(now + 300);
+        wrong_aud["aud"] = Value::String("other".to_string());
+        let jwt = decode(&rs256_jwt(&rsa, &wrong_aud));
+        assert!(provider.verify(&jwt, &keys, now).is_err());
+
+        // unsigned
+        let input = signing_input("none", "rsa-1", &claims(now + 300));
+        assert!(DecodedJwt::parse(&format!("{}.", input)).unwrap().is_err());
+
+        // not a JWT at all
+        assert!(DecodedJwt::parse("secret").is_none());
+        assert!(DecodedJwt::parse("krillapi_0123").is_none());
+    }
+
+    #[test]
+    fn refetch_stale_or_unknown_keys() {
+        let now = JwtAuthProvider::now();
+        let refresh_after = Duration::from_secs(3600);
+        let (rsa, rsa_jwk) = rsa_key();
+        let (ec, _) = ec_key();
+        let rsa_jwt = decode(&rs256_jwt(&rsa, &claims(now + 300)));
+        let ec_jwt = decode(&es256_jwt(&ec, &claims(now + 300)));
+
+        let mut cache = KeyCache {
+            keys: vec![rsa_jwk],
+            fetched: None,
+            failed: None,
+        };
+        assert!(cache.current(&rsa_jwt, refresh_after).is_none());
+
+        // Unknown keys are not fetched again straight away.
+        cache.fetched = Some(Instant::now());
+        assert!(cache.current(&rsa_jwt, refresh_after).is_some());
+        assert!(cache.current(&ec_jwt, refresh_after).is_some());
+
+        cache.fetched = Instant::now().checked_sub(JWKS_MIN_REFETCH_INTERVAL * 2);
+        assert!(cache.current(&rsa_jwt, refresh_after).is_some());
+        assert!(cache.current(&ec_jwt, refresh_after).is_none());
+
+        // Stale keys are fetched again.
+        cache.fetched = Instant::now().checked_sub(refresh_after * 2);
+        assert!(cache.current(&rsa_jwt, refresh_after).is_none());
+
+        // But not straight away if fetching them failed.
+        cache.failed = Some(Instant::now());
+        assert!(cache.current(&rsa_jwt, refresh_after).is_some());
+        assert!(cache.current(&ec_jwt, refresh_after).is_some());
+
+        cache.failed = Instant::now().checked_sub(JWKS_MIN_REFETCH_INTERVAL * 2);
+        assert!(cache.current(&rsa_jwt, refresh_after).is_none());
+    }
+
+    #[test]
+    fn require_issuer_and_audience() {
+        assert!(toml::from_str::<ConfigAuthJwt>(r#"jwks_url = "https://idp.example.net/jwks.json""#).is_err());
+        assert!(toml::from_str::<ConfigAuthJwt>(
+            r#"
+            jwks_url = "https://idp.example.net/jwks.json"
+            issuer = "https://idp.example.net/"
+            "#
+        )
+        .is_err());
+    }
+}
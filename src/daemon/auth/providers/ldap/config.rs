@@ -0,0 +1,105 @@
+/// Configuration for the [`LdapAuthProvider`], read from the `[auth_ldap]`
+/// section of the Krill configuration file. E.g.:
+///
+/// ```toml
+/// auth_type = "ldap"
+///
+/// [auth_ldap]
+/// url = "ldaps://ad.example.net"
+/// bind_dn = "CN=krill,OU=Service Accounts,DC=example,DC=net"
+/// bind_password = "..."
+/// user_search_base = "OU=Staff,DC=example,DC=net"
+/// user_search_filter = "(sAMAccountName={username})"
+///
+/// [[auth_ldap.roles]]
+/// group = "CN=RPKI Admins,OU=Groups,DC=example,DC=net"
+/// role = "admin"
+///
+/// [[auth_ldap.roles]]
+/// group = "CN=NOC,OU=Groups,DC=example,DC=net"
+/// role = "readonly"
+/// ```
+///
+/// [`LdapAuthProvider`]: super::provider::LdapAuthProvider
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthLdap {
+    /// The ldap:// or ldaps:// URL of the directory server.
+    pub url: String,
+
+    /// Upgrade a plain ldap:// connection using StartTLS.
+    #[serde(default)]
+    pub starttls: bool,
+
+    /// Do not verify the TLS certificate of the directory server. This is NOT
+    /// a good idea and is only intended for testing.
+    #[serde(default)]
+    pub insecure: bool,
+
+    /// The DN to bind as when searching for the user that is logging in. If
+    /// not set the search is done with an anonymous bind.
+    pub bind_dn: Option<String>,
+
+    pub bind_password: Option<String>,
+
+    /// The DN under which to search (sub tree) for user entries.
+    pub user_search_base: String,
+
+    /// The LDAP filter used to find the user entry. The `{username}`
+    /// placeholder is replaced with the (escaped) username entered at login.
+    #[serde(default = "ConfigDefaults::user_search_filter")]
+    pub user_search_filter: String,
+
+    /// The user entry attribute listing the DNs of the groups that the user
+    /// is a member of.
+    #[serde(default = "ConfigDefaults::group_attribute")]
+    pub group_attribute: String,
+
+    /// The user entry attribute to use as the Krill user id. If not set the
+    /// username entered at login is used.
+    pub id_attribute: Option<String>,
+
+    /// Group to role mappings. The first mapping whose group the user is a
+    /// member of determines the role of the user.
+    #[serde(default)]
+    pub roles: Vec<ConfigAuthLdapRole>,
+
+    /// The role to give to users that are not a member of any mapped group.
+    /// If not set such users are not given a role and will thus not be
+    /// permitted to login.
+    pub default_role: Option<String>,
+
+    /// How long a login session lasts before the user has to login again.
+    #[serde(default = "ConfigDefaults::session_expiry_minutes")]
+    pub session_expiry_minutes: u64,
+
+    /// The timeout for connecting to and querying the directory server.
+    #[serde(default = "ConfigDefaults::timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthLdapRole {
+    /// The DN of the group, compared case insensitively.
+    pub group: String,
+    pub role: String,
+}
+
+pub struct ConfigDefaults {}
+
+impl ConfigDefaults {
+    fn user_search_filter() -> String {
+        "(sAMAccountName={username})".to_string()
+    }
+
+    fn group_attribute() -> String {
+        "memberOf".to_string()
+    }
+
+    fn session_expiry_minutes() -> u64 {
+        480
+    }
+
+    fn timeout_seconds() -> u64 {
+        10
+    }
+}
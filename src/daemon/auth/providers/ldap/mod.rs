@@ -0,0 +1,4 @@
+pub mod config;
+pub mod provider;
+
+pub use config::ConfigAuthLdap;
@@ -0,0 +1,387 @@
+//! Authenticate users against an LDAP directory, e.g. Microsoft Active
+//! Directory.
+//!
+//! Users login via the Lagosta login form with their directory username and
+//! password. Krill then:
+//!
+//!   1. binds to the directory, either with the configured service account or
+//!      anonymously,
+//!   2. searches for the entry of the user logging in,
+//!   3. binds as the found user DN with the given password to verify it, and
+//!   4. maps the group membership of the user to a Krill role.
+//!
+//! On success a login session token is issued, just like for the config file
+//! and OpenID Connect providers. The directory is not contacted again until
+//! the session expires and the user has to login again.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use ldap3::{ldap_escape, LdapConnAsync, LdapConnSettings, LdapError, Scope, SearchEntry};
+
+use crate::{
+    commons::{actor::ActorDef, error::Error, util::httpclient, KrillResult},
+    daemon::{
+        auth::common::{
+            crypt::{self, CryptState},
+            session::*,
+        },
+        auth::providers::ldap::config::ConfigAuthLdap,
+        auth::{Auth, LoggedInUser},
+        config::{AuthType, Config},
+        http::HttpResponse,
+    },
+};
+
+// See the config file provider for why this is a Lagosta route and not a URL.
+const LAGOSTA_LOGIN_ROUTE_PATH: &str = "/login?withId=true";
+const LOGIN_SESSION_STATE_KEY_PATH: &str = "login_session_state.key"; // TODO: decide on proper location
+
+// LDAP result code 49: invalidCredentials, see RFC 4511 section 4.1.9.
+const LDAP_RC_INVALID_CREDENTIALS: u32 = 49;
+
+/// The directory details of a user that passed authentication.
+struct DirectoryUser {
+    id: String,
+    groups: Vec<String>,
+}
+
+pub struct LdapAuthProvider {
+    conf: ConfigAuthLdap,
+    session_key: CryptState,
+    session_cache: Arc<LoginSessionCache>,
+}
+
+impl LdapAuthProvider {
+    pub fn new(config: Arc<Config>, session_cache: Arc<LoginSessionCache>) -> KrillResult<Self> {
+        let conf = match &config.auth_ldap {
+            Some(conf) => conf.clone(),
+            None => return Err(Error::ConfigError("Missing [auth_ldap] config section!".into())),
+        };
+
+        if !conf.url.starts_with("ldap://") && !conf.url.starts_with("ldaps://") {
+            return Err(Error::ConfigError(format!(
+                "[auth_ldap] url must start with ldap:// or ldaps://, found: '{}'",
+                conf.url
+            )));
+        }
+
+        if !conf.user_search_filter.contains("{username}") {
+            return Err(Error::ConfigError(
+                "[auth_ldap] user_search_filter must contain the {username} placeholder".into(),
+            ));
+        }
+
+        if conf.bind_dn.is_some() != conf.bind_password.is_some() {
+            return Err(Error::ConfigError(
+                "[auth_ldap] bind_dn and bind_password must either both be set or both be omitted".into(),
+            ));
+        }
+
+        if conf.insecure {
+            warn!("LDAP: TLS certificate verification of the directory server is disabled");
+        }
+
+        let session_key = Self::init_session_key(config)?;
+
+        Ok(LdapAuthProvider {
+            conf,
+            session_key,
+            session_cache,
+        })
+    }
+
+    fn init_session_key(config: Arc<Config>) -> KrillResult<CryptState> {
+        let key_path = config.data_dir.join(LOGIN_SESSION_STATE_KEY_PATH);
+        info!("Initializing login session encryption key {}", &key_path.display());
+        crypt::crypt_init(key_path.as_path())
+    }
+
+    /// Parse HTTP Basic Authorization header
+    fn get_auth(&self, request: &hyper::Request<hyper::Body>) -> Option<Auth> {
+        let header = request.headers().get(hyper::http::header::AUTHORIZATION)?;
+        let auth = header.to_str().ok()?.strip_prefix("Basic ")?;
+        let auth = base64::decode(auth).ok()?;
+        let auth = String::from_utf8(auth).ok()?;
+        let (username, password) = auth.split_once(':')?;
+
+        Some(Auth::UsernameAndPassword {
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Log and convert the given error such that the detailed, possibly
+    /// sensitive details are logged and only the high level statement about
+    /// the error is passed back to the caller.
+    fn internal_error(msg: &str, err: LdapError) -> Error {
+        warn!("{} [additional info: {}]", msg, err);
+        Error::ApiLoginError(msg.to_string())
+    }
+
+    /// Find the user in the directory and verify their password by binding as
+    /// the user. Returns Ok(None) if the user is unknown or the password is
+    /// incorrect.
+    async fn verify_user(&self, username: &str, password: &str) -> KrillResult<Option<DirectoryUser>> {
+        let timeout = Duration::from_secs(self.conf.timeout_seconds);
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(timeout)
+            .set_starttls(self.conf.starttls)
+            .set_no_tls_verify(self.conf.insecure);
+
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.conf.url)
+            .await
+            .map_err(|e| Self::internal_error("LDAP: Unable to connect to the directory server", e))?;
+        ldap3::drive!(conn);
+        ldap.with_timeout(timeout);
+
+        // Bind as the service account, or anonymously, to find the user entry.
+        let bind_dn = self.conf.bind_dn.as_deref().unwrap_or_default();
+        let bind_password = self.conf.bind_password.as_deref().unwrap_or_default();
+        ldap.simple_bind(bind_dn, bind_password)
+            .await
+            .and_then(|res| res.success())
+            .map_err(|e| Self::internal_error("LDAP: Unable to bind to the directory server", e))?;
+
+        let filter = self
+            .conf
+            .user_search_filter
+            .replace("{username}", &ldap_escape(username));
+
+        let mut attrs = vec![self.conf.group_attribute.as_str()];
+        if let Some(id_attribute) = &self.conf.id_attribute {
+            attrs.push(id_attribute.as_str());
+        }
+
+        trace!("LDAP: Searching '{}' for '{}'", &self.conf.user_search_base, &filter);
+        let (entries, _) = ldap
+            .search(&self.conf.user_search_base, Scope::Subtree, &filter, attrs)
+            .await
+            .and_then(|res| res.success())
+            .map_err(|e| Self::internal_error("LDAP: Unable to search for the user", e))?;
+
+        let entry = match entries.len() {
+            1 => SearchEntry::construct(entries.into_iter().next().unwrap()),
+            0 => {
+                trace!("LDAP: No entry found for user '{}'", username);
+                let _ = ldap.unbind().await;
+                return Ok(None);
+            }
+            n => {
+                warn!(
+                    "LDAP: Refusing login for '{}': the user search filter matched {} entries",
+                    username, n
+                );
+                let _ = ldap.unbind().await;
+                return Ok(None);
+            }
+        };
+
+        // Now verify the password by binding as the user.
+        let user_bind = ldap.simple_bind(&entry.dn, password).await;
+        let _ = ldap.unbind().await;
+        match user_bind.and_then(|res| res.success()) {
+            Ok(_) => {}
+            Err(LdapError::LdapResult { result }) if result.rc == LDAP_RC_INVALID_CREDENTIALS => {
+                trace!("LDAP: Incorrect password for user '{}'", username);
+                return Ok(None);
+            }
+            Err(e) => return Err(Self::internal_error("LDAP: Unable to verify the user credentials", e)),
+        }
+
+        let id = match &self.conf.id_attribute {
+            Some(id_attribute) => entry
+                .attrs
+                .get(id_attribute)
+                .and_then(|values| values.first())
+                .cloned()
+                .ok_or_else(|| {
+                    warn!("LDAP: User '{}' has no '{}' attribute", username, id_attribute);
+                    Error::ApiLoginError("LDAP: Unable to determine the user id".to_string())
+                })?,
+            None => username.to_string(),
+        };
+
+        let groups = entry.attrs.get(&self.conf.group_attribute).cloned().unwrap_or_default();
+
+        Ok(Some(DirectoryUser { id, groups }))
+    }
+
+    /// Determine the Krill role for the given group memberships.
+    fn role_for_groups(&self, groups: &[String]) -> Option<String> {
+        self.conf
+            .roles
+            .iter()
+            .find(|mapping| groups.iter().any(|group| group.eq_ignore_ascii_case(&mapping.group)))
+            .map(|mapping| mapping.role.clone())
+            .or_else(|| self.conf.default_role.clone())
+    }
+}
+
+impl LdapAuthProvider {
+    pub fn authenticate(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
+        if log_enabled!(log::Level::Trace) {
+            trace!("Attempting to authenticate the request..");
+        }
+
+        let res = match httpclient::get_bearer_token(request) {
+            Some(token) => {
+                // see if we can decode, decrypt and deserialize the users token
+                // into a login session structure
+                let session = self.session_cache.decode(token, &self.session_key, true)?;
+
+                // There is nothing to refresh the session with, the user has
+                // to login again once the session expires.
+                if session.status() == SessionStatus::Expired {
+                    Err(Error::ApiAuthSessionExpired("Login session expired".to_string()))
+                } else {
+                    trace!("id={}, attributes={:?}", &session.id, &session.attributes);
+                    Ok(Some(ActorDef::user(session.id, session.attributes, None)))
+                }
+            }
+            _ => Ok(None),
+        };
+
+        if log_enabled!(log::Level::Trace) {
+            trace!("Authentication result: {:?}", res);
+        }
+
+        res
+    }
+
+    pub fn get_login_url(&self) -> KrillResult<HttpResponse> {
+        // Direct Lagosta to show the user the Lagosta username and password
+        // login form
+        Ok(HttpResponse::text_no_cache(LAGOSTA_LOGIN_ROUTE_PATH.into()))
+    }
+
+    pub async fn login(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<LoggedInUser> {
+        match self.get_auth(request) {
+            // An empty password would result in an "unauthenticated" simple
+            // bind which many directory servers report as successful, see
+            // RFC 4513 section 5.1.2. So never pass it on.
+            Some(Auth::UsernameAndPassword { username, password }) if !username.is_empty() && !password.is_empty() => {
+                let user = self
+                    .verify_user(&username, &password)
+                    .await?
+                    .ok_or_else(|| Error::ApiInvalidCredentials("Incorrect credentials".to_string()))?;
+
+                let mut attributes = HashMap::new();
+                match self.role_for_groups(&user.groups) {
+                    Some(role) => {
+                        attributes.insert("role".to_string(), role);
+                    }
+                    None => {
+                        info!(
+                            "LDAP: User '{}' is not a member of any group that is mapped to a Krill role",
+                            &user.id
+                        );
+                    }
+                }
+
+                let expires_in = Some(Duration::from_secs(self.conf.session_expiry_minutes * 60));
+                let api_token = self.session_cache.encode(
+                    AuthType::Ldap.name(),
+                    &user.id,
+                    &attributes,
+                    HashMap::new(),
+                    &self.session_key,
+                    expires_in,
+                )?;
+
+                Ok(LoggedInUser {
+                    token: api_token,
+                    id: user.id,
+                    attributes,
+                })
+            }
+            _ => {
+                trace!("Missing or incomplete credentials for login attempt");
+                Err(Error::ApiInvalidCredentials("Missing credentials".to_string()))
+            }
+        }
+    }
+
+    pub fn logout(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<HttpResponse> {
+        match httpclient::get_bearer_token(request) {
+            Some(token) => {
+                if let Ok(Some(actor)) = self.authenticate(request) {
+                    info!("User logged out: {}", actor.name.as_str());
+                }
+
+                self.session_cache.remove(&token);
+            }
+            _ => {
+                warn!("Unexpectedly received a logout request without a session token.");
+            }
+        }
+
+        // Logout is complete, direct Lagosta to show the user the Lagosta
+        // index page
+        Ok(HttpResponse::text_no_cache("/".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::daemon::auth::providers::ldap::config::ConfigAuthLdapRole;
+
+    fn provider_with_roles(roles: Vec<(&str, &str)>, default_role: Option<&str>) -> LdapAuthProvider {
+        let conf: ConfigAuthLdap = toml::from_str(
+            r#"
+            url = "ldap://localhost"
+            user_search_base = "DC=example,DC=net"
+            "#,
+        )
+        .unwrap();
+
+        let conf = ConfigAuthLdap {
+            roles: roles
+                .into_iter()
+                .map(|(group, role)| ConfigAuthLdapRole {
+                    group: group.to_string(),
+                    role: role.to_string(),
+                })
+                .collect(),
+            default_role: default_role.map(|r| r.to_string()),
+            ..conf
+        };
+
+        LdapAuthProvider {
+            conf,
+            session_key: CryptState::from_key_vec(vec![0; 32]).unwrap(),
+            session_cache: Arc::new(LoginSessionCache::new()),
+        }
+    }
+
+    #[test]
+    fn first_matching_group_determines_role() {
+        let provider = provider_with_roles(
+            vec![
+                ("CN=Admins,DC=example,DC=net", "admin"),
+                ("CN=NOC,DC=example,DC=net", "readonly"),
+            ],
+            None,
+        );
+
+        let groups = vec![
+            "cn=noc,dc=example,dc=net".to_string(),
+            "CN=Admins,DC=example,DC=net".to_string(),
+        ];
+        assert_eq!(provider.role_for_groups(&groups), Some("admin".to_string()));
+
+        let groups = vec!["CN=NOC,DC=example,DC=net".to_string()];
+        assert_eq!(provider.role_for_groups(&groups), Some("readonly".to_string()));
+
+        assert_eq!(provider.role_for_groups(&[]), None);
+    }
+
+    #[test]
+    fn default_role_is_used_for_unmapped_users() {
+        let provider = provider_with_roles(vec![("CN=Admins,DC=example,DC=net", "admin")], Some("readonly"));
+
+        let groups = vec!["CN=Other,DC=example,DC=net".to_string()];
+        assert_eq!(provider.role_for_groups(&groups), Some("readonly".to_string()));
+    }
+}
@@ -1,13 +1,29 @@
 pub mod admin_token;
 
+#[cfg(feature = "multi-user")]
+pub mod api_token;
+#[cfg(feature = "multi-user")]
+pub mod client_cert;
 #[cfg(feature = "multi-user")]
 pub mod config_file;
 #[cfg(feature = "multi-user")]
+pub mod jwt;
+#[cfg(feature = "multi-user")]
+pub mod ldap;
+#[cfg(feature = "multi-user")]
 pub mod openid_connect;
 
 pub use admin_token::AdminTokenAuthProvider;
 
+#[cfg(feature = "multi-user")]
+pub use api_token::ApiTokenAuthProvider;
+#[cfg(feature = "multi-user")]
+pub use client_cert::provider::ClientCertAuthProvider;
 #[cfg(feature = "multi-user")]
 pub use config_file::provider::ConfigFileAuthProvider;
 #[cfg(feature = "multi-user")]
+pub use jwt::provider::JwtAuthProvider;
+#[cfg(feature = "multi-user")]
+pub use ldap::provider::LdapAuthProvider;
+#[cfg(feature = "multi-user")]
 pub use openid_connect::provider::OpenIDConnectAuthProvider;
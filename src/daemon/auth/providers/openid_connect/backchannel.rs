@@ -0,0 +1,234 @@
+//! Validation of logout tokens as sent by the provider to end login sessions,
+//! see [OpenID Connect Back-Channel Logout 1.0][openid-connect-backchannel-1_0].
+//!
+//! [openid-connect-backchannel-1_0]: https://openid.net/specs/openid-connect-backchannel-1_0.html
+
+use openidconnect::{
+    core::{CoreJsonWebKeySet, CoreJwsSigningAlgorithm},
+    JsonWebKey,
+};
+use serde_json::Value;
+
+use crate::commons::{error::Error, KrillResult};
+
+const BACKCHANNEL_LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+// Allow for some clock skew between Krill and the provider.
+const LEEWAY_SECS: i64 = 300;
+
+/// The login sessions that a valid logout token refers to.
+#[derive(Debug, Eq, PartialEq)]
+pub enum LogoutToken {
+    /// The single login session at the provider with this session ID.
+    Session(String),
+
+    /// All login sessions of the user with this subject.
+    Subject(String),
+}
+
+/// Validates the logout token as described in section 2.6 of the spec. The
+/// signature is only skipped if the operator chose to disable signature
+/// checks of ID tokens as well.
+pub fn validate_logout_token(
+    token: &str,
+    keys: &CoreJsonWebKeySet,
+    issuer: &str,
+    client_id: &str,
+    check_signature: bool,
+    now: i64,
+) -> KrillResult<LogoutToken> {
+    let invalid = |msg: &str| Error::ApiInvalidCredentials(format!("Invalid logout token: {}", msg));
+
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 {
+        return Err(invalid("not a signed JWT"));
+    }
+
+    let header = decode_json(parts[0]).ok_or_else(|| invalid("cannot decode header"))?;
+    let claims = decode_json(parts[1]).ok_or_else(|| invalid("cannot decode claims"))?;
+
+    if check_signature {
+        let alg = header
+            .get("alg")
+            .cloned()
+            .and_then(|alg| serde_json::from_value::<CoreJwsSigningAlgorithm>(alg).ok())
+            .filter(|alg| *alg != CoreJwsSigningAlgorithm::None)
+            .ok_or_else(|| invalid("unsupported signature algorithm"))?;
+        let kid = header.get("kid").and_then(Value::as_str);
+        let signature =
+            base64::decode_config(parts[2], base64::URL_SAFE_NO_PAD).map_err(|_| invalid("cannot decode signature"))?;
+        let signing_input = format!("{}.{}", parts[0], parts[1]);
+
+        let verified = keys
+            .keys()
+            .iter()
+            .filter(|key| kid.is_none() || key.key_id().map(|id| id.as_str()) == kid)
+            .any(|key| key.verify_signature(&alg, signing_input.as_bytes(), &signature).is_ok());
+        if !verified {
+            return Err(invalid("signature cannot be verified"));
+        }
+    } else {
+        warn!("OpenID Connect: Not checking the signature of the logout token as configured");
+    }
+
+    if claims.get("iss").and_then(Value::as_str) != Some(issuer) {
+        return Err(invalid("unexpected issuer"));
+    }
+
+    let audience_ok = match claims.get("aud") {
+        Some(Value::String(aud)) => aud == client_id,
+        Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(client_id)),
+        _ => false,
+    };
+    if !audience_ok {
+        return Err(invalid("unexpected audience"));
+    }
+
+    match claims.get("iat").and_then(Value::as_i64) {
+        Some(iat) if iat - LEEWAY_SECS <= now => {}
+        Some(_) => return Err(invalid("issued in the future")),
+        None => return Err(invalid("missing 'iat' claim")),
+    }
+
+    if let Some(exp) = claims.get("exp").and_then(Value::as_i64) {
+        if exp + LEEWAY_SECS <= now {
+            return Err(invalid("expired"));
+        }
+    }
+
+    let is_logout_event = claims
+        .get("events")
+        .and_then(Value::as_object)
+        .and_then(|events| events.get(BACKCHANNEL_LOGOUT_EVENT))
+        .map(Value::is_object)
+        .unwrap_or(false);
+    if !is_logout_event {
+        return Err(invalid("missing back-channel logout event"));
+    }
+
+    // A nonce is forbidden so that ID tokens cannot be used as logout tokens.
+    if claims.contains_key("nonce") {
+        return Err(invalid("unexpected 'nonce' claim"));
+    }
+
+    // A session ID identifies a single login session, only fall back to the
+    // subject if the provider does not tell us which session ended.
+    match (claims.get("sid"), claims.get("sub")) {
+        (Some(Value::String(sid)), _) => Ok(LogoutToken::Session(sid.clone())),
+        (None, Some(Value::String(sub))) => Ok(LogoutToken::Subject(sub.clone())),
+        _ => Err(invalid("missing 'sub' or 'sid' claim")),
+    }
+}
+
+fn decode_json(value: &str) -> Option<serde_json::Map<String, Value>> {
+    base64::decode_config(value, base64::URL_SAFE_NO_PAD)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use openssl::{hash::MessageDigest, pkey::PKey, rsa::Rsa, sign::Signer};
+    use serde_json::json;
+
+    use super::*;
+
+    const ISSUER: &str = "https://idp.example.com";
+    const CLIENT_ID: &str = "krill";
+    const NOW: i64 = 1_700_000_000;
+
+    fn b64(bytes: &[u8]) -> String {
+        base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+    }
+
+    fn signed(key: &PKey<openssl::pkey::Private>, claims: &Value) -> String {
+        let header = json!({"alg": "RS256", "kid": "key-1", "typ": "logout+jwt"});
+        let header = b64(header.to_string().as_bytes());
+        let payload = b64(claims.to_string().as_bytes());
+        let signing_input = format!("{}.{}", header, payload);
+
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(signing_input.as_bytes()).unwrap();
+        format!("{}.{}", signing_input, b64(&signer.sign_to_vec().unwrap()))
+    }
+
+    fn claims() -> Value {
+        json!({
+            "iss": ISSUER,
+            "aud": CLIENT_ID,
+            "iat": NOW,
+            "jti": "bWJq",
+            "sub": "248289761001",
+            "sid": "08a5019c-17e1-4977-8f42-65a12843ea02",
+            "events": { BACKCHANNEL_LOGOUT_EVENT: {} }
+        })
+    }
+
+    fn setup() -> (PKey<openssl::pkey::Private>, CoreJsonWebKeySet) {
+        let rsa = Rsa::generate(2048).unwrap();
+        let jwks = json!({
+            "keys": [{
+                "kty": "RSA",
+                "use": "sig",
+                "kid": "key-1",
+                "n": b64(&rsa.n().to_vec()),
+                "e": b64(&rsa.e().to_vec()),
+            }]
+        });
+        let keys = serde_json::from_value(jwks).unwrap();
+        (PKey::from_rsa(rsa).unwrap(), keys)
+    }
+
+    #[test]
+    fn accept_valid_logout_token() {
+        let (key, keys) = setup();
+
+        let token = validate_logout_token(&signed(&key, &claims()), &keys, ISSUER, CLIENT_ID, true, NOW).unwrap();
+        assert_eq!(
+            token,
+            LogoutToken::Session("08a5019c-17e1-4977-8f42-65a12843ea02".to_string())
+        );
+    }
+
+    #[test]
+    fn reject_invalid_logout_tokens() {
+        let (key, keys) = setup();
+        let (other_key, _) = setup();
+
+        let validate = |token: &str| validate_logout_token(token, &keys, ISSUER, CLIENT_ID, true, NOW);
+        let with = |name: &str, value: Value| {
+            let mut claims = claims();
+            claims[name] = value;
+            signed(&key, &claims)
+        };
+        let without = |name: &str| {
+            let mut claims = claims();
+            claims.as_object_mut().unwrap().remove(name);
+            signed(&key, &claims)
+        };
+
+        assert!(validate("not-a-jwt").is_err());
+        assert!(validate(&signed(&other_key, &claims())).is_err());
+        assert!(validate(&with("iss", json!("https://other.example.com"))).is_err());
+        assert!(validate(&with("aud", json!(["other"]))).is_err());
+        assert!(validate(&with("iat", json!(NOW + 3600))).is_err());
+        assert!(validate(&with("exp", json!(NOW - 3600))).is_err());
+        assert!(validate(&with("events", json!({}))).is_err());
+        assert!(validate(&with("nonce", json!("n-0S6_WzA2Mj"))).is_err());
+        assert!(validate(&without("iat")).is_err());
+
+        let mut claims = claims();
+        claims.as_object_mut().unwrap().remove("sub");
+        claims.as_object_mut().unwrap().remove("sid");
+        assert!(validate(&signed(&key, &claims)).is_err());
+
+        // Either a subject or a session ID will do
+        assert_eq!(
+            validate(&without("sid")).unwrap(),
+            LogoutToken::Subject("248289761001".to_string())
+        );
+        assert!(validate(&without("sub")).is_ok());
+    }
+}
@@ -105,6 +105,48 @@ fn make_resub_fn() -> Box<CustomFunction> {
     Box::new(CustomFunction::new(fn_signature, fn_impl))
 }
 
+/// Converts the result of a JMESPath search to an attribute value.
+///
+/// Strings are used as is, numbers and booleans are converted to a string.
+/// Arrays, e.g. a list of groups or of CA handles to use for the "inc_cas" or
+/// "exc_cas" attributes, are converted to a comma separated string of their
+/// (de-duplicated) scalar elements.
+///
+/// Returns None for null, for objects, and for values that are empty after
+/// trimming leading and trailing whitespace.
+pub fn to_attribute_value(value: &jmespath::Variable) -> Option<String> {
+    fn scalar(value: &jmespath::Variable) -> Option<String> {
+        let value = match value {
+            jmespath::Variable::String(str) => str.trim().to_string(),
+            jmespath::Variable::Number(num) => num.to_string(),
+            jmespath::Variable::Bool(b) => b.to_string(),
+            _ => return None,
+        };
+        if value.is_empty() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    match value {
+        jmespath::Variable::Array(items) => {
+            let mut values: Vec<String> = vec![];
+            for value in items.iter().filter_map(|item| scalar(item)) {
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+            if values.is_empty() {
+                None
+            } else {
+                Some(values.join(","))
+            }
+        }
+        _ => scalar(value),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,4 +194,33 @@ mod tests {
 
         assert!(should_also_yield_null.search(&jmespath_var).is_err());
     }
+
+    #[test]
+    fn array_results_should_become_comma_separated_values() {
+        let runtime = init_runtime();
+
+        let json_str = r#"
+        {
+            "groups": ["krill-ca-ca1", "staff", "krill-ca-ca2", "krill-ca-ca1"],
+            "realm_access": { "roles": ["readonly"] },
+            "level": 3
+        }
+        "#;
+        let jmespath_var = jmespath::Variable::from_json(json_str).unwrap();
+
+        let search = |expr: &str| {
+            let result = runtime.compile(expr).unwrap().search(&jmespath_var).unwrap();
+            to_attribute_value(&result)
+        };
+
+        assert_eq!(
+            search("map(&resub(@, '^krill-ca-', ''), groups[?starts_with(@, 'krill-ca-')])"),
+            Some("ca1,ca2".to_string())
+        );
+        assert_eq!(search("realm_access.roles"), Some("readonly".to_string()));
+        assert_eq!(search("level"), Some("3".to_string()));
+        assert_eq!(search("groups[?@ == 'idontexist']"), None);
+        assert_eq!(search("realm_access"), None);
+        assert_eq!(search("missing"), None);
+    }
 }
@@ -1,6 +1,7 @@
 #[macro_use]
 pub mod util;
 
+pub mod backchannel;
 pub mod config;
 pub mod httpclient;
 pub mod jmespathext;
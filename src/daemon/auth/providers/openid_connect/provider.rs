@@ -9,6 +9,7 @@
 //!   - [OpenID Connect Core 1.0 incorporating errata set 1][openid-connect-core-1_0]
 //!   - [OpenID Connect Discovery 1.0 incorporating errata set 1][openid-connect-discovery-1_0] (excluding WebFinger)
 //!   - [OpenID Connect RP-Initiated Logout 1.0 - draft 01][openid-connect-rpinitiated-1_0]
+//!   - [OpenID Connect Back-Channel Logout 1.0][openid-connect-backchannel-1_0]
 //!
 //! Compliant OpenID Connect 1.0 providers (OPs) MUST support:
 //!   - [OpenID Connect Discovery 1.0][openid-connect-discovery-1_0]
@@ -19,6 +20,7 @@
 //! [openid-connect-core-1_0]: https://openid.net/specs/openid-connect-core-1_0.html
 //! [openid-connect-discovery-1_0]: https://openid.net/specs/openid-connect-discovery-1_0.html
 //! [openid-connect-rpinitiated-1_0]: https://openid.net/specs/openid-connect-rpinitiated-1_0.html
+//! [openid-connect-backchannel-1_0]: https://openid.net/specs/openid-connect-backchannel-1_0.html
 
 use std::{
     collections::{
@@ -28,6 +30,7 @@ use std::{
     ops::Deref,
     path::Path,
     sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use tokio::sync::{RwLock, RwLockReadGuard};
@@ -39,12 +42,12 @@ use jmespath::ToJmespath;
 
 use openidconnect::{
     core::{
-        CoreAuthPrompt, CoreErrorResponseType, CoreIdTokenVerifier, CoreJwsSigningAlgorithm, CoreResponseMode,
-        CoreResponseType, CoreRevocableToken,
+        CoreAuthPrompt, CoreErrorResponseType, CoreIdTokenVerifier, CoreJsonWebKeySet, CoreJwsSigningAlgorithm,
+        CoreResponseMode, CoreResponseType, CoreRevocableToken,
     },
     AccessToken, AuthenticationFlow, AuthorizationCode, ClientId, ClientSecret, CsrfToken, IssuerUrl, Nonce,
-    OAuth2TokenResponse, RedirectUrl, RefreshToken, RequestTokenError, RevocationErrorResponseType, RevocationUrl,
-    Scope, UserInfoError,
+    OAuth2TokenResponse, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, RefreshToken, RequestTokenError,
+    RevocationErrorResponseType, RevocationUrl, Scope, UserInfoError,
 };
 
 use urlparse::{urlparse, GetQuery};
@@ -65,6 +68,7 @@ use crate::{
             },
             providers::config_file::config::ConfigUserDetails,
             providers::openid_connect::{
+                backchannel::{self, LogoutToken},
                 config::{
                     ConfigAuthOpenIDConnect, ConfigAuthOpenIDConnectClaim,
                     ConfigAuthOpenIDConnectClaimSource as ClaimSource, ConfigAuthOpenIDConnectClaims,
@@ -78,7 +82,7 @@ use crate::{
             },
             Auth, LoggedInUser,
         },
-        config::Config,
+        config::{AuthType, Config},
         http::{
             auth::{url_encode, AUTH_CALLBACK_ENDPOINT},
             HttpResponse,
@@ -92,8 +96,15 @@ use crate::{
 // See: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Set-Cookie#cookie_prefixes
 const NONCE_COOKIE_NAME: &str = "__Host-krill_login_nonce";
 const CSRF_COOKIE_NAME: &str = "__Host-krill_login_csrf_hash";
+const PKCE_COOKIE_NAME: &str = "__Host-krill_login_pkce_verifier";
 const LOGIN_SESSION_STATE_KEY_PATH: &str = "login_session_state.key"; // TODO: decide on proper location
 
+// The identity of the user and of the login session at the provider, kept with
+// the session secrets so that the session can be ended by a back-channel
+// logout.
+const SUBJECT_SECRET: &str = "sub";
+const SESSION_ID_SECRET: &str = "sid";
+
 #[allow(clippy::enum_variant_names)]
 enum TokenKind {
     AccessToken,
@@ -122,7 +133,6 @@ impl From<TokenKind> for &'static str {
 }
 enum LogoutMode {
     OAuth2TokenRevocation {
-        revocation_url: String,
         post_revocation_redirect_url: String,
     },
     OperatorProvidedLogout {
@@ -139,8 +149,11 @@ enum LogoutMode {
 
 pub struct ProviderConnectionProperties {
     client: FlexibleClient,
+    issuer: String,
+    signing_keys: CoreJsonWebKeySet,
     email_scope_supported: bool,
     userinfo_endpoint_supported: bool,
+    token_revocation_supported: bool,
     logout_mode: LogoutMode,
 }
 
@@ -176,13 +189,19 @@ impl OpenIDConnectAuthProvider {
     async fn initialize_connection(&self) -> KrillResult<ProviderConnectionProperties> {
         trace!("OpenID Connect: Initializing provider connection...");
         let meta = self.discover().await?;
-        let (email_scope_supported, userinfo_endpoint_supported, logout_mode) =
+        let (email_scope_supported, userinfo_endpoint_supported, revocation_url, logout_mode) =
             self.check_provider_capabilities(&meta)?;
-        let client = self.build_client(meta, &logout_mode)?;
+        let token_revocation_supported = revocation_url.is_some();
+        let issuer = meta.issuer().as_str().to_string();
+        let signing_keys = meta.jwks().clone();
+        let client = self.build_client(meta, revocation_url)?;
         let conn = ProviderConnectionProperties {
             client,
+            issuer,
+            signing_keys,
             email_scope_supported,
             userinfo_endpoint_supported,
+            token_revocation_supported,
             logout_mode,
         };
         trace!("OpenID Connect: Provider connection initialized");
@@ -225,7 +244,7 @@ impl OpenIDConnectAuthProvider {
 
     /// Verify that the OpenID Connect: discovery metadata indicates that the
     /// provider has support for the features that we require.
-    fn check_provider_capabilities(&self, meta: &WantedMeta) -> KrillResult<(bool, bool, LogoutMode)> {
+    fn check_provider_capabilities(&self, meta: &WantedMeta) -> KrillResult<(bool, bool, Option<String>, LogoutMode)> {
         // TODO: verify token_endpoint_auth_methods_supported?
         // TODO: verify response_types_supported?
         let mut ok = true;
@@ -313,8 +332,8 @@ impl OpenIDConnectAuthProvider {
         //   no	                | yes               | no               | Direct the user to the customer logout portal
         //                      |                   |                  | If redirected back to Krill, direct the user
         //                      |                   |                  | to the login page.
-        //   no	                | yes               | yes              | Behave as if only RP-Initiated Logout is
-        //                      |                   |                  | supported
+        //   no	                | yes               | yes              | Revoke the token then direct the user to the
+        //                      |                   |                  | customer logout portal
         //   yes                | no                | no               | Direct the user to the configured logout URL
         //   yes                | no                | yes              | Revoke the token then direct the user to the
         //                      |                   |                  | configured logout URL
@@ -323,6 +342,9 @@ impl OpenIDConnectAuthProvider {
         //   yes                | yes               | yes              | Behave as if only RP-Initiated Logout is NOT
         //                      |                   |                  | supported
         //   -------------------|-------------------|------------------|---------------------------------------------
+        //
+        // In other words: when the provider supports token revocation the token is always revoked on logout, as
+        // otherwise the refresh token held in the (client side) session token remains usable until it expires.
 
         let config_file_url = self.oidc_conf()?.logout_url.as_ref();
         let mut rp_initiated_logout_url = meta.additional_metadata().end_session_endpoint.as_ref();
@@ -359,8 +381,7 @@ impl OpenIDConnectAuthProvider {
 
         let logout_mode = match (config_file_url, rp_initiated_logout_url, revocation_url) {
             (None, None, None) => LogoutMode::ReturnToUI { url: service_uri },
-            (None, None, Some(rev_url)) => LogoutMode::OAuth2TokenRevocation {
-                revocation_url: rev_url.clone(),
+            (None, None, Some(_)) => LogoutMode::OAuth2TokenRevocation {
                 post_revocation_redirect_url: service_uri,
             },
             (None, Some(rpinit_url), _) => LogoutMode::RPInitiatedLogout {
@@ -370,21 +391,25 @@ impl OpenIDConnectAuthProvider {
             (Some(config_url), _, None) => LogoutMode::OperatorProvidedLogout {
                 operator_provided_logout_url: config_url.clone(),
             },
-            (Some(config_url), _, Some(rev_url)) => LogoutMode::OAuth2TokenRevocation {
-                revocation_url: rev_url.clone(),
+            (Some(config_url), _, Some(_)) => LogoutMode::OAuth2TokenRevocation {
                 post_revocation_redirect_url: config_url.clone(),
             },
         };
 
         match ok {
-            true => Ok((email_scope_supported, userinfo_endpoint_supported, logout_mode)),
+            true => Ok((
+                email_scope_supported,
+                userinfo_endpoint_supported,
+                revocation_url.cloned(),
+                logout_mode,
+            )),
             false => Err(Error::Custom(
                 "OpenID Connect: The provider lacks support for one or more required capabilities.".to_string(),
             )),
         }
     }
 
-    fn build_client(&self, meta: WantedMeta, logout_mode: &LogoutMode) -> KrillResult<FlexibleClient> {
+    fn build_client(&self, meta: WantedMeta, revocation_url: Option<String>) -> KrillResult<FlexibleClient> {
         // Read from config the credentials we should use to authenticate
         // ourselves with the identity provider. These details should have been
         // obtained by the Krill operator when they created a registration for
@@ -422,8 +447,8 @@ impl OpenIDConnectAuthProvider {
 
         let mut client = client.set_redirect_uri(redirect_uri);
 
-        if let LogoutMode::OAuth2TokenRevocation { revocation_url, .. } = logout_mode {
-            client = client.set_revocation_uri(RevocationUrl::new(revocation_url.to_owned())?);
+        if let Some(revocation_url) = revocation_url {
+            client = client.set_revocation_uri(RevocationUrl::new(revocation_url)?);
         }
 
         Ok(client)
@@ -553,10 +578,17 @@ impl OpenIDConnectAuthProvider {
 
         match token_response {
             Ok(token_response) => {
+                // Providers that rotate refresh tokens issue a new refresh token with every refresh, which replaces
+                // the one we used. Providers that don't rotate may leave it out, in which case we must keep the
+                // refresh token we have. The same goes for the ID token which is needed for RP-Initiated Logout.
+                let mut secrets = session.secrets.clone();
+                secrets.extend(secrets_from_token_response(&token_response));
+
                 let new_token_res = self.session_cache.encode(
+                    AuthType::OpenIDConnect.name(),
                     &session.id,
                     &session.attributes,
-                    secrets_from_token_response(&token_response),
+                    secrets,
                     &self.session_key,
                     token_response.expires_in(),
                 );
@@ -706,16 +738,10 @@ impl OpenIDConnectAuthProvider {
             })?;
             debug!("Search result in {:?}: '{:?}'", source, &result);
 
-            // Did the JMESPath search find a match?
-            if !matches!(*result, jmespath::Variable::Null) {
-                // Yes. Is it a JMESPath String type?
-                if let Some(result_str) = result.as_string() {
-                    // Yes. Is it non-empty after trimming leading and trailing whitespace?
-                    if !result_str.trim().is_empty() {
-                        // Yes
-                        return Ok(Some(result_str.clone()));
-                    }
-                }
+            // Did the JMESPath search find a usable value? Arrays, e.g. of
+            // groups or CA handles, are turned into a comma separated value.
+            if let Some(value) = jmespathext::to_attribute_value(&result) {
+                return Ok(Some(value));
             }
         }
 
@@ -804,13 +830,20 @@ impl OpenIDConnectAuthProvider {
                 if let Some(state) = query.get_first_from_str("state") {
                     if let Some(nonce) = self.extract_cookie(request, NONCE_COOKIE_NAME) {
                         if let Some(csrf_token_hash) = self.extract_cookie(request, CSRF_COOKIE_NAME) {
-                            trace!("OpenID Connect: Detected RFC-6749 section 4.1.2 redirected Authorization Response");
-                            return Some(Auth::authorization_code(
-                                Token::from(code),
-                                state,
-                                nonce,
-                                csrf_token_hash,
-                            ));
+                            if let Some(pkce_verifier) = self.extract_cookie(request, PKCE_COOKIE_NAME) {
+                                trace!(
+                                    "OpenID Connect: Detected RFC-6749 section 4.1.2 redirected Authorization Response"
+                                );
+                                return Some(Auth::authorization_code(
+                                    Token::from(code),
+                                    state,
+                                    nonce,
+                                    csrf_token_hash,
+                                    pkce_verifier,
+                                ));
+                            } else {
+                                debug!("OpenID Connect: Ignoring potential RFC-6749 section 4.1.2 redirected Authorization Response due to missing PKCE code verifier cookie.");
+                            }
                         } else {
                             debug!("OpenID Connect: Ignoring potential RFC-6749 section 4.1.2 redirected Authorization Response due to missing CSRF token hash cookie.");
                         }
@@ -872,13 +905,14 @@ impl OpenIDConnectAuthProvider {
         }
     }
 
-    async fn get_token_response(&self, code: Token) -> KrillResult<FlexibleTokenResponse> {
+    async fn get_token_response(&self, code: Token, pkce_verifier: String) -> KrillResult<FlexibleTokenResponse> {
         let lock_guard = self.get_connection().await?;
         let conn = lock_guard.deref().as_ref().unwrap(); // safe to unwrap as was tested in get_connection()
 
         let token_response: FlexibleTokenResponse = conn
             .client
             .exchange_code(AuthorizationCode::new(code.to_string()))
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier))
             .request_async(logging_http_client)
             .await
             .map_err(|e| {
@@ -1120,7 +1154,7 @@ impl OpenIDConnectAuthProvider {
             Some(token) => {
                 // see if we can decode, decrypt and deserialize the users token
                 // into a login session structure
-                let session = self.session_cache.decode(token, &self.session_key, true)?;
+                let session = self.session_cache.decode(token.clone(), &self.session_key, true)?;
                 let status = session.status();
 
                 // Token found in cache and active; all good, do an early return
@@ -1144,6 +1178,25 @@ impl OpenIDConnectAuthProvider {
                     }
                 }
 
+                // The client may send more requests with this token before it has picked up the refreshed token
+                // from our response to an earlier request. Don't refresh again, as with providers that rotate
+                // refresh tokens the refresh token in this session token has already been used up, and using it
+                // again could even make the provider revoke the whole login session. Instead just hand out the
+                // refreshed token again. Requests arriving while the token is being refreshed wait for that to
+                // finish.
+                let _refresh_lock = self.session_cache.refresh_lock(&token).await;
+                if let Some(new_token) = self.session_cache.replacement(&token) {
+                    trace!(
+                        "OpenID Connect: Token for user \"{}\" was already refreshed",
+                        &session.id
+                    );
+                    return Ok(Some(ActorDef::user(
+                        session.id,
+                        session.attributes,
+                        Some(Auth::Bearer(new_token)),
+                    )));
+                }
+
                 // Token needs refresh and we have a refresh token, try to refresh
                 let new_auth = match self.try_refresh_token(&session).await {
                     Ok(auth) => {
@@ -1151,6 +1204,9 @@ impl OpenIDConnectAuthProvider {
                             "OpenID Connect: Successfully refreshed token for user \"{}\"",
                             &session.id
                         );
+                        if let Auth::Bearer(new_token) = &auth {
+                            self.session_cache.replace(&token, new_token);
+                        }
                         auth
                     }
                     Err(err) => {
@@ -1224,8 +1280,9 @@ impl OpenIDConnectAuthProvider {
     /// Generate the login URL that the client should direct the end-user to so
     /// they can login with the operators chosen OpenID Connect: provider. The
     /// URL should be requested by the client on every login as the intention is
-    /// that it contains randomly generated CSFF token and nonce values which
-    /// can be used to protect against certain cross-site and replay attacks.
+    /// that it contains randomly generated CSFF token, nonce and PKCE code
+    /// challenge values which can be used to protect against certain
+    /// cross-site, replay and code injection attacks.
     pub async fn get_login_url(&self) -> KrillResult<HttpResponse> {
         // TODO: we probably should do some more work here to ensure we get the
         // proper security benefits of the CSRF token, currently we are
@@ -1358,7 +1415,14 @@ impl OpenIDConnectAuthProvider {
             request = request.add_scope(Scope::new("email".to_string()));
         }
 
-        // TODO: use request.set_pkce_challenge() ?
+        // Bind the authorization code to this login attempt using PKCE, see
+        // https://tools.ietf.org/html/rfc7636. The code verifier is kept in a
+        // cookie, like the nonce, and is sent along with the code when
+        // exchanging it for tokens, at which point the provider verifies it
+        // against the code challenge that we send here. Providers that do not
+        // support PKCE ignore the code challenge (RFC 7636 section 5).
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+        request = request.set_pkce_challenge(pkce_challenge);
 
         for scope in &oidc_conf.extra_login_scopes {
             request = request.add_scope(Scope::new(scope.clone()));
@@ -1417,6 +1481,10 @@ impl OpenIDConnectAuthProvider {
             SET_COOKIE,
             make_secure_cookie_value(CSRF_COOKIE_NAME, &csrf_token_hash_b64_str)?,
         );
+        res.headers_mut().append(
+            SET_COOKIE,
+            make_secure_cookie_value(PKCE_COOKIE_NAME, pkce_verifier.secret())?,
+        );
 
         Ok(HttpResponse::new(res))
     }
@@ -1438,6 +1506,7 @@ impl OpenIDConnectAuthProvider {
                 state,
                 nonce,
                 csrf_token_hash,
+                pkce_verifier,
             }) => {
                 // verify the CSRF "state" value by hashing it and comparing it to the value in the CSRF cookie
                 // TODO: use constant time comparison, e.g. as provided by the ring crate?
@@ -1453,7 +1522,7 @@ impl OpenIDConnectAuthProvider {
                 // ==========================================================================================
                 trace!("OpenID Connect: Submitting RFC-6749 section 4.1.3 Access Token Request");
 
-                let token_response = self.get_token_response(code).await?;
+                let token_response = self.get_token_response(code, pkce_verifier).await?;
 
                 // TODO: extract and keep the access token and refresh token so
                 // that we can extend the login session later. These are
@@ -1581,10 +1650,14 @@ impl OpenIDConnectAuthProvider {
                 // time of 1800 seconds or 30 minutes, so attempting to refresh
                 // an access token after that much time would also fail.
                 // ==========================================================================================
+                let mut secrets = secrets_from_token_response(&token_response);
+                secrets.extend(provider_session_ids(id_token_claims));
+
                 let api_token = self.session_cache.encode(
+                    AuthType::OpenIDConnect.name(),
                     &id,
                     &attributes,
-                    secrets_from_token_response(&token_response),
+                    secrets,
                     &self.session_key,
                     token_response.expires_in(),
                 )?;
@@ -1617,10 +1690,12 @@ impl OpenIDConnectAuthProvider {
     /// provider logout endpoint, including a post_logout_redirect_url which instructs the provider to redirect the user
     /// agent back to the Krill Lagosta web UI after logout is complete.
     ///
-    /// If instead the provider supports OAuth 2.0 Token Revocation then the trip via the user agent to the provider
+    /// If instead the provider only supports OAuth 2.0 Token Revocation then the trip via the user agent to the provider
     /// logout page is not possible, instead from the end-user's perspective they are returned to the Lagosta web UI
-    /// index page (which currently immediately redirects the user to the 3rd party OpenID Connect provider login page)
-    /// but before that Krill contacts the provider on the logged-in users behalf to revoke their token at the provider.
+    /// index page (which currently immediately redirects the user to the 3rd party OpenID Connect provider login page).
+    ///
+    /// Whenever the provider supports OAuth 2.0 Token Revocation, whatever the logout mode, Krill first contacts the
+    /// provider on the logged-in users behalf to revoke their token at the provider.
     pub async fn logout(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<HttpResponse> {
         // verify the bearer token indeed represents a logged-in Krill OpenID Connect provider session
         let token = httpclient::get_bearer_token(request).ok_or_else(|| {
@@ -1654,17 +1729,20 @@ impl OpenIDConnectAuthProvider {
         let lock_guard = self.get_connection().await?;
         let conn = lock_guard.deref().as_ref().unwrap(); // safe to unwrap as was tested in get_connection()
 
+        if conn.token_revocation_supported {
+            if let Err(err) = self.try_revoke_token(&session).await {
+                OpenIDConnectAuthProvider::internal_error(
+                    format!("Error while revoking token for user '{}'", session.id),
+                    Some(err.to_string()),
+                );
+            }
+        }
+
         let go_to_url = match &conn.logout_mode {
             LogoutMode::OAuth2TokenRevocation {
                 post_revocation_redirect_url,
-                ..
             } => {
-                if let Err(err) = self.try_revoke_token(&session).await {
-                    OpenIDConnectAuthProvider::internal_error(
-                        format!("Error while revoking token for user '{}'", session.id),
-                        Some(err.to_string()),
-                    );
-                }
+                trace!("OpenID Connect: Directing user to post token revocation URL");
                 post_revocation_redirect_url.clone()
             }
             LogoutMode::OperatorProvidedLogout {
@@ -1702,6 +1780,74 @@ impl OpenIDConnectAuthProvider {
         trace!("Telling Lagosta to direct the user to logout at: {}", &go_to_url);
         Ok(HttpResponse::text_no_cache(go_to_url.into()))
     }
+
+    /// End the login sessions that the provider asks us to end by sending a logout token directly to Krill, as per
+    /// OpenID Connect Back-Channel Logout 1.0, e.g. when the user logged out elsewhere or was deprovisioned.
+    ///
+    /// The logout token refers to all sessions of a user (by subject), or to a single login session at the provider
+    /// (by session ID). As the session state is held by the client, the matching sessions are remembered as ended by
+    /// the [LoginSessionCache] which then refuses them.
+    pub async fn backchannel_logout(&self, logout_token: &str) -> KrillResult<()> {
+        self.initialize_connection_if_needed().await.map_err(|err| {
+            OpenIDConnectAuthProvider::internal_error(
+                "OpenID Connect: Cannot process back-channel logout: Failed to connect to provider",
+                Some(&stringify_cause_chain(err)),
+            )
+        })?;
+
+        let oidc_conf = self.oidc_conf()?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let token = {
+            let lock_guard = self.get_connection().await?;
+            let conn = lock_guard.deref().as_ref().unwrap(); // safe to unwrap as was tested in get_connection()
+
+            backchannel::validate_logout_token(
+                logout_token,
+                &conn.signing_keys,
+                &conn.issuer,
+                &oidc_conf.client_id,
+                !oidc_conf.insecure,
+                now,
+            )?
+        };
+
+        let (secret, value) = match &token {
+            LogoutToken::Session(sid) => (SESSION_ID_SECRET, sid),
+            LogoutToken::Subject(sub) => (SUBJECT_SECRET, sub),
+        };
+
+        let cached = self.session_cache.revoke(secret, value)?;
+        info!(
+            "OpenID Connect: Ended login sessions with {} '{}' on request of the provider ({} cached)",
+            secret, value, cached
+        );
+
+        Ok(())
+    }
+}
+
+/// Returns the subject and, if the provider includes it, the ID of the login
+/// session at the provider. These are what a back-channel logout token refers
+/// to.
+fn provider_session_ids(id_token_claims: &FlexibleIdTokenClaims) -> HashMap<String, String> {
+    let mut ids: HashMap<String, String> = HashMap::new();
+
+    ids.insert(
+        SUBJECT_SECRET.to_string(),
+        id_token_claims.subject().as_str().to_string(),
+    );
+
+    if let Ok(serde_json::Value::Object(claims)) = serde_json::to_value(id_token_claims.additional_claims()) {
+        if let Some(sid) = claims.get(SESSION_ID_SECRET).and_then(|sid| sid.as_str()) {
+            ids.insert(SESSION_ID_SECRET.to_string(), sid.to_string());
+        }
+    }
+
+    ids
 }
 
 fn secrets_from_token_response(token_response: &FlexibleTokenResponse) -> HashMap<String, String> {
@@ -0,0 +1,208 @@
+//! Operator defined roles with per-CA permissions.
+//!
+//! Roles can be defined in the `[auth_roles]` section of the Krill config
+//! file, in addition to the roles built-in to the Polar policy (see
+//! `defaults/roles.polar`). E.g.:
+//!
+//! ```toml
+//! [auth_roles.roa-editor]
+//! permissions = ["CA_LIST", "CA_READ", "ROUTES_READ", "ROUTES_ANALYSIS"]
+//! cas = ["ca1", "ca2"]
+//! ca_permissions = { ca1 = ["ROUTES_UPDATE"] }
+//! ```
+//!
+//! Users are given such a role in the same way as a built-in role, i.e. by
+//! setting their "role" attribute in the `[auth_users]` section or by mapping
+//! an OpenID Connect claim to the "role" attribute.
+//!
+//! The [`RoleTable`] is registered as the Polar constant `ConfigRoles` so that
+//! the rules in `defaults/config-roles.polar` can query it.
+
+use std::collections::HashMap;
+
+use crate::daemon::auth::common::permissions::Permission;
+
+/// The names of the roles defined by the built-in Polar policy. These cannot
+/// be redefined in the config file.
+pub const BUILT_IN_ROLES: &[&str] = &[
+    "admin",
+    "readonly",
+    "readwrite",
+    "testbed",
+    "publish-only",
+    "roas-only",
+    "proposer",
+    "approver",
+];
+
+pub type ConfigAuthRoles = HashMap<String, ConfigAuthRole>;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ConfigAuthRole {
+    /// Permissions granted for actions that do not concern a specific CA, and
+    /// for all CAs the role has access to.
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
+
+    /// If set, restricts the permissions above to these CAs only.
+    #[serde(default)]
+    pub cas: Option<Vec<String>>,
+
+    /// Additional permissions granted on specific CAs only.
+    #[serde(default)]
+    pub ca_permissions: HashMap<String, Vec<Permission>>,
+}
+
+impl ConfigAuthRole {
+    /// Does this role grant the permission for actions that do not concern a
+    /// specific CA?
+    pub fn grants(&self, action: &Permission) -> bool {
+        self.permissions.contains(action)
+    }
+
+    /// Does this role grant the permission on the given CA?
+    pub fn grants_on_ca(&self, action: &Permission, ca: &str) -> bool {
+        let ca_allowed = match &self.cas {
+            Some(cas) => cas.iter().any(|allowed| allowed == ca),
+            None => true,
+        };
+
+        (ca_allowed && self.grants(action))
+            || self
+                .ca_permissions
+                .get(ca)
+                .map(|permissions| permissions.contains(action))
+                .unwrap_or(false)
+    }
+}
+
+//------------ RoleTable -----------------------------------------------------
+
+/// Lookup table of the roles defined in the config file, for use by the Polar
+/// policy.
+#[derive(Clone, Debug, Default)]
+pub struct RoleTable {
+    roles: ConfigAuthRoles,
+}
+
+impl RoleTable {
+    pub fn new(roles: ConfigAuthRoles) -> Self {
+        RoleTable { roles }
+    }
+
+    pub fn is_defined(&self, role: &str) -> bool {
+        self.roles.contains_key(role)
+    }
+
+    pub fn grants(&self, role: &str, action: &Permission) -> bool {
+        self.roles.get(role).map(|r| r.grants(action)).unwrap_or(false)
+    }
+
+    pub fn grants_on_ca(&self, role: &str, action: &Permission, ca: &str) -> bool {
+        self.roles
+            .get(role)
+            .map(|r| r.grants_on_ca(action, ca))
+            .unwrap_or(false)
+    }
+}
+
+impl oso::PolarClass for RoleTable {
+    fn get_polar_class() -> oso::Class {
+        Self::get_polar_class_builder()
+            .add_method("is_defined", |table: &RoleTable, role: String| table.is_defined(&role))
+            .add_method("grants", |table: &RoleTable, role: String, action: Permission| {
+                table.grants(&role, &action)
+            })
+            .add_method(
+                "grants_on_ca",
+                |table: &RoleTable, role: String, action: Permission, ca: String| {
+                    table.grants_on_ca(&role, &action, &ca)
+                },
+            )
+            .build()
+    }
+
+    fn get_polar_class_builder() -> oso::ClassBuilder<Self> {
+        oso::Class::builder()
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use super::*;
+
+    use crate::{
+        commons::actor::{Actor, ActorDef},
+        daemon::{
+            auth::{policy::AuthPolicy, Handle},
+            config::Config,
+        },
+        test,
+    };
+
+    fn roa_editor() -> ConfigAuthRole {
+        toml::from_str(
+            r#"
+            permissions = ["CA_LIST", "CA_READ", "ROUTES_READ"]
+            cas = ["ca1", "ca2"]
+            ca_permissions = { ca1 = ["ROUTES_UPDATE"], ca3 = ["CA_READ"] }
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn role_grants_permissions_per_ca() {
+        let role = roa_editor();
+
+        assert!(role.grants(&Permission::CA_LIST));
+        assert!(!role.grants(&Permission::CA_CREATE));
+
+        assert!(role.grants_on_ca(&Permission::CA_READ, "ca1"));
+        assert!(role.grants_on_ca(&Permission::CA_READ, "ca2"));
+        assert!(role.grants_on_ca(&Permission::CA_READ, "ca3"));
+        assert!(!role.grants_on_ca(&Permission::CA_READ, "ca4"));
+
+        assert!(role.grants_on_ca(&Permission::ROUTES_UPDATE, "ca1"));
+        assert!(!role.grants_on_ca(&Permission::ROUTES_UPDATE, "ca2"));
+        assert!(!role.grants_on_ca(&Permission::ROUTES_READ, "ca3"));
+        assert!(!role.grants_on_ca(&Permission::CA_DELETE, "ca1"));
+    }
+
+    #[test]
+    fn policy_enforces_config_roles() {
+        let data_dir = test::tmp_dir();
+        let mut config = Config::test(&data_dir, false, false, false, false);
+        config.auth_roles.insert("roa-editor".to_string(), roa_editor());
+        let policy = AuthPolicy::new(Arc::new(config)).unwrap();
+
+        let mut attributes = HashMap::new();
+        attributes.insert("role".to_string(), "roa-editor".to_string());
+        let actor = Actor::new(ActorDef::user("joe".to_string(), attributes, None), policy);
+
+        let ca = |name: &str| Handle::from_str(name).unwrap();
+
+        assert!(actor
+            .is_allowed(Permission::LOGIN, crate::constants::NO_RESOURCE)
+            .unwrap());
+        assert!(actor
+            .is_allowed(Permission::CA_LIST, crate::constants::NO_RESOURCE)
+            .unwrap());
+        assert!(!actor
+            .is_allowed(Permission::CA_CREATE, crate::constants::NO_RESOURCE)
+            .unwrap());
+
+        assert!(actor.is_allowed(Permission::CA_READ, ca("ca1")).unwrap());
+        assert!(actor.is_allowed(Permission::CA_READ, ca("ca3")).unwrap());
+        assert!(!actor.is_allowed(Permission::CA_READ, ca("ca4")).unwrap());
+        assert!(actor.is_allowed(Permission::ROUTES_UPDATE, ca("ca1")).unwrap());
+        assert!(!actor.is_allowed(Permission::ROUTES_UPDATE, ca("ca2")).unwrap());
+        assert!(!actor.is_allowed(Permission::CA_DELETE, ca("ca1")).unwrap());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+}
@@ -0,0 +1,605 @@
+//! Backups of all Krill state, and restoring them.
+//!
+//! A backup contains the content of all key value stores used by Krill, the
+//! keys kept by the OpenSSL based signer, or the references to keys kept in
+//! an HSM, and a fingerprint of the configuration settings that determine
+//! the identity of this Krill instance. Backups can be made while the server
+//! keeps running. The aggregate and WAL stores are exported while all their
+//! instances are locked, so that each exported store is consistent. Small
+//! differences between stores, e.g. objects of a CA which were not yet
+//! published, are resolved by the synchronisation Krill does at startup.
+//!
+//! Backups are written as gzip compressed json, one line per exported value,
+//! to the `backups` directory under the data directory. They are streamed to
+//! a temporary file, which is renamed when the backup is complete. The
+//! SHA-256 checksum of each backup is kept in an accompanying info file.
+//!
+//! A backup cannot safely be restored while the server is running. Instead a
+//! restore is staged, after verifying the checksum and that the backup was
+//! made by this version of Krill, and it is applied when Krill is started.
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commons::{
+        api::{BackupInfo, BackupList, Timestamp},
+        error::{Error, KrillIoError},
+        eventsourcing::{KeyValueEntry, KeyValueError, KeyValueStorage, KeyValueStore},
+        util::{file, KrillVersion},
+        KrillResult,
+    },
+    constants::{
+        API_TOKENS_DIR, BACKUP_DIR, CASERVER_DIR, CA_OBJECTS_DIR, JOBS_DIR, KEYS_DIR, PUBSERVER_CONTENT_DIR,
+        PUBSERVER_DIR, SERIALS_DIR, SIGNERS_DIR, STATUS_DIR, TA_PROXY_SERVER_DIR, TA_SIGNER_SERVER_DIR,
+    },
+    daemon::config::Config,
+};
+
+/// The name spaces kept in the configured storage.
+const STORAGE_NAME_SPACES: &[&str] = &[
+    CASERVER_DIR,
+    TA_PROXY_SERVER_DIR,
+    TA_SIGNER_SERVER_DIR,
+    PUBSERVER_DIR,
+    PUBSERVER_CONTENT_DIR,
+    JOBS_DIR,
+    API_TOKENS_DIR,
+];
+
+/// The other name spaces, which are kept on disk in the data directory.
+/// Except for the CA objects, which are kept in the configured storage in
+/// high availability mode.
+const OTHER_NAME_SPACES: &[&str] = &[CA_OBJECTS_DIR, STATUS_DIR, SIGNERS_DIR, SERIALS_DIR];
+
+const PENDING_RESTORE: &str = "restore-pending.json";
+
+/// The number of values which are imported at a time when a backup is
+/// restored.
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+type BackupLines = io::Lines<BufReader<libflate::gzip::Decoder<BufReader<File>>>>;
+
+//------------ BackupHeader --------------------------------------------------
+
+/// The first line of a backup.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct BackupHeader {
+    krill_version: KrillVersion,
+    created: Timestamp,
+    config_fingerprint: String,
+}
+
+//------------ BackupRecord --------------------------------------------------
+
+/// The lines in a backup which follow its header.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BackupRecord {
+    /// A key and value exported from the store for a name space.
+    Entry { name_space: String, entry: KeyValueEntry },
+
+    /// A key file of the OpenSSL based signer.
+    Key(BackupKey),
+}
+
+//------------ BackupKey -----------------------------------------------------
+
+/// A key file of the OpenSSL based signer, with base64 encoded content.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct BackupKey {
+    name: String,
+    content: String,
+}
+
+//------------ PendingRestore ------------------------------------------------
+
+/// A staged restore, which is applied when Krill is started.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct PendingRestore {
+    name: String,
+    force: bool,
+}
+
+//------------ HashingWriter -------------------------------------------------
+
+/// Passes all data on to the inner writer, and keeps the SHA-256 and size
+/// of the data that was written.
+struct HashingWriter<W> {
+    inner: W,
+    sha256: openssl::sha::Sha256,
+    size: u64,
+}
+
+impl<W> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            sha256: openssl::sha::Sha256::new(),
+            size: 0,
+        }
+    }
+
+    /// Returns the inner writer, and the hex encoded SHA-256 and the size of
+    /// the data that was written.
+    fn finish(self) -> (W, String, u64) {
+        (self.inner, hex::encode(self.sha256.finish()), self.size)
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.sha256.update(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+//------------ BackupWriter --------------------------------------------------
+
+/// Writes a new backup to a temporary file, see [BackupManager::start]. The
+/// temporary file is removed if the backup is dropped before it is finished.
+pub struct BackupWriter {
+    name: String,
+    created: Timestamp,
+    tmp_path: PathBuf,
+
+    // Only taken when the backup is finished.
+    encoder: Option<libflate::gzip::Encoder<HashingWriter<BufWriter<File>>>>,
+}
+
+impl BackupWriter {
+    /// Adds a key and value exported from the store for a name space.
+    pub fn add_entry(&mut self, name_space: &str, entry: KeyValueEntry) -> Result<(), KeyValueError> {
+        self.write_line(&BackupRecord::Entry {
+            name_space: name_space.to_string(),
+            entry,
+        })
+    }
+
+    fn write_line<T: Serialize>(&mut self, line: &T) -> Result<(), KeyValueError> {
+        let tmp_path = &self.tmp_path;
+        let encoder = self.encoder.as_mut().unwrap();
+        serde_json::to_writer(&mut *encoder, line)?;
+        encoder
+            .write_all(b"\n")
+            .map_err(|e| KrillIoError::new(format!("Could not write backup: {}", tmp_path.to_string_lossy()), e))?;
+        Ok(())
+    }
+
+    /// Completes the backup and moves it to the given path. Returns the
+    /// SHA-256 and the size of the backup.
+    fn complete(mut self, path: &Path) -> Result<(String, u64), KrillIoError> {
+        let tmp_path = self.tmp_path.clone();
+        let err = |e| KrillIoError::new(format!("Could not write backup: {}", tmp_path.to_string_lossy()), e);
+
+        let encoder = self.encoder.take().unwrap();
+        let (writer, checksum, size) = encoder.finish().into_result().map_err(err)?.finish();
+        let file = writer.into_inner().map_err(|e| err(e.into_error()))?;
+        file.sync_all().map_err(err)?;
+
+        fs::rename(&tmp_path, path).map_err(err)?;
+        Ok((checksum, size))
+    }
+}
+
+impl Drop for BackupWriter {
+    fn drop(&mut self) {
+        if self.tmp_path.exists() {
+            let _ = fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+//------------ BackupManager -------------------------------------------------
+
+pub struct BackupManager {
+    data_dir: PathBuf,
+    storage: KeyValueStorage,
+    ca_objects_storage: KeyValueStorage,
+    config_fingerprint: String,
+}
+
+impl BackupManager {
+    pub fn new(config: &Config) -> Self {
+        BackupManager {
+            data_dir: config.data_dir.clone(),
+            storage: config.storage(),
+            ca_objects_storage: config.ca_objects_storage(),
+            config_fingerprint: Self::config_fingerprint(config),
+        }
+    }
+
+    /// Returns the SHA-256 over the configuration settings which determine
+    /// the identity of this Krill instance, as a hex string.
+    fn config_fingerprint(config: &Config) -> String {
+        let storage = match config.storage() {
+            KeyValueStorage::Disk(_) => "disk",
+            KeyValueStorage::Postgres(_) => "postgres",
+        };
+        let signers: Vec<&str> = config.signers.iter().map(|signer| signer.name.as_str()).collect();
+
+        let settings = format!("{}\n{}\n{}", config.service_uri(), storage, signers.join(","));
+        hex::encode(openssl::sha::sha256(settings.as_bytes()))
+    }
+
+    fn storage_for(&self, name_space: &str) -> KeyValueStorage {
+        if name_space == CA_OBJECTS_DIR {
+            self.ca_objects_storage.clone()
+        } else if STORAGE_NAME_SPACES.contains(&name_space) {
+            self.storage.clone()
+        } else {
+            KeyValueStorage::Disk(self.data_dir.clone())
+        }
+    }
+
+    /// Returns the store for a name space, unless it is kept on disk and its
+    /// directory does not exist.
+    fn existing_store(&self, name_space: &str) -> KrillResult<Option<KeyValueStore>> {
+        let storage = self.storage_for(name_space);
+        if let KeyValueStorage::Disk(dir) = &storage {
+            if !dir.join(name_space).exists() {
+                return Ok(None);
+            }
+        }
+        Ok(Some(KeyValueStore::create(&storage, name_space)?))
+    }
+
+    /// Returns all stores of this Krill instance which exist, by name space.
+    pub fn existing_stores(&self) -> KrillResult<Vec<(&'static str, KeyValueStore)>> {
+        let mut stores = vec![];
+        for name_space in STORAGE_NAME_SPACES.iter().chain(OTHER_NAME_SPACES) {
+            if let Some(kv) = self.existing_store(name_space)? {
+                stores.push((*name_space, kv));
+            }
+        }
+        Ok(stores)
+    }
+
+    fn backup_dir(&self) -> PathBuf {
+        self.data_dir.join(BACKUP_DIR)
+    }
+
+    fn backup_path(&self, name: &str) -> PathBuf {
+        self.backup_dir().join(format!("{}.json.gz", name))
+    }
+
+    fn info_path(&self, name: &str) -> PathBuf {
+        self.backup_dir().join(format!("{}.info.json", name))
+    }
+
+    fn pending_restore_path(&self) -> PathBuf {
+        self.backup_dir().join(PENDING_RESTORE)
+    }
+}
+
+/// # Create and list backups
+impl BackupManager {
+    /// Starts a new backup. The stores of the CAs and the Publication Server
+    /// should be exported to it, after which it is completed with
+    /// [BackupManager::finish].
+    pub fn start(&self) -> KrillResult<BackupWriter> {
+        let created = Timestamp::now();
+        let name = format!("backup-{}", i64::from(created));
+        if self.info_path(&name).exists() {
+            return Err(Error::custom(format!("Backup '{}' already exists", name)));
+        }
+
+        // Creating the temporary file fails if another backup with the same
+        // name is being made.
+        file::create_dir_all(&self.backup_dir())?;
+        let tmp_path = self.backup_dir().join(format!("{}.json.gz.tmp", name));
+        let tmp_file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+            .map_err(|e| KrillIoError::new(format!("Could not create backup: {}", tmp_path.to_string_lossy()), e))?;
+
+        let encoder = libflate::gzip::Encoder::new(HashingWriter::new(BufWriter::new(tmp_file)))
+            .map_err(|e| KrillIoError::new(format!("Could not create backup: {}", tmp_path.to_string_lossy()), e))?;
+
+        let mut backup = BackupWriter {
+            name,
+            created,
+            tmp_path,
+            encoder: Some(encoder),
+        };
+        backup.write_line(&BackupHeader {
+            krill_version: KrillVersion::code_version(),
+            created,
+            config_fingerprint: self.config_fingerprint.clone(),
+        })?;
+
+        Ok(backup)
+    }
+
+    /// Finishes a backup. The other stores, and the keys of the OpenSSL
+    /// signer, are added here. The backup is only listed once it is
+    /// complete.
+    pub fn finish(&self, mut backup: BackupWriter) -> KrillResult<BackupInfo> {
+        for name_space in OTHER_NAME_SPACES {
+            if let Some(kv) = self.existing_store(name_space)? {
+                kv.export_each(None, &mut |entry| backup.add_entry(name_space, entry))?;
+            }
+        }
+        self.export_keys(&mut backup)?;
+
+        let name = backup.name.clone();
+        let created = backup.created;
+        let (checksum, size) = backup.complete(&self.backup_path(&name))?;
+
+        let info = BackupInfo::new(
+            name,
+            created,
+            KrillVersion::code_version(),
+            self.config_fingerprint.clone(),
+            checksum,
+            size,
+        );
+
+        let info_path = self.info_path(info.name());
+        let info_tmp_path = self.backup_dir().join(format!("{}.info.json.tmp", info.name()));
+        file::save_json(&info, &info_tmp_path)?;
+        fs::rename(&info_tmp_path, &info_path)
+            .map_err(|e| KrillIoError::new(format!("Could not save: {}", info_path.to_string_lossy()), e))?;
+
+        info!("Created backup: {}", info);
+        Ok(info)
+    }
+
+    /// Returns all backups, oldest first.
+    pub fn list(&self) -> KrillResult<BackupList> {
+        let mut backups = vec![];
+
+        let dir = self.backup_dir();
+        if dir.exists() {
+            let entries = fs::read_dir(&dir)
+                .map_err(|e| KrillIoError::new(format!("Could not read dir: {}", dir.to_string_lossy()), e))?;
+            for entry in entries {
+                let entry = entry
+                    .map_err(|e| KrillIoError::new(format!("Could not read dir: {}", dir.to_string_lossy()), e))?;
+                if entry.file_name().to_string_lossy().ends_with(".info.json") {
+                    backups.push(file::load_json::<BackupInfo>(&entry.path())?);
+                }
+            }
+        }
+
+        backups.sort_by_key(|info| info.created());
+        Ok(BackupList::new(backups))
+    }
+
+    fn export_keys(&self, backup: &mut BackupWriter) -> KrillResult<()> {
+        let dir = self.data_dir.join(KEYS_DIR);
+        if dir.exists() {
+            let entries = fs::read_dir(&dir)
+                .map_err(|e| KrillIoError::new(format!("Could not read dir: {}", dir.to_string_lossy()), e))?;
+            for entry in entries {
+                let entry = entry
+                    .map_err(|e| KrillIoError::new(format!("Could not read dir: {}", dir.to_string_lossy()), e))?;
+                if entry.path().is_file() {
+                    backup.write_line(&BackupRecord::Key(BackupKey {
+                        name: entry.file_name().to_string_lossy().to_string(),
+                        content: base64::encode(file::read(&entry.path())?),
+                    }))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// # Restore backups
+impl BackupManager {
+    /// Stages the restore of a backup, which will be applied when Krill is
+    /// next started. The backup must match its checksum, and it must have
+    /// been made by this version of Krill, for this configuration, unless
+    /// the restore is forced.
+    pub fn stage_restore(&self, name: &str, force: bool) -> KrillResult<BackupInfo> {
+        let (info, _) = self.open(name, force)?;
+        file::save_json(
+            &PendingRestore {
+                name: name.to_string(),
+                force,
+            },
+            &self.pending_restore_path(),
+        )?;
+
+        warn!(
+            "Staged restore of backup '{}', it will be applied when Krill is restarted",
+            name
+        );
+        Ok(info)
+    }
+
+    /// Applies a staged restore, if there is one. This must be called before
+    /// any of the stores are used. If Krill stops while the restore is being
+    /// applied, it is applied again from the start when Krill is started.
+    pub fn apply_pending_restore(&self) -> KrillResult<()> {
+        let pending_path = self.pending_restore_path();
+        if !pending_path.exists() {
+            return Ok(());
+        }
+
+        let pending: PendingRestore = file::load_json(&pending_path)?;
+        let (info, lines) = self.open(&pending.name, pending.force)?;
+
+        warn!("Restoring backup: {}", info);
+
+        for (_, kv) in self.existing_stores()? {
+            kv.wipe()?;
+        }
+
+        let keys_dir = self.data_dir.join(KEYS_DIR);
+        if keys_dir.exists() {
+            file::remove_dir_all(&keys_dir)?;
+        }
+        file::create_dir_all(&keys_dir)?;
+
+        let mut name_space = String::new();
+        let mut entries = vec![];
+
+        for line in lines {
+            let line = line.map_err(|e| KrillIoError::new(format!("Could not read backup '{}'", info.name()), e))?;
+            match serde_json::from_str::<BackupRecord>(&line).map_err(Error::JsonError)? {
+                BackupRecord::Entry {
+                    name_space: entry_name_space,
+                    entry,
+                } => {
+                    if entry_name_space != name_space || entries.len() >= IMPORT_BATCH_SIZE {
+                        self.import(&name_space, &entries)?;
+                        entries.clear();
+                        name_space = entry_name_space;
+                    }
+                    entries.push(entry);
+                }
+                BackupRecord::Key(key) => {
+                    // Keys are named after their (hex) key identifier.
+                    if !key.name.chars().all(|c| c.is_ascii_alphanumeric()) {
+                        return Err(Error::custom(format!("Invalid key name '{}' in backup", key.name)));
+                    }
+                    let content = base64::decode(&key.content)
+                        .map_err(|e| Error::custom(format!("Cannot decode key '{}' in backup: {}", key.name, e)))?;
+                    file::save(&content, &keys_dir.join(&key.name))?;
+                }
+            }
+        }
+        self.import(&name_space, &entries)?;
+
+        file::delete_file(&pending_path)?;
+
+        info!("Restored backup '{}'", info.name());
+        Ok(())
+    }
+
+    fn import(&self, name_space: &str, entries: &[KeyValueEntry]) -> KrillResult<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        if !STORAGE_NAME_SPACES.contains(&name_space) && !OTHER_NAME_SPACES.contains(&name_space) {
+            return Err(Error::custom(format!("Unknown store '{}' in backup", name_space)));
+        }
+
+        let kv = KeyValueStore::create(&self.storage_for(name_space), name_space)?;
+        kv.import(entries)?;
+        Ok(())
+    }
+
+    /// Opens and verifies a backup. Returns its info, and its lines after
+    /// the header.
+    fn open(&self, name: &str, force: bool) -> KrillResult<(BackupInfo, BackupLines)> {
+        let valid_name = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        let info_path = self.info_path(name);
+        if !valid_name || !info_path.exists() {
+            return Err(Error::custom(format!("Unknown backup '{}'", name)));
+        }
+        let info: BackupInfo = file::load_json(&info_path)?;
+
+        let path = self.backup_path(name);
+        let err = |e| KrillIoError::new(format!("Could not read backup: {}", path.to_string_lossy()), e);
+
+        let mut hasher = HashingWriter::new(io::sink());
+        io::copy(&mut File::open(&path).map_err(err)?, &mut hasher).map_err(err)?;
+        let (_, checksum, _) = hasher.finish();
+        if checksum != info.checksum() {
+            return Err(Error::custom(format!("Backup '{}' does not match its checksum", name)));
+        }
+
+        let decoder = libflate::gzip::Decoder::new(BufReader::new(File::open(&path).map_err(err)?)).map_err(err)?;
+        let mut lines = BufReader::new(decoder).lines();
+        let header = lines
+            .next()
+            .ok_or_else(|| Error::custom(format!("Backup '{}' is empty", name)))?
+            .map_err(err)?;
+        let header: BackupHeader = serde_json::from_str(&header).map_err(Error::JsonError)?;
+
+        let code_version = KrillVersion::code_version();
+        if header.krill_version != code_version && !force {
+            return Err(Error::custom(format!(
+                "Backup '{}' was made by Krill {}, this is Krill {}. Use force to restore it anyway.",
+                name, header.krill_version, code_version
+            )));
+        }
+        if header.config_fingerprint != self.config_fingerprint && !force {
+            return Err(Error::custom(format!(
+                "Backup '{}' was made for a different configuration. Use force to restore it anyway.",
+                name
+            )));
+        }
+
+        Ok((info, lines))
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::{commons::eventsourcing::KeyStoreKey, test};
+
+    #[test]
+    fn create_and_restore_backup() {
+        test::test_under_tmp(|d| {
+            let config = Config::test(&d, false, false, false, false);
+            let manager = BackupManager::new(&config);
+
+            let status = KeyValueStore::disk(&d, STATUS_DIR).unwrap();
+            let key = KeyStoreKey::scoped("ca".to_string(), "status.json".to_string());
+            status.store(&key, &"before").unwrap();
+
+            let info = manager.finish(manager.start().unwrap()).unwrap();
+            assert_eq!(manager.list().unwrap().backups(), &vec![info.clone()]);
+
+            status.store(&key, &"after").unwrap();
+
+            manager.stage_restore(info.name(), false).unwrap();
+            manager.apply_pending_restore().unwrap();
+
+            assert_eq!(status.get::<String>(&key).unwrap(), Some("before".to_string()));
+            assert!(!manager.pending_restore_path().exists());
+        })
+    }
+
+    #[test]
+    fn refuse_corrupt_backup() {
+        test::test_under_tmp(|d| {
+            let config = Config::test(&d, false, false, false, false);
+            let manager = BackupManager::new(&config);
+
+            let info = manager.finish(manager.start().unwrap()).unwrap();
+            file::save(b"corrupt", &manager.backup_path(info.name())).unwrap();
+
+            assert!(manager.stage_restore(info.name(), true).is_err());
+        })
+    }
+
+    #[test]
+    fn drop_unfinished_backup() {
+        test::test_under_tmp(|d| {
+            let config = Config::test(&d, false, false, false, false);
+            let manager = BackupManager::new(&config);
+
+            let mut backup = manager.start().unwrap();
+            let key = KeyStoreKey::simple("value.json".to_string());
+            backup
+                .add_entry(STATUS_DIR, KeyValueEntry::new(key, serde_json::json!("value")))
+                .unwrap();
+            drop(backup);
+
+            assert!(manager.list().unwrap().backups().is_empty());
+            assert_eq!(fs::read_dir(manager.backup_dir()).unwrap().count(), 0);
+        })
+    }
+}
@@ -6,12 +6,13 @@
 //! https://datatracker.ietf.org/doc/draft-ietf-sidrops-aspa-verification/
 //!
 
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt, fmt::Debug};
 
 use rpki::{
-    ca::publication::Base64,
+    ca::{provisioning::ResourceClassName, publication::Base64},
     repository::{
         aspa::{Aspa, AspaBuilder},
+        resources::ResourceSet,
         sigobj::SignedObjectBuilder,
         x509::{Serial, Time, Validity},
     },
@@ -21,9 +22,10 @@ use rpki::{
 
 use crate::{
     commons::{
-        api::{AspaCustomer, AspaDefinition, AspaProvidersUpdate, ObjectName},
+        api::{AspaCustomer, AspaDefinition, AspaProvidersUpdate, Divergence, DivergenceObject, ObjectName},
         crypto::KrillSigner,
         error::Error,
+        util::clock,
         KrillResult,
     },
     daemon::{
@@ -52,10 +54,15 @@ pub fn make_aspa_object(
         let aspa_uri = incoming_cert.uri_for_name(&name);
         let ca_issuer = incoming_cert.uri().clone();
 
-        let mut object_builder =
-            SignedObjectBuilder::new(signer.random_serial()?, validity, crl_uri, ca_issuer, aspa_uri);
+        let mut object_builder = SignedObjectBuilder::new(
+            signer.next_serial(certified_key.key_id())?,
+            validity,
+            crl_uri,
+            ca_issuer,
+            aspa_uri,
+        );
         object_builder.set_issuer(Some(incoming_cert.subject().clone()));
-        object_builder.set_signing_time(Some(Time::now()));
+        object_builder.set_signing_time(Some(clock::now()));
 
         object_builder
     };
@@ -213,7 +220,7 @@ impl AspaObjects {
 
         for aspa in self.0.values() {
             let renew = renew_threshold
-                .map(|threshold| aspa.expires() < threshold)
+                .map(|threshold| issuance_timing.renew_due(&aspa.customer(), aspa.expires(), threshold))
                 .unwrap_or(true); // always renew if no threshold is specified
 
             if renew {
@@ -227,6 +234,61 @@ impl AspaObjects {
         Ok(updates)
     }
 
+    /// Returns the hashes of all current ASPA objects.
+    pub fn hashes(&self) -> impl Iterator<Item = Hash> + '_ {
+        self.0.values().map(|aspa| aspa.hash())
+    }
+
+    /// Adds the divergences between the ASPA definitions for customers in
+    /// the given resources, and the ASPA objects which were issued.
+    pub fn divergences(
+        &self,
+        all_aspa_defs: &AspaDefinitions,
+        resources: &ResourceSet,
+        divergences: &mut Vec<Divergence>,
+    ) {
+        let now = Time::now();
+
+        for relevant_aspa in all_aspa_defs
+            .all()
+            .filter(|aspa| resources.contains_asn(aspa.customer()))
+        {
+            let customer = relevant_aspa.customer();
+            match self.0.get(&customer) {
+                None => divergences.push(Divergence::missing(
+                    DivergenceObject::Aspa,
+                    customer,
+                    "the definition is configured, but no ASPA was issued",
+                )),
+                Some(existing) if existing.definition() != relevant_aspa => divergences.push(Divergence::stale(
+                    DivergenceObject::Aspa,
+                    customer,
+                    format!(
+                        "the ASPA was issued for '{}', but the definition is '{}'",
+                        existing.definition(),
+                        relevant_aspa
+                    ),
+                )),
+                Some(existing) if existing.expires() < now => divergences.push(Divergence::stale(
+                    DivergenceObject::Aspa,
+                    customer,
+                    format!("the ASPA expired at {}", existing.expires().to_rfc3339()),
+                )),
+                Some(_) => {}
+            }
+        }
+
+        for customer in self.0.keys() {
+            if !all_aspa_defs.has(*customer) || !resources.contains_asn(*customer) {
+                divergences.push(Divergence::orphaned(
+                    DivergenceObject::Aspa,
+                    customer,
+                    "an ASPA was issued, but the customer is not configured or not held",
+                ));
+            }
+        }
+    }
+
     pub fn updated(&mut self, updates: AspaObjectsUpdates) {
         let (updated, removed) = updates.unpack();
         for aspa_info in updated {
@@ -316,3 +378,78 @@ impl AspaInfo {
         self.hash
     }
 }
+
+//------------ AspaUpdateDryRun --------------------------------------------
+
+/// The outcome of an [`AspaProvidersUpdate`] that was evaluated, but not
+/// applied. Contains the resulting definition for the customer, if any
+/// would remain, and the ASPA objects that would be issued, replaced or
+/// removed in each resource class.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AspaUpdateDryRun {
+    customer: AspaCustomer,
+    definition: Option<AspaDefinition>,
+    resource_classes: HashMap<ResourceClassName, AspaObjectsUpdates>,
+}
+
+impl AspaUpdateDryRun {
+    pub fn new(
+        customer: AspaCustomer,
+        definition: Option<AspaDefinition>,
+        resource_classes: HashMap<ResourceClassName, AspaObjectsUpdates>,
+    ) -> Self {
+        AspaUpdateDryRun {
+            customer,
+            definition,
+            resource_classes,
+        }
+    }
+
+    pub fn customer(&self) -> AspaCustomer {
+        self.customer
+    }
+
+    pub fn definition(&self) -> Option<&AspaDefinition> {
+        self.definition.as_ref()
+    }
+
+    pub fn resource_classes(&self) -> &HashMap<ResourceClassName, AspaObjectsUpdates> {
+        &self.resource_classes
+    }
+
+    pub fn contains_changes(&self) -> bool {
+        self.resource_classes.values().any(|updates| updates.contains_changes())
+    }
+}
+
+impl fmt::Display for AspaUpdateDryRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.definition {
+            Some(definition) => writeln!(f, "Resulting definition: {}", definition)?,
+            None => writeln!(
+                f,
+                "Resulting definition: none, ASPA for {} would be removed",
+                self.customer
+            )?,
+        }
+
+        if !self.contains_changes() {
+            writeln!(f, "No ASPA objects would be changed.")?;
+        }
+
+        for (rcn, updates) in &self.resource_classes {
+            if !updates.contains_changes() {
+                continue;
+            }
+            writeln!(f, "Resource class: {}", rcn)?;
+            for updated in updates.updated() {
+                writeln!(f, "  issue: {}", updated.definition())?;
+            }
+            for removed in updates.removed() {
+                writeln!(f, "  remove: {}", removed)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@ use rpki::{
         x509::{Serial, Time},
         Cert,
     },
+    rrdp::Hash,
 };
 
 use crate::{
@@ -37,6 +38,11 @@ impl BgpSecCertificates {
         self.0.len()
     }
 
+    /// Returns the hashes of all current BGPSec certificates.
+    pub fn hashes(&self) -> impl Iterator<Item = Hash> + '_ {
+        self.0.values().map(|info| info.base64().to_hash())
+    }
+
     /// Update issued BGPSec certificates
     ///
     /// Will issue new BGPSec certificates for definitions using the resources of
@@ -103,7 +109,7 @@ impl BgpSecCertificates {
 
         for cert in self.0.values().filter(|cert| {
             renew_threshold
-                .map(|threshold| cert.expires() < threshold) // will expire
+                .map(|threshold| issuance_timing.renew_due(&cert.name(), cert.expires(), threshold)) // will expire
                 .unwrap_or(true) // always renew if no renew_threshold was given
         }) {
             let asn = cert.asn();
@@ -123,7 +129,7 @@ impl BgpSecCertificates {
         issuance_timing: &IssuanceTimingConfig,
         signer: &KrillSigner,
     ) -> KrillResult<BgpSecCertInfo> {
-        let serial_number = signer.random_serial()?;
+        let serial_number = signer.next_serial(certified_key.key_id())?;
 
         let incoming_cert = certified_key.incoming_cert();
         let issuer = incoming_cert.subject().clone();
@@ -20,27 +20,37 @@ use rpki::{
         rta::RtaBuilder,
         x509::{Time, Validity},
     },
+    rrdp::Hash,
 };
 
 use crate::{
     commons::{
         api::{
             AspaCustomer, AspaDefinition, AspaDefinitionList, AspaDefinitionUpdates, AspaProvidersUpdate, BgpSecAsnKey,
-            BgpSecCsrInfoList, BgpSecDefinitionUpdates, CertAuthInfo, ConfiguredRoa, IdCertInfo, IssuedCertificate,
-            ObjectName, ParentCaContact, ReceivedCert, RepositoryContact, Revocation, RoaConfiguration,
-            RoaConfigurationUpdates, RtaList, RtaName, RtaPrepResponse, StorableCaCommand,
+            BgpSecCsrInfoList, BgpSecDefinitionUpdates, CaLabels, CaLabelsUpdate, CertAuthInfo, ChildExchange,
+            ChildIssuancePolicy, ChildStats, CommandRevert, ConfiguredRoa, Divergence, IdCertInfo,
+            IssuanceTimingOverrides, IssuedCertificate, KeyOwner, KeyRole, KeyUsage, ObjectName, ParentCaContact,
+            ParentClassStats, ParentStats, ParentStatus, PendingChildRequest, PendingChildRequestKind,
+            PendingChildRequests, ReceivedCert, RepoMigrationClass, RepoMigrationStage, RepositoryContact,
+            ResourceTransfer, ResourceTransferList, ResourceTransferRequest, RetryPolicies, RetryPolicy, Revocation,
+            RoaAutoPilotPolicy, RoaConfiguration, RoaConfigurationUpdates, RoaIssuanceStrategy, RoaProposal,
+            RoaProposalList, RtaList, RtaName, RtaPrepResponse, ScheduledChange, ScheduledChangeList,
+            ScheduledChangeRequest, ScheduledUpdate, SearchMatch, SearchQuery, SignerMigrationKey,
+            SignerMigrationStatus, StorableCaCommand, Timestamp,
         },
         crypto::{CsrInfo, KrillSigner},
         error::{Error, RoaDeltaError},
-        eventsourcing::{Aggregate, StoredEvent},
+        eventsourcing::{Aggregate, Command, StoredEvent},
         KrillResult,
     },
-    constants::test_mode_enabled,
+    constants::{test_mode_enabled, RESOURCE_TRANSFER_TIMEOUT_HOURS_DFLT, SCHEDULED_CHANGE_MAX_DAYS},
     daemon::{
         ca::{
-            events::ChildCertificateUpdates, AspaDefinitions, BgpSecDefinitions, CaEvt, CaEvtDet, ChildDetails, Cmd,
-            CmdDet, DropReason, Ini, PreparedRta, ResourceClass, ResourceTaggedAttestation, Rfc8183Id,
-            RoaPayloadJsonMapKey, Routes, RtaContentRequest, RtaPrepareRequest, Rtas, SignedRta, StoredBgpSecCsr,
+            events::ChildCertificateUpdates, AspaDefinitions, AspaObjectsUpdates, AspaUpdateDryRun, BgpSecDefinitions,
+            CaEvt, CaEvtDet, ChildDetails, Cmd, CmdDet, DropReason, Ini, PreparedRta, ResourceClass,
+            ResourceTaggedAttestation, ResourceTransfers, Rfc8183Id, RoaPayloadJsonMapKey, RoaProposals, Routes,
+            RpkiSignedChecklist, RscContentRequest, RtaContentRequest, RtaPrepareRequest, Rtas, ScheduledChanges,
+            SignedRta, StoredBgpSecCsr,
         },
         config::{Config, IssuanceTimingConfig},
     },
@@ -59,7 +69,16 @@ pub struct CertAuth {
 
     id: Rfc8183Id, // Used for RFC 6492 (up-down) and RFC 8181 (publication)
 
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    next_id: Option<Rfc8183Id>, // New id to roll to, not yet used
+
     repository: Option<RepositoryContact>,
+
+    // Resource classes which publish to another repository than the one
+    // used by the CA as a whole.
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    class_repositories: HashMap<ResourceClassName, RepositoryContact>,
+
     parents: HashMap<ParentHandle, ParentCaContact>,
 
     next_class_name: u32,
@@ -68,6 +87,18 @@ pub struct CertAuth {
     children: HashMap<ChildHandle, ChildDetails>,
     routes: Routes,
 
+    #[serde(skip_serializing_if = "RoaProposals::is_empty", default)]
+    roa_proposals: RoaProposals,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    roa_issuance_strategy: Option<RoaIssuanceStrategy>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    roa_autopilot: Option<RoaAutoPilotPolicy>,
+
+    #[serde(skip_serializing_if = "ScheduledChanges::is_empty", default)]
+    scheduled_changes: ScheduledChanges,
+
     #[serde(skip_serializing_if = "Rtas::is_empty", default)]
     rtas: Rtas,
 
@@ -76,6 +107,21 @@ pub struct CertAuth {
 
     #[serde(skip_serializing_if = "BgpSecDefinitions::is_empty", default)]
     bgpsec_defs: BgpSecDefinitions,
+
+    #[serde(skip_serializing_if = "IssuanceTimingOverrides::is_empty", default)]
+    issuance_timing: IssuanceTimingOverrides,
+
+    #[serde(skip_serializing_if = "RetryPolicies::is_empty", default)]
+    retry_policies: RetryPolicies,
+
+    #[serde(skip_serializing_if = "CaLabels::is_empty", default)]
+    labels: CaLabels,
+
+    #[serde(skip_serializing_if = "ChildIssuancePolicy::is_empty", default)]
+    child_issuance_policy: ChildIssuancePolicy,
+
+    #[serde(skip_serializing_if = "ResourceTransfers::is_empty", default)]
+    resource_transfers: ResourceTransfers,
 }
 
 impl Aggregate for CertAuth {
@@ -98,17 +144,25 @@ impl Aggregate for CertAuth {
         let children = HashMap::new();
 
         let routes = Routes::default();
+        let roa_proposals = RoaProposals::default();
         let rtas = Rtas::default();
         let aspas = AspaDefinitions::default();
         let bgpsec_defs = BgpSecDefinitions::default();
+        let issuance_timing = IssuanceTimingOverrides::default();
+        let retry_policies = RetryPolicies::default();
+        let labels = CaLabels::default();
+        let child_issuance_policy = ChildIssuancePolicy::default();
+        let resource_transfers = ResourceTransfers::default();
 
         Ok(CertAuth {
             handle,
             version: 1,
 
             id,
+            next_id: None,
 
             repository,
+            class_repositories: HashMap::new(),
             parents,
 
             next_class_name,
@@ -117,9 +171,18 @@ impl Aggregate for CertAuth {
             children,
 
             routes,
+            roa_proposals,
+            roa_issuance_strategy: None,
+            roa_autopilot: None,
+            scheduled_changes: ScheduledChanges::default(),
             rtas,
             aspas,
             bgpsec_defs,
+            issuance_timing,
+            retry_policies,
+            labels,
+            child_issuance_policy,
+            resource_transfers,
         })
     }
 
@@ -209,11 +272,37 @@ impl Aggregate for CertAuth {
 
             CaEvtDet::ChildUnsuspended { child } => self.children.get_mut(&child).unwrap().unsuspend(),
 
+            CaEvtDet::ChildUpdatedSuspensionExempt { child, exempt } => {
+                self.children.get_mut(&child).unwrap().set_suspension_exempt(exempt)
+            }
+
+            CaEvtDet::ChildUpdatedManualApproval { child, manual } => {
+                self.children.get_mut(&child).unwrap().set_manual_approval(manual)
+            }
+
+            CaEvtDet::ChildIssuancePolicyUpdated { policy } => self.child_issuance_policy = policy,
+
+            CaEvtDet::ChildRequestQueued { child, request } => {
+                self.children.get_mut(&child).unwrap().queue_request(request)
+            }
+
+            CaEvtDet::ChildRequestApproved { child, kind, ki } => {
+                self.children.get_mut(&child).unwrap().approve_request(kind, ki)
+            }
+
+            CaEvtDet::ResourceTransferStarted { transfer } | CaEvtDet::ResourceTransferFinished { transfer } => {
+                self.resource_transfers.update(transfer)
+            }
+
             //-----------------------------------------------------------------------
             // Being a child
             //-----------------------------------------------------------------------
             CaEvtDet::IdUpdated { id } => {
                 self.id = id;
+                self.next_id = None;
+            }
+            CaEvtDet::IdRollInitiated { id } => {
+                self.next_id = Some(id);
             }
             CaEvtDet::ParentAdded { parent, contact } => {
                 self.parents.insert(parent, contact);
@@ -223,6 +312,7 @@ impl Aggregate for CertAuth {
             }
             CaEvtDet::ParentRemoved { parent } => {
                 self.parents.remove(&parent);
+                self.retry_policies.remove_parent(&parent);
                 self.resources.retain(|_, rc| rc.parent_handle() != &parent);
             }
 
@@ -247,6 +337,7 @@ impl Aggregate for CertAuth {
                 resource_class_name, ..
             } => {
                 self.resources.remove(&resource_class_name);
+                self.class_repositories.remove(&resource_class_name);
             }
             CaEvtDet::CertificateRequested {
                 resource_class_name,
@@ -311,6 +402,24 @@ impl Aggregate for CertAuth {
             CaEvtDet::KeyRollFinished { resource_class_name } => {
                 self.resources.get_mut(&resource_class_name).unwrap().old_key_removed();
             }
+            CaEvtDet::KeyRollAborted {
+                resource_class_name,
+                revoke_req,
+            } => {
+                // The repository which the new key used, i.e. the repository
+                // which is being aborted.
+                let new_repo = self
+                    .class_repositories
+                    .get(&resource_class_name)
+                    .or(self.repository.as_ref())
+                    .map(|repo| repo.repo_info().clone())
+                    .unwrap();
+
+                self.resources
+                    .get_mut(&resource_class_name)
+                    .unwrap()
+                    .key_roll_aborted(revoke_req, new_repo);
+            }
             CaEvtDet::UnexpectedKeyFound { .. } => {
                 // no action needed, this is marked to flag that a key may be removed on the
                 // server side. The revocation requests are picked up by the `MessageQueue`
@@ -322,6 +431,8 @@ impl Aggregate for CertAuth {
             //-----------------------------------------------------------------------
             CaEvtDet::RouteAuthorizationAdded { auth } => self.routes.add(auth),
             CaEvtDet::RouteAuthorizationComment { auth, comment } => self.routes.comment(&auth, comment),
+            CaEvtDet::RouteAuthorizationDisabled { auth } => self.routes.disabled(&auth, true),
+            CaEvtDet::RouteAuthorizationEnabled { auth } => self.routes.disabled(&auth, false),
             CaEvtDet::RouteAuthorizationRemoved { auth } => {
                 self.routes.remove(&auth);
             }
@@ -335,6 +446,19 @@ impl Aggregate for CertAuth {
                 .unwrap()
                 .roas_updated(updates),
 
+            CaEvtDet::RoaProposalAdded { proposal } => self.roa_proposals.add(proposal),
+            CaEvtDet::RoaIssuanceStrategyUpdated { strategy } => self.roa_issuance_strategy = strategy,
+            CaEvtDet::RoaAutoPilotUpdated { policy } => self.roa_autopilot = policy,
+            CaEvtDet::ScheduledChangeAdded { change } => self.scheduled_changes.add(change),
+            CaEvtDet::ScheduledChangeApplied { id }
+            | CaEvtDet::ScheduledChangeFailed { id, .. }
+            | CaEvtDet::ScheduledChangeCancelled { id } => {
+                self.scheduled_changes.remove(id);
+            }
+            CaEvtDet::RoaProposalApproved { id } | CaEvtDet::RoaProposalRejected { id } => {
+                self.roa_proposals.remove(id);
+            }
+
             //-----------------------------------------------------------------------
             // Autonomous System Provider Authorization
             //-----------------------------------------------------------------------
@@ -371,12 +495,41 @@ impl Aggregate for CertAuth {
             //-----------------------------------------------------------------------
             CaEvtDet::RepoUpdated { contact } => {
                 if let Some(current) = &self.repository {
-                    for rc in self.resources.values_mut() {
-                        rc.set_old_repo(current.repo_info().clone());
+                    for (rcn, rc) in self.resources.iter_mut() {
+                        if !self.class_repositories.contains_key(rcn) {
+                            rc.set_old_repo(current.repo_info().clone());
+                        }
                     }
                 }
                 self.repository = Some(contact);
             }
+            CaEvtDet::ClassRepoUpdated {
+                resource_class_name,
+                contact,
+            } => {
+                let current = self
+                    .class_repositories
+                    .get(&resource_class_name)
+                    .or(self.repository.as_ref())
+                    .map(|repo| repo.repo_info().clone());
+
+                if let (Some(current), Some(rc)) = (current, self.resources.get_mut(&resource_class_name)) {
+                    rc.set_old_repo(current);
+                }
+
+                match contact {
+                    Some(contact) => {
+                        self.class_repositories.insert(resource_class_name, contact);
+                    }
+                    None => {
+                        self.class_repositories.remove(&resource_class_name);
+                    }
+                }
+            }
+            CaEvtDet::RepoMigrationAborted { contact } => self.repository = Some(contact),
+            CaEvtDet::IssuanceTimingUpdated { overrides } => self.issuance_timing = overrides,
+            CaEvtDet::RetryPoliciesUpdated { policies } => self.retry_policies = policies,
+            CaEvtDet::LabelsUpdated { labels } => self.labels = labels,
 
             //-----------------------------------------------------------------------
             // Resource Tagged Attestations
@@ -400,19 +553,32 @@ impl Aggregate for CertAuth {
             );
         }
 
+        let actor = command.actor().to_string();
+
         match command.into_details() {
             // being a parent
             CmdDet::ChildAdd(child, id_cert, resources) => self.child_add(child, id_cert, resources),
             CmdDet::ChildUpdateResources(child, res) => self.child_update_resources(&child, res),
             CmdDet::ChildUpdateId(child, id_cert) => self.child_update_id_cert(&child, id_cert),
-            CmdDet::ChildCertify(child, request, config, signer) => self.child_certify(child, request, &config, signer),
+            CmdDet::ChildCertify(child, request, config, signer) => {
+                self.child_certify(child, request, &self.effective_child_config(config), signer)
+            }
             CmdDet::ChildRevokeKey(child, request) => self.child_revoke_key(child, request),
             CmdDet::ChildRemove(child) => self.child_remove(&child),
             CmdDet::ChildSuspendInactive(child) => self.child_suspend_inactive(&child),
             CmdDet::ChildUnsuspend(child) => self.child_unsuspend(&child),
+            CmdDet::ChildUpdateSuspensionExempt(child, exempt) => self.child_update_suspension_exempt(&child, exempt),
+            CmdDet::ChildUpdateManualApproval(child, manual) => self.child_update_manual_approval(&child, manual),
+            CmdDet::ChildIssuancePolicyUpdate(policy, config) => self.child_issuance_policy_update(policy, &config),
+            CmdDet::ChildRequestQueue(child, request) => self.child_request_queue(&child, request),
+            CmdDet::ChildRequestApprove(child, kind, ki) => self.child_request_approve(&child, kind, ki),
+            CmdDet::ChildResourceTransferStart(request) => self.child_resource_transfer_start(request),
+            CmdDet::ChildResourceTransferProgress(id) => self.child_resource_transfer_progress(id),
 
             // being a child
             CmdDet::GenerateNewIdKey(signer) => self.generate_new_id_key(signer),
+            CmdDet::IdRollInit(signer) => self.id_roll_init(signer),
+            CmdDet::IdRollActivate => self.id_roll_activate(),
             CmdDet::AddParent(parent, info) => self.add_parent(parent, info),
             CmdDet::UpdateParentContact(parent, info) => self.update_parent(parent, info),
             CmdDet::RemoveParent(parent) => self.remove_parent(parent),
@@ -421,41 +587,77 @@ impl Aggregate for CertAuth {
                 self.update_entitlements(parent, entitlements, signer)
             }
             CmdDet::UpdateRcvdCert(class_name, rcvd_cert, config, signer) => {
-                self.update_received_cert(class_name, rcvd_cert, &config, signer)
+                self.update_received_cert(class_name, rcvd_cert, &self.effective_config(config), signer)
             }
             CmdDet::DropResourceClass(rcn, reason, signer) => self.drop_resource_class(rcn, reason, signer),
 
             // Key rolls
             CmdDet::KeyRollInitiate(duration, signer) => self.keyroll_initiate(duration, signer),
-            CmdDet::KeyRollActivate(duration, config, signer) => self.keyroll_activate(duration, config, signer),
+            CmdDet::KeyRollMigrate(duration, config, signer) => {
+                self.keyroll_migrate(duration, self.effective_config(config), signer)
+            }
+            CmdDet::KeyRollActivate(duration, config, signer) => {
+                self.keyroll_activate(duration, self.effective_config(config), signer)
+            }
             CmdDet::KeyRollFinish(rcn, response) => self.keyroll_finish(rcn, response),
 
             // Route Authorizations
             CmdDet::RouteAuthorizationsUpdate(updates, config, signer) => {
-                self.route_authorizations_update(updates, &config, signer)
+                self.route_authorizations_update(updates, &self.effective_config(config), signer)
             }
             CmdDet::RouteAuthorizationsRenew(config, signer) => {
-                self.route_authorizations_renew(false, &config, &signer)
+                self.route_authorizations_renew(false, &self.effective_config(config), &signer)
             }
             CmdDet::RouteAuthorizationsForceRenew(config, signer) => {
-                self.route_authorizations_renew(true, &config, &signer)
+                self.route_authorizations_renew(true, &self.effective_config(config), &signer)
             }
+            CmdDet::RouteAuthorizationsPropose(updates) => self.route_authorizations_propose(updates, actor),
+            CmdDet::RouteAuthorizationsApprove(id, config, signer) => {
+                self.route_authorizations_approve(id, &actor, &self.effective_config(config), signer)
+            }
+            CmdDet::RouteAuthorizationsReject(id) => self.route_authorizations_reject(id),
+            CmdDet::RoaIssuanceStrategyUpdate(strategy, config, signer) => {
+                self.roa_issuance_strategy_update(strategy, config, &signer)
+            }
+            CmdDet::RoaAutoPilotUpdate(policy) => self.roa_autopilot_update(policy),
+
+            // Scheduled ROA and ASPA changes
+            CmdDet::ScheduledChangeAdd(request) => self.scheduled_change_add(request, actor),
+            CmdDet::ScheduledChangeApply(id, config, signer) => {
+                self.scheduled_change_apply(id, &self.effective_config(config), &signer)
+            }
+            CmdDet::ScheduledChangeCancel(id) => self.scheduled_change_cancel(id),
+            CmdDet::CommandRevert(_, revert, config, signer) => match revert {
+                CommandRevert::Roas(updates) => {
+                    self.route_authorizations_update(updates, &self.effective_config(config), signer)
+                }
+                CommandRevert::Aspas(updates) => {
+                    self.aspas_definitions_update(updates, &self.effective_config(config), &signer)
+                }
+            },
 
             // ASPA
-            CmdDet::AspasUpdate(updates, config, signer) => self.aspas_definitions_update(updates, &config, &signer),
+            CmdDet::AspasUpdate(updates, config, signer) => {
+                self.aspas_definitions_update(updates, &self.effective_config(config), &signer)
+            }
             CmdDet::AspasUpdateExisting(customer, update, config, signer) => {
-                self.aspas_update(customer, update, &config, &signer)
+                self.aspas_update(customer, update, &self.effective_config(config), &signer)
             }
-            CmdDet::AspasRenew(config, signer) => self.aspas_renew(&config, &signer),
+            CmdDet::AspasRenew(config, signer) => self.aspas_renew(&self.effective_config(config), &signer),
 
             // BGPSec
             CmdDet::BgpSecUpdateDefinitions(updates, config, signer) => {
-                self.bgpsec_definitions_update(updates, &config, &signer)
+                self.bgpsec_definitions_update(updates, &self.effective_config(config), &signer)
             }
-            CmdDet::BgpSecRenew(config, signer) => self.bgpsec_renew(&config, &signer),
+            CmdDet::BgpSecRenew(config, signer) => self.bgpsec_renew(&self.effective_config(config), &signer),
 
             // Republish
             CmdDet::RepoUpdate(contact, signer) => self.update_repo(contact, &signer),
+            CmdDet::ClassRepoUpdate(rcn, contact, signer) => self.update_class_repo(rcn, contact, &signer),
+            CmdDet::RepoMigrationAbort(contact, signer) => self.repo_migration_abort(contact, &signer),
+            CmdDet::IssuanceTimingUpdate(overrides, config) => self.update_issuance_timing(overrides, &config),
+            CmdDet::RetryPolicyUpdate(parent, policy, config) => self.update_retry_policy(parent, policy, &config),
+            CmdDet::LabelsUpdate(update) => self.update_labels(update),
 
             // Resource Tagged Attestations
             CmdDet::RtaMultiPrepare(name, request, signer) => self.rta_multi_prep(name, request, signer.deref()),
@@ -517,9 +719,86 @@ impl CertAuth {
             resources,
             children,
             suspended_children,
+            self.scheduled_changes.list().changes().clone(),
         )
     }
 
+    /// Returns the CA specific overrides of the issuance timing config.
+    pub fn issuance_timing_overrides(&self) -> &IssuanceTimingOverrides {
+        &self.issuance_timing
+    }
+
+    /// Returns the CA specific overrides of the retry policies for contacting
+    /// the repository and parents.
+    pub fn retry_policies(&self) -> &RetryPolicies {
+        &self.retry_policies
+    }
+
+    /// Returns the labels used to group and select CAs.
+    pub fn labels(&self) -> &CaLabels {
+        &self.labels
+    }
+
+    /// Returns the policy enforced before issuing certificates to children.
+    pub fn child_issuance_policy(&self) -> &ChildIssuancePolicy {
+        &self.child_issuance_policy
+    }
+
+    /// Returns all resource transfers between children, including finished
+    /// transfers.
+    pub fn resource_transfers(&self) -> ResourceTransferList {
+        self.resource_transfers.list()
+    }
+
+    /// Returns the resource transfer with the given id, if any.
+    pub fn resource_transfer(&self, id: u64) -> Option<&ResourceTransfer> {
+        self.resource_transfers.get(id)
+    }
+
+    /// Returns the ids of resource transfers which are still waiting for
+    /// re-issuance.
+    pub fn resource_transfers_waiting(&self) -> Vec<u64> {
+        self.resource_transfers
+            .waiting()
+            .map(|transfer| transfer.id())
+            .collect()
+    }
+
+    /// Returns the CA specific ROA issuance strategy, if it was set.
+    pub fn roa_issuance_strategy(&self) -> Option<RoaIssuanceStrategy> {
+        self.roa_issuance_strategy
+    }
+
+    /// Returns the CA specific ROA auto-pilot policy, if it was set.
+    pub fn roa_autopilot(&self) -> Option<&RoaAutoPilotPolicy> {
+        self.roa_autopilot.as_ref()
+    }
+
+    /// Returns the pending ROA proposals.
+    pub fn roa_proposals(&self) -> RoaProposalList {
+        self.roa_proposals.list()
+    }
+
+    /// Returns the pending scheduled ROA and ASPA changes.
+    pub fn scheduled_changes(&self) -> ScheduledChangeList {
+        self.scheduled_changes.list()
+    }
+
+    /// Returns the ids of the scheduled changes which are due.
+    pub fn scheduled_changes_due(&self) -> Vec<u64> {
+        self.scheduled_changes.due()
+    }
+
+    /// Returns the moment the next scheduled change is due, if any.
+    pub fn scheduled_changes_next(&self) -> Option<Timestamp> {
+        self.scheduled_changes.next_activation()
+    }
+
+    /// Returns the pending ROA proposal with the given id, if any.
+    pub fn roa_proposal(&self, id: u64) -> Option<&RoaProposal> {
+        self.roa_proposals.get(id)
+    }
+
     /// Returns the current ConfiguredRoas.
     pub fn configured_roas(&self) -> Vec<ConfiguredRoa> {
         let roa_configurations = self.routes.roa_configurations();
@@ -540,6 +819,13 @@ impl CertAuth {
         configured_roas
     }
 
+    /// Returns the ConfiguredRoas as they would be after applying the given
+    /// updates, which must use explicit max lengths.
+    pub fn configured_roas_after(&self, updates: &RoaConfigurationUpdates) -> KrillResult<Vec<ConfiguredRoa>> {
+        let (would_be_routes, _) = self.update_authorizations(updates)?;
+        Ok(self.configured_roas_for_configs(would_be_routes.roa_configurations()))
+    }
+
     /// Returns an RFC 8183 Child Request - which can be represented as XML to a
     /// parent of this `CertAuth`
     pub fn child_request(&self) -> idexchange::ChildRequest {
@@ -556,10 +842,69 @@ impl CertAuth {
         self.id.cert()
     }
 
+    /// Returns an RFC 8183 Publisher Request for the new ID certificate that
+    /// this `CertAuth` will roll to, if a roll was initiated.
+    pub fn next_publisher_request(&self) -> Option<idexchange::PublisherRequest> {
+        self.next_id
+            .as_ref()
+            .map(|id| idexchange::PublisherRequest::new(id.cert().base64().clone(), self.handle.convert(), None))
+    }
+
     pub fn handle(&self) -> &CaHandle {
         &self.handle
     }
 
+    /// Returns what matches the query in this CA: the CA itself if it holds
+    /// the resources, or its name matches, and any matching ROAs and
+    /// children.
+    pub fn search(&self, query: &SearchQuery) -> Vec<SearchMatch> {
+        let mut matches = vec![];
+
+        let resources = query.resources();
+
+        if query.matches_text(self.handle.as_str()) {
+            matches.push(SearchMatch::Ca {
+                ca: self.handle.clone(),
+            });
+        }
+
+        if let Some(resources) = resources.as_ref() {
+            if self.all_resources().contains(resources) {
+                matches.push(SearchMatch::CaResources {
+                    ca: self.handle.clone(),
+                });
+            }
+        }
+
+        for roa in self.routes.roa_configurations() {
+            if query.matches_roa(&roa) {
+                matches.push(SearchMatch::Roa {
+                    ca: self.handle.clone(),
+                    roa,
+                });
+            }
+        }
+
+        let mut children: Vec<_> = self.children.iter().collect();
+        children.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+        for (child, details) in children {
+            let delegated = resources
+                .as_ref()
+                .map(|resources| details.resources().contains(resources))
+                .unwrap_or(false);
+
+            if delegated || query.matches_text(child.as_str()) {
+                matches.push(SearchMatch::Child {
+                    ca: self.handle.clone(),
+                    child: child.clone(),
+                });
+            }
+        }
+
+        matches
+    }
+
     /// Returns the complete set of all currently received resources, under all parents, for
     /// this `CertAuth`
     pub fn all_resources(&self) -> ResourceSet {
@@ -573,12 +918,76 @@ impl CertAuth {
     }
 }
 
+/// # Reconciliation
+///
+impl CertAuth {
+    /// Returns the divergences between the configured ROAs, ASPAs and
+    /// children of this CA, and the objects it actually issued.
+    pub fn divergences(&self) -> Vec<Divergence> {
+        let mut divergences = vec![];
+        for rc in self.resources.values() {
+            rc.divergences(&self.routes, &self.aspas, &self.children, &mut divergences);
+        }
+        divergences
+    }
+
+    /// Returns the hashes of all objects issued by this CA, except for
+    /// manifests and CRLs, mapped to a description for reporting.
+    pub fn issued_objects(&self) -> HashMap<Hash, String> {
+        self.resources.values().flat_map(|rc| rc.issued_objects()).collect()
+    }
+}
+
 /// # Publishing
 ///
 impl CertAuth {
     pub fn repository_contact(&self) -> KrillResult<&RepositoryContact> {
         self.repository.as_ref().ok_or(Error::RepoNotSet)
     }
+
+    /// Returns the repository used by the given resource class. This is the
+    /// repository of the CA, unless the class was moved to another one.
+    pub fn class_repository_contact(&self, rcn: &ResourceClassName) -> KrillResult<&RepositoryContact> {
+        match self.class_repositories.get(rcn) {
+            Some(contact) => Ok(contact),
+            None => self.repository_contact(),
+        }
+    }
+
+    /// Returns the resource classes which publish to another repository
+    /// than the one used by the CA as a whole.
+    pub fn class_repositories(&self) -> &HashMap<ResourceClassName, RepositoryContact> {
+        &self.class_repositories
+    }
+
+    /// Returns the resource classes which are migrating to another
+    /// repository, see [`RepoMigrationStatus`].
+    ///
+    /// [`RepoMigrationStatus`]: crate::commons::api::RepoMigrationStatus
+    pub fn repo_migration_classes(&self) -> Vec<RepoMigrationClass> {
+        self.resources
+            .iter()
+            .filter_map(|(rcn, rc)| {
+                let (stage, old_repo) = rc.repo_migration_stage()?;
+                let repo = self.class_repository_contact(rcn).ok()?.repo_info().clone();
+                Some(RepoMigrationClass::new(rcn.clone(), stage, old_repo.clone(), repo))
+            })
+            .collect()
+    }
+
+    /// Returns true if all resource classes have an active key, and none is
+    /// in a key roll.
+    pub fn key_roll_possible(&self) -> bool {
+        self.resources.values().all(|rc| rc.key_roll_possible())
+    }
+
+    /// Returns true if a new key is staged in a resource class for another
+    /// reason than a migration to another repository.
+    pub fn key_roll_staged_outside_repo_migration(&self) -> bool {
+        self.resources.values().any(|rc| {
+            rc.keys_with_role().iter().any(|(role, _)| *role == KeyRole::New) && rc.repo_migration_stage().is_none()
+        })
+    }
 }
 
 /// # Being a parent
@@ -704,6 +1113,12 @@ impl CertAuth {
         // and one of them is about to expire, while the other is still valid for a while.. then telling
         // the child that they are eligible to the not after time of the other is still fine - it would
         // still trigger them to request a replacement for the first which was about to expire.
+        let capped_timing = self
+            .child_issuance_policy
+            .max_validity_weeks
+            .map(|max| issuance_timing.with_max_child_certificate_valid_weeks(max));
+        let issuance_timing = capped_timing.as_ref().unwrap_or(issuance_timing);
+
         let mut not_after = issuance_timing.new_child_cert_not_after();
         let threshold = issuance_timing.new_child_cert_issuance_threshold();
 
@@ -744,6 +1159,43 @@ impl CertAuth {
         }
     }
 
+    /// Returns the stats for a child: its entitled and certified resources,
+    /// and any outstanding revocations. The last exchange is not known to
+    /// the CA itself and must be passed in.
+    pub fn child_stats(
+        &self,
+        child_handle: &ChildHandle,
+        last_exchange: Option<ChildExchange>,
+    ) -> KrillResult<ChildStats> {
+        let child = self.get_child(child_handle)?;
+
+        let mut certified_resources = ResourceSet::default();
+        let mut outstanding_revocations = vec![];
+
+        for (rcn, rc) in self.resources.iter() {
+            let mut issued: Vec<&IssuedCertificate> = child.issued(rcn).iter().flat_map(|ki| rc.issued(ki)).collect();
+
+            for cert in issued.iter() {
+                certified_resources = certified_resources.union(cert.resources());
+            }
+
+            // Keep the most recently issued certificate, any others are for keys
+            // which the child should still revoke.
+            issued.sort_by_key(|cert| cert.validity().not_before());
+            issued.pop();
+            outstanding_revocations.extend(issued.iter().map(|cert| cert.key_identifier()));
+        }
+
+        Ok(ChildStats::new(
+            child_handle.clone(),
+            child.state(),
+            child.resources().clone(),
+            certified_resources,
+            last_exchange,
+            outstanding_revocations,
+        ))
+    }
+
     /// Returns an iterator for the handles of all children under this CA.
     pub fn children(&self) -> impl Iterator<Item = &ChildHandle> {
         self.children.keys()
@@ -796,6 +1248,8 @@ impl CertAuth {
             ));
         }
 
+        self.verify_child_issuance_policy(&child, &rcn, &csr_info.key_id())?;
+
         let issued =
             self.issue_child_certificate(&child, rcn.clone(), csr_info, limit, &config.issuance_timing, &signer)?;
 
@@ -911,6 +1365,10 @@ impl CertAuth {
             return Err(Error::KeyUseNoIssuedCert);
         }
 
+        if self.child_revoke_approval_needed(&child_handle, &key)?.is_some() {
+            return Err(Error::CaChildRequestNotApproved(self.handle.clone(), child_handle, key));
+        }
+
         let handle = &self.handle;
         let version = self.version;
 
@@ -977,6 +1435,48 @@ impl CertAuth {
         Ok(res)
     }
 
+    /// Exempts a child from the automatic suspension of inactive children, or
+    /// subjects it to this again. This is a no-op if nothing changes.
+    fn child_update_suspension_exempt(&self, child_handle: &ChildHandle, exempt: bool) -> KrillResult<Vec<CaEvt>> {
+        let child = self.get_child(child_handle)?;
+
+        if child.is_suspension_exempt() == exempt {
+            Ok(vec![])
+        } else {
+            info!(
+                "CA '{}' update child '{}' suspension exempt to: {}",
+                self.handle, child_handle, exempt
+            );
+            Ok(vec![CaEvtDet::child_updated_suspension_exempt(
+                &self.handle,
+                self.version,
+                child_handle.clone(),
+                exempt,
+            )])
+        }
+    }
+
+    /// Requires, or no longer requires, that issuance and revocation requests
+    /// from the child are approved manually. This is a no-op if nothing changes.
+    fn child_update_manual_approval(&self, child_handle: &ChildHandle, manual: bool) -> KrillResult<Vec<CaEvt>> {
+        let child = self.get_child(child_handle)?;
+
+        if child.is_manual_approval() == manual {
+            Ok(vec![])
+        } else {
+            info!(
+                "CA '{}' update child '{}' manual approval to: {}",
+                self.handle, child_handle, manual
+            );
+            Ok(vec![CaEvtDet::child_updated_manual_approval(
+                &self.handle,
+                self.version,
+                child_handle.clone(),
+                manual,
+            )])
+        }
+    }
+
     // Suspend a child. The intention is that this is called when it is discovered
     // that the child has been inactive, i.e. not contacting this parent for a pro-longed
     // period of time (hours).
@@ -1120,6 +1620,43 @@ impl CertAuth {
         Ok(vec![CaEvtDet::id_updated(&self.handle, self.version, id)])
     }
 
+    /// Generates a new ID key for this CA to roll to. The current ID key
+    /// remains in use until the roll is activated, so that the new ID
+    /// certificate can first be given to the repository.
+    fn id_roll_init(&self, signer: Arc<KrillSigner>) -> KrillResult<Vec<CaEvt>> {
+        let id = Rfc8183Id::generate(&signer)?;
+
+        info!(
+            "CA '{}' generated new ID certificate to roll to with key id: {}",
+            self.handle,
+            id.cert().public_key().key_identifier()
+        );
+        Ok(vec![CaEvtDet::id_roll_initiated(&self.handle, self.version, id)])
+    }
+
+    /// Starts using the new ID key, generated when the roll was initiated.
+    fn id_roll_activate(&self) -> KrillResult<Vec<CaEvt>> {
+        let id = self
+            .next_id
+            .clone()
+            .ok_or_else(|| Error::CaRepoIdRollNotStarted(self.handle.clone()))?;
+
+        info!(
+            "CA '{}' activated new ID certificate with key id: {}",
+            self.handle,
+            id.cert().public_key().key_identifier()
+        );
+        Ok(vec![CaEvtDet::id_updated(&self.handle, self.version, id)])
+    }
+
+    /// Returns the certificates received for all certified keys of this CA.
+    pub fn received_certificates(&self) -> Vec<&ReceivedCert> {
+        self.resources
+            .values()
+            .flat_map(|rc| rc.received_certificates())
+            .collect()
+    }
+
     /// List all parents
     pub fn parents(&self) -> impl Iterator<Item = &ParentHandle> {
         self.parents.keys()
@@ -1133,6 +1670,81 @@ impl CertAuth {
         self.parents.contains_key(parent)
     }
 
+    /// Returns the stats for a parent: the entitled and received resources,
+    /// certificate expiry and pending requests for each resource class. The
+    /// entitlements are not kept by the CA itself, but in its status, so the
+    /// status for the parent (if any) must be passed in.
+    pub fn parent_stats(&self, parent: &ParentHandle, status: Option<&ParentStatus>) -> KrillResult<ParentStats> {
+        if !self.parent_known(parent) {
+            return Err(Error::CaParentUnknown(self.handle.clone(), parent.clone()));
+        }
+
+        let mut classes = vec![];
+
+        if let Some(status) = status {
+            for entitlement in status.classes() {
+                let rc = self
+                    .resources
+                    .values()
+                    .find(|rc| rc.parent_handle() == parent && rc.parent_rc_name() == entitlement.class_name());
+
+                classes.push(Self::parent_class_stats(
+                    entitlement.class_name().clone(),
+                    entitlement.resource_set().clone(),
+                    rc,
+                ));
+            }
+        }
+
+        // Resource classes which are no longer listed by the parent (or for
+        // which we have no recorded entitlements) are still reported, so that
+        // any pending revocations for them are visible.
+        for rc in self.resources.values().filter(|rc| rc.parent_handle() == parent) {
+            if !classes.iter().any(|class| class.class_name() == rc.parent_rc_name()) {
+                classes.push(Self::parent_class_stats(
+                    rc.parent_rc_name().clone(),
+                    ResourceSet::default(),
+                    Some(rc),
+                ));
+            }
+        }
+
+        Ok(ParentStats::new(
+            parent.clone(),
+            status.and_then(|status| status.last_success()),
+            classes,
+        ))
+    }
+
+    fn parent_class_stats(
+        class_name: ResourceClassName,
+        entitled_resources: ResourceSet,
+        rc: Option<&ResourceClass>,
+    ) -> ParentClassStats {
+        let cert = rc.and_then(|rc| rc.current_certificate());
+        let received_resources = cert.map(|cert| cert.resources().clone()).unwrap_or_default();
+        let cert_expires = cert.map(|cert| cert.expires());
+
+        let pending_requests = rc
+            .map(|rc| {
+                rc.cert_requests()
+                    .iter()
+                    .map(|req| req.csr().public_key().key_identifier())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let pending_revocation = rc.and_then(|rc| rc.revoke_request()).map(|req| req.key());
+
+        ParentClassStats::new(
+            class_name,
+            entitled_resources,
+            received_resources,
+            cert_expires,
+            pending_requests,
+            pending_revocation,
+        )
+    }
+
     fn parent_for_info(&self, info: &ParentCaContact) -> Option<&ParentHandle> {
         for (parent, parent_info) in &self.parents {
             if parent_info == info {
@@ -1237,7 +1849,7 @@ impl CertAuth {
         rc: &ResourceClass,
         signer: &KrillSigner,
     ) -> KrillResult<Vec<CaEvtDet>> {
-        let repo = self.repository_contact()?;
+        let repo = self.class_repository_contact(rc.name())?;
         rc.make_entitlement_events(self.handle(), entitlement, repo.repo_info(), signer)
     }
 
@@ -1328,7 +1940,7 @@ impl CertAuth {
                 }
                 None => {
                     // Create a resource class with a pending key
-                    let pending_key = signer.create_key()?;
+                    let pending_key = signer.create_ca_key()?;
 
                     let resource_class_name = ResourceClassName::from(next_class_name);
                     next_class_name += 1;
@@ -1440,7 +2052,7 @@ impl CertAuth {
 
         for (rcn, rc) in self.resources.iter() {
             let mut started = false;
-            let repo = self.repository_contact()?;
+            let repo = self.class_repository_contact(rcn)?;
             for details in rc.keyroll_initiate(repo.repo_info(), duration, &signer)?.into_iter() {
                 started = true;
                 res.push(StoredEvent::new(self.handle(), version, details));
@@ -1460,6 +2072,82 @@ impl CertAuth {
         Ok(res)
     }
 
+    fn keyroll_migrate(
+        &self,
+        staging_time: Duration,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+    ) -> KrillResult<Vec<CaEvt>> {
+        let mut version = self.version;
+        let mut res = vec![];
+
+        for (rcn, rc) in self.resources.iter() {
+            let mut migrated = false;
+            let repo = self.class_repository_contact(rcn)?;
+            for details in rc
+                .keyroll_migrate(repo.repo_info(), staging_time, &config.issuance_timing, &signer)?
+                .into_iter()
+            {
+                migrated = true;
+                res.push(StoredEvent::new(self.handle(), version, details));
+                version += 1;
+            }
+
+            if migrated {
+                info!(
+                    "Moving keys to signer '{}' for ca: {}, rc: {}, under parent: {}",
+                    signer.default_signer_name(),
+                    &self.handle,
+                    rcn,
+                    rc.parent_handle()
+                );
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Returns which signers hold the keys of this CA, compared to the signer
+    /// used for new keys.
+    pub fn signer_migration_status(&self, signer: &KrillSigner) -> SignerMigrationStatus {
+        let keys = self
+            .resources
+            .iter()
+            .flat_map(|(rcn, rc)| {
+                rc.keys_with_role().into_iter().map(move |(role, key_id)| {
+                    SignerMigrationKey::new(rcn.clone(), role, key_id, signer.key_signer_name(&key_id))
+                })
+            })
+            .collect();
+
+        SignerMigrationStatus::new(signer.default_signer_name(), keys)
+    }
+
+    /// Returns the keys used by this CA, for the inventory of signer keys.
+    pub fn key_usages(&self) -> Vec<(KeyIdentifier, KeyUsage)> {
+        let owner = || KeyOwner::Ca(self.handle.clone());
+
+        let mut usages = vec![(
+            self.id.cert().public_key().key_identifier(),
+            KeyUsage::new(owner(), None, "id"),
+        )];
+        if let Some(next_id) = &self.next_id {
+            usages.push((
+                next_id.cert().public_key().key_identifier(),
+                KeyUsage::new(owner(), None, "next id"),
+            ));
+        }
+        for (rcn, rc) in self.resources.iter() {
+            for (role, key_id) in rc.keys_with_role() {
+                usages.push((key_id, KeyUsage::new(owner(), Some(rcn.clone()), role)));
+            }
+        }
+        for key_id in self.rtas.prepared_keys() {
+            usages.push((key_id, KeyUsage::new(owner(), None, "prepared RTA")));
+        }
+        usages
+    }
+
     fn keyroll_activate(
         &self,
         staging_time: Duration,
@@ -1528,7 +2216,13 @@ impl CertAuth {
             }
             // Initiate rolls in all RCs so we can use the new repo in the new key.
             let info = contact.repo_info().clone();
-            for rc in self.resources.values() {
+            for (rcn, rc) in self.resources.iter() {
+                // Resource classes which use their own repository are not
+                // affected by a change of the CA repository.
+                if self.class_repositories.contains_key(rcn) {
+                    continue;
+                }
+
                 // If we are in any keyroll, reject.. because we will need to
                 // introduce the change as a key roll (new key, new repo, etc),
                 // and we can only do one roll at a time.
@@ -1552,7 +2246,556 @@ impl CertAuth {
         evt_dets.push(CaEvtDet::RepoUpdated { contact });
         Ok(self.events_from_details(evt_dets))
     }
-}
+
+    /// Update the repository used by a single resource class, or revert it
+    /// to the repository of the CA if no contact is given. Like a change of
+    /// the CA repository this is done by a key roll, so that the new key
+    /// publishes to the new repository, and the objects of the old key are
+    /// withdrawn from the old repository when the roll is finished.
+    pub fn update_class_repo(
+        &self,
+        rcn: ResourceClassName,
+        contact: Option<RepositoryContact>,
+        signer: &KrillSigner,
+    ) -> KrillResult<Vec<CaEvt>> {
+        let rc = self
+            .resources
+            .get(&rcn)
+            .ok_or_else(|| Error::ResourceClassUnknown(rcn.clone()))?;
+
+        let ca_contact = self.repository_contact()?;
+        let contact = contact.filter(|contact| contact != ca_contact);
+        let new_contact = contact.as_ref().unwrap_or(ca_contact);
+
+        if self.class_repository_contact(&rcn)? == new_contact {
+            return Err(Error::CaRepoInUse(self.handle.clone()));
+        }
+
+        if !rc.key_roll_possible() {
+            return Err(Error::KeyRollInProgress);
+        }
+
+        let mut evt_dets = rc.keyroll_initiate(new_contact.repo_info(), Duration::seconds(0), signer)?;
+
+        info!(
+            "CA '{}' updated repository for resource class '{}'. Service URI will be: {}",
+            self.handle,
+            rcn,
+            new_contact.server_info().service_uri()
+        );
+
+        evt_dets.push(CaEvtDet::ClassRepoUpdated {
+            resource_class_name: rcn,
+            contact,
+        });
+        Ok(self.events_from_details(evt_dets))
+    }
+
+    /// Abort the migration of the CA to another repository, and return to
+    /// the repository it was migrating from. This is only possible as long
+    /// as no new key was activated, because until then the CA is found in
+    /// the old repository. The key rolls for the migration are aborted, and
+    /// new keys which were already certified are revoked and withdrawn
+    /// from the new repository.
+    pub fn repo_migration_abort(&self, contact: RepositoryContact, signer: &KrillSigner) -> KrillResult<Vec<CaEvt>> {
+        let mut evt_dets = vec![];
+
+        for (rcn, rc) in self.resources.iter() {
+            // Resource classes which use their own repository are not
+            // affected by a change of the CA repository.
+            if self.class_repositories.contains_key(rcn) {
+                continue;
+            }
+
+            match rc.repo_migration_stage() {
+                None => {}
+                Some((RepoMigrationStage::Activated, _)) => {
+                    return Err(Error::CaRepoIssue(
+                        self.handle.clone(),
+                        format!(
+                            "the new key for resource class '{}' is already activated, the migration cannot be aborted",
+                            rcn
+                        ),
+                    ));
+                }
+                Some((_, old_repo)) => {
+                    if old_repo != contact.repo_info() {
+                        return Err(Error::CaRepoIssue(
+                            self.handle.clone(),
+                            format!(
+                                "the CA is not migrating from repository at {}",
+                                contact.server_info().service_uri()
+                            ),
+                        ));
+                    }
+                    evt_dets.push(rc.keyroll_abort(signer)?);
+                }
+            }
+        }
+
+        if evt_dets.is_empty() {
+            return Err(Error::CaRepoIssue(
+                self.handle.clone(),
+                "no repository migration is in progress".to_string(),
+            ));
+        }
+
+        info!(
+            "CA '{}' aborted repository migration. Service URI will be: {}",
+            self.handle,
+            contact.server_info().service_uri()
+        );
+
+        evt_dets.push(CaEvtDet::RepoMigrationAborted { contact });
+        Ok(self.events_from_details(evt_dets))
+    }
+}
+
+/// # Issuance timing
+///
+impl CertAuth {
+    /// Returns the config to use for this CA. I.e. the given config, with
+    /// the CA specific issuance timing overrides and ROA issuance strategy
+    /// applied if there are any.
+    fn effective_config(&self, config: Arc<Config>) -> Arc<Config> {
+        if self.issuance_timing.is_empty() && self.roa_issuance_strategy.is_none() {
+            config
+        } else {
+            let mut effective = config.as_ref().clone();
+            effective.issuance_timing = config.issuance_timing.with_overrides(&self.issuance_timing);
+            if let Some(strategy) = self.roa_issuance_strategy {
+                effective.roa_issuance_strategy = strategy;
+            }
+            Arc::new(effective)
+        }
+    }
+
+    /// Returns the config to use when issuing certificates to children. I.e.
+    /// the effective config for this CA, with the maximum child certificate
+    /// validity from the child issuance policy applied if it is set.
+    fn effective_child_config(&self, config: Arc<Config>) -> Arc<Config> {
+        let config = self.effective_config(config);
+        match self.child_issuance_policy.max_validity_weeks {
+            None => config,
+            Some(max) => {
+                let mut effective = config.as_ref().clone();
+                effective.issuance_timing = config.issuance_timing.with_max_child_certificate_valid_weeks(max);
+                Arc::new(effective)
+            }
+        }
+    }
+
+    /// Replaces the CA specific overrides of the issuance timing config. Will
+    /// return an error if the resulting timing would be inconsistent. Objects
+    /// are not re-issued, the new timing applies when they are next issued.
+    fn update_issuance_timing(&self, overrides: IssuanceTimingOverrides, config: &Config) -> KrillResult<Vec<CaEvt>> {
+        if overrides == self.issuance_timing {
+            return Ok(vec![]);
+        }
+
+        let timing = config.issuance_timing.with_overrides(&overrides);
+        timing
+            .verify()
+            .map_err(|msg| Error::CaIssuanceTimingInvalid(self.handle.clone(), msg))?;
+
+        for warning in timing.warnings() {
+            warn!("Issuance timing for CA '{}': {}", self.handle, warning);
+        }
+
+        Ok(self.events_from_details(vec![CaEvtDet::IssuanceTimingUpdated { overrides }]))
+    }
+}
+
+/// # Child issuance policy
+///
+impl CertAuth {
+    /// Returns the reason why a request from a child for the given key needs
+    /// to be approved before it can be honored, or None if it can be honored
+    /// now. Keys which were certified to the child before are always fine.
+    pub fn child_request_approval_needed(
+        &self,
+        child_handle: &ChildHandle,
+        ki: &KeyIdentifier,
+    ) -> KrillResult<Option<String>> {
+        let child = self.get_child(child_handle)?;
+
+        if child.is_approved(PendingChildRequestKind::Issue, ki) {
+            Ok(None)
+        } else if child.is_manual_approval() {
+            Ok(Some("child requires manual approval".to_string()))
+        } else if self.child_issuance_policy.approve_new_keys && !child.is_known_key(ki) {
+            Ok(Some("new child keys require manual approval".to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the reason why a revocation request from a child for the given
+    /// key needs to be approved before it can be honored, or None if it can be
+    /// honored now.
+    pub fn child_revoke_approval_needed(
+        &self,
+        child_handle: &ChildHandle,
+        ki: &KeyIdentifier,
+    ) -> KrillResult<Option<String>> {
+        let child = self.get_child(child_handle)?;
+
+        if child.is_manual_approval() && !child.is_approved(PendingChildRequestKind::Revoke, ki) {
+            Ok(Some("child requires manual approval".to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the requests from a child which are awaiting approval.
+    pub fn child_pending_requests(&self, child_handle: &ChildHandle) -> KrillResult<PendingChildRequests> {
+        Ok(self.get_child(child_handle)?.pending_requests())
+    }
+
+    /// Returns an error if the child issuance policy does not allow that a
+    /// certificate is issued to the child for the given key and class.
+    fn verify_child_issuance_policy(
+        &self,
+        child_handle: &ChildHandle,
+        rcn: &ResourceClassName,
+        ki: &KeyIdentifier,
+    ) -> KrillResult<()> {
+        if self.child_request_approval_needed(child_handle, ki)?.is_some() {
+            return Err(Error::CaChildRequestNotApproved(
+                self.handle.clone(),
+                child_handle.clone(),
+                *ki,
+            ));
+        }
+
+        if self.child_issuance_policy.deny_asn_only {
+            let child = self.get_child(child_handle)?;
+            let my_rc = self
+                .resources
+                .get(rcn)
+                .ok_or_else(|| Error::ResourceClassUnknown(rcn.clone()))?;
+
+            let resources = my_rc
+                .current_resources()
+                .map(|parent_resources| parent_resources.intersection(child.resources()))
+                .unwrap_or_default();
+
+            if resources.ipv4().is_empty() && resources.ipv6().is_empty() {
+                return Err(Error::CaChildIssuanceDenied(
+                    self.handle.clone(),
+                    child_handle.clone(),
+                    "certificates containing only ASNs are not allowed".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the child issuance policy. Certificates which were already
+    /// issued are not affected until they are re-issued.
+    fn child_issuance_policy_update(&self, policy: ChildIssuancePolicy, config: &Config) -> KrillResult<Vec<CaEvt>> {
+        if policy == self.child_issuance_policy {
+            return Ok(vec![]);
+        }
+
+        if let Some(max) = policy.max_validity_weeks {
+            if max < 2 {
+                return Err(Error::CaChildIssuancePolicyInvalid(
+                    self.handle.clone(),
+                    "max_validity_weeks must be at least 2".to_string(),
+                ));
+            }
+
+            config
+                .issuance_timing
+                .with_overrides(&self.issuance_timing)
+                .with_max_child_certificate_valid_weeks(max)
+                .verify()
+                .map_err(|msg| Error::CaChildIssuancePolicyInvalid(self.handle.clone(), msg))?;
+        }
+
+        info!(
+            "CA '{}' updated child issuance policy: {}",
+            self.handle,
+            policy.summary()
+        );
+        Ok(self.events_from_details(vec![CaEvtDet::ChildIssuancePolicyUpdated { policy }]))
+    }
+
+    /// Holds back a request from a child until it is approved. This is a
+    /// no-op if the request is already pending.
+    fn child_request_queue(&self, child_handle: &ChildHandle, request: PendingChildRequest) -> KrillResult<Vec<CaEvt>> {
+        let child = self.get_child(child_handle)?;
+
+        if child.is_pending(request.kind, &request.key) {
+            Ok(vec![])
+        } else {
+            info!(
+                "CA '{}' queued {} request from child '{}' for key '{}': {}",
+                self.handle, request.kind, child_handle, request.key, request.reason
+            );
+            Ok(self.events_from_details(vec![CaEvtDet::ChildRequestQueued {
+                child: child_handle.clone(),
+                request,
+            }]))
+        }
+    }
+
+    /// Approves a pending request from a child.
+    fn child_request_approve(
+        &self,
+        child_handle: &ChildHandle,
+        kind: PendingChildRequestKind,
+        ki: KeyIdentifier,
+    ) -> KrillResult<Vec<CaEvt>> {
+        let child = self.get_child(child_handle)?;
+
+        if !child.is_pending(kind, &ki) {
+            Err(Error::CaChildRequestUnknown(
+                self.handle.clone(),
+                child_handle.clone(),
+                ki,
+            ))
+        } else {
+            info!(
+                "CA '{}' approved {} request from child '{}' for key '{}'",
+                self.handle, kind, child_handle, ki
+            );
+            Ok(self.events_from_details(vec![CaEvtDet::ChildRequestApproved {
+                child: child_handle.clone(),
+                kind,
+                ki,
+            }]))
+        }
+    }
+}
+
+/// # Resource transfers
+///
+impl CertAuth {
+    /// Returns the resources on the current certificates issued to a child.
+    fn child_certified_resources(&self, child: &ChildDetails) -> ResourceSet {
+        let mut certified_resources = ResourceSet::default();
+        for (rcn, rc) in self.resources.iter() {
+            for cert in child.issued(rcn).iter().flat_map(|ki| rc.issued(ki)) {
+                certified_resources = certified_resources.union(cert.resources());
+            }
+        }
+        certified_resources
+    }
+
+    /// Starts a transfer of resources between two children. The resources
+    /// are removed from the 'from' child straight away. They are added to
+    /// the 'to' child when the transfer progresses, after the 'from' child
+    /// got certificates without them.
+    fn child_resource_transfer_start(&self, request: ResourceTransferRequest) -> KrillResult<Vec<CaEvt>> {
+        let invalid = |msg: String| Error::CaResourceTransferInvalid(self.handle.clone(), msg);
+
+        if request.from == request.to {
+            return Err(invalid("cannot transfer resources to the same child".to_string()));
+        }
+        if request.resources.is_empty() {
+            return Err(invalid("no resources to transfer".to_string()));
+        }
+        if request.timeout_hours == Some(0) {
+            return Err(invalid("timeout_hours must be 1 or higher".to_string()));
+        }
+
+        let from = self.get_child(&request.from)?;
+        self.get_child(&request.to)?;
+
+        if !from.resources().contains(&request.resources) {
+            return Err(invalid(format!(
+                "child '{}' does not hold all resources '{}'",
+                request.from, request.resources
+            )));
+        }
+
+        for child in [&request.from, &request.to] {
+            if self.resource_transfers.is_transferring(child) {
+                return Err(invalid(format!(
+                    "child '{}' is already part of a waiting transfer",
+                    child
+                )));
+            }
+        }
+
+        let remaining = from.resources().difference(&request.resources);
+        let timeout_hours = request.timeout_hours.unwrap_or(RESOURCE_TRANSFER_TIMEOUT_HOURS_DFLT);
+        let transfer = ResourceTransfer::new(self.version, request, timeout_hours);
+
+        info!(
+            "CA '{}' started transfer {} of resources '{}' from child '{}' to child '{}'",
+            self.handle,
+            transfer.id(),
+            transfer.resources(),
+            transfer.from(),
+            transfer.to()
+        );
+
+        let from = transfer.from().clone();
+        Ok(self.events_from_details(vec![
+            CaEvtDet::ResourceTransferStarted { transfer },
+            CaEvtDet::ChildUpdatedResources {
+                child: from,
+                resources: remaining,
+            },
+        ]))
+    }
+
+    /// Completes a waiting transfer if the 'from' child no longer has any of
+    /// the transferred resources on its certificates, or rolls it back if the
+    /// deadline passed or the transfer can no longer be completed. This is a
+    /// no-op if the transfer is not waiting, or if it should wait longer.
+    fn child_resource_transfer_progress(&self, id: u64) -> KrillResult<Vec<CaEvt>> {
+        let transfer = self
+            .resource_transfers
+            .get(id)
+            .ok_or_else(|| Error::CaResourceTransferUnknown(self.handle.clone(), id))?;
+
+        if !transfer.is_waiting() {
+            return Ok(vec![]);
+        }
+
+        let to = match self.children.get(transfer.to()) {
+            Some(to) => to,
+            None => {
+                let reason = format!("child '{}' was removed", transfer.to());
+                return Ok(self.child_resource_transfer_rollback(transfer, reason));
+            }
+        };
+
+        // If the 'from' child was removed, then its certificates are revoked too.
+        let reissued = match self.children.get(transfer.from()) {
+            Some(from) => self
+                .child_certified_resources(from)
+                .intersection(transfer.resources())
+                .is_empty(),
+            None => true,
+        };
+
+        if reissued {
+            if !self.all_resources().contains(transfer.resources()) {
+                let reason = "the resources are no longer held by this CA".to_string();
+                return Ok(self.child_resource_transfer_rollback(transfer, reason));
+            }
+
+            info!(
+                "CA '{}' completed transfer {} of resources '{}' to child '{}'",
+                self.handle,
+                transfer.id(),
+                transfer.resources(),
+                transfer.to()
+            );
+
+            Ok(self.events_from_details(vec![
+                CaEvtDet::ChildUpdatedResources {
+                    child: transfer.to().clone(),
+                    resources: to.resources().union(transfer.resources()),
+                },
+                CaEvtDet::ResourceTransferFinished {
+                    transfer: transfer.completed(),
+                },
+            ]))
+        } else if transfer.is_expired() {
+            let reason = format!(
+                "child '{}' did not get certificates without the resources before the deadline",
+                transfer.from()
+            );
+            Ok(self.child_resource_transfer_rollback(transfer, reason))
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Gives the resources still held by this CA back to the 'from' child,
+    /// if it still exists, and marks the transfer as rolled back.
+    fn child_resource_transfer_rollback(&self, transfer: &ResourceTransfer, reason: String) -> Vec<CaEvt> {
+        warn!(
+            "CA '{}' rolled back transfer {} of resources '{}' from child '{}': {}",
+            self.handle,
+            transfer.id(),
+            transfer.resources(),
+            transfer.from(),
+            reason
+        );
+
+        let mut evt_dets = vec![];
+        if let Some(from) = self.children.get(transfer.from()) {
+            let resources = from
+                .resources()
+                .union(&transfer.resources().intersection(&self.all_resources()));
+            evt_dets.push(CaEvtDet::ChildUpdatedResources {
+                child: transfer.from().clone(),
+                resources,
+            });
+        }
+        evt_dets.push(CaEvtDet::ResourceTransferFinished {
+            transfer: transfer.rolled_back(reason),
+        });
+
+        self.events_from_details(evt_dets)
+    }
+}
+
+/// # Retry policies
+///
+impl CertAuth {
+    /// Replaces the retry policy overrides for the repository, or for the
+    /// given parent. Will return an error if the parent is unknown or if the
+    /// resulting policy would be inconsistent.
+    fn update_retry_policy(
+        &self,
+        parent: Option<ParentHandle>,
+        policy: RetryPolicy,
+        config: &Config,
+    ) -> KrillResult<Vec<CaEvt>> {
+        let global = match &parent {
+            None => &config.retry_repository,
+            Some(parent) => {
+                if !self.parent_known(parent) {
+                    return Err(Error::CaParentUnknown(self.handle.clone(), parent.clone()));
+                }
+                &config.retry_parent
+            }
+        };
+
+        global
+            .with_overrides(&policy)
+            .verify()
+            .map_err(|msg| Error::CaRetryPolicyInvalid(self.handle.clone(), msg))?;
+
+        let mut policies = self.retry_policies.clone();
+        policies.set(parent, policy);
+
+        if policies == self.retry_policies {
+            Ok(vec![])
+        } else {
+            Ok(self.events_from_details(vec![CaEvtDet::RetryPoliciesUpdated { policies }]))
+        }
+    }
+}
+
+/// # Labels
+///
+impl CertAuth {
+    /// Sets and/or removes labels. Will return an error if a resulting label
+    /// is invalid.
+    fn update_labels(&self, update: CaLabelsUpdate) -> KrillResult<Vec<CaEvt>> {
+        let mut labels = self.labels.clone();
+        labels.apply(update);
+        labels
+            .validate()
+            .map_err(|msg| Error::CaLabelsInvalid(self.handle.clone(), msg))?;
+
+        if labels == self.labels {
+            Ok(vec![])
+        } else {
+            Ok(self.events_from_details(vec![CaEvtDet::LabelsUpdated { labels }]))
+        }
+    }
+}
 
 /// # Managing Route Authorizations
 ///
@@ -1566,13 +2809,25 @@ impl CertAuth {
         config: &Config,
         signer: Arc<KrillSigner>,
     ) -> KrillResult<Vec<CaEvt>> {
+        let evt_dets = self.route_authorizations_update_details(route_auth_updates, config, signer.deref())?;
+        Ok(self.events_from_details(evt_dets))
+    }
+
+    /// Returns the event details for updating the route authorizations and
+    /// ROAs. This is shared by direct updates and approved proposals.
+    fn route_authorizations_update_details(
+        &self,
+        route_auth_updates: RoaConfigurationUpdates,
+        config: &Config,
+        signer: &KrillSigner,
+    ) -> KrillResult<Vec<CaEvtDet>> {
         let route_auth_updates = route_auth_updates.into_explicit_max_length();
 
         let (routes, mut evt_dets) = self.update_authorizations(&route_auth_updates)?;
 
         // for rc in self.resources
         for (rcn, rc) in self.resources.iter() {
-            let updates = rc.update_roas(&routes, config, signer.deref())?;
+            let updates = rc.update_roas(&routes, config, signer)?;
             if updates.contains_changes() {
                 info!("CA '{}' under RC '{}' updated ROAs: {}", self.handle, rcn, updates);
 
@@ -1583,9 +2838,250 @@ impl CertAuth {
             }
         }
 
+        Ok(evt_dets)
+    }
+
+    /// Sets, or with None removes, the CA specific ROA issuance strategy,
+    /// and re-issues the ROAs in accordance with the resulting strategy.
+    fn roa_issuance_strategy_update(
+        &self,
+        strategy: Option<RoaIssuanceStrategy>,
+        config: Arc<Config>,
+        signer: &KrillSigner,
+    ) -> KrillResult<Vec<CaEvt>> {
+        if strategy == self.roa_issuance_strategy {
+            return Ok(vec![]);
+        }
+
+        if let Some(strategy) = &strategy {
+            strategy
+                .verify()
+                .map_err(|msg| Error::CaRoaIssuanceStrategyInvalid(self.handle.clone(), msg))?;
+        }
+
+        // Without a CA specific strategy, the strategy from the config is used.
+        let mut effective = config.as_ref().clone();
+        effective.issuance_timing = config.issuance_timing.with_overrides(&self.issuance_timing);
+        if let Some(strategy) = strategy {
+            effective.roa_issuance_strategy = strategy;
+        }
+
+        info!(
+            "CA '{}' updated ROA issuance strategy to: {}",
+            self.handle, effective.roa_issuance_strategy
+        );
+
+        let mut evt_dets = vec![CaEvtDet::RoaIssuanceStrategyUpdated { strategy }];
+
+        for (rcn, rc) in self.resources.iter() {
+            let updates = rc.update_roas(&self.routes, &effective, signer)?;
+            if updates.contains_changes() {
+                info!("CA '{}' under RC '{}' updated ROAs: {}", self.handle, rcn, updates);
+
+                evt_dets.push(CaEvtDet::RoasUpdated {
+                    resource_class_name: rcn.clone(),
+                    updates,
+                });
+            }
+        }
+
+        Ok(self.events_from_details(evt_dets))
+    }
+
+    /// Sets, or with None removes, the CA specific ROA auto-pilot policy.
+    /// Without it, the policy for the CA in the config is used, if any.
+    fn roa_autopilot_update(&self, policy: Option<RoaAutoPilotPolicy>) -> KrillResult<Vec<CaEvt>> {
+        if policy.as_ref() == self.roa_autopilot.as_ref() {
+            return Ok(vec![]);
+        }
+
+        if let Some(policy) = &policy {
+            policy
+                .verify()
+                .map_err(|msg| Error::CaRoaAutoPilotInvalid(self.handle.clone(), msg))?;
+        }
+
+        Ok(self.events_from_details(vec![CaEvtDet::RoaAutoPilotUpdated { policy }]))
+    }
+
+    /// Stages an update of the route authorizations as a pending proposal.
+    /// The update is verified against the current authorizations, but no
+    /// ROAs are issued until the proposal is approved.
+    fn route_authorizations_propose(
+        &self,
+        route_auth_updates: RoaConfigurationUpdates,
+        proposer: String,
+    ) -> KrillResult<Vec<CaEvt>> {
+        let route_auth_updates = route_auth_updates.into_explicit_max_length();
+        self.update_authorizations(&route_auth_updates)?;
+
+        // The version of this CA is unique for each event, so we can use
+        // it to identify the proposal.
+        let proposal = RoaProposal::new(self.version, proposer, route_auth_updates);
+        info!(
+            "CA '{}' staged ROA proposal {} by '{}'",
+            self.handle,
+            proposal.id(),
+            proposal.proposer()
+        );
+
+        Ok(self.events_from_details(vec![CaEvtDet::RoaProposalAdded { proposal }]))
+    }
+
+    /// Approves a pending proposal. The updates are verified again, because
+    /// the authorizations may have changed since the proposal was made. If
+    /// they are no longer valid an error is returned and the proposal stays
+    /// pending, so that it can be rejected.
+    ///
+    /// The whole point of a proposal is that a second person looks at it, so
+    /// the proposer cannot approve it.
+    fn route_authorizations_approve(
+        &self,
+        id: u64,
+        approver: &str,
+        config: &Config,
+        signer: Arc<KrillSigner>,
+    ) -> KrillResult<Vec<CaEvt>> {
+        let proposal = self
+            .roa_proposals
+            .get(id)
+            .ok_or_else(|| Error::RoaProposalUnknown(self.handle.clone(), id))?;
+
+        if proposal.proposer() == approver {
+            return Err(Error::RoaProposalSelfApproval(self.handle.clone(), id));
+        }
+
+        let mut evt_dets = vec![CaEvtDet::RoaProposalApproved { id }];
+        evt_dets.append(&mut self.route_authorizations_update_details(
+            proposal.updates().clone(),
+            config,
+            signer.deref(),
+        )?);
+
+        info!("CA '{}' approved ROA proposal {}", self.handle, id);
+
+        Ok(self.events_from_details(evt_dets))
+    }
+
+    /// Rejects a pending proposal.
+    fn route_authorizations_reject(&self, id: u64) -> KrillResult<Vec<CaEvt>> {
+        if self.roa_proposals.get(id).is_none() {
+            return Err(Error::RoaProposalUnknown(self.handle.clone(), id));
+        }
+
+        info!("CA '{}' rejected ROA proposal {}", self.handle, id);
+
+        Ok(self.events_from_details(vec![CaEvtDet::RoaProposalRejected { id }]))
+    }
+
+    /// Schedules a ROA or ASPA update. The update is verified against the
+    /// current configuration, but it is only applied when it is due.
+    fn scheduled_change_add(&self, request: ScheduledChangeRequest, scheduled_by: String) -> KrillResult<Vec<CaEvt>> {
+        if request.activate_at <= Timestamp::now() {
+            return Err(Error::CaScheduledChangeInvalid(
+                self.handle.clone(),
+                "the activation time must be in the future".to_string(),
+            ));
+        }
+        if request.activate_at > Timestamp::now_plus_hours(SCHEDULED_CHANGE_MAX_DAYS * 24) {
+            return Err(Error::CaScheduledChangeInvalid(
+                self.handle.clone(),
+                format!(
+                    "the activation time must be within {} days from now",
+                    SCHEDULED_CHANGE_MAX_DAYS
+                ),
+            ));
+        }
+
+        let update = match request.update {
+            ScheduledUpdate::Roas(updates) => {
+                let updates = updates.into_explicit_max_length();
+                if updates.is_empty() {
+                    return Err(Error::CaScheduledChangeInvalid(
+                        self.handle.clone(),
+                        "the update is empty".to_string(),
+                    ));
+                }
+                self.update_authorizations(&updates)?;
+                ScheduledUpdate::Roas(updates)
+            }
+            ScheduledUpdate::Aspas(updates) => {
+                if updates.is_empty() {
+                    return Err(Error::CaScheduledChangeInvalid(
+                        self.handle.clone(),
+                        "the update is empty".to_string(),
+                    ));
+                }
+                self.aspas_definitions_changes(updates.clone())?;
+                ScheduledUpdate::Aspas(updates)
+            }
+        };
+
+        let request = ScheduledChangeRequest {
+            activate_at: request.activate_at,
+            update,
+        };
+
+        // The version of this CA is unique for each event, so we can use
+        // it to identify the change.
+        let change = ScheduledChange::new(self.version, scheduled_by, request);
+        info!(
+            "CA '{}' scheduled change {} by '{}' at {}",
+            self.handle,
+            change.id(),
+            change.scheduled_by(),
+            change.activate_at().to_rfc3339()
+        );
+
+        Ok(self.events_from_details(vec![CaEvtDet::ScheduledChangeAdded { change }]))
+    }
+
+    /// Applies a scheduled change. If the update is no longer valid, e.g.
+    /// because the resources of this CA changed since it was scheduled, then
+    /// the change is dropped and the reason is recorded in the history.
+    fn scheduled_change_apply(&self, id: u64, config: &Config, signer: &KrillSigner) -> KrillResult<Vec<CaEvt>> {
+        let change = self
+            .scheduled_changes
+            .get(id)
+            .ok_or_else(|| Error::CaScheduledChangeUnknown(self.handle.clone(), id))?;
+
+        let result = match change.update() {
+            ScheduledUpdate::Roas(updates) => self.route_authorizations_update_details(updates.clone(), config, signer),
+            ScheduledUpdate::Aspas(updates) => self.aspas_definitions_update_details(updates.clone(), config, signer),
+        };
+
+        let evt_dets = match result {
+            Ok(mut update_dets) => {
+                info!("CA '{}' applied scheduled change {}", self.handle, id);
+                let mut evt_dets = vec![CaEvtDet::ScheduledChangeApplied { id }];
+                evt_dets.append(&mut update_dets);
+                evt_dets
+            }
+            // Signer issues are not caused by the change, so try again later.
+            Err(Error::SignerError(e)) => return Err(Error::SignerError(e)),
+            Err(e) => {
+                warn!("CA '{}' could not apply scheduled change {}: {}", self.handle, id, e);
+                vec![CaEvtDet::ScheduledChangeFailed {
+                    id,
+                    reason: e.to_string(),
+                }]
+            }
+        };
+
         Ok(self.events_from_details(evt_dets))
     }
 
+    /// Cancels a scheduled change.
+    fn scheduled_change_cancel(&self, id: u64) -> KrillResult<Vec<CaEvt>> {
+        if self.scheduled_changes.get(id).is_none() {
+            return Err(Error::CaScheduledChangeUnknown(self.handle.clone(), id));
+        }
+
+        info!("CA '{}' cancelled scheduled change {}", self.handle, id);
+
+        Ok(self.events_from_details(vec![CaEvtDet::ScheduledChangeCancelled { id }]))
+    }
+
     /// Renew existing ROA objects if needed.
     pub fn route_authorizations_renew(
         &self,
@@ -1686,6 +3182,36 @@ impl CertAuth {
                         comment: comment.cloned(),
                     });
                 }
+
+                // New configurations can be added as disabled. Existing configurations
+                // are disabled or enabled explicitly, see below.
+                if roa_configuration.is_disabled() {
+                    desired_routes.disabled(&auth, true);
+                    res.push(CaEvtDet::RouteAuthorizationDisabled { auth });
+                }
+            }
+        }
+
+        // make sure that disabled and enabled payloads are configured, and
+        // only change those that are not already in the desired state
+        for (roa_payload, disable) in updates
+            .disabled()
+            .iter()
+            .map(|p| (p, true))
+            .chain(updates.enabled().iter().map(|p| (p, false)))
+        {
+            let auth = RoaPayloadJsonMapKey::from(*roa_payload);
+            match desired_routes.info(&auth).map(|info| info.is_disabled()) {
+                None => delta_errors.add_unknown(*roa_payload),
+                Some(disabled) if disabled == disable => {} // nothing to do
+                Some(_) => {
+                    desired_routes.disabled(&auth, disable);
+                    if disable {
+                        res.push(CaEvtDet::RouteAuthorizationDisabled { auth });
+                    } else {
+                        res.push(CaEvtDet::RouteAuthorizationEnabled { auth });
+                    }
+                }
             }
         }
 
@@ -1715,6 +3241,30 @@ impl CertAuth {
         config: &Config,
         signer: &KrillSigner,
     ) -> KrillResult<Vec<CaEvt>> {
+        let evt_dets = self.aspas_definitions_update_details(updates, config, signer)?;
+        Ok(self.events_from_details(evt_dets))
+    }
+
+    /// Returns the event details for updating the ASPA definitions and
+    /// objects. This is shared by direct and scheduled updates.
+    fn aspas_definitions_update_details(
+        &self,
+        updates: AspaDefinitionUpdates,
+        config: &Config,
+        signer: &KrillSigner,
+    ) -> KrillResult<Vec<CaEvtDet>> {
+        let (all_aspas, mut res) = self.aspas_definitions_changes(updates)?;
+        res.append(&mut self.create_updated_aspa_objects(&all_aspas, config, signer)?);
+        Ok(res)
+    }
+
+    /// Verifies the AspaDefinitionUpdates and returns the definitions as
+    /// they would be after applying them, as well as the event details for
+    /// the changed definitions. No ASPA objects are created.
+    fn aspas_definitions_changes(
+        &self,
+        updates: AspaDefinitionUpdates,
+    ) -> KrillResult<(AspaDefinitions, Vec<CaEvtDet>)> {
         let mut res = vec![];
 
         let (add_or_replace, remove) = updates.unpack();
@@ -1782,9 +3332,7 @@ impl CertAuth {
             }
         }
 
-        res.append(&mut self.create_updated_aspa_objects(&all_aspas, config, signer)?);
-
-        Ok(self.events_from_details(res))
+        Ok((all_aspas, res))
     }
 
     pub fn aspas_update(
@@ -1807,6 +3355,63 @@ impl CertAuth {
         }
     }
 
+    /// Determines the AspaDefinitionUpdates needed to go from the current
+    /// definitions to the given complete set of definitions. Definitions
+    /// which are unchanged are left out, so that their ASPA objects are
+    /// not re-issued. Customers which are not included are removed.
+    pub fn aspas_definitions_import_delta(
+        &self,
+        definitions: AspaDefinitionList,
+    ) -> KrillResult<AspaDefinitionUpdates> {
+        let definitions = definitions.unpack();
+
+        let mut add_or_replace: Vec<AspaDefinition> = vec![];
+        for definition in definitions {
+            let customer = definition.customer();
+            if add_or_replace.iter().any(|added| added.customer() == customer) {
+                return Err(Error::AspaCustomerAlreadyPresent(self.handle().clone(), customer));
+            }
+            add_or_replace.push(definition);
+        }
+
+        let remove = self
+            .aspas
+            .all()
+            .map(|existing| existing.customer())
+            .filter(|customer| !add_or_replace.iter().any(|def| def.customer() == *customer))
+            .collect();
+
+        add_or_replace.retain(|def| self.aspas.get(def.customer()) != Some(def));
+
+        Ok(AspaDefinitionUpdates::new(add_or_replace, remove))
+    }
+
+    /// Evaluates an update to the providers for a customer ASN without
+    /// applying it. Returns the resulting definition, and the ASPA objects
+    /// which would be issued, replaced or removed in each resource class.
+    ///
+    /// The update is validated in the same way as it would be when applied.
+    pub fn aspas_update_dry_run(
+        &self,
+        customer: AspaCustomer,
+        update: &AspaProvidersUpdate,
+        config: &Config,
+        signer: &KrillSigner,
+    ) -> KrillResult<AspaUpdateDryRun> {
+        let mut all_aspas = self.aspas.clone();
+
+        let resource_classes = if self.updated_allowed_and_needed(customer, update)? {
+            all_aspas.apply_update(customer, update);
+            self.updated_aspa_objects_per_rc(&all_aspas, config, signer)?
+        } else {
+            HashMap::new()
+        };
+
+        let definition = all_aspas.get(customer).cloned();
+
+        Ok(AspaUpdateDryRun::new(customer, definition, resource_classes))
+    }
+
     /// Renew existing ASPA objects if needed.
     pub fn aspas_renew(&self, config: &Config, signer: &KrillSigner) -> KrillResult<Vec<CaEvt>> {
         let mut evt_dets = vec![];
@@ -1835,18 +3440,35 @@ impl CertAuth {
         config: &Config,
         signer: &KrillSigner,
     ) -> KrillResult<Vec<CaEvtDet>> {
-        let mut update_events = vec![];
+        let update_events = self
+            .updated_aspa_objects_per_rc(all_aspas, config, signer)?
+            .into_iter()
+            .map(|(resource_class_name, updates)| CaEvtDet::AspaObjectsUpdated {
+                resource_class_name,
+                updates,
+            })
+            .collect();
+
+        Ok(update_events)
+    }
+
+    /// Returns the ASPA object updates for all resource classes which
+    /// would see changes given the (updated) definitions.
+    fn updated_aspa_objects_per_rc(
+        &self,
+        all_aspas: &AspaDefinitions,
+        config: &Config,
+        signer: &KrillSigner,
+    ) -> KrillResult<HashMap<ResourceClassName, AspaObjectsUpdates>> {
+        let mut res = HashMap::new();
 
         for (rcn, rc) in self.resources.iter() {
             let updates = rc.update_aspas(all_aspas, config, signer)?;
             if updates.contains_changes() {
-                update_events.push(CaEvtDet::AspaObjectsUpdated {
-                    resource_class_name: rcn.clone(),
-                    updates,
-                });
+                res.insert(rcn.clone(), updates);
             }
         }
-        Ok(update_events)
+        Ok(res)
     }
 
     /// Verifies whether the update is allowed and needs to be applied.
@@ -1867,6 +3489,10 @@ impl CertAuth {
             .cloned()
             .unwrap_or_else(|| AspaDefinition::new(customer, vec![]));
 
+        if update.contains_duplicate_providers() {
+            return Err(Error::AspaProvidersDuplicates(self.handle().clone(), customer));
+        }
+
         let mut updated = existing.clone();
         updated.apply_update(update);
 
@@ -1989,6 +3615,35 @@ impl CertAuth {
     }
 }
 
+/// # RPKI Signed Checklists
+///
+impl CertAuth {
+    /// Sign an RSC using a resource class that holds all requested resources.
+    /// RSCs are not kept by the CA, so this results in no events.
+    pub fn rsc_sign(&self, request: &RscContentRequest, signer: &KrillSigner) -> KrillResult<RpkiSignedChecklist> {
+        let rc = self
+            .resources
+            .values()
+            .find(|rc| {
+                rc.current_resources()
+                    .map(|held| held.contains(request.resources()))
+                    .unwrap_or(false)
+            })
+            .ok_or(Error::RscResourcesNotHeld)?;
+
+        let rsc = RpkiSignedChecklist::create(request, rc, signer)?;
+
+        info!(
+            "CA '{}' signed an RSC for {} file(s) over resources: {}",
+            self.handle,
+            request.checklist().len(),
+            request.resources()
+        );
+
+        Ok(rsc)
+    }
+}
+
 /// # Resource Tagged Attestations
 ///
 impl CertAuth {
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use rpki::{
     ca::{idexchange::ChildHandle, provisioning::ResourceClassName},
@@ -8,7 +8,10 @@ use rpki::{
 
 use crate::{
     commons::{
-        api::{ChildCaInfo, ChildState, IdCertInfo, IssuedCertificate, ReceivedCert, SuspendedCert, UnsuspendedCert},
+        api::{
+            ChildCaInfo, ChildState, IdCertInfo, IssuedCertificate, PendingChildRequest, PendingChildRequestKind,
+            PendingChildRequests, ReceivedCert, ResourceTransfer, ResourceTransferList, SuspendedCert, UnsuspendedCert,
+        },
         crypto::{KrillSigner, SignSupport},
         error::Error,
         KrillResult,
@@ -41,6 +44,25 @@ pub struct ChildDetails {
     id_cert: IdCertInfo,
     resources: ResourceSet,
     used_keys: HashMap<KeyIdentifier, UsedKeyState>,
+
+    // If set, then the child is never suspended automatically when it is
+    // inactive.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    suspension_exempt: bool,
+
+    // If set, then all issuance and revocation requests from the child
+    // are held back until they are approved.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    manual_approval: bool,
+
+    // Requests held back until they are approved, and approved requests
+    // which the child did not ask for again yet. Requests are identified by
+    // their kind and key, as both a certificate and its revocation can be
+    // held back for the same key.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pending_requests: Vec<PendingChildRequest>,
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    approved_requests: HashSet<(PendingChildRequestKind, KeyIdentifier)>,
 }
 
 impl ChildDetails {
@@ -50,9 +72,17 @@ impl ChildDetails {
             id_cert,
             resources,
             used_keys: HashMap::new(),
+            suspension_exempt: false,
+            manual_approval: false,
+            pending_requests: vec![],
+            approved_requests: HashSet::new(),
         }
     }
 
+    pub fn state(&self) -> ChildState {
+        self.state
+    }
+
     pub fn is_suspended(&self) -> bool {
         self.state == ChildState::Suspended
     }
@@ -65,6 +95,22 @@ impl ChildDetails {
         self.state = ChildState::Active;
     }
 
+    pub fn is_suspension_exempt(&self) -> bool {
+        self.suspension_exempt
+    }
+
+    pub fn set_suspension_exempt(&mut self, exempt: bool) {
+        self.suspension_exempt = exempt;
+    }
+
+    pub fn is_manual_approval(&self) -> bool {
+        self.manual_approval
+    }
+
+    pub fn set_manual_approval(&mut self, manual: bool) {
+        self.manual_approval = manual;
+    }
+
     pub fn id_cert(&self) -> &IdCertInfo {
         &self.id_cert
     }
@@ -100,10 +146,47 @@ impl ChildDetails {
     }
 
     pub fn add_issue_response(&mut self, rcn: ResourceClassName, ki: KeyIdentifier) {
+        self.approved_requests.remove(&(PendingChildRequestKind::Issue, ki));
         self.used_keys.insert(ki, UsedKeyState::Current(rcn));
     }
 
+    /// Returns true if a certificate was ever issued for this key.
+    pub fn is_known_key(&self, ki: &KeyIdentifier) -> bool {
+        self.used_keys.contains_key(ki)
+    }
+
+    pub fn pending_requests(&self) -> PendingChildRequests {
+        let mut requests = self.pending_requests.clone();
+        requests.sort_by_key(|req| req.since);
+        PendingChildRequests::new(requests)
+    }
+
+    pub fn is_pending(&self, kind: PendingChildRequestKind, ki: &KeyIdentifier) -> bool {
+        self.pending_requests
+            .iter()
+            .any(|req| req.kind == kind && &req.key == ki)
+    }
+
+    pub fn is_approved(&self, kind: PendingChildRequestKind, ki: &KeyIdentifier) -> bool {
+        self.approved_requests.contains(&(kind, *ki))
+    }
+
+    pub fn queue_request(&mut self, request: PendingChildRequest) {
+        self.pending_requests
+            .retain(|req| !(req.kind == request.kind && req.key == request.key));
+        self.pending_requests.push(request);
+    }
+
+    pub fn approve_request(&mut self, kind: PendingChildRequestKind, ki: KeyIdentifier) {
+        let before = self.pending_requests.len();
+        self.pending_requests.retain(|req| !(req.kind == kind && req.key == ki));
+        if self.pending_requests.len() < before {
+            self.approved_requests.insert((kind, ki));
+        }
+    }
+
     pub fn add_revoke_response(&mut self, ki: KeyIdentifier) {
+        self.approved_requests.remove(&(PendingChildRequestKind::Revoke, ki));
         self.used_keys.insert(ki, UsedKeyState::Revoked);
     }
 
@@ -125,6 +208,8 @@ impl ChildDetails {
 impl From<ChildDetails> for ChildCaInfo {
     fn from(details: ChildDetails) -> Self {
         ChildCaInfo::new(details.state, details.id_cert, details.resources)
+            .with_suspension_exempt(details.suspension_exempt)
+            .with_manual_approval(details.manual_approval)
     }
 }
 
@@ -136,6 +221,48 @@ pub struct Children {
     inner: HashMap<ChildHandle, ChildDetails>,
 }
 
+//------------ ResourceTransfers -------------------------------------------
+
+/// Resource transfers between children of a parent [`CertAuth`], including
+/// finished transfers so that their outcome can be reviewed.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResourceTransfers {
+    map: BTreeMap<u64, ResourceTransfer>,
+}
+
+impl ResourceTransfers {
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ResourceTransfer> {
+        self.map.get(&id)
+    }
+
+    /// Adds the transfer, or replaces an existing transfer with the same id.
+    pub fn update(&mut self, transfer: ResourceTransfer) {
+        self.map.insert(transfer.id(), transfer);
+    }
+
+    pub fn waiting(&self) -> impl Iterator<Item = &ResourceTransfer> {
+        self.map.values().filter(|transfer| transfer.is_waiting())
+    }
+
+    pub fn has_waiting(&self) -> bool {
+        self.waiting().next().is_some()
+    }
+
+    /// Returns true if there is a waiting transfer involving the child.
+    pub fn is_transferring(&self, child: &ChildHandle) -> bool {
+        self.waiting()
+            .any(|transfer| transfer.from() == child || transfer.to() == child)
+    }
+
+    pub fn list(&self) -> ResourceTransferList {
+        ResourceTransferList::new(self.map.values().cloned().collect())
+    }
+}
+
 //------------ ChildCertificates -------------------------------------------
 
 /// The collection of certificates issued under a [ResourceClass](ca.ResourceClass).
@@ -304,3 +431,72 @@ impl ChildCertificates {
             .collect()
     }
 }
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test;
+
+    fn child() -> ChildDetails {
+        let id_cert = IdCertInfo::from(&test::test_id_certificate());
+        ChildDetails::new(id_cert, ResourceSet::empty())
+    }
+
+    fn request(kind: PendingChildRequestKind, ki: KeyIdentifier) -> PendingChildRequest {
+        PendingChildRequest::new(kind, ki, ResourceClassName::default(), "test".to_string())
+    }
+
+    #[test]
+    fn issue_and_revoke_requests_are_kept_apart() {
+        let ki = KeyIdentifier::from([1; 20]);
+        let issue = PendingChildRequestKind::Issue;
+        let revoke = PendingChildRequestKind::Revoke;
+
+        let mut child = child();
+        child.queue_request(request(issue, ki));
+        child.queue_request(request(revoke, ki));
+        assert!(child.is_pending(issue, &ki));
+        assert!(child.is_pending(revoke, &ki));
+
+        // Queueing the same request again replaces it.
+        child.queue_request(request(issue, ki));
+        assert_eq!(child.pending_requests().len(), 2);
+
+        // Approving the issuance does not approve the revocation.
+        child.approve_request(issue, ki);
+        assert!(child.is_approved(issue, &ki));
+        assert!(!child.is_pending(issue, &ki));
+        assert!(!child.is_approved(revoke, &ki));
+        assert!(child.is_pending(revoke, &ki));
+
+        // The approval is used up by the response.
+        child.add_issue_response(ResourceClassName::default(), ki);
+        assert!(!child.is_approved(issue, &ki));
+
+        child.approve_request(revoke, ki);
+        assert!(child.is_approved(revoke, &ki));
+        child.add_revoke_response(ki);
+        assert!(!child.is_approved(revoke, &ki));
+        assert_eq!(child.pending_requests().len(), 0);
+
+        // Approving a request which is not pending does nothing.
+        child.approve_request(issue, ki);
+        assert!(!child.is_approved(issue, &ki));
+    }
+
+    #[test]
+    fn pending_requests_round_trip() {
+        let ki = KeyIdentifier::from([1; 20]);
+        let mut child = child();
+        child.queue_request(request(PendingChildRequestKind::Issue, ki));
+        child.queue_request(request(PendingChildRequestKind::Revoke, ki));
+        child.approve_request(PendingChildRequestKind::Revoke, ki);
+
+        let json = serde_json::to_string(&child).unwrap();
+        let parsed: ChildDetails = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, child);
+    }
+}
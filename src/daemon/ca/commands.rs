@@ -10,6 +10,7 @@ use rpki::{
             RevocationResponse,
         },
     },
+    crypto::KeyIdentifier,
     repository::resources::ResourceSet,
 };
 
@@ -17,12 +18,14 @@ use crate::{
     commons::{
         actor::Actor,
         api::{
-            AspaCustomer, AspaDefinitionUpdates, AspaProvidersUpdate, BgpSecDefinitionUpdates, IdCertInfo,
-            ParentCaContact, ReceivedCert, RepositoryContact, RoaConfigurationUpdates, RtaName, StorableCaCommand,
-            StorableRcEntitlement,
+            AspaCustomer, AspaDefinitionUpdates, AspaProvidersUpdate, BgpSecDefinitionUpdates, CaLabelsUpdate,
+            ChildIssuancePolicy, CommandRevert, IdCertInfo, IssuanceTimingOverrides, ParentCaContact,
+            PendingChildRequest, PendingChildRequestKind, ReceivedCert, RepositoryContact, ResourceTransferRequest,
+            RetryPolicy, RoaAutoPilotPolicy, RoaConfigurationUpdates, RoaIssuanceStrategy, RtaName,
+            ScheduledChangeRequest, StorableCaCommand, StorableRcEntitlement,
         },
         crypto::KrillSigner,
-        eventsourcing::{self, StoredCommand},
+        eventsourcing::{self, CommandKey, StoredCommand},
     },
     daemon::{
         ca::{CaEvt, ResourceTaggedAttestation, RtaContentRequest, RtaPrepareRequest},
@@ -79,6 +82,35 @@ pub enum CmdDet {
     // about to expire, and do not claim resources no longer associated with this child.
     ChildUnsuspend(ChildHandle),
 
+    // Exempt a child from (or subject it again to) the automatic suspension of
+    // inactive children. This does not change whether the child is currently
+    // suspended.
+    ChildUpdateSuspensionExempt(ChildHandle, bool),
+
+    // Require, or no longer require, that issuance and revocation requests
+    // from a child are approved manually.
+    ChildUpdateManualApproval(ChildHandle, bool),
+
+    // Replace the policy which is enforced before certificates are issued
+    // to children. The config is used to verify the resulting timing values.
+    ChildIssuancePolicyUpdate(ChildIssuancePolicy, Arc<Config>),
+
+    // Hold back a child request which needs to be approved first.
+    ChildRequestQueue(ChildHandle, PendingChildRequest),
+
+    // Approve a pending child request. It will be honored when the child
+    // asks again.
+    ChildRequestApprove(ChildHandle, PendingChildRequestKind, KeyIdentifier),
+
+    // Start moving resources from one child to another. The resources are
+    // removed from the first child straight away, but only added to the
+    // second child after the first child got certificates without them.
+    ChildResourceTransferStart(ResourceTransferRequest),
+
+    // Complete a waiting resource transfer if the resources were re-issued,
+    // or roll it back if its deadline passed.
+    ChildResourceTransferProgress(u64),
+
     // ------------------------------------------------------------
     // Being a child (only allowed if this CA is not self-signed)
     // ------------------------------------------------------------
@@ -91,6 +123,14 @@ pub enum CmdDet {
     // on how to re-do the ID exchange.
     GenerateNewIdKey(Arc<KrillSigner>),
 
+    // Generate a new ID key and cert to roll to, without using it yet. This
+    // allows for the new ID certificate to be given to the repository (and
+    // parents), before it is activated.
+    IdRollInit(Arc<KrillSigner>),
+
+    // Start using the new ID key and cert.
+    IdRollActivate,
+
     // Add a parent to this CA. Can have multiple parents.
     AddParent(ParentHandle, ParentCaContact),
     // Update a parent's contact
@@ -119,6 +159,13 @@ pub enum CmdDet {
     // exceeds the given duration.
     KeyRollInitiate(Duration, Arc<KrillSigner>),
 
+    // Initiate a key roll for all resource classes where there is a current active
+    // key only, and this key is not held by the signer used for new keys. Activate
+    // the new keys of such rolls, if they are held by the signer used for new keys
+    // and their age exceeds the given duration. This is used to move the keys of a
+    // CA to another signer, e.g. from OpenSSL to an HSM.
+    KeyRollMigrate(Duration, Arc<Config>, Arc<KrillSigner>),
+
     // For all resource classes with a 'new' key with an age exceeding the duration:
     //  - Promote the new key to current key
     //  - Publish all objects under the new current key
@@ -152,6 +199,39 @@ pub enum CmdDet {
     // Re-issue all ROA objects regardless of their expiration time.
     RouteAuthorizationsForceRenew(Arc<Config>, Arc<KrillSigner>),
 
+    // Stage an update of the authorizations for a CA. The update is kept
+    // as a pending proposal and no ROA objects are issued until it is
+    // approved.
+    RouteAuthorizationsPropose(RoaConfigurationUpdates),
+
+    // Approve a pending proposal, i.e. apply its updates and issue ROAs.
+    RouteAuthorizationsApprove(u64, Arc<Config>, Arc<KrillSigner>),
+
+    // Reject a pending proposal.
+    RouteAuthorizationsReject(u64),
+
+    // Set, or with None remove, the CA specific strategy for combining
+    // prefixes into ROA objects, and re-issue ROAs accordingly.
+    RoaIssuanceStrategyUpdate(Option<RoaIssuanceStrategy>, Arc<Config>, Arc<KrillSigner>),
+
+    // Set, or with None remove, the CA specific policy of the ROA auto-pilot.
+    RoaAutoPilotUpdate(Option<RoaAutoPilotPolicy>),
+
+    // Schedule a ROA or ASPA update to be applied at a later moment. The
+    // update is verified, but not applied, when it is scheduled.
+    ScheduledChangeAdd(ScheduledChangeRequest),
+
+    // Apply a scheduled change which is due. This command is sent by the
+    // scheduler.
+    ScheduledChangeApply(u64, Arc<Config>, Arc<KrillSigner>),
+
+    // Cancel a scheduled change.
+    ScheduledChangeCancel(u64),
+
+    // Revert the ROA or ASPA changes of an earlier command, identified by
+    // its key, by applying the given compensating changes.
+    CommandRevert(CommandKey, CommandRevert, Arc<Config>, Arc<KrillSigner>),
+
     // ------------------------------------------------------------
     // ASPA Support
     // ------------------------------------------------------------
@@ -187,6 +267,25 @@ pub enum CmdDet {
     // Update the repository where this CA publishes
     RepoUpdate(RepositoryContact, Arc<KrillSigner>),
 
+    // Update the repository where a resource class publishes, or revert
+    // it to the repository of the CA.
+    ClassRepoUpdate(ResourceClassName, Option<RepositoryContact>, Arc<KrillSigner>),
+
+    // Abort a migration to another repository, and return to the repository
+    // which the CA was migrating from.
+    RepoMigrationAbort(RepositoryContact, Arc<KrillSigner>),
+
+    // Replace the CA specific overrides of the issuance timing config.
+    // The config is used to verify the resulting timing values.
+    IssuanceTimingUpdate(IssuanceTimingOverrides, Arc<Config>),
+
+    // Replace the retry policy overrides for the repository, or for a
+    // parent. The config is used to verify the resulting policy.
+    RetryPolicyUpdate(Option<ParentHandle>, RetryPolicy, Arc<Config>),
+
+    // Set and/or remove labels of the CA.
+    LabelsUpdate(CaLabelsUpdate),
+
     // ------------------------------------------------------------
     // Resource Tagged Attestations
     // ------------------------------------------------------------
@@ -248,11 +347,28 @@ impl From<CmdDet> for StorableCaCommand {
             CmdDet::ChildRemove(child) => StorableCaCommand::ChildRemove { child },
             CmdDet::ChildSuspendInactive(child) => StorableCaCommand::ChildSuspendInactive { child },
             CmdDet::ChildUnsuspend(child) => StorableCaCommand::ChildUnsuspend { child },
+            CmdDet::ChildUpdateSuspensionExempt(child, exempt) => {
+                StorableCaCommand::ChildUpdateSuspensionExempt { child, exempt }
+            }
+            CmdDet::ChildUpdateManualApproval(child, manual) => {
+                StorableCaCommand::ChildUpdateManualApproval { child, manual }
+            }
+            CmdDet::ChildIssuancePolicyUpdate(policy, _) => StorableCaCommand::ChildIssuancePolicyUpdate { policy },
+            CmdDet::ChildRequestQueue(child, request) => StorableCaCommand::ChildRequestQueue {
+                child,
+                kind: request.kind,
+                ki: request.key,
+            },
+            CmdDet::ChildRequestApprove(child, kind, ki) => StorableCaCommand::ChildRequestApprove { child, kind, ki },
+            CmdDet::ChildResourceTransferStart(request) => StorableCaCommand::ChildResourceTransferStart { request },
+            CmdDet::ChildResourceTransferProgress(id) => StorableCaCommand::ChildResourceTransferProgress { id },
 
             // ------------------------------------------------------------
             // Being a child
             // ------------------------------------------------------------
             CmdDet::GenerateNewIdKey(_) => StorableCaCommand::GenerateNewIdKey,
+            CmdDet::IdRollInit(_) => StorableCaCommand::IdRollInit,
+            CmdDet::IdRollActivate => StorableCaCommand::IdRollActivate,
             CmdDet::AddParent(parent, contact) => StorableCaCommand::AddParent {
                 parent,
                 contact: contact.into(),
@@ -288,6 +404,9 @@ impl From<CmdDet> for StorableCaCommand {
             CmdDet::KeyRollInitiate(older_than, _) => StorableCaCommand::KeyRollInitiate {
                 older_than_seconds: older_than.num_seconds(),
             },
+            CmdDet::KeyRollMigrate(_, _, signer) => StorableCaCommand::KeyRollMigrate {
+                target_signer: signer.default_signer_name(),
+            },
             CmdDet::KeyRollActivate(staged_for, _, _) => StorableCaCommand::KeyRollActivate {
                 staged_for_seconds: staged_for.num_seconds(),
             },
@@ -299,6 +418,17 @@ impl From<CmdDet> for StorableCaCommand {
             CmdDet::RouteAuthorizationsUpdate(updates, _, _) => StorableCaCommand::RoaDefinitionUpdates { updates },
             CmdDet::RouteAuthorizationsRenew(_, _) => StorableCaCommand::ReissueBeforeExpiring,
             CmdDet::RouteAuthorizationsForceRenew(_, _) => StorableCaCommand::ForceReissue,
+            CmdDet::RouteAuthorizationsPropose(updates) => StorableCaCommand::RoaProposalAdd { updates },
+            CmdDet::RouteAuthorizationsApprove(id, _, _) => StorableCaCommand::RoaProposalApprove { id },
+            CmdDet::RouteAuthorizationsReject(id) => StorableCaCommand::RoaProposalReject { id },
+            CmdDet::RoaIssuanceStrategyUpdate(strategy, _, _) => {
+                StorableCaCommand::RoaIssuanceStrategyUpdate { strategy }
+            }
+            CmdDet::RoaAutoPilotUpdate(policy) => StorableCaCommand::RoaAutoPilotUpdate { policy },
+            CmdDet::ScheduledChangeAdd(request) => StorableCaCommand::ScheduledChangeAdd { request },
+            CmdDet::ScheduledChangeApply(id, _, _) => StorableCaCommand::ScheduledChangeApply { id },
+            CmdDet::ScheduledChangeCancel(id) => StorableCaCommand::ScheduledChangeCancel { id },
+            CmdDet::CommandRevert(command, revert, _, _) => StorableCaCommand::CommandRevert { command, revert },
 
             // ------------------------------------------------------------
             // ASPA Support
@@ -321,6 +451,16 @@ impl From<CmdDet> for StorableCaCommand {
             CmdDet::RepoUpdate(contact, _) => StorableCaCommand::RepoUpdate {
                 service_uri: contact.server_info().service_uri().clone(),
             },
+            CmdDet::ClassRepoUpdate(resource_class_name, contact, _) => StorableCaCommand::ClassRepoUpdate {
+                resource_class_name,
+                service_uri: contact.map(|contact| contact.server_info().service_uri().clone()),
+            },
+            CmdDet::RepoMigrationAbort(contact, _) => StorableCaCommand::RepoMigrationAbort {
+                service_uri: contact.server_info().service_uri().clone(),
+            },
+            CmdDet::IssuanceTimingUpdate(overrides, _) => StorableCaCommand::IssuanceTimingUpdate { overrides },
+            CmdDet::RetryPolicyUpdate(parent, policy, _) => StorableCaCommand::RetryPolicyUpdate { parent, policy },
+            CmdDet::LabelsUpdate(update) => StorableCaCommand::LabelsUpdate { update },
 
             // ------------------------------------------------------------
             // Resource Tagged Attestations
@@ -403,10 +543,82 @@ impl CmdDet {
         eventsourcing::SentCommand::new(handle, None, CmdDet::ChildUnsuspend(child_handle), actor)
     }
 
+    pub fn child_update_suspension_exempt(
+        handle: &CaHandle,
+        child_handle: ChildHandle,
+        exempt: bool,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::ChildUpdateSuspensionExempt(child_handle, exempt),
+            actor,
+        )
+    }
+
+    pub fn child_update_manual_approval(
+        handle: &CaHandle,
+        child_handle: ChildHandle,
+        manual: bool,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::ChildUpdateManualApproval(child_handle, manual),
+            actor,
+        )
+    }
+
+    pub fn child_issuance_policy_update(
+        handle: &CaHandle,
+        policy: ChildIssuancePolicy,
+        config: Arc<Config>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildIssuancePolicyUpdate(policy, config), actor)
+    }
+
+    pub fn child_request_queue(
+        handle: &CaHandle,
+        child_handle: ChildHandle,
+        request: PendingChildRequest,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildRequestQueue(child_handle, request), actor)
+    }
+
+    pub fn child_request_approve(
+        handle: &CaHandle,
+        child_handle: ChildHandle,
+        kind: PendingChildRequestKind,
+        ki: KeyIdentifier,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildRequestApprove(child_handle, kind, ki), actor)
+    }
+
+    pub fn child_resource_transfer_start(handle: &CaHandle, request: ResourceTransferRequest, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildResourceTransferStart(request), actor)
+    }
+
+    pub fn child_resource_transfer_progress(handle: &CaHandle, id: u64, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildResourceTransferProgress(id), actor)
+    }
+
     pub fn update_id(handle: &CaHandle, signer: Arc<KrillSigner>, actor: &Actor) -> Cmd {
         eventsourcing::SentCommand::new(handle, None, CmdDet::GenerateNewIdKey(signer), actor)
     }
 
+    pub fn id_roll_init(handle: &CaHandle, signer: Arc<KrillSigner>, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::IdRollInit(signer), actor)
+    }
+
+    pub fn id_roll_activate(handle: &CaHandle, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::IdRollActivate, actor)
+    }
+
     pub fn add_parent(handle: &CaHandle, parent: ParentHandle, info: ParentCaContact, actor: &Actor) -> Cmd {
         eventsourcing::SentCommand::new(handle, None, CmdDet::AddParent(parent, info), actor)
     }
@@ -473,6 +685,16 @@ impl CmdDet {
         eventsourcing::SentCommand::new(handle, None, CmdDet::KeyRollInitiate(duration, signer), actor)
     }
 
+    pub fn key_roll_migrate(
+        handle: &CaHandle,
+        staging: Duration,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::KeyRollMigrate(staging, config, signer), actor)
+    }
+
     pub fn key_roll_activate(
         handle: &CaHandle,
         staging: Duration,
@@ -491,6 +713,48 @@ impl CmdDet {
         eventsourcing::SentCommand::new(handle, None, CmdDet::RepoUpdate(contact, signer), actor)
     }
 
+    pub fn abort_repo_migration(
+        handle: &CaHandle,
+        contact: RepositoryContact,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RepoMigrationAbort(contact, signer), actor)
+    }
+
+    pub fn update_class_repo(
+        handle: &CaHandle,
+        rcn: ResourceClassName,
+        contact: Option<RepositoryContact>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ClassRepoUpdate(rcn, contact, signer), actor)
+    }
+
+    pub fn update_issuance_timing(
+        handle: &CaHandle,
+        overrides: IssuanceTimingOverrides,
+        config: Arc<Config>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::IssuanceTimingUpdate(overrides, config), actor)
+    }
+
+    pub fn update_retry_policy(
+        handle: &CaHandle,
+        parent: Option<ParentHandle>,
+        policy: RetryPolicy,
+        config: Arc<Config>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RetryPolicyUpdate(parent, policy, config), actor)
+    }
+
+    pub fn update_labels(handle: &CaHandle, update: CaLabelsUpdate, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::LabelsUpdate(update), actor)
+    }
+
     //-------------------------------------------------------------------------------
     // Route Authorizations
     //-------------------------------------------------------------------------------
@@ -509,6 +773,82 @@ impl CmdDet {
         )
     }
 
+    pub fn route_authorizations_propose(handle: &CaHandle, updates: RoaConfigurationUpdates, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RouteAuthorizationsPropose(updates), actor)
+    }
+
+    pub fn route_authorizations_approve(
+        handle: &CaHandle,
+        id: u64,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::RouteAuthorizationsApprove(id, config, signer),
+            actor,
+        )
+    }
+
+    pub fn route_authorizations_reject(handle: &CaHandle, id: u64, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RouteAuthorizationsReject(id), actor)
+    }
+
+    pub fn roa_issuance_strategy_update(
+        handle: &CaHandle,
+        strategy: Option<RoaIssuanceStrategy>,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::RoaIssuanceStrategyUpdate(strategy, config, signer),
+            actor,
+        )
+    }
+
+    pub fn roa_autopilot_update(handle: &CaHandle, policy: Option<RoaAutoPilotPolicy>, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RoaAutoPilotUpdate(policy), actor)
+    }
+
+    pub fn scheduled_change_add(handle: &CaHandle, request: ScheduledChangeRequest, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ScheduledChangeAdd(request), actor)
+    }
+
+    pub fn scheduled_change_apply(
+        handle: &CaHandle,
+        id: u64,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ScheduledChangeApply(id, config, signer), actor)
+    }
+
+    pub fn scheduled_change_cancel(handle: &CaHandle, id: u64, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ScheduledChangeCancel(id), actor)
+    }
+
+    pub fn command_revert(
+        handle: &CaHandle,
+        command: CommandKey,
+        revert: CommandRevert,
+        config: Arc<Config>,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::CommandRevert(command, revert, config, signer),
+            actor,
+        )
+    }
+
     //-------------------------------------------------------------------------------
     // Autonomous System Provider Authorization
     //-------------------------------------------------------------------------------
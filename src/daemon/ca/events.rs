@@ -12,8 +12,11 @@ use rpki::{
 use crate::{
     commons::{
         api::{
-            AspaCustomer, AspaDefinition, AspaProvidersUpdate, BgpSecAsnKey, IdCertInfo, IssuedCertificate, ObjectName,
-            ParentCaContact, ReceivedCert, RepositoryContact, RoaAggregateKey, RtaName, SuspendedCert, UnsuspendedCert,
+            AspaCustomer, AspaDefinition, AspaProvidersUpdate, BgpSecAsnKey, CaLabels, ChildIssuancePolicy, IdCertInfo,
+            IssuanceTimingOverrides, IssuedCertificate, ObjectName, ParentCaContact, PendingChildRequest,
+            PendingChildRequestKind, ReceivedCert, RepositoryContact, ResourceTransfer, RetryPolicies, RoaAggregateKey,
+            RoaAutoPilotPolicy, RoaIssuanceStrategy, RoaProposal, RtaName, ScheduledChange, SuspendedCert,
+            UnsuspendedCert,
         },
         crypto::KrillSigner,
         eventsourcing::StoredEvent,
@@ -42,6 +45,14 @@ impl Rfc8183Id {
         Ok(Rfc8183Id { cert })
     }
 
+    /// Creates an identity for an existing key, e.g. the key of a CA which
+    /// is migrated from another implementation.
+    pub fn for_key(signer: &KrillSigner, key: &KeyIdentifier) -> KrillResult<Self> {
+        let cert = signer.create_self_signed_id_cert_for_key(key)?;
+        let cert = IdCertInfo::from(&cert);
+        Ok(Rfc8183Id { cert })
+    }
+
     pub fn cert(&self) -> &IdCertInfo {
         &self.cert
     }
@@ -478,11 +489,45 @@ pub enum CaEvtDet {
     ChildUnsuspended {
         child: ChildHandle,
     },
+    ChildUpdatedSuspensionExempt {
+        child: ChildHandle,
+        exempt: bool,
+    },
+    ChildUpdatedManualApproval {
+        child: ChildHandle,
+        manual: bool,
+    },
+
+    // Child issuance policy events
+    ChildIssuancePolicyUpdated {
+        policy: ChildIssuancePolicy,
+    },
+    ChildRequestQueued {
+        child: ChildHandle,
+        request: PendingChildRequest,
+    },
+    ChildRequestApproved {
+        child: ChildHandle,
+        #[serde(default)]
+        kind: PendingChildRequestKind,
+        ki: KeyIdentifier,
+    },
+
+    // Resource transfer events
+    ResourceTransferStarted {
+        transfer: ResourceTransfer,
+    },
+    ResourceTransferFinished {
+        transfer: ResourceTransfer,
+    },
 
     // Being a child Events
     IdUpdated {
         id: Rfc8183Id,
     },
+    IdRollInitiated {
+        id: Rfc8183Id,
+    },
     ParentAdded {
         parent: ParentHandle,
         contact: ParentCaContact,
@@ -553,6 +598,15 @@ pub enum CaEvtDet {
         // We can remove it and stop publishing its mft and crl.
         resource_class_name: ResourceClassName,
     },
+    KeyRollAborted {
+        // A key roll for a repository migration is aborted before the new key was
+        // activated. A pending key is dropped. A new key which was already certified
+        // becomes the old key, so that its revocation is requested and its objects are
+        // withdrawn from the new repository when the roll is finished.
+        resource_class_name: ResourceClassName,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        revoke_req: Option<RevocationRequest>,
+    },
     UnexpectedKeyFound {
         // This event is generated in case our parent reports keys to us that we do not
         // believe we have. This should not happen in practice, but this is tracked so that
@@ -578,11 +632,59 @@ pub enum CaEvtDet {
         // Tracks a single authorization (VRP) which is removed. See remark for RouteAuthorizationAdded.
         auth: RoaPayloadJsonMapKey,
     },
+    RouteAuthorizationDisabled {
+        // Tracks a single authorization (VRP) which is kept, but for which no ROA is issued.
+        auth: RoaPayloadJsonMapKey,
+    },
+    RouteAuthorizationEnabled {
+        // Tracks a single disabled authorization (VRP) which is enabled again.
+        auth: RoaPayloadJsonMapKey,
+    },
     RoasUpdated {
         // Tracks ROA *objects* which are (re-)issued in a resource class.
         resource_class_name: ResourceClassName,
         updates: RoaUpdates,
     },
+    RoaProposalAdded {
+        // Tracks a ROA delta which needs to be approved before it is applied.
+        proposal: RoaProposal,
+    },
+    RoaProposalApproved {
+        // The approved delta is applied through the usual RouteAuthorization*
+        // and RoasUpdated events which follow this event.
+        id: u64,
+    },
+    RoaProposalRejected {
+        id: u64,
+    },
+    RoaIssuanceStrategyUpdated {
+        // None means that the strategy from the server config is used.
+        strategy: Option<RoaIssuanceStrategy>,
+    },
+    RoaAutoPilotUpdated {
+        // None means that the policy from the server config is used, if any.
+        policy: Option<RoaAutoPilotPolicy>,
+    },
+
+    // Scheduled ROA and ASPA changes
+    ScheduledChangeAdded {
+        // Tracks a ROA or ASPA update which is applied when it is due.
+        change: ScheduledChange,
+    },
+    ScheduledChangeApplied {
+        // The update is applied through the usual ROA or ASPA events which
+        // follow this event.
+        id: u64,
+    },
+    ScheduledChangeFailed {
+        // The update could no longer be applied when it was due, e.g.
+        // because the resources of the CA changed in the meantime.
+        id: u64,
+        reason: String,
+    },
+    ScheduledChangeCancelled {
+        id: u64,
+    },
 
     // ASPA
     AspaConfigAdded {
@@ -626,6 +728,30 @@ pub enum CaEvtDet {
         // requesting certificates when it knows which URIs it can use.
         contact: RepositoryContact,
     },
+    ClassRepoUpdated {
+        // Moves a resource class to another repository, or back to the repository
+        // of the CA if there is no contact. A key roll was started so that the new
+        // key will publish to the new repository.
+        resource_class_name: ResourceClassName,
+        contact: Option<RepositoryContact>,
+    },
+    RepoMigrationAborted {
+        // Returns the CA to the repository it was migrating from. This follows the
+        // `KeyRollAborted` events for the resource classes which were migrating.
+        contact: RepositoryContact,
+    },
+    IssuanceTimingUpdated {
+        // Replaces the CA specific overrides of the issuance timing config.
+        overrides: IssuanceTimingOverrides,
+    },
+    RetryPoliciesUpdated {
+        // Replaces the retry policy overrides for the repository and parents.
+        policies: RetryPolicies,
+    },
+    LabelsUpdated {
+        // Replaces all labels of the CA.
+        labels: CaLabels,
+    },
 
     // Rta
     //
@@ -650,6 +776,11 @@ impl CaEvtDet {
         StoredEvent::new(handle, version, CaEvtDet::IdUpdated { id })
     }
 
+    /// This marks that a new RFC8183Id was generated to roll to
+    pub(super) fn id_roll_initiated(handle: &CaHandle, version: u64, id: Rfc8183Id) -> CaEvt {
+        StoredEvent::new(handle, version, CaEvtDet::IdRollInitiated { id })
+    }
+
     /// This marks a parent as added to the CA.
     pub(super) fn parent_added(
         handle: &CaHandle,
@@ -706,6 +837,28 @@ impl CaEvtDet {
         StoredEvent::new(handle, version, CaEvtDet::ChildUpdatedResources { child, resources })
     }
 
+    pub(super) fn child_updated_suspension_exempt(
+        handle: &CaHandle,
+        version: u64,
+        child: ChildHandle,
+        exempt: bool,
+    ) -> CaEvt {
+        StoredEvent::new(
+            handle,
+            version,
+            CaEvtDet::ChildUpdatedSuspensionExempt { child, exempt },
+        )
+    }
+
+    pub(super) fn child_updated_manual_approval(
+        handle: &CaHandle,
+        version: u64,
+        child: ChildHandle,
+        manual: bool,
+    ) -> CaEvt {
+        StoredEvent::new(handle, version, CaEvtDet::ChildUpdatedManualApproval { child, manual })
+    }
+
     pub(super) fn child_certificate_issued(
         handle: &CaHandle,
         version: u64,
@@ -860,6 +1013,36 @@ impl fmt::Display for CaEvtDet {
             CaEvtDet::ChildRemoved { child } => write!(f, "removed child '{}'", child),
             CaEvtDet::ChildSuspended { child } => write!(f, "suspended child '{}'", child),
             CaEvtDet::ChildUnsuspended { child } => write!(f, "unsuspended child '{}'", child),
+            CaEvtDet::ChildUpdatedSuspensionExempt { child, exempt } => {
+                write!(f, "updated child '{}' suspension exempt to '{}'", child, exempt)
+            }
+            CaEvtDet::ChildUpdatedManualApproval { child, manual } => {
+                write!(f, "updated child '{}' manual approval to '{}'", child, manual)
+            }
+            CaEvtDet::ChildIssuancePolicyUpdated { policy } => {
+                write!(f, "updated child issuance policy: {}", policy.summary())
+            }
+            CaEvtDet::ChildRequestQueued { child, request } => {
+                write!(
+                    f,
+                    "queued {} request from child '{}' for key '{}': {}",
+                    request.kind, child, request.key, request.reason
+                )
+            }
+            CaEvtDet::ChildRequestApproved { child, kind, ki } => {
+                write!(f, "approved {} request from child '{}' for key '{}'", kind, child, ki)
+            }
+            CaEvtDet::ResourceTransferStarted { transfer } => write!(
+                f,
+                "started transfer {} of resources '{}' from child '{}' to child '{}'",
+                transfer.id(),
+                transfer.resources(),
+                transfer.from(),
+                transfer.to()
+            ),
+            CaEvtDet::ResourceTransferFinished { transfer } => {
+                write!(f, "finished transfer {}: {}", transfer.id(), transfer.state())
+            }
 
             // Being a child Events
             CaEvtDet::IdUpdated { id } => write!(
@@ -867,6 +1050,11 @@ impl fmt::Display for CaEvtDet {
                 "updated RFC8183 id to key '{}'",
                 id.cert().public_key().key_identifier()
             ),
+            CaEvtDet::IdRollInitiated { id } => write!(
+                f,
+                "generated new RFC8183 id to roll to, key '{}'",
+                id.cert().public_key().key_identifier()
+            ),
             CaEvtDet::ParentAdded { parent, .. } => {
                 write!(f, "added parent '{}' ", parent)
             }
@@ -947,6 +1135,22 @@ impl fmt::Display for CaEvtDet {
             CaEvtDet::KeyRollFinished { resource_class_name } => {
                 write!(f, "key roll: finished for resource class '{}'", resource_class_name)
             }
+            CaEvtDet::KeyRollAborted {
+                resource_class_name,
+                revoke_req,
+            } => match revoke_req {
+                Some(revoke_req) => write!(
+                    f,
+                    "key roll: aborted, requested revocation of new key '{}' under resource class '{}'",
+                    revoke_req.key(),
+                    resource_class_name
+                ),
+                None => write!(
+                    f,
+                    "key roll: aborted, removed pending key under resource class '{}'",
+                    resource_class_name
+                ),
+            },
             CaEvtDet::UnexpectedKeyFound {
                 resource_class_name,
                 revoke_req,
@@ -967,6 +1171,38 @@ impl fmt::Display for CaEvtDet {
                 }
             }
             CaEvtDet::RouteAuthorizationRemoved { auth } => write!(f, "removed ROA: '{}'", auth),
+            CaEvtDet::RouteAuthorizationDisabled { auth } => write!(f, "disabled ROA: '{}'", auth),
+            CaEvtDet::RouteAuthorizationEnabled { auth } => write!(f, "enabled ROA: '{}'", auth),
+            CaEvtDet::RoaProposalAdded { proposal } => write!(
+                f,
+                "added ROA proposal {} by '{}': {} additions, {} removals",
+                proposal.id(),
+                proposal.proposer(),
+                proposal.updates().added().len(),
+                proposal.updates().removed().len()
+            ),
+            CaEvtDet::RoaProposalApproved { id } => write!(f, "approved ROA proposal {}", id),
+            CaEvtDet::RoaProposalRejected { id } => write!(f, "rejected ROA proposal {}", id),
+            CaEvtDet::RoaIssuanceStrategyUpdated { strategy } => match strategy {
+                Some(strategy) => write!(f, "updated ROA issuance strategy to: {}", strategy),
+                None => write!(f, "removed CA specific ROA issuance strategy"),
+            },
+            CaEvtDet::RoaAutoPilotUpdated { policy } => match policy {
+                Some(policy) => write!(f, "updated ROA auto-pilot to: {}", policy),
+                None => write!(f, "removed CA specific ROA auto-pilot policy"),
+            },
+            CaEvtDet::ScheduledChangeAdded { change } => write!(
+                f,
+                "scheduled change {} by '{}' at {}",
+                change.id(),
+                change.scheduled_by(),
+                change.activate_at().to_rfc3339()
+            ),
+            CaEvtDet::ScheduledChangeApplied { id } => write!(f, "applied scheduled change {}", id),
+            CaEvtDet::ScheduledChangeFailed { id, reason } => {
+                write!(f, "could not apply scheduled change {}: {}", id, reason)
+            }
+            CaEvtDet::ScheduledChangeCancelled { id } => write!(f, "cancelled scheduled change {}", id),
             CaEvtDet::RoasUpdated {
                 resource_class_name,
                 updates,
@@ -1078,6 +1314,36 @@ impl fmt::Display for CaEvtDet {
                     contact.server_info().service_uri()
                 )
             }
+            CaEvtDet::ClassRepoUpdated {
+                resource_class_name,
+                contact,
+            } => match contact {
+                Some(contact) => write!(
+                    f,
+                    "updated repository for resource class '{}' to remote server: {}",
+                    resource_class_name,
+                    contact.server_info().service_uri()
+                ),
+                None => write!(
+                    f,
+                    "updated repository for resource class '{}' to the CA repository",
+                    resource_class_name
+                ),
+            },
+            CaEvtDet::RepoMigrationAborted { contact } => {
+                write!(
+                    f,
+                    "aborted repository migration, returned to remote server: {}",
+                    contact.server_info().service_uri()
+                )
+            }
+            CaEvtDet::IssuanceTimingUpdated { overrides } => {
+                write!(f, "updated issuance timing overrides: {}", overrides.summary())
+            }
+            CaEvtDet::RetryPoliciesUpdated { policies } => {
+                write!(f, "updated retry policy overrides: {}", policies.summary())
+            }
+            CaEvtDet::LabelsUpdated { labels } => write!(f, "updated labels: {}", labels.summary()),
 
             // Rta
             CaEvtDet::RtaPrepared { name, prepared } => {
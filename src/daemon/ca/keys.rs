@@ -90,6 +90,16 @@ impl CertifiedKey {
         self.old_repo = Some(repo)
     }
 
+    pub fn clear_old_repo(&mut self) {
+        self.old_repo = None
+    }
+
+    /// Returns the repository which this key publishes to, if it is not the
+    /// repository currently used by its resource class.
+    pub fn old_repo(&self) -> Option<&RepoInfo> {
+        self.old_repo.as_ref()
+    }
+
     pub fn wants_update(
         &self,
         handle: &CaHandle,
@@ -549,7 +559,7 @@ impl KeyState {
     ) -> KrillResult<Vec<CaEvtDet>> {
         match self {
             KeyState::Active(_current) => {
-                let pending_key_id = signer.create_key()?;
+                let pending_key_id = signer.create_ca_key()?;
 
                 let req =
                     self.create_issuance_req(base_repo, name_space, parent_class_name, &pending_key_id, signer)?;
@@ -594,6 +604,31 @@ impl KeyState {
         }
     }
 
+    /// Aborts a key roll before the new key is activated. A new key which is
+    /// already certified by the parent must be revoked, a pending key can
+    /// simply be removed.
+    pub fn keyroll_abort(
+        &self,
+        resource_class_name: ResourceClassName,
+        parent_class_name: ResourceClassName,
+        signer: &KrillSigner,
+    ) -> KrillResult<CaEvtDet> {
+        match self {
+            KeyState::RollPending(_, _) => Ok(CaEvtDet::KeyRollAborted {
+                resource_class_name,
+                revoke_req: None,
+            }),
+            KeyState::RollNew(new, _) => {
+                let revoke_req = Self::revoke_key(parent_class_name, new.key_id(), signer)?;
+                Ok(CaEvtDet::KeyRollAborted {
+                    resource_class_name,
+                    revoke_req: Some(revoke_req),
+                })
+            }
+            _ => Err(Error::KeyUseNoNewKey),
+        }
+    }
+
     /// Returns the new key, iff there is a key roll in progress and there is a new key.
     pub fn new_key(&self) -> Option<&CertifiedKey> {
         match self {
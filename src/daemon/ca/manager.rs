@@ -1,4 +1,11 @@
-use std::{collections::HashMap, convert::TryFrom, ops::Deref, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    ops::Deref,
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
 
 use bytes::Bytes;
 use chrono::Duration;
@@ -16,7 +23,7 @@ use rpki::{
         publication::{ListReply, Publish, PublishDelta, Update, Withdraw},
     },
     crypto::KeyIdentifier,
-    repository::resources::ResourceSet,
+    repository::{resources::ResourceSet, x509::Time},
     uri,
 };
 
@@ -24,36 +31,49 @@ use crate::{
     commons::{
         actor::Actor,
         api::{
-            rrdp::PublishElement, BgpSecCsrInfoList, BgpSecDefinitionUpdates, IdCertInfo, ParentServerInfo,
-            PublicationServerInfo, RoaConfigurationUpdates, Timestamp,
+            rrdp::PublishElement, AggregateSnapshot, Backoff, BgpSecCsrInfoList, BgpSecDefinitionUpdates,
+            CaLabelsUpdate, ChildIssuancePolicy, CmsValidation, IdCertInfo, IssuanceTimingOverrides, ObjectsExpiry,
+            ParentExchangeLog, ParentExchangeRecord, ParentServerInfo, PendingChildRequest, PendingChildRequestKind,
+            PendingChildRequests, PublicationCheck, PublicationCheckProtocol, PublicationServerInfo,
+            RepoMigrationReport, RepoMigrationStage, RepoMigrationStatus, RepoMigrationStep, ResourceTransferRequest,
+            RetryPolicy, RoaAutoPilotPolicy, RoaConfigurationUpdates, RoaIssuanceStrategy, ScheduledChangeRequest,
+            SignerMigrationStatus, Timestamp,
         },
         api::{
             AddChildRequest, AspaCustomer, AspaDefinitionList, AspaDefinitionUpdates, AspaProvidersUpdate,
-            CaCommandDetails, CaCommandResult, CertAuthList, CertAuthSummary, ChildCaInfo, CommandHistory,
-            CommandHistoryCriteria, ParentCaContact, ParentCaReq, ReceivedCert, RepositoryContact, RtaName,
-            StoredEffect, UpdateChildRequest,
+            CaCommandDetails, CaCommandResult, CertAuthList, CertAuthSummary, ChildCaInfo, ChildrenStats,
+            CommandHistory, CommandHistoryCriteria, CommandRevert, Divergence, DivergenceObject, ParentCaContact,
+            ParentCaReq, ParentsStats, ReceivedCert, Reconciliation, RepositoryContact, RtaName, StoredEffect,
+            UpdateChildRequest,
         },
         crypto::KrillSigner,
         error::Error,
-        eventsourcing::{Aggregate, AggregateStore, CommandKey},
+        eventsourcing::{Aggregate, AggregateStore, Command, CommandKey, WithStorableDetails},
         util::{cmslogger::CmsLogger, httpclient},
         KrillResult,
     },
-    constants::{CASERVER_DIR, STATUS_DIR, TA_PROXY_SERVER_DIR, TA_SIGNER_SERVER_DIR},
+    constants::{
+        CASERVER_DIR, PUBLICATION_CHECK_DIR, PUBLICATION_CONFIRM_RETRY_SECONDS, SIGNER_MIGRATION_STAGING_HOURS,
+        STATUS_DIR, TA_PROXY_SERVER_DIR, TA_SIGNER_SERVER_DIR,
+    },
     daemon::{
         auth::common::permissions::Permission,
         auth::Handle,
+        backup::BackupWriter,
         ca::{
-            CaObjectsStore, CaStatus, CertAuth, Cmd, CmdDet, DeprecatedRepository, IniDet, ResourceTaggedAttestation,
+            selfcheck, AspaUpdateDryRun, CaObjectsStore, CaStatus, CertAuth, Cmd, CmdDet, DeprecatedRepository, IniDet,
+            RepublishMetrics, ResourceTaggedAttestation, Rfc8183Id, RpkiSignedChecklist, RscContentRequest,
             RtaContentRequest, RtaPrepareRequest, StatusStore,
         },
-        config::Config,
-        mq::{now, TaskQueue},
+        config::{Config, RetryConfig},
+        mq::{in_seconds, now, Priority, TaskQueue},
+        notify::{Alert, AlertEvent, CaEvent, CaEventType, EventStream, Notifier},
         ta::{
             self, ta_handle, TrustAnchorProxy, TrustAnchorProxyCommand, TrustAnchorSignedRequest,
             TrustAnchorSignedResponse, TrustAnchorSigner, TrustAnchorSignerCommand, TrustAnchorSignerInfo,
             TrustAnchorSignerInitCommand, TA_NAME,
         },
+        telemetry::{self, KeyValue, SpanKind},
     },
     pubd::RepositoryManager,
 };
@@ -89,9 +109,21 @@ pub struct CaManager {
     // - can be used here to schedule tasks through the api
     tasks: Arc<TaskQueue>,
 
-    config: Arc<Config>,
+    // Sends alerts to the configured notification channels. Also listens
+    // for events in the ca_store, e.g. to report ROA changes.
+    notifier: Arc<Notifier>,
+
+    // Streams events to connected clients. Also listens for events in the
+    // ca_store, e.g. to report ROA changes and key rolls.
+    events: Arc<EventStream>,
+
+    config: RwLock<Arc<Config>>,
     signer: Arc<KrillSigner>,
 
+    // Follows the RRDP repositories where CAs publish, to confirm that their
+    // objects can be fetched back.
+    rrdp_follower: selfcheck::RrdpFollower,
+
     // System actor is used for (scheduled or triggered) system actions where
     // we have no operator actor context.
     system_actor: Actor,
@@ -102,12 +134,14 @@ impl CaManager {
     pub async fn build(
         config: Arc<Config>,
         tasks: Arc<TaskQueue>,
+        events: Arc<EventStream>,
         signer: Arc<KrillSigner>,
         system_actor: Actor,
     ) -> KrillResult<Self> {
         // Create the AggregateStore for the event-sourced `CertAuth` structures that handle
         // most CA functions.
-        let mut ca_store = AggregateStore::<CertAuth>::disk(&config.data_dir, CASERVER_DIR)?;
+        let mut ca_store = AggregateStore::<CertAuth>::create(&config.storage(), CASERVER_DIR)?;
+        ca_store.set_snapshot_policy(config.snapshots.policy());
 
         if config.always_recover_data {
             // If the user chose to 'always recover data' then do so.
@@ -131,9 +165,10 @@ impl CaManager {
         // Create the `CaObjectStore` that is responsible for maintaining CA objects: the `CaObjects`
         // for a CA gets copies of all ROAs and issued certificates from the `CertAuth` and is responsible
         // for manifests and CRL generation.
-        let ca_objects_store = Arc::new(CaObjectsStore::disk(
-            &config.data_dir,
+        let ca_objects_store = Arc::new(CaObjectsStore::create(
+            &config.ca_objects_storage(),
             config.issuance_timing.clone(),
+            config.republish_workers,
             signer.clone(),
         )?);
 
@@ -147,9 +182,19 @@ impl CaManager {
         // the RPKI repository.
         ca_store.add_post_save_listener(tasks.clone());
 
+        // Register the `Notifier` as a post-save listener to 'ca_store' so that it can send alerts
+        // for relevant changes, such as ROA updates and certificates issued to children.
+        let notifier = Arc::new(Notifier::build(&config.alerts));
+        ca_store.add_post_save_listener(notifier.clone());
+
+        // Register the `EventStream` as a post-save listener to 'ca_store' so that connected clients
+        // learn about relevant changes, such as ROA updates and key rolls.
+        ca_store.add_post_save_listener(events.clone());
+
         // Create TA proxy store if we need it.
         let ta_proxy_store = if config.ta_proxy_enabled() {
-            let mut store = AggregateStore::<TrustAnchorProxy>::disk(&config.data_dir, TA_PROXY_SERVER_DIR)?;
+            let mut store = AggregateStore::<TrustAnchorProxy>::create(&config.storage(), TA_PROXY_SERVER_DIR)?;
+            store.set_snapshot_policy(config.snapshots.policy());
 
             // We need to listen for proxy events so that we can schedule:
             // 1. publication on updates
@@ -162,7 +207,9 @@ impl CaManager {
         };
 
         let ta_signer_store = if config.ta_signer_enabled() {
-            Some(AggregateStore::disk(&config.data_dir, TA_SIGNER_SERVER_DIR)?)
+            let mut store = AggregateStore::create(&config.storage(), TA_SIGNER_SERVER_DIR)?;
+            store.set_snapshot_policy(config.snapshots.policy());
+            Some(store)
         } else {
             None
         };
@@ -178,25 +225,124 @@ impl CaManager {
             ta_proxy_store,
             ta_signer_store,
             tasks,
-            config,
+            notifier,
+            events,
+            config: RwLock::new(config),
             signer,
+            rrdp_follower: selfcheck::RrdpFollower::default(),
             system_actor,
         })
     }
 
+    /// Returns the current config, which is replaced when the config file
+    /// is reloaded.
+    pub fn config(&self) -> Arc<Config> {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Applies a reloaded configuration. Note that only settings which are
+    /// read when they are used, such as the issuance timing, take effect.
+    pub fn update_config(&self, config: Arc<Config>) {
+        self.ca_objects_store
+            .update_issuance_timing(config.issuance_timing.clone());
+        *self.config.write().unwrap() = config;
+    }
+
     pub fn testbed_enabled(&self) -> bool {
-        self.config.testbed().is_some()
+        self.config().testbed().is_some()
     }
 
     /// Send a command to a CA
     async fn send_ca_command(&self, cmd: Cmd) -> KrillResult<Arc<CertAuth>> {
-        self.ca_store.command(cmd)
+        let ca = cmd.handle().clone();
+        let label = cmd.store().summary().label;
+
+        let cx = telemetry::span(
+            "ca command",
+            SpanKind::Internal,
+            vec![
+                KeyValue::new("krill.ca", ca.to_string()),
+                KeyValue::new("krill.command", label.clone()),
+            ],
+        );
+
+        let res = telemetry::traced_sync(cx, || self.ca_store.command(cmd));
+        if res.is_ok() {
+            if let Err(e) = self.status_store.count_command(&ca, label) {
+                warn!("Cannot save the number of commands for CA '{}': {}", ca, e);
+            }
+        }
+        res
     }
 
     /// Republish the embedded TA and CAs if needed, i.e. if they are close
     /// to their next update time.
     pub async fn republish_all(&self, force: bool) -> KrillResult<Vec<CaHandle>> {
-        self.ca_objects_store.reissue_all(force)
+        self.ca_objects_store.reissue_all(force).await
+    }
+
+    /// Republish a single CA if needed, or always if forced. Returns true if
+    /// the manifest and CRL were re-issued.
+    pub fn ca_republish(&self, ca: &CaHandle, force: bool) -> KrillResult<bool> {
+        Ok(self.ca_objects_store.reissue(ca.clone(), force)?.is_some())
+    }
+
+    /// Returns the progress and throughput of republishing.
+    pub fn republish_metrics(&self) -> &RepublishMetrics {
+        self.ca_objects_store.republish_metrics()
+    }
+
+    /// Saves snapshots for the CAs, and the TA proxy and signer if enabled,
+    /// which had changes since their last snapshot. Or for all if forced.
+    pub fn save_snapshots(&self, force: bool) -> KrillResult<Vec<AggregateSnapshot>> {
+        let mut res = self.ca_store.save_snapshots(force)?;
+        if let Some(ta_proxy_store) = self.ta_proxy_store.as_ref() {
+            res.append(&mut ta_proxy_store.save_snapshots(force)?);
+        }
+        if let Some(ta_signer_store) = self.ta_signer_store.as_ref() {
+            res.append(&mut ta_signer_store.save_snapshots(force)?);
+        }
+        Ok(res)
+    }
+
+    /// Archives the history of the CAs, and the TA proxy and signer if
+    /// enabled, before the given time. Returns the number of archived commands.
+    pub fn archive_history(&self, before: Time) -> KrillResult<usize> {
+        let mut archived = self.ca_store.archive_history(before)?;
+        if let Some(ta_proxy_store) = self.ta_proxy_store.as_ref() {
+            archived += ta_proxy_store.archive_history(before)?;
+        }
+        if let Some(ta_signer_store) = self.ta_signer_store.as_ref() {
+            archived += ta_signer_store.archive_history(before)?;
+        }
+        Ok(archived)
+    }
+
+    /// Verifies that the snapshots for the CAs, and the TA proxy and signer
+    /// if enabled, are equivalent to the state rebuilt from their events.
+    pub fn verify_snapshots(&self) -> KrillResult<Vec<AggregateSnapshot>> {
+        let mut res = self.ca_store.verify_snapshots()?;
+        if let Some(ta_proxy_store) = self.ta_proxy_store.as_ref() {
+            res.append(&mut ta_proxy_store.verify_snapshots()?);
+        }
+        if let Some(ta_signer_store) = self.ta_signer_store.as_ref() {
+            res.append(&mut ta_signer_store.verify_snapshots()?);
+        }
+        Ok(res)
+    }
+
+    /// Exports the stores for the CAs, and the TA proxy and signer if
+    /// enabled, to a backup.
+    pub fn export(&self, backup: &mut BackupWriter) -> KrillResult<()> {
+        self.ca_store
+            .export(&mut |entry| backup.add_entry(CASERVER_DIR, entry))?;
+        if let Some(ta_proxy_store) = self.ta_proxy_store.as_ref() {
+            ta_proxy_store.export(&mut |entry| backup.add_entry(TA_PROXY_SERVER_DIR, entry))?;
+        }
+        if let Some(ta_signer_store) = self.ta_signer_store.as_ref() {
+            ta_signer_store.export(&mut |entry| backup.add_entry(TA_SIGNER_SERVER_DIR, entry))?;
+        }
+        Ok(())
     }
 }
 
@@ -402,14 +548,14 @@ impl CaManager {
 
         // Add repository to proxy
         let contact = RepositoryContact::for_response(repository_response).map_err(Error::rfc8183)?;
-        self.ta_proxy_repository_update(contact, &self.system_actor).await?;
+        self.ta_proxy_repository_update(contact, actor).await?;
 
         // Initialise signer
         self.ta_signer_init(ta_uris, ta_aia, ta_key_pem).await?;
 
         // Add signer to proxy
         let signer_info = self.get_trust_anchor_signer().await?.get_signer_info();
-        self.ta_proxy_signer_add(signer_info, &self.system_actor).await?;
+        self.ta_proxy_signer_add(signer_info, actor).await?;
 
         self.sync_ta_proxy_signer_if_possible().await?;
         self.cas_repo_sync_single(repo_manager, &ta_handle).await?;
@@ -423,7 +569,13 @@ impl CaManager {
 impl CaManager {
     /// Initializes a CA without a repo, no parents, no children, no nothing
     pub fn init_ca(&self, handle: &CaHandle) -> KrillResult<()> {
-        if handle == &ta_handle() || handle.as_str() == "version" {
+        self.init_ca_with_id_key(handle, None)
+    }
+
+    /// Initializes a CA, using the given existing key for its identity
+    /// certificate if set. Otherwise a new key is created.
+    pub fn init_ca_with_id_key(&self, handle: &CaHandle, id_key: Option<&KeyIdentifier>) -> KrillResult<()> {
+        if handle == &ta_handle() || handle.as_str() == "version" || handle.as_str() == "schema_version" {
             Err(Error::TaNameReserved)
         } else if self.ca_store.has(handle)? {
             Err(Error::CaDuplicate(handle.clone()))
@@ -431,7 +583,10 @@ impl CaManager {
             // Initialize the CA in self.ca_store, but note that there is no need to create
             // a new CA entry in self.ca_objects_store or self.status_store, because they will
             // generate empty default entries if needed.
-            let init = IniDet::init(handle, self.signer.deref())?;
+            let init = match id_key {
+                Some(key) => IniDet::new(handle, Rfc8183Id::for_key(self.signer.deref(), key)?),
+                None => IniDet::init(handle, self.signer.deref())?,
+            };
             self.ca_store.add(init)?;
             Ok(())
         }
@@ -449,6 +604,21 @@ impl CaManager {
         Ok(())
     }
 
+    /// Generates a new ID key for a CA to roll to, and returns the RFC 8183
+    /// Publisher Request with the new ID certificate.
+    pub async fn ca_id_roll_init(&self, handle: CaHandle, actor: &Actor) -> KrillResult<idexchange::PublisherRequest> {
+        let cmd = CmdDet::id_roll_init(&handle, self.signer.clone(), actor);
+        let ca = self.send_ca_command(cmd).await?;
+        ca.next_publisher_request().ok_or(Error::CaRepoIdRollNotStarted(handle))
+    }
+
+    /// Starts using the new ID key for a CA.
+    pub async fn ca_id_roll_activate(&self, handle: CaHandle, actor: &Actor) -> KrillResult<()> {
+        let cmd = CmdDet::id_roll_activate(&handle, actor);
+        self.send_ca_command(cmd).await?;
+        Ok(())
+    }
+
     /// Get the CAs that the given actor is permitted to see.
     pub fn ca_list(&self, actor: &Actor) -> KrillResult<CertAuthList> {
         Ok(CertAuthList::new(
@@ -456,7 +626,15 @@ impl CaManager {
                 .list()?
                 .into_iter()
                 .filter(|handle| matches!(actor.is_allowed(Permission::CA_READ, Handle::from(handle)), Ok(true)))
-                .map(CertAuthSummary::new)
+                .map(|handle| {
+                    // CAs are cached, so getting the labels is cheap.
+                    let labels = self
+                        .ca_store
+                        .get_latest(&handle)
+                        .map(|ca| ca.labels().clone())
+                        .unwrap_or_default();
+                    CertAuthSummary::new(handle, labels)
+                })
                 .collect(),
         ))
     }
@@ -483,46 +661,86 @@ impl CaManager {
         }
     }
 
-    /// Delete a CA. Let it do best effort revocation requests and withdraw
-    /// all its objects first. Note that any children of this CA will be left
-    /// orphaned, and they will only learn of this sad fact when they choose
-    /// to call home.
+    /// Returns the recent RFC 6492 exchanges of a CA with one of its parents.
+    pub async fn ca_parent_exchanges(&self, ca: &CaHandle, parent: &ParentHandle) -> KrillResult<ParentExchangeLog> {
+        self.get_ca(ca).await?.parent(parent)?;
+        Ok(self.status_store.get_ca_status(ca).parent_exchanges(parent))
+    }
+
+    /// Delete a CA gracefully. Before its local state is removed, the CA:
+    ///  - revokes the certificates issued to all its children, by removing them
+    ///  - requests revocation of its own certificates from all its parents
+    ///  - withdraws all its objects from all its (current and deprecated) repositories
+    ///
+    /// If a parent or the current repository cannot be reached, then the deletion
+    /// is aborted and the CA is kept, unless 'force' is used. In that case these
+    /// steps are best effort only and the CA is removed regardless. Note that
+    /// cleaning up deprecated repositories is always best effort.
     pub async fn delete_ca(
         &self,
         repo_manager: &RepositoryManager,
         ca_handle: &CaHandle,
+        force: bool,
         actor: &Actor,
     ) -> KrillResult<()> {
         warn!("Deleting CA '{}' as requested by: {}", ca_handle, actor);
 
         let ca = self.get_ca(ca_handle).await?;
 
-        // Request revocations from all parents - best effort
+        // The steps which can make us give up come first, so that nothing
+        // irreversible happens to the children if we do.
+
+        // Request revocations from all parents
         info!(
             "Will try to request revocations from all parents CA '{}' before removing it.",
             ca_handle
         );
         for parent in ca.parents() {
             if let Err(e) = self.ca_parent_revoke(ca_handle, parent).await {
-                warn!(
-                    "Removing CA '{}', but could not send revoke requests to parent '{}': {}",
-                    ca_handle, parent, e
-                );
+                if force {
+                    warn!(
+                        "Removing CA '{}', but could not send revoke requests to parent '{}': {}",
+                        ca_handle, parent, e
+                    );
+                } else {
+                    return Err(Error::CaDeleteIncomplete(
+                        ca_handle.clone(),
+                        format!("could not request revocation from parent '{}': {}", parent, e),
+                    ));
+                }
             }
         }
 
-        // Clean all repos - again best effort
+        // Withdraw all objects from the current repository
         info!(
             "Will try to clean up all repositories for CA '{}' before removing it.",
             ca_handle
         );
-        let mut repos: Vec<RepositoryContact> = self.ca_repo_elements(ca_handle).await?.into_keys().collect();
+        self.ca_repo_withdraw_all(repo_manager, ca_handle, force).await?;
+
+        // Revoke all certificates issued to children, by removing the children
+        let children: Vec<ChildHandle> = ca.children().cloned().collect();
+        if !children.is_empty() {
+            info!(
+                "Will revoke the certificates of all children of CA '{}' before removing it.",
+                ca_handle
+            );
+            for child in children {
+                self.ca_child_remove(ca_handle, child, actor).await?;
+            }
 
-        for deprecated in self.ca_deprecated_repos(ca_handle)? {
-            repos.push(deprecated.into());
+            // Removing the children results in a new CRL and manifest, which
+            // may have been published already. Withdraw these as well, but
+            // only as best effort: we are past the point of giving up.
+            self.tasks.remove_tasks_for_ca(ca_handle);
+            self.ca_repo_withdraw_all(repo_manager, ca_handle, true).await?;
         }
 
-        for repo_contact in repos {
+        let ca = self.get_ca(ca_handle).await?;
+
+        // Clean deprecated repositories - best effort
+        for deprecated in self.ca_deprecated_repos(ca_handle)? {
+            let repo_contact: RepositoryContact = deprecated.into();
             if self
                 .ca_repo_sync(repo_manager, ca_handle, ca.id_cert(), &repo_contact, vec![])
                 .await
@@ -540,6 +758,36 @@ impl CaManager {
 
         Ok(())
     }
+
+    /// Withdraws all objects of the CA from its current repositories. Errors
+    /// are only logged if forced.
+    async fn ca_repo_withdraw_all(
+        &self,
+        repo_manager: &RepositoryManager,
+        ca_handle: &CaHandle,
+        force: bool,
+    ) -> KrillResult<()> {
+        let ca = self.get_ca(ca_handle).await?;
+        for repo_contact in self.ca_repo_elements(ca_handle).await?.into_keys() {
+            if let Err(e) = self
+                .ca_repo_sync(repo_manager, ca_handle, ca.id_cert(), &repo_contact, vec![])
+                .await
+            {
+                if force {
+                    warn!(
+                        "Removing CA '{}', but could not withdraw its objects from {}: {}",
+                        ca_handle, repo_contact, e
+                    );
+                } else {
+                    return Err(Error::CaDeleteIncomplete(
+                        ca_handle.clone(),
+                        format!("could not withdraw objects from {}: {}", repo_contact, e),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 /// # CA History
@@ -560,9 +808,12 @@ impl CaManager {
             StoredEffect::Success { events } => {
                 let mut stored_events = vec![];
                 for version in events {
-                    let evt = self.ca_store.get_event(handle, version)?.ok_or_else(|| {
-                        Error::Custom(format!("Cannot find evt: {} in history for CA: {}", version, handle))
-                    })?;
+                    let evt = match self.ca_store.get_event(handle, version)? {
+                        Some(evt) => evt,
+                        None => self.ca_store.get_archived_event(handle, version)?.ok_or_else(|| {
+                            Error::Custom(format!("Cannot find evt: {} in history for CA: {}", version, handle))
+                        })?,
+                    };
                     stored_events.push(evt);
                 }
 
@@ -570,6 +821,37 @@ impl CaManager {
             }
         }
     }
+
+    /// Returns the changes which revert the ROA or ASPA changes made by the
+    /// given command, if possible.
+    pub async fn ca_command_revert_plan(&self, handle: &CaHandle, command: CommandKey) -> KrillResult<CommandRevert> {
+        let details = self.ca_command_details(handle, command)?;
+        let aspas = self.get_ca(handle).await?.aspas_definitions_show();
+
+        CommandRevert::for_command(&details, &aspas)
+            .map_err(|e| Error::Custom(format!("Cannot revert command for CA '{}': {}", handle, e)))
+    }
+
+    /// Applies the changes which revert the given command as a new command,
+    /// which refers to the reverted command in the history of the CA.
+    pub async fn ca_command_revert(
+        &self,
+        handle: &CaHandle,
+        command: CommandKey,
+        revert: CommandRevert,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(CmdDet::command_revert(
+            handle,
+            command,
+            revert,
+            self.config(),
+            self.signer.clone(),
+            actor,
+        ))
+        .await?;
+        Ok(())
+    }
 }
 
 /// # CAs as parents
@@ -606,6 +888,36 @@ impl CaManager {
         ca.get_child(child).map(|details| details.clone().into())
     }
 
+    /// Show the stats for all children of a CA.
+    pub async fn ca_stats_children(&self, ca: &CaHandle) -> KrillResult<ChildrenStats> {
+        let ca_status = self.get_ca_status(ca).await?;
+        let ca = self.get_ca(ca).await?;
+
+        let mut children = vec![];
+        for child in ca.children() {
+            let last_exchange = ca_status
+                .children()
+                .get(child)
+                .and_then(|status| status.last_exchange().cloned());
+            children.push(ca.child_stats(child, last_exchange)?);
+        }
+
+        Ok(ChildrenStats::new(children))
+    }
+
+    /// Show the stats for all parents of a CA.
+    pub async fn ca_stats_parents(&self, ca: &CaHandle) -> KrillResult<ParentsStats> {
+        let ca_status = self.get_ca_status(ca).await?;
+        let ca = self.get_ca(ca).await?;
+
+        let mut parents = vec![];
+        for parent in ca.parents() {
+            parents.push(ca.parent_stats(parent, ca_status.parents().get(parent))?);
+        }
+
+        Ok(ParentsStats::new(parents))
+    }
+
     /// Show a contact for a child.
     pub async fn ca_parent_contact(
         &self,
@@ -666,7 +978,7 @@ impl CaManager {
         req: UpdateChildRequest,
         actor: &Actor,
     ) -> KrillResult<()> {
-        let (id_opt, resources_opt, suspend_opt) = req.unpack();
+        let (id_opt, resources_opt, suspend_opt, suspension_exempt_opt, manual_approval_opt) = req.unpack();
 
         if let Some(id) = id_opt {
             self.send_ca_command(CmdDet::child_update_id(ca, child.clone(), id.into(), actor))
@@ -676,6 +988,14 @@ impl CaManager {
             self.send_ca_command(CmdDet::child_update_resources(ca, child.clone(), resources, actor))
                 .await?;
         }
+        if let Some(exempt) = suspension_exempt_opt {
+            self.send_ca_command(CmdDet::child_update_suspension_exempt(ca, child.clone(), exempt, actor))
+                .await?;
+        }
+        if let Some(manual) = manual_approval_opt {
+            self.send_ca_command(CmdDet::child_update_manual_approval(ca, child.clone(), manual, actor))
+                .await?;
+        }
         if let Some(suspend) = suspend_opt {
             if suspend {
                 self.send_ca_command(CmdDet::child_suspend_inactive(ca, child, actor))
@@ -687,6 +1007,109 @@ impl CaManager {
         Ok(())
     }
 
+    /// Replace the policy enforced before certificates are issued to children.
+    pub async fn ca_child_issuance_policy_update(
+        &self,
+        ca: &CaHandle,
+        policy: ChildIssuancePolicy,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let cmd = CmdDet::child_issuance_policy_update(ca, policy, self.config(), actor);
+        self.send_ca_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Returns the requests from a child which are awaiting approval.
+    pub async fn ca_child_requests(&self, ca: &CaHandle, child: &ChildHandle) -> KrillResult<PendingChildRequests> {
+        self.get_ca(ca).await?.child_pending_requests(child)
+    }
+
+    /// Approves a pending request from a child. The certificate is issued, or
+    /// revoked, when the child asks for it again.
+    pub async fn ca_child_request_approve(
+        &self,
+        ca: &CaHandle,
+        child: ChildHandle,
+        kind: PendingChildRequestKind,
+        ki: KeyIdentifier,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let cmd = CmdDet::child_request_approve(ca, child, kind, ki, actor);
+        self.send_ca_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Starts a transfer of resources from one child to another. The
+    /// resources are added to the receiving child once the other child got
+    /// certificates without them, see [`Self::ca_resource_transfers_progress`].
+    pub async fn ca_resource_transfer_start(
+        &self,
+        ca: &CaHandle,
+        request: ResourceTransferRequest,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let cmd = CmdDet::child_resource_transfer_start(ca, request, actor);
+        self.send_ca_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Completes, or rolls back, the waiting resource transfers of a CA
+    /// where possible. Returns true if any transfers are still waiting.
+    pub async fn ca_resource_transfers_progress(&self, ca_handle: &CaHandle, actor: &Actor) -> KrillResult<bool> {
+        let mut ca = self.get_ca(ca_handle).await?;
+
+        for id in ca.resource_transfers_waiting() {
+            ca = self
+                .send_ca_command(CmdDet::child_resource_transfer_progress(ca_handle, id, actor))
+                .await?;
+        }
+
+        Ok(!ca.resource_transfers_waiting().is_empty())
+    }
+
+    /// Schedules a ROA or ASPA update for the CA, to be applied when it is due.
+    pub async fn ca_scheduled_change_add(
+        &self,
+        ca: &CaHandle,
+        request: ScheduledChangeRequest,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(CmdDet::scheduled_change_add(ca, request, actor))
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels a scheduled change for the CA.
+    pub async fn ca_scheduled_change_cancel(&self, ca: &CaHandle, id: u64, actor: &Actor) -> KrillResult<()> {
+        self.send_ca_command(CmdDet::scheduled_change_cancel(ca, id, actor))
+            .await?;
+        Ok(())
+    }
+
+    /// Applies the scheduled changes of the CA which are due, and returns
+    /// when the next pending change is due, if there is any.
+    pub async fn ca_scheduled_changes_apply(
+        &self,
+        ca_handle: &CaHandle,
+        actor: &Actor,
+    ) -> KrillResult<Option<Timestamp>> {
+        let mut ca = self.get_ca(ca_handle).await?;
+
+        for id in ca.scheduled_changes_due() {
+            ca = self
+                .send_ca_command(CmdDet::scheduled_change_apply(
+                    ca_handle,
+                    id,
+                    self.config(),
+                    self.signer.clone(),
+                    actor,
+                ))
+                .await?;
+        }
+
+        Ok(ca.scheduled_changes_next())
+    }
+
     /// Removes a child from this CA. This will also ensure that certificates issued to the child
     /// are revoked and withdrawn.
     pub async fn ca_child_remove(&self, ca: &CaHandle, child: ChildHandle, actor: &Actor) -> KrillResult<()> {
@@ -718,7 +1141,7 @@ impl CaManager {
 
         // Create a logger for CMS (avoid cloning recipient)
         let cms_logger = CmsLogger::for_rfc6492_rcvd(
-            self.config.rfc6492_log_dir.as_ref(),
+            self.config().rfc6492_log_dir.as_ref(),
             req_msg.recipient(),
             req_msg.sender(),
         );
@@ -818,11 +1241,13 @@ impl CaManager {
     /// List the entitlements for a child: 3.3.2 of RFC 6492.
     async fn list(&self, ca_handle: &CaHandle, child: &ChildHandle) -> KrillResult<provisioning::Message> {
         let list_response = if ca_handle.as_str() != TA_NAME {
-            self.get_ca(ca_handle).await?.list(child, &self.config.issuance_timing)
+            self.get_ca(ca_handle)
+                .await?
+                .list(child, &self.config().issuance_timing)
         } else {
             self.get_trust_anchor_proxy()
                 .await?
-                .entitlements(child, &self.config.issuance_timing)
+                .entitlements(child, &self.config().issuance_timing)
                 .map(|entitlements| ResourceClassListResponse::new(vec![entitlements]))
         }?;
 
@@ -850,11 +1275,18 @@ impl CaManager {
             let class_name = issue_req.class_name();
             let pub_key = issue_req.csr().public_key();
 
+            let ki = pub_key.key_identifier();
+            let ca = self.get_ca(ca_handle).await?;
+            if let Some(reason) = ca.child_request_approval_needed(&child, &ki)? {
+                let request = PendingChildRequest::new(PendingChildRequestKind::Issue, ki, class_name.clone(), reason);
+                return self.child_request_hold(ca_handle, &ca, child, request, actor).await;
+            }
+
             let cmd = CmdDet::child_certify(
                 ca_handle,
                 child.clone(),
                 issue_req.clone(),
-                self.config.clone(),
+                self.config(),
                 self.signer.clone(),
                 actor,
             );
@@ -862,7 +1294,7 @@ impl CaManager {
             let ca = self.send_ca_command(cmd).await?;
 
             // The updated CA will now include the newly issued certificate.
-            let response = ca.issuance_response(&child, class_name, pub_key, &self.config.issuance_timing)?;
+            let response = ca.issuance_response(&child, class_name, pub_key, &self.config().issuance_timing)?;
 
             Ok(provisioning::Message::issue_response(
                 ca_handle.convert(),
@@ -872,6 +1304,29 @@ impl CaManager {
         }
     }
 
+    /// Holds back an RFC 6492 request from a child which needs to be approved
+    /// first. The child gets a 1104 not performed response, so that it will ask
+    /// again later, or 1101 if the request was already queued.
+    async fn child_request_hold(
+        &self,
+        ca_handle: &CaHandle,
+        ca: &CertAuth,
+        child: ChildHandle,
+        request: PendingChildRequest,
+        actor: &Actor,
+    ) -> KrillResult<provisioning::Message> {
+        let not_performed = if ca.get_child(&child)?.is_pending(request.kind, &request.key) {
+            provisioning::NotPerformedResponse::err_1101()
+        } else {
+            let cmd = CmdDet::child_request_queue(ca_handle, child.clone(), request, actor);
+            self.send_ca_command(cmd).await?;
+            provisioning::NotPerformedResponse::err_1104()
+        };
+
+        provisioning::Message::not_performed_response(ca_handle.convert(), child.into_converted(), not_performed)
+            .map_err(|_| Error::custom("creation of not performed response should never fail"))
+    }
+
     /// Process an RFC 6492 revocation request sent by a child.
     /// See: https://tools.ietf.org/html/rfc6492#section3.5.1-2
     async fn revoke(
@@ -885,6 +1340,17 @@ impl CaManager {
             let request = ta::ProvisioningRequest::Revocation(revoke_request);
             self.ta_slow_rfc6492_request(ca_handle, child, request, actor).await
         } else {
+            let ca = self.get_ca(ca_handle).await?;
+            if let Some(reason) = ca.child_revoke_approval_needed(&child, &revoke_request.key())? {
+                let request = PendingChildRequest::new(
+                    PendingChildRequestKind::Revoke,
+                    revoke_request.key(),
+                    revoke_request.class_name().clone(),
+                    reason,
+                );
+                return self.child_request_hold(ca_handle, &ca, child, request, actor).await;
+            }
+
             let res = RevocationResponse::from(&revoke_request); // response provided that no errors are returned
             let msg = provisioning::Message::revoke_response(ca_handle.convert(), child.convert(), res);
 
@@ -1017,7 +1483,7 @@ impl CaManager {
     /// Note: this function can be called manually through the API, but normally this
     ///       is replanned on the task queue automatically IF suspension is enabled.
     pub fn cas_schedule_suspend_all(&self) {
-        if self.config.suspend_child_after_inactive_seconds().is_some() {
+        if self.config().suspend_child_after_inactive_seconds().is_some() {
             if let Ok(cas) = self.ca_store.list() {
                 for ca_handle in cas {
                     self.tasks.suspend_children(ca_handle, now());
@@ -1033,16 +1499,28 @@ impl CaManager {
         // without prior recorded status are suspended on upgrade, or that *all* children
         // are suspended if the server had been down for more than the threshold hours.
         let threshold_seconds = self
-            .config
+            .config()
             .suspend_child_after_inactive_seconds()
             .filter(|secs| started < Timestamp::now_minus_seconds(*secs));
 
         // suspend inactive children, if so configured
         if let Some(threshold_seconds) = threshold_seconds {
-            if let Ok(ca_status) = self.get_ca_status(ca_handle).await {
+            if let (Ok(ca), Ok(ca_status)) = (self.get_ca(ca_handle).await, self.get_ca_status(ca_handle).await) {
                 let connections = ca_status.get_children_connection_stats();
 
                 for child in connections.suspension_candidates(threshold_seconds) {
+                    if ca
+                        .get_child(&child)
+                        .map(|details| details.is_suspension_exempt())
+                        .unwrap_or(false)
+                    {
+                        debug!(
+                            "Child '{}' under CA '{}' is inactive, but exempt from suspension.",
+                            child, ca_handle
+                        );
+                        continue;
+                    }
+
                     let threshold_string = if threshold_seconds >= 3600 {
                         format!("{} hours", threshold_seconds / 3600)
                     } else {
@@ -1072,7 +1550,7 @@ impl CaManager {
         if let Ok(ca) = self.get_ca(ca_handle).await {
             // get updates from parents
             {
-                if ca.nr_parents() <= self.config.ca_refresh_parents_batch_size {
+                if ca.nr_parents() <= self.config().ca_refresh_parents_batch_size {
                     // Nr of parents is below batch size, so just process all of them
                     for parent in ca.parents() {
                         self.tasks.sync_parent(ca_handle.clone(), parent.clone(), now());
@@ -1084,7 +1562,7 @@ impl CaManager {
 
                     for parent in status
                         .parents()
-                        .sync_candidates(ca.parents().collect(), self.config.ca_refresh_parents_batch_size)
+                        .sync_candidates(ca.parents().collect(), self.config().ca_refresh_parents_batch_size)
                     {
                         self.tasks.sync_parent(ca_handle.clone(), parent, now());
                     }
@@ -1213,17 +1691,21 @@ impl CaManager {
 
         let server_info = child.parent(parent)?.parent_server_info();
         let parent_uri = server_info.service_uri();
+        let timeout = self.parent_protocol_timeout(handle, parent).await;
 
         match self
             .send_revoke_requests_rfc6492(
+                handle,
+                parent,
                 revoke_requests,
                 &child.id_cert().public_key().key_identifier(),
                 server_info,
+                timeout,
             )
             .await
         {
             Err(e) => {
-                self.status_store.set_parent_failure(handle, parent, parent_uri, &e)?;
+                self.set_parent_failure(handle, parent, parent_uri, &e)?;
                 Err(e)
             }
             Ok(res) => {
@@ -1249,9 +1731,12 @@ impl CaManager {
 
     async fn send_revoke_requests_rfc6492(
         &self,
+        handle: &CaHandle,
+        parent: &ParentHandle,
         revoke_requests: HashMap<ResourceClassName, Vec<RevocationRequest>>,
         signing_key: &KeyIdentifier,
         server_info: &ParentServerInfo,
+        timeout: u64,
     ) -> KrillResult<HashMap<ResourceClassName, Vec<RevocationResponse>>> {
         let mut revoke_map = HashMap::new();
 
@@ -1264,7 +1749,7 @@ impl CaManager {
                 let revoke = provisioning::Message::revoke(sender, recipient, req.clone());
 
                 let response = self
-                    .send_rfc6492_and_validate_response(revoke, server_info, signing_key)
+                    .send_rfc6492_and_validate_response(handle, parent, revoke, server_info, signing_key, timeout)
                     .await?;
 
                 let payload = response.into_payload();
@@ -1334,6 +1819,7 @@ impl CaManager {
         let requests = ca.cert_requests(parent);
         let signing_key = ca.id_cert().public_key().key_identifier();
         let server_info = ca.parent(parent)?.parent_server_info();
+        let timeout = self.parent_protocol_timeout(ca_handle, parent).await;
 
         // We may need to do work for multiple resource class and there may therefore be
         // multiple errors. We want to keep track of those, rather than bailing out on the
@@ -1353,7 +1839,7 @@ impl CaManager {
                 let msg = provisioning::Message::issue(sender, recipient, req);
 
                 match self
-                    .send_rfc6492_and_validate_response(msg, server_info, &signing_key)
+                    .send_rfc6492_and_validate_response(ca_handle, parent, msg, server_info, &signing_key, timeout)
                     .await
                 {
                     Err(e) => {
@@ -1422,7 +1908,7 @@ impl CaManager {
                                                         ca_handle,
                                                         rcn.clone(),
                                                         rcvd_cert,
-                                                        self.config.clone(),
+                                                        self.config(),
                                                         self.signer.clone(),
                                                         actor,
                                                     ))
@@ -1595,7 +2081,7 @@ impl CaManager {
                 Error::Multiple(errors)
             };
 
-            self.status_store.set_parent_failure(ca_handle, parent, uri, &e)?;
+            self.set_parent_failure(ca_handle, parent, uri, &e)?;
 
             Err(e)
         }
@@ -1631,8 +2117,9 @@ impl CaManager {
     ) -> KrillResult<ResourceClassListResponse> {
         let server_info = contact.parent_server_info();
         let uri = server_info.service_uri();
+        let timeout = self.parent_protocol_timeout(ca, parent).await;
 
-        let result = self.get_entitlements_rfc6492(ca, server_info).await;
+        let result = self.get_entitlements_rfc6492(ca, parent, server_info, timeout).await;
 
         match &result {
             Err(error) => {
@@ -1640,7 +2127,7 @@ impl CaManager {
                     // only update the status store with errors for existing parents
                     // otherwise we end up with entries if a new parent is rejected because
                     // of the error.
-                    self.status_store.set_parent_failure(ca, parent, uri, error)?;
+                    self.set_parent_failure(ca, parent, uri, error)?;
                 }
             }
             Ok(entitlements) => {
@@ -1654,7 +2141,9 @@ impl CaManager {
     async fn get_entitlements_rfc6492(
         &self,
         handle: &CaHandle,
+        parent: &ParentHandle,
         server_info: &ParentServerInfo,
+        timeout: u64,
     ) -> KrillResult<ResourceClassListResponse> {
         debug!(
             "Getting entitlements for CA '{}' from parent '{}'",
@@ -1671,7 +2160,14 @@ impl CaManager {
         let list = provisioning::Message::list(sender, recipient);
 
         let response = self
-            .send_rfc6492_and_validate_response(list, server_info, &child.id_cert().public_key().key_identifier())
+            .send_rfc6492_and_validate_response(
+                handle,
+                parent,
+                list,
+                server_info,
+                &child.id_cert().public_key().key_identifier(),
+                timeout,
+            )
             .await?;
 
         let payload = response.into_payload();
@@ -1687,55 +2183,128 @@ impl CaManager {
         }
     }
 
+    /// Sends an RFC 6492 message to a parent and returns the validated
+    /// response. A summary of the exchange is kept in the exchange log for
+    /// the parent.
     async fn send_rfc6492_and_validate_response(
         &self,
+        ca_handle: &CaHandle,
+        parent: &ParentHandle,
         message: provisioning::Message,
         server_info: &ParentServerInfo,
         signing_key: &KeyIdentifier,
+        timeout: u64,
     ) -> KrillResult<provisioning::Message> {
         let service_uri = server_info.service_uri();
-        if let Some(parent) = Self::local_parent(service_uri, &self.config.service_uri()) {
-            let ca_handle = parent.into_converted();
-            let user_agent = Some("local-child".to_string());
+        let request = message.payload().payload_type().to_string();
+        let started = Instant::now();
+
+        let (result, http_status, cms) =
+            if let Some(local_parent) = Self::local_parent(service_uri, &self.config().service_uri()) {
+                let local_parent = local_parent.into_converted();
+                let user_agent = Some("local-child".to_string());
+
+                let result = self
+                    .rfc6492_process_request(&local_parent, message, user_agent, &self.system_actor)
+                    .await;
+                (result, None, CmsValidation::Local)
+            } else {
+                self.send_rfc6492_cms(message, server_info, signing_key, timeout).await
+            };
 
-            self.rfc6492_process_request(&ca_handle, message, user_agent, &self.system_actor)
-                .await
-        } else {
-            // Set up a logger for CMS exchanges. Note that this logger is always set
-            // up and used, but.. it will only actually save files in case the given
-            // rfc6492_log_dir is Some.
-            let sender = message.sender().clone();
-            let recipient = message.recipient().clone();
+        let record = ParentExchangeRecord::new(
+            service_uri.clone(),
+            request,
+            result
+                .as_ref()
+                .ok()
+                .map(|response| response.payload().payload_type().to_string()),
+            http_status,
+            cms,
+            started.elapsed().as_millis() as u64,
+            result.as_ref().err().map(|e| e.to_string()),
+        );
+
+        // The exchange log is only used for diagnostics, so failing to save
+        // it should not fail the exchange.
+        if let Err(e) = self.status_store.add_parent_exchange(ca_handle, parent, record) {
+            warn!(
+                "Could not save exchange of CA '{}' with parent '{}'. Error: {}",
+                ca_handle, parent, e
+            );
+        }
+
+        result
+    }
+
+    /// Sends an RFC 6492 message to a remote parent as signed CMS. Returns
+    /// the validated response, together with the HTTP status code and the
+    /// outcome of the CMS validation for the exchange log.
+    async fn send_rfc6492_cms(
+        &self,
+        message: provisioning::Message,
+        server_info: &ParentServerInfo,
+        signing_key: &KeyIdentifier,
+        timeout: u64,
+    ) -> (KrillResult<provisioning::Message>, Option<u16>, CmsValidation) {
+        let service_uri = server_info.service_uri();
 
-            let cms_logger = CmsLogger::for_rfc6492_sent(self.config.rfc6492_log_dir.as_ref(), &sender, &recipient);
+        // Set up a logger for CMS exchanges. Note that this logger is always set
+        // up and used, but.. it will only actually save files in case the given
+        // rfc6492_log_dir is Some.
+        let sender = message.sender().clone();
+        let recipient = message.recipient().clone();
 
-            let cms = self.signer.create_rfc6492_cms(message, signing_key)?.to_bytes();
+        let cms_logger = CmsLogger::for_rfc6492_sent(self.config().rfc6492_log_dir.as_ref(), &sender, &recipient);
 
-            let res_bytes = self
-                .post_protocol_cms_binary(&cms, service_uri, provisioning::CONTENT_TYPE, &cms_logger)
-                .await?;
+        let cms = match self.signer.create_rfc6492_cms(message, signing_key) {
+            Ok(cms) => cms.to_bytes(),
+            Err(e) => return (Err(e.into()), None, CmsValidation::NoResponse),
+        };
+
+        let res_bytes = match self
+            .post_protocol_cms_binary(&cms, service_uri, provisioning::CONTENT_TYPE, &cms_logger, timeout)
+            .await
+        {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let http_status = match &e {
+                    Error::HttpClientError(e) => e.status_code(),
+                    _ => None,
+                };
+                return (Err(e), http_status, CmsValidation::NoResponse);
+            }
+        };
 
-            match ProvisioningCms::decode(&res_bytes) {
+        // Only a response with status 200 has a body.
+        let http_status = Some(200);
+
+        match ProvisioningCms::decode(&res_bytes) {
+            Err(e) => {
+                error!(
+                    "Could not decode response from parent (handle): {}, for ca (handle): {}, at URI: {}. Error: {}",
+                    recipient, sender, service_uri, e
+                );
+                let cms = CmsValidation::Undecodable(e.to_string());
+                match cms_logger.err(format!("Could not decode CMS: {}", e)) {
+                    Err(log_err) => (Err(log_err.into()), http_status, cms),
+                    Ok(()) => (Err(Error::Rfc6492(e)), http_status, cms),
+                }
+            }
+            Ok(cms) => match cms.validate(server_info.id_cert().public_key()) {
                 Err(e) => {
                     error!(
-                        "Could not decode response from parent (handle): {}, for ca (handle): {}, at URI: {}. Error: {}",
+                        "Could not validate response from parent (handle): {}, for ca (handle): {}, at URI: {}. Error: {}",
                         recipient, sender, service_uri, e
                     );
-                    cms_logger.err(format!("Could not decode CMS: {}", e))?;
-                    Err(Error::Rfc6492(e))
-                }
-                Ok(cms) => match cms.validate(server_info.id_cert().public_key()) {
-                    Err(e) => {
-                        error!(
-                            "Could not validate response from parent (handle): {}, for ca (handle): {}, at URI: {}. Error: {}",
-                            recipient, sender, service_uri, e
-                        );
-                        cms_logger.err(format!("Response invalid: {}", e))?;
-                        Err(Error::Rfc6492(e))
+                    let cms = CmsValidation::Invalid(e.to_string());
+                    match cms_logger.err(format!("Response invalid: {}", e)) {
+                        Err(log_err) => (Err(log_err.into()), http_status, cms),
+                        Ok(()) => (Err(Error::Rfc6492(e)), http_status, cms),
                     }
-                    Ok(()) => Ok(cms.into_message()),
-                },
-            }
+                }
+                Ok(()) => (Ok(cms.into_message()), http_status, CmsValidation::Valid),
+            },
         }
     }
 
@@ -1745,12 +2314,20 @@ impl CaManager {
         service_uri: &ServiceUri,
         content_type: &str,
         cms_logger: &CmsLogger,
+        timeout: u64,
     ) -> KrillResult<Bytes> {
         cms_logger.sent(msg)?;
 
-        let timeout = self.config.post_protocol_msg_timeout_seconds;
+        let cx = telemetry::span(
+            format!("POST {}", content_type),
+            SpanKind::Client,
+            vec![KeyValue::new("http.url", service_uri.to_string())],
+        );
+        // Let the parent or publication server continue the trace, if any.
+        let headers = telemetry::trace_headers(&cx);
+        let post = httpclient::post_binary_with_full_ua(service_uri.as_str(), msg, content_type, timeout, headers);
 
-        match httpclient::post_binary_with_full_ua(service_uri.as_str(), msg, content_type, timeout).await {
+        match telemetry::traced(cx, post).await {
             Err(e) => {
                 cms_logger.err(format!("Error posting CMS to {}: {}", service_uri, e))?;
                 Err(Error::HttpClientError(e))
@@ -1827,14 +2404,30 @@ impl CaManager {
             let proxy = self.get_trust_anchor_proxy().await?;
             let id = proxy.id();
             let repo = proxy.repository().ok_or(Error::TaProxyHasNoRepository)?;
-            let objects = proxy.get_trust_anchor_objects()?.publish_elements()?;
+            let mut objects = proxy.get_trust_anchor_objects()?.publish_elements()?;
+
+            // Publish the TA certificate itself as well, if it lives in the TA repository.
+            if let Ok(ta_details) = proxy.get_ta_details() {
+                if let Some(ta_cert) = ta_details.publish_element(repo.repo_info()) {
+                    objects.push(ta_cert);
+                }
+            }
 
             self.ca_repo_sync(repo_manager, ca_handle, id, repo, objects).await
         } else {
             let ca = self.get_ca(ca_handle).await?;
+
+            // A failure at one repository should not keep the CA from
+            // publishing in the others. The first failure is returned when
+            // all repositories were tried.
+            let mut failure = None;
             for (repo_contact, objects) in self.ca_repo_elements(ca_handle).await? {
-                self.ca_repo_sync(repo_manager, ca_handle, ca.id_cert(), &repo_contact, objects)
-                    .await?;
+                if let Err(e) = self
+                    .ca_repo_sync(repo_manager, ca_handle, ca.id_cert(), &repo_contact, objects)
+                    .await
+                {
+                    failure.get_or_insert(e);
+                }
             }
 
             // Clean-up of old repos
@@ -1860,7 +2453,10 @@ impl CaManager {
                 self.ca_deprecated_repo_remove(ca_handle, deprecated.contact())?;
             }
 
-            Ok(())
+            match failure {
+                Some(e) => Err(e),
+                None => Ok(()),
+            }
         }
     }
 
@@ -1880,6 +2476,13 @@ impl CaManager {
 
         let elements: HashMap<_, _> = list_reply.into_elements().into_iter().map(|el| el.unpack()).collect();
 
+        // Objects published by the TA proxy, or withdrawn from deprecated
+        // repositories, are not confirmed.
+        let confirm = self.config().publication_confirm_lag_minutes.is_some()
+            && ca_handle.as_str() != TA_NAME
+            && !publish_elements.is_empty()
+            && repo_contact.repo_info().rpki_notify().is_some();
+
         let mut all_objects: HashMap<_, _> = publish_elements.into_iter().map(|el| el.unpack()).collect();
 
         let mut delta = PublishDelta::empty();
@@ -1904,7 +2507,14 @@ impl CaManager {
             self.send_rfc8181_delta(repo_manager, ca_handle, id_cert, repo_contact.server_info(), delta)
                 .await?;
             debug!("CA '{}' sent delta", ca_handle);
-        } else {
+
+            if confirm {
+                self.status_store
+                    .set_status_repo_unconfirmed(ca_handle, repo_contact.server_info().service_uri())?;
+                self.tasks
+                    .confirm_publication(ca_handle.clone(), in_seconds(PUBLICATION_CONFIRM_RETRY_SECONDS));
+            }
+        } else {
             info!("CA '{}' has nothing to publish", ca_handle);
         }
 
@@ -1923,6 +2533,449 @@ impl CaManager {
         Ok(self.ca_objects_store.ca_objects(ca)?.repo_elements_map())
     }
 
+    /// Fetches the objects published by a CA from its repository, and checks
+    /// them in the way that a relying party would. The result is kept in the
+    /// status of the CA, and issues are logged as warnings.
+    pub async fn ca_publication_check(&self, ca_handle: &CaHandle) -> KrillResult<PublicationCheck> {
+        let ca = self.get_ca(ca_handle).await?;
+        let repo_contact = ca.repository_contact()?;
+        let intended = self
+            .ca_repo_elements(ca_handle)
+            .await?
+            .remove(repo_contact)
+            .unwrap_or_default();
+        let certs = ca.received_certificates();
+
+        let mut sources = vec![];
+
+        if let Some(notify) = repo_contact.repo_info().rpki_notify() {
+            let fetched = selfcheck::fetch_rrdp(notify).await;
+            sources.push(selfcheck::check_source(
+                PublicationCheckProtocol::Rrdp,
+                notify.to_string(),
+                fetched,
+                &certs,
+                &intended,
+            ));
+        }
+
+        if self.config().publication_check_rsync {
+            let mut dirs: Vec<uri::Rsync> = certs.iter().map(|cert| cert.ca_repository().clone()).collect();
+            dirs.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            dirs.dedup();
+            let uri = dirs.iter().map(|dir| dir.as_str()).collect::<Vec<_>>().join(", ");
+
+            let work_dir = self
+                .config()
+                .data_dir
+                .join(PUBLICATION_CHECK_DIR)
+                .join(ca_handle.as_str());
+
+            // Running rsync blocks, so keep it off the async runtime threads.
+            let fetched = tokio::task::spawn_blocking(move || {
+                let mut objects = HashMap::new();
+                for (nr, dir) in dirs.iter().enumerate() {
+                    objects.extend(selfcheck::fetch_rsync(dir, &work_dir.join(nr.to_string()))?);
+                }
+                Ok(objects)
+            })
+            .await
+            .unwrap_or_else(|e| Err(format!("rsync fetch task failed: {}", e)));
+
+            sources.push(selfcheck::check_source(
+                PublicationCheckProtocol::Rsync,
+                uri,
+                fetched,
+                &certs,
+                &intended,
+            ));
+        }
+
+        for source in &sources {
+            for issue in source.issues() {
+                warn!(
+                    "Publication check for CA '{}' using {} found issue: {}",
+                    ca_handle,
+                    source.protocol(),
+                    issue
+                );
+            }
+        }
+
+        let previous_failed = self
+            .status_store
+            .get_ca_status(ca_handle)
+            .publication_check()
+            .map(|check| !check.is_ok())
+            .unwrap_or(false);
+
+        let check = PublicationCheck::new(sources);
+        self.status_store.set_publication_check(ca_handle, check.clone())?;
+
+        // Only alert about new issues, not for every check that finds them.
+        if !check.is_ok() && !previous_failed {
+            self.notifier.notify(Alert::new(
+                AlertEvent::PublicationFailure,
+                ca_handle.clone(),
+                format!("publication check found issues:\n{}", check),
+            ));
+        }
+
+        Ok(check)
+    }
+
+    /// Confirms that the objects which a CA published can be fetched back
+    /// over RRDP from each repository where they are not yet confirmed. An
+    /// alert is raised if this lags more than 'publication_confirm_lag_minutes'.
+    ///
+    /// Returns true if all published objects are confirmed, or false if this
+    /// should be tried again later.
+    pub async fn ca_publication_confirm(&self, ca_handle: &CaHandle) -> KrillResult<bool> {
+        let lag_minutes = match self.config().publication_confirm_lag_minutes {
+            Some(minutes) => minutes,
+            None => return Ok(true),
+        };
+
+        let status = self.status_store.get_ca_status(ca_handle);
+        let mut all_confirmed = true;
+
+        for (repo_contact, intended) in self.ca_repo_elements(ca_handle).await? {
+            let uri = repo_contact.server_info().service_uri();
+
+            let confirmation = match status.repos().get(uri).and_then(|repo| repo.confirmation()) {
+                Some(confirmation) if confirmation.pending_since().is_some() => confirmation,
+                _ => continue,
+            };
+
+            let notify = match repo_contact.repo_info().rpki_notify() {
+                Some(notify) => notify,
+                None => continue,
+            };
+
+            let confirmed = self
+                .rrdp_follower
+                .fetch(notify)
+                .await
+                .and_then(|(serial, fetched)| selfcheck::confirm_published(&intended, &fetched).map(|_| serial));
+
+            match confirmed {
+                Ok(serial) => {
+                    debug!(
+                        "CA '{}' confirmed its objects at repository '{}' in RRDP serial {}",
+                        ca_handle, uri, serial
+                    );
+                    self.status_store.set_status_repo_confirmed(ca_handle, uri, serial)?;
+                }
+                Err(e) => {
+                    debug!(
+                        "CA '{}' could not (yet) confirm its objects at repository '{}': {}",
+                        ca_handle, uri, e
+                    );
+                    all_confirmed = false;
+
+                    // Only alert once for objects that are not confirmed.
+                    if confirmation.lags(lag_minutes) && !confirmation.is_lagging() {
+                        let message = format!(
+                            "objects published at repository '{}' are not available over RRDP after {} minutes: {}",
+                            uri, lag_minutes, e
+                        );
+                        warn!("CA '{}' {}", ca_handle, message);
+                        self.status_store.set_status_repo_confirm_lagging(ca_handle, uri)?;
+                        self.events.publish(CaEvent::new(
+                            CaEventType::PublicationFailure,
+                            ca_handle.clone(),
+                            &message,
+                        ));
+                        self.notifier
+                            .notify(Alert::new(AlertEvent::PublicationFailure, ca_handle.clone(), message));
+                    }
+                }
+            }
+        }
+
+        Ok(all_confirmed)
+    }
+
+    /// Runs the publication check for all CAs which have published objects.
+    pub async fn ca_publication_check_all(&self) -> KrillResult<()> {
+        for ca_handle in self.ca_store.list()? {
+            let ca = self.get_ca(&ca_handle).await?;
+            if ca.repository_contact().is_err() || ca.received_certificates().is_empty() {
+                continue;
+            }
+
+            if let Err(e) = self.ca_publication_check(&ca_handle).await {
+                error!("Could not run publication check for CA '{}'. Error: {}", ca_handle, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the configuration of a CA, i.e. its ROA and ASPA definitions
+    /// and its children, with the objects it issued and published. If repair
+    /// is true and divergences were found, then the ROA and ASPA objects are
+    /// issued again in accordance with the configuration, and divergences
+    /// which remain after that are reported.
+    ///
+    /// Divergences of child certificates are not repaired, because these
+    /// are only issued on request of the children.
+    pub async fn ca_reconcile(&self, ca_handle: &CaHandle, repair: bool, actor: &Actor) -> KrillResult<Reconciliation> {
+        let mut divergences = self.ca_divergences(ca_handle).await?;
+
+        let repairable = divergences
+            .iter()
+            .any(|divergence| divergence.object() != DivergenceObject::ChildCertificate);
+
+        let repaired = repair && repairable;
+        if repaired {
+            self.ca_reconcile_repair(ca_handle, &divergences, actor).await?;
+            divergences = self.ca_divergences(ca_handle).await?;
+        }
+
+        for divergence in &divergences {
+            warn!("Reconciliation of CA '{}' found divergence: {}", ca_handle, divergence);
+        }
+
+        let reconciliation = Reconciliation::new(divergences, repaired);
+        self.status_store
+            .set_reconciliation(ca_handle, reconciliation.clone())?;
+
+        Ok(reconciliation)
+    }
+
+    /// Runs the reconciliation for all CAs.
+    pub async fn ca_reconcile_all(&self, repair: bool, actor: &Actor) -> KrillResult<()> {
+        for ca_handle in self.ca_store.list()? {
+            if let Err(e) = self.ca_reconcile(&ca_handle, repair, actor).await {
+                error!("Could not reconcile CA '{}'. Error: {}", ca_handle, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the divergences between the configuration of a CA and the
+    /// objects it issued, and between the issued objects and the objects
+    /// it publishes. Manifests and CRLs are not compared, as they are not
+    /// issued by the CA itself, but for the set of published objects.
+    async fn ca_divergences(&self, ca_handle: &CaHandle) -> KrillResult<Vec<Divergence>> {
+        let ca = self.get_ca(ca_handle).await?;
+        let mut divergences = ca.divergences();
+
+        // Nothing is published by CAs without a repository.
+        if ca.repository_contact().is_err() {
+            return Ok(divergences);
+        }
+
+        let issued = ca.issued_objects();
+        let published: HashMap<_, _> = self
+            .ca_objects_store
+            .ca_objects(ca_handle)?
+            .all_publish_elements()
+            .into_iter()
+            .filter(|element| {
+                let uri = element.uri().as_str();
+                !uri.ends_with(".mft") && !uri.ends_with(".crl")
+            })
+            .map(|element| (element.base64().to_hash(), element.uri().clone()))
+            .collect();
+
+        for (hash, description) in &issued {
+            if !published.contains_key(hash) {
+                divergences.push(Divergence::missing(
+                    DivergenceObject::PublishedObject,
+                    description,
+                    "the object was issued, but it is not published",
+                ));
+            }
+        }
+
+        for (hash, uri) in &published {
+            if !issued.contains_key(hash) {
+                divergences.push(Divergence::orphaned(
+                    DivergenceObject::PublishedObject,
+                    uri,
+                    "the object is published, but it is not issued by the CA",
+                ));
+            }
+        }
+
+        Ok(divergences)
+    }
+
+    /// Issues the ROA and ASPA objects of a CA again in accordance with its
+    /// configuration.
+    async fn ca_reconcile_repair(
+        &self,
+        ca_handle: &CaHandle,
+        divergences: &[Divergence],
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        info!("Repairing the ROA and ASPA objects of CA '{}'", ca_handle);
+
+        // Updates without any changes make the CA issue and remove objects
+        // as needed for its current configuration.
+        self.ca_routes_update(ca_handle.clone(), RoaConfigurationUpdates::empty(), actor)
+            .await?;
+        self.ca_aspas_definitions_update(ca_handle.clone(), AspaDefinitionUpdates::new(vec![], vec![]), actor)
+            .await?;
+
+        // Objects which expired are renewed, but published objects which are
+        // missing are only published again when they are re-issued.
+        let force = divergences
+            .iter()
+            .any(|divergence| divergence.object() == DivergenceObject::PublishedObject);
+        let roas_renew = if force {
+            CmdDet::RouteAuthorizationsForceRenew(self.config(), self.signer.clone())
+        } else {
+            CmdDet::RouteAuthorizationsRenew(self.config(), self.signer.clone())
+        };
+        self.send_ca_command(Cmd::new(ca_handle, None, roas_renew, actor))
+            .await?;
+        self.send_ca_command(Cmd::new(
+            ca_handle,
+            None,
+            CmdDet::AspasRenew(self.config(), self.signer.clone()),
+            actor,
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the expiry times of all objects published by a CA. Objects
+    /// are marked as expiring if they expire within the given window, or
+    /// the configured window if none is given.
+    pub fn ca_objects_expiry(&self, ca: &CaHandle, window_hours: Option<u32>) -> KrillResult<ObjectsExpiry> {
+        let window_hours = window_hours.unwrap_or(self.config().expiry_window_hours);
+        Ok(self.ca_objects_store.ca_objects(ca)?.objects_expiry(window_hours))
+    }
+
+    /// Sends alerts for certificates received from parents which expire
+    /// within the configured number of days. Krill cannot renew these by
+    /// itself, so this typically means that the parent needs attention.
+    pub async fn ca_expiry_alerts_all(&self) -> KrillResult<()> {
+        let days = self.config().alerts.expiry_warning_days;
+        let threshold = Time::now() + Duration::days(days.into());
+
+        for ca_handle in self.ca_store.list()? {
+            let ca = self.get_ca(&ca_handle).await?;
+            for cert in ca.received_certificates() {
+                if cert.expires() < threshold {
+                    self.notifier.notify(Alert::new(
+                        AlertEvent::Expiry,
+                        ca_handle.clone(),
+                        format!(
+                            "certificate '{}' received from parent expires at {}",
+                            cert.uri(),
+                            cert.expires().to_rfc3339()
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a failure to contact a parent, and sends an alert unless
+    /// the previous contact failed as well. Connected clients are told
+    /// about every failure.
+    fn set_parent_failure(
+        &self,
+        ca: &CaHandle,
+        parent: &ParentHandle,
+        uri: &ServiceUri,
+        error: &Error,
+    ) -> KrillResult<()> {
+        let previous_failed = self
+            .status_store
+            .get_ca_status(ca)
+            .parents()
+            .get(parent)
+            .and_then(|status| status.last_exchange())
+            .map(|exchange| !exchange.was_success())
+            .unwrap_or(false);
+
+        self.status_store.set_parent_failure(ca, parent, uri, error)?;
+
+        let message = format!("failed to contact parent '{}' at '{}': {}", parent, uri, error);
+        self.events
+            .publish(CaEvent::new(CaEventType::ParentFailure, ca.clone(), &message));
+
+        if !previous_failed {
+            self.notifier
+                .notify(Alert::new(AlertEvent::ParentFailure, ca.clone(), message));
+        }
+        Ok(())
+    }
+
+    /// Returns the retry config for contacting the given parent, with the CA
+    /// specific overrides applied if there are any.
+    async fn parent_retry_config(&self, ca: &CaHandle, parent: &ParentHandle) -> RetryConfig {
+        let config = self.config();
+        match self.get_ca(ca).await {
+            Ok(ca) => config.retry_parent.with_overrides(&ca.retry_policies().parent(parent)),
+            Err(_) => config.retry_parent.clone(),
+        }
+    }
+
+    async fn parent_protocol_timeout(&self, ca: &CaHandle, parent: &ParentHandle) -> u64 {
+        let retry = self.parent_retry_config(ca, parent).await;
+        self.config().protocol_msg_timeout_seconds(&retry)
+    }
+
+    /// Records another consecutive failure to synchronise the CA with the given
+    /// parent, and returns when the next attempt should be made following the
+    /// retry policy for the parent.
+    pub async fn ca_parent_backoff(&self, ca: &CaHandle, parent: &ParentHandle) -> Priority {
+        let retry = self.parent_retry_config(ca, parent).await;
+        let failures = self
+            .status_store
+            .get_ca_status(ca)
+            .parents()
+            .get(parent)
+            .and_then(|status| status.backoff())
+            .map(|backoff| backoff.failures())
+            .unwrap_or(0)
+            + 1;
+
+        let next = self.config().requeue_remote_failed(&retry, failures);
+
+        let backoff = Backoff::new(failures, (&next).into());
+        if let Err(e) = self.status_store.set_parent_backoff(ca, parent, backoff) {
+            error!(
+                "Could not save backoff state for CA '{}' and parent '{}': {}",
+                ca, parent, e
+            );
+        }
+
+        next
+    }
+
+    /// Records a failure to contact the repository, and sends an alert
+    /// unless the previous contact failed as well. Connected clients are
+    /// told about every failure.
+    fn set_repo_failure(&self, ca: &CaHandle, uri: ServiceUri, error: &Error) -> KrillResult<()> {
+        let previous_failed = self
+            .status_store
+            .get_ca_status(ca)
+            .repo()
+            .last_exchange()
+            .map(|exchange| !exchange.was_success())
+            .unwrap_or(false);
+
+        self.status_store.set_status_repo_failure(ca, uri.clone(), error)?;
+
+        let message = format!("failed to publish at repository '{}': {}", uri, error);
+        self.events
+            .publish(CaEvent::new(CaEventType::PublicationFailure, ca.clone(), &message));
+
+        if !previous_failed {
+            self.notifier
+                .notify(Alert::new(AlertEvent::PublicationFailure, ca.clone(), message));
+        }
+        Ok(())
+    }
+
     /// Get deprecated repositories so that they can be cleaned.
     pub fn ca_deprecated_repos(&self, ca: &CaHandle) -> KrillResult<Vec<DeprecatedRepository>> {
         Ok(self.ca_objects_store.ca_objects(ca)?.deprecated_repos().clone())
@@ -1933,7 +2986,9 @@ impl CaManager {
         self.ca_objects_store.with_ca_objects(ca, |objects| {
             objects.deprecated_repo_remove(to_remove);
             Ok(())
-        })
+        })?;
+        self.status_store
+            .remove_repo_status(ca, to_remove.server_info().service_uri())
     }
 
     /// Increase the clean attempt counter for a deprecated repository
@@ -1969,6 +3024,376 @@ impl CaManager {
         Ok(())
     }
 
+    /// Update the repository where a resource class of a CA publishes, or
+    /// revert it to the repository of the CA if no contact is given.
+    pub async fn update_class_repo(
+        &self,
+        repo_manager: &RepositoryManager,
+        ca_handle: CaHandle,
+        rcn: ResourceClassName,
+        new_contact: Option<RepositoryContact>,
+        check_repo: bool,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        if check_repo {
+            if let Some(new_contact) = &new_contact {
+                let ca = self.get_ca(&ca_handle).await?;
+                self.send_rfc8181_list(repo_manager, &ca_handle, ca.id_cert(), new_contact.server_info())
+                    .await
+                    .map_err(|e| Error::CaRepoIssue(ca_handle.clone(), e.to_string()))?;
+            }
+        }
+        let cmd = CmdDet::update_class_repo(&ca_handle, rcn, new_contact, self.signer.clone(), actor);
+        self.send_ca_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Returns the progress of moving a CA, or some of its resource classes,
+    /// to another repository.
+    pub async fn ca_repo_migration_status(&self, ca_handle: &CaHandle) -> KrillResult<RepoMigrationStatus> {
+        let classes = self.get_ca(ca_handle).await?.repo_migration_classes();
+        let deprecated_repos = self
+            .ca_deprecated_repos(ca_handle)?
+            .iter()
+            .map(|deprecated| deprecated.contact().server_info().service_uri().clone())
+            .collect();
+        Ok(RepoMigrationStatus::new(classes, deprecated_repos))
+    }
+
+    /// Activates the new keys of resource classes which are migrating to
+    /// another repository, so that the CA is found in the new repository.
+    ///
+    /// The new repository is verified first. Its RRDP content must match the
+    /// objects which the CA published there for its new keys, or else the
+    /// keys are not activated. The objects in the old repository are only
+    /// withdrawn after activation, when the key rolls are finished.
+    pub async fn ca_repo_migration_activate(
+        &self,
+        ca_handle: &CaHandle,
+        actor: &Actor,
+    ) -> KrillResult<RepoMigrationStatus> {
+        let ca = self.get_ca(ca_handle).await?;
+        let status = self.ca_repo_migration_status(ca_handle).await?;
+
+        if !status.is_ready_to_activate() {
+            return Err(Error::CaRepoIssue(
+                ca_handle.clone(),
+                "no repository migration is ready to be activated".to_string(),
+            ));
+        }
+
+        // Activating new keys is done for the CA as a whole, so we cannot
+        // do this while a new key is staged for other reasons.
+        if ca.key_roll_staged_outside_repo_migration() {
+            return Err(Error::KeyRollInProgress);
+        }
+
+        let mut repos = vec![];
+        for class in status.classes() {
+            if class.stage() == RepoMigrationStage::Staged {
+                let contact = ca.class_repository_contact(class.resource_class_name())?;
+                if !repos.contains(&contact) {
+                    repos.push(contact);
+                }
+            }
+        }
+
+        for repo in repos {
+            let check = self.ca_repo_migration_check(&ca, repo).await?;
+            if !check.is_ok() {
+                return Err(Error::CaRepoIssue(
+                    ca_handle.clone(),
+                    format!(
+                        "new repository at {} does not serve the objects of the CA yet:\n{}",
+                        repo.server_info().service_uri(),
+                        check
+                    ),
+                ));
+            }
+        }
+
+        self.ca_keyroll_activate(ca_handle.clone(), Duration::seconds(0), actor)
+            .await?;
+
+        self.ca_repo_migration_status(ca_handle).await
+    }
+
+    /// Migrates a CA to a new repository in as few steps as possible. This
+    /// checks that the migration can be done, starts key rolls so that new
+    /// keys publish to the new repository, and activates the new keys once
+    /// the new repository is verified to serve the objects of the CA. The
+    /// objects in the old repository are withdrawn when the parents have
+    /// revoked the old keys.
+    ///
+    /// Synchronisation with the parents and repositories is done by the
+    /// scheduler in the background. This stops at the first step that
+    /// cannot be completed yet, e.g. because a remote parent did not yet
+    /// issue a certificate. Calling this again with the same repository
+    /// resumes the migration.
+    pub async fn ca_repo_migrate(
+        &self,
+        repo_manager: &RepositoryManager,
+        ca_handle: &CaHandle,
+        new_contact: RepositoryContact,
+        actor: &Actor,
+    ) -> KrillResult<RepoMigrationReport> {
+        let mut steps = vec![];
+        let ca = self.get_ca(ca_handle).await?;
+
+        if ca.repository_contact()? != &new_contact {
+            if let Some(failure) = self.ca_repo_migrate_check(repo_manager, &ca, &new_contact).await {
+                steps.push(failure);
+                return self.ca_repo_migrate_report(ca_handle, steps).await;
+            }
+            steps.push(RepoMigrationStep::done("checked that the CA can migrate"));
+
+            // The key rolls result in certificate requests, which are sent
+            // to the parents by the scheduler.
+            self.update_repo(repo_manager, ca_handle.clone(), new_contact, false, actor)
+                .await?;
+            steps.push(RepoMigrationStep::done("started key rolls for the new repository"));
+        } else if self.ca_repo_migration_status(ca_handle).await?.is_complete() {
+            steps.push(RepoMigrationStep::done("the CA uses the new repository"));
+            return self.ca_repo_migrate_report(ca_handle, steps).await;
+        }
+
+        let status = self.ca_repo_migration_status(ca_handle).await?;
+        if status
+            .classes()
+            .iter()
+            .any(|class| class.stage() == RepoMigrationStage::Requested)
+        {
+            // Make sure that the requests are sent again now, rather than at
+            // the next planned synchronisation with the parents.
+            self.ca_repo_migrate_schedule_sync(ca_handle).await;
+            steps.push(RepoMigrationStep::waiting(
+                "certify new keys",
+                "not all parents have issued a certificate for the new keys yet",
+            ));
+            return self.ca_repo_migrate_report(ca_handle, steps).await;
+        }
+        steps.push(RepoMigrationStep::done(
+            "certified new keys and published in the new repository",
+        ));
+
+        if status.is_ready_to_activate() {
+            if let Err(e) = self.ca_repo_migration_activate(ca_handle, actor).await {
+                steps.push(RepoMigrationStep::waiting("verify the new repository", e));
+                return self.ca_repo_migrate_report(ca_handle, steps).await;
+            }
+            steps.push(RepoMigrationStep::done(
+                "verified the new repository using RRDP, and activated the new keys",
+            ));
+
+            // The activation results in revocation requests for the old keys,
+            // which are sent to the parents by the scheduler. The objects of
+            // the old keys are withdrawn when they are revoked.
+        } else if !status.classes().is_empty() {
+            self.ca_repo_migrate_schedule_sync(ca_handle).await;
+        }
+
+        if self.ca_repo_migration_status(ca_handle).await?.is_complete() {
+            steps.push(RepoMigrationStep::done("withdrew all objects from the old repository"));
+        } else {
+            steps.push(RepoMigrationStep::waiting(
+                "withdraw from the old repository",
+                "not all parents have revoked the old keys yet, or the old repository could not be cleaned",
+            ));
+        }
+
+        self.ca_repo_migrate_report(ca_handle, steps).await
+    }
+
+    /// Checks that a CA can migrate to a new repository. Returns the failed
+    /// step if it cannot.
+    async fn ca_repo_migrate_check(
+        &self,
+        repo_manager: &RepositoryManager,
+        ca: &CertAuth,
+        new_contact: &RepositoryContact,
+    ) -> Option<RepoMigrationStep> {
+        let check = "check that the CA can migrate";
+
+        if !ca.key_roll_possible() {
+            return Some(RepoMigrationStep::failed(
+                check,
+                "a key roll is in progress, or a resource class has no active key",
+            ));
+        }
+
+        if new_contact.repo_info().rpki_notify().is_none() {
+            return Some(RepoMigrationStep::failed(
+                check,
+                "the new repository has no RRDP notification URI, so it cannot be verified",
+            ));
+        }
+
+        if let Err(e) = self
+            .send_rfc8181_list(repo_manager, ca.handle(), ca.id_cert(), new_contact.server_info())
+            .await
+        {
+            return Some(RepoMigrationStep::failed(
+                check,
+                format!("the new repository cannot be reached: {}", e),
+            ));
+        }
+
+        None
+    }
+
+    /// Schedules synchronisation of a CA with its parents and repositories,
+    /// for a migration which is waiting for either of them. Failures show as
+    /// steps which cannot be completed yet when the migration is resumed.
+    async fn ca_repo_migrate_schedule_sync(&self, ca_handle: &CaHandle) {
+        if let Ok(ca) = self.get_ca(ca_handle).await {
+            for parent in ca.parents() {
+                self.tasks.sync_parent(ca_handle.clone(), parent.clone(), now());
+            }
+        }
+        self.tasks.sync_repo(ca_handle.clone(), now());
+    }
+
+    /// Aborts the migration of a CA to another repository, and returns to
+    /// the repository it was migrating from. This is possible until the new
+    /// keys are activated. Certified new keys are revoked, and their objects
+    /// are withdrawn from the new repository, by the scheduler.
+    pub async fn ca_repo_migration_abort(
+        &self,
+        ca_handle: &CaHandle,
+        actor: &Actor,
+    ) -> KrillResult<RepoMigrationStatus> {
+        let contact = self
+            .ca_objects_store
+            .ca_objects(ca_handle)?
+            .repo_migrating_from()
+            .cloned()
+            .ok_or_else(|| {
+                Error::CaRepoIssue(ca_handle.clone(), "no repository migration is in progress".to_string())
+            })?;
+
+        let cmd = CmdDet::abort_repo_migration(ca_handle, contact, self.signer.clone(), actor);
+        self.send_ca_command(cmd).await?;
+
+        self.ca_repo_migration_status(ca_handle).await
+    }
+
+    async fn ca_repo_migrate_report(
+        &self,
+        ca_handle: &CaHandle,
+        steps: Vec<RepoMigrationStep>,
+    ) -> KrillResult<RepoMigrationReport> {
+        let status = self.ca_repo_migration_status(ca_handle).await?;
+        Ok(RepoMigrationReport::new(steps, status))
+    }
+
+    /// Checks that the RRDP server of a repository which a CA migrates to
+    /// serves the objects which the CA published there.
+    async fn ca_repo_migration_check(&self, ca: &CertAuth, repo: &RepositoryContact) -> KrillResult<PublicationCheck> {
+        let notify = repo.repo_info().rpki_notify().ok_or_else(|| {
+            Error::CaRepoIssue(
+                ca.handle().clone(),
+                "new repository has no RRDP notification URI, and cannot be verified".to_string(),
+            )
+        })?;
+
+        let intended = self
+            .ca_repo_elements(ca.handle())
+            .await?
+            .remove(repo)
+            .unwrap_or_default();
+
+        // Only the keys which publish in the new repository are checked.
+        let base_uri = repo.repo_info().base_uri();
+        let certs: Vec<&ReceivedCert> = ca
+            .received_certificates()
+            .into_iter()
+            .filter(|cert| cert.ca_repository().as_str().starts_with(base_uri.as_str()))
+            .collect();
+
+        let fetched = selfcheck::fetch_rrdp(notify).await;
+        let source = selfcheck::check_source(
+            PublicationCheckProtocol::Rrdp,
+            notify.to_string(),
+            fetched,
+            &certs,
+            &intended,
+        );
+
+        Ok(PublicationCheck::new(vec![source]))
+    }
+
+    /// Replace the CA specific overrides of the issuance timing config.
+    pub async fn ca_issuance_timing_update(
+        &self,
+        ca: CaHandle,
+        overrides: IssuanceTimingOverrides,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let cmd = CmdDet::update_issuance_timing(&ca, overrides, self.config(), actor);
+        self.send_ca_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Replace the CA specific overrides of the retry policy for contacting
+    /// the repository, or the given parent.
+    pub async fn ca_retry_policy_update(
+        &self,
+        ca: CaHandle,
+        parent: Option<ParentHandle>,
+        policy: RetryPolicy,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let cmd = CmdDet::update_retry_policy(&ca, parent, policy, self.config(), actor);
+        self.send_ca_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Set and/or remove labels of a CA.
+    pub async fn ca_labels_update(&self, ca: CaHandle, update: CaLabelsUpdate, actor: &Actor) -> KrillResult<()> {
+        let cmd = CmdDet::update_labels(&ca, update, actor);
+        self.send_ca_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Returns the retry config for synchronising with the repository, with the
+    /// CA specific overrides applied if there are any.
+    async fn repo_retry_config(&self, ca: &CaHandle) -> RetryConfig {
+        let config = self.config();
+        match self.get_ca(ca).await {
+            Ok(ca) => config.retry_repository.with_overrides(ca.retry_policies().repository()),
+            Err(_) => config.retry_repository.clone(),
+        }
+    }
+
+    async fn repo_protocol_timeout(&self, ca: &CaHandle) -> u64 {
+        let retry = self.repo_retry_config(ca).await;
+        self.config().protocol_msg_timeout_seconds(&retry)
+    }
+
+    /// Records another consecutive failure to synchronise the CA with its
+    /// repository, and returns when the next attempt should be made following
+    /// the retry policy for the repository.
+    pub async fn ca_repo_backoff(&self, ca: &CaHandle) -> Priority {
+        let retry = self.repo_retry_config(ca).await;
+        let failures = self
+            .status_store
+            .get_ca_status(ca)
+            .repo()
+            .backoff()
+            .map(|backoff| backoff.failures())
+            .unwrap_or(0)
+            + 1;
+
+        let next = self.config().requeue_remote_failed(&retry, failures);
+
+        let backoff = Backoff::new(failures, (&next).into());
+        if let Err(e) = self.status_store.set_status_repo_backoff(ca, backoff) {
+            error!("Could not save repository backoff state for CA '{}': {}", ca, e);
+        }
+
+        next
+    }
+
     async fn send_rfc8181_list(
         &self,
         repo_manager: &RepositoryManager,
@@ -1987,7 +3412,7 @@ impl CaManager {
         {
             Ok(reply) => reply,
             Err(e) => {
-                self.status_store.set_status_repo_failure(ca_handle, uri.clone(), &e)?;
+                self.set_repo_failure(ca_handle, uri.clone(), &e)?;
                 return Err(e);
             }
         };
@@ -1999,14 +3424,12 @@ impl CaManager {
             }
             publication::Reply::Success => {
                 let err = Error::custom("Got success reply to list query?!");
-                self.status_store
-                    .set_status_repo_failure(ca_handle, uri.clone(), &err)?;
+                self.set_repo_failure(ca_handle, uri.clone(), &err)?;
                 Err(err)
             }
             publication::Reply::ErrorReply(e) => {
                 let err = Error::Custom(format!("Got error reply: {}", e));
-                self.status_store
-                    .set_status_repo_failure(ca_handle, uri.clone(), &err)?;
+                self.set_repo_failure(ca_handle, uri.clone(), &err)?;
                 Err(err)
             }
         }
@@ -2031,7 +3454,7 @@ impl CaManager {
         {
             Ok(reply) => reply,
             Err(e) => {
-                self.status_store.set_status_repo_failure(ca_handle, uri.clone(), &e)?;
+                self.set_repo_failure(ca_handle, uri.clone(), &e)?;
                 return Err(e);
             }
         };
@@ -2044,14 +3467,12 @@ impl CaManager {
             }
             publication::Reply::ErrorReply(e) => {
                 let err = Error::Custom(format!("Got error reply: {}", e));
-                self.status_store
-                    .set_status_repo_failure(ca_handle, uri.clone(), &err)?;
+                self.set_repo_failure(ca_handle, uri.clone(), &err)?;
                 Err(err)
             }
             publication::Reply::List(_) => {
                 let err = Error::custom("Got list reply to delta query?!");
-                self.status_store
-                    .set_status_repo_failure(ca_handle, uri.clone(), &err)?;
+                self.set_repo_failure(ca_handle, uri.clone(), &err)?;
                 Err(err)
             }
         }
@@ -2069,7 +3490,7 @@ impl CaManager {
 
         if repo_service_uri
             .as_str()
-            .starts_with(self.config.service_uri().as_str())
+            .starts_with(self.config().service_uri().as_str())
         {
             // this maps back to *this* Krill instance
             let query = message.as_query()?;
@@ -2080,12 +3501,13 @@ impl CaManager {
             // Set up a logger for CMS exchanges. Note that this logger is always set
             // up and used, but.. it will only actually save files in case the given
             // rfc8181_log_dir is Some.
-            let cms_logger = CmsLogger::for_rfc8181_sent(self.config.rfc8181_log_dir.as_ref(), ca_handle);
+            let cms_logger = CmsLogger::for_rfc8181_sent(self.config().rfc8181_log_dir.as_ref(), ca_handle);
 
             let cms = self.signer.create_rfc8181_cms(message, signing_key)?.to_bytes();
+            let timeout = self.repo_protocol_timeout(ca_handle).await;
 
             let res_bytes = self
-                .post_protocol_cms_binary(&cms, repo_service_uri, publication::CONTENT_TYPE, &cms_logger)
+                .post_protocol_cms_binary(&cms, repo_service_uri, publication::CONTENT_TYPE, &cms_logger, timeout)
                 .await?;
 
             match publication::PublicationCms::decode(&res_bytes) {
@@ -2132,7 +3554,7 @@ impl CaManager {
         self.send_ca_command(CmdDet::aspas_definitions_update(
             &ca,
             updates,
-            self.config.clone(),
+            self.config(),
             self.signer.clone(),
             actor,
         ))
@@ -2140,6 +3562,24 @@ impl CaManager {
         Ok(())
     }
 
+    /// Replace all ASPA definitions for this CA with the given definitions.
+    /// Only the changes are applied, so that ASPA objects for unchanged
+    /// definitions are not re-issued. Returns the applied changes.
+    pub async fn ca_aspas_definitions_import(
+        &self,
+        ca: CaHandle,
+        definitions: AspaDefinitionList,
+        actor: &Actor,
+    ) -> KrillResult<AspaDefinitionUpdates> {
+        let updates = self.get_ca(&ca).await?.aspas_definitions_import_delta(definitions)?;
+
+        if updates.contains_changes() {
+            self.ca_aspas_definitions_update(ca, updates.clone(), actor).await?;
+        }
+
+        Ok(updates)
+    }
+
     /// Update the ASPA definition for this CA and the customer ASN in the update.
     pub async fn ca_aspas_update_aspa(
         &self,
@@ -2152,13 +3592,25 @@ impl CaManager {
             &ca,
             customer,
             update,
-            self.config.clone(),
+            self.config(),
             self.signer.clone(),
             actor,
         ))
         .await?;
         Ok(())
     }
+
+    /// Shows the outcome of an update to the providers for a customer ASN
+    /// in a CA, without applying it.
+    pub async fn ca_aspas_update_aspa_dry_run(
+        &self,
+        ca: CaHandle,
+        customer: AspaCustomer,
+        update: AspaProvidersUpdate,
+    ) -> KrillResult<AspaUpdateDryRun> {
+        let ca = self.get_ca(&ca).await?;
+        ca.aspas_update_dry_run(customer, &update, &self.config(), &self.signer)
+    }
 }
 
 /// # BGPSec functions
@@ -2178,7 +3630,7 @@ impl CaManager {
         self.send_ca_command(CmdDet::bgpsec_update_definitions(
             &ca,
             updates,
-            self.config.clone(),
+            self.config(),
             self.signer.clone(),
             actor,
         ))
@@ -2207,7 +3659,7 @@ impl CaManager {
         self.send_ca_command(CmdDet::route_authorizations_update(
             &ca,
             updates,
-            self.config.clone(),
+            self.config(),
             self.signer.clone(),
             actor,
         ))
@@ -2215,6 +3667,71 @@ impl CaManager {
         Ok(())
     }
 
+    /// Stage a ROA update as a proposal for the CA. The update is verified,
+    /// but no ROAs are issued until the proposal is approved.
+    pub async fn ca_routes_propose(
+        &self,
+        ca: CaHandle,
+        updates: RoaConfigurationUpdates,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(CmdDet::route_authorizations_propose(&ca, updates, actor))
+            .await?;
+        Ok(())
+    }
+
+    /// Approve a pending ROA proposal, i.e. apply its updates and issue the
+    /// resulting ROAs. See [`CaManager::ca_routes_update`].
+    pub async fn ca_routes_proposal_approve(&self, ca: CaHandle, id: u64, actor: &Actor) -> KrillResult<()> {
+        self.send_ca_command(CmdDet::route_authorizations_approve(
+            &ca,
+            id,
+            self.config(),
+            self.signer.clone(),
+            actor,
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Reject a pending ROA proposal.
+    pub async fn ca_routes_proposal_reject(&self, ca: CaHandle, id: u64, actor: &Actor) -> KrillResult<()> {
+        self.send_ca_command(CmdDet::route_authorizations_reject(&ca, id, actor))
+            .await?;
+        Ok(())
+    }
+
+    /// Set, or with None remove, the CA specific ROA issuance strategy. The
+    /// ROAs of the CA are re-issued in accordance with the resulting strategy.
+    pub async fn ca_roa_issuance_strategy_update(
+        &self,
+        ca: CaHandle,
+        strategy: Option<RoaIssuanceStrategy>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(CmdDet::roa_issuance_strategy_update(
+            &ca,
+            strategy,
+            self.config(),
+            self.signer.clone(),
+            actor,
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Set, or with None remove, the CA specific ROA auto-pilot policy.
+    pub async fn ca_roa_autopilot_update(
+        &self,
+        ca: CaHandle,
+        policy: Option<RoaAutoPilotPolicy>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_ca_command(CmdDet::roa_autopilot_update(&ca, policy, actor))
+            .await?;
+        Ok(())
+    }
+
     /// Re-issue about to expire objects in all CAs. This is a no-op in case
     /// ROAs do not need re-issuance. If new objects are created they will also
     /// be published (event will trigger that MFT and CRL are also made, and
@@ -2228,7 +3745,7 @@ impl CaManager {
             let cmd = Cmd::new(
                 &ca,
                 None,
-                CmdDet::RouteAuthorizationsRenew(self.config.clone(), self.signer.clone()),
+                CmdDet::RouteAuthorizationsRenew(self.config(), self.signer.clone()),
                 actor,
             );
 
@@ -2236,12 +3753,7 @@ impl CaManager {
                 error!("Renewing ROAs for CA '{}' failed with error: {}", ca, e);
             }
 
-            let cmd = Cmd::new(
-                &ca,
-                None,
-                CmdDet::AspasRenew(self.config.clone(), self.signer.clone()),
-                actor,
-            );
+            let cmd = Cmd::new(&ca, None, CmdDet::AspasRenew(self.config(), self.signer.clone()), actor);
 
             if let Err(e) = self.send_ca_command(cmd).await {
                 error!("Renewing ASPAs for CA '{}' failed with error: {}", ca, e);
@@ -2250,7 +3762,7 @@ impl CaManager {
             let cmd = Cmd::new(
                 &ca,
                 None,
-                CmdDet::BgpSecRenew(self.config.clone(), self.signer.clone()),
+                CmdDet::BgpSecRenew(self.config(), self.signer.clone()),
                 actor,
             );
 
@@ -2271,7 +3783,7 @@ impl CaManager {
             let cmd = Cmd::new(
                 &ca,
                 None,
-                CmdDet::RouteAuthorizationsForceRenew(self.config.clone(), self.signer.clone()),
+                CmdDet::RouteAuthorizationsForceRenew(self.config(), self.signer.clone()),
                 actor,
             );
             if let Err(e) = self.send_ca_command(cmd).await {
@@ -2282,6 +3794,16 @@ impl CaManager {
     }
 }
 
+/// # RPKI Signed Checklist functions
+///
+impl CaManager {
+    /// Sign an RPKI Signed Checklist (RSC)
+    pub async fn rsc_sign(&self, ca: &CaHandle, request: RscContentRequest) -> KrillResult<RpkiSignedChecklist> {
+        let ca = self.get_ca(ca).await?;
+        ca.rsc_sign(&request, &self.signer)
+    }
+}
+
 /// # Resource Tagged Attestation functions
 ///
 impl CaManager {
@@ -2340,8 +3862,35 @@ impl CaManager {
     /// a staging period of 24 hours, but we may use a shorter period for testing and/or emergency
     /// manual key rolls.
     pub async fn ca_keyroll_activate(&self, handle: CaHandle, staging: Duration, actor: &Actor) -> KrillResult<()> {
-        let activate_cmd = CmdDet::key_roll_activate(&handle, staging, self.config.clone(), self.signer.clone(), actor);
+        let activate_cmd = CmdDet::key_roll_activate(&handle, staging, self.config(), self.signer.clone(), actor);
         self.send_ca_command(activate_cmd).await?;
         Ok(())
     }
+
+    /// Returns which signers hold the keys of a CA.
+    pub async fn ca_signer_migration_status(&self, handle: &CaHandle) -> KrillResult<SignerMigrationStatus> {
+        Ok(self.get_ca(handle).await?.signer_migration_status(&self.signer))
+    }
+
+    /// Takes the next step in moving the keys of a CA to the signer used for
+    /// new keys, i.e. the default signer. Key rolls are initiated for resource
+    /// classes where the current key is held by another signer, and the new
+    /// keys of these rolls are activated after the RFC 6489 staging period.
+    /// Other key rolls are left alone.
+    ///
+    /// This is meant to be called repeatedly until the returned status shows
+    /// that the migration is complete. The previous signer must remain
+    /// configured until then, so that its keys can still be used.
+    pub async fn ca_signer_migrate(&self, handle: CaHandle, actor: &Actor) -> KrillResult<SignerMigrationStatus> {
+        let migrate_cmd = CmdDet::key_roll_migrate(
+            &handle,
+            Duration::hours(SIGNER_MIGRATION_STAGING_HOURS),
+            self.config(),
+            self.signer.clone(),
+            actor,
+        );
+        self.send_ca_command(migrate_cmd).await?;
+
+        self.ca_signer_migration_status(&handle).await
+    }
 }
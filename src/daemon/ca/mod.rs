@@ -38,9 +38,14 @@ pub use self::events::*;
 mod manager;
 pub use self::manager::CaManager;
 
+mod rsc;
+pub use self::rsc::*;
+
 mod rta;
 pub use self::rta::*;
 
+mod selfcheck;
+
 mod status;
 pub use self::status::*;
 
@@ -3,12 +3,16 @@
 use std::{
     borrow::BorrowMut,
     collections::HashMap,
-    path::Path,
     str::FromStr,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::Instant,
 };
 
 use chrono::Duration;
+use futures_util::{stream, StreamExt};
 
 use rpki::{
     ca::{idexchange::CaHandle, provisioning::ResourceClassName, publication::Base64},
@@ -26,12 +30,13 @@ use rpki::{
 use crate::{
     commons::{
         api::{
-            rrdp::PublishElement, CertInfo, IssuedCertificate, ObjectName, ReceivedCert, RepositoryContact, Revocation,
-            Revocations,
+            rrdp::PublishElement, CertInfo, IssuanceTimingOverrides, IssuedCertificate, ObjectExpiry, ObjectName,
+            ObjectsExpiry, ReceivedCert, RepositoryContact, Revocation, Revocations,
         },
         crypto::KrillSigner,
         error::Error,
-        eventsourcing::{KeyStoreKey, KeyValueStore, PreSaveEventListener},
+        eventsourcing::{locks::HandleLocks, KeyStoreKey, KeyValueStorage, KeyValueStore, PreSaveEventListener},
+        util::clock,
         KrillResult,
     },
     constants::CA_OBJECTS_DIR,
@@ -56,24 +61,48 @@ use super::{AspaInfo, AspaObjectsUpdates, BgpSecCertInfo, BgpSecCertificateUpdat
 /// new Manifests and CRLs was done through the event sourcing framework. However,
 /// this led to excessive use of disk space, makes the history more difficult to
 /// inspect, and causes issues with regards to replaying CA state from scratch.
+///
+/// Updates to the objects of a CA are done while holding a lock for that CA
+/// only, so that objects for different CAs can be re-issued concurrently.
 #[derive(Clone, Debug)]
 pub struct CaObjectsStore {
-    store: Arc<RwLock<KeyValueStore>>,
+    store: Arc<KeyValueStore>,
+    locks: Arc<HandleLocks>,
     signer: Arc<KrillSigner>,
-    issuance_timing: IssuanceTimingConfig,
+    issuance_timing: Arc<RwLock<IssuanceTimingConfig>>,
+    republish_workers: usize,
+    metrics: Arc<RepublishMetrics>,
 }
 
 /// # Construct
 impl CaObjectsStore {
-    pub fn disk(work_dir: &Path, issuance_timing: IssuanceTimingConfig, signer: Arc<KrillSigner>) -> KrillResult<Self> {
-        let store = KeyValueStore::disk(work_dir, CA_OBJECTS_DIR)?;
-        let store = Arc::new(RwLock::new(store));
+    pub fn create(
+        storage: &KeyValueStorage,
+        issuance_timing: IssuanceTimingConfig,
+        republish_workers: usize,
+        signer: Arc<KrillSigner>,
+    ) -> KrillResult<Self> {
+        let store = KeyValueStore::create(storage, CA_OBJECTS_DIR)?;
+        let store = Arc::new(store);
         Ok(CaObjectsStore {
             store,
+            locks: Arc::new(HandleLocks::default()),
             signer,
-            issuance_timing,
+            issuance_timing: Arc::new(RwLock::new(issuance_timing)),
+            republish_workers: republish_workers.max(1),
+            metrics: Arc::new(RepublishMetrics::default()),
         })
     }
+
+    /// Replaces the issuance timing, e.g. after the configuration was
+    /// reloaded. The new timing is used when objects are next issued.
+    pub fn update_issuance_timing(&self, issuance_timing: IssuanceTimingConfig) {
+        *self.issuance_timing.write().unwrap() = issuance_timing;
+    }
+
+    fn timing(&self, overrides: &IssuanceTimingOverrides) -> IssuanceTimingConfig {
+        self.issuance_timing.read().unwrap().with_overrides(overrides)
+    }
 }
 
 /// # Process new objects as they are being produced
@@ -82,10 +111,14 @@ impl PreSaveEventListener<CertAuth> for CaObjectsStore {
         // Note that the `CertAuth` which is passed in has already been
         // updated with the state changes contained in the event.
 
-        let timing = &self.issuance_timing;
         let signer = &self.signer;
 
         self.with_ca_objects(ca.handle(), |objects| {
+            // Keep the CA specific timing overrides, so that they can also
+            // be used when manifests and CRLs are re-issued without any event.
+            objects.update_issuance_timing(ca.issuance_timing_overrides());
+            let timing = &self.timing(objects.issuance_timing());
+
             let mut force_reissue = false;
 
             for event in events {
@@ -139,6 +172,12 @@ impl PreSaveEventListener<CertAuth> for CaObjectsStore {
                     super::CaEvtDet::KeyRollFinished { resource_class_name } => {
                         objects.keyroll_finish(resource_class_name)?;
                     }
+                    super::CaEvtDet::KeyRollAborted {
+                        resource_class_name, ..
+                    } => {
+                        objects.keyroll_abort(resource_class_name)?;
+                        force_reissue = true;
+                    }
                     super::CaEvtDet::CertificateReceived {
                         resource_class_name,
                         rcvd_cert,
@@ -160,6 +199,17 @@ impl PreSaveEventListener<CertAuth> for CaObjectsStore {
                         objects.update_repo(contact);
                         force_reissue = true;
                     }
+                    super::CaEvtDet::ClassRepoUpdated {
+                        resource_class_name,
+                        contact,
+                    } => {
+                        objects.update_class_repo(resource_class_name, contact.as_ref());
+                        force_reissue = true;
+                    }
+                    super::CaEvtDet::RepoMigrationAborted { contact } => {
+                        objects.repo_migration_abort(contact);
+                        force_reissue = true;
+                    }
                     _ => {}
                 }
             }
@@ -177,8 +227,6 @@ impl CaObjectsStore {
     fn cas(&self) -> KrillResult<Vec<CaHandle>> {
         let cas = self
             .store
-            .read()
-            .unwrap()
             .keys(None, ".json")?
             .iter()
             .flat_map(|k| {
@@ -199,7 +247,7 @@ impl CaObjectsStore {
     pub fn ca_objects(&self, ca: &CaHandle) -> KrillResult<CaObjects> {
         let key = Self::key(ca);
 
-        match self.store.read().unwrap().get(&key).map_err(Error::KeyValueError)? {
+        match self.store.get(&key).map_err(Error::KeyValueError)? {
             None => {
                 let objects = CaObjects::new(ca.clone(), None, HashMap::new(), vec![]);
                 Ok(objects)
@@ -210,47 +258,142 @@ impl CaObjectsStore {
 
     /// Perform an action (closure) on a mutable instance of the CaObjects for a
     /// CA. If the CA did not have any CaObjects yet, one will be created. The
-    /// closure is executed within a write lock.
+    /// closure is executed within a write lock for the CA.
     pub fn with_ca_objects<F>(&self, ca: &CaHandle, op: F) -> KrillResult<()>
     where
         F: FnOnce(&mut CaObjects) -> KrillResult<()>,
     {
-        let lock = self.store.write().unwrap();
+        let handle_lock = self.locks.for_handle(ca.clone());
+        let _write = handle_lock.write();
 
         let key = Self::key(ca);
 
-        let mut objects = lock
+        // Do not hold on to the store while the closure runs, as it may sign
+        // new objects which can take a while, e.g. when using an HSM.
+        let mut objects = self
+            .store
             .get(&key)
             .map_err(Error::KeyValueError)?
             .unwrap_or_else(|| CaObjects::new(ca.clone(), None, HashMap::new(), vec![]));
 
         op(&mut objects)?;
 
-        lock.store(&key, &objects).map_err(Error::KeyValueError)?;
-
-        Ok(())
+        self.store.store(&key, &objects).map_err(Error::KeyValueError)
     }
 
     pub fn put_ca_objects(&self, ca: &CaHandle, objects: &CaObjects) -> KrillResult<()> {
-        self.store
-            .write()
-            .unwrap()
-            .store(&Self::key(ca), objects)
-            .map_err(Error::KeyValueError)
+        let handle_lock = self.locks.for_handle(ca.clone());
+        let _write = handle_lock.write();
+
+        self.store.store(&Self::key(ca), objects).map_err(Error::KeyValueError)
     }
 
     // Re-issue MFT and CRL for all CAs *if needed*, returns all CAs which were updated.
-    pub fn reissue_all(&self, force: bool) -> KrillResult<Vec<CaHandle>> {
+    //
+    // CAs are handled concurrently by a bounded number of workers. Signing is
+    // blocking, and may involve a round trip to an HSM, so each CA is handled
+    // on a thread for blocking operations rather than on the async runtime.
+    pub async fn reissue_all(&self, force: bool) -> KrillResult<Vec<CaHandle>> {
+        let cas = self.cas()?;
+        let started = Instant::now();
+        self.metrics.queued.store(cas.len(), Ordering::Relaxed);
+
+        let results: Vec<KrillResult<Option<CaHandle>>> = stream::iter(cas)
+            .map(|ca| {
+                let store = self.clone();
+                async move {
+                    store.metrics.queued.fetch_sub(1, Ordering::Relaxed);
+                    tokio::task::spawn_blocking(move || store.reissue(ca, force))
+                        .await
+                        .map_err(|e| Error::custom(format!("Re-issuance task failed: {}", e)))?
+                }
+            })
+            .buffer_unordered(self.republish_workers)
+            .collect()
+            .await;
+
+        // Report the CAs which were re-issued in the metrics, even if there
+        // were failures for other CAs.
         let mut res = vec![];
-        for ca in self.cas()? {
-            self.with_ca_objects(&ca, |objects| {
-                if objects.re_issue(force, &self.issuance_timing, &self.signer)? {
-                    res.push(ca.clone())
+        let mut failure = None;
+        for result in results {
+            match result {
+                Ok(Some(ca)) => res.push(ca),
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Could not re-issue MFT and CRL: {}", e);
+                    failure.get_or_insert(e);
                 }
-                Ok(())
-            })?;
+            }
         }
-        Ok(res)
+
+        self.metrics.finish(res.len(), started);
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(res),
+        }
+    }
+
+    // Re-issue MFT and CRL for a single CA *if needed*, returns the CA if it was updated.
+    pub fn reissue(&self, ca: CaHandle, force: bool) -> KrillResult<Option<CaHandle>> {
+        let mut reissued = false;
+        self.with_ca_objects(&ca, |objects| {
+            let timing = self.timing(objects.issuance_timing());
+            reissued = objects.re_issue(force, &timing, &self.signer)?;
+            Ok(())
+        })?;
+        Ok(if reissued { Some(ca) } else { None })
+    }
+
+    pub fn republish_workers(&self) -> usize {
+        self.republish_workers
+    }
+
+    pub fn republish_metrics(&self) -> &RepublishMetrics {
+        &self.metrics
+    }
+}
+
+//------------ RepublishMetrics --------------------------------------------
+
+/// Keeps track of the progress and throughput of re-issuing manifests and
+/// CRLs for all CAs, for reporting in the metrics.
+#[derive(Debug, Default)]
+pub struct RepublishMetrics {
+    // Number of CAs waiting to be checked in the current run
+    queued: AtomicUsize,
+
+    // Number of CAs re-issued since the server was started
+    reissued_total: AtomicU64,
+
+    // Duration of the last completed run, and the number of CAs re-issued in it
+    last_run_millis: AtomicU64,
+    last_run_reissued: AtomicUsize,
+}
+
+impl RepublishMetrics {
+    fn finish(&self, reissued: usize, started: Instant) {
+        self.reissued_total.fetch_add(reissued as u64, Ordering::Relaxed);
+        self.last_run_reissued.store(reissued, Ordering::Relaxed);
+        self.last_run_millis
+            .store(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    pub fn reissued_total(&self) -> u64 {
+        self.reissued_total.load(Ordering::Relaxed)
+    }
+
+    pub fn last_run_seconds(&self) -> f64 {
+        self.last_run_millis.load(Ordering::Relaxed) as f64 / 1000.0
+    }
+
+    pub fn last_run_reissued(&self) -> usize {
+        self.last_run_reissued.load(Ordering::Relaxed)
     }
 }
 
@@ -261,8 +404,15 @@ pub struct CaObjects {
 
     classes: HashMap<ResourceClassName, ResourceClassObjects>,
 
+    // Resource classes which publish to another repository than 'repo'.
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    class_repos: HashMap<ResourceClassName, RepositoryContact>,
+
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     deprecated_repos: Vec<DeprecatedRepository>,
+
+    #[serde(skip_serializing_if = "IssuanceTimingOverrides::is_empty", default)]
+    issuance_timing: IssuanceTimingOverrides,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -309,7 +459,9 @@ impl CaObjects {
             ca,
             repo,
             classes,
+            class_repos: HashMap::new(),
             deprecated_repos,
+            issuance_timing: IssuanceTimingOverrides::default(),
         }
     }
 
@@ -321,17 +473,27 @@ impl CaObjects {
 
         if let Some(repo) = &self.repo {
             res.insert(repo.clone(), vec![]);
+            for class_repo in self.class_repos.values() {
+                res.entry(class_repo.clone()).or_insert_with(Vec::new);
+            }
 
-            for resource_class_objects in self.classes.values() {
+            for (rcn, resource_class_objects) in self.classes.iter() {
                 // Note the map 'res' will get entries for other (old) repositories
                 // if there are any keys with such repositories.
-                resource_class_objects.add_elements(&mut res, repo);
+                let class_repo = self.class_repos.get(rcn).unwrap_or(repo);
+                resource_class_objects.add_elements(&mut res, class_repo);
             }
         }
 
         res
     }
 
+    /// Returns the resource classes which publish to another repository than
+    /// the one used by the CA as a whole.
+    pub fn class_repos(&self) -> &HashMap<ResourceClassName, RepositoryContact> {
+        &self.class_repos
+    }
+
     /// Returns all PublishElements in all repositories (if there is more than one).
     pub fn all_publish_elements(&self) -> Vec<PublishElement> {
         let mut all_elements = vec![];
@@ -344,6 +506,23 @@ impl CaObjects {
         all_elements
     }
 
+    /// Returns the expiry times of all objects published by the CA.
+    pub fn objects_expiry(&self, window_hours: u32) -> ObjectsExpiry {
+        let mut objects = vec![];
+        for resource_class_objects in self.classes.values() {
+            resource_class_objects.add_expiry(&mut objects, window_hours);
+        }
+        ObjectsExpiry::new(window_hours, objects)
+    }
+
+    pub fn issuance_timing(&self) -> &IssuanceTimingOverrides {
+        &self.issuance_timing
+    }
+
+    fn update_issuance_timing(&mut self, overrides: &IssuanceTimingOverrides) {
+        self.issuance_timing = overrides.clone();
+    }
+
     pub fn deprecated_repos(&self) -> &Vec<DeprecatedRepository> {
         &self.deprecated_repos
     }
@@ -385,6 +564,10 @@ impl CaObjects {
         if let Some(old_repo) = old_repo_opt {
             self.deprecate_repo_if_no_longer_used(old_repo);
         }
+
+        if let Some(class_repo) = self.class_repos.remove(class_name) {
+            self.deprecate_repo_if_no_longer_used(class_repo);
+        }
     }
 
     fn get_class_mut(&mut self, rcn: &ResourceClassName) -> KrillResult<&mut ResourceClassObjects> {
@@ -424,6 +607,19 @@ impl CaObjects {
         Ok(())
     }
 
+    // Abort a keyroll for a repository migration. The objects of a staged key
+    // are withdrawn from the repository it used when the roll is finished.
+    fn keyroll_abort(&mut self, rcn: &ResourceClassName) -> KrillResult<()> {
+        let repo = self
+            .class_repos
+            .get(rcn)
+            .or(self.repo.as_ref())
+            .cloned()
+            .ok_or_else(|| Error::publishing("No repository for resource class"))?;
+
+        self.get_class_mut(rcn)?.keyroll_abort(&repo)
+    }
+
     // Update the ROAs in the current set
     fn update_roas(&mut self, rcn: &ResourceClassName, roa_updates: &RoaUpdates) -> KrillResult<()> {
         self.get_class_mut(rcn).map(|rco| rco.update_roas(roa_updates))
@@ -474,22 +670,72 @@ impl CaObjects {
     // If the repository is being migrated, i.e. there already is a current repository,
     // then make sure that the current repository is preserved as the old repository for
     // existing keys.
+    //
+    // Resource classes which use their own repository are not affected.
     fn update_repo(&mut self, repo: &RepositoryContact) {
         if let Some(old) = &self.repo {
-            for resource_class_objects in self.classes.values_mut() {
-                resource_class_objects.set_old_repo(old);
+            for (rcn, resource_class_objects) in self.classes.iter_mut() {
+                if !self.class_repos.contains_key(rcn) {
+                    resource_class_objects.set_old_repo(old);
+                }
             }
         }
+        // A repository may be used again, e.g. after a migration to it was
+        // aborted. It must then no longer be cleaned.
+        self.deprecated_repo_remove(repo);
         self.repo = Some(repo.clone());
     }
 
+    // Update the repository of a single resource class, or revert it to the
+    // repository of the CA. Like for 'update_repo' the current repository of
+    // the class is preserved as the old repository for its existing keys.
+    fn update_class_repo(&mut self, rcn: &ResourceClassName, repo: Option<&RepositoryContact>) {
+        let current = self.class_repos.get(rcn).or(self.repo.as_ref()).cloned();
+        if let (Some(current), Some(resource_class_objects)) = (current, self.classes.get_mut(rcn)) {
+            resource_class_objects.set_old_repo(&current);
+        }
+
+        match repo {
+            Some(repo) => {
+                self.deprecated_repo_remove(repo);
+                self.class_repos.insert(rcn.clone(), repo.clone());
+            }
+            None => {
+                self.class_repos.remove(rcn);
+            }
+        }
+    }
+
+    // Return to the repository which the CA was migrating from. The key rolls
+    // for the migration were aborted, so the repository which it was migrating
+    // to is deprecated unless it is still used by a staged key which is now
+    // waiting to be revoked.
+    fn repo_migration_abort(&mut self, repo: &RepositoryContact) {
+        if let Some(aborted) = self.repo.replace(repo.clone()) {
+            self.deprecate_repo_if_no_longer_used(aborted);
+        }
+    }
+
+    /// Returns the repository which the CA is migrating from, if a migration
+    /// of the CA repository is in progress.
+    pub fn repo_migrating_from(&self) -> Option<&RepositoryContact> {
+        self.classes
+            .iter()
+            .filter(|(rcn, _)| !self.class_repos.contains_key(*rcn))
+            .find_map(|(_, rco)| rco.old_repo())
+    }
+
     fn has_old_repo(&self, old_repo: &RepositoryContact) -> bool {
         self.classes.values().any(|rco| rco.has_old_repo(old_repo))
     }
 
+    fn is_repo_in_use(&self, repo: &RepositoryContact) -> bool {
+        self.repo.as_ref() == Some(repo) || self.class_repos.values().any(|class_repo| class_repo == repo)
+    }
+
     // Marks a repository as deprecated unless it's (still) in use by any key
     fn deprecate_repo_if_no_longer_used(&mut self, old_repo: RepositoryContact) {
-        if !self.has_old_repo(&old_repo) {
+        if !self.has_old_repo(&old_repo) && !self.is_repo_in_use(&old_repo) {
             self.deprecated_repos.push(DeprecatedRepository::new(old_repo, 0));
         }
     }
@@ -523,6 +769,20 @@ impl ResourceClassObjects {
         }
     }
 
+    fn add_expiry(&self, objects: &mut Vec<ObjectExpiry>, window_hours: u32) {
+        match &self.keys {
+            ResourceClassKeyState::Current(state) => state.current_set.add_expiry(objects, window_hours),
+            ResourceClassKeyState::Staging(state) => {
+                state.current_set.add_expiry(objects, window_hours);
+                state.staging_set.add_expiry(objects, window_hours);
+            }
+            ResourceClassKeyState::Old(state) => {
+                state.current_set.add_expiry(objects, window_hours);
+                state.old_set.add_expiry(objects, window_hours);
+            }
+        }
+    }
+
     fn create(key: &CertifiedKey, timing: &IssuanceTimingConfig, signer: &KrillSigner) -> KrillResult<Self> {
         let current_set = KeyObjectSet::create(key, timing, signer)?;
 
@@ -577,6 +837,32 @@ impl ResourceClassObjects {
         }
     }
 
+    // Aborts a keyroll before the staged key is activated. The current set
+    // no longer publishes to an old repository. A staged set is retired, and
+    // its objects are withdrawn from the given repository when the roll is
+    // finished.
+    fn keyroll_abort(&mut self, repo: &RepositoryContact) -> KrillResult<()> {
+        self.keys = match &self.keys {
+            ResourceClassKeyState::Current(state) => {
+                let mut current_set = state.current_set.clone();
+                current_set.clear_old_repo();
+                ResourceClassKeyState::current(current_set)
+            }
+            ResourceClassKeyState::Staging(state) => {
+                let mut current_set = state.current_set.clone();
+                current_set.clear_old_repo();
+                let mut old_set = state.staging_set.retire()?;
+                old_set.set_old_repo(repo);
+                ResourceClassKeyState::old(current_set, old_set)
+            }
+            ResourceClassKeyState::Old(_) => {
+                return Err(Error::publishing("published resource class in the wrong key state"))
+            }
+        };
+
+        Ok(())
+    }
+
     fn update_received_cert(&mut self, updated_cert: &ReceivedCert) -> KrillResult<()> {
         self.keys.update_received_cert(updated_cert)
     }
@@ -906,8 +1192,29 @@ impl KeyObjectSet {
         }
     }
 
+    fn add_expiry(&self, objects: &mut Vec<ObjectExpiry>, window_hours: u32) {
+        objects.push(ObjectExpiry::new(
+            self.signing_cert.mft_uri(),
+            self.manifest.expires,
+            window_hours,
+        ));
+        objects.push(ObjectExpiry::new(
+            self.signing_cert.crl_uri(),
+            self.crl.expires,
+            window_hours,
+        ));
+
+        for (name, object) in &self.published_objects {
+            objects.push(ObjectExpiry::new(
+                self.signing_cert.uri_for_name(name),
+                object.expires,
+                window_hours,
+            ));
+        }
+    }
+
     pub fn requires_reissuance(&self, hours: i64) -> bool {
-        Time::now() > self.next_update() - Duration::hours(hours)
+        clock::now() > self.next_update() - Duration::hours(hours)
     }
 
     pub fn next_update(&self) -> Time {
@@ -1046,6 +1353,10 @@ impl KeyObjectSet {
         self.old_repo = Some(repo.clone())
     }
 
+    fn clear_old_repo(&mut self) {
+        self.old_repo = None
+    }
+
     fn old_repo(&self) -> Option<&RepositoryContact> {
         self.old_repo.as_ref()
     }
@@ -1085,14 +1396,14 @@ impl ObjectSetRevision {
     fn create(next_update: Time) -> Self {
         ObjectSetRevision {
             number: 1,
-            this_update: Time::five_minutes_ago(),
+            this_update: clock::five_minutes_ago(),
             next_update,
         }
     }
 
     pub fn next(&mut self, next_update: Time) {
         self.number += 1;
-        self.this_update = Time::five_minutes_ago();
+        self.this_update = clock::five_minutes_ago();
         self.next_update = next_update;
     }
 }
@@ -1287,14 +1598,14 @@ impl ManifestBuilder {
                 entries,
             );
             let mut object_builder = SignedObjectBuilder::new(
-                signer.random_serial()?,
+                signer.next_serial(&aki)?,
                 Validity::new(self.revision.this_update, self.revision.next_update),
                 crl_uri,
                 aia.clone(),
                 mft_uri,
             );
             object_builder.set_issuer(Some(signing_cert.subject().clone()));
-            object_builder.set_signing_time(Some(Time::now()));
+            object_builder.set_signing_time(Some(clock::now()));
 
             signer.sign_manifest(mft_content, object_builder, &aki)?
         };
@@ -1302,3 +1613,93 @@ impl ManifestBuilder {
         Ok(manifest)
     }
 }
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::mpsc, thread, time::Duration};
+
+    use super::*;
+    use crate::{commons::crypto::KrillSignerBuilder, daemon::config::ConfigDefaults, test};
+
+    fn objects_store(d: &Path) -> CaObjectsStore {
+        let signers = ConfigDefaults::signers();
+        let signer = KrillSignerBuilder::new(d, Duration::from_secs(1), &signers)
+            .build()
+            .unwrap();
+        let config = test::test_config(d, false, false, false, false);
+
+        CaObjectsStore::create(
+            &KeyValueStorage::Disk(d.to_path_buf()),
+            config.issuance_timing,
+            2,
+            Arc::new(signer),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn update_other_ca_while_signing() {
+        test::test_under_tmp(|d| {
+            let store = objects_store(&d);
+            let ca1 = CaHandle::from_str("ca1").unwrap();
+            let ca2 = CaHandle::from_str("ca2").unwrap();
+
+            // Keep updating the objects of ca1, as if its objects are being
+            // signed by a slow HSM.
+            let (started_tx, started_rx) = mpsc::channel();
+            let (release_tx, release_rx) = mpsc::channel::<()>();
+            let signing = {
+                let store = store.clone();
+                thread::spawn(move || {
+                    store.with_ca_objects(&ca1, |_| {
+                        started_tx.send(()).unwrap();
+                        release_rx.recv().unwrap();
+                        Ok(())
+                    })
+                })
+            };
+            started_rx.recv().unwrap();
+
+            // The objects of ca2 can be updated and replaced in the meantime.
+            let (done_tx, done_rx) = mpsc::channel();
+            {
+                let store = store.clone();
+                let ca2 = ca2.clone();
+                thread::spawn(move || {
+                    store.with_ca_objects(&ca2, |_| Ok(())).unwrap();
+                    let objects = store.ca_objects(&ca2).unwrap();
+                    store.put_ca_objects(&ca2, &objects).unwrap();
+                    done_tx.send(()).unwrap();
+                });
+            }
+            assert!(done_rx.recv_timeout(Duration::from_secs(10)).is_ok());
+
+            release_tx.send(()).unwrap();
+            signing.join().unwrap().unwrap();
+
+            let mut cas = store.cas().unwrap();
+            cas.sort_by_key(|ca| ca.to_string());
+            assert_eq!(cas, vec![CaHandle::from_str("ca1").unwrap(), ca2]);
+        });
+    }
+
+    #[tokio::test]
+    async fn reissue_all_with_workers() {
+        let d = test::tmp_dir();
+        let store = objects_store(&d);
+        for i in 0..5 {
+            let ca = CaHandle::from_str(&format!("ca{}", i)).unwrap();
+            store.with_ca_objects(&ca, |_| Ok(())).unwrap();
+        }
+
+        let reissued = store.reissue_all(true).await.unwrap();
+
+        let metrics = store.republish_metrics();
+        assert_eq!(metrics.queued(), 0);
+        assert_eq!(metrics.reissued_total(), reissued.len() as u64);
+
+        let _ = std::fs::remove_dir_all(d);
+    }
+}
@@ -1,24 +1,30 @@
+use std::collections::HashMap;
+
 use chrono::Duration;
 use serde::{Deserialize, Serialize};
 
 use rpki::{
     ca::{
-        idexchange::{CaHandle, ParentHandle, RepoInfo},
+        idexchange::{CaHandle, ChildHandle, ParentHandle, RepoInfo},
         provisioning::{
             IssuanceRequest, RequestResourceLimit, ResourceClassEntitlements, ResourceClassName, RevocationRequest,
         },
     },
-    crypto::KeyIdentifier,
+    crypto::{KeyIdentifier, PublicKey},
     repository::{
         cert::Cert,
         resources::ResourceSet,
         x509::{Time, Validity},
     },
+    rrdp::Hash,
 };
 
 use crate::{
     commons::{
-        api::{IssuedCertificate, ReceivedCert, ResourceClassInfo, RoaConfiguration, SuspendedCert, UnsuspendedCert},
+        api::{
+            Divergence, DivergenceObject, IssuedCertificate, KeyRole, ReceivedCert, RepoMigrationStage,
+            ResourceClassInfo, RoaConfiguration, SuspendedCert, UnsuspendedCert,
+        },
         crypto::{CsrInfo, KrillSigner, SignSupport},
         error::Error,
         KrillResult,
@@ -26,8 +32,8 @@ use crate::{
     daemon::{
         ca::events::RoaUpdates,
         ca::{
-            self, AspaObjects, AspaObjectsUpdates, CaEvtDet, CertifiedKey, ChildCertificates, CurrentKey, KeyState,
-            NewKey, OldKey, PendingKey, Roas, Routes,
+            self, AspaObjects, AspaObjectsUpdates, CaEvtDet, CertifiedKey, ChildCertificates, ChildDetails, CurrentKey,
+            KeyState, NewKey, OldKey, PendingKey, Roas, Routes,
         },
         config::{Config, IssuanceTimingConfig},
         ta::ta_handle,
@@ -117,6 +123,10 @@ impl ResourceClass {
 /// # Data Access
 ///
 impl ResourceClass {
+    pub fn name(&self) -> &ResourceClassName {
+        &self.name
+    }
+
     pub fn name_space(&self) -> &str {
         &self.name_space
     }
@@ -141,6 +151,17 @@ impl ResourceClass {
         self.current_key().map(|k| k.incoming_cert())
     }
 
+    /// Returns the certificates for all certified keys in this resource
+    /// class, i.e. the current key and the new or old key during a roll.
+    pub fn received_certificates(&self) -> Vec<&ReceivedCert> {
+        match &self.key_state {
+            KeyState::Pending(_) => vec![],
+            KeyState::Active(current) | KeyState::RollPending(_, current) => vec![current.incoming_cert()],
+            KeyState::RollNew(new, current) => vec![current.incoming_cert(), new.incoming_cert()],
+            KeyState::RollOld(current, old) => vec![current.incoming_cert(), old.incoming_cert()],
+        }
+    }
+
     /// Returns the current resources for this resource class
     pub fn current_resources(&self) -> Option<&ResourceSet> {
         self.current_certificate().map(|c| c.resources())
@@ -557,6 +578,28 @@ impl ResourceClass {
         }
     }
 
+    /// Aborts the key roll, the current key stays active. It stops using the
+    /// repository it was migrating from, if any. A new key which was already
+    /// certified becomes the old key, so that it is revoked and its objects
+    /// are withdrawn from the repository it used when the roll is finished.
+    pub fn key_roll_aborted(&mut self, revoke_req: Option<RevocationRequest>, new_repo: RepoInfo) {
+        match (&self.key_state, revoke_req) {
+            (KeyState::RollPending(_pending, current), _) => {
+                let mut current = current.clone();
+                current.clear_old_repo();
+                self.key_state = KeyState::Active(current);
+            }
+            (KeyState::RollNew(new, current), Some(revoke_req)) => {
+                let mut current = current.clone();
+                current.clear_old_repo();
+                let mut new = new.clone();
+                new.set_old_repo(new_repo);
+                self.key_state = KeyState::RollOld(current, OldKey::new(new, revoke_req));
+            }
+            _ => panic!("Should never create event to abort key roll when there is no pending or new key"),
+        }
+    }
+
     /// Removes the old key, we return the to the state where there is one active key.
     pub fn old_key_removed(&mut self) {
         match &self.key_state {
@@ -587,6 +630,67 @@ impl ResourceClass {
         )
     }
 
+    /// Takes the next step in moving this resource class to the signer used
+    /// for new keys. A key roll is initiated if the current key is held by
+    /// another signer, and the new key of such a roll is activated after the
+    /// staging period. Key rolls which do not move the resource class to the
+    /// signer, e.g. rolls initiated by hand, are left alone.
+    pub fn keyroll_migrate(
+        &self,
+        base_repo: &RepoInfo,
+        staging_time: Duration,
+        issuance_timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<Vec<CaEvtDet>> {
+        let is_target_key = |key_id: &KeyIdentifier| signer.is_default_signer_key(key_id);
+        match signer_migration_step(&self.keys_with_role(), is_target_key) {
+            Some(SignerMigrationStep::InitiateRoll) => self.key_state.keyroll_initiate(
+                self.name.clone(),
+                self.parent_rc_name.clone(),
+                base_repo,
+                &self.name_space,
+                signer,
+            ),
+            Some(SignerMigrationStep::ActivateNewKey) => self.keyroll_activate(staging_time, issuance_timing, signer),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Returns all keys of this resource class, with their role.
+    pub fn keys_with_role(&self) -> Vec<(KeyRole, KeyIdentifier)> {
+        match &self.key_state {
+            KeyState::Pending(pending) => vec![(KeyRole::Pending, *pending.key_id())],
+            KeyState::Active(current) => vec![(KeyRole::Current, *current.key_id())],
+            KeyState::RollPending(pending, current) => vec![
+                (KeyRole::Pending, *pending.key_id()),
+                (KeyRole::Current, *current.key_id()),
+            ],
+            KeyState::RollNew(new, current) => {
+                vec![(KeyRole::New, *new.key_id()), (KeyRole::Current, *current.key_id())]
+            }
+            KeyState::RollOld(current, old) => {
+                vec![(KeyRole::Current, *current.key_id()), (KeyRole::Old, *old.key_id())]
+            }
+        }
+    }
+
+    /// Returns the stage of the migration of this resource class to another
+    /// repository, and the repository it migrates from, if it is migrating.
+    /// Migrations are done by a key roll, where the keys which publish to the
+    /// old repository have the old repository set.
+    pub fn repo_migration_stage(&self) -> Option<(RepoMigrationStage, &RepoInfo)> {
+        match &self.key_state {
+            KeyState::RollPending(_, current) => current
+                .old_repo()
+                .map(|old_repo| (RepoMigrationStage::Requested, old_repo)),
+            KeyState::RollNew(_, current) => current
+                .old_repo()
+                .map(|old_repo| (RepoMigrationStage::Staged, old_repo)),
+            KeyState::RollOld(_, old) => old.old_repo().map(|old_repo| (RepoMigrationStage::Activated, old_repo)),
+            KeyState::Pending(_) | KeyState::Active(_) => None,
+        }
+    }
+
     /// Activate a new key, if it's been longer than the staging period.
     pub fn keyroll_activate(
         &self,
@@ -648,6 +752,12 @@ impl ResourceClass {
         }
     }
 
+    /// Abort a key roll before the new key is activated.
+    pub fn keyroll_abort(&self, signer: &KrillSigner) -> KrillResult<CaEvtDet> {
+        self.key_state
+            .keyroll_abort(self.name.clone(), self.parent_rc_name.clone(), signer)
+    }
+
     /// Finish a key roll, withdraw the old key
     pub fn keyroll_finish(&self) -> KrillResult<CaEvtDet> {
         match &self.key_state {
@@ -886,3 +996,237 @@ impl ResourceClass {
         Ok(ee)
     }
 }
+
+/// # RPKI Signed Checklists (RSC)
+///
+impl ResourceClass {
+    /// Create an EE certificate for the (one-off) key used to sign an RSC.
+    /// Like the EE certificate on an RTA this has no Subject Information
+    /// Access, because an RSC is never published.
+    pub fn create_rsc_ee(
+        &self,
+        resources: &ResourceSet,
+        validity: Validity,
+        pub_key: PublicKey,
+        signer: &KrillSigner,
+    ) -> KrillResult<Cert> {
+        let current = self
+            .current_key()
+            .ok_or_else(|| Error::custom("No current key to sign RSC with"))?;
+
+        if !current.incoming_cert().resources().contains(resources) {
+            return Err(Error::RscResourcesNotHeld);
+        }
+
+        SignSupport::make_rta_ee_cert(resources, current, validity, pub_key, signer)
+    }
+}
+
+/// # Reconciliation
+///
+impl ResourceClass {
+    /// Adds the divergences between the configuration of the CA and the
+    /// objects issued under the current key of this resource class. Nothing
+    /// is issued in a class without a current key, so no divergences are
+    /// reported for it either.
+    pub fn divergences(
+        &self,
+        routes: &Routes,
+        aspa_defs: &AspaDefinitions,
+        children: &HashMap<ChildHandle, ChildDetails>,
+        divergences: &mut Vec<Divergence>,
+    ) {
+        let key = match self.current_key() {
+            Some(key) => key,
+            None => return,
+        };
+        let resources = key.incoming_cert().resources();
+
+        self.roas.divergences(&routes.enabled().filter(resources), divergences);
+        self.aspas.divergences(aspa_defs, resources, divergences);
+
+        let now = Time::now();
+        for cert in self.certificates.current() {
+            let ki = cert.key_identifier();
+            match children.iter().find(|(_, details)| details.is_issued(&ki)) {
+                None => divergences.push(Divergence::orphaned(
+                    DivergenceObject::ChildCertificate,
+                    ki,
+                    "the certificate was issued, but its key is not used by any child",
+                )),
+                Some((child, details)) => {
+                    if !details.resources().intersection(resources).contains(cert.resources()) {
+                        divergences.push(Divergence::stale(
+                            DivergenceObject::ChildCertificate,
+                            child,
+                            format!(
+                                "the certificate for key {} has resources the child is not entitled to",
+                                ki
+                            ),
+                        ));
+                    } else if cert.expires() < now {
+                        divergences.push(Divergence::stale(
+                            DivergenceObject::ChildCertificate,
+                            child,
+                            format!(
+                                "the certificate for key {} expired at {}",
+                                ki,
+                                cert.expires().to_rfc3339()
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+
+        for (child, details) in children {
+            if !details.is_suspended()
+                && details.issued(&self.name).is_empty()
+                && !details.resources().intersection(resources).is_empty()
+            {
+                divergences.push(Divergence::missing(
+                    DivergenceObject::ChildCertificate,
+                    child,
+                    format!(
+                        "the child is entitled to resources in class '{}', but has no certificate",
+                        self.name
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Returns the hashes of all objects issued under the current key of
+    /// this resource class, with a description for reporting.
+    pub fn issued_objects(&self) -> Vec<(Hash, String)> {
+        let mut objects = vec![];
+        objects.extend(
+            self.roas
+                .hashes()
+                .map(|hash| (hash, format!("ROA in class '{}'", self.name))),
+        );
+        objects.extend(
+            self.aspas
+                .hashes()
+                .map(|hash| (hash, format!("ASPA in class '{}'", self.name))),
+        );
+        objects.extend(
+            self.bgpsec_certificates
+                .hashes()
+                .map(|hash| (hash, format!("BGPSec certificate in class '{}'", self.name))),
+        );
+        objects.extend(
+            self.certificates
+                .current()
+                .map(|cert| (cert.hash(), format!("child certificate {}", cert.uri()))),
+        );
+        objects
+    }
+}
+
+//------------ SignerMigrationStep -------------------------------------------
+
+/// The next step in moving a resource class to the signer used for new keys.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum SignerMigrationStep {
+    InitiateRoll,
+    ActivateNewKey,
+}
+
+/// Returns the next step in moving a resource class with the given keys to
+/// the target signer, if any. A roll is initiated only if there is no key
+/// roll in progress, and a new key is only activated if the roll moves the
+/// resource class to the target signer.
+fn signer_migration_step(
+    keys: &[(KeyRole, KeyIdentifier)],
+    is_target_key: impl Fn(&KeyIdentifier) -> bool,
+) -> Option<SignerMigrationStep> {
+    let key = |role: KeyRole| keys.iter().find(|(r, _)| *r == role).map(|(_, key_id)| key_id);
+
+    let current = key(KeyRole::Current)?;
+    if is_target_key(current) {
+        return None;
+    }
+
+    if keys.len() == 1 {
+        Some(SignerMigrationStep::InitiateRoll)
+    } else if key(KeyRole::New).map(&is_target_key).unwrap_or(false) {
+        Some(SignerMigrationStep::ActivateNewKey)
+    } else {
+        None
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signer_migration_steps() {
+        let old_signer_key = KeyIdentifier::from([1; 20]);
+        let other_old_signer_key = KeyIdentifier::from([2; 20]);
+        let target_key = KeyIdentifier::from([3; 20]);
+        let is_target_key = |key_id: &KeyIdentifier| *key_id == target_key;
+
+        // The current key is held by another signer, and no roll is in progress.
+        assert_eq!(
+            signer_migration_step(&[(KeyRole::Current, old_signer_key)], is_target_key),
+            Some(SignerMigrationStep::InitiateRoll)
+        );
+
+        // The new key is held by the target signer.
+        assert_eq!(
+            signer_migration_step(
+                &[(KeyRole::New, target_key), (KeyRole::Current, old_signer_key)],
+                is_target_key
+            ),
+            Some(SignerMigrationStep::ActivateNewKey)
+        );
+
+        // A roll which does not move the keys to the target signer is left
+        // alone, as is a roll which is not yet staged.
+        assert_eq!(
+            signer_migration_step(
+                &[(KeyRole::New, other_old_signer_key), (KeyRole::Current, old_signer_key)],
+                is_target_key
+            ),
+            None
+        );
+        assert_eq!(
+            signer_migration_step(
+                &[(KeyRole::Pending, target_key), (KeyRole::Current, old_signer_key)],
+                is_target_key
+            ),
+            None
+        );
+
+        // The current key is held by the target signer, so there is nothing to
+        // do, even if another roll is in progress.
+        assert_eq!(
+            signer_migration_step(&[(KeyRole::Current, target_key)], is_target_key),
+            None
+        );
+        assert_eq!(
+            signer_migration_step(
+                &[(KeyRole::New, other_old_signer_key), (KeyRole::Current, target_key)],
+                is_target_key
+            ),
+            None
+        );
+        assert_eq!(
+            signer_migration_step(
+                &[(KeyRole::Current, target_key), (KeyRole::Old, old_signer_key)],
+                is_target_key
+            ),
+            None
+        );
+
+        // Nothing can be done before the resource class has a current key.
+        assert_eq!(
+            signer_migration_step(&[(KeyRole::Pending, target_key)], is_target_key),
+            None
+        );
+    }
+}
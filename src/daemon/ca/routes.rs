@@ -1,4 +1,9 @@
-use std::{collections::HashMap, fmt, ops::Deref, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    ops::Deref,
+    str::FromStr,
+};
 
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
@@ -16,9 +21,14 @@ use rpki::{
 
 use crate::{
     commons::{
-        api::{ObjectName, Revocation, RoaAggregateKey, RoaConfiguration, RoaPayload},
+        api::{
+            Divergence, DivergenceObject, ObjectName, Revocation, RoaAggregateKey, RoaConfiguration,
+            RoaIssuanceStrategy, RoaPayload, RoaProposal, RoaProposalList, ScheduledChange, ScheduledChangeList,
+            Timestamp,
+        },
         crypto::KrillSigner,
         error::Error,
+        util::clock,
         KrillResult,
     },
     daemon::{
@@ -118,6 +128,18 @@ impl Routes {
         Routes { map: filtered }
     }
 
+    /// Returns the authorizations which are not disabled, i.e. those for
+    /// which ROA objects should be issued.
+    pub fn enabled(&self) -> Self {
+        let enabled = self
+            .map
+            .iter()
+            .filter(|(_, info)| !info.is_disabled())
+            .map(|(auth, info)| (*auth, info.clone()))
+            .collect();
+        Routes { map: enabled }
+    }
+
     pub fn all(&self) -> impl Iterator<Item = (&RoaPayloadJsonMapKey, &RouteInfo)> {
         self.map.iter()
     }
@@ -125,7 +147,10 @@ impl Routes {
     pub fn roa_configurations(&self) -> Vec<RoaConfiguration> {
         self.map
             .iter()
-            .map(|(payload_key, route_info)| RoaConfiguration::new(payload_key.0, route_info.comment().cloned()))
+            .map(|(payload_key, route_info)| {
+                RoaConfiguration::new(payload_key.0, route_info.comment().cloned())
+                    .with_disabled(route_info.is_disabled())
+            })
             .collect()
     }
 
@@ -152,6 +177,20 @@ impl Routes {
         map
     }
 
+    /// Returns aggregates per ASN with at most `max_prefixes` authorizations
+    /// each. The aggregates for an ASN are numbered from 0, in order of their
+    /// sorted authorizations.
+    pub fn as_grouped_aggregates(&self, max_prefixes: usize) -> HashMap<RoaAggregateKey, Vec<RoaPayloadJsonMapKey>> {
+        let mut map = HashMap::new();
+
+        for (key, authorizations) in self.as_aggregates() {
+            for (nr, chunk) in authorizations.chunks(max_prefixes.max(1)).enumerate() {
+                map.insert(RoaAggregateKey::new(key.asn(), Some(nr as u32)), chunk.to_vec());
+            }
+        }
+        map
+    }
+
     pub fn len(&self) -> usize {
         self.map.len()
     }
@@ -180,6 +219,13 @@ impl Routes {
         }
     }
 
+    /// Disables, or enables, an authorization
+    pub fn disabled(&mut self, auth: &RoaPayloadJsonMapKey, disabled: bool) {
+        if let Some(info) = self.map.get_mut(auth) {
+            info.set_disabled(disabled)
+        }
+    }
+
     /// Removes an authorization
     pub fn remove(&mut self, auth: &RoaPayloadJsonMapKey) -> bool {
         self.map.remove(auth).is_some()
@@ -198,6 +244,10 @@ pub struct RouteInfo {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     group: Option<u32>,
+
+    // No ROA objects are issued for disabled authorizations
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    disabled: bool,
 }
 
 impl RouteInfo {
@@ -213,6 +263,14 @@ impl RouteInfo {
         self.comment = comment;
     }
 
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
     /// The idea was to allow grouping of specific payloads.
     /// But perhaps we should deprecate this as it's not used.
     pub fn group(&self) -> Option<u32> {
@@ -226,10 +284,85 @@ impl Default for RouteInfo {
             since: Time::now(),
             comment: None,
             group: None,
+            disabled: false,
         }
     }
 }
 
+//------------ RoaProposals ------------------------------------------------
+
+/// ROA deltas which were proposed, but not yet approved or rejected.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaProposals {
+    map: BTreeMap<u64, RoaProposal>,
+}
+
+impl RoaProposals {
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&RoaProposal> {
+        self.map.get(&id)
+    }
+
+    pub fn add(&mut self, proposal: RoaProposal) {
+        self.map.insert(proposal.id(), proposal);
+    }
+
+    pub fn remove(&mut self, id: u64) -> bool {
+        self.map.remove(&id).is_some()
+    }
+
+    pub fn list(&self) -> RoaProposalList {
+        RoaProposalList::new(self.map.values().cloned().collect())
+    }
+}
+
+//------------ ScheduledChanges --------------------------------------------
+
+/// ROA and ASPA updates which are applied by the scheduler at a later moment.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScheduledChanges {
+    map: BTreeMap<u64, ScheduledChange>,
+}
+
+impl ScheduledChanges {
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ScheduledChange> {
+        self.map.get(&id)
+    }
+
+    pub fn add(&mut self, change: ScheduledChange) {
+        self.map.insert(change.id(), change);
+    }
+
+    pub fn remove(&mut self, id: u64) -> bool {
+        self.map.remove(&id).is_some()
+    }
+
+    /// Returns the ids of the changes which are due, in order.
+    pub fn due(&self) -> Vec<u64> {
+        self.map
+            .values()
+            .filter(|change| change.is_due())
+            .map(|change| change.id())
+            .collect()
+    }
+
+    /// Returns the moment the first pending change is due, if any.
+    pub fn next_activation(&self) -> Option<Timestamp> {
+        self.map.values().map(|change| change.activate_at()).min()
+    }
+
+    pub fn list(&self) -> ScheduledChangeList {
+        ScheduledChangeList::new(self.map.values().cloned().collect())
+    }
+}
+
 //------------ RoaInfo -----------------------------------------------------
 
 /// This type defines information about a ROA *object*
@@ -348,6 +481,54 @@ impl Roas {
         }
     }
 
+    /// Returns the hashes of all current ROA objects.
+    pub fn hashes(&self) -> impl Iterator<Item = Hash> + '_ {
+        self.simple
+            .values()
+            .chain(self.aggregate.values())
+            .map(|info| info.hash())
+    }
+
+    /// Adds the divergences between the given routes, which are expected
+    /// to be the enabled routes held by the owning resource class, and the
+    /// ROAs which were issued.
+    pub fn divergences(&self, routes: &Routes, divergences: &mut Vec<Divergence>) {
+        let now = Time::now();
+        let mut issued = HashSet::new();
+
+        for info in self.simple.values().chain(self.aggregate.values()) {
+            for auth in info.authorizations() {
+                issued.insert(*auth);
+                if !routes.has(auth) {
+                    divergences.push(Divergence::orphaned(
+                        DivergenceObject::Roa,
+                        auth,
+                        "a ROA was issued, but the authorization is not configured or not held",
+                    ));
+                }
+            }
+
+            if info.expires() < now {
+                let authorizations: Vec<String> = info.authorizations().iter().map(|auth| auth.to_string()).collect();
+                divergences.push(Divergence::stale(
+                    DivergenceObject::Roa,
+                    authorizations.join(", "),
+                    format!("the ROA expired at {}", info.expires().to_rfc3339()),
+                ));
+            }
+        }
+
+        for auth in routes.roa_payload_keys() {
+            if !issued.contains(auth) {
+                divergences.push(Divergence::missing(
+                    DivergenceObject::Roa,
+                    auth,
+                    "the authorization is configured, but no ROA was issued",
+                ));
+            }
+        }
+    }
+
     /// Returns all the current RoaInfos matching the given config
     pub fn matching_roa_infos(&self, config: &RoaConfiguration) -> Vec<RoaInfo> {
         let payload = RoaPayloadJsonMapKey::from(config.payload().into_explicit_max_length());
@@ -371,10 +552,11 @@ impl Roas {
     }
 
     /// Returns whether ROAs are currently being aggregated. I.e. whether
-    /// there are any aggregated ROAs for which no explicit group number
-    /// was set.
+    /// there are any aggregated ROAs. This includes grouped ROAs that were
+    /// issued under the 'grouped' ROA issuance strategy, so that these are
+    /// replaced when switching back to the automatic strategy.
     fn is_currently_aggregating(&self) -> bool {
-        self.aggregate.keys().any(|k| k.group().is_none())
+        !self.aggregate.is_empty()
     }
 
     /// Returns the desired RoaMode based on the current situation, and
@@ -463,17 +645,18 @@ impl Roas {
         Ok(roa_updates)
     }
 
-    /// Process authorization updates that triggered aggregating ROAs
+    /// Process authorization updates that triggered aggregating ROAs into the
+    /// desired aggregates. Any other aggregates are removed as well.
     fn update_start_aggregating(
         &self,
-        relevant_routes: &Routes,
+        desired_aggregates: HashMap<RoaAggregateKey, Vec<RoaPayloadJsonMapKey>>,
         certified_key: &CertifiedKey,
         issuance_timing: &IssuanceTimingConfig,
         signer: &KrillSigner,
     ) -> KrillResult<RoaUpdates> {
         // First trigger the aggregate update, this will make sure that all current routes
         // are added as aggregate ROAs
-        let mut roa_updates = self.update_aggregate(relevant_routes, certified_key, issuance_timing, signer)?;
+        let mut roa_updates = self.update_aggregate(desired_aggregates, certified_key, issuance_timing, signer)?;
 
         // Then remove all simple ROAs
         for roa_key in self.simple.keys() {
@@ -484,18 +667,17 @@ impl Roas {
         Ok(roa_updates)
     }
 
-    /// Process authorization updates in aggregation mode
+    /// Process authorization updates in aggregation mode, using the given
+    /// desired aggregates.
     fn update_aggregate(
         &self,
-        relevant_routes: &Routes,
+        desired_aggregates: HashMap<RoaAggregateKey, Vec<RoaPayloadJsonMapKey>>,
         certified_key: &CertifiedKey,
         issuance_timing: &IssuanceTimingConfig,
         signer: &KrillSigner,
     ) -> KrillResult<RoaUpdates> {
         let mut roa_updates = RoaUpdates::default();
 
-        let desired_aggregates = relevant_routes.as_aggregates();
-
         debug!("Will create '{}' aggregates", desired_aggregates.len());
 
         // Add new ROAs, and update ROAs with changed authorizations
@@ -531,6 +713,10 @@ impl Roas {
 
     /// Process updates, return [`RoaUpdates`] and create new ROA objects if
     /// authorizations change, or if ROAs are about to expire.
+    ///
+    /// The ROA issuance strategy in the config determines how prefixes are
+    /// combined into ROAs. If the strategy was changed, then ROAs issued
+    /// under the previous strategy are replaced.
     pub fn update(
         &self,
         all_routes: &Routes,
@@ -538,23 +724,43 @@ impl Roas {
         config: &Config,
         signer: &KrillSigner,
     ) -> KrillResult<RoaUpdates> {
-        let relevant_routes = all_routes.filter(certified_key.incoming_cert().resources());
-
-        match self.mode(
-            relevant_routes.len(),
-            config.roa_deaggregate_threshold,
-            config.roa_aggregate_threshold,
-        ) {
-            RoaMode::Simple => self.update_simple(&relevant_routes, certified_key, &config.issuance_timing, signer),
-            RoaMode::StopAggregating => {
-                self.update_stop_aggregating(&relevant_routes, certified_key, &config.issuance_timing, signer)
-            }
-            RoaMode::StartAggregating => {
-                self.update_start_aggregating(&relevant_routes, certified_key, &config.issuance_timing, signer)
+        // Disabled authorizations are kept in the configuration, but no ROAs
+        // are issued for them.
+        let relevant_routes = all_routes.enabled().filter(certified_key.incoming_cert().resources());
+        let issuance_timing = &config.issuance_timing;
+
+        match config.roa_issuance_strategy {
+            RoaIssuanceStrategy::Auto => match self.mode(
+                relevant_routes.len(),
+                config.roa_deaggregate_threshold,
+                config.roa_aggregate_threshold,
+            ) {
+                RoaMode::Simple => self.update_simple(&relevant_routes, certified_key, issuance_timing, signer),
+                RoaMode::StopAggregating => {
+                    self.update_stop_aggregating(&relevant_routes, certified_key, issuance_timing, signer)
+                }
+                RoaMode::StartAggregating => self.update_start_aggregating(
+                    relevant_routes.as_aggregates(),
+                    certified_key,
+                    issuance_timing,
+                    signer,
+                ),
+                RoaMode::Aggregate => {
+                    self.update_aggregate(relevant_routes.as_aggregates(), certified_key, issuance_timing, signer)
+                }
+            },
+            RoaIssuanceStrategy::PerPrefix => {
+                self.update_stop_aggregating(&relevant_routes, certified_key, issuance_timing, signer)
             }
-            RoaMode::Aggregate => {
-                self.update_aggregate(&relevant_routes, certified_key, &config.issuance_timing, signer)
+            RoaIssuanceStrategy::PerAsn => {
+                self.update_start_aggregating(relevant_routes.as_aggregates(), certified_key, issuance_timing, signer)
             }
+            RoaIssuanceStrategy::Grouped { max_prefixes } => self.update_start_aggregating(
+                relevant_routes.as_grouped_aggregates(max_prefixes),
+                certified_key,
+                issuance_timing,
+                signer,
+            ),
         }
     }
 
@@ -574,7 +780,7 @@ impl Roas {
 
         for (auth, roa_info) in self.simple.iter() {
             let name = ObjectName::from(auth);
-            if force || roa_info.expires() < renew_threshold {
+            if force || issuance_timing.renew_due(&name, roa_info.expires(), renew_threshold) {
                 let authorizations = vec![*auth];
                 let roa = Self::make_roa(
                     &authorizations,
@@ -589,9 +795,9 @@ impl Roas {
         }
 
         for (roa_key, roa_info) in self.aggregate.iter() {
-            if force || roa_info.expires() < renew_threshold {
+            let name = ObjectName::from(roa_key);
+            if force || issuance_timing.renew_due(&name, roa_info.expires(), renew_threshold) {
                 let authorizations = roa_info.authorizations().clone();
-                let name = ObjectName::from(roa_key);
                 let new_roa = Self::make_roa(
                     authorizations.as_slice(),
                     &name,
@@ -641,10 +847,15 @@ impl Roas {
             }
         }
 
-        let mut object_builder =
-            SignedObjectBuilder::new(signer.random_serial()?, validity, crl_uri, aia.clone(), roa_uri);
+        let mut object_builder = SignedObjectBuilder::new(
+            signer.next_serial(signing_key)?,
+            validity,
+            crl_uri,
+            aia.clone(),
+            roa_uri,
+        );
         object_builder.set_issuer(Some(incoming_cert.subject().clone()));
-        object_builder.set_signing_time(Some(Time::now()));
+        object_builder.set_signing_time(Some(clock::now()));
 
         Ok(signer.sign_roa(roa_builder, object_builder, signing_key)?)
     }
@@ -729,4 +940,39 @@ mod tests {
 
         assert_eq!(agg_2, &vec![auth2_1])
     }
+
+    #[test]
+    fn routes_as_grouped_aggregates() {
+        let mut routes = Routes::default();
+        let auth1_1 = authorization("192.168.0.0/16 => 64496");
+        let auth1_2 = authorization("192.168.0.0/16-24 => 64496");
+        let auth1_3 = authorization("2001:db8::/32 => 64496");
+        let auth2_1 = authorization("2001:db8::/32-48 => 64497");
+        routes.add(auth1_1);
+        routes.add(auth1_2);
+        routes.add(auth1_3);
+        routes.add(auth2_1);
+
+        let aggregates = routes.as_grouped_aggregates(2);
+
+        assert_eq!(3, aggregates.keys().len());
+
+        let mut agg_1_expected = vec![auth1_1, auth1_2, auth1_3];
+        agg_1_expected.sort();
+
+        let agg_1_0 = aggregates
+            .get(&RoaAggregateKey::new(AsNumber::new(64496), Some(0)))
+            .unwrap();
+        assert_eq!(agg_1_0.as_slice(), &agg_1_expected[0..2]);
+
+        let agg_1_1 = aggregates
+            .get(&RoaAggregateKey::new(AsNumber::new(64496), Some(1)))
+            .unwrap();
+        assert_eq!(agg_1_1.as_slice(), &agg_1_expected[2..]);
+
+        let agg_2_0 = aggregates
+            .get(&RoaAggregateKey::new(AsNumber::new(64497), Some(0)))
+            .unwrap();
+        assert_eq!(agg_2_0, &vec![auth2_1])
+    }
 }
@@ -0,0 +1,336 @@
+//! RPKI Signed Checklists (RSC)
+//!
+//! An RSC lets a CA sign a list of file hashes, e.g. for a geofeed file or
+//! contact details, using the resources it holds. RSC objects are handed out
+//! to the user for distribution alongside the files; they are not published
+//! in the RPKI repository.
+//!
+//! See: https://www.rfc-editor.org/rfc/rfc9323.html
+
+use std::fmt;
+
+use bcder::{
+    encode, encode::PrimitiveContent, encode::Values, Captured, ConstOid, Ia5String, Mode, OctetString, Oid, Tag,
+};
+use bytes::Bytes;
+
+use rpki::{
+    ca::publication::Base64,
+    crypto::DigestAlgorithm,
+    oid,
+    repository::{
+        resources::{AddressFamily, ResourceSet},
+        x509::Validity,
+    },
+};
+
+use crate::{
+    commons::{crypto::KrillSigner, error::Error, util::ext_serde, KrillResult},
+    daemon::ca::ResourceClass,
+};
+
+/// The content type of an RSC: id-ct-signedChecklist (1.2.840.113549.1.9.16.1.48)
+pub const CT_RPKI_SIGNED_CHECKLIST: ConstOid = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 48]);
+
+//------------ RscFileHash --------------------------------------------------
+
+/// A single entry on the checklist: the SHA-256 hash of a file, and
+/// optionally the name of that file.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RscFileHash {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    file_name: Option<String>,
+    #[serde(
+        deserialize_with = "ext_serde::de_hex_bytes",
+        serialize_with = "ext_serde::ser_hex_bytes"
+    )]
+    hash: Bytes,
+}
+
+impl RscFileHash {
+    pub fn new(file_name: Option<String>, hash: Bytes) -> Self {
+        RscFileHash { file_name, hash }
+    }
+
+    /// Hashes the given content using the (only) digest algorithm allowed
+    /// for RSC: SHA-256.
+    pub fn for_content(file_name: Option<String>, content: &[u8]) -> Self {
+        let digest = DigestAlgorithm::default().digest(content);
+        RscFileHash::new(file_name, Bytes::copy_from_slice(digest.as_ref()))
+    }
+
+    pub fn file_name(&self) -> Option<&String> {
+        self.file_name.as_ref()
+    }
+
+    pub fn hash(&self) -> &Bytes {
+        &self.hash
+    }
+
+    /// Returns the file name as an IA5String, verifying that it only uses
+    /// the POSIX portable filename character set as required by RFC 9323.
+    fn portable_file_name(&self) -> KrillResult<Option<Ia5String>> {
+        match &self.file_name {
+            None => Ok(None),
+            Some(name) => {
+                let portable = !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-');
+                if !portable {
+                    return Err(Error::custom(format!(
+                        "Invalid file name '{}' for RSC, only a-z, A-Z, 0-9, '.', '_' and '-' are allowed",
+                        name
+                    )));
+                }
+                Ia5String::from_string(name.clone())
+                    .map(Some)
+                    .map_err(|_| Error::custom(format!("Invalid file name '{}' for RSC", name)))
+            }
+        }
+    }
+}
+
+impl fmt::Display for RscFileHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file_name {
+            Some(name) => write!(f, "{} {}", hex::encode(&self.hash), name),
+            None => write!(f, "{}", hex::encode(&self.hash)),
+        }
+    }
+}
+
+//------------ RscContentRequest --------------------------------------------
+
+/// Request to sign a checklist of file hashes over the given resources.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RscContentRequest {
+    resources: ResourceSet,
+    validity: Validity,
+    checklist: Vec<RscFileHash>,
+}
+
+impl RscContentRequest {
+    pub fn new(resources: ResourceSet, validity: Validity, checklist: Vec<RscFileHash>) -> Self {
+        RscContentRequest {
+            resources,
+            validity,
+            checklist,
+        }
+    }
+
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    pub fn validity(&self) -> Validity {
+        self.validity
+    }
+
+    pub fn checklist(&self) -> &[RscFileHash] {
+        &self.checklist
+    }
+
+    /// Encodes the RpkiSignedChecklist eContent:
+    ///
+    /// ```text
+    /// RpkiSignedChecklist ::= SEQUENCE {
+    ///   version [0] INTEGER DEFAULT 0,
+    ///   resources ResourceBlock,
+    ///   digestAlgorithm DigestAlgorithmIdentifier,
+    ///   checkList SEQUENCE (SIZE(1..MAX)) OF FileNameAndHash }
+    /// ```
+    fn encode_content(&self) -> KrillResult<Bytes> {
+        if self.resources.is_empty() {
+            return Err(Error::custom("An RSC must include at least one resource"));
+        }
+        if self.checklist.is_empty() {
+            return Err(Error::custom("An RSC must include at least one file hash"));
+        }
+
+        let mut checklist = vec![];
+        for item in &self.checklist {
+            if item.hash.len() != 32 {
+                return Err(Error::custom(format!(
+                    "Invalid hash '{}' for RSC, expected a SHA-256 hash",
+                    hex::encode(&item.hash)
+                )));
+            }
+            checklist.push((item.portable_file_name()?, item.hash.clone()));
+        }
+
+        let asns = self.resources.asn();
+        let ipv4 = self.resources.ipv4();
+        let ipv6 = self.resources.ipv6();
+
+        // The ASN.1 module uses explicit tags. Empty parts must be left out.
+        let as_id = if asns.is_empty() {
+            None
+        } else {
+            Some(encode::sequence_as(
+                Tag::CTX_0,
+                encode::sequence(encode::sequence_as(Tag::CTX_0, encode::sequence(asns.encode_ref()))),
+            ))
+        };
+
+        let ip_addr_blocks = if ipv4.is_empty() && ipv6.is_empty() {
+            None
+        } else {
+            Some(encode::sequence_as(
+                Tag::CTX_1,
+                encode::sequence((
+                    (!ipv4.is_empty()).then(|| ipv4.encode_family(AddressFamily::Ipv4)),
+                    (!ipv6.is_empty()).then(|| ipv6.encode_family(AddressFamily::Ipv6)),
+                )),
+            ))
+        };
+
+        let content = encode::sequence((
+            // version is DEFAULT
+            encode::sequence((as_id, ip_addr_blocks)),
+            DigestAlgorithm::default().encode(),
+            encode::sequence(encode::iter(checklist.iter().map(|(name, hash)| {
+                encode::sequence((
+                    name.as_ref().map(|name| name.encode_ref()),
+                    OctetString::encode_slice(hash),
+                ))
+            }))),
+        ));
+
+        Ok(content.to_captured(Mode::Der).into_bytes())
+    }
+}
+
+impl fmt::Display for RscContentRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "resources: {}", &self.resources)?;
+        writeln!(
+            f,
+            "validity, {}-{}",
+            self.validity.not_before().to_rfc3339(),
+            self.validity.not_after().to_rfc3339()
+        )?;
+        writeln!(f, "checklist:")?;
+        for item in &self.checklist {
+            writeln!(f, "  {}", item)?;
+        }
+
+        Ok(())
+    }
+}
+
+//------------ RpkiSignedChecklist ------------------------------------------
+
+/// A DER encoded RPKI Signed Checklist.
+#[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
+pub struct RpkiSignedChecklist {
+    #[serde(deserialize_with = "ext_serde::de_bytes", serialize_with = "ext_serde::ser_bytes")]
+    bytes: Bytes,
+}
+
+impl AsRef<Bytes> for RpkiSignedChecklist {
+    fn as_ref(&self) -> &Bytes {
+        &self.bytes
+    }
+}
+
+impl RpkiSignedChecklist {
+    pub fn new(bytes: Bytes) -> Self {
+        RpkiSignedChecklist { bytes }
+    }
+
+    /// Signs the requested checklist under the current key of the given
+    /// resource class.
+    ///
+    /// This follows RFC 6488 for the CMS structure, except that the EE
+    /// certificate has no Subject Information Access as required by
+    /// RFC 9323. The EE key is a one-off key, so that it cannot be used
+    /// for anything else.
+    pub fn create(request: &RscContentRequest, rc: &ResourceClass, signer: &KrillSigner) -> KrillResult<Self> {
+        let content = request.encode_content()?;
+
+        let digest_algorithm = DigestAlgorithm::default();
+        let message_digest = digest_algorithm.digest(&content);
+        let signed_attrs = Self::encode_signed_attrs(message_digest.as_ref());
+
+        let (signature, pub_key) = signer
+            .sign_one_off(&Self::signed_attrs_for_signing(&signed_attrs))
+            .map_err(Error::signer)?;
+        let sid = pub_key.key_identifier();
+
+        let ee = rc.create_rsc_ee(request.resources(), request.validity(), pub_key, signer)?;
+
+        let signed_data = encode::sequence((
+            oid::SIGNED_DATA.encode(), // contentType
+            encode::sequence_as(
+                Tag::CTX_0, // content
+                encode::sequence((
+                    3u8.encode(),                  // version
+                    digest_algorithm.encode_set(), // digestAlgorithms
+                    encode::sequence((
+                        // encapContentInfo
+                        CT_RPKI_SIGNED_CHECKLIST.encode(),
+                        encode::sequence_as(Tag::CTX_0, OctetString::encode_slice(content.as_ref())),
+                    )),
+                    encode::sequence_as(Tag::CTX_0, ee.encode_ref()), // certificates
+                    // crls -- omitted
+                    encode::set(encode::sequence((
+                        // SignerInfo
+                        3u8.encode(), // version
+                        OctetString::encode_slice_as(sid.as_slice(), Tag::CTX_0),
+                        digest_algorithm.encode(),
+                        encode::sequence_as(Tag::CTX_0, &signed_attrs),
+                        signature.algorithm().cms_encode(),
+                        OctetString::encode_slice(signature.value().as_ref()),
+                        // unsignedAttrs -- omitted
+                    ))),
+                )),
+            ),
+        ));
+
+        Ok(RpkiSignedChecklist {
+            bytes: signed_data.to_captured(Mode::Der).into_bytes(),
+        })
+    }
+
+    /// Encodes the content of the signed attributes SET: the content-type
+    /// and message-digest attributes, in DER order.
+    fn encode_signed_attrs(message_digest: &[u8]) -> Captured {
+        let content_type = Captured::from_values(
+            Mode::Der,
+            encode::sequence((
+                oid::CONTENT_TYPE.encode(),
+                encode::set(CT_RPKI_SIGNED_CHECKLIST.encode()),
+            )),
+        );
+
+        let message_digest = Captured::from_values(
+            Mode::Der,
+            encode::sequence((
+                oid::MESSAGE_DIGEST.encode(),
+                encode::set(OctetString::encode_slice(message_digest)),
+            )),
+        );
+
+        let mut attrs = vec![content_type, message_digest];
+        attrs.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+
+        let mut res = Captured::builder(Mode::Der);
+        for attr in attrs {
+            res.extend(attr);
+        }
+        res.freeze()
+    }
+
+    /// The signature is calculated over the signed attributes encoded as a
+    /// SET OF, rather than with the implicit [0] tag used in the SignerInfo.
+    fn signed_attrs_for_signing(signed_attrs: &Captured) -> Bytes {
+        encode::set(signed_attrs).to_captured(Mode::Der).into_bytes()
+    }
+}
+
+impl fmt::Display for RpkiSignedChecklist {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Base64::from_content(self.bytes.as_ref()))
+    }
+}
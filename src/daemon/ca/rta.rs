@@ -51,6 +51,18 @@ impl Rtas {
         }
     }
 
+    /// Returns the one-off keys of the RTAs which are prepared, but not yet
+    /// signed.
+    pub fn prepared_keys(&self) -> Vec<KeyIdentifier> {
+        self.map
+            .values()
+            .flat_map(|state| match state {
+                RtaState::Prepared(prepared) => prepared.keys(),
+                RtaState::Signed(_) => vec![],
+            })
+            .collect()
+    }
+
     pub fn add_prepared(&mut self, name: RtaName, prepared: PreparedRta) {
         self.map.insert(name, RtaState::Prepared(prepared));
     }
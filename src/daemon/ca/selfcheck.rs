@@ -0,0 +1,625 @@
+//! Check the objects which a CA published in its repository, as they are
+//! seen by relying parties.
+//!
+//! The objects are fetched using RRDP and (optionally) rsync, and then
+//! compared to the objects which the CA intends to publish. They are also
+//! checked top-down starting from the certificates received by the CA: the
+//! manifest and CRL for each key, and all objects listed on the manifest
+//! are decoded, and their signatures, validity and hashes are verified.
+//!
+//! Note that the CA certificates themselves are taken as given. Validating
+//! them would require the full chain up to a trust anchor, which is not
+//! known to the CA.
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    process::Command,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+
+use rpki::{
+    repository::{
+        aspa::Aspa,
+        cert::Cert,
+        crl::Crl,
+        manifest::Manifest,
+        roa::Roa,
+        sigobj::SignedObject,
+        x509::{Serial, Time},
+    },
+    rrdp::{self, Delta, DeltaElement, NotificationFile, Snapshot},
+    uri,
+};
+use uuid::Uuid;
+
+use crate::commons::{
+    api::{
+        rrdp::PublishElement, PublicationCheckIssue, PublicationCheckProtocol, PublicationCheckSource, ReceivedCert,
+    },
+    util::{file, httpclient},
+};
+
+/// The objects fetched from a repository, by their rsync URI.
+pub type FetchedObjects = HashMap<uri::Rsync, Bytes>;
+
+/// A repository which was fetched this recently is not fetched again when
+/// the next CA confirms its objects.
+const RRDP_RECHECK_SECONDS: u64 = 10;
+
+/// Fetches all objects from the RRDP snapshot listed in the notification
+/// file at the given URI.
+pub async fn fetch_rrdp(notify: &uri::Https) -> Result<FetchedObjects, String> {
+    let notification = fetch_notification(notify).await?;
+    fetch_snapshot(&notification).await
+}
+
+async fn fetch_notification(notify: &uri::Https) -> Result<NotificationFile, String> {
+    let notification_xml = httpclient::get_text(notify.as_str(), None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    NotificationFile::parse(notification_xml.as_bytes()).map_err(|e| format!("cannot parse notification file: {}", e))
+}
+
+/// Fetches all objects from the snapshot listed in the notification file.
+async fn fetch_snapshot(notification: &NotificationFile) -> Result<FetchedObjects, String> {
+    let snapshot_uri = notification.snapshot().uri();
+    let snapshot_xml = httpclient::get_text(snapshot_uri.as_str(), None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if rrdp::Hash::from_data(snapshot_xml.as_bytes()) != notification.snapshot().hash() {
+        return Err(format!(
+            "hash of snapshot at '{}' does not match notification file",
+            snapshot_uri
+        ));
+    }
+
+    let snapshot = Snapshot::parse(snapshot_xml.as_bytes())
+        .map_err(|e| format!("cannot parse snapshot at '{}': {}", snapshot_uri, e))?;
+
+    if snapshot.session_id() != notification.session_id() || snapshot.serial() != notification.serial() {
+        return Err(format!(
+            "session or serial of snapshot at '{}' does not match notification file",
+            snapshot_uri
+        ));
+    }
+
+    Ok(snapshot.into_elements().into_iter().map(|el| el.unpack()).collect())
+}
+
+//------------ RrdpFollower --------------------------------------------------
+
+/// The objects in an RRDP repository, as of a session and serial.
+#[derive(Clone)]
+struct RrdpState {
+    session: Uuid,
+    serial: u64,
+    objects: Arc<FetchedObjects>,
+    checked: Instant,
+}
+
+/// Follows the RRDP repositories where CAs publish, to confirm that their
+/// objects can be fetched back. The state of a repository is shared by all
+/// CAs publishing there, and it is updated using the deltas since it was
+/// last fetched. The snapshot is only fetched when a repository is first
+/// followed, or when the deltas do not suffice, e.g. because the session
+/// changed. CAs which confirm their objects at the same time wait for one
+/// fetch, and use its result.
+#[derive(Default)]
+pub struct RrdpFollower {
+    repos: Mutex<HashMap<uri::Https, Arc<tokio::sync::Mutex<Option<RrdpState>>>>>,
+}
+
+impl RrdpFollower {
+    /// Returns the current serial and objects of the repository with the
+    /// given notification file.
+    pub async fn fetch(&self, notify: &uri::Https) -> Result<(u64, Arc<FetchedObjects>), String> {
+        let repo = self.repos.lock().unwrap().entry(notify.clone()).or_default().clone();
+        let mut state = repo.lock().await;
+
+        if let Some(state) = state.as_ref() {
+            if state.checked.elapsed() < Duration::from_secs(RRDP_RECHECK_SECONDS) {
+                return Ok((state.serial, state.objects.clone()));
+            }
+        }
+
+        let notification = fetch_notification(notify).await?;
+
+        let updated = match state.take() {
+            Some(current) if current.session == notification.session_id() => {
+                match Self::apply_deltas(current, &notification).await {
+                    Ok(updated) => Some(updated),
+                    Err(e) => {
+                        debug!("Fetching snapshot for '{}', deltas could not be used: {}", notify, e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let updated = match updated {
+            Some(updated) => updated,
+            None => RrdpState {
+                session: notification.session_id(),
+                serial: notification.serial(),
+                objects: Arc::new(fetch_snapshot(&notification).await?),
+                checked: Instant::now(),
+            },
+        };
+
+        let res = (updated.serial, updated.objects.clone());
+        *state = Some(updated);
+        Ok(res)
+    }
+
+    /// Brings the state up to the serial of the notification file using
+    /// its deltas.
+    async fn apply_deltas(mut state: RrdpState, notification: &NotificationFile) -> Result<RrdpState, String> {
+        if notification.serial() < state.serial {
+            return Err(format!(
+                "serial went back from {} to {}",
+                state.serial,
+                notification.serial()
+            ));
+        }
+
+        while state.serial < notification.serial() {
+            let serial = state.serial + 1;
+            let delta = notification
+                .deltas()
+                .iter()
+                .find(|delta| delta.serial() == serial)
+                .ok_or_else(|| format!("no delta for serial {}", serial))?;
+
+            let xml = httpclient::get_bytes(delta.uri().as_str())
+                .await
+                .map_err(|e| e.to_string())?;
+            if !delta.hash().matches(xml.as_ref()) {
+                return Err(format!(
+                    "hash of delta at '{}' does not match notification file",
+                    delta.uri()
+                ));
+            }
+
+            apply_delta(Arc::make_mut(&mut state.objects), state.session, serial, xml.as_ref())?;
+            state.serial = serial;
+        }
+
+        state.checked = Instant::now();
+        Ok(state)
+    }
+}
+
+/// Applies an RRDP delta with the given session and serial to the objects.
+fn apply_delta(objects: &mut FetchedObjects, session: Uuid, serial: u64, xml: &[u8]) -> Result<(), String> {
+    let delta = Delta::parse(xml).map_err(|e| format!("cannot parse delta: {}", e))?;
+    if delta.session_id() != session || delta.serial() != serial {
+        return Err(format!(
+            "delta has session {} and serial {}, expected {} and {}",
+            delta.session_id(),
+            delta.serial(),
+            session,
+            serial
+        ));
+    }
+
+    let current_hash =
+        |objects: &FetchedObjects, uri: &uri::Rsync| objects.get(uri).map(|bytes| rrdp::Hash::from_data(bytes));
+
+    for element in delta.into_elements() {
+        match element {
+            DeltaElement::Publish(publish) => {
+                let (uri, data) = publish.unpack();
+                if objects.contains_key(&uri) {
+                    return Err(format!("delta publishes existing object '{}'", uri));
+                }
+                objects.insert(uri, data);
+            }
+            DeltaElement::Update(update) => {
+                let (uri, hash, data) = update.unpack();
+                if current_hash(objects, &uri) != Some(hash) {
+                    return Err(format!("delta updates unknown object '{}'", uri));
+                }
+                objects.insert(uri, data);
+            }
+            DeltaElement::Withdraw(withdraw) => {
+                let (uri, hash) = withdraw.unpack();
+                if current_hash(objects, &uri) != Some(hash) {
+                    return Err(format!("delta withdraws unknown object '{}'", uri));
+                }
+                objects.remove(&uri);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Confirms that all objects which the CA published were fetched, with the
+/// right hashes. Other objects are not considered, they may belong to other
+/// CAs or be withdrawn in a later update. Returns a description of the
+/// objects which could not be confirmed otherwise.
+pub fn confirm_published(intended: &[PublishElement], fetched: &FetchedObjects) -> Result<(), String> {
+    let mut missing = 0;
+    let mut differ = 0;
+    for element in intended {
+        match fetched.get(element.uri()) {
+            None => missing += 1,
+            Some(bytes) => {
+                if rrdp::Hash::from_data(bytes) != element.base64().to_hash() {
+                    differ += 1;
+                }
+            }
+        }
+    }
+
+    if missing == 0 && differ == 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} of {} published objects are missing and {} have a different hash",
+            missing,
+            intended.len(),
+            differ
+        ))
+    }
+}
+
+/// Fetches all objects from the given rsync directory, using the rsync
+/// command. The objects are copied into the given working directory which
+/// is cleared first.
+pub fn fetch_rsync(dir: &uri::Rsync, work_dir: &Path) -> Result<FetchedObjects, String> {
+    if work_dir.exists() {
+        file::remove_dir_all(work_dir).map_err(|e| e.to_string())?;
+    }
+    file::create_dir_all(work_dir).map_err(|e| e.to_string())?;
+
+    let output = Command::new("rsync")
+        .arg("-rt")
+        .arg("--delete")
+        .arg(dir.as_str())
+        .arg(work_dir)
+        .output()
+        .map_err(|e| format!("cannot run rsync: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "rsync failed for '{}': {}",
+            dir,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let files = file::crawl_incl_rsync_base(work_dir, dir).map_err(|e| e.to_string())?;
+    Ok(files.into_iter().map(|f| (f.uri().clone(), f.to_bytes())).collect())
+}
+
+/// Checks the objects fetched using a protocol from the given uri, or
+/// reports the failure to fetch them.
+pub fn check_source(
+    protocol: PublicationCheckProtocol,
+    uri: String,
+    fetched: Result<FetchedObjects, String>,
+    certs: &[&ReceivedCert],
+    intended: &[PublishElement],
+) -> PublicationCheckSource {
+    match fetched {
+        Err(e) => PublicationCheckSource::new(protocol, uri, 0, vec![PublicationCheckIssue::new(None, e)]),
+        Ok(mut fetched) => {
+            // Other objects in the repository belong to other CAs.
+            let dirs: HashSet<&uri::Rsync> = certs.iter().map(|cert| cert.ca_repository()).collect();
+            fetched.retain(|uri, _| dirs.iter().any(|dir| uri.as_str().starts_with(dir.as_str())));
+
+            let issues = check_objects(certs, intended, &fetched);
+            PublicationCheckSource::new(protocol, uri, fetched.len(), issues)
+        }
+    }
+}
+
+/// Checks the fetched objects against the objects which the CA intends to
+/// publish, and verifies them top-down starting from the given certificates.
+fn check_objects(
+    certs: &[&ReceivedCert],
+    intended: &[PublishElement],
+    fetched: &FetchedObjects,
+) -> Vec<PublicationCheckIssue> {
+    let mut issues = vec![];
+
+    // Compare with the intended state.
+    let mut intended_uris = HashSet::new();
+    for element in intended {
+        intended_uris.insert(element.uri());
+        match fetched.get(element.uri()) {
+            None => issues.push(PublicationCheckIssue::for_object(element.uri(), "object is missing")),
+            Some(bytes) => {
+                if bytes != &element.base64().to_bytes() {
+                    issues.push(PublicationCheckIssue::for_object(
+                        element.uri(),
+                        "object differs from the object published by the CA",
+                    ));
+                }
+            }
+        }
+    }
+
+    for uri in fetched.keys() {
+        if !intended_uris.contains(uri) {
+            issues.push(PublicationCheckIssue::for_object(
+                uri,
+                "unexpected object, not published by the CA",
+            ));
+        }
+    }
+
+    // Verify the objects top-down for each key.
+    let mut listed = HashSet::new();
+    for cert in certs {
+        listed.insert(cert.mft_uri());
+        check_key_objects(cert, fetched, &mut listed, &mut issues);
+    }
+
+    for uri in fetched.keys() {
+        if !listed.contains(uri) {
+            issues.push(PublicationCheckIssue::for_object(
+                uri,
+                "object is not listed on a manifest",
+            ));
+        }
+    }
+
+    issues
+}
+
+/// Verifies the manifest, CRL and all objects on the manifest for a key.
+fn check_key_objects(
+    received: &ReceivedCert,
+    fetched: &FetchedObjects,
+    listed: &mut HashSet<uri::Rsync>,
+    issues: &mut Vec<PublicationCheckIssue>,
+) {
+    let now = Time::now();
+
+    let ca_cert = match received.to_cert() {
+        Ok(cert) => cert,
+        Err(e) => {
+            issues.push(PublicationCheckIssue::for_object(received.uri(), e));
+            return;
+        }
+    };
+
+    if ca_cert.validity().verify_at(now).is_err() {
+        issues.push(PublicationCheckIssue::for_object(
+            received.uri(),
+            "CA certificate is not valid at this time",
+        ));
+    }
+
+    // Manifest
+    let mft_uri = received.mft_uri();
+    let mft = match fetched.get(&mft_uri) {
+        None => {
+            issues.push(PublicationCheckIssue::for_object(&mft_uri, "manifest is missing"));
+            return;
+        }
+        Some(bytes) => match Manifest::decode(bytes.as_ref(), true) {
+            Ok(mft) => mft,
+            Err(e) => {
+                issues.push(PublicationCheckIssue::for_object(
+                    &mft_uri,
+                    format!("cannot decode manifest: {}", e),
+                ));
+                return;
+            }
+        },
+    };
+
+    check_ee_cert(&mft_uri, mft.cert(), &ca_cert, issues);
+
+    if mft.content().is_stale() {
+        issues.push(PublicationCheckIssue::for_object(&mft_uri, "manifest is stale"));
+    }
+
+    // CRL
+    let crl_uri = received.crl_uri();
+    let crl = match fetched.get(&crl_uri) {
+        None => {
+            issues.push(PublicationCheckIssue::for_object(&crl_uri, "CRL is missing"));
+            None
+        }
+        Some(bytes) => match Crl::decode(bytes.as_ref()) {
+            Ok(crl) => {
+                if crl.verify_signature(ca_cert.subject_public_key_info()).is_err() {
+                    issues.push(PublicationCheckIssue::for_object(&crl_uri, "invalid CRL signature"));
+                }
+                if crl.next_update() < now {
+                    issues.push(PublicationCheckIssue::for_object(&crl_uri, "CRL is stale"));
+                }
+                Some(crl)
+            }
+            Err(e) => {
+                issues.push(PublicationCheckIssue::for_object(
+                    &crl_uri,
+                    format!("cannot decode CRL: {}", e),
+                ));
+                None
+            }
+        },
+    };
+
+    let is_revoked = |serial: Serial| crl.as_ref().map(|crl| crl.contains(serial)).unwrap_or(false);
+
+    if is_revoked(mft.cert().serial_number()) {
+        issues.push(PublicationCheckIssue::for_object(
+            &mft_uri,
+            "manifest EE certificate is revoked",
+        ));
+    }
+
+    // Objects listed on the manifest
+    let mut crl_listed = false;
+    for (uri, hash) in mft.content().iter_uris(received.ca_repository()) {
+        let bytes = match fetched.get(&uri) {
+            Some(bytes) => bytes,
+            None => {
+                issues.push(PublicationCheckIssue::for_object(
+                    &uri,
+                    "object is listed on manifest, but missing",
+                ));
+                continue;
+            }
+        };
+
+        if hash.verify(bytes).is_err() {
+            issues.push(PublicationCheckIssue::for_object(
+                &uri,
+                "object hash does not match manifest",
+            ));
+        }
+
+        if uri == crl_uri {
+            crl_listed = true;
+        } else if uri.ends_with(".roa") || uri.ends_with(".asa") {
+            let decoded = if uri.ends_with(".roa") {
+                Roa::decode(bytes.as_ref(), true).map(|_| ()).map_err(|e| e.to_string())
+            } else {
+                Aspa::decode(bytes.as_ref(), true)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            };
+
+            match decoded.and_then(|_| SignedObject::decode(bytes.as_ref(), true).map_err(|e| e.to_string())) {
+                Ok(signed) => {
+                    check_ee_cert(&uri, signed.cert(), &ca_cert, issues);
+                    if is_revoked(signed.cert().serial_number()) {
+                        issues.push(PublicationCheckIssue::for_object(&uri, "EE certificate is revoked"));
+                    }
+                }
+                Err(e) => issues.push(PublicationCheckIssue::for_object(
+                    &uri,
+                    format!("cannot decode object: {}", e),
+                )),
+            }
+        } else if uri.ends_with(".cer") {
+            match Cert::decode(bytes.as_ref()) {
+                Ok(cert) => {
+                    check_issued_cert(&uri, &cert, &ca_cert, issues);
+                    if is_revoked(cert.serial_number()) {
+                        issues.push(PublicationCheckIssue::for_object(&uri, "certificate is revoked"));
+                    }
+                }
+                Err(e) => issues.push(PublicationCheckIssue::for_object(
+                    &uri,
+                    format!("cannot decode certificate: {}", e),
+                )),
+            }
+        }
+
+        listed.insert(uri);
+    }
+
+    if !crl_listed {
+        issues.push(PublicationCheckIssue::for_object(
+            &crl_uri,
+            "CRL is not listed on manifest",
+        ));
+    }
+}
+
+/// Verifies that the EE certificate of a signed object is signed by the CA
+/// and valid at this time.
+fn check_ee_cert(uri: &uri::Rsync, ee: &Cert, ca_cert: &Cert, issues: &mut Vec<PublicationCheckIssue>) {
+    if let Err(e) = ee.inspect_ee(true) {
+        issues.push(PublicationCheckIssue::for_object(
+            uri,
+            format!("invalid EE certificate: {}", e),
+        ));
+    }
+    check_issued_cert(uri, ee, ca_cert, issues);
+}
+
+/// Verifies that a certificate is signed by the CA and valid at this time.
+fn check_issued_cert(uri: &uri::Rsync, cert: &Cert, ca_cert: &Cert, issues: &mut Vec<PublicationCheckIssue>) {
+    if cert.authority_key_identifier() != Some(ca_cert.subject_key_identifier()) {
+        issues.push(PublicationCheckIssue::for_object(
+            uri,
+            "certificate was not issued by this CA key",
+        ));
+    } else if cert.verify_signature(ca_cert, true).is_err() {
+        issues.push(PublicationCheckIssue::for_object(uri, "invalid certificate signature"));
+    }
+
+    if cert.verify_validity(Time::now()).is_err() {
+        issues.push(PublicationCheckIssue::for_object(
+            uri,
+            "certificate is not valid at this time",
+        ));
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn uri(name: &str) -> uri::Rsync {
+        uri::Rsync::from_str(&format!("rsync://example.com/repo/{}", name)).unwrap()
+    }
+
+    fn hash(data: &[u8]) -> rrdp::Hash {
+        rrdp::Hash::from_data(data)
+    }
+
+    fn delta_xml(session: Uuid, serial: u64, elements: &str) -> String {
+        format!(
+            r#"<delta xmlns="http://www.ripe.net/rpki/rrdp" version="1" session_id="{}" serial="{}">{}</delta>"#,
+            session, serial, elements
+        )
+    }
+
+    #[test]
+    fn apply_delta_to_objects() {
+        let session = Uuid::from_str("9df4b597-af9e-4dca-bdda-719cce2c4e28").unwrap();
+
+        let mut objects = FetchedObjects::new();
+        objects.insert(uri("a.cer"), Bytes::from_static(&[0, 0, 0]));
+        objects.insert(uri("b.cer"), Bytes::from_static(&[4, 16, 65]));
+
+        // Publish [1, 1, 1], update to [2, 2, 2] and withdraw.
+        let elements = format!(
+            concat!(
+                r#"<publish uri="{}">AQEB</publish>"#,
+                r#"<publish uri="{}" hash="{}">AgIC</publish>"#,
+                r#"<withdraw uri="{}" hash="{}"/>"#
+            ),
+            uri("c.cer"),
+            uri("a.cer"),
+            hash(&[0, 0, 0]),
+            uri("b.cer"),
+            hash(&[4, 16, 65]),
+        );
+        let xml = delta_xml(session, 2, &elements);
+
+        // The session and serial must match.
+        assert!(apply_delta(&mut objects.clone(), Uuid::new_v4(), 2, xml.as_bytes()).is_err());
+        assert!(apply_delta(&mut objects.clone(), session, 3, xml.as_bytes()).is_err());
+
+        apply_delta(&mut objects, session, 2, xml.as_bytes()).unwrap();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects.get(&uri("a.cer")).unwrap().as_ref(), &[2, 2, 2]);
+        assert_eq!(objects.get(&uri("c.cer")).unwrap().as_ref(), &[1, 1, 1]);
+        assert!(!objects.contains_key(&uri("b.cer")));
+
+        // Updates and withdraws must match the current object.
+        let elements = format!(r#"<withdraw uri="{}" hash="{}"/>"#, uri("a.cer"), hash(&[0, 0, 0]));
+        let xml = delta_xml(session, 3, &elements);
+        assert!(apply_delta(&mut objects, session, 3, xml.as_bytes()).is_err());
+    }
+}
@@ -6,19 +6,24 @@ use rpki::ca::{
     publication::PublishDelta,
 };
 
-use crate::commons::{
-    api::{
-        ChildConnectionStats, ChildStatus, ChildrenConnectionStats, ErrorResponse, ParentStatus, ParentStatuses,
-        RepoStatus,
+use crate::{
+    commons::{
+        api::{
+            Backoff, ChildConnectionStats, ChildStatus, ChildrenConnectionStats, ErrorResponse, ParentExchangeLog,
+            ParentExchangeRecord, ParentStatus, ParentStatuses, PublicationCheck, Reconciliation, RepoStatus,
+            RepoStatuses,
+        },
+        error::Error,
+        eventsourcing::{KeyStoreKey, KeyValueStore},
+        util::httpclient,
+        KrillResult,
     },
-    error::Error,
-    eventsourcing::{KeyStoreKey, KeyValueStore},
-    util::httpclient,
-    KrillResult,
+    constants::PARENT_EXCHANGE_LOG_LEN,
 };
 
 const PARENTS_PREFIX: &str = "parents-";
 const CHILDREN_PREFIX: &str = "children-";
+const EXCHANGES_PREFIX: &str = "exchanges-";
 const JSON_SUFFIX: &str = ".json";
 
 //------------ CaStatus ------------------------------------------------------
@@ -26,9 +31,19 @@ const JSON_SUFFIX: &str = ".json";
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CaStatus {
     repo: RepoStatus,
+    #[serde(skip_serializing_if = "RepoStatuses::is_empty", default)]
+    repos: RepoStatuses,
     parents: ParentStatuses,
     #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
     children: HashMap<ChildHandle, ChildStatus>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    publication_check: Option<PublicationCheck>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    reconciliation: Option<Reconciliation>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
+    parent_exchanges: HashMap<ParentHandle, ParentExchangeLog>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
+    command_counts: HashMap<String, u64>,
 }
 
 impl CaStatus {
@@ -45,17 +60,42 @@ impl CaStatus {
         ChildrenConnectionStats::new(children)
     }
 
+    /// Returns the status of the last exchange with any repository.
     pub fn repo(&self) -> &RepoStatus {
         &self.repo
     }
 
+    /// Returns the status of each repository, in case the CA publishes to
+    /// more than one.
+    pub fn repos(&self) -> &RepoStatuses {
+        &self.repos
+    }
+
     pub fn parents(&self) -> &ParentStatuses {
         &self.parents
     }
 
+    /// Returns the recent exchanges with the given parent.
+    pub fn parent_exchanges(&self, parent: &ParentHandle) -> ParentExchangeLog {
+        self.parent_exchanges.get(parent).cloned().unwrap_or_default()
+    }
+
     pub fn children(&self) -> &HashMap<ChildHandle, ChildStatus> {
         &self.children
     }
+
+    pub fn publication_check(&self) -> Option<&PublicationCheck> {
+        self.publication_check.as_ref()
+    }
+
+    pub fn reconciliation(&self) -> Option<&Reconciliation> {
+        self.reconciliation.as_ref()
+    }
+
+    /// Returns the number of commands processed for the CA, by type.
+    pub fn command_counts(&self) -> &HashMap<String, u64> {
+        &self.command_counts
+    }
 }
 
 //------------ StatusStore ---------------------------------------------------
@@ -95,9 +135,16 @@ impl StatusStore {
     /// It will get updated with new status values as Krill is running.
     fn load_full_status(&self, ca: &CaHandle) -> KrillResult<()> {
         let repo: RepoStatus = self.store.get(&Self::repo_status_key(ca))?.unwrap_or_default();
+        let repos: RepoStatuses = self
+            .store
+            .get(&Self::repo_statuses_key(ca))
+            .ok()
+            .flatten()
+            .unwrap_or_default();
 
         // We use the following mapping for keystore keys to parents/children:
         //  parents-{parent-handle}.json
+        //  exchanges-{parent-handle}.json
         //  children-{child-handle}.json
 
         // parents
@@ -123,6 +170,26 @@ impl StatusStore {
             }
         }
 
+        // recent exchanges with parents
+        let mut parent_exchanges = HashMap::new();
+        for exchanges_key in self.store.keys(Some(ca.to_string()), EXCHANGES_PREFIX)? {
+            if let Some(parent) = exchanges_key
+                .name()
+                .strip_prefix(EXCHANGES_PREFIX)
+                .and_then(|pfx_stripped| pfx_stripped.strip_suffix(JSON_SUFFIX))
+                .and_then(|handle_str| ParentHandle::from_str(handle_str).ok())
+            {
+                let log: ParentExchangeLog = self
+                    .store
+                    .get(&Self::parent_exchanges_key(ca, &parent))
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default();
+
+                parent_exchanges.insert(parent, log);
+            }
+        }
+
         // children
         let mut children = HashMap::new();
         for child_key in self.store.keys(Some(ca.to_string()), CHILDREN_PREFIX)? {
@@ -143,10 +210,31 @@ impl StatusStore {
             }
         }
 
+        // publication check, if it was done. Like the other status values
+        // we just ignore it if it cannot be parsed.
+        let publication_check: Option<PublicationCheck> =
+            self.store.get(&Self::publication_check_key(ca)).ok().flatten();
+
+        // reconciliation, if it was done
+        let reconciliation: Option<Reconciliation> = self.store.get(&Self::reconciliation_key(ca)).ok().flatten();
+
+        // command counts, for the metrics
+        let command_counts: HashMap<String, u64> = self
+            .store
+            .get(&Self::command_counts_key(ca))
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+
         let status = CaStatus {
             repo,
+            repos,
             parents,
             children,
+            publication_check,
+            reconciliation,
+            parent_exchanges,
+            command_counts,
         };
 
         // Update the cache. Note that this is what we will use at runtime.
@@ -184,14 +272,33 @@ impl StatusStore {
     }
 
     fn repo_status_key(ca: &CaHandle) -> KeyStoreKey {
-        // we may need to support multiple repos in future
         KeyStoreKey::scoped(ca.to_string(), "repos-main.json".to_string())
     }
 
+    fn repo_statuses_key(ca: &CaHandle) -> KeyStoreKey {
+        KeyStoreKey::scoped(ca.to_string(), "repos-all.json".to_string())
+    }
+
+    fn publication_check_key(ca: &CaHandle) -> KeyStoreKey {
+        KeyStoreKey::scoped(ca.to_string(), "publication-check.json".to_string())
+    }
+
+    fn reconciliation_key(ca: &CaHandle) -> KeyStoreKey {
+        KeyStoreKey::scoped(ca.to_string(), "reconciliation.json".to_string())
+    }
+
+    fn command_counts_key(ca: &CaHandle) -> KeyStoreKey {
+        KeyStoreKey::scoped(ca.to_string(), "commands.json".to_string())
+    }
+
     fn parent_status_key(ca: &CaHandle, parent: &ParentHandle) -> KeyStoreKey {
         KeyStoreKey::scoped(ca.to_string(), format!("{}{}{}", PARENTS_PREFIX, parent, JSON_SUFFIX))
     }
 
+    fn parent_exchanges_key(ca: &CaHandle, parent: &ParentHandle) -> KeyStoreKey {
+        KeyStoreKey::scoped(ca.to_string(), format!("{}{}{}", EXCHANGES_PREFIX, parent, JSON_SUFFIX))
+    }
+
     fn child_status_key(ca: &CaHandle, child: &ChildHandle) -> KeyStoreKey {
         KeyStoreKey::scoped(ca.to_string(), format!("{}{}{}", CHILDREN_PREFIX, child, JSON_SUFFIX))
     }
@@ -212,6 +319,12 @@ impl StatusStore {
         self.update_ca_parent_status(ca, parent, |status| status.set_failure(uri.clone(), error_response))
     }
 
+    /// Records the backoff state after a failure to contact a parent. This is
+    /// cleared again when the parent is contacted successfully.
+    pub fn set_parent_backoff(&self, ca: &CaHandle, parent: &ParentHandle, backoff: Backoff) -> KrillResult<()> {
+        self.update_ca_parent_status(ca, parent, |status| status.set_backoff(backoff))
+    }
+
     pub fn set_parent_last_updated(&self, ca: &CaHandle, parent: &ParentHandle, uri: &ServiceUri) -> KrillResult<()> {
         self.update_ca_parent_status(ca, parent, |status| status.set_last_updated(uri.clone()))
     }
@@ -232,10 +345,37 @@ impl StatusStore {
         if let Some(ca_status) = cache.get_mut(ca) {
             ca_status.parents.remove(parent);
             self.store.drop_key(&Self::parent_status_key(ca, parent))?;
+
+            if ca_status.parent_exchanges.remove(parent).is_some() {
+                self.store.drop_key(&Self::parent_exchanges_key(ca, parent))?;
+            }
         }
         Ok(())
     }
 
+    /// Records an exchange with a parent, keeping only the most recent
+    /// exchanges.
+    pub fn add_parent_exchange(
+        &self,
+        ca: &CaHandle,
+        parent: &ParentHandle,
+        record: ParentExchangeRecord,
+    ) -> KrillResult<()> {
+        let mut cache = self.cache.write().unwrap();
+
+        let log = cache
+            .entry(ca.clone())
+            .or_default()
+            .parent_exchanges
+            .entry(parent.clone())
+            .or_default();
+        log.add(record, PARENT_EXCHANGE_LOG_LEN);
+
+        self.store.store(&Self::parent_exchanges_key(ca, parent), log)?;
+
+        Ok(())
+    }
+
     pub fn set_child_success(&self, ca: &CaHandle, child: &ChildHandle, user_agent: Option<String>) -> KrillResult<()> {
         self.update_ca_child_status(ca, child, |status| status.set_success(user_agent))
     }
@@ -283,20 +423,93 @@ impl StatusStore {
 
     pub fn set_status_repo_failure(&self, ca: &CaHandle, uri: ServiceUri, error: &Error) -> KrillResult<()> {
         let error_response = Self::error_to_error_res(error);
-        self.update_repo_status(ca, |status| status.set_failure(uri, error_response))
+        self.update_repo_status(ca, Some(&uri), |status| {
+            status.set_failure(uri.clone(), error_response.clone())
+        })
+    }
+
+    /// Records the backoff state after a failure to synchronise with the
+    /// repository. This is cleared again when the repository is contacted
+    /// successfully.
+    pub fn set_status_repo_backoff(&self, ca: &CaHandle, backoff: Backoff) -> KrillResult<()> {
+        self.update_repo_status(ca, None, |status| status.set_backoff(backoff.clone()))
     }
 
     pub fn set_status_repo_success(&self, ca: &CaHandle, uri: ServiceUri) -> KrillResult<()> {
-        self.update_repo_status(ca, |status| status.set_last_updated(uri))
+        self.update_repo_status(ca, Some(&uri), |status| status.set_last_updated(uri.clone()))
     }
 
     pub fn set_status_repo_published(&self, ca: &CaHandle, uri: ServiceUri, delta: PublishDelta) -> KrillResult<()> {
-        self.update_repo_status(ca, |status| status.update_published(uri, delta))
+        self.update_repo_status(ca, Some(&uri), |status| {
+            status.update_published(uri.clone(), delta.clone())
+        })
+    }
+
+    /// Records that objects were published at the repository which are not
+    /// yet confirmed to be available over RRDP.
+    pub fn set_status_repo_unconfirmed(&self, ca: &CaHandle, uri: &ServiceUri) -> KrillResult<()> {
+        self.update_repo_status(ca, Some(uri), |status| status.set_unconfirmed())
+    }
+
+    /// Records that all objects published at the repository were found in
+    /// the RRDP snapshot with the given serial.
+    pub fn set_status_repo_confirmed(&self, ca: &CaHandle, uri: &ServiceUri, serial: u64) -> KrillResult<()> {
+        self.update_repo_status(ca, Some(uri), |status| status.set_confirmed(serial))
+    }
+
+    /// Records that an alert was raised because confirmation of the objects
+    /// published at the repository lags.
+    pub fn set_status_repo_confirm_lagging(&self, ca: &CaHandle, uri: &ServiceUri) -> KrillResult<()> {
+        self.update_repo_status(ca, Some(uri), |status| status.set_confirm_lagging())
+    }
+
+    /// Removes the status of a repository which is no longer used.
+    pub fn remove_repo_status(&self, ca: &CaHandle, uri: &ServiceUri) -> KrillResult<()> {
+        let mut cache = self.cache.write().unwrap();
+
+        if let Some(ca_status) = cache.get_mut(ca) {
+            ca_status.repos.remove(uri);
+            self.store.store(&Self::repo_statuses_key(ca), &ca_status.repos)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_publication_check(&self, ca: &CaHandle, check: PublicationCheck) -> KrillResult<()> {
+        let mut cache = self.cache.write().unwrap();
+
+        self.store.store(&Self::publication_check_key(ca), &check)?;
+        cache.entry(ca.clone()).or_default().publication_check = Some(check);
+
+        Ok(())
+    }
+
+    pub fn set_reconciliation(&self, ca: &CaHandle, reconciliation: Reconciliation) -> KrillResult<()> {
+        let mut cache = self.cache.write().unwrap();
+
+        self.store.store(&Self::reconciliation_key(ca), &reconciliation)?;
+        cache.entry(ca.clone()).or_default().reconciliation = Some(reconciliation);
+
+        Ok(())
     }
 
-    fn update_repo_status<F>(&self, ca: &CaHandle, op: F) -> KrillResult<()>
+    /// Counts a command processed for the CA, by its type.
+    pub fn count_command(&self, ca: &CaHandle, label: String) -> KrillResult<()> {
+        let mut cache = self.cache.write().unwrap();
+
+        let command_counts = &mut cache.entry(ca.clone()).or_default().command_counts;
+        *command_counts.entry(label).or_insert(0) += 1;
+        self.store.store(&Self::command_counts_key(ca), command_counts)?;
+
+        Ok(())
+    }
+
+    /// Applies the update to the status of the last exchange with any
+    /// repository, and to the status of the repository with the given service
+    /// URI, if there is one.
+    fn update_repo_status<F>(&self, ca: &CaHandle, uri: Option<&ServiceUri>, op: F) -> KrillResult<()>
     where
-        F: FnOnce(&mut RepoStatus),
+        F: Fn(&mut RepoStatus),
     {
         let mut cache = self.cache.write().unwrap();
 
@@ -306,9 +519,13 @@ impl StatusStore {
 
         let ca_status = cache.get_mut(ca).unwrap(); // safe, we just set it if missing
         op(&mut ca_status.repo);
-
         self.store.store(&Self::repo_status_key(ca), ca_status.repo())?;
 
+        if let Some(uri) = uri {
+            op(ca_status.repos.get_mut_status(uri));
+            self.store.store(&Self::repo_statuses_key(ca), &ca_status.repos)?;
+        }
+
         Ok(())
     }
 
@@ -378,7 +595,9 @@ mod tests {
 
     use std::path::PathBuf;
 
-    use crate::commons::util::file;
+    use rpki::uri;
+
+    use crate::commons::{api::Timestamp, util::file};
     use crate::test::test_under_tmp;
 
     #[test]
@@ -402,4 +621,94 @@ mod tests {
             assert_eq!(status_testbed_before_migration, status_testbed_migrated);
         });
     }
+
+    #[test]
+    fn track_status_per_repo() {
+        test_under_tmp(|d| {
+            let ca = CaHandle::from_str("ca").unwrap();
+            let old = ServiceUri::Https(uri::Https::from_str("https://old.example.com/rfc8181/ca/").unwrap());
+            let new = ServiceUri::Https(uri::Https::from_str("https://new.example.com/rfc8181/ca/").unwrap());
+
+            let store = StatusStore::new(&d, "status").unwrap();
+            store.set_status_repo_success(&ca, old.clone()).unwrap();
+            store
+                .set_status_repo_failure(&ca, new.clone(), &Error::custom("unreachable"))
+                .unwrap();
+
+            // The status survives a restart.
+            let store = StatusStore::new(&d, "status").unwrap();
+            let status = store.get_ca_status(&ca);
+            assert!(status.repo().to_failure_opt().is_some());
+            assert!(status.repos().get(&old).unwrap().to_failure_opt().is_none());
+            assert!(status.repos().get(&new).unwrap().to_failure_opt().is_some());
+
+            store.remove_repo_status(&ca, &old).unwrap();
+            assert!(store.get_ca_status(&ca).repos().get(&old).is_none());
+        });
+    }
+
+    #[test]
+    fn save_backoff() {
+        test_under_tmp(|d| {
+            let ca = CaHandle::from_str("ca").unwrap();
+            let parent = ParentHandle::from_str("parent").unwrap();
+            let uri = ServiceUri::Https(uri::Https::from_str("https://example.com/rfc6492/ca/").unwrap());
+            let next_attempt = Timestamp::now_plus_minutes(10);
+
+            let store = StatusStore::new(&d, "status").unwrap();
+            store
+                .set_parent_failure(&ca, &parent, &uri, &Error::custom("unreachable"))
+                .unwrap();
+            store
+                .set_parent_backoff(&ca, &parent, Backoff::new(2, next_attempt))
+                .unwrap();
+            store
+                .set_status_repo_failure(&ca, uri.clone(), &Error::custom("unreachable"))
+                .unwrap();
+            store
+                .set_status_repo_backoff(&ca, Backoff::new(1, next_attempt))
+                .unwrap();
+
+            // The backoff state survives a restart.
+            let store = StatusStore::new(&d, "status").unwrap();
+            let status = store.get_ca_status(&ca);
+            let backoff = status.parents().get(&parent).unwrap().backoff().unwrap();
+            assert_eq!(backoff.failures(), 2);
+            assert_eq!(backoff.next_attempt(), next_attempt);
+            assert_eq!(status.repo().backoff().unwrap().failures(), 1);
+
+            // And it is cleared when the parent and repository are contacted
+            // successfully again.
+            store.set_parent_last_updated(&ca, &parent, &uri).unwrap();
+            store.set_status_repo_success(&ca, uri).unwrap();
+
+            let store = StatusStore::new(&d, "status").unwrap();
+            let status = store.get_ca_status(&ca);
+            assert!(status.parents().get(&parent).unwrap().backoff().is_none());
+            assert!(status.repo().backoff().is_none());
+        });
+    }
+
+    #[test]
+    fn count_commands() {
+        test_under_tmp(|d| {
+            let ca = CaHandle::from_str("ca").unwrap();
+
+            let store = StatusStore::new(&d, "status").unwrap();
+            store.count_command(&ca, "cmd-ca-roas-updated".to_string()).unwrap();
+            store.count_command(&ca, "cmd-ca-roas-updated".to_string()).unwrap();
+            store.count_command(&ca, "cmd-ca-repo-update".to_string()).unwrap();
+
+            // The counts survive a restart, and are dropped with the CA.
+            let store = StatusStore::new(&d, "status").unwrap();
+            let counts = store.get_ca_status(&ca).command_counts().clone();
+            assert_eq!(counts.len(), 2);
+            assert_eq!(counts.get("cmd-ca-roas-updated"), Some(&2));
+            assert_eq!(counts.get("cmd-ca-repo-update"), Some(&1));
+
+            store.remove_ca(&ca).unwrap();
+            let store = StatusStore::new(&d, "status").unwrap();
+            assert!(store.get_ca_status(&ca).command_counts().is_empty());
+        });
+    }
 }
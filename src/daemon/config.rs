@@ -1,6 +1,8 @@
 use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
     env, fmt,
     fs::File,
+    hash::{Hash, Hasher},
     io::{self, Read},
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::{Path, PathBuf},
@@ -15,28 +17,56 @@ use serde::{de, Deserialize, Deserializer};
 use syslog::Facility;
 
 use rpki::{
-    ca::idexchange::PublisherHandle,
-    repository::x509::{Time, Validity},
+    ca::idexchange::{CaHandle, PublisherHandle},
+    crypto::PublicKeyFormat,
+    repository::{
+        resources::ResourceSet,
+        x509::{Time, Validity},
+    },
     uri,
 };
 
 use crate::{
     commons::{
-        api::{PublicationServerUris, Token},
-        crypto::{OpenSslSignerConfig, SignSupport},
+        api::{
+            ConfigReloadReport, IssuanceTimingOverrides, PublicationServerUris, PublisherQuota, PublisherValidation,
+            ResourceSetSummary, RetryPolicy, RoaAutoPilotPolicy, RoaIssuanceStrategy, Token,
+        },
+        crypto::{OpenSslSignerConfig, SerialNumberStrategy, SignSupport},
         error::KrillIoError,
-        util::ext_serde,
+        eventsourcing::{KeyValueStorage, SnapshotPolicy},
+        util::{clock, ext_serde, file::Compression, logging::LogContext, postgres::PostgresConfig},
     },
     constants::*,
-    daemon::http::tls_keys,
+    daemon::ha::HaConfig,
+    daemon::http::{
+        acme::{acme_domain, AcmeConfig},
+        ratelimit::RateLimitConfig,
+        tls::TlsConfig,
+        tls_keys,
+    },
     daemon::mq::{in_seconds, Priority},
+    daemon::notify::{AlertEvent, EmailChannelConfig, WebhookChannelConfig},
+    daemon::telemetry::TelemetryConfig,
+    daemon::timecheck::TimeCheckConfig,
+    pubd::{RepositoryArchiveConfig, RepositoryReplicaConfig, RepositoryS3Config, RepositoryWebhookConfig},
 };
 
 #[cfg(feature = "multi-user")]
-use crate::daemon::auth::providers::{config_file::config::ConfigAuthUsers, openid_connect::ConfigAuthOpenIDConnect};
+use crate::daemon::auth::{
+    orgs::ConfigAuthOrgs,
+    providers::{
+        client_cert::ConfigAuthClientCerts,
+        config_file::config::ConfigAuthUsers,
+        jwt::ConfigAuthJwt,
+        ldap::ConfigAuthLdap,
+        openid_connect::{config::ConfigAuthOpenIDConnectClaimSource, jmespathext, ConfigAuthOpenIDConnect},
+    },
+    roles::{ConfigAuthRoles, BUILT_IN_ROLES},
+};
 
 #[cfg(feature = "hsm")]
-use crate::commons::crypto::{KmipSignerConfig, Pkcs11SignerConfig};
+use crate::commons::crypto::{AwsKmsSignerConfig, GcpKmsSignerConfig, KmipSignerConfig, Pkcs11SignerConfig};
 
 //------------ ConfigDefaults ------------------------------------------------
 
@@ -61,6 +91,10 @@ impl ConfigDefaults {
         env::var(KRILL_ENV_FORCE_RECOVER).is_ok()
     }
 
+    fn allow_downgrade() -> bool {
+        env::var(KRILL_ENV_ALLOW_DOWNGRADE).is_ok()
+    }
+
     pub fn log_level() -> LevelFilter {
         match env::var(KRILL_ENV_LOG_LEVEL) {
             Ok(level) => match LevelFilter::from_str(&level) {
@@ -191,6 +225,14 @@ impl ConfigDefaults {
         90
     }
 
+    fn expiry_window_hours() -> u32 {
+        4
+    }
+
+    fn republish_workers() -> usize {
+        4
+    }
+
     fn timing_publish_next_hours() -> u32 {
         24
     }
@@ -219,6 +261,10 @@ impl ConfigDefaults {
         4
     }
 
+    fn timing_roa_valid_jitter_hours() -> u32 {
+        0
+    }
+
     fn timing_aspa_valid_weeks() -> u32 {
         52
     }
@@ -227,6 +273,10 @@ impl ConfigDefaults {
         4
     }
 
+    fn timing_aspa_valid_jitter_hours() -> u32 {
+        0
+    }
+
     fn timing_bgpsec_valid_weeks() -> u32 {
         52
     }
@@ -235,6 +285,10 @@ impl ConfigDefaults {
         4
     }
 
+    fn timing_renew_spread_hours() -> u32 {
+        0
+    }
+
     pub fn openssl_signer_only() -> Vec<SignerConfig> {
         let signer_config = OpenSslSignerConfig { keys_path: None };
         vec![SignerConfig::new(
@@ -417,12 +471,47 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::https_mode")]
     https_mode: HttpsMode,
 
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    // Obtain and renew the HTTPS certificate using ACME, if set.
+    #[serde(default)]
+    pub acme: Option<AcmeConfig>,
+
+    // Also serve the API on a Unix domain socket for local administration,
+    // if set.
+    #[serde(default)]
+    pub unix_socket: Option<UnixSocketConfig>,
+
+    // Limit the request rate per client and the number of concurrent
+    // expensive operations, if set.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+
     #[serde(default = "ConfigDefaults::data_dir")]
     pub data_dir: PathBuf,
 
     #[serde(default)] // default is false
     pub data_dir_use_lock: bool,
 
+    // Keep the CA and Publication Server events, commands and snapshots
+    // in this PostgreSQL database instead of the data_dir, if set.
+    #[serde(default)]
+    pub storage_uri: Option<PostgresConfig>,
+
+    // Run as the active or standby instance of two instances which share
+    // the storage_uri database, if set.
+    #[serde(default)]
+    pub ha: Option<HaConfig>,
+
+    // Export OpenTelemetry spans to an OTLP endpoint, if set.
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
+
+    // Compare the system time with these time sources, if set.
+    #[serde(default)]
+    pub time_check: Option<TimeCheckConfig>,
+
     // default is false
     // implicitly enabled in case of testbed
     // for that reason.. not pub, but fn provided
@@ -442,6 +531,15 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::always_recover_data")]
     pub always_recover_data: bool,
 
+    #[serde(default = "ConfigDefaults::allow_downgrade")]
+    pub allow_downgrade: bool,
+
+    #[serde(flatten)]
+    pub snapshots: SnapshotConfig,
+
+    #[serde(default)]
+    pub history_archive_days: Option<u32>,
+
     pub pid_file: Option<PathBuf>,
 
     service_uri: Option<uri::Https>,
@@ -455,6 +553,9 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::log_type")]
     log_type: LogType,
 
+    #[serde(default)]
+    log_format: LogFormat,
+
     #[serde(default = "ConfigDefaults::log_file")]
     log_file: PathBuf,
 
@@ -467,6 +568,17 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::auth_type")]
     pub auth_type: AuthType,
 
+    /// Providers to enable in addition to the one for `auth_type`, which
+    /// remains the default for the web UI.
+    #[cfg(feature = "multi-user")]
+    #[serde(default)]
+    pub auth_additional_types: Vec<AuthType>,
+
+    /// Record authentication and authorization events in an audit log in
+    /// the data directory.
+    #[serde(default)]
+    pub auth_audit_log: bool,
+
     #[cfg(feature = "multi-user")]
     #[serde(default = "ConfigDefaults::auth_policies")]
     pub auth_policies: Vec<PathBuf>,
@@ -475,12 +587,29 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::auth_private_attributes")]
     pub auth_private_attributes: Vec<String>,
 
+    #[cfg(feature = "multi-user")]
+    #[serde(default)]
+    pub auth_roles: ConfigAuthRoles,
+
+    #[cfg(feature = "multi-user")]
+    #[serde(default)]
+    pub auth_orgs: ConfigAuthOrgs,
+
     #[cfg(feature = "multi-user")]
     pub auth_users: Option<ConfigAuthUsers>,
 
     #[cfg(feature = "multi-user")]
     pub auth_openidconnect: Option<ConfigAuthOpenIDConnect>,
 
+    #[cfg(feature = "multi-user")]
+    pub auth_ldap: Option<ConfigAuthLdap>,
+
+    #[cfg(feature = "multi-user")]
+    pub auth_jwt: Option<ConfigAuthJwt>,
+
+    #[cfg(feature = "multi-user")]
+    pub auth_client_certs: Option<ConfigAuthClientCerts>,
+
     #[serde(default, deserialize_with = "deserialize_signer_ref")]
     pub default_signer: SignerReference,
 
@@ -493,6 +622,12 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::signers")]
     pub signers: Vec<SignerConfig>,
 
+    #[serde(default)]
+    pub ca_key_algorithm: KeyAlgorithm,
+
+    #[serde(default)]
+    pub serial_number_strategy: SerialNumberStrategy,
+
     #[serde(default = "ConfigDefaults::ca_refresh_seconds", alias = "ca_refresh")]
     ca_refresh_seconds: u32,
 
@@ -521,6 +656,12 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::post_protocol_msg_timeout_seconds")]
     pub post_protocol_msg_timeout_seconds: u64,
 
+    #[serde(default)]
+    pub retry_parent: RetryConfig,
+
+    #[serde(default)]
+    pub retry_repository: RetryConfig,
+
     #[serde(default = "ConfigDefaults::rfc6492_log_dir")]
     pub rfc6492_log_dir: Option<PathBuf>,
 
@@ -532,6 +673,11 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::bgp_risdumps_v6_uri")]
     pub bgp_risdumps_v6_uri: String,
 
+    // Reject ROA updates which would invalidate announcements seen in BGP,
+    // unless they are forced
+    #[serde(default)] // false
+    pub roa_update_bgp_check: bool,
+
     // ROA Aggregation per ASN
     #[serde(default = "ConfigDefaults::roa_aggregate_threshold")]
     pub roa_aggregate_threshold: usize,
@@ -539,18 +685,141 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::roa_deaggregate_threshold")]
     pub roa_deaggregate_threshold: usize,
 
+    // How prefixes are combined into ROAs, CAs can override this
+    #[serde(default)]
+    pub roa_issuance_strategy: RoaIssuanceStrategy,
+
+    // ROA auto-pilot per CA, CAs can override this
+    #[serde(default)]
+    pub roa_autopilot: HashMap<CaHandle, RoaAutoPilotPolicy>,
+
+    // Check of the objects published by CAs, disabled if not set
+    #[serde(default)]
+    pub publication_check_interval_minutes: Option<u32>,
+
+    #[serde(default)] // false
+    pub publication_check_rsync: bool,
+
+    // Fetch back published objects over RRDP to confirm that they are
+    // available, and alert if this lags more than the given number of
+    // minutes, disabled if not set
+    #[serde(default)]
+    pub publication_confirm_lag_minutes: Option<u32>,
+
+    // Compare the configuration of CAs with the objects they issued and
+    // published, disabled if not set
+    #[serde(default)]
+    pub reconcile_interval_minutes: Option<u32>,
+
+    #[serde(default)] // false
+    pub reconcile_auto_repair: bool,
+
+    // Objects which expire within this window are highlighted in the expiry
+    // report and metrics.
+    #[serde(default = "ConfigDefaults::expiry_window_hours")]
+    pub expiry_window_hours: u32,
+
+    // Alerts sent to notification channels, disabled if no channels are set
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+
     #[serde(flatten)]
     pub issuance_timing: IssuanceTimingConfig,
 
+    // Number of CAs for which manifests and CRLs are re-issued concurrently
+    #[serde(default = "ConfigDefaults::republish_workers")]
+    pub republish_workers: usize,
+
     #[serde(flatten)]
     pub rrdp_updates_config: RrdpUpdatesConfig,
 
+    // Default limits for publishers, can be overridden per publisher
+    #[serde(default)]
+    pub publisher_quota: PublisherQuota,
+
+    // Default validation of published content, can be overridden per publisher
+    #[serde(default)]
+    pub publisher_validation: PublisherValidation,
+
+    // Write an rsyncd module configuration for the repository, if set
+    #[serde(default)]
+    pub rsyncd_conf: Option<PathBuf>,
+
+    // Archive the repository content, disabled if not set
+    #[serde(default)]
+    pub repository_archive: Option<RepositoryArchiveConfig>,
+
+    // Webhooks called on publication server events, disabled if none are set
+    #[serde(default)]
+    pub repository_webhooks: Vec<RepositoryWebhookConfig>,
+
+    // Replicate the repository of a primary publication server, if set
+    #[serde(default)]
+    pub repository_replica: Option<RepositoryReplicaConfig>,
+
+    // Upload the repository files to an S3 compatible bucket, if set
+    #[serde(default)]
+    pub repository_s3: Option<RepositoryS3Config>,
+
+    // CAs for which a read-only status page is shown without credentials
+    #[serde(default)]
+    pub public_status_cas: Vec<CaHandle>,
+
+    // Hosts from which parent responses may be fetched, none if empty
+    #[serde(default)]
+    pub parent_response_fetch_hosts: Vec<String>,
+
     #[serde(flatten)]
     pub metrics: MetricsConfig,
 
     pub testbed: Option<TestBed>,
 
     pub benchmark: Option<Benchmark>,
+
+    // The file and the settings in it that this config was read from, used
+    // to find changed settings when the config is reloaded.
+    #[serde(skip)]
+    config_file: Option<PathBuf>,
+    #[serde(skip)]
+    file_settings: toml::value::Table,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AlertsConfig {
+    #[serde(default = "AlertEvent::all")]
+    pub events: Vec<AlertEvent>,
+    #[serde(default = "AlertsConfig::dflt_expiry_warning_days")]
+    pub expiry_warning_days: u32,
+    #[serde(default)]
+    pub channels: Vec<NotificationChannelConfig>,
+}
+
+impl AlertsConfig {
+    fn dflt_expiry_warning_days() -> u32 {
+        14
+    }
+
+    /// Returns true if alerts for this event would be sent anywhere.
+    pub fn is_enabled(&self, event: AlertEvent) -> bool {
+        !self.channels.is_empty() && self.events.contains(&event)
+    }
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        AlertsConfig {
+            events: AlertEvent::all(),
+            expiry_warning_days: Self::dflt_expiry_warning_days(),
+            channels: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationChannelConfig {
+    Webhook(WebhookChannelConfig),
+    Email(EmailChannelConfig),
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -569,17 +838,234 @@ pub struct IssuanceTimingConfig {
     timing_roa_valid_weeks: u32,
     #[serde(default = "ConfigDefaults::timing_roa_reissue_weeks_before")]
     timing_roa_reissue_weeks_before: u32,
+    #[serde(default = "ConfigDefaults::timing_roa_valid_jitter_hours")]
+    timing_roa_valid_jitter_hours: u32,
     #[serde(default = "ConfigDefaults::timing_aspa_valid_weeks")]
     timing_aspa_valid_weeks: u32,
     #[serde(default = "ConfigDefaults::timing_aspa_reissue_weeks_before")]
     timing_aspa_reissue_weeks_before: u32,
+    #[serde(default = "ConfigDefaults::timing_aspa_valid_jitter_hours")]
+    timing_aspa_valid_jitter_hours: u32,
     #[serde(default = "ConfigDefaults::timing_bgpsec_valid_weeks")]
     timing_bgpsec_valid_weeks: u32,
     #[serde(default = "ConfigDefaults::timing_bgpsec_reissue_weeks_before")]
     timing_bgpsec_reissue_weeks_before: u32,
+    #[serde(default = "ConfigDefaults::timing_renew_spread_hours")]
+    timing_renew_spread_hours: u32,
 }
 
 impl IssuanceTimingConfig {
+    /// Verifies that the timing values are consistent.
+    pub fn verify(&self) -> Result<(), String> {
+        if self.timing_publish_next_hours < 2 {
+            return Err(String::from("timing_publish_next_hours must be at least 2"));
+        }
+
+        if self.timing_publish_next_jitter_hours > (self.timing_publish_next_hours / 2) {
+            return Err(String::from(
+                "timing_publish_next_jitter_hours must be at most timing_publish_next_hours divided by 2",
+            ));
+        }
+
+        if self.timing_publish_hours_before_next < 1 {
+            return Err(String::from("timing_publish_hours_before_next must be at least 1"));
+        }
+
+        if self.timing_publish_hours_before_next >= self.timing_publish_next_hours {
+            return Err(String::from(
+                "timing_publish_hours_before_next must be smaller than timing_publish_hours",
+            ));
+        }
+
+        if self.timing_child_certificate_valid_weeks < 2 {
+            return Err(String::from("timing_child_certificate_valid_weeks must be at least 2"));
+        }
+
+        if self.timing_child_certificate_reissue_weeks_before < 1 {
+            return Err(String::from(
+                "timing_child_certificate_reissue_weeks_before must be at least 1",
+            ));
+        }
+
+        if self.timing_child_certificate_reissue_weeks_before >= self.timing_child_certificate_valid_weeks {
+            return Err(String::from(
+                "timing_child_certificate_reissue_weeks_before must be smaller than timing_child_certificate_valid_weeks",
+            ));
+        }
+
+        if self.timing_roa_valid_weeks < 2 {
+            return Err(String::from("timing_roa_valid_weeks must be at least 2"));
+        }
+
+        if self.timing_roa_reissue_weeks_before < 1 {
+            return Err(String::from("timing_roa_reissue_weeks_before must be at least 1"));
+        }
+
+        if self.timing_roa_reissue_weeks_before >= self.timing_roa_valid_weeks {
+            return Err(String::from(
+                "timing_roa_reissue_weeks_before must be smaller than timing_roa_valid_week",
+            ));
+        }
+
+        if self.timing_aspa_valid_weeks < 2 {
+            return Err(String::from("timing_aspa_valid_weeks must be at least 2"));
+        }
+
+        if self.timing_aspa_reissue_weeks_before < 1 {
+            return Err(String::from("timing_aspa_reissue_weeks_before must be at least 1"));
+        }
+
+        if self.timing_aspa_reissue_weeks_before >= self.timing_aspa_valid_weeks {
+            return Err(String::from(
+                "timing_aspa_reissue_weeks_before must be smaller than timing_aspa_valid_weeks",
+            ));
+        }
+
+        // Jitter and spread are limited to half the time between issuance
+        // and re-issuance, so that renewed objects are never due for renewal
+        // again straight away.
+        let roa_renew_hours = (self.timing_roa_valid_weeks - self.timing_roa_reissue_weeks_before) * 168;
+        let aspa_renew_hours = (self.timing_aspa_valid_weeks - self.timing_aspa_reissue_weeks_before) * 168;
+
+        if self.timing_roa_valid_jitter_hours > roa_renew_hours / 2 {
+            return Err(String::from(
+                "timing_roa_valid_jitter_hours must be at most half the hours between timing_roa_valid_weeks and timing_roa_reissue_weeks_before",
+            ));
+        }
+
+        if self.timing_aspa_valid_jitter_hours > aspa_renew_hours / 2 {
+            return Err(String::from(
+                "timing_aspa_valid_jitter_hours must be at most half the hours between timing_aspa_valid_weeks and timing_aspa_reissue_weeks_before",
+            ));
+        }
+
+        if self.timing_renew_spread_hours > roa_renew_hours.min(aspa_renew_hours) / 2 {
+            return Err(String::from(
+                "timing_renew_spread_hours must be at most half the hours between the validity and re-issue times of ROAs and ASPA objects",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns warnings about values which are allowed, but which may be
+    /// unwise. In particular for short manifest and CRL next update times.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+
+        let next = self.timing_publish_next_hours;
+        let before = self.timing_publish_hours_before_next;
+        let replay = next + self.timing_publish_next_jitter_hours;
+
+        if before > next / 2 {
+            warnings.push(format!(
+                "Manifests and CRLs are re-issued after only {} of the {} hours until their next update, which causes extra load for the publication server and relying parties",
+                next - before,
+                next
+            ));
+        }
+
+        if before < TIMING_PUBLISH_WARN_HOURS_BEFORE_NEXT {
+            warnings.push(format!(
+                "Manifests and CRLs are re-issued only {} hour(s) before their next update, leaving little time to resolve issues before relying parties see them as stale",
+                before
+            ));
+        }
+
+        if replay > TIMING_PUBLISH_WARN_REPLAY_HOURS {
+            warnings.push(format!(
+                "Manifests and CRLs can have a next update up to {} hours after they are issued, which allows replay of old objects for a long time",
+                replay
+            ));
+        }
+
+        if self.timing_roa_reissue_weeks_before > self.timing_roa_valid_weeks / 2 {
+            warnings.push(format!(
+                "ROAs are re-issued after only {} of the {} weeks of their validity",
+                self.timing_roa_valid_weeks - self.timing_roa_reissue_weeks_before,
+                self.timing_roa_valid_weeks
+            ));
+        }
+
+        if self.timing_aspa_reissue_weeks_before > self.timing_aspa_valid_weeks / 2 {
+            warnings.push(format!(
+                "ASPA objects are re-issued after only {} of the {} weeks of their validity",
+                self.timing_aspa_valid_weeks - self.timing_aspa_reissue_weeks_before,
+                self.timing_aspa_valid_weeks
+            ));
+        }
+
+        warnings
+    }
+
+    /// Returns all values which can be overridden for a CA, as they are in
+    /// effect in this configuration.
+    pub fn effective(&self) -> IssuanceTimingOverrides {
+        IssuanceTimingOverrides {
+            timing_publish_next_hours: Some(self.timing_publish_next_hours),
+            timing_publish_next_jitter_hours: Some(self.timing_publish_next_jitter_hours),
+            timing_publish_hours_before_next: Some(self.timing_publish_hours_before_next),
+            timing_roa_valid_weeks: Some(self.timing_roa_valid_weeks),
+            timing_roa_reissue_weeks_before: Some(self.timing_roa_reissue_weeks_before),
+            timing_aspa_valid_weeks: Some(self.timing_aspa_valid_weeks),
+            timing_aspa_reissue_weeks_before: Some(self.timing_aspa_reissue_weeks_before),
+            timing_roa_valid_jitter_hours: Some(self.timing_roa_valid_jitter_hours),
+            timing_aspa_valid_jitter_hours: Some(self.timing_aspa_valid_jitter_hours),
+            timing_renew_spread_hours: Some(self.timing_renew_spread_hours),
+        }
+    }
+
+    /// Returns a copy of this configuration, with the given CA specific
+    /// overrides applied.
+    pub fn with_overrides(&self, overrides: &IssuanceTimingOverrides) -> Self {
+        let mut timing = self.clone();
+        if let Some(hours) = overrides.timing_publish_next_hours {
+            timing.timing_publish_next_hours = hours;
+        }
+        if let Some(hours) = overrides.timing_publish_next_jitter_hours {
+            timing.timing_publish_next_jitter_hours = hours;
+        }
+        if let Some(hours) = overrides.timing_publish_hours_before_next {
+            timing.timing_publish_hours_before_next = hours;
+        }
+        if let Some(weeks) = overrides.timing_roa_valid_weeks {
+            timing.timing_roa_valid_weeks = weeks;
+        }
+        if let Some(weeks) = overrides.timing_roa_reissue_weeks_before {
+            timing.timing_roa_reissue_weeks_before = weeks;
+        }
+        if let Some(weeks) = overrides.timing_aspa_valid_weeks {
+            timing.timing_aspa_valid_weeks = weeks;
+        }
+        if let Some(weeks) = overrides.timing_aspa_reissue_weeks_before {
+            timing.timing_aspa_reissue_weeks_before = weeks;
+        }
+        if let Some(hours) = overrides.timing_roa_valid_jitter_hours {
+            timing.timing_roa_valid_jitter_hours = hours;
+        }
+        if let Some(hours) = overrides.timing_aspa_valid_jitter_hours {
+            timing.timing_aspa_valid_jitter_hours = hours;
+        }
+        if let Some(hours) = overrides.timing_renew_spread_hours {
+            timing.timing_renew_spread_hours = hours;
+        }
+        timing
+    }
+
+    /// Returns a copy of this configuration, with the validity of child
+    /// certificates capped to the given maximum. If needed the re-issue
+    /// threshold is reduced to half of this maximum.
+    pub fn with_max_child_certificate_valid_weeks(&self, max: u32) -> Self {
+        let mut timing = self.clone();
+        if timing.timing_child_certificate_valid_weeks > max {
+            timing.timing_child_certificate_valid_weeks = max;
+        }
+        if timing.timing_child_certificate_reissue_weeks_before >= timing.timing_child_certificate_valid_weeks {
+            timing.timing_child_certificate_reissue_weeks_before = timing.timing_child_certificate_valid_weeks / 2;
+        }
+        timing
+    }
+
     //-- Publishing Manifests and CRLs
 
     /// Returns the next update time based on configuration:
@@ -588,14 +1074,8 @@ impl IssuanceTimingConfig {
     /// defaults: now + 24 hours + 0 to 4 hours
     pub fn publish_next(&self) -> Time {
         let regular_mins = self.timing_publish_next_hours as i64 * 60;
-        let random_mins = if self.timing_publish_next_jitter_hours == 0 {
-            0
-        } else {
-            use rand::Rng;
-            let mut rng = rand::thread_rng();
-            rng.gen_range(0..(60 * self.timing_publish_next_jitter_hours))
-        } as i64;
-        Time::now() + Duration::minutes(regular_mins + random_mins)
+        let random_mins = Self::jitter_minutes(self.timing_publish_next_jitter_hours);
+        clock::now() + Duration::minutes(regular_mins + random_mins)
     }
 
     /// Returns the number of hours before expiry that should trigger that
@@ -604,6 +1084,52 @@ impl IssuanceTimingConfig {
         self.timing_publish_hours_before_next.into()
     }
 
+    //-- Jitter and spread
+
+    /// Returns a random number of minutes up to the given number of hours.
+    fn jitter_minutes(hours: u32) -> i64 {
+        if hours == 0 {
+            0
+        } else {
+            use rand::Rng;
+            let mut rng = rand::thread_rng();
+            rng.gen_range(0..(60 * hours)) as i64
+        }
+    }
+
+    /// Returns a validity period from 5 minutes ago, to the given number of
+    /// weeks plus a random number of minutes up to the jitter hours from now.
+    /// The jitter ensures that objects issued at the same time do not also
+    /// expire at the same time.
+    fn validity_with_jitter(weeks: u32, jitter_hours: u32) -> Validity {
+        let until =
+            clock::now() + Duration::weeks(weeks.into()) + Duration::minutes(Self::jitter_minutes(jitter_hours));
+        Validity::new(clock::five_minutes_ago(), until)
+    }
+
+    /// Returns how much earlier than the re-issue threshold the object with
+    /// the given key should be renewed: up to timing_renew_spread_hours.
+    ///
+    /// This is derived from the key rather than random, so that objects
+    /// which expire at the same time are renewed in different runs of the
+    /// scheduler, while each object is renewed once.
+    pub fn renew_spread(&self, key: &impl Hash) -> Duration {
+        if self.timing_renew_spread_hours == 0 {
+            Duration::zero()
+        } else {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            let minutes = hasher.finish() % (60 * u64::from(self.timing_renew_spread_hours));
+            Duration::minutes(minutes as i64)
+        }
+    }
+
+    /// Returns whether an object with the given key and expiry time is due
+    /// for renewal given the threshold, taking the renew spread into account.
+    pub fn renew_due(&self, key: &impl Hash, expires: Time, threshold: Time) -> bool {
+        expires < threshold + self.renew_spread(key)
+    }
+
     //-- Child Cert
 
     /// Validity period for newly issued child certificates
@@ -613,42 +1139,42 @@ impl IssuanceTimingConfig {
 
     /// Not after time for newly issued child certificates
     pub fn new_child_cert_not_after(&self) -> Time {
-        Time::now() + Duration::weeks(self.timing_child_certificate_valid_weeks.into())
+        clock::now() + Duration::weeks(self.timing_child_certificate_valid_weeks.into())
     }
 
     /// Threshold time for issuing new child certificates
     ///
     /// i.e. certificates with a not after time *before* this moment should be re-issued.
     pub fn new_child_cert_issuance_threshold(&self) -> Time {
-        Time::now() + Duration::weeks(self.timing_child_certificate_reissue_weeks_before.into())
+        clock::now() + Duration::weeks(self.timing_child_certificate_reissue_weeks_before.into())
     }
 
     //-- ROAs
 
     /// Validity period for new ROA objects
     pub fn new_roa_validity(&self) -> Validity {
-        SignSupport::sign_validity_weeks(self.timing_roa_valid_weeks.into())
+        Self::validity_with_jitter(self.timing_roa_valid_weeks, self.timing_roa_valid_jitter_hours)
     }
 
     /// Threshold time for issuing new ROA objects
     ///
     /// i.e. ROA objects with a not after time *before* this moment should be re-issued.
     pub fn new_roa_issuance_threshold(&self) -> Time {
-        Time::now() + Duration::weeks(self.timing_roa_reissue_weeks_before.into())
+        clock::now() + Duration::weeks(self.timing_roa_reissue_weeks_before.into())
     }
 
     //-- ASPA
 
     /// Validity period for new ASPA objects
     pub fn new_aspa_validity(&self) -> Validity {
-        SignSupport::sign_validity_weeks(self.timing_aspa_valid_weeks.into())
+        Self::validity_with_jitter(self.timing_aspa_valid_weeks, self.timing_aspa_valid_jitter_hours)
     }
 
     /// Threshold time for issuing new ASPA objects
     ///
     /// i.e. ASPA objects with a not after time *before* this moment should be re-issued.
     pub fn new_aspa_issuance_threshold(&self) -> Time {
-        Time::now() + Duration::weeks(self.timing_aspa_reissue_weeks_before.into())
+        clock::now() + Duration::weeks(self.timing_aspa_reissue_weeks_before.into())
     }
 
     //-- BGPSec
@@ -662,7 +1188,7 @@ impl IssuanceTimingConfig {
     ///
     /// i.e. certs with a not after time *before* this moment should be re-issued.
     pub fn new_bgpsec_issuance_threshold(&self) -> Time {
-        Time::now() + Duration::weeks(self.timing_bgpsec_reissue_weeks_before.into())
+        clock::now() + Duration::weeks(self.timing_bgpsec_reissue_weeks_before.into())
     }
 }
 
@@ -680,6 +1206,14 @@ pub struct RrdpUpdatesConfig {
     pub rrdp_delta_interval_min_seconds: u32,
     #[serde(default = "RrdpUpdatesConfig::dflt_rrdp_files_archive")]
     pub rrdp_files_archive: bool,
+    #[serde(default = "RrdpUpdatesConfig::dflt_rrdp_delta_files_max_size_percentage")]
+    pub rrdp_delta_files_max_size_percentage: u32,
+    #[serde(default)]
+    pub rrdp_session_reset_interval_hours: Option<u32>,
+    #[serde(default = "RrdpUpdatesConfig::dflt_rrdp_files_gzip")]
+    pub rrdp_files_gzip: bool,
+    #[serde(default = "RrdpUpdatesConfig::dflt_rrdp_files_brotli")]
+    pub rrdp_files_brotli: bool,
 }
 
 impl RrdpUpdatesConfig {
@@ -724,27 +1258,253 @@ impl RrdpUpdatesConfig {
     fn dflt_rrdp_files_archive() -> bool {
         false
     }
-}
-
-#[derive(Clone, Debug, Deserialize)]
-pub struct MetricsConfig {
-    #[serde(default)] // false
-    pub metrics_hide_ca_details: bool,
-    #[serde(default)] // false
-    pub metrics_hide_child_details: bool,
-    #[serde(default)] // false
-    pub metrics_hide_publisher_details: bool,
-    #[serde(default)] // false
-    pub metrics_hide_roa_details: bool,
-}
 
-#[derive(Clone, Debug, Deserialize)]
-pub struct TestBed {
-    ta_aia: uri::Rsync,
-    ta_uri: uri::Https,
-    rrdp_base_uri: uri::Https,
-    rsync_jail: uri::Rsync,
-}
+    // Never keep deltas with a total size of more than this percentage
+    // of the snapshot size. Defaults to 100, as RFC 8182 requires.
+    fn dflt_rrdp_delta_files_max_size_percentage() -> u32 {
+        100
+    }
+
+    // If set to true, we will also write gzip compressed variants of
+    // the notification, snapshot and delta files when they are written,
+    // so that they can be served to clients that accept gzip without
+    // having to compress them for each request.
+    fn dflt_rrdp_files_gzip() -> bool {
+        false
+    }
+
+    // If set to true, we will also write brotli compressed variants of
+    // the RRDP files. These are smaller than the gzip variants, and are
+    // preferred for clients that accept both.
+    fn dflt_rrdp_files_brotli() -> bool {
+        false
+    }
+
+    /// Returns the compressed variants to write for the RRDP files.
+    pub fn rrdp_files_compressions(&self) -> Vec<Compression> {
+        let mut compressions = vec![];
+        if self.rrdp_files_gzip {
+            compressions.push(Compression::Gzip);
+        }
+        if self.rrdp_files_brotli {
+            compressions.push(Compression::Brotli);
+        }
+        compressions
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct SnapshotConfig {
+    #[serde(default = "SnapshotConfig::dflt_snapshot_events")]
+    pub snapshot_events: u64,
+    #[serde(default = "SnapshotConfig::dflt_snapshot_interval_hours")]
+    pub snapshot_interval_hours: u32,
+    #[serde(default)] // 0
+    pub snapshot_retention: usize,
+}
+
+impl SnapshotConfig {
+    // Save a snapshot of a CA or the Publication Server whenever there
+    // are new events (default). Higher values mean that fewer snapshots
+    // are written, at the cost of replaying up to this many events when
+    // the state is loaded.
+    fn dflt_snapshot_events() -> u64 {
+        1
+    }
+
+    // Save snapshots for all CAs and the Publication Server which have
+    // events since their last snapshot once a day (default).
+    fn dflt_snapshot_interval_hours() -> u32 {
+        24
+    }
+
+    pub fn policy(&self) -> SnapshotPolicy {
+        SnapshotPolicy {
+            events: self.snapshot_events,
+            retention: self.snapshot_retention,
+        }
+    }
+}
+
+/// Determines when a CA tries again to contact a parent or its repository
+/// after consecutive failures. The first retry happens after the initial
+/// delay, which is multiplied by the backoff factor for each further failure
+/// up to the maximum delay. When the maximum number of attempts has been
+/// reached the CA only tries again at its regular refresh.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct RetryConfig {
+    #[serde(default = "RetryConfig::dflt_initial_delay_seconds")]
+    pub initial_delay_seconds: u32,
+    #[serde(default = "RetryConfig::dflt_backoff_factor")]
+    pub backoff_factor: u32,
+    #[serde(default = "RetryConfig::dflt_max_delay_seconds")]
+    pub max_delay_seconds: u32,
+    #[serde(default)] // unlimited
+    pub max_attempts: Option<u32>,
+    #[serde(default)] // 0
+    pub jitter_seconds: u32,
+    #[serde(default)] // use post_protocol_msg_timeout_seconds
+    pub timeout_seconds: Option<u64>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            initial_delay_seconds: Self::dflt_initial_delay_seconds(),
+            backoff_factor: Self::dflt_backoff_factor(),
+            max_delay_seconds: Self::dflt_max_delay_seconds(),
+            max_attempts: None,
+            jitter_seconds: 0,
+            timeout_seconds: None,
+        }
+    }
+}
+
+impl RetryConfig {
+    // Retry after 5 minutes (default).
+    fn dflt_initial_delay_seconds() -> u32 {
+        SCHEDULER_REQUEUE_DELAY_SECONDS as u32
+    }
+
+    // Do not increase the delay between retries (default).
+    fn dflt_backoff_factor() -> u32 {
+        1
+    }
+
+    // Never wait more than 1 hour between retries (default), this
+    // only matters if the backoff factor is increased.
+    fn dflt_max_delay_seconds() -> u32 {
+        3600
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        if self.initial_delay_seconds < 1 {
+            return Err(String::from("initial_delay_seconds must be 1 or higher"));
+        }
+
+        if self.backoff_factor < 1 {
+            return Err(String::from("backoff_factor must be 1 or higher"));
+        }
+
+        if self.max_delay_seconds < self.initial_delay_seconds {
+            return Err(String::from(
+                "max_delay_seconds must not be smaller than initial_delay_seconds",
+            ));
+        }
+
+        if self.max_attempts == Some(0) {
+            return Err(String::from("max_attempts must be 1 or higher"));
+        }
+
+        if self.timeout_seconds == Some(0) {
+            return Err(String::from("timeout_seconds must be 1 or higher"));
+        }
+
+        Ok(())
+    }
+
+    /// Returns a copy of this configuration, with the given CA specific
+    /// overrides applied.
+    pub fn with_overrides(&self, overrides: &RetryPolicy) -> Self {
+        let mut retry = self.clone();
+        if let Some(seconds) = overrides.initial_delay_seconds {
+            retry.initial_delay_seconds = seconds;
+        }
+        if let Some(factor) = overrides.backoff_factor {
+            retry.backoff_factor = factor;
+        }
+        if let Some(seconds) = overrides.max_delay_seconds {
+            retry.max_delay_seconds = seconds;
+        }
+        if let Some(attempts) = overrides.max_attempts {
+            retry.max_attempts = Some(attempts);
+        }
+        if let Some(seconds) = overrides.jitter_seconds {
+            retry.jitter_seconds = seconds;
+        }
+        if let Some(seconds) = overrides.timeout_seconds {
+            retry.timeout_seconds = Some(seconds);
+        }
+        retry
+    }
+
+    /// Returns the delay in seconds, without jitter, before the next attempt
+    /// after the given number of consecutive failures. Returns None if the
+    /// maximum number of attempts has been reached.
+    pub fn delay_seconds(&self, failures: u32) -> Option<u32> {
+        if let Some(max_attempts) = self.max_attempts {
+            if failures >= max_attempts {
+                return None;
+            }
+        }
+
+        let mut delay = self.initial_delay_seconds;
+        for _ in 1..failures {
+            if delay >= self.max_delay_seconds {
+                break;
+            }
+            delay = delay.saturating_mul(self.backoff_factor);
+        }
+
+        Some(delay.min(self.max_delay_seconds))
+    }
+
+    /// Returns the delay in seconds before the next attempt, including a
+    /// random jitter, or None if the maximum number of attempts has been
+    /// reached.
+    pub fn retry_after_seconds(&self, failures: u32) -> Option<u32> {
+        self.delay_seconds(failures).map(|delay| {
+            if self.jitter_seconds == 0 {
+                delay
+            } else {
+                use rand::Rng;
+                let mut rng = rand::thread_rng();
+                delay.saturating_add(rng.gen_range(0..self.jitter_seconds))
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MetricsConfig {
+    #[serde(default)] // false
+    pub metrics_hide_ca_details: bool,
+    #[serde(default)] // false
+    pub metrics_hide_child_details: bool,
+    #[serde(default)] // false
+    pub metrics_hide_publisher_details: bool,
+    #[serde(default)] // false
+    pub metrics_hide_roa_details: bool,
+    #[serde(default)] // false
+    pub metrics_show_ca_extended_details: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TestBed {
+    ta_aia: uri::Rsync,
+    ta_uri: uri::Https,
+    rrdp_base_uri: uri::Https,
+    rsync_jail: uri::Rsync,
+
+    /// The resources which children may claim when they register. If not
+    /// set, they can claim any resources.
+    #[serde(default)]
+    child_resources: Option<ResourceSet>,
+
+    /// The maximum number of ASN, IPv4 and IPv6 blocks a child may claim.
+    #[serde(default)]
+    child_max_blocks: Option<usize>,
+
+    /// Remove children and publishers which did not contact the testbed for
+    /// this many days.
+    #[serde(default)]
+    remove_inactive_after_days: Option<u32>,
+
+    /// The token which must be given when registering or removing children
+    /// and publishers. If not set, anyone can do this.
+    #[serde(default)]
+    registration_token: Option<Token>,
+}
 
 impl TestBed {
     pub fn new(ta_aia: uri::Rsync, ta_uri: uri::Https, rrdp_base_uri: uri::Https, rsync_jail: uri::Rsync) -> Self {
@@ -753,6 +1513,10 @@ impl TestBed {
             ta_uri,
             rrdp_base_uri,
             rsync_jail,
+            child_resources: None,
+            child_max_blocks: None,
+            remove_inactive_after_days: None,
+            registration_token: None,
         }
     }
 
@@ -767,6 +1531,37 @@ impl TestBed {
     pub fn publication_server_uris(&self) -> PublicationServerUris {
         PublicationServerUris::new(self.rrdp_base_uri.clone(), self.rsync_jail.clone())
     }
+
+    /// Checks that a child may claim the resources, returns the reason if
+    /// it may not.
+    pub fn check_child_resources(&self, resources: &ResourceSet) -> Result<(), String> {
+        if let Some(allowed) = &self.child_resources {
+            if !allowed.contains(resources) {
+                return Err(format!("only resources within '{}' may be claimed", allowed));
+            }
+        }
+
+        if let Some(max) = self.child_max_blocks {
+            let summary = ResourceSetSummary::from(resources);
+            let blocks = summary.asn_blocks() + summary.ipv4_blocks() + summary.ipv6_blocks();
+            if blocks > max {
+                return Err(format!(
+                    "at most {} resource blocks may be claimed, not {}",
+                    max, blocks
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn remove_inactive_after_seconds(&self) -> Option<i64> {
+        self.remove_inactive_after_days.map(|days| i64::from(days) * 24 * 3600)
+    }
+
+    pub fn registration_token(&self) -> Option<&Token> {
+        self.registration_token.as_ref()
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -775,6 +1570,32 @@ pub struct Benchmark {
     pub ca_roas: usize,
 }
 
+/// A Unix domain socket on which the API is served without authentication.
+/// Access is controlled by the file system permissions of the socket, so
+/// every local user who can connect to it has admin rights.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct UnixSocketConfig {
+    pub path: PathBuf,
+
+    /// The permissions of the socket file, as an octal string, e.g. "660".
+    #[serde(default = "UnixSocketConfig::dflt_mode")]
+    pub mode: String,
+}
+
+impl UnixSocketConfig {
+    fn dflt_mode() -> String {
+        "600".to_string()
+    }
+
+    /// Returns the permissions of the socket file.
+    pub fn permissions(&self) -> Result<u32, String> {
+        match u32::from_str_radix(&self.mode, 8) {
+            Ok(mode) if mode <= 0o777 => Ok(mode),
+            _ => Err(format!("invalid unix_socket.mode: {}", self.mode)),
+        }
+    }
+}
+
 /// # Accessors
 impl Config {
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
@@ -853,14 +1674,27 @@ impl Config {
         }
     }
 
-    pub fn requeue_remote_failed(&self) -> Priority {
+    /// Get the priority for the next attempt to contact a parent or repository
+    /// after the given number of consecutive failures, based on the retry
+    /// config. Falls back to the next regular CA refresh when the maximum
+    /// number of attempts has been reached.
+    pub fn requeue_remote_failed(&self, retry: &RetryConfig, failures: u32) -> Priority {
         if test_mode_enabled() {
             in_seconds(5)
         } else {
-            in_seconds(SCHEDULER_REQUEUE_DELAY_SECONDS)
+            match retry.retry_after_seconds(failures) {
+                Some(seconds) => in_seconds(seconds.into()),
+                None => self.ca_refresh_next(),
+            }
         }
     }
 
+    /// Returns the timeout for RFC 6492 and RFC 8181 exchanges, using the
+    /// timeout from the retry config if it is set.
+    pub fn protocol_msg_timeout_seconds(&self, retry: &RetryConfig) -> u64 {
+        retry.timeout_seconds.unwrap_or(self.post_protocol_msg_timeout_seconds)
+    }
+
     /// Get the priority for the next CA refresh based on the configured
     /// ca_refresh_seconds (1 day), and jitter (12 hours)
     pub fn ca_refresh_next(&self) -> Priority {
@@ -906,6 +1740,33 @@ impl Config {
     pub fn upgrade_data_dir(&self) -> PathBuf {
         self.data_dir.join("upgrade-data")
     }
+
+    /// Returns the storage for the CA and Publication Server aggregates and
+    /// the repository content.
+    pub fn storage(&self) -> KeyValueStorage {
+        match &self.storage_uri {
+            Some(postgres) => KeyValueStorage::Postgres(postgres.clone()),
+            None => KeyValueStorage::Disk(self.data_dir.clone()),
+        }
+    }
+
+    /// Returns the storage for the objects published by CAs. These are kept
+    /// in the shared storage in high availability mode, so that a standby can
+    /// take over publishing. Otherwise they are kept in the data_dir.
+    pub fn ca_objects_storage(&self) -> KeyValueStorage {
+        if self.ha.is_some() {
+            self.storage()
+        } else {
+            KeyValueStorage::Disk(self.data_dir.clone())
+        }
+    }
+
+    /// Returns the storage for the signer mapper, serial numbers and the keys
+    /// of OpenSSL signers without a keys_path. These must be shared in high
+    /// availability mode, or a promoted standby would be unable to sign.
+    pub fn signer_storage(&self) -> KeyValueStorage {
+        self.ca_objects_storage()
+    }
 }
 
 /// # Create
@@ -927,22 +1788,37 @@ impl Config {
         let data_dir = data_dir.to_path_buf();
         let data_dir_use_lock = true; // ensure we touch this in tests
         let always_recover_data = false;
+        let allow_downgrade = false;
 
         let log_level = LevelFilter::Debug;
         let log_type = LogType::Stderr;
+        let log_format = LogFormat::Text;
         let mut log_file = data_dir.clone();
         log_file.push("krill.log");
         let syslog_facility = ConfigDefaults::syslog_facility();
         let auth_type = AuthType::AdminToken;
+        #[cfg(feature = "multi-user")]
+        let auth_additional_types = vec![];
         let admin_token = Token::from("secret");
+        let auth_audit_log = true;
         #[cfg(feature = "multi-user")]
         let auth_policies = vec![];
         #[cfg(feature = "multi-user")]
         let auth_private_attributes = vec![];
         #[cfg(feature = "multi-user")]
+        let auth_roles = ConfigAuthRoles::new();
+        #[cfg(feature = "multi-user")]
+        let auth_orgs = ConfigAuthOrgs::new();
+        #[cfg(feature = "multi-user")]
         let auth_users = None;
         #[cfg(feature = "multi-user")]
         let auth_openidconnect = None;
+        #[cfg(feature = "multi-user")]
+        let auth_ldap = None;
+        #[cfg(feature = "multi-user")]
+        let auth_jwt = None;
+        #[cfg(feature = "multi-user")]
+        let auth_client_certs = None;
 
         let default_signer = SignerReference::default();
         let one_off_signer = SignerReference::default();
@@ -981,6 +1857,7 @@ impl Config {
         let bgp_risdumps_enabled = false;
         let bgp_risdumps_v4_uri = ConfigDefaults::bgp_risdumps_v4_uri();
         let bgp_risdumps_v6_uri = ConfigDefaults::bgp_risdumps_v6_uri();
+        let roa_update_bgp_check = false;
 
         let roa_aggregate_threshold = 3;
         let roa_deaggregate_threshold = 2;
@@ -997,6 +1874,9 @@ impl Config {
         let timing_aspa_reissue_weeks_before = ConfigDefaults::timing_aspa_reissue_weeks_before();
         let timing_bgpsec_valid_weeks = ConfigDefaults::timing_bgpsec_valid_weeks();
         let timing_bgpsec_reissue_weeks_before = ConfigDefaults::timing_bgpsec_reissue_weeks_before();
+        let timing_roa_valid_jitter_hours = ConfigDefaults::timing_roa_valid_jitter_hours();
+        let timing_aspa_valid_jitter_hours = ConfigDefaults::timing_aspa_valid_jitter_hours();
+        let timing_renew_spread_hours = ConfigDefaults::timing_renew_spread_hours();
 
         let issuance_timing = IssuanceTimingConfig {
             timing_publish_next_hours,
@@ -1006,10 +1886,13 @@ impl Config {
             timing_child_certificate_reissue_weeks_before,
             timing_roa_valid_weeks,
             timing_roa_reissue_weeks_before,
+            timing_roa_valid_jitter_hours,
             timing_aspa_valid_weeks,
             timing_aspa_reissue_weeks_before,
+            timing_aspa_valid_jitter_hours,
             timing_bgpsec_valid_weeks,
             timing_bgpsec_reissue_weeks_before,
+            timing_renew_spread_hours,
         };
 
         let rrdp_updates_config = RrdpUpdatesConfig {
@@ -1019,6 +1902,16 @@ impl Config {
             rrdp_delta_files_max_nr: 50,
             rrdp_delta_interval_min_seconds: 0,
             rrdp_files_archive: false,
+            rrdp_delta_files_max_size_percentage: 100,
+            rrdp_session_reset_interval_hours: None,
+            rrdp_files_gzip: false,
+            rrdp_files_brotli: false,
+        };
+
+        let snapshots = SnapshotConfig {
+            snapshot_events: 1,
+            snapshot_interval_hours: 24,
+            snapshot_retention: 0,
         };
 
         let metrics = MetricsConfig {
@@ -1026,6 +1919,7 @@ impl Config {
             metrics_hide_child_details: false,
             metrics_hide_publisher_details: false,
             metrics_hide_roa_details: false,
+            metrics_show_ca_extended_details: false,
         };
 
         let testbed = if enable_testbed {
@@ -1045,30 +1939,57 @@ impl Config {
             ip,
             port,
             https_mode,
+            tls: TlsConfig::default(),
+            acme: None,
+            unix_socket: None,
+            rate_limit: None,
             data_dir,
             data_dir_use_lock,
+            storage_uri: None,
+            ha: None,
+            telemetry: None,
+            time_check: None,
             ta_support_enabled: false, // but, enabled by testbed where applicable
             ta_signer_enabled: false,  // same as above
             always_recover_data,
+            allow_downgrade,
+            snapshots,
+            history_archive_days: None,
             pid_file,
             service_uri: None,
             log_level,
             log_type,
+            log_format,
             log_file,
             syslog_facility,
             admin_token,
             auth_type,
             #[cfg(feature = "multi-user")]
+            auth_additional_types,
+            auth_audit_log,
+            #[cfg(feature = "multi-user")]
             auth_policies,
             #[cfg(feature = "multi-user")]
             auth_private_attributes,
             #[cfg(feature = "multi-user")]
+            auth_roles,
+            #[cfg(feature = "multi-user")]
+            auth_orgs,
+            #[cfg(feature = "multi-user")]
             auth_users,
             #[cfg(feature = "multi-user")]
             auth_openidconnect,
+            #[cfg(feature = "multi-user")]
+            auth_ldap,
+            #[cfg(feature = "multi-user")]
+            auth_jwt,
+            #[cfg(feature = "multi-user")]
+            auth_client_certs,
             default_signer,
             one_off_signer,
             signers,
+            ca_key_algorithm: KeyAlgorithm::default(),
+            serial_number_strategy: SerialNumberStrategy::default(),
             signer_probe_retry_seconds,
             ca_refresh_seconds,
             ca_refresh_jitter_seconds,
@@ -1081,16 +2002,41 @@ impl Config {
             post_limit_rfc6492,
             rfc6492_log_dir,
             post_protocol_msg_timeout_seconds,
+            retry_parent: RetryConfig::default(),
+            retry_repository: RetryConfig::default(),
             bgp_risdumps_enabled,
             bgp_risdumps_v4_uri,
             bgp_risdumps_v6_uri,
+            roa_update_bgp_check,
             roa_aggregate_threshold,
             roa_deaggregate_threshold,
+            roa_issuance_strategy: RoaIssuanceStrategy::default(),
+            roa_autopilot: HashMap::new(),
+            publication_check_interval_minutes: None,
+            publication_check_rsync: false,
+            publication_confirm_lag_minutes: None,
+            reconcile_interval_minutes: None,
+            reconcile_auto_repair: false,
+            expiry_window_hours: ConfigDefaults::expiry_window_hours(),
+            alerts: AlertsConfig::default(),
             issuance_timing,
+            republish_workers: ConfigDefaults::republish_workers(),
             rrdp_updates_config,
+            publisher_quota: PublisherQuota::default(),
+            publisher_validation: PublisherValidation::default(),
+            rsyncd_conf: None,
+            repository_archive: None,
+            repository_webhooks: vec![],
+            repository_replica: None,
+            repository_s3: None,
+            public_status_cas: vec![],
+            // The functional tests fetch parent responses from the test server
+            parent_response_fetch_hosts: vec!["localhost".to_string()],
             metrics,
             testbed,
             benchmark: None,
+            config_file: None,
+            file_settings: toml::value::Table::new(),
         }
     }
 
@@ -1153,6 +2099,95 @@ impl Config {
         Ok(())
     }
 
+    /// Reads the config file again, and returns the config to use from now
+    /// on together with a report of the changed settings. Only the settings
+    /// which can be changed while Krill is running are taken from the file,
+    /// all other settings keep their current value until Krill is restarted.
+    pub fn reload(&self) -> Result<(Self, ConfigReloadReport), ConfigError> {
+        let config_file = self
+            .config_file
+            .as_ref()
+            .ok_or_else(|| ConfigError::other("Krill was not started with a config file"))?;
+        let config_file = config_file.to_string_lossy();
+
+        let mut reloaded = Self::read_config(&config_file)?;
+        reloaded
+            .process()
+            .map_err(|e| ConfigError::Other(format!("Error parsing config file: {}, error: {}", config_file, e)))?;
+
+        let mut names: Vec<&String> = self.file_settings.keys().chain(reloaded.file_settings.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        let mut config = self.clone();
+        let mut applied = vec![];
+        let mut restart_required = vec![];
+
+        for name in names {
+            let value = reloaded.file_settings.get(name);
+            if self.file_settings.get(name) == value {
+                continue;
+            }
+
+            if Self::is_reloadable_setting(name) {
+                // Remember the new value, so that it is not reported again
+                // on the next reload.
+                match value {
+                    Some(value) => config.file_settings.insert(name.clone(), value.clone()),
+                    None => config.file_settings.remove(name),
+                };
+                applied.push(name.clone());
+            } else {
+                restart_required.push(name.clone());
+            }
+        }
+
+        config.log_level = reloaded.log_level;
+        config.issuance_timing = reloaded.issuance_timing;
+        config.roa_aggregate_threshold = reloaded.roa_aggregate_threshold;
+        config.roa_deaggregate_threshold = reloaded.roa_deaggregate_threshold;
+        config.roa_issuance_strategy = reloaded.roa_issuance_strategy;
+        config.roa_autopilot = reloaded.roa_autopilot;
+        config.expiry_window_hours = reloaded.expiry_window_hours;
+        config.metrics = reloaded.metrics;
+        config.public_status_cas = reloaded.public_status_cas;
+        config.parent_response_fetch_hosts = reloaded.parent_response_fetch_hosts;
+        config.post_limit_api = reloaded.post_limit_api;
+        config.post_limit_rfc8181 = reloaded.post_limit_rfc8181;
+        config.post_limit_rfc6492 = reloaded.post_limit_rfc6492;
+        config.post_protocol_msg_timeout_seconds = reloaded.post_protocol_msg_timeout_seconds;
+        config.retry_parent = reloaded.retry_parent;
+        config.retry_repository = reloaded.retry_repository;
+        config.bgp_risdumps_enabled = reloaded.bgp_risdumps_enabled;
+        config.bgp_risdumps_v4_uri = reloaded.bgp_risdumps_v4_uri;
+        config.bgp_risdumps_v6_uri = reloaded.bgp_risdumps_v6_uri;
+
+        Ok((config, ConfigReloadReport::new(applied, restart_required)))
+    }
+
+    /// Returns whether a setting is always read when it is used, so that a
+    /// changed value can be applied without restarting Krill.
+    fn is_reloadable_setting(name: &str) -> bool {
+        name.starts_with("timing_")
+            || name.starts_with("metrics_")
+            || name.starts_with("post_limit_")
+            || name.starts_with("bgp_risdumps_")
+            || matches!(
+                name,
+                "log_level"
+                    | "roa_aggregate_threshold"
+                    | "roa_deaggregate_threshold"
+                    | "roa_issuance_strategy"
+                    | "roa_autopilot"
+                    | "expiry_window_hours"
+                    | "public_status_cas"
+                    | "parent_response_fetch_hosts"
+                    | "post_protocol_msg_timeout_seconds"
+                    | "retry_parent"
+                    | "retry_repository"
+            )
+    }
+
     fn fix(&mut self) {
         if self.ca_refresh_seconds < CA_REFRESH_SECONDS_MIN {
             warn!(
@@ -1176,6 +2211,22 @@ impl Config {
             warn!("The value for 'ca_refresh_jitter_seconds' exceeded 50% of 'ca_refresh_seconds'. Changing it to {} seconds", half_refresh);
             self.ca_refresh_jitter_seconds = half_refresh;
         }
+
+        // The ASPA timing values were not verified by earlier versions of
+        // Krill, so fall back to the defaults rather than refusing to start
+        // with an existing config file.
+        let timing = &mut self.issuance_timing;
+        if timing.timing_aspa_valid_weeks < 2
+            || timing.timing_aspa_reissue_weeks_before < 1
+            || timing.timing_aspa_reissue_weeks_before >= timing.timing_aspa_valid_weeks
+        {
+            timing.timing_aspa_valid_weeks = ConfigDefaults::timing_aspa_valid_weeks();
+            timing.timing_aspa_reissue_weeks_before = ConfigDefaults::timing_aspa_reissue_weeks_before();
+            warn!(
+                "The values for 'timing_aspa_valid_weeks' and 'timing_aspa_reissue_weeks_before' were inconsistent, changing them to {} and {} weeks",
+                timing.timing_aspa_valid_weeks, timing.timing_aspa_reissue_weeks_before
+            );
+        }
     }
 
     fn resolve(&mut self) {
@@ -1240,137 +2291,314 @@ impl Config {
             }
         }
 
-        if self.issuance_timing.timing_publish_next_hours < 2 {
-            return Err(ConfigError::other("timing_publish_next_hours must be at least 2"));
+        self.issuance_timing.verify().map_err(ConfigError::Other)?;
+        for warning in self.issuance_timing.warnings() {
+            warn!("Issuance timing: {}", warning);
         }
 
-        if self.issuance_timing.timing_publish_next_jitter_hours > (self.issuance_timing.timing_publish_next_hours / 2)
-        {
-            return Err(ConfigError::other(
-                "timing_publish_next_jitter_hours must be at most timing_publish_next_hours divided by 2",
-            ));
-        }
+        self.retry_parent
+            .verify()
+            .map_err(|msg| ConfigError::Other(format!("retry_parent: {}", msg)))?;
+        self.retry_repository
+            .verify()
+            .map_err(|msg| ConfigError::Other(format!("retry_repository: {}", msg)))?;
 
-        if self.issuance_timing.timing_publish_hours_before_next < 1 {
-            return Err(ConfigError::other(
-                "timing_publish_hours_before_next must be at least 1",
-            ));
+        if self.republish_workers < 1 {
+            return Err(ConfigError::other("republish_workers must be 1 or higher"));
         }
 
-        if self.issuance_timing.timing_publish_hours_before_next >= self.issuance_timing.timing_publish_next_hours {
-            return Err(ConfigError::other(
-                "timing_publish_hours_before_next must be smaller than timing_publish_hours",
-            ));
-        }
+        self.roa_issuance_strategy
+            .verify()
+            .map_err(|msg| ConfigError::Other(format!("roa_issuance_strategy: {}", msg)))?;
 
-        if self.issuance_timing.timing_child_certificate_valid_weeks < 2 {
-            return Err(ConfigError::other(
-                "timing_child_certificate_valid_weeks must be at least 2",
-            ));
+        for (ca, policy) in self.roa_autopilot.iter() {
+            policy
+                .verify()
+                .map_err(|msg| ConfigError::Other(format!("roa_autopilot.{}: {}", ca, msg)))?;
         }
 
-        if self.issuance_timing.timing_child_certificate_reissue_weeks_before < 1 {
+        let max_size_percentage = self.rrdp_updates_config.rrdp_delta_files_max_size_percentage;
+        if !(1..=100).contains(&max_size_percentage) {
             return Err(ConfigError::other(
-                "timing_child_certificate_reissue_weeks_before must be at least 1",
+                "rrdp_delta_files_max_size_percentage must be between 1 and 100",
             ));
         }
 
-        if self.issuance_timing.timing_child_certificate_reissue_weeks_before
-            >= self.issuance_timing.timing_child_certificate_valid_weeks
-        {
-            return Err(ConfigError::other("timing_child_certificate_reissue_weeks_before must be smaller than timing_child_certificate_valid_weeks"));
-        }
-
-        if self.issuance_timing.timing_roa_valid_weeks < 2 {
-            return Err(ConfigError::other("timing_roa_valid_weeks must be at least 2"));
+        if self.snapshots.snapshot_events < 1 {
+            return Err(ConfigError::other("snapshot_events must be 1 or higher"));
         }
 
-        if self.issuance_timing.timing_roa_reissue_weeks_before < 1 {
-            return Err(ConfigError::other("timing_roa_reissue_weeks_before must be at least 1"));
+        if self.snapshots.snapshot_interval_hours < 1 {
+            return Err(ConfigError::other("snapshot_interval_hours must be 1 or higher"));
         }
 
-        if self.issuance_timing.timing_roa_reissue_weeks_before >= self.issuance_timing.timing_roa_valid_weeks {
-            return Err(ConfigError::other(
-                "timing_roa_reissue_weeks_before must be smaller than timing_roa_valid_week",
-            ));
+        if self.history_archive_days == Some(0) {
+            return Err(ConfigError::other("history_archive_days must be 1 or higher"));
         }
 
-        if let Some(threshold) = self.suspend_child_after_inactive_hours {
-            if threshold < CA_SUSPEND_MIN_HOURS {
-                return Err(ConfigError::Other(format!(
-                    "suspend_child_after_inactive_hours must be {} or higher (or not set at all)",
-                    CA_SUSPEND_MIN_HOURS
-                )));
+        if let Some(archive) = &self.repository_archive {
+            if archive.interval_hours == 0 {
+                return Err(ConfigError::other(
+                    "repository_archive.interval_hours must be 1 or higher",
+                ));
             }
         }
 
-        if let Some(benchmark) = &self.benchmark {
-            if self.testbed.is_none() {
-                return Err(ConfigError::other("[benchmark] section requires [testbed] config"));
-            }
-            if benchmark.cas > 65535 {
-                return Err(ConfigError::other("[benchmark] allows only up to 65536 CAs"));
+        if let Some(ha) = &self.ha {
+            if self.storage_uri.is_none() {
+                return Err(ConfigError::other("ha requires that storage_uri is set"));
             }
-            if benchmark.ca_roas > 100 {
-                return Err(ConfigError::other("[benchmark] allows only up to 100 ROAs per CA"));
+            if ha.lease_seconds < 3 {
+                return Err(ConfigError::other("ha.lease_seconds must be 3 or higher"));
             }
         }
 
-        if self.signers.is_empty() {
-            // Since Config.signers defaults via Serde to ConfigDefaults::signers() which creates a vector with a
-            // single signer, this can only happen if we were invoked on a config object created or modified by test
-            // code.
-            return Err(ConfigError::Other("No signers configured".to_string()));
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.verify().map_err(ConfigError::Other)?;
         }
 
-        #[cfg(not(feature = "hsm"))]
-        {
-            fn mk_err_msg(setting_name: &str) -> String {
-                format!("This build of Krill lacks support for the '{}' config file setting. Please use a version of Krill that has the 'hsm' feature enabled.", setting_name)
-            }
+        if let Some(time_check) = &self.time_check {
+            time_check.verify().map_err(ConfigError::Other)?;
+        }
 
-            if self.default_signer.is_named() {
-                return Err(ConfigError::other(&mk_err_msg("default_signer")));
-            }
-            if self.one_off_signer.is_named() {
-                return Err(ConfigError::other(&mk_err_msg("one_off_signer")));
+        self.tls.verify().map_err(ConfigError::Other)?;
+
+        if let Some(acme) = &self.acme {
+            acme.verify().map_err(ConfigError::Other)?;
+            if self.https_mode().is_disable_https() {
+                return Err(ConfigError::other("acme cannot be used if https_mode is \"disable\""));
             }
-            if self.signers != ConfigDefaults::signers() {
-                return Err(ConfigError::other(&mk_err_msg("[[signers]]")));
+            if acme_domain(&self.service_uri()).is_none() {
+                return Err(ConfigError::other(
+                    "acme requires a service_uri with a hostname, other than localhost",
+                ));
             }
         }
 
-        for n in &self.signers {
-            if self.signers.iter().filter(|m| m.name == n.name).count() > 1 {
-                return Err(ConfigError::other(&format!("Signer name '{}' is not unique", n.name)));
+        if let Some(unix_socket) = &self.unix_socket {
+            unix_socket.permissions().map_err(ConfigError::Other)?;
+        }
+
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.verify().map_err(ConfigError::Other)?;
+        }
+
+        if let Some(replica) = &self.repository_replica {
+            if replica.interval_seconds == 0 {
+                return Err(ConfigError::other(
+                    "repository_replica.interval_seconds must be 1 or higher",
+                ));
             }
         }
 
-        if self.signers.len() > 1 && !self.default_signer.is_set() {
+        if self.rrdp_updates_config.rrdp_session_reset_interval_hours == Some(0) {
             return Err(ConfigError::other(
-                "'default_signer' must be set when more than one [[signers]] configuration is defined",
+                "rrdp_session_reset_interval_hours must be 1 or higher (or not set at all)",
             ));
         }
 
-        if self.default_signer.is_named() {
-            if self.find_signer_reference(&self.default_signer).is_none() {
-                return Err(ConfigError::other(&format!(
-                    "'{}' cannot be used as the 'default_signer' as no signer with that name is defined",
-                    self.default_signer.name()
+        if !self.ca_key_algorithm.public_key_format().allow_rpki_cert() {
+            return Err(ConfigError::Other(format!(
+                "ca_key_algorithm \"{}\" cannot be used for RPKI resource certificates, which must use RSA keys (RFC 7935)",
+                self.ca_key_algorithm
+            )));
+        }
+
+        if let Some(interval) = self.publication_check_interval_minutes {
+            if interval < PUBLICATION_CHECK_MIN_INTERVAL_MINS {
+                return Err(ConfigError::Other(format!(
+                    "publication_check_interval_minutes must be {} or higher (or not set at all)",
+                    PUBLICATION_CHECK_MIN_INTERVAL_MINS
                 )));
             }
-        } else {
         }
 
-        if self.one_off_signer.is_named() && self.find_signer_reference(&self.one_off_signer).is_none() {
-            return Err(ConfigError::other(&format!(
-                "'{}' cannot be used as the 'one_off_signer' as no signer with that name is defined",
-                self.one_off_signer.name()
-            )));
+        if let Some(interval) = self.reconcile_interval_minutes {
+            if interval < RECONCILE_MIN_INTERVAL_MINS {
+                return Err(ConfigError::Other(format!(
+                    "reconcile_interval_minutes must be {} or higher (or not set at all)",
+                    RECONCILE_MIN_INTERVAL_MINS
+                )));
+            }
         }
 
-        Ok(())
-    }
+        if let Some(lag) = self.publication_confirm_lag_minutes {
+            if lag < PUBLICATION_CONFIRM_MIN_LAG_MINS {
+                return Err(ConfigError::Other(format!(
+                    "publication_confirm_lag_minutes must be {} or higher (or not set at all)",
+                    PUBLICATION_CONFIRM_MIN_LAG_MINS
+                )));
+            }
+        }
+
+        for channel in &self.alerts.channels {
+            match channel {
+                NotificationChannelConfig::Webhook(webhook) => webhook.verify().map_err(ConfigError::Other)?,
+                NotificationChannelConfig::Email(email) => email.verify().map_err(ConfigError::Other)?,
+            }
+        }
+
+        for webhook in &self.repository_webhooks {
+            webhook.channel_config().verify().map_err(ConfigError::Other)?;
+        }
+
+        if let Some(threshold) = self.suspend_child_after_inactive_hours {
+            if threshold < CA_SUSPEND_MIN_HOURS {
+                return Err(ConfigError::Other(format!(
+                    "suspend_child_after_inactive_hours must be {} or higher (or not set at all)",
+                    CA_SUSPEND_MIN_HOURS
+                )));
+            }
+        }
+
+        #[cfg(feature = "multi-user")]
+        for role in self.auth_roles.keys() {
+            if BUILT_IN_ROLES.contains(&role.as_str()) {
+                return Err(ConfigError::Other(format!(
+                    "[auth_roles] cannot redefine built-in role '{}'",
+                    role
+                )));
+            }
+        }
+
+        #[cfg(feature = "multi-user")]
+        for (i, auth_type) in self.auth_additional_types.iter().enumerate() {
+            if *auth_type == AuthType::AdminToken {
+                return Err(ConfigError::other(
+                    "auth_additional_types cannot include \"admin-token\", the admin token is always accepted",
+                ));
+            }
+            if *auth_type == self.auth_type || self.auth_additional_types[..i].contains(auth_type) {
+                return Err(ConfigError::Other(format!(
+                    "auth type \"{}\" is enabled more than once",
+                    auth_type
+                )));
+            }
+        }
+
+        // Check the claim mapping expressions now, rather than failing on the
+        // first login.
+        #[cfg(feature = "multi-user")]
+        if let Some(claims) = self.auth_openidconnect.as_ref().and_then(|oidc| oidc.claims.as_ref()) {
+            let runtime = jmespathext::init_runtime();
+            for (name, claim) in claims.iter() {
+                match &claim.jmespath {
+                    Some(expr) => {
+                        if let Err(e) = runtime.compile(expr) {
+                            return Err(ConfigError::Other(format!(
+                                "[auth_openidconnect] claim '{}' has an invalid jmespath expression: {}",
+                                name, e
+                            )));
+                        }
+                    }
+                    None if !matches!(claim.source, Some(ConfigAuthOpenIDConnectClaimSource::ConfigFile)) => {
+                        return Err(ConfigError::Other(format!(
+                            "[auth_openidconnect] claim '{}' must have a jmespath expression",
+                            name
+                        )));
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        #[cfg(feature = "multi-user")]
+        if let Some(client_certs) = &self.auth_client_certs {
+            if self.https_mode().is_disable_https() {
+                return Err(ConfigError::other(
+                    "[auth_client_certs] cannot be used with https_mode = \"disable\"",
+                ));
+            }
+            for (id, user) in client_certs.users.iter() {
+                if !user.is_valid() {
+                    return Err(ConfigError::Other(format!(
+                        "[auth_client_certs] user '{}' must have a subject and/or spki_sha256",
+                        id
+                    )));
+                }
+            }
+        }
+
+        if let Some(testbed) = &self.testbed {
+            if testbed.remove_inactive_after_days == Some(0) {
+                return Err(ConfigError::other(
+                    "[testbed] remove_inactive_after_days must be 1 or higher",
+                ));
+            }
+            if testbed.remove_inactive_after_days.is_some() && self.benchmark.is_some() {
+                return Err(ConfigError::other(
+                    "[testbed] remove_inactive_after_days cannot be used with [benchmark]",
+                ));
+            }
+        }
+
+        if let Some(benchmark) = &self.benchmark {
+            if self.testbed.is_none() {
+                return Err(ConfigError::other("[benchmark] section requires [testbed] config"));
+            }
+            if benchmark.cas > 65535 {
+                return Err(ConfigError::other("[benchmark] allows only up to 65536 CAs"));
+            }
+            if benchmark.ca_roas > 100 {
+                return Err(ConfigError::other("[benchmark] allows only up to 100 ROAs per CA"));
+            }
+        }
+
+        if self.signers.is_empty() {
+            // Since Config.signers defaults via Serde to ConfigDefaults::signers() which creates a vector with a
+            // single signer, this can only happen if we were invoked on a config object created or modified by test
+            // code.
+            return Err(ConfigError::Other("No signers configured".to_string()));
+        }
+
+        #[cfg(not(feature = "hsm"))]
+        {
+            fn mk_err_msg(setting_name: &str) -> String {
+                format!("This build of Krill lacks support for the '{}' config file setting. Please use a version of Krill that has the 'hsm' feature enabled.", setting_name)
+            }
+
+            if self.default_signer.is_named() {
+                return Err(ConfigError::other(&mk_err_msg("default_signer")));
+            }
+            if self.one_off_signer.is_named() {
+                return Err(ConfigError::other(&mk_err_msg("one_off_signer")));
+            }
+            if self.signers != ConfigDefaults::signers() {
+                return Err(ConfigError::other(&mk_err_msg("[[signers]]")));
+            }
+        }
+
+        for n in &self.signers {
+            if self.signers.iter().filter(|m| m.name == n.name).count() > 1 {
+                return Err(ConfigError::other(&format!("Signer name '{}' is not unique", n.name)));
+            }
+        }
+
+        if self.signers.len() > 1 && !self.default_signer.is_set() {
+            return Err(ConfigError::other(
+                "'default_signer' must be set when more than one [[signers]] configuration is defined",
+            ));
+        }
+
+        if self.default_signer.is_named() {
+            if self.find_signer_reference(&self.default_signer).is_none() {
+                return Err(ConfigError::other(&format!(
+                    "'{}' cannot be used as the 'default_signer' as no signer with that name is defined",
+                    self.default_signer.name()
+                )));
+            }
+        } else {
+        }
+
+        if self.one_off_signer.is_named() && self.find_signer_reference(&self.one_off_signer).is_none() {
+            return Err(ConfigError::other(&format!(
+                "'{}' cannot be used as the 'one_off_signer' as no signer with that name is defined",
+                self.one_off_signer.name()
+            )));
+        }
+
+        Ok(())
+    }
 
     pub fn read_config(file: &str) -> Result<Self, ConfigError> {
         let mut v = Vec::new();
@@ -1386,25 +2614,42 @@ impl Config {
         f.read_to_end(&mut v)
             .map_err(|e| KrillIoError::new(format!("Could not read config file '{}'", file), e))?;
 
-        toml::from_slice(v.as_slice())
-            .map_err(|e| ConfigError::Other(format!("Error parsing config file: {}, error: {}", file, e)))
+        let file_settings: toml::value::Table = toml::from_slice(v.as_slice())
+            .map_err(|e| ConfigError::Other(format!("Error parsing config file: {}, error: {}", file, e)))?;
+
+        let mut config: Config = toml::Value::Table(file_settings.clone())
+            .try_into()
+            .map_err(|e| ConfigError::Other(format!("Error parsing config file: {}, error: {}", file, e)))?;
+        config.config_file = Some(PathBuf::from(file));
+        config.file_settings = file_settings;
+
+        Ok(config)
     }
 
     pub fn init_logging(&self) -> Result<(), ConfigError> {
         match self.log_type {
-            LogType::File => self.file_logger(&self.log_file),
-            LogType::Stderr => self.stderr_logger(),
+            LogType::File => self.file_logger(&self.log_file)?,
+            LogType::Stderr => self.stderr_logger()?,
             LogType::Syslog => {
                 let facility = Facility::from_str(&self.syslog_facility)
                     .map_err(|_| ConfigError::other("Invalid syslog_facility"))?;
-                self.syslog_logger(facility)
+                self.syslog_logger(facility)?
             }
         }
+        self.apply_log_level();
+        Ok(())
+    }
+
+    /// Sets the maximum log level. The logger itself is set up to allow all
+    /// levels, except for some noisy dependencies, so that the level can be
+    /// changed when the config is reloaded.
+    pub fn apply_log_level(&self) {
+        log::set_max_level(self.log_level);
     }
 
     /// Creates a stderr logger.
     fn stderr_logger(&self) -> Result<(), ConfigError> {
-        self.fern_logger()
+        self.fern_logger(LevelFilter::Trace)
             .chain(io::stderr())
             .apply()
             .map_err(|e| ConfigError::Other(format!("Failed to init stderr logging: {}", e)))
@@ -1420,7 +2665,7 @@ impl Config {
                 return Err(ConfigError::Other(error_string));
             }
         };
-        self.fern_logger()
+        self.fern_logger(LevelFilter::Trace)
             .chain(file)
             .apply()
             .map_err(|e| ConfigError::Other(format!("Failed to init file logging: {}", e)))
@@ -1449,7 +2694,7 @@ impl Config {
             .or_else(|_| syslog::udp(formatter, ("127.0.0.1", 0), ("127.0.0.1", 514)));
         match logger {
             Ok(logger) => self
-                .fern_logger()
+                .fern_logger(LevelFilter::Trace)
                 .chain(logger)
                 .apply()
                 .map_err(|e| ConfigError::Other(format!("Failed to init syslog: {}", e))),
@@ -1460,24 +2705,39 @@ impl Config {
         }
     }
 
-    /// Creates and returns a fern logger with log level tweaks
-    fn fern_logger(&self) -> fern::Dispatch {
+    /// Creates and returns a fern logger with log level tweaks, which logs
+    /// up to the given level.
+    fn fern_logger(&self, log_level: LevelFilter) -> fern::Dispatch {
         // suppress overly noisy logging
-        let framework_level = self.log_level.min(LevelFilter::Warn);
-        let krill_framework_level = self.log_level.min(LevelFilter::Debug);
+        let framework_level = log_level.min(LevelFilter::Warn);
+        let krill_framework_level = log_level.min(LevelFilter::Debug);
 
         // disable Oso logging unless the Oso specific POLAR_LOG environment
         // variable is set, it's too noisy otherwise
         let oso_framework_level = if env::var("POLAR_LOG").is_ok() {
-            self.log_level.min(LevelFilter::Trace)
+            log_level.min(LevelFilter::Trace)
         } else {
-            self.log_level.min(LevelFilter::Info)
+            log_level.min(LevelFilter::Info)
         };
 
-        let show_target = self.log_level == LevelFilter::Trace || self.log_level == LevelFilter::Debug;
+        let log_format = self.log_format;
+
         fern::Dispatch::new()
             .format(move |out, message, record| {
-                if show_target {
+                let show_target = log::max_level() >= LevelFilter::Debug;
+                if log_format == LogFormat::Json {
+                    let mut line = serde_json::Map::new();
+                    line.insert("time".into(), chrono::Local::now().to_rfc3339().into());
+                    line.insert("level".into(), record.level().as_str().into());
+                    line.insert("target".into(), record.target().into());
+                    line.insert("message".into(), message.to_string().into());
+                    if let Some(Ok(serde_json::Value::Object(context))) =
+                        LogContext::current().map(serde_json::to_value)
+                    {
+                        line.extend(context);
+                    }
+                    out.finish(format_args!("{}", serde_json::Value::Object(line)))
+                } else if show_target {
                     out.finish(format_args!(
                         "{} [{}] [{}] {}",
                         chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
@@ -1494,7 +2754,7 @@ impl Config {
                     ))
                 }
             })
-            .level(self.log_level)
+            .level(log_level)
             .level_for("rustls", framework_level)
             .level_for("hyper", framework_level)
             .level_for("mio", framework_level)
@@ -1589,6 +2849,38 @@ impl<'de> Deserialize<'de> for LogType {
     }
 }
 
+//------------ LogFormat -----------------------------------------------------
+
+/// The format of log messages.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(log_format: &str) -> Result<LogFormat, Self::Err> {
+        match log_format {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("expected \"text\" or \"json\", found : \"{}\"", log_format)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LogFormat {
+    fn deserialize<D>(d: D) -> Result<LogFormat, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(d)?;
+        LogFormat::from_str(string.as_str()).map_err(de::Error::custom)
+    }
+}
+
 //------------ HttpsMode -----------------------------------------------------
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -1626,44 +2918,123 @@ impl<'de> Deserialize<'de> for HttpsMode {
     }
 }
 
+//------------ KeyAlgorithm -------------------------------------------------
+
+/// The algorithm used for new CA keys.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum KeyAlgorithm {
+    #[default]
+    Rsa,
+    EcdsaP256,
+}
+
+impl KeyAlgorithm {
+    pub fn public_key_format(&self) -> PublicKeyFormat {
+        match self {
+            KeyAlgorithm::Rsa => PublicKeyFormat::Rsa,
+            KeyAlgorithm::EcdsaP256 => PublicKeyFormat::EcdsaP256,
+        }
+    }
+}
+
+impl fmt::Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyAlgorithm::Rsa => write!(f, "rsa"),
+            KeyAlgorithm::EcdsaP256 => write!(f, "ecdsa-p256"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyAlgorithm {
+    fn deserialize<D>(d: D) -> Result<KeyAlgorithm, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(d)?;
+        match string.as_str() {
+            "rsa" => Ok(KeyAlgorithm::Rsa),
+            "ecdsa-p256" => Ok(KeyAlgorithm::EcdsaP256),
+            _ => Err(de::Error::custom(format!(
+                "expected \"rsa\" or \"ecdsa-p256\" found: \"{}\"",
+                string
+            ))),
+        }
+    }
+}
+
 //------------ AuthType -----------------------------------------------------
 
 /// The target to log to.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum AuthType {
     AdminToken,
     #[cfg(feature = "multi-user")]
     ConfigFile,
     #[cfg(feature = "multi-user")]
     OpenIDConnect,
+    #[cfg(feature = "multi-user")]
+    Ldap,
 }
 
-impl<'de> Deserialize<'de> for AuthType {
-    fn deserialize<D>(d: D) -> Result<AuthType, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let string = String::deserialize(d)?;
-        match string.as_str() {
+impl AuthType {
+    /// The name used for this type in the configuration file, and to refer to
+    /// the provider in session tokens and login requests.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuthType::AdminToken => "admin-token",
+            #[cfg(feature = "multi-user")]
+            AuthType::ConfigFile => "config-file",
+            #[cfg(feature = "multi-user")]
+            AuthType::OpenIDConnect => "openid-connect",
+            #[cfg(feature = "multi-user")]
+            AuthType::Ldap => "ldap",
+        }
+    }
+}
+
+impl fmt::Display for AuthType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AuthType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
             "admin-token" => Ok(AuthType::AdminToken),
             #[cfg(feature = "multi-user")]
             "config-file" => Ok(AuthType::ConfigFile),
             #[cfg(feature = "multi-user")]
             "openid-connect" => Ok(AuthType::OpenIDConnect),
+            #[cfg(feature = "multi-user")]
+            "ldap" => Ok(AuthType::Ldap),
             _ => {
                 #[cfg(not(feature = "multi-user"))]
-                let msg = format!("expected \"admin-token\", found: \"{}\"", string);
+                let msg = format!("expected \"admin-token\", found: \"{}\"", s);
                 #[cfg(feature = "multi-user")]
                 let msg = format!(
-                    "expected \"config-file\", \"admin-token\", or \"openid-connect\", found: \"{}\"",
-                    string
+                    "expected \"config-file\", \"admin-token\", \"openid-connect\", or \"ldap\", found: \"{}\"",
+                    s
                 );
-                Err(de::Error::custom(msg))
+                Err(msg)
             }
         }
     }
 }
 
+impl<'de> Deserialize<'de> for AuthType {
+    fn deserialize<D>(d: D) -> Result<AuthType, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(d)?;
+        AuthType::from_str(&string).map_err(de::Error::custom)
+    }
+}
+
 //------------ Signers -----------------------------------------------------
 
 // Supports TOML such as:
@@ -1717,6 +3088,14 @@ pub enum SignerType {
     #[cfg(feature = "hsm")]
     #[serde(alias = "KMIP")]
     Kmip(KmipSignerConfig),
+
+    #[cfg(feature = "hsm")]
+    #[serde(alias = "AWS KMS")]
+    AwsKms(AwsKmsSignerConfig),
+
+    #[cfg(feature = "hsm")]
+    #[serde(alias = "Google Cloud KMS")]
+    GcpKms(GcpKmsSignerConfig),
 }
 
 impl std::fmt::Display for SignerType {
@@ -1729,6 +3108,12 @@ impl std::fmt::Display for SignerType {
 
             #[cfg(feature = "hsm")]
             SignerType::Kmip(_) => f.write_str("KMIP"),
+
+            #[cfg(feature = "hsm")]
+            SignerType::AwsKms(_) => f.write_str("AWS KMS"),
+
+            #[cfg(feature = "hsm")]
+            SignerType::GcpKms(_) => f.write_str("Google Cloud KMS"),
         }
     }
 }
@@ -1748,6 +3133,12 @@ mod tests {
 
     use super::*;
 
+    #[cfg(feature = "multi-user")]
+    use crate::daemon::auth::common::permissions::Permission;
+
+    #[cfg(feature = "hsm")]
+    use crate::commons::crypto::GcpProtectionLevel;
+
     fn assert_err_msg(res: Result<Config, ConfigError>, expected_err_msg: &str) {
         if let Err(ConfigError::Other(msg)) = res {
             assert_eq!(msg, expected_err_msg);
@@ -1785,6 +3176,35 @@ mod tests {
         assert_eq!(uris.rsync_jail(), &test::rsync("rsync://testbed.example.com/repo/"));
     }
 
+    #[test]
+    fn should_limit_testbed_child_resources() {
+        let mut testbed = TestBed::new(
+            test::rsync("rsync://localhost/ta/ta.cer"),
+            test::https("https://localhost/ta/ta.cer"),
+            test::https("https://localhost/rrdp/"),
+            test::rsync("rsync://localhost/repo/"),
+        );
+
+        let resources = test::resources("AS65000", "10.0.0.0/16, 10.2.0.0/16", "");
+        assert!(testbed.check_child_resources(&resources).is_ok());
+
+        testbed.child_resources = Some(test::ipv4_resources("10.0.0.0/15"));
+        assert!(testbed
+            .check_child_resources(&resources)
+            .unwrap_err()
+            .starts_with("only resources within"));
+        assert!(testbed
+            .check_child_resources(&test::ipv4_resources("10.1.0.0/16"))
+            .is_ok());
+
+        testbed.child_resources = None;
+        testbed.child_max_blocks = Some(2);
+        assert_eq!(
+            testbed.check_child_resources(&resources).unwrap_err(),
+            "at most 2 resource blocks may be claimed, not 3"
+        );
+    }
+
     #[test]
     fn should_set_correct_log_levels() {
         use log::Level as LL;
@@ -1792,7 +3212,7 @@ mod tests {
         fn void_logger_from_krill_config(config_bytes: &[u8]) -> Box<dyn log::Log> {
             let c: Config = toml::from_slice(config_bytes).unwrap();
             let void_output = fern::Output::writer(Box::new(io::sink()), "");
-            let (_, void_logger) = c.fern_logger().chain(void_output).into_log();
+            let (_, void_logger) = c.fern_logger(c.log_level).chain(void_output).into_log();
             void_logger
         }
 
@@ -1933,6 +3353,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_reload_settings_which_do_not_need_a_restart() {
+        test::test_under_tmp(|d| {
+            let path = d.join("krill.conf");
+            let config_file = path.to_string_lossy().to_string();
+
+            test::save_file(&d, "krill.conf", br#"admin_token = "secret""#);
+            let mut config = Config::read_config(&config_file).unwrap();
+            config.process().unwrap();
+
+            test::save_file(
+                &d,
+                "krill.conf",
+                br#"
+                admin_token = "changed"
+                log_level = "debug"
+                port = 3001
+                roa_aggregate_threshold = 50
+                "#,
+            );
+
+            let (reloaded, report) = config.reload().unwrap();
+            assert_eq!(
+                report.applied(),
+                &vec!["log_level".to_string(), "roa_aggregate_threshold".to_string()]
+            );
+            // Authentication settings are only used after a restart.
+            let restart_required = vec!["admin_token".to_string(), "port".to_string()];
+            assert_eq!(report.restart_required(), &restart_required);
+            assert_eq!(reloaded.log_level, LevelFilter::Debug);
+            assert_eq!(reloaded.roa_aggregate_threshold, 50);
+            assert_eq!(reloaded.port, 3000);
+            assert_eq!(reloaded.admin_token, Token::from("secret"));
+
+            // Settings which need a restart are reported until Krill is restarted.
+            let (_, report) = reloaded.reload().unwrap();
+            assert!(report.applied().is_empty());
+            assert_eq!(report.restart_required(), &restart_required);
+        })
+    }
+
     fn parse_and_process_config_str(config_str: &str) -> Result<Config, ConfigError> {
         let mut c: Config = toml::from_str(config_str).unwrap();
         c.process()?;
@@ -1947,39 +3408,705 @@ mod tests {
     }
 
     #[test]
-    fn parse_single_ip() {
+    fn parse_log_format() {
+        let c = parse_and_process_config_str(r#"auth_token = "secret""#).unwrap();
+        assert_eq!(c.log_format, LogFormat::Text);
+
         let config_str = r#"
             auth_token = "secret"
-            ip         = "127.0.0.1"
+            log_format = "json"
         "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.log_format, LogFormat::Json);
 
-        parse_and_process_config_str(config_str).unwrap();
-    }
-
-    #[test]
-    fn parse_multiple_ips() {
         let config_str = r#"
             auth_token = "secret"
-            ip         =  [ "127.0.0.1", "::1" ]
+            log_format = "xml"
         "#;
-
-        parse_and_process_config_str(config_str).unwrap();
+        assert!(toml::from_str::<Config>(config_str).is_err());
     }
 
-    #[cfg(not(feature = "hsm"))]
     #[test]
-    fn should_fail_when_config_defines_signers_but_hsm_support_is_not_enabled() {
-        fn assert_unexpected_setting_err(res: Result<Config, ConfigError>, setting_name: &str) {
-            let expected_err_msg = format!("This build of Krill lacks support for the '{}' config file setting. Please use a version of Krill that has the 'hsm' feature enabled.", setting_name);
-            assert_err_msg(res, &expected_err_msg);
-        }
+    fn parse_tls_options() {
+        use crate::daemon::http::tls::TlsVersion;
+
+        let c = parse_and_process_config_str(r#"auth_token = "secret""#).unwrap();
+        assert_eq!(c.tls, TlsConfig::default());
 
         let config_str = r#"
             auth_token = "secret"
+            tls = { min_version = "1.3", cipher_suites = [ "TLS13_AES_256_GCM_SHA384" ] }
+        "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.tls.min_version, TlsVersion::Tls13);
 
-            [[signers]]
-            type = "OpenSSL"
-            name = "Signer 1"
+        let config_str = r#"
+            auth_token = "secret"
+            tls = { cipher_suites = [ "TLS_NULL_WITH_NULL_NULL" ] }
+        "#;
+        assert!(parse_and_process_config_str(config_str).is_err());
+
+        let config_str = r#"
+            auth_token = "secret"
+            tls = { min_version = "1.3", cipher_suites = [ "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384" ] }
+        "#;
+        assert!(parse_and_process_config_str(config_str).is_err());
+    }
+
+    #[test]
+    fn parse_acme() {
+        use crate::daemon::http::acme::AcmeChallengeType;
+
+        let config_str = r#"
+            auth_token = "secret"
+            service_uri = "https://krill.example.net/"
+
+            [acme]
+            terms_of_service_agreed = true
+            contact = [ "mailto:rpki@example.net" ]
+            http_challenge_listen = "0.0.0.0:80"
+        "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let acme = c.acme.unwrap();
+        assert_eq!(acme.challenge, AcmeChallengeType::Http01);
+        assert_eq!(acme.http_challenge_listen, Some("0.0.0.0:80".parse().unwrap()));
+        assert_eq!(acme.renew_before_days, 30);
+
+        // HTTP-01 is validated over plain HTTP, so it needs a listener.
+        let config_str = r#"
+            auth_token = "secret"
+            service_uri = "https://krill.example.net/"
+            acme = { terms_of_service_agreed = true }
+        "#;
+        assert_err_msg(
+            parse_and_process_config_str(config_str),
+            "acme.http_challenge_listen must be set for the http-01 challenge, e.g. \"0.0.0.0:80\"",
+        );
+
+        let config_str = r#"
+            auth_token = "secret"
+            service_uri = "https://krill.example.net/"
+            acme = { terms_of_service_agreed = true, challenge = "tls-alpn-01" }
+        "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.acme.unwrap().challenge, AcmeChallengeType::TlsAlpn01);
+
+        let config_str = r#"
+            auth_token = "secret"
+            service_uri = "https://krill.example.net/"
+            acme = { challenge = "tls-alpn-01" }
+        "#;
+        assert_err_msg(
+            parse_and_process_config_str(config_str),
+            "acme.terms_of_service_agreed must be true to create an account at the ACME server",
+        );
+
+        let config_str = r#"
+            auth_token = "secret"
+            acme = { terms_of_service_agreed = true, challenge = "tls-alpn-01" }
+        "#;
+        assert_err_msg(
+            parse_and_process_config_str(config_str),
+            "acme requires a service_uri with a hostname, other than localhost",
+        );
+    }
+
+    #[test]
+    fn parse_unix_socket() {
+        let config_str = r#"
+            auth_token = "secret"
+            unix_socket = { path = "/run/krill/krill.sock" }
+        "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let unix_socket = c.unix_socket.unwrap();
+        assert_eq!(unix_socket.path, PathBuf::from("/run/krill/krill.sock"));
+        assert_eq!(unix_socket.permissions(), Ok(0o600));
+
+        let config_str = r#"
+            auth_token = "secret"
+            unix_socket = { path = "/run/krill/krill.sock", mode = "0660" }
+        "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.unix_socket.unwrap().permissions(), Ok(0o660));
+
+        let config_str = r#"
+            auth_token = "secret"
+            unix_socket = { path = "/run/krill/krill.sock", mode = "rw" }
+        "#;
+        assert!(parse_and_process_config_str(config_str).is_err());
+    }
+
+    #[test]
+    fn parse_rate_limit() {
+        let config_str = r#"
+            auth_token = "secret"
+            rate_limit = { protocol_per_minute = 120 }
+        "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let rate_limit = c.rate_limit.unwrap();
+        assert_eq!(rate_limit.api_per_minute, 600);
+        assert_eq!(rate_limit.protocol_per_minute, 120);
+        assert_eq!(rate_limit.monitoring_per_minute, 0);
+        assert_eq!(rate_limit.burst, None);
+        assert_eq!(rate_limit.max_concurrent_expensive, 4);
+
+        let config_str = r#"
+            auth_token = "secret"
+            rate_limit = { max_concurrent_expensive = 0 }
+        "#;
+        assert_err_msg(
+            parse_and_process_config_str(config_str),
+            "rate_limit.max_concurrent_expensive must be 1 or higher",
+        );
+    }
+
+    #[test]
+    fn parse_single_ip() {
+        let config_str = r#"
+            auth_token = "secret"
+            ip         = "127.0.0.1"
+        "#;
+
+        parse_and_process_config_str(config_str).unwrap();
+    }
+
+    #[test]
+    fn parse_multiple_ips() {
+        let config_str = r#"
+            auth_token = "secret"
+            ip         =  [ "127.0.0.1", "::1" ]
+        "#;
+
+        parse_and_process_config_str(config_str).unwrap();
+    }
+
+    #[test]
+    fn parse_roa_autopilot_config() {
+        let config_str = r#"
+            auth_token = "secret"
+            roa_autopilot.ca = { min_seen_hours = 24 }
+            roa_autopilot.other_ca = { max_prefix_length_v4 = 22 }
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.roa_autopilot.len(), 2);
+
+        let ca = c.roa_autopilot.get(&CaHandle::from_str("ca").unwrap()).unwrap();
+        assert_eq!(ca.min_seen(), Duration::hours(24));
+        assert_eq!(ca.max_prefix_length_v4, 24);
+        assert_eq!(ca.max_prefix_length_v6, 48);
+
+        let other_ca = c.roa_autopilot.get(&CaHandle::from_str("other_ca").unwrap()).unwrap();
+        assert_eq!(other_ca.min_seen(), Duration::hours(168));
+        assert_eq!(other_ca.max_prefix_length_v4, 22);
+        assert!(other_ca.enabled);
+
+        let config_str = r#"
+            auth_token = "secret"
+            roa_autopilot.ca = { max_prefix_length_v4 = 33 }
+        "#;
+        assert!(parse_and_process_config_str(config_str).is_err());
+    }
+
+    #[test]
+    fn parse_alerts_config() {
+        let c = parse_and_process_config_str(r#"auth_token = "secret""#).unwrap();
+        assert!(c.alerts.channels.is_empty());
+        assert!(!c.alerts.is_enabled(AlertEvent::ParentFailure));
+
+        let config_str = r#"
+            auth_token = "secret"
+
+            [alerts]
+            events = [ "parent_failure", "roa_change" ]
+
+            [[alerts.channels]]
+            type = "webhook"
+            url = "https://example.com/alerts"
+
+            [[alerts.channels]]
+            type = "email"
+            smtp_server = "localhost:25"
+            from = "krill@example.com"
+            to = [ "noc@example.com" ]
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.alerts.channels.len(), 2);
+        assert_eq!(c.alerts.expiry_warning_days, 14);
+        assert!(c.alerts.is_enabled(AlertEvent::ParentFailure));
+        assert!(!c.alerts.is_enabled(AlertEvent::Expiry));
+
+        let config_str = r#"
+            auth_token = "secret"
+
+            [[alerts.channels]]
+            type = "email"
+            smtp_server = "localhost:25"
+            from = "krill@example.com"
+            to = [ "noc@example.com\r\nBcc: other@example.com" ]
+        "#;
+        assert!(parse_and_process_config_str(config_str).is_err());
+
+        let config_str = r#"
+            auth_token = "secret"
+
+            [[alerts.channels]]
+            type = "webhook"
+            url = "ftp://example.com/alerts"
+        "#;
+        assert!(parse_and_process_config_str(config_str).is_err());
+    }
+
+    #[test]
+    fn parse_ca_key_algorithm() {
+        let c = parse_and_process_config_str(r#"auth_token = "secret""#).unwrap();
+        assert_eq!(c.ca_key_algorithm, KeyAlgorithm::Rsa);
+
+        let c = parse_and_process_config_str(
+            r#"
+            auth_token = "secret"
+            ca_key_algorithm = "rsa"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(c.ca_key_algorithm.public_key_format(), PublicKeyFormat::Rsa);
+
+        // ECDSA keys are not (yet) allowed in RPKI resource certificates
+        let res = parse_and_process_config_str(
+            r#"
+            auth_token = "secret"
+            ca_key_algorithm = "ecdsa-p256"
+        "#,
+        );
+        assert!(res.is_err());
+
+        let res = toml::from_str::<Config>(
+            r#"
+            auth_token = "secret"
+            ca_key_algorithm = "dsa"
+        "#,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn issuance_timing_fix_aspa_timing_of_existing_config() {
+        // Earlier versions accepted ASPA timing values which are now
+        // considered invalid. They are replaced by the defaults.
+        let config_str = r#"
+            auth_token = "secret"
+            timing_aspa_valid_weeks = 4
+            timing_aspa_reissue_weeks_before = 4
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(
+            c.issuance_timing.timing_aspa_valid_weeks,
+            ConfigDefaults::timing_aspa_valid_weeks()
+        );
+        assert_eq!(
+            c.issuance_timing.timing_aspa_reissue_weeks_before,
+            ConfigDefaults::timing_aspa_reissue_weeks_before()
+        );
+
+        let config_str = r#"
+            auth_token = "secret"
+            timing_aspa_valid_weeks = 1
+        "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(
+            c.issuance_timing.timing_aspa_valid_weeks,
+            ConfigDefaults::timing_aspa_valid_weeks()
+        );
+
+        // Consistent values are kept.
+        let config_str = r#"
+            auth_token = "secret"
+            timing_aspa_valid_weeks = 8
+            timing_aspa_reissue_weeks_before = 2
+        "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.issuance_timing.timing_aspa_valid_weeks, 8);
+        assert_eq!(c.issuance_timing.timing_aspa_reissue_weeks_before, 2);
+    }
+
+    #[test]
+    fn issuance_timing_with_overrides() {
+        let config_str = r#"
+            auth_token = "secret"
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let global = &c.issuance_timing;
+
+        let overrides = IssuanceTimingOverrides {
+            timing_roa_valid_weeks: Some(8),
+            timing_roa_reissue_weeks_before: Some(2),
+            ..Default::default()
+        };
+
+        let timing = global.with_overrides(&overrides);
+        assert_eq!(timing.timing_roa_valid_weeks, 8);
+        assert_eq!(timing.timing_roa_reissue_weeks_before, 2);
+        assert_eq!(timing.timing_publish_next_hours, global.timing_publish_next_hours);
+        assert_eq!(timing.timing_aspa_valid_weeks, global.timing_aspa_valid_weeks);
+        assert!(timing.verify().is_ok());
+
+        let invalid = IssuanceTimingOverrides {
+            timing_roa_valid_weeks: Some(global.timing_roa_reissue_weeks_before),
+            ..Default::default()
+        };
+        assert!(global.with_overrides(&invalid).verify().is_err());
+    }
+
+    #[test]
+    fn issuance_timing_jitter_and_spread() {
+        let config_str = r#"
+            auth_token = "secret"
+            timing_roa_valid_jitter_hours = 48
+            timing_renew_spread_hours = 24
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let timing = &c.issuance_timing;
+
+        let earliest = clock::now() + Duration::weeks(52);
+        let validity = timing.new_roa_validity();
+        assert!(validity.not_after() >= earliest);
+        assert!(validity.not_after() < earliest + Duration::hours(49));
+
+        // The spread is the same for the same object, and within the limit.
+        let name = "object.roa";
+        assert_eq!(timing.renew_spread(&name), timing.renew_spread(&name));
+        assert!(timing.renew_spread(&name) < Duration::hours(24));
+
+        let threshold = timing.new_roa_issuance_threshold();
+        assert!(timing.renew_due(&name, threshold - Duration::minutes(1), threshold));
+        assert!(!timing.renew_due(&name, threshold + Duration::hours(24), threshold));
+
+        let invalid = IssuanceTimingOverrides {
+            timing_renew_spread_hours: Some(52 * 168),
+            ..Default::default()
+        };
+        assert!(timing.with_overrides(&invalid).verify().is_err());
+    }
+
+    #[test]
+    fn issuance_timing_warnings() {
+        let c = parse_and_process_config_str(r#"auth_token = "secret""#).unwrap();
+        let global = &c.issuance_timing;
+        assert!(global.warnings().is_empty());
+
+        // Short windows are allowed, but result in warnings.
+        let overrides = IssuanceTimingOverrides {
+            timing_publish_next_hours: Some(4),
+            timing_publish_next_jitter_hours: Some(0),
+            timing_publish_hours_before_next: Some(3),
+            ..Default::default()
+        };
+        let timing = global.with_overrides(&overrides);
+        assert!(timing.verify().is_ok());
+        assert_eq!(timing.warnings().len(), 2);
+
+        let effective = timing.effective();
+        assert_eq!(effective.timing_publish_next_hours, Some(4));
+        assert_eq!(effective.timing_roa_valid_weeks, Some(global.timing_roa_valid_weeks));
+    }
+
+    #[test]
+    fn issuance_timing_with_max_child_certificate_validity() {
+        let config_str = r#"
+            auth_token = "secret"
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let global = &c.issuance_timing;
+
+        let timing = global.with_max_child_certificate_valid_weeks(1000);
+        assert_eq!(
+            timing.timing_child_certificate_valid_weeks,
+            global.timing_child_certificate_valid_weeks
+        );
+
+        let timing = global.with_max_child_certificate_valid_weeks(4);
+        assert_eq!(timing.timing_child_certificate_valid_weeks, 4);
+        assert_eq!(timing.timing_child_certificate_reissue_weeks_before, 2);
+        assert!(timing.verify().is_ok());
+    }
+
+    #[test]
+    fn retry_config_backoff() {
+        let config_str = r#"
+            auth_token = "secret"
+            retry_parent = { initial_delay_seconds = 60, backoff_factor = 2, max_delay_seconds = 300, max_attempts = 6 }
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.retry_repository, RetryConfig::default());
+
+        // The default retries every 5 minutes, without giving up.
+        assert_eq!(c.retry_repository.delay_seconds(1), Some(300));
+        assert_eq!(c.retry_repository.delay_seconds(100), Some(300));
+
+        let retry = &c.retry_parent;
+        assert_eq!(retry.delay_seconds(1), Some(60));
+        assert_eq!(retry.delay_seconds(2), Some(120));
+        assert_eq!(retry.delay_seconds(3), Some(240));
+        assert_eq!(retry.delay_seconds(4), Some(300));
+        assert_eq!(retry.delay_seconds(5), Some(300));
+        assert_eq!(retry.delay_seconds(6), None);
+
+        let overrides = RetryPolicy {
+            max_attempts: Some(10),
+            timeout_seconds: Some(30),
+            ..Default::default()
+        };
+        let retry = retry.with_overrides(&overrides);
+        assert_eq!(retry.delay_seconds(6), Some(300));
+        assert_eq!(c.protocol_msg_timeout_seconds(&retry), 30);
+        assert_eq!(
+            c.protocol_msg_timeout_seconds(&c.retry_parent),
+            c.post_protocol_msg_timeout_seconds
+        );
+
+        let invalid = RetryPolicy {
+            max_delay_seconds: Some(10),
+            ..Default::default()
+        };
+        assert!(c.retry_parent.with_overrides(&invalid).verify().is_err());
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn parse_ldap_auth_config() {
+        let config_str = r#"
+            auth_token = "secret"
+            auth_type  = "ldap"
+
+            [auth_ldap]
+            url = "ldaps://ad.example.net"
+            user_search_base = "OU=Staff,DC=example,DC=net"
+
+            [[auth_ldap.roles]]
+            group = "CN=RPKI Admins,OU=Groups,DC=example,DC=net"
+            role = "admin"
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.auth_type, AuthType::Ldap);
+
+        let ldap = c.auth_ldap.unwrap();
+        assert_eq!(ldap.user_search_filter, "(sAMAccountName={username})");
+        assert_eq!(ldap.group_attribute, "memberOf");
+        assert_eq!(ldap.roles.len(), 1);
+        assert_eq!(ldap.roles[0].role, "admin");
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn parse_jwt_auth_config() {
+        let config_str = r#"
+            auth_token = "secret"
+
+            [auth_jwt]
+            jwks_url = "https://idp.example.net/.well-known/jwks.json"
+            issuer = "https://idp.example.net/"
+            audience = "krill"
+
+            [auth_jwt.attributes]
+            role = "krill_role"
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let jwt = c.auth_jwt.unwrap();
+        assert_eq!(jwt.id_claim, "sub");
+        assert_eq!(jwt.leeway_seconds, 60);
+        assert_eq!(jwt.attributes.get("role").unwrap(), "krill_role");
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn parse_auth_roles_config() {
+        let config_str = r#"
+            auth_token = "secret"
+
+            [auth_roles.roa-editor]
+            permissions = ["CA_LIST", "CA_READ"]
+            ca_permissions = { ca1 = ["ROUTES_UPDATE"] }
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let role = c.auth_roles.get("roa-editor").unwrap();
+        assert!(role.grants_on_ca(&Permission::ROUTES_UPDATE, "ca1"));
+        assert!(!role.grants_on_ca(&Permission::ROUTES_UPDATE, "ca2"));
+
+        let config_str = r#"
+            auth_token = "secret"
+
+            [auth_roles.admin]
+            permissions = ["CA_LIST"]
+        "#;
+        assert_err_msg(
+            parse_and_process_config_str(config_str),
+            "[auth_roles] cannot redefine built-in role 'admin'",
+        );
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn parse_auth_orgs_config() {
+        let config_str = r#"
+            auth_token = "secret"
+
+            [auth_orgs.customer-a]
+            cas = ["customer-a-*"]
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let org = c.auth_orgs.get("customer-a").unwrap();
+        assert!(org.owns_ca("customer-a-ca1"));
+        assert!(!org.owns_ca("customer-b-ca1"));
+        assert!(org.publishers.is_empty());
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn parse_auth_additional_types() {
+        let config_str = r#"
+            auth_token = "secret"
+            auth_type = "ldap"
+            auth_additional_types = ["config-file"]
+        "#;
+        let c = parse_and_process_config_str(config_str).unwrap();
+        assert_eq!(c.auth_additional_types, vec![AuthType::ConfigFile]);
+
+        let config_str = r#"
+            auth_token = "secret"
+            auth_type = "ldap"
+            auth_additional_types = ["config-file", "ldap"]
+        "#;
+        assert_err_msg(
+            parse_and_process_config_str(config_str),
+            "auth type \"ldap\" is enabled more than once",
+        );
+
+        let config_str = r#"
+            auth_token = "secret"
+            auth_type = "ldap"
+            auth_additional_types = ["admin-token"]
+        "#;
+        assert!(parse_and_process_config_str(config_str).is_err());
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn verify_openidconnect_claim_expressions() {
+        let config_str = r#"
+            auth_token = "secret"
+            auth_type = "openid-connect"
+
+            [auth_openidconnect]
+            issuer_url = "https://idp.example.net/"
+            client_id = "krill"
+            client_secret = "secret"
+
+            [auth_openidconnect.claims]
+            role = { jmespath = "realm_access.roles | [0]" }
+            inc_cas = { jmespath = "map(&resub(@, '^krill-ca-', ''), groups[?starts_with(@, 'krill-ca-')])" }
+            exc_cas = { source = "config-file" }
+        "#;
+        assert!(parse_and_process_config_str(config_str).is_ok());
+
+        let config_str = r#"
+            auth_token = "secret"
+            auth_type = "openid-connect"
+
+            [auth_openidconnect]
+            issuer_url = "https://idp.example.net/"
+            client_id = "krill"
+            client_secret = "secret"
+
+            [auth_openidconnect.claims]
+            role = { source = "id-token-additional-claim" }
+        "#;
+        assert_err_msg(
+            parse_and_process_config_str(config_str),
+            "[auth_openidconnect] claim 'role' must have a jmespath expression",
+        );
+
+        let config_str = r#"
+            auth_token = "secret"
+            auth_type = "openid-connect"
+
+            [auth_openidconnect]
+            issuer_url = "https://idp.example.net/"
+            client_id = "krill"
+            client_secret = "secret"
+
+            [auth_openidconnect.claims]
+            role = { jmespath = "groups[?" }
+        "#;
+        assert!(parse_and_process_config_str(config_str).is_err());
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn parse_client_certs_auth_config() {
+        let config_str = r#"
+            auth_token = "secret"
+
+            [auth_client_certs]
+            ca_cert = "/etc/krill/client-ca.pem"
+
+            [auth_client_certs.users.automation]
+            subject = "CN=automation,O=Example"
+            attributes = { role = "readwrite" }
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+        let client_certs = c.auth_client_certs.unwrap();
+        assert!(!client_certs.required);
+        assert_eq!(
+            client_certs
+                .users
+                .get("automation")
+                .unwrap()
+                .attributes
+                .get("role")
+                .unwrap(),
+            "readwrite"
+        );
+
+        let config_str = r#"
+            auth_token = "secret"
+
+            [auth_client_certs]
+            ca_cert = "/etc/krill/client-ca.pem"
+
+            [auth_client_certs.users.automation]
+            attributes = { role = "readwrite" }
+        "#;
+        assert_err_msg(
+            parse_and_process_config_str(config_str),
+            "[auth_client_certs] user 'automation' must have a subject and/or spki_sha256",
+        );
+    }
+
+    #[cfg(not(feature = "hsm"))]
+    #[test]
+    fn should_fail_when_config_defines_signers_but_hsm_support_is_not_enabled() {
+        fn assert_unexpected_setting_err(res: Result<Config, ConfigError>, setting_name: &str) {
+            let expected_err_msg = format!("This build of Krill lacks support for the '{}' config file setting. Please use a version of Krill that has the 'hsm' feature enabled.", setting_name);
+            assert_err_msg(res, &expected_err_msg);
+        }
+
+        let config_str = r#"
+            auth_token = "secret"
+
+            [[signers]]
+            type = "OpenSSL"
+            name = "Signer 1"
         "#;
 
         let res = parse_and_process_config_str(config_str);
@@ -2099,6 +4226,46 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "hsm")]
+    #[test]
+    fn should_parse_cloud_kms_signers() {
+        let config_str = r#"
+            auth_token = "secret"
+            default_signer = "AWS"
+
+            [[signers]]
+            type = "AWS KMS"
+            name = "AWS"
+            region = "eu-west-1"
+
+            [[signers]]
+            type = "Google Cloud KMS"
+            name = "GCP"
+            key_ring = "projects/krill/locations/europe-west4/keyRings/krill"
+            protection_level = "SOFTWARE"
+        "#;
+
+        let c = parse_and_process_config_str(config_str).unwrap();
+
+        match &c.signers[0].signer_type {
+            SignerType::AwsKms(conf) => {
+                assert_eq!(conf.region, "eu-west-1");
+                assert_eq!(conf.endpoint, None);
+                assert_eq!(conf.deletion_window_days, 7);
+            }
+            other => panic!("expected AWS KMS signer, found {}", other),
+        }
+
+        match &c.signers[1].signer_type {
+            SignerType::GcpKms(conf) => {
+                assert_eq!(conf.key_ring, "projects/krill/locations/europe-west4/keyRings/krill");
+                assert_eq!(conf.protection_level, GcpProtectionLevel::Software);
+                assert_eq!(conf.endpoint, "https://cloudkms.googleapis.com");
+            }
+            other => panic!("expected Google Cloud KMS signer, found {}", other),
+        }
+    }
+
     #[cfg(feature = "hsm")]
     #[test]
     fn should_fail_if_signer_name_is_not_unique() {
@@ -0,0 +1,413 @@
+//! Active/standby high availability.
+//!
+//! Two Krill instances can share the PostgreSQL database configured with
+//! 'storage_uri'. One of them is active: it accepts changes, signs, and runs
+//! the scheduled tasks. The other is a standby, which serves read-only API
+//! and monitoring requests from the shared storage.
+//!
+//! The active instance holds a lease in the shared storage, which it renews
+//! periodically. If the lease expires, e.g. because the active instance was
+//! stopped, then the standby takes over, unless automatic failover is
+//! disabled. A standby can also be promoted explicitly through the API, in
+//! which case the other instance becomes the standby when it next tries to
+//! renew its lease. Because the other instance may keep acting on its lease
+//! until then, the promoted instance only becomes active when that lease
+//! expires.
+use std::sync::{Mutex, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commons::{
+        api::{HaStatus, Timestamp},
+        error::Error,
+        eventsourcing::{KeyStoreKey, KeyValueStorage, KeyValueStore},
+        KrillResult,
+    },
+    constants::HA_DIR,
+};
+
+//------------ HaConfig ------------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct HaConfig {
+    /// The name of this instance, which must be unique for the instances
+    /// which share the storage.
+    pub instance: String,
+
+    /// The time after which the lease of the active instance expires, if it
+    /// is not renewed.
+    #[serde(default = "HaConfig::dflt_lease_seconds")]
+    pub lease_seconds: u32,
+
+    /// Whether a standby takes over when the lease of the active instance
+    /// expires. If false, a standby is only promoted through the API.
+    #[serde(default = "HaConfig::dflt_auto_failover")]
+    pub auto_failover: bool,
+}
+
+impl HaConfig {
+    fn dflt_lease_seconds() -> u32 {
+        30
+    }
+
+    fn dflt_auto_failover() -> bool {
+        true
+    }
+}
+
+//------------ HaLease -------------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct HaLease {
+    instance: String,
+
+    /// The time from which the instance may act as the active instance. This
+    /// is later than the time the lease was taken if it was taken from an
+    /// instance whose lease had not yet expired.
+    #[serde(default = "HaLease::dflt_active_from")]
+    active_from: Timestamp,
+
+    expires: Timestamp,
+}
+
+impl HaLease {
+    fn dflt_active_from() -> Timestamp {
+        Timestamp::new(0)
+    }
+}
+
+//------------ HaManager -----------------------------------------------------
+
+pub struct HaManager {
+    config: HaConfig,
+    store: KeyValueStore,
+
+    // The lease as it was last seen in the store.
+    lease: RwLock<Option<HaLease>>,
+
+    // The time when the lease should be renewed next.
+    next_renewal: Mutex<Timestamp>,
+}
+
+impl HaManager {
+    pub fn build(config: HaConfig, storage: &KeyValueStorage) -> KrillResult<Self> {
+        let store = KeyValueStore::create(storage, HA_DIR)?;
+        Ok(HaManager {
+            config,
+            store,
+            lease: RwLock::new(None),
+            next_renewal: Mutex::new(Timestamp::now()),
+        })
+    }
+
+    fn lease_key() -> KeyStoreKey {
+        KeyStoreKey::simple("lease.json".to_string())
+    }
+
+    /// Returns a lease for this instance, which is active from the given
+    /// time, and which expires 'lease_seconds' after that time or now,
+    /// whichever is later.
+    fn new_lease(&self, active_from: Timestamp) -> HaLease {
+        HaLease {
+            instance: self.config.instance.clone(),
+            active_from,
+            expires: active_from
+                .max(Timestamp::now())
+                .plus_seconds(self.config.lease_seconds as i64),
+        }
+    }
+
+    /// Returns the time from which this instance may be active if it takes
+    /// the given lease. If another instance holds a lease which has not yet
+    /// expired, then this is when that lease expires.
+    fn active_from(&self, current: Option<&HaLease>) -> Timestamp {
+        let now = Timestamp::now();
+        match current {
+            Some(lease) if lease.instance == self.config.instance => lease.active_from,
+            Some(lease) if lease.expires > now => lease.expires,
+            _ => now,
+        }
+    }
+
+    /// Returns whether this instance holds a lease that is in effect.
+    pub fn is_active(&self) -> bool {
+        match self.lease.read().unwrap().as_ref() {
+            Some(lease) => {
+                let now = Timestamp::now();
+                lease.instance == self.config.instance && lease.active_from <= now && lease.expires > now
+            }
+            None => false,
+        }
+    }
+
+    pub fn status(&self) -> HaStatus {
+        let lease = self.lease.read().unwrap().clone();
+        HaStatus::new(
+            self.config.instance.clone(),
+            self.is_active(),
+            self.config.auto_failover,
+            lease.as_ref().map(|lease| lease.instance.clone()),
+            lease.map(|lease| lease.expires),
+        )
+    }
+}
+
+/// # Leader election
+impl HaManager {
+    /// Renews the lease if a third of its time has passed since the last
+    /// renewal. Returns whether this instance is active.
+    pub fn renew_lease_if_due(&self) -> KrillResult<bool> {
+        {
+            let mut next_renewal = self.next_renewal.lock().unwrap();
+            if *next_renewal > Timestamp::now() {
+                return Ok(self.is_active());
+            }
+            *next_renewal = Timestamp::now_plus_seconds((self.config.lease_seconds as i64 / 3).max(1));
+        }
+        self.renew_lease()
+    }
+
+    /// Renews the lease if this instance holds it, or takes it if there is
+    /// no lease, or if it expired and automatic failover is enabled. Returns
+    /// whether this instance is active.
+    pub fn renew_lease(&self) -> KrillResult<bool> {
+        let was_active = self.is_active();
+
+        let key = Self::lease_key();
+        let current: Option<HaLease> = self.store.get(&key)?;
+
+        let take = match current.as_ref() {
+            None => true,
+            Some(lease) if lease.instance == self.config.instance => true,
+            Some(lease) => self.config.auto_failover && lease.expires < Timestamp::now(),
+        };
+
+        let lease = if take {
+            let new = self.new_lease(self.active_from(current.as_ref()));
+            if self.store.store_if_unchanged(&key, current.as_ref(), &new)? {
+                Some(new)
+            } else {
+                // Another instance changed the lease first.
+                self.store.get(&key)?
+            }
+        } else {
+            current
+        };
+
+        *self.lease.write().unwrap() = lease;
+
+        let active = self.is_active();
+        if active && !was_active {
+            warn!("Instance '{}' is now the active Krill instance", self.config.instance);
+        } else if was_active && !active {
+            warn!("Instance '{}' is now a standby Krill instance", self.config.instance);
+        }
+
+        Ok(active)
+    }
+
+    /// Takes the lease for this instance, regardless of which instance holds
+    /// it now. The lease is only replaced if it was not changed since it was
+    /// read, so that a concurrent renewal or promotion is not silently lost.
+    ///
+    /// If another instance holds the lease, this instance only becomes
+    /// active when that lease expires. The other instance may go on signing
+    /// and publishing until it next renews its lease, and finds that it was
+    /// taken over.
+    pub fn promote(&self) -> KrillResult<HaStatus> {
+        let key = Self::lease_key();
+        let current: Option<HaLease> = self.store.get(&key)?;
+
+        let lease = self.new_lease(self.active_from(current.as_ref()));
+        if !self.store.store_if_unchanged(&key, current.as_ref(), &lease)? {
+            *self.lease.write().unwrap() = self.store.get(&key)?;
+            return Err(Error::ApiHaLeaseChanged);
+        }
+        *self.lease.write().unwrap() = Some(lease);
+
+        warn!(
+            "Instance '{}' was promoted to be the active Krill instance",
+            self.config.instance
+        );
+        Ok(self.status())
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::test;
+
+    fn config(instance: &str, auto_failover: bool) -> HaConfig {
+        HaConfig {
+            instance: instance.to_string(),
+            lease_seconds: 30,
+            auto_failover,
+        }
+    }
+
+    #[test]
+    fn elect_and_promote() {
+        test::test_under_tmp(|d| {
+            let storage = KeyValueStorage::Disk(d);
+            let a = HaManager::build(config("a", false), &storage).unwrap();
+            let b = HaManager::build(config("b", false), &storage).unwrap();
+
+            assert!(a.renew_lease().unwrap());
+            assert!(!b.renew_lease().unwrap());
+            assert_eq!(b.status().leader(), Some(&"a".to_string()));
+
+            assert!(a.renew_lease().unwrap());
+            assert!(!b.renew_lease().unwrap());
+
+            b.promote().unwrap();
+            assert_eq!(b.status().leader(), Some(&"b".to_string()));
+            assert!(!a.renew_lease().unwrap());
+            assert!(!a.is_active());
+        })
+    }
+
+    #[test]
+    fn promoted_instance_waits_for_previous_lease() {
+        test::test_under_tmp(|d| {
+            let storage = KeyValueStorage::Disk(d);
+            let short = |instance: &str| HaConfig {
+                instance: instance.to_string(),
+                lease_seconds: 2,
+                auto_failover: false,
+            };
+            let a = HaManager::build(short("a"), &storage).unwrap();
+            let b = HaManager::build(short("b"), &storage).unwrap();
+            assert!(a.renew_lease().unwrap());
+
+            // Until a finds out about the promotion, it still considers itself
+            // active, so b must wait until the lease of a expired.
+            b.promote().unwrap();
+            assert!(a.is_active());
+            assert!(!b.is_active());
+            assert!(!b.renew_lease().unwrap());
+
+            std::thread::sleep(std::time::Duration::from_millis(2100));
+            assert!(!a.is_active());
+            assert!(b.is_active());
+
+            // Renewing the lease keeps b active.
+            assert!(b.renew_lease().unwrap());
+            assert!(!a.renew_lease().unwrap());
+        })
+    }
+
+    #[test]
+    fn lease_is_only_replaced_if_unchanged() {
+        test::test_under_tmp(|d| {
+            let storage = KeyValueStorage::Disk(d);
+            let a = HaManager::build(config("a", false), &storage).unwrap();
+            let b = HaManager::build(config("b", false), &storage).unwrap();
+            assert!(a.renew_lease().unwrap());
+
+            // Change the lease between reading and replacing it in promote,
+            // as a renewal by the active instance would.
+            let store = KeyValueStore::create(&storage, HA_DIR).unwrap();
+            let current: HaLease = store.get(&HaManager::lease_key()).unwrap().unwrap();
+            let renewed = HaLease {
+                instance: "a".to_string(),
+                active_from: current.active_from,
+                expires: Timestamp::now_plus_seconds(60),
+            };
+            assert!(store
+                .store_if_unchanged(&HaManager::lease_key(), Some(&current), &renewed)
+                .unwrap());
+            assert!(!store
+                .store_if_unchanged(&HaManager::lease_key(), Some(&current), &b.new_lease(Timestamp::now()))
+                .unwrap());
+
+            // A promotion based on the lease as it is now succeeds, but b
+            // only becomes active when the renewed lease of a expires.
+            b.promote().unwrap();
+            assert_eq!(b.status().leader(), Some(&"b".to_string()));
+            assert!(!b.is_active());
+            assert!(!a.renew_lease().unwrap());
+        })
+    }
+
+    #[test]
+    fn only_one_instance_takes_a_free_lease() {
+        test::test_under_tmp(|d| {
+            let storage = KeyValueStorage::Disk(d);
+            let managers: Vec<_> = (0..8)
+                .map(|i| std::sync::Arc::new(HaManager::build(config(&format!("i{}", i), true), &storage).unwrap()))
+                .collect();
+
+            let barrier = std::sync::Arc::new(std::sync::Barrier::new(managers.len()));
+            let handles: Vec<_> = managers
+                .iter()
+                .cloned()
+                .map(|manager| {
+                    let barrier = barrier.clone();
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        manager.renew_lease().unwrap()
+                    })
+                })
+                .collect();
+
+            let active = handles.into_iter().map(|h| h.join().unwrap()).filter(|a| *a).count();
+            assert_eq!(active, 1);
+            assert_eq!(managers.iter().filter(|m| m.is_active()).count(), 1);
+        })
+    }
+
+    #[test]
+    fn holder_keeps_lease_and_standby_takes_over_after_expiry() {
+        test::test_under_tmp(|d| {
+            let storage = KeyValueStorage::Disk(d);
+            let short = |instance: &str| HaConfig {
+                instance: instance.to_string(),
+                lease_seconds: 1,
+                auto_failover: true,
+            };
+            let a = HaManager::build(short("a"), &storage).unwrap();
+            let b = HaManager::build(short("b"), &storage).unwrap();
+
+            assert!(a.renew_lease().unwrap());
+            assert!(!b.renew_lease().unwrap());
+
+            // While a keeps renewing, b cannot take over.
+            assert!(a.renew_lease().unwrap());
+            assert!(!b.renew_lease().unwrap());
+
+            // When a stops renewing, b takes over once the lease expired, and
+            // a becomes a standby.
+            std::thread::sleep(std::time::Duration::from_millis(2100));
+            assert!(!a.is_active());
+            assert!(b.renew_lease().unwrap());
+            assert!(!a.renew_lease().unwrap());
+            assert_eq!(a.status().leader(), Some(&"b".to_string()));
+        })
+    }
+
+    #[test]
+    fn failover_after_lease_expired() {
+        test::test_under_tmp(|d| {
+            let storage = KeyValueStorage::Disk(d);
+            let store = KeyValueStore::create(&storage, HA_DIR).unwrap();
+            let expired = HaLease {
+                instance: "a".to_string(),
+                active_from: Timestamp::now_minus_seconds(31),
+                expires: Timestamp::now_minus_seconds(1),
+            };
+            store.store(&HaManager::lease_key(), &expired).unwrap();
+
+            let manual = HaManager::build(config("b", false), &storage).unwrap();
+            assert!(!manual.renew_lease().unwrap());
+
+            let auto = HaManager::build(config("c", true), &storage).unwrap();
+            assert!(auto.renew_lease().unwrap());
+        })
+    }
+}
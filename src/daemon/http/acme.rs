@@ -0,0 +1,1030 @@
+//! Obtain and renew the certificate for the HTTPS server using ACME
+//! (RFC 8555), e.g. from Let's Encrypt.
+//!
+//! The certificate is requested for the hostname of the service URI, and is
+//! saved as the key and certificate under data_dir/ssl which are otherwise
+//! generated by Krill or provided by the operator. Control of the hostname is
+//! proven using either the HTTP-01 challenge, which Krill answers over plain
+//! HTTP on a separate listener, or the TLS-ALPN-01 challenge (RFC 8737),
+//! which it answers during the TLS handshake.
+use std::{
+    collections::HashMap,
+    fmt,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use openssl::{
+    asn1::Asn1Time,
+    bn::{BigNum, BigNumContext},
+    ec::{EcGroup, EcKey},
+    ecdsa::EcdsaSig,
+    error::ErrorStack,
+    hash::{hash, MessageDigest},
+    nid::Nid,
+    pkey::{PKey, Private},
+    stack::Stack,
+    x509::{extension::SubjectAlternativeName, X509Builder, X509Extension, X509NameBuilder, X509ReqBuilder, X509},
+};
+use reqwest::header::{CONTENT_TYPE, LOCATION};
+use rpki::uri;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tokio_rustls::rustls::{
+    internal::pemfile,
+    sign::{self, CertifiedKey},
+    Certificate, ClientHello, ResolvesServerCert,
+};
+
+use crate::{
+    commons::{
+        error::KrillIoError,
+        util::{file, httpclient},
+    },
+    daemon::http::tls_keys,
+};
+
+/// The path under which HTTP-01 challenges are requested.
+pub const ACME_HTTP_CHALLENGE_PATH: &str = "/.well-known/acme-challenge/";
+
+/// The ALPN protocol used for TLS-ALPN-01 challenges.
+pub const ACME_TLS_ALPN_PROTOCOL: &[u8] = b"acme-tls/1";
+
+/// How often to check whether the certificate needs to be renewed.
+pub const ACME_CHECK_INTERVAL_SECONDS: u64 = 12 * 3600;
+
+/// How long to wait before trying again if a certificate could not be
+/// obtained.
+pub const ACME_RETRY_INTERVAL_SECONDS: u64 = 3600;
+
+const ACCOUNT_KEY_FILE: &str = "acme_account_key.pem";
+
+const POLL_INTERVAL_SECONDS: u64 = 2;
+const POLL_ATTEMPTS: usize = 30;
+
+//------------ AcmeConfig ----------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct AcmeConfig {
+    /// The directory URI of the ACME server.
+    #[serde(default = "AcmeConfig::dflt_directory")]
+    pub directory: String,
+
+    /// Contact URIs for the account, e.g. "mailto:rpki@example.net".
+    #[serde(default)]
+    pub contact: Vec<String>,
+
+    /// Whether the operator agreed to the terms of service of the ACME
+    /// server, which is required to create an account.
+    #[serde(default)]
+    pub terms_of_service_agreed: bool,
+
+    #[serde(default)]
+    pub challenge: AcmeChallengeType,
+
+    /// The address on which HTTP-01 challenges, and nothing else, are
+    /// answered over plain HTTP, e.g. "0.0.0.0:80". Required for HTTP-01,
+    /// because ACME servers do not validate it over HTTPS.
+    #[serde(default)]
+    pub http_challenge_listen: Option<SocketAddr>,
+
+    /// Renew the certificate when it expires within this many days.
+    #[serde(default = "AcmeConfig::dflt_renew_before_days")]
+    pub renew_before_days: u32,
+}
+
+impl AcmeConfig {
+    fn dflt_directory() -> String {
+        "https://acme-v02.api.letsencrypt.org/directory".to_string()
+    }
+
+    fn dflt_renew_before_days() -> u32 {
+        30
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        if url::Url::parse(&self.directory).is_err() {
+            return Err(format!("invalid acme.directory: {}", self.directory));
+        }
+
+        if !self.terms_of_service_agreed {
+            return Err(String::from(
+                "acme.terms_of_service_agreed must be true to create an account at the ACME server",
+            ));
+        }
+
+        if let Some(contact) = self.contact.iter().find(|contact| !contact.starts_with("mailto:")) {
+            return Err(format!("acme.contact must be mailto: URIs, found: {}", contact));
+        }
+
+        if self.renew_before_days < 1 {
+            return Err(String::from("acme.renew_before_days must be 1 or higher"));
+        }
+
+        if self.challenge == AcmeChallengeType::Http01 && self.http_challenge_listen.is_none() {
+            return Err(String::from(
+                "acme.http_challenge_listen must be set for the http-01 challenge, e.g. \"0.0.0.0:80\"",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the hostname to request a certificate for, i.e. the host of the
+/// service URI if it is a domain name other than localhost.
+pub fn acme_domain(service_uri: &uri::Https) -> Option<String> {
+    match url::Url::parse(service_uri.as_str()).ok()?.host()? {
+        url::Host::Domain(domain) if domain != "localhost" => Some(domain.to_string()),
+        _ => None,
+    }
+}
+
+//------------ AcmeChallengeType ---------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+pub enum AcmeChallengeType {
+    #[default]
+    #[serde(rename = "http-01")]
+    Http01,
+
+    #[serde(rename = "tls-alpn-01")]
+    TlsAlpn01,
+}
+
+impl fmt::Display for AcmeChallengeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AcmeChallengeType::Http01 => write!(f, "http-01"),
+            AcmeChallengeType::TlsAlpn01 => write!(f, "tls-alpn-01"),
+        }
+    }
+}
+
+//------------ AcmeChallenges ------------------------------------------------
+
+/// The responses to the pending challenges of an order, which are answered
+/// by the HTTP and TLS listeners until the order is validated.
+#[derive(Clone, Default)]
+pub struct AcmeChallenges {
+    // The key authorization for the token of each HTTP-01 challenge.
+    http: Arc<RwLock<HashMap<String, String>>>,
+
+    // The validation certificate for the domain of each TLS-ALPN-01
+    // challenge.
+    tls_alpn: Arc<RwLock<HashMap<String, CertifiedKey>>>,
+}
+
+impl AcmeChallenges {
+    /// Returns the response for the token of a pending HTTP-01 challenge.
+    pub fn http_response(&self, token: &str) -> Option<String> {
+        self.http.read().unwrap().get(token).cloned()
+    }
+
+    fn tls_alpn_certificate(&self, domain: &str) -> Option<CertifiedKey> {
+        self.tls_alpn.read().unwrap().get(domain).cloned()
+    }
+
+    fn clear(&self) {
+        self.http.write().unwrap().clear();
+        self.tls_alpn.write().unwrap().clear();
+    }
+}
+
+//------------ AcmeCertResolver ----------------------------------------------
+
+/// Uses the validation certificate for TLS-ALPN-01 challenges, and the
+/// normal certificate for all other connections.
+pub(crate) struct AcmeCertResolver {
+    inner: Arc<dyn ResolvesServerCert>,
+    challenges: AcmeChallenges,
+}
+
+impl AcmeCertResolver {
+    pub(crate) fn new(inner: Arc<dyn ResolvesServerCert>, challenges: AcmeChallenges) -> Self {
+        AcmeCertResolver { inner, challenges }
+    }
+}
+
+impl ResolvesServerCert for AcmeCertResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<CertifiedKey> {
+        let is_validation = client_hello
+            .alpn()
+            .map(|protocols| protocols.contains(&ACME_TLS_ALPN_PROTOCOL))
+            .unwrap_or(false);
+
+        if is_validation {
+            let domain: &str = client_hello.server_name()?.into();
+            self.challenges.tls_alpn_certificate(domain)
+        } else {
+            self.inner.resolve(client_hello)
+        }
+    }
+}
+
+//------------ Renewal -------------------------------------------------------
+
+/// Obtains a new certificate for the domain if the current certificate is
+/// not for that domain, e.g. because it was generated by Krill, or if it
+/// expires soon. Returns whether a new certificate was saved.
+pub async fn renew_certificate_if_needed(
+    data_dir: &Path,
+    domain: &str,
+    config: &AcmeConfig,
+    challenges: &AcmeChallenges,
+) -> Result<bool, Error> {
+    if !needs_certificate(data_dir, domain, config.renew_before_days) {
+        return Ok(false);
+    }
+
+    info!("Requesting HTTPS certificate for {} from {}", domain, config.directory);
+
+    let mut client = AcmeClient::new(&config.directory, account_key(data_dir)?).await?;
+    client.register(config).await?;
+
+    let res = client.order_certificate(domain, config.challenge, challenges).await;
+    challenges.clear();
+    let (key, chain) = res?;
+
+    file::save(&key, &tls_keys::key_file_path(data_dir))?;
+    file::save(chain.as_bytes(), &tls_keys::cert_file_path(data_dir))?;
+
+    info!("Saved new HTTPS certificate for {}", domain);
+    Ok(true)
+}
+
+fn needs_certificate(data_dir: &Path, domain: &str, renew_before_days: u32) -> bool {
+    let cert = match file::read(&tls_keys::cert_file_path(data_dir))
+        .ok()
+        .and_then(|pem| X509::from_pem(&pem).ok())
+    {
+        Some(cert) => cert,
+        None => return true,
+    };
+
+    let is_for_domain = cert
+        .subject_alt_names()
+        .map(|names| names.iter().any(|name| name.dnsname() == Some(domain)))
+        .unwrap_or(false);
+
+    let expires_soon = Asn1Time::days_from_now(renew_before_days)
+        .map(|renew_time| cert.not_after() < renew_time)
+        .unwrap_or(true);
+
+    !is_for_domain || expires_soon
+}
+
+/// Returns the key of the ACME account, which is created on first use.
+fn account_key(data_dir: &Path) -> Result<PKey<Private>, Error> {
+    let path = file::file_path(&data_dir.join(tls_keys::HTTPS_SUB_DIR), ACCOUNT_KEY_FILE);
+    if path.exists() {
+        Ok(PKey::private_key_from_pem(&file::read(&path)?)?)
+    } else {
+        let key = new_key()?;
+        file::save(&key.private_key_to_pem_pkcs8()?, &path)?;
+        Ok(key)
+    }
+}
+
+//------------ AcmeClient ----------------------------------------------------
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+#[derive(Deserialize)]
+struct Order {
+    status: String,
+    authorizations: Vec<String>,
+    finalize: String,
+    certificate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Authorization {
+    status: String,
+    identifier: Identifier,
+    challenges: Vec<Challenge>,
+}
+
+#[derive(Deserialize)]
+struct Identifier {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct Challenge {
+    #[serde(rename = "type")]
+    challenge_type: String,
+    url: String,
+    #[serde(default)]
+    token: String,
+    error: Option<Problem>,
+}
+
+#[derive(Deserialize)]
+struct Problem {
+    #[serde(rename = "type")]
+    problem_type: String,
+    #[serde(default)]
+    detail: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.problem_type, self.detail)
+    }
+}
+
+/// A minimal ACME client, which signs its requests with an ECDSA P-256
+/// account key.
+struct AcmeClient {
+    client: reqwest::Client,
+    directory: Directory,
+    key: PKey<Private>,
+    account: Option<String>,
+    nonce: Option<String>,
+}
+
+impl AcmeClient {
+    async fn new(directory_uri: &str, key: PKey<Private>) -> Result<Self, Error> {
+        let client = httpclient::client(directory_uri).map_err(|e| Error::Http(e.to_string()))?;
+        let directory = client
+            .get(directory_uri)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| Error::http(directory_uri, e))?
+            .json()
+            .await
+            .map_err(|e| Error::http(directory_uri, e))?;
+
+        Ok(AcmeClient {
+            client,
+            directory,
+            key,
+            account: None,
+            nonce: None,
+        })
+    }
+
+    /// Creates the account, or finds the existing account for the key.
+    async fn register(&mut self, config: &AcmeConfig) -> Result<(), Error> {
+        let url = self.directory.new_account.clone();
+        let payload = json!({
+            "termsOfServiceAgreed": config.terms_of_service_agreed,
+            "contact": config.contact,
+        });
+        let res = self.post(&url, Some(&payload)).await?;
+        self.account = Some(location(&url, &res)?);
+        Ok(())
+    }
+
+    /// Orders a certificate for the domain, and returns the new private key
+    /// and the certificate chain in PEM format.
+    async fn order_certificate(
+        &mut self,
+        domain: &str,
+        challenge_type: AcmeChallengeType,
+        challenges: &AcmeChallenges,
+    ) -> Result<(Vec<u8>, String), Error> {
+        let url = self.directory.new_order.clone();
+        let payload = json!({ "identifiers": [{ "type": "dns", "value": domain }] });
+        let res = self.post(&url, Some(&payload)).await?;
+        let order_url = location(&url, &res)?;
+        let order: Order = res.json().await.map_err(|e| Error::http(&url, e))?;
+
+        for authorization_url in &order.authorizations {
+            let authorization: Authorization = self.fetch(authorization_url).await?;
+            if authorization.status == "valid" {
+                continue;
+            }
+
+            let challenge = authorization
+                .challenges
+                .iter()
+                .find(|challenge| challenge.challenge_type == challenge_type.to_string())
+                .ok_or_else(|| Error::Protocol(format!("no {} challenge offered for {}", challenge_type, domain)))?;
+
+            let key_authorization = format!("{}.{}", challenge.token, self.thumbprint()?);
+            match challenge_type {
+                AcmeChallengeType::Http01 => {
+                    challenges
+                        .http
+                        .write()
+                        .unwrap()
+                        .insert(challenge.token.clone(), key_authorization);
+                }
+                AcmeChallengeType::TlsAlpn01 => {
+                    let domain = authorization.identifier.value.clone();
+                    let certificate = validation_certificate(&domain, &key_authorization)?;
+                    challenges.tls_alpn.write().unwrap().insert(domain, certificate);
+                }
+            }
+
+            // Tell the server that the challenge can be validated.
+            self.post(&challenge.url, Some(&json!({}))).await?;
+            self.wait_for_authorization(authorization_url).await?;
+        }
+
+        let key = new_key()?;
+        self.post(&order.finalize, Some(&json!({ "csr": b64(&csr(domain, &key)?) })))
+            .await?;
+
+        let certificate_url = self.wait_for_order(&order_url).await?;
+        let chain = self
+            .post(&certificate_url, None)
+            .await?
+            .text()
+            .await
+            .map_err(|e| Error::http(&certificate_url, e))?;
+
+        Ok((key.private_key_to_pem_pkcs8()?, chain))
+    }
+
+    async fn wait_for_authorization(&mut self, url: &str) -> Result<(), Error> {
+        for _ in 0..POLL_ATTEMPTS {
+            let authorization: Authorization = self.fetch(url).await?;
+            match authorization.status.as_str() {
+                "valid" => return Ok(()),
+                "pending" => tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECONDS)).await,
+                status => {
+                    let problem = authorization
+                        .challenges
+                        .iter()
+                        .find_map(|challenge| challenge.error.as_ref())
+                        .map(|problem| format!(", error: {}", problem))
+                        .unwrap_or_default();
+                    return Err(Error::Protocol(format!(
+                        "authorization for {} is {}{}",
+                        authorization.identifier.value, status, problem
+                    )));
+                }
+            }
+        }
+        Err(Error::Protocol(format!("authorization not completed in time: {}", url)))
+    }
+
+    /// Waits until the order is valid, and returns the certificate URL.
+    async fn wait_for_order(&mut self, url: &str) -> Result<String, Error> {
+        for _ in 0..POLL_ATTEMPTS {
+            let order: Order = self.fetch(url).await?;
+            match order.status.as_str() {
+                "valid" => {
+                    return order
+                        .certificate
+                        .ok_or_else(|| Error::Protocol(format!("valid order without certificate: {}", url)))
+                }
+                "pending" | "ready" | "processing" => {
+                    tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECONDS)).await
+                }
+                status => return Err(Error::Protocol(format!("order is {}: {}", status, url))),
+            }
+        }
+        Err(Error::Protocol(format!("order not completed in time: {}", url)))
+    }
+
+    /// Gets a resource using a POST-as-GET request.
+    async fn fetch<T: DeserializeOwned>(&mut self, url: &str) -> Result<T, Error> {
+        self.post(url, None)
+            .await?
+            .json()
+            .await
+            .map_err(|e| Error::http(url, e))
+    }
+
+    /// Sends a signed request, or a POST-as-GET request if there is no
+    /// payload, and returns the response if successful.
+    async fn post(&mut self, url: &str, payload: Option<&Value>) -> Result<reqwest::Response, Error> {
+        // Servers may reject a nonce at any time, in which case the request
+        // is sent once more with the fresh nonce from the error response.
+        let mut retry = true;
+        loop {
+            let nonce = self.nonce().await?;
+            let res = self
+                .client
+                .post(url)
+                .header(CONTENT_TYPE, "application/jose+json")
+                .body(self.jws(url, &nonce, payload)?)
+                .send()
+                .await
+                .map_err(|e| Error::http(url, e))?;
+
+            self.nonce = res
+                .headers()
+                .get("Replay-Nonce")
+                .and_then(|nonce| nonce.to_str().ok())
+                .map(|nonce| nonce.to_string());
+
+            let status = res.status();
+            if status.is_success() {
+                return Ok(res);
+            }
+
+            match res.json::<Problem>().await {
+                Ok(problem) if retry && problem.problem_type == "urn:ietf:params:acme:error:badNonce" => retry = false,
+                Ok(problem) => return Err(Error::Protocol(format!("{} returned {}", url, problem))),
+                Err(_) => return Err(Error::Protocol(format!("{} returned status {}", url, status))),
+            }
+        }
+    }
+
+    async fn nonce(&mut self) -> Result<String, Error> {
+        if let Some(nonce) = self.nonce.take() {
+            return Ok(nonce);
+        }
+
+        let url = &self.directory.new_nonce;
+        let res = self.client.head(url).send().await.map_err(|e| Error::http(url, e))?;
+        res.headers()
+            .get("Replay-Nonce")
+            .and_then(|nonce| nonce.to_str().ok())
+            .map(|nonce| nonce.to_string())
+            .ok_or_else(|| Error::Protocol(format!("no nonce received from {}", url)))
+    }
+
+    /// Returns the request as a JSON Web Signature in flattened JSON
+    /// serialization. The account key is included until the account URL is
+    /// known.
+    fn jws(&self, url: &str, nonce: &str, payload: Option<&Value>) -> Result<String, Error> {
+        let mut protected = json!({ "alg": "ES256", "nonce": nonce, "url": url });
+        match &self.account {
+            Some(account) => protected["kid"] = json!(account),
+            None => protected["jwk"] = jwk(&self.key)?,
+        }
+
+        let protected = b64(protected.to_string().as_bytes());
+        let payload = payload
+            .map(|payload| b64(payload.to_string().as_bytes()))
+            .unwrap_or_default();
+        let signature = b64(&sign(&self.key, format!("{}.{}", protected, payload).as_bytes())?);
+
+        Ok(json!({ "protected": protected, "payload": payload, "signature": signature }).to_string())
+    }
+
+    /// Returns the JWK thumbprint of the account key (RFC 7638).
+    fn thumbprint(&self) -> Result<String, Error> {
+        let jwk = jwk(&self.key)?.to_string();
+        Ok(b64(&hash(MessageDigest::sha256(), jwk.as_bytes())?))
+    }
+}
+
+fn location(url: &str, res: &reqwest::Response) -> Result<String, Error> {
+    res.headers()
+        .get(LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .map(|location| location.to_string())
+        .ok_or_else(|| Error::Protocol(format!("no location received from {}", url)))
+}
+
+//------------ Crypto helpers ------------------------------------------------
+
+fn b64(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+fn new_key() -> Result<PKey<Private>, ErrorStack> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    PKey::from_ec_key(EcKey::generate(&group)?)
+}
+
+/// Returns the public key as a JWK. Its members are in lexicographic order,
+/// as required for the thumbprint.
+fn jwk(key: &PKey<Private>) -> Result<Value, ErrorStack> {
+    let ec_key = key.ec_key()?;
+    let mut ctx = BigNumContext::new()?;
+    let mut x = BigNum::new()?;
+    let mut y = BigNum::new()?;
+    ec_key
+        .public_key()
+        .affine_coordinates_gfp(ec_key.group(), &mut x, &mut y, &mut ctx)?;
+
+    Ok(json!({
+        "crv": "P-256",
+        "kty": "EC",
+        "x": b64(&x.to_vec_padded(32)?),
+        "y": b64(&y.to_vec_padded(32)?),
+    }))
+}
+
+/// Signs the data using ES256, which uses the plain concatenation of r and s
+/// rather than the DER encoding of the signature.
+fn sign(key: &PKey<Private>, data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let digest = hash(MessageDigest::sha256(), data)?;
+    let signature = EcdsaSig::sign(&digest, &key.ec_key()?)?;
+    let mut res = signature.r().to_vec_padded(32)?;
+    res.extend(signature.s().to_vec_padded(32)?);
+    Ok(res)
+}
+
+fn csr(domain: &str, key: &PKey<Private>) -> Result<Vec<u8>, ErrorStack> {
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::COMMONNAME, domain)?;
+
+    let mut builder = X509ReqBuilder::new()?;
+    builder.set_subject_name(&name.build())?;
+    builder.set_pubkey(key)?;
+
+    let san = SubjectAlternativeName::new()
+        .dns(domain)
+        .build(&builder.x509v3_context(None))?;
+    let mut extensions = Stack::new()?;
+    extensions.push(san)?;
+    builder.add_extensions(&extensions)?;
+
+    builder.sign(key, MessageDigest::sha256())?;
+    builder.build().to_der()
+}
+
+/// Creates the self-signed certificate for a TLS-ALPN-01 challenge, which
+/// contains the SHA-256 digest of the key authorization in the critical
+/// acmeIdentifier extension.
+fn validation_certificate(domain: &str, key_authorization: &str) -> Result<CertifiedKey, Error> {
+    let key = new_key()?;
+
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::COMMONNAME, domain)?;
+    let name = name.build();
+
+    let mut builder = X509Builder::new()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(&BigNum::from_u32(1)?.to_asn1_integer()?)?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(&name)?;
+    builder.set_pubkey(&key)?;
+    builder.set_not_before(&Asn1Time::days_from_now(0)?)?;
+    builder.set_not_after(&Asn1Time::days_from_now(7)?)?;
+
+    let san = SubjectAlternativeName::new()
+        .dns(domain)
+        .build(&builder.x509v3_context(None, None))?;
+    builder.append_extension(san)?;
+
+    // The extension value is an OCTET STRING of 32 bytes.
+    let digest = hash(MessageDigest::sha256(), key_authorization.as_bytes())?;
+    let hex: Vec<String> = digest.iter().map(|byte| format!("{:02X}", byte)).collect();
+    #[allow(deprecated)]
+    let acme_identifier = X509Extension::new(
+        None,
+        None,
+        "1.3.6.1.5.5.7.1.31",
+        &format!("critical,DER:04:20:{}", hex.join(":")),
+    )?;
+    builder.append_extension(acme_identifier)?;
+
+    builder.sign(&key, MessageDigest::sha256())?;
+    let cert = builder.build();
+
+    let private_key = pemfile::pkcs8_private_keys(&mut key.private_key_to_pem_pkcs8()?.as_slice())
+        .ok()
+        .and_then(|mut keys| keys.pop())
+        .ok_or_else(|| Error::Protocol("cannot use validation key".to_string()))?;
+    let signing_key =
+        sign::any_supported_type(&private_key).map_err(|_| Error::Protocol("cannot use validation key".to_string()))?;
+
+    Ok(CertifiedKey::new(
+        vec![Certificate(cert.to_der()?)],
+        Arc::new(signing_key),
+    ))
+}
+
+//------------ Error ---------------------------------------------------------
+
+#[derive(Debug)]
+pub enum Error {
+    Http(String),
+    Protocol(String),
+    OpenSsl(ErrorStack),
+    Io(KrillIoError),
+}
+
+impl Error {
+    fn http(uri: &str, e: impl fmt::Display) -> Self {
+        Error::Http(format!("{}: {}", uri, e))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(msg) => write!(f, "Could not reach ACME server: {}", msg),
+            Error::Protocol(msg) => write!(f, "ACME error: {}", msg),
+            Error::OpenSsl(e) => write!(f, "{}", e),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<ErrorStack> for Error {
+    fn from(e: ErrorStack) -> Self {
+        Error::OpenSsl(e)
+    }
+}
+
+impl From<KrillIoError> for Error {
+    fn from(e: KrillIoError) -> Self {
+        Error::Io(e)
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        str::FromStr,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use hyper::{
+        server::conn::AddrIncoming,
+        service::{make_service_fn, service_fn},
+    };
+    use openssl::x509::X509Req;
+
+    use super::*;
+
+    use crate::test;
+
+    #[test]
+    fn acme_domain_from_service_uri() {
+        let domain = |uri: &str| acme_domain(&uri::Https::from_str(uri).unwrap());
+        assert_eq!(
+            domain("https://krill.example.net/"),
+            Some("krill.example.net".to_string())
+        );
+        assert_eq!(
+            domain("https://krill.example.net:3000/"),
+            Some("krill.example.net".to_string())
+        );
+        assert_eq!(domain("https://localhost:3000/"), None);
+        assert_eq!(domain("https://192.0.2.1/"), None);
+    }
+
+    #[test]
+    fn jws_signature_verifies() {
+        let key = new_key().unwrap();
+        let signature = sign(&key, b"protected.payload").unwrap();
+        assert_eq!(signature.len(), 64);
+
+        let r = BigNum::from_slice(&signature[..32]).unwrap();
+        let s = BigNum::from_slice(&signature[32..]).unwrap();
+        let signature = EcdsaSig::from_private_components(r, s).unwrap();
+        let digest = hash(MessageDigest::sha256(), b"protected.payload").unwrap();
+        assert!(signature.verify(&digest, &key.ec_key().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn validation_certificate_is_usable() {
+        validation_certificate("krill.example.net", "token.thumbprint").unwrap();
+    }
+
+    //------------ Mock ACME server ------------------------------------------
+
+    const MOCK_DOMAIN: &str = "krill.example.net";
+    const MOCK_TOKEN: &str = "mock-token";
+
+    /// A mock ACME server with a single account, order and authorization,
+    /// which validates the HTTP-01 challenge by looking at the responses
+    /// Krill would serve, and issues a certificate for the CSR.
+    #[derive(Clone)]
+    struct MockAcme {
+        base: String,
+        challenges: AcmeChallenges,
+        validated: Arc<AtomicBool>,
+        bad_nonce_sent: Arc<AtomicBool>,
+        certificate: Arc<RwLock<Option<String>>>,
+    }
+
+    impl MockAcme {
+        async fn start(challenges: AcmeChallenges) -> Self {
+            let incoming = AddrIncoming::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+            let mock = MockAcme {
+                base: format!("http://{}", incoming.local_addr()),
+                challenges,
+                validated: Arc::new(AtomicBool::new(false)),
+                bad_nonce_sent: Arc::new(AtomicBool::new(false)),
+                certificate: Arc::new(RwLock::new(None)),
+            };
+
+            let service_mock = mock.clone();
+            let service = make_service_fn(move |_| {
+                let mock = service_mock.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |req| {
+                        let mock = mock.clone();
+                        async move { Ok::<_, Infallible>(mock.respond(req).await) }
+                    }))
+                }
+            });
+            tokio::spawn(hyper::Server::builder(incoming).serve(service));
+
+            mock
+        }
+
+        fn url(&self, path: &str) -> String {
+            format!("{}{}", self.base, path)
+        }
+
+        fn order(&self, status: &str) -> Value {
+            let mut order = json!({
+                "status": status,
+                "authorizations": [ self.url("/authz/1") ],
+                "finalize": self.url("/finalize/1"),
+            });
+            if status == "valid" {
+                order["certificate"] = json!(self.url("/cert/1"));
+            }
+            order
+        }
+
+        async fn respond(&self, req: hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body> {
+            let path = req.uri().path().to_string();
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let (protected, payload) = decode_jws(&body);
+
+            let (status, location, body) = match path.as_str() {
+                "/directory" => (
+                    200,
+                    None,
+                    json!({
+                        "newNonce": self.url("/nonce"),
+                        "newAccount": self.url("/account"),
+                        "newOrder": self.url("/order"),
+                    }),
+                ),
+                "/nonce" => (200, None, Value::Null),
+                "/account" if !self.bad_nonce_sent.swap(true, Ordering::SeqCst) => {
+                    (400, None, problem("badNonce", "nonce expired"))
+                }
+                "/account" if protected["jwk"].is_null() || payload["termsOfServiceAgreed"] != json!(true) => {
+                    (400, None, problem("malformed", "jwk and terms of service expected"))
+                }
+                "/account" => (201, Some(self.url("/account/1")), json!({ "status": "valid" })),
+                _ if protected["kid"] != json!(self.url("/account/1")) => {
+                    (400, None, problem("malformed", "account kid expected"))
+                }
+                "/order" => (201, Some(self.url("/order/1")), self.order("pending")),
+                "/authz/1" => {
+                    let status = if self.validated.load(Ordering::SeqCst) {
+                        "valid"
+                    } else {
+                        "pending"
+                    };
+                    let body = json!({
+                        "status": status,
+                        "identifier": { "type": "dns", "value": MOCK_DOMAIN },
+                        "challenges": [
+                            { "type": "http-01", "url": self.url("/chall/1"), "token": MOCK_TOKEN },
+                            { "type": "tls-alpn-01", "url": self.url("/chall/2"), "token": "other-token" },
+                        ],
+                    });
+                    (200, None, body)
+                }
+                "/chall/1" => {
+                    let prefix = format!("{}.", MOCK_TOKEN);
+                    match self.challenges.http_response(MOCK_TOKEN) {
+                        Some(key_authorization) if key_authorization.starts_with(&prefix) => {
+                            self.validated.store(true, Ordering::SeqCst);
+                            (200, None, json!({ "status": "valid" }))
+                        }
+                        _ => (403, None, problem("unauthorized", "challenge not answered")),
+                    }
+                }
+                "/finalize/1" => match issue_certificate(&payload) {
+                    Some(certificate) => {
+                        *self.certificate.write().unwrap() = Some(certificate);
+                        (200, None, self.order("processing"))
+                    }
+                    None => (400, None, problem("badCSR", "no CSR for the domain")),
+                },
+                "/order/1" => (200, None, self.order("valid")),
+                "/cert/1" => {
+                    let certificate = self.certificate.read().unwrap().clone().unwrap_or_default();
+                    return mock_response(200, None, certificate);
+                }
+                _ => (404, None, problem("malformed", "not found")),
+            };
+
+            let body = if body.is_null() {
+                String::new()
+            } else {
+                body.to_string()
+            };
+            mock_response(status, location, body)
+        }
+    }
+
+    fn mock_response(status: u16, location: Option<String>, body: String) -> hyper::Response<hyper::Body> {
+        let mut builder = hyper::Response::builder()
+            .status(status)
+            .header("Replay-Nonce", format!("nonce-{}", rand::random::<u32>()));
+        if let Some(location) = location {
+            builder = builder.header(LOCATION, location);
+        }
+        builder.body(hyper::Body::from(body)).unwrap()
+    }
+
+    fn problem(problem_type: &str, detail: &str) -> Value {
+        json!({ "type": format!("urn:ietf:params:acme:error:{}", problem_type), "detail": detail })
+    }
+
+    /// Returns the protected header and payload of a JWS, or nulls if the
+    /// request has none, e.g. for the directory or POST-as-GET.
+    fn decode_jws(body: &[u8]) -> (Value, Value) {
+        let decode = |jws: &Value, member: &str| {
+            jws[member]
+                .as_str()
+                .and_then(|part| base64::decode_config(part, base64::URL_SAFE_NO_PAD).ok())
+                .and_then(|json| serde_json::from_slice(&json).ok())
+                .unwrap_or(Value::Null)
+        };
+        let jws: Value = serde_json::from_slice(body).unwrap_or(Value::Null);
+        (decode(&jws, "protected"), decode(&jws, "payload"))
+    }
+
+    /// Issues a certificate for the key in the CSR of a finalize request, if
+    /// it is signed by that key and mentions the domain.
+    fn issue_certificate(payload: &Value) -> Option<String> {
+        let der = base64::decode_config(payload["csr"].as_str()?, base64::URL_SAFE_NO_PAD).ok()?;
+        let csr = X509Req::from_der(&der).ok()?;
+        let key = csr.public_key().ok()?;
+        if !csr.verify(&key).ok()? {
+            return None;
+        }
+        if !der
+            .windows(MOCK_DOMAIN.len())
+            .any(|window| window == MOCK_DOMAIN.as_bytes())
+        {
+            return None;
+        }
+
+        let issuer_key = new_key().ok()?;
+        let mut name = X509NameBuilder::new().ok()?;
+        name.append_entry_by_nid(Nid::COMMONNAME, MOCK_DOMAIN).ok()?;
+        let name = name.build();
+
+        let mut builder = X509Builder::new().ok()?;
+        builder.set_version(2).ok()?;
+        builder.set_subject_name(&name).ok()?;
+        builder.set_issuer_name(&name).ok()?;
+        builder.set_pubkey(&key).ok()?;
+        builder.set_not_before(&Asn1Time::days_from_now(0).ok()?).ok()?;
+        builder.set_not_after(&Asn1Time::days_from_now(90).ok()?).ok()?;
+        let san = SubjectAlternativeName::new()
+            .dns(MOCK_DOMAIN)
+            .build(&builder.x509v3_context(None, None))
+            .ok()?;
+        builder.append_extension(san).ok()?;
+        builder.sign(&issuer_key, MessageDigest::sha256()).ok()?;
+
+        String::from_utf8(builder.build().to_pem().ok()?).ok()
+    }
+
+    #[tokio::test]
+    async fn renew_certificate_using_mock_acme_server() {
+        let challenges = AcmeChallenges::default();
+        let mock = MockAcme::start(challenges.clone()).await;
+        let data_dir = test::tmp_dir();
+
+        let config = AcmeConfig {
+            directory: mock.url("/directory"),
+            contact: vec!["mailto:rpki@example.net".to_string()],
+            terms_of_service_agreed: true,
+            challenge: AcmeChallengeType::Http01,
+            http_challenge_listen: Some("127.0.0.1:0".parse().unwrap()),
+            renew_before_days: 30,
+        };
+        config.verify().unwrap();
+
+        // There is no certificate for the domain yet, so one is ordered.
+        assert!(
+            renew_certificate_if_needed(&data_dir, MOCK_DOMAIN, &config, &challenges)
+                .await
+                .unwrap()
+        );
+        assert!(mock.validated.load(Ordering::SeqCst));
+        assert!(challenges.http_response(MOCK_TOKEN).is_none());
+
+        let cert = X509::from_pem(&file::read(&tls_keys::cert_file_path(&data_dir)).unwrap()).unwrap();
+        let key = PKey::private_key_from_pem(&file::read(&tls_keys::key_file_path(&data_dir)).unwrap()).unwrap();
+        assert!(cert.public_key().unwrap().public_eq(&key));
+        assert!(data_dir.join(tls_keys::HTTPS_SUB_DIR).join(ACCOUNT_KEY_FILE).exists());
+
+        // The new certificate does not need to be renewed yet.
+        assert!(
+            !renew_certificate_if_needed(&data_dir, MOCK_DOMAIN, &config, &challenges)
+                .await
+                .unwrap()
+        );
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+}
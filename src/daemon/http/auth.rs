@@ -7,13 +7,20 @@ use crate::{
 
 #[cfg(feature = "multi-user")]
 use {
-    crate::daemon::{auth::LoggedInUser, http::server::render_error_redirect},
-    urlparse::quote,
+    crate::daemon::{
+        auth::{LoggedInUser, LoginMethod},
+        http::server::render_error_redirect,
+    },
+    hyper::{header, StatusCode},
+    urlparse::{quote, urlparse, GetQuery},
 };
 
+pub const AUTH_BACKCHANNEL_LOGOUT_ENDPOINT: &str = "/auth/backchannel-logout";
 pub const AUTH_CALLBACK_ENDPOINT: &str = "/auth/callback";
 pub const AUTH_LOGIN_ENDPOINT: &str = "/auth/login";
 pub const AUTH_LOGOUT_ENDPOINT: &str = "/auth/logout";
+pub const AUTH_METHODS_ENDPOINT: &str = "/auth/methods";
+pub const AUTH_SELECT_ENDPOINT: &str = "/auth/select";
 
 #[cfg(feature = "multi-user")]
 pub fn url_encode<S: AsRef<str>>(s: S) -> Result<String, Error> {
@@ -40,6 +47,96 @@ fn build_auth_redirect_location(user: LoggedInUser) -> Result<String, Error> {
     ))
 }
 
+/// Renders a page listing the enabled login methods, for when the web UI
+/// should offer more than its default method.
+#[cfg(feature = "multi-user")]
+fn render_login_methods(methods: Vec<LoginMethod>) -> HttpResponse {
+    fn label(name: &str) -> &str {
+        match name {
+            "admin-token" => "Admin token",
+            "config-file" => "Username and password",
+            "openid-connect" => "Single sign-on (OpenID Connect)",
+            "ldap" => "Directory account (LDAP)",
+            _ => name,
+        }
+    }
+
+    let items: String = methods
+        .iter()
+        .map(|method| {
+            format!(
+                "<li><a href=\"{}?method={}\">{}</a></li>",
+                AUTH_SELECT_ENDPOINT,
+                method.name,
+                label(&method.name)
+            )
+        })
+        .collect();
+
+    let page = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Krill login</title></head>\
+         <body><h1>Log in to Krill</h1><ul>{}</ul></body></html>",
+        items
+    );
+    HttpResponse::html(page.as_bytes())
+}
+
+/// Turns the login URL response of a provider into a redirect, keeping any
+/// cookies that the provider sets. Login URLs that are routes of the web UI
+/// are made relative to its base path.
+#[cfg(feature = "multi-user")]
+async fn redirect_to_login_url(res: HttpResponse) -> Result<HttpResponse, Error> {
+    let (mut parts, body) = res.response().into_parts();
+    let body = hyper::body::to_bytes(body)
+        .await
+        .map_err(|err| Error::custom(format!("Cannot read login URL: {}", err)))?;
+    let location = String::from_utf8_lossy(&body).to_string();
+    let location = if location.starts_with('/') {
+        format!("/ui{}", location)
+    } else {
+        location
+    };
+
+    parts.status = StatusCode::FOUND;
+    parts.headers.remove(header::CONTENT_TYPE);
+    parts.headers.insert(
+        header::LOCATION,
+        location
+            .parse()
+            .map_err(|_| Error::custom(format!("Invalid login URL: {}", location)))?,
+    );
+    Ok(HttpResponse::new(hyper::Response::from_parts(
+        parts,
+        hyper::Body::empty(),
+    )))
+}
+
+/// Responds to the provider as required by OpenID Connect Back-Channel Logout
+/// 1.0, i.e. without caching and with a 400 Bad Request if the logout failed.
+#[cfg(feature = "multi-user")]
+fn render_backchannel_logout(res: Result<(), Error>) -> RoutingResult {
+    let (status, body) = match res {
+        Ok(()) => (StatusCode::OK, hyper::Body::empty()),
+        Err(err) => {
+            warn!("OpenID Connect: Back-channel logout failed: {}", err);
+            let body = serde_json::json!({
+                "error": "invalid_request",
+                "error_description": err.to_string(),
+            });
+            (StatusCode::BAD_REQUEST, hyper::Body::from(body.to_string()))
+        }
+    };
+
+    Ok(HttpResponse::new(
+        hyper::Response::builder()
+            .status(status)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CACHE_CONTROL, "no-cache, no-store")
+            .body(body)
+            .unwrap(),
+    ))
+}
+
 #[allow(clippy::unnecessary_wraps)]
 fn render_error(err: Error) -> RoutingResult {
     Ok(HttpResponse::response_from_error(err))
@@ -67,11 +164,33 @@ pub async fn auth(req: Request) -> RoutingResult {
                 .or_else(render_error_redirect)
         }
         AUTH_LOGIN_ENDPOINT if *req.method() == Method::GET => req.get_login_url().await.or_else(render_error),
+        #[cfg(feature = "multi-user")]
+        AUTH_METHODS_ENDPOINT if *req.method() == Method::GET => Ok(HttpResponse::json(&req.login_methods())),
+        #[cfg(feature = "multi-user")]
+        AUTH_SELECT_ENDPOINT if *req.method() == Method::GET => {
+            let method_selected = urlparse(req.request.uri().to_string())
+                .get_parsed_query()
+                .map(|query| query.get_first_from_str("method").is_some())
+                .unwrap_or(false);
+
+            if method_selected {
+                match req.get_login_url().await {
+                    Ok(res) => redirect_to_login_url(res).await.or_else(render_error_redirect),
+                    Err(err) => render_error_redirect(err),
+                }
+            } else {
+                Ok(render_login_methods(req.login_methods()))
+            }
+        }
         AUTH_LOGIN_ENDPOINT if *req.method() == Method::POST => match req.login().await {
             Ok(logged_in_user) => Ok(HttpResponse::json(&logged_in_user)),
             Err(err) => render_error(err),
         },
         AUTH_LOGOUT_ENDPOINT if *req.method() == Method::POST => req.logout().await.or_else(render_error),
+        #[cfg(feature = "multi-user")]
+        AUTH_BACKCHANNEL_LOGOUT_ENDPOINT if *req.method() == Method::POST => {
+            render_backchannel_logout(req.backchannel_logout().await)
+        }
         _ => Err(req),
     }
 }
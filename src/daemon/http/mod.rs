@@ -1,4 +1,10 @@
-use std::{convert::TryInto, io, str::from_utf8, str::FromStr};
+use std::{
+    convert::TryInto,
+    io,
+    net::{IpAddr, SocketAddr},
+    str::from_utf8,
+    str::FromStr,
+};
 
 use bytes::{Buf, BufMut, Bytes};
 use serde::{de::DeserializeOwned, Serialize};
@@ -17,9 +23,16 @@ use crate::{
     daemon::{auth::LoggedInUser, http::server::State},
 };
 
+#[cfg(feature = "multi-user")]
+use {crate::daemon::auth::LoginMethod, urlparse::GetQuery};
+
+pub mod acme;
 pub mod auth;
+pub mod ratelimit;
+pub mod rrdp;
 pub mod server;
 pub mod statics;
+pub mod status;
 pub mod testbed;
 pub mod tls;
 pub mod tls_keys;
@@ -102,6 +115,10 @@ impl Response {
             builder = builder.header("WWW-Authenticate", "Bearer");
         }
 
+        if let Some(Error::ApiRateLimited(retry_after)) = &self.cause {
+            builder = builder.header("Retry-After", &retry_after.to_string());
+        }
+
         let response = builder.body(self.body.into()).unwrap();
 
         let mut r = HttpResponse::new(response);
@@ -233,15 +250,29 @@ impl HttpResponse {
         Self::ok_response(ContentType::Xml, body)
     }
 
-    pub fn xml_with_cache(body: Vec<u8>, seconds: usize) -> Self {
-        Response {
-            status: StatusCode::OK,
-            content_type: ContentType::Xml,
-            max_age: Some(seconds),
-            body,
-            cause: None,
+    /// Serve an RRDP file, with the validators needed for conditional
+    /// requests. If a content encoding is given, then the body contains the
+    /// file compressed using that encoding.
+    pub fn rrdp_file(
+        body: Vec<u8>,
+        seconds: usize,
+        etag: &str,
+        last_modified: &str,
+        content_encoding: Option<&str>,
+    ) -> Self {
+        let mut builder = hyper::Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", ContentType::Xml.as_ref())
+            .header("Cache-Control", &format!("max-age={}", seconds))
+            .header("ETag", etag)
+            .header("Last-Modified", last_modified)
+            .header("Vary", "Accept-Encoding");
+
+        if let Some(content_encoding) = content_encoding {
+            builder = builder.header("Content-Encoding", content_encoding);
         }
-        .finalize()
+
+        HttpResponse::new(builder.body(body.into()).unwrap())
     }
 
     pub fn rfc8181(body: Vec<u8>) -> Self {
@@ -312,6 +343,19 @@ impl HttpResponse {
         )
     }
 
+    pub fn not_modified(etag: &str, last_modified: &str, seconds: usize) -> Self {
+        Self::new(
+            hyper::Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .header("Cache-Control", &format!("max-age={}", seconds))
+                .header("ETag", etag)
+                .header("Last-Modified", last_modified)
+                .header("Vary", "Accept-Encoding")
+                .body(hyper::Body::empty())
+                .unwrap(),
+        )
+    }
+
     pub fn not_found() -> Self {
         Response::new(StatusCode::NOT_FOUND).finalize()
     }
@@ -323,8 +367,27 @@ impl HttpResponse {
     pub fn forbidden(reason: String) -> Self {
         Self::response_from_error(Error::ApiInsufficientRights(reason))
     }
+
+    pub fn too_many_requests(retry_after: u64) -> Self {
+        Self::response_from_error(Error::ApiRateLimited(retry_after))
+    }
 }
 
+//------------ RemoteAddr ----------------------------------------------------
+
+/// The address of the client that sent a request. This is added to the
+/// extensions of each request by the server.
+#[derive(Clone, Copy, Debug)]
+pub struct RemoteAddr(pub SocketAddr);
+
+//------------ LocalSocket ---------------------------------------------------
+
+/// Marks a request as received on the local Unix domain socket. This is
+/// added to the extensions of each such request by the server, and it is
+/// never derived from the request itself.
+#[derive(Clone, Copy, Debug)]
+pub struct LocalSocket;
+
 //------------ Request -------------------------------------------------------
 
 pub struct Request {
@@ -366,6 +429,14 @@ impl Request {
         }
     }
 
+    /// Returns the first value of the query parameter with this name, if any.
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        let query = self.request.uri().query()?;
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+
     pub async fn upgrade_from_anonymous(&mut self, actor_def: ActorDef) {
         if self.actor.is_anonymous() {
             self.actor = self.state.actor_from_def(actor_def);
@@ -390,6 +461,11 @@ impl Request {
         &self.state
     }
 
+    /// Returns the IP address of the client that sent this request, if known.
+    pub fn source(&self) -> Option<IpAddr> {
+        self.request.extensions().get::<RemoteAddr>().map(|addr| addr.0.ip())
+    }
+
     /// Returns the method of this request.
     pub fn method(&self) -> &Method {
         self.request.method()
@@ -419,17 +495,17 @@ impl Request {
     }
 
     pub async fn api_bytes(self) -> Result<Bytes, Error> {
-        let limit = self.state().config.post_limit_api;
+        let limit = self.state().config().post_limit_api;
         self.read_bytes(limit).await
     }
 
     pub async fn rfc6492_bytes(self) -> Result<Bytes, Error> {
-        let limit = self.state().config.post_limit_rfc6492;
+        let limit = self.state().config().post_limit_rfc6492;
         self.read_bytes(limit).await
     }
 
     pub async fn rfc8181_bytes(self) -> Result<Bytes, Error> {
-        let limit = self.state().config.post_limit_rfc8181;
+        let limit = self.state().config().post_limit_rfc8181;
         self.read_bytes(limit).await
     }
 
@@ -497,7 +573,12 @@ impl Request {
     }
 
     pub async fn get_login_url(&self) -> KrillResult<HttpResponse> {
-        self.state.get_login_url().await
+        self.state.get_login_url(&self.request).await
+    }
+
+    #[cfg(feature = "multi-user")]
+    pub fn login_methods(&self) -> Vec<LoginMethod> {
+        self.state.login_methods()
     }
 
     pub async fn login(&self) -> KrillResult<LoggedInUser> {
@@ -507,6 +588,21 @@ impl Request {
     pub async fn logout(&self) -> KrillResult<HttpResponse> {
         self.state.logout(&self.request).await
     }
+
+    /// Ends the login sessions referred to by the logout token in the form
+    /// encoded body of this request.
+    #[cfg(feature = "multi-user")]
+    pub async fn backchannel_logout(self) -> KrillResult<()> {
+        let state = self.state.clone();
+        let bytes = self.api_bytes().await?;
+        let body = from_utf8(&bytes).map_err(|_| Error::InvalidUtf8Input)?;
+
+        let logout_token = urlparse::parse_qs(body)
+            .get_first_from_str("logout_token")
+            .ok_or_else(|| Error::ApiInvalidCredentials("Missing logout token".to_string()))?;
+
+        state.backchannel_logout(&logout_token).await
+    }
 }
 
 //------------ RequestPath ---------------------------------------------------
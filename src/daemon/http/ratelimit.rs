@@ -0,0 +1,445 @@
+//! Limits the rate of requests per client and the number of concurrent
+//! expensive operations, so that a misbehaving client cannot starve others.
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use hyper::Method;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::daemon::http::{LocalSocket, RemoteAddr};
+
+/// Remove buckets of clients that were not seen for this many seconds.
+const PRUNE_IDLE_SECONDS: u64 = 600;
+
+/// The maximum number of buckets kept. When reached, the least recently used
+/// half is evicted, so that clients cycling through many addresses cannot
+/// exhaust memory.
+const MAX_BUCKETS: usize = 100_000;
+
+/// Suggested seconds to wait when the cap on expensive operations is reached.
+const EXPENSIVE_RETRY_AFTER_SECONDS: u64 = 1;
+
+//------------ RateLimitConfig -----------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct RateLimitConfig {
+    /// Requests per minute per client for the API and authentication, 0 for
+    /// no limit.
+    #[serde(default = "RateLimitConfig::dflt_api_per_minute")]
+    pub api_per_minute: u32,
+
+    /// Requests per minute per client for RFC 6492 and RFC 8181 and the TA
+    /// proxy, 0 for no limit.
+    #[serde(default)]
+    pub protocol_per_minute: u32,
+
+    /// Requests per minute per client for metrics, stats and health, 0 for
+    /// no limit.
+    #[serde(default)]
+    pub monitoring_per_minute: u32,
+
+    /// The number of requests a client may make in quick succession before
+    /// the per minute rate applies, defaults to the per minute limit.
+    #[serde(default)]
+    pub burst: Option<u32>,
+
+    /// The maximum number of expensive operations, such as bulk and ROA
+    /// updates and history queries, processed at the same time.
+    #[serde(default = "RateLimitConfig::dflt_max_concurrent_expensive")]
+    pub max_concurrent_expensive: usize,
+}
+
+impl RateLimitConfig {
+    fn dflt_api_per_minute() -> u32 {
+        600
+    }
+
+    fn dflt_max_concurrent_expensive() -> usize {
+        4
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        if self.burst == Some(0) {
+            return Err(String::from("rate_limit.burst must be 1 or higher"));
+        }
+
+        if self.max_concurrent_expensive < 1 {
+            return Err(String::from("rate_limit.max_concurrent_expensive must be 1 or higher"));
+        }
+
+        Ok(())
+    }
+
+    fn per_minute(&self, class: EndpointClass) -> u32 {
+        match class {
+            EndpointClass::Api => self.api_per_minute,
+            EndpointClass::Protocol => self.protocol_per_minute,
+            EndpointClass::Monitoring => self.monitoring_per_minute,
+        }
+    }
+}
+
+//------------ EndpointClass -------------------------------------------------
+
+/// The classes of endpoints which are limited separately. RRDP and the UI
+/// are never limited.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum EndpointClass {
+    Api,
+    Protocol,
+    Monitoring,
+}
+
+impl EndpointClass {
+    fn for_path(path: &str) -> Option<Self> {
+        let first = path.trim_start_matches('/').split('/').next().unwrap_or_default();
+        match first {
+            "api" | "auth" => Some(EndpointClass::Api),
+            "rfc6492" | "rfc8181" | "ta" => Some(EndpointClass::Protocol),
+            "metrics" | "stats" | "health" => Some(EndpointClass::Monitoring),
+            _ => None,
+        }
+    }
+}
+
+/// Returns whether a request is for an expensive operation. Expects API v1
+/// paths, as other API versions are mapped onto these first.
+fn is_expensive(method: &Method, path: &str) -> bool {
+    let mut segments = path.trim_start_matches('/').split('/');
+    if segments.next() != Some("api") || segments.next() != Some("v1") {
+        return false;
+    }
+    match segments.next() {
+        Some("bulk") => true,
+        Some("cas") => {
+            let _ca = segments.next();
+            match segments.next() {
+                Some("history") => true,
+                Some("routes") => match segments.next() {
+                    None | Some("") | Some("try") => method == Method::POST,
+                    Some("analysis") => true,
+                    _ => false,
+                },
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+//------------ ClientKey -----------------------------------------------------
+
+/// Identifies a client by its IP address until it is authenticated, and by
+/// the name of the authenticated actor after that. Unvalidated credentials
+/// are never used, as a client could otherwise get a fresh bucket for every
+/// request by sending a different made up token.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ClientKey {
+    Ip(IpAddr),
+    Actor(String),
+}
+
+//------------ RateLimitCharge -----------------------------------------------
+
+/// The bucket charged for a request before it was authenticated, see
+/// [`RateLimiter::authenticated`].
+#[derive(Clone, Debug)]
+pub struct RateLimitCharge {
+    class: EndpointClass,
+    ip: IpAddr,
+    per_minute: u32,
+    capacity: u32,
+}
+
+//------------ TokenBucket ---------------------------------------------------
+
+#[derive(Clone, Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn full(capacity: u32, now: Instant) -> Self {
+        TokenBucket {
+            tokens: capacity as f64,
+            updated: now,
+        }
+    }
+
+    /// Takes a token if available, or returns the number of seconds until
+    /// one will be.
+    fn take(&mut self, capacity: u32, per_minute: u32, now: Instant) -> Result<(), u64> {
+        let per_second = per_minute as f64 / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity as f64);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - self.tokens) / per_second).ceil().max(1.0) as u64)
+        }
+    }
+
+    /// Gives back a token taken earlier.
+    fn refund(&mut self, capacity: u32) {
+        self.tokens = (self.tokens + 1.0).min(capacity as f64);
+    }
+}
+
+//------------ RateLimiter ---------------------------------------------------
+
+/// Applies the configured [`RateLimitConfig`] to incoming requests.
+pub struct RateLimiter {
+    config: Option<RateLimitConfig>,
+    buckets: Mutex<HashMap<(EndpointClass, ClientKey), TokenBucket>>,
+    last_pruned: Mutex<Instant>,
+    expensive: Arc<Semaphore>,
+}
+
+impl RateLimiter {
+    pub fn new(config: Option<RateLimitConfig>) -> Self {
+        let permits = config
+            .as_ref()
+            .map(|config| config.max_concurrent_expensive)
+            .unwrap_or(Semaphore::MAX_PERMITS);
+
+        RateLimiter {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+            last_pruned: Mutex::new(Instant::now()),
+            expensive: Arc::new(Semaphore::new(permits)),
+        }
+    }
+
+    /// Checks whether the request may be processed now. The request is
+    /// charged to the IP address of the client, as it is not yet known who
+    /// the client is. If it may be processed, returns the charge, and a
+    /// permit for expensive operations which must be held until the request
+    /// is done. If not, returns the number of seconds after which the client
+    /// may try again.
+    ///
+    /// Requests received on the local Unix domain socket are never limited.
+    pub fn check<B>(
+        &self,
+        req: &hyper::Request<B>,
+    ) -> Result<(Option<RateLimitCharge>, Option<OwnedSemaphorePermit>), u64> {
+        let config = match &self.config {
+            Some(config) => config,
+            None => return Ok((None, None)),
+        };
+
+        if req.extensions().get::<LocalSocket>().is_some() {
+            return Ok((None, None));
+        }
+
+        let path = req.uri().path();
+
+        let mut charge = None;
+        if let Some(class) = EndpointClass::for_path(path) {
+            let per_minute = config.per_minute(class);
+            if per_minute > 0 {
+                if let Some(addr) = req.extensions().get::<RemoteAddr>() {
+                    let ip = addr.0.ip();
+                    let capacity = config.burst.unwrap_or(per_minute);
+                    self.take(class, ClientKey::Ip(ip), per_minute, capacity)?;
+                    charge = Some(RateLimitCharge {
+                        class,
+                        ip,
+                        per_minute,
+                        capacity,
+                    });
+                }
+            }
+        }
+
+        if is_expensive(req.method(), path) {
+            self.expensive
+                .clone()
+                .try_acquire_owned()
+                .map(|permit| (charge, Some(permit)))
+                .map_err(|_| EXPENSIVE_RETRY_AFTER_SECONDS)
+        } else {
+            Ok((charge, None))
+        }
+    }
+
+    /// Moves the charge for a request from the IP address of the client to
+    /// the actor it was authenticated as, so that authenticated clients
+    /// behind a shared address are limited separately. Returns the number of
+    /// seconds after which the client may try again if the actor exceeds its
+    /// rate limit.
+    pub fn authenticated(&self, charge: RateLimitCharge, actor: &str) -> Result<(), u64> {
+        {
+            let mut buckets = self.buckets.lock().unwrap();
+            if let Some(bucket) = buckets.get_mut(&(charge.class, ClientKey::Ip(charge.ip))) {
+                bucket.refund(charge.capacity);
+            }
+        }
+        self.take(
+            charge.class,
+            ClientKey::Actor(actor.to_string()),
+            charge.per_minute,
+            charge.capacity,
+        )
+    }
+
+    fn take(&self, class: EndpointClass, client: ClientKey, per_minute: u32, capacity: u32) -> Result<(), u64> {
+        let now = Instant::now();
+        self.prune(now);
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&(class, client.clone())) {
+            Self::evict(&mut buckets);
+        }
+        buckets
+            .entry((class, client))
+            .or_insert_with(|| TokenBucket::full(capacity, now))
+            .take(capacity, per_minute, now)
+    }
+
+    /// Evicts the least recently used half of the buckets.
+    fn evict(buckets: &mut HashMap<(EndpointClass, ClientKey), TokenBucket>) {
+        let mut updated: Vec<Instant> = buckets.values().map(|bucket| bucket.updated).collect();
+        updated.sort_unstable();
+        if let Some(cutoff) = updated.get(updated.len() / 2).copied() {
+            buckets.retain(|_, bucket| bucket.updated > cutoff);
+        }
+    }
+
+    /// Forgets clients that were idle for a while, so that the number of
+    /// buckets does not grow without bound.
+    fn prune(&self, now: Instant) {
+        let mut last_pruned = self.last_pruned.lock().unwrap();
+        if now.saturating_duration_since(*last_pruned).as_secs() < PRUNE_IDLE_SECONDS {
+            return;
+        }
+        *last_pruned = now;
+
+        self.buckets
+            .lock()
+            .unwrap()
+            .retain(|_, bucket| now.saturating_duration_since(bucket.updated).as_secs() < PRUNE_IDLE_SECONDS);
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::*;
+
+    fn limiter(api_per_minute: u32, burst: Option<u32>, max_concurrent_expensive: usize) -> RateLimiter {
+        RateLimiter::new(Some(RateLimitConfig {
+            api_per_minute,
+            protocol_per_minute: 0,
+            monitoring_per_minute: 0,
+            burst,
+            max_concurrent_expensive,
+        }))
+    }
+
+    fn request(method: Method, path: &str, from: &str, token: Option<&str>) -> hyper::Request<()> {
+        let mut builder = hyper::Request::builder().method(method).uri(path);
+        if let Some(token) = token {
+            builder = builder.header(hyper::header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let mut req = builder.body(()).unwrap();
+        req.extensions_mut()
+            .insert(RemoteAddr(from.parse::<SocketAddr>().unwrap()));
+        req
+    }
+
+    #[test]
+    fn limits_per_client_and_class() {
+        let limiter = limiter(60, Some(2), 4);
+        let get = |path: &str, from: &str, token: Option<&str>| limiter.check(&request(Method::GET, path, from, token));
+
+        assert!(get("/api/v1/cas", "192.0.2.1:1000", None).is_ok());
+        assert!(get("/api/v1/cas", "192.0.2.1:1001", None).is_ok());
+        assert_eq!(get("/api/v1/cas", "192.0.2.1:1002", None).unwrap_err(), 1);
+
+        // other clients, and unlimited classes, are not affected
+        assert!(get("/api/v1/cas", "192.0.2.2:1000", None).is_ok());
+        assert!(get("/metrics", "192.0.2.1:1004", None).is_ok());
+        assert!(get("/rrdp/notification.xml", "192.0.2.1:1005", None).is_ok());
+    }
+
+    #[test]
+    fn unvalidated_tokens_do_not_escape_the_ip_limit() {
+        let limiter = limiter(60, Some(2), 4);
+        let get = |token: &str| limiter.check(&request(Method::GET, "/api/v1/cas", "192.0.2.1:1000", Some(token)));
+
+        assert!(get("guess-1").is_ok());
+        assert!(get("guess-2").is_ok());
+        assert_eq!(get("guess-3").unwrap_err(), 1);
+    }
+
+    #[test]
+    fn authenticated_requests_are_charged_to_the_actor() {
+        let limiter = limiter(60, Some(2), 4);
+        let check = |from: &str| limiter.check(&request(Method::GET, "/api/v1/cas", from, Some("secret")));
+
+        // Two actors behind the same address each get their own bucket, and
+        // the address keeps its budget for unauthenticated requests.
+        for actor in ["alice", "bob"] {
+            for port in 1000..1002 {
+                let (charge, _) = check(&format!("192.0.2.1:{}", port)).unwrap();
+                limiter.authenticated(charge.unwrap(), actor).unwrap();
+            }
+        }
+        let (charge, _) = check("192.0.2.1:1002").unwrap();
+        assert_eq!(limiter.authenticated(charge.unwrap(), "alice").unwrap_err(), 1);
+
+        assert!(check("192.0.2.1:1003").is_ok());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_buckets() {
+        let mut buckets = HashMap::new();
+        let start = Instant::now();
+        for i in 0..10u8 {
+            let key = (EndpointClass::Api, ClientKey::Ip(IpAddr::from([192, 0, 2, i])));
+            let updated = start + std::time::Duration::from_secs(i as u64);
+            buckets.insert(key, TokenBucket::full(1, updated));
+        }
+
+        RateLimiter::evict(&mut buckets);
+        assert_eq!(buckets.len(), 4);
+        assert!(buckets.contains_key(&(EndpointClass::Api, ClientKey::Ip(IpAddr::from([192, 0, 2, 9])))));
+        assert!(!buckets.contains_key(&(EndpointClass::Api, ClientKey::Ip(IpAddr::from([192, 0, 2, 0])))));
+    }
+
+    #[test]
+    fn caps_concurrent_expensive_operations() {
+        let limiter = limiter(0, None, 1);
+
+        let (_, permit) = limiter
+            .check(&request(
+                Method::GET,
+                "/api/v1/cas/ca/history/commands",
+                "192.0.2.1:1000",
+                None,
+            ))
+            .unwrap();
+        assert!(permit.is_some());
+
+        let post_routes = request(Method::POST, "/api/v1/cas/ca/routes", "192.0.2.2:1000", None);
+        assert_eq!(limiter.check(&post_routes).unwrap_err(), EXPENSIVE_RETRY_AFTER_SECONDS);
+
+        // showing routes is cheap
+        let get_routes = request(Method::GET, "/api/v1/cas/ca/routes", "192.0.2.2:1000", None);
+        assert!(limiter.check(&get_routes).unwrap().1.is_none());
+
+        drop(permit);
+        assert!(limiter.check(&post_routes).unwrap().1.is_some());
+    }
+}
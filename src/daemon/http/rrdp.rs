@@ -0,0 +1,224 @@
+//! Serve the RRDP files, if Krill is configured to do this itself.
+//!
+//! This supports conditional requests, using the 'ETag' and 'Last-Modified'
+//! headers, so that Relying Parties polling for an unchanged notification file
+//! get a small '304 Not Modified' response. If compressed variants of the
+//! files were written (see 'rrdp_files_gzip' and 'rrdp_files_brotli'), then
+//! these are served to clients which accept the 'br' or 'gzip' content
+//! encoding, preferring brotli if both are accepted.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Utc};
+use hyper::{header, HeaderMap};
+use rpki::rrdp::Hash;
+
+use crate::{
+    commons::util::file::{self, Compression},
+    daemon::http::{HttpResponse, Request, RoutingResult},
+};
+
+/// The compressed variants we can serve, in order of preference.
+const PREFERRED_COMPRESSIONS: [Compression; 2] = [Compression::Brotli, Compression::Gzip];
+
+pub async fn rrdp(req: Request) -> RoutingResult {
+    if !req.path().full().starts_with("/rrdp/") {
+        Err(req) // Not for us
+    } else {
+        let mut full_path: PathBuf = req.state().rrdp_base_path();
+        let (_, path) = req.path.remaining().split_at(1);
+        let cache_seconds = if path.ends_with("notification.xml") { 60 } else { 86400 };
+        full_path.push(path);
+
+        if !full_path.is_file() {
+            return Ok(HttpResponse::not_found());
+        }
+
+        let compression = PREFERRED_COMPRESSIONS
+            .iter()
+            .copied()
+            .find(|compression| accepts(req.headers(), *compression) && compression.path(&full_path).is_file());
+        let serve_path = match compression {
+            Some(compression) => compression.path(&full_path),
+            None => full_path,
+        };
+
+        let modified = match fs::metadata(&serve_path).and_then(|meta| meta.modified()) {
+            Ok(modified) => modified,
+            Err(_) => return Ok(HttpResponse::not_found()),
+        };
+
+        let body = match read_file(&serve_path) {
+            Some(body) => body,
+            None => return Ok(HttpResponse::not_found()),
+        };
+
+        let validators = RrdpFileValidators::new(modified, &body);
+        if validators.not_modified(req.headers()) {
+            Ok(HttpResponse::not_modified(
+                &validators.etag,
+                &validators.last_modified,
+                cache_seconds,
+            ))
+        } else {
+            Ok(HttpResponse::rrdp_file(
+                body,
+                cache_seconds,
+                &validators.etag,
+                &validators.last_modified,
+                compression.map(Compression::content_encoding),
+            ))
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Option<Vec<u8>> {
+    file::read(path).ok().map(|bytes| bytes.to_vec())
+}
+
+/// Returns true if the client accepts the content encoding of the given
+/// compression, i.e. if it is included in the 'Accept-Encoding' header
+/// without a quality value of 0.
+fn accepts(headers: &HeaderMap, compression: Compression) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(|part| part.trim());
+            let name = parts.next().unwrap_or_default();
+            let rejected = parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .map(|q| q == 0.0)
+                    .unwrap_or(false)
+            });
+
+            (name.eq_ignore_ascii_case(compression.content_encoding()) || name == "*") && !rejected
+        })
+}
+
+//------------ RrdpFileValidators --------------------------------------------
+
+/// The validators used for conditional requests for an RRDP file. The ETag
+/// is the SHA-256 hash of the served bytes, so it stays the same if the same
+/// file is written again, e.g. by another node behind a load balancer, and
+/// the compressed variants get their own ETag.
+struct RrdpFileValidators {
+    modified: DateTime<Utc>,
+    etag: String,
+    last_modified: String,
+}
+
+impl RrdpFileValidators {
+    fn new(modified: SystemTime, body: &[u8]) -> Self {
+        let etag = format!("\"{}\"", Hash::from_data(body));
+
+        let modified = DateTime::<Utc>::from(modified);
+        let last_modified = modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+
+        RrdpFileValidators {
+            modified,
+            etag,
+            last_modified,
+        }
+    }
+
+    /// Returns true if the request headers indicate that the client already
+    /// has the current file. As per RFC 7232, 'If-Modified-Since' is ignored
+    /// if 'If-None-Match' is present.
+    fn not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+            match if_none_match.to_str() {
+                Ok(tags) => tags
+                    .split(',')
+                    .map(|tag| tag.trim())
+                    .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == self.etag),
+                Err(_) => false,
+            }
+        } else if let Some(if_modified_since) = headers.get(header::IF_MODIFIED_SINCE) {
+            match if_modified_since
+                .to_str()
+                .ok()
+                .and_then(|since| DateTime::parse_from_rfc2822(since).ok())
+            {
+                Some(since) => self.modified.timestamp() <= since.timestamp(),
+                None => false,
+            }
+        } else {
+            false
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use hyper::http::HeaderValue;
+
+    use super::*;
+
+    fn headers(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn should_negotiate_compression() {
+        let gzip = Compression::Gzip;
+        let brotli = Compression::Brotli;
+
+        assert!(!accepts(&HeaderMap::new(), gzip));
+        assert!(!accepts(&HeaderMap::new(), brotli));
+        assert!(accepts(&headers(header::ACCEPT_ENCODING, "gzip"), gzip));
+        assert!(!accepts(&headers(header::ACCEPT_ENCODING, "gzip"), brotli));
+        assert!(accepts(&headers(header::ACCEPT_ENCODING, "br, gzip;q=0.8"), gzip));
+        assert!(accepts(&headers(header::ACCEPT_ENCODING, "br, gzip;q=0.8"), brotli));
+        assert!(accepts(&headers(header::ACCEPT_ENCODING, "*"), gzip));
+        assert!(accepts(&headers(header::ACCEPT_ENCODING, "*"), brotli));
+        assert!(!accepts(&headers(header::ACCEPT_ENCODING, "gzip;q=0"), gzip));
+        assert!(!accepts(&headers(header::ACCEPT_ENCODING, "br;q=0, gzip"), brotli));
+        assert!(!accepts(&headers(header::ACCEPT_ENCODING, "br, deflate"), gzip));
+    }
+
+    #[test]
+    fn should_handle_conditional_requests() {
+        let modified = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let validators = RrdpFileValidators::new(modified, b"<notification/>");
+
+        assert_eq!(validators.last_modified, "Sun, 13 Sep 2020 12:26:40 GMT");
+        assert!(!validators.not_modified(&HeaderMap::new()));
+
+        assert!(validators.not_modified(&headers(header::IF_NONE_MATCH, &validators.etag)));
+        assert!(validators.not_modified(&headers(header::IF_NONE_MATCH, "*")));
+        assert!(!validators.not_modified(&headers(header::IF_NONE_MATCH, "\"other\"")));
+
+        // The ETag depends on the content only, so different content, such
+        // as a compressed variant, has a different ETag
+        let same = RrdpFileValidators::new(modified + Duration::from_secs(60), b"<notification/>");
+        assert_eq!(same.etag, validators.etag);
+        let other = RrdpFileValidators::new(modified, b"<notification serial=\"2\"/>");
+        assert!(!other.not_modified(&headers(header::IF_NONE_MATCH, &validators.etag)));
+
+        assert!(validators.not_modified(&headers(header::IF_MODIFIED_SINCE, "Sun, 13 Sep 2020 12:26:40 GMT")));
+        assert!(!validators.not_modified(&headers(header::IF_MODIFIED_SINCE, "Sun, 13 Sep 2020 12:26:39 GMT")));
+        assert!(!validators.not_modified(&headers(header::IF_MODIFIED_SINCE, "not a date")));
+
+        // If-None-Match takes precedence over If-Modified-Since
+        let mut both = headers(header::IF_NONE_MATCH, "\"other\"");
+        both.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Sun, 13 Sep 2020 12:26:40 GMT"),
+        );
+        assert!(!validators.not_modified(&both));
+    }
+}
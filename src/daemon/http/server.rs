@@ -1,68 +1,92 @@
 //! Hyper based HTTP server for Krill.
 //!
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     convert::{Infallible, TryInto},
-    env,
-    fs::File,
-    io::Read,
-    net::SocketAddr,
+    env, fmt,
+    net::{IpAddr, SocketAddr},
     path::{Path, PathBuf},
     process,
-    str::FromStr,
+    str::{from_utf8, FromStr},
     sync::Arc,
+    time::{Duration, SystemTime},
 };
 
 use bytes::Bytes;
 use serde::Serialize;
 
 use hyper::{
-    header::HeaderName,
+    header::{self, HeaderName},
     http::HeaderValue,
-    server::conn::AddrIncoming,
+    server::conn::{AddrIncoming, AddrStream},
     service::{make_service_fn, service_fn},
     Method,
 };
 
 use tokio::select;
 use tokio::signal::unix::SignalKind;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_rustls::rustls::ServerConfig;
 
 use rpki::{
     ca::{
         idexchange,
         idexchange::{CaHandle, ChildHandle, ParentHandle, PublisherHandle},
+        provisioning::ResourceClassName,
     },
+    crypto::KeyIdentifier,
     repository::resources::Asn,
 };
 
 use crate::{
     commons::{
+        actor::Actor,
         api::{
-            ApiRepositoryContact, AspaDefinitionUpdates, BgpStats, CommandHistoryCriteria, ParentCaReq, PublisherList,
-            RepositoryContact, RoaConfigurationUpdates, RtaName, Token,
+            ApiRepositoryContact, ApiVersion, ApiVersions, AspaDefinitionList, AspaDefinitionUpdates, AuditEvent,
+            AuditEventKind, BgpStats, CaLabelSelector, CertAuthInit, CommandHistoryCriteria, HistoryOrder,
+            ObjectExpiryType, ParentCaReq, PendingChildRequestKind, PublisherList, PublisherStatsList,
+            RepositoryContact, RoaConfigurationUpdates, RtaName, SearchQuery, Timestamp, Token,
         },
         bgp::BgpAnalysisAdvice,
         error::Error,
-        eventsourcing::AggregateStoreError,
-        util::file,
+        eventsourcing::{AggregateStoreError, CommandKey},
+        util::{
+            file,
+            logging::{LogContext, REQUEST_ID_HEADER},
+        },
     },
     constants::{
         KRILL_ENV_HTTP_LOG_INFO, KRILL_ENV_UPGRADE_ONLY, KRILL_VERSION_MAJOR, KRILL_VERSION_MINOR, KRILL_VERSION_PATCH,
-        NO_RESOURCE,
+        METRICS_EXPIRY_BUCKETS_HOURS, NO_RESOURCE,
     },
     daemon::{
         auth::common::permissions::Permission,
-        auth::{Auth, Handle},
-        ca::CaStatus,
-        config::Config,
+        auth::{Auth, Handle, PublisherResource},
+        backup::BackupManager,
+        ca::{testbed_ca_handle, CaStatus},
+        config::{Config, UnixSocketConfig},
         http::{
-            auth::auth, statics::statics, testbed::testbed, tls, tls_keys, HttpResponse, Request, RequestPath,
-            RoutingResult,
+            acme::{
+                self, acme_domain, AcmeChallengeType, AcmeChallenges, ACME_CHECK_INTERVAL_SECONDS,
+                ACME_HTTP_CHALLENGE_PATH, ACME_RETRY_INTERVAL_SECONDS,
+            },
+            auth::{auth, AUTH_BACKCHANNEL_LOGOUT_ENDPOINT, AUTH_LOGOUT_ENDPOINT},
+            rrdp::rrdp,
+            statics::statics,
+            status::public_status,
+            testbed::testbed,
+            tls, tls_keys, HttpResponse, LocalSocket, RemoteAddr, Request, RequestPath, RoutingResult,
         },
+        jobs,
         krillserver::KrillServer,
+        notify::LiveEvent,
         ta::{self, TA_NAME},
+        telemetry,
+    },
+    upgrades::{
+        finalise_data_migration, migrate_disk_to_storage, post_start_upgrade, prepare_upgrade_data_migrations,
+        stamp_store_versions, UpgradeMode,
     },
-    upgrades::{finalise_data_migration, post_start_upgrade, prepare_upgrade_data_migrations, UpgradeMode},
 };
 
 //------------ State -----------------------------------------------------
@@ -126,6 +150,13 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
     write_pid_file_or_die(&config);
     test_data_dirs_or_die(&config);
 
+    if let Some(telemetry_config) = &config.telemetry {
+        telemetry::init(telemetry_config)?;
+    }
+
+    // Apply a staged restore of a backup, if any, before the data is used.
+    BackupManager::new(&config).apply_pending_restore()?;
+
     // Call upgrade, this will only do actual work if needed.
     let upgrade_report = prepare_upgrade_data_migrations(UpgradeMode::PrepareToFinalise, config.clone())?;
     if let Some(report) = &upgrade_report {
@@ -143,6 +174,10 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
         }
     }
 
+    // Copy existing data under the data_dir to the database, if 'storage_uri'
+    // was set for it. This is done after any upgrade, which works on disk.
+    migrate_disk_to_storage(&config)?;
+
     // Create the server, this will create the necessary data sub-directories if needed
     let krill_server = KrillServer::build(config.clone()).await?;
 
@@ -152,31 +187,90 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
         post_start_upgrade(report.versions(), &krill_server).await?;
     }
 
+    // Record that the data is now used by this version of Krill, so that older
+    // versions refuse to use it.
+    stamp_store_versions(&config)?;
+
     // If the operator wanted to do the upgrade only, now is a good time to report success and stop
     if env::var(KRILL_ENV_UPGRADE_ONLY).is_ok() {
         println!("Krill upgrade successful");
         std::process::exit(0);
     }
 
-    // Build the scheduler which will be responsible for executing planned/triggered tasks
-    let scheduler = krill_server.build_scheduler();
-    let scheduler_future = scheduler.run();
-
     // Start creating the server.
     let krill_server = Arc::new(krill_server);
 
+    // Build the scheduler which will be responsible for executing planned/triggered tasks.
+    // It also resumes the jobs which were running when Krill stopped.
+    let scheduler = krill_server.build_scheduler()?;
+    let scheduler_future = scheduler.run();
+
+    // Reload the config file when SIGHUP is received.
+    #[cfg(unix)]
+    tokio::spawn(reload_config_on_sig_hup(krill_server.clone()));
+
     // Create self-signed HTTPS cert if configured and not generated earlier.
     if config.https_mode().is_generate_https_cert() {
         tls_keys::create_key_cert_if_needed(&config.data_dir).map_err(|e| Error::HttpsSetup(format!("{}", e)))?;
     }
 
+    // Set up the TLS config, and reload it when the files change.
+    let tls_config = if config.https_mode().is_disable_https() {
+        None
+    } else {
+        let acme_challenges = krill_server.acme_challenges();
+        let server_config =
+            tls_server_config(&config, acme_challenges).map_err(|e| Error::HttpsSetup(e.to_string()))?;
+        let tls_config = tls::SharedServerConfig::new(server_config);
+        if config.tls.reload_check_seconds > 0 {
+            tokio::spawn(reload_tls_config_on_change(
+                tls_config.clone(),
+                acme_challenges.clone(),
+                config.clone(),
+            ));
+        }
+        if config.acme.is_some() {
+            tokio::spawn(renew_acme_certificate(
+                tls_config.clone(),
+                acme_challenges.clone(),
+                config.clone(),
+            ));
+        }
+        Some(tls_config)
+    };
+
     // Start a hyper server for the configured socket.
-    let server_futures = futures_util::future::select_all(
-        config
-            .socket_addresses()
-            .into_iter()
-            .map(|socket_addr| tokio::spawn(single_http_listener(krill_server.clone(), socket_addr, config.clone()))),
-    );
+    #[cfg_attr(not(unix), allow(unused_mut))]
+    let mut listeners: Vec<_> = config
+        .socket_addresses()
+        .into_iter()
+        .map(|socket_addr| {
+            tokio::spawn(single_http_listener(
+                krill_server.clone(),
+                socket_addr,
+                tls_config.clone(),
+            ))
+        })
+        .collect();
+
+    // And for answering ACME HTTP-01 challenges, if configured.
+    if let Some(socket_addr) = config.acme.as_ref().and_then(|acme| acme.http_challenge_listen) {
+        listeners.push(tokio::spawn(acme_http_challenge_listener(
+            krill_server.acme_challenges().clone(),
+            socket_addr,
+        )));
+    }
+
+    // And for the unix socket, if configured.
+    #[cfg(unix)]
+    if let Some(unix_socket) = &config.unix_socket {
+        listeners.push(tokio::spawn(unix_socket_listener(
+            krill_server.clone(),
+            unix_socket.clone(),
+        )));
+    }
+
+    let server_futures = futures_util::future::select_all(listeners);
 
     if let Some(lock) = optional_lock {
         #[cfg(not(unix))]
@@ -199,10 +293,184 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
         );
     }
 
+    telemetry::shutdown();
+
     Err(Error::custom("stopping krill process"))
 }
 
-async fn single_http_listener(krill_server: Arc<KrillServer>, socket_addr: SocketAddr, config: Arc<Config>) {
+#[cfg(unix)]
+async fn reload_config_on_sig_hup(krill_server: Arc<KrillServer>) {
+    let mut hangup = match tokio::signal::unix::signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            error!(
+                "Cannot listen for sig HUP, the config can only be reloaded through the API: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        info!("sig HUP received, reloading config file");
+        if let Err(e) = krill_server.reload_config().await {
+            error!("Could not reload config file: {}", e);
+        }
+    }
+}
+
+/// Builds the TLS config for the HTTPS server from the files on disk.
+fn tls_server_config(config: &Config, acme_challenges: &AcmeChallenges) -> Result<ServerConfig, tls::TlsConfigError> {
+    let server_config_builder = tls::TlsConfigBuilder::new()
+        .cert_path(tls_keys::cert_file_path(&config.data_dir))
+        .key_path(tls_keys::key_file_path(&config.data_dir))
+        .options(&config.tls);
+
+    let server_config_builder = match &config.acme {
+        Some(acme) if acme.challenge == AcmeChallengeType::TlsAlpn01 => {
+            server_config_builder.acme_challenges(acme_challenges.clone())
+        }
+        _ => server_config_builder,
+    };
+
+    #[cfg(feature = "multi-user")]
+    let server_config_builder = match &config.auth_client_certs {
+        Some(client_certs) => server_config_builder.client_auth_path(&client_certs.ca_cert, client_certs.required),
+        None => server_config_builder,
+    };
+
+    server_config_builder.build()
+}
+
+/// Returns the files which the TLS config is built from.
+fn tls_config_files(config: &Config) -> Vec<PathBuf> {
+    let mut files = vec![
+        tls_keys::cert_file_path(&config.data_dir),
+        tls_keys::key_file_path(&config.data_dir),
+    ];
+    files.extend(config.tls.ocsp_response.clone());
+
+    #[cfg(feature = "multi-user")]
+    files.extend(config.auth_client_certs.as_ref().map(|certs| certs.ca_cert.clone()));
+
+    files
+}
+
+/// Reloads the TLS config when any of its files changes, so that a renewed
+/// certificate, e.g. from Let's Encrypt, is used for new connections without
+/// restarting Krill.
+async fn reload_tls_config_on_change(
+    tls_config: tls::SharedServerConfig,
+    acme_challenges: AcmeChallenges,
+    config: Arc<Config>,
+) {
+    fn modified(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+        files
+            .iter()
+            .map(|file| std::fs::metadata(file).and_then(|metadata| metadata.modified()).ok())
+            .collect()
+    }
+
+    let files = tls_config_files(&config);
+    let mut last_modified = modified(&files);
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(config.tls.reload_check_seconds)).await;
+
+        let now_modified = modified(&files);
+        if now_modified == last_modified {
+            continue;
+        }
+
+        // If the new files cannot be used yet, e.g. because only the
+        // certificate was replaced so far, keep the current config and try
+        // again on the next check.
+        match tls_server_config(&config, &acme_challenges) {
+            Ok(server_config) => {
+                info!("Reloaded the HTTPS certificate and key");
+                tls_config.replace(server_config);
+                last_modified = now_modified;
+            }
+            Err(e) => warn!(
+                "Could not reload the HTTPS certificate and key, keeping the current ones: {}",
+                e
+            ),
+        }
+    }
+}
+
+/// Obtains and renews the HTTPS certificate using ACME, and uses a new
+/// certificate for new connections right away.
+async fn renew_acme_certificate(
+    tls_config: tls::SharedServerConfig,
+    acme_challenges: AcmeChallenges,
+    config: Arc<Config>,
+) {
+    // Both are checked when the config is verified.
+    let (acme, domain) = match (&config.acme, acme_domain(&config.service_uri())) {
+        (Some(acme), Some(domain)) => (acme, domain),
+        _ => return,
+    };
+
+    loop {
+        let wait_seconds =
+            match acme::renew_certificate_if_needed(&config.data_dir, &domain, acme, &acme_challenges).await {
+                Ok(renewed) => {
+                    if renewed {
+                        match tls_server_config(&config, &acme_challenges) {
+                            Ok(server_config) => tls_config.replace(server_config),
+                            Err(e) => error!("Could not use the new HTTPS certificate: {}", e),
+                        }
+                    }
+                    ACME_CHECK_INTERVAL_SECONDS
+                }
+                Err(e) => {
+                    error!("Could not obtain HTTPS certificate for {}: {}", domain, e);
+                    ACME_RETRY_INTERVAL_SECONDS
+                }
+            };
+        tokio::time::sleep(Duration::from_secs(wait_seconds)).await;
+    }
+}
+
+/// Answers ACME HTTP-01 challenges, and nothing else, over plain HTTP.
+async fn acme_http_challenge_listener(acme_challenges: AcmeChallenges, socket_addr: SocketAddr) {
+    let incoming = match AddrIncoming::bind(&socket_addr) {
+        Err(e) => {
+            error!("Could not bind to address and port: {}, Error: {}", &socket_addr, e);
+            return;
+        }
+        Ok(incoming) => incoming,
+    };
+
+    let service = make_service_fn(|_: &AddrStream| {
+        let acme_challenges = acme_challenges.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| {
+                let response = match req
+                    .uri()
+                    .path()
+                    .strip_prefix(ACME_HTTP_CHALLENGE_PATH)
+                    .and_then(|token| acme_challenges.http_response(token))
+                {
+                    Some(key_authorization) => HttpResponse::text(key_authorization.into_bytes()),
+                    None => HttpResponse::not_found(),
+                };
+                async move { Ok::<_, Infallible>(response.response()) }
+            }))
+        }
+    });
+
+    if let Err(e) = hyper::Server::builder(incoming).serve(service).await {
+        error!("Fatal server error: {}", e)
+    }
+}
+
+async fn single_http_listener(
+    krill_server: Arc<KrillServer>,
+    socket_addr: SocketAddr,
+    tls_config: Option<tls::SharedServerConfig>,
+) {
     // See if we can bind to the configured address and port first.
     let incoming = match AddrIncoming::bind(&socket_addr) {
         Err(e) => {
@@ -212,48 +480,190 @@ async fn single_http_listener(krill_server: Arc<KrillServer>, socket_addr: Socke
         Ok(incoming) => incoming,
     };
 
-    if config.https_mode().is_disable_https() {
-        // Make a service function.
-        let service = make_service_fn(|_| {
+    if let Some(tls_config) = tls_config {
+        // Set up a TLS acceptor to use.
+        let acceptor = tls::TlsAcceptor::new(tls_config, incoming);
+
+        // Make a service function. We have to do this again because of hyper types..
+        // It won't like a service made for a Server that is not of the type of the
+        // TlsAcceptor we are about to set up.
+        //
+        // The client certificate, if any, is only known once the handshake is
+        // done, which happens after this is called for the connection. So we
+        // keep hold of the slot and add its content to each request.
+        let service = make_service_fn(|conn: &tls::TlsStream| {
             let krill_server = krill_server.clone();
+            let remote_addr = RemoteAddr(conn.peer_addr());
+            let client_certificate = conn.client_certificate();
             async move {
-                Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| {
+                Ok::<_, Infallible>(service_fn(move |mut req: hyper::Request<hyper::Body>| {
                     let krill_server = krill_server.clone();
+                    req.extensions_mut().insert(remote_addr);
+                    if let Some(cert) = client_certificate.get() {
+                        req.extensions_mut().insert(cert);
+                    }
                     map_requests(req, krill_server)
                 }))
             }
         });
-        if let Err(e) = hyper::Server::builder(incoming).serve(service).await {
+
+        if let Err(e) = hyper::Server::builder(acceptor).serve(service).await {
             error!("Fatal server error: {}", e)
         }
     } else {
-        // Set up a TLS acceptor to use.
-        let server_config_builder = tls::TlsConfigBuilder::new()
-            .cert_path(tls_keys::cert_file_path(&config.data_dir))
-            .key_path(tls_keys::key_file_path(&config.data_dir));
-
-        let server_config = server_config_builder.build().unwrap();
-        let acceptor = tls::TlsAcceptor::new(server_config, incoming);
-
-        // Make a service function. We have to do this again because of hyper types..
-        // It won't like a service made for a Server that is not of the type of the
-        // TlsAcceptor we are about to set up.
-        let service = make_service_fn(|_| {
+        // Make a service function.
+        let service = make_service_fn(|conn: &AddrStream| {
             let krill_server = krill_server.clone();
+            let remote_addr = RemoteAddr(conn.remote_addr());
             async move {
-                Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| {
+                Ok::<_, Infallible>(service_fn(move |mut req: hyper::Request<hyper::Body>| {
                     let krill_server = krill_server.clone();
+                    req.extensions_mut().insert(remote_addr);
                     map_requests(req, krill_server)
                 }))
             }
         });
-
-        if let Err(e) = hyper::Server::builder(acceptor).serve(service).await {
+        if let Err(e) = hyper::Server::builder(incoming).serve(service).await {
             error!("Fatal server error: {}", e)
         }
     }
 }
 
+/// Serves the API on a Unix domain socket for local administration.
+///
+/// Requests on the socket are authenticated as admin, so access is controlled
+/// by the permissions of the socket file, and the credentials of the peer are
+/// checked against these permissions.
+#[cfg(unix)]
+async fn unix_socket_listener(krill_server: Arc<KrillServer>, config: UnixSocketConfig) {
+    use std::os::unix::fs::FileTypeExt;
+
+    let path = &config.path;
+
+    // Remove the socket of an earlier run, but nothing else that might be
+    // there by mistake.
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            error!(
+                "Could not bind to unix socket: {}, Error: file exists and is not a socket",
+                path.display()
+            );
+            return;
+        }
+        if let Err(e) = std::fs::remove_file(path) {
+            error!("Could not remove old unix socket: {}, Error: {}", path.display(), e);
+            return;
+        }
+    }
+
+    // The mode was checked when the config was verified.
+    let mode = config.permissions().unwrap_or(0o600);
+
+    let listener = match bind_unix_socket(path, mode) {
+        Err(e) => {
+            error!("Could not bind to unix socket: {}, Error: {}", path.display(), e);
+            return;
+        }
+        Ok(listener) => listener,
+    };
+
+    info!("Serving the API on unix socket: {}", path.display());
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                // Errors like running out of file descriptors are usually
+                // temporary, so wait a bit rather than stop.
+                error!(
+                    "Could not accept connection on unix socket: {}, Error: {}",
+                    path.display(),
+                    e
+                );
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+
+        // The file permissions are the access control, but check the peer
+        // as well in case the socket was reachable while they were changed
+        // by someone else, or through a directory with looser permissions.
+        match stream.peer_cred() {
+            Ok(cred) if unix_peer_allowed(cred.uid(), cred.gid(), mode) => {}
+            Ok(cred) => {
+                warn!(
+                    "Refused connection on unix socket: {} from uid {} gid {}",
+                    path.display(),
+                    cred.uid(),
+                    cred.gid()
+                );
+                continue;
+            }
+            Err(e) => {
+                warn!(
+                    "Refused connection on unix socket: {}, could not get peer credentials: {}",
+                    path.display(),
+                    e
+                );
+                continue;
+            }
+        }
+
+        let krill_server = krill_server.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |mut req: hyper::Request<hyper::Body>| {
+                req.extensions_mut().insert(LocalSocket);
+                map_requests(req, krill_server.clone())
+            });
+            if let Err(e) = hyper::server::conn::Http::new().serve_connection(stream, service).await {
+                debug!("Error serving connection on unix socket: {}", e);
+            }
+        });
+    }
+}
+
+/// Binds a unix socket at the path with the given permissions. The socket is
+/// created in a new directory that only this user can access, and moved into
+/// place after its permissions are set, so that no other user can connect to
+/// it before that.
+#[cfg(unix)]
+fn bind_unix_socket(path: &std::path::Path, mode: u32) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let parent = path.parent().unwrap_or_else(|| std::path::Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "socket path has no file name"))?;
+
+    let mut rnd_bytes = [0; 8];
+    openssl::rand::rand_bytes(&mut rnd_bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    let private_dir = parent.join(format!(".{}-{}", file_name.to_string_lossy(), hex::encode(rnd_bytes)));
+    std::fs::DirBuilder::new().mode(0o700).create(&private_dir)?;
+
+    let bind = || {
+        let tmp_path = private_dir.join(file_name);
+        let listener = tokio::net::UnixListener::bind(&tmp_path)?;
+        std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(mode))?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(listener)
+    };
+    let res = bind();
+
+    let _ = std::fs::remove_dir_all(&private_dir);
+    res
+}
+
+/// Returns whether a peer with the given user and group id may use a unix
+/// socket with the given permissions. Only the primary group of the peer is
+/// known, so group access requires that the group of Krill is the primary
+/// group of the peer.
+#[cfg(unix)]
+fn unix_peer_allowed(uid: u32, gid: u32, mode: u32) -> bool {
+    let (own_uid, own_gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+
+    uid == 0 || uid == own_uid || (mode & 0o060 != 0 && gid == own_gid) || mode & 0o006 != 0
+}
+
 struct RequestLogger {
     req_method: hyper::Method,
     req_path: String,
@@ -299,16 +709,106 @@ impl RequestLogger {
             }
         }
     }
+
+    /// Records authentication and authorization events, as well as all other
+    /// API calls, in the audit log if enabled. Logins are recorded by the
+    /// [Authorizer](crate::daemon::auth::Authorizer) itself.
+    fn audit(&self, state: &State, source: Option<IpAddr>, actor: &Actor, res: Result<&HttpResponse, &Error>) {
+        let status = match res {
+            Ok(response) => response.status(),
+            Err(err) => err.status(),
+        };
+
+        let event = |kind| {
+            AuditEvent::new(kind, actor.name())
+                .with_source(source)
+                .with_request(self.req_method.as_str(), self.req_path.as_str())
+                .with_ca(audited_ca(&self.req_path))
+                .with_status(status.as_u16())
+        };
+
+        if actor.new_auth().is_some() {
+            state.audit(event(AuditEventKind::TokenRefresh));
+        }
+
+        if let Some(err) = actor.auth_error() {
+            state.audit(event(AuditEventKind::AuthFailed).with_details(err.to_string()));
+        } else if status == hyper::StatusCode::FORBIDDEN {
+            let event = event(AuditEventKind::PermissionDenied);
+            match res.ok().and_then(|response| response.cause()) {
+                Some(cause) => state.audit(event.with_details(cause.to_string())),
+                None => state.audit(event),
+            }
+        } else if self.req_path == AUTH_LOGOUT_ENDPOINT || self.req_path == AUTH_BACKCHANNEL_LOGOUT_ENDPOINT {
+            state.audit(event(AuditEventKind::Logout));
+        } else if self.req_path.starts_with("/api/") {
+            state.audit(event(AuditEventKind::ApiCall));
+        }
+    }
+}
+
+/// Returns the CA affected by a request for a path under `/api/<version>/cas/`.
+fn audited_ca(path: &str) -> Option<CaHandle> {
+    ApiVersion::split_path(path)
+        .and_then(|(_, remaining)| remaining.strip_prefix("/cas/"))
+        .and_then(|remaining| remaining.split('/').next())
+        .and_then(|ca| CaHandle::from_str(ca).ok())
 }
 
 async fn map_requests(req: hyper::Request<hyper::Body>, state: State) -> Result<hyper::Response<hyper::Body>, Error> {
+    let log_context = LogContext::request(req.headers()).with_ca(audited_ca(req.uri().path()).as_ref());
+    let request_id = log_context.request_id().and_then(|id| HeaderValue::from_str(id).ok());
+
+    let cx = telemetry::request_span(&req);
+    let res = log_context
+        .scope(telemetry::in_span(&cx, route_request(req, state)))
+        .await;
+    telemetry::end_request(&cx, res.as_ref().ok().map(|response| response.status()));
+
+    res.map(|mut response| {
+        if let Some(request_id) = request_id {
+            response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+        }
+        response
+    })
+}
+
+async fn route_request(req: hyper::Request<hyper::Body>, state: State) -> Result<hyper::Response<hyper::Body>, Error> {
     let logger = RequestLogger::begin(&req);
 
+    // Refuse the request if the client exceeds its rate limit, or if too many
+    // expensive operations are in progress. Otherwise hold on to the permit
+    // for an expensive operation until the response is done.
+    let (rate_limit_charge, _expensive_permit) = match state.rate_limiter().check(&req) {
+        Ok(checked) => checked,
+        Err(retry_after) => {
+            let res = HttpResponse::too_many_requests(retry_after);
+            logger.end(Ok(&res));
+            return Ok(res.response());
+        }
+    };
+
+    let audit_state = state.clone();
     let req = Request::new(req, state).await;
+    let source = req.source();
+    let actor = req.actor();
+    LogContext::set_actor(actor.name());
+
+    // Once the client is authenticated, its requests count against the
+    // actor rather than its address.
+    if let Some(charge) = rate_limit_charge {
+        if !actor.is_anonymous() && actor.auth_error().is_none() {
+            if let Err(retry_after) = req.state().rate_limiter().authenticated(charge, actor.name()) {
+                let res = HttpResponse::too_many_requests(retry_after);
+                logger.end(Ok(&res));
+                return Ok(res.response());
+            }
+        }
+    }
 
     // Save any updated auth details, e.g. if an OpenID Connect token needed
     // refreshing.
-    let new_auth = req.actor().new_auth();
+    let new_auth = actor.new_auth();
 
     // We used to use .or_else() here but that causes a large recursive call
     // tree due to these calls being to async functions, large enough with the
@@ -316,13 +816,19 @@ async fn map_requests(req: hyper::Request<hyper::Body>, state: State) -> Result<
     // stack overflow. By doing it by hand like this we avoid the use of the
     // macros that cause the recursion. We could also look at putting less data
     // on the stack.
-    let mut res = api(req).await;
+    let mut res = standby(req).await;
+    if let Err(req) = res {
+        res = api(req).await;
+    }
     if let Err(req) = res {
         res = auth(req).await;
     }
     if let Err(req) = res {
         res = health(req).await;
     }
+    if let Err(req) = res {
+        res = acme_challenge(req).await;
+    }
     if let Err(req) = res {
         res = metrics(req).await;
     }
@@ -344,6 +850,9 @@ async fn map_requests(req: hyper::Request<hyper::Body>, state: State) -> Result<
     if let Err(req) = res {
         res = testbed(req).await;
     }
+    if let Err(req) = res {
+        res = public_status(req).await;
+    }
     if let Err(req) = res {
         res = statics(req).await;
     }
@@ -361,6 +870,7 @@ async fn map_requests(req: hyper::Request<hyper::Body>, state: State) -> Result<
 
     // Log the request and the response.
     logger.end(res.as_ref());
+    logger.audit(&audit_state, source, &actor, res.as_ref());
 
     res.map(|res| res.response())
 }
@@ -431,6 +941,20 @@ pub async fn render_not_found(_req: Request) -> RoutingResult {
     Ok(HttpResponse::not_found())
 }
 
+/// Answers ACME HTTP-01 challenges while the HTTPS certificate is obtained
+/// or renewed.
+async fn acme_challenge(req: Request) -> RoutingResult {
+    if !req.is_get() || !req.path().full().starts_with(ACME_HTTP_CHALLENGE_PATH) {
+        return Err(req);
+    }
+
+    let token = &req.path().full()[ACME_HTTP_CHALLENGE_PATH.len()..];
+    match req.state().acme_challenges().http_response(token) {
+        Some(key_authorization) => Ok(HttpResponse::text(key_authorization.into_bytes())),
+        None => Ok(HttpResponse::not_found()),
+    }
+}
+
 /// Returns the server health.
 pub async fn health(req: Request) -> RoutingResult {
     if req.is_get() && req.path().segment() == "health" {
@@ -440,12 +964,38 @@ pub async fn health(req: Request) -> RoutingResult {
     }
 }
 
+/// Refuse requests that would result in changes, if this is the standby
+/// instance in high availability mode. Read-only requests are still served,
+/// as are the requests to promote this instance and to reload its config.
+pub async fn standby(req: Request) -> RoutingResult {
+    if !req.state().ha_is_standby() || req.is_get() || req.method() == Method::HEAD {
+        return Err(req);
+    }
+
+    let path = req.path().full();
+    let changes = ["/api/v1", "/rfc8181", "/rfc6492", "/ta", "/testbed"]
+        .iter()
+        .any(|prefix| path.starts_with(prefix));
+
+    if changes && !path.starts_with("/api/v1/ha/promote") && !path.starts_with("/api/v1/admin/reload") {
+        render_error(Error::ApiHaStandby)
+    } else {
+        Err(req)
+    }
+}
+
 /// Produce prometheus style metrics
 #[allow(clippy::format_push_string)]
 pub async fn metrics(req: Request) -> RoutingResult {
     if req.is_get() && req.path().segment().starts_with("metrics") {
         let server = req.state();
 
+        // The CA metrics can be limited to the CAs with matching labels.
+        let label_selector = match label_selector(&req) {
+            Ok(selector) => selector,
+            Err(e) => return render_error(e),
+        };
+
         struct AllBgpStats {
             announcements_valid: HashMap<CaHandle, usize>,
             announcements_invalid_asn: HashMap<CaHandle, usize>,
@@ -506,7 +1056,105 @@ pub async fn metrics(req: Request) -> RoutingResult {
             ));
         }
 
-        if let Ok(cas_stats) = server.cas_stats().await {
+        if let Ok(Some(time_check)) = server.time_check_status() {
+            res.push('\n');
+            res.push_str(
+                "# HELP krill_time_check_last_time unix timestamp in seconds of last check of the system time\n",
+            );
+            res.push_str("# TYPE krill_time_check_last_time gauge\n");
+            res.push_str(&format!("krill_time_check_last_time {}\n", time_check.timestamp()));
+
+            if let Some(skew) = time_check.skew_millis() {
+                res.push('\n');
+                res.push_str(
+                    "# HELP krill_time_skew_seconds difference between the time sources and the system time\n",
+                );
+                res.push_str("# TYPE krill_time_skew_seconds gauge\n");
+                res.push_str(&format!("krill_time_skew_seconds {}\n", skew as f64 / 1000.0));
+            }
+
+            res.push('\n');
+            res.push_str(
+                "# HELP krill_time_skew_exceeded whether the system time skew exceeds the maximum (0=no, 1=yes)\n",
+            );
+            res.push_str("# TYPE krill_time_skew_exceeded gauge\n");
+            res.push_str(&format!(
+                "krill_time_skew_exceeded {}\n",
+                i32::from(time_check.skew_exceeded())
+            ));
+
+            res.push('\n');
+            res.push_str("# HELP krill_time_issuance_refused whether issuance is refused because of the system time skew (0=no, 1=yes)\n");
+            res.push_str("# TYPE krill_time_issuance_refused gauge\n");
+            res.push_str(&format!(
+                "krill_time_issuance_refused {}\n",
+                i32::from(time_check.issuance_refused())
+            ));
+        }
+
+        {
+            res.push('\n');
+            res.push_str(
+                "# HELP krill_auth_failures_total number of failed logins, authentications and authorizations\n",
+            );
+            res.push_str("# TYPE krill_auth_failures_total counter\n");
+            for (kind, total) in server.auth_failures().totals() {
+                res.push_str(&format!("krill_auth_failures_total{{reason=\"{}\"}} {}\n", kind, total));
+            }
+        }
+
+        {
+            let republish = server.republish_metrics();
+
+            res.push('\n');
+            res.push_str("# HELP krill_republish_queue_depth number of CAs waiting to be checked for re-issuance\n");
+            res.push_str("# TYPE krill_republish_queue_depth gauge\n");
+            res.push_str(&format!("krill_republish_queue_depth {}\n", republish.queued()));
+
+            res.push('\n');
+            res.push_str(
+                "# HELP krill_republish_reissued_total number of CAs for which the manifest and CRL were re-issued\n",
+            );
+            res.push_str("# TYPE krill_republish_reissued_total counter\n");
+            res.push_str(&format!(
+                "krill_republish_reissued_total {}\n",
+                republish.reissued_total()
+            ));
+
+            res.push('\n');
+            res.push_str("# HELP krill_republish_last_run_seconds duration in seconds of the last re-issuance run\n");
+            res.push_str("# TYPE krill_republish_last_run_seconds gauge\n");
+            res.push_str(&format!(
+                "krill_republish_last_run_seconds {}\n",
+                republish.last_run_seconds()
+            ));
+
+            res.push('\n');
+            res.push_str(
+                "# HELP krill_republish_last_run_reissued number of CAs re-issued in the last re-issuance run\n",
+            );
+            res.push_str("# TYPE krill_republish_last_run_reissued gauge\n");
+            res.push_str(&format!(
+                "krill_republish_last_run_reissued {}\n",
+                republish.last_run_reissued()
+            ));
+        }
+
+        if let Ok(mut cas_stats) = server.cas_stats().await {
+            if let Some(selector) = &label_selector {
+                let selected: Vec<CaHandle> = server
+                    .ca_list(server.system_actor())
+                    .map(|list| {
+                        list.select(selector)
+                            .cas()
+                            .iter()
+                            .map(|ca| ca.handle().clone())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                cas_stats.retain(|ca, _| selected.contains(ca));
+            }
+
             let number_cas = cas_stats.len();
 
             res.push('\n');
@@ -514,7 +1162,7 @@ pub async fn metrics(req: Request) -> RoutingResult {
             res.push_str("# TYPE krill_cas gauge\n");
             res.push_str(&format!("krill_cas {}\n", number_cas));
 
-            if !server.config.metrics.metrics_hide_ca_details {
+            if !server.config().metrics.metrics_hide_ca_details {
                 // Show per CA details
 
                 let mut ca_status_map: HashMap<CaHandle, CaStatus> = HashMap::new();
@@ -613,26 +1261,279 @@ pub async fn metrics(req: Request) -> RoutingResult {
                     }
                 }
 
-                // Do not show child metrics if none of the CAs has any children..
-                // Many users do not delegate so, showing these metrics would just be confusing.
-                let any_children = cas_stats.values().any(|ca| ca.child_count() > 0);
-
-                if any_children && !server.config.metrics.metrics_hide_child_details {
-                    // CA -> Children
+                if ca_status_map
+                    .values()
+                    .any(|status| status.publication_check().is_some())
+                {
+                    // CA -> Publication Check
 
-                    // krill_cas_children{ca="parent"} 11 // nr of children
-                    // krill_ca_child_success{ca="parent", child="child"} 1
-                    // krill_ca_child_state{ca="parent", child="child"} 1
-                    // krill_ca_child_last_connection{ca="parent", child="child"} 1630921599
-                    // krill_ca_child_last_success{ca="parent", child="child"} 1630921599
-                    // krill_ca_child_agent_total{ca="parent", ua="krill/0.9.2"} 11
+                    // krill_ca_publication_check_success{{ca="ca", protocol="rrdp"}} 1
+                    // krill_ca_publication_check_issues{{ca="ca", protocol="rrdp"}} 0
+                    // krill_ca_publication_check_last_time{{ca="ca"}} 1630921599
 
                     res.push('\n');
-                    res.push_str("# HELP krill_cas_children number of children for CA\n");
-                    res.push_str("# TYPE krill_cas_children gauge\n");
-                    for (ca, status) in cas_stats.iter() {
-                        res.push_str(&format!(
-                            "krill_cas_children{{ca=\"{}\"}} {}\n",
+                    res.push_str("# HELP krill_ca_publication_check_success status of last check of the objects published by the CA (0=issues, 1=success)\n");
+                    res.push_str("# TYPE krill_ca_publication_check_success gauge\n");
+                    for (ca, status) in ca_status_map.iter() {
+                        if let Some(check) = status.publication_check() {
+                            for source in check.sources() {
+                                res.push_str(&format!(
+                                    "krill_ca_publication_check_success{{ca=\"{}\", protocol=\"{}\"}} {}\n",
+                                    ca,
+                                    source.protocol().to_string().to_lowercase(),
+                                    i32::from(source.issues().is_empty())
+                                ));
+                            }
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_publication_check_issues number of issues found in last check of the objects published by the CA\n");
+                    res.push_str("# TYPE krill_ca_publication_check_issues gauge\n");
+                    for (ca, status) in ca_status_map.iter() {
+                        if let Some(check) = status.publication_check() {
+                            for source in check.sources() {
+                                res.push_str(&format!(
+                                    "krill_ca_publication_check_issues{{ca=\"{}\", protocol=\"{}\"}} {}\n",
+                                    ca,
+                                    source.protocol().to_string().to_lowercase(),
+                                    source.issues().len()
+                                ));
+                            }
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_publication_check_last_time unix timestamp in seconds of last check of the objects published by the CA\n");
+                    res.push_str("# TYPE krill_ca_publication_check_last_time gauge\n");
+                    for (ca, status) in ca_status_map.iter() {
+                        if let Some(check) = status.publication_check() {
+                            res.push_str(&format!(
+                                "krill_ca_publication_check_last_time{{ca=\"{}\"}} {}\n",
+                                ca,
+                                check.timestamp()
+                            ));
+                        }
+                    }
+                }
+
+                if ca_status_map.values().any(|status| status.reconciliation().is_some()) {
+                    // CA -> Reconciliation
+
+                    // krill_ca_reconciliation_divergences{{ca="ca"}} 0
+                    // krill_ca_reconciliation_last_time{{ca="ca"}} 1630921599
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_reconciliation_divergences number of divergences between the configuration of the CA and its objects found in last reconciliation\n");
+                    res.push_str("# TYPE krill_ca_reconciliation_divergences gauge\n");
+                    for (ca, status) in ca_status_map.iter() {
+                        if let Some(reconciliation) = status.reconciliation() {
+                            res.push_str(&format!(
+                                "krill_ca_reconciliation_divergences{{ca=\"{}\"}} {}\n",
+                                ca,
+                                reconciliation.divergences().len()
+                            ));
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_reconciliation_last_time unix timestamp in seconds of last reconciliation of the CA\n");
+                    res.push_str("# TYPE krill_ca_reconciliation_last_time gauge\n");
+                    for (ca, status) in ca_status_map.iter() {
+                        if let Some(reconciliation) = status.reconciliation() {
+                            res.push_str(&format!(
+                                "krill_ca_reconciliation_last_time{{ca=\"{}\"}} {}\n",
+                                ca,
+                                reconciliation.timestamp()
+                            ));
+                        }
+                    }
+                }
+
+                {
+                    // CA -> Published objects expiry
+
+                    // krill_ca_objects_expiring{{ca="ca", type="roa"}} 0
+                    // krill_ca_objects_next_expiry_time{{ca="ca", type="roa"}} 1630921599
+                    // krill_ca_objects_expiry_hours_bucket{{ca="ca", type="roa", le="168"}} 2
+
+                    let mut expiry_map: HashMap<CaHandle, BTreeMap<ObjectExpiryType, (usize, i64)>> = HashMap::new();
+                    let mut distribution_map = HashMap::new();
+                    for ca in cas_stats.keys() {
+                        if let Ok(expiry) = server.ca_objects_expiry(ca, None) {
+                            distribution_map.insert(ca.clone(), expiry.distribution(&METRICS_EXPIRY_BUCKETS_HOURS));
+
+                            let mut types: BTreeMap<ObjectExpiryType, (usize, i64)> = BTreeMap::new();
+                            for object in expiry.objects() {
+                                let (expiring, next) = types
+                                    .entry(object.object_type())
+                                    .or_insert((0, object.not_after().timestamp()));
+                                if object.expiring() {
+                                    *expiring += 1;
+                                }
+                                *next = (*next).min(object.not_after().timestamp());
+                            }
+                            expiry_map.insert(ca.clone(), types);
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str(&format!(
+                        "# HELP krill_ca_objects_expiring number of objects published by the CA which expire within {} hours\n",
+                        server.config().expiry_window_hours
+                    ));
+                    res.push_str("# TYPE krill_ca_objects_expiring gauge\n");
+                    for (ca, types) in expiry_map.iter() {
+                        for (object_type, (expiring, _)) in types.iter() {
+                            res.push_str(&format!(
+                                "krill_ca_objects_expiring{{ca=\"{}\", type=\"{}\"}} {}\n",
+                                ca, object_type, expiring
+                            ));
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_objects_next_expiry_time unix timestamp in seconds of the first expiry of an object published by the CA\n");
+                    res.push_str("# TYPE krill_ca_objects_next_expiry_time gauge\n");
+                    for (ca, types) in expiry_map.iter() {
+                        for (object_type, (_, next)) in types.iter() {
+                            res.push_str(&format!(
+                                "krill_ca_objects_next_expiry_time{{ca=\"{}\", type=\"{}\"}} {}\n",
+                                ca, object_type, next
+                            ));
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_objects_expiry_hours distribution of the hours until objects published by the CA expire\n");
+                    res.push_str("# TYPE krill_ca_objects_expiry_hours histogram\n");
+                    for (ca, types) in distribution_map.iter() {
+                        for (object_type, distribution) in types.iter() {
+                            for (bound, count) in distribution.buckets() {
+                                res.push_str(&format!(
+                                    "krill_ca_objects_expiry_hours_bucket{{ca=\"{}\", type=\"{}\", le=\"{}\"}} {}\n",
+                                    ca, object_type, bound, count
+                                ));
+                            }
+                            res.push_str(&format!(
+                                "krill_ca_objects_expiry_hours_bucket{{ca=\"{}\", type=\"{}\", le=\"+Inf\"}} {}\n",
+                                ca,
+                                object_type,
+                                distribution.count()
+                            ));
+                            res.push_str(&format!(
+                                "krill_ca_objects_expiry_hours_sum{{ca=\"{}\", type=\"{}\"}} {}\n",
+                                ca,
+                                object_type,
+                                distribution.sum_hours()
+                            ));
+                            res.push_str(&format!(
+                                "krill_ca_objects_expiry_hours_count{{ca=\"{}\", type=\"{}\"}} {}\n",
+                                ca,
+                                object_type,
+                                distribution.count()
+                            ));
+                        }
+                    }
+                }
+
+                if server.config().metrics.metrics_show_ca_extended_details {
+                    // CA -> Extended details
+
+                    // krill_ca_roas{{ca="ca"}} 12
+                    // krill_ca_aspas{{ca="ca"}} 1
+                    // krill_ca_parent_failures{{ca="ca", parent="parent"}} 0
+                    // krill_ca_ps_lag_seconds{{ca="ca"}} 0
+                    // krill_ca_commands_total{{ca="ca", type="cmd-ca-roas-updated"}} 3
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_roas number of ROAs configured for the CA\n");
+                    res.push_str("# TYPE krill_ca_roas gauge\n");
+                    for (ca, stats) in cas_stats.iter() {
+                        res.push_str(&format!("krill_ca_roas{{ca=\"{}\"}} {}\n", ca, stats.roa_count()));
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_aspas number of ASPAs configured for the CA\n");
+                    res.push_str("# TYPE krill_ca_aspas gauge\n");
+                    for (ca, stats) in cas_stats.iter() {
+                        res.push_str(&format!("krill_ca_aspas{{ca=\"{}\"}} {}\n", ca, stats.aspa_count()));
+                    }
+
+                    res.push('\n');
+                    res.push_str(
+                        "# HELP krill_ca_parent_failures number of consecutive failed CA to parent connections\n",
+                    );
+                    res.push_str("# TYPE krill_ca_parent_failures gauge\n");
+                    for (ca, status) in ca_status_map.iter() {
+                        if ca.as_str() != TA_NAME {
+                            for (parent, status) in status.parents().iter() {
+                                if status.last_exchange().is_some() {
+                                    res.push_str(&format!(
+                                        "krill_ca_parent_failures{{ca=\"{}\", parent=\"{}\"}} {}\n",
+                                        ca,
+                                        parent,
+                                        status.backoff().map(|backoff| backoff.failures()).unwrap_or(0)
+                                    ));
+                                }
+                            }
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_ps_lag_seconds seconds since the last successful CA to Publication Server connection if the last connection failed (0=up to date)\n");
+                    res.push_str("# TYPE krill_ca_ps_lag_seconds gauge\n");
+                    let now = i64::from(Timestamp::now());
+                    for (ca, status) in ca_status_map.iter() {
+                        // skip the ones which were never successful, the
+                        // krill_ca_ps_success metric will say that the status is 0
+                        if let (Some(exchange), Some(last_success)) =
+                            (status.repo().last_exchange(), status.repo().last_success())
+                        {
+                            let lag = if exchange.was_success() {
+                                0
+                            } else {
+                                (now - i64::from(last_success)).max(0)
+                            };
+                            res.push_str(&format!("krill_ca_ps_lag_seconds{{ca=\"{}\"}} {}\n", ca, lag));
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_ca_commands_total number of commands processed for the CA, by type\n");
+                    res.push_str("# TYPE krill_ca_commands_total counter\n");
+                    for (ca, status) in ca_status_map.iter() {
+                        if cas_stats.contains_key(ca) {
+                            for (label, count) in status.command_counts().iter() {
+                                res.push_str(&format!(
+                                    "krill_ca_commands_total{{ca=\"{}\", type=\"{}\"}} {}\n",
+                                    ca, label, count
+                                ));
+                            }
+                        }
+                    }
+                }
+
+                // Do not show child metrics if none of the CAs has any children..
+                // Many users do not delegate so, showing these metrics would just be confusing.
+                let any_children = cas_stats.values().any(|ca| ca.child_count() > 0);
+
+                if any_children && !server.config().metrics.metrics_hide_child_details {
+                    // CA -> Children
+
+                    // krill_cas_children{ca="parent"} 11 // nr of children
+                    // krill_ca_child_success{ca="parent", child="child"} 1
+                    // krill_ca_child_state{ca="parent", child="child"} 1
+                    // krill_ca_child_last_connection{ca="parent", child="child"} 1630921599
+                    // krill_ca_child_last_success{ca="parent", child="child"} 1630921599
+                    // krill_ca_child_agent_total{ca="parent", ua="krill/0.9.2"} 11
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_cas_children number of children for CA\n");
+                    res.push_str("# TYPE krill_cas_children gauge\n");
+                    for (ca, status) in cas_stats.iter() {
+                        res.push_str(&format!(
+                            "krill_cas_children{{ca=\"{}\"}} {}\n",
                             ca,
                             status.child_count()
                         ));
@@ -735,7 +1636,7 @@ pub async fn metrics(req: Request) -> RoutingResult {
                     }
                 }
 
-                if !server.config.metrics.metrics_hide_roa_details {
+                if !server.config().metrics.metrics_hide_roa_details {
                     // BGP Announcement metrics
 
                     // Aggregate ROA vs BGP stats per status
@@ -844,7 +1745,35 @@ pub async fn metrics(req: Request) -> RoutingResult {
             res.push_str("# TYPE krill_repo_rrdp_serial counter\n");
             res.push_str(&format!("krill_repo_rrdp_serial {}\n", stats.serial()));
 
-            if !server.config.metrics.metrics_hide_publisher_details {
+            if let Ok(rrdp_stats) = server.rrdp_stats() {
+                if let Some(size) = rrdp_stats.notification_size() {
+                    res.push('\n');
+                    res.push_str(
+                        "# HELP krill_repo_rrdp_notification_size size of the RRDP notification file in bytes\n",
+                    );
+                    res.push_str("# TYPE krill_repo_rrdp_notification_size gauge\n");
+                    res.push_str(&format!("krill_repo_rrdp_notification_size {}\n", size));
+                }
+
+                if let Some(size) = rrdp_stats.snapshot_size() {
+                    res.push('\n');
+                    res.push_str("# HELP krill_repo_rrdp_snapshot_size size of the RRDP snapshot file in bytes\n");
+                    res.push_str("# TYPE krill_repo_rrdp_snapshot_size gauge\n");
+                    res.push_str(&format!("krill_repo_rrdp_snapshot_size {}\n", size));
+                }
+
+                res.push('\n');
+                res.push_str("# HELP krill_repo_rrdp_deltas number of RRDP deltas\n");
+                res.push_str("# TYPE krill_repo_rrdp_deltas gauge\n");
+                res.push_str(&format!("krill_repo_rrdp_deltas {}\n", rrdp_stats.deltas().len()));
+
+                res.push('\n');
+                res.push_str("# HELP krill_repo_rrdp_deltas_size total size of the RRDP delta files in bytes\n");
+                res.push_str("# TYPE krill_repo_rrdp_deltas_size gauge\n");
+                res.push_str(&format!("krill_repo_rrdp_deltas_size {}\n", rrdp_stats.deltas_size()));
+            }
+
+            if !server.config().metrics.metrics_hide_publisher_details {
                 res.push('\n');
                 res.push_str("# HELP krill_repo_objects number of objects in repository for publisher\n");
                 res.push_str("# TYPE krill_repo_objects gauge\n");
@@ -879,6 +1808,75 @@ pub async fn metrics(req: Request) -> RoutingResult {
                         ));
                     }
                 }
+
+                if let Ok(publishers_stats) = server.publishers_stats() {
+                    let publishers_stats = publishers_stats.publishers();
+
+                    res.push('\n');
+                    res.push_str(
+                        "# HELP krill_repo_last_changed_serial RRDP serial at which the content for publisher last changed\n",
+                    );
+                    res.push_str("# TYPE krill_repo_last_changed_serial gauge\n");
+                    for stats in publishers_stats {
+                        if let Some(serial) = stats.last_changed_serial() {
+                            res.push_str(&format!(
+                                "krill_repo_last_changed_serial{{publisher=\"{}\"}} {}\n",
+                                stats.publisher(),
+                                serial
+                            ));
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str(
+                        "# HELP krill_repo_last_publish unix timestamp in seconds of last delta published by publisher\n",
+                    );
+                    res.push_str("# TYPE krill_repo_last_publish gauge\n");
+                    for stats in publishers_stats {
+                        if let Some(last_publish) = stats.activity().last_publish {
+                            res.push_str(&format!(
+                                "krill_repo_last_publish{{publisher=\"{}\"}} {}\n",
+                                stats.publisher(),
+                                last_publish
+                            ));
+                        }
+                    }
+
+                    res.push('\n');
+                    res.push_str(
+                        "# HELP krill_repo_deltas_last_hour number of deltas published by publisher in the last hour\n",
+                    );
+                    res.push_str("# TYPE krill_repo_deltas_last_hour gauge\n");
+                    for stats in publishers_stats {
+                        res.push_str(&format!(
+                            "krill_repo_deltas_last_hour{{publisher=\"{}\"}} {}\n",
+                            stats.publisher(),
+                            stats.activity().deltas_last_hour
+                        ));
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_repo_deltas_total number of deltas published by publisher\n");
+                    res.push_str("# TYPE krill_repo_deltas_total counter\n");
+                    for stats in publishers_stats {
+                        res.push_str(&format!(
+                            "krill_repo_deltas_total{{publisher=\"{}\"}} {}\n",
+                            stats.publisher(),
+                            stats.activity().deltas_total
+                        ));
+                    }
+
+                    res.push('\n');
+                    res.push_str("# HELP krill_repo_errors_total number of failed requests by publisher\n");
+                    res.push_str("# TYPE krill_repo_errors_total counter\n");
+                    for stats in publishers_stats {
+                        res.push_str(&format!(
+                            "krill_repo_errors_total{{publisher=\"{}\"}} {}\n",
+                            stats.publisher(),
+                            stats.activity().errors_total
+                        ));
+                    }
+                }
             }
         }
 
@@ -930,7 +1928,7 @@ async fn ta(req: Request) -> RoutingResult {
 
 pub async fn tal(req: Request) -> RoutingResult {
     match req.state().ta_cert_details().await {
-        Ok(ta) => Ok(HttpResponse::text(format!("{}", ta.tal()).into_bytes())),
+        Ok(ta) => Ok(HttpResponse::text(format!("{}", ta.current_tal()).into_bytes())),
         Err(_) => render_unknown_resource(),
     }
 }
@@ -1094,9 +2092,25 @@ macro_rules! aa {
     }};
 }
 
+/// The error for an actor who lacks the permission on a resource which could
+/// not be checked with [`aa!`] because it is named in the request body.
+fn insufficient_rights(actor: &Actor, permission: Permission, resource: &impl fmt::Display) -> Error {
+    Error::ApiInsufficientRights(format!(
+        "User '{}' does not have permission '{}' on resource '{}'",
+        actor.name(),
+        permission,
+        resource
+    ))
+}
+
 /// Maps the API methods
 async fn api(req: Request) -> RoutingResult {
-    if !req.path().full().starts_with("/api/v1") {
+    if req.path().full() == "/api/versions" {
+        match *req.method() {
+            Method::GET => render_json(ApiVersions::current()),
+            _ => render_unknown_method(),
+        }
+    } else if !req.path().full().starts_with("/api/v1") {
         Err(req) // Not for us
     } else {
         // Eat the first two segments of the path "api/v1"
@@ -1113,6 +2127,17 @@ async fn api(req: Request) -> RoutingResult {
                         Some("cas") => api_cas(req, &mut path).await,
                         Some("pubd") => aa!(req, Permission::PUB_ADMIN, api_publication_server(req, &mut path).await),
                         Some("ta") => aa!(req, Permission::CA_ADMIN, api_ta(req, &mut path).await),
+                        Some("audit") => aa!(req, Permission::CA_ADMIN, api_audit(req, &mut path).await),
+                        Some("backups") => aa!(req, Permission::CA_ADMIN, api_backups(req, &mut path).await),
+                        Some("ha") => aa!(req, Permission::CA_ADMIN, api_ha(req, &mut path).await),
+                        Some("time") => aa!(req, Permission::CA_ADMIN, api_time(req, &mut path).await),
+                        Some("admin") => aa!(req, Permission::CA_ADMIN, api_admin(req, &mut path).await),
+                        Some("tasks") => aa!(req, Permission::CA_ADMIN, api_tasks(req, &mut path).await),
+                        Some("jobs") => api_jobs(req, &mut path).await,
+                        Some("events") => api_events(req).await,
+                        Some("search") => api_search(req).await,
+                        #[cfg(feature = "multi-user")]
+                        Some("tokens") => aa!(req, Permission::CA_ADMIN, api_tokens(req, &mut path).await),
                         _ => render_unknown_method(),
                     }
                 })
@@ -1121,6 +2146,25 @@ async fn api(req: Request) -> RoutingResult {
     }
 }
 
+/// Handles /api/v1/search?q=<query>, returning what matches the query in
+/// the CAs the user may read, and the publishers if the user may administer
+/// the publication server.
+async fn api_search(req: Request) -> RoutingResult {
+    if *req.method() != Method::GET {
+        return render_unknown_method();
+    }
+
+    aa!(req, Permission::CA_LIST, {
+        let query = match req.query_param("q").map(|q| SearchQuery::from_str(&q)) {
+            Some(Ok(query)) => query,
+            _ => return render_error(Error::ApiInvalidQueryParam("q".to_string())),
+        };
+
+        let actor = req.actor();
+        render_json_res(req.state().search(&query, &actor).await)
+    })
+}
+
 async fn api_authorized(req: Request) -> RoutingResult {
     // Use 'no_warn' to prevent the log being filled with warnings about
     // insufficient user rights as this API endpoint is invoked by Lagosta on
@@ -1139,12 +2183,16 @@ async fn api_authorized(req: Request) -> RoutingResult {
 async fn api_bulk(req: Request, path: &mut RequestPath) -> RoutingResult {
     match path.full() {
         "/api/v1/bulk/cas/import" => api_cas_import(req).await,
+        "/api/v1/bulk/cas/migrate" => api_cas_migrate(req).await,
         "/api/v1/bulk/cas/issues" => api_all_ca_issues(req).await,
         "/api/v1/bulk/cas/sync/parent" => api_refresh_all(req).await,
         "/api/v1/bulk/cas/sync/repo" => api_resync_all(req).await,
         "/api/v1/bulk/cas/publish" => api_republish_all(req, false).await,
         "/api/v1/bulk/cas/force_publish" => api_republish_all(req, true).await,
         "/api/v1/bulk/cas/suspend" => api_suspend_all(req).await,
+        "/api/v1/bulk/snapshots" => api_snapshots_save(req).await,
+        "/api/v1/bulk/snapshots/verify" => api_snapshots_verify(req).await,
+        "/api/v1/bulk/jobs" => api_bulk_job_start(req).await,
         _ => render_unknown_method(),
     }
 }
@@ -1160,18 +2208,30 @@ async fn api_cas(req: Request, path: &mut RequestPath) -> RoutingResult {
                 },
                 Some("aspas") => api_ca_aspas(req, path, ca).await,
                 Some("bgpsec") => api_ca_bgpsec(req, path, ca).await,
+                Some("child_policy") => api_ca_child_policy(req, path, ca).await,
                 Some("children") => api_ca_children(req, path, ca).await,
+                #[cfg(feature = "multi-user")]
+                Some("delegations") => api_ca_delegations(req, path, ca).await,
+                Some("expiry") => api_ca_expiry(req, ca).await,
+                Some("export") => api_ca_export(req, ca).await,
                 Some("history") => api_ca_history(req, path, ca).await,
 
                 Some("id") => api_ca_id(req, path, ca).await,
                 Some("issues") => api_ca_issues(req, ca).await,
                 Some("keys") => api_ca_keys(req, path, ca).await,
+                Some("labels") => api_ca_labels(req, ca).await,
                 Some("parents") => api_ca_parents(req, path, ca).await,
+                Some("reconcile") => api_ca_reconcile(req, ca).await,
                 Some("repo") => api_ca_repo(req, path, ca).await,
+                Some("retry") => api_ca_retry(req, path, ca).await,
                 Some("routes") => api_ca_routes(req, path, ca).await,
+                Some("scheduled") => api_ca_scheduled_changes(req, path, ca).await,
                 Some("stats") => api_ca_stats(req, path, ca).await,
                 Some("sync") => api_ca_sync(req, path, ca).await,
+                Some("timing") => api_ca_timing(req, path, ca).await,
+                Some("transfers") => api_ca_resource_transfers(req, path, ca).await,
 
+                Some("rsc") => api_ca_rsc(req, path, ca).await,
                 Some("rta") => api_ca_rta(req, path, ca).await,
 
                 _ => render_unknown_method(),
@@ -1190,6 +2250,11 @@ async fn api_ca_keys(req: Request, path: &mut RequestPath, ca: CaHandle) -> Rout
         Method::POST => match path.next() {
             Some("roll_init") => api_ca_kr_init(req, ca).await,
             Some("roll_activate") => api_ca_kr_activate(req, ca).await,
+            Some("migrate") => api_ca_signer_migrate(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        Method::GET => match path.next() {
+            Some("signers") => api_ca_signer_migration_status(req, ca).await,
             _ => render_unknown_method(),
         },
         _ => render_unknown_method(),
@@ -1198,10 +2263,21 @@ async fn api_ca_keys(req: Request, path: &mut RequestPath, ca: CaHandle) -> Rout
 
 async fn api_ca_parents(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
     if let Some(parent) = path.path_arg() {
-        match *req.method() {
-            Method::GET => api_ca_my_parent_contact(req, ca, parent).await,
-            Method::POST => api_ca_parent_add_or_update(req, ca, Some(parent)).await,
-            Method::DELETE => api_ca_remove_parent(req, ca, parent).await,
+        match path.next() {
+            None => match *req.method() {
+                Method::GET => api_ca_my_parent_contact(req, ca, parent).await,
+                Method::POST => api_ca_parent_add_or_update(req, ca, Some(parent)).await,
+                Method::DELETE => api_ca_remove_parent(req, ca, parent).await,
+                _ => render_unknown_method(),
+            },
+            Some("fetch") => match *req.method() {
+                Method::POST => api_ca_parent_fetch(req, ca, parent).await,
+                _ => render_unknown_method(),
+            },
+            Some("exchanges") => match *req.method() {
+                Method::GET => api_ca_parent_exchanges(req, ca, parent).await,
+                _ => render_unknown_method(),
+            },
             _ => render_unknown_method(),
         }
     } else {
@@ -1221,32 +2297,169 @@ async fn api_ca_repo(req: Request, path: &mut RequestPath, ca: CaHandle) -> Rout
             _ => render_unknown_method(),
         },
         Some("status") => api_ca_repo_status(req, ca).await,
+        Some("statuses") => api_ca_repo_statuses(req, ca).await,
+        Some("check") => api_ca_repo_check(req, ca).await,
+        Some("id") => api_ca_repo_id(req, path, ca).await,
+        Some("classes") => api_ca_repo_classes(req, path, ca).await,
+        Some("migration") => api_ca_repo_migration(req, path, ca).await,
+        Some("migrate") => match *req.method() {
+            Method::POST => api_ca_repo_migrate(req, ca).await,
+            _ => render_unknown_method(),
+        },
         _ => render_unknown_method(),
     }
 }
 
-async fn api_ca_routes(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
-    match path.next() {
+async fn api_ca_repo_classes(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match path.next().map(ResourceClassName::from) {
         None => match *req.method() {
-            Method::GET => api_ca_routes_show(req, ca).await,
-            Method::POST => api_ca_routes_update(req, ca).await,
+            Method::GET => aa!(
+                req,
+                Permission::CA_READ,
+                Handle::from(&ca),
+                render_json_res(req.state().ca_class_repos(&ca).await)
+            ),
             _ => render_unknown_method(),
         },
-        Some("try") => match *req.method() {
-            Method::POST => api_ca_routes_try_update(req, ca).await,
+        Some(rcn) => match *req.method() {
+            Method::POST => api_ca_class_repo_update(req, ca, rcn).await,
+            Method::DELETE => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+                let actor = req.actor();
+                render_empty_res(req.state().ca_class_repo_update(ca, rcn, None, &actor).await)
+            }),
             _ => render_unknown_method(),
         },
-        Some("analysis") => api_ca_routes_analysis(req, path, ca).await,
+    }
+}
+
+async fn api_ca_repo_migration(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match (req.method().clone(), path.next()) {
+        (Method::GET, None) => aa!(
+            req,
+            Permission::CA_READ,
+            Handle::from(&ca),
+            render_json_res(req.state().ca_repo_migration_status(&ca).await)
+        ),
+        (Method::POST, Some("activate")) => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+            let actor = req.actor();
+            render_json_res(req.state().ca_repo_migration_activate(&ca, &actor).await)
+        }),
+        (Method::POST, Some("abort")) => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+            let actor = req.actor();
+            render_json_res(req.state().ca_repo_migration_abort(&ca, &actor).await)
+        }),
         _ => render_unknown_method(),
     }
 }
 
-async fn api_ca_stats(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
-    match path.next() {
-        Some("children") => match path.next() {
-            Some("connections") => api_ca_stats_child_connections(req, ca).await,
-            _ => render_unknown_method(),
-        },
+async fn api_ca_repo_id(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match (req.method().clone(), path.next()) {
+        (Method::POST, Some("init")) => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+            let actor = req.actor();
+            render_json_res(req.state().ca_id_roll_init(ca, &actor).await)
+        }),
+        (Method::POST, Some("activate")) => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+            let actor = req.actor();
+            render_empty_res(req.state().ca_id_roll_activate(ca, &actor).await)
+        }),
+        (Method::GET, Some("publisher_request.json")) => aa!(
+            req,
+            Permission::CA_READ,
+            Handle::from(&ca),
+            render_json_res(req.state().ca_id_roll_request(&ca).await)
+        ),
+        (Method::GET, Some("publisher_request.xml")) => aa!(
+            req,
+            Permission::CA_READ,
+            Handle::from(&ca),
+            match req.state().ca_id_roll_request(&ca).await {
+                Ok(publisher_request) => Ok(HttpResponse::xml(publisher_request.to_xml_vec())),
+                Err(e) => render_error(e),
+            }
+        ),
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ca_timing(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => api_ca_timing_show(req, ca).await,
+            Method::POST => api_ca_timing_update(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        Some("effective") => match *req.method() {
+            Method::GET => api_ca_timing_effective(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ca_retry(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match (req.method().clone(), path.next()) {
+        (Method::GET, None) => api_ca_retry_show(req, ca).await,
+        (Method::POST, Some("repo")) => api_ca_retry_update(req, ca, None).await,
+        (Method::POST, Some("parents")) => match path.path_arg() {
+            Some(parent) => api_ca_retry_update(req, ca, Some(parent)).await,
+            None => render_unknown_method(),
+        },
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ca_labels(req: Request, ca: CaHandle) -> RoutingResult {
+    match *req.method() {
+        Method::GET => aa!(
+            req,
+            Permission::CA_READ,
+            Handle::from(&ca),
+            render_json_res(req.state().ca_labels(&ca).await)
+        ),
+        Method::POST => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+            let actor = req.actor();
+            let state = req.state().clone();
+
+            match req.json().await {
+                Err(e) => render_error(e),
+                Ok(update) => render_empty_res(state.ca_labels_update(ca, update, &actor).await),
+            }
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ca_routes(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => api_ca_routes_show(req, ca).await,
+            Method::POST => api_ca_routes_update(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        Some("try") => match *req.method() {
+            Method::POST => api_ca_routes_try_update(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        Some("analysis") => api_ca_routes_analysis(req, path, ca).await,
+        Some("import") => match *req.method() {
+            Method::POST => api_ca_routes_import(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        Some("proposals") => api_ca_routes_proposals(req, path, ca).await,
+        Some("strategy") => api_ca_routes_strategy(req, ca).await,
+        Some("autopilot") => api_ca_routes_autopilot(req, ca).await,
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ca_stats(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match path.next() {
+        Some("children") => match path.next() {
+            None => api_ca_stats_children(req, ca).await,
+            Some("connections") => api_ca_stats_child_connections(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        Some("parents") => api_ca_stats_parents(req, ca).await,
         _ => render_unknown_method(),
     }
 }
@@ -1280,6 +2493,10 @@ async fn api_publication_server(req: Request, path: &mut RequestPath) -> Routing
             _ => render_unknown_method(),
         },
         Some("stale") => api_stale_publishers(req, path.next()).await,
+        Some("stats") => match (path.next(), req.method()) {
+            (Some("publishers"), &Method::GET) => api_publishers_stats(req).await,
+            _ => render_unknown_method(),
+        },
         Some("init") => match *req.method() {
             Method::POST => {
                 let state = req.state.clone();
@@ -1295,6 +2512,20 @@ async fn api_publication_server(req: Request, path: &mut RequestPath) -> Routing
             Method::POST => render_empty_res(req.state().repository_session_reset()),
             _ => render_unknown_method(),
         },
+        Some("rrdp") => match *req.method() {
+            Method::GET => render_json_res(req.state().rrdp_stats()),
+            _ => render_unknown_method(),
+        },
+        Some("archive") => match *req.method() {
+            Method::GET => render_json_res(req.state().repository_archive_list()),
+            Method::POST => render_json_res(req.state().repository_archive().await),
+            _ => render_unknown_method(),
+        },
+        Some("replica") => match *req.method() {
+            Method::GET => render_json_res(req.state().repository_replica_status()),
+            Method::POST => render_json_res(req.state().repository_replicate().await),
+            _ => render_unknown_method(),
+        },
         _ => render_unknown_method(),
     }
 }
@@ -1306,6 +2537,9 @@ async fn api_publishers(req: Request, path: &mut RequestPath) -> RoutingResult {
                 None => api_show_pbl(req, publisher).await,
                 Some("response.xml") => api_repository_response_xml(req, publisher).await,
                 Some("response.json") => api_repository_response_json(req, publisher).await,
+                Some("quota") => api_show_pbl_quota(req, publisher).await,
+                Some("validation") => api_show_pbl_validation(req, publisher).await,
+                Some("stats") => api_show_pbl_stats(req, publisher).await,
 
                 _ => render_unknown_method(),
             },
@@ -1313,16 +2547,251 @@ async fn api_publishers(req: Request, path: &mut RequestPath) -> RoutingResult {
         },
         Method::POST => match path.next() {
             None => api_add_pbl(req).await,
-            _ => render_unknown_method(),
+            Some(publisher) => match (PublisherHandle::from_str(publisher), path.next()) {
+                (Ok(publisher), Some("quota")) => api_update_pbl_quota(req, publisher).await,
+                (Ok(publisher), Some("validation")) => api_update_pbl_validation(req, publisher).await,
+                (Ok(publisher), Some("id_cert")) => match path.next() {
+                    None => api_stage_pbl_id_cert(req, publisher).await,
+                    Some("activate") => api_activate_pbl_id_cert(req, publisher).await,
+                    _ => render_unknown_method(),
+                },
+                _ => render_unknown_method(),
+            },
         },
         Method::DELETE => match path.path_arg() {
-            Some(publisher) => api_remove_pbl(req, publisher).await,
+            Some(publisher) => match path.next() {
+                None => api_remove_pbl(req, publisher).await,
+                Some("quota") => api_reset_pbl_quota(req, publisher).await,
+                Some("validation") => api_reset_pbl_validation(req, publisher).await,
+                Some("id_cert") => api_cancel_pbl_id_cert(req, publisher).await,
+                _ => render_unknown_method(),
+            },
             None => render_error(Error::ApiInvalidHandle),
         },
         _ => render_unknown_method(),
     }
 }
 
+//------------ Admin: API Tokens ---------------------------------------------
+
+/// Manages the named API tokens. Only actors with the CA_ADMIN permission,
+/// i.e. admins, may do this. Otherwise the holder of a limited token could
+/// simply create themselves a less limited one.
+#[cfg(feature = "multi-user")]
+async fn api_tokens(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match *req.method() {
+        Method::GET => match path.next() {
+            None => render_json(req.state().api_tokens_list()),
+            _ => render_unknown_method(),
+        },
+        Method::POST => match path.next() {
+            None => {
+                let actor = req.actor();
+                let server = req.state().clone();
+                match req.json().await {
+                    Ok(request) => render_json_res(server.api_token_create(request, &actor)),
+                    Err(e) => render_error(e),
+                }
+            }
+            _ => render_unknown_method(),
+        },
+        Method::DELETE => match path.next() {
+            Some(name) => render_empty_res(req.state().api_token_revoke(name)),
+            None => render_unknown_method(),
+        },
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_audit(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match *req.method() {
+        Method::GET => match path.next() {
+            Some("export") => {
+                // /api/v1/audit/export/<after>/<before>
+                let after = path.path_arg().map(Timestamp::new);
+                let before = path.path_arg().map(Timestamp::new);
+
+                match req.state().audit_export(after, before) {
+                    Ok(export) => Ok(HttpResponse::text_no_cache(export.into_bytes())),
+                    Err(e) => render_error(e),
+                }
+            }
+            rows => {
+                // /api/v1/audit/<rows>/<offset>/<after>/<before>
+                let rows = rows.and_then(|rows| usize::from_str(rows).ok()).unwrap_or(100);
+                let offset = path.path_arg().unwrap_or(0);
+                let after = path.path_arg().map(Timestamp::new);
+                let before = path.path_arg().map(Timestamp::new);
+
+                render_json_res(req.state().audit_events(rows, offset, after, before))
+            }
+        },
+        _ => render_unknown_method(),
+    }
+}
+
+//------------ Admin: Backups ------------------------------------------------
+
+async fn api_backups(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match path.next() {
+        // /api/v1/backups
+        None => match *req.method() {
+            Method::GET => render_json_res(req.state().backups()),
+            Method::POST => render_json_res(req.state().create_backup()),
+            _ => render_unknown_method(),
+        },
+        // /api/v1/backups/<name>/restore
+        Some(name) => {
+            let name = name.to_string();
+            match (req.method().clone(), path.next()) {
+                (Method::POST, Some("restore")) => {
+                    let force = req.query_param("force").as_deref() == Some("true");
+                    render_json_res(req.state().restore_backup(&name, force))
+                }
+                _ => render_unknown_method(),
+            }
+        }
+    }
+}
+
+//------------ Admin: Configuration -----------------------------------------
+
+async fn api_admin(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match (req.method().clone(), path.next()) {
+        // POST /api/v1/admin/reload
+        (Method::POST, Some("reload")) => render_json_res(req.state().reload_config().await),
+        // GET /api/v1/admin/doctor
+        (Method::GET, Some("doctor")) => {
+            let actor = req.actor();
+            render_json_res(req.state().doctor(&actor).await)
+        }
+        // GET /api/v1/admin/schema
+        (Method::GET, Some("schema")) => render_json_res(req.state().schema_versions()),
+        (method, Some("keys")) => match (method, path.next()) {
+            // GET /api/v1/admin/keys
+            (Method::GET, None) => render_json_res(req.state().key_inventory().await),
+            // POST /api/v1/admin/keys/gc[?confirm=true]
+            (Method::POST, Some("gc")) => {
+                let confirm = req.query_param("confirm").as_deref() == Some("true");
+                render_json_res(req.state().keys_gc(confirm).await)
+            }
+            _ => render_unknown_method(),
+        },
+        _ => render_unknown_method(),
+    }
+}
+
+//------------ Admin: Scheduled Tasks ---------------------------------------
+
+async fn api_tasks(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match path.next() {
+        // GET /api/v1/tasks
+        None => match *req.method() {
+            Method::GET => render_json(req.state().tasks()),
+            _ => render_unknown_method(),
+        },
+        // POST /api/v1/tasks/<name>/<trigger|pause|resume>
+        Some(name) => {
+            let name = name.to_string();
+            match (req.method().clone(), path.next()) {
+                (Method::POST, Some("trigger")) => render_empty_res(req.state().task_trigger(&name)),
+                (Method::POST, Some("pause")) => render_empty_res(req.state().task_pause(&name)),
+                (Method::POST, Some("resume")) => render_empty_res(req.state().task_resume(&name)),
+                _ => render_unknown_method(),
+            }
+        }
+    }
+}
+
+//------------ Event Stream --------------------------------------------------
+
+// The interval for comments sent to keep idle event streams open, and to
+// notice clients which went away.
+const EVENTS_KEEP_ALIVE_SECONDS: u64 = 15;
+
+/// Streams CA and publication server events as Server-Sent Events. The
+/// stream only includes events for CAs which the actor may read, and
+/// publication server events if the actor may administer it. The optional
+/// 'ca' query parameter limits the stream to events for the given, comma
+/// separated, CAs.
+async fn api_events(req: Request) -> RoutingResult {
+    if *req.method() != Method::GET {
+        return render_unknown_method();
+    }
+
+    let cas = match req.query_param("ca") {
+        None => None,
+        Some(cas) => match cas.split(',').map(CaHandle::from_str).collect::<Result<Vec<_>, _>>() {
+            Ok(cas) => Some(cas),
+            Err(_) => return render_error(Error::ApiInvalidQueryParam("ca".to_string())),
+        },
+    };
+
+    let actor = req.actor();
+    let is_visible = move |event: &LiveEvent| event.is_visible(cas.as_deref(), &actor);
+
+    let mut events = req.state().events_subscribe();
+    let (mut sender, body) = hyper::Body::channel();
+
+    tokio::spawn(async move {
+        let mut keep_alive = tokio::time::interval(Duration::from_secs(EVENTS_KEEP_ALIVE_SECONDS));
+        loop {
+            let chunk = select! {
+                event = events.recv() => match event {
+                    Ok(event) if is_visible(&event) => event.to_sse(),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => LiveEvent::lagged_sse(missed),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = keep_alive.tick() => ": keep-alive\n\n".to_string(),
+            };
+
+            if sender.send_data(chunk.into()).await.is_err() {
+                break; // The client went away.
+            }
+        }
+    });
+
+    let mut response = HttpResponse::new(
+        hyper::Response::builder()
+            .status(hyper::StatusCode::OK)
+            .header("Content-Type", "text/event-stream")
+            .header("Cache-Control", "no-cache")
+            .body(body)
+            .unwrap(),
+    );
+    response.do_not_log();
+    Ok(response)
+}
+
+//------------ Admin: High Availability -------------------------------------
+
+async fn api_ha(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match (req.method().clone(), path.next()) {
+        // GET /api/v1/ha
+        (Method::GET, None) => render_json_res(req.state().ha_status()),
+        // POST /api/v1/ha/promote
+        (Method::POST, Some("promote")) => render_json_res(req.state().ha_promote().await),
+        _ => render_unknown_method(),
+    }
+}
+
+//------------ Admin: Time Check ---------------------------------------------
+
+async fn api_time(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match (req.method().clone(), path.next()) {
+        // GET /api/v1/time
+        (Method::GET, None) => match req.state().time_check_status() {
+            Ok(Some(status)) => render_json(status),
+            Ok(None) => render_unknown_resource(),
+            Err(e) => render_error(e),
+        },
+        // POST /api/v1/time/check
+        (Method::POST, Some("check")) => render_json_res(req.state().time_check().await),
+        _ => render_unknown_method(),
+    }
+}
+
 //------------ Admin: Publishers ---------------------------------------------
 
 /// Returns a list of publisher which have not updated for more
@@ -1331,11 +2800,15 @@ pub async fn api_stale_publishers(req: Request, seconds: Option<&str>) -> Routin
     aa!(req, Permission::PUB_LIST, {
         let seconds = seconds.unwrap_or("");
         match i64::from_str(seconds) {
-            Ok(seconds) => render_json_res(
-                req.state()
-                    .repo_stats()
-                    .map(|stats| PublisherList::build(&stats.stale_publishers(seconds))),
-            ),
+            Ok(seconds) => render_json_res(req.state().repo_stats().map(|stats| {
+                let actor = req.actor();
+                let stale: Vec<_> = stats
+                    .stale_publishers(seconds)
+                    .into_iter()
+                    .filter(|publisher| req.state().publisher_visible(publisher, &actor))
+                    .collect();
+                PublisherList::build(&stale)
+            })),
             Err(_) => render_error(Error::ApiInvalidSeconds),
         }
     })
@@ -1346,7 +2819,7 @@ pub async fn api_list_pbl(req: Request) -> RoutingResult {
     aa!(req, Permission::PUB_LIST, {
         render_json_res(
             req.state()
-                .publishers()
+                .publishers(&req.actor())
                 .map(|publishers| PublisherList::build(&publishers)),
         )
     })
@@ -1357,8 +2830,17 @@ pub async fn api_add_pbl(req: Request) -> RoutingResult {
     aa!(req, Permission::PUB_CREATE, {
         let actor = req.actor();
         let server = req.state().clone();
-        match req.json().await {
-            Ok(pbl) => render_json_res(server.add_publisher(pbl, &actor)),
+        match req.json::<idexchange::PublisherRequest>().await {
+            Ok(pbl) => {
+                // The handle is only known now, so check that it may be
+                // used, e.g. because it is owned by the user's organization.
+                let publisher = PublisherResource::from(pbl.publisher_handle());
+                match actor.is_allowed(Permission::PUB_CREATE, publisher.clone()) {
+                    Ok(true) => render_json_res(server.add_publisher(pbl, &actor)),
+                    Ok(false) => render_error(insufficient_rights(&actor, Permission::PUB_CREATE, &publisher)),
+                    Err(e) => render_error(e),
+                }
+            }
             Err(e) => render_error(e),
         }
     })
@@ -1368,7 +2850,7 @@ pub async fn api_add_pbl(req: Request) -> RoutingResult {
 /// that's just fine.
 #[allow(clippy::redundant_clone)] // false positive
 pub async fn api_remove_pbl(req: Request, publisher: PublisherHandle) -> RoutingResult {
-    aa!(req, Permission::PUB_DELETE, {
+    aa!(req, Permission::PUB_DELETE, PublisherResource::from(&publisher), {
         let actor = req.actor();
         render_empty_res(req.state().remove_publisher(publisher, &actor))
     })
@@ -1380,15 +2862,144 @@ pub async fn api_show_pbl(req: Request, publisher: PublisherHandle) -> RoutingRe
     aa!(
         req,
         Permission::PUB_READ,
+        PublisherResource::from(&publisher),
         render_json_res(req.state().get_publisher(&publisher))
     )
 }
 
+/// Returns the content stats and publication activity for a publisher
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_show_pbl_stats(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::PUB_READ,
+        PublisherResource::from(&publisher),
+        render_json_res(req.state().publisher_stats(&publisher))
+    )
+}
+
+/// Returns the content stats and publication activity for all publishers
+pub async fn api_publishers_stats(req: Request) -> RoutingResult {
+    aa!(
+        req,
+        Permission::PUB_LIST,
+        render_json_res(req.state().publishers_stats().map(|stats| {
+            let actor = req.actor();
+            PublisherStatsList::new(
+                stats
+                    .publishers()
+                    .iter()
+                    .filter(|info| req.state().publisher_visible(info.publisher(), &actor))
+                    .cloned()
+                    .collect(),
+            )
+        }))
+    )
+}
+
+/// Returns the quota for a publisher and its current usage
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_show_pbl_quota(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::PUB_READ,
+        PublisherResource::from(&publisher),
+        render_json_res(req.state().publisher_quota(&publisher))
+    )
+}
+
+/// Sets a custom quota for a publisher
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_update_pbl_quota(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(req, Permission::PUB_ADMIN, PublisherResource::from(&publisher), {
+        let actor = req.actor();
+        let server = req.state().clone();
+        match req.json().await {
+            Ok(quota) => render_empty_res(server.update_publisher_quota(publisher, Some(quota), &actor)),
+            Err(e) => render_error(e),
+        }
+    })
+}
+
+/// Removes the custom quota for a publisher, so that the configured default applies
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_reset_pbl_quota(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(req, Permission::PUB_ADMIN, PublisherResource::from(&publisher), {
+        let actor = req.actor();
+        render_empty_res(req.state().update_publisher_quota(publisher, None, &actor))
+    })
+}
+
+/// Returns the validation of published content for a publisher
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_show_pbl_validation(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::PUB_READ,
+        PublisherResource::from(&publisher),
+        render_json_res(req.state().publisher_validation(&publisher))
+    )
+}
+
+/// Sets the validation of published content for a publisher
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_update_pbl_validation(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(req, Permission::PUB_ADMIN, PublisherResource::from(&publisher), {
+        let actor = req.actor();
+        let server = req.state().clone();
+        match req.json().await {
+            Ok(validation) => render_empty_res(server.update_publisher_validation(publisher, Some(validation), &actor)),
+            Err(e) => render_error(e),
+        }
+    })
+}
+
+/// Removes the custom validation for a publisher, so that the configured default applies
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_reset_pbl_validation(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(req, Permission::PUB_ADMIN, PublisherResource::from(&publisher), {
+        let actor = req.actor();
+        render_empty_res(req.state().update_publisher_validation(publisher, None, &actor))
+    })
+}
+
+/// Stages a new ID certificate for a publisher, taken from the RFC 8183 Publisher Request
+/// in the body. Both the current and new certificate are accepted until it is activated.
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_stage_pbl_id_cert(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(req, Permission::PUB_ADMIN, PublisherResource::from(&publisher), {
+        let actor = req.actor();
+        let server = req.state().clone();
+        match req.json().await {
+            Ok(pbl_req) => render_empty_res(server.stage_publisher_id_cert(publisher, Some(pbl_req), &actor)),
+            Err(e) => render_error(e),
+        }
+    })
+}
+
+/// Replaces the current ID certificate of a publisher with the staged one
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_activate_pbl_id_cert(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(req, Permission::PUB_ADMIN, PublisherResource::from(&publisher), {
+        let actor = req.actor();
+        render_empty_res(req.state().activate_publisher_id_cert(publisher, &actor))
+    })
+}
+
+/// Removes the staged ID certificate for a publisher, cancelling the rollover
+#[allow(clippy::redundant_clone)] // false positive
+pub async fn api_cancel_pbl_id_cert(req: Request, publisher: PublisherHandle) -> RoutingResult {
+    aa!(req, Permission::PUB_ADMIN, PublisherResource::from(&publisher), {
+        let actor = req.actor();
+        render_empty_res(req.state().stage_publisher_id_cert(publisher, None, &actor))
+    })
+}
+
 //------------ repository_response ---------------------------------------------
 
 #[allow(clippy::redundant_clone)] // false positive
 pub async fn api_repository_response_xml(req: Request, publisher: PublisherHandle) -> RoutingResult {
-    aa!(req, Permission::PUB_READ, {
+    aa!(req, Permission::PUB_READ, PublisherResource::from(&publisher), {
         match repository_response(&req, &publisher).await {
             Ok(repository_response) => Ok(HttpResponse::xml(repository_response.to_xml_vec())),
             Err(e) => render_error(e),
@@ -1398,7 +3009,7 @@ pub async fn api_repository_response_xml(req: Request, publisher: PublisherHandl
 
 #[allow(clippy::redundant_clone)] // false positive
 pub async fn api_repository_response_json(req: Request, publisher: PublisherHandle) -> RoutingResult {
-    aa!(req, Permission::PUB_READ, {
+    aa!(req, Permission::PUB_READ, PublisherResource::from(&publisher), {
         match repository_response(&req, &publisher).await {
             Ok(res) => render_json(res),
             Err(e) => render_error(e),
@@ -1424,6 +3035,20 @@ pub async fn api_ca_add_child(req: Request, ca: CaHandle) -> RoutingResult {
     })
 }
 
+/// Adds a child to the testbed CA, if it claims resources within the limits
+/// configured for the testbed.
+pub async fn api_testbed_add_child(req: Request) -> RoutingResult {
+    let ca = testbed_ca_handle();
+    aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let server = req.state().clone();
+        match req.json().await {
+            Ok(child_req) => render_json_res(server.testbed_add_child(child_req, &actor).await),
+            Err(e) => render_error(e),
+        }
+    })
+}
+
 async fn api_ca_child_update(req: Request, ca: CaHandle, child: ChildHandle) -> RoutingResult {
     aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
         let actor = req.actor();
@@ -1451,6 +3076,186 @@ async fn api_ca_child_show(req: Request, ca: CaHandle, child: ChildHandle) -> Ro
     )
 }
 
+async fn api_ca_child_requests(
+    req: Request,
+    path: &mut RequestPath,
+    ca: CaHandle,
+    child: ChildHandle,
+) -> RoutingResult {
+    match path.path_arg::<KeyIdentifier>() {
+        None => match *req.method() {
+            Method::GET => aa!(
+                req,
+                Permission::CA_READ,
+                Handle::from(&ca),
+                render_json_res(req.state().ca_child_requests(&ca, &child).await)
+            ),
+            _ => render_unknown_method(),
+        },
+        Some(ki) => match (req.method().clone(), path.next()) {
+            (Method::POST, Some("approve")) => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+                let kind = match req
+                    .query_param("kind")
+                    .map(|kind| PendingChildRequestKind::from_str(&kind))
+                {
+                    None => PendingChildRequestKind::Issue,
+                    Some(Ok(kind)) => kind,
+                    Some(Err(_)) => return render_error(Error::ApiInvalidQueryParam("kind".to_string())),
+                };
+                let actor = req.actor();
+                render_empty_res(req.state().ca_child_request_approve(&ca, child, kind, ki, &actor).await)
+            }),
+            _ => render_unknown_method(),
+        },
+    }
+}
+
+async fn api_ca_child_policy(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => aa!(
+                req,
+                Permission::CA_READ,
+                Handle::from(&ca),
+                render_json_res(req.state().ca_child_issuance_policy(&ca).await)
+            ),
+            Method::POST => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+                let actor = req.actor();
+                let state = req.state().clone();
+
+                match req.json().await {
+                    Err(e) => render_error(e),
+                    Ok(policy) => render_empty_res(state.ca_child_issuance_policy_update(&ca, policy, &actor).await),
+                }
+            }),
+            _ => render_unknown_method(),
+        },
+        _ => render_unknown_method(),
+    }
+}
+
+/// Handles /api/v1/cas/{ca}/transfers:
+///
+///   GET  /                  list the resource transfers between children
+///   POST /                  start a ResourceTransferRequest
+///   GET  /{id}              show a resource transfer
+async fn api_ca_resource_transfers(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => aa!(
+                req,
+                Permission::CA_READ,
+                Handle::from(&ca),
+                render_json_res(req.state().ca_resource_transfers(&ca).await)
+            ),
+            Method::POST => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+                let actor = req.actor();
+                let state = req.state().clone();
+
+                match req.json().await {
+                    Err(e) => render_error(e),
+                    Ok(request) => render_empty_res(state.ca_resource_transfer_start(&ca, request, &actor).await),
+                }
+            }),
+            _ => render_unknown_method(),
+        },
+        Some(id) => match (u64::from_str(id), req.method().clone(), path.next()) {
+            (Ok(id), Method::GET, None) => aa!(
+                req,
+                Permission::CA_READ,
+                Handle::from(&ca),
+                render_json_res(req.state().ca_resource_transfer(&ca, id).await)
+            ),
+            _ => render_unknown_method(),
+        },
+    }
+}
+
+/// Handles /api/v1/cas/{ca}/scheduled:
+///
+///   GET    /                list the pending scheduled ROA and ASPA changes
+///   POST   /                schedule a change using a ScheduledChangeRequest
+///   DELETE /{id}            cancel a pending scheduled change
+async fn api_ca_scheduled_changes(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => aa!(
+                req,
+                Permission::CA_READ,
+                Handle::from(&ca),
+                render_json_res(req.state().ca_scheduled_changes(&ca).await)
+            ),
+            Method::POST => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+                let actor = req.actor();
+                let state = req.state().clone();
+
+                match req.json().await {
+                    Err(e) => render_error(e),
+                    Ok(request) => render_empty_res(state.ca_scheduled_change_add(&ca, request, &actor).await),
+                }
+            }),
+            _ => render_unknown_method(),
+        },
+        Some(id) => match (u64::from_str(id), req.method().clone(), path.next()) {
+            (Ok(id), Method::DELETE, None) => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+                let actor = req.actor();
+                render_empty_res(req.state().ca_scheduled_change_cancel(&ca, id, &actor).await)
+            }),
+            _ => render_unknown_method(),
+        },
+    }
+}
+
+/// Manages the API tokens which delegate the management of the ROAs of a CA,
+/// e.g. to the holder of its resources. Like other API tokens these may only
+/// be managed by admins, but their access can be limited to the CAs of their
+/// organization.
+#[cfg(feature = "multi-user")]
+async fn api_ca_delegations(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match (req.method().clone(), path.next()) {
+        (Method::GET, None) => aa!(
+            req,
+            Permission::CA_ADMIN,
+            Handle::from(&ca),
+            render_json_res(req.state().ca_delegations(&ca).await)
+        ),
+        (Method::POST, None) => aa!(req, Permission::CA_ADMIN, Handle::from(&ca), {
+            let actor = req.actor();
+            let state = req.state().clone();
+
+            match req.json().await {
+                Err(e) => render_error(e),
+                Ok(request) => render_json_res(state.ca_delegation_create(&ca, request, &actor).await),
+            }
+        }),
+        (Method::DELETE, Some(name)) => aa!(
+            req,
+            Permission::CA_ADMIN,
+            Handle::from(&ca),
+            render_empty_res(req.state().ca_delegation_revoke(&ca, name))
+        ),
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ca_stats_children(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        Handle::from(&ca),
+        render_json_res(req.state().ca_stats_children(&ca).await)
+    )
+}
+
+async fn api_ca_stats_parents(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        Handle::from(&ca),
+        render_json_res(req.state().ca_stats_parents(&ca).await)
+    )
+}
+
 async fn api_ca_stats_child_connections(req: Request, ca: CaHandle) -> RoutingResult {
     aa!(
         req,
@@ -1484,8 +3289,28 @@ async fn api_cas_import(req: Request) -> RoutingResult {
     match *req.method() {
         Method::POST => aa!(req, Permission::CA_ADMIN, {
             let server = req.state().clone();
+            let force = req.query_param("force").as_deref() == Some("true");
+            match req.json().await {
+                Ok(structure) => render_empty_res(server.cas_import(structure, force).await),
+                Err(e) => render_error(e),
+            }
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
+/// Migrates CAs, or starts a job to do so with ?background=true.
+async fn api_cas_migrate(req: Request) -> RoutingResult {
+    match *req.method() {
+        Method::POST => aa!(req, Permission::CA_ADMIN, {
+            let actor = req.actor();
+            let server = req.state().clone();
+            let background = req.query_param("background").as_deref() == Some("true");
             match req.json().await {
-                Ok(structure) => render_empty_res(server.cas_import(structure).await),
+                Ok(migration) if background => {
+                    render_json_res(jobs::start_ca_migration(&server, migration, &actor).await)
+                }
+                Ok(migration) => render_json_res(server.cas_migrate(migration, &actor).await),
                 Err(e) => render_error(e),
             }
         }),
@@ -1493,6 +3318,43 @@ async fn api_cas_import(req: Request) -> RoutingResult {
     }
 }
 
+/// Starts a job for an operation on the selected CAs.
+async fn api_bulk_job_start(req: Request) -> RoutingResult {
+    match *req.method() {
+        Method::POST => aa!(req, Permission::CA_ADMIN, {
+            let actor = req.actor();
+            let server = req.state().clone();
+            match req.json().await {
+                Ok(request) => render_json_res(jobs::start_bulk(&server, request, &actor)),
+                Err(e) => render_error(e),
+            }
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
+/// Shows the progress and results of jobs. Users only see the jobs for CAs
+/// they may read.
+async fn api_jobs(req: Request, path: &mut RequestPath) -> RoutingResult {
+    if *req.method() != Method::GET {
+        return render_unknown_method();
+    }
+
+    let actor = req.actor();
+    match path.next() {
+        // GET /api/v1/jobs
+        None => render_json(req.state().jobs(&actor)),
+        // GET /api/v1/jobs/<id>
+        Some(id) => {
+            let id = id.to_string();
+            match path.next() {
+                None => render_json_res(req.state().job(&id, &actor)),
+                Some(_) => render_unknown_method(),
+            }
+        }
+    }
+}
+
 async fn api_all_ca_issues(req: Request) -> RoutingResult {
     match *req.method() {
         Method::GET => aa!(req, Permission::CA_READ, {
@@ -1516,19 +3378,104 @@ async fn api_ca_issues(req: Request, ca: CaHandle) -> RoutingResult {
     }
 }
 
+async fn api_ca_reconcile(req: Request, ca: CaHandle) -> RoutingResult {
+    // GET  /api/v1/cas/{ca}/reconcile                 -> last reconciliation
+    // POST /api/v1/cas/{ca}/reconcile[?repair=true]   -> reconcile now
+    match *req.method() {
+        Method::GET => aa!(req, Permission::CA_READ, Handle::from(&ca), {
+            match req.state().ca_reconciliation(&ca).await {
+                Ok(Some(reconciliation)) => render_json(reconciliation),
+                Ok(None) => render_unknown_resource(),
+                Err(e) => render_error(e),
+            }
+        }),
+        // Reconciling updates the status of the CA, even without a repair.
+        Method::POST => aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+            let repair = req.query_param("repair").as_deref() == Some("true");
+            let actor = req.actor();
+            render_json_res(req.state().ca_reconcile(&ca, repair, &actor).await)
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ca_expiry(req: Request, ca: CaHandle) -> RoutingResult {
+    match *req.method() {
+        Method::GET => aa!(req, Permission::CA_READ, Handle::from(&ca), {
+            let window_hours = match req.query_param("window_hours") {
+                None => None,
+                Some(hours) => match u32::from_str(&hours) {
+                    Ok(hours) => Some(hours),
+                    Err(_) => return render_error(Error::ApiInvalidQueryParam("window_hours".to_string())),
+                },
+            };
+            render_json_res(req.state().ca_objects_expiry(&ca, window_hours))
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
+/// Exports the configuration of a CA. Including the private key of its
+/// identity certificate requires admin rights for the CA.
+async fn api_ca_export(req: Request, ca: CaHandle) -> RoutingResult {
+    match *req.method() {
+        Method::GET => {
+            let full = req.query_param("full").as_deref() == Some("true");
+            let include_key = req.query_param("keys").as_deref() == Some("true");
+            if include_key {
+                aa!(
+                    req,
+                    Permission::CA_ADMIN,
+                    Handle::from(&ca),
+                    render_json_res(req.state().ca_export(&ca, full, true).await)
+                )
+            } else {
+                aa!(
+                    req,
+                    Permission::CA_READ,
+                    Handle::from(&ca),
+                    render_json_res(req.state().ca_export(&ca, full, false).await)
+                )
+            }
+        }
+        _ => render_unknown_method(),
+    }
+}
+
 async fn api_cas_list(req: Request) -> RoutingResult {
     aa!(req, Permission::CA_LIST, {
         let actor = req.actor();
-        render_json_res(req.state().ca_list(&actor))
+        match label_selector(&req) {
+            Ok(None) => render_json_res(req.state().ca_list(&actor)),
+            Ok(Some(selector)) => render_json_res(req.state().ca_list(&actor).map(|list| list.select(&selector))),
+            Err(e) => render_error(e),
+        }
     })
 }
 
+/// Returns the CA label selector from the 'label' query parameter, if any,
+/// e.g. '?label=customer=acme,env=prod'.
+fn label_selector(req: &Request) -> Result<Option<CaLabelSelector>, Error> {
+    req.query_param("label")
+        .map(|selector| CaLabelSelector::from_str(&selector).map_err(Error::ApiInvalidCaSelector))
+        .transpose()
+}
+
 pub async fn api_ca_init(req: Request) -> RoutingResult {
     aa!(req, Permission::CA_CREATE, {
+        let actor = req.actor();
         let state = req.state().clone();
 
-        match req.json().await {
-            Ok(ca_init) => render_empty_res(state.ca_init(ca_init)),
+        match req.json::<CertAuthInit>().await {
+            Ok(ca_init) => {
+                // As for publishers, the handle is only known now.
+                let ca = Handle::from(ca_init.handle());
+                match actor.is_allowed(Permission::CA_CREATE, ca.clone()) {
+                    Ok(true) => render_empty_res(state.ca_init(ca_init)),
+                    Ok(false) => render_error(insufficient_rights(&actor, Permission::CA_CREATE, &ca)),
+                    Err(e) => render_error(e),
+                }
+            }
             Err(e) => render_error(e),
         }
     })
@@ -1560,13 +3507,17 @@ async fn api_ca_info(req: Request, handle: CaHandle) -> RoutingResult {
     )
 }
 
+/// Delete a CA, after revoking its children, requesting revocation from its
+/// parents and withdrawing its objects. Use `?force=true` to delete the CA even
+/// if its parents or repository cannot be reached.
 async fn api_ca_delete(req: Request, handle: CaHandle) -> RoutingResult {
     let actor = req.actor();
+    let force = req.query_param("force").as_deref() == Some("true");
     aa!(
         req,
         Permission::CA_DELETE,
         Handle::from(&handle),
-        render_json_res(req.state().ca_delete(&handle, &actor).await)
+        render_json_res(req.state().ca_delete(&handle, force, &actor).await)
     )
 }
 
@@ -1579,6 +3530,15 @@ async fn api_ca_my_parent_contact(req: Request, ca: CaHandle, parent: ParentHand
     )
 }
 
+async fn api_ca_parent_exchanges(req: Request, ca: CaHandle, parent: ParentHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        Handle::from(&ca),
+        render_json_res(req.state().ca_parent_exchanges(&ca, &parent).await)
+    )
+}
+
 async fn api_ca_my_parent_statuses(req: Request, ca: CaHandle) -> RoutingResult {
     aa!(
         req,
@@ -1595,6 +3555,10 @@ async fn api_ca_aspas(req: Request, path: &mut RequestPath, ca: CaHandle) -> Rou
             Method::POST => api_ca_aspas_definitions_update(req, ca).await,
             _ => render_unknown_method(),
         },
+        Some("import") => match *req.method() {
+            Method::POST => api_ca_aspas_definitions_import(req, ca).await,
+            _ => render_unknown_method(),
+        },
         // We may need other functions in future, such as 'analyze' or 'try'.
         // So keep the base namespace clean and use '/api/v1/aspas/as/<asn>/..'
         // for functions on specific ASPA definitions for the given (customer)
@@ -1659,6 +3623,7 @@ async fn api_ca_children(req: Request, path: &mut RequestPath, ca: CaHandle) ->
             },
             Some("contact") | Some("parent_response.json") => api_ca_parent_res_json(req, ca, child).await,
             Some("parent_response.xml") => api_ca_parent_res_xml(req, ca, child).await,
+            Some("requests") => api_ca_child_requests(req, path, ca, child).await,
             _ => render_unknown_method(),
         },
         None => match *req.method() {
@@ -1671,7 +3636,8 @@ async fn api_ca_children(req: Request, path: &mut RequestPath, ca: CaHandle) ->
 async fn api_ca_history_commands(req: Request, path: &mut RequestPath, handle: CaHandle) -> RoutingResult {
     match *req.method() {
         Method::GET => aa!(req, Permission::CA_READ, Handle::from(&handle), {
-            // /api/v1/cas/{ca}/history/commands  /<rows>/<offset>/<after>/<before>
+            // /api/v1/cas/{ca}/history/commands  /<rows>/<offset>/<after>/<before>[?actor=<actor>][&archived=true]
+            //     [&type=<label>[,<label>]][&order=<asc|desc>][&cursor=<sequence>]
             let mut crit = CommandHistoryCriteria::default();
 
             if let Some(rows) = path.path_arg() {
@@ -1689,6 +3655,34 @@ async fn api_ca_history_commands(req: Request, path: &mut RequestPath, handle: C
             if let Some(before) = path.path_arg() {
                 crit.set_before(before);
             }
+
+            if let Some(actor) = req.query_param("actor") {
+                crit.set_actor(actor);
+            }
+
+            if req.query_param("archived").as_deref() == Some("true") {
+                crit.set_include_archived();
+            }
+
+            if let Some(types) = req.query_param("type") {
+                let types: Vec<&str> = types.split(',').collect();
+                crit.set_includes(&types);
+            }
+
+            if let Some(order) = req.query_param("order") {
+                match HistoryOrder::from_str(&order) {
+                    Ok(order) => crit.set_order(order),
+                    Err(_) => return render_error(Error::ApiInvalidQueryParam("order".to_string())),
+                }
+            }
+
+            if let Some(cursor) = req.query_param("cursor") {
+                match u64::from_str(&cursor) {
+                    Ok(cursor) => crit.set_cursor(cursor),
+                    Err(_) => return render_error(Error::ApiInvalidQueryParam("cursor".to_string())),
+                }
+            }
+
             match req.state().ca_history(&handle, crit).await {
                 Ok(history) => render_json(history),
                 Err(e) => render_error(e),
@@ -1702,10 +3696,45 @@ async fn api_ca_history(req: Request, path: &mut RequestPath, ca: CaHandle) -> R
     match path.next() {
         Some("details") => api_ca_command_details(req, path, ca).await,
         Some("commands") => api_ca_history_commands(req, path, ca).await,
+        Some("revert") => api_ca_command_revert(req, path, ca).await,
         _ => render_unknown_method(),
     }
 }
 
+/// Revert the ROA or ASPA changes of a previous command, by applying the
+/// inverse changes as a new command. Reverting ROA changes needs permission
+/// to update routes, and is subject to the same BGP impact check, unless
+/// `?force=true` is used.
+async fn api_ca_command_revert(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    // POST /api/v1/cas/{ca}/history/revert/<command-key>[?force=true]
+    match path.path_arg() {
+        Some(key) => match *req.method() {
+            Method::POST => aa!(req, Permission::CA_READ, Handle::from(&ca), {
+                let revert = match req.state().ca_command_revert_plan(&ca, key.clone()).await {
+                    Ok(revert) => revert,
+                    Err(Error::AggregateStoreError(AggregateStoreError::UnknownCommand(_, _))) => {
+                        return render_unknown_resource()
+                    }
+                    Err(e) => return render_error(e),
+                };
+
+                let force = req.query_param("force").as_deref() == Some("true");
+
+                match revert {
+                    CommandRevert::Roas(_) => aa!(req, Permission::ROUTES_UPDATE, Handle::from(&ca), {
+                        ca_command_revert(&req, ca, key, revert, force).await
+                    }),
+                    CommandRevert::Aspas(_) => aa!(req, Permission::ASPAS_UPDATE, Handle::from(&ca), {
+                        ca_command_revert(&req, ca, key, revert, force).await
+                    }),
+                }
+            }),
+            _ => render_unknown_method(),
+        },
+        None => render_unknown_resource(),
+    }
+}
+
 #[allow(clippy::redundant_clone)] // false positive
 async fn api_ca_command_details(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
     // /api/v1/cas/{ca}/command/<command-key>
@@ -1728,6 +3757,24 @@ async fn api_ca_command_details(req: Request, path: &mut RequestPath, ca: CaHand
     }
 }
 
+async fn ca_command_revert(
+    req: &Request,
+    ca: CaHandle,
+    key: CommandKey,
+    revert: CommandRevert,
+    force: bool,
+) -> RoutingResult {
+    let actor = req.actor();
+    match req
+        .state()
+        .ca_command_revert(ca, &key, revert.clone(), force, &actor)
+        .await
+    {
+        Ok(()) => render_json(revert),
+        Err(e) => render_error(e),
+    }
+}
+
 async fn api_ca_child_req_xml(req: Request, ca: CaHandle) -> RoutingResult {
     match *req.method() {
         Method::GET => aa!(
@@ -1810,6 +3857,30 @@ async fn api_ca_repo_status(req: Request, ca: CaHandle) -> RoutingResult {
     }
 }
 
+async fn api_ca_repo_statuses(req: Request, ca: CaHandle) -> RoutingResult {
+    match *req.method() {
+        Method::GET => aa!(
+            req,
+            Permission::CA_READ,
+            Handle::from(&ca),
+            render_json_res(req.state().ca_repo_statuses(&ca).await)
+        ),
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ca_repo_check(req: Request, ca: CaHandle) -> RoutingResult {
+    match *req.method() {
+        Method::POST => aa!(
+            req,
+            Permission::CA_UPDATE,
+            Handle::from(&ca),
+            render_json_res(req.state().ca_publication_check(&ca).await)
+        ),
+        _ => render_unknown_method(),
+    }
+}
+
 fn extract_repository_contact(ca: &CaHandle, bytes: Bytes) -> Result<RepositoryContact, Error> {
     let string = String::from_utf8(bytes.to_vec()).map_err(Error::custom)?;
 
@@ -1849,6 +3920,100 @@ async fn api_ca_repo_update(req: Request, ca: CaHandle) -> RoutingResult {
     })
 }
 
+/// Migrate to the repository in the POSTed RFC 8183 Repository Response,
+/// or resume the migration.
+async fn api_ca_repo_migrate(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let server = req.state().clone();
+
+        let background = req.query_param("background").as_deref() == Some("true");
+
+        match req
+            .api_bytes()
+            .await
+            .map(|bytes| extract_repository_contact(&ca, bytes))
+        {
+            Ok(Ok(contact)) if background => render_json_res(jobs::start_repo_migration(&server, ca, contact, &actor)),
+            Ok(Ok(contact)) => render_json_res(server.ca_repo_migrate(&ca, contact, &actor).await),
+            Ok(Err(e)) | Err(e) => render_error(e),
+        }
+    })
+}
+
+async fn api_ca_class_repo_update(req: Request, ca: CaHandle, rcn: ResourceClassName) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let server = req.state().clone();
+
+        match req
+            .api_bytes()
+            .await
+            .map(|bytes| extract_repository_contact(&ca, bytes))
+        {
+            Ok(Ok(update)) => render_empty_res(server.ca_class_repo_update(ca, rcn, Some(update), &actor).await),
+            Ok(Err(e)) | Err(e) => render_error(e),
+        }
+    })
+}
+
+async fn api_ca_timing_show(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        Handle::from(&ca),
+        render_json_res(req.state().ca_issuance_timing(&ca).await)
+    )
+}
+
+/// Show the issuance timing in effect for the CA, with warnings about values
+/// which may be unwise.
+async fn api_ca_timing_effective(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        Handle::from(&ca),
+        render_json_res(req.state().ca_issuance_timing_report(&ca).await)
+    )
+}
+
+/// Replace the CA specific overrides of the issuance timing config. Values
+/// which are left out fall back to the global configuration.
+async fn api_ca_timing_update(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let state = req.state().clone();
+
+        match req.json().await {
+            Err(e) => render_error(e),
+            Ok(overrides) => render_empty_res(state.ca_issuance_timing_update(ca, overrides, &actor).await),
+        }
+    })
+}
+
+async fn api_ca_retry_show(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        Handle::from(&ca),
+        render_json_res(req.state().ca_retry_policies(&ca).await)
+    )
+}
+
+/// Replace the CA specific overrides of the retry policy for the repository,
+/// or a parent. Values which are left out fall back to the global configuration.
+async fn api_ca_retry_update(req: Request, ca: CaHandle, parent: Option<ParentHandle>) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let state = req.state().clone();
+
+        match req.json().await {
+            Err(e) => render_error(e),
+            Ok(policy) => render_empty_res(state.ca_retry_policy_update(ca, parent, policy, &actor).await),
+        }
+    })
+}
+
 async fn api_ca_parent_add_or_update(
     req: Request,
     ca: CaHandle,
@@ -1870,6 +4035,24 @@ async fn api_ca_parent_add_or_update(
     })
 }
 
+/// Add or update a parent, using a Parent Response XML which Krill fetches
+/// itself from the location in the posted JSON.
+async fn api_ca_parent_fetch(req: Request, ca: CaHandle, parent: ParentHandle) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let server = req.state().clone();
+
+        match req.json().await {
+            Ok(fetch) => render_empty_res(
+                server
+                    .ca_parent_fetch_and_add_or_update(ca, parent, fetch, &actor)
+                    .await,
+            ),
+            Err(e) => render_error(e),
+        }
+    })
+}
+
 fn extract_parent_ca_req(
     ca: &CaHandle,
     bytes: Bytes,
@@ -1931,6 +4114,22 @@ async fn api_ca_kr_activate(req: Request, ca: CaHandle) -> RoutingResult {
     })
 }
 
+/// Show which signer holds each key of the CA, compared to the signer used for new keys.
+async fn api_ca_signer_migration_status(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::CA_READ, Handle::from(&ca), {
+        render_json_res(req.state().ca_signer_migration_status(&ca).await)
+    })
+}
+
+/// Start key rolls for keys held by another signer than the one used for new keys, and
+/// activate new keys which have been staged for long enough.
+async fn api_ca_signer_migrate(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        render_json_res(req.state().ca_signer_migrate(ca, &actor).await)
+    })
+}
+
 // -- ASPA functions
 
 /// List the current ASPA definitions for a CA
@@ -1954,15 +4153,61 @@ async fn api_ca_aspas_definitions_update(req: Request, ca: CaHandle) -> RoutingR
     })
 }
 
-/// Update an existing ASPA definition for a CA based on the update in the POST
+/// Replace all ASPA definitions for a CA with the definitions in the POST. The
+/// body is expected to be a JSON AspaDefinitionList, or CSV if the content type
+/// is 'text/csv'. Returns the changes that were applied.
+async fn api_ca_aspas_definitions_import(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::ASPAS_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let state = req.state().clone();
+
+        let is_csv = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.starts_with("text/csv"))
+            .unwrap_or(false);
+
+        let definitions = if is_csv {
+            match req.api_bytes().await {
+                Err(e) => return render_error(e),
+                Ok(bytes) => match from_utf8(&bytes) {
+                    Err(_) => return render_error(Error::InvalidUtf8Input),
+                    Ok(csv) => match AspaDefinitionList::from_csv(csv) {
+                        Err(e) => return render_error(Error::custom(e)),
+                        Ok(definitions) => definitions,
+                    },
+                },
+            }
+        } else {
+            match req.json().await {
+                Err(e) => return render_error(e),
+                Ok(definitions) => definitions,
+            }
+        };
+
+        render_json_res(state.ca_aspas_definitions_import(ca, definitions, &actor).await)
+    })
+}
+
+/// Update an existing ASPA definition for a CA based on the update in the POST.
+/// With `?dryrun=true` the update is only evaluated, and the resulting ASPA
+/// object changes are returned.
 async fn api_ca_aspas_update_aspa(req: Request, ca: CaHandle, customer: Asn) -> RoutingResult {
     aa!(req, Permission::ASPAS_UPDATE, Handle::from(&ca), {
         let actor = req.actor();
         let state = req.state().clone();
+        let dry_run = req.query_param("dryrun").as_deref() == Some("true");
 
         match req.json().await {
             Err(e) => render_error(e),
-            Ok(update) => render_empty_res(state.ca_aspas_update_aspa(ca, customer, update, &actor).await),
+            Ok(update) => {
+                if dry_run {
+                    render_json_res(state.ca_aspas_update_aspa_dry_run(ca, customer, update).await)
+                } else {
+                    render_empty_res(state.ca_aspas_update_aspa(ca, customer, update, &actor).await)
+                }
+            }
         }
     })
 }
@@ -1978,19 +4223,145 @@ async fn api_ca_aspas_delete(req: Request, ca: CaHandle, customer: Asn) -> Routi
     })
 }
 
-/// Update the route authorizations for this CA
+/// Update the route authorizations for this CA. The update is rejected, and
+/// the impact is returned in the error, if it would invalidate announcements
+/// seen in BGP, unless `?force=true` is used.
 async fn api_ca_routes_update(req: Request, ca: CaHandle) -> RoutingResult {
     aa!(req, Permission::ROUTES_UPDATE, Handle::from(&ca), {
         let actor = req.actor();
         let state = req.state().clone();
+        let force = req.query_param("force").as_deref() == Some("true");
+
+        match req.json().await {
+            Err(e) => render_error(e),
+            Ok(updates) => render_empty_res(state.ca_routes_update(ca, updates, force, &actor).await),
+        }
+    })
+}
+
+/// Import RPSL route objects or CSV as ROA configurations. With `?dryrun=true`
+/// only the report of additions and conflicts is returned.
+async fn api_ca_routes_import(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::ROUTES_UPDATE, Handle::from(&ca), {
+        let actor = req.actor();
+        let state = req.state().clone();
+        let dry_run = req.query_param("dryrun").as_deref() == Some("true");
+        let force = req.query_param("force").as_deref() == Some("true");
+        let background = req.query_param("background").as_deref() == Some("true");
 
         match req.json().await {
             Err(e) => render_error(e),
-            Ok(updates) => render_empty_res(state.ca_routes_update(ca, updates, &actor).await),
+            Ok(import) if background && !dry_run => {
+                render_json_res(jobs::start_roa_import(&state, ca, import, force, &actor))
+            }
+            Ok(import) => render_json_res(state.ca_routes_import(&ca, import, dry_run, force, &actor).await),
         }
     })
 }
 
+/// Handles /api/v1/cas/{ca}/routes/strategy:
+///
+///   GET    /                show the ROA issuance strategy in use
+///   POST   /                set a CA specific RoaIssuanceStrategy, and re-issue ROAs
+///   DELETE /                use the strategy from the server config again
+async fn api_ca_routes_strategy(req: Request, ca: CaHandle) -> RoutingResult {
+    match *req.method() {
+        Method::GET => aa!(req, Permission::ROUTES_READ, Handle::from(&ca), {
+            render_json_res(req.state().ca_roa_issuance_strategy(&ca).await)
+        }),
+        Method::POST => aa!(req, Permission::ROUTES_UPDATE, Handle::from(&ca), {
+            let actor = req.actor();
+            let state = req.state().clone();
+
+            match req.json().await {
+                Err(e) => render_error(e),
+                Ok(strategy) => {
+                    render_empty_res(state.ca_roa_issuance_strategy_update(ca, Some(strategy), &actor).await)
+                }
+            }
+        }),
+        Method::DELETE => aa!(req, Permission::ROUTES_UPDATE, Handle::from(&ca), {
+            let actor = req.actor();
+            render_empty_res(req.state().ca_roa_issuance_strategy_update(ca, None, &actor).await)
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
+/// Handles /api/v1/cas/{ca}/routes/autopilot:
+///
+///   GET    /                show the ROA auto-pilot policy in use, if any
+///   POST   /                set a CA specific RoaAutoPilotPolicy
+///   DELETE /                use the policy from the server config again
+async fn api_ca_routes_autopilot(req: Request, ca: CaHandle) -> RoutingResult {
+    match *req.method() {
+        Method::GET => aa!(req, Permission::ROUTES_READ, Handle::from(&ca), {
+            render_json_res(req.state().ca_roa_autopilot(&ca).await)
+        }),
+        Method::POST => aa!(req, Permission::ROUTES_UPDATE, Handle::from(&ca), {
+            let actor = req.actor();
+            let state = req.state().clone();
+
+            match req.json().await {
+                Err(e) => render_error(e),
+                Ok(policy) => render_empty_res(state.ca_roa_autopilot_update(ca, Some(policy), &actor).await),
+            }
+        }),
+        Method::DELETE => aa!(req, Permission::ROUTES_UPDATE, Handle::from(&ca), {
+            let actor = req.actor();
+            render_empty_res(req.state().ca_roa_autopilot_update(ca, None, &actor).await)
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
+/// Handles /api/v1/cas/{ca}/routes/proposals:
+///
+///   GET  /                  list the pending proposals
+///   POST /                  propose a RoaConfigurationUpdates delta
+///   POST /{id}/approve      approve a proposal, and issue the ROAs (supports ?force=true)
+///   POST /{id}/reject       reject a proposal
+async fn api_ca_routes_proposals(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => aa!(req, Permission::ROUTES_READ, Handle::from(&ca), {
+                render_json_res(req.state().ca_routes_proposals(&ca).await)
+            }),
+            Method::POST => aa!(req, Permission::ROUTES_PROPOSE, Handle::from(&ca), {
+                let actor = req.actor();
+                let state = req.state().clone();
+
+                match req.json().await {
+                    Err(e) => render_error(e),
+                    Ok(updates) => render_empty_res(state.ca_routes_propose(ca, updates, &actor).await),
+                }
+            }),
+            _ => render_unknown_method(),
+        },
+        Some(id) => match u64::from_str(id) {
+            Err(_) => render_unknown_method(),
+            Ok(id) => match path.next() {
+                Some("approve") => match *req.method() {
+                    Method::POST => aa!(req, Permission::ROUTES_APPROVE, Handle::from(&ca), {
+                        let actor = req.actor();
+                        let force = req.query_param("force").as_deref() == Some("true");
+                        render_empty_res(req.state().ca_routes_proposal_approve(ca, id, force, &actor).await)
+                    }),
+                    _ => render_unknown_method(),
+                },
+                Some("reject") => match *req.method() {
+                    Method::POST => aa!(req, Permission::ROUTES_APPROVE, Handle::from(&ca), {
+                        let actor = req.actor();
+                        render_empty_res(req.state().ca_routes_proposal_reject(ca, id, &actor).await)
+                    }),
+                    _ => render_unknown_method(),
+                },
+                _ => render_unknown_method(),
+            },
+        },
+    }
+}
+
 /// Tries an update. If the dry-run for it would be successful, and the analysis
 /// for the resources in the update have no remaining invalids, apply it. Otherwise
 /// return the analysis and a suggestion.
@@ -2011,7 +4382,7 @@ async fn api_ca_routes_try_update(req: Request, ca: CaHandle) -> RoutingResult {
                     Ok(effect) => {
                         if !effect.contains_invalids() {
                             // no issues found, apply
-                            render_empty_res(server.ca_routes_update(ca, updates, &actor).await)
+                            render_empty_res(server.ca_routes_update(ca, updates, true, &actor).await)
                         } else {
                             // remaining invalids exist, advise user
                             let updates = updates.into_explicit_max_length();
@@ -2054,6 +4425,16 @@ async fn api_ca_routes_analysis(req: Request, path: &mut RequestPath, ca: CaHand
                 }
                 _ => render_unknown_method(),
             },
+            Some("impact") => match *req.method() {
+                Method::POST => {
+                    let state = req.state.clone();
+                    match req.json().await {
+                        Err(e) => render_error(e),
+                        Ok(updates) => render_json_res(state.ca_routes_bgp_impact(&ca, updates).await),
+                    }
+                }
+                _ => render_unknown_method(),
+            },
             Some("suggest") => match *req.method() {
                 Method::GET => render_json_res(req.state().ca_routes_bgp_suggest(&ca, None).await),
                 Method::POST => {
@@ -2111,30 +4492,48 @@ async fn api_suspend_all(req: Request) -> RoutingResult {
     }
 }
 
-//------------ Serve RRDP Files ----------------------------------------------
+/// Save snapshots for all CAs and the Publication Server
+async fn api_snapshots_save(req: Request) -> RoutingResult {
+    match *req.method() {
+        Method::POST => aa!(req, Permission::CA_ADMIN, {
+            render_json_res(req.state().save_snapshots())
+        }),
+        _ => render_unknown_method(),
+    }
+}
 
-async fn rrdp(req: Request) -> RoutingResult {
-    if !req.path().full().starts_with("/rrdp/") {
-        Err(req) // Not for us
-    } else {
-        let mut full_path: PathBuf = req.state.rrdp_base_path();
-        let (_, path) = req.path.remaining().split_at(1);
-        let cache_seconds = if path.ends_with("notification.xml") { 60 } else { 86400 };
-        full_path.push(path);
-
-        match File::open(&full_path) {
-            Ok(mut file) if full_path.is_file() => {
-                let mut buffer = Vec::new();
-                match file.read_to_end(&mut buffer) {
-                    Ok(_) => Ok(HttpResponse::xml_with_cache(buffer, cache_seconds)),
-                    Err(_) => Ok(HttpResponse::not_found()),
-                }
-            }
-            _ => Ok(HttpResponse::not_found()),
-        }
+/// Verify the snapshots for all CAs and the Publication Server against their events
+async fn api_snapshots_verify(req: Request) -> RoutingResult {
+    match *req.method() {
+        Method::GET => aa!(req, Permission::CA_ADMIN, {
+            render_json_res(req.state().verify_snapshots())
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
+//------------ Support RPKI Signed Checklists (RSC) ----------------------------
+
+async fn api_ca_rsc(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::POST => api_ca_rsc_sign(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        _ => render_unknown_method(),
     }
 }
 
+async fn api_ca_rsc_sign(req: Request, ca: CaHandle) -> RoutingResult {
+    aa!(req, Permission::RSC_SIGN, Handle::from(&ca), {
+        let state = req.state().clone();
+        match req.json().await {
+            Ok(request) => render_json_res(state.rsc_sign(ca, request).await),
+            Err(e) => render_error(e),
+        }
+    })
+}
+
 //------------ Support Resource Tagged Attestations (RTA) ----------------------
 
 async fn api_ca_rta(req: Request, path: &mut RequestPath, ca: CaHandle) -> RoutingResult {
@@ -2238,6 +4637,10 @@ async fn api_ta(req: Request, path: &mut RequestPath) -> RoutingResult {
     //    GET  /proxy/signer/request           show open sign request if any
     //    POST /proxy/signer/response          process sign response from signer
     //
+    //    - TAL
+    //    GET  /tal                            get the current TAL
+    //    GET  /tal/check                      verify the TAL against the TA certificate
+    //
     //    - children
     //    GET  /proxy/children/                 future: list children
     //    POST /proxy/children/                 add child
@@ -2350,6 +4753,14 @@ async fn api_ta(req: Request, path: &mut RequestPath) -> RoutingResult {
             },
             _ => render_unknown_method(),
         },
+        Some("tal") => match *req.method() {
+            Method::GET => match path.next() {
+                None => render_json_res(req.state().ta_tal().await),
+                Some("check") => render_json_res(req.state().ta_tal_check().await),
+                _ => render_unknown_method(),
+            },
+            _ => render_unknown_method(),
+        },
         _ => render_unknown_method(),
     }
 }
@@ -2428,4 +4839,35 @@ mod tests {
         let dir = test::start_krill_pubd(0).await;
         let _ = fs::remove_dir_all(dir);
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_is_bound_with_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        test::test_under_tmp(|d| {
+            let path = d.join("krill.sock");
+            let _listener = super::bind_unix_socket(&path, 0o600).unwrap();
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+
+            // The private dir used for binding is gone.
+            assert_eq!(fs::read_dir(&d).unwrap().count(), 1);
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_peer_allowed_by_mode() {
+        let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+        let other = uid.wrapping_add(1000);
+        let other_gid = gid.wrapping_add(1000);
+
+        assert!(super::unix_peer_allowed(uid, other_gid, 0o600));
+        assert!(super::unix_peer_allowed(0, other_gid, 0o600));
+        assert!(!super::unix_peer_allowed(other, gid, 0o600));
+        assert!(super::unix_peer_allowed(other, gid, 0o660));
+        assert!(!super::unix_peer_allowed(other, other_gid, 0o660));
+        assert!(super::unix_peer_allowed(other, other_gid, 0o666));
+    }
 }
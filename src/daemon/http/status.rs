@@ -0,0 +1,140 @@
+use std::str::FromStr;
+
+use rpki::ca::idexchange::CaHandle;
+
+use crate::{
+    commons::api::{PublicCaStatus, Timestamp},
+    daemon::http::{HttpResponse, Request, RoutingResult},
+};
+
+//------------ Public status pages ---------------------------------------------
+//
+// Operators can expose a read-only status page for selected CAs, configured
+// with 'public_status_cas', so that e.g. their NOC or their customers can see
+// the objects published by a CA and the health of its repository connection
+// without credentials for the Krill API:
+//
+//   /status/<ca>:       an HTML page
+//   /status/<ca>.json:  the same status as JSON
+//
+// CAs which are not exposed are not found, whether they exist or not.
+
+const STATUS_PATH: &str = "/status/";
+
+pub async fn public_status(req: Request) -> RoutingResult {
+    if !req.is_get() || !req.path().full().starts_with(STATUS_PATH) {
+        return Err(req);
+    }
+
+    let name = &req.path().full()[STATUS_PATH.len()..];
+    let (name, json) = match name.strip_suffix(".json") {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+
+    let ca = match CaHandle::from_str(name) {
+        Ok(ca) => ca,
+        Err(_) => return Ok(HttpResponse::not_found()),
+    };
+
+    match req.state().ca_public_status(&ca).await {
+        Ok(status) if json => Ok(HttpResponse::json(&status)),
+        Ok(status) => Ok(HttpResponse::html(render_status_page(&status).as_bytes())),
+        Err(_) => Ok(HttpResponse::not_found()),
+    }
+}
+
+fn render_status_page(status: &PublicCaStatus) -> String {
+    let optional_time = |timestamp: Option<Timestamp>| {
+        timestamp
+            .map(|timestamp| timestamp.to_rfc3339())
+            .unwrap_or_else(|| "never".to_string())
+    };
+
+    let rows: String = status
+        .objects()
+        .objects()
+        .iter()
+        .map(|object| {
+            format!(
+                "<tr{}><td>{}</td><td>{}</td><td>{}</td></tr>",
+                if object.expiring() { " class=\"expiring\"" } else { "" },
+                escape(object.uri().as_str()),
+                object.object_type(),
+                object.not_after().to_rfc3339()
+            )
+        })
+        .collect();
+
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>RPKI status of {ca}</title>\
+         <style>body{{font-family:sans-serif}}td,th{{padding:2px 8px;text-align:left}}\
+         .expiring{{color:#b00}}</style></head>\
+         <body><h1>RPKI status of {ca}</h1>\
+         <p>Repository: {health}<br>Last contact: {last_exchange}<br>Last success: {last_success}</p>\
+         <h2>Published objects</h2><p>Objects expiring within {window} hours are shown in red.</p>\
+         <table><tr><th>URI</th><th>Type</th><th>Expires</th></tr>{rows}</table></body></html>",
+        ca = escape(status.ca().as_str()),
+        health = if status.repo_healthy() {
+            "healthy"
+        } else {
+            "not healthy"
+        },
+        last_exchange = optional_time(status.repo_last_exchange()),
+        last_success = optional_time(status.repo_last_success()),
+        window = status.objects().window_hours(),
+        rows = rows
+    )
+}
+
+/// Escapes text for use in HTML.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use rpki::repository::x509::Time;
+
+    use crate::{
+        commons::api::{ObjectExpiry, ObjectsExpiry, RepoStatus},
+        test,
+    };
+
+    use super::*;
+
+    #[test]
+    fn escape_html() {
+        assert_eq!(escape("<b>R&D</b>"), "&lt;b&gt;R&amp;D&lt;/b&gt;");
+        assert_eq!(escape("&lt;"), "&amp;lt;");
+    }
+
+    #[test]
+    fn render_page_escapes_and_marks_expiring_objects() {
+        let objects = ObjectsExpiry::new(
+            24,
+            vec![
+                ObjectExpiry::new(
+                    test::rsync("rsync://localhost/repo/ca/0/R&D.roa"),
+                    Time::now() + chrono::Duration::hours(1),
+                    24,
+                ),
+                ObjectExpiry::new(
+                    test::rsync("rsync://localhost/repo/ca/0/key.mft"),
+                    Time::now() + chrono::Duration::hours(48),
+                    24,
+                ),
+            ],
+        );
+        let status = PublicCaStatus::new(CaHandle::from_str("ca").unwrap(), &RepoStatus::default(), objects);
+
+        let page = render_status_page(&status);
+        assert!(page.contains("<title>RPKI status of ca</title>"));
+        assert!(page.contains("Repository: not healthy<br>Last contact: never<br>Last success: never"));
+        assert!(page.contains("<tr class=\"expiring\"><td>rsync://localhost/repo/ca/0/R&amp;D.roa</td>"));
+        assert!(page.contains("<tr><td>rsync://localhost/repo/ca/0/key.mft</td>"));
+        assert!(!page.contains("R&D"));
+    }
+}
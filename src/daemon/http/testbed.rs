@@ -8,8 +8,8 @@ use crate::{
         ca::testbed_ca_handle,
         http::{
             server::{
-                api_add_pbl, api_ca_add_child, api_ca_child_remove, api_ca_parent_res_xml, api_remove_pbl,
-                api_repository_response_xml, render_ok, render_unknown_method,
+                api_add_pbl, api_ca_child_remove, api_ca_parent_res_xml, api_remove_pbl, api_repository_response_xml,
+                api_testbed_add_child, render_ok, render_unknown_method,
             },
             HttpResponse, Request, RequestPath, RoutingResult,
         },
@@ -40,12 +40,25 @@ use crate::{
 //
 // This feature assumes the existence of a built-in "testbed" CA and publisher
 // when testbed mode is enabled.
+//
+// Operators can limit the resources children may claim, have inactive
+// children and publishers removed, and require a token for registration,
+// see the [testbed] section in the config.
+
+/// The header with the token required for registering and removing children
+/// and publishers, if one is configured.
+pub const TESTBED_TOKEN_HEADER: &str = "x-krill-testbed-token";
 
 pub async fn testbed(mut req: Request) -> RoutingResult {
     if !req.path().full().starts_with("/testbed") {
         Err(req) // Not for us
     } else if !req.state().testbed_enabled() {
         render_unknown_method()
+    } else if *req.method() != Method::GET && !testbed_token_valid(&req) {
+        Ok(HttpResponse::forbidden(format!(
+            "Registration requires a valid token in header '{}'",
+            TESTBED_TOKEN_HEADER
+        )))
     } else {
         // The testbed is intended to be used without being logged in but
         // anonymous users don't have the necessary rights to manipulate
@@ -64,6 +77,20 @@ pub async fn testbed(mut req: Request) -> RoutingResult {
     }
 }
 
+// Is the token configured for registration, if any, given?
+fn testbed_token_valid(req: &Request) -> bool {
+    let config = req.state().config();
+    match config.testbed().and_then(|testbed| testbed.registration_token()) {
+        None => true,
+        Some(token) => {
+            req.headers()
+                .get(TESTBED_TOKEN_HEADER)
+                .and_then(|value| value.to_str().ok())
+                == Some(token.as_ref())
+        }
+    }
+}
+
 // Is the testbed feature enabled or not? used by the web-UI to conditionally
 // enable the testbed web-UI.
 async fn testbed_enabled(req: Request) -> RoutingResult {
@@ -74,9 +101,10 @@ async fn testbed_enabled(req: Request) -> RoutingResult {
 }
 
 // Open (token-less) addition/removal of child CAs under the testbed CA.
-// Note: Anyone can request any resources irrespective of the resources they
-// have the rights to in the real global RPKI hierarchy and anyone can
-// un-register any child CA even if not "owned" by them.
+// Note: Anyone can request any resources within the configured limits,
+// irrespective of the resources they have the rights to in the real global
+// RPKI hierarchy, and anyone can un-register any child CA even if not "owned"
+// by them.
 async fn testbed_children(req: Request, path: &mut RequestPath) -> RoutingResult {
     match (req.method().clone(), path.path_arg()) {
         (Method::GET, Some(child)) => match path.next() {
@@ -84,7 +112,7 @@ async fn testbed_children(req: Request, path: &mut RequestPath) -> RoutingResult
             _ => render_unknown_method(),
         },
         (Method::DELETE, Some(child)) => api_ca_child_remove(req, testbed_ca_handle(), child).await,
-        (Method::POST, None) => api_ca_add_child(req, testbed_ca_handle()).await,
+        (Method::POST, None) => api_testbed_add_child(req).await,
         _ => render_unknown_method(),
     }
 }
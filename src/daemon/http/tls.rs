@@ -21,21 +21,139 @@ use std::{
     net::SocketAddr,
     path::{Path, PathBuf},
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, RwLock},
     task::{Context, Poll},
 };
 
 use futures::ready;
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio_rustls::rustls::{KeyLogFile, NoClientAuth, ServerConfig, TLSError};
+use tokio_rustls::rustls::{
+    AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, KeyLogFile, NoClientAuth, ProtocolVersion,
+    RootCertStore, ServerConfig, Session, SupportedCipherSuite, TLSError, ALL_CIPHERSUITES,
+};
 
 use hyper::server::{
     accept::Accept,
     conn::{AddrIncoming, AddrStream},
 };
 
+use crate::daemon::http::acme::{AcmeCertResolver, AcmeChallenges, ACME_TLS_ALPN_PROTOCOL};
+
 const SSLKEYLOGFILE_ENV_VAR_NAME: &str = "SSLKEYLOGFILE";
 
+//------------ TlsConfig -----------------------------------------------------
+
+/// Options for the HTTPS server, which are used unless "https_mode" is
+/// "disable".
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct TlsConfig {
+    /// The minimum TLS version accepted from clients.
+    #[serde(default)]
+    pub min_version: TlsVersion,
+
+    /// The names of the cipher suites to offer, e.g.
+    /// "TLS13_AES_256_GCM_SHA384". All cipher suites supported by rustls
+    /// are offered if empty.
+    #[serde(default)]
+    pub cipher_suites: Vec<String>,
+
+    /// A DER encoded OCSP response for the certificate, which is stapled to
+    /// the handshake if set.
+    #[serde(default)]
+    pub ocsp_response: Option<PathBuf>,
+
+    /// How often the certificate, key and OCSP response files are checked
+    /// for changes. They are only read at start up if this is 0.
+    #[serde(default = "TlsConfig::dflt_reload_check_seconds")]
+    pub reload_check_seconds: u64,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig {
+            min_version: TlsVersion::default(),
+            cipher_suites: vec![],
+            ocsp_response: None,
+            reload_check_seconds: Self::dflt_reload_check_seconds(),
+        }
+    }
+}
+
+impl TlsConfig {
+    fn dflt_reload_check_seconds() -> u64 {
+        60
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        self.supported_cipher_suites().map(|_| ())
+    }
+
+    /// Returns the cipher suites to offer for the allowed TLS versions.
+    fn supported_cipher_suites(&self) -> Result<Vec<&'static SupportedCipherSuite>, String> {
+        let suites = if self.cipher_suites.is_empty() {
+            ALL_CIPHERSUITES.to_vec()
+        } else {
+            self.cipher_suites
+                .iter()
+                .map(|name| {
+                    ALL_CIPHERSUITES
+                        .iter()
+                        .find(|suite| format!("{:?}", suite.suite) == *name)
+                        .copied()
+                        .ok_or_else(|| format!("unsupported cipher suite in tls.cipher_suites: {}", name))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let versions = self.min_version.protocol_versions();
+        let suites: Vec<_> = suites
+            .into_iter()
+            .filter(|suite| versions.iter().any(|version| suite.usable_for_version(*version)))
+            .collect();
+
+        if suites.is_empty() {
+            Err(format!(
+                "tls.cipher_suites contains no cipher suite for TLS {} or higher",
+                self.min_version
+            ))
+        } else {
+            Ok(suites)
+        }
+    }
+}
+
+//------------ TlsVersion ----------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+pub enum TlsVersion {
+    #[default]
+    #[serde(rename = "1.2")]
+    Tls12,
+
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+impl TlsVersion {
+    fn protocol_versions(self) -> Vec<ProtocolVersion> {
+        match self {
+            TlsVersion::Tls12 => vec![ProtocolVersion::TLSv1_3, ProtocolVersion::TLSv1_2],
+            TlsVersion::Tls13 => vec![ProtocolVersion::TLSv1_3],
+        }
+    }
+}
+
+impl std::fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TlsVersion::Tls12 => write!(f, "1.2"),
+            TlsVersion::Tls13 => write!(f, "1.3"),
+        }
+    }
+}
+
+//------------ Transport -----------------------------------------------------
+
 pub trait Transport: AsyncRead + AsyncWrite {
     fn remote_addr(&self) -> Option<SocketAddr>;
 }
@@ -88,6 +206,10 @@ pub(crate) enum TlsConfigError {
     EmptyKey,
     /// An error from an invalid key
     InvalidKey(TLSError),
+    /// An Error parsing the CA certificates for client authentication
+    ClientCaParseError,
+    /// An error in the TLS options
+    Options(String),
 }
 
 impl std::fmt::Display for TlsConfigError {
@@ -99,6 +221,8 @@ impl std::fmt::Display for TlsConfigError {
             TlsConfigError::RsaParseError => write!(f, "rsa parse error"),
             TlsConfigError::EmptyKey => write!(f, "key contains no private key"),
             TlsConfigError::InvalidKey(err) => write!(f, "key contains an invalid key, {}", err),
+            TlsConfigError::ClientCaParseError => write!(f, "client CA certificate parse error"),
+            TlsConfigError::Options(msg) => msg.fmt(f),
         }
     }
 }
@@ -109,6 +233,17 @@ impl std::error::Error for TlsConfigError {}
 pub(crate) struct TlsConfigBuilder {
     cert: Box<dyn Read + Send + Sync>,
     key: Box<dyn Read + Send + Sync>,
+    ocsp: Option<Box<dyn Read + Send + Sync>>,
+    client_auth: Option<ClientAuth>,
+    options: TlsConfig,
+    acme_challenges: Option<AcmeChallenges>,
+}
+
+/// The CA certificates to verify client certificates against, and whether
+/// clients must present a certificate.
+struct ClientAuth {
+    ca_certs: Box<dyn Read + Send + Sync>,
+    mandatory: bool,
 }
 
 impl std::fmt::Debug for TlsConfigBuilder {
@@ -123,9 +258,32 @@ impl TlsConfigBuilder {
         TlsConfigBuilder {
             key: Box::new(io::empty()),
             cert: Box::new(io::empty()),
+            ocsp: None,
+            client_auth: None,
+            options: TlsConfig::default(),
+            acme_challenges: None,
         }
     }
 
+    /// Answer TLS-ALPN-01 challenges using the given validation certificates.
+    pub(crate) fn acme_challenges(mut self, challenges: AcmeChallenges) -> Self {
+        self.acme_challenges = Some(challenges);
+        self
+    }
+
+    /// Use the TLS version, cipher suites and OCSP response from the given
+    /// options.
+    pub(crate) fn options(mut self, options: &TlsConfig) -> Self {
+        self.ocsp = options.ocsp_response.as_ref().map(|path| {
+            Box::new(LazyFile {
+                path: path.clone(),
+                file: None,
+            }) as Box<dyn Read + Send + Sync>
+        });
+        self.options = options.clone();
+        self
+    }
+
     /// sets the Tls key via File Path, returns `TlsConfigError::IoError` if the file cannot be open
     pub(crate) fn key_path(mut self, path: impl AsRef<Path>) -> Self {
         self.key = Box::new(LazyFile {
@@ -144,6 +302,21 @@ impl TlsConfigBuilder {
         self
     }
 
+    /// Request clients to authenticate using a certificate issued by one of
+    /// the CA certificates in the given PEM file. If `mandatory` is false,
+    /// clients without a certificate are still accepted.
+    #[cfg(feature = "multi-user")]
+    pub(crate) fn client_auth_path(mut self, path: impl AsRef<Path>, mandatory: bool) -> Self {
+        self.client_auth = Some(ClientAuth {
+            ca_certs: Box::new(LazyFile {
+                path: path.as_ref().into(),
+                file: None,
+            }),
+            mandatory,
+        });
+        self
+    }
+
     pub(crate) fn build(mut self) -> Result<ServerConfig, TlsConfigError> {
         let mut cert_rdr = BufReader::new(self.cert);
         let cert = tokio_rustls::rustls::internal::pemfile::certs(&mut cert_rdr)
@@ -175,9 +348,44 @@ impl TlsConfigBuilder {
             }
         };
 
-        let mut config = ServerConfig::new(NoClientAuth::new());
-        config.set_single_cert(cert, key).map_err(TlsConfigError::InvalidKey)?;
+        let mut config = match self.client_auth {
+            None => ServerConfig::new(NoClientAuth::new()),
+            Some(client_auth) => {
+                let mut roots = RootCertStore::empty();
+                let (added, _) = roots
+                    .add_pem_file(&mut BufReader::new(client_auth.ca_certs))
+                    .map_err(|()| TlsConfigError::ClientCaParseError)?;
+                if added == 0 {
+                    return Err(TlsConfigError::ClientCaParseError);
+                }
+
+                if client_auth.mandatory {
+                    ServerConfig::new(AllowAnyAuthenticatedClient::new(roots))
+                } else {
+                    ServerConfig::new(AllowAnyAnonymousOrAuthenticatedClient::new(roots))
+                }
+            }
+        };
+        match self.ocsp {
+            None => config.set_single_cert(cert, key).map_err(TlsConfigError::InvalidKey)?,
+            Some(mut ocsp) => {
+                let mut ocsp_vec = Vec::new();
+                ocsp.read_to_end(&mut ocsp_vec).map_err(TlsConfigError::Io)?;
+                config
+                    .set_single_cert_with_ocsp_and_sct(cert, key, ocsp_vec, vec![])
+                    .map_err(TlsConfigError::InvalidKey)?
+            }
+        }
         config.set_protocols(&["h2".into(), "http/1.1".into()]);
+        if let Some(challenges) = self.acme_challenges {
+            config.cert_resolver = Arc::new(AcmeCertResolver::new(config.cert_resolver.clone(), challenges));
+            config.alpn_protocols.push(ACME_TLS_ALPN_PROTOCOL.to_vec());
+        }
+        config.versions = self.options.min_version.protocol_versions();
+        config.ciphersuites = self
+            .options
+            .supported_cipher_suites()
+            .map_err(TlsConfigError::Options)?;
 
         // See: https://wiki.wireshark.org/TLS#tls-decryption
         if std::env::var(SSLKEYLOGFILE_ENV_VAR_NAME).is_ok() {
@@ -214,10 +422,7 @@ impl Read for LazyFile {
 
 impl Transport for TlsStream {
     fn remote_addr(&self) -> Option<SocketAddr> {
-        match self.state {
-            State::Handshaking(_) => None,
-            State::Streaming(ref stream) => Some(stream.get_ref().0.remote_addr()),
-        }
+        Some(self.remote_addr)
     }
 }
 
@@ -226,20 +431,63 @@ enum State {
     Streaming(tokio_rustls::server::TlsStream<AddrStream>),
 }
 
+//------------ ClientCertificate ---------------------------------------------
+
+/// The DER encoded end-entity certificate presented by the client during the
+/// TLS handshake. This is added to the extensions of each request received on
+/// the connection, so that it can be used to authenticate the request.
+#[derive(Clone, Debug)]
+pub struct ClientCertificate(pub Vec<u8>);
+
+/// Holds the client certificate of a connection once the handshake, which
+/// only completes after hyper has set up the service for the connection, is
+/// done.
+#[derive(Clone, Default)]
+pub(crate) struct ClientCertificateSlot(Arc<RwLock<Option<ClientCertificate>>>);
+
+impl ClientCertificateSlot {
+    pub(crate) fn get(&self) -> Option<ClientCertificate> {
+        self.0.read().unwrap().clone()
+    }
+
+    fn fill(&self, stream: &tokio_rustls::server::TlsStream<AddrStream>) {
+        let cert = stream
+            .get_ref()
+            .1
+            .get_peer_certificates()
+            .and_then(|certs| certs.into_iter().next())
+            .map(|cert| ClientCertificate(cert.0));
+        *self.0.write().unwrap() = cert;
+    }
+}
+
 // tokio_rustls::server::TlsStream doesn't expose constructor methods,
 // so we have to TlsAcceptor::accept and handshake to have access to it
 // TlsStream implements AsyncRead/AsyncWrite handshaking tokio_rustls::Accept first
 pub(crate) struct TlsStream {
     state: State,
+    remote_addr: SocketAddr,
+    client_certificate: ClientCertificateSlot,
 }
 
 impl TlsStream {
     fn new(stream: AddrStream, config: Arc<ServerConfig>) -> TlsStream {
+        let remote_addr = stream.remote_addr();
         let accept = tokio_rustls::TlsAcceptor::from(config).accept(stream);
         TlsStream {
             state: State::Handshaking(accept),
+            remote_addr,
+            client_certificate: ClientCertificateSlot::default(),
         }
     }
+
+    pub(crate) fn peer_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    pub(crate) fn client_certificate(&self) -> ClientCertificateSlot {
+        self.client_certificate.clone()
+    }
 }
 
 impl AsyncRead for TlsStream {
@@ -248,6 +496,7 @@ impl AsyncRead for TlsStream {
         match pin.state {
             State::Handshaking(ref mut accept) => match ready!(Pin::new(accept).poll(cx)) {
                 Ok(mut stream) => {
+                    pin.client_certificate.fill(&stream);
                     let result = Pin::new(&mut stream).poll_read(cx, buf);
                     pin.state = State::Streaming(stream);
                     result
@@ -265,6 +514,7 @@ impl AsyncWrite for TlsStream {
         match pin.state {
             State::Handshaking(ref mut accept) => match ready!(Pin::new(accept).poll(cx)) {
                 Ok(mut stream) => {
+                    pin.client_certificate.fill(&stream);
                     let result = Pin::new(&mut stream).poll_write(cx, buf);
                     pin.state = State::Streaming(stream);
                     result
@@ -290,17 +540,36 @@ impl AsyncWrite for TlsStream {
     }
 }
 
+//------------ SharedServerConfig --------------------------------------------
+
+/// The TLS config used for new connections. This can be replaced while the
+/// server is running, e.g. when the certificate was renewed, without
+/// affecting the connections which use the previous config.
+#[derive(Clone)]
+pub(crate) struct SharedServerConfig(Arc<RwLock<Arc<ServerConfig>>>);
+
+impl SharedServerConfig {
+    pub(crate) fn new(config: ServerConfig) -> Self {
+        SharedServerConfig(Arc::new(RwLock::new(Arc::new(config))))
+    }
+
+    fn current(&self) -> Arc<ServerConfig> {
+        self.0.read().unwrap().clone()
+    }
+
+    pub(crate) fn replace(&self, config: ServerConfig) {
+        *self.0.write().unwrap() = Arc::new(config);
+    }
+}
+
 pub(crate) struct TlsAcceptor {
-    config: Arc<ServerConfig>,
+    config: SharedServerConfig,
     incoming: AddrIncoming,
 }
 
 impl TlsAcceptor {
-    pub(crate) fn new(config: ServerConfig, incoming: AddrIncoming) -> TlsAcceptor {
-        TlsAcceptor {
-            config: Arc::new(config),
-            incoming,
-        }
+    pub(crate) fn new(config: SharedServerConfig, incoming: AddrIncoming) -> TlsAcceptor {
+        TlsAcceptor { config, incoming }
     }
 }
 
@@ -311,7 +580,7 @@ impl Accept for TlsAcceptor {
     fn poll_accept(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
         let pin = self.get_mut();
         match ready!(Pin::new(&mut pin.incoming).poll_accept(cx)) {
-            Some(Ok(sock)) => Poll::Ready(Some(Ok(TlsStream::new(sock, pin.config.clone())))),
+            Some(Ok(sock)) => Poll::Ready(Some(Ok(TlsStream::new(sock, pin.config.current())))),
             Some(Err(e)) => Poll::Ready(Some(Err(e))),
             None => Poll::Ready(None),
         }
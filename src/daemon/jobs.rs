@@ -0,0 +1,575 @@
+//! Running operations which can take long as jobs in the background.
+//!
+//! A job does an operation for one or more CAs, one at a time. The job,
+//! with its progress and the results for the CAs done so far, is saved
+//! after each CA. Jobs which were still running when Krill stopped are
+//! resumed when it starts again, for the CAs which were not done yet, as
+//! the actor who started them. In high availability mode they are resumed
+//! by the instance which becomes active. The input of a job is saved
+//! separately, because it can be large, and it is removed when the job is
+//! finished. A CA migration can include private keys, so its input is never
+//! saved, and it cannot be resumed.
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, RwLock},
+};
+
+use rpki::ca::idexchange::CaHandle;
+
+use crate::{
+    commons::{
+        actor::{Actor, ActorDef, ActorName, Attributes},
+        api::{
+            import::{CaMigration, CaMigrationReport},
+            BulkJobRequest, Job, JobCaResult, JobList, JobOutput, JobRequest, RepositoryContact, RoaImport,
+        },
+        error::Error,
+        eventsourcing::{KeyStoreKey, KeyValueStorage, KeyValueStore},
+        KrillResult,
+    },
+    constants::{JOBS_DIR, JOBS_KEEP, NO_RESOURCE},
+    daemon::{
+        auth::{common::permissions::Permission, Handle},
+        krillserver::KrillServer,
+    },
+};
+
+const JOB_PREFIX: &str = "job-";
+const INPUT_PREFIX: &str = "input-";
+const CREATOR_PREFIX: &str = "creator-";
+const JSON_SUFFIX: &str = ".json";
+
+//------------ JobInput ------------------------------------------------------
+
+/// The input for a job, other than the request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum JobInput {
+    RoaImport(RoaImport),
+    RepoMigrate(RepositoryContact),
+
+    // Never saved.
+    #[serde(skip)]
+    CaMigrate(CaMigration),
+}
+
+impl JobInput {
+    fn is_saved(&self) -> bool {
+        !matches!(self, JobInput::CaMigrate(_))
+    }
+}
+
+//------------ JobCreator ----------------------------------------------------
+
+/// The actor who started a job, so that the job can be resumed with the
+/// same permissions.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct JobCreator {
+    name: String,
+    is_user: bool,
+    attributes: HashMap<String, String>,
+}
+
+impl JobCreator {
+    fn new(actor: &Actor) -> Self {
+        JobCreator {
+            name: actor.name().to_string(),
+            is_user: actor.is_user(),
+            attributes: actor.attributes(),
+        }
+    }
+
+    fn into_actor_def(self) -> ActorDef {
+        ActorDef {
+            name: ActorName::AsString(self.name),
+            is_user: self.is_user,
+            attributes: Attributes::UserDefined(self.attributes),
+            new_auth: None,
+            auth_error: None,
+        }
+    }
+}
+
+//------------ JobStore ------------------------------------------------------
+
+/// Keeps all running jobs, and the most recently finished jobs.
+pub struct JobStore {
+    store: KeyValueStore,
+
+    // Oldest first.
+    jobs: RwLock<Vec<Job>>,
+
+    // The ids of the jobs which are being run by this instance.
+    running_here: Mutex<HashSet<String>>,
+}
+
+impl JobStore {
+    pub fn create(storage: &KeyValueStorage) -> KrillResult<Self> {
+        let store = KeyValueStore::create(storage, JOBS_DIR)?;
+        let jobs = Self::load(&store)?;
+
+        Ok(JobStore {
+            store,
+            jobs: RwLock::new(jobs),
+            running_here: Mutex::new(HashSet::new()),
+        })
+    }
+
+    fn load(store: &KeyValueStore) -> KrillResult<Vec<Job>> {
+        let mut jobs = vec![];
+        for key in store.keys(None, JOB_PREFIX)? {
+            match store.get::<Job>(&key) {
+                Ok(Some(job)) => jobs.push(job),
+                Ok(None) => {}
+                Err(e) => warn!("Ignoring job '{}' which cannot be read: {}", key, e),
+            }
+        }
+        jobs.sort_by_key(|job| job.started());
+        Ok(jobs)
+    }
+
+    /// Loads the jobs again, to pick up the jobs which were started or
+    /// updated by another instance in high availability mode.
+    fn reload(&self) -> KrillResult<()> {
+        let mut jobs = self.jobs.write().unwrap();
+        *jobs = Self::load(&self.store)?;
+        Ok(())
+    }
+
+    fn job_key(id: &str) -> KeyStoreKey {
+        KeyStoreKey::simple(format!("{}{}{}", JOB_PREFIX, id, JSON_SUFFIX))
+    }
+
+    fn input_key(id: &str) -> KeyStoreKey {
+        KeyStoreKey::simple(format!("{}{}{}", INPUT_PREFIX, id, JSON_SUFFIX))
+    }
+
+    fn creator_key(id: &str) -> KeyStoreKey {
+        KeyStoreKey::simple(format!("{}{}{}", CREATOR_PREFIX, id, JSON_SUFFIX))
+    }
+
+    /// Adds a new job, and removes the oldest finished jobs if there are
+    /// too many.
+    fn add(&self, job: Job, input: Option<&JobInput>, creator: &JobCreator) -> KrillResult<()> {
+        if let Some(input) = input.filter(|input| input.is_saved()) {
+            self.store.store(&Self::input_key(job.id()), input)?;
+        }
+        if job.is_running() {
+            self.store.store(&Self::creator_key(job.id()), creator)?;
+        }
+        self.store.store(&Self::job_key(job.id()), &job)?;
+
+        let mut jobs = self.jobs.write().unwrap();
+        jobs.push(job);
+
+        let finished = jobs.iter().filter(|job| !job.is_running()).count();
+        if finished > JOBS_KEEP {
+            let mut remove = finished - JOBS_KEEP;
+            let mut removed = vec![];
+            jobs.retain(|job| {
+                if remove > 0 && !job.is_running() {
+                    remove -= 1;
+                    removed.push(job.id().to_string());
+                    false
+                } else {
+                    true
+                }
+            });
+            for id in removed {
+                self.store.drop_key(&Self::job_key(&id))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Changes a job and saves it. Removes the input and creator when the job
+    /// is no longer running.
+    fn update<F: FnOnce(&mut Job)>(&self, id: &str, op: F) -> KrillResult<Job> {
+        let mut jobs = self.jobs.write().unwrap();
+        let job = jobs
+            .iter_mut()
+            .find(|job| job.id() == id)
+            .ok_or_else(|| Error::ApiJobUnknown(id.to_string()))?;
+
+        op(job);
+        self.store.store(&Self::job_key(id), job)?;
+        if !job.is_running() {
+            self.store.drop_key(&Self::input_key(id))?;
+            self.store.drop_key(&Self::creator_key(id))?;
+        }
+
+        Ok(job.clone())
+    }
+
+    fn input(&self, id: &str) -> KrillResult<Option<JobInput>> {
+        Ok(self.store.get(&Self::input_key(id))?)
+    }
+
+    fn creator(&self, id: &str) -> KrillResult<Option<JobCreator>> {
+        Ok(self.store.get(&Self::creator_key(id))?)
+    }
+
+    /// Marks the job as being run by this instance. Returns false if it
+    /// already is.
+    fn claim(&self, id: &str) -> bool {
+        self.running_here.lock().unwrap().insert(id.to_string())
+    }
+
+    fn release(&self, id: &str) {
+        self.running_here.lock().unwrap().remove(id);
+    }
+
+    pub fn list(&self) -> JobList {
+        JobList::new(self.jobs.read().unwrap().clone())
+    }
+
+    pub fn get(&self, id: &str) -> KrillResult<Job> {
+        self.jobs
+            .read()
+            .unwrap()
+            .iter()
+            .find(|job| job.id() == id)
+            .cloned()
+            .ok_or_else(|| Error::ApiJobUnknown(id.to_string()))
+    }
+
+    fn running(&self) -> Vec<Job> {
+        self.jobs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|job| job.is_running())
+            .cloned()
+            .collect()
+    }
+}
+
+//------------ Access --------------------------------------------------------
+
+/// Returns true if the actor may see the job, i.e. if it may read all of
+/// the CAs of the job. Jobs which are not for any CA can only be seen by
+/// those who may administer all CAs.
+pub fn job_visible(job: &Job, actor: &Actor) -> bool {
+    if job.cas().is_empty() {
+        matches!(actor.is_allowed(Permission::CA_ADMIN, NO_RESOURCE), Ok(true))
+    } else {
+        job.cas()
+            .iter()
+            .all(|ca| matches!(actor.is_allowed(Permission::CA_READ, Handle::from(ca)), Ok(true)))
+    }
+}
+
+//------------ Starting jobs -------------------------------------------------
+
+/// Starts a job for an operation for the CAs selected by the request, from
+/// the CAs which the actor may see.
+pub fn start_bulk(server: &Arc<KrillServer>, request: BulkJobRequest, actor: &Actor) -> KrillResult<Job> {
+    let cas = server.ca_list(actor)?.cas().clone();
+    let cas = request.selector().select(cas).map_err(Error::ApiInvalidCaSelector)?;
+    start(server, JobRequest::Bulk(request), cas, None, actor)
+}
+
+/// Starts a job to import ROAs for a CA. The content is checked first.
+pub fn start_roa_import(
+    server: &Arc<KrillServer>,
+    ca: CaHandle,
+    import: RoaImport,
+    force: bool,
+    actor: &Actor,
+) -> KrillResult<Job> {
+    import.payloads().map_err(Error::custom)?;
+    let input = JobInput::RoaImport(import);
+    start(server, JobRequest::RoaImport { force }, vec![ca], Some(input), actor)
+}
+
+/// Starts a job to migrate a CA to a new repository.
+pub fn start_repo_migration(
+    server: &Arc<KrillServer>,
+    ca: CaHandle,
+    contact: RepositoryContact,
+    actor: &Actor,
+) -> KrillResult<Job> {
+    let request = JobRequest::RepoMigrate {
+        service_uri: contact.server_info().service_uri().clone(),
+    };
+    start(server, request, vec![ca], Some(JobInput::RepoMigrate(contact)), actor)
+}
+
+/// Starts a job to migrate CAs from another RPKI CA implementation. All
+/// CAs are checked first.
+pub async fn start_ca_migration(server: &Arc<KrillServer>, migration: CaMigration, actor: &Actor) -> KrillResult<Job> {
+    server.cas_migrate_check(&migration).await?;
+    let cas = migration.cas().iter().map(|ca| ca.handle().clone()).collect();
+    start(
+        server,
+        JobRequest::CaMigrate,
+        cas,
+        Some(JobInput::CaMigrate(migration)),
+        actor,
+    )
+}
+
+fn start(
+    server: &Arc<KrillServer>,
+    request: JobRequest,
+    cas: Vec<CaHandle>,
+    input: Option<JobInput>,
+    actor: &Actor,
+) -> KrillResult<Job> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let job = Job::new(id, request, cas, actor.name().to_string());
+    server
+        .job_store()
+        .add(job.clone(), input.as_ref(), &JobCreator::new(actor))?;
+    info!(
+        "Started job {}: {}, for {} CAs",
+        job.id(),
+        job.request(),
+        job.cas().len()
+    );
+
+    if job.is_running() && server.job_store().claim(job.id()) {
+        tokio::spawn(run(server.clone(), job.clone(), input, actor.clone()));
+    }
+
+    Ok(job)
+}
+
+/// Resumes the running jobs which are not run by this instance, i.e. the
+/// jobs which were running when Krill stopped, or when this instance became
+/// the active instance in high availability mode. The jobs are done as the
+/// actor who started them. Jobs for which the input was not saved fail, as
+/// do jobs for which it is not known who started them.
+pub fn resume(server: &Arc<KrillServer>) {
+    let store = server.job_store();
+    if let Err(e) = store.reload() {
+        error!("Cannot resume jobs: {}", e);
+        return;
+    }
+
+    for job in store.running() {
+        if !store.claim(job.id()) {
+            continue;
+        }
+
+        match (store.input(job.id()), store.creator(job.id())) {
+            (Ok(input), Ok(Some(creator))) => {
+                info!(
+                    "Resuming job {}: {}, for {} remaining CAs, as '{}'",
+                    job.id(),
+                    job.request(),
+                    job.remaining_cas().len(),
+                    creator.name
+                );
+                let actor = server.actor_from_def(creator.into_actor_def());
+                tokio::spawn(run(server.clone(), job, input, actor));
+            }
+            (Ok(_), Ok(None)) => {
+                store.release(job.id());
+                let msg = "the job was interrupted by a restart of Krill, and it is not known who started it";
+                warn!("Job {} failed: {}", job.id(), msg);
+                if let Err(e) = store.update(job.id(), |job| job.fail(msg.to_string())) {
+                    error!("Cannot save job {}: {}", job.id(), e);
+                }
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                store.release(job.id());
+                error!("Cannot resume job {}: {}", job.id(), e);
+            }
+        }
+    }
+}
+
+//------------ Running jobs --------------------------------------------------
+
+async fn run(server: Arc<KrillServer>, job: Job, input: Option<JobInput>, actor: Actor) {
+    let id = job.id().to_string();
+    let store = server.job_store();
+
+    let result = match (job.request(), input) {
+        (JobRequest::Bulk(request), _) => {
+            let operation = request.operation();
+            for ca in job.remaining_cas() {
+                let error = server
+                    .bulk_operation(operation, &ca, &actor)
+                    .await
+                    .err()
+                    .map(|e| e.to_string());
+                if let Some(e) = &error {
+                    warn!("Job {}: {} failed for CA '{}': {}", id, operation, ca, e);
+                }
+                if let Err(e) = store.update(&id, |job| job.add_result(JobCaResult::new(ca, error))) {
+                    error!("Cannot save job {}: {}", id, e);
+                }
+            }
+            Ok(())
+        }
+        (JobRequest::RoaImport { force }, Some(JobInput::RoaImport(import))) => {
+            for ca in job.remaining_cas() {
+                let result = server
+                    .ca_routes_import(&ca, import.clone(), false, *force, &actor)
+                    .await
+                    .map(JobOutput::RoaImport);
+                save_result(&server, &id, ca, result);
+            }
+            Ok(())
+        }
+        (JobRequest::RepoMigrate { .. }, Some(JobInput::RepoMigrate(contact))) => {
+            for ca in job.remaining_cas() {
+                let result = server
+                    .ca_repo_migrate(&ca, contact.clone(), &actor)
+                    .await
+                    .map(JobOutput::RepoMigration);
+                save_result(&server, &id, ca, result);
+            }
+            Ok(())
+        }
+        (JobRequest::CaMigrate, Some(JobInput::CaMigrate(migration))) => {
+            run_ca_migration(&server, &id, migration, &actor).await
+        }
+        _ => Err("the job was interrupted by a restart of Krill, and cannot be resumed".to_string()),
+    };
+
+    if let Err(msg) = result {
+        warn!("Job {} failed: {}", id, msg);
+        if let Err(e) = store.update(&id, |job| job.fail(msg)) {
+            error!("Cannot save job {}: {}", id, e);
+        }
+    }
+
+    if let Ok(job) = store.get(&id) {
+        if !job.is_running() {
+            info!("Finished job {}, {} CAs failed", id, job.failed());
+        }
+    }
+    store.release(&id);
+}
+
+/// Saves the result for a CA of a job with an output.
+fn save_result(server: &KrillServer, id: &str, ca: CaHandle, result: KrillResult<JobOutput>) {
+    let saved = server.job_store().update(id, |job| match result {
+        Ok(output) => {
+            job.set_output(output);
+            job.add_result(JobCaResult::new(ca, None));
+        }
+        Err(e) => job.add_result(JobCaResult::new(ca, Some(e.to_string()))),
+    });
+    if let Err(e) = saved {
+        error!("Cannot save job {}: {}", id, e);
+    }
+}
+
+/// Migrates the CAs one by one, and stops at the first CA which fails, like
+/// a migration which is not done as a job.
+async fn run_ca_migration(server: &KrillServer, id: &str, migration: CaMigration, actor: &Actor) -> Result<(), String> {
+    let mut report = CaMigrationReport::default();
+    let mut cas = migration.into_cas().into_iter();
+    while let Some(ca) = cas.next() {
+        let handle = ca.handle().clone();
+        match server.ca_migrate(ca, actor).await {
+            Ok(migrated) => {
+                report.add(migrated);
+                let output = JobOutput::CaMigration(report.clone());
+                server
+                    .job_store()
+                    .update(id, |job| {
+                        job.set_output(output);
+                        job.add_result(JobCaResult::new(handle, None));
+                    })
+                    .map_err(|e| e.to_string())?;
+            }
+            Err(e) => {
+                let exists = server.ca_exists(&handle).map_err(|e| e.to_string())?;
+                let not_attempted = cas.map(|ca| ca.handle().clone()).collect();
+                report.fail(handle.clone(), e.to_string(), exists, not_attempted);
+                let output = JobOutput::CaMigration(report);
+                server
+                    .job_store()
+                    .update(id, |job| {
+                        job.set_output(output);
+                        job.add_result(JobCaResult::new(handle.clone(), Some(e.to_string())))
+                    })
+                    .map_err(|e| e.to_string())?;
+                return Err(format!("stopped after CA '{}' failed", handle));
+            }
+        }
+    }
+    Ok(())
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(all(test, feature = "multi-user"))]
+mod tests {
+    use std::{collections::HashMap, str::FromStr};
+
+    use super::*;
+
+    use crate::{
+        commons::{
+            actor::ActorDef,
+            api::{BulkOperation, CaSelector},
+        },
+        constants::ACTOR_DEF_ADMIN_TOKEN,
+        daemon::{auth::policy::AuthPolicy, config::Config},
+        test,
+    };
+
+    #[test]
+    fn jobs_without_cas_only_visible_to_admins() {
+        test::test_under_tmp(|d| {
+            let config = Config::test(&d, false, false, false, false);
+            let policy = AuthPolicy::new(Arc::new(config)).unwrap();
+
+            let mut attributes = HashMap::new();
+            attributes.insert("role".to_string(), "readonly".to_string());
+            let reader = Actor::new(ActorDef::user("joe".to_string(), attributes, None), policy.clone());
+            let admin = Actor::new(ACTOR_DEF_ADMIN_TOKEN, policy);
+
+            let request = || JobRequest::Bulk(BulkJobRequest::new(CaSelector::All, BulkOperation::SyncRepo));
+            let ca = CaHandle::from_str("ca1").unwrap();
+            let job = Job::new("1".to_string(), request(), vec![ca], "admin".to_string());
+            let no_cas = Job::new("2".to_string(), request(), vec![], "admin".to_string());
+
+            assert!(job_visible(&job, &reader));
+            assert!(!job_visible(&no_cas, &reader));
+
+            assert!(job_visible(&job, &admin));
+            assert!(job_visible(&no_cas, &admin));
+        });
+    }
+
+    #[test]
+    fn resume_as_creator() {
+        test::test_under_tmp(|d| {
+            let config = Config::test(&d, false, false, false, false);
+            let policy = AuthPolicy::new(Arc::new(config.clone())).unwrap();
+
+            let mut attributes = HashMap::new();
+            attributes.insert("role".to_string(), "readwrite".to_string());
+            attributes.insert("inc_cas".to_string(), "ca1".to_string());
+            let creator = Actor::new(ActorDef::user("joe".to_string(), attributes, None), policy.clone());
+
+            let request = JobRequest::Bulk(BulkJobRequest::new(CaSelector::All, BulkOperation::SyncRepo));
+            let ca = CaHandle::from_str("ca1").unwrap();
+            let job = Job::new("1".to_string(), request, vec![ca], creator.name().to_string());
+
+            let store = JobStore::create(&config.storage()).unwrap();
+            store.add(job, None, &JobCreator::new(&creator)).unwrap();
+            assert!(store.claim("1"));
+            assert!(!store.claim("1"));
+
+            // Another instance, or this one after a restart, knows who
+            // started the job.
+            let other = JobStore::create(&config.storage()).unwrap();
+            let resumed = other.creator("1").unwrap().unwrap().into_actor_def();
+            assert_eq!(Actor::new(resumed, policy), creator);
+            assert!(other.claim("1"));
+
+            // The creator is removed when the job is done.
+            other.update("1", |job| job.fail("stop".to_string())).unwrap();
+            assert!(other.creator("1").unwrap().is_none());
+        });
+    }
+}
@@ -1,5 +1,11 @@
 //! An RPKI publication protocol server.
-use std::{collections::HashMap, path::PathBuf, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::UNIX_EPOCH,
+};
 
 use bytes::Bytes;
 use chrono::Duration;
@@ -10,7 +16,9 @@ use rpki::{
     ca::{
         idexchange,
         idexchange::{CaHandle, ChildHandle, ParentHandle, PublisherHandle},
+        provisioning::ResourceClassName,
     },
+    crypto::KeyIdentifier,
     repository::resources::ResourceSet,
     uri,
 };
@@ -19,37 +27,72 @@ use crate::{
     commons::{
         actor::{Actor, ActorDef},
         api::{
-            self, AddChildRequest, AllCertAuthIssues, AspaCustomer, AspaDefinitionList, AspaDefinitionUpdates,
-            AspaProvidersUpdate, BgpSecCsrInfoList, BgpSecDefinitionUpdates, CaCommandDetails, CaRepoDetails,
-            CertAuthInfo, CertAuthInit, CertAuthIssues, CertAuthList, CertAuthStats, ChildCaInfo,
-            ChildrenConnectionStats, CommandHistory, CommandHistoryCriteria, ConfiguredRoa, IdCertInfo,
-            ParentCaContact, ParentCaReq, PublicationServerUris, PublisherDetails, ReceivedCert,
-            RepoFileDeleteCriteria, RepositoryContact, RoaConfiguration, RoaConfigurationUpdates, RoaPayload, RtaList,
-            RtaName, RtaPrepResponse, ServerInfo, Timestamp, UpdateChildRequest,
+            self, AddChildRequest, AggregateSnapshots, AllCertAuthIssues, AspaCustomer, AspaDefinitionList,
+            AspaDefinitionUpdates, AspaProvidersUpdate, AuditEvent, AuditEventList, BackupInfo, BackupList,
+            BgpSecCsrInfoList, BgpSecDefinitionUpdates, BulkOperation, CaCommandDetails, CaLabels, CaLabelsUpdate,
+            CaRepoDetails, CertAuthInfo, CertAuthInit, CertAuthIssues, CertAuthList, CertAuthStats, ChildCaInfo,
+            ChildIssuancePolicy, ChildrenConnectionStats, ChildrenStats, ClassRepoDetails, CommandHistory,
+            CommandHistoryCriteria, CommandRevert, ConfigReloadReport, ConfiguredRoa, DoctorFinding, DoctorReport,
+            HaStatus, IdCertInfo, IssuanceTimingOverrides, IssuanceTimingReport, Job, JobList, KeyGcReport,
+            KeyInventory, KeyOwner, KeyUsage, ObjectsExpiry, ParentCaContact, ParentCaReq, ParentExchangeLog,
+            ParentResponseFetch, ParentsStats, PendingChildRequestKind, PendingChildRequests, PublicCaStatus,
+            PublicationCheck, PublicationServerUris, PublisherDetails, PublisherQuota, PublisherQuotaInfo,
+            PublisherStatsInfo, PublisherStatsList, PublisherValidation, PublisherValidationInfo, ReceivedCert,
+            Reconciliation, RepoFileDeleteCriteria, RepoMigrationReport, RepoMigrationStatus, RepoStatuses,
+            RepositoryContact, ResourceTransfer, ResourceTransferList, ResourceTransferRequest, RetryPolicies,
+            RetryPolicy, RoaAutoPilotInfo, RoaAutoPilotPolicy, RoaConfiguration, RoaConfigurationUpdates, RoaImport,
+            RoaImportReport, RoaIssuanceStrategy, RoaIssuanceStrategyInfo, RoaPayload, RoaProposalList, RtaList,
+            RtaName, RtaPrepResponse, ScheduledChangeList, ScheduledChangeRequest, SchemaVersions, SearchMatch,
+            SearchQuery, SearchResults, ServerInfo, SignerMigrationStatus, StoredKey, TaskList, TimeCheck, Timestamp,
+            UpdateChildRequest,
         },
-        bgp::{BgpAnalyser, BgpAnalysisReport, BgpAnalysisSuggestion},
-        crypto::KrillSignerBuilder,
+        bgp::{BgpAnalyser, BgpAnalysisImpact, BgpAnalysisReport, BgpAnalysisSuggestion},
+        crypto::{KrillSigner, KrillSignerBuilder},
         error::Error,
-        eventsourcing::CommandKey,
+        eventsourcing::{AggregateStoreError, CommandKey},
+        util::httpclient,
         KrillEmptyResult, KrillResult,
     },
     constants::*,
     daemon::{
-        auth::{providers::AdminTokenAuthProvider, Authorizer, LoggedInUser},
-        ca::{self, testbed_ca_handle, CaStatus, ResourceTaggedAttestation, RtaContentRequest, RtaPrepareRequest},
-        config::{AuthType, Config},
-        http::HttpResponse,
+        auth::{
+            common::permissions::Permission, providers::AdminTokenAuthProvider, AuthFailureMetrics, Authorizer,
+            LoggedInUser, PublisherResource,
+        },
+        backup::BackupManager,
+        ca::{
+            self, testbed_ca_handle, AspaUpdateDryRun, CaStatus, RepublishMetrics, ResourceTaggedAttestation,
+            RpkiSignedChecklist, RscContentRequest, RtaContentRequest, RtaPrepareRequest,
+        },
+        config::Config,
+        ha::HaManager,
+        http::{acme::AcmeChallenges, ratelimit::RateLimiter, HttpResponse},
+        jobs::{self, JobStore},
         mq::TaskQueue,
+        notify::{EventStream, LiveEvent},
         scheduler::Scheduler,
-        ta::{ta_handle, TaCertDetails, TA_NAME},
+        ta::{ta_handle, TaCertDetails, TrustAnchorLocator, TrustAnchorTalCheck, TA_NAME},
+        timecheck::TimeChecker,
+    },
+    pubd::{
+        RepoStats, RepositoryArchiveEntry, RepositoryArchiveList, RepositoryManager, RepositoryReplicaStatus, RrdpStats,
     },
-    pubd::{RepoStats, RepositoryManager},
+    upgrades,
 };
 
 #[cfg(feature = "multi-user")]
-use crate::daemon::auth::{
-    common::session::LoginSessionCache,
-    providers::{ConfigFileAuthProvider, OpenIDConnectAuthProvider},
+use crate::commons::{
+    api::{ApiTokenCreated, ApiTokenList, ApiTokenRequest, CaDelegationRequest},
+    eventsourcing::KeyValueStore,
+};
+#[cfg(feature = "multi-user")]
+use crate::daemon::{
+    auth::{
+        common::session::LoginSessionCache,
+        providers::{ConfigFileAuthProvider, LdapAuthProvider, OpenIDConnectAuthProvider},
+        AuthProvider, LoginMethod,
+    },
+    config::AuthType,
 };
 
 use super::{
@@ -76,12 +119,36 @@ pub struct KrillServer {
     // Handles the internal TA and/or CAs
     ca_manager: Arc<ca::CaManager>,
 
+    // Signs on behalf of the CAs and the publication server
+    signer: Arc<KrillSigner>,
+
     // Handles the internal TA and/or CAs
     bgp_analyser: Arc<BgpAnalyser>,
 
+    // Creates and restores backups
+    backup_manager: BackupManager,
+
+    // Jobs for operations which can take long, run in the background
+    jobs: JobStore,
+
+    // Leader election, if running in high availability mode
+    ha_manager: Option<Arc<HaManager>>,
+
+    // Compares the system time with the configured time sources, if any
+    time_checker: Option<Arc<TimeChecker>>,
+
     // Shared message queue
     mq: Arc<TaskQueue>,
 
+    // Shared stream of events for connected clients
+    events: Arc<EventStream>,
+
+    // Responses to pending ACME challenges for the HTTPS certificate
+    acme_challenges: AcmeChallenges,
+
+    // Limits the request rate per client and concurrent expensive operations
+    rate_limiter: RateLimiter,
+
     // Time this server was started
     started: Timestamp,
 
@@ -92,7 +159,8 @@ pub struct KrillServer {
     // System actor
     system_actor: Actor,
 
-    pub config: Arc<Config>,
+    // The configuration, which can be partially reloaded
+    config: RwLock<Arc<Config>>,
 }
 
 /// # Set up and initialization
@@ -110,53 +178,101 @@ impl KrillServer {
         // Config::resolve() has been used to update signer name references to resolve to the corresponding signer
         // configurations.
         let probe_interval = std::time::Duration::from_secs(config.signer_probe_retry_seconds);
+        let signer_storage = config.signer_storage();
         let signer = KrillSignerBuilder::new(work_dir, probe_interval, &config.signers)
             .with_default_signer(config.default_signer())
             .with_one_off_signer(config.one_off_signer())
+            .with_ca_key_format(config.ca_key_algorithm.public_key_format())
+            .with_serial_number_strategy(config.serial_number_strategy)
+            .with_storage(&signer_storage)
             .build()?;
         let signer = Arc::new(signer);
 
+        // Check the system time before anything is issued, so that issuance
+        // can be refused if the time is off.
+        let time_checker = match config.time_check.as_ref() {
+            Some(time_check) => {
+                let time_checker = TimeChecker::new(time_check.clone());
+                time_checker.check().await;
+                Some(Arc::new(time_checker))
+            }
+            None => None,
+        };
+
         #[cfg(feature = "multi-user")]
-        let login_session_cache = Arc::new(LoginSessionCache::new());
+        let login_session_cache =
+            Arc::new(LoginSessionCache::new().with_store(KeyValueStore::disk(&config.data_dir, STATUS_DIR)?)?);
+        #[cfg(feature = "multi-user")]
+        LoginSessionCache::spawn_sweeper(&login_session_cache);
 
         // Construct the authorizer used to verify API access requests and to
         // tell Lagosta where to send end-users to login and logout.
-        // TODO: remove the ugly duplication, however attempts to do so have so
-        // far failed due to incompatible match arm types, or unknown size of
-        // dyn AuthProvider, or concrete type needs to be known in async fn,
-        // etc.
-        let authorizer = match config.auth_type {
-            AuthType::AdminToken => {
-                Authorizer::new(config.clone(), AdminTokenAuthProvider::new(config.clone()).into())?
+        #[cfg(not(feature = "multi-user"))]
+        let authorizer = Authorizer::new(config.clone(), AdminTokenAuthProvider::new(config.clone()).into())?;
+
+        #[cfg(feature = "multi-user")]
+        let authorizer = {
+            let mut additional_providers = vec![];
+            for auth_type in config.auth_additional_types.iter() {
+                additional_providers.push(Self::auth_provider(
+                    *auth_type,
+                    config.clone(),
+                    login_session_cache.clone(),
+                )?);
             }
-            #[cfg(feature = "multi-user")]
-            AuthType::ConfigFile => Authorizer::new(
-                config.clone(),
-                ConfigFileAuthProvider::new(config.clone(), login_session_cache.clone())?.into(),
-            )?,
-            #[cfg(feature = "multi-user")]
-            AuthType::OpenIDConnect => Authorizer::new(
+
+            Authorizer::new(
                 config.clone(),
-                OpenIDConnectAuthProvider::new(config.clone(), login_session_cache.clone())?.into(),
-            )?,
+                Self::auth_provider(config.auth_type, config.clone(), login_session_cache.clone())?,
+            )?
+            .with_additional_providers(additional_providers)
         };
+
         let system_actor = authorizer.actor_from_def(ACTOR_DEF_KRILL);
 
         // Used to have a shared queue for the ca_manager, repo_manager and the background job scheduler.
         let mq = Arc::new(TaskQueue::default());
 
+        // Shared stream of CA and publication server events for connected clients.
+        let events = Arc::new(EventStream::default());
+
         // for now, support that existing embedded repositories are still supported.
         // this should be removed in future after people have had a chance to separate.
-        let repo_manager = Arc::new(RepositoryManager::build(config.clone(), mq.clone(), signer.clone())?);
-
-        let ca_manager =
-            Arc::new(ca::CaManager::build(config.clone(), mq.clone(), signer, system_actor.clone()).await?);
+        let repo_manager = Arc::new(RepositoryManager::build(
+            config.clone(),
+            mq.clone(),
+            events.clone(),
+            signer.clone(),
+        )?);
+
+        let ca_manager = Arc::new(
+            ca::CaManager::build(
+                config.clone(),
+                mq.clone(),
+                events.clone(),
+                signer.clone(),
+                system_actor.clone(),
+            )
+            .await?,
+        );
 
         let bgp_analyser = Arc::new(BgpAnalyser::new(
             config.bgp_risdumps_enabled,
             &config.bgp_risdumps_v4_uri,
             &config.bgp_risdumps_v6_uri,
-        ));
+            &config.data_dir,
+        )?);
+
+        let ha_manager = match config.ha.as_ref() {
+            Some(ha) => {
+                let ha_manager = HaManager::build(ha.clone(), &config.storage())?;
+                if !ha_manager.renew_lease()? {
+                    info!("Starting as standby instance '{}'", ha.instance);
+                }
+                Some(Arc::new(ha_manager))
+            }
+            None => None,
+        };
 
         mq.server_started();
 
@@ -164,15 +280,23 @@ impl KrillServer {
             service_uri,
             work_dir: work_dir.clone(),
             authorizer,
-            repo_manager,
-            ca_manager,
+            repo_manager: repo_manager.clone(),
+            ca_manager: ca_manager.clone(),
+            signer,
             bgp_analyser,
+            backup_manager: BackupManager::new(&config),
+            jobs: JobStore::create(&config.storage())?,
+            ha_manager,
+            time_checker,
             mq,
+            events,
+            acme_challenges: AcmeChallenges::default(),
+            rate_limiter: RateLimiter::new(config.rate_limit.clone()),
             started: Timestamp::now(),
             #[cfg(feature = "multi-user")]
             login_session_cache,
             system_actor,
-            config: config.clone(),
+            config: RwLock::new(config.clone()),
         };
 
         // Check if we need to do any testbed or benchmarking set up.
@@ -246,23 +370,26 @@ impl KrillServer {
                     testbed.publication_server_uris(),
                     import_cas,
                 );
-                server.cas_import(startup_structure).await?;
+                // The benchmark ROAs are generated for testing, so do not
+                // refuse them because of their impact on announcements.
+                server.cas_import(startup_structure, true).await?;
             }
         }
 
         Ok(server)
     }
 
-    pub fn build_scheduler(&self) -> Scheduler {
+    pub fn build_scheduler(self: &Arc<Self>) -> KrillResult<Scheduler> {
         Scheduler::build(
             self.mq.clone(),
             self.ca_manager.clone(),
             self.repo_manager.clone(),
             self.bgp_analyser.clone(),
-            #[cfg(feature = "multi-user")]
-            self.login_session_cache.clone(),
-            self.config.clone(),
+            self.ha_manager.clone(),
+            self.time_checker.clone(),
             self.system_actor.clone(),
+            self.authorizer.actor_from_def(ACTOR_DEF_ROA_AUTOPILOT),
+            Arc::downgrade(self),
         )
     }
 
@@ -281,6 +408,20 @@ impl KrillServer {
         &self.system_actor
     }
 
+    #[cfg(feature = "multi-user")]
+    fn auth_provider(
+        auth_type: AuthType,
+        config: Arc<Config>,
+        login_session_cache: Arc<LoginSessionCache>,
+    ) -> KrillResult<AuthProvider> {
+        Ok(match auth_type {
+            AuthType::AdminToken => AdminTokenAuthProvider::new(config).into(),
+            AuthType::ConfigFile => ConfigFileAuthProvider::new(config, login_session_cache)?.into(),
+            AuthType::OpenIDConnect => OpenIDConnectAuthProvider::new(config, login_session_cache)?.into(),
+            AuthType::Ldap => LdapAuthProvider::new(config, login_session_cache)?.into(),
+        })
+    }
+
     pub async fn actor_from_request(&self, request: &hyper::Request<hyper::Body>) -> Actor {
         self.authorizer.actor_from_request(request).await
     }
@@ -289,8 +430,13 @@ impl KrillServer {
         self.authorizer.actor_from_def(actor_def)
     }
 
-    pub async fn get_login_url(&self) -> KrillResult<HttpResponse> {
-        self.authorizer.get_login_url().await
+    pub async fn get_login_url(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<HttpResponse> {
+        self.authorizer.get_login_url(request).await
+    }
+
+    #[cfg(feature = "multi-user")]
+    pub fn login_methods(&self) -> Vec<LoginMethod> {
+        self.authorizer.login_methods()
     }
 
     pub async fn login(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<LoggedInUser> {
@@ -301,6 +447,11 @@ impl KrillServer {
         self.authorizer.logout(request).await
     }
 
+    #[cfg(feature = "multi-user")]
+    pub async fn backchannel_logout(&self, logout_token: &str) -> KrillResult<()> {
+        self.authorizer.backchannel_logout(logout_token).await
+    }
+
     pub fn testbed_enabled(&self) -> bool {
         self.ca_manager.testbed_enabled()
     }
@@ -311,6 +462,235 @@ impl KrillServer {
     }
 }
 
+/// # Manage API tokens
+#[cfg(feature = "multi-user")]
+impl KrillServer {
+    pub fn api_tokens_list(&self) -> ApiTokenList {
+        self.authorizer.api_tokens().list()
+    }
+
+    pub fn api_token_create(&self, request: ApiTokenRequest, actor: &Actor) -> KrillResult<ApiTokenCreated> {
+        let org = actor.attribute("org".to_string());
+        self.authorizer.api_tokens().create(request, actor.name(), org)
+    }
+
+    pub fn api_token_revoke(&self, name: &str) -> KrillResult<()> {
+        self.authorizer.api_tokens().revoke(name)
+    }
+
+    /// Lists the API tokens which delegate the management of the ROAs of
+    /// the CA.
+    pub async fn ca_delegations(&self, ca: &CaHandle) -> KrillResult<ApiTokenList> {
+        self.ca_manager.get_ca(ca).await?;
+        Ok(self.authorizer.api_tokens().list_delegated_for(ca))
+    }
+
+    /// Creates an API token which can only be used to see the CA and manage
+    /// its ROAs.
+    pub async fn ca_delegation_create(
+        &self,
+        ca: &CaHandle,
+        request: CaDelegationRequest,
+        actor: &Actor,
+    ) -> KrillResult<ApiTokenCreated> {
+        self.ca_manager.get_ca(ca).await?;
+        self.api_token_create(request.into_token_request(ca.clone()), actor)
+    }
+
+    pub fn ca_delegation_revoke(&self, ca: &CaHandle, name: &str) -> KrillResult<()> {
+        self.authorizer.api_tokens().revoke_delegated_for(name, ca)
+    }
+}
+
+/// # Audit log
+impl KrillServer {
+    pub fn audit(&self, event: AuditEvent) {
+        self.authorizer.audit(event)
+    }
+
+    pub fn auth_failures(&self) -> &AuthFailureMetrics {
+        self.authorizer.auth_failures()
+    }
+
+    pub fn audit_events(
+        &self,
+        rows: usize,
+        offset: usize,
+        after: Option<Timestamp>,
+        before: Option<Timestamp>,
+    ) -> KrillResult<AuditEventList> {
+        self.authorizer.audit_log()?.events(rows, offset, after, before)
+    }
+
+    pub fn audit_export(&self, after: Option<Timestamp>, before: Option<Timestamp>) -> KrillResult<String> {
+        self.authorizer.audit_log()?.export(after, before)
+    }
+}
+
+/// # Snapshots
+impl KrillServer {
+    /// Saves snapshots for all CAs and the Publication Server, also if they
+    /// did not change since their last snapshot.
+    pub fn save_snapshots(&self) -> KrillResult<AggregateSnapshots> {
+        let mut aggregates = self.ca_manager.save_snapshots(true)?;
+        aggregates.append(&mut self.repo_manager.save_snapshots(true)?);
+        Ok(AggregateSnapshots::new(aggregates))
+    }
+
+    /// Verifies that the snapshots for all CAs and the Publication Server are
+    /// equivalent to the state rebuilt from their events.
+    pub fn verify_snapshots(&self) -> KrillResult<AggregateSnapshots> {
+        let mut aggregates = self.ca_manager.verify_snapshots()?;
+        aggregates.append(&mut self.repo_manager.verify_snapshots()?);
+        Ok(AggregateSnapshots::new(aggregates))
+    }
+}
+
+/// # Configuration
+impl KrillServer {
+    pub fn config(&self) -> Arc<Config> {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Reloads the config file. Changed settings which can be used while
+    /// running are applied, all other changed settings are reported as
+    /// requiring a restart.
+    ///
+    /// The new config is used by the CA and repository managers, and by the
+    /// scheduler through the CA manager. The authorizer keeps the config it
+    /// was built with, so changes to the authentication settings always
+    /// require a restart.
+    pub async fn reload_config(&self) -> KrillResult<ConfigReloadReport> {
+        let (config, report) = self.config().reload().map_err(|e| Error::ConfigError(e.to_string()))?;
+        let config = Arc::new(config);
+
+        config.apply_log_level();
+        self.ca_manager.update_config(config.clone());
+        self.repo_manager.update_config(config.clone());
+        self.bgp_analyser
+            .update_config(
+                config.bgp_risdumps_enabled,
+                &config.bgp_risdumps_v4_uri,
+                &config.bgp_risdumps_v6_uri,
+            )
+            .await;
+        *self.config.write().unwrap() = config;
+
+        info!("Reloaded config file. {}", report);
+        Ok(report)
+    }
+}
+
+/// # Scheduled tasks
+impl KrillServer {
+    pub fn tasks(&self) -> TaskList {
+        self.mq.list()
+    }
+
+    pub fn task_trigger(&self, name: &str) -> KrillResult<()> {
+        self.mq.trigger(name)
+    }
+
+    pub fn task_pause(&self, name: &str) -> KrillResult<()> {
+        self.mq.pause(name)
+    }
+
+    pub fn task_resume(&self, name: &str) -> KrillResult<()> {
+        self.mq.resume(name)
+    }
+}
+
+/// # Event stream
+impl KrillServer {
+    /// Returns a receiver for all CA and publication server events from
+    /// now on. Callers should filter the events the actor may see.
+    pub fn events_subscribe(&self) -> tokio::sync::broadcast::Receiver<LiveEvent> {
+        self.events.subscribe()
+    }
+}
+
+/// # HTTPS certificate
+impl KrillServer {
+    /// Returns the responses to pending ACME challenges, which are answered
+    /// while the HTTPS certificate is obtained or renewed.
+    pub fn acme_challenges(&self) -> &AcmeChallenges {
+        &self.acme_challenges
+    }
+}
+
+/// # Rate limiting
+impl KrillServer {
+    /// Returns the limiter for the request rate per client and the number of
+    /// concurrent expensive operations.
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+}
+
+/// # High availability
+impl KrillServer {
+    /// Returns true if this is the standby instance in high availability
+    /// mode, which may not make any changes.
+    pub fn ha_is_standby(&self) -> bool {
+        self.ha_manager.as_ref().map(|ha| !ha.is_active()).unwrap_or(false)
+    }
+
+    pub fn ha_status(&self) -> KrillResult<HaStatus> {
+        self.ha_manager
+            .as_ref()
+            .map(|ha| ha.status())
+            .ok_or(Error::ApiHaNotConfigured)
+    }
+
+    /// Promotes this instance to be the active instance. The scheduler picks
+    /// this up and takes over the scheduled tasks.
+    pub async fn ha_promote(&self) -> KrillResult<HaStatus> {
+        let ha = self.ha_manager.clone().ok_or(Error::ApiHaNotConfigured)?;
+        tokio::task::spawn_blocking(move || ha.promote())
+            .await
+            .map_err(|e| Error::custom(format!("Promotion task failed: {}", e)))?
+    }
+}
+
+/// # Time check
+impl KrillServer {
+    /// Returns the result of the last comparison of the system time with the
+    /// configured time sources.
+    pub fn time_check_status(&self) -> KrillResult<Option<TimeCheck>> {
+        self.time_checker
+            .as_ref()
+            .map(|time_checker| time_checker.status())
+            .ok_or(Error::ApiTimeCheckNotConfigured)
+    }
+
+    /// Compares the system time with the configured time sources now.
+    pub async fn time_check(&self) -> KrillResult<TimeCheck> {
+        let time_checker = self.time_checker.as_ref().ok_or(Error::ApiTimeCheckNotConfigured)?;
+        Ok(time_checker.check().await)
+    }
+}
+
+/// # Backups
+impl KrillServer {
+    /// Creates a backup of all Krill state, while the server keeps running.
+    pub fn create_backup(&self) -> KrillResult<BackupInfo> {
+        let mut backup = self.backup_manager.start()?;
+        self.ca_manager.export(&mut backup)?;
+        self.repo_manager.export(&mut backup)?;
+        self.backup_manager.finish(backup)
+    }
+
+    pub fn backups(&self) -> KrillResult<BackupList> {
+        self.backup_manager.list()
+    }
+
+    /// Stages the restore of a backup, which is applied when Krill is next
+    /// started.
+    pub fn restore_backup(&self, name: &str, force: bool) -> KrillResult<BackupInfo> {
+        self.backup_manager.stage_restore(name, force)
+    }
+}
+
 /// # Configure publishers
 impl KrillServer {
     /// Returns the repository server stats
@@ -318,9 +698,28 @@ impl KrillServer {
         self.repo_manager.repo_stats()
     }
 
-    /// Returns all current publishers.
-    pub fn publishers(&self) -> KrillResult<Vec<PublisherHandle>> {
-        self.repo_manager.publishers()
+    /// Returns the stats for the RRDP files of the repository.
+    pub fn rrdp_stats(&self) -> KrillResult<RrdpStats> {
+        self.repo_manager.rrdp_stats()
+    }
+
+    /// Returns all current publishers which the actor may see.
+    pub fn publishers(&self, actor: &Actor) -> KrillResult<Vec<PublisherHandle>> {
+        Ok(self
+            .repo_manager
+            .publishers()?
+            .into_iter()
+            .filter(|publisher| self.publisher_visible(publisher, actor))
+            .collect())
+    }
+
+    /// Returns true if the actor may see the publisher. Users who belong to
+    /// an organization can only see the publishers owned by it.
+    pub fn publisher_visible(&self, publisher: &PublisherHandle, actor: &Actor) -> bool {
+        matches!(
+            actor.is_allowed(Permission::PUB_READ, PublisherResource::from(publisher)),
+            Ok(true)
+        )
     }
 
     /// Adds the publishers, blows up if it already existed.
@@ -349,6 +748,64 @@ impl KrillServer {
         self.repo_manager.get_publisher_details(publisher)
     }
 
+    /// Returns the content stats and publication activity for a publisher.
+    pub fn publisher_stats(&self, publisher: &PublisherHandle) -> KrillResult<PublisherStatsInfo> {
+        self.repo_manager.publisher_stats(publisher)
+    }
+
+    /// Returns the content stats and publication activity for all publishers.
+    pub fn publishers_stats(&self) -> KrillResult<PublisherStatsList> {
+        self.repo_manager.publishers_stats()
+    }
+
+    /// Returns the quota and current usage for a publisher.
+    pub fn publisher_quota(&self, publisher: &PublisherHandle) -> KrillResult<PublisherQuotaInfo> {
+        self.repo_manager.publisher_quota(publisher)
+    }
+
+    /// Sets the quota for a publisher, or reverts to the configured default if `None` is given.
+    pub fn update_publisher_quota(
+        &self,
+        publisher: PublisherHandle,
+        quota: Option<PublisherQuota>,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.repo_manager.update_publisher_quota(publisher, quota, actor)
+    }
+
+    /// Returns the validation of published content for a publisher.
+    pub fn publisher_validation(&self, publisher: &PublisherHandle) -> KrillResult<PublisherValidationInfo> {
+        self.repo_manager.publisher_validation(publisher)
+    }
+
+    /// Sets the validation of published content for a publisher, or reverts to the configured
+    /// default if `None` is given.
+    pub fn update_publisher_validation(
+        &self,
+        publisher: PublisherHandle,
+        validation: Option<PublisherValidation>,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.repo_manager
+            .update_publisher_validation(publisher, validation, actor)
+    }
+
+    /// Stages a new ID certificate for a publisher, taken from an RFC 8183 Publisher Request,
+    /// or cancels the rollover if `None` is given.
+    pub fn stage_publisher_id_cert(
+        &self,
+        publisher: PublisherHandle,
+        req: Option<idexchange::PublisherRequest>,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.repo_manager.stage_publisher_id_cert(publisher, req, actor)
+    }
+
+    /// Replaces the current ID certificate of a publisher with the staged one.
+    pub fn activate_publisher_id_cert(&self, publisher: PublisherHandle, actor: &Actor) -> KrillEmptyResult {
+        self.repo_manager.activate_publisher_id_cert(publisher, actor)
+    }
+
     pub fn rrdp_base_path(&self) -> PathBuf {
         let mut path = self.work_dir.clone();
         path.push("repo/rrdp");
@@ -372,7 +829,7 @@ impl KrillServer {
 ///
 impl KrillServer {
     pub fn ta_proxy_enabled(&self) -> bool {
-        self.config.ta_proxy_enabled()
+        self.config().ta_proxy_enabled()
     }
 
     pub async fn ta_proxy_init(&self) -> KrillResult<()> {
@@ -422,7 +879,12 @@ impl KrillServer {
     ) -> KrillResult<idexchange::ParentResponse> {
         // TA as parent is handled a special case in the following
         self.ca_manager
-            .ca_add_child(&ta_handle().convert(), child_request, &self.config.service_uri(), actor)
+            .ca_add_child(
+                &ta_handle().convert(),
+                child_request,
+                &self.config().service_uri(),
+                actor,
+            )
             .await
     }
 
@@ -434,6 +896,20 @@ impl KrillServer {
     pub async fn trust_anchor_cert(&self) -> Option<ReceivedCert> {
         self.ta_cert_details().await.ok().map(|details| details.into())
     }
+
+    /// Returns the TAL for the current TA certificate.
+    pub async fn ta_tal(&self) -> KrillResult<TrustAnchorLocator> {
+        self.ta_cert_details().await.map(|details| details.current_tal())
+    }
+
+    /// Verifies that the TAL matches the TA certificate and the locations
+    /// where it is published.
+    pub async fn ta_tal_check(&self) -> KrillResult<TrustAnchorTalCheck> {
+        let proxy = self.ca_manager.get_trust_anchor_proxy().await?;
+        let details = proxy.get_ta_details()?;
+        let repo_info = proxy.repository().map(|contact| contact.repo_info());
+        Ok(details.tal_check(repo_info, &self.config().service_uri()))
+    }
 }
 
 /// # Being a parent
@@ -450,6 +926,22 @@ impl KrillServer {
         self.ca_manager.ca_add_child(ca, req, &self.service_uri, actor).await
     }
 
+    /// Adds a child to the testbed CA, if it claims resources within the
+    /// limits configured for the testbed.
+    pub async fn testbed_add_child(
+        &self,
+        req: AddChildRequest,
+        actor: &Actor,
+    ) -> KrillResult<idexchange::ParentResponse> {
+        let ca = testbed_ca_handle();
+        if let Some(testbed) = self.config().testbed() {
+            testbed
+                .check_child_resources(req.resources())
+                .map_err(|reason| Error::CaChildResourcesNotAllowed(ca.clone(), req.handle().clone(), reason))?;
+        }
+        self.ca_add_child(&ca, req, actor).await
+    }
+
     /// Shows the parent contact for a child.
     pub async fn ca_parent_contact(&self, ca: &CaHandle, child: ChildHandle) -> KrillResult<ParentCaContact> {
         self.ca_manager.ca_parent_contact(ca, child, &self.service_uri).await
@@ -487,6 +979,94 @@ impl KrillServer {
         Ok(child)
     }
 
+    /// Returns the policy enforced before certificates are issued to children.
+    pub async fn ca_child_issuance_policy(&self, ca: &CaHandle) -> KrillResult<ChildIssuancePolicy> {
+        let ca = self.ca_manager.get_ca(ca).await?;
+        Ok(ca.child_issuance_policy().clone())
+    }
+
+    pub async fn ca_child_issuance_policy_update(
+        &self,
+        ca: &CaHandle,
+        policy: ChildIssuancePolicy,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_child_issuance_policy_update(ca, policy, actor).await
+    }
+
+    /// Returns the requests from a child which are awaiting approval.
+    pub async fn ca_child_requests(&self, ca: &CaHandle, child: &ChildHandle) -> KrillResult<PendingChildRequests> {
+        self.ca_manager.ca_child_requests(ca, child).await
+    }
+
+    /// Approves a pending request from a child.
+    pub async fn ca_child_request_approve(
+        &self,
+        ca: &CaHandle,
+        child: ChildHandle,
+        kind: PendingChildRequestKind,
+        ki: KeyIdentifier,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager
+            .ca_child_request_approve(ca, child, kind, ki, actor)
+            .await
+    }
+
+    /// Starts a transfer of resources between two children of the CA.
+    pub async fn ca_resource_transfer_start(
+        &self,
+        ca: &CaHandle,
+        request: ResourceTransferRequest,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_resource_transfer_start(ca, request, actor).await
+    }
+
+    /// Returns all resource transfers between children of the CA.
+    pub async fn ca_resource_transfers(&self, ca: &CaHandle) -> KrillResult<ResourceTransferList> {
+        let ca = self.ca_manager.get_ca(ca).await?;
+        Ok(ca.resource_transfers())
+    }
+
+    /// Returns the resource transfer with the given id.
+    pub async fn ca_resource_transfer(&self, handle: &CaHandle, id: u64) -> KrillResult<ResourceTransfer> {
+        let ca = self.ca_manager.get_ca(handle).await?;
+        ca.resource_transfer(id)
+            .cloned()
+            .ok_or_else(|| Error::CaResourceTransferUnknown(handle.clone(), id))
+    }
+
+    /// Schedules a ROA or ASPA update for the CA.
+    pub async fn ca_scheduled_change_add(
+        &self,
+        ca: &CaHandle,
+        request: ScheduledChangeRequest,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_scheduled_change_add(ca, request, actor).await
+    }
+
+    /// Returns the pending scheduled changes for the CA.
+    pub async fn ca_scheduled_changes(&self, ca: &CaHandle) -> KrillResult<ScheduledChangeList> {
+        let ca = self.ca_manager.get_ca(ca).await?;
+        Ok(ca.scheduled_changes())
+    }
+
+    /// Cancels a pending scheduled change for the CA.
+    pub async fn ca_scheduled_change_cancel(&self, ca: &CaHandle, id: u64, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ca_scheduled_change_cancel(ca, id, actor).await
+    }
+
+    /// Show the consolidated stats for all children under the CA.
+    pub async fn ca_stats_children(&self, ca: &CaHandle) -> KrillResult<ChildrenStats> {
+        self.ca_manager.ca_stats_children(ca).await
+    }
+
+    pub async fn ca_stats_parents(&self, ca: &CaHandle) -> KrillResult<ParentsStats> {
+        self.ca_manager.ca_stats_parents(ca).await
+    }
+
     /// Show children stats under the CA.
     pub async fn ca_stats_child_connections(&self, ca: &CaHandle) -> KrillResult<ChildrenConnectionStats> {
         self.ca_manager
@@ -524,6 +1104,59 @@ impl KrillServer {
         self.ca_manager.ca_parent_add_or_update(ca, parent_req, actor).await
     }
 
+    /// Fetches the RFC 8183 Parent Response XML for a parent from the given
+    /// location, and then adds or updates the parent using that response.
+    pub async fn ca_parent_fetch_and_add_or_update(
+        &self,
+        ca: CaHandle,
+        parent: ParentHandle,
+        fetch: ParentResponseFetch,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        info!(
+            "CA '{}' will fetch the response for parent '{}' from: {}",
+            ca, parent, fetch
+        );
+
+        // Only fetch from hosts which were explicitly allowed, so that this
+        // cannot be used to make Krill send requests to arbitrary (internal)
+        // services.
+        let host = url::Url::parse(fetch.uri().as_str())
+            .ok()
+            .and_then(|uri| uri.host_str().map(|host| host.to_string()));
+        let allowed = host.map_or(false, |host| {
+            self.config()
+                .parent_response_fetch_hosts
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(&host))
+        });
+        if !allowed {
+            return Err(Error::CaParentResponseFetch(
+                ca,
+                format!(
+                    "host of '{}' is not listed in 'parent_response_fetch_hosts'",
+                    fetch.uri()
+                ),
+            ));
+        }
+
+        let timeout = self.config().post_protocol_msg_timeout_seconds;
+        let xml = httpclient::get_text_no_redirects(fetch.uri().as_str(), fetch.token(), timeout)
+            .await
+            .map_err(|e| Error::CaParentResponseFetch(ca.clone(), e.to_string()))?;
+
+        let xml = xml.trim();
+        if xml.starts_with("<repository") {
+            return Err(Error::CaParentResponseWrongXml(ca));
+        }
+
+        let response = idexchange::ParentResponse::parse(xml.as_bytes())
+            .map_err(|e| Error::CaParentResponseInvalid(ca.clone(), e.to_string()))?;
+
+        self.ca_parent_add_or_update(ca, ParentCaReq::new(parent, response), actor)
+            .await
+    }
+
     pub async fn ca_parent_remove(&self, handle: CaHandle, parent: ParentHandle, actor: &Actor) -> KrillEmptyResult {
         self.ca_manager.ca_parent_remove(handle, parent, actor).await
     }
@@ -544,6 +1177,7 @@ impl KrillServer {
             if let Ok(ca) = self.ca_manager.get_ca(ca.handle()).await {
                 let roas = ca.configured_roas();
                 let roa_count = roas.len();
+                let aspa_count = ca.aspas_definitions_show().definitions().len();
                 let child_count = ca.children().count();
 
                 let bgp_report = if ca.handle().as_str() == "ta" || ca.handle().as_str() == "testbed" {
@@ -556,7 +1190,7 @@ impl KrillServer {
 
                 res.insert(
                     ca.handle().clone(),
-                    CertAuthStats::new(roa_count, child_count, bgp_report.into()),
+                    CertAuthStats::new(roa_count, aspa_count, child_count, bgp_report.into()),
                 );
             }
         }
@@ -564,7 +1198,9 @@ impl KrillServer {
         Ok(res)
     }
 
-    pub async fn cas_import(&self, structure: api::import::Structure) -> KrillResult<()> {
+    /// Imports CAs. The ROAs of the CAs are checked in the same way as a ROA
+    /// update before anything is imported, see [`KrillServer::ca_routes_update`].
+    pub async fn cas_import(&self, structure: api::import::Structure, force: bool) -> KrillResult<()> {
         let actor = Arc::new(self.system_actor().clone());
 
         // We need to know which CAs already exist. They should not be imported again,
@@ -576,6 +1212,7 @@ impl KrillServer {
             existing_cas.insert(parent_handle, resources);
         }
         structure.validate_ca_hierarchy(existing_cas)?;
+        self.cas_import_verify_impact(&structure, force).await?;
 
         if let Some(publication_server_uris) = structure.publication_server.clone() {
             info!("Initialising publication server");
@@ -583,7 +1220,7 @@ impl KrillServer {
         }
 
         if let Some(import_ta) = structure.ta.clone() {
-            if self.config.ta_proxy_enabled() && self.config.ta_signer_enabled() {
+            if self.config().ta_proxy_enabled() && self.config().ta_signer_enabled() {
                 info!("Creating embedded Trust Anchor");
                 let (ta_aia, ta_uris, ta_key_pem) = import_ta.unpack();
                 self.ca_manager
@@ -599,7 +1236,7 @@ impl KrillServer {
         info!("Bulk import {} CAs", structure.cas.len());
         // Set up each online TA child with local repo, do this in parallel.
         let mut import_fns = vec![];
-        let service_uri = Arc::new(self.config.service_uri());
+        let service_uri = Arc::new(self.config().service_uri());
         for ca in structure.into_cas() {
             import_fns.push(tokio::spawn(Self::import_ca(
                 ca,
@@ -616,30 +1253,353 @@ impl KrillServer {
         Ok(())
     }
 
-    async fn import_ca(
-        ca: api::import::ImportCa,
-        ca_manager: Arc<CaManager>,
-        repo_manager: Arc<RepositoryManager>,
-        service_uri: Arc<uri::Https>,
-        actor: Arc<Actor>,
-    ) -> KrillEmptyResult {
-        // outline:
-        // - init ca
-        // - set up under repo
-        // - set up under parent
-        // - wait for resources
-        // - recurse for children
-        let (ca_handle, parents, roas) = ca.unpack();
-        info!("Importing CA: '{}'", ca_handle);
-
-        // init CA
-        ca_manager.init_ca(&ca_handle)?;
+    /// Returns an error including the impact of the ROAs of an imported CA,
+    /// in case they would invalidate announcements seen in BGP. The CAs do
+    /// not exist yet, so their ROAs are compared to having no ROAs at all,
+    /// for the resources they get from their parents.
+    async fn cas_import_verify_impact(&self, structure: &api::import::Structure, force: bool) -> KrillEmptyResult {
+        if force || !self.config().roa_update_bgp_check {
+            return Ok(());
+        }
 
-        // Get Publisher Request
-        let pub_req = {
-            let ca = ca_manager.get_ca(&ca_handle).await?;
-            idexchange::PublisherRequest::new(ca.id_cert().base64().clone(), ca_handle.convert(), None)
-        };
+        for ca in &structure.cas {
+            let updates = RoaConfigurationUpdates::new(ca.roas().clone(), vec![]).into_explicit_max_length();
+            let roas: Vec<ConfiguredRoa> = updates
+                .added()
+                .iter()
+                .map(|roa| ConfiguredRoa::new(roa.clone(), vec![]))
+                .collect();
+
+            let impact = self
+                .bgp_analyser
+                .impact(&[], &roas, &ca.resources(), updates.affected_prefixes())
+                .await;
+            if impact.invalidates_announcements() {
+                return Err(Error::RoaUpdateInvalidatesAnnouncements(ca.handle().clone(), impact));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Migrates CAs from another RPKI CA implementation, or another Krill
+    /// instance. All CAs, and their identity keys, are checked before
+    /// anything is changed. The CAs are then set up one by one: using the
+    /// migrated identity key if given, under the repository and parents, and
+    /// with the ROAs, ASPAs and children for the resources received from the
+    /// parents.
+    ///
+    /// Setting up a CA can still fail, e.g. if its parent refuses it. The
+    /// migration then stops, and the report includes the CAs which were set
+    /// up, the CA which failed and the CAs which were not attempted.
+    pub async fn cas_migrate(
+        &self,
+        migration: api::import::CaMigration,
+        actor: &Actor,
+    ) -> KrillResult<api::import::CaMigrationReport> {
+        self.cas_migrate_check(&migration).await?;
+
+        let mut report = api::import::CaMigrationReport::default();
+        let mut cas = migration.into_cas().into_iter();
+        while let Some(ca) = cas.next() {
+            let handle = ca.handle().clone();
+            match self.ca_migrate(ca, actor).await {
+                Ok(migrated) => report.add(migrated),
+                Err(e) => {
+                    let exists = self.ca_exists(&handle)?;
+                    report.fail(
+                        handle,
+                        e.to_string(),
+                        exists,
+                        cas.map(|ca| ca.handle().clone()).collect(),
+                    );
+                    break;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Validates all CAs of a migration, and checks that none of them exist
+    /// and that their identity keys can be used.
+    pub async fn cas_migrate_check(&self, migration: &api::import::CaMigration) -> KrillEmptyResult {
+        migration.validate()?;
+        for ca in migration.cas() {
+            if self.ca_manager.has_ca(ca.handle())? {
+                return Err(Error::CaDuplicate(ca.handle().clone()));
+            }
+        }
+
+        let usages = self.key_usages().await?;
+        let mut keys = vec![];
+        for ca in migration.cas() {
+            if let Some(key) = ca.id_key() {
+                let key = self.ca_migrate_key_check(ca.handle(), key, &usages)?;
+                if keys.contains(&key) {
+                    return Err(Error::CaMigrationKeyInvalid(
+                        ca.handle().clone(),
+                        format!("key {} is used by more than one CA in this migration", key),
+                    ));
+                }
+                keys.push(key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the identifier of the identity key for a migrated CA, if the
+    /// key is not used by anything in this Krill instance. Otherwise the
+    /// migrated CA would take over the identity of e.g. another CA. A key
+    /// given by its identifier must be held by a signer, a key given as PEM
+    /// is imported later.
+    fn ca_migrate_key_check(
+        &self,
+        handle: &CaHandle,
+        key: &api::import::MigrateKey,
+        usages: &HashMap<KeyIdentifier, Vec<KeyUsage>>,
+    ) -> KrillResult<KeyIdentifier> {
+        let key_id = match key {
+            api::import::MigrateKey::Pem(pem) => KrillSigner::pem_key_identifier(pem)
+                .map_err(|e| Error::CaMigrationKeyInvalid(handle.clone(), e.to_string()))?,
+            api::import::MigrateKey::KeyId(key_id) => {
+                if self.signer.key_signer_name(key_id).is_none() {
+                    return Err(Error::CaMigrationKeyInvalid(
+                        handle.clone(),
+                        format!("key {} is not held by any signer", key_id),
+                    ));
+                }
+                *key_id
+            }
+        };
+
+        match usages.get(&key_id).and_then(|usages| usages.first()) {
+            Some(usage) => Err(Error::CaMigrationKeyInvalid(
+                handle.clone(),
+                format!("key {} is already used by {}", key_id, usage),
+            )),
+            None => Ok(key_id),
+        }
+    }
+
+    /// Migrates a single CA, see `cas_migrate`. The identity key is checked
+    /// again, because other CAs may have been set up since the migration
+    /// was checked.
+    pub async fn ca_migrate(&self, ca: api::import::MigrateCa, actor: &Actor) -> KrillResult<api::import::MigratedCa> {
+        let handle = ca.handle().clone();
+        info!("Migrating CA: '{}'", handle);
+
+        if let Some(key) = ca.id_key() {
+            let usages = self.key_usages().await?;
+            self.ca_migrate_key_check(&handle, key, &usages)?;
+        }
+
+        let id_key = match ca.id_key() {
+            Some(api::import::MigrateKey::Pem(pem)) => Some(self.signer.import_key(pem)?),
+            Some(api::import::MigrateKey::KeyId(key)) => Some(*key),
+            None => None,
+        };
+        self.ca_manager.init_ca_with_id_key(&handle, id_key.as_ref())?;
+
+        // The repository and parents are not contacted before they are added,
+        // because they will not recognise the CA if it has a new identity.
+        let repository = match ca.repository_contact()? {
+            Some(contact) => {
+                self.ca_manager
+                    .update_repo(self.repo_manager.as_ref(), handle.clone(), contact, false, actor)
+                    .await?;
+                true
+            }
+            None => false,
+        };
+
+        let mut parents = vec![];
+        for parent_req in ca.parent_requests()? {
+            let parent = parent_req.handle().clone();
+            self.ca_manager
+                .ca_parent_add_or_update(handle.clone(), parent_req, actor)
+                .await?;
+
+            // Get the entitlements, and then the certificates, so that the
+            // ROAs can be added. If this fails, then the scheduler will try
+            // again later, and the ROAs are reported as not held.
+            if repository {
+                for _ in 0..2 {
+                    if let Err(e) = self.ca_manager.ca_sync_parent(&handle, &parent, actor).await {
+                        warn!(
+                            "Migrated CA '{}' could not sync with parent '{}': {}",
+                            handle, parent, e
+                        );
+                        break;
+                    }
+                }
+            }
+            parents.push(parent);
+        }
+
+        let resources = self.ca_manager.get_ca(&handle).await?.all_resources();
+        let (roas_added, roas_not_held): (Vec<_>, Vec<_>) = ca
+            .roas()
+            .iter()
+            .cloned()
+            .partition(|roa| resources.contains_roa_address(&roa.payload().as_roa_ip_address()));
+
+        if !roas_added.is_empty() {
+            // The ROAs exist already, so do not refuse them because of their
+            // impact on announcements seen in BGP.
+            let updates = RoaConfigurationUpdates::new(roas_added.clone(), vec![]);
+            self.ca_routes_update(handle.clone(), updates, true, actor).await?;
+        }
+
+        let (aspas_added, aspas_not_held): (Vec<_>, Vec<_>) = ca
+            .aspas()
+            .iter()
+            .cloned()
+            .partition(|aspa| resources.contains_asn(aspa.customer()));
+
+        if !aspas_added.is_empty() {
+            let definitions = AspaDefinitionList::new(aspas_added.clone());
+            self.ca_aspas_definitions_import(handle.clone(), definitions, actor)
+                .await?;
+        }
+
+        let mut children_added = vec![];
+        let mut children_not_held = vec![];
+        for child_req in ca.child_requests()? {
+            let child = child_req.handle().clone();
+            if resources.contains(child_req.resources()) {
+                self.ca_add_child(&handle, child_req, actor).await?;
+                children_added.push(child);
+            } else {
+                children_not_held.push(child);
+            }
+        }
+
+        let id_key_used = self
+            .ca_manager
+            .get_ca(&handle)
+            .await?
+            .id_cert()
+            .public_key()
+            .key_identifier();
+
+        if let Some(id_cert) = ca.id_cert() {
+            if id_cert.public_key().key_identifier() != id_key_used {
+                warn!(
+                    "Migrated CA '{}' uses a different identity key than the exported identity certificate",
+                    handle
+                );
+            }
+        }
+
+        Ok(api::import::MigratedCa::new(
+            handle,
+            id_key_used,
+            id_key.is_some(),
+            repository,
+            parents,
+            resources,
+            roas_added,
+            roas_not_held,
+        )
+        .with_aspas(
+            aspas_added.iter().map(|aspa| aspa.customer()).collect(),
+            aspas_not_held.iter().map(|aspa| aspa.customer()).collect(),
+        )
+        .with_children(children_added, children_not_held))
+    }
+
+    /// Exports the configuration of a CA in the interchange format used to
+    /// migrate CAs, so that it can be imported into another Krill instance,
+    /// or used to rebuild the CA. The export includes the repository,
+    /// parents, ROAs and ASPAs. A full export also includes the resources
+    /// held, the children and the identity certificate. The private key of
+    /// the identity certificate is only included if asked for, and if the
+    /// key is held by the OpenSSL signer.
+    pub async fn ca_export(
+        &self,
+        handle: &CaHandle,
+        full: bool,
+        include_key: bool,
+    ) -> KrillResult<api::import::CaMigration> {
+        let ca = self.ca_manager.get_ca(handle).await?;
+
+        let mut export = api::import::MigrateCa::new(handle.clone())
+            .with_roas(
+                ca.configured_roas()
+                    .into_iter()
+                    .map(|roa| roa.roa_configuration().clone())
+                    .collect(),
+            )
+            .with_aspas(ca.aspas_definitions_show().unpack());
+
+        if let Ok(contact) = ca.repository_contact() {
+            export = export.with_repository(contact.clone());
+        }
+
+        for parent in ca.parents() {
+            let info = ca.parent(parent)?.parent_server_info();
+            let response = idexchange::ParentResponse::new(
+                info.id_cert().base64().clone(),
+                info.parent_handle().clone(),
+                info.child_handle().clone(),
+                info.service_uri().clone(),
+                None,
+            );
+            export = export.with_parent(api::import::MigrateParent::new(
+                parent.clone(),
+                response.to_xml_string(),
+            ));
+        }
+
+        if full {
+            export = export
+                .with_resources(ca.all_resources())
+                .with_id_cert(ca.id_cert().clone());
+
+            for child in ca.children() {
+                let details = ca.get_child(child)?;
+                let request = idexchange::ChildRequest::new(details.id_cert().base64().clone(), child.clone());
+                export = export.with_child(api::import::MigrateChild::new(
+                    child.clone(),
+                    request.to_xml_string(),
+                    details.resources().clone(),
+                ));
+            }
+        }
+
+        if include_key {
+            let key = ca.id_cert().public_key().key_identifier();
+            let pem = self.signer.export_key(&key)?;
+            export = export.with_id_key(api::import::MigrateKey::Pem(pem));
+        }
+
+        Ok(api::import::CaMigration::new(vec![export]))
+    }
+
+    async fn import_ca(
+        ca: api::import::ImportCa,
+        ca_manager: Arc<CaManager>,
+        repo_manager: Arc<RepositoryManager>,
+        service_uri: Arc<uri::Https>,
+        actor: Arc<Actor>,
+    ) -> KrillEmptyResult {
+        // outline:
+        // - init ca
+        // - set up under repo
+        // - set up under parent
+        // - wait for resources
+        // - recurse for children
+        let (ca_handle, parents, roas) = ca.unpack();
+        info!("Importing CA: '{}'", ca_handle);
+
+        // init CA
+        ca_manager.init_ca(&ca_handle)?;
+
+        // Get Publisher Request
+        let pub_req = {
+            let ca = ca_manager.get_ca(&ca_handle).await?;
+            idexchange::PublisherRequest::new(ca.id_cert().base64().clone(), ca_handle.convert(), None)
+        };
 
         // Add Publisher
         repo_manager.create_publisher(pub_req, &actor)?;
@@ -741,6 +1701,27 @@ impl KrillServer {
         Ok(())
     }
 
+    /// Searches all CAs the actor may read, and all publishers if the actor
+    /// may administer the publication server.
+    pub async fn search(&self, query: &SearchQuery, actor: &Actor) -> KrillResult<SearchResults> {
+        let mut matches = vec![];
+        for ca in self.ca_list(actor)?.cas() {
+            let ca = self.ca_manager.get_ca(ca.handle()).await?;
+            matches.append(&mut ca.search(query));
+        }
+
+        if matches!(actor.is_allowed(Permission::PUB_ADMIN, NO_RESOURCE), Ok(true)) {
+            for found in self.repo_manager.search(query)? {
+                match &found {
+                    SearchMatch::Publisher { publisher, .. } if !self.publisher_visible(publisher, actor) => {}
+                    _ => matches.push(found),
+                }
+            }
+        }
+
+        Ok(SearchResults::new(query, matches))
+    }
+
     pub async fn all_ca_issues(&self, actor: &Actor) -> KrillResult<AllCertAuthIssues> {
         let mut all_issues = AllCertAuthIssues::default();
         for ca in self.ca_list(actor)?.cas() {
@@ -772,6 +1753,328 @@ impl KrillServer {
     }
 }
 
+/// # Signer keys
+///
+impl KrillServer {
+    /// Returns the keys stored by the signers, with what uses them. Keys
+    /// held by HSMs cannot be listed and are not included.
+    pub async fn key_inventory(&self) -> KrillResult<KeyInventory> {
+        let mut usages = self.key_usages().await?;
+
+        let mut keys: Vec<StoredKey> = self
+            .signer
+            .stored_keys()?
+            .into_iter()
+            .map(|(signer, key_id, modified)| {
+                let modified = modified
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|since| Timestamp::new(since.as_secs() as i64));
+                let key_usages = usages.remove(&key_id).unwrap_or_default();
+                StoredKey::new(key_id, signer, modified, key_usages)
+            })
+            .collect();
+        keys.sort_by_key(|key| key.key_id().to_string());
+
+        Ok(KeyInventory::new(Timestamp::now(), keys))
+    }
+
+    /// Returns what uses each key, for all keys used by the CAs, the
+    /// Publication Server, the TA and the signers themselves.
+    async fn key_usages(&self) -> KrillResult<HashMap<KeyIdentifier, Vec<KeyUsage>>> {
+        let mut usages: HashMap<KeyIdentifier, Vec<KeyUsage>> = HashMap::new();
+
+        for ca in self.ca_list(&self.system_actor)?.cas() {
+            // Fail rather than miss the keys of a CA that cannot be loaded,
+            // or they would appear to be unreferenced.
+            let ca = self.ca_manager.get_ca(ca.handle()).await?;
+            for (key_id, usage) in ca.key_usages() {
+                usages.entry(key_id).or_default().push(usage);
+            }
+        }
+
+        if let Some(key_id) = self.repo_manager.id_key()? {
+            usages
+                .entry(key_id)
+                .or_default()
+                .push(KeyUsage::new(KeyOwner::PublicationServer, None, "id"));
+        }
+
+        // The TA proxy and signer may not have been initialised yet, but any
+        // other error must not make their keys appear to be unreferenced.
+        let config = self.config();
+        if config.ta_proxy_enabled() {
+            if let Some(proxy) = Self::unless_uninitialised(self.ca_manager.get_trust_anchor_proxy().await)? {
+                usages
+                    .entry(proxy.id().public_key().key_identifier())
+                    .or_default()
+                    .push(KeyUsage::new(KeyOwner::TaProxy, None, "id"));
+            }
+        }
+        if config.ta_signer_enabled() {
+            if let Some(signer) = Self::unless_uninitialised(self.ca_manager.get_trust_anchor_signer().await)? {
+                let info = signer.get_signer_info();
+                usages
+                    .entry(info.id.public_key().key_identifier())
+                    .or_default()
+                    .push(KeyUsage::new(KeyOwner::TaSigner, None, "id"));
+                usages
+                    .entry(info.ta_cert_details.cert().key_identifier())
+                    .or_default()
+                    .push(KeyUsage::new(KeyOwner::TaSigner, None, "TA certificate"));
+            }
+        }
+
+        for key_id in self.signer.binding_keys()? {
+            usages
+                .entry(key_id)
+                .or_default()
+                .push(KeyUsage::new(KeyOwner::Signer, None, "binding"));
+        }
+
+        Ok(usages)
+    }
+
+    fn unless_uninitialised<T>(res: KrillResult<T>) -> KrillResult<Option<T>> {
+        match res {
+            Ok(value) => Ok(Some(value)),
+            Err(Error::AggregateStoreError(AggregateStoreError::UnknownAggregate(_))) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Archives the stored keys which are not used by anything, if confirm
+    /// is true. Otherwise reports which keys would be archived. Keys which
+    /// were modified recently, or for which this is unknown, are skipped
+    /// because they may be in the process of being taken into use.
+    pub async fn keys_gc(&self, confirm: bool) -> KrillResult<KeyGcReport> {
+        let inventory = self.key_inventory().await?;
+        let threshold = Timestamp::now_minus_hours(KEYS_GC_MIN_AGE_HOURS as i64);
+        let (keys, skipped_recent) = inventory.gc_candidates(threshold);
+
+        if confirm {
+            for key in &keys {
+                self.signer.archive_key(key.key_id())?;
+                info!("Archived unreferenced key {} of signer {}", key.key_id(), key.signer());
+            }
+        }
+
+        Ok(KeyGcReport::new(confirm, keys, skipped_recent))
+    }
+}
+
+/// # Self-diagnosis
+///
+impl KrillServer {
+    /// Returns the Krill and schema versions which last used the stores, and
+    /// those of the running code.
+    pub fn schema_versions(&self) -> KrillResult<SchemaVersions> {
+        upgrades::schema_versions(&self.config())
+    }
+
+    /// Runs the checks for 'krillc doctor' which need access to the data
+    /// directory and signers, and to the status of the CAs which the actor
+    /// may see.
+    pub async fn doctor(&self, actor: &Actor) -> KrillResult<DoctorReport> {
+        let config = self.config();
+        let mut report = DoctorReport::new(Timestamp::now(), vec![]);
+
+        Self::doctor_data_dir(&config.data_dir, &mut report);
+        self.doctor_signers(&config, &mut report);
+
+        for ca in self.ca_list(actor)?.cas() {
+            self.doctor_ca(ca.handle(), &mut report).await?;
+        }
+
+        Ok(report)
+    }
+
+    fn doctor_data_dir(data_dir: &Path, report: &mut DoctorReport) {
+        const CHECK: &str = "data-dir";
+        let dir = data_dir.display();
+        let action = format!("make sure that the user running Krill owns {} and may write to it", dir);
+
+        match std::fs::metadata(data_dir) {
+            Err(e) => {
+                report.add(DoctorFinding::error(
+                    CHECK,
+                    format!("cannot access data directory {}: {}", dir, e),
+                    action,
+                ));
+                return;
+            }
+            Ok(meta) if !meta.is_dir() => {
+                report.add(DoctorFinding::error(
+                    CHECK,
+                    format!("data directory {} is not a directory", dir),
+                    "check the 'data_dir' setting",
+                ));
+                return;
+            }
+            Ok(meta) => {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+
+                    let mode = meta.permissions().mode() & 0o777;
+                    let restrict = format!("restrict access, e.g. using: chmod o-rwx {}", dir);
+                    if mode & 0o002 != 0 {
+                        report.add(DoctorFinding::error(
+                            CHECK,
+                            format!("data directory {} is writable by all users (mode {:o})", dir, mode),
+                            restrict,
+                        ));
+                    } else if mode & 0o004 != 0 {
+                        report.add(DoctorFinding::warning(
+                            CHECK,
+                            format!(
+                                "data directory {} is readable by all users (mode {:o}), it may contain private keys",
+                                dir, mode
+                            ),
+                            restrict,
+                        ));
+                    }
+                }
+                #[cfg(not(unix))]
+                let _ = meta;
+            }
+        }
+
+        let probe = data_dir.join(".krill-doctor");
+        match std::fs::write(&probe, b"").and_then(|_| std::fs::remove_file(&probe)) {
+            Ok(()) => report.add(DoctorFinding::ok(CHECK, format!("data directory {} is writable", dir))),
+            Err(e) => report.add(DoctorFinding::error(
+                CHECK,
+                format!("cannot write to data directory {}: {}", dir, e),
+                action,
+            )),
+        }
+    }
+
+    fn doctor_signers(&self, config: &Config, report: &mut DoctorReport) {
+        const CHECK: &str = "signers";
+        let action = "check the signer settings and that the HSM can be reached, \
+                      Krill tries again every 'signer_probe_retry_seconds'";
+
+        let active: Vec<String> = self
+            .signer
+            .get_active_signers()
+            .values()
+            .map(|signer| signer.get_name().to_string())
+            .collect();
+        let default_signer = &config.default_signer().name;
+
+        for signer in &config.signers {
+            let finding = if active.contains(&signer.name) {
+                DoctorFinding::ok(CHECK, "signer is available")
+            } else if &signer.name == default_signer {
+                DoctorFinding::error(
+                    CHECK,
+                    "default signer is not available, no new keys can be made",
+                    action,
+                )
+            } else {
+                DoctorFinding::warning(CHECK, "signer is not available, its keys cannot be used", action)
+            };
+            report.add(finding.with_subject(&signer.name));
+        }
+    }
+
+    async fn doctor_ca(&self, ca: &CaHandle, report: &mut DoctorReport) -> KrillEmptyResult {
+        let status = self.ca_manager.get_ca_status(ca).await?;
+
+        let repo_finding = match (status.repo().to_failure_opt(), status.repo().last_success()) {
+            (Some(error), _) => DoctorFinding::error(
+                "repository",
+                format!("publication failed: {}", error.msg()),
+                format!(
+                    "check that the repository can be reached, see: krillc repo status --ca {}",
+                    ca
+                ),
+            ),
+            (None, Some(when)) => DoctorFinding::ok("repository", format!("last published {}", when.to_rfc3339())),
+            (None, None) => DoctorFinding::info("repository", "nothing published yet"),
+        };
+        report.add(repo_finding.with_subject(ca));
+
+        for (parent, parent_status) in status.parents().iter() {
+            let finding = match (parent_status.to_failure_opt(), parent_status.last_success()) {
+                (Some(error), _) => DoctorFinding::error(
+                    "parents",
+                    format!("exchange with parent failed: {}", error.msg()),
+                    format!(
+                        "check that the parent can be reached and still knows this CA, see: krillc parents statuses --ca {}",
+                        ca
+                    ),
+                ),
+                (None, Some(when)) => DoctorFinding::ok("parents", format!("last contacted {}", when.to_rfc3339())),
+                (None, None) => DoctorFinding::info("parents", "not contacted yet"),
+            };
+            report.add(finding.with_subject(format!("{}/{}", ca, parent)));
+        }
+
+        self.doctor_ca_uris(ca, report).await
+    }
+
+    /// Checks that the rsync and RRDP URIs used by the CA look right, and
+    /// match those of the embedded publication server if the CA publishes
+    /// there.
+    async fn doctor_ca_uris(&self, ca: &CaHandle, report: &mut DoctorReport) -> KrillEmptyResult {
+        const CHECK: &str = "repository-uris";
+        let action = format!(
+            "check the URIs of the repository, and update them using: krillc repo configure --ca {}",
+            ca
+        );
+
+        let ca_info = self.ca_manager.get_ca(ca).await?;
+        let repo_info = match ca_info.repository_contact() {
+            Ok(contact) => contact.repo_info().clone(),
+            Err(_) => {
+                report.add(DoctorFinding::info(CHECK, "no repository configured").with_subject(ca));
+                return Ok(());
+            }
+        };
+
+        let base_uri = repo_info.base_uri();
+        let finding = match repo_info.rpki_notify() {
+            None => DoctorFinding::warning(
+                CHECK,
+                format!(
+                    "no RRDP notification URI for {}, relying parties must use rsync",
+                    base_uri
+                ),
+                action,
+            ),
+            Some(notify) if !notify.as_str().ends_with(".xml") => DoctorFinding::warning(
+                CHECK,
+                format!("RRDP notification URI {} does not refer to an XML file", notify),
+                action,
+            ),
+            _ if !base_uri.as_str().ends_with('/') => DoctorFinding::warning(
+                CHECK,
+                format!("rsync base URI {} does not end with a slash", base_uri),
+                action,
+            ),
+            Some(notify) => match self.repo_manager.repo_info_for(&ca.convert()) {
+                Ok(embedded) if embedded != repo_info => DoctorFinding::warning(
+                    CHECK,
+                    format!(
+                        "URIs {} and {} differ from those of the embedded publication server: {} and {}",
+                        base_uri,
+                        notify,
+                        embedded.base_uri(),
+                        embedded.rpki_notify().map(|uri| uri.as_str()).unwrap_or("<none>")
+                    ),
+                    action,
+                ),
+                _ => DoctorFinding::ok(CHECK, format!("publishes at {} and {}", base_uri, notify)),
+            },
+        };
+        report.add(finding.with_subject(ca));
+
+        Ok(())
+    }
+}
+
 /// # Synchronization operations for CAS
 ///
 impl KrillServer {
@@ -785,6 +2088,10 @@ impl KrillServer {
         Ok(())
     }
 
+    pub fn republish_metrics(&self) -> &RepublishMetrics {
+        self.ca_manager.republish_metrics()
+    }
+
     /// Re-sync all CAs with their repositories
     pub fn cas_repo_sync_all(&self, actor: &Actor) -> KrillEmptyResult {
         self.ca_manager.cas_schedule_repo_sync_all(actor);
@@ -814,6 +2121,64 @@ impl KrillServer {
         self.ca_manager.cas_schedule_suspend_all();
         Ok(())
     }
+
+    /// Does a bulk operation for a single CA.
+    pub async fn bulk_operation(&self, operation: BulkOperation, ca: &CaHandle, actor: &Actor) -> KrillEmptyResult {
+        match operation {
+            BulkOperation::SyncParents => {
+                let parents: Vec<_> = self.ca_manager.get_ca(ca).await?.parents().cloned().collect();
+                for parent in parents {
+                    self.ca_manager.ca_sync_parent(ca, &parent, actor).await?;
+                }
+                Ok(())
+            }
+            BulkOperation::SyncRepo => {
+                self.ca_manager
+                    .cas_repo_sync_single(self.repo_manager.as_ref(), ca)
+                    .await
+            }
+            BulkOperation::Publish | BulkOperation::ForcePublish => {
+                let force = operation == BulkOperation::ForcePublish;
+                if self.ca_manager.ca_republish(ca, force)? {
+                    self.ca_manager
+                        .cas_repo_sync_single(self.repo_manager.as_ref(), ca)
+                        .await?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// # Jobs
+///
+impl KrillServer {
+    pub fn job_store(&self) -> &JobStore {
+        &self.jobs
+    }
+
+    /// Returns the jobs for which the actor may see all CAs.
+    pub fn jobs(&self, actor: &Actor) -> JobList {
+        let jobs = self
+            .jobs
+            .list()
+            .jobs()
+            .iter()
+            .filter(|job| jobs::job_visible(job, actor))
+            .cloned()
+            .collect();
+        JobList::new(jobs)
+    }
+
+    /// Returns the job, unless the actor may not see all of its CAs.
+    pub fn job(&self, id: &str, actor: &Actor) -> KrillResult<Job> {
+        let job = self.jobs.get(id)?;
+        if jobs::job_visible(&job, actor) {
+            Ok(job)
+        } else {
+            Err(Error::ApiJobUnknown(id.to_string()))
+        }
+    }
 }
 
 /// # Admin CAS
@@ -823,6 +2188,10 @@ impl KrillServer {
         self.ca_manager.ca_list(actor)
     }
 
+    pub fn ca_exists(&self, ca: &CaHandle) -> KrillResult<bool> {
+        self.ca_manager.has_ca(ca)
+    }
+
     /// Returns the public CA info for a CA, or NONE if the CA cannot be found.
     pub async fn ca_info(&self, ca: &CaHandle) -> KrillResult<CertAuthInfo> {
         self.ca_manager.get_ca(ca).await.map(|ca| ca.as_ca_info())
@@ -837,8 +2206,19 @@ impl KrillServer {
     /// all its objects first. Note that any children of this CA will be left
     /// orphaned, and they will only learn of this sad fact when they choose
     /// to call home.
-    pub async fn ca_delete(&self, ca: &CaHandle, actor: &Actor) -> KrillResult<()> {
-        self.ca_manager.delete_ca(self.repo_manager.as_ref(), ca, actor).await
+    pub async fn ca_delete(&self, ca: &CaHandle, force: bool, actor: &Actor) -> KrillResult<()> {
+        self.ca_manager
+            .delete_ca(self.repo_manager.as_ref(), ca, force, actor)
+            .await?;
+
+        // Tokens delegated for the CA should not give access to a new CA
+        // which happens to be created with the same name later.
+        #[cfg(feature = "multi-user")]
+        for token in self.authorizer.api_tokens().list_delegated_for(ca).tokens() {
+            self.authorizer.api_tokens().revoke(token.name())?;
+        }
+
+        Ok(())
     }
 
     /// Returns the parent contact for a CA and parent, or NONE if either the CA or the parent cannot be found.
@@ -847,6 +2227,11 @@ impl KrillServer {
         ca.parent(parent).map(|p| p.clone())
     }
 
+    /// Returns the recent exchanges of a CA with one of its parents.
+    pub async fn ca_parent_exchanges(&self, ca: &CaHandle, parent: &ParentHandle) -> KrillResult<ParentExchangeLog> {
+        self.ca_manager.ca_parent_exchanges(ca, parent).await
+    }
+
     /// Returns the history for a CA.
     pub async fn ca_history(&self, ca: &CaHandle, crit: CommandHistoryCriteria) -> KrillResult<CommandHistory> {
         self.ca_manager.ca_history(ca, crit).await
@@ -856,6 +2241,31 @@ impl KrillServer {
         self.ca_manager.ca_command_details(ca, command)
     }
 
+    /// Returns the changes which would revert the given command.
+    pub async fn ca_command_revert_plan(&self, ca: &CaHandle, command: CommandKey) -> KrillResult<CommandRevert> {
+        self.ca_manager.ca_command_revert_plan(ca, command).await
+    }
+
+    /// Reverts the ROA or ASPA changes of a previous command by applying the
+    /// given compensating changes as a new command. ROA changes are checked
+    /// for their impact on BGP announcements, unless forced.
+    pub async fn ca_command_revert(
+        &self,
+        ca: CaHandle,
+        command: &CommandKey,
+        revert: CommandRevert,
+        force: bool,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        info!("Reverting command '{}' for CA '{}'", command, ca);
+        if let CommandRevert::Roas(updates) = &revert {
+            self.ca_routes_verify_impact(&ca, updates, force).await?;
+        }
+        self.ca_manager
+            .ca_command_revert(&ca, command.clone(), revert, actor)
+            .await
+    }
+
     /// Returns the publisher request for a CA, or NONE of the CA cannot be found.
     pub async fn ca_publisher_req(&self, ca: &CaHandle) -> KrillResult<idexchange::PublisherRequest> {
         self.ca_manager.get_ca(ca).await.map(|ca| ca.publisher_request())
@@ -874,6 +2284,40 @@ impl KrillServer {
         Ok(CaRepoDetails::new(contact.clone()))
     }
 
+    /// Returns the expiry times of all objects published by a CA.
+    pub fn ca_objects_expiry(&self, ca: &CaHandle, window_hours: Option<u32>) -> KrillResult<ObjectsExpiry> {
+        self.ca_manager.ca_objects_expiry(ca, window_hours)
+    }
+
+    /// Returns the read-only status of a CA for its public status page. CAs
+    /// which are not listed in 'public_status_cas' are reported as unknown,
+    /// so that the page does not reveal which CAs exist.
+    pub async fn ca_public_status(&self, ca: &CaHandle) -> KrillResult<PublicCaStatus> {
+        if !self.config().public_status_cas.contains(ca) {
+            return Err(Error::CaUnknown(ca.clone()));
+        }
+
+        let status = self.ca_manager.get_ca_status(ca).await?;
+        let objects = self.ca_manager.ca_objects_expiry(ca, None)?;
+        Ok(PublicCaStatus::new(ca.clone(), status.repo(), objects))
+    }
+
+    /// Check the objects published by a CA, as seen by relying parties.
+    pub async fn ca_publication_check(&self, ca: &CaHandle) -> KrillResult<PublicationCheck> {
+        self.ca_manager.ca_publication_check(ca).await
+    }
+
+    /// Returns the result of the last reconciliation of a CA, if any.
+    pub async fn ca_reconciliation(&self, ca: &CaHandle) -> KrillResult<Option<Reconciliation>> {
+        Ok(self.ca_manager.get_ca_status(ca).await?.reconciliation().cloned())
+    }
+
+    /// Compares the configuration of a CA with its issued and published
+    /// objects, and optionally repairs any divergences found.
+    pub async fn ca_reconcile(&self, ca: &CaHandle, repair: bool, actor: &Actor) -> KrillResult<Reconciliation> {
+        self.ca_manager.ca_reconcile(ca, repair, actor).await
+    }
+
     /// Update the repository for a CA, or return an error. (see `CertAuth::repo_update`)
     pub async fn ca_repo_update(&self, ca: CaHandle, contact: RepositoryContact, actor: &Actor) -> KrillEmptyResult {
         self.ca_manager
@@ -881,10 +2325,129 @@ impl KrillServer {
             .await
     }
 
+    /// Returns the repositories used by resource classes which do not publish
+    /// to the repository of the CA.
+    pub async fn ca_class_repos(&self, ca: &CaHandle) -> KrillResult<ClassRepoDetails> {
+        let ca = self.ca_manager.get_ca(ca).await?;
+        Ok(ClassRepoDetails::new(ca.class_repositories().clone()))
+    }
+
+    /// Update the repository for a resource class of a CA, or revert it to
+    /// the repository of the CA if no contact is given.
+    pub async fn ca_class_repo_update(
+        &self,
+        ca: CaHandle,
+        rcn: ResourceClassName,
+        contact: Option<RepositoryContact>,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager
+            .update_class_repo(self.repo_manager.as_ref(), ca, rcn, contact, true, actor)
+            .await
+    }
+
+    /// Returns the status of each repository that a CA publishes to.
+    pub async fn ca_repo_statuses(&self, ca: &CaHandle) -> KrillResult<RepoStatuses> {
+        Ok(self.ca_manager.get_ca_status(ca).await?.repos().clone())
+    }
+
+    pub async fn ca_repo_migration_status(&self, ca: &CaHandle) -> KrillResult<RepoMigrationStatus> {
+        self.ca_manager.ca_repo_migration_status(ca).await
+    }
+
+    pub async fn ca_repo_migration_activate(&self, ca: &CaHandle, actor: &Actor) -> KrillResult<RepoMigrationStatus> {
+        self.ca_manager.ca_repo_migration_activate(ca, actor).await
+    }
+
+    pub async fn ca_repo_migration_abort(&self, ca: &CaHandle, actor: &Actor) -> KrillResult<RepoMigrationStatus> {
+        self.ca_manager.ca_repo_migration_abort(ca, actor).await
+    }
+
+    /// Migrate a CA to a new repository, or resume the migration, see
+    /// `CaManager::ca_repo_migrate`.
+    pub async fn ca_repo_migrate(
+        &self,
+        ca: &CaHandle,
+        contact: RepositoryContact,
+        actor: &Actor,
+    ) -> KrillResult<RepoMigrationReport> {
+        self.ca_manager
+            .ca_repo_migrate(self.repo_manager.as_ref(), ca, contact, actor)
+            .await
+    }
+
+    /// Returns the CA specific overrides of the issuance timing config.
+    pub async fn ca_issuance_timing(&self, ca: &CaHandle) -> KrillResult<IssuanceTimingOverrides> {
+        let ca = self.ca_manager.get_ca(ca).await?;
+        Ok(ca.issuance_timing_overrides().clone())
+    }
+
+    /// Returns the issuance timing in effect for the CA, with warnings about
+    /// values which may be unwise.
+    pub async fn ca_issuance_timing_report(&self, ca: &CaHandle) -> KrillResult<IssuanceTimingReport> {
+        let ca = self.ca_manager.get_ca(ca).await?;
+        let overrides = ca.issuance_timing_overrides().clone();
+        let timing = self.config().issuance_timing.with_overrides(&overrides);
+        Ok(IssuanceTimingReport::new(
+            overrides,
+            timing.effective(),
+            timing.warnings(),
+        ))
+    }
+
+    pub async fn ca_issuance_timing_update(
+        &self,
+        ca: CaHandle,
+        overrides: IssuanceTimingOverrides,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_issuance_timing_update(ca, overrides, actor).await
+    }
+
+    /// Returns the CA specific overrides of the retry policies for contacting
+    /// the repository and parents.
+    pub async fn ca_retry_policies(&self, ca: &CaHandle) -> KrillResult<RetryPolicies> {
+        let ca = self.ca_manager.get_ca(ca).await?;
+        Ok(ca.retry_policies().clone())
+    }
+
+    pub async fn ca_retry_policy_update(
+        &self,
+        ca: CaHandle,
+        parent: Option<ParentHandle>,
+        policy: RetryPolicy,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_retry_policy_update(ca, parent, policy, actor).await
+    }
+
+    pub async fn ca_labels(&self, ca: &CaHandle) -> KrillResult<CaLabels> {
+        let ca = self.ca_manager.get_ca(ca).await?;
+        Ok(ca.labels().clone())
+    }
+
+    pub async fn ca_labels_update(&self, ca: CaHandle, update: CaLabelsUpdate, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ca_labels_update(ca, update, actor).await
+    }
+
     pub async fn ca_update_id(&self, ca: CaHandle, actor: &Actor) -> KrillEmptyResult {
         self.ca_manager.ca_update_id(ca, actor).await
     }
 
+    pub async fn ca_id_roll_init(&self, ca: CaHandle, actor: &Actor) -> KrillResult<idexchange::PublisherRequest> {
+        self.ca_manager.ca_id_roll_init(ca, actor).await
+    }
+
+    pub async fn ca_id_roll_request(&self, ca: &CaHandle) -> KrillResult<idexchange::PublisherRequest> {
+        let ca = self.ca_manager.get_ca(ca).await?;
+        ca.next_publisher_request()
+            .ok_or_else(|| Error::CaRepoIdRollNotStarted(ca.handle().clone()))
+    }
+
+    pub async fn ca_id_roll_activate(&self, ca: CaHandle, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ca_id_roll_activate(ca, actor).await
+    }
+
     pub async fn ca_keyroll_init(&self, ca: CaHandle, actor: &Actor) -> KrillEmptyResult {
         self.ca_manager.ca_keyroll_init(ca, Duration::seconds(0), actor).await
     }
@@ -895,6 +2458,14 @@ impl KrillServer {
             .await
     }
 
+    pub async fn ca_signer_migration_status(&self, ca: &CaHandle) -> KrillResult<SignerMigrationStatus> {
+        self.ca_manager.ca_signer_migration_status(ca).await
+    }
+
+    pub async fn ca_signer_migrate(&self, ca: CaHandle, actor: &Actor) -> KrillResult<SignerMigrationStatus> {
+        self.ca_manager.ca_signer_migrate(ca, actor).await
+    }
+
     pub async fn rfc6492(
         &self,
         ca: CaHandle,
@@ -922,6 +2493,17 @@ impl KrillServer {
         self.ca_manager.ca_aspas_definitions_update(ca, updates, actor).await
     }
 
+    pub async fn ca_aspas_definitions_import(
+        &self,
+        ca: CaHandle,
+        definitions: AspaDefinitionList,
+        actor: &Actor,
+    ) -> KrillResult<AspaDefinitionUpdates> {
+        self.ca_manager
+            .ca_aspas_definitions_import(ca, definitions, actor)
+            .await
+    }
+
     pub async fn ca_aspas_update_aspa(
         &self,
         ca: CaHandle,
@@ -931,6 +2513,15 @@ impl KrillServer {
     ) -> KrillEmptyResult {
         self.ca_manager.ca_aspas_update_aspa(ca, customer, update, actor).await
     }
+
+    pub async fn ca_aspas_update_aspa_dry_run(
+        &self,
+        ca: CaHandle,
+        customer: AspaCustomer,
+        update: AspaProvidersUpdate,
+    ) -> KrillResult<AspaUpdateDryRun> {
+        self.ca_manager.ca_aspas_update_aspa_dry_run(ca, customer, update).await
+    }
 }
 
 /// # Handle BGPSec requests
@@ -953,15 +2544,157 @@ impl KrillServer {
 /// # Handle route authorization requests
 ///
 impl KrillServer {
+    /// Updates the ROAs for a CA. If 'roa_update_bgp_check' is enabled, then
+    /// the update is rejected if it would make announcements seen in BGP RPKI
+    /// invalid, unless it is forced.
     pub async fn ca_routes_update(
         &self,
         ca: CaHandle,
         updates: RoaConfigurationUpdates,
+        force: bool,
         actor: &Actor,
     ) -> KrillEmptyResult {
+        self.ca_routes_verify_impact(&ca, &updates, force).await?;
         self.ca_manager.ca_routes_update(ca, updates, actor).await
     }
 
+    /// Returns an error including the impact of the updates, in case they
+    /// would invalidate announcements seen in BGP. This check is skipped if
+    /// the update is forced, or if 'roa_update_bgp_check' is disabled.
+    async fn ca_routes_verify_impact(
+        &self,
+        ca: &CaHandle,
+        updates: &RoaConfigurationUpdates,
+        force: bool,
+    ) -> KrillEmptyResult {
+        if force || !self.config().roa_update_bgp_check {
+            return Ok(());
+        }
+
+        let impact = self.ca_routes_bgp_impact(ca, updates.clone()).await?;
+        if impact.invalidates_announcements() {
+            Err(Error::RoaUpdateInvalidatesAnnouncements(ca.clone(), impact))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub async fn ca_routes_proposals(&self, handle: &CaHandle) -> KrillResult<RoaProposalList> {
+        let ca = self.ca_manager.get_ca(handle).await?;
+        Ok(ca.roa_proposals())
+    }
+
+    pub async fn ca_routes_propose(
+        &self,
+        ca: CaHandle,
+        updates: RoaConfigurationUpdates,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_routes_propose(ca, updates, actor).await
+    }
+
+    /// Approves a ROA proposal. The approval is checked in the same way as a
+    /// ROA update, see [`KrillServer::ca_routes_update`].
+    pub async fn ca_routes_proposal_approve(
+        &self,
+        ca: CaHandle,
+        id: u64,
+        force: bool,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        let proposal = self
+            .ca_manager
+            .get_ca(&ca)
+            .await?
+            .roa_proposal(id)
+            .cloned()
+            .ok_or_else(|| Error::RoaProposalUnknown(ca.clone(), id))?;
+
+        self.ca_routes_verify_impact(&ca, proposal.updates(), force).await?;
+        self.ca_manager.ca_routes_proposal_approve(ca, id, actor).await
+    }
+
+    pub async fn ca_routes_proposal_reject(&self, ca: CaHandle, id: u64, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ca_routes_proposal_reject(ca, id, actor).await
+    }
+
+    /// Returns the ROA issuance strategy in use by the CA.
+    pub async fn ca_roa_issuance_strategy(&self, handle: &CaHandle) -> KrillResult<RoaIssuanceStrategyInfo> {
+        let ca = self.ca_manager.get_ca(handle).await?;
+        Ok(match ca.roa_issuance_strategy() {
+            Some(effective) => RoaIssuanceStrategyInfo {
+                effective,
+                ca_override: true,
+            },
+            None => RoaIssuanceStrategyInfo {
+                effective: self.config().roa_issuance_strategy,
+                ca_override: false,
+            },
+        })
+    }
+
+    /// Set, or with None remove, the CA specific ROA issuance strategy.
+    pub async fn ca_roa_issuance_strategy_update(
+        &self,
+        ca: CaHandle,
+        strategy: Option<RoaIssuanceStrategy>,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager
+            .ca_roa_issuance_strategy_update(ca, strategy, actor)
+            .await
+    }
+
+    /// Returns the ROA auto-pilot policy in use by the CA, if any.
+    pub async fn ca_roa_autopilot(&self, handle: &CaHandle) -> KrillResult<RoaAutoPilotInfo> {
+        let ca = self.ca_manager.get_ca(handle).await?;
+        Ok(match ca.roa_autopilot() {
+            Some(policy) => RoaAutoPilotInfo {
+                effective: Some(policy.clone()),
+                ca_override: true,
+            },
+            None => RoaAutoPilotInfo {
+                effective: self.config().roa_autopilot.get(handle).cloned(),
+                ca_override: false,
+            },
+        })
+    }
+
+    /// Set, or with None remove, the CA specific ROA auto-pilot policy.
+    pub async fn ca_roa_autopilot_update(
+        &self,
+        ca: CaHandle,
+        policy: Option<RoaAutoPilotPolicy>,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_roa_autopilot_update(ca, policy, actor).await
+    }
+
+    /// Imports route objects, e.g. from an IRR, as ROA configurations. Only
+    /// payloads which are not yet configured, and which do not conflict with
+    /// the resources held by the CA, are added. In case of a dry run the
+    /// report is returned, but nothing is changed.
+    pub async fn ca_routes_import(
+        &self,
+        handle: &CaHandle,
+        import: RoaImport,
+        dry_run: bool,
+        force: bool,
+        actor: &Actor,
+    ) -> KrillResult<RoaImportReport> {
+        let payloads = import.payloads().map_err(Error::custom)?;
+
+        let ca = self.ca_manager.get_ca(handle).await?;
+        let report = RoaImportReport::new(payloads, &ca.configured_roas(), &ca.all_resources());
+
+        if !dry_run && !report.updates().is_empty() {
+            self.ca_routes_update(handle.clone(), report.updates().clone(), force, actor)
+                .await?;
+        }
+
+        Ok(report)
+    }
+
     pub async fn ca_routes_show(&self, handle: &CaHandle) -> KrillResult<Vec<ConfiguredRoa>> {
         let ca = self.ca_manager.get_ca(handle).await?;
 
@@ -999,6 +2732,28 @@ impl KrillServer {
             .await)
     }
 
+    /// Returns the impact of the updates on the announcements seen in BGP
+    /// for the affected prefixes, i.e. which announcements would become
+    /// RPKI invalid.
+    pub async fn ca_routes_bgp_impact(
+        &self,
+        handle: &CaHandle,
+        updates: RoaConfigurationUpdates,
+    ) -> KrillResult<BgpAnalysisImpact> {
+        let ca = self.ca_manager.get_ca(handle).await?;
+        let updates = updates.into_explicit_max_length();
+
+        Ok(self
+            .bgp_analyser
+            .impact(
+                &ca.configured_roas(),
+                &ca.configured_roas_after(&updates)?,
+                &ca.all_resources(),
+                updates.affected_prefixes(),
+            )
+            .await)
+    }
+
     pub async fn ca_routes_bgp_suggest(
         &self,
         handle: &CaHandle,
@@ -1039,6 +2794,35 @@ impl KrillServer {
     pub fn repository_session_reset(&self) -> KrillResult<()> {
         self.repo_manager.rrdp_session_reset()
     }
+
+    /// Add an entry with the current content to the repository archive.
+    pub async fn repository_archive(&self) -> KrillResult<RepositoryArchiveEntry> {
+        self.repo_manager.archive_repository().await
+    }
+
+    /// List the entries in the repository archive.
+    pub fn repository_archive_list(&self) -> KrillResult<RepositoryArchiveList> {
+        self.repo_manager.archive_list()
+    }
+
+    /// Synchronise the replicated repository with the primary.
+    pub async fn repository_replicate(&self) -> KrillResult<RepositoryReplicaStatus> {
+        self.repo_manager.replicate_repository().await
+    }
+
+    /// Show the status of the replicated repository.
+    pub fn repository_replica_status(&self) -> KrillResult<RepositoryReplicaStatus> {
+        self.repo_manager.replica_status()
+    }
+}
+
+/// # Handle RPKI Signed Checklist requests
+///
+impl KrillServer {
+    /// Sign an RPKI Signed Checklist (RSC)
+    pub async fn rsc_sign(&self, ca: CaHandle, request: RscContentRequest) -> KrillResult<RpkiSignedChecklist> {
+        self.ca_manager.rsc_sign(&ca, request).await
+    }
 }
 
 /// # Handle Resource Tagged Attestation requests
@@ -1,8 +1,14 @@
 pub mod auth;
+pub mod backup;
 pub mod ca;
 pub mod config;
+pub mod ha;
 pub mod http;
+pub mod jobs;
 pub mod krillserver;
 pub mod mq;
+pub mod notify;
 pub mod scheduler;
 pub mod ta;
+pub mod telemetry;
+pub mod timecheck;
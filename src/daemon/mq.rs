@@ -3,7 +3,11 @@
 //! signed material, or asking a newly added parent for resource
 //! entitlements.
 
-use std::{fmt, sync::RwLock};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::RwLock,
+};
 
 use priority_queue::PriorityQueue;
 
@@ -17,12 +21,16 @@ use rpki::{
 
 use crate::{
     commons::{
-        api::Timestamp,
+        api::{TaskInfo, TaskList, TaskOutcome, Timestamp},
+        error::Error,
         eventsourcing::{self, Event},
+        util::logging::LogContext,
+        KrillResult,
     },
     daemon::{
         ca::{CaEvt, CaEvtDet, CertAuth},
         ta::{ta_handle, TrustAnchorProxyEventDetails},
+        telemetry::{self, SpanContext},
     },
 };
 
@@ -51,17 +59,48 @@ pub enum Task {
         ca: CaHandle,
     },
 
+    ResourceTransfers {
+        ca: CaHandle,
+    },
+
+    ScheduledChanges {
+        ca: CaHandle,
+    },
+
     RepublishIfNeeded,
     RenewObjectsIfNeeded,
 
     RefreshAnnouncementsInfo,
 
+    RoaAutoPilot,
+
     UpdateSnapshots,
 
+    ArchiveHistory,
+
     RrdpUpdateIfNeeded,
 
-    #[cfg(feature = "multi-user")]
-    SweepLoginCache,
+    RrdpSessionResetIfNeeded,
+
+    RepositoryArchive,
+
+    RepositoryReplicate,
+
+    RepositoryS3Sync,
+
+    PublicationCheck,
+
+    Reconcile,
+
+    CheckTime,
+
+    ConfirmPublication {
+        ca: CaHandle,
+    },
+
+    ExpiryAlerts,
+
+    TestbedCleanup,
 
     ResourceClassRemoved {
         ca: CaHandle,
@@ -84,14 +123,25 @@ impl fmt::Display for Task {
             Task::SyncParent { ca, parent } => write!(f, "synchronize CA '{}' with parent '{}'", ca, parent),
             Task::SyncTrustAnchorProxySignerIfPossible => write!(f, "sync TA Proxy and Signer if both in this server."),
             Task::SuspendChildrenIfNeeded { ca } => write!(f, "verify if CA '{}' has children to suspend", ca),
+            Task::ResourceTransfers { ca } => write!(f, "progress resource transfers between children of CA '{}'", ca),
+            Task::ScheduledChanges { ca } => write!(f, "apply scheduled ROA and ASPA changes for CA '{}'", ca),
             Task::RepublishIfNeeded => write!(f, "let CAs republish their mft/crls if needed"),
             Task::RenewObjectsIfNeeded => write!(f, "let CAs renew their signed objects if needed"),
             Task::RefreshAnnouncementsInfo => write!(f, "check for new announcement info"),
-            Task::UpdateSnapshots => write!(f, "update repository content snapshot on disk"),
+            Task::RoaAutoPilot => write!(f, "authorize stable announcements for CAs using the ROA auto-pilot"),
+            Task::UpdateSnapshots => write!(f, "update CA and repository snapshots"),
+            Task::ArchiveHistory => write!(f, "archive the older history of CAs and the repository"),
             Task::RrdpUpdateIfNeeded => write!(f, "create new RRDP delta, if needed"),
-
-            #[cfg(feature = "multi-user")]
-            Task::SweepLoginCache => write!(f, "sweep up expired logins"),
+            Task::RrdpSessionResetIfNeeded => write!(f, "reset the RRDP session, if needed"),
+            Task::RepositoryArchive => write!(f, "archive the repository content"),
+            Task::RepositoryReplicate => write!(f, "replicate the repository of the primary"),
+            Task::RepositoryS3Sync => write!(f, "upload the repository files to S3"),
+            Task::PublicationCheck => write!(f, "check the objects published by CAs"),
+            Task::Reconcile => write!(f, "compare the configuration of CAs with their objects"),
+            Task::CheckTime => write!(f, "compare the system time with the time sources"),
+            Task::ConfirmPublication { ca } => write!(f, "confirm the objects published by CA '{}'", ca),
+            Task::ExpiryAlerts => write!(f, "send alerts for certificates nearing expiry"),
+            Task::TestbedCleanup => write!(f, "remove inactive testbed children and publishers"),
             Task::ResourceClassRemoved { ca, .. } => {
                 write!(f, "resource class removed for '{}' ", ca)
             }
@@ -102,17 +152,143 @@ impl fmt::Display for Task {
     }
 }
 
+impl Task {
+    /// Returns the name for this kind of task, as used in the API to
+    /// trigger or pause tasks.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Task::QueueStartTasks => "queue-start-tasks",
+            Task::SyncRepo { .. } => "sync-repo",
+            Task::SyncParent { .. } => "sync-parent",
+            Task::SyncTrustAnchorProxySignerIfPossible => "sync-ta-proxy-signer",
+            Task::SuspendChildrenIfNeeded { .. } => "suspend-children",
+            Task::ResourceTransfers { .. } => "resource-transfers",
+            Task::ScheduledChanges { .. } => "scheduled-changes",
+            Task::RepublishIfNeeded => "republish",
+            Task::RenewObjectsIfNeeded => "renew-objects",
+            Task::RefreshAnnouncementsInfo => "refresh-announcements",
+            Task::RoaAutoPilot => "roa-autopilot",
+            Task::UpdateSnapshots => "update-snapshots",
+            Task::ArchiveHistory => "archive-history",
+            Task::RrdpUpdateIfNeeded => "rrdp-update",
+            Task::RrdpSessionResetIfNeeded => "rrdp-session-reset",
+            Task::RepositoryArchive => "repository-archive",
+            Task::RepositoryReplicate => "repository-replicate",
+            Task::RepositoryS3Sync => "repository-s3-sync",
+            Task::PublicationCheck => "publication-check",
+            Task::Reconcile => "reconcile",
+            Task::CheckTime => "check-time",
+            Task::ConfirmPublication { .. } => "confirm-publication",
+            Task::ExpiryAlerts => "expiry-alerts",
+            Task::TestbedCleanup => "testbed-cleanup",
+            Task::ResourceClassRemoved { .. } => "resource-class-removed",
+            Task::UnexpectedKey { .. } => "unexpected-key",
+        }
+    }
+
+    fn verify_name(name: &str) -> KrillResult<()> {
+        let known = matches!(
+            name,
+            "queue-start-tasks"
+                | "sync-repo"
+                | "sync-parent"
+                | "sync-ta-proxy-signer"
+                | "suspend-children"
+                | "resource-transfers"
+                | "scheduled-changes"
+                | "republish"
+                | "renew-objects"
+                | "refresh-announcements"
+                | "roa-autopilot"
+                | "update-snapshots"
+                | "archive-history"
+                | "rrdp-update"
+                | "rrdp-session-reset"
+                | "repository-archive"
+                | "repository-replicate"
+                | "repository-s3-sync"
+                | "publication-check"
+                | "reconcile"
+                | "check-time"
+                | "confirm-publication"
+                | "expiry-alerts"
+                | "testbed-cleanup"
+                | "resource-class-removed"
+                | "unexpected-key"
+        );
+        if known {
+            Ok(())
+        } else {
+            Err(Error::ApiTaskUnknown(name.to_string()))
+        }
+    }
+
+    /// Returns the CA this task is for, if it is a CA specific task.
+    pub fn ca(&self) -> Option<&CaHandle> {
+        match self {
+            Task::SyncRepo { ca }
+            | Task::SyncParent { ca, .. }
+            | Task::SuspendChildrenIfNeeded { ca }
+            | Task::ResourceTransfers { ca }
+            | Task::ScheduledChanges { ca }
+            | Task::ConfirmPublication { ca }
+            | Task::ResourceClassRemoved { ca, .. }
+            | Task::UnexpectedKey { ca, .. } => Some(ca),
+            _ => None,
+        }
+    }
+}
+
 //------------ TaskQueue ----------------------------------------------------
 
 #[derive(Debug)]
 pub struct TaskQueue {
     q: RwLock<PriorityQueue<Task, Priority>>,
+
+    // The outcome of the last run of each task
+    outcomes: RwLock<HashMap<Task, TaskOutcome>>,
+
+    // The names of the kinds of tasks which are paused
+    paused: RwLock<HashSet<String>>,
+
+    // The spans and requests in which pending tasks were scheduled, so that
+    // the span and log messages of a task can be correlated with them.
+    origins: RwLock<HashMap<Task, TaskOrigins>>,
+}
+
+// The maximum number of spans or requests to which a task is correlated.
+const TASK_MAX_ORIGINS: usize = 32;
+
+/// The spans and requests in which a task was scheduled.
+#[derive(Clone, Debug, Default)]
+pub struct TaskOrigins {
+    pub spans: Vec<SpanContext>,
+    pub requests: Vec<String>,
+}
+
+impl TaskOrigins {
+    fn add_current(&mut self) {
+        if let Some(span) = telemetry::current_span_context() {
+            if self.spans.len() < TASK_MAX_ORIGINS && !self.spans.contains(&span) {
+                self.spans.push(span);
+            }
+        }
+
+        if let Some(request) = LogContext::current_request_id() {
+            if self.requests.len() < TASK_MAX_ORIGINS && !self.requests.contains(&request) {
+                self.requests.push(request);
+            }
+        }
+    }
 }
 
 impl Default for TaskQueue {
     fn default() -> Self {
         TaskQueue {
             q: RwLock::new(PriorityQueue::new()),
+            outcomes: RwLock::new(HashMap::new()),
+            paused: RwLock::new(HashSet::new()),
+            origins: RwLock::new(HashMap::new()),
         }
     }
 }
@@ -153,6 +329,13 @@ impl TaskQueue {
     /// Recurring tasks will typically be re-added by the Scheduler when
     /// needed (and can then be moved forward if needed).
     fn schedule(&self, task: Task, priority: Priority) {
+        self.origins
+            .write()
+            .unwrap()
+            .entry(task.clone())
+            .or_default()
+            .add_current();
+
         let mut q = self.q.write().unwrap();
 
         let prio_opt = q.get_priority(&task).copied();
@@ -190,20 +373,11 @@ impl TaskQueue {
         // tasks for a removed CA. So, this is unlikely to be an issue.
         let mut tasks_to_remove = vec![];
 
-        // Find matching tasks and clone them
+        // Find matching tasks and clone them. Tasks which are not CA
+        // specific are kept.
         for (task, _) in q.iter() {
-            match task {
-                Task::SyncRepo { ca }
-                | Task::SyncParent { ca, .. }
-                | Task::SuspendChildrenIfNeeded { ca }
-                | Task::ResourceClassRemoved { ca, .. }
-                | Task::UnexpectedKey { ca, .. } => {
-                    if ca == removed_ca {
-                        tasks_to_remove.push(task.clone())
-                    }
-                }
-
-                _ => {} // Not a CA specific task, ignore it and keep it
+            if task.ca() == Some(removed_ca) {
+                tasks_to_remove.push(task.clone())
             }
         }
 
@@ -211,6 +385,22 @@ impl TaskQueue {
         for task in tasks_to_remove {
             q.remove(&task);
         }
+
+        self.outcomes
+            .write()
+            .unwrap()
+            .retain(|task, _| task.ca() != Some(removed_ca));
+
+        self.origins
+            .write()
+            .unwrap()
+            .retain(|task, _| task.ca() != Some(removed_ca));
+    }
+
+    /// Returns the spans and requests in which the task was scheduled since
+    /// it was last run, if any.
+    pub fn take_origins(&self, task: &Task) -> TaskOrigins {
+        self.origins.write().unwrap().remove(task).unwrap_or_default()
     }
 
     pub fn server_started(&self) {
@@ -233,6 +423,14 @@ impl TaskQueue {
         self.schedule(Task::SuspendChildrenIfNeeded { ca }, priority);
     }
 
+    pub fn resource_transfers(&self, ca: CaHandle, priority: Priority) {
+        self.schedule(Task::ResourceTransfers { ca }, priority);
+    }
+
+    pub fn scheduled_changes(&self, ca: CaHandle, priority: Priority) {
+        self.schedule(Task::ScheduledChanges { ca }, priority);
+    }
+
     pub fn republish_if_needed(&self, priority: Priority) {
         self.schedule(Task::RepublishIfNeeded, priority);
     }
@@ -245,17 +443,60 @@ impl TaskQueue {
         self.schedule(Task::RefreshAnnouncementsInfo, priority);
     }
 
+    pub fn roa_autopilot(&self, priority: Priority) {
+        self.schedule(Task::RoaAutoPilot, priority);
+    }
+
     pub fn update_snapshots(&self, priority: Priority) {
         self.schedule(Task::UpdateSnapshots, priority)
     }
 
+    pub fn archive_history(&self, priority: Priority) {
+        self.schedule(Task::ArchiveHistory, priority)
+    }
+
     pub fn update_rrdp_if_needed(&self, priority: Priority) {
         self.schedule(Task::RrdpUpdateIfNeeded, priority)
     }
 
-    #[cfg(feature = "multi-user")]
-    pub fn sweep_login_cache(&self, priority: Priority) {
-        self.schedule(Task::SweepLoginCache, priority);
+    pub fn rrdp_session_reset_if_needed(&self, priority: Priority) {
+        self.schedule(Task::RrdpSessionResetIfNeeded, priority)
+    }
+
+    pub fn repository_archive(&self, priority: Priority) {
+        self.schedule(Task::RepositoryArchive, priority)
+    }
+
+    pub fn repository_replicate(&self, priority: Priority) {
+        self.schedule(Task::RepositoryReplicate, priority)
+    }
+
+    pub fn repository_s3_sync(&self, priority: Priority) {
+        self.schedule(Task::RepositoryS3Sync, priority)
+    }
+
+    pub fn publication_check(&self, priority: Priority) {
+        self.schedule(Task::PublicationCheck, priority)
+    }
+
+    pub fn reconcile(&self, priority: Priority) {
+        self.schedule(Task::Reconcile, priority)
+    }
+
+    pub fn check_time(&self, priority: Priority) {
+        self.schedule(Task::CheckTime, priority)
+    }
+
+    pub fn confirm_publication(&self, ca: CaHandle, priority: Priority) {
+        self.schedule(Task::ConfirmPublication { ca }, priority)
+    }
+
+    pub fn expiry_alerts(&self, priority: Priority) {
+        self.schedule(Task::ExpiryAlerts, priority)
+    }
+
+    pub fn testbed_cleanup(&self, priority: Priority) {
+        self.schedule(Task::TestbedCleanup, priority)
     }
 
     fn drop_sync_parent(&self, ca: CaHandle, parent: ParentHandle) {
@@ -265,6 +506,98 @@ impl TaskQueue {
     }
 }
 
+/// # Introspection and control
+impl TaskQueue {
+    /// Lists all queued tasks in the order in which they are due, followed
+    /// by tasks which ran before but are not queued.
+    pub fn list(&self) -> TaskList {
+        let q = self.q.read().unwrap();
+        let outcomes = self.outcomes.read().unwrap();
+        let paused = self.paused.read().unwrap();
+
+        let info = |task: &Task, next_run: Option<Timestamp>| {
+            TaskInfo::new(
+                task.name().to_string(),
+                task.to_string(),
+                paused.contains(task.name()),
+                next_run,
+                outcomes.get(task).cloned(),
+            )
+        };
+
+        let mut tasks: Vec<TaskInfo> = q
+            .iter()
+            .map(|(task, priority)| info(task, Some(priority.into())))
+            .collect();
+        tasks.sort_by_key(|task| task.next_run());
+
+        tasks.extend(
+            outcomes
+                .keys()
+                .filter(|task| q.get(*task).is_none())
+                .map(|task| info(task, None)),
+        );
+
+        TaskList::new(tasks)
+    }
+
+    /// Records the outcome of a task which was run.
+    pub fn finished(&self, task: &Task, error: Option<String>) {
+        let outcome = TaskOutcome::new(Timestamp::now(), error);
+        self.outcomes.write().unwrap().insert(task.clone(), outcome);
+    }
+
+    /// Moves all queued tasks with the given name forward, so that they
+    /// are run now.
+    pub fn trigger(&self, name: &str) -> KrillResult<()> {
+        Task::verify_name(name)?;
+
+        let mut q = self.q.write().unwrap();
+        let tasks: Vec<Task> = q
+            .iter()
+            .map(|(task, _)| task)
+            .filter(|task| task.name() == name)
+            .cloned()
+            .collect();
+
+        if tasks.is_empty() {
+            return Err(Error::ApiTaskNotQueued(name.to_string()));
+        }
+
+        for task in tasks {
+            info!("Triggering task: {}", task);
+            q.change_priority(&task, now());
+        }
+        Ok(())
+    }
+
+    /// Pauses all tasks with the given name. They stay queued, but are not
+    /// run until resumed.
+    pub fn pause(&self, name: &str) -> KrillResult<()> {
+        Task::verify_name(name)?;
+        warn!("Pausing tasks: {}", name);
+        self.paused.write().unwrap().insert(name.to_string());
+        Ok(())
+    }
+
+    pub fn resume(&self, name: &str) -> KrillResult<()> {
+        Task::verify_name(name)?;
+        info!("Resuming tasks: {}", name);
+        self.paused.write().unwrap().remove(name);
+        Ok(())
+    }
+
+    pub fn is_paused(&self, task: &Task) -> bool {
+        self.paused.read().unwrap().contains(task.name())
+    }
+
+    /// Puts a paused task back in the queue, so that it can be checked
+    /// again in a minute.
+    pub fn postpone(&self, task: Task) {
+        self.schedule(task, in_minutes(1));
+    }
+}
+
 /// Implement listening for CertAuth events.
 impl eventsourcing::PostSaveEventListener<CertAuth> for TaskQueue {
     fn listen(&self, ca: &CertAuth, events: &[CaEvt]) {
@@ -276,13 +609,28 @@ impl eventsourcing::PostSaveEventListener<CertAuth> for TaskQueue {
             match event.details() {
                 CaEvtDet::RoasUpdated { .. }
                 | CaEvtDet::AspaObjectsUpdated { .. }
-                | CaEvtDet::ChildCertificatesUpdated { .. }
                 | CaEvtDet::BgpSecCertificatesUpdated { .. }
                 | CaEvtDet::ChildKeyRevoked { .. }
                 | CaEvtDet::KeyPendingToNew { .. }
                 | CaEvtDet::KeyPendingToActive { .. }
                 | CaEvtDet::KeyRollFinished { .. } => self.sync_repo(handle.clone(), now()),
 
+                CaEvtDet::ChildCertificatesUpdated { .. } => {
+                    self.sync_repo(handle.clone(), now());
+
+                    // A child may have been re-issued without resources
+                    // which are being transferred to another child.
+                    if !ca.resource_transfers_waiting().is_empty() {
+                        self.resource_transfers(handle.clone(), now());
+                    }
+                }
+
+                CaEvtDet::ResourceTransferStarted { .. } => self.resource_transfers(handle.clone(), now()),
+
+                CaEvtDet::ScheduledChangeAdded { change } => {
+                    self.scheduled_changes(handle.clone(), change.activate_at().into())
+                }
+
                 CaEvtDet::KeyRollActivated {
                     resource_class_name, ..
                 } => {
@@ -292,6 +640,21 @@ impl eventsourcing::PostSaveEventListener<CertAuth> for TaskQueue {
                     self.sync_repo(handle.clone(), now());
                 }
 
+                CaEvtDet::KeyRollAborted {
+                    resource_class_name,
+                    revoke_req,
+                } => {
+                    // A new key which was already certified must be revoked.
+                    if revoke_req.is_some() {
+                        if let Ok(parent) = ca.parent_for_rc(resource_class_name) {
+                            self.sync_parent(handle.clone(), parent.clone(), now());
+                        }
+                    }
+                    self.sync_repo(handle.clone(), now());
+                }
+
+                CaEvtDet::RepoMigrationAborted { .. } => self.sync_repo(handle.clone(), now()),
+
                 CaEvtDet::ParentRemoved { parent } => {
                     self.drop_sync_parent(handle.clone(), parent.clone());
                     self.sync_repo(handle.clone(), now());
@@ -345,6 +708,13 @@ impl eventsourcing::PostSaveEventListener<CertAuth> for TaskQueue {
                         self.sync_parent(handle.clone(), parent.clone(), now());
                     }
                 }
+                CaEvtDet::ClassRepoUpdated {
+                    resource_class_name, ..
+                } => {
+                    if let Ok(parent) = ca.parent_for_rc(resource_class_name) {
+                        self.sync_parent(handle.clone(), parent.clone(), now());
+                    }
+                }
                 CaEvtDet::CertificateRequested {
                     resource_class_name, ..
                 } => {
@@ -454,3 +824,40 @@ impl From<Time> for Priority {
         Priority(time.timestamp())
     }
 }
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn trigger_and_pause_tasks() {
+        let tasks = TaskQueue::default();
+        tasks.republish_if_needed(in_hours(1));
+        tasks.expiry_alerts(in_hours(2));
+
+        assert!(tasks.pop(now()).is_none());
+        assert_eq!(tasks.list().tasks()[0].name(), "republish");
+
+        tasks.trigger("expiry-alerts").unwrap();
+        assert!(tasks.trigger("rrdp-update").is_err());
+        assert!(tasks.trigger("no-such-task").is_err());
+
+        let task = tasks.pop(in_seconds(1)).unwrap();
+        assert_eq!(task, Task::ExpiryAlerts);
+
+        tasks.finished(&task, None);
+        let list = tasks.list();
+        let last = list.tasks().last().unwrap();
+        assert_eq!(last.name(), "expiry-alerts");
+        assert!(last.next_run().is_none());
+        assert!(last.last_run().unwrap().error().is_none());
+
+        tasks.pause("republish").unwrap();
+        assert!(tasks.is_paused(&Task::RepublishIfNeeded));
+        tasks.resume("republish").unwrap();
+        assert!(!tasks.is_paused(&Task::RepublishIfNeeded));
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    fmt,
+    io::{BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{constants::NOTIFY_SMTP_TIMEOUT_SECS, daemon::notify::Alert};
+
+//------------ EmailChannelConfig --------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct EmailChannelConfig {
+    /// The SMTP server to relay through, as "host:port".
+    pub smtp_server: String,
+
+    /// The sender address.
+    pub from: String,
+
+    /// The recipient addresses.
+    pub to: Vec<String>,
+}
+
+impl EmailChannelConfig {
+    /// Verifies that the addresses can be used safely in SMTP commands and
+    /// mail headers.
+    pub fn verify(&self) -> Result<(), String> {
+        if self.to.is_empty() {
+            return Err(format!("email alert channel from '{}' has no recipients", self.from));
+        }
+
+        for address in std::iter::once(&self.from).chain(self.to.iter()) {
+            if !address.contains('@') || address.contains(|c: char| c.is_whitespace() || c == '<' || c == '>') {
+                return Err(format!("invalid email address '{}' in alert channel", address));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ EmailChannel --------------------------------------------------
+
+/// Sends alerts as plain text email through an SMTP relay.
+///
+/// Only plain SMTP without authentication is supported. Use a local mail
+/// transfer agent to relay to servers which require TLS or authentication.
+#[derive(Clone, Debug)]
+pub struct EmailChannel {
+    config: EmailChannelConfig,
+}
+
+impl EmailChannel {
+    pub fn new(config: EmailChannelConfig) -> Self {
+        EmailChannel { config }
+    }
+
+    pub async fn send(&self, alert: &Alert) -> Result<(), String> {
+        let channel = self.clone();
+        let alert = alert.clone();
+
+        tokio::task::spawn_blocking(move || channel.send_blocking(&alert))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    fn send_blocking(&self, alert: &Alert) -> Result<(), String> {
+        let mut session = SmtpSession::connect(&self.config.smtp_server)?;
+
+        session.expect(220)?;
+        session.command("EHLO krill", 250)?;
+        session.command(&format!("MAIL FROM:<{}>", self.config.from), 250)?;
+        for to in &self.config.to {
+            session.command(&format!("RCPT TO:<{}>", to), 250)?;
+        }
+        session.command("DATA", 354)?;
+        session.command(&self.message(alert), 250)?;
+        session.command("QUIT", 221)
+    }
+
+    /// Returns the message including headers, ending with the final '.'
+    /// line. Lines starting with a '.' are escaped as per RFC 5321.
+    fn message(&self, alert: &Alert) -> String {
+        let mut message = String::new();
+        message.push_str(&format!("From: {}\r\n", self.config.from));
+        message.push_str(&format!("To: {}\r\n", self.config.to.join(", ")));
+        message.push_str(&format!("Subject: {}\r\n", alert.summary()));
+        message.push_str(&format!("Date: {}\r\n", chrono::Utc::now().to_rfc2822()));
+        message.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+        message.push_str("\r\n");
+
+        for line in alert.message().lines() {
+            if line.starts_with('.') {
+                message.push('.');
+            }
+            message.push_str(line);
+            message.push_str("\r\n");
+        }
+        message.push('.');
+
+        message
+    }
+}
+
+impl fmt::Display for EmailChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "email to '{}'", self.config.to.join(", "))
+    }
+}
+
+//------------ SmtpSession ---------------------------------------------------
+
+struct SmtpSession {
+    server: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl SmtpSession {
+    fn connect(server: &str) -> Result<Self, String> {
+        let timeout = Duration::from_secs(NOTIFY_SMTP_TIMEOUT_SECS);
+
+        let addr = server
+            .to_socket_addrs()
+            .map_err(|e| format!("cannot resolve SMTP server '{}': {}", server, e))?
+            .next()
+            .ok_or_else(|| format!("cannot resolve SMTP server '{}'", server))?;
+
+        let stream = TcpStream::connect_timeout(&addr, timeout)
+            .map_err(|e| format!("cannot connect to SMTP server '{}': {}", server, e))?;
+        stream.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+        stream.set_write_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+
+        let writer = stream.try_clone().map_err(|e| e.to_string())?;
+
+        Ok(SmtpSession {
+            server: server.to_string(),
+            reader: BufReader::new(stream),
+            writer,
+        })
+    }
+
+    fn command(&mut self, command: &str, code: u16) -> Result<(), String> {
+        self.writer
+            .write_all(format!("{}\r\n", command).as_bytes())
+            .map_err(|e| format!("cannot write to SMTP server '{}': {}", self.server, e))?;
+        self.expect(code)
+    }
+
+    /// Reads a (possibly multi-line) reply and checks its code.
+    fn expect(&mut self, code: u16) -> Result<(), String> {
+        loop {
+            let mut line = String::new();
+            self.reader
+                .read_line(&mut line)
+                .map_err(|e| format!("cannot read from SMTP server '{}': {}", self.server, e))?;
+
+            let line = line.trim_end();
+            let received = line.get(0..3).and_then(|c| c.parse::<u16>().ok());
+
+            if received != Some(code) {
+                return Err(format!("unexpected reply from SMTP server '{}': {}", self.server, line));
+            }
+
+            // A '-' after the code indicates that more lines follow.
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return Ok(());
+            }
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, str::FromStr, thread};
+
+    use rpki::ca::idexchange::CaHandle;
+
+    use super::*;
+    use crate::daemon::notify::AlertEvent;
+
+    #[test]
+    fn send_email_using_smtp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let smtp_server = listener.local_addr().unwrap().to_string();
+
+        // A minimal SMTP server which accepts one message and returns the
+        // commands and data it received.
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut received = vec![];
+
+            writer.write_all(b"220 localhost ESMTP\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end().to_string();
+                received.push(line.clone());
+
+                let reply: &[u8] = if line.starts_with("EHLO") {
+                    b"250-localhost\r\n250 8BITMIME\r\n"
+                } else if line == "DATA" {
+                    b"354 go ahead\r\n"
+                } else if line == "." {
+                    b"250 queued\r\n"
+                } else if line == "QUIT" {
+                    writer.write_all(b"221 bye\r\n").unwrap();
+                    return received;
+                } else if line.starts_with("MAIL") || line.starts_with("RCPT") {
+                    b"250 ok\r\n"
+                } else {
+                    continue; // message data
+                };
+                writer.write_all(reply).unwrap();
+            }
+        });
+
+        let channel = EmailChannel::new(EmailChannelConfig {
+            smtp_server,
+            from: "krill@example.com".to_string(),
+            to: vec!["noc@example.com".to_string()],
+        });
+        let alert = Alert::new(
+            AlertEvent::RoaChange,
+            CaHandle::from_str("ca").unwrap(),
+            "first line\n.second line",
+        );
+
+        channel.send_blocking(&alert).unwrap();
+
+        let received = server.join().unwrap();
+        assert_eq!(received[1], "MAIL FROM:<krill@example.com>");
+        assert_eq!(received[2], "RCPT TO:<noc@example.com>");
+        assert!(received.contains(&"Subject: Krill ROA change alert for CA 'ca'".to_string()));
+        assert!(received.contains(&"..second line".to_string()));
+        assert_eq!(received.last().unwrap(), "QUIT");
+    }
+}
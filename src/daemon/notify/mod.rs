@@ -0,0 +1,280 @@
+//! Alerts about CA events which may require the attention of an operator,
+//! and the channels used to send them, e.g. a webhook or email.
+//!
+//! Additional channels can be supported by adding a variant to the
+//! [`NotificationChannel`] enum, and a corresponding configuration type to
+//! [`NotificationChannelConfig`].
+//!
+//! The [`EventStream`] in the `stream` module serves a live stream of CA and
+//! publication server events to clients instead.
+mod email;
+pub use self::email::*;
+
+mod stream;
+pub use self::stream::*;
+
+mod webhook;
+pub use self::webhook::*;
+
+use std::{fmt, sync::Arc};
+
+use rpki::ca::idexchange::CaHandle;
+
+use crate::{
+    commons::{api::Timestamp, eventsourcing},
+    daemon::{
+        ca::{CaEvt, CaEvtDet, CertAuth},
+        config::{AlertsConfig, NotificationChannelConfig},
+    },
+};
+
+//------------ AlertEvent ----------------------------------------------------
+
+/// The kinds of events for which alerts can be sent.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertEvent {
+    /// A certificate received from a parent is nearing expiry.
+    Expiry,
+
+    /// The CA failed to contact its parent.
+    ParentFailure,
+
+    /// The CA failed to publish at its repository.
+    PublicationFailure,
+
+    /// The CA issued a certificate to a child at its request.
+    ChildRequest,
+
+    /// The ROAs of the CA changed.
+    RoaChange,
+}
+
+impl AlertEvent {
+    pub fn all() -> Vec<AlertEvent> {
+        vec![
+            AlertEvent::Expiry,
+            AlertEvent::ParentFailure,
+            AlertEvent::PublicationFailure,
+            AlertEvent::ChildRequest,
+            AlertEvent::RoaChange,
+        ]
+    }
+}
+
+impl fmt::Display for AlertEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertEvent::Expiry => write!(f, "expiry"),
+            AlertEvent::ParentFailure => write!(f, "parent failure"),
+            AlertEvent::PublicationFailure => write!(f, "publication failure"),
+            AlertEvent::ChildRequest => write!(f, "child request"),
+            AlertEvent::RoaChange => write!(f, "ROA change"),
+        }
+    }
+}
+
+//------------ Alert ---------------------------------------------------------
+
+/// An alert about an event for a CA. This is also the JSON payload posted
+/// to webhooks.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Alert {
+    event: AlertEvent,
+    ca: CaHandle,
+    timestamp: Timestamp,
+    message: String,
+}
+
+impl Alert {
+    pub fn new(event: AlertEvent, ca: CaHandle, message: impl fmt::Display) -> Self {
+        Alert {
+            event,
+            ca,
+            timestamp: Timestamp::now(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn event(&self) -> AlertEvent {
+        self.event
+    }
+
+    pub fn ca(&self) -> &CaHandle {
+        &self.ca
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// A one line summary, used e.g. as the subject of an email.
+    pub fn summary(&self) -> String {
+        format!("Krill {} alert for CA '{}'", self.event, self.ca)
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.summary(), self.message)
+    }
+}
+
+//------------ NotificationChannel -------------------------------------------
+
+/// A channel through which alerts are sent.
+#[derive(Clone, Debug)]
+pub enum NotificationChannel {
+    Webhook(WebhookChannel),
+    Email(EmailChannel),
+}
+
+impl NotificationChannel {
+    pub fn build(config: &NotificationChannelConfig) -> Self {
+        match config {
+            NotificationChannelConfig::Webhook(config) => {
+                NotificationChannel::Webhook(WebhookChannel::new(config.clone()))
+            }
+            NotificationChannelConfig::Email(config) => NotificationChannel::Email(EmailChannel::new(config.clone())),
+        }
+    }
+
+    pub async fn send(&self, alert: &Alert) -> Result<(), String> {
+        match self {
+            NotificationChannel::Webhook(channel) => channel.send(alert).await,
+            NotificationChannel::Email(channel) => channel.send(alert).await,
+        }
+    }
+}
+
+impl fmt::Display for NotificationChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotificationChannel::Webhook(channel) => channel.fmt(f),
+            NotificationChannel::Email(channel) => channel.fmt(f),
+        }
+    }
+}
+
+//------------ Notifier ------------------------------------------------------
+
+/// Sends alerts for the configured events to all configured channels.
+///
+/// Alerts are sent in the background, so that CA operations are never held
+/// up by slow or unavailable channels. Failures to send are logged.
+#[derive(Debug)]
+pub struct Notifier {
+    config: AlertsConfig,
+    channels: Vec<Arc<NotificationChannel>>,
+}
+
+impl Notifier {
+    pub fn build(config: &AlertsConfig) -> Self {
+        Notifier {
+            config: config.clone(),
+            channels: config
+                .channels
+                .iter()
+                .map(|channel| Arc::new(NotificationChannel::build(channel)))
+                .collect(),
+        }
+    }
+
+    pub fn notify(&self, alert: Alert) {
+        if !self.config.is_enabled(alert.event()) {
+            return;
+        }
+
+        let runtime = match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => runtime,
+            Err(_) => {
+                warn!("Cannot send alert outside of async runtime: {}", alert);
+                return;
+            }
+        };
+
+        for channel in &self.channels {
+            let channel = channel.clone();
+            let alert = alert.clone();
+            runtime.spawn(async move {
+                if let Err(e) = channel.send(&alert).await {
+                    warn!("Could not send alert using {}: {}. Alert was: {}", channel, e, alert);
+                }
+            });
+        }
+    }
+}
+
+/// Implement listening for CertAuth events which warrant an alert.
+impl eventsourcing::PostSaveEventListener<CertAuth> for Notifier {
+    fn listen(&self, ca: &CertAuth, events: &[CaEvt]) {
+        let mut roa_changes = vec![];
+
+        for event in events {
+            match event.details() {
+                CaEvtDet::ChildCertificateIssued { .. } => self.notify(Alert::new(
+                    AlertEvent::ChildRequest,
+                    ca.handle().clone(),
+                    event.details(),
+                )),
+                CaEvtDet::RouteAuthorizationAdded { .. }
+                | CaEvtDet::RouteAuthorizationRemoved { .. }
+                | CaEvtDet::RouteAuthorizationDisabled { .. }
+                | CaEvtDet::RouteAuthorizationEnabled { .. } => roa_changes.push(event.details().to_string()),
+                _ => {}
+            }
+        }
+
+        // Send a single alert for all changes in a ROA update.
+        if !roa_changes.is_empty() {
+            self.notify(Alert::new(
+                AlertEvent::RoaChange,
+                ca.handle().clone(),
+                roa_changes.join(", "),
+            ));
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use hyper::StatusCode;
+
+    use crate::test;
+
+    #[tokio::test]
+    async fn send_enabled_alerts() {
+        let (url, received) = test::start_webhook_receiver(StatusCode::OK);
+        let config = AlertsConfig {
+            events: vec![AlertEvent::ParentFailure],
+            channels: vec![NotificationChannelConfig::Webhook(WebhookChannelConfig { url })],
+            ..Default::default()
+        };
+        assert!(config.is_enabled(AlertEvent::ParentFailure));
+        assert!(!config.is_enabled(AlertEvent::RoaChange));
+
+        let notifier = Notifier::build(&config);
+        let ca = CaHandle::from_str("ca").unwrap();
+        notifier.notify(Alert::new(AlertEvent::RoaChange, ca.clone(), "ROAs changed"));
+        notifier.notify(Alert::new(AlertEvent::ParentFailure, ca, "parent unreachable"));
+
+        let payloads = test::wait_for_webhook_payloads(&received, 1).await;
+        assert_eq!(payloads.len(), 1);
+        let alert: Alert = serde_json::from_value(payloads[0].clone()).unwrap();
+        assert_eq!(alert.event(), AlertEvent::ParentFailure);
+        assert_eq!(alert.message(), "parent unreachable");
+
+        // Without channels no alerts are sent at all.
+        assert!(!AlertsConfig::default().is_enabled(AlertEvent::ParentFailure));
+    }
+}
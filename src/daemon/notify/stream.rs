@@ -0,0 +1,331 @@
+//! A live stream of CA and publication server events, which is served to the
+//! UI and other clients as Server-Sent Events, so that they can update their
+//! views when something changes rather than poll.
+//!
+//! Events are only kept in memory. Clients which connect receive the events
+//! which happen from then on, and clients which cannot keep up are told how
+//! many events they missed.
+use std::fmt;
+
+use rpki::ca::idexchange::CaHandle;
+use tokio::sync::broadcast;
+
+use crate::{
+    commons::{actor::Actor, api::Timestamp, eventsourcing},
+    constants::NO_RESOURCE,
+    daemon::{
+        auth::{common::permissions::Permission, Handle},
+        ca::{CaEvt, CaEvtDet, CertAuth},
+    },
+    pubd::RepositoryEvent,
+};
+
+/// The number of events kept for clients which are slow to read them.
+const EVENT_STREAM_CAPACITY: usize = 1024;
+
+//------------ CaEventType ---------------------------------------------------
+
+/// The kinds of CA events in the stream.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaEventType {
+    /// ROAs were added or removed.
+    RoaChange,
+
+    /// ASPA definitions were added, updated or removed.
+    AspaChange,
+
+    /// A key roll was started, progressed or finished.
+    KeyRoll,
+
+    /// A parent was added, updated or removed, or a certificate was received.
+    ParentChange,
+
+    /// A child was added, updated or removed, or a certificate was issued to it.
+    ChildChange,
+
+    /// The repository of the CA was updated.
+    RepositoryChange,
+
+    /// The CA failed to contact its parent.
+    ParentFailure,
+
+    /// The CA failed to publish at its repository.
+    PublicationFailure,
+}
+
+impl CaEventType {
+    fn for_event(details: &CaEvtDet) -> Option<Self> {
+        match details {
+            CaEvtDet::RouteAuthorizationAdded { .. }
+            | CaEvtDet::RouteAuthorizationRemoved { .. }
+            | CaEvtDet::RouteAuthorizationDisabled { .. }
+            | CaEvtDet::RouteAuthorizationEnabled { .. } => Some(CaEventType::RoaChange),
+            CaEvtDet::AspaConfigAdded { .. }
+            | CaEvtDet::AspaConfigUpdated { .. }
+            | CaEvtDet::AspaConfigRemoved { .. } => Some(CaEventType::AspaChange),
+            CaEvtDet::KeyRollPendingKeyAdded { .. }
+            | CaEvtDet::KeyPendingToNew { .. }
+            | CaEvtDet::KeyPendingToActive { .. }
+            | CaEvtDet::KeyRollActivated { .. }
+            | CaEvtDet::KeyRollFinished { .. }
+            | CaEvtDet::KeyRollAborted { .. } => Some(CaEventType::KeyRoll),
+            CaEvtDet::ParentAdded { .. }
+            | CaEvtDet::ParentUpdated { .. }
+            | CaEvtDet::ParentRemoved { .. }
+            | CaEvtDet::CertificateReceived { .. } => Some(CaEventType::ParentChange),
+            CaEvtDet::ChildAdded { .. }
+            | CaEvtDet::ChildCertificateIssued { .. }
+            | CaEvtDet::ChildUpdatedResources { .. }
+            | CaEvtDet::ChildRemoved { .. }
+            | CaEvtDet::ChildSuspended { .. }
+            | CaEvtDet::ChildUnsuspended { .. } => Some(CaEventType::ChildChange),
+            CaEvtDet::RepoUpdated { .. }
+            | CaEvtDet::ClassRepoUpdated { .. }
+            | CaEvtDet::RepoMigrationAborted { .. } => Some(CaEventType::RepositoryChange),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CaEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaEventType::RoaChange => write!(f, "ROA change"),
+            CaEventType::AspaChange => write!(f, "ASPA change"),
+            CaEventType::KeyRoll => write!(f, "key roll"),
+            CaEventType::ParentChange => write!(f, "parent change"),
+            CaEventType::ChildChange => write!(f, "child change"),
+            CaEventType::RepositoryChange => write!(f, "repository change"),
+            CaEventType::ParentFailure => write!(f, "parent failure"),
+            CaEventType::PublicationFailure => write!(f, "publication failure"),
+        }
+    }
+}
+
+//------------ CaEvent -------------------------------------------------------
+
+/// An event for a CA in the stream.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CaEvent {
+    event: CaEventType,
+    ca: CaHandle,
+    timestamp: Timestamp,
+    message: String,
+}
+
+impl CaEvent {
+    pub fn new(event: CaEventType, ca: CaHandle, message: impl fmt::Display) -> Self {
+        CaEvent {
+            event,
+            ca,
+            timestamp: Timestamp::now(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn event(&self) -> CaEventType {
+        self.event
+    }
+
+    pub fn ca(&self) -> &CaHandle {
+        &self.ca
+    }
+}
+
+//------------ LiveEvent -----------------------------------------------------
+
+/// An event in the stream, either for a CA or for the publication server.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LiveEvent {
+    Ca(CaEvent),
+    Repository(RepositoryEvent),
+}
+
+impl LiveEvent {
+    /// Returns the CA this event is about, or None for publication server
+    /// events.
+    pub fn ca(&self) -> Option<&CaHandle> {
+        match self {
+            LiveEvent::Ca(event) => Some(event.ca()),
+            LiveEvent::Repository(_) => None,
+        }
+    }
+
+    /// Returns true if the event may be streamed to the actor. Events for a
+    /// CA require that the actor may read the CA, and that the CA is one of
+    /// the given CAs, if any. Publication server events require that the
+    /// actor may administer it, and are left out if CAs are given.
+    pub fn is_visible(&self, cas: Option<&[CaHandle]>, actor: &Actor) -> bool {
+        match self.ca() {
+            Some(ca) => {
+                cas.map(|cas| cas.contains(ca)).unwrap_or(true)
+                    && matches!(actor.is_allowed(Permission::CA_READ, Handle::from(ca)), Ok(true))
+            }
+            None => cas.is_none() && matches!(actor.is_allowed(Permission::PUB_ADMIN, NO_RESOURCE), Ok(true)),
+        }
+    }
+
+    /// Renders the event as a Server-Sent Event. The name of the event is
+    /// "ca" or "repository", and the data is the event as JSON.
+    pub fn to_sse(&self) -> String {
+        let (name, data) = match self {
+            LiveEvent::Ca(event) => ("ca", serde_json::to_string(event)),
+            LiveEvent::Repository(event) => ("repository", serde_json::to_string(event)),
+        };
+        // Neither can fail, as they only contain strings and numbers.
+        format!("event: {}\ndata: {}\n\n", name, data.unwrap_or_default())
+    }
+
+    /// Renders the Server-Sent Event which tells a client that it was too
+    /// slow and missed events, so that it can fully refresh its view.
+    pub fn lagged_sse(missed: u64) -> String {
+        format!("event: lagged\ndata: {{\"missed\":{}}}\n\n", missed)
+    }
+}
+
+impl From<CaEvent> for LiveEvent {
+    fn from(event: CaEvent) -> Self {
+        LiveEvent::Ca(event)
+    }
+}
+
+impl From<RepositoryEvent> for LiveEvent {
+    fn from(event: RepositoryEvent) -> Self {
+        LiveEvent::Repository(event)
+    }
+}
+
+//------------ EventStream ---------------------------------------------------
+
+/// Broadcasts events to all connected clients.
+#[derive(Debug)]
+pub struct EventStream {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl Default for EventStream {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_STREAM_CAPACITY);
+        EventStream { sender }
+    }
+}
+
+impl EventStream {
+    /// Returns true if any client is connected, so that the cost of creating
+    /// events can be avoided if there are none.
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, event: impl Into<LiveEvent>) {
+        // This only fails if no client is connected, which is fine.
+        let _ = self.sender.send(event.into());
+    }
+}
+
+/// Implement listening for CertAuth events which are of interest to clients.
+impl eventsourcing::PostSaveEventListener<CertAuth> for EventStream {
+    fn listen(&self, ca: &CertAuth, events: &[CaEvt]) {
+        if !self.has_subscribers() {
+            return;
+        }
+
+        // Send a single event for each type of change in a command, e.g. for
+        // all ROAs added and removed in a ROA update.
+        let mut changes: Vec<(CaEventType, Vec<String>)> = vec![];
+        for event in events {
+            if let Some(event_type) = CaEventType::for_event(event.details()) {
+                let details = event.details().to_string();
+                match changes.iter_mut().find(|(existing, _)| *existing == event_type) {
+                    Some((_, messages)) => messages.push(details),
+                    None => changes.push((event_type, vec![details])),
+                }
+            }
+        }
+
+        for (event_type, messages) in changes {
+            self.publish(CaEvent::new(event_type, ca.handle().clone(), messages.join(", ")));
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn publish_to_subscribers() {
+        let stream = EventStream::default();
+        assert!(!stream.has_subscribers());
+
+        // Publishing without subscribers is fine.
+        let ca = CaHandle::from_str("ca").unwrap();
+        stream.publish(CaEvent::new(CaEventType::ParentFailure, ca.clone(), "no"));
+
+        let mut receiver = stream.subscribe();
+        assert!(stream.has_subscribers());
+
+        stream.publish(CaEvent::new(CaEventType::RoaChange, ca.clone(), "added"));
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(Some(&ca), event.ca());
+
+        let sse = event.to_sse();
+        assert!(sse.starts_with("event: ca\ndata: {\"event\":\"roa_change\",\"ca\":\"ca\""));
+        assert!(sse.ends_with("\n\n"));
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn visible_events() {
+        use std::{collections::HashMap, sync::Arc};
+
+        use crate::{
+            commons::{actor::ActorDef, api::PublishDelta},
+            constants::ACTOR_DEF_ADMIN_TOKEN,
+            daemon::{auth::policy::AuthPolicy, config::Config},
+            test,
+        };
+
+        test::test_under_tmp(|d| {
+            let config = Config::test(&d, false, false, false, false);
+            let policy = AuthPolicy::new(Arc::new(config)).unwrap();
+
+            let mut attributes = HashMap::new();
+            attributes.insert("role".to_string(), "readonly".to_string());
+            attributes.insert("inc_cas".to_string(), "ca1".to_string());
+            let reader = Actor::new(ActorDef::user("joe".to_string(), attributes, None), policy.clone());
+            let admin = Actor::new(ACTOR_DEF_ADMIN_TOKEN, policy);
+
+            let ca1 = CaHandle::from_str("ca1").unwrap();
+            let ca2 = CaHandle::from_str("ca2").unwrap();
+            let ca1_event = LiveEvent::from(CaEvent::new(CaEventType::RoaChange, ca1.clone(), "added"));
+            let ca2_event = LiveEvent::from(CaEvent::new(CaEventType::RoaChange, ca2, "added"));
+            let repo_event = LiveEvent::from(RepositoryEvent::publish("ca1".parse().unwrap(), &PublishDelta::empty()));
+
+            // Readers only see the CAs they may read, and no publication
+            // server events.
+            assert!(ca1_event.is_visible(None, &reader));
+            assert!(!ca2_event.is_visible(None, &reader));
+            assert!(!repo_event.is_visible(None, &reader));
+
+            assert!(ca1_event.is_visible(None, &admin));
+            assert!(ca2_event.is_visible(None, &admin));
+            assert!(repo_event.is_visible(None, &admin));
+
+            // Only events for the requested CAs are included.
+            let cas = [ca1];
+            assert!(ca1_event.is_visible(Some(&cas), &admin));
+            assert!(!ca2_event.is_visible(Some(&cas), &admin));
+            assert!(!repo_event.is_visible(Some(&cas), &admin));
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::commons::util::httpclient;
+
+//------------ WebhookChannelConfig ------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct WebhookChannelConfig {
+    /// The URL to which alerts are posted as JSON.
+    pub url: String,
+}
+
+impl WebhookChannelConfig {
+    /// Verifies that the URL is an absolute HTTP or HTTPS URL.
+    pub fn verify(&self) -> Result<(), String> {
+        let url = url::Url::parse(&self.url).map_err(|e| format!("invalid webhook URL '{}': {}", self.url, e))?;
+
+        if !matches!(url.scheme(), "http" | "https") || url.host().is_none() {
+            return Err(format!(
+                "invalid webhook URL '{}': must be an http or https URL",
+                self.url
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+//------------ WebhookChannel ------------------------------------------------
+
+/// Posts alerts, or other payloads, as JSON to a URL. Any 2xx response is
+/// taken as success.
+#[derive(Clone, Debug)]
+pub struct WebhookChannel {
+    config: WebhookChannelConfig,
+}
+
+impl WebhookChannel {
+    pub fn new(config: WebhookChannelConfig) -> Self {
+        WebhookChannel { config }
+    }
+
+    pub async fn send(&self, payload: &impl Serialize) -> Result<(), String> {
+        let url = self.config.url.as_str();
+
+        let res = httpclient::client(url)
+            .map_err(|e| e.to_string())?
+            .post(url)
+            .json(payload)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if res.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("webhook responded with status {}", res.status()))
+        }
+    }
+}
+
+impl fmt::Display for WebhookChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "webhook '{}'", self.config.url)
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hyper::StatusCode;
+
+    use crate::test;
+
+    fn webhook(url: &str) -> WebhookChannelConfig {
+        WebhookChannelConfig { url: url.to_string() }
+    }
+
+    #[test]
+    fn verify_url() {
+        assert!(webhook("https://example.com/alerts").verify().is_ok());
+        assert!(webhook("http://127.0.0.1:8080/alerts").verify().is_ok());
+
+        assert!(webhook("example.com/alerts").verify().is_err());
+        assert!(webhook("ftp://example.com/alerts").verify().is_err());
+        assert!(webhook("file:///tmp/alerts").verify().is_err());
+    }
+
+    #[tokio::test]
+    async fn post_json() {
+        let (url, received) = test::start_webhook_receiver(StatusCode::OK);
+        let channel = WebhookChannel::new(webhook(&url));
+
+        channel.send(&serde_json::json!({ "message": "hello" })).await.unwrap();
+        assert_eq!(
+            received.lock().unwrap().as_slice(),
+            &[serde_json::json!({ "message": "hello" })]
+        );
+
+        let (url, _) = test::start_webhook_receiver(StatusCode::INTERNAL_SERVER_ERROR);
+        let channel = WebhookChannel::new(webhook(&url));
+        assert!(channel.send(&serde_json::json!({})).await.is_err());
+    }
+}
@@ -1,117 +1,263 @@
 //! Deal with asynchronous scheduled processes, either triggered by an
 //! event that occurred, or planned (e.g. re-publishing).
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
 
 use tokio::time::sleep;
 
-use rpki::ca::{
-    idexchange::{CaHandle, ParentHandle},
-    provisioning::{ResourceClassName, RevocationRequest},
+use rpki::{
+    ca::{
+        idexchange::{CaHandle, ChildHandle, ParentHandle, PublisherHandle},
+        provisioning::{ResourceClassName, RevocationRequest},
+    },
+    repository::x509::Time,
 };
 
 use crate::{
-    commons::{actor::Actor, api::Timestamp, bgp::BgpAnalyser, KrillResult},
+    commons::{
+        actor::Actor,
+        api::{RoaConfigurationUpdates, RoaPayload, Timestamp},
+        bgp::BgpAnalyser,
+        eventsourcing::{KeyStoreKey, KeyValueStore},
+        util::logging::LogContext,
+        KrillResult,
+    },
     constants::{
-        SCHEDULER_INTERVAL_RENEW_MINS, SCHEDULER_INTERVAL_REPUBLISH_MINS, SCHEDULER_RESYNC_REPO_CAS_THRESHOLD,
-        SCHEDULER_USE_JITTER_CAS_THRESHOLD,
+        PUBLICATION_CONFIRM_RETRY_SECONDS, SCHEDULER_INTERVAL_RENEW_MINS, SCHEDULER_INTERVAL_REPUBLISH_MINS,
+        SCHEDULER_INTERVAL_RESOURCE_TRANSFER_MINS, SCHEDULER_INTERVAL_ROA_AUTOPILOT_MINS,
+        SCHEDULER_INTERVAL_SCHEDULED_CHANGES_RETRY_MINS, SCHEDULER_RESYNC_REPO_CAS_THRESHOLD,
+        SCHEDULER_USE_JITTER_CAS_THRESHOLD, STATUS_DIR,
     },
     daemon::{
-        ca::CaManager,
+        ca::{testbed_ca_handle, CaManager},
         config::Config,
-        mq::{in_hours, in_minutes, now, Task, TaskQueue},
+        ha::HaManager,
+        jobs,
+        krillserver::KrillServer,
+        mq::{in_hours, in_minutes, in_seconds, now, Task, TaskQueue},
+        notify::AlertEvent,
+        ta::ta_handle,
+        telemetry,
+        timecheck::TimeChecker,
     },
     pubd::RepositoryManager,
 };
 
-#[cfg(feature = "multi-user")]
-use crate::daemon::auth::common::session::LoginSessionCache;
-
 pub struct Scheduler {
     tasks: Arc<TaskQueue>,
     ca_manager: Arc<CaManager>,
     repo_manager: Arc<RepositoryManager>,
     bgp_analyser: Arc<BgpAnalyser>,
-    #[cfg(feature = "multi-user")]
-    // Responsible for purging expired cached login tokens
-    login_session_cache: Arc<LoginSessionCache>,
-    config: Arc<Config>,
+    ha_manager: Option<Arc<HaManager>>,
+    time_checker: Option<Arc<TimeChecker>>,
     system_actor: Actor,
+    autopilot_actor: Actor,
     started: Timestamp,
+    testbed_first_seen: TestbedFirstSeen,
+
+    // The server, for resuming its jobs when this instance becomes active.
+    server: Weak<KrillServer>,
 }
 
 impl Scheduler {
+    #[allow(clippy::too_many_arguments)]
     pub fn build(
         tasks: Arc<TaskQueue>,
         ca_manager: Arc<CaManager>,
         repo_manager: Arc<RepositoryManager>,
         bgp_analyser: Arc<BgpAnalyser>,
-        #[cfg(feature = "multi-user")] login_session_cache: Arc<LoginSessionCache>,
-        config: Arc<Config>,
+        ha_manager: Option<Arc<HaManager>>,
+        time_checker: Option<Arc<TimeChecker>>,
         system_actor: Actor,
-    ) -> Self {
-        Scheduler {
+        autopilot_actor: Actor,
+        server: Weak<KrillServer>,
+    ) -> KrillResult<Self> {
+        let testbed_first_seen = TestbedFirstSeen::new(&ca_manager.config().data_dir)?;
+
+        Ok(Scheduler {
             tasks,
             ca_manager,
             repo_manager,
             bgp_analyser,
-            #[cfg(feature = "multi-user")]
-            login_session_cache,
-            config,
+            ha_manager,
+            time_checker,
             system_actor,
+            autopilot_actor,
             started: Timestamp::now(),
-        }
+            testbed_first_seen,
+            server,
+        })
+    }
+
+    /// Returns the current config. This is shared with the CA manager, so
+    /// that settings which are changed when the config file is reloaded are
+    /// used by the next run of each task.
+    fn config(&self) -> Arc<Config> {
+        self.ca_manager.config()
     }
 
     /// Run the scheduler in the background. It will sweep the message queue for tasks
     /// and re-schedule new tasks as needed.
+    ///
+    /// In high availability mode tasks are only run while this instance is
+    /// the active instance.
     pub async fn run(&self) {
+        let mut active = false;
         loop {
-            while let Some(evt) = self.tasks.pop(now()) {
-                if let Err(e) = match evt {
-                    Task::QueueStartTasks => self.queue_start_tasks().await, // return error and stop server on failure
+            let now_active = self.ha_is_active().await;
+            if now_active && !active {
+                if let Err(e) = self.ha_take_over() {
+                    error!("Fatal error in scheduler: {}", e);
+                    return;
+                }
+            }
+            active = now_active;
+
+            while let Some(task) = active.then(|| self.tasks.pop(now())).flatten() {
+                if self.tasks.is_paused(&task) {
+                    debug!("Postponing paused task: {}", task);
+                    self.tasks.postpone(task);
+                    continue;
+                }
+
+                let res = self.run_task(task.clone()).await;
 
-                    Task::SyncRepo { ca } => self.sync_repo(ca).await,
+                self.tasks.finished(&task, res.as_ref().err().map(|e| e.to_string()));
 
-                    Task::SyncParent { ca, parent } => self.sync_parent(ca, parent).await,
+                if let Err(e) = res {
+                    error!("Fatal error in scheduler: {}", e);
+                    return;
+                }
+            }
+
+            sleep(Duration::from_millis(500)).await;
+        }
+    }
 
-                    Task::SyncTrustAnchorProxySignerIfPossible => self.sync_ta_proxy_signer_if_possible().await,
+    /// Runs the task in a span, which is linked to the spans in which the
+    /// task was scheduled. Log messages for the task refer to the requests
+    /// in which it was scheduled.
+    async fn run_task(&self, task: Task) -> KrillResult<()> {
+        let origins = self.tasks.take_origins(&task);
+        let log_context = LogContext::task(task.name(), origins.requests).with_ca(task.ca());
+        let cx = telemetry::task_span(task.name(), task.to_string(), origins.spans);
+        log_context.scope(telemetry::traced(cx, self.execute_task(task))).await
+    }
 
-                    Task::SuspendChildrenIfNeeded { ca } => self.suspend_children_if_needed(ca).await,
+    async fn execute_task(&self, task: Task) -> KrillResult<()> {
+        match task {
+            Task::QueueStartTasks => self.queue_start_tasks().await, // return error and stop server on failure
 
-                    Task::RepublishIfNeeded => self.republish_if_needed().await,
+            Task::SyncRepo { ca } => self.sync_repo(ca).await,
 
-                    Task::RenewObjectsIfNeeded => self.renew_objects_if_needed().await,
+            Task::SyncParent { ca, parent } => self.sync_parent(ca, parent).await,
 
-                    Task::RefreshAnnouncementsInfo => self.announcements_refresh().await,
+            Task::SyncTrustAnchorProxySignerIfPossible => self.sync_ta_proxy_signer_if_possible().await,
 
-                    #[cfg(feature = "multi-user")]
-                    Task::SweepLoginCache => self.sweep_login_cache(),
+            Task::SuspendChildrenIfNeeded { ca } => self.suspend_children_if_needed(ca).await,
 
-                    Task::UpdateSnapshots => self.update_snapshots(),
+            Task::ResourceTransfers { ca } => self.resource_transfers(ca).await,
 
-                    Task::RrdpUpdateIfNeeded => self.update_rrdp_if_needed(),
+            Task::ScheduledChanges { ca } => self.scheduled_changes(ca).await,
 
-                    Task::ResourceClassRemoved {
-                        ca,
-                        parent,
-                        rcn,
-                        revocation_requests,
-                    } => self.resource_class_removed(ca, parent, rcn, revocation_requests).await,
+            Task::RepublishIfNeeded => self.republish_if_needed().await,
 
-                    Task::UnexpectedKey {
-                        ca,
-                        rcn,
-                        revocation_request,
-                    } => self.unexpected_key(ca, rcn, revocation_request).await,
-                } {
-                    error!("Fatal error in scheduler: {}", e);
-                    return;
-                }
+            Task::RenewObjectsIfNeeded => self.renew_objects_if_needed().await,
+
+            Task::RefreshAnnouncementsInfo => self.announcements_refresh().await,
+
+            Task::RoaAutoPilot => self.roa_autopilot().await,
+
+            Task::UpdateSnapshots => self.update_snapshots(),
+
+            Task::ArchiveHistory => self.archive_history(),
+
+            Task::RrdpUpdateIfNeeded => self.update_rrdp_if_needed(),
+
+            Task::RrdpSessionResetIfNeeded => self.rrdp_session_reset_if_needed(),
+
+            Task::RepositoryArchive => self.repository_archive().await,
+
+            Task::RepositoryReplicate => self.repository_replicate().await,
+
+            Task::RepositoryS3Sync => self.repository_s3_sync().await,
+
+            Task::PublicationCheck => self.publication_check().await,
+
+            Task::Reconcile => self.reconcile().await,
+
+            Task::CheckTime => self.check_time().await,
+
+            Task::ConfirmPublication { ca } => self.confirm_publication(ca).await,
+
+            Task::ExpiryAlerts => self.expiry_alerts().await,
+
+            Task::TestbedCleanup => self.testbed_cleanup().await,
+
+            Task::ResourceClassRemoved {
+                ca,
+                parent,
+                rcn,
+                revocation_requests,
+            } => self.resource_class_removed(ca, parent, rcn, revocation_requests).await,
+
+            Task::UnexpectedKey {
+                ca,
+                rcn,
+                revocation_request,
+            } => self.unexpected_key(ca, rcn, revocation_request).await,
+        }
+    }
+
+    /// Renews the high availability lease if needed, and returns whether
+    /// this instance is active. Always true if high availability is not used.
+    ///
+    /// The lease is renewed on the blocking thread pool, so that a slow
+    /// database does not hold up other work on the runtime.
+    async fn ha_is_active(&self) -> bool {
+        let ha = match self.ha_manager.clone() {
+            None => return true,
+            Some(ha) => ha,
+        };
+
+        let renewer = ha.clone();
+        match tokio::task::spawn_blocking(move || renewer.renew_lease_if_due()).await {
+            Ok(Ok(active)) => active,
+            Ok(Err(e)) => {
+                error!("Could not renew high availability lease: {}", e);
+                ha.is_active()
             }
+            Err(e) => {
+                error!("Could not renew high availability lease: {}", e);
+                ha.is_active()
+            }
+        }
+    }
 
-            sleep(Duration::from_millis(500)).await;
+    /// Prepares this instance to do the work when it becomes active. The
+    /// start tasks are queued so that CAs are synced, and the repository
+    /// files are written from the shared storage. This is harmless if this
+    /// instance was active from the start.
+    ///
+    /// The jobs which were running when Krill stopped, or which the
+    /// previously active instance was running, are resumed. Without high
+    /// availability this is done when the scheduler starts.
+    fn ha_take_over(&self) -> KrillResult<()> {
+        if self.ha_manager.is_some() {
+            self.tasks.server_started();
+            if self.repo_manager.initialized()? {
+                self.repo_manager.write_repository()?;
+            }
         }
+        if let Some(server) = self.server.upgrade() {
+            jobs::resume(&server);
+        }
+        Ok(())
     }
 
     /// Queues tasks for background jobs when the server is started
@@ -124,6 +270,7 @@ impl Scheduler {
         // to avoid a thundering herd. Note that the operator can always
         // choose to run bulk operations manually if they know that they
         // cannot wait.
+        let config = self.config();
         let ca_list = self.ca_manager.ca_list(&self.system_actor)?;
         let cas = ca_list.cas();
 
@@ -135,7 +282,7 @@ impl Scheduler {
         for summary in cas {
             let ca = self.ca_manager.get_ca(summary.handle()).await?;
 
-            let too_many_parents = ca.nr_parents() >= self.config.ca_refresh_parents_batch_size;
+            let too_many_parents = ca.nr_parents() >= config.ca_refresh_parents_batch_size;
 
             // Plan a regular sync for each parent. Spread these out if there
             // are too many CAs or parents for a CA. In cases where there are only
@@ -155,7 +302,7 @@ impl Scheduler {
                     "Will force jitter for sync between CA {} and parents. Nr of parents ({}) exceeds batch size ({})",
                     ca.handle(),
                     ca.nr_parents(),
-                    self.config.ca_refresh_parents_batch_size
+                    config.ca_refresh_parents_batch_size
                 )
             }
 
@@ -163,7 +310,7 @@ impl Scheduler {
                 self.tasks.sync_parent(
                     ca.handle().clone(),
                     parent.clone(),
-                    self.config.ca_refresh_start_up(use_parent_sync_jitter),
+                    config.ca_refresh_start_up(use_parent_sync_jitter),
                 );
             }
 
@@ -182,19 +329,95 @@ impl Scheduler {
             // add this task then it will not be executed (obviously), but more
             // importantly.. by adding this task we ensure that it will keep being
             // re-scheduled when it's done.
-            if self.config.suspend_child_after_inactive_seconds().is_some() {
+            if config.suspend_child_after_inactive_seconds().is_some() {
                 self.tasks.suspend_children(ca.handle().clone(), now())
             }
+
+            // Pick up resource transfers between children which were still
+            // waiting for re-issuance when the server stopped.
+            if !ca.resource_transfers_waiting().is_empty() {
+                self.tasks.resource_transfers(ca.handle().clone(), now())
+            }
+
+            // Plan to apply scheduled ROA and ASPA changes when the first
+            // of them is due. Changes which became due while the server was
+            // stopped are applied now.
+            if let Some(next) = ca.scheduled_changes_next() {
+                self.tasks.scheduled_changes(ca.handle().clone(), next.into())
+            }
         }
 
         self.tasks.republish_if_needed(now());
         self.tasks.renew_if_needed(now());
         self.tasks.refresh_announcements_info(now());
 
-        #[cfg(feature = "multi-user")]
-        self.tasks.sweep_login_cache(in_minutes(1));
+        // CAs can opt in to the ROA auto-pilot at any time, through the API
+        // or a config reload, so always plan it. It is a cheap no-op if no
+        // CA uses it.
+        self.tasks
+            .roa_autopilot(in_minutes(SCHEDULER_INTERVAL_ROA_AUTOPILOT_MINS));
 
-        self.tasks.update_snapshots(in_hours(24));
+        self.tasks
+            .update_snapshots(in_hours(config.snapshots.snapshot_interval_hours.into()));
+
+        // Only plan the history archive if it is configured.
+        if config.history_archive_days.is_some() {
+            self.tasks.archive_history(now());
+        }
+
+        // Only plan the RRDP session reset check if an interval is configured.
+        if config.rrdp_updates_config.rrdp_session_reset_interval_hours.is_some() {
+            self.tasks.rrdp_session_reset_if_needed(now());
+        }
+
+        // Only plan the repository archive if it is configured. The next
+        // entry is due one interval after the last entry, so that restarts
+        // neither skip nor add entries.
+        match self.repo_manager.archive_repository_next_due() {
+            Ok(Some(next_due)) => self.tasks.repository_archive(next_due.into()),
+            Ok(None) => {}
+            Err(e) => {
+                error!("Could not determine when to archive repository content! Error: {}", e);
+                self.tasks.repository_archive(in_hours(1));
+            }
+        }
+
+        // Only plan the repository replica if it is configured.
+        if config.repository_replica.is_some() {
+            self.tasks.repository_replicate(now());
+        }
+
+        // Upload any changes made while the server was not running.
+        if config.repository_s3.is_some() {
+            self.tasks.repository_s3_sync(now());
+        }
+
+        // Only plan the publication check if it is enabled. Give the CAs
+        // some time to synchronise with their repositories first.
+        if let Some(interval) = config.publication_check_interval_minutes {
+            self.tasks.publication_check(in_minutes(interval.into()));
+        }
+
+        if let Some(interval) = config.reconcile_interval_minutes {
+            self.tasks.reconcile(in_minutes(interval.into()));
+        }
+
+        // The system time was already checked when the server was started.
+        if let Some(time_checker) = &self.time_checker {
+            self.tasks
+                .check_time(in_minutes(time_checker.interval_minutes().into()));
+        }
+
+        if config.alerts.is_enabled(AlertEvent::Expiry) {
+            self.tasks.expiry_alerts(now());
+        }
+
+        // Plan the testbed cleanup if there is a testbed. Whether inactive
+        // registrations should be removed is checked when it runs, as this
+        // can change when the config is reloaded.
+        if config.testbed().is_some() {
+            self.tasks.testbed_cleanup(now());
+        }
 
         Ok(())
     }
@@ -207,7 +430,7 @@ impl Scheduler {
             .cas_repo_sync_single(self.repo_manager.as_ref(), &ca)
             .await
         {
-            let next = self.config.requeue_remote_failed();
+            let next = self.ca_manager.ca_repo_backoff(&ca).await;
 
             error!(
                 "Failed to publish for '{}'. Will reschedule to: '{}'. Error: {}",
@@ -225,7 +448,7 @@ impl Scheduler {
         if self.ca_manager.has_ca(&ca)? {
             info!("Synchronize CA '{}' with its parent '{}'", ca, parent);
             if let Err(e) = self.ca_manager.ca_sync_parent(&ca, &parent, &self.system_actor).await {
-                let next = self.config.requeue_remote_failed();
+                let next = self.ca_manager.ca_parent_backoff(&ca, &parent).await;
 
                 error!(
                     "Failed to synchronize CA '{}' with its parent '{}'. Will reschedule to: '{}'. Error: {}",
@@ -233,7 +456,7 @@ impl Scheduler {
                 );
                 self.tasks.sync_parent(ca, parent, next);
             } else {
-                let next = self.config.ca_refresh_next();
+                let next = self.config().ca_refresh_next();
                 self.tasks.sync_parent(ca, parent, next);
             }
 
@@ -271,10 +494,64 @@ impl Scheduler {
         Ok(())
     }
 
+    /// Progress the resource transfers between children of a CA, and check
+    /// again later if any of them are still waiting.
+    async fn resource_transfers(&self, ca_handle: CaHandle) -> KrillResult<()> {
+        debug!("Progress resource transfers for CA '{}'", ca_handle);
+        let waiting = self
+            .ca_manager
+            .ca_resource_transfers_progress(&ca_handle, &self.system_actor)
+            .await
+            .unwrap_or_else(|e| {
+                // Errors here must not stop the scheduler, just try again later.
+                error!("Could not progress resource transfers for CA '{}': {}", ca_handle, e);
+                true
+            });
+
+        if waiting {
+            self.tasks
+                .resource_transfers(ca_handle, in_minutes(SCHEDULER_INTERVAL_RESOURCE_TRANSFER_MINS));
+        }
+
+        Ok(())
+    }
+
+    /// Apply the scheduled ROA and ASPA changes of a CA which are due, and
+    /// run again when the next pending change is due.
+    async fn scheduled_changes(&self, ca_handle: CaHandle) -> KrillResult<()> {
+        debug!("Apply due scheduled changes for CA '{}'", ca_handle);
+        match self
+            .ca_manager
+            .ca_scheduled_changes_apply(&ca_handle, &self.system_actor)
+            .await
+        {
+            Ok(Some(next)) => self.tasks.scheduled_changes(ca_handle, next.into()),
+            Ok(None) => {}
+            Err(e) => {
+                // Errors here must not stop the scheduler, just try again later.
+                error!("Could not apply scheduled changes for CA '{}': {}", ca_handle, e);
+                self.tasks
+                    .scheduled_changes(ca_handle, in_minutes(SCHEDULER_INTERVAL_SCHEDULED_CHANGES_RETRY_MINS));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Let CAs that need it republish their CRL/MFT
+    ///
+    /// CAs are re-issued concurrently, see the `republish_workers` setting.
     async fn republish_if_needed(&self) -> KrillResult<()> {
         let cas = self.ca_manager.republish_all(false).await?; // can only fail on critical errors
 
+        if !cas.is_empty() {
+            debug!(
+                "Re-issued MFT and CRL for {} CAs in {} seconds",
+                cas.len(),
+                self.ca_manager.republish_metrics().last_run_seconds()
+            );
+        }
+
         for ca in cas {
             info!("Re-issued MFT and CRL for CA: {}", ca);
             self.tasks.sync_repo(ca, now());
@@ -301,7 +578,95 @@ impl Scheduler {
         Ok(())
     }
 
+    /// Authorize stable announcements which are not covered by any ROA yet,
+    /// for CAs which opted in to the ROA auto-pilot. The policy set for a CA
+    /// through the API takes precedence over the policy in the config.
+    async fn roa_autopilot(&self) -> KrillResult<()> {
+        let config = self.config();
+        for summary in self.ca_manager.ca_list(&self.system_actor)?.cas() {
+            let handle = summary.handle();
+            let ca = match self.ca_manager.get_ca(handle).await {
+                Ok(ca) => ca,
+                Err(e) => {
+                    warn!("Cannot run ROA auto-pilot for CA '{}', error: {}", handle, e);
+                    continue;
+                }
+            };
+
+            let autopilot = match ca.roa_autopilot().or_else(|| config.roa_autopilot.get(handle)) {
+                Some(autopilot) if autopilot.enabled => autopilot,
+                _ => continue,
+            };
+
+            let stable = self
+                .bgp_analyser
+                .stable_not_found(
+                    ca.configured_roas().as_slice(),
+                    &ca.all_resources(),
+                    autopilot.min_seen(),
+                    autopilot.max_prefix_length_v4,
+                    autopilot.max_prefix_length_v6,
+                )
+                .await;
+
+            if stable.is_empty() {
+                continue;
+            }
+
+            let mut updates = RoaConfigurationUpdates::empty();
+            for announcement in stable {
+                updates.add(RoaPayload::from(announcement).into());
+            }
+
+            // The auto-pilot cannot force updates, so it skips updates which
+            // would be rejected when made by hand.
+            if config.roa_update_bgp_check {
+                let updates = updates.clone().into_explicit_max_length();
+                let after = match ca.configured_roas_after(&updates) {
+                    Ok(after) => after,
+                    Err(e) => {
+                        warn!("Cannot run ROA auto-pilot for CA '{}', error: {}", handle, e);
+                        continue;
+                    }
+                };
+                let impact = self
+                    .bgp_analyser
+                    .impact(
+                        &ca.configured_roas(),
+                        &after,
+                        &ca.all_resources(),
+                        updates.affected_prefixes(),
+                    )
+                    .await;
+                if impact.invalidates_announcements() {
+                    warn!(
+                        "ROA auto-pilot will not add ROAs for CA '{}': {}\n{}",
+                        handle, updates, impact
+                    );
+                    continue;
+                }
+            }
+
+            info!("ROA auto-pilot will add ROAs for CA '{}': {}", handle, updates);
+            if let Err(e) = self
+                .ca_manager
+                .ca_routes_update(handle.clone(), updates, &self.autopilot_actor)
+                .await
+            {
+                error!("ROA auto-pilot could not update ROAs for CA '{}', error: {}", handle, e);
+            }
+        }
+
+        self.tasks
+            .roa_autopilot(in_minutes(SCHEDULER_INTERVAL_ROA_AUTOPILOT_MINS));
+
+        Ok(())
+    }
+
     /// Let CAs that need it re-issue signed objects
+    ///
+    /// Objects which expire at the same time are renewed over a number of
+    /// runs, if the `timing_renew_spread_hours` setting is used.
     async fn renew_objects_if_needed(&self) -> KrillResult<()> {
         self.ca_manager.renew_objects_all(&self.system_actor).await?; // only fails on fatal errors
 
@@ -311,13 +676,153 @@ impl Scheduler {
         Ok(())
     }
 
-    #[cfg(feature = "multi-user")]
-    fn sweep_login_cache(&self) -> KrillResult<()> {
-        if let Err(e) = self.login_session_cache.sweep() {
-            error!("Background sweep of session decryption cache failed: {}", e);
+    async fn publication_check(&self) -> KrillResult<()> {
+        self.ca_manager.ca_publication_check_all().await?;
+
+        if let Some(interval) = self.config().publication_check_interval_minutes {
+            self.tasks.publication_check(in_minutes(interval.into()));
         }
 
-        self.tasks.sweep_login_cache(in_minutes(1));
+        Ok(())
+    }
+
+    async fn reconcile(&self) -> KrillResult<()> {
+        self.ca_manager
+            .ca_reconcile_all(self.config().reconcile_auto_repair, &self.system_actor)
+            .await?;
+
+        if let Some(interval) = self.config().reconcile_interval_minutes {
+            self.tasks.reconcile(in_minutes(interval.into()));
+        }
+
+        Ok(())
+    }
+
+    async fn check_time(&self) -> KrillResult<()> {
+        if let Some(time_checker) = &self.time_checker {
+            time_checker.check().await;
+            self.tasks
+                .check_time(in_minutes(time_checker.interval_minutes().into()));
+        }
+        Ok(())
+    }
+
+    /// Confirms that the objects published by a CA can be fetched back over
+    /// RRDP, and tries again later if they cannot be confirmed (yet).
+    async fn confirm_publication(&self, ca: CaHandle) -> KrillResult<()> {
+        if !self.ca_manager.has_ca(&ca)? {
+            return Ok(());
+        }
+
+        match self.ca_manager.ca_publication_confirm(&ca).await {
+            Ok(true) => {}
+            Ok(false) => self
+                .tasks
+                .confirm_publication(ca, in_seconds(PUBLICATION_CONFIRM_RETRY_SECONDS)),
+            Err(e) => {
+                error!("Could not confirm publication for CA '{}': {}", ca, e);
+                self.tasks
+                    .confirm_publication(ca, in_seconds(PUBLICATION_CONFIRM_RETRY_SECONDS));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends alerts for expiring certificates and checks again in a day.
+    async fn expiry_alerts(&self) -> KrillResult<()> {
+        self.ca_manager.ca_expiry_alerts_all().await?;
+        self.tasks.expiry_alerts(in_hours(24));
+        Ok(())
+    }
+
+    /// Removes testbed children and publishers which did not contact the
+    /// testbed for longer than configured, and checks again in an hour.
+    async fn testbed_cleanup(&self) -> KrillResult<()> {
+        self.tasks.testbed_cleanup(in_hours(1));
+
+        let threshold_seconds = match self
+            .config()
+            .testbed()
+            .and_then(|testbed| testbed.remove_inactive_after_seconds())
+        {
+            Some(seconds) => seconds,
+            None => return Ok(()),
+        };
+
+        let testbed = testbed_ca_handle();
+        let ca = self.ca_manager.get_ca(&testbed).await?;
+        let status = self.ca_manager.get_ca_status(&testbed).await?;
+
+        let mut inactive_children: Vec<ChildHandle> = vec![];
+        let mut never_active = vec![];
+        for child in ca.children() {
+            if ca
+                .get_child(child)
+                .map(|details| details.is_suspension_exempt())
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            match status.children().get(child).and_then(|status| status.last_exchange()) {
+                Some(exchange) => {
+                    if exchange.more_than_seconds_ago(threshold_seconds) {
+                        inactive_children.push(child.clone());
+                    }
+                }
+                None => never_active.push(format!("child:{}", child)),
+            }
+        }
+
+        let mut inactive_publishers: Vec<PublisherHandle> = vec![];
+        for (publisher, stats) in self.repo_manager.repo_stats()?.get_publishers() {
+            if publisher.as_str() == ta_handle().as_str() || publisher.as_str() == testbed.as_str() {
+                continue;
+            }
+            match stats.last_update() {
+                Some(time) => {
+                    if Time::now().timestamp() - time.timestamp() >= threshold_seconds {
+                        inactive_publishers.push(publisher.clone());
+                    }
+                }
+                None => never_active.push(format!("publisher:{}", publisher)),
+            }
+        }
+
+        // Children and publishers which never contacted the testbed are
+        // removed once this server has seen them like this for long enough.
+        let never_active = self.testbed_first_seen.seen_before(never_active, threshold_seconds);
+        for child in ca.children() {
+            if never_active.contains(&format!("child:{}", child)) {
+                inactive_children.push(child.clone());
+            }
+        }
+        for publisher in self.repo_manager.publishers()? {
+            if never_active.contains(&format!("publisher:{}", publisher)) {
+                inactive_publishers.push(publisher);
+            }
+        }
+
+        for child in inactive_children {
+            info!("Removing testbed child '{}' which was inactive for too long", child);
+            if let Err(e) = self
+                .ca_manager
+                .ca_child_remove(&testbed, child, &self.system_actor)
+                .await
+            {
+                error!("Could not remove inactive testbed child, error: {}", e);
+            }
+        }
+
+        for publisher in inactive_publishers {
+            info!(
+                "Removing testbed publisher '{}' which was inactive for too long",
+                publisher
+            );
+            if let Err(e) = self.repo_manager.remove_publisher(publisher, &self.system_actor) {
+                error!("Could not remove inactive testbed publisher, error: {}", e);
+            }
+        }
 
         Ok(())
     }
@@ -327,7 +832,38 @@ impl Scheduler {
             error!("Could not update snapshots on disk! Error: {}", e);
         }
 
-        self.tasks.update_snapshots(in_hours(24));
+        if let Err(e) = self.ca_manager.save_snapshots(false) {
+            error!("Could not save CA snapshots! Error: {}", e);
+        }
+
+        if let Err(e) = self.repo_manager.save_snapshots(false) {
+            error!("Could not save Publication Server snapshot! Error: {}", e);
+        }
+
+        self.tasks
+            .update_snapshots(in_hours(self.config().snapshots.snapshot_interval_hours.into()));
+
+        Ok(())
+    }
+
+    /// Archives commands and events older than the configured number of
+    /// days, and checks again in a day.
+    fn archive_history(&self) -> KrillResult<()> {
+        if let Some(days) = self.config().history_archive_days {
+            let before = Timestamp::now_minus_hours(i64::from(days) * 24).into();
+
+            match self.ca_manager.archive_history(before) {
+                Ok(archived) => debug!("Archived {} commands for CAs", archived),
+                Err(e) => error!("Could not archive CA history! Error: {}", e),
+            }
+
+            match self.repo_manager.archive_history(before) {
+                Ok(archived) => debug!("Archived {} commands for the Publication Server", archived),
+                Err(e) => error!("Could not archive Publication Server history! Error: {}", e),
+            }
+
+            self.tasks.archive_history(in_hours(24));
+        }
 
         Ok(())
     }
@@ -353,6 +889,66 @@ impl Scheduler {
         Ok(())
     }
 
+    fn rrdp_session_reset_if_needed(&self) -> KrillResult<()> {
+        match self.repo_manager.rrdp_session_reset_if_needed() {
+            Err(e) => {
+                error!("Could not reset RRDP session! Error: {}", e);
+                self.tasks.rrdp_session_reset_if_needed(in_hours(1));
+            }
+            Ok(None) => {
+                // no interval is configured
+            }
+            Ok(Some(next_time)) => {
+                self.tasks.rrdp_session_reset_if_needed(next_time.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn repository_archive(&self) -> KrillResult<()> {
+        match self.repo_manager.archive_repository_if_configured().await {
+            Err(e) => {
+                error!("Could not archive repository content! Error: {}", e);
+                self.tasks.repository_archive(in_hours(1));
+            }
+            Ok(None) => {
+                // the archive is not configured
+            }
+            Ok(Some(next_time)) => {
+                self.tasks.repository_archive(next_time.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn repository_replicate(&self) -> KrillResult<()> {
+        match self.repo_manager.replicate_repository_if_configured().await {
+            Err(e) => {
+                error!("Could not replicate repository content! Error: {}", e);
+                self.tasks.repository_replicate(in_minutes(1));
+            }
+            Ok(None) => {
+                // the replica is not configured
+            }
+            Ok(Some(next_time)) => {
+                self.tasks.repository_replicate(next_time.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn repository_s3_sync(&self) -> KrillResult<()> {
+        if let Err(e) = self.repo_manager.sync_s3().await {
+            error!("Could not upload repository files to S3! Error: {}", e);
+            self.tasks.repository_s3_sync(in_minutes(1));
+        }
+
+        Ok(())
+    }
+
     async fn resource_class_removed(
         &self,
         ca: CaHandle,
@@ -405,3 +1001,98 @@ impl Scheduler {
         Ok(())
     }
 }
+
+//------------ TestbedFirstSeen ----------------------------------------------
+
+const TESTBED_FIRST_SEEN_KEY: &str = "testbed-first-seen.json";
+
+/// Remembers when testbed children and publishers which never contacted the
+/// testbed were first seen, so that they can be removed as inactive. This is
+/// saved in the status directory, so that a restart of the server does not
+/// postpone their removal.
+struct TestbedFirstSeen {
+    store: KeyValueStore,
+    first_seen: Mutex<HashMap<String, Timestamp>>,
+}
+
+impl TestbedFirstSeen {
+    fn new(data_dir: &Path) -> KrillResult<Self> {
+        let store = KeyValueStore::disk(data_dir, STATUS_DIR)?;
+
+        // If this cannot be read, the children and publishers are simply
+        // considered to be seen for the first time.
+        let first_seen = store.get(&Self::key()).ok().flatten().unwrap_or_default();
+
+        Ok(TestbedFirstSeen {
+            store,
+            first_seen: Mutex::new(first_seen),
+        })
+    }
+
+    fn key() -> KeyStoreKey {
+        KeyStoreKey::simple(TESTBED_FIRST_SEEN_KEY.to_string())
+    }
+
+    /// Remembers when the keys were first seen, and returns the keys which
+    /// were first seen longer than the threshold ago. Keys which are no
+    /// longer given are forgotten.
+    fn seen_before(&self, keys: Vec<String>, threshold_seconds: i64) -> Vec<String> {
+        let mut first_seen = self.first_seen.lock().unwrap();
+        first_seen.retain(|key, _| keys.contains(key));
+
+        let threshold = Timestamp::now_minus_seconds(threshold_seconds);
+        let res = keys
+            .into_iter()
+            .filter(|key| *first_seen.entry(key.clone()).or_insert_with(Timestamp::now) < threshold)
+            .collect();
+
+        if let Err(e) = self.store.store(&Self::key(), &*first_seen) {
+            warn!(
+                "Cannot save when inactive testbed children and publishers were first seen: {}",
+                e
+            );
+        }
+
+        res
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test;
+
+    #[test]
+    fn testbed_first_seen_survives_restart() {
+        test::test_under_tmp(|d| {
+            let first_seen = TestbedFirstSeen::new(&d).unwrap();
+            let keys = vec!["child:alice".to_string(), "publisher:bob".to_string()];
+            assert!(first_seen.seen_before(keys.clone(), 3600).is_empty());
+
+            // Pretend that alice was first seen two hours ago.
+            first_seen
+                .first_seen
+                .lock()
+                .unwrap()
+                .insert("child:alice".to_string(), Timestamp::now_minus_seconds(7200));
+            assert_eq!(
+                first_seen.seen_before(keys.clone(), 3600),
+                vec!["child:alice".to_string()]
+            );
+
+            // After a restart alice is still removed. Bob is forgotten when
+            // no longer listed as inactive.
+            let first_seen = TestbedFirstSeen::new(&d).unwrap();
+            assert_eq!(
+                first_seen.seen_before(vec!["child:alice".to_string()], 3600),
+                vec!["child:alice".to_string()]
+            );
+
+            let first_seen = TestbedFirstSeen::new(&d).unwrap();
+            assert_eq!(first_seen.first_seen.lock().unwrap().len(), 1);
+        });
+    }
+}
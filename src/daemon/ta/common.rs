@@ -9,7 +9,7 @@ use std::{
 use bytes::Bytes;
 use rpki::{
     ca::{
-        idexchange::{ChildHandle, RecipientHandle, SenderHandle},
+        idexchange::{ChildHandle, RecipientHandle, RepoInfo, SenderHandle},
         provisioning,
         publication::Base64,
         sigmsg::SignedMessage,
@@ -22,9 +22,10 @@ use serde::Serialize;
 
 use crate::{
     commons::{
-        api::{IdCertInfo, IssuedCertificate, ObjectName, ReceivedCert, Revocations},
+        api::{rrdp::PublishElement, IdCertInfo, IssuedCertificate, ObjectName, ReceivedCert, Revocations},
         crypto::KrillSigner,
         error::Error,
+        util::clock,
         KrillResult,
     },
     daemon::ca::{
@@ -130,7 +131,7 @@ impl TrustAnchorObjects {
         }
     }
 
-    pub fn publish_elements(&self) -> KrillResult<Vec<crate::commons::api::rrdp::PublishElement>> {
+    pub fn publish_elements(&self) -> KrillResult<Vec<PublishElement>> {
         let mut res = vec![];
 
         let mft_uri = self
@@ -175,11 +176,11 @@ impl TrustAnchorObjects {
     }
 
     pub fn this_update() -> Time {
-        Time::five_minutes_ago()
+        clock::five_minutes_ago()
     }
 
     pub fn next_update() -> Time {
-        Time::now() + chrono::Duration::weeks(TA_MFT_NEXT_UPDATE_WEEKS)
+        clock::now() + chrono::Duration::weeks(TA_MFT_NEXT_UPDATE_WEEKS)
     }
 
     // Adds a new issued certificate, replaces and revokes the previous if present.
@@ -251,6 +252,75 @@ impl TaCertDetails {
     pub fn tal(&self) -> &TrustAnchorLocator {
         &self.tal
     }
+
+    /// Returns the TAL for the current TA certificate. The URIs are kept as
+    /// configured, but the public key is always taken from the certificate
+    /// so that the TAL is regenerated whenever the TA key changes.
+    pub fn current_tal(&self) -> TrustAnchorLocator {
+        TrustAnchorLocator::new(
+            self.tal.uris.clone(),
+            self.tal.rsync_uri.clone(),
+            self.cert.csr_info().key(),
+        )
+    }
+
+    /// Returns the element needed to publish the TA certificate at the rsync
+    /// URI in the TAL, if that URI falls under the base URI of the repository
+    /// used by the TA. Otherwise the certificate cannot be published there by
+    /// Krill, and it should be made available by other means.
+    pub fn publish_element(&self, repo_info: &RepoInfo) -> Option<PublishElement> {
+        self.tal
+            .rsync_uri
+            .relative_to(repo_info.base_uri())
+            .map(|_| PublishElement::new(self.cert.base64().clone(), self.tal.rsync_uri.clone()))
+    }
+
+    /// Checks that the TAL matches the TA certificate, and that the
+    /// certificate is published at the locations listed in the TAL.
+    pub fn tal_check(&self, repo_info: Option<&RepoInfo>, service_uri: &uri::Https) -> TrustAnchorTalCheck {
+        let tal = self.current_tal();
+        let mut issues = vec![];
+
+        if self.tal.encoded_ski != tal.encoded_ski {
+            issues.push(
+                "The public key in the stored TAL does not match the TA certificate. Relying parties need the current TAL."
+                    .to_string(),
+            );
+        }
+
+        if self.cert.uri() != &self.tal.rsync_uri {
+            issues.push(format!(
+                "The TA certificate was issued for '{}', but the TAL uses '{}'",
+                self.cert.uri(),
+                self.tal.rsync_uri
+            ));
+        }
+
+        match repo_info {
+            None => issues.push("The TA has no repository, its certificate is not published".to_string()),
+            Some(repo_info) => {
+                if self.publish_element(repo_info).is_none() {
+                    issues.push(format!(
+                        "The rsync URI '{}' is outside of the TA repository '{}', the certificate must be published there by other means",
+                        self.tal.rsync_uri,
+                        repo_info.base_uri()
+                    ));
+                }
+            }
+        }
+
+        let ta_cer = format!("{}ta/ta.cer", service_uri);
+        for https_uri in &self.tal.uris {
+            if https_uri.as_str() != ta_cer {
+                issues.push(format!(
+                    "The HTTPS URI '{}' is not served by this Krill (which uses '{}'), the certificate must be published there by other means",
+                    https_uri, ta_cer
+                ));
+            }
+        }
+
+        TrustAnchorTalCheck { tal, issues }
+    }
 }
 
 impl From<TaCertDetails> for ReceivedCert {
@@ -321,6 +391,45 @@ impl std::fmt::Display for TrustAnchorLocator {
     }
 }
 
+//------------ TrustAnchorTalCheck ------------------------------------------
+
+/// The result of verifying that the TAL matches the TA certificate, and
+/// that the certificate is available at the locations in the TAL.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorTalCheck {
+    tal: TrustAnchorLocator,
+    issues: Vec<String>,
+}
+
+impl TrustAnchorTalCheck {
+    pub fn tal(&self) -> &TrustAnchorLocator {
+        &self.tal
+    }
+
+    pub fn issues(&self) -> &Vec<String> {
+        &self.issues
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for TrustAnchorTalCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            writeln!(f, "The TAL matches the published TA certificate.")?;
+        } else {
+            writeln!(f, "Found issues with the TAL:")?;
+            for issue in &self.issues {
+                writeln!(f, " - {}", issue)?;
+            }
+        }
+        writeln!(f)?;
+        writeln!(f, "{}", self.tal)
+    }
+}
+
 //------------ TrustAnchorSignerInfo ---------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
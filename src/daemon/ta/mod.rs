@@ -33,12 +33,13 @@ mod tests {
 
     use super::*;
 
-    use std::{sync::Arc, time::Duration};
+    use std::{collections::HashMap, sync::Arc, time::Duration};
 
     use crate::{
         commons::{
             api::{PublicationServerInfo, RepositoryContact},
             crypto::KrillSignerBuilder,
+            error::Error,
             eventsourcing::AggregateStore,
         },
         daemon::config::ConfigDefaults,
@@ -118,6 +119,14 @@ mod tests {
             let ta_cert_details = proxy.get_ta_details().unwrap();
             assert_eq!(ta_cert_details.tal().uris(), &tal_https);
             assert_eq!(ta_cert_details.tal().rsync_uri(), &tal_rsync);
+            assert_eq!(&ta_cert_details.current_tal(), ta_cert_details.tal());
+
+            // The TA certificate lives outside of the TA repository in this setup,
+            // so it cannot be published by Krill using rsync.
+            let repo_info = proxy.repository().unwrap().repo_info();
+            assert!(ta_cert_details.publish_element(repo_info).is_none());
+            let tal_check = ta_cert_details.tal_check(Some(repo_info), &https("https://example.krill.cloud/"));
+            assert_eq!(tal_check.issues().len(), 1);
 
             // We can make a new signer request to make a new manifest and CRL
             // even if we do not yet have any issued certificates to publish.
@@ -127,10 +136,24 @@ mod tests {
             let signed_request = proxy.get_signer_request(&signer).unwrap();
             let request_nonce = signed_request.content().nonce.clone();
 
-            let ta_signer_process_request_command =
-                TrustAnchorSignerCommand::make_process_request_command(&signer_handle, signed_request, signer, &actor);
+            let ta_signer_process_request_command = TrustAnchorSignerCommand::make_process_request_command(
+                &signer_handle,
+                signed_request.clone(),
+                signer.clone(),
+                &actor,
+            );
             ta_signer = ta_signer_store.command(ta_signer_process_request_command).unwrap();
 
+            // The signer must refuse to process the same request again.
+            let replay_command = TrustAnchorSignerCommand::make_process_request_command(
+                &signer_handle,
+                signed_request,
+                signer.clone(),
+                &actor,
+            );
+            let err = ta_signer_store.command(replay_command).unwrap_err();
+            assert!(matches!(err, Error::TaSignerRequestReplayed(nonce) if nonce == request_nonce));
+
             let exchange = ta_signer.get_exchange(&request_nonce).unwrap();
             let ta_proxy_process_signer_response_command =
                 TrustAnchorProxyCommand::process_signer_response(&proxy_handle, exchange.response.clone(), &actor);
@@ -139,11 +162,37 @@ mod tests {
                 .command(ta_proxy_process_signer_response_command)
                 .unwrap();
 
+            // The same response cannot be processed again, as the request
+            // it answers is no longer open.
+            let replay_response_command =
+                TrustAnchorProxyCommand::process_signer_response(&proxy_handle, exchange.response.clone(), &actor);
+            let err = ta_proxy_store.command(replay_response_command).unwrap_err();
+            assert!(matches!(err, Error::TaProxyHasNoRequest));
+
             // The TA should have published again, the revision used for manifest and crl will
             // have been updated.
             let ta_objects = proxy.get_trust_anchor_objects().unwrap();
             assert_eq!(ta_objects.revision().number(), 2);
 
+            // A response for the open request which does not move the objects
+            // forward is refused, e.g. when the signer was restored from an
+            // old backup.
+            let make_publish_request_cmd = TrustAnchorProxyCommand::make_signer_request(&proxy_handle, &actor);
+            proxy = ta_proxy_store.command(make_publish_request_cmd).unwrap();
+            let open_nonce = proxy.get_signer_request(&signer).unwrap().content().nonce.clone();
+
+            let stale_response = TrustAnchorSignerResponse {
+                nonce: open_nonce,
+                objects: exchange.response.content().objects.clone(),
+                child_responses: HashMap::new(),
+            }
+            .sign(ta_signer.get_signer_info().id.public_key().key_identifier(), &signer)
+            .unwrap();
+            let stale_response_command =
+                TrustAnchorProxyCommand::process_signer_response(&proxy_handle, stale_response, &actor);
+            let err = ta_proxy_store.command(stale_response_command).unwrap_err();
+            assert!(matches!(err, Error::TaProxyResponseStale(2, 2)));
+
             // We still need to test some higher order functions:
             // - add child
             // - let the child request a certificate
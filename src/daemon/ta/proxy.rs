@@ -486,6 +486,24 @@ impl eventsourcing::Aggregate for TrustAnchorProxy {
                     // Ensure that the response was validly signed.
                     response.validate(&signer.id)?;
 
+                    // Reconcile the state of the objects in the response with what we
+                    // have. The signer increments the revision for every request it
+                    // processes, so a response can never go back in time. If it skipped
+                    // ahead, then the signer processed requests we did not see a response
+                    // for. That is unexpected, but the signer has the authoritative state.
+                    let current = signer.objects.revision().number();
+                    let received = response.content().objects.revision().number();
+                    if received <= current {
+                        return Err(Error::TaProxyResponseStale(received, current));
+                    } else if received > current + 1 {
+                        warn!(
+                            "Trust Anchor Proxy '{}' received response with revision '{}', expected '{}'. Signer state will be used.",
+                            self.handle,
+                            received,
+                            current + 1
+                        );
+                    }
+
                     // We accept the response as is. Since children cannot be modified, and requests
                     // cannot change as long as there is an open signer request we cannot have any
                     // mismatches between the children and child requests in the proxy vs the
@@ -309,7 +309,7 @@ impl TrustAnchorSigner {
         let resources = ResourceSet::all();
 
         let cert = {
-            let serial: Serial = signer.random_serial()?;
+            let serial: Serial = signer.next_serial(&key)?;
 
             let pub_key = signer.get_key_info(&key).map_err(Error::signer)?;
             let name = pub_key.to_subject_name();
@@ -364,6 +364,13 @@ impl TrustAnchorSigner {
         // and the 'content' is not tampered with.
         request.validate(&self.proxy_id)?;
 
+        // Refuse to process the same request twice. Doing so would re-issue
+        // certificates and bump the revision for the published objects, and
+        // the proxy can only accept one response for its open request anyway.
+        if self.get_exchange(&request.content().nonce).is_some() {
+            return Err(Error::TaSignerRequestReplayed(request.content().nonce.clone()));
+        }
+
         let mut objects = self.objects.clone();
 
         let mut child_responses: HashMap<ChildHandle, HashMap<KeyIdentifier, ProvisioningResponse>> = HashMap::new();
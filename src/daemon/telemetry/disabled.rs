@@ -0,0 +1,83 @@
+//! The telemetry functions used when Krill is built without the 'telemetry'
+//! feature. They have the same signatures as the OpenTelemetry versions, but
+//! do nothing.
+use std::{borrow::Cow, future::Future};
+
+use crate::{
+    commons::{error::Error, KrillResult},
+    daemon::telemetry::TelemetryConfig,
+};
+
+//------------ Types ---------------------------------------------------------
+
+#[derive(Clone, Debug, Default)]
+pub struct Context;
+
+impl Context {
+    pub fn new() -> Self {
+        Context
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SpanKind {
+    Client,
+    Server,
+    Internal,
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyValue;
+
+impl KeyValue {
+    pub fn new<K, V>(_key: K, _value: V) -> Self {
+        KeyValue
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpanContext;
+
+//------------ Set up --------------------------------------------------------
+
+pub fn init(_config: &TelemetryConfig) -> KrillResult<()> {
+    Err(Error::custom("Krill was built without the 'telemetry' feature"))
+}
+
+pub fn shutdown() {}
+
+//------------ Spans ---------------------------------------------------------
+
+pub fn span(_name: impl Into<Cow<'static, str>>, _kind: SpanKind, _attributes: Vec<KeyValue>) -> Context {
+    Context
+}
+
+pub fn request_span(_request: &hyper::Request<hyper::Body>) -> Context {
+    Context
+}
+
+pub fn task_span(_name: &'static str, _description: String, _origins: Vec<SpanContext>) -> Context {
+    Context
+}
+
+pub fn current_span_context() -> Option<SpanContext> {
+    None
+}
+
+pub fn trace_headers(_cx: &Context) -> hyper::HeaderMap {
+    hyper::HeaderMap::new()
+}
+
+pub async fn in_span<F: Future>(_cx: &Context, future: F) -> F::Output {
+    future.await
+}
+
+pub async fn traced<F: Future>(_cx: Context, future: F) -> F::Output {
+    future.await
+}
+
+pub fn traced_sync<T>(_cx: Context, f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+pub fn end_request(_cx: &Context, _status: Option<hyper::StatusCode>) {}
@@ -0,0 +1,105 @@
+//! Distributed tracing using OpenTelemetry.
+//!
+//! Krill creates spans for the HTTP requests it serves, the CA commands it
+//! processes, its signer calls, the scheduled tasks it runs, and the RFC 6492
+//! and RFC 8181 exchanges with parents and publication servers. If an OTLP
+//! endpoint is configured, these spans are exported to it. Otherwise they
+//! are discarded at virtually no cost.
+//!
+//! The W3C trace context in the 'traceparent' header of incoming requests is
+//! honored, and it is passed on in requests to parents and publication
+//! servers. Tasks that are scheduled while handling a request, e.g. to
+//! publish updated ROAs, are linked to the span of that request.
+//!
+//! The OpenTelemetry dependencies are only included if Krill is built with
+//! the 'telemetry' feature. Without it, the functions in this module do
+//! nothing, and configuring telemetry is an error.
+use serde::Deserialize;
+
+#[cfg(feature = "telemetry")]
+mod otlp;
+#[cfg(feature = "telemetry")]
+pub use self::otlp::*;
+
+#[cfg(not(feature = "telemetry"))]
+mod disabled;
+#[cfg(not(feature = "telemetry"))]
+pub use self::disabled::*;
+
+//------------ TelemetryConfig -----------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct TelemetryConfig {
+    /// The URI of the OTLP/HTTP trace endpoint of an OpenTelemetry collector,
+    /// e.g. "http://localhost:4318/v1/traces".
+    pub otlp_endpoint: String,
+
+    /// The service name reported for the spans.
+    #[serde(default = "TelemetryConfig::dflt_service_name")]
+    pub service_name: String,
+
+    /// The ratio of traces started by Krill which are sampled. Traces which
+    /// are started by a client follow the sampling decision of the client.
+    #[serde(default = "TelemetryConfig::dflt_sample_ratio")]
+    pub sample_ratio: f64,
+
+    /// The timeout for exporting a batch of spans.
+    #[serde(default = "TelemetryConfig::dflt_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+impl TelemetryConfig {
+    fn dflt_service_name() -> String {
+        "krill".to_string()
+    }
+
+    fn dflt_sample_ratio() -> f64 {
+        1.0
+    }
+
+    fn dflt_timeout_seconds() -> u64 {
+        10
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        if url::Url::parse(&self.otlp_endpoint).is_err() {
+            return Err(format!("invalid telemetry.otlp_endpoint: {}", self.otlp_endpoint));
+        }
+
+        if !(0.0..=1.0).contains(&self.sample_ratio) {
+            return Err(String::from("telemetry.sample_ratio must be between 0 and 1"));
+        }
+
+        if self.timeout_seconds < 1 {
+            return Err(String::from("telemetry.timeout_seconds must be 1 or higher"));
+        }
+
+        if cfg!(not(feature = "telemetry")) {
+            return Err(String::from(
+                "telemetry is configured, but Krill was built without the 'telemetry' feature",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_config() {
+        let config: TelemetryConfig = toml::from_str(r#"otlp_endpoint = "http://localhost:4318/v1/traces""#).unwrap();
+        assert_eq!(config.service_name, "krill");
+        assert_eq!(config.verify().is_ok(), cfg!(feature = "telemetry"));
+
+        let config = TelemetryConfig {
+            sample_ratio: 1.5,
+            ..config
+        };
+        assert!(config.verify().is_err());
+    }
+}
@@ -0,0 +1,282 @@
+//! The OpenTelemetry implementation of the telemetry functions, used when
+//! Krill is built with the 'telemetry' feature.
+use std::{borrow::Cow, fmt, future::Future, time::Duration};
+
+pub use opentelemetry::{
+    trace::{SpanContext, SpanKind},
+    Context, KeyValue,
+};
+
+use opentelemetry::{
+    global,
+    propagation::TextMapPropagator,
+    sdk::{
+        propagation::TraceContextPropagator,
+        trace::{self, Sampler},
+        Resource,
+    },
+    trace::{FutureExt, Link, Status, TraceContextExt, Tracer},
+};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_otlp::WithExportConfig;
+
+use crate::{
+    commons::{error::Error, KrillResult},
+    daemon::telemetry::TelemetryConfig,
+};
+
+const TRACER_NAME: &str = "krill";
+
+//------------ Set up --------------------------------------------------------
+
+/// Sets up the export of spans to the configured OTLP endpoint. Must be
+/// called from within the Tokio runtime.
+pub fn init(config: &TelemetryConfig) -> KrillResult<()> {
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(config.otlp_endpoint.as_str())
+        .with_timeout(Duration::from_secs(config.timeout_seconds));
+
+    let trace_config = trace::config()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            config.service_name.clone(),
+        )]));
+
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(trace_config)
+        .install_batch(opentelemetry::runtime::Tokio)
+        .map_err(|e| Error::Custom(format!("Cannot set up OpenTelemetry exporter: {}", e)))?;
+
+    info!("Exporting OpenTelemetry spans to: {}", config.otlp_endpoint);
+    Ok(())
+}
+
+/// Exports any pending spans and stops the export.
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+//------------ Spans ---------------------------------------------------------
+
+/// Starts a span as a child of the current span, if any, and returns the
+/// context to use for the work done in the span.
+pub fn span(name: impl Into<Cow<'static, str>>, kind: SpanKind, attributes: Vec<KeyValue>) -> Context {
+    child_span(&Context::current(), name, kind, attributes, vec![])
+}
+
+fn child_span(
+    parent: &Context,
+    name: impl Into<Cow<'static, str>>,
+    kind: SpanKind,
+    attributes: Vec<KeyValue>,
+    links: Vec<Link>,
+) -> Context {
+    let tracer = global::tracer(TRACER_NAME);
+    let span = tracer
+        .span_builder(name)
+        .with_kind(kind)
+        .with_attributes(attributes)
+        .with_links(links)
+        .start_with_context(&tracer, parent);
+    parent.with_span(span)
+}
+
+/// Starts a span for an HTTP request served by Krill, continuing the trace
+/// of the client if the request has a 'traceparent' header.
+pub fn request_span(request: &hyper::Request<hyper::Body>) -> Context {
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(request.headers()));
+
+    child_span(
+        &parent,
+        format!("{} {}", request.method(), request.uri().path()),
+        SpanKind::Server,
+        vec![
+            KeyValue::new("http.method", request.method().to_string()),
+            KeyValue::new("http.target", request.uri().path().to_string()),
+        ],
+        vec![],
+    )
+}
+
+/// Starts a new trace for a scheduled task, linked to the spans in which the
+/// task was scheduled.
+pub fn task_span(name: &'static str, description: String, origins: Vec<SpanContext>) -> Context {
+    let links = origins.into_iter().map(|origin| Link::new(origin, vec![])).collect();
+
+    child_span(
+        &Context::new(),
+        format!("task {}", name),
+        SpanKind::Internal,
+        vec![KeyValue::new("krill.task", description)],
+        links,
+    )
+}
+
+/// Returns the context of the current span, if it is sampled, so that work
+/// that is done later can be linked to it.
+pub fn current_span_context() -> Option<SpanContext> {
+    let cx = Context::current();
+    let span_context = cx.span().span_context().clone();
+    if span_context.is_valid() && span_context.is_sampled() {
+        Some(span_context)
+    } else {
+        None
+    }
+}
+
+/// Returns the headers that pass the trace context of the given span on in
+/// a request sent by Krill, so that the receiver can continue the trace.
+pub fn trace_headers(cx: &Context) -> hyper::HeaderMap {
+    let mut headers = hyper::HeaderMap::new();
+    TraceContextPropagator::new().inject_context(cx, &mut HeaderInjector(&mut headers));
+    headers
+}
+
+/// Runs the future in the span of the given context, without ending the
+/// span.
+pub async fn in_span<F: Future>(cx: &Context, future: F) -> F::Output {
+    future.with_context(cx.clone()).await
+}
+
+/// Runs the future in the span of the given context, and ends the span when
+/// it is done. The span status is set to error if the future fails.
+pub async fn traced<F, T, E>(cx: Context, future: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: fmt::Display,
+{
+    let res = future.with_context(cx.clone()).await;
+    end(&cx, res.as_ref().err());
+    res
+}
+
+/// Runs the function in the span of the given context, and ends the span
+/// when it is done. The span status is set to error if the function fails.
+pub fn traced_sync<T, E>(cx: Context, f: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+where
+    E: fmt::Display,
+{
+    let res = {
+        let _guard = cx.clone().attach();
+        f()
+    };
+    end(&cx, res.as_ref().err());
+    res
+}
+
+/// Ends the span of the given context, and sets the status to error if an
+/// error is given.
+pub fn end(cx: &Context, err: Option<&impl fmt::Display>) {
+    let span = cx.span();
+    if let Some(err) = err {
+        span.set_status(Status::error(err.to_string()));
+    }
+    span.end();
+}
+
+/// Ends the span for an HTTP request, recording the response status.
+pub fn end_request(cx: &Context, status: Option<hyper::StatusCode>) {
+    let span = cx.span();
+    match status {
+        Some(status) => {
+            span.set_attribute(KeyValue::new("http.status_code", i64::from(status.as_u16())));
+            if status.is_server_error() {
+                span.set_status(Status::error(status.to_string()));
+            }
+        }
+        None => span.set_status(Status::error("request failed")),
+    }
+    span.end();
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_span_context_without_exporter() {
+        let cx = span("test", SpanKind::Internal, vec![]);
+        let _guard = cx.attach();
+        assert!(current_span_context().is_none());
+    }
+
+    #[tokio::test]
+    async fn pass_on_trace_context() {
+        use std::{
+            convert::Infallible,
+            sync::{Arc, Mutex},
+        };
+
+        use bytes::Bytes;
+        use hyper::{
+            server::conn::AddrIncoming,
+            service::{make_service_fn, service_fn},
+        };
+
+        use crate::commons::util::httpclient;
+
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+        // A parent or publication server which remembers the trace context
+        // of the request it receives.
+        let received: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+        let incoming = AddrIncoming::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let uri = format!("http://{}/rfc8181/ca", incoming.local_addr());
+        let service_received = received.clone();
+        let service = make_service_fn(move |_| {
+            let received = service_received.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| {
+                    let traceparent = req
+                        .headers()
+                        .get("traceparent")
+                        .map(|v| v.to_str().unwrap().to_string());
+                    *received.lock().unwrap() = traceparent;
+                    async { Ok::<_, Infallible>(hyper::Response::new(hyper::Body::from("reply"))) }
+                }))
+            }
+        });
+        tokio::spawn(hyper::Server::builder(incoming).serve(service));
+
+        // A request which Krill sends while it handles a request from a
+        // client continues the trace of the client.
+        let request = hyper::Request::post("/api/v1/cas")
+            .header("traceparent", traceparent)
+            .body(hyper::Body::empty())
+            .unwrap();
+        let cx = request_span(&request);
+        assert_eq!(
+            cx.span().span_context().trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+
+        let reply = in_span(&cx, async {
+            let cx = span("POST", SpanKind::Client, vec![]);
+            let data = Bytes::from_static(b"query");
+            let post = httpclient::post_binary_with_full_ua(
+                &uri,
+                &data,
+                "application/rpki-publication",
+                5,
+                trace_headers(&cx),
+            );
+            traced(cx, post).await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(reply, Bytes::from_static(b"reply"));
+        assert_eq!(received.lock().unwrap().as_deref(), Some(traceparent));
+
+        // Without a trace, no trace context is passed on.
+        assert!(trace_headers(&Context::new()).is_empty());
+    }
+}
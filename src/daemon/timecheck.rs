@@ -0,0 +1,361 @@
+//! Checking the system time against external time sources.
+//!
+//! Objects issued while the system clock is off are not valid yet, or expire
+//! sooner than intended, and nothing else will report this. If time sources
+//! are configured in the `[time_check]` section of the config file, then
+//! Krill compares its clock with them at start up and periodically after
+//! that. Sources can be NTP servers, which are queried using SNTP (RFC 4330),
+//! or HTTPS servers, in which case the Date header of their response is used.
+//!
+//! The result is logged, and shown in the API and the metrics. If the skew
+//! exceeds the configured maximum and `refuse_issuance` is set, then the
+//! signer refuses to issue RPKI objects until a later check finds that the
+//! clock was corrected.
+use std::{convert::TryFrom, fmt, str::FromStr, sync::RwLock, time::Duration};
+
+use serde::Deserialize;
+use tokio::net::{lookup_host, UdpSocket};
+
+use crate::commons::{
+    api::{TimeCheck, TimeSourceCheck},
+    util::{clock, httpclient},
+};
+
+const NTP_DEFAULT_PORT: u16 = 123;
+const NTP_TIMEOUT_SECS: u64 = 5;
+
+// Seconds between the NTP epoch (1900) and the UNIX epoch (1970).
+const NTP_UNIX_OFFSET_SECS: i64 = 2_208_988_800;
+
+//------------ TimeCheckConfig -----------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct TimeCheckConfig {
+    /// The time sources to compare the system time with.
+    pub sources: Vec<TimeSource>,
+
+    /// The maximum difference between the system time and the time sources
+    /// which is tolerated.
+    #[serde(default = "TimeCheckConfig::dflt_max_skew_seconds")]
+    pub max_skew_seconds: u32,
+
+    /// Whether the issuance of RPKI objects is refused while the maximum
+    /// skew is exceeded.
+    #[serde(default)]
+    pub refuse_issuance: bool,
+
+    /// The interval between checks after the check at start up.
+    #[serde(default = "TimeCheckConfig::dflt_interval_minutes")]
+    pub interval_minutes: u32,
+}
+
+impl TimeCheckConfig {
+    fn dflt_max_skew_seconds() -> u32 {
+        60
+    }
+
+    fn dflt_interval_minutes() -> u32 {
+        60
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        if self.sources.is_empty() {
+            return Err(String::from("time_check.sources must not be empty"));
+        }
+        if self.max_skew_seconds < 1 {
+            return Err(String::from("time_check.max_skew_seconds must be 1 or higher"));
+        }
+        if self.interval_minutes < 1 {
+            return Err(String::from("time_check.interval_minutes must be 1 or higher"));
+        }
+        Ok(())
+    }
+}
+
+//------------ TimeSource ----------------------------------------------------
+
+/// A source for the correct time, e.g. "ntp://pool.ntp.org" or
+/// "https://www.example.com/".
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "String")]
+pub enum TimeSource {
+    Ntp { host: String, port: u16 },
+    Https(String),
+}
+
+impl TimeSource {
+    /// Returns the offset of the system time in milliseconds, i.e. the time
+    /// of this source minus the system time. The time of the system is taken
+    /// halfway between sending the request and receiving the response.
+    pub async fn offset_millis(&self) -> Result<i64, String> {
+        match self {
+            TimeSource::Ntp { host, port } => Self::ntp_offset_millis(host, *port).await,
+            TimeSource::Https(uri) => Self::https_offset_millis(uri).await,
+        }
+    }
+
+    async fn https_offset_millis(uri: &str) -> Result<i64, String> {
+        let sent = clock::now().timestamp_millis();
+        let date = httpclient::head_date(uri).await.map_err(|e| e.to_string())?;
+        let received = clock::now().timestamp_millis();
+
+        // The Date header has a resolution of seconds, so on average the
+        // time of the server was half a second later.
+        Ok(date.timestamp_millis() + 500 - (sent + received) / 2)
+    }
+
+    async fn ntp_offset_millis(host: &str, port: u16) -> Result<i64, String> {
+        let addr = lookup_host((host, port))
+            .await
+            .map_err(|e| format!("cannot resolve {}: {}", host, e))?
+            .next()
+            .ok_or_else(|| format!("cannot resolve {}", host))?;
+
+        let local = if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(local).await.map_err(|e| e.to_string())?;
+        socket.connect(addr).await.map_err(|e| e.to_string())?;
+
+        // A client request: leap indicator 0, version 4, mode 3 (client).
+        let mut request = [0u8; 48];
+        request[0] = 0x23;
+
+        let sent = clock::now().timestamp_millis();
+        socket.send(&request).await.map_err(|e| e.to_string())?;
+
+        let mut response = [0u8; 48];
+        let len = tokio::time::timeout(Duration::from_secs(NTP_TIMEOUT_SECS), socket.recv(&mut response))
+            .await
+            .map_err(|_| "no response".to_string())?
+            .map_err(|e| e.to_string())?;
+        let received = clock::now().timestamp_millis();
+
+        if len < 48 {
+            return Err("response too short".to_string());
+        }
+        if response[0] & 0x07 != 4 {
+            return Err("response is not from a server".to_string());
+        }
+        if response[1] == 0 {
+            return Err("server refused the request (kiss-o'-death)".to_string());
+        }
+
+        let server_received = Self::ntp_timestamp_millis(&response[32..40]);
+        let server_sent = Self::ntp_timestamp_millis(&response[40..48]);
+
+        Ok(((server_received - sent) + (server_sent - received)) / 2)
+    }
+
+    /// Converts an NTP timestamp to milliseconds since the UNIX epoch.
+    fn ntp_timestamp_millis(bytes: &[u8]) -> i64 {
+        let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64;
+        let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as i64;
+        (seconds - NTP_UNIX_OFFSET_SECS) * 1000 + ((fraction * 1000) >> 32)
+    }
+}
+
+impl FromStr for TimeSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(host_port) = s.strip_prefix("ntp://") {
+            let host_port = host_port.trim_end_matches('/');
+            let parse_port =
+                |port: &str| u16::from_str(port).map_err(|_| format!("invalid port in time source: {}", s));
+
+            // IPv6 addresses must be enclosed in brackets, as in URIs.
+            let (host, port) = if let Some(rest) = host_port.strip_prefix('[') {
+                match rest.split_once(']') {
+                    Some((host, "")) => (host, NTP_DEFAULT_PORT),
+                    Some((host, port)) => match port.strip_prefix(':') {
+                        Some(port) => (host, parse_port(port)?),
+                        None => return Err(format!("invalid time source: {}", s)),
+                    },
+                    None => return Err(format!("invalid time source: {}", s)),
+                }
+            } else {
+                match host_port.split_once(':') {
+                    Some((host, port)) => (host, parse_port(port)?),
+                    None => (host_port, NTP_DEFAULT_PORT),
+                }
+            };
+            if host.is_empty() {
+                return Err(format!("missing host in time source: {}", s));
+            }
+            Ok(TimeSource::Ntp {
+                host: host.to_string(),
+                port,
+            })
+        } else if s.starts_with("https://") {
+            url::Url::parse(s).map_err(|e| format!("invalid time source {}: {}", s, e))?;
+            Ok(TimeSource::Https(s.to_string()))
+        } else {
+            Err(format!(
+                "invalid time source '{}', expected ntp://<host>[:<port>] or https://<host>/",
+                s
+            ))
+        }
+    }
+}
+
+impl TryFrom<String> for TimeSource {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        TimeSource::from_str(&s)
+    }
+}
+
+impl fmt::Display for TimeSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeSource::Ntp { host, port } if host.contains(':') => write!(f, "ntp://[{}]:{}", host, port),
+            TimeSource::Ntp { host, port } => write!(f, "ntp://{}:{}", host, port),
+            TimeSource::Https(uri) => write!(f, "{}", uri),
+        }
+    }
+}
+
+//------------ TimeChecker ---------------------------------------------------
+
+pub struct TimeChecker {
+    config: TimeCheckConfig,
+
+    // The result of the last check, if any.
+    last: RwLock<Option<TimeCheck>>,
+}
+
+impl TimeChecker {
+    pub fn new(config: TimeCheckConfig) -> Self {
+        TimeChecker {
+            config,
+            last: RwLock::new(None),
+        }
+    }
+
+    pub fn interval_minutes(&self) -> u32 {
+        self.config.interval_minutes
+    }
+
+    /// Returns the result of the last check, if any.
+    pub fn status(&self) -> Option<TimeCheck> {
+        self.last.read().unwrap().clone()
+    }
+
+    /// Compares the system time with all configured sources, and refuses or
+    /// allows issuance depending on the result.
+    pub async fn check(&self) -> TimeCheck {
+        let mut sources = vec![];
+        for source in &self.config.sources {
+            sources.push(TimeSourceCheck::new(source, source.offset_millis().await));
+        }
+
+        let check = TimeCheck::new(sources, self.config.max_skew_seconds, self.config.refuse_issuance);
+
+        for source in check.sources() {
+            if let Some(e) = source.error() {
+                warn!("Could not get the time from time source {}: {}", source.source(), e);
+            }
+        }
+
+        match check.skew_millis() {
+            None => warn!("Could not check the system time, none of the time sources responded"),
+            Some(skew) if check.skew_exceeded() => error!(
+                "The system time is off by {} ms, which exceeds the maximum of {} seconds. Objects issued now will have incorrect validity times.",
+                skew,
+                self.config.max_skew_seconds
+            ),
+            Some(skew) => debug!("The system time is off by {} ms", skew),
+        }
+
+        if check.issuance_refused() {
+            clock::refuse_issuance(Some(format!(
+                "the system time is off by {} ms, see 'time_check' in the configuration",
+                check.skew_millis().unwrap_or_default()
+            )));
+        } else {
+            clock::refuse_issuance(None);
+        }
+
+        *self.last.write().unwrap() = Some(check.clone());
+        check
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_time_sources() {
+        let ntp = |host: &str, port| TimeSource::Ntp {
+            host: host.to_string(),
+            port,
+        };
+
+        assert_eq!(
+            TimeSource::from_str("ntp://pool.ntp.org").unwrap(),
+            ntp("pool.ntp.org", 123)
+        );
+        assert_eq!(
+            TimeSource::from_str("ntp://time.example.com:1123").unwrap(),
+            ntp("time.example.com", 1123)
+        );
+        assert_eq!(
+            TimeSource::from_str("ntp://[2001:db8::1]").unwrap(),
+            ntp("2001:db8::1", 123)
+        );
+        assert_eq!(
+            TimeSource::from_str("ntp://[2001:db8::1]:1123").unwrap(),
+            ntp("2001:db8::1", 1123)
+        );
+        assert_eq!(
+            TimeSource::from_str("https://www.example.com/").unwrap(),
+            TimeSource::Https("https://www.example.com/".to_string())
+        );
+
+        assert!(TimeSource::from_str("ntp://").is_err());
+        assert!(TimeSource::from_str("ntp://host:port").is_err());
+        assert!(TimeSource::from_str("http://www.example.com/").is_err());
+
+        let source = ntp("2001:db8::1", 123);
+        assert_eq!(TimeSource::from_str(&source.to_string()).unwrap(), source);
+    }
+
+    #[test]
+    fn skew_is_median_of_responding_sources() {
+        let sources = vec![
+            TimeSourceCheck::new("a", Ok(-1_000)),
+            TimeSourceCheck::new("b", Ok(120_000)),
+            TimeSourceCheck::new("c", Err("no response".to_string())),
+            TimeSourceCheck::new("d", Ok(90_000)),
+        ];
+
+        let check = TimeCheck::new(sources.clone(), 60, true);
+        assert_eq!(check.skew_millis(), Some(90_000));
+        assert!(check.skew_exceeded());
+        assert!(check.issuance_refused());
+
+        let check = TimeCheck::new(sources, 100, true);
+        assert!(!check.skew_exceeded());
+        assert!(!check.issuance_refused());
+
+        let check = TimeCheck::new(
+            vec![TimeSourceCheck::new("c", Err("no response".to_string()))],
+            60,
+            true,
+        );
+        assert_eq!(check.skew_millis(), None);
+        assert!(!check.issuance_refused());
+    }
+
+    #[test]
+    fn ntp_timestamp() {
+        // 2022-01-01T00:00:00.5Z
+        let seconds = (1_640_995_200 + NTP_UNIX_OFFSET_SECS) as u32;
+        let mut bytes = seconds.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&0x8000_0000u32.to_be_bytes());
+        assert_eq!(TimeSource::ntp_timestamp_millis(&bytes), 1_640_995_200_500);
+    }
+}
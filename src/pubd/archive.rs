@@ -0,0 +1,435 @@
+//! Archive the content of the repository, so that it can be reconstructed
+//! exactly what was published at any point in time, e.g. for audits and
+//! incident forensics.
+//!
+//! Each archive entry is the gzip compressed RRDP snapshot file for the
+//! session and serial at the time the entry was made. Entries are stored in
+//! a local directory, or in an S3 compatible bucket. An index of the entries
+//! is kept in the data directory, and is used to apply the retention policy.
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use bytes::Bytes;
+use chrono::{Duration, SubsecRound};
+use rpki::repository::x509::Time;
+
+use crate::{
+    commons::{
+        api::rrdp::RrdpSession,
+        error::Error,
+        util::{
+            file,
+            s3::{S3Client, S3Config},
+        },
+        KrillResult,
+    },
+    constants::{REPOSITORY_ARCHIVE_DIR, REPOSITORY_ARCHIVE_INDEX},
+};
+
+//------------ RepositoryArchiveConfig ---------------------------------------
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RepositoryArchiveConfig {
+    /// The interval between archive entries made automatically.
+    #[serde(default = "RepositoryArchiveConfig::dflt_interval_hours")]
+    pub interval_hours: u32,
+
+    /// Remove entries older than this number of days, if set.
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+
+    /// Keep at most this number of entries, if set.
+    #[serde(default)]
+    pub retention_count: Option<usize>,
+
+    /// The directory for the entries, defaults to '$data_dir/repo_archive'.
+    /// Not used if 's3' is set.
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+
+    /// Store entries in this S3 compatible bucket instead of on disk.
+    #[serde(default)]
+    pub s3: Option<S3Config>,
+}
+
+impl RepositoryArchiveConfig {
+    fn dflt_interval_hours() -> u32 {
+        24
+    }
+}
+
+//------------ RepositoryArchiveEntry ----------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepositoryArchiveEntry {
+    name: String,
+    time: Time,
+    session: RrdpSession,
+    serial: u64,
+    size: u64,
+}
+
+impl RepositoryArchiveEntry {
+    fn new(time: Time, session: RrdpSession, serial: u64, size: u64) -> Self {
+        let name = format!("{}-{}-{}.xml.gz", time.format("%Y%m%dT%H%M%S%.3fZ"), session, serial);
+        RepositoryArchiveEntry {
+            name,
+            time,
+            session,
+            serial,
+            size,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn time(&self) -> Time {
+        self.time
+    }
+
+    pub fn session(&self) -> RrdpSession {
+        self.session
+    }
+
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl fmt::Display for RepositoryArchiveEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {}, {}, {}, {}",
+            self.name,
+            self.time.to_rfc3339(),
+            self.session,
+            self.serial,
+            self.size
+        )
+    }
+}
+
+//------------ RepositoryArchiveList -----------------------------------------
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepositoryArchiveList {
+    location: String,
+    entries: Vec<RepositoryArchiveEntry>,
+}
+
+impl RepositoryArchiveList {
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    pub fn entries(&self) -> &Vec<RepositoryArchiveEntry> {
+        &self.entries
+    }
+}
+
+impl fmt::Display for RepositoryArchiveList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Archive location: {}", self.location)?;
+        writeln!(f)?;
+        writeln!(f, "Name, Time, Session, Serial, Size")?;
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ RepositoryArchiveTarget ---------------------------------------
+
+#[derive(Debug)]
+enum RepositoryArchiveTarget {
+    Dir(PathBuf),
+    S3(S3Client),
+}
+
+impl fmt::Display for RepositoryArchiveTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryArchiveTarget::Dir(dir) => write!(f, "{}", dir.to_string_lossy()),
+            RepositoryArchiveTarget::S3(client) => write!(f, "{}", client.config()),
+        }
+    }
+}
+
+//------------ RepositoryArchiver --------------------------------------------
+
+/// Makes archive entries of the repository content, and removes entries
+/// which are no longer retained according to the configuration.
+#[derive(Debug)]
+pub struct RepositoryArchiver {
+    config: RepositoryArchiveConfig,
+    target: RepositoryArchiveTarget,
+    index_path: PathBuf,
+
+    // Guards changes to the index.
+    index_lock: Mutex<()>,
+
+    // The time of the last entry made since Krill was started.
+    last_entry_time: Mutex<Option<Time>>,
+}
+
+impl RepositoryArchiver {
+    pub fn new(config: RepositoryArchiveConfig, data_dir: &Path) -> Self {
+        let target = match &config.s3 {
+            Some(s3) => RepositoryArchiveTarget::S3(S3Client::new(s3.clone())),
+            None => RepositoryArchiveTarget::Dir(
+                config
+                    .dir
+                    .clone()
+                    .unwrap_or_else(|| data_dir.join(REPOSITORY_ARCHIVE_DIR)),
+            ),
+        };
+
+        let index_path = data_dir.join(REPOSITORY_ARCHIVE_INDEX);
+
+        RepositoryArchiver {
+            config,
+            target,
+            index_path,
+            index_lock: Mutex::new(()),
+            last_entry_time: Mutex::new(None),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::hours(self.config.interval_hours.into())
+    }
+
+    /// Returns when the next entry is due, i.e. one interval after the last
+    /// entry, so that restarting Krill neither skips nor adds entries.
+    pub fn next_due(&self) -> KrillResult<Time> {
+        let _lock = self.index_lock.lock().unwrap();
+        Ok(match self.load_index()?.last() {
+            Some(last) => last.time + self.interval(),
+            None => Time::now(),
+        })
+    }
+
+    /// Lists the current entries, oldest first.
+    pub fn list(&self) -> KrillResult<RepositoryArchiveList> {
+        let _lock = self.index_lock.lock().unwrap();
+        Ok(RepositoryArchiveList {
+            location: self.target.to_string(),
+            entries: self.load_index()?,
+        })
+    }
+
+    /// Adds an entry for the given RRDP snapshot XML, and then removes any
+    /// entries that are no longer retained.
+    pub async fn archive(
+        &self,
+        session: RrdpSession,
+        serial: u64,
+        snapshot_xml: &[u8],
+    ) -> KrillResult<RepositoryArchiveEntry> {
+        let compressed = Self::gzip(snapshot_xml)?;
+        let entry = RepositoryArchiveEntry::new(self.new_entry_time()?, session, serial, compressed.len() as u64);
+
+        info!(
+            "Archiving repository content at RRDP serial {} to {}",
+            serial, self.target
+        );
+        match &self.target {
+            RepositoryArchiveTarget::Dir(dir) => file::save(&compressed, &dir.join(entry.name()))?,
+            RepositoryArchiveTarget::S3(client) => client
+                .put_object(entry.name(), compressed, "application/gzip")
+                .await
+                .map_err(Error::HttpClientError)?,
+        }
+
+        let expired = {
+            let _lock = self.index_lock.lock().unwrap();
+            let mut entries = self.load_index()?;
+            entries.push(entry.clone());
+            let expired = self.expired(&mut entries, Time::now());
+            file::save_json(&entries, &self.index_path)?;
+            expired
+        };
+
+        for entry in expired {
+            info!("Removing repository archive entry '{}'", entry.name());
+            let res = match &self.target {
+                RepositoryArchiveTarget::Dir(dir) => file::delete_file(&dir.join(entry.name())).map_err(Error::from),
+                RepositoryArchiveTarget::S3(client) => {
+                    client.delete_object(entry.name()).await.map_err(Error::HttpClientError)
+                }
+            };
+            if let Err(e) = res {
+                warn!("Could not remove repository archive entry '{}': {}", entry.name(), e);
+            }
+        }
+
+        Ok(entry)
+    }
+
+    /// Returns the time for a new entry. This is later than the time of all
+    /// earlier entries, so that entries made in quick succession, e.g. by
+    /// hand, do not get the same name and overwrite each other. The time is
+    /// truncated to milliseconds, as used in the names of entries.
+    fn new_entry_time(&self) -> KrillResult<Time> {
+        let _lock = self.index_lock.lock().unwrap();
+        let mut last_entry_time = self.last_entry_time.lock().unwrap();
+
+        let entries = self.load_index()?;
+        let mut time = Time::new(Time::now().trunc_subsecs(3));
+        for earlier in entries.iter().map(|entry| entry.time).chain(*last_entry_time) {
+            if earlier >= time {
+                time = earlier + Duration::milliseconds(1);
+            }
+        }
+
+        *last_entry_time = Some(time);
+        Ok(time)
+    }
+
+    /// Removes the entries that are no longer retained from the list,
+    /// which is sorted oldest first, and returns them.
+    fn expired(&self, entries: &mut Vec<RepositoryArchiveEntry>, now: Time) -> Vec<RepositoryArchiveEntry> {
+        let mut expired = vec![];
+
+        if let Some(days) = self.config.retention_days {
+            let threshold = now - Duration::days(days.into());
+            while entries.first().map(|entry| entry.time < threshold).unwrap_or(false) {
+                expired.push(entries.remove(0));
+            }
+        }
+
+        if let Some(count) = self.config.retention_count {
+            while entries.len() > count {
+                expired.push(entries.remove(0));
+            }
+        }
+
+        expired
+    }
+
+    fn load_index(&self) -> KrillResult<Vec<RepositoryArchiveEntry>> {
+        if self.index_path.exists() {
+            file::load_json(&self.index_path).map_err(Error::from)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn gzip(content: &[u8]) -> KrillResult<Bytes> {
+        use std::io::Write;
+
+        let err = |e| Error::Custom(format!("Could not compress repository archive entry: {}", e));
+        let mut encoder = libflate::gzip::Encoder::new(Vec::new()).map_err(err)?;
+        encoder.write_all(content).map_err(err)?;
+        encoder.finish().into_result().map(Bytes::from).map_err(err)
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test;
+
+    fn config(retention_days: Option<u32>, retention_count: Option<usize>) -> RepositoryArchiveConfig {
+        RepositoryArchiveConfig {
+            interval_hours: 24,
+            retention_days,
+            retention_count,
+            dir: None,
+            s3: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn archive_and_retain_entries() {
+        let d = test::tmp_dir();
+        let archiver = RepositoryArchiver::new(config(None, Some(2)), &d);
+        let session = RrdpSession::default();
+
+        let first = archiver.archive(session, 1, b"<snapshot/>").await.unwrap();
+        assert!(d.join(REPOSITORY_ARCHIVE_DIR).join(first.name()).is_file());
+
+        archiver.archive(session, 2, b"<snapshot/>").await.unwrap();
+        archiver.archive(session, 3, b"<snapshot/>").await.unwrap();
+
+        let list = archiver.list().unwrap();
+        let serials: Vec<u64> = list.entries().iter().map(|entry| entry.serial()).collect();
+        assert_eq!(serials, vec![2, 3]);
+        assert!(!d.join(REPOSITORY_ARCHIVE_DIR).join(first.name()).exists());
+
+        let _ = std::fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn expire_entries_by_age() {
+        let archiver = RepositoryArchiver::new(config(Some(7), None), Path::new("/tmp"));
+        let session = RrdpSession::default();
+        let now = Time::now();
+
+        let mut entries = vec![
+            RepositoryArchiveEntry::new(now - Duration::days(10), session, 1, 0),
+            RepositoryArchiveEntry::new(now - Duration::days(8), session, 2, 0),
+            RepositoryArchiveEntry::new(now - Duration::days(1), session, 3, 0),
+        ];
+
+        let expired = archiver.expired(&mut entries, now);
+        assert_eq!(expired.len(), 2);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].serial(), 3);
+    }
+
+    #[tokio::test]
+    async fn archive_in_quick_succession() {
+        let d = test::tmp_dir();
+        let archiver = RepositoryArchiver::new(config(None, None), &d);
+        let session = RrdpSession::default();
+
+        for serial in 1..=5 {
+            archiver.archive(session, serial, b"<snapshot/>").await.unwrap();
+        }
+
+        let entries = archiver.list().unwrap().entries().clone();
+        assert_eq!(entries.len(), 5);
+        for pair in entries.windows(2) {
+            assert!(pair[0].time() < pair[1].time());
+            assert_ne!(pair[0].name(), pair[1].name());
+        }
+        for entry in &entries {
+            assert!(d.join(REPOSITORY_ARCHIVE_DIR).join(entry.name()).is_file());
+        }
+
+        let _ = std::fs::remove_dir_all(d);
+    }
+
+    #[tokio::test]
+    async fn next_due_after_restart() {
+        let d = test::tmp_dir();
+        let session = RrdpSession::default();
+
+        let archiver = RepositoryArchiver::new(config(None, None), &d);
+        assert!(archiver.next_due().unwrap() <= Time::now());
+
+        let entry = archiver.archive(session, 1, b"<snapshot/>").await.unwrap();
+
+        // A new archiver, as used after a restart, plans the next entry one
+        // interval after the last entry.
+        let archiver = RepositoryArchiver::new(config(None, None), &d);
+        assert_eq!(archiver.next_due().unwrap(), entry.time() + Duration::hours(24));
+
+        let _ = std::fs::remove_dir_all(d);
+    }
+}
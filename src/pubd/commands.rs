@@ -4,7 +4,7 @@ use rpki::uri;
 
 use rpki::ca::idexchange::{MyHandle, PublisherHandle};
 
-use crate::commons::api::IdCertInfo;
+use crate::commons::api::{IdCertInfo, PublisherQuota, PublisherValidation};
 use crate::{
     commons::{
         actor::Actor,
@@ -30,6 +30,21 @@ pub enum RepoAccessCmdDet {
     RemovePublisher {
         name: PublisherHandle,
     },
+    UpdatePublisherQuota {
+        name: PublisherHandle,
+        quota: Option<PublisherQuota>,
+    },
+    UpdatePublisherValidation {
+        name: PublisherHandle,
+        validation: Option<PublisherValidation>,
+    },
+    StagePublisherIdCert {
+        name: PublisherHandle,
+        id_cert: Option<IdCertInfo>,
+    },
+    ActivatePublisherIdCert {
+        name: PublisherHandle,
+    },
 }
 
 impl CommandDetails for RepoAccessCmdDet {
@@ -64,6 +79,52 @@ impl RepoAccessCmdDet {
     pub fn remove_publisher(handle: &MyHandle, name: PublisherHandle, actor: &Actor) -> RepoAccessCmd {
         SentCommand::new(handle, None, RepoAccessCmdDet::RemovePublisher { name }, actor)
     }
+
+    pub fn update_publisher_quota(
+        handle: &MyHandle,
+        name: PublisherHandle,
+        quota: Option<PublisherQuota>,
+        actor: &Actor,
+    ) -> RepoAccessCmd {
+        SentCommand::new(
+            handle,
+            None,
+            RepoAccessCmdDet::UpdatePublisherQuota { name, quota },
+            actor,
+        )
+    }
+
+    pub fn update_publisher_validation(
+        handle: &MyHandle,
+        name: PublisherHandle,
+        validation: Option<PublisherValidation>,
+        actor: &Actor,
+    ) -> RepoAccessCmd {
+        SentCommand::new(
+            handle,
+            None,
+            RepoAccessCmdDet::UpdatePublisherValidation { name, validation },
+            actor,
+        )
+    }
+
+    pub fn stage_publisher_id_cert(
+        handle: &MyHandle,
+        name: PublisherHandle,
+        id_cert: Option<IdCertInfo>,
+        actor: &Actor,
+    ) -> RepoAccessCmd {
+        SentCommand::new(
+            handle,
+            None,
+            RepoAccessCmdDet::StagePublisherIdCert { name, id_cert },
+            actor,
+        )
+    }
+
+    pub fn activate_publisher_id_cert(handle: &MyHandle, name: PublisherHandle, actor: &Actor) -> RepoAccessCmd {
+        SentCommand::new(handle, None, RepoAccessCmdDet::ActivatePublisherIdCert { name }, actor)
+    }
 }
 
 impl fmt::Display for RepoAccessCmdDet {
@@ -77,6 +138,21 @@ impl From<RepoAccessCmdDet> for StorableRepositoryCommand {
         match d {
             RepoAccessCmdDet::AddPublisher { name, .. } => StorableRepositoryCommand::AddPublisher { name },
             RepoAccessCmdDet::RemovePublisher { name } => StorableRepositoryCommand::RemovePublisher { name },
+            RepoAccessCmdDet::UpdatePublisherQuota { name, .. } => {
+                StorableRepositoryCommand::UpdatePublisherQuota { name }
+            }
+            RepoAccessCmdDet::UpdatePublisherValidation { name, validation } => {
+                StorableRepositoryCommand::UpdatePublisherValidation { name, validation }
+            }
+            RepoAccessCmdDet::StagePublisherIdCert { name, id_cert } => {
+                StorableRepositoryCommand::StagePublisherIdCert {
+                    name,
+                    key: id_cert.map(|id_cert| id_cert.public_key().key_identifier()),
+                }
+            }
+            RepoAccessCmdDet::ActivatePublisherIdCert { name } => {
+                StorableRepositoryCommand::ActivatePublisherIdCert { name }
+            }
         }
     }
 }
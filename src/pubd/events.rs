@@ -6,7 +6,12 @@ use rpki::{
 };
 
 use crate::{
-    commons::{api::IdCertInfo, crypto::KrillSigner, eventsourcing::StoredEvent, KrillResult},
+    commons::{
+        api::{IdCertInfo, PublisherQuota, PublisherValidation},
+        crypto::KrillSigner,
+        eventsourcing::StoredEvent,
+        KrillResult,
+    },
     pubd::Publisher,
 };
 
@@ -81,6 +86,21 @@ pub enum RepositoryAccessEventDetails {
     PublisherRemoved {
         name: PublisherHandle,
     },
+    PublisherQuotaUpdated {
+        name: PublisherHandle,
+        quota: Option<PublisherQuota>,
+    },
+    PublisherValidationUpdated {
+        name: PublisherHandle,
+        validation: Option<PublisherValidation>,
+    },
+    PublisherIdCertStaged {
+        name: PublisherHandle,
+        id_cert: Option<IdCertInfo>,
+    },
+    PublisherIdCertActivated {
+        name: PublisherHandle,
+    },
 }
 
 impl fmt::Display for RepositoryAccessEventDetails {
@@ -88,6 +108,26 @@ impl fmt::Display for RepositoryAccessEventDetails {
         match self {
             RepositoryAccessEventDetails::PublisherAdded { name, .. } => write!(f, "Publisher '{}' added", name),
             RepositoryAccessEventDetails::PublisherRemoved { name } => write!(f, "Publisher '{}' removed", name),
+            RepositoryAccessEventDetails::PublisherQuotaUpdated { name, quota } => match quota {
+                Some(_) => write!(f, "Publisher '{}' quota updated", name),
+                None => write!(f, "Publisher '{}' quota reset to server default", name),
+            },
+            RepositoryAccessEventDetails::PublisherValidationUpdated { name, validation } => match validation {
+                Some(validation) => write!(f, "Publisher '{}' validation set to '{}'", name, validation),
+                None => write!(f, "Publisher '{}' validation reset to server default", name),
+            },
+            RepositoryAccessEventDetails::PublisherIdCertStaged { name, id_cert } => match id_cert {
+                Some(id_cert) => write!(
+                    f,
+                    "Publisher '{}' staged new ID certificate with key id: {}",
+                    name,
+                    id_cert.public_key().key_identifier()
+                ),
+                None => write!(f, "Publisher '{}' ID certificate rollover cancelled", name),
+            },
+            RepositoryAccessEventDetails::PublisherIdCertActivated { name } => {
+                write!(f, "Publisher '{}' activated staged ID certificate", name)
+            }
         }
     }
 }
@@ -109,4 +149,55 @@ impl RepositoryAccessEventDetails {
     pub(super) fn publisher_removed(me: &MyHandle, version: u64, name: PublisherHandle) -> RepositoryAccessEvent {
         StoredEvent::new(me, version, RepositoryAccessEventDetails::PublisherRemoved { name })
     }
+
+    pub(super) fn publisher_quota_updated(
+        me: &MyHandle,
+        version: u64,
+        name: PublisherHandle,
+        quota: Option<PublisherQuota>,
+    ) -> RepositoryAccessEvent {
+        StoredEvent::new(
+            me,
+            version,
+            RepositoryAccessEventDetails::PublisherQuotaUpdated { name, quota },
+        )
+    }
+
+    pub(super) fn publisher_validation_updated(
+        me: &MyHandle,
+        version: u64,
+        name: PublisherHandle,
+        validation: Option<PublisherValidation>,
+    ) -> RepositoryAccessEvent {
+        StoredEvent::new(
+            me,
+            version,
+            RepositoryAccessEventDetails::PublisherValidationUpdated { name, validation },
+        )
+    }
+
+    pub(super) fn publisher_id_cert_staged(
+        me: &MyHandle,
+        version: u64,
+        name: PublisherHandle,
+        id_cert: Option<IdCertInfo>,
+    ) -> RepositoryAccessEvent {
+        StoredEvent::new(
+            me,
+            version,
+            RepositoryAccessEventDetails::PublisherIdCertStaged { name, id_cert },
+        )
+    }
+
+    pub(super) fn publisher_id_cert_activated(
+        me: &MyHandle,
+        version: u64,
+        name: PublisherHandle,
+    ) -> RepositoryAccessEvent {
+        StoredEvent::new(
+            me,
+            version,
+            RepositoryAccessEventDetails::PublisherIdCertActivated { name },
+        )
+    }
 }
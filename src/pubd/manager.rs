@@ -1,6 +1,11 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, VecDeque},
+    path::Path,
+    sync::{Arc, RwLock},
+};
 
 use bytes::Bytes;
+use chrono::Duration;
 
 use rpki::{
     ca::{
@@ -9,20 +14,31 @@ use rpki::{
         publication,
         publication::{ListReply, PublishDelta},
     },
+    crypto::KeyIdentifier,
     repository::x509::Time,
 };
 
 use crate::{
     commons::{
         actor::Actor,
-        api::{PublicationServerUris, PublisherDetails, RepoFileDeleteCriteria},
+        api::{
+            AggregateSnapshot, PublicationServerUris, PublisherActivity, PublisherDetails, PublisherQuota,
+            PublisherQuotaInfo, PublisherStatsInfo, PublisherStatsList, PublisherValidation, PublisherValidationInfo,
+            RepoFileDeleteCriteria, SearchMatch, SearchQuery,
+        },
         crypto::KrillSigner,
         error::Error,
-        util::cmslogger::CmsLogger,
+        eventsourcing::{KeyStoreKey, KeyValueStore},
+        util::{cmslogger::CmsLogger, file},
         KrillResult,
     },
-    daemon::{config::Config, mq::TaskQueue},
-    pubd::{RepoStats, RepositoryAccessProxy, RepositoryContentProxy},
+    constants::{PUBSERVER_CONTENT_DIR, PUBSERVER_DIR, STATUS_DIR},
+    daemon::{backup::BackupWriter, config::Config, mq::TaskQueue, notify::EventStream},
+    pubd::{
+        RepoStats, RepositoryAccessProxy, RepositoryArchiveEntry, RepositoryArchiveList, RepositoryArchiver,
+        RepositoryContent, RepositoryContentProxy, RepositoryEvent, RepositoryEventType, RepositoryNotifier,
+        RepositoryReplica, RepositoryReplicaStatus, RepositoryS3Store, RrdpStats,
+    },
 };
 
 use super::RrdpUpdateNeeded;
@@ -39,26 +55,202 @@ pub struct RepositoryManager {
     // shared task queue, use to schedule RRDP updates when content is updated.
     tasks: Arc<TaskQueue>,
 
-    config: Arc<Config>,
+    // publication activity per publisher, used to enforce quotas and for
+    // statistics.
+    activity: PublisherActivityLogs,
+
+    // archives the repository content, if configured
+    archiver: Option<RepositoryArchiver>,
+
+    // posts events to the configured webhooks
+    notifier: RepositoryNotifier,
+
+    // streams events to connected clients
+    events: Arc<EventStream>,
+
+    // replicates the repository of a primary, if configured
+    replica: Option<RepositoryReplica>,
+
+    // uploads the repository files to S3, if configured
+    s3_store: Option<RepositoryS3Store>,
+
+    config: RwLock<Arc<Config>>,
     signer: Arc<KrillSigner>,
 }
 
+//------------ PublisherActivityLog ------------------------------------------
+
+/// Keeps track of the publication activity of a publisher.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct PublisherActivityLog {
+    // times of the deltas published in the last hour, used to enforce quotas
+    recent_deltas: VecDeque<Time>,
+    last_publish: Option<Time>,
+    deltas_total: u64,
+    errors_total: u64,
+    last_error: Option<Time>,
+}
+
+impl PublisherActivityLog {
+    /// Returns the number of deltas published in the last hour, and forgets
+    /// about older deltas.
+    fn deltas_last_hour(&mut self) -> usize {
+        let since = Time::now() - Duration::hours(1);
+        while self.recent_deltas.front().map(|time| *time < since).unwrap_or(false) {
+            self.recent_deltas.pop_front();
+        }
+        self.recent_deltas.len()
+    }
+}
+
+//------------ PublisherActivityLogs -----------------------------------------
+
+const PUBLISHER_ACTIVITY_KEY: &str = "publisher-activity.json";
+
+/// Keeps track of the publication activity of all publishers. The activity
+/// is saved in the status directory whenever it changes, so that the quotas
+/// still apply and the totals are not reset after the server restarts.
+struct PublisherActivityLogs {
+    store: KeyValueStore,
+    logs: RwLock<HashMap<PublisherHandle, PublisherActivityLog>>,
+}
+
+impl PublisherActivityLogs {
+    fn new(data_dir: &Path) -> KrillResult<Self> {
+        let store = KeyValueStore::disk(data_dir, STATUS_DIR)?;
+
+        // Start with empty logs if they cannot be read, rather than refusing
+        // to start the publication server.
+        let logs = store.get(&Self::key()).ok().flatten().unwrap_or_default();
+
+        Ok(PublisherActivityLogs {
+            store,
+            logs: RwLock::new(logs),
+        })
+    }
+
+    fn key() -> KeyStoreKey {
+        KeyStoreKey::simple(PUBLISHER_ACTIVITY_KEY.to_string())
+    }
+
+    /// Remembers that the publisher published a delta.
+    fn record_publish(&self, publisher: &PublisherHandle) {
+        let now = Time::now();
+        let mut logs = self.logs.write().unwrap();
+        let log = logs.entry(publisher.clone()).or_default();
+        log.recent_deltas.push_back(now);
+        log.last_publish = Some(now);
+        log.deltas_total += 1;
+        self.save(&logs);
+    }
+
+    /// Remembers that a request by the publisher resulted in an error.
+    fn record_error(&self, publisher: &PublisherHandle) {
+        let mut logs = self.logs.write().unwrap();
+        let log = logs.entry(publisher.clone()).or_default();
+        log.errors_total += 1;
+        log.last_error = Some(Time::now());
+        self.save(&logs);
+    }
+
+    /// Returns the number of deltas published by the publisher in the last hour,
+    /// and forgets about older deltas.
+    fn deltas_last_hour(&self, publisher: &PublisherHandle) -> usize {
+        self.logs
+            .write()
+            .unwrap()
+            .get_mut(publisher)
+            .map(|log| log.deltas_last_hour())
+            .unwrap_or(0)
+    }
+
+    /// Returns the publication activity for the publisher.
+    fn activity(&self, publisher: &PublisherHandle) -> PublisherActivity {
+        self.logs
+            .write()
+            .unwrap()
+            .get_mut(publisher)
+            .map(|log| PublisherActivity {
+                last_publish: log.last_publish.map(Into::into),
+                deltas_last_hour: log.deltas_last_hour(),
+                deltas_total: log.deltas_total,
+                errors_total: log.errors_total,
+                last_error: log.last_error.map(Into::into),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Forgets the activity of a removed publisher.
+    fn remove(&self, publisher: &PublisherHandle) {
+        let mut logs = self.logs.write().unwrap();
+        if logs.remove(publisher).is_some() {
+            self.save(&logs);
+        }
+    }
+
+    fn save(&self, logs: &HashMap<PublisherHandle, PublisherActivityLog>) {
+        if let Err(e) = self.store.store(&Self::key(), logs) {
+            warn!("Cannot save the publication activity of publishers: {}", e);
+        }
+    }
+}
+
 /// # Constructing
 ///
 impl RepositoryManager {
     /// Builds a RepositoryManager. This will use a disk based KeyValueStore using the
     /// the data directory specified in the supplied `Config`.
-    pub fn build(config: Arc<Config>, tasks: Arc<TaskQueue>, signer: Arc<KrillSigner>) -> Result<Self, Error> {
-        let access_proxy = Arc::new(RepositoryAccessProxy::disk(&config)?);
-        let content_proxy = Arc::new(RepositoryContentProxy::disk(&config)?);
-
-        Ok(RepositoryManager {
+    pub fn build(
+        config: Arc<Config>,
+        tasks: Arc<TaskQueue>,
+        events: Arc<EventStream>,
+        signer: Arc<KrillSigner>,
+    ) -> Result<Self, Error> {
+        let access_proxy = Arc::new(RepositoryAccessProxy::create(&config)?);
+        let content_proxy = Arc::new(RepositoryContentProxy::create(&config)?);
+        let archiver = config
+            .repository_archive
+            .clone()
+            .map(|archive_config| RepositoryArchiver::new(archive_config, &config.data_dir));
+        let replica = config
+            .repository_replica
+            .clone()
+            .map(|replica_config| RepositoryReplica::new(replica_config, &config.data_dir));
+        let s3_store = config
+            .repository_s3
+            .clone()
+            .map(|s3_config| RepositoryS3Store::new(s3_config, &config.data_dir));
+
+        let manager = RepositoryManager {
             access: access_proxy,
             content: content_proxy,
             tasks,
-            config,
+            activity: PublisherActivityLogs::new(&config.data_dir)?,
+            archiver,
+            notifier: RepositoryNotifier::build(&config.repository_webhooks),
+            events,
+            replica,
+            s3_store,
+            config: RwLock::new(config),
             signer,
-        })
+        };
+
+        if manager.initialized()? {
+            manager.write_rsyncd_conf();
+        }
+
+        Ok(manager)
+    }
+
+    fn config(&self) -> Arc<Config> {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Applies a reloaded configuration. Note that only settings which are
+    /// read when they are used take effect, e.g. the archive, replica and S3
+    /// settings are only used after a restart.
+    pub fn update_config(&self, config: Arc<Config>) {
+        *self.config.write().unwrap() = config;
     }
 }
 /// # Repository Server Management
@@ -68,16 +260,54 @@ impl RepositoryManager {
         self.access.initialized()
     }
 
+    /// Returns the key of the identity of the Publication Server, if it is
+    /// initialized.
+    pub fn id_key(&self) -> KrillResult<Option<KeyIdentifier>> {
+        self.access.id_key()
+    }
+
     /// Create the publication server, will fail if it was already created.
     pub fn init(&self, uris: PublicationServerUris) -> KrillResult<()> {
+        if self.replica.is_some() {
+            return Err(Error::custom(
+                "Cannot initialize the repository when it is configured as a replica, see 'repository_replica'",
+            ));
+        }
+
         info!("Initializing repository");
         self.access.init(uris.clone(), &self.signer)?;
-        self.content.init(&self.config.data_dir, uris)?;
-        self.content.write_repository(self.config.rrdp_updates_config)?;
+        self.content.init(&self.config().data_dir, uris)?;
+        self.content.write_repository(self.config().rrdp_updates_config)?;
+        self.write_rsyncd_conf();
+        self.schedule_s3_sync();
 
         Ok(())
     }
 
+    /// Writes the rsyncd module configuration for the repository to the
+    /// configured file, if any. The file is only written if its content
+    /// changed. Failures are logged, they do not affect the repository.
+    fn write_rsyncd_conf(&self) {
+        let config = self.config();
+        if let Some(path) = &config.rsyncd_conf {
+            let res = self.content.rsyncd_conf().and_then(|conf| {
+                if file::read(path).ok().as_deref() != Some(conf.as_bytes()) {
+                    info!("Writing rsyncd configuration to '{}'", path.to_string_lossy());
+                    file::save(conf.as_bytes(), path)?;
+                }
+                Ok(())
+            });
+
+            if let Err(e) = res {
+                error!(
+                    "Could not write rsyncd configuration to '{}': {}",
+                    path.to_string_lossy(),
+                    e
+                );
+            }
+        }
+    }
+
     /// Clear the publication server. Will fail if it still
     /// has publishers. Or if it does not exist.
     pub fn repository_clear(&self) -> KrillResult<()> {
@@ -94,10 +324,57 @@ impl RepositoryManager {
         }
     }
 
+    /// Saves a snapshot of the publishers known to the server, if they changed
+    /// since the last snapshot, or if forced. Note that the repository content
+    /// uses its own snapshot, see `update_snapshots`.
+    pub fn save_snapshots(&self, force: bool) -> KrillResult<Vec<AggregateSnapshot>> {
+        self.access.save_snapshots(force)
+    }
+
+    /// Archives the history of the publishers known to the server before the
+    /// given time. Returns the number of archived commands.
+    pub fn archive_history(&self, before: Time) -> KrillResult<usize> {
+        self.access.archive_history(before)
+    }
+
+    /// Verifies that the snapshot of the publishers known to the server is
+    /// equivalent to the state rebuilt from its events.
+    pub fn verify_snapshots(&self) -> KrillResult<Vec<AggregateSnapshot>> {
+        self.access.verify_snapshots()
+    }
+
+    /// Exports the stores for the publishers known to the server, and the
+    /// repository content, to a backup.
+    pub fn export(&self, backup: &mut BackupWriter) -> KrillResult<()> {
+        self.access
+            .export(&mut |entry| backup.add_entry(PUBSERVER_DIR, entry))?;
+        self.content
+            .export(&mut |entry| backup.add_entry(PUBSERVER_CONTENT_DIR, entry))
+    }
+
     /// List all current publishers
     pub fn publishers(&self) -> KrillResult<Vec<PublisherHandle>> {
         self.access.publishers()
     }
+
+    /// Returns the publishers which match the query, because their name
+    /// matches, or because they publish the URI.
+    pub fn search(&self, query: &SearchQuery) -> KrillResult<Vec<SearchMatch>> {
+        let mut matches = vec![];
+
+        if !self.initialized()? {
+            return Ok(matches);
+        }
+
+        for publisher in self.access.publishers()? {
+            let base_uri = self.access.get_publisher(&publisher)?.base_uri().clone();
+            if query.matches_text(publisher.as_str()) || query.matches_base_uri(&base_uri) {
+                matches.push(SearchMatch::Publisher { publisher, base_uri });
+            }
+        }
+
+        Ok(matches)
+    }
 }
 
 /// # Publication Protocol support
@@ -105,12 +382,15 @@ impl RepositoryManager {
 impl RepositoryManager {
     /// Handle an RFC8181 request and sign the response.
     pub fn rfc8181(&self, publisher_handle: PublisherHandle, msg_bytes: Bytes) -> KrillResult<Bytes> {
-        let cms_logger = CmsLogger::for_rfc8181_rcvd(self.config.rfc8181_log_dir.as_ref(), &publisher_handle);
+        let cms_logger = CmsLogger::for_rfc8181_rcvd(self.config().rfc8181_log_dir.as_ref(), &publisher_handle);
 
         let cms = self
             .access
             .decode_and_validate(&publisher_handle, &msg_bytes)
             .map_err(|e| {
+                if self.access.get_publisher(&publisher_handle).is_ok() {
+                    self.activity.record_error(&publisher_handle);
+                }
                 Error::Custom(format!(
                     "Issue with publication request by publisher '{}': {}",
                     publisher_handle, e
@@ -151,31 +431,96 @@ impl RepositoryManager {
         publisher_handle: &PublisherHandle,
         query: publication::Query,
     ) -> KrillResult<publication::Message> {
-        match query {
+        let res = match query {
             publication::Query::List => {
                 debug!("Received RFC 8181 list query for {}", publisher_handle);
-                let list_reply = self.list(publisher_handle)?;
-                Ok(publication::Message::list_reply(list_reply))
+                self.list(publisher_handle).map(publication::Message::list_reply)
             }
             publication::Query::Delta(delta) => {
                 debug!("Received RFC 8181 delta query for {}", publisher_handle);
-                self.publish(publisher_handle, delta)?;
-                Ok(publication::Message::success())
+                self.publish(publisher_handle, delta)
+                    .map(|_| publication::Message::success())
             }
+        };
+
+        if res.is_err() {
+            self.activity.record_error(publisher_handle);
         }
+
+        res
     }
 
     /// Do an RRDP session reset.
     pub fn rrdp_session_reset(&self) -> KrillResult<()> {
-        self.content.session_reset(self.config.rrdp_updates_config)
+        self.content.session_reset(self.config().rrdp_updates_config)?;
+        if self.initialized()? {
+            self.notify_rrdp_update();
+            self.schedule_s3_sync();
+        }
+        Ok(())
+    }
+
+    /// Do an RRDP session reset if the configured interval has passed since
+    /// the current session was started. If the start of the current session
+    /// is not known, the session is reset now.
+    ///
+    /// Returns the time when this should be checked again, or `None` if no
+    /// interval is configured.
+    pub fn rrdp_session_reset_if_needed(&self) -> KrillResult<Option<Time>> {
+        let hours = match self.config().rrdp_updates_config.rrdp_session_reset_interval_hours {
+            None => return Ok(None),
+            Some(hours) => hours,
+        };
+        let interval = Duration::hours(hours.into());
+
+        if !self.initialized()? {
+            return Ok(Some(Time::now() + Duration::hours(1)));
+        }
+
+        if let Some(start) = self.content.rrdp_stats()?.session_start() {
+            let due = start + interval;
+            if due > Time::now() {
+                return Ok(Some(due));
+            }
+        }
+
+        info!("Performing scheduled RRDP session reset");
+        self.rrdp_session_reset()?;
+
+        Ok(Some(Time::now() + interval))
     }
 
     /// Let a known publisher publish in a repository.
     pub fn publish(&self, publisher_handle: &PublisherHandle, delta: PublishDelta) -> KrillResult<()> {
         let publisher = self.access.get_publisher(publisher_handle)?;
+        let quota = publisher.quota().unwrap_or(self.config().publisher_quota);
+        let validation = publisher.validation().unwrap_or(self.config().publisher_validation);
+
+        if let Some(max_deltas) = quota.max_deltas_per_hour {
+            let deltas = self.activity.deltas_last_hour(publisher_handle);
+            if deltas >= max_deltas {
+                return Err(Error::PublisherQuotaExceeded(
+                    publisher_handle.clone(),
+                    format!("{} deltas published in the last hour, max is {}", deltas, max_deltas),
+                ));
+            }
+        }
+
+        let event = if self.notifier.is_enabled(RepositoryEventType::Publish) || self.events.has_subscribers() {
+            Some(RepositoryEvent::publish(publisher_handle.clone(), &delta))
+        } else {
+            None
+        };
 
         self.content
-            .publish(publisher_handle.clone(), delta, publisher.base_uri())?;
+            .publish(publisher_handle.clone(), delta, publisher.base_uri(), quota, validation)?;
+
+        if let Some(event) = event {
+            self.events.publish(event.clone());
+            self.notifier.notify(event);
+        }
+
+        self.activity.record_publish(publisher_handle);
 
         self.tasks.update_rrdp_if_needed(Time::now().into());
         Ok(())
@@ -187,40 +532,175 @@ impl RepositoryManager {
     pub fn update_rrdp_if_needed(&self) -> KrillResult<Option<Time>> {
         // See if an update is needed
         {
-            match self.content.rrdp_update_needed(self.config.rrdp_updates_config)? {
+            match self.content.rrdp_update_needed(self.config().rrdp_updates_config)? {
                 RrdpUpdateNeeded::No => return Ok(None),
                 RrdpUpdateNeeded::Later(time) => return Ok(Some(time)),
                 RrdpUpdateNeeded::Yes => {} // proceed
             }
         }
 
-        let content = self.content.update_rrdp(self.config.rrdp_updates_config)?;
-        content.write_repository(self.config.rrdp_updates_config)?;
+        let content = self.update_rrdp()?;
+        content.write_repository(self.config().rrdp_updates_config)?;
+        self.schedule_s3_sync();
 
         Ok(None)
     }
 
+    /// Updates RRDP with the staged changes, if the update interval allows.
+    /// Calls the webhooks if this resulted in a new RRDP serial.
+    fn update_rrdp(&self) -> KrillResult<Arc<RepositoryContent>> {
+        let serial = self.content.rrdp_serial()?;
+        let content = self.content.update_rrdp(self.config().rrdp_updates_config)?;
+        if content.rrdp_serial() != serial {
+            self.notify_rrdp_update();
+        }
+        Ok(content)
+    }
+
+    /// Posts the event for the current RRDP serial to the webhooks and
+    /// connected clients, if any.
+    fn notify_rrdp_update(&self) {
+        if self.notifier.is_enabled(RepositoryEventType::RrdpUpdate) || self.events.has_subscribers() {
+            match self.content.rrdp_update_event() {
+                Ok(event) => {
+                    self.events.publish(event.clone());
+                    self.notifier.notify(event);
+                }
+                Err(e) => warn!("Could not determine RRDP update event: {}", e),
+            }
+        }
+    }
+
     /// Purge URI(s) from the server.
     pub fn delete_matching_files(&self, criteria: RepoFileDeleteCriteria) -> KrillResult<()> {
         // update RRDP first so we apply any staged deltas.
-        self.content.update_rrdp(self.config.rrdp_updates_config)?;
+        self.update_rrdp()?;
 
         // delete matching files using the updated snapshot and stage a delta if needed.
         self.content.delete_matching_files(criteria.into())?;
 
         // update RRDP again to make the delta effective immediately.
-        let content = self.content.update_rrdp(self.config.rrdp_updates_config)?;
+        let content = self.update_rrdp()?;
 
         // Write the updated repository - NOTE: we no longer lock it.
-        content.write_repository(self.config.rrdp_updates_config)?;
+        content.write_repository(self.config().rrdp_updates_config)?;
+        self.schedule_s3_sync();
 
         Ok(())
     }
 
+    /// Adds an entry with the current content to the repository archive.
+    pub async fn archive_repository(&self) -> KrillResult<RepositoryArchiveEntry> {
+        let archiver = self.archiver.as_ref().ok_or(Error::RepositoryArchiveNotConfigured)?;
+        let (session, serial, snapshot_xml) = self.content.rrdp_snapshot_xml()?;
+        archiver.archive(session, serial, &snapshot_xml).await
+    }
+
+    /// Adds an entry to the repository archive if it is configured, and if
+    /// the repository is initialized.
+    ///
+    /// Returns the time when this should be done again, or `None` if the
+    /// archive is not configured.
+    pub async fn archive_repository_if_configured(&self) -> KrillResult<Option<Time>> {
+        let archiver = match self.archiver.as_ref() {
+            None => return Ok(None),
+            Some(archiver) => archiver,
+        };
+
+        if self.initialized()? {
+            self.archive_repository().await?;
+        }
+
+        Ok(Some(Time::now() + archiver.interval()))
+    }
+
+    /// Returns when the next entry should be added to the repository archive,
+    /// or `None` if the archive is not configured.
+    pub fn archive_repository_next_due(&self) -> KrillResult<Option<Time>> {
+        self.archiver.as_ref().map(|archiver| archiver.next_due()).transpose()
+    }
+
+    /// Lists the entries in the repository archive.
+    pub fn archive_list(&self) -> KrillResult<RepositoryArchiveList> {
+        self.archiver
+            .as_ref()
+            .ok_or(Error::RepositoryArchiveNotConfigured)?
+            .list()
+    }
+
+    /// Synchronises the replicated repository with the primary.
+    pub async fn replicate_repository(&self) -> KrillResult<RepositoryReplicaStatus> {
+        let replica = self.replica.as_ref().ok_or(Error::RepositoryReplicaNotConfigured)?;
+
+        // A replica writes the same files as a primary would.
+        if self.initialized()? {
+            return Err(Error::custom(
+                "Cannot replicate a primary, because this repository is initialized as a primary itself",
+            ));
+        }
+
+        let status = replica.sync().await?;
+        self.schedule_s3_sync();
+
+        Ok(status)
+    }
+
+    /// Synchronises the replicated repository with the primary, if the
+    /// replica is configured.
+    ///
+    /// Returns the time when this should be done again, or `None` if the
+    /// replica is not configured.
+    pub async fn replicate_repository_if_configured(&self) -> KrillResult<Option<Time>> {
+        let replica = match self.replica.as_ref() {
+            None => return Ok(None),
+            Some(replica) => replica,
+        };
+
+        self.replicate_repository().await?;
+
+        Ok(Some(Time::now() + replica.interval()))
+    }
+
+    /// Uploads the repository files to S3, if this is configured and there
+    /// are any files. This works for a primary as well as for a replica.
+    pub async fn sync_s3(&self) -> KrillResult<()> {
+        let s3_store = match self.s3_store.as_ref() {
+            None => return Ok(()),
+            Some(s3_store) => s3_store,
+        };
+
+        let notification_uri = match self.replica.as_ref() {
+            Some(replica) => replica.notification_uri().clone(),
+            None if self.initialized()? => self.content.notification_uri()?,
+            None => return Ok(()),
+        };
+
+        s3_store.sync(&notification_uri).await
+    }
+
+    /// Schedules uploading the repository files to S3, if configured.
+    fn schedule_s3_sync(&self) {
+        if self.s3_store.is_some() {
+            self.tasks.repository_s3_sync(Time::now().into());
+        }
+    }
+
+    /// Returns the status of the replicated repository.
+    pub fn replica_status(&self) -> KrillResult<RepositoryReplicaStatus> {
+        self.replica
+            .as_ref()
+            .map(|replica| replica.status())
+            .ok_or(Error::RepositoryReplicaNotConfigured)
+    }
+
     pub fn repo_stats(&self) -> KrillResult<RepoStats> {
         self.content.stats()
     }
 
+    pub fn rrdp_stats(&self) -> KrillResult<RrdpStats> {
+        self.content.rrdp_stats()
+    }
+
     /// Returns a list reply for a known publisher in a repository.
     pub fn list(&self, publisher: &PublisherHandle) -> KrillResult<ListReply> {
         self.content.list_reply(publisher)
@@ -238,16 +718,117 @@ impl RepositoryManager {
     pub fn get_publisher_details(&self, name: &PublisherHandle) -> KrillResult<PublisherDetails> {
         let publisher = self.access.get_publisher(name)?;
         let id_cert = publisher.id_cert().clone();
+        let staged_id_cert = publisher.staged_id_cert().cloned();
         let base_uri = publisher.base_uri().clone();
 
         let current = self.content.current_objects(name)?.try_into_publish_elements()?;
 
-        Ok(PublisherDetails::new(name, id_cert, base_uri, current))
+        Ok(PublisherDetails::new(name, id_cert, base_uri, current).with_staged_id_cert(staged_id_cert))
+    }
+
+    /// Returns the quota which applies to the publisher, and its current usage.
+    pub fn publisher_quota(&self, name: &PublisherHandle) -> KrillResult<PublisherQuotaInfo> {
+        let publisher = self.access.get_publisher(name)?;
+        let custom = publisher.quota();
+        let quota = custom.unwrap_or(self.config().publisher_quota);
+
+        let current = self.content.current_objects(name)?;
+
+        Ok(PublisherQuotaInfo::new(
+            name.clone(),
+            quota,
+            custom.is_some(),
+            current.len(),
+            current.size_approx(),
+            self.activity.deltas_last_hour(name),
+        ))
+    }
+
+    /// Returns the content stats and publication activity for the publisher.
+    pub fn publisher_stats(&self, name: &PublisherHandle) -> KrillResult<PublisherStatsInfo> {
+        self.access.get_publisher(name)?;
+        let stats = self.content.publisher_stats(name)?;
+
+        Ok(PublisherStatsInfo::new(
+            name.clone(),
+            stats.objects(),
+            stats.size(),
+            stats.last_changed_serial(),
+            self.activity.activity(name),
+        ))
+    }
+
+    /// Returns the content stats and publication activity for all publishers.
+    pub fn publishers_stats(&self) -> KrillResult<PublisherStatsList> {
+        let repo_stats = self.content.stats()?;
+
+        let mut publishers: Vec<_> = repo_stats
+            .get_publishers()
+            .iter()
+            .map(|(name, stats)| {
+                PublisherStatsInfo::new(
+                    name.clone(),
+                    stats.objects(),
+                    stats.size(),
+                    stats.last_changed_serial(),
+                    self.activity.activity(name),
+                )
+            })
+            .collect();
+        publishers.sort_by(|a, b| a.publisher().as_str().cmp(b.publisher().as_str()));
+
+        Ok(PublisherStatsList::new(publishers))
+    }
+
+    /// Sets the quota for the publisher, or reverts to the default quota
+    /// from the server configuration if `None` is given.
+    pub fn update_publisher_quota(
+        &self,
+        name: PublisherHandle,
+        quota: Option<PublisherQuota>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.access.update_publisher_quota(name, quota, actor)
+    }
+
+    /// Returns the validation which applies to content from the publisher.
+    pub fn publisher_validation(&self, name: &PublisherHandle) -> KrillResult<PublisherValidationInfo> {
+        let custom = self.access.get_publisher(name)?.validation();
+        let validation = custom.unwrap_or(self.config().publisher_validation);
+
+        Ok(PublisherValidationInfo::new(name.clone(), validation, custom.is_some()))
+    }
+
+    /// Sets the validation for the publisher, or reverts to the default
+    /// validation from the server configuration if `None` is given.
+    pub fn update_publisher_validation(
+        &self,
+        name: PublisherHandle,
+        validation: Option<PublisherValidation>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.access.update_publisher_validation(name, validation, actor)
+    }
+
+    /// Stages a new ID certificate for the publisher, taken from the given
+    /// RFC 8183 Publisher Request, or cancels the rollover if `None` is given.
+    pub fn stage_publisher_id_cert(
+        &self,
+        name: PublisherHandle,
+        req: Option<idexchange::PublisherRequest>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.access.stage_publisher_id_cert(name, req, actor)
+    }
+
+    /// Replaces the current ID certificate of the publisher with the staged one.
+    pub fn activate_publisher_id_cert(&self, name: PublisherHandle, actor: &Actor) -> KrillResult<()> {
+        self.access.activate_publisher_id_cert(name, actor)
     }
 
     /// Returns the RFC8183 Repository Response for the publisher.
     pub fn repository_response(&self, publisher: &PublisherHandle) -> KrillResult<idexchange::RepositoryResponse> {
-        let rfc8181_uri = self.config.rfc8181_uri(publisher);
+        let rfc8181_uri = self.config().rfc8181_uri(publisher);
         self.access.repository_response(rfc8181_uri, publisher)
     }
 
@@ -262,7 +843,8 @@ impl RepositoryManager {
     /// Removes a publisher and all of its content.
     pub fn remove_publisher(&self, name: PublisherHandle, actor: &Actor) -> KrillResult<()> {
         self.content.remove_publisher(name.clone())?;
-        self.access.remove_publisher(name, actor)?;
+        self.access.remove_publisher(name.clone(), actor)?;
+        self.activity.remove(&name);
 
         self.tasks.update_rrdp_if_needed(Time::now().into());
 
@@ -275,7 +857,7 @@ impl RepositoryManager {
 impl RepositoryManager {
     /// Update the RRDP files and rsync content on disk.
     pub fn write_repository(&self) -> KrillResult<()> {
-        self.content.write_repository(self.config.rrdp_updates_config)
+        self.content.write_repository(self.config().rrdp_updates_config)
     }
 }
 
@@ -314,8 +896,7 @@ mod tests {
         },
         constants::*,
         daemon::config::{SignerConfig, SignerType},
-        pubd::Publisher,
-        pubd::RrdpServer,
+        pubd::{Publisher, RepositoryWebhookConfig, RrdpServer},
         test::{self, https, init_config, rsync},
     };
 
@@ -346,9 +927,14 @@ mod tests {
     }
 
     fn make_server(work_dir: &Path) -> RepositoryManager {
+        make_server_with_config(work_dir, |_| {})
+    }
+
+    fn make_server_with_config(work_dir: &Path, update_config: impl FnOnce(&mut Config)) -> RepositoryManager {
         enable_test_mode();
         let mut config = Config::test(work_dir, true, false, false, false);
         init_config(&mut config);
+        update_config(&mut config);
 
         let signer = KrillSignerBuilder::new(work_dir, Duration::from_secs(1), &config.signers)
             .with_default_signer(config.default_signer())
@@ -359,7 +945,8 @@ mod tests {
         let signer = Arc::new(signer);
         let config = Arc::new(config);
         let mq = Arc::new(TaskQueue::default());
-        let repository_manager = RepositoryManager::build(config, mq, signer).unwrap();
+        let events = Arc::new(EventStream::default());
+        let repository_manager = RepositoryManager::build(config, mq, events, signer).unwrap();
 
         let rsync_base = rsync("rsync://localhost/repo/");
         let rrdp_base = https("https://localhost/repo/rrdp/");
@@ -432,6 +1019,428 @@ mod tests {
         let _ = fs::remove_dir_all(d);
     }
 
+    #[test]
+    fn should_roll_publisher_id_cert() {
+        let d = test::tmp_dir();
+        let server = make_server(&d);
+
+        let signer = {
+            let signer_type = SignerType::OpenSsl(OpenSslSignerConfig::default());
+            let signer_config = SignerConfig::new("Alice".to_string(), signer_type);
+            KrillSignerBuilder::new(&d, Duration::from_secs(1), &[signer_config])
+                .build()
+                .unwrap()
+        };
+        let old_id: IdCertInfo = signer.create_self_signed_id_cert().unwrap().into();
+        let new_id: IdCertInfo = signer.create_self_signed_id_cert().unwrap().into();
+
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+        server
+            .create_publisher(make_publisher_req(alice_handle.as_str(), &old_id), &actor)
+            .unwrap();
+
+        let list_query_signed_by = |id: &IdCertInfo| {
+            let key = id.public_key().key_identifier();
+            let cms = signer
+                .create_rfc8181_cms(publication::Message::list_query(), &key)
+                .unwrap();
+            server.rfc8181(alice_handle.clone(), cms.to_bytes())
+        };
+
+        assert!(list_query_signed_by(&old_id).is_ok());
+        assert!(list_query_signed_by(&new_id).is_err());
+
+        match server.activate_publisher_id_cert(alice_handle.clone(), &actor) {
+            Err(Error::PublisherNoStagedIdCert(name)) => assert_eq!(name, alice_handle),
+            _ => panic!("Expected error"),
+        }
+
+        // Both certificates are accepted while the new one is staged
+        let new_req = make_publisher_req(alice_handle.as_str(), &new_id);
+        server
+            .stage_publisher_id_cert(alice_handle.clone(), Some(new_req), &actor)
+            .unwrap();
+        let details = server.get_publisher_details(&alice_handle).unwrap();
+        assert_eq!(details.id_cert(), &old_id);
+        assert_eq!(details.staged_id_cert(), Some(&new_id));
+
+        assert!(list_query_signed_by(&old_id).is_ok());
+        assert!(list_query_signed_by(&new_id).is_ok());
+
+        // Only the new certificate is accepted once activated
+        server.activate_publisher_id_cert(alice_handle.clone(), &actor).unwrap();
+        let details = server.get_publisher_details(&alice_handle).unwrap();
+        assert_eq!(details.id_cert(), &new_id);
+        assert!(details.staged_id_cert().is_none());
+
+        assert!(list_query_signed_by(&old_id).is_err());
+        assert!(list_query_signed_by(&new_id).is_ok());
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn should_enforce_publisher_quota() {
+        let d = test::tmp_dir();
+        let server = make_server(&d);
+
+        let alice = publisher_alice(&d);
+
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+        let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+        server.create_publisher(publisher_req, &actor).unwrap();
+
+        let quota = PublisherQuota::new(Some(2), None, Some(2));
+        server
+            .update_publisher_quota(alice_handle.clone(), Some(quota), &actor)
+            .unwrap();
+
+        let publish = |name: &str| {
+            let file = CurrentFile::new(
+                test::rsync(&format!("rsync://localhost/repo/alice/{}", name)),
+                &Bytes::from("example content"),
+            );
+            let mut delta = PublishDelta::empty();
+            delta.add_publish(file.as_publish());
+            server.publish(&alice_handle, delta)
+        };
+
+        // Two objects are fine, but a third exceeds the object limit
+        publish("file1.txt").unwrap();
+        publish("file2.txt").unwrap();
+        match publish("file3.txt") {
+            Err(Error::PublisherQuotaExceeded(name, _)) => assert_eq!(name, alice_handle),
+            _ => panic!("Expected quota exceeded error"),
+        }
+
+        let info = server.publisher_quota(&alice_handle).unwrap();
+        assert!(info.custom());
+        assert_eq!(info.quota(), quota);
+        assert_eq!(info.objects(), 2);
+        assert_eq!(info.deltas_last_hour(), 2);
+
+        // Without an object limit the delta rate limit still applies
+        let quota = PublisherQuota::new(None, None, Some(2));
+        server
+            .update_publisher_quota(alice_handle.clone(), Some(quota), &actor)
+            .unwrap();
+        match publish("file3.txt") {
+            Err(Error::PublisherQuotaExceeded(name, _)) => assert_eq!(name, alice_handle),
+            _ => panic!("Expected quota exceeded error"),
+        }
+
+        // The recent deltas are remembered when the server restarts
+        let activity = PublisherActivityLogs::new(&d).unwrap();
+        assert_eq!(activity.deltas_last_hour(&alice_handle), 2);
+
+        // The server default quota has no limits
+        server
+            .update_publisher_quota(alice_handle.clone(), None, &actor)
+            .unwrap();
+        publish("file3.txt").unwrap();
+
+        let info = server.publisher_quota(&alice_handle).unwrap();
+        assert!(!info.custom());
+        assert_eq!(info.quota(), PublisherQuota::default());
+        assert_eq!(info.objects(), 3);
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn should_validate_publisher_content() {
+        let d = test::tmp_dir();
+        let server = make_server(&d);
+
+        let alice = publisher_alice(&d);
+
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+        let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+        server.create_publisher(publisher_req, &actor).unwrap();
+
+        let delta = || {
+            let file = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/file.roa"),
+                &Bytes::from("not a roa"),
+            );
+            let mut delta = PublishDelta::empty();
+            delta.add_publish(file.as_publish());
+            delta
+        };
+
+        server
+            .update_publisher_validation(alice_handle.clone(), Some(PublisherValidation::Reject), &actor)
+            .unwrap();
+        let info = server.publisher_validation(&alice_handle).unwrap();
+        assert!(info.custom());
+        assert_eq!(info.validation(), PublisherValidation::Reject);
+
+        match server.publish(&alice_handle, delta()) {
+            Err(Error::PublisherInvalidContent(name, _)) => assert_eq!(name, alice_handle),
+            _ => panic!("Expected invalid content error"),
+        }
+
+        server
+            .update_publisher_validation(alice_handle.clone(), Some(PublisherValidation::Warn), &actor)
+            .unwrap();
+        server.publish(&alice_handle, delta()).unwrap();
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn should_switch_rsync_content() {
+        let d = test::tmp_dir();
+        let server = make_server(&d);
+        let rsync_dir = d.join("repo/rsync");
+        let current = rsync_dir.join(REPOSITORY_RSYNC_CURRENT);
+
+        let alice = publisher_alice(&d);
+
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+        let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+        server.create_publisher(publisher_req, &actor).unwrap();
+
+        let publish = |name: &str| {
+            let file = CurrentFile::new(
+                test::rsync(&format!("rsync://localhost/repo/alice/{}", name)),
+                &Bytes::from(name.to_string()),
+            );
+            let mut delta = PublishDelta::empty();
+            delta.add_publish(file.as_publish());
+            server.publish(&alice_handle, delta).unwrap();
+            server.update_rrdp_if_needed().unwrap();
+            server.write_repository().unwrap();
+
+            let stats = server.repo_stats().unwrap();
+            format!("{}-{}", stats.session(), stats.serial())
+        };
+
+        let first = publish("file1.txt");
+        assert_eq!(fs::read_link(&current).unwrap(), PathBuf::from(&first));
+        assert!(current.join("alice/file1.txt").is_file());
+
+        let second = publish("file2.txt");
+        assert_eq!(fs::read_link(&current).unwrap(), PathBuf::from(&second));
+        assert!(current.join("alice/file1.txt").is_file());
+        assert!(current.join("alice/file2.txt").is_file());
+
+        // The previous content is kept for rsync clients which are still
+        // fetching it, older content is removed.
+        assert!(rsync_dir.join(&first).is_dir());
+        let third = publish("file3.txt");
+        assert_eq!(fs::read_link(&current).unwrap(), PathBuf::from(&third));
+        assert!(rsync_dir.join(&second).is_dir());
+        assert!(!rsync_dir.join(&first).exists());
+
+        let conf = server.content.rsyncd_conf().unwrap();
+        assert!(conf.contains("[repo]"));
+        assert!(conf.contains(&format!("path = {}", current.to_string_lossy())));
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn should_manage_rrdp_deltas() {
+        let d = test::tmp_dir();
+        let server = make_server_with_config(&d, |config| {
+            config.rrdp_updates_config.rrdp_delta_files_max_size_percentage = 50;
+            config.rrdp_updates_config.rrdp_session_reset_interval_hours = Some(1);
+        });
+
+        let alice = publisher_alice(&d);
+
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+        let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+        server.create_publisher(publisher_req, &actor).unwrap();
+
+        let publish = |name: &str, size: usize| {
+            let file = CurrentFile::new(
+                test::rsync(&format!("rsync://localhost/repo/alice/{}", name)),
+                &Bytes::from(vec![0; size]),
+            );
+            let mut delta = PublishDelta::empty();
+            delta.add_publish(file.as_publish());
+            server.publish(&alice_handle, delta).unwrap();
+            server.update_rrdp_if_needed().unwrap();
+            server.write_repository().unwrap();
+            server.rrdp_stats().unwrap()
+        };
+
+        // A delta with all content exceeds half of the snapshot size.
+        let stats = publish("file1.txt", 1000);
+        assert!(stats.deltas().is_empty());
+        assert!(stats.notification_size().is_some());
+        assert!(stats.snapshot_size().is_some());
+
+        let stats = publish("file2.txt", 100);
+        assert_eq!(stats.deltas().len(), 1);
+        assert_eq!(stats.deltas()[0].serial(), stats.serial());
+        assert_eq!(stats.deltas_size(), stats.deltas()[0].size().unwrap());
+
+        // The session was started when the repository was initialized, so
+        // it is not due for a reset yet.
+        let next = server.rrdp_session_reset_if_needed().unwrap().unwrap();
+        assert!(next > Time::now());
+        assert_eq!(server.rrdp_stats().unwrap().session(), stats.session());
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn should_report_publisher_stats() {
+        let d = test::tmp_dir();
+        let server = make_server(&d);
+
+        let alice = publisher_alice(&d);
+
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+        let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+        server.create_publisher(publisher_req, &actor).unwrap();
+
+        let stats = server.publisher_stats(&alice_handle).unwrap();
+        assert_eq!(stats.objects(), 0);
+        assert_eq!(stats.last_changed_serial(), None);
+        assert_eq!(stats.activity(), &PublisherActivity::default());
+
+        let file = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file.txt"),
+            &Bytes::from("example content"),
+        );
+        let mut delta = PublishDelta::empty();
+        delta.add_publish(file.as_publish());
+        server
+            .rfc8181_message(&alice_handle, publication::Query::Delta(delta))
+            .unwrap();
+        server.update_rrdp_if_needed().unwrap();
+
+        let serial = server.rrdp_stats().unwrap().serial();
+        let stats = server.publisher_stats(&alice_handle).unwrap();
+        assert_eq!(stats.objects(), 1);
+        assert_eq!(stats.bytes(), 15);
+        assert_eq!(stats.last_changed_serial(), Some(serial));
+        assert_eq!(stats.activity().deltas_total, 1);
+        assert_eq!(stats.activity().deltas_last_hour, 1);
+        assert!(stats.activity().last_publish.is_some());
+        assert_eq!(stats.activity().errors_total, 0);
+
+        // Publishing the same file again is rejected and counted as an error
+        let mut delta = PublishDelta::empty();
+        delta.add_publish(file.as_publish());
+        assert!(server
+            .rfc8181_message(&alice_handle, publication::Query::Delta(delta))
+            .is_err());
+
+        let stats = server.publisher_stats(&alice_handle).unwrap();
+        assert_eq!(stats.activity().deltas_total, 1);
+        assert_eq!(stats.activity().errors_total, 1);
+        assert!(stats.activity().last_error.is_some());
+
+        // The activity is remembered when the server restarts
+        let activity = PublisherActivityLogs::new(&d).unwrap();
+        assert_eq!(&activity.activity(&alice_handle), stats.activity());
+
+        // All content is part of the first serial after a session reset
+        server.rrdp_session_reset().unwrap();
+        let stats = server.publishers_stats().unwrap();
+        assert_eq!(stats.publishers().len(), 1);
+        assert_eq!(stats.publishers()[0].last_changed_serial(), Some(RRDP_FIRST_SERIAL));
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn should_create_rrdp_update_event() {
+        let d = test::tmp_dir();
+        let server = make_server_with_config(&d, |config| {
+            config.repository_webhooks = vec![RepositoryWebhookConfig {
+                url: "https://localhost/hook".to_string(),
+                events: RepositoryEventType::all(),
+            }];
+        });
+
+        let alice = publisher_alice(&d);
+
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+        let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+        server.create_publisher(publisher_req, &actor).unwrap();
+
+        // Events cannot be posted outside of an async runtime, but
+        // publishing must not fail because of this.
+        let file = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file.txt"),
+            &Bytes::from("example content"),
+        );
+        let mut delta = PublishDelta::empty();
+        delta.add_publish(file.as_publish());
+        server.publish(&alice_handle, delta).unwrap();
+        server.update_rrdp_if_needed().unwrap();
+
+        match server.content.rrdp_update_event().unwrap() {
+            RepositoryEvent::RrdpUpdate { serial, publishers, .. } => {
+                assert_eq!(serial, server.rrdp_stats().unwrap().serial());
+                assert_eq!(publishers, vec![alice_handle]);
+            }
+            _ => panic!("Expected RRDP update event"),
+        }
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[tokio::test]
+    async fn should_post_events_to_webhook() {
+        let d = test::tmp_dir();
+        let (url, received) = test::start_webhook_receiver(hyper::StatusCode::OK);
+        let server = make_server_with_config(&d, |config| {
+            config.repository_webhooks = vec![RepositoryWebhookConfig {
+                url,
+                events: RepositoryEventType::all(),
+            }];
+        });
+
+        let alice = publisher_alice(&d);
+
+        let alice_handle = Handle::from_str("alice").unwrap();
+        let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+        let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+        server.create_publisher(publisher_req, &actor).unwrap();
+
+        let file = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file.txt"),
+            &Bytes::from("example content"),
+        );
+        let mut delta = PublishDelta::empty();
+        delta.add_publish(file.as_publish());
+        server.publish(&alice_handle, delta).unwrap();
+        server.update_rrdp_if_needed().unwrap();
+
+        // The publish event is posted before the RRDP update it ends up in.
+        let payloads = test::wait_for_webhook_payloads(&received, 2).await;
+        assert_eq!(payloads.len(), 2);
+        assert_eq!(payloads[0]["event"], "publish");
+        assert_eq!(payloads[0]["publisher"], "alice");
+        assert_eq!(payloads[0]["published"][0], "rsync://localhost/repo/alice/file.txt");
+        assert_eq!(payloads[1]["event"], "rrdp_update");
+        assert_eq!(payloads[1]["serial"], server.rrdp_stats().unwrap().serial());
+        assert_eq!(payloads[1]["publishers"][0], "alice");
+
+        let _ = fs::remove_dir_all(d);
+    }
+
     #[tokio::test]
     async fn should_publish_files() {
         let d = test::tmp_dir();
@@ -1,14 +1,23 @@
+mod archive;
 mod commands;
 mod events;
 mod manager;
 mod publishers;
+mod replica;
 #[allow(clippy::mutable_key_type)]
 mod repository;
+mod s3;
+mod validation;
+mod webhooks;
 
+pub use self::archive::{RepositoryArchiveConfig, RepositoryArchiveEntry, RepositoryArchiveList, RepositoryArchiver};
 pub use self::commands::{RepoAccessCmd, RepoAccessCmdDet};
 pub use self::events::{
     RepositoryAccessEvent, RepositoryAccessEventDetails, RepositoryAccessIni, RepositoryAccessInitDetails,
 };
 pub use self::manager::RepositoryManager;
 pub use self::publishers::Publisher;
+pub use self::replica::{RepositoryReplica, RepositoryReplicaConfig, RepositoryReplicaStatus};
 pub use self::repository::*;
+pub use self::s3::{RepositoryS3Config, RepositoryS3Store};
+pub use self::webhooks::{RepositoryEvent, RepositoryEventType, RepositoryNotifier, RepositoryWebhookConfig};
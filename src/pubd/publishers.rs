@@ -1,6 +1,6 @@
 use rpki::uri;
 
-use crate::commons::api::IdCertInfo;
+use crate::commons::api::{IdCertInfo, PublisherQuota, PublisherValidation};
 
 //------------ Publisher -----------------------------------------------------
 
@@ -10,8 +10,21 @@ pub struct Publisher {
     /// Used by remote RFC8181 publishers
     id_cert: IdCertInfo,
 
+    /// A new ID certificate for the publisher, which is accepted in addition
+    /// to the current one until it is activated, or the rollover is cancelled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    staged_id_cert: Option<IdCertInfo>,
+
     /// Publication jail for this publisher
     base_uri: uri::Rsync,
+
+    /// Quota for this publisher, if not set the server default applies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quota: Option<PublisherQuota>,
+
+    /// Validation of content for this publisher, if not set the server default applies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    validation: Option<PublisherValidation>,
 }
 
 /// # Accessors
@@ -19,15 +32,49 @@ impl Publisher {
     pub fn id_cert(&self) -> &IdCertInfo {
         &self.id_cert
     }
+    pub fn staged_id_cert(&self) -> Option<&IdCertInfo> {
+        self.staged_id_cert.as_ref()
+    }
     pub fn base_uri(&self) -> &uri::Rsync {
         &self.base_uri
     }
+    pub fn quota(&self) -> Option<PublisherQuota> {
+        self.quota
+    }
+    pub fn validation(&self) -> Option<PublisherValidation> {
+        self.validation
+    }
 }
 
 /// # Life cycle
 ///
 impl Publisher {
     pub fn new(id_cert: IdCertInfo, base_uri: uri::Rsync) -> Self {
-        Publisher { id_cert, base_uri }
+        Publisher {
+            id_cert,
+            staged_id_cert: None,
+            base_uri,
+            quota: None,
+            validation: None,
+        }
+    }
+
+    pub fn set_staged_id_cert(&mut self, id_cert: Option<IdCertInfo>) {
+        self.staged_id_cert = id_cert;
+    }
+
+    /// Replaces the current ID certificate with the staged one, if any.
+    pub fn activate_staged_id_cert(&mut self) {
+        if let Some(id_cert) = self.staged_id_cert.take() {
+            self.id_cert = id_cert;
+        }
+    }
+
+    pub fn set_quota(&mut self, quota: Option<PublisherQuota>) {
+        self.quota = quota;
+    }
+
+    pub fn set_validation(&mut self, validation: Option<PublisherValidation>) {
+        self.validation = validation;
     }
 }
@@ -0,0 +1,441 @@
+//! Replicate the repository of a primary publication server, so that it can
+//! be served from another location, e.g. in another region.
+//!
+//! The replica follows the RRDP notification file of the primary and keeps
+//! byte-identical copies of the notification, snapshot and delta files. The
+//! files are stored under the same paths, relative to the RRDP base URI, as
+//! they are by a Krill primary. So, the replica serves them for the same
+//! URIs, and a replica can take over from a failed primary without relying
+//! parties noticing a change in naming. The objects can optionally be
+//! written for rsync as well.
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use bytes::Bytes;
+use chrono::Duration;
+use rpki::{
+    repository::x509::Time,
+    rrdp::{NotificationFile, Snapshot, UriAndHash},
+    uri,
+};
+
+use crate::{
+    commons::{
+        api::rrdp::RrdpSession,
+        error::{Error, KrillIoError},
+        util::{file, httpclient},
+        KrillResult,
+    },
+    constants::{REPOSITORY_DIR, REPOSITORY_RRDP_DIR},
+    pubd::RsyncdStore,
+};
+
+const NOTIFICATION_FILE: &str = "notification.xml";
+const NOTIFICATION_FILE_NEW: &str = "new-notification.xml";
+
+//------------ RepositoryReplicaConfig ---------------------------------------
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RepositoryReplicaConfig {
+    /// The URI of the RRDP notification file of the primary.
+    pub notification_uri: uri::Https,
+
+    /// The interval between synchronisations with the primary.
+    #[serde(default = "RepositoryReplicaConfig::dflt_interval_seconds")]
+    pub interval_seconds: u32,
+
+    /// Also write the objects for rsync, relative to this base URI.
+    #[serde(default)]
+    pub rsync_base_uri: Option<uri::Rsync>,
+}
+
+impl RepositoryReplicaConfig {
+    fn dflt_interval_seconds() -> u32 {
+        60
+    }
+}
+
+//------------ RepositoryReplicaStatus ---------------------------------------
+
+/// The status of the replica, as shown in the API.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepositoryReplicaStatus {
+    notification_uri: uri::Https,
+
+    /// The RRDP session and serial of the replicated content, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session: Option<RrdpSession>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    serial: Option<u64>,
+
+    /// The last time the replicated content changed, since the server
+    /// was started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_update: Option<Time>,
+
+    /// The last successful synchronisation, since the server was started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_sync: Option<Time>,
+
+    /// The error of the last synchronisation, if it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+}
+
+impl RepositoryReplicaStatus {
+    pub fn notification_uri(&self) -> &uri::Https {
+        &self.notification_uri
+    }
+
+    pub fn session(&self) -> Option<RrdpSession> {
+        self.session
+    }
+
+    pub fn serial(&self) -> Option<u64> {
+        self.serial
+    }
+
+    pub fn last_update(&self) -> Option<Time> {
+        self.last_update
+    }
+
+    pub fn last_sync(&self) -> Option<Time> {
+        self.last_sync
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+}
+
+impl std::fmt::Display for RepositoryReplicaStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let time = |time: Option<Time>| time.map(|t| t.to_rfc3339()).unwrap_or_else(|| "never".to_string());
+
+        writeln!(f, "primary: {}", self.notification_uri)?;
+        match (self.session, self.serial) {
+            (Some(session), Some(serial)) => {
+                writeln!(f, "session: {}", session)?;
+                writeln!(f, "serial: {}", serial)?;
+            }
+            _ => writeln!(f, "content: none")?,
+        }
+        writeln!(f, "last update: {}", time(self.last_update))?;
+        writeln!(f, "last sync: {}", time(self.last_sync))?;
+        if let Some(error) = &self.last_error {
+            writeln!(f, "last error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ RepositoryReplica ---------------------------------------------
+
+/// Keeps a copy of the repository of a primary publication server.
+pub struct RepositoryReplica {
+    config: RepositoryReplicaConfig,
+
+    // The RRDP base URI of the primary, i.e. the notification URI without
+    // the file name. The paths of the RRDP files are relative to this.
+    rrdp_base_uri: String,
+    rrdp_dir: PathBuf,
+    rsync: Option<RsyncdStore>,
+
+    status: Mutex<RepositoryReplicaStatus>,
+
+    // Held while synchronising, so that a sync which is triggered while the
+    // scheduled sync is still running does not write the same files.
+    sync_lock: tokio::sync::Mutex<()>,
+}
+
+impl RepositoryReplica {
+    pub fn new(config: RepositoryReplicaConfig, data_dir: &Path) -> Self {
+        let repo_dir = data_dir.join(REPOSITORY_DIR);
+        let rrdp_dir = repo_dir.join(REPOSITORY_RRDP_DIR);
+
+        let notification_uri = config.notification_uri.as_str();
+        let rrdp_base_uri = notification_uri[..notification_uri.rfind('/').unwrap_or(0) + 1].to_string();
+
+        let rsync = config
+            .rsync_base_uri
+            .clone()
+            .map(|base_uri| RsyncdStore::new(base_uri, &repo_dir));
+
+        // Pick up the replicated content from before a restart, if any.
+        let current = Self::read_notification(&rrdp_dir.join(NOTIFICATION_FILE));
+        let status = RepositoryReplicaStatus {
+            notification_uri: config.notification_uri.clone(),
+            session: current.as_ref().map(|n| n.session_id().into()),
+            serial: current.as_ref().map(|n| n.serial()),
+            last_update: None,
+            last_sync: None,
+            last_error: None,
+        };
+
+        RepositoryReplica {
+            config,
+            rrdp_base_uri,
+            rrdp_dir,
+            rsync,
+            status: Mutex::new(status),
+            sync_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn notification_uri(&self) -> &uri::Https {
+        &self.config.notification_uri
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::seconds(self.config.interval_seconds.into())
+    }
+
+    pub fn status(&self) -> RepositoryReplicaStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Synchronises the replica with the primary, and returns the updated
+    /// status. The status includes the error if synchronisation failed.
+    ///
+    /// Only one synchronisation runs at a time, others wait for it to finish.
+    pub async fn sync(&self) -> KrillResult<RepositoryReplicaStatus> {
+        let _lock = self.sync_lock.lock().await;
+        let res = self.sync_files().await;
+
+        let mut status = self.status.lock().unwrap();
+        match res {
+            Ok(updated) => {
+                let now = Time::now();
+                if let Some((session, serial)) = updated {
+                    info!(
+                        "Replicated repository content for RRDP session {} serial {} from {}",
+                        session, serial, self.config.notification_uri
+                    );
+                    status.session = Some(session);
+                    status.serial = Some(serial);
+                    status.last_update = Some(now);
+                }
+                status.last_sync = Some(now);
+                status.last_error = None;
+                Ok(status.clone())
+            }
+            Err(e) => {
+                status.last_error = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// Fetches the notification file of the primary and all files it refers
+    /// to. Returns the new RRDP session and serial, or `None` if the
+    /// notification file did not change.
+    async fn sync_files(&self) -> KrillResult<Option<(RrdpSession, u64)>> {
+        let notification_uri = self.config.notification_uri.as_str();
+        let notification_xml = Self::fetch(notification_uri).await?;
+
+        let notification_path = self.rrdp_dir.join(NOTIFICATION_FILE);
+        let previous_xml = file::read(&notification_path).ok();
+        if previous_xml.as_ref() == Some(&notification_xml) {
+            debug!("Replica is up-to-date with {}", notification_uri);
+            return Ok(None);
+        }
+
+        let notification = NotificationFile::parse(notification_xml.as_ref()).map_err(|e| {
+            Error::Custom(format!(
+                "Invalid RRDP notification file at '{}': {}",
+                notification_uri, e
+            ))
+        })?;
+
+        let mut files: Vec<&UriAndHash> = vec![notification.snapshot()];
+        files.extend(notification.deltas().iter().map(|delta| &**delta));
+
+        for rrdp_file in files.iter() {
+            self.sync_file(rrdp_file).await?;
+        }
+
+        let session = RrdpSession::from(notification.session_id());
+        let serial = notification.serial();
+
+        if let Some(rsync) = &self.rsync {
+            let snapshot_path = self.path_for(notification.snapshot().uri())?;
+            let snapshot = Snapshot::parse(file::read(&snapshot_path)?.as_ref()).map_err(|e| {
+                Error::Custom(format!(
+                    "Invalid RRDP snapshot file at '{}': {}",
+                    notification.snapshot().uri(),
+                    e
+                ))
+            })?;
+
+            rsync.write_with(session, serial, |dir| {
+                for element in snapshot.elements() {
+                    rsync.save_object(dir, element.uri(), element.data())?;
+                }
+                Ok(())
+            })?;
+        }
+
+        // Replace the notification file only after all files it refers to
+        // are in place, so that it never refers to missing files.
+        let new_path = self.rrdp_dir.join(NOTIFICATION_FILE_NEW);
+        file::save(&notification_xml, &new_path)?;
+        fs::rename(&new_path, &notification_path).map_err(|e| {
+            KrillIoError::new(
+                format!(
+                    "Could not rename '{}' to '{}'",
+                    new_path.to_string_lossy(),
+                    notification_path.to_string_lossy()
+                ),
+                e,
+            )
+        })?;
+
+        // Keep the files that the previous notification file referred to, so
+        // that relying parties which just fetched it can still get them.
+        let mut keep: HashSet<PathBuf> = HashSet::new();
+        keep.insert(notification_path);
+        for rrdp_file in files {
+            keep.insert(self.path_for(rrdp_file.uri())?);
+        }
+        if let Some(previous) = previous_xml.and_then(|xml| NotificationFile::parse(xml.as_ref()).ok()) {
+            keep.insert(self.path_for(previous.snapshot().uri())?);
+            for delta in previous.deltas() {
+                keep.insert(self.path_for(delta.uri())?);
+            }
+        }
+        Self::remove_unreferenced(&self.rrdp_dir, &keep)?;
+
+        Ok(Some((session, serial)))
+    }
+
+    /// Fetches the RRDP file, unless the local copy is up-to-date.
+    async fn sync_file(&self, rrdp_file: &UriAndHash) -> KrillResult<()> {
+        let path = self.path_for(rrdp_file.uri())?;
+
+        if let Ok(local) = file::read(&path) {
+            if rrdp_file.hash().matches(local.as_ref()) {
+                return Ok(());
+            }
+        }
+
+        let uri = rrdp_file.uri().as_str();
+        let content = Self::fetch(uri).await?;
+        if !rrdp_file.hash().matches(content.as_ref()) {
+            return Err(Error::Custom(format!(
+                "RRDP file at '{}' does not match the hash in the notification file",
+                uri
+            )));
+        }
+
+        file::save(&content, &path)?;
+        Ok(())
+    }
+
+    async fn fetch(uri: &str) -> KrillResult<Bytes> {
+        httpclient::get_bytes(uri).await.map_err(Error::HttpClientError)
+    }
+
+    /// Returns the local path for an RRDP file of the primary. This is the
+    /// path of the URI relative to the RRDP base URI.
+    fn path_for(&self, uri: &uri::Https) -> KrillResult<PathBuf> {
+        uri.as_str()
+            .strip_prefix(&self.rrdp_base_uri)
+            .filter(|rel| !rel.is_empty() && !rel.split('/').any(|part| part.is_empty() || part == ".."))
+            .map(|rel| self.rrdp_dir.join(rel))
+            .ok_or_else(|| {
+                Error::Custom(format!(
+                    "RRDP file '{}' is not below the base URI '{}'",
+                    uri, self.rrdp_base_uri
+                ))
+            })
+    }
+
+    fn read_notification(path: &Path) -> Option<NotificationFile> {
+        file::read(path)
+            .ok()
+            .and_then(|xml| NotificationFile::parse(xml.as_ref()).ok())
+    }
+
+    /// Removes all files in the directory and its sub-directories, except
+    /// for the given files. Directories which become empty are removed too.
+    fn remove_unreferenced(dir: &Path, keep: &HashSet<PathBuf>) -> KrillResult<()> {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| KrillIoError::new(format!("Could not read dir '{}'", dir.to_string_lossy()), e))?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                Self::remove_unreferenced(&path, keep)?;
+                // Only succeeds if the directory is empty now.
+                let _ = fs::remove_dir(&path);
+            } else if !keep.contains(&path) {
+                fs::remove_file(&path)
+                    .map_err(|e| KrillIoError::new(format!("Could not remove file '{}'", path.to_string_lossy()), e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use crate::test;
+
+    fn replica(d: &Path) -> RepositoryReplica {
+        let config = RepositoryReplicaConfig {
+            notification_uri: uri::Https::from_str("https://primary.example.com/rrdp/notification.xml").unwrap(),
+            interval_seconds: 60,
+            rsync_base_uri: None,
+        };
+        RepositoryReplica::new(config, d)
+    }
+
+    #[test]
+    fn path_for_rrdp_files() {
+        let d = test::tmp_dir();
+        let replica = replica(&d);
+        let rrdp_dir = d.join(REPOSITORY_DIR).join(REPOSITORY_RRDP_DIR);
+
+        let path = |uri: &str| replica.path_for(&uri::Https::from_str(uri).unwrap());
+
+        assert_eq!(
+            path("https://primary.example.com/rrdp/session/1/random/snapshot.xml").unwrap(),
+            rrdp_dir.join("session/1/random/snapshot.xml")
+        );
+        assert!(path("https://other.example.com/rrdp/session/1/random/snapshot.xml").is_err());
+        assert!(path("https://primary.example.com/rrdp/../snapshot.xml").is_err());
+        assert!(path("https://primary.example.com/rrdp/").is_err());
+
+        let _ = fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn remove_unreferenced_files() {
+        let d = test::tmp_dir();
+        let keep_file = d.join("session/2/random/snapshot.xml");
+        let old_file = d.join("session/1/random/snapshot.xml");
+        file::save(b"<snapshot/>", &keep_file).unwrap();
+        file::save(b"<snapshot/>", &old_file).unwrap();
+
+        let mut keep = HashSet::new();
+        keep.insert(keep_file.clone());
+        RepositoryReplica::remove_unreferenced(&d, &keep).unwrap();
+
+        assert!(keep_file.is_file());
+        assert!(!old_file.exists());
+        assert!(!d.join("session/1").exists());
+
+        let _ = fs::remove_dir_all(d);
+    }
+}
@@ -32,24 +32,29 @@ use crate::{
             },
             IdCertInfo,
         },
-        api::{PublicationServerUris, StorableRepositoryCommand},
+        api::{
+            AggregateSnapshot, PublicationServerUris, PublisherQuota, PublisherValidation, StorableRepositoryCommand,
+        },
         crypto::KrillSigner,
         error::{Error, KrillIoError},
-        eventsourcing::{Aggregate, AggregateStore, WalChange, WalCommand, WalSet, WalStore, WalSupport},
-        util::file,
+        eventsourcing::{
+            Aggregate, AggregateStore, KeyValueEntry, KeyValueError, WalChange, WalCommand, WalSet, WalStore,
+            WalSupport,
+        },
+        util::file::{self, Compression},
         KrillResult,
     },
     constants::{
         PUBSERVER_CONTENT_DIR, PUBSERVER_DFLT, PUBSERVER_DIR, REPOSITORY_DIR, REPOSITORY_RRDP_ARCHIVE_DIR,
-        REPOSITORY_RRDP_DIR, REPOSITORY_RSYNC_DIR, RRDP_FIRST_SERIAL,
+        REPOSITORY_RRDP_DIR, REPOSITORY_RSYNC_CURRENT, REPOSITORY_RSYNC_DIR, RRDP_FIRST_SERIAL,
     },
     daemon::{
         config::{Config, RrdpUpdatesConfig},
         ta::TA_NAME,
     },
     pubd::{
-        publishers::Publisher, RepoAccessCmd, RepoAccessCmdDet, RepositoryAccessEvent, RepositoryAccessEventDetails,
-        RepositoryAccessIni, RepositoryAccessInitDetails,
+        publishers::Publisher, validation, RepoAccessCmd, RepoAccessCmdDet, RepositoryAccessEvent,
+        RepositoryAccessEventDetails, RepositoryAccessIni, RepositoryAccessInitDetails, RepositoryEvent,
     },
 };
 
@@ -65,9 +70,8 @@ pub struct RepositoryContentProxy {
 }
 
 impl RepositoryContentProxy {
-    pub fn disk(config: &Config) -> KrillResult<Self> {
-        let work_dir = &config.data_dir;
-        let store = Arc::new(WalStore::disk(work_dir, PUBSERVER_CONTENT_DIR)?);
+    pub fn create(config: &Config) -> KrillResult<Self> {
+        let store = Arc::new(WalStore::create(&config.storage(), PUBSERVER_CONTENT_DIR)?);
         store.warm()?;
 
         let default_handle = MyHandle::new("0".into());
@@ -125,11 +129,37 @@ impl RepositoryContentProxy {
             .map_err(Error::WalStoreError)
     }
 
+    /// Export the stored content, e.g. for a backup.
+    pub fn export(&self, f: &mut dyn FnMut(KeyValueEntry) -> Result<(), KeyValueError>) -> KrillResult<()> {
+        self.store.export(f).map_err(Error::WalStoreError)
+    }
+
     /// Return the repository content stats
     pub fn stats(&self) -> KrillResult<RepoStats> {
         self.get_default_content().map(|content| content.stats())
     }
 
+    /// Return the current RRDP session, serial and snapshot XML
+    pub fn rrdp_snapshot_xml(&self) -> KrillResult<(RrdpSession, u64, Vec<u8>)> {
+        let content = self.get_default_content()?;
+        let rrdp = &content.rrdp;
+        Ok((
+            rrdp.session,
+            rrdp.serial,
+            rrdp.snapshot().xml(rrdp.session, rrdp.serial),
+        ))
+    }
+
+    /// Return the RRDP stats
+    pub fn rrdp_stats(&self) -> KrillResult<RrdpStats> {
+        self.get_default_content().map(|content| content.rrdp.stats())
+    }
+
+    /// Return the rsyncd module configuration for the repository
+    pub fn rsyncd_conf(&self) -> KrillResult<String> {
+        self.get_default_content()?.rsyncd_conf()
+    }
+
     /// Add a publisher with an empty set of published objects.
     ///
     /// Replaces an existing publisher if it existed.
@@ -154,12 +184,28 @@ impl RepositoryContentProxy {
     /// Publish an update for a publisher.
     ///
     /// Assumes that the RFC 8181 CMS has been verified, but will check that all objects
-    /// are within the publisher's uri space (jail).
-    pub fn publish(&self, publisher: PublisherHandle, delta: PublishDelta, jail: &uri::Rsync) -> KrillResult<()> {
+    /// are within the publisher's uri space (jail), and that the resulting content does
+    /// not exceed the publisher's quota. Objects are checked further depending on the
+    /// validation for the publisher.
+    pub fn publish(
+        &self,
+        publisher: PublisherHandle,
+        delta: PublishDelta,
+        jail: &uri::Rsync,
+        quota: PublisherQuota,
+        validation: PublisherValidation,
+    ) -> KrillResult<()> {
         debug!("Publish delta for {}", publisher);
         let delta = DeltaElements::from(delta);
 
-        let command = RepositoryContentCommand::publish(self.default_handle.clone(), publisher, jail.clone(), delta);
+        let command = RepositoryContentCommand::publish(
+            self.default_handle.clone(),
+            publisher,
+            jail.clone(),
+            quota,
+            validation,
+            delta,
+        );
         self.store.send_command(command)?;
 
         Ok(())
@@ -212,6 +258,28 @@ impl RepositoryContentProxy {
         self.get_default_content()
             .map(|content| content.objects_for_publisher(name).into_owned())
     }
+
+    /// Returns the current RRDP serial.
+    pub fn rrdp_serial(&self) -> KrillResult<u64> {
+        self.get_default_content().map(|content| content.rrdp_serial())
+    }
+
+    /// Returns the URI of the RRDP notification file.
+    pub fn notification_uri(&self) -> KrillResult<uri::Https> {
+        self.get_default_content()
+            .map(|content| content.rrdp.notification_uri())
+    }
+
+    /// Returns the event for the current RRDP serial, as posted to webhooks.
+    pub fn rrdp_update_event(&self) -> KrillResult<RepositoryEvent> {
+        self.get_default_content().map(|content| content.rrdp_update_event())
+    }
+
+    /// Returns the content stats for a publisher.
+    pub fn publisher_stats(&self, name: &PublisherHandle) -> KrillResult<PublisherStats> {
+        self.get_default_content()
+            .map(|content| content.stats_for_publisher(name))
+    }
 }
 
 //------------ RepositoryContentCommand ------------------------------------
@@ -237,6 +305,8 @@ pub enum RepositoryContentCommand {
         handle: MyHandle,
         publisher: PublisherHandle,
         jail: uri::Rsync,
+        quota: PublisherQuota,
+        validation: PublisherValidation,
         delta: DeltaElements,
     },
     CreateRrdpDelta {
@@ -262,11 +332,20 @@ impl RepositoryContentCommand {
         RepositoryContentCommand::DeleteMatchingFiles { handle, uri }
     }
 
-    pub fn publish(handle: MyHandle, publisher: PublisherHandle, jail: uri::Rsync, delta: DeltaElements) -> Self {
+    pub fn publish(
+        handle: MyHandle,
+        publisher: PublisherHandle,
+        jail: uri::Rsync,
+        quota: PublisherQuota,
+        validation: PublisherValidation,
+        delta: DeltaElements,
+    ) -> Self {
         RepositoryContentCommand::Publish {
             handle,
             publisher,
             jail,
+            quota,
+            validation,
             delta,
         }
     }
@@ -352,6 +431,16 @@ pub struct RrdpUpdated {
     pub time: Time,
     pub random: RrdpFileRandom,
     pub deltas_truncate: usize,
+    #[serde(default = "RrdpUpdated::dflt_deltas_max_size_percentage")]
+    pub deltas_max_size_percentage: u32,
+}
+
+impl RrdpUpdated {
+    // Updates from before this was configurable kept deltas up to the
+    // size of the snapshot.
+    fn dflt_deltas_max_size_percentage() -> u32 {
+        100
+    }
 }
 
 impl fmt::Display for RepositoryContentChange {
@@ -437,8 +526,13 @@ impl WalSupport for RepositoryContent {
             RepositoryContentCommand::RemovePublisher { publisher, .. } => self.remove_publisher(publisher),
             RepositoryContentCommand::DeleteMatchingFiles { uri, .. } => self.delete_files(uri),
             RepositoryContentCommand::Publish {
-                publisher, jail, delta, ..
-            } => self.publish(publisher, jail, delta),
+                publisher,
+                jail,
+                quota,
+                validation,
+                delta,
+                ..
+            } => self.publish(publisher, jail, quota, validation, delta),
         }
     }
 }
@@ -500,7 +594,12 @@ impl RepositoryContent {
 
     pub fn write_repository(&self, config: RrdpUpdatesConfig) -> KrillResult<()> {
         self.rrdp.write(config)?;
-        self.rsync.write(self.rrdp.serial, self.rrdp.snapshot())
+        self.rsync
+            .write(self.rrdp.session, self.rrdp.serial, self.rrdp.snapshot())
+    }
+
+    pub fn rsyncd_conf(&self) -> KrillResult<String> {
+        self.rsync.rsyncd_conf()
     }
 
     fn add_publisher(&self, publisher: PublisherHandle) -> KrillResult<Vec<RepositoryContentChange>> {
@@ -563,12 +662,51 @@ impl RepositoryContent {
         &self,
         publisher: PublisherHandle,
         jail: uri::Rsync,
+        quota: PublisherQuota,
+        validation: PublisherValidation,
         delta: DeltaElements,
     ) -> KrillResult<Vec<RepositoryContentChange>> {
         // Verifying the delta first.
         let current_objects = self.objects_for_publisher(&publisher);
         current_objects.verify_delta(&delta, &jail)?;
 
+        // Then check the content after this delta, if needed.
+        if quota.max_objects.is_some() || quota.max_bytes.is_some() || validation != PublisherValidation::Off {
+            let mut updated_objects = current_objects.into_owned();
+            updated_objects.apply_delta(delta.clone());
+
+            if let Some(max_objects) = quota.max_objects {
+                if updated_objects.len() > max_objects {
+                    return Err(Error::PublisherQuotaExceeded(
+                        publisher,
+                        format!("would have {} objects, max is {}", updated_objects.len(), max_objects),
+                    ));
+                }
+            }
+
+            if let Some(max_bytes) = quota.max_bytes {
+                let bytes = updated_objects.size_approx();
+                if bytes > max_bytes {
+                    return Err(Error::PublisherQuotaExceeded(
+                        publisher,
+                        format!("would have {} bytes, max is {}", bytes, max_bytes),
+                    ));
+                }
+            }
+
+            if validation != PublisherValidation::Off {
+                let issues = validation::check_delta(&delta, &updated_objects);
+                if !issues.is_empty() {
+                    if validation == PublisherValidation::Reject {
+                        return Err(Error::PublisherInvalidContent(publisher, issues.join(", ")));
+                    }
+                    for issue in issues {
+                        warn!("Publisher '{}' published invalid content: {}", publisher, issue);
+                    }
+                }
+            }
+        }
+
         Ok(vec![RepositoryContentChange::RrdpDeltaStaged { publisher, delta }])
     }
 
@@ -599,11 +737,42 @@ impl RepositoryContent {
         self.publishers()
             .into_iter()
             .map(|publisher| {
-                let stats = self.objects_for_publisher(&publisher).as_ref().into();
+                let stats = self.stats_for_publisher(&publisher);
                 (publisher, stats)
             })
             .collect()
     }
+
+    /// Returns the current RRDP serial
+    pub fn rrdp_serial(&self) -> u64 {
+        self.rrdp.serial
+    }
+
+    /// Returns the event for the current RRDP serial, with the publishers
+    /// whose content changed in it.
+    pub fn rrdp_update_event(&self) -> RepositoryEvent {
+        let mut publishers: Vec<_> = self
+            .rrdp
+            .publisher_serials
+            .iter()
+            .filter(|(_, serial)| **serial == self.rrdp.serial)
+            .map(|(publisher, _)| publisher.clone())
+            .collect();
+        publishers.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        RepositoryEvent::rrdp_update(
+            self.rrdp.session,
+            self.rrdp.serial,
+            self.rrdp.notification_uri(),
+            publishers,
+        )
+    }
+
+    /// Returns the stats for a publisher
+    pub fn stats_for_publisher(&self, publisher: &PublisherHandle) -> PublisherStats {
+        PublisherStats::from(self.objects_for_publisher(publisher).as_ref())
+            .with_last_changed_serial(self.rrdp.publisher_serial(publisher))
+    }
 }
 
 //------------ RsyncdStore ---------------------------------------------------
@@ -649,85 +818,235 @@ impl RsyncdStore {
 /// # Publishing
 ///
 impl RsyncdStore {
-    /// Write all the files to disk for rsync to a tmp-dir, then switch
-    /// things over in an effort to minimize the chance of people getting
-    /// inconsistent syncs..
-    pub fn write(&self, serial: u64, snapshot: &SnapshotData) -> KrillResult<()> {
+    /// Write all the files to disk for rsync to a new directory for the RRDP
+    /// session and serial, and then switch the 'current' symlink over to it.
+    ///
+    /// The switch is atomic, so rsync clients always see the complete content
+    /// of a single RRDP snapshot. The previous directory is kept so that rsync
+    /// clients which started a transfer before the switch can still finish it.
+    pub fn write(&self, session: RrdpSession, serial: u64, snapshot: &SnapshotData) -> KrillResult<()> {
+        self.write_with(session, serial, |dir| {
+            for current in snapshot.publishers_current_objects().values() {
+                for (uri_key, base64) in current.iter() {
+                    // Note that this check should not be needed here, as the content
+                    // already verified before it was accepted into the snapshot.
+                    let uri = uri::Rsync::try_from(uri_key)?;
+                    self.save_object(dir, &uri, &base64.to_bytes())?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Writes the files for rsync for the RRDP session and serial in the
+    /// same way as [`RsyncdStore::write`], but lets the caller write the
+    /// objects to the given directory, e.g. using [`RsyncdStore::save_object`].
+    pub fn write_with(
+        &self,
+        session: RrdpSession,
+        serial: u64,
+        write_objects: impl FnOnce(&Path) -> KrillResult<()>,
+    ) -> KrillResult<()> {
         let _lock = self
             .lock
             .write()
             .map_err(|_| Error::custom("Could not get write lock for rsync repo"))?;
 
-        let mut new_dir = self.rsync_dir.clone();
-        new_dir.push(&format!("tmp-{}", serial));
-        fs::create_dir_all(&new_dir).map_err(|e| {
+        let name = format!("{}-{}", session, serial);
+        let previous = self.current_target();
+
+        if previous.as_deref() == Some(name.as_str()) {
+            debug!(
+                "Rsync content for session {} and serial {} was already written",
+                session, serial
+            );
+            return Ok(());
+        }
+
+        let tmp_dir = self.rsync_dir.join(format!("tmp-{}", name));
+        let new_dir = self.rsync_dir.join(&name);
+        Self::remove_dir(&tmp_dir)?;
+        Self::remove_dir(&new_dir)?;
+
+        fs::create_dir_all(&tmp_dir).map_err(|e| {
             KrillIoError::new(
                 format!(
                     "Could not create dir(s) '{}' for publishing rsync",
+                    tmp_dir.to_string_lossy()
+                ),
+                e,
+            )
+        })?;
+
+        write_objects(&tmp_dir)?;
+
+        fs::rename(&tmp_dir, &new_dir).map_err(|e| {
+            KrillIoError::new(
+                format!(
+                    "Could not rename new rsync dir from '{}' to '{}' while publishing",
+                    tmp_dir.to_string_lossy(),
                     new_dir.to_string_lossy()
                 ),
                 e,
             )
         })?;
 
-        for current in snapshot.publishers_current_objects().values() {
-            for (uri_key, base64) in current.iter() {
-                // Note that this check should not be needed here, as the content
-                // already verified before it was accepted into the snapshot.
-                let uri = uri::Rsync::try_from(uri_key)?;
-                let rel = uri
-                    .relative_to(&self.base_uri)
-                    .ok_or_else(|| Error::publishing_outside_jail(&uri, &self.base_uri))?;
+        self.switch_current(&name)?;
 
-                let mut path = new_dir.clone();
-                path.push(rel);
+        // Remove all other directories, except for the previous one.
+        let entries = fs::read_dir(&self.rsync_dir).map_err(|e| {
+            KrillIoError::new(
+                format!("Could not read rsync dir '{}'", self.rsync_dir.to_string_lossy()),
+                e,
+            )
+        })?;
+
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
 
-                file::save(&base64.to_bytes(), &path)?;
+            if is_dir
+                && file_name != REPOSITORY_RSYNC_CURRENT
+                && file_name != name
+                && Some(&file_name) != previous.as_ref()
+            {
+                Self::remove_dir(&entry.path())?;
             }
         }
 
-        let mut current_dir = self.rsync_dir.clone();
-        current_dir.push("current");
+        Ok(())
+    }
+
+    /// Saves an object in the given directory, at the path for its URI
+    /// relative to the base URI of this store.
+    pub fn save_object(&self, dir: &Path, uri: &uri::Rsync, content: &[u8]) -> KrillResult<()> {
+        let rel = uri
+            .relative_to(&self.base_uri)
+            .ok_or_else(|| Error::publishing_outside_jail(uri, &self.base_uri))?;
 
-        let mut old_dir = self.rsync_dir.clone();
-        old_dir.push("old");
+        let mut path = dir.to_path_buf();
+        path.push(rel);
 
-        if current_dir.exists() {
-            fs::rename(&current_dir, &old_dir).map_err(|e| {
+        file::save(content, &path)?;
+        Ok(())
+    }
+
+    /// Returns an rsyncd.conf module section which serves the current
+    /// content for the rsync base URI of this repository.
+    ///
+    /// This requires that the rsync base URI does not have a path beyond the
+    /// module, e.g. 'rsync://example.com/repo/'.
+    pub fn rsyncd_conf(&self) -> KrillResult<String> {
+        if !self.base_uri.path().is_empty() {
+            return Err(Error::Custom(format!(
+                "Cannot generate rsyncd configuration for rsync base URI '{}', it has a path beyond the module",
+                self.base_uri
+            )));
+        }
+
+        Ok(format!(
+            "# Generated by Krill for rsync base URI '{}'. Do not edit, changes\n\
+             # will be overwritten.\n\
+             [{}]\n\
+             \x20   path = {}\n\
+             \x20   comment = RPKI repository\n\
+             \x20   read only = yes\n",
+            self.base_uri,
+            self.base_uri.module_name(),
+            self.rsync_dir.join(REPOSITORY_RSYNC_CURRENT).to_string_lossy()
+        ))
+    }
+
+    /// Returns the name of the directory that 'current' links to, if any.
+    fn current_target(&self) -> Option<String> {
+        fs::read_link(self.rsync_dir.join(REPOSITORY_RSYNC_CURRENT))
+            .ok()
+            .map(|target| target.to_string_lossy().to_string())
+    }
+
+    /// Points 'current' to the named directory, by creating a new symlink and
+    /// renaming it over the existing one.
+    #[cfg(unix)]
+    fn switch_current(&self, name: &str) -> KrillResult<()> {
+        let current = self.rsync_dir.join(REPOSITORY_RSYNC_CURRENT);
+        let tmp_link = self.rsync_dir.join(format!("{}.tmp", REPOSITORY_RSYNC_CURRENT));
+
+        // Older versions of Krill used a plain directory for 'current'. It
+        // cannot be replaced by a symlink atomically, so move it out of the
+        // way first. It will be removed after the switch.
+        if current.is_dir() && self.current_target().is_none() {
+            let legacy = self.rsync_dir.join("old");
+            Self::remove_dir(&legacy)?;
+            fs::rename(&current, &legacy).map_err(|e| {
                 KrillIoError::new(
                     format!(
                         "Could not rename current rsync dir from '{}' to '{}' while publishing",
-                        current_dir.to_string_lossy(),
-                        old_dir.to_string_lossy()
+                        current.to_string_lossy(),
+                        legacy.to_string_lossy()
                     ),
                     e,
                 )
             })?;
         }
 
-        fs::rename(&new_dir, &current_dir).map_err(|e| {
+        let _ = fs::remove_file(&tmp_link);
+        std::os::unix::fs::symlink(name, &tmp_link).map_err(|e| {
+            KrillIoError::new(
+                format!(
+                    "Could not create symlink '{}' to '{}'",
+                    tmp_link.to_string_lossy(),
+                    name
+                ),
+                e,
+            )
+        })?;
+
+        fs::rename(&tmp_link, &current).map_err(|e| {
+            KrillIoError::new(
+                format!(
+                    "Could not rename symlink from '{}' to '{}' while publishing",
+                    tmp_link.to_string_lossy(),
+                    current.to_string_lossy()
+                ),
+                e,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Symlinks are not portable, so fall back to renaming the named directory
+    /// to 'current'. Note that this switch is not atomic.
+    #[cfg(not(unix))]
+    fn switch_current(&self, name: &str) -> KrillResult<()> {
+        let current = self.rsync_dir.join(REPOSITORY_RSYNC_CURRENT);
+        let new_dir = self.rsync_dir.join(name);
+        Self::remove_dir(&current)?;
+        fs::rename(&new_dir, &current).map_err(|e| {
             KrillIoError::new(
                 format!(
                     "Could not rename new rsync dir from '{}' to '{}' while publishing",
                     new_dir.to_string_lossy(),
-                    current_dir.to_string_lossy()
+                    current.to_string_lossy()
                 ),
                 e,
             )
         })?;
+        Ok(())
+    }
 
-        if old_dir.exists() {
-            fs::remove_dir_all(&old_dir).map_err(|e| {
+    fn remove_dir(dir: &Path) -> KrillResult<()> {
+        if dir.exists() {
+            fs::remove_dir_all(dir).map_err(|e| {
                 KrillIoError::new(
                     format!(
-                        "Could not remove up old rsync dir '{}' while publishing",
-                        old_dir.to_string_lossy()
+                        "Could not remove rsync dir '{}' while publishing",
+                        dir.to_string_lossy()
                     ),
                     e,
                 )
             })?;
         }
-
         Ok(())
     }
 
@@ -762,11 +1081,22 @@ pub struct RrdpServer {
     serial: u64,
     last_update: Time,
 
+    // The time the current session was started. This is not known for
+    // sessions started before this was tracked.
+    #[serde(default)]
+    session_start: Option<Time>,
+
     snapshot: SnapshotData,
     deltas: VecDeque<DeltaData>,
 
     #[serde(default)]
     staged_elements: HashMap<PublisherHandle, StagedElements>,
+
+    // The RRDP serial at which the content of each publisher last changed.
+    // This is not known for publishers which did not publish since this
+    // was tracked.
+    #[serde(default)]
+    publisher_serials: HashMap<PublisherHandle, u64>,
 }
 
 /// This type is used to combine staged delta elements for publishers.
@@ -975,9 +1305,11 @@ impl RrdpServer {
             session,
             serial,
             last_update,
+            session_start: None,
             snapshot,
             deltas,
             staged_elements,
+            publisher_serials: HashMap::new(),
         }
     }
 
@@ -999,9 +1331,11 @@ impl RrdpServer {
             session,
             serial,
             last_update,
+            session_start: Some(last_update),
             snapshot,
             deltas: VecDeque::new(),
             staged_elements: HashMap::new(),
+            publisher_serials: HashMap::new(),
         }
     }
 
@@ -1014,6 +1348,39 @@ impl RrdpServer {
         &self.snapshot
     }
 
+    /// Returns the RRDP serial at which the content of the publisher last
+    /// changed, if known.
+    fn publisher_serial(&self, publisher: &PublisherHandle) -> Option<u64> {
+        self.publisher_serials.get(publisher).copied()
+    }
+
+    /// Returns the stats for the current RRDP files. The sizes are taken
+    /// from the files on disk, so they are missing if a file was not (yet)
+    /// written.
+    fn stats(&self) -> RrdpStats {
+        let file_size = |path: PathBuf| fs::metadata(path).ok().map(|meta| meta.len());
+
+        let deltas = self
+            .deltas
+            .iter()
+            .map(|delta| RrdpDeltaStats {
+                serial: delta.serial(),
+                time: delta.time(),
+                size: file_size(delta.path(self.session, delta.serial(), &self.rrdp_base_dir)),
+            })
+            .collect();
+
+        RrdpStats {
+            session: self.session,
+            serial: self.serial,
+            session_start: self.session_start,
+            last_update: self.last_update,
+            notification_size: file_size(self.notification_path()),
+            snapshot_size: file_size(self.snapshot.path(self.session, self.serial, &self.rrdp_base_dir)),
+            deltas,
+        }
+    }
+
     pub fn reset_session(&self) -> RrdpSessionReset {
         let last_update = Time::now();
         let session = RrdpSession::random();
@@ -1031,8 +1398,14 @@ impl RrdpServer {
         self.snapshot = reset.snapshot;
         self.session = reset.session;
         self.last_update = reset.last_update;
+        self.session_start = Some(reset.last_update);
         self.serial = RRDP_FIRST_SERIAL;
         self.deltas = VecDeque::new();
+
+        // All content is (re-)published in the snapshot of the new session.
+        for serial in self.publisher_serials.values_mut() {
+            *serial = RRDP_FIRST_SERIAL;
+        }
     }
 
     /// Apply a change that a publisher was added.
@@ -1048,6 +1421,7 @@ impl RrdpServer {
     /// Apply a change that a publisher was removed.
     fn apply_publisher_removed(&mut self, publisher: &PublisherHandle) {
         self.snapshot.apply_publisher_removed(publisher);
+        self.publisher_serials.remove(publisher);
     }
 
     /// Applies staged DeltaElements
@@ -1071,13 +1445,15 @@ impl RrdpServer {
 
             // extend next RRDP delta with elements for this publisher.
             rrdp_delta_elements += delta;
+
+            self.publisher_serials.insert(publisher, self.serial);
         }
 
         let delta = DeltaData::new(self.serial, update.time, update.random, rrdp_delta_elements);
 
         self.deltas.truncate(update.deltas_truncate);
         self.deltas.push_front(delta);
-        self.deltas_truncate_size();
+        self.deltas_truncate_size(update.deltas_max_size_percentage);
 
         self.last_update = update.time;
     }
@@ -1106,11 +1482,13 @@ impl RrdpServer {
         let random = RrdpFileRandom::default();
 
         let deltas_truncate = self.find_deltas_truncate_age(rrdp_updates_config);
+        let deltas_max_size_percentage = rrdp_updates_config.rrdp_delta_files_max_size_percentage;
 
         Ok(RrdpUpdated {
             time,
             random,
             deltas_truncate,
+            deltas_max_size_percentage,
         })
     }
 
@@ -1119,16 +1497,18 @@ impl RrdpServer {
     /// deterministic. Compared to truncating the deltas based
     /// on age and number, because *that* depends on when the
     /// update was generated, and what the RrdpUpdatesConfig
-    /// was set to at the time.
-    fn deltas_truncate_size(&mut self) {
+    /// was set to at the time. The maximum size, as a percentage
+    /// of the snapshot size, is kept in the update for this reason.
+    fn deltas_truncate_size(&mut self, max_size_percentage: u32) {
         let snapshot_size = self.snapshot().size_approx();
+        let max_size = snapshot_size * max_size_percentage as usize / 100;
         let mut total_deltas_size = 0;
         let mut keep = 0;
 
         for delta in &self.deltas {
             total_deltas_size += delta.elements().size_approx();
-            if total_deltas_size > snapshot_size {
-                // never keep more than the size of the snapshot
+            if total_deltas_size > max_size {
+                // never keep more than the configured share of the snapshot
                 break;
             } else {
                 keep += 1;
@@ -1182,6 +1562,13 @@ impl RrdpServer {
         // and which old snapshot and delta files may be removed.
         debug!("Write updated RRDP state to disk - if there are any updates that is.");
 
+        let compressions = rrdp_updates_config.rrdp_files_compressions();
+        for compression in Compression::ALL.iter().filter(|c| !compressions.contains(c)) {
+            // Make sure that we do not keep serving an outdated compressed
+            // notification file, in case compression was disabled.
+            let _best_effort_rm = fs::remove_file(compression.path(&self.notification_path()));
+        }
+
         // Get the current notification file - as long as it's present and can
         // be parsed. If it cannot be parsed we just ignore it. I.e. we will generate
         // all current files in that case.
@@ -1196,16 +1583,20 @@ impl RrdpServer {
             }
         }
 
-        let deltas = self.write_delta_files(old_notification_opt)?;
-        let snapshot = self.write_snapshot_file()?;
+        let deltas = self.write_delta_files(old_notification_opt, &compressions)?;
+        let snapshot = self.write_snapshot_file(&compressions)?;
 
-        self.write_notification_file(snapshot, deltas)?;
+        self.write_notification_file(snapshot, deltas, &compressions)?;
 
         // clean up under the base dir:
         self.cleanup_old_rrdp_files(rrdp_updates_config)
     }
 
-    fn write_delta_files(&self, old_notification_opt: Option<NotificationFile>) -> KrillResult<Vec<DeltaInfo>> {
+    fn write_delta_files(
+        &self,
+        old_notification_opt: Option<NotificationFile>,
+        compressions: &[Compression],
+    ) -> KrillResult<Vec<DeltaInfo>> {
         // Find existing deltas in current file, if present and still applicable:
         // - there is a notification that can be parsed
         // - session did not change
@@ -1269,6 +1660,9 @@ impl RrdpServer {
 
             debug!("Write delta file to: {}", path.to_string_lossy());
             file::save(&xml_bytes, &path)?;
+            for compression in compressions {
+                file::save_compressed(&xml_bytes, &path, *compression)?;
+            }
 
             deltas.push(DeltaInfo::new(delta.serial(), uri, hash));
         }
@@ -1281,7 +1675,7 @@ impl RrdpServer {
         Ok(deltas)
     }
 
-    fn write_snapshot_file(&self) -> KrillResult<SnapshotInfo> {
+    fn write_snapshot_file(&self, compressions: &[Compression]) -> KrillResult<SnapshotInfo> {
         let path = self.snapshot().path(self.session, self.serial, &self.rrdp_base_dir);
         let uri = self.snapshot().uri(self.session, self.serial, &self.rrdp_base_uri);
         let xml_bytes = self.snapshot().xml(self.session, self.serial);
@@ -1289,17 +1683,25 @@ impl RrdpServer {
 
         debug!("Write snapshot file to: {}", path.to_string_lossy());
         file::save(&xml_bytes, &path)?;
+        for compression in compressions {
+            file::save_compressed(&xml_bytes, &path, *compression)?;
+        }
 
         Ok(SnapshotInfo::new(uri, hash))
     }
 
-    fn write_notification_file(&self, snapshot: SnapshotInfo, deltas: Vec<DeltaInfo>) -> KrillResult<()> {
+    fn write_notification_file(
+        &self,
+        snapshot: SnapshotInfo,
+        deltas: Vec<DeltaInfo>,
+        compressions: &[Compression],
+    ) -> KrillResult<()> {
         // Write new notification file to new file first.
         // Prevent that half-overwritten files are served.
         let notification = NotificationFile::new(self.session.into(), self.serial, snapshot, deltas);
         let notification_path_new = self.notification_path_new();
-        let mut notification_file_new = file::create_file_with_path(&notification_path_new)?;
-        notification.write_xml(&mut notification_file_new).map_err(|e| {
+        let mut xml_bytes = vec![];
+        notification.write_xml(&mut xml_bytes).map_err(|e| {
             KrillIoError::new(
                 format!(
                     "could not write new notification file to {}",
@@ -1308,9 +1710,30 @@ impl RrdpServer {
                 e,
             )
         })?;
+        file::save(&xml_bytes, &notification_path_new)?;
 
-        // Rename the new file so it becomes current.
         let notification_path = self.notification_path();
+
+        // Rename the new compressed files first, so that the compressed files
+        // are never older than the uncompressed file.
+        for compression in compressions {
+            file::save_compressed(&xml_bytes, &notification_path_new, *compression)?;
+
+            let compressed_path_new = compression.path(&notification_path_new);
+            let compressed_path = compression.path(&notification_path);
+            fs::rename(&compressed_path_new, &compressed_path).map_err(|e| {
+                KrillIoError::new(
+                    format!(
+                        "Could not rename notification file from '{}' to '{}'",
+                        compressed_path_new.to_string_lossy(),
+                        compressed_path.to_string_lossy()
+                    ),
+                    e,
+                )
+            })?;
+        }
+
+        // Rename the new file so it becomes current.
         fs::rename(&notification_path_new, &notification_path).map_err(|e| {
             KrillIoError::new(
                 format!(
@@ -1325,6 +1748,30 @@ impl RrdpServer {
         Ok(())
     }
 
+    /// Moves the serial dirs of a superseded session to the archive. This is
+    /// done per serial dir, because the archive may already contain serial
+    /// dirs for the session which went out of scope before the session reset.
+    fn archive_session_dir(&self, session_dir: &Path) {
+        let session = session_dir.file_name().unwrap_or_default();
+        let dest_session_dir = self.rrdp_archive_dir.join(session);
+
+        info!(
+            "Archiving superseded RRDP session '{}' to '{}'",
+            session.to_string_lossy(),
+            dest_session_dir.to_string_lossy()
+        );
+
+        if let Ok(serial_dirs) = fs::read_dir(session_dir) {
+            for serial_dir in serial_dirs.flatten() {
+                let dest = dest_session_dir.join(serial_dir.file_name());
+                if !dest.exists() {
+                    let _ = fs::create_dir_all(&dest_session_dir);
+                    let _ = fs::rename(serial_dir.path(), dest);
+                }
+            }
+        }
+    }
+
     fn cleanup_old_rrdp_files(&self, rrdp_updates_config: RrdpUpdatesConfig) -> KrillResult<()> {
         // - old session dirs
         for entry in fs::read_dir(&self.rrdp_base_dir).map_err(|e| {
@@ -1350,6 +1797,10 @@ impl RrdpServer {
             } else {
                 let path = entry.path();
                 if path.is_dir() {
+                    if rrdp_updates_config.rrdp_files_archive {
+                        // Retain the superseded session by moving its serial dirs to the archive
+                        self.archive_session_dir(&path);
+                    }
                     let _best_effort_rm = fs::remove_dir_all(path);
                 }
             }
@@ -1424,6 +1875,9 @@ impl RrdpServer {
                                 e
                             );
                         }
+                        for compression in &Compression::ALL {
+                            let _best_effort_rm = fs::remove_file(compression.path(&snapshot_file_to_remove));
+                        }
                     }
                 } else {
                     // archiving was enabled, keep the old snapshot file until the directory is archived
@@ -1516,8 +1970,9 @@ pub struct RepositoryAccessProxy {
 }
 
 impl RepositoryAccessProxy {
-    pub fn disk(config: &Config) -> KrillResult<Self> {
-        let store = AggregateStore::<RepositoryAccess>::disk(&config.data_dir, PUBSERVER_DIR)?;
+    pub fn create(config: &Config) -> KrillResult<Self> {
+        let mut store = AggregateStore::<RepositoryAccess>::create(&config.storage(), PUBSERVER_DIR)?;
+        store.set_snapshot_policy(config.snapshots.policy());
         let key = MyHandle::from_str(PUBSERVER_DFLT).unwrap();
 
         if store.has(&key)? {
@@ -1539,6 +1994,22 @@ impl RepositoryAccessProxy {
         self.store.has(&self.key).map_err(Error::AggregateStoreError)
     }
 
+    pub fn save_snapshots(&self, force: bool) -> KrillResult<Vec<AggregateSnapshot>> {
+        self.store.save_snapshots(force).map_err(Error::AggregateStoreError)
+    }
+
+    pub fn verify_snapshots(&self) -> KrillResult<Vec<AggregateSnapshot>> {
+        self.store.verify_snapshots().map_err(Error::AggregateStoreError)
+    }
+
+    pub fn archive_history(&self, before: Time) -> KrillResult<usize> {
+        self.store.archive_history(before).map_err(Error::AggregateStoreError)
+    }
+
+    pub fn export(&self, f: &mut dyn FnMut(KeyValueEntry) -> Result<(), KeyValueError>) -> KrillResult<()> {
+        self.store.export(f).map_err(Error::AggregateStoreError)
+    }
+
     pub fn init(&self, uris: PublicationServerUris, signer: &KrillSigner) -> KrillResult<()> {
         if self.initialized()? {
             Err(Error::RepositoryServerAlreadyInitialized)
@@ -1574,6 +2045,16 @@ impl RepositoryAccessProxy {
         }
     }
 
+    /// Returns the key of the identity of the Publication Server, if it is
+    /// initialized.
+    pub fn id_key(&self) -> KrillResult<Option<KeyIdentifier>> {
+        if self.initialized()? {
+            Ok(Some(self.read()?.key_id()))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn publishers(&self) -> KrillResult<Vec<PublisherHandle>> {
         Ok(self.read()?.publishers())
     }
@@ -1602,6 +2083,70 @@ impl RepositoryAccessProxy {
         }
     }
 
+    /// Sets the validation for a publisher, or reverts to the server default if `None` is given.
+    pub fn update_publisher_validation(
+        &self,
+        name: PublisherHandle,
+        validation: Option<PublisherValidation>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        if !self.initialized()? {
+            Err(Error::RepositoryServerNotInitialized)
+        } else {
+            let cmd = RepoAccessCmdDet::update_publisher_validation(&self.key, name, validation, actor);
+            self.store.command(cmd)?;
+            Ok(())
+        }
+    }
+
+    /// Sets the quota for a publisher, or reverts to the server default if `None` is given.
+    pub fn update_publisher_quota(
+        &self,
+        name: PublisherHandle,
+        quota: Option<PublisherQuota>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        if !self.initialized()? {
+            Err(Error::RepositoryServerNotInitialized)
+        } else {
+            let cmd = RepoAccessCmdDet::update_publisher_quota(&self.key, name, quota, actor);
+            self.store.command(cmd)?;
+            Ok(())
+        }
+    }
+
+    /// Stages a new ID certificate for a publisher, taken from an RFC 8183
+    /// Publisher Request, or cancels the rollover if `None` is given.
+    pub fn stage_publisher_id_cert(
+        &self,
+        name: PublisherHandle,
+        req: Option<idexchange::PublisherRequest>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        if !self.initialized()? {
+            Err(Error::RepositoryServerNotInitialized)
+        } else {
+            let id_cert = match req {
+                Some(req) => Some(req.validate().map_err(Error::rfc8183)?.into()),
+                None => None,
+            };
+            let cmd = RepoAccessCmdDet::stage_publisher_id_cert(&self.key, name, id_cert, actor);
+            self.store.command(cmd)?;
+            Ok(())
+        }
+    }
+
+    /// Replaces the current ID certificate of a publisher with the staged one.
+    pub fn activate_publisher_id_cert(&self, name: PublisherHandle, actor: &Actor) -> KrillResult<()> {
+        if !self.initialized()? {
+            Err(Error::RepositoryServerNotInitialized)
+        } else {
+            let cmd = RepoAccessCmdDet::activate_publisher_id_cert(&self.key, name, actor);
+            self.store.command(cmd)?;
+            Ok(())
+        }
+    }
+
     /// Returns the repository URI information for a publisher.
     pub fn repo_info_for(&self, name: &PublisherHandle) -> KrillResult<RepoInfo> {
         self.read()?.repo_info_for(name)
@@ -1624,8 +2169,20 @@ impl RepositoryAccessProxy {
     ) -> KrillResult<publication::PublicationCms> {
         let publisher = self.get_publisher(publisher)?;
         let msg = PublicationCms::decode(bytes).map_err(Error::Rfc8181)?;
-        msg.validate(publisher.id_cert().public_key()).map_err(Error::Rfc8181)?;
-        Ok(msg)
+
+        // During an ID certificate rollover messages signed with either the
+        // current or the staged certificate are accepted.
+        match (
+            msg.validate(publisher.id_cert().public_key()),
+            publisher.staged_id_cert(),
+        ) {
+            (Ok(()), _) => Ok(msg),
+            (Err(e), None) => Err(Error::Rfc8181(e)),
+            (Err(e), Some(staged)) => {
+                msg.validate(staged.public_key()).map_err(|_| Error::Rfc8181(e))?;
+                Ok(msg)
+            }
+        }
     }
 
     // /// Creates and signs an RFC8181 CMS response.
@@ -1698,6 +2255,26 @@ impl Aggregate for RepositoryAccess {
             RepositoryAccessEventDetails::PublisherRemoved { name } => {
                 self.publishers.remove(&name);
             }
+            RepositoryAccessEventDetails::PublisherQuotaUpdated { name, quota } => {
+                if let Some(publisher) = self.publishers.get_mut(&name) {
+                    publisher.set_quota(quota);
+                }
+            }
+            RepositoryAccessEventDetails::PublisherValidationUpdated { name, validation } => {
+                if let Some(publisher) = self.publishers.get_mut(&name) {
+                    publisher.set_validation(validation);
+                }
+            }
+            RepositoryAccessEventDetails::PublisherIdCertStaged { name, id_cert } => {
+                if let Some(publisher) = self.publishers.get_mut(&name) {
+                    publisher.set_staged_id_cert(id_cert);
+                }
+            }
+            RepositoryAccessEventDetails::PublisherIdCertActivated { name } => {
+                if let Some(publisher) = self.publishers.get_mut(&name) {
+                    publisher.activate_staged_id_cert();
+                }
+            }
         }
     }
 
@@ -1714,6 +2291,12 @@ impl Aggregate for RepositoryAccess {
                 base_uri,
             } => self.add_publisher(id_cert, name, base_uri),
             RepoAccessCmdDet::RemovePublisher { name } => self.remove_publisher(name),
+            RepoAccessCmdDet::UpdatePublisherQuota { name, quota } => self.update_publisher_quota(name, quota),
+            RepoAccessCmdDet::UpdatePublisherValidation { name, validation } => {
+                self.update_publisher_validation(name, validation)
+            }
+            RepoAccessCmdDet::StagePublisherIdCert { name, id_cert } => self.stage_publisher_id_cert(name, id_cert),
+            RepoAccessCmdDet::ActivatePublisherIdCert { name } => self.activate_publisher_id_cert(name),
         }
     }
 }
@@ -1755,6 +2338,82 @@ impl RepositoryAccess {
         }
     }
 
+    /// Updates the quota for a publisher
+    fn update_publisher_quota(
+        &self,
+        publisher_handle: PublisherHandle,
+        quota: Option<PublisherQuota>,
+    ) -> Result<Vec<RepositoryAccessEvent>, Error> {
+        let publisher = self.get_publisher(&publisher_handle)?;
+        if publisher.quota() == quota {
+            Ok(vec![])
+        } else {
+            Ok(vec![RepositoryAccessEventDetails::publisher_quota_updated(
+                &self.handle,
+                self.version,
+                publisher_handle,
+                quota,
+            )])
+        }
+    }
+
+    /// Updates the validation for a publisher
+    fn update_publisher_validation(
+        &self,
+        publisher_handle: PublisherHandle,
+        validation: Option<PublisherValidation>,
+    ) -> Result<Vec<RepositoryAccessEvent>, Error> {
+        let publisher = self.get_publisher(&publisher_handle)?;
+        if publisher.validation() == validation {
+            Ok(vec![])
+        } else {
+            Ok(vec![RepositoryAccessEventDetails::publisher_validation_updated(
+                &self.handle,
+                self.version,
+                publisher_handle,
+                validation,
+            )])
+        }
+    }
+
+    /// Stages a new ID certificate for a publisher, or cancels the rollover
+    /// if `None` is given. Both the current and the staged certificate are
+    /// accepted for RFC 8181 messages until the staged one is activated.
+    fn stage_publisher_id_cert(
+        &self,
+        publisher_handle: PublisherHandle,
+        id_cert: Option<IdCertInfo>,
+    ) -> Result<Vec<RepositoryAccessEvent>, Error> {
+        let publisher = self.get_publisher(&publisher_handle)?;
+        if publisher.staged_id_cert() == id_cert.as_ref() || Some(publisher.id_cert()) == id_cert.as_ref() {
+            Ok(vec![])
+        } else {
+            Ok(vec![RepositoryAccessEventDetails::publisher_id_cert_staged(
+                &self.handle,
+                self.version,
+                publisher_handle,
+                id_cert,
+            )])
+        }
+    }
+
+    /// Replaces the current ID certificate of a publisher with the staged one.
+    fn activate_publisher_id_cert(
+        &self,
+        publisher_handle: PublisherHandle,
+    ) -> Result<Vec<RepositoryAccessEvent>, Error> {
+        let publisher = self.get_publisher(&publisher_handle)?;
+        if publisher.staged_id_cert().is_none() {
+            Err(Error::PublisherNoStagedIdCert(publisher_handle))
+        } else {
+            Ok(vec![RepositoryAccessEventDetails::publisher_id_cert_activated(
+                &self.handle,
+                self.version,
+                publisher_handle,
+            )])
+        }
+    }
+
     fn notification_uri(&self) -> uri::Https {
         self.rrdp_base.join(b"notification.xml").unwrap()
     }
@@ -1810,6 +2469,111 @@ impl RepositoryAccess {
     }
 }
 
+//------------ RrdpStats -----------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RrdpStats {
+    session: RrdpSession,
+    serial: u64,
+    session_start: Option<Time>,
+    last_update: Time,
+    notification_size: Option<u64>,
+    snapshot_size: Option<u64>,
+    deltas: Vec<RrdpDeltaStats>,
+}
+
+impl RrdpStats {
+    pub fn session(&self) -> RrdpSession {
+        self.session
+    }
+
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    pub fn session_start(&self) -> Option<Time> {
+        self.session_start
+    }
+
+    pub fn last_update(&self) -> Time {
+        self.last_update
+    }
+
+    pub fn notification_size(&self) -> Option<u64> {
+        self.notification_size
+    }
+
+    pub fn snapshot_size(&self) -> Option<u64> {
+        self.snapshot_size
+    }
+
+    pub fn deltas(&self) -> &Vec<RrdpDeltaStats> {
+        &self.deltas
+    }
+
+    /// The total size of the delta files found on disk.
+    pub fn deltas_size(&self) -> u64 {
+        self.deltas.iter().filter_map(|delta| delta.size).sum()
+    }
+}
+
+impl fmt::Display for RrdpStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let size_str = |size: Option<u64>| match size {
+            None => "unknown".to_string(),
+            Some(size) => size.to_string(),
+        };
+
+        let session_start = match self.session_start {
+            None => "unknown".to_string(),
+            Some(start) => start.to_rfc3339(),
+        };
+
+        writeln!(f, "RRDP session:      {}", self.session)?;
+        writeln!(f, "Session started:   {}", session_start)?;
+        writeln!(f, "RRDP serial:       {}", self.serial)?;
+        writeln!(f, "RRDP updated:      {}", self.last_update.to_rfc3339())?;
+        writeln!(f, "Notification size: {}", size_str(self.notification_size))?;
+        writeln!(f, "Snapshot size:     {}", size_str(self.snapshot_size))?;
+        writeln!(f, "Deltas:            {}", self.deltas.len())?;
+        writeln!(f, "Deltas size:       {}", self.deltas_size())?;
+        writeln!(f)?;
+        writeln!(f, "Serial, Created, Size")?;
+        for delta in &self.deltas {
+            writeln!(
+                f,
+                "{}, {}, {}",
+                delta.serial,
+                delta.time.to_rfc3339(),
+                size_str(delta.size)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RrdpDeltaStats {
+    serial: u64,
+    time: Time,
+    size: Option<u64>,
+}
+
+impl RrdpDeltaStats {
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    pub fn time(&self) -> Time {
+        self.time
+    }
+
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+}
+
 //------------ RepoStats -----------------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -1919,6 +2683,9 @@ pub struct PublisherStats {
     objects: usize,
     size: usize,
     manifests: Vec<PublisherManifestStats>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_changed_serial: Option<u64>,
 }
 
 impl PublisherStats {
@@ -1926,6 +2693,11 @@ impl PublisherStats {
         Self::from(current_objects)
     }
 
+    pub fn with_last_changed_serial(mut self, last_changed_serial: Option<u64>) -> Self {
+        self.last_changed_serial = last_changed_serial;
+        self
+    }
+
     pub fn objects(&self) -> usize {
         self.objects
     }
@@ -1956,6 +2728,12 @@ impl PublisherStats {
     pub fn manifests(&self) -> &Vec<PublisherManifestStats> {
         &self.manifests
     }
+
+    /// Returns the RRDP serial at which the content of this publisher
+    /// last changed, if known.
+    pub fn last_changed_serial(&self) -> Option<u64> {
+        self.last_changed_serial
+    }
 }
 
 impl From<&CurrentObjects> for PublisherStats {
@@ -1977,6 +2755,7 @@ impl From<&CurrentObjects> for PublisherStats {
             objects: objects.len(),
             size: objects.size_approx(),
             manifests,
+            last_changed_serial: None,
         }
     }
 }
@@ -0,0 +1,703 @@
+//! Store the served repository files in an S3 compatible bucket, so that the
+//! repository can be fronted by a CDN without running local web servers.
+//!
+//! The files on disk remain the source: after they are written, the RRDP
+//! snapshot and delta files and the rsync objects are uploaded, and the
+//! notification file is uploaded last. So, relying parties using the bucket
+//! never see a notification file which refers to missing files. An index of
+//! the uploaded files is kept in the data directory, so that only changes are
+//! uploaded, and files which are no longer used can be removed.
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use futures_util::{stream, StreamExt};
+use openssl::sha::sha256;
+use rpki::{
+    rrdp::{Hash, NotificationFile, Snapshot},
+    uri,
+};
+use tokio::sync::Mutex;
+
+use crate::{
+    commons::{
+        api::rrdp::RrdpSession,
+        error::Error,
+        util::{
+            file,
+            s3::{S3Client, S3Config},
+        },
+        KrillResult,
+    },
+    constants::{REPOSITORY_DIR, REPOSITORY_RRDP_DIR, REPOSITORY_S3_INDEX},
+};
+
+const NOTIFICATION_FILE: &str = "notification.xml";
+const CONTENT_TYPE_XML: &str = "application/xml";
+const CONTENT_TYPE_OBJECT: &str = "application/octet-stream";
+
+//------------ RepositoryS3Config --------------------------------------------
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RepositoryS3Config {
+    /// The bucket, credentials and key prefix.
+    #[serde(flatten)]
+    pub s3: S3Config,
+
+    /// The prefix for the RRDP files, relative to the bucket prefix.
+    #[serde(default = "RepositoryS3Config::dflt_rrdp_prefix")]
+    pub rrdp_prefix: String,
+
+    /// The prefix for the rsync objects, relative to the bucket prefix.
+    #[serde(default = "RepositoryS3Config::dflt_rsync_prefix")]
+    pub rsync_prefix: String,
+
+    /// Read back uploaded RRDP files and verify their hashes, before the
+    /// notification file is updated.
+    #[serde(default = "RepositoryS3Config::dflt_verify")]
+    pub verify: bool,
+
+    /// The maximum number of files which are uploaded at the same time.
+    #[serde(default = "RepositoryS3Config::dflt_upload_concurrency")]
+    pub upload_concurrency: usize,
+}
+
+impl RepositoryS3Config {
+    fn dflt_rrdp_prefix() -> String {
+        "rrdp/".to_string()
+    }
+
+    fn dflt_rsync_prefix() -> String {
+        "rsync/".to_string()
+    }
+
+    fn dflt_verify() -> bool {
+        true
+    }
+
+    fn dflt_upload_concurrency() -> usize {
+        8
+    }
+}
+
+//------------ S3Upload ------------------------------------------------------
+
+/// A file which needs to be uploaded.
+struct S3Upload {
+    key: String,
+    content: Bytes,
+    content_type: &'static str,
+    hash: Option<Hash>,
+}
+
+//------------ RepositoryS3Index ---------------------------------------------
+
+/// The files in the bucket, as far as they were uploaded by Krill.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+struct RepositoryS3Index {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session: Option<RrdpSession>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    serial: Option<u64>,
+
+    // The keys of the RRDP files referenced by the current, and by the
+    // previous notification file.
+    rrdp: HashSet<String>,
+    rrdp_previous: HashSet<String>,
+
+    // The keys and hex encoded SHA-256 hashes of the rsync objects.
+    rsync: HashMap<String, String>,
+}
+
+impl RepositoryS3Index {
+    fn is_current(&self, session: RrdpSession, serial: u64) -> bool {
+        self.session == Some(session) && self.serial == Some(serial)
+    }
+
+    fn has_rrdp(&self, key: &str) -> bool {
+        self.rrdp.contains(key) || self.rrdp_previous.contains(key)
+    }
+
+    /// Updates the index for the new content, and returns the keys which
+    /// are no longer used.
+    ///
+    /// The RRDP files referenced by the previous notification file are kept,
+    /// so that relying parties which just fetched it can still get them.
+    fn update(
+        &mut self,
+        session: RrdpSession,
+        serial: u64,
+        rrdp: HashSet<String>,
+        rsync: HashMap<String, String>,
+    ) -> Vec<String> {
+        let mut removed: Vec<String> = self
+            .rrdp_previous
+            .iter()
+            .filter(|key| !rrdp.contains(*key) && !self.rrdp.contains(*key))
+            .cloned()
+            .collect();
+        removed.extend(self.rsync.keys().filter(|key| !rsync.contains_key(*key)).cloned());
+        removed.sort();
+
+        self.session = Some(session);
+        self.serial = Some(serial);
+        self.rrdp_previous = std::mem::replace(&mut self.rrdp, rrdp);
+        self.rsync = rsync;
+
+        removed
+    }
+}
+
+//------------ RepositoryS3Store ---------------------------------------------
+
+/// Uploads the repository files on disk to an S3 compatible bucket.
+pub struct RepositoryS3Store {
+    config: RepositoryS3Config,
+    client: S3Client,
+    rrdp_dir: PathBuf,
+    index_path: PathBuf,
+
+    // Guards uploads and the index. Set to true after the content of the
+    // bucket was checked against the index, which is done once after start.
+    checked: Mutex<bool>,
+}
+
+impl RepositoryS3Store {
+    pub fn new(config: RepositoryS3Config, data_dir: &Path) -> Self {
+        let client = S3Client::new(config.s3.clone());
+        RepositoryS3Store {
+            config,
+            client,
+            rrdp_dir: data_dir.join(REPOSITORY_DIR).join(REPOSITORY_RRDP_DIR),
+            index_path: data_dir.join(REPOSITORY_S3_INDEX),
+            checked: Mutex::new(false),
+        }
+    }
+
+    /// Uploads the current repository files, if they changed since the last
+    /// upload. Does nothing if no repository files were written yet.
+    pub async fn sync(&self, notification_uri: &uri::Https) -> KrillResult<()> {
+        let mut checked = self.checked.lock().await;
+
+        let notification_uri = notification_uri.as_str();
+        let rrdp_base_uri = &notification_uri[..notification_uri.rfind('/').unwrap_or(0) + 1];
+
+        let notification_xml = match file::read(&self.rrdp_dir.join(NOTIFICATION_FILE)) {
+            Ok(xml) => xml,
+            Err(_) => {
+                debug!("No repository files to upload to {}", self.client.config());
+                return Ok(());
+            }
+        };
+        let notification = NotificationFile::parse(notification_xml.as_ref())
+            .map_err(|e| Error::Custom(format!("Invalid RRDP notification file on disk: {}", e)))?;
+        let notification_key = self.rrdp_key(NOTIFICATION_FILE);
+
+        let session = RrdpSession::from(notification.session_id());
+        let serial = notification.serial();
+
+        let mut index = self.load_index()?;
+
+        // Upload everything again, if the notification file in the bucket is
+        // not the one we uploaded last, e.g. because the bucket was changed.
+        if !*checked {
+            let remote = self
+                .client
+                .get_object(&notification_key)
+                .await
+                .map_err(Error::HttpClientError)?;
+            let remote_is_indexed = remote
+                .and_then(|xml| NotificationFile::parse(xml.as_ref()).ok())
+                .map(|remote| index.is_current(remote.session_id().into(), remote.serial()))
+                .unwrap_or(false);
+            if !remote_is_indexed && index.serial.is_some() {
+                warn!(
+                    "Notification file in {} does not match the last upload, will upload all files",
+                    self.client.config()
+                );
+                index = RepositoryS3Index::default();
+            }
+            *checked = true;
+        }
+
+        if index.is_current(session, serial) {
+            debug!("Repository files in {} are up-to-date", self.client.config());
+            return Ok(());
+        }
+
+        info!(
+            "Uploading repository files for RRDP session {} serial {} to {}",
+            session,
+            serial,
+            self.client.config()
+        );
+
+        // RRDP snapshot and delta files, and the rsync objects from the
+        // snapshot, can be uploaded in any order.
+        let mut uploads = vec![];
+
+        let mut rrdp = HashSet::new();
+        let mut rrdp_files = vec![notification.snapshot().clone()];
+        rrdp_files.extend(notification.deltas().iter().map(|delta| (**delta).clone()));
+
+        let mut snapshot_xml = None;
+        for rrdp_file in rrdp_files {
+            let relative = Self::rrdp_relative(rrdp_base_uri, rrdp_file.uri())?;
+            let key = self.rrdp_key(&relative);
+
+            let needs_upload = !index.has_rrdp(&key);
+            let is_snapshot = rrdp_file.uri() == notification.snapshot().uri();
+
+            if needs_upload || is_snapshot {
+                let content = file::read(&self.rrdp_dir.join(&relative))?;
+                if !rrdp_file.hash().matches(content.as_ref()) {
+                    return Err(Error::Custom(format!(
+                        "RRDP file on disk for '{}' does not match the hash in the notification file",
+                        rrdp_file.uri()
+                    )));
+                }
+                if needs_upload {
+                    uploads.push(S3Upload {
+                        key: key.clone(),
+                        content: content.clone(),
+                        content_type: CONTENT_TYPE_XML,
+                        hash: Some(rrdp_file.hash()),
+                    });
+                }
+                if is_snapshot {
+                    snapshot_xml = Some(content);
+                }
+            }
+            rrdp.insert(key);
+        }
+
+        let snapshot_xml = snapshot_xml.ok_or_else(|| Error::custom("RRDP snapshot file was not read"))?;
+        let snapshot = Snapshot::parse(snapshot_xml.as_ref())
+            .map_err(|e| Error::Custom(format!("Invalid RRDP snapshot file on disk: {}", e)))?;
+
+        let mut rsync = HashMap::new();
+        for element in snapshot.elements() {
+            let key = self.rsync_key(element.uri());
+            let hash = hex::encode(sha256(element.data()));
+            if index.rsync.get(&key) != Some(&hash) {
+                uploads.push(S3Upload {
+                    key: key.clone(),
+                    content: element.data().clone(),
+                    content_type: CONTENT_TYPE_OBJECT,
+                    hash: None,
+                });
+            }
+            rsync.insert(key, hash);
+        }
+
+        self.upload_all(uploads).await?;
+
+        // The notification file goes last.
+        let notification_hash = Hash::from_data(notification_xml.as_ref());
+        self.upload(
+            &notification_key,
+            notification_xml,
+            CONTENT_TYPE_XML,
+            Some(notification_hash),
+        )
+        .await?;
+
+        let removed = index.update(session, serial, rrdp, rsync);
+        file::save_json(&index, &self.index_path)?;
+
+        for key in removed {
+            debug!("Removing '{}' from {}", key, self.client.config());
+            if let Err(e) = self.client.delete_object(&key).await {
+                warn!("Could not remove '{}' from {}: {}", key, self.client.config(), e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Uploads the files, with at most the configured number of uploads at
+    /// the same time. Returns an error if any upload failed.
+    async fn upload_all(&self, uploads: Vec<S3Upload>) -> KrillResult<()> {
+        let results: Vec<KrillResult<()>> = stream::iter(uploads)
+            .map(|upload| async move {
+                self.upload(&upload.key, upload.content, upload.content_type, upload.hash)
+                    .await
+            })
+            .buffer_unordered(self.config.upload_concurrency.max(1))
+            .collect()
+            .await;
+
+        results.into_iter().collect()
+    }
+
+    /// Uploads the content, and verifies it if a hash is given and this is
+    /// configured.
+    async fn upload(&self, key: &str, content: Bytes, content_type: &str, hash: Option<Hash>) -> KrillResult<()> {
+        self.client
+            .put_object(key, content, content_type)
+            .await
+            .map_err(Error::HttpClientError)?;
+
+        if let (true, Some(hash)) = (self.config.verify, hash) {
+            let uploaded = self.client.get_object(key).await.map_err(Error::HttpClientError)?;
+            if !uploaded.map(|content| hash.matches(content.as_ref())).unwrap_or(false) {
+                return Err(Error::Custom(format!(
+                    "Uploaded '{}' to {}, but could not read it back with the same content",
+                    key,
+                    self.client.config()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the path of the RRDP file relative to the RRDP base URI, i.e.
+    /// the directory of the notification file. This is also where Krill keeps
+    /// the file on disk.
+    fn rrdp_relative(rrdp_base_uri: &str, uri: &uri::Https) -> KrillResult<String> {
+        uri.as_str()
+            .strip_prefix(rrdp_base_uri)
+            .filter(|rel| !rel.is_empty() && !rel.split('/').any(|part| part.is_empty() || part == ".."))
+            .map(|rel| rel.to_string())
+            .ok_or_else(|| {
+                Error::Custom(format!(
+                    "RRDP file '{}' is not below the RRDP base URI '{}'",
+                    uri, rrdp_base_uri
+                ))
+            })
+    }
+
+    fn rrdp_key(&self, relative: &str) -> String {
+        format!("{}{}", self.config.rrdp_prefix, relative)
+    }
+
+    fn rsync_key(&self, uri: &uri::Rsync) -> String {
+        format!("{}{}/{}", self.config.rsync_prefix, uri.module_name(), uri.path())
+    }
+
+    fn load_index(&self) -> KrillResult<RepositoryS3Index> {
+        if self.index_path.exists() {
+            file::load_json(&self.index_path).map_err(Error::from)
+        } else {
+            Ok(RepositoryS3Index::default())
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        convert::Infallible,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, RwLock,
+        },
+    };
+
+    use hyper::{
+        server::conn::AddrIncoming,
+        service::{make_service_fn, service_fn},
+        Method,
+    };
+
+    use crate::test;
+
+    const RRDP_BASE_URI: &str = "https://rrdp.example.com/rrdp/";
+
+    fn keys(keys: &[&str]) -> HashSet<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    //------------ Mock S3 server --------------------------------------------
+
+    /// A mock S3 server which keeps the objects in memory, and counts the
+    /// uploads and the maximum number of uploads at the same time.
+    #[derive(Clone, Default)]
+    struct MockS3 {
+        objects: Arc<RwLock<HashMap<String, Bytes>>>,
+        puts: Arc<AtomicUsize>,
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl MockS3 {
+        /// Starts the server, and returns it together with its endpoint.
+        async fn start() -> (Self, String) {
+            let incoming = AddrIncoming::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+            let endpoint = format!("http://{}", incoming.local_addr());
+            let mock = MockS3::default();
+
+            let service_mock = mock.clone();
+            let service = make_service_fn(move |_| {
+                let mock = service_mock.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |req| {
+                        let mock = mock.clone();
+                        async move { Ok::<_, Infallible>(mock.respond(req).await) }
+                    }))
+                }
+            });
+            tokio::spawn(hyper::Server::builder(incoming).serve(service));
+
+            (mock, endpoint)
+        }
+
+        async fn respond(&self, req: hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body> {
+            let method = req.method().clone();
+            let path = req.uri().path().to_string();
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+
+            let status = match method {
+                Method::PUT => {
+                    // Take some time, so that concurrent uploads overlap.
+                    let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                    self.puts.fetch_add(1, Ordering::SeqCst);
+                    self.objects.write().unwrap().insert(path, body);
+                    200
+                }
+                Method::GET => match self.objects.read().unwrap().get(&path) {
+                    Some(content) => return hyper::Response::new(hyper::Body::from(content.clone())),
+                    None => 404,
+                },
+                Method::DELETE => {
+                    self.objects.write().unwrap().remove(&path);
+                    204
+                }
+                _ => 405,
+            };
+
+            hyper::Response::builder()
+                .status(status)
+                .body(hyper::Body::empty())
+                .unwrap()
+        }
+
+        /// Returns the keys of the objects, relative to the bucket prefix.
+        fn keys(&self) -> HashSet<String> {
+            self.objects
+                .read()
+                .unwrap()
+                .keys()
+                .filter_map(|path| path.strip_prefix("/bucket/krill/"))
+                .map(|key| key.to_string())
+                .collect()
+        }
+
+        fn object(&self, key: &str) -> Option<Bytes> {
+            self.objects
+                .read()
+                .unwrap()
+                .get(&format!("/bucket/krill/{}", key))
+                .cloned()
+        }
+
+        fn puts(&self) -> usize {
+            self.puts.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Writes the RRDP notification and snapshot files for the serial, with
+    /// the given rsync objects, and returns the relative path of the snapshot.
+    fn write_repository(rrdp_dir: &Path, session: RrdpSession, serial: u64, objects: &[(&str, &[u8])]) -> String {
+        let snapshot_relative = format!("{}/{}/snapshot.xml", session, serial);
+        let mut snapshot = format!(
+            r#"<snapshot xmlns="http://www.ripe.net/rpki/rrdp" version="1" session_id="{}" serial="{}">"#,
+            session, serial
+        );
+        for (uri, content) in objects {
+            snapshot.push_str(&format!(
+                r#"<publish uri="{}">{}</publish>"#,
+                uri,
+                base64::encode(content)
+            ));
+        }
+        snapshot.push_str("</snapshot>");
+        file::save(snapshot.as_bytes(), &rrdp_dir.join(&snapshot_relative)).unwrap();
+
+        let notification = format!(
+            concat!(
+                r#"<notification xmlns="http://www.ripe.net/rpki/rrdp" version="1" session_id="{}" serial="{}">"#,
+                r#"<snapshot uri="{}{}" hash="{}"/></notification>"#
+            ),
+            session,
+            serial,
+            RRDP_BASE_URI,
+            snapshot_relative,
+            hex::encode(sha256(snapshot.as_bytes()))
+        );
+        file::save(notification.as_bytes(), &rrdp_dir.join(NOTIFICATION_FILE)).unwrap();
+
+        snapshot_relative
+    }
+
+    #[test]
+    fn rrdp_relative_path() {
+        let base = "https://rrdp.example.com/rrdp/";
+        let relative = |uri: &str| RepositoryS3Store::rrdp_relative(base, &uri::Https::from_str(uri).unwrap());
+
+        assert_eq!(
+            relative("https://rrdp.example.com/rrdp/session/1/random/snapshot.xml").unwrap(),
+            "session/1/random/snapshot.xml"
+        );
+        assert!(relative("https://other.example.com/rrdp/session/1/random/snapshot.xml").is_err());
+        assert!(relative("https://rrdp.example.com/rrdp/../snapshot.xml").is_err());
+    }
+
+    #[test]
+    fn index_keeps_previous_rrdp_files() {
+        let session = RrdpSession::default();
+        let mut index = RepositoryS3Index::default();
+        assert!(!index.is_current(session, 1));
+
+        let mut rsync = HashMap::new();
+        rsync.insert("rsync/repo/a.cer".to_string(), "aa".to_string());
+        rsync.insert("rsync/repo/b.roa".to_string(), "bb".to_string());
+
+        let removed = index.update(session, 1, keys(&["rrdp/s/1/snapshot.xml"]), rsync.clone());
+        assert!(removed.is_empty());
+        assert!(index.is_current(session, 1));
+
+        rsync.remove("rsync/repo/b.roa");
+        let removed = index.update(
+            session,
+            2,
+            keys(&["rrdp/s/2/snapshot.xml", "rrdp/s/2/delta.xml"]),
+            rsync.clone(),
+        );
+        assert_eq!(removed, vec!["rsync/repo/b.roa".to_string()]);
+        assert!(index.has_rrdp("rrdp/s/1/snapshot.xml"));
+
+        let removed = index.update(
+            session,
+            3,
+            keys(&["rrdp/s/3/snapshot.xml", "rrdp/s/3/delta.xml", "rrdp/s/2/delta.xml"]),
+            rsync,
+        );
+        assert_eq!(removed, vec!["rrdp/s/1/snapshot.xml".to_string()]);
+        assert!(index.has_rrdp("rrdp/s/2/snapshot.xml"));
+    }
+
+    #[tokio::test]
+    async fn sync_to_mock_s3_server() {
+        let (mock, endpoint) = MockS3::start().await;
+        let d = test::tmp_dir();
+        let rrdp_dir = d.join(REPOSITORY_DIR).join(REPOSITORY_RRDP_DIR);
+        let notification_uri = uri::Https::from_str(&format!("{}{}", RRDP_BASE_URI, NOTIFICATION_FILE)).unwrap();
+
+        let config: RepositoryS3Config = toml::from_str(&format!(
+            r#"
+            bucket = "bucket"
+            prefix = "krill/"
+            endpoint = "{}"
+            access_key_id = "AKIDEXAMPLE"
+            secret_access_key = "secret"
+            upload_concurrency = 2
+            "#,
+            endpoint
+        ))
+        .unwrap();
+        let store = RepositoryS3Store::new(config.clone(), &d);
+
+        // Nothing is uploaded before the repository files are written.
+        store.sync(&notification_uri).await.unwrap();
+        assert!(mock.keys().is_empty());
+
+        let session = RrdpSession::default();
+        let a: (&str, &[u8]) = ("rsync://rsync.example.com/repo/ca/a.roa", b"a");
+        let b: (&str, &[u8]) = ("rsync://rsync.example.com/repo/ca/b.roa", b"b");
+        let c: (&str, &[u8]) = ("rsync://rsync.example.com/repo/ca/c.roa", b"c");
+
+        // The snapshot and the objects are uploaded at most two at a time,
+        // and the notification file after them.
+        let snapshot_1 = format!("rrdp/{}", write_repository(&rrdp_dir, session, 1, &[a, b]));
+        store.sync(&notification_uri).await.unwrap();
+        assert_eq!(
+            mock.keys(),
+            keys(&[
+                "rrdp/notification.xml",
+                &snapshot_1,
+                "rsync/repo/ca/a.roa",
+                "rsync/repo/ca/b.roa"
+            ])
+        );
+        assert_eq!(
+            mock.object("rrdp/notification.xml").unwrap(),
+            file::read(&rrdp_dir.join(NOTIFICATION_FILE)).unwrap()
+        );
+        assert_eq!(mock.object("rsync/repo/ca/a.roa").unwrap(), Bytes::from_static(b"a"));
+        assert_eq!(mock.max_in_flight.load(Ordering::SeqCst), 2);
+
+        // Nothing is uploaded if nothing changed.
+        let puts = mock.puts();
+        store.sync(&notification_uri).await.unwrap();
+        assert_eq!(mock.puts(), puts);
+
+        // Only the new snapshot, the changed object and the notification file
+        // are uploaded. The removed object is deleted, but the snapshot of
+        // the previous notification file is kept.
+        let snapshot_2 = format!("rrdp/{}", write_repository(&rrdp_dir, session, 2, &[a, c]));
+        store.sync(&notification_uri).await.unwrap();
+        assert_eq!(mock.puts(), puts + 3);
+        assert_eq!(
+            mock.keys(),
+            keys(&[
+                "rrdp/notification.xml",
+                &snapshot_1,
+                &snapshot_2,
+                "rsync/repo/ca/a.roa",
+                "rsync/repo/ca/c.roa"
+            ])
+        );
+
+        let snapshot_3 = format!("rrdp/{}", write_repository(&rrdp_dir, session, 3, &[a, c]));
+        store.sync(&notification_uri).await.unwrap();
+        let current = keys(&[
+            "rrdp/notification.xml",
+            &snapshot_3,
+            "rsync/repo/ca/a.roa",
+            "rsync/repo/ca/c.roa",
+        ]);
+        let mut expected = current.clone();
+        expected.insert(snapshot_2);
+        assert_eq!(mock.keys(), expected);
+
+        // If the bucket no longer has the last upload after a restart, then
+        // all current files are uploaded again.
+        mock.objects.write().unwrap().clear();
+        let store = RepositoryS3Store::new(config, &d);
+        store.sync(&notification_uri).await.unwrap();
+        assert_eq!(mock.keys(), current);
+
+        let _ = std::fs::remove_dir_all(d);
+    }
+
+    #[test]
+    fn parse_config() {
+        let config: RepositoryS3Config = toml::from_str(
+            r#"
+            bucket = "my-bucket"
+            prefix = "krill/"
+            region = "eu-west-1"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.s3.bucket, "my-bucket");
+        assert_eq!(config.rrdp_prefix, "rrdp/");
+        assert_eq!(config.rsync_prefix, "rsync/");
+        assert!(config.verify);
+        assert_eq!(config.upload_concurrency, 8);
+    }
+}
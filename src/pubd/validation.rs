@@ -0,0 +1,156 @@
+//! Sanity checks for the content submitted by publishers.
+//!
+//! RFC 8181 only requires that the publication server checks that publishers
+//! publish within their own base URI, and that updates and withdraws match
+//! existing objects. The checks here are optional, and are meant to keep
+//! garbage out of the RRDP and rsync repository.
+use rpki::{
+    repository::{aspa::Aspa, cert::Cert, crl::Crl, manifest::Manifest, roa::Roa, sigobj::SignedObject},
+    uri,
+};
+
+use crate::commons::api::rrdp::{CurrentObjectUri, CurrentObjects, DeltaElements};
+
+/// Checks the objects which are published or updated in a delta.
+///
+/// Objects must be parseable as the type indicated by their file extension.
+/// Ghostbuster records, RSCs, Trust Anchor Keys and Signed Prefix Lists are
+/// only checked to be signed objects. Manifests must match the objects of
+/// the publisher after the delta is applied. Returns a description of each
+/// issue that was found.
+pub fn check_delta(delta: &DeltaElements, objects: &CurrentObjects) -> Vec<String> {
+    let published = delta
+        .publishes()
+        .iter()
+        .map(|publish| (publish.uri(), publish.base64()))
+        .chain(delta.updates().iter().map(|update| (update.uri(), update.base64())));
+
+    let mut issues = vec![];
+    for (uri, base64) in published {
+        if let Err(issue) = check_object(uri, base64.to_bytes().as_ref(), objects) {
+            issues.push(format!("'{}': {}", uri, issue));
+        }
+    }
+    issues
+}
+
+fn check_object(uri: &uri::Rsync, bytes: &[u8], objects: &CurrentObjects) -> Result<(), String> {
+    if uri.ends_with(".cer") {
+        Cert::decode(bytes)
+            .map(|_| ())
+            .map_err(|_| "cannot decode certificate".to_string())
+    } else if uri.ends_with(".crl") {
+        Crl::decode(bytes)
+            .map(|_| ())
+            .map_err(|_| "cannot decode CRL".to_string())
+    } else if uri.ends_with(".roa") {
+        Roa::decode(bytes, false)
+            .map(|_| ())
+            .map_err(|_| "cannot decode ROA".to_string())
+    } else if uri.ends_with(".asa") {
+        Aspa::decode(bytes, false)
+            .map(|_| ())
+            .map_err(|_| "cannot decode ASPA".to_string())
+    } else if uri.ends_with(".gbr") || uri.ends_with(".sig") || uri.ends_with(".tak") || uri.ends_with(".spl") {
+        SignedObject::decode(bytes, false)
+            .map(|_| ())
+            .map_err(|_| "cannot decode signed object".to_string())
+    } else if uri.ends_with(".mft") {
+        let mft = Manifest::decode(bytes, false).map_err(|_| "cannot decode manifest".to_string())?;
+        check_manifest(uri, &mft, objects)
+    } else {
+        Err("unsupported object type".to_string())
+    }
+}
+
+/// Checks that all objects listed on the manifest are present with the
+/// listed hash.
+fn check_manifest(uri: &uri::Rsync, mft: &Manifest, objects: &CurrentObjects) -> Result<(), String> {
+    let base = uri
+        .parent()
+        .ok_or_else(|| "manifest has no parent directory".to_string())?;
+
+    for (listed_uri, hash) in mft.content().iter_uris(&base) {
+        match objects.get(&CurrentObjectUri::from(&listed_uri)) {
+            None => return Err(format!("listed object '{}' is missing", listed_uri)),
+            Some(base64) => {
+                if hash.verify(base64.to_bytes()).is_err() {
+                    return Err(format!("listed object '{}' does not match hash", listed_uri));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use rpki::ca::publication::Base64;
+
+    use super::*;
+
+    use crate::{commons::api::rrdp::PublishElement, test};
+
+    fn publish(uri: &str, content: &[u8]) -> PublishElement {
+        PublishElement::new(Base64::from_content(content), test::rsync(uri))
+    }
+
+    #[test]
+    fn reject_garbage_objects() {
+        let delta = DeltaElements::new(
+            vec![
+                publish("rsync://localhost/repo/alice/0/garbage.roa", b"garbage"),
+                publish("rsync://localhost/repo/alice/0/file.txt", b"text"),
+            ],
+            vec![],
+            vec![],
+        );
+
+        let issues = check_delta(&delta, &CurrentObjects::default());
+        assert_eq!(
+            issues,
+            vec![
+                "'rsync://localhost/repo/alice/0/garbage.roa': cannot decode ROA".to_string(),
+                "'rsync://localhost/repo/alice/0/file.txt': unsupported object type".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_other_signed_objects() {
+        let delta = DeltaElements::new(
+            vec![
+                publish("rsync://localhost/repo/alice/0/garbage.tak", b"garbage"),
+                publish("rsync://localhost/repo/alice/0/garbage.spl", b"garbage"),
+            ],
+            vec![],
+            vec![],
+        );
+
+        let issues = check_delta(&delta, &CurrentObjects::default());
+        assert_eq!(
+            issues,
+            vec![
+                "'rsync://localhost/repo/alice/0/garbage.tak': cannot decode signed object".to_string(),
+                "'rsync://localhost/repo/alice/0/garbage.spl': cannot decode signed object".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn accept_valid_objects() {
+        let cert = Bytes::from_static(include_bytes!("../../test-resources/ta.cer"));
+        let delta = DeltaElements::new(
+            vec![publish("rsync://localhost/repo/alice/0/ta.cer", cert.as_ref())],
+            vec![],
+            vec![],
+        );
+
+        assert!(check_delta(&delta, &CurrentObjects::default()).is_empty());
+    }
+}
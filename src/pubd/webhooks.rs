@@ -0,0 +1,358 @@
+//! Webhooks called by the publication server when publishers change their
+//! content, and when a new RRDP serial is produced. This lets downstream
+//! systems, such as CDNs or monitoring, react immediately rather than poll
+//! the RRDP notification file.
+//!
+//! Events are posted as JSON using the same [`WebhookChannel`] as alerts.
+use std::{fmt, sync::Mutex};
+
+use rpki::{
+    ca::{
+        idexchange::PublisherHandle,
+        publication::{PublishDelta, PublishDeltaElement},
+    },
+    uri,
+};
+
+use tokio::sync::mpsc;
+
+use crate::{
+    commons::api::{rrdp::RrdpSession, Timestamp},
+    constants::REPOSITORY_WEBHOOK_QUEUE_SIZE,
+    daemon::notify::{WebhookChannel, WebhookChannelConfig},
+};
+
+//------------ RepositoryEventType -------------------------------------------
+
+/// The kinds of publication server events for which webhooks can be called.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositoryEventType {
+    /// A publisher published, updated or withdrew objects.
+    Publish,
+
+    /// A new RRDP serial was produced.
+    RrdpUpdate,
+}
+
+impl RepositoryEventType {
+    pub fn all() -> Vec<RepositoryEventType> {
+        vec![RepositoryEventType::Publish, RepositoryEventType::RrdpUpdate]
+    }
+}
+
+impl fmt::Display for RepositoryEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryEventType::Publish => write!(f, "publish"),
+            RepositoryEventType::RrdpUpdate => write!(f, "RRDP update"),
+        }
+    }
+}
+
+//------------ RepositoryWebhookConfig ---------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct RepositoryWebhookConfig {
+    /// The URL to which events are posted as JSON.
+    pub url: String,
+
+    /// The events which are posted to this URL, defaults to all events.
+    #[serde(default = "RepositoryEventType::all")]
+    pub events: Vec<RepositoryEventType>,
+}
+
+impl RepositoryWebhookConfig {
+    pub fn channel_config(&self) -> WebhookChannelConfig {
+        WebhookChannelConfig { url: self.url.clone() }
+    }
+}
+
+//------------ RepositoryEvent -----------------------------------------------
+
+/// An event in the publication server. This is also the JSON payload posted
+/// to webhooks.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RepositoryEvent {
+    /// A publisher changed its content. The changes become visible in RRDP
+    /// and rsync with the next RRDP update.
+    Publish {
+        timestamp: Timestamp,
+        publisher: PublisherHandle,
+        published: Vec<uri::Rsync>,
+        updated: Vec<uri::Rsync>,
+        withdrawn: Vec<uri::Rsync>,
+    },
+
+    /// A new RRDP serial was produced, either by a delta or by a session
+    /// reset. The publishers are those whose content changed in this serial.
+    RrdpUpdate {
+        timestamp: Timestamp,
+        session: RrdpSession,
+        serial: u64,
+        notification_uri: uri::Https,
+        publishers: Vec<PublisherHandle>,
+    },
+}
+
+impl RepositoryEvent {
+    pub fn publish(publisher: PublisherHandle, delta: &PublishDelta) -> Self {
+        let mut published = vec![];
+        let mut updated = vec![];
+        let mut withdrawn = vec![];
+
+        for element in delta.clone().into_elements() {
+            match element {
+                PublishDeltaElement::Publish(publish) => published.push(publish.uri().clone()),
+                PublishDeltaElement::Update(update) => updated.push(update.uri().clone()),
+                PublishDeltaElement::Withdraw(withdraw) => withdrawn.push(withdraw.uri().clone()),
+            }
+        }
+
+        RepositoryEvent::Publish {
+            timestamp: Timestamp::now(),
+            publisher,
+            published,
+            updated,
+            withdrawn,
+        }
+    }
+
+    pub fn rrdp_update(
+        session: RrdpSession,
+        serial: u64,
+        notification_uri: uri::Https,
+        publishers: Vec<PublisherHandle>,
+    ) -> Self {
+        RepositoryEvent::RrdpUpdate {
+            timestamp: Timestamp::now(),
+            session,
+            serial,
+            notification_uri,
+            publishers,
+        }
+    }
+
+    pub fn event_type(&self) -> RepositoryEventType {
+        match self {
+            RepositoryEvent::Publish { .. } => RepositoryEventType::Publish,
+            RepositoryEvent::RrdpUpdate { .. } => RepositoryEventType::RrdpUpdate,
+        }
+    }
+}
+
+impl fmt::Display for RepositoryEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryEvent::Publish {
+                publisher,
+                published,
+                updated,
+                withdrawn,
+                ..
+            } => write!(
+                f,
+                "publisher '{}' published {}, updated {} and withdrew {} objects",
+                publisher,
+                published.len(),
+                updated.len(),
+                withdrawn.len()
+            ),
+            RepositoryEvent::RrdpUpdate {
+                session,
+                serial,
+                publishers,
+                ..
+            } => write!(
+                f,
+                "RRDP updated to session {} serial {} for {} publishers",
+                session,
+                serial,
+                publishers.len()
+            ),
+        }
+    }
+}
+
+//------------ RepositoryNotifier --------------------------------------------
+
+/// Posts publication server events to the configured webhooks.
+///
+/// Events are posted in the background, so that publishers are never held
+/// up by slow or unavailable webhooks. Each webhook has a bounded queue of
+/// events which are posted in order by a single task. If a webhook cannot
+/// keep up and its queue is full, new events for it are dropped. Failures
+/// to post are logged.
+#[derive(Debug, Default)]
+pub struct RepositoryNotifier {
+    webhooks: Vec<RepositoryWebhook>,
+}
+
+#[derive(Debug)]
+struct RepositoryWebhook {
+    events: Vec<RepositoryEventType>,
+    channel: WebhookChannel,
+    queue: mpsc::Sender<RepositoryEvent>,
+
+    // Taken when the task posting the queued events is started, which is
+    // done on first use as it needs an async runtime.
+    receiver: Mutex<Option<mpsc::Receiver<RepositoryEvent>>>,
+}
+
+impl RepositoryWebhook {
+    fn new(config: &RepositoryWebhookConfig) -> Self {
+        let (queue, receiver) = mpsc::channel(REPOSITORY_WEBHOOK_QUEUE_SIZE);
+        RepositoryWebhook {
+            events: config.events.clone(),
+            channel: WebhookChannel::new(config.channel_config()),
+            queue,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    fn start(&self, runtime: &tokio::runtime::Handle) {
+        if let Some(mut receiver) = self.receiver.lock().unwrap().take() {
+            let channel = self.channel.clone();
+            runtime.spawn(async move {
+                while let Some(event) = receiver.recv().await {
+                    if let Err(e) = channel.send(&event).await {
+                        warn!("Could not post to {}: {}. Event was: {}", channel, e, event);
+                    }
+                }
+            });
+        }
+    }
+
+    fn enqueue(&self, event: RepositoryEvent) {
+        if let Err(e) = self.queue.try_send(event) {
+            match e {
+                mpsc::error::TrySendError::Full(event) => {
+                    warn!("Queue for {} is full, dropping event: {}", self.channel, event)
+                }
+                mpsc::error::TrySendError::Closed(event) => {
+                    warn!("Cannot post to {}, dropping event: {}", self.channel, event)
+                }
+            }
+        }
+    }
+}
+
+impl RepositoryNotifier {
+    pub fn build(configs: &[RepositoryWebhookConfig]) -> Self {
+        RepositoryNotifier {
+            webhooks: configs.iter().map(RepositoryWebhook::new).collect(),
+        }
+    }
+
+    /// Returns true if this event type would be posted to any webhook.
+    pub fn is_enabled(&self, event_type: RepositoryEventType) -> bool {
+        self.webhooks.iter().any(|webhook| webhook.events.contains(&event_type))
+    }
+
+    pub fn notify(&self, event: RepositoryEvent) {
+        if !self.is_enabled(event.event_type()) {
+            return;
+        }
+
+        let runtime = match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => runtime,
+            Err(_) => {
+                warn!("Cannot call webhooks outside of async runtime for event: {}", event);
+                return;
+            }
+        };
+
+        for webhook in &self.webhooks {
+            if webhook.events.contains(&event.event_type()) {
+                webhook.start(&runtime);
+                webhook.enqueue(event.clone());
+            }
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use bytes::Bytes;
+    use hyper::StatusCode;
+
+    use crate::{commons::util::file::CurrentFile, test};
+
+    #[test]
+    fn publish_event_json() {
+        let publisher = PublisherHandle::from_str("alice").unwrap();
+        let file = CurrentFile::new(
+            test::rsync("rsync://localhost/repo/alice/file.txt"),
+            &Bytes::from("example content"),
+        );
+        let mut delta = PublishDelta::empty();
+        delta.add_publish(file.as_publish());
+
+        let event = RepositoryEvent::publish(publisher, &delta);
+        assert_eq!(event.event_type(), RepositoryEventType::Publish);
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "publish");
+        assert_eq!(json["publisher"], "alice");
+        assert_eq!(json["published"][0], "rsync://localhost/repo/alice/file.txt");
+        assert!(json["withdrawn"].as_array().unwrap().is_empty());
+
+        let parsed: RepositoryEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, event);
+    }
+
+    #[test]
+    fn parse_webhook_config() {
+        let config: RepositoryWebhookConfig = toml::from_str(r#"url = "https://example.com/hook""#).unwrap();
+        assert_eq!(config.events, RepositoryEventType::all());
+
+        let config: RepositoryWebhookConfig = toml::from_str(
+            r#"
+            url = "https://example.com/hook"
+            events = [ "rrdp_update" ]
+            "#,
+        )
+        .unwrap();
+
+        let notifier = RepositoryNotifier::build(&[config]);
+        assert!(notifier.is_enabled(RepositoryEventType::RrdpUpdate));
+        assert!(!notifier.is_enabled(RepositoryEventType::Publish));
+    }
+
+    #[tokio::test]
+    async fn post_events_in_order() {
+        let (url, received) = test::start_webhook_receiver(StatusCode::OK);
+        let (rrdp_url, rrdp_received) = test::start_webhook_receiver(StatusCode::OK);
+        let notifier = RepositoryNotifier::build(&[
+            RepositoryWebhookConfig {
+                url,
+                events: RepositoryEventType::all(),
+            },
+            RepositoryWebhookConfig {
+                url: rrdp_url,
+                events: vec![RepositoryEventType::RrdpUpdate],
+            },
+        ]);
+
+        let publishers = ["alice", "bob", "carol"];
+        for publisher in publishers.iter() {
+            let publisher = PublisherHandle::from_str(publisher).unwrap();
+            notifier.notify(RepositoryEvent::publish(publisher, &PublishDelta::empty()));
+        }
+
+        let payloads = test::wait_for_webhook_payloads(&received, publishers.len()).await;
+        let posted: Vec<_> = payloads
+            .iter()
+            .map(|json| json["publisher"].as_str().unwrap())
+            .collect();
+        assert_eq!(posted, publishers);
+        assert!(rrdp_received.lock().unwrap().is_empty());
+    }
+}
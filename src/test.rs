@@ -1,18 +1,23 @@
 //! Helper functions for testing Krill.
 
 use std::{
+    convert::Infallible,
     fs,
     fs::File,
     io::Write,
     path::{Path, PathBuf},
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
 use bytes::Bytes;
 
-use hyper::StatusCode;
+use hyper::{
+    server::conn::AddrIncoming,
+    service::{make_service_fn, service_fn},
+    StatusCode,
+};
 use tokio::time::{sleep, timeout};
 
 use rpki::{
@@ -28,24 +33,31 @@ use rpki::{
 
 use crate::{
     cli::{
-        options::{BulkCaCommand, CaCommand, Command, Options, PubServerCommand},
+        options::{BackupCommand, BulkCaCommand, CaCommand, Command, KeysCommand, Options, PubServerCommand},
         report::{ApiResponse, ReportFormat},
         {Error, KrillClient},
     },
     commons::{
         api::{
-            self, AddChildRequest, AspaCustomer, AspaDefinition, AspaDefinitionList, AspaProvidersUpdate, BgpSecAsnKey,
-            BgpSecCsrInfoList, BgpSecDefinition, CertAuthInfo, CertAuthInit, CertifiedKeyInfo, ConfiguredRoa,
-            ConfiguredRoas, ObjectName, ParentCaContact, ParentCaReq, ParentStatuses, PublicationServerUris,
-            PublisherDetails, PublisherList, ResourceClassKeysInfo, RoaConfiguration, RoaConfigurationUpdates,
-            RoaPayload, RtaList, RtaName, RtaPrepResponse, TypedPrefix, UpdateChildRequest,
+            self, AddChildRequest, AspaCustomer, AspaDefinition, AspaDefinitionList, AspaDefinitionUpdates,
+            AspaProvidersUpdate, BgpSecAsnKey, BgpSecCsrInfoList, BgpSecDefinition, CertAuthInfo, CertAuthInit,
+            CertifiedKeyInfo, ChildrenStats, ConfiguredRoa, ConfiguredRoas, IssuanceTimingOverrides, ObjectName,
+            ObjectsExpiry, ParentCaContact, ParentCaReq, ParentResponseFetch, ParentStatuses, ParentsStats,
+            PendingChildRequest, PendingChildRequestKind, PendingChildRequests, PublicationCheck,
+            PublicationServerUris, PublisherDetails, PublisherList, PublisherValidation, ResourceClassKeysInfo,
+            RetryPolicies, RetryPolicy, RoaConfiguration, RoaConfigurationUpdates, RoaImport, RoaImportReport,
+            RoaPayload, RoaProposalList, RtaList, RtaName, RtaPrepResponse, SignerMigrationStatus, Token, TypedPrefix,
+            UpdateChildRequest,
         },
         bgp::{Announcement, BgpAnalysisReport, BgpAnalysisSuggestion},
         crypto::SignSupport,
         util::httpclient,
     },
     daemon::{
-        ca::{ResourceTaggedAttestation, RtaContentRequest, RtaPrepareRequest},
+        ca::{
+            AspaUpdateDryRun, ResourceTaggedAttestation, RpkiSignedChecklist, RscContentRequest, RscFileHash,
+            RtaContentRequest, RtaPrepareRequest,
+        },
         config::Config,
         http::server,
     },
@@ -184,6 +196,7 @@ pub async fn start_krill_pubd(rrdp_delta_rrdp_delta_min_interval_seconds: u32) -
     let dir = tmp_dir();
     let mut config = test_config(&dir, false, false, false, true);
     config.rrdp_updates_config.rrdp_delta_interval_min_seconds = rrdp_delta_rrdp_delta_min_interval_seconds;
+    config.publisher_validation = PublisherValidation::Reject;
     init_config(&mut config);
     config.port = 3001;
 
@@ -262,6 +275,13 @@ pub async fn krill_admin_expect_error(command: Command) -> Error {
     }
 }
 
+pub async fn key_inventory() -> api::KeyInventory {
+    match krill_admin(Command::Keys(KeysCommand::List)).await {
+        ApiResponse::KeyInventory(inventory) => inventory,
+        _ => panic!("Expected key inventory"),
+    }
+}
+
 pub async fn cas_force_publish_all() {
     krill_admin(Command::Bulk(BulkCaCommand::ForcePublish)).await;
 }
@@ -294,6 +314,151 @@ pub async fn ca_unsuspend_child(ca: &CaHandle, child: &CaHandle) {
     .await;
 }
 
+pub async fn ca_child_suspension_exempt(ca: &CaHandle, child: &CaHandle, exempt: bool) {
+    let child_handle = child.convert();
+    krill_admin(Command::CertAuth(CaCommand::ChildUpdate(
+        ca.clone(),
+        child_handle,
+        UpdateChildRequest::suspension_exempt(exempt),
+    )))
+    .await;
+}
+
+pub async fn ca_child_manual_approval(ca: &CaHandle, child: &CaHandle, manual: bool) {
+    let child_handle = child.convert();
+    krill_admin(Command::CertAuth(CaCommand::ChildUpdate(
+        ca.clone(),
+        child_handle,
+        UpdateChildRequest::manual_approval(manual),
+    )))
+    .await;
+}
+
+pub async fn ca_child_requests(ca: &CaHandle, child: &CaHandle) -> PendingChildRequests {
+    match krill_admin(Command::CertAuth(CaCommand::ChildRequests(ca.clone(), child.convert()))).await {
+        ApiResponse::ChildRequests(requests) => requests,
+        _ => panic!("Expected pending child requests"),
+    }
+}
+
+/// Waits for a request of the given kind from the child to be held back by
+/// the parent, and returns it. The child is refreshed so that it asks.
+pub async fn ca_child_request_becomes_pending(
+    ca: &CaHandle,
+    child: &CaHandle,
+    kind: PendingChildRequestKind,
+) -> Option<PendingChildRequest> {
+    for _ in 0..30_u8 {
+        if let Some(request) = ca_child_requests(ca, child).await.iter().find(|req| req.kind == kind) {
+            return Some(request.clone());
+        }
+        cas_refresh_single(child).await;
+        sleep_seconds(1).await
+    }
+    None
+}
+
+pub async fn ca_child_request_approve(
+    ca: &CaHandle,
+    child: &CaHandle,
+    kind: PendingChildRequestKind,
+    key: KeyIdentifier,
+) {
+    krill_admin(Command::CertAuth(CaCommand::ChildRequestApprove(
+        ca.clone(),
+        child.convert(),
+        kind,
+        key,
+    )))
+    .await;
+}
+
+pub async fn ca_child_issuance_policy(ca: &CaHandle) -> api::ChildIssuancePolicy {
+    match krill_admin(Command::CertAuth(CaCommand::ChildIssuancePolicyShow(ca.clone()))).await {
+        ApiResponse::ChildIssuancePolicy(policy) => policy,
+        _ => panic!("Expected child issuance policy"),
+    }
+}
+
+pub async fn ca_child_issuance_policy_update(ca: &CaHandle, policy: api::ChildIssuancePolicy) {
+    krill_admin(Command::CertAuth(CaCommand::ChildIssuancePolicyUpdate(
+        ca.clone(),
+        policy,
+    )))
+    .await;
+}
+
+pub async fn ca_child_issuance_policy_update_expect_error(ca: &CaHandle, policy: api::ChildIssuancePolicy) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::ChildIssuancePolicyUpdate(
+        ca.clone(),
+        policy,
+    )))
+    .await
+}
+
+pub async fn ca_child_transfer_start(ca: &CaHandle, request: api::ResourceTransferRequest) {
+    krill_admin(Command::CertAuth(CaCommand::ChildTransferStart(ca.clone(), request))).await;
+}
+
+pub async fn ca_child_transfer_start_expect_error(ca: &CaHandle, request: api::ResourceTransferRequest) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::ChildTransferStart(ca.clone(), request))).await
+}
+
+pub async fn ca_child_transfers(ca: &CaHandle) -> api::ResourceTransferList {
+    match krill_admin(Command::CertAuth(CaCommand::ChildTransfers(ca.clone()))).await {
+        ApiResponse::ResourceTransfers(transfers) => transfers,
+        _ => panic!("Expected resource transfers"),
+    }
+}
+
+/// Waits for the most recent resource transfer of the CA to reach the
+/// given state.
+pub async fn ca_child_transfer_becomes(ca: &CaHandle, state: api::ResourceTransferState) -> bool {
+    for _ in 0..30_u8 {
+        if let Some(transfer) = ca_child_transfers(ca).await.transfers().last() {
+            if transfer.state() == state {
+                return true;
+            }
+        }
+        sleep_seconds(1).await
+    }
+    false
+}
+
+pub async fn ca_scheduled_change_add(ca: &CaHandle, request: api::ScheduledChangeRequest) {
+    krill_admin(Command::CertAuth(CaCommand::ScheduledChangeAdd(ca.clone(), request))).await;
+}
+
+pub async fn ca_scheduled_change_add_expect_error(ca: &CaHandle, request: api::ScheduledChangeRequest) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::ScheduledChangeAdd(ca.clone(), request))).await
+}
+
+pub async fn ca_scheduled_changes(ca: &CaHandle) -> api::ScheduledChangeList {
+    match krill_admin(Command::CertAuth(CaCommand::ScheduledChanges(ca.clone()))).await {
+        ApiResponse::ScheduledChanges(changes) => changes,
+        _ => panic!("Expected scheduled changes"),
+    }
+}
+
+pub async fn ca_scheduled_change_cancel(ca: &CaHandle, id: u64) {
+    krill_admin(Command::CertAuth(CaCommand::ScheduledChangeCancel(ca.clone(), id))).await;
+}
+
+pub async fn ca_scheduled_change_cancel_expect_error(ca: &CaHandle, id: u64) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::ScheduledChangeCancel(ca.clone(), id))).await
+}
+
+/// Waits until the CA has the given number of pending scheduled changes.
+pub async fn ca_scheduled_changes_become(ca: &CaHandle, nr: usize) -> bool {
+    for _ in 0..30_u8 {
+        if ca_scheduled_changes(ca).await.changes().len() == nr {
+            return true;
+        }
+        sleep_seconds(1).await
+    }
+    false
+}
+
 pub async fn init_ca(ca: &CaHandle) {
     krill_admin(Command::CertAuth(CaCommand::Init(CertAuthInit::new(ca.clone())))).await;
 }
@@ -303,7 +468,47 @@ pub async fn init_ca_krill2(ca: &CaHandle) {
 }
 
 pub async fn delete_ca(ca: &CaHandle) {
-    krill_admin(Command::CertAuth(CaCommand::Delete(ca.clone()))).await;
+    krill_admin(Command::CertAuth(CaCommand::Delete(ca.clone(), false))).await;
+}
+
+pub async fn delete_ca_expect_error(ca: &CaHandle) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::Delete(ca.clone(), false))).await
+}
+
+pub async fn delete_ca_force(ca: &CaHandle) {
+    krill_admin(Command::CertAuth(CaCommand::Delete(ca.clone(), true))).await;
+}
+
+pub async fn ca_export(ca: &CaHandle, include_key: bool) -> api::import::CaMigration {
+    match krill_admin(Command::CertAuth(CaCommand::Export(
+        ca.clone(),
+        true,
+        include_key,
+        None,
+    )))
+    .await
+    {
+        ApiResponse::CaExport(export) => export,
+        _ => panic!("Expected CA export"),
+    }
+}
+
+pub async fn cas_migrate(migration: api::import::CaMigration) -> api::import::CaMigrationReport {
+    match krill_admin(Command::Bulk(BulkCaCommand::Migrate(migration, false))).await {
+        ApiResponse::CaMigration(report) => report,
+        _ => panic!("Expected CA migration report"),
+    }
+}
+
+pub async fn cas_migrate_expect_error(migration: api::import::CaMigration) -> Error {
+    krill_admin_expect_error(Command::Bulk(BulkCaCommand::Migrate(migration, false))).await
+}
+
+pub async fn has_ca(ca: &CaHandle) -> bool {
+    match krill_admin(Command::CertAuth(CaCommand::List(None))).await {
+        ApiResponse::CertAuths(list) => list.cas().iter().any(|summary| summary.handle() == ca),
+        _ => panic!("Expected CA list"),
+    }
 }
 
 pub async fn ca_repo_update_rfc8181(ca: &CaHandle, response: idexchange::RepositoryResponse) {
@@ -428,10 +633,50 @@ pub async fn delete_parent(ca: &CaHandle, parent: &CaHandle) {
     krill_admin(Command::CertAuth(CaCommand::RemoveParent(ca.clone(), parent.convert()))).await;
 }
 
+pub async fn ca_roa_strategy(ca: &CaHandle) -> api::RoaIssuanceStrategyInfo {
+    match krill_admin(Command::CertAuth(CaCommand::RoaStrategyShow(ca.clone()))).await {
+        ApiResponse::RoaIssuanceStrategy(info) => info,
+        _ => panic!("Expected ROA issuance strategy"),
+    }
+}
+
+pub async fn ca_roa_strategy_update(ca: &CaHandle, strategy: Option<api::RoaIssuanceStrategy>) {
+    krill_admin(Command::CertAuth(CaCommand::RoaStrategyUpdate(ca.clone(), strategy))).await;
+}
+
+pub async fn ca_roa_strategy_update_expect_error(ca: &CaHandle, strategy: Option<api::RoaIssuanceStrategy>) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RoaStrategyUpdate(ca.clone(), strategy))).await
+}
+
+pub async fn ca_roa_autopilot(ca: &CaHandle) -> api::RoaAutoPilotInfo {
+    match krill_admin(Command::CertAuth(CaCommand::RoaAutoPilotShow(ca.clone()))).await {
+        ApiResponse::RoaAutoPilot(info) => info,
+        _ => panic!("Expected ROA auto-pilot policy"),
+    }
+}
+
+pub async fn ca_roa_autopilot_update(ca: &CaHandle, policy: Option<api::RoaAutoPilotPolicy>) {
+    krill_admin(Command::CertAuth(CaCommand::RoaAutoPilotUpdate(ca.clone(), policy))).await;
+}
+
+pub async fn ca_roa_autopilot_update_expect_error(ca: &CaHandle, policy: Option<api::RoaAutoPilotPolicy>) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RoaAutoPilotUpdate(ca.clone(), policy))).await
+}
+
 pub async fn ca_route_authorizations_update(ca: &CaHandle, updates: RoaConfigurationUpdates) {
     krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
         ca.clone(),
         updates,
+        false,
+    )))
+    .await;
+}
+
+pub async fn ca_route_authorizations_update_forced(ca: &CaHandle, updates: RoaConfigurationUpdates) {
+    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
+        ca.clone(),
+        updates,
+        true,
     )))
     .await;
 }
@@ -440,16 +685,18 @@ pub async fn ca_route_authorizations_update_krill2(ca: &CaHandle, updates: RoaCo
     krill2_admin(Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
         ca.clone(),
         updates,
+        false,
     )))
     .await;
 }
 
-pub async fn ca_route_authorizations_update_expect_error(ca: &CaHandle, updates: RoaConfigurationUpdates) {
+pub async fn ca_route_authorizations_update_expect_error(ca: &CaHandle, updates: RoaConfigurationUpdates) -> Error {
     krill_admin_expect_error(Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
         ca.clone(),
         updates,
+        false,
     )))
-    .await;
+    .await
 }
 
 pub async fn ca_configured_roas(ca: &CaHandle) -> ConfiguredRoas {
@@ -498,6 +745,172 @@ pub async fn expect_roa_objects(ca: &CaHandle, roas: &[RoaPayload]) {
     assert!(will_publish_embedded("published ROAs do not match expectations", ca, &expected_files).await);
 }
 
+pub async fn ca_route_authorizations_import(ca: &CaHandle, import: RoaImport, dry_run: bool) -> RoaImportReport {
+    match krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsImport(
+        ca.clone(),
+        import,
+        dry_run,
+        false,
+    )))
+    .await
+    {
+        ApiResponse::RoaImportReport(report) => report,
+        _ => panic!("Expected ROA import report"),
+    }
+}
+
+pub async fn ca_route_authorizations_propose(ca: &CaHandle, updates: RoaConfigurationUpdates) {
+    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsPropose(
+        ca.clone(),
+        updates,
+    )))
+    .await;
+}
+
+pub async fn ca_route_authorizations_proposals(ca: &CaHandle) -> RoaProposalList {
+    match krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsProposals(ca.clone()))).await {
+        ApiResponse::RoaProposals(proposals) => proposals,
+        _ => panic!("Expected ROA proposals"),
+    }
+}
+
+pub async fn ca_route_authorizations_approve(ca: &CaHandle, id: u64) {
+    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsApprove(
+        ca.clone(),
+        id,
+        false,
+    )))
+    .await;
+}
+
+/// Approves a ROA proposal using the given token, as the proposer may not
+/// approve their own proposal.
+pub async fn ca_route_authorizations_approve_as(token: &Token, ca: &CaHandle, id: u64) {
+    let command = Command::CertAuth(CaCommand::RouteAuthorizationsApprove(ca.clone(), id, false));
+    let options = Options::new(
+        service_uri(KRILL_SERVER_URI),
+        token.as_ref(),
+        ReportFormat::Json,
+        command,
+    );
+    if let Err(e) = KrillClient::process(options).await {
+        panic!("{}", e)
+    }
+}
+
+/// Sends a command using the given token, rather than the admin token.
+pub async fn krill_as(token: &Token, command: Command) -> Result<ApiResponse, Error> {
+    let options = Options::new(
+        service_uri(KRILL_SERVER_URI),
+        token.as_ref(),
+        ReportFormat::Json,
+        command,
+    );
+    KrillClient::process(options).await
+}
+
+pub async fn ca_reconcile(ca: &CaHandle, repair: bool) -> api::Reconciliation {
+    match krill_admin(Command::CertAuth(CaCommand::Reconcile(ca.clone(), repair))).await {
+        ApiResponse::Reconciliation(reconciliation) => reconciliation,
+        _ => panic!("Expected reconciliation"),
+    }
+}
+
+pub async fn ca_reconcile_last(ca: &CaHandle) -> api::Reconciliation {
+    match krill_admin(Command::CertAuth(CaCommand::ReconcileLast(ca.clone()))).await {
+        ApiResponse::Reconciliation(reconciliation) => reconciliation,
+        _ => panic!("Expected reconciliation"),
+    }
+}
+
+pub async fn ca_route_authorizations_approve_expect_error(ca: &CaHandle, id: u64) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RouteAuthorizationsApprove(
+        ca.clone(),
+        id,
+        false,
+    )))
+    .await
+}
+
+pub async fn ca_route_authorizations_approve_forced(ca: &CaHandle, id: u64) {
+    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsApprove(
+        ca.clone(),
+        id,
+        true,
+    )))
+    .await;
+}
+
+/// Creates a named API token with the given scope, and returns the secret.
+#[cfg(feature = "multi-user")]
+pub async fn api_token_create(name: &str, scope: api::ApiTokenScope) -> Token {
+    let request = api::ApiTokenRequest::new(name.to_string(), scope, vec![], None);
+    let created: api::ApiTokenCreated = httpclient::post_json_with_response(
+        &format!("{}api/v1/tokens", KRILL_SERVER_URI),
+        request,
+        Some(&Token::from("secret")),
+    )
+    .await
+    .unwrap();
+    created.token().clone()
+}
+
+pub async fn ca_route_authorizations_reject(ca: &CaHandle, id: u64) {
+    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsReject(ca.clone(), id))).await;
+}
+
+pub async fn ca_issuance_timing(ca: &CaHandle) -> IssuanceTimingOverrides {
+    match krill_admin(Command::CertAuth(CaCommand::IssuanceTimingShow(ca.clone()))).await {
+        ApiResponse::IssuanceTiming(overrides) => overrides,
+        _ => panic!("Expected issuance timing overrides"),
+    }
+}
+
+pub async fn ca_issuance_timing_update(ca: &CaHandle, overrides: IssuanceTimingOverrides) {
+    krill_admin(Command::CertAuth(CaCommand::IssuanceTimingUpdate(
+        ca.clone(),
+        overrides,
+    )))
+    .await;
+}
+
+pub async fn ca_issuance_timing_update_expect_error(ca: &CaHandle, overrides: IssuanceTimingOverrides) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::IssuanceTimingUpdate(
+        ca.clone(),
+        overrides,
+    )))
+    .await
+}
+
+pub async fn ca_retry_policies(ca: &CaHandle) -> RetryPolicies {
+    match krill_admin(Command::CertAuth(CaCommand::RetryPolicyShow(ca.clone()))).await {
+        ApiResponse::RetryPolicies(policies) => policies,
+        _ => panic!("Expected retry policies"),
+    }
+}
+
+pub async fn ca_retry_policy_update(ca: &CaHandle, parent: Option<ParentHandle>, policy: RetryPolicy) {
+    krill_admin(Command::CertAuth(CaCommand::RetryPolicyUpdate(
+        ca.clone(),
+        parent,
+        policy,
+    )))
+    .await;
+}
+
+pub async fn ca_retry_policy_update_expect_error(
+    ca: &CaHandle,
+    parent: Option<ParentHandle>,
+    policy: RetryPolicy,
+) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RetryPolicyUpdate(
+        ca.clone(),
+        parent,
+        policy,
+    )))
+    .await
+}
+
 pub async fn ca_route_authorizations_suggestions(ca: &CaHandle) -> BgpAnalysisSuggestion {
     match krill_admin(Command::CertAuth(CaCommand::BgpAnalysisSuggest(ca.clone(), None))).await {
         ApiResponse::BgpAnalysisSuggestions(suggestion) => suggestion,
@@ -557,10 +970,34 @@ pub async fn expect_aspa_definitions(ca: &CaHandle, expected_aspas: AspaDefiniti
     }
 }
 
+pub async fn ca_aspas_import(ca: &CaHandle, definitions: AspaDefinitionList) -> AspaDefinitionUpdates {
+    match krill_admin(Command::CertAuth(CaCommand::AspasImport(ca.clone(), definitions))).await {
+        ApiResponse::AspaDefinitionUpdates(updates) => updates,
+        _ => panic!("Expected AspaDefinitionUpdates"),
+    }
+}
+
 pub async fn ca_aspas_update(ca: &CaHandle, customer: AspaCustomer, update: AspaProvidersUpdate) {
     krill_admin(Command::CertAuth(CaCommand::AspasUpdate(ca.clone(), customer, update))).await;
 }
 
+pub async fn ca_aspas_update_dry_run(
+    ca: &CaHandle,
+    customer: AspaCustomer,
+    update: AspaProvidersUpdate,
+) -> AspaUpdateDryRun {
+    match krill_admin(Command::CertAuth(CaCommand::AspasUpdateDryRun(
+        ca.clone(),
+        customer,
+        update,
+    )))
+    .await
+    {
+        ApiResponse::AspaUpdateDryRun(dry_run) => dry_run,
+        _ => panic!("Expected ASPA update dry run"),
+    }
+}
+
 pub async fn ca_aspas_update_expect_error(ca: &CaHandle, customer: AspaCustomer, update: AspaProvidersUpdate) {
     krill_admin_expect_error(Command::CertAuth(CaCommand::AspasUpdate(ca.clone(), customer, update))).await;
 }
@@ -569,6 +1006,34 @@ pub async fn ca_aspas_remove(ca: &CaHandle, customer: AspaCustomer) {
     krill_admin(Command::CertAuth(CaCommand::AspasRemove(ca.clone(), customer))).await;
 }
 
+pub async fn ca_stats_children(ca: &CaHandle) -> ChildrenStats {
+    match krill_admin(Command::CertAuth(CaCommand::ChildStats(ca.clone()))).await {
+        ApiResponse::ChildrenReport(stats) => stats,
+        _ => panic!("Expected children stats"),
+    }
+}
+
+pub async fn ca_stats_parents(ca: &CaHandle) -> ParentsStats {
+    match krill_admin(Command::CertAuth(CaCommand::ParentStats(ca.clone()))).await {
+        ApiResponse::ParentsReport(stats) => stats,
+        _ => panic!("Expected parents stats"),
+    }
+}
+
+pub async fn ca_publication_check(ca: &CaHandle) -> PublicationCheck {
+    match krill_admin(Command::CertAuth(CaCommand::RepoCheck(ca.clone()))).await {
+        ApiResponse::PublicationCheck(check) => check,
+        _ => panic!("Expected publication check"),
+    }
+}
+
+pub async fn ca_objects_expiry(ca: &CaHandle, window_hours: Option<u32>) -> ObjectsExpiry {
+    match krill_admin(Command::CertAuth(CaCommand::Expiry(ca.clone(), window_hours))).await {
+        ApiResponse::ObjectsExpiry(expiry) => expiry,
+        _ => panic!("Expected objects expiry"),
+    }
+}
+
 pub async fn ca_details(ca: &CaHandle) -> CertAuthInfo {
     match krill_admin(Command::CertAuth(CaCommand::Show(ca.clone()))).await {
         ApiResponse::CertAuthInfo(inf) => inf,
@@ -583,6 +1048,19 @@ pub async fn ca_details_krill2(ca: &CaHandle) -> CertAuthInfo {
     }
 }
 
+pub async fn rsc_sign(ca: &CaHandle, resources: ResourceSet, checklist: Vec<RscFileHash>) -> RpkiSignedChecklist {
+    let request = RscContentRequest::new(resources, SignSupport::sign_validity_days(14), checklist);
+    match krill_admin(Command::CertAuth(CaCommand::RscSign(ca.clone(), request, None))).await {
+        ApiResponse::Rsc(rsc) => rsc,
+        _ => panic!("Expected RSC"),
+    }
+}
+
+pub async fn rsc_sign_expect_error(ca: &CaHandle, resources: ResourceSet, checklist: Vec<RscFileHash>) -> Error {
+    let request = RscContentRequest::new(resources, SignSupport::sign_validity_days(14), checklist);
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RscSign(ca.clone(), request, None))).await
+}
+
 pub async fn rta_sign_sign(
     ca: CaHandle,
     name: RtaName,
@@ -783,6 +1261,36 @@ pub async fn publisher_request(ca: &CaHandle) -> idexchange::PublisherRequest {
     }
 }
 
+pub async fn ca_repo_id_roll_init(ca: &CaHandle) -> idexchange::PublisherRequest {
+    match krill_admin(Command::CertAuth(CaCommand::RepoIdRollInit(ca.clone()))).await {
+        ApiResponse::Rfc8183PublisherRequest(req) => req,
+        _ => panic!("Expected publisher request"),
+    }
+}
+
+pub async fn ca_repo_id_roll_request(ca: &CaHandle) -> idexchange::PublisherRequest {
+    match krill_admin(Command::CertAuth(CaCommand::RepoIdRollRequest(ca.clone()))).await {
+        ApiResponse::Rfc8183PublisherRequest(req) => req,
+        _ => panic!("Expected publisher request"),
+    }
+}
+
+pub async fn ca_repo_id_roll_activate(ca: &CaHandle) {
+    krill_admin(Command::CertAuth(CaCommand::RepoIdRollActivate(ca.clone()))).await;
+}
+
+pub async fn ca_repo_id_roll_activate_expect_error(ca: &CaHandle) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RepoIdRollActivate(ca.clone()))).await
+}
+
+pub async fn publisher_id_cert_stage(publisher: PublisherHandle, req: idexchange::PublisherRequest) {
+    krill_embedded_pubd_admin(PubServerCommand::StagePublisherIdCert(publisher, Some(req))).await;
+}
+
+pub async fn publisher_id_cert_activate(publisher: PublisherHandle) {
+    krill_embedded_pubd_admin(PubServerCommand::ActivatePublisherIdCert(publisher)).await;
+}
+
 pub async fn publisher_request_krill2(ca: &CaHandle) -> idexchange::PublisherRequest {
     match krill2_admin(Command::CertAuth(CaCommand::RepoPublisherRequest(ca.clone()))).await {
         ApiResponse::Rfc8183PublisherRequest(req) => req,
@@ -808,6 +1316,49 @@ where
     let _result = fs::remove_dir_all(path);
 }
 
+/// The JSON documents received by a webhook receiver.
+pub type WebhookPayloads = Arc<Mutex<Vec<serde_json::Value>>>;
+
+/// Starts an HTTP server on a random local port which remembers the JSON
+/// documents posted to it, and responds with the given status. Returns the
+/// URL to post to, and the documents received.
+pub fn start_webhook_receiver(status: StatusCode) -> (String, WebhookPayloads) {
+    let received = WebhookPayloads::default();
+    let incoming = AddrIncoming::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let url = format!("http://{}/webhook", incoming.local_addr());
+
+    let service_received = received.clone();
+    let service = make_service_fn(move |_| {
+        let received = service_received.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| {
+                let received = received.clone();
+                async move {
+                    let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                    received.lock().unwrap().push(serde_json::from_slice(&body).unwrap());
+                    let response = hyper::Response::builder().status(status).body(hyper::Body::empty());
+                    Ok::<_, Infallible>(response.unwrap())
+                }
+            }))
+        }
+    });
+    tokio::spawn(hyper::Server::builder(incoming).serve(service));
+
+    (url, received)
+}
+
+/// Waits up to 10 seconds until a webhook receiver got the given number of
+/// documents, and returns the documents received.
+pub async fn wait_for_webhook_payloads(received: &WebhookPayloads, count: usize) -> Vec<serde_json::Value> {
+    for _ in 0..100 {
+        if received.lock().unwrap().len() >= count {
+            break;
+        }
+        sleep_millis(100).await;
+    }
+    received.lock().unwrap().clone()
+}
+
 pub fn tmp_dir() -> PathBuf {
     sub_dir(&PathBuf::from("work"))
 }
@@ -899,6 +1450,60 @@ pub async fn repo_update_krill2(ca: &CaHandle, response: idexchange::RepositoryR
     krill2_admin(command).await;
 }
 
+pub async fn ca_class_repo_update(
+    ca: &CaHandle,
+    rcn: &ResourceClassName,
+    response: Option<idexchange::RepositoryResponse>,
+) {
+    let command = Command::CertAuth(CaCommand::ClassRepoUpdate(ca.clone(), rcn.clone(), response));
+    krill_admin(command).await;
+}
+
+pub async fn ca_repo_statuses(ca: &CaHandle) -> api::RepoStatuses {
+    match krill_admin(Command::CertAuth(CaCommand::RepoStatuses(ca.clone()))).await {
+        ApiResponse::RepoStatuses(statuses) => statuses,
+        _ => panic!("Expected repository statuses"),
+    }
+}
+
+pub async fn ca_repo_migration_status(ca: &CaHandle) -> api::RepoMigrationStatus {
+    match krill_admin(Command::CertAuth(CaCommand::RepoMigrationStatus(ca.clone()))).await {
+        ApiResponse::RepoMigrationStatus(status) => status,
+        _ => panic!("Expected repository migration status"),
+    }
+}
+
+/// Activates a repository migration as soon as the new repository serves
+/// the objects of the CA. Returns false if it did not within 30 seconds.
+pub async fn ca_repo_migration_activate_when_ready(ca: &CaHandle) -> bool {
+    for _ in 0..30 {
+        let command = Command::CertAuth(CaCommand::RepoMigrationActivate(ca.clone()));
+        if krill_as(&Token::from("secret"), command).await.is_ok() {
+            return true;
+        }
+        sleep_seconds(1).await;
+    }
+    false
+}
+
+pub async fn repo_migrate(ca: &CaHandle, response: idexchange::RepositoryResponse) -> api::RepoMigrationReport {
+    match krill_admin(Command::CertAuth(CaCommand::RepoMigrate(ca.clone(), response))).await {
+        ApiResponse::RepoMigrationReport(report) => report,
+        _ => panic!("Expected repository migration report"),
+    }
+}
+
+pub async fn repo_migration_abort(ca: &CaHandle) -> api::RepoMigrationStatus {
+    match krill_admin(Command::CertAuth(CaCommand::RepoMigrationAbort(ca.clone()))).await {
+        ApiResponse::RepoMigrationStatus(status) => status,
+        _ => panic!("Expected repository migration status"),
+    }
+}
+
+pub async fn repo_migration_abort_expect_error(ca: &CaHandle) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RepoMigrationAbort(ca.clone()))).await
+}
+
 pub async fn embedded_repository_response(publisher: PublisherHandle) -> idexchange::RepositoryResponse {
     let command = PubServerCommand::RepositoryResponse(publisher);
     match krill_embedded_pubd_admin(command).await {
@@ -926,6 +1531,11 @@ pub async fn dedicated_repo_add_publisher(req: idexchange::PublisherRequest) {
     krill_dedicated_pubd_admin(command).await;
 }
 
+pub async fn dedicated_repo_remove_publisher(publisher: PublisherHandle) {
+    let command = PubServerCommand::RemovePublisher(publisher);
+    krill_dedicated_pubd_admin(command).await;
+}
+
 pub async fn set_up_ca_with_repo(ca: &CaHandle) {
     init_ca(ca).await;
 
@@ -948,6 +1558,31 @@ pub async fn import_cas(structure: api::import::Structure) {
     }
 }
 
+pub async fn import_cas_expect_error(structure: api::import::Structure) -> Error {
+    krill_admin_expect_error(Command::Bulk(BulkCaCommand::Import(structure))).await
+}
+
+pub async fn backup_create() -> api::BackupInfo {
+    match krill_admin(Command::Backup(BackupCommand::Create)).await {
+        ApiResponse::BackupInfo(info) => info,
+        _ => panic!("Expected backup info"),
+    }
+}
+
+pub async fn backups() -> api::BackupList {
+    match krill_admin(Command::Backup(BackupCommand::List)).await {
+        ApiResponse::BackupList(list) => list,
+        _ => panic!("Expected backup list"),
+    }
+}
+
+pub async fn backup_restore(name: &str, force: bool) -> api::BackupInfo {
+    match krill_admin(Command::Backup(BackupCommand::Restore(name.to_string(), force))).await {
+        ApiResponse::BackupInfo(info) => info,
+        _ => panic!("Expected backup info"),
+    }
+}
+
 pub async fn expected_mft_and_crl(ca: &CaHandle, rcn: &ResourceClassName) -> Vec<String> {
     let rc_key = ca_key_for_rcn(ca, rcn).await;
     let mft_file = rc_key.incoming_cert().mft_name().to_string();
@@ -1041,6 +1676,45 @@ pub async fn set_up_ca_under_parent_with_resources(ca: &CaHandle, parent: &CaHan
     assert!(ca_contains_resources(ca, resources).await);
 }
 
+/// Like [set_up_ca_under_parent_with_resources], but lets Krill fetch the
+/// parent response XML from the API of the (local) parent.
+pub async fn set_up_ca_under_parent_with_fetched_response(ca: &CaHandle, parent: &CaHandle, resources: &ResourceSet) {
+    let child_request = request(ca).await;
+    add_child_rfc6492(parent.convert(), ca.convert(), child_request, resources.clone()).await;
+
+    let uri = format!(
+        "{}api/v1/cas/{}/children/{}/parent_response.xml",
+        KRILL_SERVER_URI, parent, ca
+    );
+
+    // The test config only allows fetching from 'localhost', so the same
+    // response cannot be fetched using the loopback address.
+    let not_allowed = uri.replace("localhost", "127.0.0.1");
+    let fetch = ParentResponseFetch::new(uri::Https::from_str(&not_allowed).unwrap(), Some(Token::from("secret")));
+    match krill_admin_expect_error(Command::CertAuth(CaCommand::AddParentFetch(
+        ca.clone(),
+        parent.convert(),
+        fetch,
+    )))
+    .await
+    {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+            assert_eq!(res.label(), "ca-parent-response-fetch");
+        }
+        e => panic!("Expected parent response fetch error, got: {}", e),
+    }
+
+    let fetch = ParentResponseFetch::new(uri::Https::from_str(&uri).unwrap(), Some(Token::from("secret")));
+    krill_admin(Command::CertAuth(CaCommand::AddParentFetch(
+        ca.clone(),
+        parent.convert(),
+        fetch,
+    )))
+    .await;
+
+    assert!(ca_contains_resources(ca, resources).await);
+}
+
 pub async fn ca_roll_init(ca: &CaHandle) {
     krill_admin(Command::CertAuth(CaCommand::KeyRollInit(ca.clone()))).await;
 }
@@ -1049,6 +1723,20 @@ pub async fn ca_roll_activate(ca: &CaHandle) {
     krill_admin(Command::CertAuth(CaCommand::KeyRollActivate(ca.clone()))).await;
 }
 
+pub async fn ca_signer_migration_status(ca: &CaHandle) -> SignerMigrationStatus {
+    match krill_admin(Command::CertAuth(CaCommand::KeyRollSigners(ca.clone()))).await {
+        ApiResponse::SignerMigrationStatus(status) => status,
+        _ => panic!("Expected signer migration status"),
+    }
+}
+
+pub async fn ca_signer_migrate(ca: &CaHandle) -> SignerMigrationStatus {
+    match krill_admin(Command::CertAuth(CaCommand::KeyRollMigrate(ca.clone()))).await {
+        ApiResponse::SignerMigrationStatus(status) => status,
+        _ => panic!("Expected signer migration status"),
+    }
+}
+
 pub async fn state_becomes_new_key(ca: &CaHandle) -> bool {
     for _ in 0..30_u8 {
         let ca = ca_details(ca).await;
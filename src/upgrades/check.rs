@@ -0,0 +1,301 @@
+//! Checking an upgrade before doing it.
+//!
+//! Operators can find out which data migrations an upgrade to this version
+//! would run, how much data is involved, whether there is enough disk space
+//! for the migrated data, and roughly how long the migration will take. The
+//! migration can also be run against a copy of the data to validate it, and
+//! to measure the time it takes, without touching the data in use.
+
+use std::{
+    fmt,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    commons::util::{file, KrillVersion},
+    constants::{CASERVER_DIR, PUBSERVER_CONTENT_DIR, PUBSERVER_DIR, UPGRADE_CHECK_FILES_PER_SECOND},
+    daemon::config::Config,
+};
+
+use super::{
+    check_no_downgrade, check_no_old_ta, finalise_data_migration, prepare_upgrade_data_migrations, unsupported_upgrade,
+    upgrade_versions, PrepareUpgradeError, UpgradeMode, UpgradeResult, UpgradeVersions,
+};
+
+//------------ DataMigration -------------------------------------------------
+
+/// A migration of stored data that an upgrade will run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DataMigration {
+    /// The command and event history of CAs and the Publication Server
+    /// changed format in 0.10.0.
+    History0_10,
+
+    /// The content of the Publication Server moved in 0.12.0.
+    RepositoryContentPre0_12,
+
+    /// The content of the Publication Server changed format in 0.13.0.
+    RepositoryContent0_12,
+}
+
+impl DataMigration {
+    /// Returns the migrations needed for an upgrade from the given version,
+    /// given the data which exists in the data dir.
+    fn planned(from: &KrillVersion, config: &Config) -> Vec<Self> {
+        let repository_content = config.data_dir.join(PUBSERVER_CONTENT_DIR).exists();
+
+        let mut migrations = vec![];
+        if from < &KrillVersion::candidate(0, 10, 0, 1) {
+            migrations.push(DataMigration::History0_10);
+            if repository_content {
+                migrations.push(DataMigration::RepositoryContentPre0_12);
+            }
+        } else if from < &KrillVersion::candidate(0, 12, 0, 2) {
+            if repository_content {
+                migrations.push(DataMigration::RepositoryContentPre0_12);
+            }
+        } else if from < &KrillVersion::candidate(0, 13, 0, 0) && repository_content {
+            migrations.push(DataMigration::RepositoryContent0_12);
+        }
+        migrations
+    }
+
+    /// The data dirs migrated.
+    fn dirs(&self) -> &'static [&'static str] {
+        match self {
+            DataMigration::History0_10 => &[CASERVER_DIR, PUBSERVER_DIR],
+            DataMigration::RepositoryContentPre0_12 | DataMigration::RepositoryContent0_12 => &[PUBSERVER_CONTENT_DIR],
+        }
+    }
+}
+
+impl fmt::Display for DataMigration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataMigration::History0_10 => write!(f, "migrate CA and Publication Server history to the 0.10 format"),
+            DataMigration::RepositoryContentPre0_12 => write!(f, "move Publication Server content (pre 0.12)"),
+            DataMigration::RepositoryContent0_12 => write!(f, "migrate Publication Server content to the 0.13 format"),
+        }
+    }
+}
+
+//------------ UpgradeCheck --------------------------------------------------
+
+/// What an upgrade of the data of a Krill instance to this version involves.
+#[derive(Debug)]
+pub struct UpgradeCheck {
+    versions: UpgradeVersions,
+    migrations: Vec<DataMigration>,
+
+    /// The number of files and bytes to migrate.
+    files: u64,
+    bytes: u64,
+
+    /// The bytes available on the file system of the data dir, if known.
+    available_bytes: Option<u64>,
+
+    /// The time taken to migrate a copy of the data, if it was validated.
+    validated: Option<Duration>,
+}
+
+impl UpgradeCheck {
+    pub fn versions(&self) -> &UpgradeVersions {
+        &self.versions
+    }
+
+    pub fn migrations(&self) -> &Vec<DataMigration> {
+        &self.migrations
+    }
+
+    pub fn needs_data_migration(&self) -> bool {
+        !self.migrations.is_empty()
+    }
+
+    /// The migrated data is prepared next to the current data, which is
+    /// archived rather than removed, so the migration needs about as much
+    /// free space as the data to migrate takes. Returns None if the free
+    /// space is not known.
+    pub fn has_sufficient_space(&self) -> Option<bool> {
+        self.available_bytes.map(|available| available >= self.bytes)
+    }
+
+    /// Returns the measured duration if the migration was validated, or
+    /// else a rough estimate based on the number of files to migrate.
+    pub fn duration(&self) -> Duration {
+        self.validated.unwrap_or_else(|| {
+            let files = self.files + UPGRADE_CHECK_FILES_PER_SECOND - 1;
+            Duration::from_secs(files / UPGRADE_CHECK_FILES_PER_SECOND)
+        })
+    }
+
+    pub fn is_validated(&self) -> bool {
+        self.validated.is_some()
+    }
+}
+
+impl fmt::Display for UpgradeCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Upgrade from {} to {}", self.versions.from(), self.versions.to())?;
+        if !self.needs_data_migration() {
+            return writeln!(f, "No data migrations are needed.");
+        }
+
+        writeln!(f, "Data migrations:")?;
+        for migration in &self.migrations {
+            writeln!(f, "  - {}", migration)?;
+        }
+        writeln!(f, "Data to migrate: {} files, {} bytes", self.files, self.bytes)?;
+        match self.available_bytes {
+            Some(available) => writeln!(
+                f,
+                "Disk space available: {} bytes ({})",
+                available,
+                if available >= self.bytes {
+                    "sufficient"
+                } else {
+                    "NOT sufficient"
+                }
+            )?,
+            None => writeln!(f, "Disk space available: unknown")?,
+        }
+        if self.validated.is_some() {
+            writeln!(
+                f,
+                "Migration validated on a copy of the data in {} seconds",
+                self.duration().as_secs()
+            )
+        } else {
+            writeln!(f, "Estimated duration: {} seconds", self.duration().as_secs())
+        }
+    }
+}
+
+//------------ Checking ------------------------------------------------------
+
+/// Inspects the data of the Krill instance using the given config, and
+/// reports what an upgrade to this version would do. Returns None if no
+/// upgrade is needed. Returns an error if the data cannot be upgraded.
+///
+/// This does not change any data, and can be used while Krill is running.
+pub fn check_upgrade(config: &Config) -> UpgradeResult<Option<UpgradeCheck>> {
+    check_no_downgrade(config)?;
+    check_no_old_ta(config)?;
+
+    let versions = match upgrade_versions(config) {
+        Some(versions) => versions,
+        None => return Ok(None),
+    };
+
+    if let Some(e) = unsupported_upgrade(versions.from()) {
+        return Err(e);
+    }
+
+    let migrations = DataMigration::planned(versions.from(), config);
+
+    let mut dirs: Vec<&str> = migrations
+        .iter()
+        .flat_map(|migration| migration.dirs().iter().copied())
+        .collect();
+    dirs.dedup();
+
+    let mut files = 0;
+    let mut bytes = 0;
+    for dir in dirs {
+        let (dir_files, dir_bytes) = file::dir_usage(&config.data_dir.join(dir))?;
+        files += dir_files;
+        bytes += dir_bytes;
+    }
+
+    Ok(Some(UpgradeCheck {
+        versions,
+        migrations,
+        files,
+        bytes,
+        available_bytes: file::available_space(&config.data_dir),
+        validated: None,
+    }))
+}
+
+/// Like [`check_upgrade`], but also runs the data migrations against a copy
+/// of the data in the given dir, which must not exist yet or be empty. The
+/// copy is removed if the migration succeeds, and left for inspection if it
+/// fails.
+pub fn check_upgrade_on_copy(config: &Config, copy_dir: &Path) -> UpgradeResult<Option<UpgradeCheck>> {
+    let mut check = match check_upgrade(config)? {
+        Some(check) => check,
+        None => return Ok(None),
+    };
+
+    if !check.needs_data_migration() {
+        return Ok(Some(check));
+    }
+
+    if copy_dir.starts_with(&config.data_dir) {
+        return Err(PrepareUpgradeError::custom(format!(
+            "Cannot copy data to '{}' inside the data dir",
+            copy_dir.to_string_lossy()
+        )));
+    }
+    if copy_dir.exists() && copy_dir.read_dir().map(|mut dir| dir.next().is_some()).unwrap_or(true) {
+        return Err(PrepareUpgradeError::custom(format!(
+            "Cannot copy data to '{}', it is not empty",
+            copy_dir.to_string_lossy()
+        )));
+    }
+
+    info!("Copying data to {} to validate the upgrade", copy_dir.to_string_lossy());
+    file::create_dir_all(copy_dir)?;
+    file::backup_dir(&config.data_dir, copy_dir).map_err(PrepareUpgradeError::custom)?;
+
+    let mut copy_config = config.clone();
+    copy_config.data_dir = copy_dir.to_path_buf();
+
+    let start = Instant::now();
+    if let Some(report) =
+        prepare_upgrade_data_migrations(UpgradeMode::PrepareToFinalise, Arc::new(copy_config.clone()))?
+    {
+        finalise_data_migration(report.versions(), &copy_config)?;
+    }
+    check.validated = Some(start.elapsed());
+
+    file::remove_dir_all(copy_dir)?;
+
+    Ok(Some(check))
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    use crate::test::tmp_dir;
+
+    #[test]
+    fn check_upgrade_0_12_1() {
+        let work_dir = tmp_dir();
+        let source = PathBuf::from("test-resources/migrations/v0_12_1/");
+        file::backup_dir(&source, &work_dir).unwrap();
+
+        let config = Config::test(&work_dir, false, false, false, false);
+
+        let check = check_upgrade(&config).unwrap().unwrap();
+        assert_eq!(check.migrations(), &vec![DataMigration::RepositoryContent0_12]);
+        assert!(!check.is_validated());
+
+        // Nothing was migrated by the check.
+        assert!(!config.upgrade_data_dir().exists());
+
+        let copy_dir = tmp_dir();
+        let check = check_upgrade_on_copy(&config, &copy_dir).unwrap().unwrap();
+        assert!(check.is_validated());
+        assert!(!copy_dir.exists());
+        assert!(!config.upgrade_data_dir().exists());
+
+        let _ = fs::remove_dir_all(work_dir);
+    }
+}
@@ -38,6 +38,15 @@ pub mod pre_0_10_0;
 #[allow(clippy::mutable_key_type)]
 pub mod pre_0_13_0;
 
+mod check;
+pub use self::check::*;
+
+mod storage;
+pub use self::storage::*;
+
+mod versions;
+pub use self::versions::*;
+
 pub type UpgradeResult<T> = Result<T, PrepareUpgradeError>;
 
 //------------ KrillUpgradeReport --------------------------------------------
@@ -108,6 +117,7 @@ pub enum PrepareUpgradeError {
     CannotLoadAggregate(MyHandle),
     IdExchange(String),
     OldTaMigration,
+    Downgrade(String),
     Custom(String),
 }
 
@@ -121,6 +131,7 @@ impl fmt::Display for PrepareUpgradeError {
             PrepareUpgradeError::CannotLoadAggregate(h) => format!("Cannot load: {}", h),
             PrepareUpgradeError::IdExchange(s) => format!("Could not use exchanged id info: {}", s),
             PrepareUpgradeError::OldTaMigration => "Your installation cannot be upgraded to Krill 0.13.0 or later because it includes a CA called \"ta\". These CAs were used for the preliminary Trust Anchor support needed by testbed and benchmark setups. They cannot be migrated to the production grade Trust Anchor support that was introduced in Krill 0.13.0. If you want to continue to use your existing installation we recommend that you downgrade to Krill 0.12.1 or earlier. If you want to operate a testbed using Krill 0.13.0 or later, then you can create a fresh testbed instead of migrating your existing testbed. If you believe that you should not have a CA called \"ta\" - i.e. it may have been left over from an abandoned testbed set up - then you can delete the \"ta\" directory under your krill data \"cas\" directory and restart Krill.".to_string(),
+            PrepareUpgradeError::Downgrade(s) => s.clone(),
             PrepareUpgradeError::Custom(s) => s.clone(),
         };
 
@@ -323,6 +334,9 @@ pub trait UpgradeStore {
 /// knowing that no changes are added to the event history at this time. After this,
 /// the migration will be finalised.
 pub fn prepare_upgrade_data_migrations(mode: UpgradeMode, config: Arc<Config>) -> UpgradeResult<Option<UpgradeReport>> {
+    // Never touch data which was last used by a newer version of Krill.
+    check_no_downgrade(config.as_ref())?;
+
     // First of all ALWAYS check the existing keys if the hsm feature is enabled.
     // Remember that this feature - although enabled by default from 0.10.x - may be enabled by installing
     // a new krill binary of the same Krill version as the the previous binary. In other words, we cannot
@@ -332,30 +346,15 @@ pub fn prepare_upgrade_data_migrations(mode: UpgradeMode, config: Arc<Config>) -
     #[cfg(feature = "hsm")]
     record_preexisting_openssl_keys_in_signer_mapper(config.clone())?;
 
-    // Check if there is any CA named "ta". If so, then we are trying to upgrade a Krill testbed
-    // or benchmark set up that uses the old deprecated trust anchor set up. These TAs cannot easily
-    // be migrated to the new setup in 0.13.0. Well.. it could be done, if there would be a strong use
-    // case to put in the effort, but there really isn't.
-    let ca_store_path = config.data_dir.join(CASERVER_DIR);
-    if ca_store_path.exists() {
-        let ca_kv_store = KeyValueStore::disk(&config.data_dir, CASERVER_DIR)?;
-        if ca_kv_store.has_scope("ta".to_string())? {
-            return Err(PrepareUpgradeError::OldTaMigration);
-        }
-    }
+    check_no_old_ta(config.as_ref())?;
 
     match upgrade_versions(config.as_ref()) {
         None => Ok(None),
         Some(versions) => {
             info!("Preparing upgrade from {} to {}", versions.from(), versions.to());
-            if versions.from < KrillVersion::release(0, 6, 0) {
-                let msg = "Cannot upgrade Krill installations from before version 0.6.0. Please upgrade to 0.8.1 first, then upgrade to 0.12.3, and then upgrade to this version.";
-                error!("{}", msg);
-                Err(PrepareUpgradeError::custom(msg))
-            } else if versions.from < KrillVersion::release(0, 9, 0) {
-                let msg = "Cannot upgrade Krill installations from before version 0.9.0. Please upgrade to 0.12.3 first, and then upgrade to this version.";
-                error!("{}", msg);
-                Err(PrepareUpgradeError::custom(msg))
+            if let Some(e) = unsupported_upgrade(versions.from()) {
+                error!("{}", e);
+                Err(e)
             } else if versions.from < KrillVersion::candidate(0, 10, 0, 1) {
                 let upgrade_data_dir = config.upgrade_data_dir();
                 if !upgrade_data_dir.exists() {
@@ -378,10 +377,6 @@ pub fn prepare_upgrade_data_migrations(mode: UpgradeMode, config: Arc<Config>) -
                 pre_0_10_0::CasMigration::prepare(mode, &config)?;
                 migrate_pre_0_12_pubd_objects(&config)?;
                 Ok(Some(UpgradeReport::new(true, versions)))
-            } else if versions.from < KrillVersion::candidate(0, 10, 0, 3) {
-                Err(PrepareUpgradeError::custom(
-                    "Cannot upgrade from 0.10.0 RC1 or RC2. Please contact rpki-team@nlnetlabs.nl",
-                ))
             } else if versions.from < KrillVersion::candidate(0, 12, 0, 2) {
                 info!(
                     "Krill upgrade from {} to {}. Check if publication server objects need migration.",
@@ -400,6 +395,37 @@ pub fn prepare_upgrade_data_migrations(mode: UpgradeMode, config: Arc<Config>) -
     }
 }
 
+/// Check if there is any CA named "ta". If so, then we are trying to upgrade a Krill testbed
+/// or benchmark set up that uses the old deprecated trust anchor set up. These TAs cannot easily
+/// be migrated to the new setup in 0.13.0. Well.. it could be done, if there would be a strong use
+/// case to put in the effort, but there really isn't.
+fn check_no_old_ta(config: &Config) -> UpgradeResult<()> {
+    let ca_store_path = config.data_dir.join(CASERVER_DIR);
+    if ca_store_path.exists() {
+        let ca_kv_store = KeyValueStore::disk(&config.data_dir, CASERVER_DIR)?;
+        if ca_kv_store.has_scope("ta".to_string())? {
+            return Err(PrepareUpgradeError::OldTaMigration);
+        }
+    }
+    Ok(())
+}
+
+/// Returns an error if data from the given Krill version cannot be upgraded
+/// to this version directly.
+fn unsupported_upgrade(from: &KrillVersion) -> Option<PrepareUpgradeError> {
+    if from < &KrillVersion::release(0, 6, 0) {
+        Some(PrepareUpgradeError::custom("Cannot upgrade Krill installations from before version 0.6.0. Please upgrade to 0.8.1 first, then upgrade to 0.12.3, and then upgrade to this version."))
+    } else if from < &KrillVersion::release(0, 9, 0) {
+        Some(PrepareUpgradeError::custom("Cannot upgrade Krill installations from before version 0.9.0. Please upgrade to 0.12.3 first, and then upgrade to this version."))
+    } else if from >= &KrillVersion::candidate(0, 10, 0, 1) && from < &KrillVersion::candidate(0, 10, 0, 3) {
+        Some(PrepareUpgradeError::custom(
+            "Cannot upgrade from 0.10.0 RC1 or RC2. Please contact rpki-team@nlnetlabs.nl",
+        ))
+    } else {
+        None
+    }
+}
+
 /// Migrate v0.12.x RepositoryContent to the new 0.13.0+ format.
 /// Apply any open WAL changes to the source first.
 fn migrate_0_12_pubd_objects(config: &Config) -> KrillResult<bool> {
@@ -0,0 +1,150 @@
+//! Protection against running older code against newer data.
+//!
+//! Every store is stamped with the version of Krill, and the version of the
+//! format of the stored data, that last used it. Krill refuses to start if
+//! any store was stamped by a newer version, because older code may not
+//! understand the data and could corrupt it. Operators can override this in
+//! an emergency with the `allow_downgrade` setting, or the environment
+//! variable `KRILL_ALLOW_DOWNGRADE`.
+
+use crate::{
+    commons::{
+        api::{SchemaVersions, StoreVersion},
+        util::KrillVersion,
+        KrillResult,
+    },
+    constants::KRILL_SCHEMA_VERSION,
+    daemon::{backup::BackupManager, config::Config},
+};
+
+use super::{PrepareUpgradeError, UpgradeResult};
+
+/// Returns the versions which last used the existing stores.
+pub fn schema_versions(config: &Config) -> KrillResult<SchemaVersions> {
+    let mut stores = vec![];
+    for (name_space, kv) in BackupManager::new(config).existing_stores()? {
+        stores.push(StoreVersion::new(name_space, kv.version()?, kv.schema_version()?));
+    }
+    Ok(SchemaVersions::for_stores(stores))
+}
+
+/// Returns an error if any store was last used by a newer version of Krill,
+/// unless downgrades are allowed in the config, in which case a warning is
+/// logged instead.
+pub fn check_no_downgrade(config: &Config) -> UpgradeResult<()> {
+    let versions = schema_versions(config)?;
+    let newer = versions.newer_stores();
+    if newer.is_empty() {
+        return Ok(());
+    }
+
+    let stores: Vec<String> = newer.iter().map(|store| store.to_string()).collect();
+    let newest = newer.iter().map(|store| store.krill_version()).max().unwrap(); // not empty
+
+    if config.allow_downgrade {
+        warn!(
+            "Running Krill {} (schema version {}) against data last used by newer versions, \
+             because downgrades are allowed. Stores: {}",
+            KrillVersion::code_version(),
+            KRILL_SCHEMA_VERSION,
+            stores.join("; ")
+        );
+        Ok(())
+    } else {
+        Err(PrepareUpgradeError::Downgrade(format!(
+            "Krill {} (schema version {}) cannot use data which was last used by a newer version, \
+             because it may not understand the data and could corrupt it. Stores: {}. \
+             Please use Krill {} or later. If you must use this version in an emergency, \
+             then make a backup of your data directory and set 'allow_downgrade = true' \
+             in your config file, or set the ENV variable KRILL_ALLOW_DOWNGRADE.",
+            KrillVersion::code_version(),
+            KRILL_SCHEMA_VERSION,
+            stores.join("; "),
+            newest
+        )))
+    }
+}
+
+/// Stamps all existing stores with the current Krill and schema version.
+/// This must be called after any data migrations are done.
+///
+/// Stores which were last used by a newer version, which is only possible
+/// if downgrades are allowed, keep their stamp. Otherwise running an older
+/// version once would let all older versions use the data without warning.
+pub fn stamp_store_versions(config: &Config) -> KrillResult<()> {
+    for (name_space, kv) in BackupManager::new(config).existing_stores()? {
+        let version = StoreVersion::new(name_space, kv.version()?, kv.schema_version()?);
+        if version.is_newer_than_code() {
+            warn!("Keeping the stamp of newer store {}", version);
+        } else if !kv.version_is_current()? || version.schema_version() != KRILL_SCHEMA_VERSION {
+            info!(
+                "Stamping store '{}' with Krill version {} and schema version {}",
+                name_space,
+                KrillVersion::code_version(),
+                KRILL_SCHEMA_VERSION
+            );
+            kv.version_set_current()?;
+        }
+    }
+    Ok(())
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    use crate::{
+        commons::eventsourcing::{KeyStoreKey, KeyValueStore},
+        constants::CASERVER_DIR,
+        test::tmp_dir,
+    };
+
+    #[test]
+    fn refuse_data_of_newer_version() {
+        let work_dir = tmp_dir();
+        let mut config = Config::test(&work_dir, false, false, false, false);
+
+        let cas = KeyValueStore::disk(&work_dir, CASERVER_DIR).unwrap();
+        check_no_downgrade(&config).unwrap();
+
+        cas.store(
+            &KeyStoreKey::simple("schema_version".to_string()),
+            &(KRILL_SCHEMA_VERSION + 1),
+        )
+        .unwrap();
+        assert!(matches!(
+            check_no_downgrade(&config),
+            Err(PrepareUpgradeError::Downgrade(_))
+        ));
+
+        config.allow_downgrade = true;
+        check_no_downgrade(&config).unwrap();
+
+        // Running with downgrades allowed keeps the newer stamp, so this
+        // version still refuses the data when they are no longer allowed.
+        stamp_store_versions(&config).unwrap();
+        assert_eq!(cas.schema_version().unwrap(), KRILL_SCHEMA_VERSION + 1);
+
+        config.allow_downgrade = false;
+        assert!(matches!(
+            check_no_downgrade(&config),
+            Err(PrepareUpgradeError::Downgrade(_))
+        ));
+
+        // Stores last used by an older version are stamped.
+        cas.store(
+            &KeyStoreKey::simple("schema_version".to_string()),
+            &(KRILL_SCHEMA_VERSION - 1),
+        )
+        .unwrap();
+        stamp_store_versions(&config).unwrap();
+        assert_eq!(cas.schema_version().unwrap(), KRILL_SCHEMA_VERSION);
+        check_no_downgrade(&config).unwrap();
+
+        let _ = fs::remove_dir_all(work_dir);
+    }
+}
@@ -9,7 +9,10 @@ use rpki::{
 };
 
 use krill::{
-    commons::api::{AspaCustomer, AspaDefinition, AspaDefinitionList, AspaProvidersUpdate, ObjectName},
+    commons::api::{
+        AspaCustomer, AspaDefinition, AspaDefinitionList, AspaProvidersUpdate, ObjectExpiryType, ObjectName,
+        PublicationCheckProtocol,
+    },
     test::*,
 };
 use rpki::repository::aspa::ProviderAs;
@@ -140,6 +143,45 @@ pub async fn functional_aspa() {
         expect_aspa_definitions(&ca, AspaDefinitionList::new(aspas)).await;
     }
 
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Dry run an update, and reject duplicate providers              #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+
+        let customer = AspaCustomer::from_str("AS65000").unwrap();
+        let aspa_update = AspaProvidersUpdate::new(
+            vec![ProviderAs::from_str("AS65006").unwrap()],
+            vec![ProviderAs::from_str("AS65002").unwrap()],
+        );
+
+        let dry_run = ca_aspas_update_dry_run(&ca, customer, aspa_update).await;
+
+        let updated_aspa = AspaDefinition::from_str("AS65000 => AS65003(v4), AS65005(v6), AS65006").unwrap();
+        assert_eq!(dry_run.definition(), Some(&updated_aspa));
+
+        let rc_updates = dry_run.resource_classes().get(&ResourceClassName::from(0)).unwrap();
+        assert_eq!(rc_updates.updated().len(), 1);
+        assert_eq!(rc_updates.updated()[0].definition(), &updated_aspa);
+        assert!(rc_updates.removed().is_empty());
+
+        let duplicate_update = AspaProvidersUpdate::new(
+            vec![
+                ProviderAs::from_str("AS65006(v4)").unwrap(),
+                ProviderAs::from_str("AS65006(v6)").unwrap(),
+            ],
+            vec![],
+        );
+        ca_aspas_update_expect_error(&ca, customer, duplicate_update).await;
+
+        // nothing was changed
+        let aspas = vec![AspaDefinition::from_str("AS65000 => AS65002, AS65003(v4), AS65005(v6)").unwrap()];
+        expect_aspa_objects(&ca, &aspas).await;
+        expect_aspa_definitions(&ca, AspaDefinitionList::new(aspas)).await;
+    }
+
     {
         info("##################################################################");
         info("#                                                                #");
@@ -163,6 +205,75 @@ pub async fn functional_aspa() {
         expect_aspa_definitions(&ca, AspaDefinitionList::new(aspas)).await;
     }
 
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Check the objects published by the CA, as seen over RRDP       #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+
+        // The RRDP files may be written shortly after the objects were
+        // published, so give it a few tries.
+        let mut check = ca_publication_check(&ca).await;
+        for _ in 0..5 {
+            if check.is_ok() {
+                break;
+            }
+            sleep_seconds(1).await;
+            check = ca_publication_check(&ca).await;
+        }
+
+        assert!(check.is_ok(), "publication check found issues:\n{}", check);
+        assert_eq!(check.sources().len(), 1);
+
+        let rrdp = &check.sources()[0];
+        assert_eq!(rrdp.protocol(), PublicationCheckProtocol::Rrdp);
+        assert!(rrdp.objects() >= 3); // mft, crl, aspa
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Show the expiry times of the objects published by the CA      #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+
+        let expiry = ca_objects_expiry(&ca, None).await;
+        assert_eq!(expiry.window_hours(), 4);
+        assert_eq!(expiry.expiring().count(), 0);
+
+        let types: Vec<_> = expiry.objects().iter().map(|object| object.object_type()).collect();
+        assert!(types.contains(&ObjectExpiryType::Manifest));
+        assert!(types.contains(&ObjectExpiryType::Crl));
+        assert!(types.contains(&ObjectExpiryType::Aspa));
+
+        // Everything expires within two years
+        let expiry = ca_objects_expiry(&ca, Some(2 * 365 * 24)).await;
+        assert_eq!(expiry.expiring().count(), expiry.objects().len());
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# All keys are held by the default signer, so there is nothing  #");
+        info("# to migrate                                                     #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+
+        let status = ca_signer_migration_status(&ca).await;
+        assert!(status.is_complete());
+        assert!(!status.keys().is_empty());
+        for key in status.keys() {
+            assert_eq!(key.signer(), Some(status.target_signer()));
+        }
+
+        let status = ca_signer_migrate(&ca).await;
+        assert!(status.is_complete());
+    }
+
     {
         info("##################################################################");
         info("#                                                                #");
@@ -311,5 +422,29 @@ pub async fn functional_aspa() {
         expect_aspa_definitions(&ca, AspaDefinitionList::new(vec![])).await;
     }
 
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Import a complete set of ASPAs, only changes are applied       #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+
+        let imported = AspaDefinitionList::from_csv("AS65000,AS65002,AS65003(v4),AS65005(v6)\n").unwrap();
+
+        let updates = ca_aspas_import(&ca, imported.clone()).await;
+        assert!(updates.contains_changes());
+        expect_aspa_objects(&ca, imported.definitions()).await;
+        expect_aspa_definitions(&ca, imported.clone()).await;
+
+        let updates = ca_aspas_import(&ca, imported).await;
+        assert!(!updates.contains_changes());
+
+        let updates = ca_aspas_import(&ca, AspaDefinitionList::new(vec![])).await;
+        assert!(updates.contains_changes());
+        expect_aspa_objects(&ca, &[]).await;
+        expect_aspa_definitions(&ca, AspaDefinitionList::new(vec![])).await;
+    }
+
     let _ = fs::remove_dir_all(krill_dir);
 }
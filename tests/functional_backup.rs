@@ -0,0 +1,104 @@
+//! Create a backup of a running Krill, and restore it. The restored data
+//! contains the state of the CAs and their keys at the time of the backup.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_backup() {
+    use std::{fs, path::Path};
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        cli::options::{BulkCaCommand, Command},
+        commons::{
+            api::{BulkJobRequest, BulkOperation, CaSelector, RoaConfigurationUpdates},
+            eventsourcing::AggregateStore,
+        },
+        constants::{BACKUP_DIR, CASERVER_DIR, JOBS_DIR, KEYS_DIR},
+        daemon::{backup::BackupManager, ca::CertAuth},
+        test::*,
+    };
+
+    fn copy_dir(from: &Path, to: &Path) {
+        fs::create_dir_all(to).unwrap();
+        for entry in fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+        }
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let roa_a = roa_configuration("10.0.0.0/24 => 65000");
+    let roa_b = roa_configuration("10.0.1.0/24 => 65000");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+    ca_route_authorizations_update(&ca1, RoaConfigurationUpdates::new(vec![roa_a.clone()], vec![])).await;
+
+    // Jobs and API tokens are included in the backup as well.
+    let bulk = BulkJobRequest::new(CaSelector::All, BulkOperation::Publish);
+    krill_admin(Command::Bulk(BulkCaCommand::JobStart(bulk))).await;
+    #[cfg(feature = "multi-user")]
+    api_token_create("backup", krill::commons::api::ApiTokenScope::ReadOnly).await;
+
+    // Make a backup while the server is running, and change the CA after.
+    let info = backup_create().await;
+    assert_eq!(backups().await.backups(), &vec![info.clone()]);
+    let keys_at_backup = file_names(&krill_dir.join(KEYS_DIR));
+    let jobs_at_backup = file_names(&krill_dir.join(JOBS_DIR));
+    assert!(!jobs_at_backup.is_empty());
+    #[cfg(feature = "multi-user")]
+    let tokens_at_backup = file_names(&krill_dir.join(krill::constants::API_TOKENS_DIR));
+
+    ca_route_authorizations_update(&ca1, RoaConfigurationUpdates::new(vec![roa_b.clone()], vec![])).await;
+    expect_configured_roas(&ca1, &[roa_a.clone(), roa_b]).await;
+
+    // Stage the restore. It is applied when Krill is started, and the running
+    // server keeps using its data directory, so apply it to a new data
+    // directory with a copy of the backups instead.
+    backup_restore(info.name(), false).await;
+
+    let restore_dir = tmp_dir();
+    copy_dir(&krill_dir.join(BACKUP_DIR), &restore_dir.join(BACKUP_DIR));
+    let config = test_config(&restore_dir, false, false, false, false);
+    BackupManager::new(&config).apply_pending_restore().unwrap();
+
+    let cas = AggregateStore::<CertAuth>::create(&config.storage(), CASERVER_DIR).unwrap();
+    let ca = cas.get_latest(&ca1).unwrap();
+    let roas: Vec<_> = ca.configured_roas().iter().map(|roa| roa.payload()).collect();
+    assert_eq!(roas, vec![roa_a.into_explicit_max_length().payload()]);
+
+    assert_eq!(file_names(&restore_dir.join(KEYS_DIR)), keys_at_backup);
+    assert_eq!(file_names(&restore_dir.join(JOBS_DIR)), jobs_at_backup);
+    #[cfg(feature = "multi-user")]
+    {
+        assert_eq!(tokens_at_backup.len(), 1);
+        assert_eq!(
+            file_names(&restore_dir.join(krill::constants::API_TOKENS_DIR)),
+            tokens_at_backup
+        );
+    }
+
+    // The restore is only applied once.
+    BackupManager::new(&config).apply_pending_restore().unwrap();
+    assert!(!restore_dir.join(BACKUP_DIR).join("restore-pending.json").exists());
+
+    let _ = fs::remove_dir_all(krill_dir);
+    let _ = fs::remove_dir_all(restore_dir);
+}
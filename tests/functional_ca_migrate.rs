@@ -0,0 +1,82 @@
+//! Migrate a CA using an export of the CA, including its identity key.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_ca_migrate() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        cli::Error,
+        commons::{
+            api::{
+                import::{CaMigration, MigrateCa, MigrateKey},
+                ObjectName, RoaConfigurationUpdates,
+            },
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let legacy = ca_handle("legacy");
+    let legacy_resources = resources("AS65000", "10.0.0.0/16", "");
+    let legacy_roas = vec![
+        roa_configuration("10.0.0.0/24 => 65000"),
+        roa_configuration("10.0.1.0/24 => 65000"),
+    ];
+    let rcn_0 = rcn(0);
+
+    // Wait for the testbed CA to get its certificate.
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    // Set up the CA to migrate, under testbed and with ROAs.
+    set_up_ca_with_repo(&legacy).await;
+    set_up_ca_under_parent_with_resources(&legacy, &testbed, &legacy_resources).await;
+    ca_route_authorizations_update(&legacy, RoaConfigurationUpdates::new(legacy_roas.clone(), vec![])).await;
+    let legacy_id_key = ca_details(&legacy).await.id_cert().public_key().key_identifier();
+
+    // Export it, including its identity key, and remove it.
+    let export = ca_export(&legacy, true).await;
+    delete_ca(&legacy).await;
+    assert!(!has_ca(&legacy).await);
+
+    // Migrate it again. The parent and repository still know the CA by its
+    // identity, so it gets its resources back and publishes its ROAs.
+    let report = cas_migrate(export).await;
+    assert_eq!(report.cas().len(), 1);
+    assert!(report.failed().is_none());
+    assert_eq!(report.cas()[0].roas_added().len(), legacy_roas.len());
+
+    assert_eq!(
+        ca_details(&legacy).await.id_cert().public_key().key_identifier(),
+        legacy_id_key
+    );
+    assert!(ca_contains_resources(&legacy, &legacy_resources).await);
+    expect_configured_roas(&legacy, &legacy_roas).await;
+
+    let mut expected_files = expected_mft_and_crl(&legacy, &rcn_0).await;
+    for roa in &legacy_roas {
+        expected_files.push(ObjectName::from(&roa.payload().into_explicit_max_length()).to_string());
+    }
+    assert!(will_publish_embedded("migrated CA should publish its ROAs", &legacy, &expected_files).await);
+
+    // A migrated CA cannot take over a key which is in use, e.g. the
+    // identity key of another CA.
+    let testbed_id_key = ca_details(&testbed).await.id_cert().public_key().key_identifier();
+    let hijack = CaMigration::new(vec![
+        MigrateCa::new(ca_handle("hijack")).with_id_key(MigrateKey::KeyId(testbed_id_key))
+    ]);
+    match cas_migrate_expect_error(hijack).await {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+            assert_eq!(res.label(), "ca-migration-key-invalid");
+        }
+        e => panic!("Expected key error, got: {}", e),
+    }
+    assert!(!has_ca(&ca_handle("hijack")).await);
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
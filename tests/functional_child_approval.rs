@@ -0,0 +1,79 @@
+//! Requests from a child which requires manual approval are held back until
+//! they are approved.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_child_approval() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        commons::api::{ParentCaReq, PendingChildRequestKind},
+        test::*,
+    };
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let child = ca_handle("child");
+    let child_resources = resources("AS65000", "10.0.0.0/16", "");
+    let rcn_0 = rcn(0);
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    // Add the child, and require manual approval before it is given its
+    // parent, so that its first request is held back.
+    set_up_ca_with_repo(&child).await;
+    let child_request = request(&child).await;
+    let response = add_child_rfc6492(
+        testbed.convert(),
+        child.convert(),
+        child_request,
+        child_resources.clone(),
+    )
+    .await;
+    ca_child_manual_approval(&testbed, &child, true).await;
+    add_parent_to_ca(&child, ParentCaReq::new(testbed.convert(), response)).await;
+
+    let issue = ca_child_request_becomes_pending(&testbed, &child, PendingChildRequestKind::Issue)
+        .await
+        .unwrap();
+    assert!(ca_current_resources(&child).await.is_empty());
+
+    ca_child_request_approve(&testbed, &child, PendingChildRequestKind::Issue, issue.key).await;
+    cas_refresh_single(&child).await;
+    assert!(ca_contains_resources(&child, &child_resources).await);
+    assert!(ca_child_requests(&testbed, &child).await.is_empty());
+
+    // A key roll needs approval for the new key, and for the revocation of
+    // the old key. Approving one does not approve the other.
+    let old_key = *ca_key_for_rcn(&child, &rcn_0).await.key_id();
+    ca_roll_init(&child).await;
+    let issue = ca_child_request_becomes_pending(&testbed, &child, PendingChildRequestKind::Issue)
+        .await
+        .unwrap();
+    assert_ne!(issue.key, old_key);
+    ca_child_request_approve(&testbed, &child, PendingChildRequestKind::Issue, issue.key).await;
+    cas_refresh_single(&child).await;
+    assert!(state_becomes_new_key(&child).await);
+
+    ca_roll_activate(&child).await;
+    assert!(state_becomes_active(&child).await);
+    let revoke = ca_child_request_becomes_pending(&testbed, &child, PendingChildRequestKind::Revoke)
+        .await
+        .unwrap();
+    assert_eq!(revoke.key, old_key);
+
+    ca_child_request_approve(&testbed, &child, PendingChildRequestKind::Revoke, revoke.key).await;
+    cas_refresh_single(&child).await;
+    for _ in 0..30 {
+        if ca_child_requests(&testbed, &child).await.is_empty() {
+            break;
+        }
+        sleep_seconds(1).await;
+    }
+    assert!(ca_child_requests(&testbed, &child).await.is_empty());
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,138 @@
+//! A parent CA enforces its child issuance policy: new child keys are held
+//! back until they are approved, the validity time of child certificates is
+//! capped, and certificates with only ASNs can be refused.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_child_issuance_policy() {
+    use std::fs;
+
+    use chrono::Duration;
+    use rpki::repository::{resources::ResourceSet, x509::Time};
+
+    use krill::{
+        cli::Error,
+        commons::{
+            api::{ChildIssuancePolicy, ParentCaReq, PendingChildRequestKind},
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let ca2 = ca_handle("CA2");
+    let ca3 = ca_handle("CA3");
+    let ca2_resources = ipv4_resources("10.1.0.0/16");
+    let rcn_0 = rcn(0);
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &resources("AS65000", "10.0.0.0/16", "")).await;
+
+    // The validity time cannot be capped below two weeks.
+    let invalid = ChildIssuancePolicy {
+        max_validity_weeks: Some(1),
+        ..Default::default()
+    };
+    match ca_child_issuance_policy_update_expect_error(&testbed, invalid).await {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+            assert_eq!(res.label(), "ca-child-issuance-policy-invalid");
+        }
+        e => panic!("Expected invalid policy, got: {}", e),
+    }
+
+    let policy = ChildIssuancePolicy {
+        max_validity_weeks: Some(4),
+        deny_asn_only: false,
+        approve_new_keys: true,
+    };
+    ca_child_issuance_policy_update(&testbed, policy.clone()).await;
+    assert_eq!(ca_child_issuance_policy(&testbed).await, policy);
+
+    // The key of a new child is held back until it is approved, and then
+    // certified for at most four weeks.
+    set_up_ca_with_repo(&ca2).await;
+    let response = add_child_rfc6492(
+        testbed.convert(),
+        ca2.convert(),
+        request(&ca2).await,
+        ca2_resources.clone(),
+    )
+    .await;
+    add_parent_to_ca(&ca2, ParentCaReq::new(testbed.convert(), response)).await;
+
+    let issue = ca_child_request_becomes_pending(&testbed, &ca2, PendingChildRequestKind::Issue)
+        .await
+        .unwrap();
+    assert!(ca_current_resources(&ca2).await.is_empty());
+
+    ca_child_request_approve(&testbed, &ca2, PendingChildRequestKind::Issue, issue.key).await;
+    cas_refresh_single(&ca2).await;
+    assert!(ca_contains_resources(&ca2, &ca2_resources).await);
+
+    let expires = ca_key_for_rcn(&ca2, &rcn_0).await.incoming_cert().expires();
+    assert!(expires < Time::now() + Duration::weeks(4) + Duration::days(1));
+
+    // The new key of a key roll is held back as well, but the revocation of
+    // the old key is not, as the child does not need manual approval.
+    let old_key = *ca_key_for_rcn(&ca1, &rcn_0).await.key_id();
+    ca_roll_init(&ca1).await;
+    let issue = ca_child_request_becomes_pending(&testbed, &ca1, PendingChildRequestKind::Issue)
+        .await
+        .unwrap();
+    assert_ne!(issue.key, old_key);
+    ca_child_request_approve(&testbed, &ca1, PendingChildRequestKind::Issue, issue.key).await;
+    cas_refresh_single(&ca1).await;
+    assert!(state_becomes_new_key(&ca1).await);
+
+    ca_roll_activate(&ca1).await;
+    assert!(state_becomes_active(&ca1).await);
+    cas_refresh_single(&ca1).await;
+    assert!(ca_child_requests(&testbed, &ca1).await.is_empty());
+
+    // Certificates with only ASNs are refused, while other children keep
+    // getting their certificates.
+    ca_child_issuance_policy_update(
+        &testbed,
+        ChildIssuancePolicy {
+            deny_asn_only: true,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    set_up_ca_with_repo(&ca3).await;
+    let response = add_child_rfc6492(
+        testbed.convert(),
+        ca3.convert(),
+        request(&ca3).await,
+        resources("AS65002", "", ""),
+    )
+    .await;
+    add_parent_to_ca(&ca3, ParentCaReq::new(testbed.convert(), response)).await;
+
+    let mut refused = false;
+    for _ in 0..30 {
+        cas_refresh_single(&ca3).await;
+        let statuses = parent_statuses(&ca3).await;
+        if let Some(status) = statuses.get(&testbed.convert()) {
+            if status.to_failure_opt().is_some() {
+                refused = true;
+                break;
+            }
+        }
+        sleep_seconds(1).await;
+    }
+    assert!(refused);
+    assert!(ca_current_resources(&ca3).await.is_empty());
+    assert!(ca_child_requests(&testbed, &ca3).await.is_empty());
+
+    cas_refresh_single(&ca1).await;
+    assert!(ca_contains_resources(&ca1, &resources("AS65000", "10.0.0.0/16", "")).await);
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,143 @@
+//! Publish a resource class of a CA in another repository than the CA
+//! repository, activate the migration of the class, and keep publishing in
+//! one repository when the other fails.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_class_repo() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        commons::api::{ObjectName, RepoMigrationStage, RoaConfigurationUpdates},
+        test::*,
+    };
+
+    let krill_dir = start_krill_testbed_with_rrdp_interval(5).await;
+    let pubd_dir = start_krill_pubd(5).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let ca2 = ca_handle("CA2");
+    let ca3 = ca_handle("CA3");
+    let ca3_roa = roa_configuration("10.0.0.0/24 => 65000");
+    let rcn_0 = rcn(0);
+    let rcn_1 = rcn(1);
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    // CA3 gets a resource class under each of CA1 and CA2.
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &resources("65000", "10.0.0.0/16", "")).await;
+    set_up_ca_with_repo(&ca2).await;
+    set_up_ca_under_parent_with_resources(&ca2, &testbed, &resources("65001", "10.1.0.0/16", "")).await;
+    set_up_ca_with_repo(&ca3).await;
+    set_up_ca_under_parent_with_resources(&ca3, &ca1, &resources("65000", "10.0.0.0/24", "")).await;
+    set_up_ca_under_parent_with_resources(&ca3, &ca2, &resources("65001", "10.1.0.0/24", "")).await;
+
+    let mut expected_files = expected_mft_and_crl(&ca3, &rcn_0).await;
+    expected_files.append(&mut expected_mft_and_crl(&ca3, &rcn_1).await);
+    assert!(
+        will_publish_embedded(
+            "CA3 should publish both classes in the embedded repo",
+            &ca3,
+            &expected_files
+        )
+        .await
+    );
+
+    // Move the second resource class to the dedicated repository. The class
+    // rolls its key, and the new key publishes in the dedicated repository,
+    // while both current keys keep publishing in the embedded repository.
+    dedicated_repo_add_publisher(publisher_request(&ca3).await).await;
+    let dedicated = dedicated_repository_response(&ca3).await;
+    let dedicated_uri = dedicated.service_uri().clone();
+    ca_class_repo_update(&ca3, &rcn_1, Some(dedicated)).await;
+
+    let mut staged = false;
+    for _ in 0..30 {
+        let status = ca_repo_migration_status(&ca3).await;
+        if status.classes().len() == 1
+            && status.classes()[0].resource_class_name() == &rcn_1
+            && status.classes()[0].stage() == RepoMigrationStage::Staged
+        {
+            staged = true;
+            break;
+        }
+        sleep_seconds(1).await;
+    }
+    assert!(staged);
+
+    assert!(
+        will_publish_embedded(
+            "CA3 should publish both current keys in the embedded repo",
+            &ca3,
+            &expected_files
+        )
+        .await
+    );
+    assert!(
+        will_publish_dedicated(
+            "CA3 should publish the new key of the second class in the dedicated repo",
+            &ca3,
+            &expected_new_key_mft_and_crl(&ca3, &rcn_1).await
+        )
+        .await
+    );
+
+    // Activate the migration once the dedicated repository serves the new
+    // key. The old key of the class is then withdrawn from the embedded repo.
+    assert!(ca_repo_migration_activate_when_ready(&ca3).await);
+    assert!(state_becomes_active(&ca3).await);
+
+    assert!(
+        will_publish_embedded(
+            "CA3 should publish only the first class in the embedded repo",
+            &ca3,
+            &expected_mft_and_crl(&ca3, &rcn_0).await
+        )
+        .await
+    );
+    assert!(
+        will_publish_dedicated(
+            "CA3 should publish the second class in the dedicated repo",
+            &ca3,
+            &expected_mft_and_crl(&ca3, &rcn_1).await
+        )
+        .await
+    );
+    assert!(ca_repo_migration_status(&ca3).await.is_complete());
+
+    // When the dedicated repository fails, the CA still publishes its
+    // changes in the embedded repository, and the failure is reported for
+    // the dedicated repository only.
+    dedicated_repo_remove_publisher(ca3.convert()).await;
+    ca_route_authorizations_update(&ca3, RoaConfigurationUpdates::new(vec![ca3_roa.clone()], vec![])).await;
+
+    let mut expected_files = expected_mft_and_crl(&ca3, &rcn_0).await;
+    expected_files.push(ObjectName::from(&ca3_roa.payload().into_explicit_max_length()).to_string());
+    assert!(will_publish_embedded("CA3 should publish its ROA in the embedded repo", &ca3, &expected_files).await);
+
+    let mut failed = false;
+    for _ in 0..30 {
+        cas_force_publish_all().await;
+        let statuses = ca_repo_statuses(&ca3).await;
+        if let Some(status) = statuses.get(&dedicated_uri) {
+            if status.to_failure_opt().is_some() {
+                failed = true;
+                for (uri, status) in statuses.iter() {
+                    if uri != &dedicated_uri.to_string() {
+                        assert!(status.to_failure_opt().is_none(), "repository {} failed", uri);
+                    }
+                }
+                break;
+            }
+        }
+        sleep_seconds(1).await;
+    }
+    assert!(failed);
+
+    let _ = fs::remove_dir_all(krill_dir);
+    let _ = fs::remove_dir_all(pubd_dir);
+}
@@ -0,0 +1,69 @@
+//! Check that the keys in use by the CAs, the TA proxy and signer, the
+//! Publication Server and the signers are never chosen for archiving, even
+//! when they are old enough.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_key_gc() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        commons::api::{KeyOwner, Timestamp},
+        test::*,
+    };
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let rcn_0 = rcn(0);
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+
+    let inventory = key_inventory().await;
+
+    // Every owner has a key in the inventory.
+    for owner in [
+        KeyOwner::Ca(ca1.clone()),
+        KeyOwner::Ca(testbed.clone()),
+        KeyOwner::TaProxy,
+        KeyOwner::TaSigner,
+        KeyOwner::PublicationServer,
+    ] {
+        assert!(
+            inventory
+                .keys()
+                .iter()
+                .any(|key| key.usages().iter().any(|usage| usage.owner() == &owner)),
+            "no key found for {:?}",
+            owner
+        );
+    }
+
+    // Pretend that all keys are old enough to be archived, so that only
+    // their usages keep them.
+    let (candidates, skipped_recent) = inventory.gc_candidates(Timestamp::now_plus_hours(1));
+    assert!(skipped_recent.iter().all(|key| key.modified().is_none()));
+
+    let ca1_details = ca_details(&ca1).await;
+    let ca1_id_key = ca1_details.id_cert().public_key().key_identifier();
+    let ca1_current_key = ca_key_for_rcn(&ca1, &rcn_0).await;
+    let testbed_id_key = ca_details(&testbed).await.id_cert().public_key().key_identifier();
+
+    for in_use in [&ca1_id_key, ca1_current_key.key_id(), &testbed_id_key] {
+        assert!(inventory.keys().iter().any(|key| key.key_id() == in_use));
+        assert!(!candidates.iter().any(|key| key.key_id() == in_use));
+    }
+
+    for key in inventory.keys().iter().filter(|key| key.is_referenced()) {
+        assert!(!candidates.iter().any(|candidate| candidate.key_id() == key.key_id()));
+        assert!(!skipped_recent.iter().any(|skipped| skipped.key_id() == key.key_id()));
+    }
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,74 @@
+//! The extended CA metrics report the configured ROAs and ASPAs of each CA,
+//! and the number of commands it processed, and failed authentications are
+//! counted.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_metrics() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        commons::{
+            api::{RoaConfigurationUpdates, Token},
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    fn metric(metrics: &str, name: &str) -> Option<u64> {
+        metrics
+            .lines()
+            .find(|line| line.starts_with(name) && line[name.len()..].starts_with(' '))
+            .map(|line| line[name.len() + 1..].parse().unwrap())
+    }
+
+    let krill_dir = tmp_dir();
+    let mut config = test_config(&krill_dir, true, false, false, false);
+    config.metrics.metrics_show_ca_extended_details = true;
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+
+    let roas = vec![
+        roa_configuration("10.0.0.0/24 => 65000"),
+        roa_configuration("10.0.1.0/24 => 65000"),
+    ];
+    ca_route_authorizations_update(&ca1, RoaConfigurationUpdates::new(roas, vec![])).await;
+    ca_route_authorizations_update(
+        &ca1,
+        RoaConfigurationUpdates::new(vec![roa_configuration("10.0.2.0/24 => 65001")], vec![]),
+    )
+    .await;
+
+    // A request with a wrong token fails to authenticate.
+    let uri = format!("{}api/v1/cas", KRILL_SERVER_URI);
+    assert!(httpclient::get_text(&uri, Some(&Token::from("wrong"))).await.is_err());
+
+    let metrics = krill_anon_http_get("metrics").await.unwrap();
+
+    assert_eq!(metric(&metrics, "krill_ca_roas{ca=\"CA1\"}"), Some(3));
+    assert_eq!(metric(&metrics, "krill_ca_aspas{ca=\"CA1\"}"), Some(0));
+    assert_eq!(
+        metric(
+            &metrics,
+            "krill_ca_commands_total{ca=\"CA1\", type=\"cmd-ca-roas-updated\"}"
+        ),
+        Some(2)
+    );
+    assert!(metric(
+        &metrics,
+        "krill_ca_commands_total{ca=\"CA1\", type=\"cmd-ca-repo-update\"}"
+    )
+    .is_some());
+    assert!(metric(&metrics, "krill_auth_failures_total{reason=\"auth-failed\"}").unwrap() >= 1);
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
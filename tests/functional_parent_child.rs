@@ -23,15 +23,19 @@ async fn functional_parent_child() {
     info("#                  CA3 (two parents, two resource classes)       #");
     info("#                  | |                                           #");
     info("#                  CA4 (two resource classes)                    #");
-    info("#                                                                #");
+    info("#                   |                                            #");
+    info("#                  CA5                                           #");
     info("#                                                                #");
     info("# We will verify that:                                           #");
     info("#  * CAs can be set up as parent child using RFC6492             #");
     info("#  * CAs can publish using RFC8181                               #");
+    info("#  * CAs can fetch the RFC 8183 parent response from a URI       #");
     info("#  * CA1 can perform a key roll                                  #");
     info("#  * We can remove and re-add parents / children                 #");
     info("#  * A CA will request revocation and withdraw objects when      #");
     info("#     it is deleted gracefully                                   #");
+    info("#  * A CA is only deleted without reaching its parents if this   #");
+    info("#     is forced                                                  #");
     info("#                                                                #");
     info("##################################################################");
     info("");
@@ -51,6 +55,9 @@ async fn functional_parent_child() {
     let ca4 = ca_handle("CA4");
     let ca4_res_under_ca_3 = resources("65000", "10.0.0.0-10.1.0.255", "");
 
+    let ca5 = ca_handle("CA5");
+    let ca5_res_under_ca_4 = resources("", "10.0.0.0/24", "");
+
     let rcn_0 = rcn(0);
     let rcn_1 = rcn(1);
 
@@ -78,12 +85,13 @@ async fn functional_parent_child() {
     {
         info("##################################################################");
         info("#                                                                #");
-        info("#                      Set up CA2 under testbed                  #");
+        info("#   Set up CA2 under testbed, let Krill fetch the parent         #");
+        info("#   response XML itself                                          #");
         info("#                                                                #");
         info("##################################################################");
         info("");
         set_up_ca_with_repo(&ca2).await;
-        set_up_ca_under_parent_with_resources(&ca2, &testbed, &ca2_res).await;
+        set_up_ca_under_parent_with_fetched_response(&ca2, &testbed, &ca2_res).await;
     }
 
     {
@@ -205,6 +213,56 @@ async fn functional_parent_child() {
         );
     }
 
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Expect that the stats for CA4 under CA3 show that all its      #");
+        info("# resources are certified, and that it contacted CA3             #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let stats = ca_stats_children(&ca3).await;
+        assert_eq!(1, stats.children().len());
+
+        let ca4_stats = stats.children().first().unwrap();
+        assert_eq!(ca4_stats.handle(), &ca4.convert());
+        assert_eq!(ca4_stats.entitled_resources(), &ca4_res_under_ca_3);
+        assert_eq!(ca4_stats.certified_resources(), &ca4_res_under_ca_3);
+        assert!(ca4_stats.uncertified_resources().is_empty());
+        assert!(ca4_stats.last_exchange().unwrap().was_success());
+        assert!(ca4_stats.outstanding_revocations().is_empty());
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Expect that the parent stats for CA3 show the resources it     #");
+        info("# received from each of CA1 and CA2, without pending requests    #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let stats = ca_stats_parents(&ca3).await;
+        assert_eq!(2, stats.parents().len());
+
+        for (parent, expected_resources) in [(&ca1, &ca3_res_under_ca_1), (&ca2, &ca3_res_under_ca_2)] {
+            let parent_stats = stats
+                .parents()
+                .iter()
+                .find(|stats| stats.handle() == &parent.convert())
+                .unwrap();
+            assert!(parent_stats.last_success().is_some());
+            assert_eq!(1, parent_stats.classes().len());
+
+            let class_stats = parent_stats.classes().first().unwrap();
+            assert_eq!(class_stats.entitled_resources(), expected_resources);
+            assert_eq!(class_stats.received_resources(), expected_resources);
+            assert!(class_stats.not_received_resources().is_empty());
+            assert!(class_stats.cert_expires().is_some());
+            assert!(class_stats.pending_requests().is_empty());
+            assert!(class_stats.pending_revocation().is_none());
+        }
+    }
+
     {
         info("##################################################################");
         info("#                                                                #");
@@ -287,6 +345,17 @@ async fn functional_parent_child() {
         );
     }
 
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#                      Set up CA5 under CA4                      #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        set_up_ca_with_repo(&ca5).await;
+        set_up_ca_under_parent_with_resources(&ca5, &ca4, &ca5_res_under_ca_4).await;
+    }
+
     info("##################################################################");
     info("#                                                                #");
     info("# Remove CA3, we expect that its objects are also removed since  #");
@@ -323,5 +392,32 @@ async fn functional_parent_child() {
         }
     }
 
+    info("##################################################################");
+    info("#                                                                #");
+    info("# Remove CA4. Its parent CA3 is gone, so it cannot request       #");
+    info("# revocation. This should fail, unless forced. If it fails, the  #");
+    info("# certificate of its child CA5 is not revoked.                   #");
+    info("#                                                                #");
+    info("##################################################################");
+    info("");
+    {
+        let _ = delete_ca_expect_error(&ca4).await;
+        assert!(has_ca(&ca4).await);
+        assert_eq!(ca_details(&ca4).await.children(), &vec![ca5.convert()]);
+        assert!(ca_contains_resources(&ca5, &ca5_res_under_ca_4).await);
+
+        delete_ca_force(&ca4).await;
+        assert!(!has_ca(&ca4).await);
+
+        assert!(
+            will_publish_embedded(
+                "CA4 should no longer publish anything after it has been deleted",
+                &ca4,
+                &[]
+            )
+            .await
+        );
+    }
+
     let _ = fs::remove_dir_all(krill_dir);
 }
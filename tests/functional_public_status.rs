@@ -0,0 +1,60 @@
+//! Public status pages are served without credentials, for the CAs listed in
+//! 'public_status_cas' only.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_public_status() {
+    use std::fs;
+
+    use hyper::StatusCode;
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{commons::api::PublicCaStatus, test::*};
+
+    let krill_dir = tmp_dir();
+    let mut config = test_config(&krill_dir, true, false, false, false);
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let ca2 = ca_handle("CA2");
+    let rcn_0 = rcn(0);
+
+    config.public_status_cas = vec![ca1.clone()];
+    start_krill(config).await;
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+    set_up_ca_with_repo(&ca2).await;
+    set_up_ca_under_parent_with_resources(&ca2, &testbed, &ipv4_resources("10.1.0.0/16")).await;
+
+    let expected_files = expected_mft_and_crl(&ca1, &rcn_0).await;
+    assert!(will_publish_embedded("CA1 should publish its manifest and CRL", &ca1, &expected_files).await);
+
+    // The listed CA has a JSON and an HTML status page, showing its
+    // published objects.
+    let json = krill_anon_http_get("status/CA1.json").await.unwrap();
+    let status: PublicCaStatus = serde_json::from_str(&json).unwrap();
+    assert_eq!(status.ca(), &ca1);
+    assert_eq!(status.objects().objects().len(), expected_files.len());
+    for object in status.objects().objects() {
+        let name = object.uri().as_str().rsplit('/').next().unwrap();
+        assert!(expected_files.iter().any(|file| file == name));
+    }
+
+    let html = krill_anon_http_get("status/CA1").await.unwrap();
+    assert!(html.contains("RPKI status of CA1"));
+    for file in &expected_files {
+        assert!(html.contains(file.as_str()));
+    }
+
+    // CAs which are not listed are not found, just like CAs which do not
+    // exist, and neither are invalid CA names.
+    assert_http_status(krill_anon_http_get("status/CA2").await, StatusCode::NOT_FOUND);
+    assert_http_status(krill_anon_http_get("status/CA2.json").await, StatusCode::NOT_FOUND);
+    assert_http_status(krill_anon_http_get("status/CA3").await, StatusCode::NOT_FOUND);
+    assert_http_status(krill_anon_http_get("status/CA%20%3Cb%3E").await, StatusCode::NOT_FOUND);
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,74 @@
+//! Reconcile the configuration of a CA with the objects it issued and
+//! published.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_reconcile() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        commons::api::{ObjectName, RoaConfigurationUpdates},
+        test::*,
+    };
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca = ca_handle("ca");
+    let ca_resources = resources("AS65000", "10.0.0.0/16", "");
+    let roas = vec![roa_configuration("10.0.0.0/24 => 65000")];
+    let rcn_0 = rcn(0);
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca).await;
+    set_up_ca_under_parent_with_resources(&ca, &testbed, &ca_resources).await;
+    ca_route_authorizations_update(&ca, RoaConfigurationUpdates::new(roas.clone(), vec![])).await;
+
+    let mut expected_files = expected_mft_and_crl(&ca, &rcn_0).await;
+    for roa in &roas {
+        expected_files.push(ObjectName::from(&roa.payload().into_explicit_max_length()).to_string());
+    }
+    assert!(will_publish_embedded("CA should publish its ROA", &ca, &expected_files).await);
+
+    // The CA publishes what it was configured to issue, so there is nothing
+    // to repair, and the result is kept as the last reconciliation.
+    let reconciliation = ca_reconcile(&ca, false).await;
+    assert!(reconciliation.is_ok(), "{}", reconciliation);
+    assert!(!reconciliation.repaired());
+    assert_eq!(ca_reconcile_last(&ca).await, reconciliation);
+
+    let reconciliation = ca_reconcile(&ca, true).await;
+    assert!(reconciliation.is_ok(), "{}", reconciliation);
+    assert!(!reconciliation.repaired());
+
+    // Reconciling updates the status of the CA, so it needs the right to
+    // update the CA even without a repair. Showing the last result does not.
+    #[cfg(feature = "multi-user")]
+    {
+        use krill::{
+            cli::{
+                options::{CaCommand, Command},
+                Error,
+            },
+            commons::{api::ApiTokenScope, util::httpclient},
+        };
+
+        let read_only = api_token_create("monitor", ApiTokenScope::ReadOnly).await;
+        for repair in [false, true] {
+            match krill_as(&read_only, Command::CertAuth(CaCommand::Reconcile(ca.clone(), repair))).await {
+                Err(Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res))) => {
+                    assert_eq!(res.label(), "api-insufficient-rights");
+                }
+                res => panic!("Expected insufficient rights, got: {:?}", res.map(|_| ())),
+            }
+        }
+        krill_as(&read_only, Command::CertAuth(CaCommand::ReconcileLast(ca.clone())))
+            .await
+            .unwrap();
+    }
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
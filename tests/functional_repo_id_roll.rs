@@ -0,0 +1,89 @@
+//! Roll the ID certificate that a CA uses for the publication protocol. The
+//! new certificate is staged at the publication server first, so that the CA
+//! can keep publishing while it activates the new certificate.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_repo_id_roll() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        cli::Error,
+        commons::{
+            api::{ObjectName, RoaConfigurationUpdates},
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let rcn_0 = rcn(0);
+    let roa_a = roa_configuration("10.0.0.0/24 => 65000");
+    let roa_b = roa_configuration("10.0.1.0/24 => 65000");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+
+    let old_id_cert = ca_details(&ca1).await.id_cert().clone();
+    assert_eq!(publisher_details(ca1.convert()).await.id_cert(), &old_id_cert);
+
+    // A roll must be initiated before it can be activated.
+    match ca_repo_id_roll_activate_expect_error(&ca1).await {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+            assert_eq!(res.label(), "ca-repo-id-roll-not-started");
+        }
+        e => panic!("Expected roll not started, got: {}", e),
+    }
+
+    // Initiating a roll generates a new ID certificate, but the CA keeps
+    // using the old one for now.
+    let new_request = ca_repo_id_roll_init(&ca1).await;
+    assert_eq!(ca_repo_id_roll_request(&ca1).await.id_cert(), new_request.id_cert());
+    assert_ne!(new_request.id_cert(), old_id_cert.base64());
+    assert_eq!(ca_details(&ca1).await.id_cert(), &old_id_cert);
+
+    // Stage the new certificate at the publication server, which then
+    // accepts both.
+    publisher_id_cert_stage(ca1.convert(), new_request.clone()).await;
+    let details = publisher_details(ca1.convert()).await;
+    assert_eq!(details.id_cert(), &old_id_cert);
+    let staged_id_cert = details.staged_id_cert().unwrap().clone();
+    assert_eq!(staged_id_cert.base64(), new_request.id_cert());
+
+    // The CA publishes using the new certificate once it is activated.
+    ca_repo_id_roll_activate(&ca1).await;
+    assert_eq!(ca_details(&ca1).await.id_cert(), &staged_id_cert);
+
+    ca_route_authorizations_update(&ca1, RoaConfigurationUpdates::new(vec![roa_a.clone()], vec![])).await;
+    let mut expected_files = expected_mft_and_crl(&ca1, &rcn_0).await;
+    expected_files.push(ObjectName::from(&roa_a.payload().into_explicit_max_length()).to_string());
+    assert!(will_publish_embedded("CA1 should publish using its staged ID cert", &ca1, &expected_files).await);
+
+    // Activating the new certificate at the publication server completes
+    // the roll, and the CA keeps publishing.
+    publisher_id_cert_activate(ca1.convert()).await;
+    let details = publisher_details(ca1.convert()).await;
+    assert_eq!(details.id_cert(), &staged_id_cert);
+    assert!(details.staged_id_cert().is_none());
+
+    ca_route_authorizations_update(&ca1, RoaConfigurationUpdates::new(vec![roa_b.clone()], vec![])).await;
+    expected_files.push(ObjectName::from(&roa_b.payload().into_explicit_max_length()).to_string());
+    assert!(will_publish_embedded("CA1 should publish using its new ID cert", &ca1, &expected_files).await);
+
+    // The roll is finished, so it cannot be activated again.
+    match ca_repo_id_roll_activate_expect_error(&ca1).await {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+            assert_eq!(res.label(), "ca-repo-id-roll-not-started");
+        }
+        e => panic!("Expected roll not started, got: {}", e),
+    }
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
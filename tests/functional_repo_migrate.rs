@@ -0,0 +1,130 @@
+//! Migrate a CA to another repository using the migration wizard, and abort
+//! a migration before the new keys are activated.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_repo_migrate() {
+    use std::fs;
+
+    use rpki::{ca::idexchange::CaHandle, repository::resources::ResourceSet};
+
+    use krill::{
+        cli::Error,
+        commons::{
+            api::{ObjectName, RoaConfiguration, RoaConfigurationUpdates},
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    async fn expected_files(ca: &CaHandle, roa: &RoaConfiguration) -> Vec<String> {
+        let mut expected_files = expected_mft_and_crl(ca, &rcn(0)).await;
+        expected_files.push(ObjectName::from(&roa.payload().into_explicit_max_length()).to_string());
+        expected_files
+    }
+
+    let krill_dir = start_krill_testbed_with_rrdp_interval(5).await;
+    let pubd_dir = start_krill_pubd(5).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let ca1_res = ipv4_resources("10.0.0.0/16");
+    let ca1_roa = roa_configuration("10.0.0.0/16-16 => 65000");
+    let rcn_0 = rcn(0);
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ca1_res).await;
+    ca_route_authorizations_update(&ca1, RoaConfigurationUpdates::new(vec![ca1_roa.clone()], vec![])).await;
+
+    let embedded_repo = ca_details(&ca1).await.repo_info().cloned();
+    assert!(
+        will_publish_embedded(
+            "CA1 should publish in the embedded repo",
+            &ca1,
+            &expected_files(&ca1, &ca1_roa).await
+        )
+        .await
+    );
+
+    dedicated_repo_add_publisher(publisher_request(&ca1).await).await;
+
+    // Start migrating to the dedicated repository. The wizard stops while it
+    // waits for the parent to certify the new key.
+    let report = repo_migrate(&ca1, dedicated_repository_response(&ca1).await).await;
+    assert!(!report.is_aborted());
+    assert!(!report.status().is_complete());
+    assert!(report.steps().iter().any(|step| step.is_done()));
+    assert!(state_becomes_new_key(&ca1).await);
+
+    // Until the new key is activated, the CA is found in the old repository,
+    // while the new key publishes in the new repository.
+    assert!(
+        will_publish_embedded(
+            "CA1 should still publish its current key in the embedded repo",
+            &ca1,
+            &expected_files(&ca1, &ca1_roa).await
+        )
+        .await
+    );
+    assert!(
+        will_publish_dedicated(
+            "CA1 should publish its new key in the dedicated repo",
+            &ca1,
+            &expected_new_key_mft_and_crl(&ca1, &rcn_0).await
+        )
+        .await
+    );
+
+    // Abort the migration. The new key is revoked and withdrawn from the
+    // new repository, and the CA keeps using the old repository.
+    repo_migration_abort(&ca1).await;
+    assert!(state_becomes_active(&ca1).await);
+    assert_eq!(ca_details(&ca1).await.repo_info().cloned(), embedded_repo);
+    assert!(
+        will_publish_embedded(
+            "CA1 should publish in the embedded repo after the abort",
+            &ca1,
+            &expected_files(&ca1, &ca1_roa).await
+        )
+        .await
+    );
+    assert!(will_publish_dedicated("CA1 should be withdrawn from the dedicated repo", &ca1, &[]).await);
+
+    // Migrate again, and resume the wizard until the migration is complete.
+    let response = dedicated_repository_response(&ca1).await;
+    let mut complete = false;
+    for _ in 0..60 {
+        let report = repo_migrate(&ca1, response.clone()).await;
+        assert!(!report.is_aborted(), "migration aborted: {}", report);
+        if report.status().is_complete() {
+            complete = true;
+            break;
+        }
+        sleep_seconds(1).await;
+    }
+    assert!(complete);
+    assert_ne!(ca_details(&ca1).await.repo_info().cloned(), embedded_repo);
+
+    assert!(
+        will_publish_dedicated(
+            "CA1 should publish in the dedicated repo",
+            &ca1,
+            &expected_files(&ca1, &ca1_roa).await
+        )
+        .await
+    );
+    assert!(will_publish_embedded("CA1 should be withdrawn from the embedded repo", &ca1, &[]).await);
+
+    // There is nothing left to abort.
+    match repo_migration_abort_expect_error(&ca1).await {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+            assert_eq!(res.label(), "ca-repo-issue");
+        }
+        e => panic!("Expected repository issue, got: {}", e),
+    }
+
+    let _ = fs::remove_dir_all(krill_dir);
+    let _ = fs::remove_dir_all(pubd_dir);
+}
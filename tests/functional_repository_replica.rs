@@ -0,0 +1,129 @@
+//! Replicate the repository of a running Krill. The replica has copies of
+//! the RRDP files of the primary, and keeps up when the primary publishes
+//! new content.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_repository_replica() {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        str::FromStr,
+    };
+
+    use rpki::{repository::resources::ResourceSet, rrdp::NotificationFile, uri};
+
+    use krill::{
+        commons::{api::RoaConfigurationUpdates, util::file},
+        constants::{REPOSITORY_DIR, REPOSITORY_RRDP_DIR},
+        pubd::{RepositoryReplica, RepositoryReplicaConfig},
+        test::*,
+    };
+
+    const RRDP_BASE_URI: &str = "https://localhost:3000/rrdp/";
+
+    fn local_path(rrdp_dir: &Path, uri: &uri::Https) -> PathBuf {
+        rrdp_dir.join(uri.as_str().strip_prefix(RRDP_BASE_URI).unwrap())
+    }
+
+    /// Returns the replicated notification file, after checking that all
+    /// files it refers to are replicated as well.
+    fn replicated_notification(rrdp_dir: &Path) -> NotificationFile {
+        let xml = file::read(&rrdp_dir.join("notification.xml")).unwrap();
+        let notification = NotificationFile::parse(xml.as_ref()).unwrap();
+
+        let snapshot = notification.snapshot();
+        let content = file::read(&local_path(rrdp_dir, snapshot.uri())).unwrap();
+        assert!(snapshot.hash().matches(content.as_ref()));
+        for delta in notification.deltas() {
+            let content = file::read(&local_path(rrdp_dir, delta.uri())).unwrap();
+            assert!(delta.hash().matches(content.as_ref()));
+        }
+
+        notification
+    }
+
+    fn files_in(dir: &Path) -> Vec<PathBuf> {
+        let mut files = vec![];
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(files_in(&path));
+            } else {
+                files.push(path);
+            }
+        }
+        files
+    }
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    let replica_dir = tmp_dir();
+    let rrdp_dir = replica_dir.join(REPOSITORY_DIR).join(REPOSITORY_RRDP_DIR);
+    let config = RepositoryReplicaConfig {
+        notification_uri: uri::Https::from_str(&format!("{}notification.xml", RRDP_BASE_URI)).unwrap(),
+        interval_seconds: 60,
+        rsync_base_uri: None,
+    };
+    let replica = RepositoryReplica::new(config, &replica_dir);
+    assert!(replica.status().serial().is_none());
+
+    // The first sync copies the current RRDP files. Syncs which are started
+    // at the same time wait for each other, and find the same content.
+    let (first, second) = tokio::join!(replica.sync(), replica.sync());
+    let first = first.unwrap();
+    let second = second.unwrap();
+    assert!(first.last_error().is_none());
+    assert_eq!(first.serial(), second.serial());
+
+    let notification = replicated_notification(&rrdp_dir);
+    assert_eq!(first.session(), Some(notification.session_id().into()));
+    assert_eq!(first.serial(), Some(notification.serial()));
+
+    // Publish new content, and sync until the replica has it.
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+    let roa = roa_configuration("10.0.0.0/24 => 65000");
+    ca_route_authorizations_update(&ca1, RoaConfigurationUpdates::new(vec![roa], vec![])).await;
+
+    let mut previous = notification;
+    let mut status = first.clone();
+    for _ in 0..30 {
+        sleep_seconds(1).await;
+        let replicated = replicated_notification(&rrdp_dir);
+        status = replica.sync().await.unwrap();
+        if status.serial() > first.serial() {
+            previous = replicated;
+            break;
+        }
+    }
+    assert!(status.serial() > first.serial());
+
+    // Only the files of the current and the previous notification file are
+    // kept, so that relying parties which just fetched the previous one can
+    // still get its files.
+    let current = replicated_notification(&rrdp_dir);
+    assert_eq!(status.serial(), Some(current.serial()));
+
+    let mut kept = vec![rrdp_dir.join("notification.xml")];
+    for notification in &[&current, &previous] {
+        kept.push(local_path(&rrdp_dir, notification.snapshot().uri()));
+        kept.extend(
+            notification
+                .deltas()
+                .iter()
+                .map(|delta| local_path(&rrdp_dir, delta.uri())),
+        );
+    }
+    for file in files_in(&rrdp_dir) {
+        assert!(kept.contains(&file), "unexpected file {}", file.to_string_lossy());
+    }
+
+    let _ = fs::remove_dir_all(krill_dir);
+    let _ = fs::remove_dir_all(replica_dir);
+}
@@ -0,0 +1,90 @@
+//! Transfer resources between two children of a CA. A transfer completes
+//! once the 'from' child got certificates without the resources, and is
+//! rolled back if it can no longer complete.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_resource_transfer() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        cli::Error,
+        commons::{
+            api::{PendingChildRequestKind, ResourceTransferRequest, ResourceTransferState},
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    fn expect_invalid(e: Error) {
+        match e {
+            Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+                assert_eq!(res.label(), "ca-resource-transfer-invalid");
+            }
+            e => panic!("Expected invalid resource transfer, got: {}", e),
+        }
+    }
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let ca2 = ca_handle("CA2");
+    let ca3 = ca_handle("CA3");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/15")).await;
+    set_up_ca_with_repo(&ca2).await;
+    set_up_ca_under_parent_with_resources(&ca2, &testbed, &ipv4_resources("10.2.0.0/16")).await;
+    set_up_ca_with_repo(&ca3).await;
+    set_up_ca_under_parent_with_resources(&ca3, &testbed, &ipv4_resources("10.3.0.0/16")).await;
+
+    let transfer = |from: &CaHandle, to: &CaHandle, resources: &str| ResourceTransferRequest {
+        from: from.convert(),
+        to: to.convert(),
+        resources: ipv4_resources(resources),
+        timeout_hours: None,
+    };
+
+    // A transfer is rejected if the 'from' child does not hold all of the
+    // resources, or if it transfers to itself.
+    expect_invalid(ca_child_transfer_start_expect_error(&testbed, transfer(&ca1, &ca2, "10.2.0.0/16")).await);
+    expect_invalid(ca_child_transfer_start_expect_error(&testbed, transfer(&ca1, &ca1, "10.1.0.0/16")).await);
+    assert!(ca_child_transfers(&testbed).await.transfers().is_empty());
+
+    // Start a transfer. It completes once CA1 got a certificate without the
+    // resources, and then CA2 gets them.
+    ca_child_transfer_start(&testbed, transfer(&ca1, &ca2, "10.1.0.0/16")).await;
+    assert!(ca_child_transfer_becomes(&testbed, ResourceTransferState::Completed).await);
+    assert!(ca_equals_resources(&ca1, &ipv4_resources("10.0.0.0/16")).await);
+    assert!(ca_equals_resources(&ca2, &ipv4_resources("10.1.0.0/16, 10.2.0.0/16")).await);
+
+    // Hold back the requests from CA1, so that it keeps its certificate with
+    // the resources of the next transfer. The transfer then keeps waiting,
+    // and neither child can be part of another transfer in the meantime.
+    ca_child_manual_approval(&testbed, &ca1, true).await;
+    ca_child_transfer_start(&testbed, transfer(&ca1, &ca3, "10.0.0.0/24")).await;
+    let issue = ca_child_request_becomes_pending(&testbed, &ca1, PendingChildRequestKind::Issue)
+        .await
+        .unwrap();
+    assert!(ca_child_transfer_becomes(&testbed, ResourceTransferState::Waiting).await);
+    assert!(ca_contains_resources(&ca1, &ipv4_resources("10.0.0.0/16")).await);
+    expect_invalid(ca_child_transfer_start_expect_error(&testbed, transfer(&ca2, &ca3, "10.2.0.0/24")).await);
+
+    // Remove the 'to' child. The transfer is rolled back, and CA1 is
+    // entitled to the resources again, so that approving its request does
+    // not shrink its certificate.
+    delete_child(&testbed, &ca3).await;
+    assert!(ca_child_transfer_becomes(&testbed, ResourceTransferState::RolledBack).await);
+    assert_eq!(ca_child_transfers(&testbed).await.transfers().len(), 2);
+
+    ca_child_request_approve(&testbed, &ca1, PendingChildRequestKind::Issue, issue.key).await;
+    cas_refresh_single(&ca1).await;
+    assert!(ca_equals_resources(&ca1, &ipv4_resources("10.0.0.0/16")).await);
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
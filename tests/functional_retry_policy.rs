@@ -0,0 +1,112 @@
+//! Override the retry policies of a CA for contacting its repository and its
+//! parent, and follow the backoff state of the CA when its parent can no
+//! longer be contacted successfully.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_retry_policy() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        cli::Error,
+        commons::{
+            api::{RetryPolicies, RetryPolicy},
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    fn expect_label(e: Error, label: &str) {
+        match e {
+            Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+                assert_eq!(res.label(), label);
+            }
+            e => panic!("Expected {}, got: {}", label, e),
+        }
+    }
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let parent = testbed.convert();
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+    assert_eq!(ca_retry_policies(&ca1).await, RetryPolicies::default());
+
+    // Override the policies for the repository and the parent.
+    let repo_policy = RetryPolicy {
+        max_attempts: Some(3),
+        timeout_seconds: Some(10),
+        ..Default::default()
+    };
+    let parent_policy = RetryPolicy {
+        initial_delay_seconds: Some(60),
+        jitter_seconds: Some(0),
+        ..Default::default()
+    };
+    ca_retry_policy_update(&ca1, None, repo_policy.clone()).await;
+    ca_retry_policy_update(&ca1, Some(parent.clone()), parent_policy.clone()).await;
+
+    let policies = ca_retry_policies(&ca1).await;
+    assert_eq!(policies.repository(), &repo_policy);
+    assert_eq!(policies.parent(&parent), parent_policy);
+
+    // Policies for unknown parents, and policies which are inconsistent
+    // with the rest of the configuration, are rejected.
+    expect_label(
+        ca_retry_policy_update_expect_error(&ca1, Some(ca_handle("unknown").convert()), parent_policy).await,
+        "ca-parent-unknown",
+    );
+    expect_label(
+        ca_retry_policy_update_expect_error(
+            &ca1,
+            None,
+            RetryPolicy {
+                max_attempts: Some(0),
+                ..Default::default()
+            },
+        )
+        .await,
+        "ca-retry-policy-invalid",
+    );
+    assert_eq!(ca_retry_policies(&ca1).await, policies);
+
+    // The CA keeps getting its certificate using the overrides, and there is
+    // no backoff while the parent can be contacted.
+    cas_refresh_single(&ca1).await;
+    assert!(ca_contains_resources(&ca1, &ipv4_resources("10.0.0.0/16")).await);
+    let status = parent_statuses(&ca1).await;
+    assert!(status.get(&parent).unwrap().backoff().is_none());
+
+    // When the parent no longer knows the CA, the consecutive failures are
+    // counted.
+    delete_child(&testbed, &ca1).await;
+    cas_refresh_single(&ca1).await;
+
+    let mut failures = 0;
+    for _ in 0..30 {
+        let status = parent_statuses(&ca1).await;
+        if let Some(backoff) = status.get(&parent).and_then(|status| status.backoff()) {
+            failures = backoff.failures();
+            if failures >= 2 {
+                break;
+            }
+        }
+        sleep_seconds(1).await;
+    }
+    assert!(failures >= 2);
+
+    // An empty policy removes the overrides for the parent.
+    ca_retry_policy_update(&ca1, Some(parent.clone()), RetryPolicy::default()).await;
+    let policies = ca_retry_policies(&ca1).await;
+    assert_eq!(policies.parent(&parent), RetryPolicy::default());
+    assert_eq!(policies.repository(), &repo_policy);
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
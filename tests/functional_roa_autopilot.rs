@@ -0,0 +1,72 @@
+//! Set the ROA auto-pilot policy of a CA, which takes precedence over the
+//! policy in the server configuration, and go back to the server default.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_roa_autopilot() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        cli::Error,
+        commons::{api::RoaAutoPilotPolicy, util::httpclient},
+        test::*,
+    };
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+
+    // The auto-pilot is off unless it is configured.
+    let info = ca_roa_autopilot(&ca1).await;
+    assert!(info.enabled().is_none());
+    assert!(!info.ca_override);
+
+    // Turn it on for the CA.
+    let policy = RoaAutoPilotPolicy {
+        min_seen_hours: 24,
+        ..RoaAutoPilotPolicy::with_defaults()
+    };
+    ca_roa_autopilot_update(&ca1, Some(policy.clone())).await;
+    let info = ca_roa_autopilot(&ca1).await;
+    assert_eq!(info.enabled(), Some(&policy));
+    assert!(info.ca_override);
+
+    // An invalid policy is rejected, and the policy is left alone.
+    let invalid = RoaAutoPilotPolicy {
+        max_prefix_length_v4: 33,
+        ..RoaAutoPilotPolicy::with_defaults()
+    };
+    match ca_roa_autopilot_update_expect_error(&ca1, Some(invalid)).await {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+            assert_eq!(res.label(), "ca-roa-autopilot-invalid");
+        }
+        e => panic!("Expected invalid policy, got: {}", e),
+    }
+    assert_eq!(ca_roa_autopilot(&ca1).await.enabled(), Some(&policy));
+
+    // A disabled policy turns it off for the CA.
+    let off = RoaAutoPilotPolicy {
+        enabled: false,
+        ..policy
+    };
+    ca_roa_autopilot_update(&ca1, Some(off)).await;
+    let info = ca_roa_autopilot(&ca1).await;
+    assert!(info.enabled().is_none());
+    assert!(info.ca_override);
+
+    // Go back to the server default.
+    ca_roa_autopilot_update(&ca1, None).await;
+    let info = ca_roa_autopilot(&ca1).await;
+    assert!(info.enabled().is_none());
+    assert!(!info.ca_override);
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,84 @@
+//! With 'roa_update_bgp_check' enabled, ROA updates and CA imports which
+//! would invalidate announcements seen in BGP are refused, unless they are
+//! forced.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_roa_bgp_check() {
+    use std::fs;
+
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        cli::Error,
+        commons::{
+            api::{
+                import::{ImportCa, ImportParent, Structure},
+                RoaConfigurationUpdates,
+            },
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    fn expect_invalidated(e: Error, nr_invalidated: usize) {
+        match e {
+            Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+                assert_eq!(res.label(), "ca-roa-update-invalidates-announcements");
+                assert_eq!(res.bgp_impact().unwrap().invalidated().len(), nr_invalidated);
+            }
+            e => panic!("Expected the update to be refused, got: {}", e),
+        }
+    }
+
+    let krill_dir = tmp_dir();
+    let mut config = test_config(&krill_dir, true, false, false, false);
+    config.roa_update_bgp_check = true;
+    start_krill(config).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let ca2 = ca_handle("CA2");
+
+    // The test announcements for 10.0.0.0/16 are made by AS64496 and AS64497,
+    // for prefixes up to /24.
+    let roa_too_broad = roa_configuration("10.0.0.0/16-16 => 64496");
+    let roa_unseen = roa_configuration("10.1.0.0/24 => 64496");
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/8")).await;
+
+    // A ROA which would invalidate all five announcements is refused.
+    let too_broad = RoaConfigurationUpdates::new(vec![roa_too_broad.clone()], vec![]);
+    expect_invalidated(
+        ca_route_authorizations_update_expect_error(&ca1, too_broad.clone()).await,
+        5,
+    );
+    expect_configured_roas(&ca1, &[]).await;
+
+    // A ROA for a prefix without announcements invalidates nothing.
+    ca_route_authorizations_update(&ca1, RoaConfigurationUpdates::new(vec![roa_unseen.clone()], vec![])).await;
+    expect_configured_roas(&ca1, &[roa_unseen.clone()]).await;
+
+    // The refused ROA can still be added when the update is forced.
+    ca_route_authorizations_update_forced(&ca1, too_broad).await;
+    expect_configured_roas(&ca1, &[roa_too_broad, roa_unseen]).await;
+
+    // The ROAs of imported CAs are checked before any CA is imported. The
+    // ROA invalidates both announcements by AS64497 and the one by AS64496.
+    let structure = Structure {
+        ta: None,
+        publication_server: None,
+        cas: vec![ImportCa::new(
+            ca2.clone(),
+            vec![ImportParent::new(testbed.convert(), ipv4_resources("192.168.0.0/16"))],
+            vec![roa_configuration("192.168.0.0/16-16 => 64496")],
+        )],
+    };
+    expect_invalidated(import_cas_expect_error(structure).await, 3);
+    assert!(!has_ca(&ca2).await);
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,112 @@
+//! Change the ROA issuance strategy of a CA. Its ROAs are re-issued using
+//! the new strategy straight away.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_roa_strategy() {
+    use std::fs;
+
+    use rpki::{ca::idexchange::CaHandle, repository::resources::ResourceSet};
+
+    use krill::{
+        cli::Error,
+        commons::{
+            api::{ObjectName, RoaConfiguration, RoaConfigurationUpdates, RoaIssuanceStrategy},
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    async fn expected_files(ca: &CaHandle, roa_files: &[&str]) -> Vec<String> {
+        let mut expected_files = expected_mft_and_crl(ca, &rcn(0)).await;
+        expected_files.extend(roa_files.iter().map(|file| file.to_string()));
+        expected_files
+    }
+
+    fn roa_file(roa: &RoaConfiguration) -> String {
+        ObjectName::from(&roa.payload().into_explicit_max_length()).to_string()
+    }
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let roas = vec![
+        roa_configuration("10.0.0.0/24 => 65000"),
+        roa_configuration("10.0.1.0/24 => 65000"),
+        roa_configuration("10.0.2.0/24 => 65001"),
+    ];
+    let per_prefix: Vec<String> = roas.iter().map(roa_file).collect();
+    let per_prefix: Vec<&str> = per_prefix.iter().map(|file| file.as_str()).collect();
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+    ca_route_authorizations_update(&ca1, RoaConfigurationUpdates::new(roas.clone(), vec![])).await;
+
+    // Below the aggregation threshold, the default strategy uses one ROA per
+    // prefix.
+    let info = ca_roa_strategy(&ca1).await;
+    assert_eq!(info.effective, RoaIssuanceStrategy::Auto);
+    assert!(!info.ca_override);
+    assert!(
+        will_publish_embedded(
+            "CA1 should publish a ROA per prefix",
+            &ca1,
+            &expected_files(&ca1, &per_prefix).await
+        )
+        .await
+    );
+
+    // One ROA per ASN.
+    ca_roa_strategy_update(&ca1, Some(RoaIssuanceStrategy::PerAsn)).await;
+    let info = ca_roa_strategy(&ca1).await;
+    assert_eq!(info.effective, RoaIssuanceStrategy::PerAsn);
+    assert!(info.ca_override);
+    assert!(
+        will_publish_embedded(
+            "CA1 should publish a ROA per ASN",
+            &ca1,
+            &expected_files(&ca1, &["AS65000.roa", "AS65001.roa"]).await
+        )
+        .await
+    );
+
+    // ROAs per ASN, with at most one prefix each.
+    ca_roa_strategy_update(&ca1, Some(RoaIssuanceStrategy::Grouped { max_prefixes: 1 })).await;
+    assert!(
+        will_publish_embedded(
+            "CA1 should publish grouped ROAs",
+            &ca1,
+            &expected_files(&ca1, &["AS65000-0.roa", "AS65000-1.roa", "AS65001-0.roa"]).await
+        )
+        .await
+    );
+
+    // An invalid strategy is rejected, and the ROAs are left alone.
+    match ca_roa_strategy_update_expect_error(&ca1, Some(RoaIssuanceStrategy::Grouped { max_prefixes: 0 })).await {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+            assert_eq!(res.label(), "ca-roa-issuance-strategy-invalid");
+        }
+        e => panic!("Expected invalid strategy, got: {}", e),
+    }
+    assert_eq!(
+        ca_roa_strategy(&ca1).await.effective,
+        RoaIssuanceStrategy::Grouped { max_prefixes: 1 }
+    );
+
+    // Going back to the server default re-issues a ROA per prefix.
+    ca_roa_strategy_update(&ca1, None).await;
+    assert!(!ca_roa_strategy(&ca1).await.ca_override);
+    assert!(
+        will_publish_embedded(
+            "CA1 should publish a ROA per prefix again",
+            &ca1,
+            &expected_files(&ca1, &per_prefix).await
+        )
+        .await
+    );
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
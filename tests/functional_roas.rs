@@ -2,10 +2,37 @@
 //!
 use std::fs;
 
+use chrono::Duration;
 use hyper::StatusCode;
-use rpki::repository::resources::ResourceSet;
+use rpki::{
+    ca::idexchange::CaHandle,
+    repository::{resources::ResourceSet, roa::Roa, x509::Time},
+};
 
-use krill::{commons::api::RoaConfigurationUpdates, test::*};
+use krill::{
+    commons::api::{
+        IssuanceTimingOverrides, ObjectName, RoaConfiguration, RoaConfigurationUpdates, RoaImport,
+        RoaImportConflictReason, RoaImportFormat,
+    },
+    test::*,
+};
+
+/// Returns the expiry time of the published ROA for the given configuration,
+/// once it is published.
+async fn published_roa_expires(ca: &CaHandle, roa: &RoaConfiguration) -> Time {
+    let name = ObjectName::from(&roa.payload().into_explicit_max_length()).to_string();
+    for _ in 0..30 {
+        let details = publisher_details(ca.convert()).await;
+        for file in details.current_files() {
+            if file.uri().as_str().ends_with(&name) {
+                let roa = Roa::decode(file.base64().to_bytes().as_ref(), true).unwrap();
+                return roa.cert().validity().not_after();
+            }
+        }
+        sleep_seconds(1).await;
+    }
+    panic!("ROA {} was not published", name);
+}
 
 #[tokio::test]
 async fn functional_roas() {
@@ -180,6 +207,151 @@ async fn functional_roas() {
         expect_roa_objects(&ca, &[route_resource_set_10_0_0_0_def_1.payload()]).await;
     }
 
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Import RPSL route objects, first as a dry run                  #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let rpsl = concat!(
+            "route:  10.0.0.0/16\n",
+            "origin: AS64496\n",
+            "\n",
+            "route:  10.2.0.0/16\n",
+            "origin: AS64498\n",
+            "\n",
+            "route:  11.0.0.0/8\n",
+            "origin: AS64498\n",
+        );
+        let import = RoaImport::new(RoaImportFormat::Rpsl, rpsl.to_string());
+
+        let report = ca_route_authorizations_import(&ca, import.clone(), true).await;
+        assert_eq!(report.unchanged(), &vec![roa_payload("10.0.0.0/16 => 64496")]);
+        assert_eq!(
+            report.updates().added(),
+            &vec![roa_configuration("10.2.0.0/16 => 64498")]
+        );
+        assert_eq!(report.conflicts().len(), 1);
+        assert_eq!(report.conflicts()[0].reason(), &RoaImportConflictReason::NotHeld);
+
+        expect_roa_objects(&ca, &[route_resource_set_10_0_0_0_def_1.payload()]).await;
+
+        ca_route_authorizations_import(&ca, import, false).await;
+        expect_roa_objects(
+            &ca,
+            &[
+                route_resource_set_10_0_0_0_def_1.payload(),
+                roa_payload("10.2.0.0/16 => 64498"),
+            ],
+        )
+        .await;
+    }
+
+    // Proposals must be approved by another actor, which needs a named API
+    // token.
+    #[cfg(feature = "multi-user")]
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Propose ROA updates, these are only applied after approval     #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(roa_configuration("10.3.0.0/16 => 64499"));
+        updates.remove(roa_payload("10.2.0.0/16 => 64498"));
+        ca_route_authorizations_propose(&ca, updates.clone()).await;
+
+        let proposals = ca_route_authorizations_proposals(&ca).await.unpack();
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].updates(), &updates.clone().into_explicit_max_length());
+
+        // Nothing is issued until the proposal is approved
+        expect_roa_objects(
+            &ca,
+            &[
+                route_resource_set_10_0_0_0_def_1.payload(),
+                roa_payload("10.2.0.0/16 => 64498"),
+            ],
+        )
+        .await;
+
+        // The proposer cannot approve their own proposal
+        let err = ca_route_authorizations_approve_expect_error(&ca, proposals[0].id()).await;
+        assert!(err.to_string().contains("ca-roa-proposal-self-approval"));
+        assert_eq!(ca_route_authorizations_proposals(&ca).await.proposals().len(), 1);
+
+        let approver = api_token_create("approver", krill::commons::api::ApiTokenScope::ReadWrite).await;
+        ca_route_authorizations_approve_as(&approver, &ca, proposals[0].id()).await;
+        assert!(ca_route_authorizations_proposals(&ca).await.proposals().is_empty());
+        expect_roa_objects(
+            &ca,
+            &[
+                route_resource_set_10_0_0_0_def_1.payload(),
+                roa_payload("10.3.0.0/16 => 64499"),
+            ],
+        )
+        .await;
+
+        // A rejected proposal is discarded without changing anything
+        let mut updates = RoaConfigurationUpdates::empty();
+        updates.add(roa_configuration("10.4.0.0/16 => 64499"));
+        ca_route_authorizations_propose(&ca, updates).await;
+
+        let proposals = ca_route_authorizations_proposals(&ca).await.unpack();
+        assert_eq!(proposals.len(), 1);
+
+        ca_route_authorizations_reject(&ca, proposals[0].id()).await;
+        assert!(ca_route_authorizations_proposals(&ca).await.proposals().is_empty());
+        expect_roa_objects(
+            &ca,
+            &[
+                route_resource_set_10_0_0_0_def_1.payload(),
+                roa_payload("10.3.0.0/16 => 64499"),
+            ],
+        )
+        .await;
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Override the issuance timing for the CA, invalid combinations  #");
+        info("# are rejected.                                                  #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        assert!(ca_issuance_timing(&ca).await.is_empty());
+
+        let overrides = IssuanceTimingOverrides {
+            timing_roa_valid_weeks: Some(8),
+            timing_roa_reissue_weeks_before: Some(2),
+            ..Default::default()
+        };
+        ca_issuance_timing_update(&ca, overrides.clone()).await;
+        assert_eq!(ca_issuance_timing(&ca).await, overrides);
+
+        // New ROAs are issued using the overridden validity time, rather
+        // than the default of 52 weeks.
+        let roa = roa_configuration("10.5.0.0/16 => 64499");
+        ca_route_authorizations_update(&ca, RoaConfigurationUpdates::new(vec![roa.clone()], vec![])).await;
+        let expires = published_roa_expires(&ca, &roa).await;
+        assert!(expires > Time::now() + Duration::weeks(7));
+        assert!(expires < Time::now() + Duration::weeks(8) + Duration::days(1));
+
+        let invalid = IssuanceTimingOverrides {
+            timing_roa_valid_weeks: Some(1),
+            ..Default::default()
+        };
+        let err = ca_issuance_timing_update_expect_error(&ca, invalid).await;
+        assert!(err.to_string().contains("ca-issuance-timing-invalid"));
+        assert_eq!(ca_issuance_timing(&ca).await, overrides);
+
+        ca_issuance_timing_update(&ca, IssuanceTimingOverrides::default()).await;
+        assert!(ca_issuance_timing(&ca).await.is_empty());
+    }
+
     {
         info("##################################################################");
         info("#                                                                #");
@@ -0,0 +1,189 @@
+//! Perform functional tests on a Krill instance, using the API
+//!
+use std::{convert::TryFrom, fs};
+
+use bcder::{Ia5String, Mode, OctetString};
+use chrono::Duration;
+use openssl::{
+    cms::{CMSOptions, CmsContentInfo},
+    x509::X509,
+};
+use rpki::{
+    crypto::DigestAlgorithm,
+    repository::{resources::ResourceSet, sigobj::SignedObject, x509::Time},
+};
+
+use krill::{
+    cli::Error,
+    commons::util::httpclient,
+    daemon::ca::{RscFileHash, CT_RPKI_SIGNED_CHECKLIST},
+    test::*,
+};
+
+/// Decodes the digest algorithm and the checklist from the RpkiSignedChecklist
+/// eContent, see RFC 9323 section 4.
+fn decode_checklist(content: &[u8]) -> (DigestAlgorithm, Vec<RscFileHash>) {
+    Mode::Der
+        .decode(content, |cons| {
+            cons.take_sequence(|cons| {
+                // version is DEFAULT, and the resources are checked against
+                // the EE certificate.
+                cons.take_sequence(|cons| cons.skip_all())?;
+                let digest_algorithm = DigestAlgorithm::take_from(cons)?;
+                let checklist = cons.take_sequence(|cons| {
+                    let mut checklist = vec![];
+                    while let Some(item) = cons.take_opt_sequence(|cons| {
+                        let file_name = Ia5String::take_opt_from(cons)?.map(|name| name.to_string());
+                        let hash = OctetString::take_from(cons)?.to_bytes();
+                        Ok(RscFileHash::new(file_name, hash))
+                    })? {
+                        checklist.push(item);
+                    }
+                    Ok(checklist)
+                })?;
+                Ok((digest_algorithm, checklist))
+            })
+        })
+        .unwrap()
+}
+
+fn expect_label(e: Error, label: &str) {
+    match e {
+        Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+            assert_eq!(res.label(), label);
+        }
+        e => panic!("Expected {}, got: {}", label, e),
+    }
+}
+
+#[tokio::test]
+async fn functional_rsc() {
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    info("##################################################################");
+    info("#                                                                #");
+    info("# Test RPKI Signed Checklist (RSC) support.                      #");
+    info("#                                                                #");
+    info("# Uses the following lay-out:                                    #");
+    info("#                                                                #");
+    info("#                  TA                                            #");
+    info("#                   |                                            #");
+    info("#                testbed                                         #");
+    info("#                   |                                            #");
+    info("#                  CA                                            #");
+    info("#                                                                #");
+    info("##################################################################");
+    info("");
+
+    let testbed = ca_handle("testbed");
+    let ca = ca_handle("CA");
+    let ca_res = resources("AS65000", "10.0.0.0/16", "");
+
+    info("##################################################################");
+    info("#                                                                #");
+    info("# Wait for the *testbed* CA to get its certificate, this means   #");
+    info("# that all CAs which are set up as part of krill_start under the #");
+    info("# testbed config have been set up.                               #");
+    info("#                                                                #");
+    info("##################################################################");
+    info("");
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#                      Set up CA  under testbed                  #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        set_up_ca_with_repo(&ca).await;
+        set_up_ca_under_parent_with_resources(&ca, &testbed, &ca_res).await;
+    }
+
+    let geofeed = RscFileHash::for_content(Some("geofeed.csv".to_string()), b"10.0.0.0/24,NL,,,\n");
+    let contact = RscFileHash::for_content(None, b"noc@example.com\n");
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Sign an RSC over part of the resources of the CA               #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let rsc_res = resources("AS65000", "10.0.0.0/24", "");
+        let rsc = rsc_sign(&ca, rsc_res.clone(), vec![geofeed.clone(), contact.clone()]).await;
+
+        let signed = SignedObject::decode(rsc.as_ref().as_ref(), true).unwrap();
+        assert_eq!(signed.content_type(), &CT_RPKI_SIGNED_CHECKLIST);
+
+        // The checklist contains the requested hashes, in order, using the
+        // only digest algorithm allowed: SHA-256.
+        let content = signed.content().to_bytes();
+        let (digest_algorithm, checklist) = decode_checklist(content.as_ref());
+        assert_eq!(digest_algorithm, DigestAlgorithm::default());
+        assert_eq!(checklist, vec![geofeed.clone(), contact.clone()]);
+
+        // The EE certificate is issued by the current key of the CA, for
+        // exactly the requested resources and validity time. RFC 9323 does
+        // not allow it to have a signedObject SIA.
+        let ee = signed.cert();
+        let ca_cert = ca_key_for_rcn(&ca, &rcn(0)).await.incoming_cert().clone();
+        assert_eq!(ee.authority_key_identifier(), Some(ca_cert.key_identifier()));
+        assert_eq!(ResourceSet::try_from(ee).unwrap(), rsc_res);
+        assert!(ee.signed_object().is_none());
+
+        let not_after = ee.validity().not_after();
+        assert!(not_after > Time::now() + Duration::days(13));
+        assert!(not_after < Time::now() + Duration::days(15));
+
+        let ca_key = X509::from_der(ca_cert.to_bytes().as_ref())
+            .unwrap()
+            .public_key()
+            .unwrap();
+        let ee = X509::from_der(ee.to_captured().as_slice()).unwrap();
+        assert!(ee.verify(&ca_key).unwrap());
+
+        // Verify the CMS signature (but not the RPKI path) independently
+        let mut cms = CmsContentInfo::from_der(rsc.as_ref()).unwrap();
+        let mut verified_content = vec![];
+        cms.verify(
+            None,
+            None,
+            None,
+            Some(&mut verified_content),
+            CMSOptions::NO_SIGNER_CERT_VERIFY,
+        )
+        .unwrap();
+        assert_eq!(verified_content, content.as_ref());
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("# Refuse to sign RSCs for resources not held, or for invalid     #");
+        info("# checklists                                                     #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        expect_label(
+            rsc_sign_expect_error(&ca, resources("AS65001", "", ""), vec![geofeed.clone()]).await,
+            "rsc-resources-not-held",
+        );
+        expect_label(
+            rsc_sign_expect_error(&ca, resources("", "10.1.0.0/24", ""), vec![geofeed]).await,
+            "rsc-resources-not-held",
+        );
+        expect_label(
+            rsc_sign_expect_error(&ca, ca_res.clone(), vec![]).await,
+            "general-error",
+        );
+
+        let not_portable = RscFileHash::for_content(Some("geo feed.csv".to_string()), b"");
+        expect_label(
+            rsc_sign_expect_error(&ca, ca_res, vec![not_portable]).await,
+            "general-error",
+        );
+    }
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,109 @@
+//! Schedule ROA changes for a CA. Changes are applied when they are due,
+//! unless they are cancelled before, and a change which can no longer be
+//! applied is dropped.
+//!
+#[cfg(not(any(feature = "hsm-tests-kmip", feature = "hsm-tests-pkcs11")))]
+#[tokio::test]
+async fn functional_scheduled_changes() {
+    use std::fs;
+
+    use chrono::Duration;
+    use rpki::repository::resources::ResourceSet;
+
+    use krill::{
+        cli::Error,
+        commons::{
+            api::{ObjectName, RoaConfigurationUpdates, ScheduledChangeRequest, ScheduledUpdate, Timestamp},
+            util::httpclient,
+        },
+        test::*,
+    };
+
+    fn expect_label(e: Error, label: &str) {
+        match e {
+            Error::HttpClientError(httpclient::Error::ErrorResponseWithJson(_, _, res)) => {
+                assert_eq!(res.label(), label);
+            }
+            e => panic!("Expected {}, got: {}", label, e),
+        }
+    }
+
+    fn scheduled(seconds: i64, updates: RoaConfigurationUpdates) -> ScheduledChangeRequest {
+        ScheduledChangeRequest {
+            activate_at: Timestamp::now() + Duration::seconds(seconds),
+            update: ScheduledUpdate::Roas(updates),
+        }
+    }
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false, false).await;
+
+    let testbed = ca_handle("testbed");
+    let ca1 = ca_handle("CA1");
+    let rcn_0 = rcn(0);
+    let roa_a = roa_configuration("10.0.0.0/24 => 65000");
+    let roa_b = roa_configuration("10.0.1.0/24 => 65000");
+    let add_a = RoaConfigurationUpdates::new(vec![roa_a.clone()], vec![]);
+    let add_b = RoaConfigurationUpdates::new(vec![roa_b.clone()], vec![]);
+    let remove_a = RoaConfigurationUpdates::new(vec![], vec![roa_a.payload()]);
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all()).await);
+
+    set_up_ca_with_repo(&ca1).await;
+    set_up_ca_under_parent_with_resources(&ca1, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+
+    // Changes must be due in the future, and are verified when they are
+    // scheduled.
+    expect_label(
+        ca_scheduled_change_add_expect_error(&ca1, scheduled(-60, add_a.clone())).await,
+        "ca-scheduled-change-invalid",
+    );
+    expect_label(
+        ca_scheduled_change_add_expect_error(
+            &ca1,
+            ScheduledChangeRequest {
+                activate_at: Timestamp::new(i64::MAX),
+                update: ScheduledUpdate::Roas(add_a.clone()),
+            },
+        )
+        .await,
+        "ca-scheduled-change-invalid",
+    );
+    expect_label(
+        ca_scheduled_change_add_expect_error(&ca1, scheduled(60, remove_a.clone())).await,
+        "ca-roa-delta-error",
+    );
+
+    // Schedule two changes. The first is applied when it is due, the
+    // second is still pending.
+    ca_scheduled_change_add(&ca1, scheduled(5, add_a)).await;
+    ca_scheduled_change_add(&ca1, scheduled(24 * 3600, add_b.clone())).await;
+    assert_eq!(ca_scheduled_changes(&ca1).await.changes().len(), 2);
+    expect_configured_roas(&ca1, &[]).await;
+
+    assert!(ca_scheduled_changes_become(&ca1, 1).await);
+    expect_configured_roas(&ca1, &[roa_a.clone()]).await;
+
+    let mut expected_files = expected_mft_and_crl(&ca1, &rcn_0).await;
+    expected_files.push(ObjectName::from(&roa_a.payload().into_explicit_max_length()).to_string());
+    assert!(will_publish_embedded("CA1 should publish the scheduled ROA", &ca1, &expected_files).await);
+
+    // Cancel the pending change. It can only be cancelled once.
+    let pending = ca_scheduled_changes(&ca1).await.changes()[0].id();
+    ca_scheduled_change_cancel(&ca1, pending).await;
+    assert!(ca_scheduled_changes(&ca1).await.changes().is_empty());
+    expect_label(
+        ca_scheduled_change_cancel_expect_error(&ca1, pending).await,
+        "ca-scheduled-change-unknown",
+    );
+
+    // A change which can no longer be applied when it is due is dropped,
+    // and does not hold back later changes.
+    ca_scheduled_change_add(&ca1, scheduled(5, remove_a.clone())).await;
+    ca_scheduled_change_add(&ca1, scheduled(10, add_b)).await;
+    ca_route_authorizations_update(&ca1, remove_a).await;
+
+    assert!(ca_scheduled_changes_become(&ca1, 0).await);
+    expect_configured_roas(&ca1, &[roa_b]).await;
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -100,5 +100,23 @@ async fn test_suspension() {
         expect_not_suspended(&testbed, &ca).await;
     }
 
+    // Children which are exempt from suspension are not suspended when inactive
+    {
+        ca_child_suspension_exempt(&testbed, &ca, true).await;
+
+        sleep_seconds(5).await;
+        cas_suspend_all().await;
+        sleep_seconds(2).await;
+        expect_not_suspended(&testbed, &ca).await;
+    }
+
+    // Unless the exemption is lifted again
+    {
+        ca_child_suspension_exempt(&testbed, &ca, false).await;
+
+        cas_suspend_all().await;
+        expect_suspended(&testbed, &ca).await;
+    }
+
     let _ = fs::remove_dir_all(krill_dir);
 }